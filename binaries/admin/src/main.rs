@@ -2,10 +2,19 @@
 //!
 //! 这是 Stepflow Tool System 的管理工具入口。
 
-use anyhow::Result;
-use clap::Parser;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use anyhow::{bail, Context, Result};
+use clap::{Parser, Subcommand};
+use stepflow_api::{DirectorySyncService, TenantBundle, TenantBundleService, TenantExportOptions};
+use stepflow_core::TenantId;
+use stepflow_database::{current_environment, MigrationManager, OperationalMode, OperationalModeRepository, SqliteDatabase};
+use stepflow_openapi::{SdkGenerator, SdkLanguage, SdkOptions};
 use tracing::{info, error};
 
+/// 未通过 `--passphrase` 提供口令时读取的环境变量
+const PASSPHRASE_ENV: &str = "STEPFLOW_BUNDLE_PASSPHRASE";
+
 #[derive(Parser)]
 #[command(name = "stepflow-admin")]
 #[command(about = "Stepflow Tool System Admin Tool")]
@@ -16,15 +25,223 @@ struct Cli {
     /// 日志级别
     #[arg(short, long, default_value = "info")]
     log_level: String,
+    /// 数据库连接地址
+    #[arg(long, default_value = "sqlite://stepflow.db")]
+    database: String,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// 列出已执行的迁移和待执行的数量
+    Status,
+    /// 校验已执行迁移的 checksum
+    Verify,
+    /// 升级或回滚到指定版本，缺省为最新版本
+    Migrate {
+        /// 目标版本
+        version: Option<u32>,
+        /// 只打印执行计划中的 SQL，不执行
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// 导入 fixture 文件，fixture 不允许当前环境时拒绝
+    Seed {
+        /// fixture 文件
+        fixture: PathBuf,
+        /// 目标环境，缺省读取 STEPFLOW_ENV，未设置时视为 production
+        #[arg(long)]
+        environment: Option<String>,
+    },
+    /// 导出租户状态包
+    ExportTenant {
+        /// 租户 ID
+        tenant_id: String,
+        /// 输出文件
+        #[arg(long)]
+        out: PathBuf,
+        /// 加密密钥的口令，缺省时读取 STEPFLOW_BUNDLE_PASSPHRASE
+        #[arg(long)]
+        passphrase: Option<String>,
+        /// 导出最近多少天的执行元数据
+        #[arg(long, default_value_t = 30)]
+        execution_history_days: u32,
+    },
+    /// 导入租户状态包
+    ImportTenant {
+        /// 租户状态包文件
+        bundle: PathBuf,
+        /// 导出时使用的口令，缺省时读取 STEPFLOW_BUNDLE_PASSPHRASE
+        #[arg(long)]
+        passphrase: Option<String>,
+    },
+    /// 从租户配置的 LDAP/AD 目录同步用户
+    DirectorySync {
+        /// 租户 ID
+        tenant_id: String,
+        /// 只预览变更，不写入
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// 查看或切换系统运行模式，服务实例在几秒内生效
+    Mode {
+        /// 新的模式：normal、maintenance 或 read_only，缺省时只显示当前模式
+        mode: Option<String>,
+        /// 被拒绝的请求收到的提示
+        #[arg(long)]
+        message: Option<String>,
+    },
+    /// 根据 Stepflow API 的 OpenAPI 文档生成客户端 SDK
+    GenerateSdk {
+        /// 目标语言：rust 或 typescript
+        #[arg(long)]
+        language: String,
+        /// 输出目录
+        #[arg(long)]
+        out: PathBuf,
+        /// 包名，缺省为 stepflow-client
+        #[arg(long, default_value = "stepflow-client")]
+        package_name: String,
+        /// 使用指定的 OpenAPI 文档（JSON），缺省为内置的 API 文档
+        #[arg(long)]
+        spec: Option<PathBuf>,
+    },
+}
+
+fn passphrase(passphrase: Option<String>) -> Result<String> {
+    passphrase
+        .or_else(|| std::env::var(PASSPHRASE_ENV).ok())
+        .with_context(|| format!("--passphrase or {} is required", PASSPHRASE_ENV))
+}
+
+/// 待执行的迁移数量
+async fn pending_migrations(db: &SqliteDatabase) -> Result<usize> {
+    Ok(MigrationManager::migrate_to(db, MigrationManager::latest_version(), true).await?.steps.len())
+}
+
+/// 打开数据库；结构不是最新版本时报错，迁移只由 `migrate` 命令执行
+async fn open_database(url: &str) -> Result<Arc<SqliteDatabase>> {
+    let db = Arc::new(SqliteDatabase::new(url).await?);
+    let pending = pending_migrations(&db).await?;
+    if pending > 0 {
+        bail!("Database has {} pending migration(s), run `stepflow-admin migrate` first", pending);
+    }
+    Ok(db)
+}
+
+/// 生成客户端 SDK 并写入输出目录
+fn generate_sdk(language: &str, out: &Path, package_name: String, spec: Option<PathBuf>) -> Result<()> {
+    let document = match spec {
+        Some(path) => {
+            let content = std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+            serde_json::from_str(&content).with_context(|| format!("Invalid OpenAPI document {}", path.display()))?
+        }
+        None => stepflow_api::api_spec(),
+    };
+    let language: SdkLanguage = language.parse()?;
+    let files = SdkGenerator::new(SdkOptions::new(language, package_name)).generate(&document)?;
+    for file in &files {
+        let path = out.join(&file.path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        std::fs::write(&path, &file.contents).with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    info!("Generated {:?} SDK in {} ({} files)", language, out.display(), files.len());
+    Ok(())
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    let Some(command) = cli.command else {
+        info!("Stepflow Admin 启动");
+        return Ok(());
+    };
+    match command {
+        Command::Status => {
+            let db = SqliteDatabase::new(&cli.database).await?;
+            for record in MigrationManager::get_migration_history(&db).await? {
+                println!(
+                    "{:>4}  {:<40} {}  {}",
+                    record.version,
+                    record.name,
+                    record.applied_at,
+                    record.checksum.as_deref().unwrap_or("-")
+                );
+            }
+            println!("{} pending migration(s)", pending_migrations(&db).await?);
+        }
+        Command::Verify => {
+            MigrationManager::verify_checksums(&SqliteDatabase::new(&cli.database).await?).await?;
+            println!("All applied migrations match their checksums");
+        }
+        Command::Migrate { version, dry_run } => {
+            let db = SqliteDatabase::new(&cli.database).await?;
+            let plan = MigrationManager::migrate_to(&db, version.unwrap_or_else(MigrationManager::latest_version), dry_run).await?;
+            if dry_run {
+                print!("{}", plan);
+            } else {
+                println!("Migrated to version {} ({} step(s))", plan.target, plan.steps.len());
+            }
+        }
+        Command::Seed { fixture, environment } => {
+            let db = open_database(&cli.database).await?;
+            let environment = environment.unwrap_or_else(current_environment);
+            let report = MigrationManager::seed(&db, &fixture, &environment).await?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
+        Command::ExportTenant { tenant_id, out, passphrase: given, execution_history_days } => {
+            let service = TenantBundleService::new(open_database(&cli.database).await?);
+            let mut options = TenantExportOptions::new(passphrase(given)?);
+            options.execution_history = chrono::Duration::days(execution_history_days as i64);
+            let bundle = service.export(&TenantId::from_string(tenant_id.clone()), &options).await?;
+            std::fs::write(&out, &bundle).with_context(|| format!("Failed to write {}", out.display()))?;
+            info!("Exported tenant {} to {} ({} bytes)", tenant_id, out.display(), bundle.len());
+        }
+        Command::ImportTenant { bundle, passphrase: given } => {
+            let service = TenantBundleService::new(open_database(&cli.database).await?);
+            let bytes = std::fs::read(&bundle).with_context(|| format!("Failed to read {}", bundle.display()))?;
+            let bundle = TenantBundle::open(&bytes)?;
+            let report = service.import(&bundle, &passphrase(given)?).await?;
+            for section in &report.sections {
+                info!("{}: {} rows imported, {} failed", section.name, section.imported, section.failures.len());
+                for failure in &section.failures {
+                    error!("{}: {}", section.name, failure);
+                }
+            }
+            info!("Imported tenant {}", report.tenant_id);
+        }
+        Command::DirectorySync { tenant_id, dry_run } => {
+            let db = open_database(&cli.database).await?;
+            let report = DirectorySyncService::new(db.as_ref().clone())
+                .sync_tenant(&TenantId::from_string(tenant_id), dry_run)
+                .await?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
+        Command::Mode { mode, message } => {
+            let repository = OperationalModeRepository::new(open_database(&cli.database).await?.as_ref().clone());
+            let record = match mode {
+                Some(mode) => {
+                    let mode = OperationalMode::parse(&mode)
+                        .with_context(|| format!("Unknown mode '{}', expected normal, maintenance or read_only", mode))?;
+                    repository.set(mode, message.as_deref(), Some("stepflow-admin")).await?
+                }
+                None => repository.get().await?,
+            };
+            println!("{}", serde_json::to_string_pretty(&record)?);
+        }
+        Command::GenerateSdk { language, out, package_name, spec } => generate_sdk(&language, &out, package_name, spec)?,
+    }
+    Ok(())
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
+    // 命令结果以 JSON 输出到 stdout，日志写到 stderr
     tracing_subscriber::fmt()
         .with_env_filter(&cli.log_level)
+        .with_writer(std::io::stderr)
         .init();
-    info!("Stepflow Admin 启动");
-    // TODO: 管理工具逻辑
-    Ok(())
-} 
\ No newline at end of file
+    run(cli).await
+}
@@ -1,44 +1,650 @@
 //! Stepflow Server - Main Binary
-//! 
-//! This is the main server binary for the Stepflow Tool System.
+//!
+//! This is the main server binary for the Stepflow Tool System. It loads the
+//! configuration, opens and migrates the database, starts the registry,
+//! executor and sandbox, and serves the HTTP API and the RPC server until it
+//! receives SIGINT or SIGTERM.
+//!
+//! The configuration file is overridden by the `STEPFLOW_*` environment
+//! variables, which are in turn overridden by command line flags.
 
-use anyhow::Result;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
 use clap::Parser;
-use tracing::{info, error};
+use stepflow_api::server::{AppState, AuthService, RateLimitService};
+use stepflow_api::types::{AuthConfig, CorsConfig, ServerConfig as ApiServerConfig};
+use stepflow_api::middleware::{execution_pause_reason, load_tenant_network_acls};
+use stepflow_api::{build_app, DirectorySyncService, ToolSessionConfig, ToolSessionManager};
+#[cfg(feature = "redis")]
+use stepflow_api::RedisRateLimitService;
+use stepflow_core::{Config, ConfigLoader, DefaultConfigLoader, EventSinkKind, NetworkAcl, StepflowError};
+use stepflow_database::{
+    JobScheduler, JobSchedulerConfig, JobTrigger, LeaderElection, LeaderElectionConfig, MasterKey, MasterKeyring,
+    SessionRepository, SlowQueryRepository, SqliteDatabase,
+};
+use stepflow_executor::{
+    Executor, SchedulerConfig, SqliteExecutionStore, TaskQueue, ToolTestRunner, WorkerPoolConfig,
+};
+use stepflow_monitoring::{
+    connect_event_sink, init_logging, AlertManager, EventBusNotifier, EventRelay, LogOutputFormat, LoggingConfig, SlowExecutionLog,
+};
+use stepflow_registry::{CacheBackend, ChangeFeed, ChangeFeedRpcHandler, RegistryCacheConfig, RegistryGc};
+#[cfg(feature = "redis")]
+use stepflow_executor::RedisTaskQueue;
+#[cfg(feature = "redis")]
+use stepflow_registry::RedisCache;
+use stepflow_rpc::{EventPublisher, RequestContext, RpcAuthConfig, RpcAuthenticator, RpcError, RpcMethodGuard, RpcPrincipal, RpcServer};
+use stepflow_runtime::StepflowRuntime;
+use tracing::{error, info, warn};
+
+const DEFAULT_CONFIG_PATH: &str = "config.json";
 
 #[derive(Parser)]
 #[command(name = "stepflow-server")]
 #[command(about = "Stepflow Tool System Server")]
 struct Cli {
-    /// Configuration file path
-    #[arg(short, long, default_value = "config.toml")]
-    config: String,
-    
-    /// Log level
-    #[arg(short, long, default_value = "info")]
-    log_level: String,
+    /// Configuration file path [default: config.json, skipped when it does not exist]
+    #[arg(short, long)]
+    config: Option<String>,
+
+    /// Log level, overrides `monitoring.log_level`
+    #[arg(short, long)]
+    log_level: Option<String>,
+
+    /// HTTP bind address, overrides `server.host`
+    #[arg(long)]
+    host: Option<String>,
+
+    /// HTTP port, overrides `server.port`
+    #[arg(short, long)]
+    port: Option<u16>,
+
+    /// RPC port, overrides `server.rpc_port`
+    #[arg(long)]
+    rpc_port: Option<u16>,
+
+    /// Database URL, overrides `database.url`
+    #[arg(long)]
+    database_url: Option<String>,
+
+    /// Override any configuration field by dotted key, e.g. `--set worker_pool.max_workers=32`
+    #[arg(long = "set", value_name = "KEY=VALUE", value_parser = parse_override)]
+    overrides: Vec<(String, String)>,
+
+    /// Print the effective configuration with secrets masked and exit
+    #[arg(long)]
+    check_config: bool,
+}
+
+impl Cli {
+    /// Configuration overrides given on the command line, in increasing precedence
+    fn config_overrides(&self) -> Vec<(String, String)> {
+        let flags = [
+            ("server.host", self.host.clone()),
+            ("server.port", self.port.map(|port| port.to_string())),
+            ("server.rpc_port", self.rpc_port.map(|port| port.to_string())),
+            ("database.url", self.database_url.clone()),
+            ("monitoring.log_level", self.log_level.clone()),
+        ];
+        flags
+            .into_iter()
+            .filter_map(|(key, value)| value.map(|value| (key.to_string(), value)))
+            .chain(self.overrides.iter().cloned())
+            .collect()
+    }
+}
+
+fn parse_override(arg: &str) -> Result<(String, String), String> {
+    arg.split_once('=')
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .ok_or_else(|| format!("expected KEY=VALUE, got `{}`", arg))
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
-    
+    let config = load_config(&cli).await?;
+    let keyring = master_keyring(&config)?;
+
+    if cli.check_config {
+        println!("{}", serde_json::to_string_pretty(&config.redacted())?);
+        return Ok(());
+    }
+
+    if config.server.tls.is_some() {
+        anyhow::bail!("TLS termination is not supported yet; serve behind a TLS-terminating proxy and unset server.tls");
+    }
+
     // Initialize logging
-    tracing_subscriber::fmt()
-        .with_env_filter(&cli.log_level)
-        .init();
-    
+    let logging = init_logging(&LoggingConfig {
+        level: config.monitoring.log_level.clone(),
+        format: if config.monitoring.log_format == "json" { LogOutputFormat::Json } else { LogOutputFormat::Text },
+        ..LoggingConfig::default()
+    })?;
+
     info!("Starting Stepflow Server...");
-    
-    // TODO: Initialize components
-    // TODO: Start API server
-    // TODO: Start monitoring
-    
+
+    let redis = connect_redis(&config).await;
+
+    // One identity for this instance in task leases, leader election and job runs
+    let scheduler_config = SchedulerConfig {
+        shared_queue: config.worker_pool.shared_queue,
+        task_queue: redis.task_queue,
+        ..SchedulerConfig::default()
+    };
+    let instance_id = scheduler_config.instance_id.clone();
+    if let Some(queue) = &scheduler_config.task_queue {
+        info!("Pulling tasks from the {} queue as instance {}", queue.backend(), instance_id);
+    } else if scheduler_config.shared_queue {
+        info!("Pulling tasks from the shared queue as instance {}", instance_id);
+    }
+
+    let mut runtime = StepflowRuntime::builder();
+    match keyring {
+        Some(keyring) => {
+            info!("Encrypting tenant secrets with master key {}", keyring.current_key_id());
+            runtime = runtime.encryption(keyring);
+        }
+        None => warn!("security.encryption_key is not set; tenant secrets are stored in plaintext"),
+    }
+    let runtime = runtime
+        .database_url(&config.database.url)
+        .run_migrations(config.database.enable_migrations)
+        .slow_query_threshold(config.database.slow_query_threshold)
+        .registry_cache_config(RegistryCacheConfig {
+            backend: redis.registry_cache,
+            ..RegistryCacheConfig::from(&config.tools)
+        })
+        .scheduler_config(scheduler_config)
+        .feature_flags(config.feature_flags.clone())
+        .worker_pool_config(WorkerPoolConfig {
+            min_workers: config.worker_pool.min_workers,
+            max_workers: config.worker_pool.max_workers,
+            worker_idle_timeout: config.worker_pool.worker_idle_timeout,
+            work_queue_size: config.worker_pool.work_queue_size,
+            enable_auto_scaling: config.worker_pool.enable_auto_scaling,
+            ..WorkerPoolConfig::default()
+        })
+        .build()
+        .await
+        .context("Failed to start runtime")?;
+    let db = runtime.database();
+    let sandbox = runtime.sandbox().context("Runtime was built without a sandbox")?;
+
+    // One ACL shared by the HTTP API and the RPC server, so tenant lists changed
+    // through the admin API apply to both
+    let network_acl = Arc::new(NetworkAcl::new(&config.security.network_acl));
+    let tenant_acls = load_tenant_network_acls(&db, &network_acl).await
+        .context("Failed to load tenant network ACLs")?;
+    if tenant_acls > 0 {
+        info!("Loaded network ACLs for {} tenant(s)", tenant_acls);
+    }
+
+    // Instances sharing the database elect one leader to run scheduled background jobs
+    let leader = Arc::new(LeaderElection::with_config(db.as_ref().clone(), LeaderElectionConfig {
+        instance_id: instance_id.clone(),
+        ..LeaderElectionConfig::default()
+    }));
+    info!("Campaigning for leadership as instance {}", instance_id);
+    let leader_task = leader.clone().start();
+    let jobs = JobScheduler::with_config(db.as_ref().clone(), JobSchedulerConfig {
+        instance_id,
+        ..JobSchedulerConfig::default()
+    })
+    .with_trigger_overrides(
+        config.job_schedules.iter().map(|(name, schedule)| (name.clone(), JobTrigger::Calendar(schedule.clone()))).collect(),
+    )
+    .with_leader_election(leader.clone());
+    let jobs = Arc::new(jobs);
+
+    let http_addr: SocketAddr = format!("{}:{}", config.server.host, config.server.port).parse()
+        .context("Invalid server address")?;
+    let rpc_addr: SocketAddr = format!("{}:{}", config.server.host, config.server.rpc_port).parse()
+        .context("Invalid RPC address")?;
+    // Created before the jobs so alert notifications can be published on its event bus
+    let rpc_server = RpcServer::new(stepflow_rpc::ServerConfig {
+        bind_addr: rpc_addr,
+        ..Default::default()
+    });
+
+    register_jobs(&jobs, &db, runtime.executor(), rpc_server.event_publisher().clone(), &config)
+        .await
+        .context("Failed to register background jobs")?;
+    for name in config.job_schedules.keys().filter(|name| !jobs.contains(name)) {
+        warn!("Ignoring schedule for unknown background job {}", name);
+    }
+    let jobs_task = jobs.clone().start();
+
+    let mut tool_sessions = ToolSessionManager::new(sandbox.clone(), ToolSessionConfig::default());
+    if let Some(integrity) = runtime.integrity_verifier() {
+        tool_sessions = tool_sessions.with_integrity_verifier(integrity);
+    }
+    let mut state = AppState::with_default_services(db.clone(), runtime.registry(), runtime.executor(), sandbox, api_server_config(&config))
+        .with_tool_session_manager(Arc::new(tool_sessions))
+        .with_logging_handle(logging)
+        .with_network_acl(network_acl.clone())
+        .with_job_scheduler(jobs)
+        .with_leader_election(leader.clone())
+        .with_flags(runtime.flags())
+        .with_gc_policy(config.tools.gc.clone());
+    state.tool_sessions.spawn_reaper(Duration::from_secs(60));
+    if let Some(rate_limits) = redis.rate_limits {
+        state = state.with_rate_limit_service(rate_limits);
+    }
+
+    let rpc_server = rpc_server
+        .with_auth(RpcAuthConfig::new(Arc::new(ApiTokenAuthenticator { auth_service: state.auth_service.clone() })))
+        .with_network_acl(network_acl)
+        .with_method_guard(Arc::new(OperationalModeGuard { state: state.clone() }));
+    // Subscribers of `registry.tool.*` only see changes made after startup and
+    // page through `registry.changes` for anything older
+    let change_feed = Arc::new(ChangeFeed::new(db.clone()));
+    let since = change_feed.latest_sequence().await.context("Failed to read the registry change feed")?;
+    let change_task = change_feed.clone().forward_to(rpc_server.event_publisher().clone(), since, CHANGE_FEED_POLL_INTERVAL);
+    rpc_server.register_handler(Arc::new(ChangeFeedRpcHandler::new(change_feed)));
+    let mut rpc_task = tokio::spawn(Arc::new(rpc_server).serve());
+
+    let listener = tokio::net::TcpListener::bind(http_addr).await
+        .with_context(|| format!("Failed to bind {}", http_addr))?;
+    info!("HTTP API listening on: {}", http_addr);
     info!("Stepflow Server started successfully");
-    
-    // Keep the server running
-    tokio::signal::ctrl_c().await?;
+
+    let app = build_app(state).into_make_service_with_connect_info::<SocketAddr>();
+    let http = async { axum::serve(listener, app).with_graceful_shutdown(shutdown_signal()).await };
+    let result = tokio::select! {
+        result = http => result.context("HTTP server failed"),
+        result = &mut rpc_task => match result {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(e)) => Err(anyhow::Error::new(e).context("RPC server failed")),
+            Err(e) => Err(anyhow::Error::new(e).context("RPC server task failed")),
+        },
+    };
+
+    // The HTTP server has drained its connections; stop accepting RPC connections,
+    // stop scheduling jobs, hand leadership to another instance and close the database
     info!("Shutting down Stepflow Server...");
-    
+    rpc_task.abort();
+    change_task.abort();
+    jobs_task.abort();
+    leader_task.abort();
+    if let Err(e) = leader.resign().await {
+        warn!("Failed to resign leadership: {}", e);
+    }
+    if let Err(e) = runtime.shutdown().await {
+        error!("Failed to close database: {}", e);
+    }
+    result
+}
+
+/// How often registry changes are relayed to RPC subscribers
+const CHANGE_FEED_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long to wait for Redis at startup before falling back
+#[cfg(feature = "redis")]
+const REDIS_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Redis backends enabled in `config.redis` that connected at startup
+#[derive(Default)]
+struct RedisBackends {
+    task_queue: Option<Arc<dyn TaskQueue>>,
+    registry_cache: Option<Arc<dyn CacheBackend>>,
+    rate_limits: Option<Arc<dyn RateLimitService>>,
+}
+
+/// Connect the enabled Redis backends
+///
+/// A backend that cannot connect is left out with a warning, so the instance keeps
+/// serving with the in-process implementation instead of failing to start.
+#[cfg(feature = "redis")]
+async fn connect_redis(config: &Config) -> RedisBackends {
+    let mut backends = RedisBackends::default();
+    let redis = &config.redis;
+    let Some(url) = redis.url.as_deref() else {
+        return backends;
+    };
+    let prefix = redis.key_prefix.as_str();
+    let timeout = |what: &str| format!("timed out connecting the Redis {} after {:?}", what, REDIS_CONNECT_TIMEOUT);
+
+    if redis.task_queue {
+        match tokio::time::timeout(REDIS_CONNECT_TIMEOUT, RedisTaskQueue::connect(url, prefix)).await {
+            Ok(Ok(queue)) => backends.task_queue = Some(Arc::new(queue)),
+            Ok(Err(e)) => warn!("Redis task queue unavailable, using the database: {}", e),
+            Err(_) => warn!("Redis task queue unavailable, using the database: {}", timeout("task queue")),
+        }
+    }
+    if redis.registry_cache && config.tools.tool_cache_size > 0 {
+        match tokio::time::timeout(REDIS_CONNECT_TIMEOUT, RedisCache::connect(url, prefix, config.tools.tool_cache_ttl)).await {
+            Ok(Ok(cache)) => backends.registry_cache = Some(Arc::new(cache)),
+            Ok(Err(e)) => warn!("Redis registry cache unavailable, caching in process: {}", e),
+            Err(_) => warn!("Redis registry cache unavailable, caching in process: {}", timeout("registry cache")),
+        }
+    }
+    if redis.rate_limits {
+        match tokio::time::timeout(REDIS_CONNECT_TIMEOUT, RedisRateLimitService::connect(url, prefix)).await {
+            Ok(Ok(service)) => backends.rate_limits = Some(Arc::new(service)),
+            Ok(Err(e)) => warn!("Redis rate limits unavailable, counting in process: {}", e),
+            Err(_) => warn!("Redis rate limits unavailable, counting in process: {}", timeout("rate limiter")),
+        }
+    }
+
+    let connected: Vec<&str> = [
+        backends.task_queue.is_some().then_some("task queue"),
+        backends.registry_cache.is_some().then_some("registry cache"),
+        backends.rate_limits.is_some().then_some("rate limits"),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    if !connected.is_empty() {
+        info!("Using Redis for: {}", connected.join(", "));
+    }
+    backends
+}
+
+/// Without the `redis` feature every backend stays in process
+#[cfg(not(feature = "redis"))]
+async fn connect_redis(config: &Config) -> RedisBackends {
+    if config.redis.url.is_some() {
+        warn!("redis.url is set but this build lacks the `redis` feature, using in-process backends");
+    }
+    RedisBackends::default()
+}
+
+/// Revoked and expired sessions are kept this long for the session history
+const SESSION_RETENTION_DAYS: i64 = 30;
+
+/// Slow query and slow execution captures are kept this long
+const SLOW_LOG_RETENTION_DAYS: i64 = 14;
+
+/// Register the built-in maintenance jobs
+///
+/// Every instance registers the same jobs; the scheduler's leases make sure each
+/// run happens on only one of them.
+async fn register_jobs(
+    jobs: &JobScheduler,
+    db: &Arc<SqliteDatabase>,
+    executor: Arc<dyn Executor>,
+    events: EventPublisher,
+    config: &Config,
+) -> stepflow_core::StepflowResult<()> {
+    let sessions = Arc::new(SessionRepository::new(db.as_ref().clone()));
+    jobs.register_fn(
+        "session_purge",
+        "Delete sessions that expired or were revoked more than 30 days ago",
+        "17 * * * *".parse().map(JobTrigger::Cron).map_err(StepflowError::ConfigurationError)?,
+        move || {
+            let sessions = sessions.clone();
+            async move {
+                let purged = sessions.purge_sessions(chrono::Utc::now() - chrono::Duration::days(SESSION_RETENTION_DAYS)).await?;
+                Ok(format!("{} session(s) purged", purged))
+            }
+        },
+    ).await?;
+
+    let slow_queries = Arc::new(SlowQueryRepository::new(db.as_ref().clone()));
+    let slow_executions = Arc::new(SlowExecutionLog::new(db.clone()));
+    jobs.register_fn(
+        "slow_log_purge",
+        "Delete slow query and slow execution captures older than 14 days",
+        "43 3 * * *".parse().map(JobTrigger::Cron).map_err(StepflowError::ConfigurationError)?,
+        move || {
+            let (slow_queries, slow_executions) = (slow_queries.clone(), slow_executions.clone());
+            async move {
+                let before = chrono::Utc::now() - chrono::Duration::days(SLOW_LOG_RETENTION_DAYS);
+                let queries = slow_queries.purge(before).await?;
+                let executions = slow_executions.purge(before).await
+                    .map_err(|e| StepflowError::InternalError(e.to_string()))?;
+                Ok(format!("{} slow query(ies) and {} slow execution(s) purged", queries, executions))
+            }
+        },
+    ).await?;
+
+    // Notifications go to the configured webhook and to RPC subscribers of `alert.*`
+    if let Some(alerts) = AlertManager::from_config(db.clone(), &config.monitoring) {
+        let alerts = Arc::new(alerts.with_notifier(Arc::new(EventBusNotifier::new(events))));
+        jobs.register_fn(
+            "alert_evaluation",
+            "Evaluate alert rules and deliver notifications",
+            JobTrigger::interval(Duration::from_secs(60)),
+            move || {
+                let alerts = alerts.clone();
+                async move {
+                    let notifications = alerts.evaluate().await
+                        .map_err(|e| StepflowError::InternalError(e.to_string()))?;
+                    Ok(format!("{} notification(s) sent", notifications.len()))
+                }
+            },
+        ).await?;
+    }
+
+    let directory = Arc::new(DirectorySyncService::new(db.as_ref().clone()));
+    jobs.register_fn(
+        "directory_sync",
+        "Synchronize users from tenant directories",
+        JobTrigger::interval(Duration::from_secs(15 * 60)),
+        move || {
+            let directory = directory.clone();
+            async move {
+                let reports = directory.sync_all().await
+                    .map_err(|e| StepflowError::InternalError(e.to_string()))?;
+                Ok(format!("{} tenant directory(ies) synchronized", reports.len()))
+            }
+        },
+    ).await?;
+
+    // Versions that already have a test run are skipped, so the cursor can
+    // start from the beginning of the change feed on every instance
+    let tests = Arc::new(ToolTestRunner::new(executor, Arc::new(SqliteExecutionStore::new(db.clone()))));
+    let feed = Arc::new(ChangeFeed::new(db.clone()));
+    let cursor = Arc::new(AtomicU64::new(0));
+    jobs.register_fn(
+        "tool_example_tests",
+        "Run the examples of newly registered tools and versions",
+        JobTrigger::interval(Duration::from_secs(60)),
+        move || {
+            let (tests, feed, cursor) = (tests.clone(), feed.clone(), cursor.clone());
+            async move {
+                let context = ToolTestRunner::system_context("system");
+                let (runs, next) = tests.test_registered(&feed, cursor.load(Ordering::SeqCst), &context).await
+                    .map_err(|e| StepflowError::InternalError(e.to_string()))?;
+                cursor.store(next, Ordering::SeqCst);
+                let failed = runs.iter().filter(|run| !run.passed).count();
+                Ok(format!("{} tool version(s) tested, {} failed", runs.len(), failed))
+            }
+        },
+    ).await?;
+
+    if config.tools.gc.enabled {
+        jobs.register(
+            "registry_gc",
+            "Delete data of old tool versions and deleted tools",
+            "29 4 * * *".parse().map(JobTrigger::Cron).map_err(StepflowError::ConfigurationError)?,
+            Arc::new(RegistryGc::new(db.clone(), config.tools.gc.clone())),
+        ).await?;
+    }
+
+    for sink_config in &config.event_sinks {
+        if let Some(feature) = missing_sink_feature(&sink_config.kind) {
+            warn!("Event sink {} disabled: this build lacks the `{}` feature", sink_config.name, feature);
+            continue;
+        }
+        let sink = match connect_event_sink(sink_config).await {
+            Ok(sink) => sink,
+            Err(e) => {
+                warn!("Event sink {} disabled: {}", sink_config.name, e);
+                continue;
+            }
+        };
+        let relay = Arc::new(EventRelay::new(db.clone(), sink, sink_config.clone()));
+        jobs.register_fn(
+            &format!("event_sink:{}", sink_config.name),
+            &format!("Publish events to {:?} sink {}", sink_config.kind, sink_config.name),
+            JobTrigger::interval(sink_config.poll_interval),
+            move || {
+                let relay = relay.clone();
+                async move {
+                    let report = relay.relay().await
+                        .map_err(|e| StepflowError::InternalError(e.to_string()))?;
+                    if !report.stalled.is_empty() {
+                        return Err(StepflowError::InternalError(format!(
+                            "{} event(s) delivered, stalled on {:?}", report.delivered, report.stalled,
+                        )));
+                    }
+                    Ok(format!("{} event(s) delivered, {} dead-lettered", report.delivered, report.dead_lettered))
+                }
+            },
+        ).await?;
+    }
     Ok(())
-} 
\ No newline at end of file
+}
+
+/// Cargo feature that builds the client for `kind`, `None` when this build includes it
+fn missing_sink_feature(kind: &EventSinkKind) -> Option<&'static str> {
+    match kind {
+        EventSinkKind::Nats => (!cfg!(feature = "nats")).then_some("nats"),
+        EventSinkKind::Kafka => (!cfg!(feature = "kafka")).then_some("kafka"),
+    }
+}
+
+/// Layer the configuration file, environment variables and command line flags
+///
+/// An explicitly given file must exist; the default `config.json` is skipped
+/// when missing.
+async fn load_config(cli: &Cli) -> Result<Config> {
+    let loader = DefaultConfigLoader;
+    let mut config = match cli.config.as_deref() {
+        Some(path) => loader.load_from_file(path).await
+            .with_context(|| format!("Failed to load {}", path))?,
+        None if Path::new(DEFAULT_CONFIG_PATH).exists() => loader.load_from_file(DEFAULT_CONFIG_PATH).await
+            .with_context(|| format!("Failed to load {}", DEFAULT_CONFIG_PATH))?,
+        None => Config::default(),
+    };
+    config.apply_env_overrides().context("Invalid environment override")?;
+    let overrides = cli.config_overrides();
+    config.apply_overrides(overrides.iter().map(|(key, value)| (key.as_str(), value.as_str())))
+        .context("Invalid command line override")?;
+    loader.validate(&config).await.context("Invalid configuration")?;
+    Ok(config)
+}
+
+/// Master keyring from `security.encryption_key`, `None` when it is unset
+fn master_keyring(config: &Config) -> Result<Option<MasterKeyring>> {
+    let Some(current) = &config.security.encryption_key else {
+        return Ok(None);
+    };
+    let mut keyring = MasterKeyring::new(MasterKey::from_base64(current).context("Invalid security.encryption_key")?);
+    for previous in &config.security.previous_encryption_keys {
+        let key = MasterKey::from_base64(previous).context("Invalid security.previous_encryption_keys")?;
+        keyring = keyring.with_previous(key);
+    }
+    Ok(Some(keyring))
+}
+
+/// HTTP API settings derived from the server configuration
+fn api_server_config(config: &Config) -> ApiServerConfig {
+    let security = &config.security;
+    ApiServerConfig {
+        host: config.server.host.clone(),
+        port: config.server.port,
+        workers: config.server.workers,
+        max_connections: config.server.max_connections,
+        request_timeout: config.server.request_timeout,
+        enable_cors: security.enable_cors,
+        enable_compression: config.server.enable_compression,
+        enable_logging: config.server.enable_logging,
+        enable_metrics: config.server.enable_metrics,
+        cors_config: CorsConfig {
+            allow_origins: security.cors_origins.clone(),
+            ..CorsConfig::default()
+        },
+        auth_config: AuthConfig {
+            jwt_secret: security.jwt_secret.clone(),
+            jwt_expiration: security.jwt_expiration,
+            max_failed_logins: security.max_login_attempts,
+            lockout_duration: security.lockout_duration,
+            system_tenant: security.system_tenant.clone(),
+            ..AuthConfig::default()
+        },
+        ..ApiServerConfig::default()
+    }
+}
+
+/// Resolve when SIGINT or SIGTERM is received
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            error!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    info!("Shutdown signal received");
+}
+
+/// Authenticates RPC connections with the access tokens issued by the HTTP API
+struct ApiTokenAuthenticator {
+    auth_service: Arc<dyn AuthService>,
+}
+
+#[async_trait::async_trait]
+impl RpcAuthenticator for ApiTokenAuthenticator {
+    async fn authenticate(&self, token: &str) -> Result<RpcPrincipal, RpcError> {
+        let user = self.auth_service.validate_jwt_token(token).await
+            .map_err(|_| RpcError::unauthenticated())?;
+        Ok(RpcPrincipal {
+            subject: user.user_id.as_str().to_string(),
+            tenant_id: user.tenant_id,
+            roles: user.roles,
+            permissions: user.permissions,
+        })
+    }
+}
+
+/// RPC methods that start executions; patterns ending in `*` match by prefix
+const EXECUTION_RPC_METHODS: &[&str] = &["tools.execute*", "workflows.run*"];
+
+/// Holds back RPC-initiated executions while maintenance or read-only mode pauses them
+struct OperationalModeGuard {
+    state: AppState,
+}
+
+#[async_trait::async_trait]
+impl RpcMethodGuard for OperationalModeGuard {
+    async fn check(&self, method: &str, _context: &RequestContext) -> Result<(), RpcError> {
+        let starts_execution = EXECUTION_RPC_METHODS.iter().any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => method.starts_with(prefix),
+            None => *pattern == method,
+        });
+        if !starts_execution {
+            return Ok(());
+        }
+        match execution_pause_reason(&self.state).await {
+            Some(reason) => Err(RpcError::unavailable(&reason)),
+            None => Ok(()),
+        }
+    }
+}
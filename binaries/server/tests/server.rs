@@ -0,0 +1,296 @@
+//! Boots the server binary and talks to it over HTTP and TCP
+
+use std::collections::HashMap;
+use std::net::TcpListener;
+use std::path::Path;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::time::{Duration, Instant};
+
+use serde_json::json;
+use stepflow_core::{Config, TenantId, TenantInfo, UserId, UserInfo, UserRole};
+use stepflow_database::{MigrationManager, SqliteDatabase, TenantRepository, UserRepository};
+
+/// A running server process, killed when dropped
+struct TestServer {
+    child: Child,
+    base_url: String,
+    rpc_port: u16,
+}
+
+impl TestServer {
+    /// Write a config for `database_path` and start the binary with it
+    async fn start(dir: &Path, database_path: &Path) -> Self {
+        Self::start_with(dir, database_path, |_| {}).await
+    }
+
+    /// Like [`TestServer::start`], letting `configure` adjust the config first
+    async fn start_with(dir: &Path, database_path: &Path, configure: impl FnOnce(&mut Config)) -> Self {
+        let mut config = Config::default();
+        config.server.port = free_port();
+        config.server.rpc_port = free_port();
+        config.database.url = database_url(database_path);
+        config.security.secret_key = "integration-test-secret-key-0123456789".to_string();
+        config.security.jwt_secret = "integration-test-jwt-secret-0123456789".to_string();
+        configure(&mut config);
+        let config_path = dir.join("config.json");
+        std::fs::write(&config_path, serde_json::to_string(&config).unwrap()).unwrap();
+
+        let child = Command::new(env!("CARGO_BIN_EXE_stepflow-server"))
+            .arg("--config")
+            .arg(&config_path)
+            .arg("--log-level")
+            .arg("warn")
+            .stdout(Stdio::null())
+            .spawn()
+            .unwrap();
+        let server = Self {
+            child,
+            base_url: format!("http://127.0.0.1:{}", config.server.port),
+            rpc_port: config.server.rpc_port,
+        };
+        server.wait_until_healthy().await;
+        server
+    }
+
+    async fn wait_until_healthy(&self) {
+        let deadline = Instant::now() + Duration::from_secs(60);
+        while Instant::now() < deadline {
+            if let Ok(response) = reqwest::get(format!("{}/health", self.base_url)).await {
+                if response.status().is_success() {
+                    return;
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        panic!("server did not become healthy");
+    }
+
+    /// Send SIGTERM and wait for the process to exit
+    fn terminate(&mut self) -> ExitStatus {
+        let status = Command::new("kill").arg("-TERM").arg(self.child.id().to_string()).status().unwrap();
+        assert!(status.success());
+        let deadline = Instant::now() + Duration::from_secs(30);
+        while Instant::now() < deadline {
+            if let Some(status) = self.child.try_wait().unwrap() {
+                return status;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        panic!("server did not shut down");
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+}
+
+fn database_url(path: &Path) -> String {
+    format!("sqlite://{}?mode=rwc", path.display())
+}
+
+/// Create the schema with a tenant and a user who can log in
+async fn seed_user(database_path: &Path, username: &str, password: &str, role: UserRole) {
+    let db = SqliteDatabase::new(&database_url(database_path)).await.unwrap();
+    MigrationManager::run_migrations(&db).await.unwrap();
+
+    let now = chrono::Utc::now();
+    let tenant = TenantInfo {
+        id: TenantId::new(),
+        name: "acme".to_string(),
+        description: String::new(),
+        domain: None,
+        settings: HashMap::new(),
+        created_at: now,
+        updated_at: now,
+    };
+    TenantRepository::new(db.clone()).create_tenant(&tenant).await.unwrap();
+    let user = UserInfo {
+        id: UserId::new(),
+        username: username.to_string(),
+        email: format!("{}@example.com", username),
+        role,
+        tenant_id: tenant.id,
+        settings: HashMap::new(),
+        email_verified: true,
+        created_at: now,
+        updated_at: now,
+    };
+    UserRepository::new(db.clone()).register_user(&user, password).await.unwrap();
+    db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_server_serves_health_and_shuts_down() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut server = TestServer::start(dir.path(), &dir.path().join("stepflow.db")).await;
+
+    let response = reqwest::get(format!("{}/health/ready", server.base_url)).await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["services"]["database"]["status"], "healthy");
+
+    let response = reqwest::get(format!("{}/api/v1/tools", server.base_url)).await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+    assert!(std::net::TcpStream::connect(("127.0.0.1", server.rpc_port)).is_ok());
+
+    assert!(server.terminate().success());
+    assert!(reqwest::get(format!("{}/health", server.base_url)).await.is_err());
+}
+
+#[tokio::test]
+async fn test_server_login_and_authenticated_request() {
+    let dir = tempfile::tempdir().unwrap();
+    let database_path = dir.path().join("stepflow.db");
+    seed_user(&database_path, "alice", "correct horse battery", UserRole::User).await;
+    let server = TestServer::start(dir.path(), &database_path).await;
+    let client = reqwest::Client::new();
+
+    let response = client.post(format!("{}/api/v1/auth/login", server.base_url))
+        .json(&json!({"username": "alice", "password": "correct horse battery"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let body: serde_json::Value = response.json().await.unwrap();
+    let token = body["access_token"].as_str().unwrap();
+
+    let response = client.get(format!("{}/api/v1/tools", server.base_url)).bearer_auth(token).send().await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+}
+
+/// Log in as `username` and return the names of the registered background jobs
+async fn background_jobs(server: &TestServer, username: &str) -> Vec<String> {
+    let client = reqwest::Client::new();
+    let body: serde_json::Value = client.post(format!("{}/api/v1/auth/login", server.base_url))
+        .json(&json!({"username": username, "password": "correct horse battery"}))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let token = body["access_token"].as_str().unwrap();
+    let body: serde_json::Value = client.get(format!("{}/api/v1/admin/jobs", server.base_url))
+        .bearer_auth(token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    body["jobs"].as_array().unwrap().iter().map(|job| job["name"].as_str().unwrap().to_string()).collect()
+}
+
+#[tokio::test]
+async fn test_alert_evaluation_follows_monitoring_config() {
+    let dir = tempfile::tempdir().unwrap();
+    let database_path = dir.path().join("stepflow.db");
+    seed_user(&database_path, "alice", "correct horse battery", UserRole::Admin).await;
+
+    let mut server = TestServer::start(dir.path(), &database_path).await;
+    let jobs = background_jobs(&server, "alice").await;
+    assert!(jobs.contains(&"session_purge".to_string()), "{:?}", jobs);
+    assert!(!jobs.contains(&"alert_evaluation".to_string()), "{:?}", jobs);
+    assert!(server.terminate().success());
+
+    let server = TestServer::start_with(dir.path(), &database_path, |config| config.monitoring.enable_alerting = true).await;
+    assert!(background_jobs(&server, "alice").await.contains(&"alert_evaluation".to_string()));
+}
+
+/// Run `--check-config` with `config` as the configuration file
+fn check_config(dir: &Path, config: &str, env: &[(&str, &str)], args: &[&str]) -> std::process::Output {
+    let config_path = dir.join("config.json");
+    std::fs::write(&config_path, config).unwrap();
+    Command::new(env!("CARGO_BIN_EXE_stepflow-server"))
+        .arg("--config")
+        .arg(&config_path)
+        .arg("--check-config")
+        .args(args)
+        .envs(env.iter().copied())
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_check_config_layers_file_env_and_flags() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = r#"{
+        "server": {"host": "${STEPFLOW_TEST_HOST:-0.0.0.0}", "port": ${STEPFLOW_TEST_PORT}, "rpc_port": 9001},
+        "security": {"secret_key": "file-secret-key-0123456789abcdef", "jwt_secret": "file-jwt-secret-0123456789abcdef"}
+    }"#;
+    let output = check_config(
+        dir.path(),
+        config,
+        &[("STEPFLOW_TEST_PORT", "9100"), ("STEPFLOW_RPC_PORT", "9200"), ("STEPFLOW_SERVER_PORT", "9300")],
+        &["--port", "9400", "--set", "worker_pool.max_workers=32"],
+    );
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let effective: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(effective["server"]["host"], "0.0.0.0");
+    assert_eq!(effective["server"]["port"], 9400);
+    assert_eq!(effective["server"]["rpc_port"], 9200);
+    assert_eq!(effective["worker_pool"]["max_workers"], 32);
+    assert_eq!(effective["security"]["jwt_secret"], "********");
+}
+
+#[test]
+fn test_check_config_rejects_invalid_config() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let output = check_config(dir.path(), r#"{"server": {"prot": 9000}}"#, &[], &[]);
+    assert!(!output.status.success());
+
+    let output = check_config(dir.path(), r#"{"server": {"port": ${STEPFLOW_TEST_UNSET_PORT}}}"#, &[], &[]);
+    assert!(!output.status.success());
+
+    let secrets = r#"{"security": {"secret_key": "file-secret-key-0123456789abcdef", "jwt_secret": "file-jwt-secret-0123456789abcdef"}}"#;
+    let output = check_config(dir.path(), secrets, &[], &["--set", "worker_pool.min_workers=100"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Invalid worker pool size"));
+
+    // The placeholder and short secrets would let anyone forge tokens
+    let output = check_config(dir.path(), "{}", &[], &[]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("security.secret_key must be set"));
+    let output = check_config(dir.path(), secrets, &[("STEPFLOW_JWT_SECRET", "short")], &[]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("security.jwt_secret must be at least"));
+}
+
+#[test]
+fn test_server_refuses_to_start_without_secrets() {
+    // No config file: the defaults carry the placeholder secrets
+    let dir = tempfile::tempdir().unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_stepflow-server"))
+        .current_dir(dir.path())
+        .env_remove("STEPFLOW_SECRET_KEY")
+        .env_remove("STEPFLOW_JWT_SECRET")
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let deadline = Instant::now() + Duration::from_secs(30);
+    let status = loop {
+        if let Some(status) = child.try_wait().unwrap() {
+            break status;
+        }
+        if Instant::now() > deadline {
+            child.kill().unwrap();
+            panic!("server started with the placeholder secrets");
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    };
+    assert!(!status.success());
+    let mut stderr = String::new();
+    std::io::Read::read_to_string(&mut child.stderr.take().unwrap(), &mut stderr).unwrap();
+    assert!(stderr.contains("must be set"), "{}", stderr);
+}
@@ -0,0 +1,1326 @@
+//! HTTP 应用组装
+//!
+//! 将各模块的路由合并为完整的应用，并按各路由的挂载要求接入认证与会话中间件。
+
+use crate::handlers::health::{detailed_health, health_check, liveness_check};
+use crate::errors::ApiError;
+use crate::middleware::{
+    cors, current_operational_mode, jwt_auth, localize_errors, network_acl, read_only_guard, request_tracing, require_active_session,
+    require_two_factor, require_verified_email, tenant_network_acl,
+};
+use crate::models::responses;
+use crate::routes::{
+    AdminRouter, ApprovalsRouter, AuthRouter, EventsRouter, ExecutionsRouter, MarketplaceRouter, ToolSessionsRouter, ToolsRouter,
+    UsersRouter, WorkflowsRouter,
+};
+use crate::server::AppState;
+use axum::{
+    extract::State,
+    http::StatusCode,
+    middleware::{from_fn, from_fn_with_state},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+
+/// 构建完整的 HTTP 应用
+///
+/// - `/health`、`/health/live`、`/health/ready` 与认证接口无需登录
+/// - 用户自身的接口需要登录，但不要求已验证邮箱，以便申请验证邮件
+/// - 其余接口还需满足邮箱验证策略
+///
+/// 所有请求先经过网络访问控制列表检查；登录后的接口还会按令牌中的租户检查租户列表，
+/// 并检查会话是否有效以及管理员双因素认证策略。
+pub fn build_app(state: AppState) -> Router {
+    let verified = Router::new()
+        .merge(ToolsRouter::new().router())
+        .merge(ExecutionsRouter::new().router())
+        .merge(MarketplaceRouter::new().router())
+        .merge(WorkflowsRouter::new().router())
+        .merge(ApprovalsRouter::new().router())
+        .merge(ToolSessionsRouter::new().router())
+        .merge(EventsRouter::new().router())
+        .merge(AdminRouter::new().router())
+        .route_layer(from_fn_with_state(state.clone(), require_verified_email));
+
+    // route_layer 后添加的先执行：先校验令牌，再检查租户网络列表、会话与双因素认证
+    let authenticated = Router::new()
+        .merge(UsersRouter::new().router())
+        .merge(verified)
+        .route_layer(from_fn_with_state(state.clone(), require_two_factor))
+        .route_layer(from_fn_with_state(state.clone(), require_active_session))
+        .route_layer(from_fn_with_state(state.clone(), tenant_network_acl))
+        .route_layer(from_fn_with_state(state.config.auth_config.jwt_secret.clone(), jwt_auth));
+
+    Router::new()
+        .route("/health", get(health))
+        .route("/health/live", get(liveness_check))
+        .route("/health/ready", get(readiness))
+        .merge(AuthRouter::new().router())
+        .merge(authenticated)
+        .layer(from_fn_with_state(state.clone(), read_only_guard))
+        .layer(from_fn_with_state(state.clone(), cors))
+        .layer(from_fn_with_state(state.clone(), network_acl))
+        .layer(from_fn(localize_errors))
+        .layer(from_fn(request_tracing))
+        .with_state(state)
+}
+
+/// 健康检查，附带当前运行模式与领导者状态
+async fn health(State(state): State<AppState>) -> Result<Json<responses::HealthResponse>, ApiError> {
+    let Json(mut health) = health_check().await?;
+    health.mode = Some(current_operational_mode(&state).await.mode);
+    health.leadership = state.leader.as_ref().map(|leader| leader.status());
+    Ok(Json(health))
+}
+
+/// 就绪检查：数据库、注册表、执行器和沙箱都健康时返回 200，否则返回 503
+///
+/// 维护模式和只读模式下仍可处理读请求，因此不影响就绪状态，只在响应中标明；
+/// 非领导者实例同样可以处理请求，领导者状态也只在响应中标明。
+async fn readiness(State(state): State<AppState>) -> Response {
+    match detailed_health(State(responses::AppState::from(&state))).await {
+        Ok(Json(mut health)) => {
+            health.mode = Some(current_operational_mode(&state).await.mode);
+            health.leadership = state.leader.as_ref().map(|leader| leader.status());
+            let status = if health.status == "healthy" { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+            (status, Json(health)).into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ServerConfig;
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+    use stepflow_core::{
+        AclRules, Database, ExecutionId, NetworkAcl, NetworkAclConfig, TenantId, TenantInfo, ToolId, ToolInfo, ToolLocalization,
+        ToolSessionCommand, ToolType, UserId, UserInfo, UserRole,
+    };
+    use stepflow_database::{
+        JobScheduler, JobTrigger, LeaderElection, MigrationManager, OperationalMode, OperationalModeRepository, SqliteDatabase,
+        TenantRepository, ToolRepository, UserRepository,
+    };
+    use stepflow_executor::{create_default_executor, ExecutionState, TimelineRecorder};
+    use stepflow_registry::RegistryImpl;
+    use crate::tool_sessions::{ToolSessionConfig, ToolSessionManager};
+    use stepflow_sandbox::{IsolationType, SandboxConfig, SandboxImpl, SandboxImplConfig};
+
+    /// 在随机端口上启动应用，返回基础 URL
+    async fn serve_test_app() -> (String, Arc<SqliteDatabase>) {
+        serve_test_app_with_acl(Arc::new(NetworkAcl::default())).await
+    }
+
+    async fn serve_test_app_with_acl(network_acl: Arc<NetworkAcl>) -> (String, Arc<SqliteDatabase>) {
+        serve_test_app_with(|state| state.with_network_acl(network_acl)).await
+    }
+
+    /// 在随机端口上启动应用，启动前由 `configure` 调整应用状态
+    async fn serve_test_app_with(configure: impl FnOnce(AppState) -> AppState) -> (String, Arc<SqliteDatabase>) {
+        let db = Arc::new(SqliteDatabase::new("sqlite::memory:").await.unwrap());
+        MigrationManager::run_migrations(&db).await.unwrap();
+        let registry = Arc::new(RegistryImpl::new(db.clone()).await.unwrap());
+        let executor = Arc::new(create_default_executor(db.clone(), registry.clone()).unwrap());
+        let sandbox = Arc::new(SandboxImpl::new(db.clone(), SandboxImplConfig {
+            enable_monitoring: false,
+            ..SandboxImplConfig::default()
+        }).await.unwrap());
+        let state = configure(AppState::with_default_services(db.clone(), registry, executor, sandbox, ServerConfig::default()));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = build_app(state).into_make_service_with_connect_info::<SocketAddr>();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        (format!("http://{}", addr), db)
+    }
+
+    /// 测试中作为系统租户的租户 ID
+    const SYSTEM_TENANT: &str = "system";
+
+    /// 把 [`SYSTEM_TENANT`] 配置为系统租户
+    fn with_system_tenant(mut state: AppState) -> AppState {
+        state.config.auth_config.system_tenant = Some(SYSTEM_TENANT.to_string());
+        state
+    }
+
+    /// 创建租户和管理员并登录，返回租户 ID 与访问令牌
+    async fn login_as_admin(client: &reqwest::Client, base: &str, db: &SqliteDatabase) -> (TenantId, String) {
+        login_as_admin_of(client, base, db, TenantId::new()).await
+    }
+
+    /// 创建系统租户和管理员并登录，返回访问令牌
+    async fn login_as_system_admin(client: &reqwest::Client, base: &str, db: &SqliteDatabase) -> String {
+        login_as_admin_of(client, base, db, TenantId::from_string(SYSTEM_TENANT.to_string())).await.1
+    }
+
+    /// 创建指定 ID 的租户和管理员并登录，返回租户 ID 与访问令牌
+    async fn login_as_admin_of(client: &reqwest::Client, base: &str, db: &SqliteDatabase, tenant_id: TenantId) -> (TenantId, String) {
+        let now = chrono::Utc::now();
+        let tenant = TenantInfo {
+            id: tenant_id,
+            name: "acme".to_string(),
+            description: String::new(),
+            domain: None,
+            settings: HashMap::new(),
+            created_at: now,
+            updated_at: now,
+        };
+        TenantRepository::new(db.clone()).create_tenant(&tenant).await.unwrap();
+        let admin = UserInfo {
+            id: UserId::new(),
+            username: "alice".to_string(),
+            email: "alice@example.com".to_string(),
+            role: UserRole::Admin,
+            tenant_id: tenant.id.clone(),
+            settings: HashMap::new(),
+            email_verified: true,
+            created_at: now,
+            updated_at: now,
+        };
+        UserRepository::new(db.clone()).register_user(&admin, "correct horse battery").await.unwrap();
+
+        let response = client.post(format!("{}/api/v1/auth/login", base))
+            .json(&json!({"username": "alice", "password": "correct horse battery"}))
+            .send()
+            .await
+            .unwrap();
+        let body: serde_json::Value = response.json().await.unwrap();
+        (tenant.id, body["access_token"].as_str().unwrap().to_string())
+    }
+
+    #[tokio::test]
+    async fn test_health_is_public() {
+        let (base, _db) = serve_test_app().await;
+        let client = reqwest::Client::new();
+
+        let response = client.get(format!("{}/health", base)).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["status"], "healthy");
+
+        let response = client.get(format!("{}/health/ready", base)).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_protected_routes_require_login() {
+        let (base, db) = serve_test_app().await;
+        let client = reqwest::Client::new();
+
+        let response = client.get(format!("{}/api/v1/tools", base)).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        let now = chrono::Utc::now();
+        let tenant = TenantInfo {
+            id: TenantId::new(),
+            name: "acme".to_string(),
+            description: String::new(),
+            domain: None,
+            settings: HashMap::new(),
+            created_at: now,
+            updated_at: now,
+        };
+        TenantRepository::new(db.as_ref().clone()).create_tenant(&tenant).await.unwrap();
+        let user = UserInfo {
+            id: UserId::new(),
+            username: "alice".to_string(),
+            email: "alice@example.com".to_string(),
+            role: UserRole::User,
+            tenant_id: tenant.id.clone(),
+            settings: HashMap::new(),
+            email_verified: true,
+            created_at: now,
+            updated_at: now,
+        };
+        UserRepository::new(db.as_ref().clone()).register_user(&user, "correct horse battery").await.unwrap();
+
+        let response = client.post(format!("{}/api/v1/auth/login", base))
+            .json(&json!({"username": "alice", "password": "correct horse battery"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let body: serde_json::Value = response.json().await.unwrap();
+        let token = body["access_token"].as_str().unwrap();
+
+        let response = client.get(format!("{}/api/v1/tools", base)).bearer_auth(token).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_token_only_refreshes() {
+        let (base, db) = serve_test_app().await;
+        let client = reqwest::Client::new();
+        login_as_admin(&client, &base, &db).await;
+
+        let response = client.post(format!("{}/api/v1/auth/login", base))
+            .json(&json!({"username": "alice", "password": "correct horse battery"}))
+            .send()
+            .await
+            .unwrap();
+        let body: serde_json::Value = response.json().await.unwrap();
+        let access_token = body["access_token"].as_str().unwrap();
+        let refresh_token = body["refresh_token"].as_str().unwrap();
+        assert_ne!(access_token, refresh_token);
+
+        // 刷新令牌不能直接调用 API
+        let response = client.get(format!("{}/api/v1/tools", base)).bearer_auth(refresh_token).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        // 访问令牌也不能用来刷新
+        let response = client.post(format!("{}/api/v1/auth/refresh", base))
+            .json(&json!({"refresh_token": access_token}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        let response = client.post(format!("{}/api/v1/auth/refresh", base))
+            .json(&json!({"refresh_token": refresh_token}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["refresh_token"], refresh_token);
+        let refreshed = body["access_token"].as_str().unwrap();
+        let response = client.get(format!("{}/api/v1/tools", base)).bearer_auth(refreshed).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_localized_errors_and_tool_descriptions() {
+        let (base, db) = serve_test_app().await;
+        let client = reqwest::Client::new();
+        let tool = ToolInfo::builder()
+            .id(ToolId::from_string("echo".to_string()))
+            .name("Echo")
+            .description("Returns its input")
+            .tool_type(ToolType::Custom("echo".to_string()))
+            .author("stepflow")
+            .localization("zh", ToolLocalization {
+                name: Some("回显".to_string()),
+                description: None,
+            })
+            .build()
+            .unwrap();
+        ToolRepository::new(db.as_ref().clone()).create_tool(&tool).await.unwrap();
+
+        // 未登录的错误响应按请求语言返回，英文保持原样
+        let response = client.get(format!("{}/api/v1/tools", base))
+            .header("Accept-Language", "zh-CN,en;q=0.8")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers()["content-language"], "zh");
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["error"]["code"], "UNAUTHORIZED");
+        assert!(body["error"]["message"].as_str().unwrap().starts_with("未认证："));
+        let response = client.get(format!("{}/api/v1/tools", base)).header("Accept-Language", "en").send().await.unwrap();
+        assert!(response.headers().get("content-language").is_none());
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert!(body["error"]["message"].as_str().unwrap().starts_with("Unauthorized: "));
+
+        // 工具名称使用翻译，缺少翻译的描述回退到默认文本
+        let (_tenant_id, token) = login_as_admin(&client, &base, &db).await;
+        let list = |language: &'static str| client.get(format!("{}/api/v1/tools", base))
+            .bearer_auth(&token)
+            .header("Accept-Language", language)
+            .send();
+        let body: serde_json::Value = list("zh-TW").await.unwrap().json().await.unwrap();
+        assert_eq!(body["tools"][0]["name"], "回显");
+        assert_eq!(body["tools"][0]["description"], "Returns its input");
+        let body: serde_json::Value = list("fr, en;q=0.5").await.unwrap().json().await.unwrap();
+        assert_eq!(body["tools"][0]["name"], "Echo");
+    }
+
+    #[tokio::test]
+    async fn test_global_network_acl_uses_trusted_proxy_header() {
+        let acl = Arc::new(NetworkAcl::new(&NetworkAclConfig {
+            deny: vec!["203.0.113.0/24".parse().unwrap()],
+            trusted_proxies: vec!["127.0.0.1".parse().unwrap()],
+            ..NetworkAclConfig::default()
+        }));
+        let (base, _db) = serve_test_app_with_acl(acl.clone()).await;
+        let client = reqwest::Client::new();
+
+        let response = client.get(format!("{}/health", base)).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+        let response = client.get(format!("{}/health", base))
+            .header("x-forwarded-for", "203.0.113.7")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+
+        // 客户端伪造的最左侧地址不会被采用
+        let response = client.get(format!("{}/health", base))
+            .header("x-forwarded-for", "10.0.0.1, 203.0.113.7")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+        assert_eq!(acl.metrics().rejected_global, 2);
+
+        acl.set_global_rules(AclRules::default());
+        let response = client.get(format!("{}/health", base))
+            .header("x-forwarded-for", "203.0.113.7")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_session_ignores_untrusted_forwarded_for() {
+        let (base, db) = serve_test_app().await;
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert("x-forwarded-for", reqwest::header::HeaderValue::from_static("203.0.113.7"));
+        let client = reqwest::Client::builder().default_headers(headers).build().unwrap();
+
+        login_as_admin(&client, &base, &db).await;
+
+        // 未配置可信代理时，会话记录的是对端地址而不是客户端声明的地址
+        let sessions = db.execute("SELECT ip_address FROM user_sessions", &[]).await.unwrap();
+        assert_eq!(sessions.rows[0]["ip_address"], json!("127.0.0.1"));
+    }
+
+    #[tokio::test]
+    async fn test_tenant_network_acl_applies_after_login() {
+        let acl = Arc::new(NetworkAcl::default());
+        let (base, db) = serve_test_app_with_acl(acl.clone()).await;
+        let client = reqwest::Client::new();
+
+        let (tenant_id, token) = login_as_admin(&client, &base, &db).await;
+        let network_url = format!("{}/api/v1/admin/security/network", base);
+
+        // 不允许保存会拒绝当前客户端地址的列表
+        let response = client.put(&network_url)
+            .bearer_auth(&token)
+            .json(&json!({"allow": ["10.0.0.0/8"]}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+
+        let response = client.put(&network_url)
+            .bearer_auth(&token)
+            .json(&json!({"allow": ["127.0.0.0/8"], "deny": ["127.0.0.2"]}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let stored = TenantRepository::new(db.as_ref().clone()).get_tenant(&tenant_id).await.unwrap().unwrap();
+        assert!(stored.settings.contains_key(crate::types::NETWORK_ACL_SETTING));
+
+        let response = client.get(&network_url).bearer_auth(&token).send().await.unwrap();
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["rules"]["allow"], json!(["127.0.0.0/8"]));
+        assert_eq!(body["rules"]["deny"], json!(["127.0.0.2/32"]));
+
+        // 管理员在运行时收紧列表后，已登录的请求立即被拒绝
+        acl.set_tenant_rules(tenant_id.as_str(), None, AclRules {
+            allow: vec!["10.0.0.0/8".parse().unwrap()],
+            deny: vec![],
+        });
+        let response = client.get(format!("{}/api/v1/tools", base)).bearer_auth(&token).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+        assert_eq!(acl.metrics().rejected_tenant, 1);
+    }
+
+    #[tokio::test]
+    async fn test_maintenance_and_read_only_modes() {
+        let (base, db) = serve_test_app_with(with_system_tenant).await;
+        let client = reqwest::Client::new();
+        let token = login_as_system_admin(&client, &base, &db).await;
+        let mode_url = format!("{}/api/v1/admin/system/mode", base);
+        let set_mode = |body: serde_json::Value| client.put(&mode_url).bearer_auth(&token).json(&body).send();
+
+        let response = set_mode(json!({"mode": "maintenance", "message": "Back at 10:00 UTC"})).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let stored = OperationalModeRepository::new(db.as_ref().clone()).get().await.unwrap();
+        assert_eq!(stored.mode, OperationalMode::Maintenance);
+
+        let body: serde_json::Value = client.get(format!("{}/health", base)).send().await.unwrap().json().await.unwrap();
+        assert_eq!(body["mode"], "maintenance");
+        let response = client.get(format!("{}/health/ready", base)).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+        // 维护模式拒绝新的执行，但读请求与其它写请求照常处理
+        let response = client.post(format!("{}/api/v1/executions/run-sync", base))
+            .bearer_auth(&token)
+            .json(&json!({"tool_id": "echo", "input": {}}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert!(body.to_string().contains("Back at 10:00 UTC"));
+        let response = client.get(format!("{}/api/v1/tools", base)).bearer_auth(&token).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let view = json!({"name": "failed", "filter": {}});
+        let response = client.post(format!("{}/api/v1/execution-views", base))
+            .bearer_auth(&token)
+            .json(&view)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+        // 只读模式拒绝所有写请求，切换模式本身除外
+        let response = set_mode(json!({"mode": "read_only"})).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let response = client.post(format!("{}/api/v1/execution-views", base))
+            .bearer_auth(&token)
+            .json(&view)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+        let response = client.get(format!("{}/api/v1/execution-views", base)).bearer_auth(&token).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        // OIDC 回调虽是 GET 请求，但会创建用户与会话
+        let response = client.get(format!("{}/api/v1/auth/oidc/callback?code=abc&state=xyz", base)).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+
+        let response = set_mode(json!({"mode": "normal"})).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let body: serde_json::Value = client.get(format!("{}/health", base)).send().await.unwrap().json().await.unwrap();
+        assert_eq!(body["mode"], "normal");
+    }
+
+    #[tokio::test]
+    async fn test_tenant_admins_cannot_change_deployment_settings() {
+        let (base, db) = serve_test_app_with(with_system_tenant).await;
+        let client = reqwest::Client::new();
+        let (_tenant_id, token) = login_as_admin(&client, &base, &db).await;
+
+        let response = client.put(format!("{}/api/v1/admin/system/mode", base))
+            .bearer_auth(&token)
+            .json(&json!({"mode": "read_only"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+        let stored = OperationalModeRepository::new(db.as_ref().clone()).get().await.unwrap();
+        assert_eq!(stored.mode, OperationalMode::Normal);
+
+        let response = client.put(format!("{}/api/v1/admin/logging", base))
+            .bearer_auth(&token)
+            .json(&json!({"level": "trace"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+
+        let response = client.post(format!("{}/api/v1/admin/jobs/cleanup/run", base)).bearer_auth(&token).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+
+        for gc in ["storage", "registry"] {
+            let response = client.post(format!("{}/api/v1/admin/{}/gc", base, gc))
+                .bearer_auth(&token)
+                .json(&json!({"dry_run": false}))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+        }
+
+        let response = client.post(format!("{}/api/v1/admin/security/encryption/rewrap", base)).bearer_auth(&token).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_feature_flag_admin() {
+        let (base, db) = serve_test_app().await;
+        let client = reqwest::Client::new();
+        let (tenant_id, token) = login_as_admin(&client, &base, &db).await;
+        let flag_url = format!("{}/api/v1/admin/flags/new_scheduler", base);
+        let my_flags = || async {
+            let response = client.get(format!("{}/api/v1/users/me/flags", base)).bearer_auth(&token).send().await.unwrap();
+            response.json::<serde_json::Value>().await.unwrap()
+        };
+
+        let body = json!({"rollout_percentage": 0, "tenants": {tenant_id.as_str(): true}});
+        let response = client.put(&flag_url).bearer_auth(&token).json(&body).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let stored: serde_json::Value = response.json().await.unwrap();
+        assert!(stored["updated_by"].is_string());
+        assert_eq!(my_flags().await["flags"]["new_scheduler"], true);
+
+        let response = client.get(format!("{}/api/v1/admin/flags", base)).bearer_auth(&token).send().await.unwrap();
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["flags"][0]["name"], "new_scheduler");
+
+        // 非法名称与超出范围的比例被拒绝
+        let response = client.put(&flag_url).bearer_auth(&token).json(&json!({"rollout_percentage": 101})).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+        let response = client.put(format!("{}/api/v1/admin/flags/Bad%20Name", base))
+            .bearer_auth(&token)
+            .json(&json!({}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+
+        let response = client.delete(&flag_url).bearer_auth(&token).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert_eq!(my_flags().await["flags"], json!({}));
+        let response = client.delete(&flag_url).bearer_auth(&token).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+
+        // 租户管理员不能修改作用于其它租户的开关
+        for body in [json!({"rollout_percentage": 50}), json!({"tenants": {"other": true}}), json!({"users": {"bob": true}})] {
+            let response = client.put(&flag_url).bearer_auth(&token).json(&body).send().await.unwrap();
+            assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_global_feature_flags_require_system_admin() {
+        let (base, db) = serve_test_app_with(with_system_tenant).await;
+        let client = reqwest::Client::new();
+        let system_token = login_as_system_admin(&client, &base, &db).await;
+        let now = chrono::Utc::now();
+        let tenant = TenantInfo {
+            id: TenantId::new(),
+            name: "globex".to_string(),
+            description: String::new(),
+            domain: None,
+            settings: HashMap::new(),
+            created_at: now,
+            updated_at: now,
+        };
+        TenantRepository::new(db.as_ref().clone()).create_tenant(&tenant).await.unwrap();
+        let admin = UserInfo {
+            id: UserId::new(),
+            username: "bob".to_string(),
+            email: "bob@example.com".to_string(),
+            role: UserRole::Admin,
+            tenant_id: tenant.id.clone(),
+            settings: HashMap::new(),
+            email_verified: true,
+            created_at: now,
+            updated_at: now,
+        };
+        UserRepository::new(db.as_ref().clone()).register_user(&admin, "correct horse battery").await.unwrap();
+        let body: serde_json::Value = client.post(format!("{}/api/v1/auth/login", base))
+            .json(&json!({"username": "bob", "password": "correct horse battery"}))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let tenant_token = body["access_token"].as_str().unwrap();
+        let flag_url = format!("{}/api/v1/admin/flags/new_scheduler", base);
+
+        let response = client.put(&flag_url).bearer_auth(&system_token).json(&json!({"rollout_percentage": 50})).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+        // 即使新定义只作用于本租户，也不能覆盖或删除全局开关
+        let body = json!({"tenants": {tenant.id.as_str(): false}});
+        let response = client.put(&flag_url).bearer_auth(tenant_token).json(&body).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+        let response = client.delete(&flag_url).bearer_auth(tenant_token).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+
+        let response = client.delete(&flag_url).bearer_auth(&system_token).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_notification_channels() {
+        let (base, db) = serve_test_app().await;
+        let client = reqwest::Client::new();
+        let (_tenant_id, token) = login_as_admin(&client, &base, &db).await;
+        let channels_url = format!("{}/api/v1/admin/notifications/channels", base);
+
+        let channel = json!({
+            "name": "on-call",
+            "target": {"type": "email", "recipients": ["oncall@example.com"]},
+            "events": ["alert.*", "approval.requested"]
+        });
+        let response = client.post(&channels_url).bearer_auth(&token).json(&channel).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let created: serde_json::Value = response.json().await.unwrap();
+        let channel_id = created["id"].as_str().unwrap().to_string();
+
+        let invalid = json!({"name": "slack", "target": {"type": "slack", "webhook_url": "not-a-url"}});
+        let response = client.post(&channels_url).bearer_auth(&token).json(&invalid).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+
+        // 测试消息经默认的日志邮件发送器投递并留下记录
+        let response = client.post(format!("{}/{}/test", channels_url, channel_id)).bearer_auth(&token).send().await.unwrap();
+        let delivery: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(delivery["status"], "delivered");
+        let response = client.get(format!("{}/api/v1/admin/notifications/deliveries?channel_id={}", base, channel_id))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .unwrap();
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["deliveries"].as_array().unwrap().len(), 1);
+
+        let body: serde_json::Value = client.get(&channels_url).bearer_auth(&token).send().await.unwrap().json().await.unwrap();
+        assert_eq!(body["channels"][0]["target"]["type"], "email");
+        let response = client.delete(format!("{}/{}", channels_url, channel_id)).bearer_auth(&token).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let response = client.post(format!("{}/{}/test", channels_url, channel_id)).bearer_auth(&token).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_group_endpoints() {
+        let (base, db) = serve_test_app().await;
+        let client = reqwest::Client::new();
+        let (_tenant_id, token) = login_as_admin(&client, &base, &db).await;
+
+        let body: serde_json::Value = client.get(format!("{}/api/v1/executions/concurrency-groups", base))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(body["groups"], json!([]));
+
+        let response = client.get(format!("{}/api/v1/executions/missing/queue-position", base))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+
+        // 并发组格式错误时拒绝提交
+        let response = client.post(format!("{}/api/v1/executions/run-sync", base))
+            .bearer_auth(&token)
+            .json(&json!({"tool_id": "echo", "concurrency": {"key": "echo", "max_concurrency": "two"}}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    /// 注册一个没有本地化信息的 `echo` 工具
+    async fn create_echo_tool(db: &SqliteDatabase) {
+        let tool = ToolInfo::builder()
+            .id(ToolId::from_string("echo".to_string()))
+            .name("Echo")
+            .description("Returns its input")
+            .tool_type(ToolType::Custom("echo".to_string()))
+            .author("stepflow")
+            .build()
+            .unwrap();
+        ToolRepository::new(db.clone()).create_tool(&tool).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_only_tool_authors_can_publish() {
+        let (base, db) = serve_test_app().await;
+        let client = reqwest::Client::new();
+        let (tenant_id, token) = login_as_admin(&client, &base, &db).await;
+        create_echo_tool(&db).await;
+        let own = ToolInfo::builder()
+            .id(ToolId::from_string("own".to_string()))
+            .name("Own")
+            .tool_type(ToolType::Custom("echo".to_string()))
+            .author(tenant_id.as_str())
+            .build()
+            .unwrap();
+        ToolRepository::new(db.as_ref().clone()).create_tool(&own).await.unwrap();
+        let user = UserInfo {
+            id: UserId::new(),
+            username: "bob".to_string(),
+            email: "bob@example.com".to_string(),
+            role: UserRole::User,
+            tenant_id: tenant_id.clone(),
+            settings: HashMap::new(),
+            email_verified: true,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        };
+        UserRepository::new(db.as_ref().clone()).register_user(&user, "correct horse battery").await.unwrap();
+        let body: serde_json::Value = client.post(format!("{}/api/v1/auth/login", base))
+            .json(&json!({"username": "bob", "password": "correct horse battery"}))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let user_token = body["access_token"].as_str().unwrap();
+        let publish = |token: &str, tool_id: &str| {
+            client.post(format!("{}/api/v1/marketplace/listings", base))
+                .bearer_auth(token)
+                .json(&json!({"tool_id": tool_id}))
+                .send()
+        };
+
+        // 工具作者不是本租户时，管理员也不能发布
+        assert_eq!(publish(&token, "echo").await.unwrap().status(), reqwest::StatusCode::FORBIDDEN);
+        assert_eq!(publish(user_token, "own").await.unwrap().status(), reqwest::StatusCode::FORBIDDEN);
+        assert_eq!(publish(&token, "own").await.unwrap().status(), reqwest::StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_tool_environment_preview() {
+        let (base, db) = serve_test_app().await;
+        let client = reqwest::Client::new();
+        let (_tenant_id, token) = login_as_admin(&client, &base, &db).await;
+        let tool = ToolInfo::builder()
+            .id(ToolId::from_string("envtool".to_string()))
+            .name("Env")
+            .tool_type(ToolType::Custom("echo".to_string()))
+            .env("REGION", "us-east-1")
+            .env("LOG_LEVEL", "info")
+            .build()
+            .unwrap();
+        ToolRepository::new(db.as_ref().clone()).create_tool(&tool).await.unwrap();
+
+        let response = client.put(format!("{}/api/v1/tools/envtool/config", base))
+            .bearer_auth(&token)
+            .json(&json!({
+                "environment": {"REGION": "eu-west-1", "DB_PASSWORD": "${secret:db}"},
+                "secrets": {"db": "hunter2"}
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+        let preview: serde_json::Value = client.post(format!("{}/api/v1/tools/envtool/config/environment", base))
+            .bearer_auth(&token)
+            .json(&json!({"environment": {"LOG_LEVEL": "debug"}}))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let variables = preview["variables"].as_array().unwrap();
+        let variable = |name: &str| variables.iter().find(|v| v["name"] == name).unwrap().clone();
+        assert_eq!(variables.len(), 3);
+        assert_eq!(variable("DB_PASSWORD")["value"], "********");
+        assert_eq!(variable("DB_PASSWORD")["secret"], true);
+        assert_eq!(variable("REGION")["source"], "tenant_config");
+        assert_eq!(variable("LOG_LEVEL")["value"], "debug");
+        assert_eq!(variable("LOG_LEVEL")["source"], "request");
+        assert!(!preview.to_string().contains("hunter2"));
+
+        // 引用不存在的密钥时拒绝保存
+        let response = client.put(format!("{}/api/v1/tools/envtool/config", base))
+            .bearer_auth(&token)
+            .json(&json!({"environment": {"TOKEN": "${secret:missing}"}}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_tool_presets() {
+        let (base, db) = serve_test_app().await;
+        let client = reqwest::Client::new();
+        let (tenant_id, token) = login_as_admin(&client, &base, &db).await;
+        create_echo_tool(&db).await;
+        let presets = format!("{}/api/v1/tools/echo/presets", base);
+
+        let response = client.post(&presets)
+            .bearer_auth(&token)
+            .json(&json!({"name": "prod", "parameters": {"region": "eu", "retries": 3}, "shared": true}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let shared: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(shared["tenant_id"], tenant_id.to_string());
+        assert!(shared["user_id"].is_null());
+
+        let private: serde_json::Value = client.post(&presets)
+            .bearer_auth(&token)
+            .json(&json!({"name": "mine", "parameters": {"region": "us"}}))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert!(private["user_id"].is_string());
+
+        let body: serde_json::Value = client.get(&presets).bearer_auth(&token).send().await.unwrap().json().await.unwrap();
+        assert_eq!(body["presets"].as_array().unwrap().len(), 2);
+
+        // 预设名称不能为空
+        let response = client.post(&presets).bearer_auth(&token).json(&json!({"name": ""})).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+
+        // 引用不存在的预设时拒绝执行
+        let response = client.post(format!("{}/api/v1/executions/run-sync", base))
+            .bearer_auth(&token)
+            .json(&json!({"tool_id": "echo", "preset": "staging"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+
+        let preset_url = format!("{}/{}", presets, private["id"].as_str().unwrap());
+        let updated: serde_json::Value = client.put(&preset_url)
+            .bearer_auth(&token)
+            .json(&json!({"name": "mine-v2", "parameters": {"region": "ap"}}))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(updated["name"], "mine-v2");
+        assert_eq!(updated["parameters"], json!({"region": "ap"}));
+
+        let response = client.delete(&preset_url).bearer_auth(&token).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let response = client.get(&preset_url).bearer_auth(&token).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_tool_docs() {
+        let (base, db) = serve_test_app().await;
+        let client = reqwest::Client::new();
+        let (_tenant_id, token) = login_as_admin(&client, &base, &db).await;
+        create_echo_tool(&db).await;
+        let docs = format!("{}/api/v1/tools/echo/docs", base);
+
+        let response = client.get(&docs).bearer_auth(&token).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+
+        let saved: serde_json::Value = client.put(&docs)
+            .bearer_auth(&token)
+            .json(&json!({"markdown": "# Echo\n\n<script>alert(1)</script>\n\n```example hello\n{\"text\": \"hi\"}\n```\n"}))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(saved["version"], "1.0.0");
+
+        // 默认返回清理后的 HTML
+        let response = client.get(&docs).bearer_auth(&token).send().await.unwrap();
+        assert!(response.headers()["content-type"].to_str().unwrap().starts_with("text/html"));
+        let html = response.text().await.unwrap();
+        assert!(html.contains("<h1>Echo</h1>"));
+        assert!(!html.contains("<script"));
+        assert!(html.contains("stepflow-example"));
+
+        let rendered: serde_json::Value = client.get(format!("{}?format=json", docs))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(rendered["version"], "1.0.0");
+        // echo 工具没有名为 hello 的示例，代码块不可运行
+        assert_eq!(rendered["examples"], json!([]));
+
+        let response = client.get(format!("{}?version=2.0.0", docs)).bearer_auth(&token).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_registry_gc() {
+        let (base, db) = serve_test_app_with(with_system_tenant).await;
+        let client = reqwest::Client::new();
+        let (tenant_id, token) = login_as_admin_of(&client, &base, &db, TenantId::from_string(SYSTEM_TENANT.to_string())).await;
+        create_echo_tool(&db).await;
+        let gc_url = format!("{}/api/v1/admin/registry/gc", base);
+        db.execute(
+            "INSERT INTO tool_presets (id, tenant_id, tool_id, name, parameters, created_by, created_at, updated_at) \
+             VALUES ('p1', ?, 'deleted-tool', 'old', '{}', 'admin', '2026-01-01T00:00:00Z', '2026-01-01T00:00:00Z')",
+            &[serde_json::Value::String(tenant_id.to_string())],
+        ).await.unwrap();
+
+        // 默认只生成报告
+        let report: serde_json::Value = client.post(&gc_url).bearer_auth(&token).json(&json!({})).send().await.unwrap().json().await.unwrap();
+        assert_eq!(report["dry_run"], true);
+        assert_eq!(report["total_rows"], 1);
+        let report: serde_json::Value = client.post(&gc_url)
+            .bearer_auth(&token)
+            .json(&json!({"dry_run": false}))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(report["total_rows"], 1);
+        assert!(db.execute("SELECT 1 FROM tool_presets", &[]).await.unwrap().rows.is_empty());
+
+        let response = client.post(&gc_url).bearer_auth(&token).json(&json!({"keep_versions": 0})).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_admin_overview() {
+        let (base, db) = serve_test_app().await;
+        let client = reqwest::Client::new();
+        let (_tenant_id, token) = login_as_admin(&client, &base, &db).await;
+        create_echo_tool(&db).await;
+        let now = chrono::Utc::now().to_rfc3339();
+        for (id, status) in [("e1", "failed"), ("e2", "completed"), ("e3", "pending")] {
+            db.execute(
+                "INSERT INTO executions (id, tool_id, tenant_id, user_id, status, request, started_at, created_at, updated_at) \
+                 SELECT ?, 'echo', tenant_id, id, ?, '{}', ?, ?, ? FROM users LIMIT 1",
+                &[json!(id), json!(status), json!(now), json!(now), json!(now)],
+            ).await.unwrap();
+        }
+
+        let overview: serde_json::Value = client.get(format!("{}/api/v1/admin/overview?bucket=day&top=5", base))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(overview["tools"]["total"], 1);
+        assert_eq!(overview["executions"]["total"], 3);
+        assert_eq!(overview["executions"]["by_status"]["failed"], 1);
+        assert_eq!(overview["executions"]["bucket"], "day");
+        assert_eq!(overview["executions"]["top_failing_tools"][0]["tool_id"], "echo");
+        assert_eq!(overview["queue"]["pending_executions"], 1);
+        assert_eq!(overview["workers"]["healthy"], true);
+        assert!(overview["workers"]["load"]["queue"].is_object());
+        assert!(overview["database"]["size_bytes"].as_u64().unwrap() > 0);
+        assert_eq!(overview["caches"][0]["name"], "statements");
+
+        // 仅管理员可见
+        let response = client.get(format!("{}/api/v1/admin/overview", base)).send().await.unwrap();
+        assert!(response.status().is_client_error());
+    }
+
+    #[tokio::test]
+    async fn test_event_schemas() {
+        let (base, db) = serve_test_app().await;
+        let client = reqwest::Client::new();
+        let (_tenant_id, token) = login_as_admin(&client, &base, &db).await;
+        let schemas = format!("{}/api/v1/events/schemas/tool.changed", base);
+
+        let response = client.get(format!("{}/latest", schemas)).bearer_auth(&token).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+
+        let v1 = json!({"type": "object", "properties": {"tool_id": {"type": "string"}}, "required": ["tool_id"]});
+        let published: serde_json::Value = client.post(&schemas)
+            .bearer_auth(&token)
+            .json(&json!({"schema": v1, "description": "Registry tool changes"}))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(published["version"], 1);
+        assert_eq!(published["compatibility"], "backward");
+
+        // 新增必填字段破坏向后兼容
+        let v2 = json!({"type": "object", "properties": {"tool_id": {"type": "string"}, "tenant_id": {"type": "string"}}, "required": ["tool_id", "tenant_id"]});
+        let report: serde_json::Value = client.post(format!("{}/compatibility", schemas))
+            .bearer_auth(&token)
+            .json(&json!({"schema": v2}))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(report["compatible"], false);
+        assert_eq!(report["previous_version"], 1);
+        let response = client.post(&schemas).bearer_auth(&token).json(&json!({"schema": v2})).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::CONFLICT);
+
+        let response = client.post(&schemas)
+            .bearer_auth(&token)
+            .json(&json!({"schema": v2, "compatibility": "forward"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+        let fetched: serde_json::Value = client.get(format!("{}/versions/1", schemas))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(fetched["json_schema"], v1);
+        let versions: serde_json::Value = client.get(&schemas).bearer_auth(&token).send().await.unwrap().json().await.unwrap();
+        assert_eq!(versions["total_count"], 2);
+        let all: serde_json::Value = client.get(format!("{}/api/v1/events/schemas", base))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(all["schemas"][0]["version"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_interactive_sessions() {
+        let (base, db) = serve_test_app_with(|state| {
+            let sessions = ToolSessionManager::new(state.sandbox.clone(), ToolSessionConfig {
+                max_sessions_per_tenant: 1,
+                idle_timeout: std::time::Duration::from_millis(500),
+                sandbox_config: SandboxConfig { isolation_type: IsolationType::Process, ..SandboxConfig::default() },
+                ..ToolSessionConfig::default()
+            });
+            state.with_tool_session_manager(Arc::new(sessions))
+        }).await;
+        let client = reqwest::Client::new();
+        let (_tenant_id, token) = login_as_admin(&client, &base, &db).await;
+        create_echo_tool(&db).await;
+        let tool = ToolInfo::builder()
+            .id(ToolId::from_string("repl".to_string()))
+            .name("Python REPL")
+            .tool_type(ToolType::Python)
+            .session(ToolSessionCommand {
+                command: vec!["python3".to_string(), "-c".to_string()],
+                allowed_args: vec!["-u".to_string()],
+            })
+            .build()
+            .unwrap();
+        ToolRepository::new(db.as_ref().clone()).create_tool(&tool).await.unwrap();
+        let sessions = format!("{}/api/v1/tool-sessions", base);
+
+        // 会话命令取自工具定义：没有会话命令的工具和工具不允许的参数都被拒绝
+        let response = client.post(&sessions).bearer_auth(&token).json(&json!({"tool_id": "echo"})).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+        let response = client.post(&sessions)
+            .bearer_auth(&token)
+            .json(&json!({"tool_id": "repl", "args": ["-m", "http.server"]}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+
+        // 会话环境与执行环境受同一策略约束
+        let response = client.post(&sessions)
+            .bearer_auth(&token)
+            .json(&json!({"tool_id": "repl", "environment": {"LD_PRELOAD": "/tmp/hook.so"}}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert!(body["error"]["message"].as_str().unwrap().contains("LD_PRELOAD"), "{}", body);
+
+        let session: serde_json::Value = client.post(&sessions)
+            .bearer_auth(&token)
+            .json(&json!({"tool_id": "repl", "args": ["-u"]}))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(session["command"], json!(["python3", "-u", "-c"]));
+        let session_url = format!("{}/{}", sessions, session["id"].as_str().unwrap());
+
+        // 达到租户配额后不能再打开会话
+        let response = client.post(&sessions)
+            .bearer_auth(&token)
+            .json(&json!({"tool_id": "repl"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::CONFLICT);
+
+        for sequence in 1..=2 {
+            let output: serde_json::Value = client.post(format!("{}/input", session_url))
+                .bearer_auth(&token)
+                .json(&json!({"input": "print(1)"}))
+                .send()
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+            assert_eq!(output["sequence"], sequence);
+            assert_eq!(output["exit_code"], 0);
+        }
+        let body: serde_json::Value = client.get(&session_url).bearer_auth(&token).send().await.unwrap().json().await.unwrap();
+        assert_eq!(body["inputs"], 2);
+
+        let response = client.delete(&session_url).bearer_auth(&token).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let response = client.post(format!("{}/input", session_url)).bearer_auth(&token).json(&json!({"input": "1"})).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+
+        // 空闲超时的会话被关闭，不再占用配额
+        let response = client.post(&sessions)
+            .bearer_auth(&token)
+            .json(&json!({"tool_id": "repl"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        tokio::time::sleep(std::time::Duration::from_millis(600)).await;
+        let body: serde_json::Value = client.get(&sessions).bearer_auth(&token).send().await.unwrap().json().await.unwrap();
+        assert_eq!(body["sessions"], json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_list_and_run_background_jobs() {
+        // 调度器只通过 `background_jobs` 表协调，这里给它单独的数据库
+        let jobs_db = SqliteDatabase::new("sqlite::memory:").await.unwrap();
+        MigrationManager::run_migrations(&jobs_db).await.unwrap();
+        let jobs = Arc::new(JobScheduler::new(jobs_db));
+        jobs.register_fn("cleanup", "Delete stale rows", JobTrigger::interval(std::time::Duration::from_secs(3600)), || async {
+            Ok("3 rows deleted".to_string())
+        }).await.unwrap();
+
+        let (base, db) = serve_test_app_with(|state| with_system_tenant(state).with_job_scheduler(jobs.clone())).await;
+        let client = reqwest::Client::new();
+        let token = login_as_system_admin(&client, &base, &db).await;
+
+        let body: serde_json::Value = client.get(format!("{}/api/v1/admin/jobs", base))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(body["jobs"][0]["name"], "cleanup");
+        assert!(body["jobs"][0]["last_run"].is_null());
+
+        let response = client.post(format!("{}/api/v1/admin/jobs/cleanup/run", base)).bearer_auth(&token).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let run: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(run["status"], "succeeded");
+        assert_eq!(run["message"], "3 rows deleted");
+        assert_eq!(run["manual"], true);
+
+        let body: serde_json::Value = client.get(format!("{}/api/v1/admin/jobs", base))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(body["jobs"][0]["last_run"]["status"], "succeeded");
+
+        let response = client.post(format!("{}/api/v1/admin/jobs/missing/run", base)).bearer_auth(&token).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+
+        // 2026-03-06 is a Friday; the following Monday is a holiday
+        let preview = |schedule: serde_json::Value| {
+            client.post(format!("{}/api/v1/admin/jobs/schedule-preview", base))
+                .bearer_auth(&token)
+                .json(&serde_json::json!({"schedule": schedule, "count": 2, "after": "2026-03-05T12:00:00Z"}))
+                .send()
+        };
+        let response = preview(serde_json::json!({
+            "cron": "0 9 * * *",
+            "timezone": "Asia/Shanghai",
+            "business_days_only": true,
+            "holidays": ["2026-03-09"],
+        })).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["timezone"], "Asia/Shanghai");
+        assert_eq!(body["fire_times"][0]["at"], "2026-03-06T01:00:00Z");
+        assert_eq!(body["fire_times"][0]["local"], "2026-03-06T09:00:00+08:00");
+        assert_eq!(body["fire_times"][1]["at"], "2026-03-10T01:00:00Z");
+
+        let response = preview(serde_json::json!({
+            "cron": "0 9 * * *",
+            "blackouts": [{"start": "2026-03-07T00:00:00Z", "end": "2026-03-06T00:00:00Z"}],
+        })).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_health_reports_leadership() {
+        let (base, _db) = serve_test_app().await;
+        let body: serde_json::Value = reqwest::get(format!("{}/health", base)).await.unwrap().json().await.unwrap();
+        assert!(body.get("leadership").is_none());
+
+        let leader_db = SqliteDatabase::new("sqlite::memory:").await.unwrap();
+        MigrationManager::run_migrations(&leader_db).await.unwrap();
+        let leader = Arc::new(LeaderElection::new(leader_db));
+        assert!(leader.campaign().await.unwrap());
+
+        let (base, _db) = serve_test_app_with(|state| state.with_leader_election(leader.clone())).await;
+        let body: serde_json::Value = reqwest::get(format!("{}/health", base)).await.unwrap().json().await.unwrap();
+        assert_eq!(body["leadership"]["is_leader"], true);
+        assert_eq!(body["leadership"]["leader"], leader.instance_id());
+        assert_eq!(body["leadership"]["term"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_execution_report_formats() {
+        let (base, db) = serve_test_app().await;
+        let client = reqwest::Client::new();
+        let (tenant_id, token) = login_as_admin(&client, &base, &db).await;
+
+        let recorder = TimelineRecorder::new(db.clone());
+        let execution_id = ExecutionId::from_string("exec-report".to_string());
+        for state in [ExecutionState::Queued, ExecutionState::Running, ExecutionState::Completed] {
+            recorder.record(&execution_id, None, Some(tenant_id.as_str()), state, None, None).await.unwrap();
+        }
+        let other = ExecutionId::from_string("exec-other-tenant".to_string());
+        recorder.record(&other, None, Some("other"), ExecutionState::Queued, None, None).await.unwrap();
+
+        let response = client.get(format!("{}/api/v1/executions/exec-report/report", base)).bearer_auth(&token).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "text/html; charset=utf-8");
+        let html = response.text().await.unwrap();
+        assert!(html.contains("Execution exec-report"));
+        assert!(html.contains("completed"));
+
+        let response = client.get(format!("{}/api/v1/executions/exec-report/report?format=pdf", base))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "application/pdf");
+        assert_eq!(response.headers()["content-disposition"], "attachment; filename=\"execution-exec-report.pdf\"");
+        assert!(response.bytes().await.unwrap().starts_with(b"%PDF"));
+
+        // 其他租户的执行不可见
+        let response = client.get(format!("{}/api/v1/executions/exec-other-tenant/report", base))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+
+        // 格式不合法的执行 ID 在入口处即被拒绝
+        let response = client.get(format!("{}/api/v1/executions/exec%27%3B/report", base))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["error"]["code"], "VALIDATION_ERROR");
+        assert_eq!(body["error"]["reason"], "INVALID_INPUT");
+        assert_eq!(body["error"]["retryable"], false);
+    }
+}
@@ -0,0 +1,90 @@
+//! 审批通知
+//!
+//! 工作流审批步骤创建审批任务或审批被决定、过期时，通过 [`WebhookApprovalNotifier`]
+//! 将 [`ApprovalEvent`] 封装为 CloudEvent（结构化 JSON 模式）推送到配置的 webhook，
+//! 或通过 [`ChannelApprovalNotifier`] 发送到租户配置的通知渠道（Slack、邮件等）。
+
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use stepflow_core::{CloudEvent, TenantId, CLOUDEVENTS_CONTENT_TYPE};
+use stepflow_executor::{ApprovalEvent, ApprovalNotifier, ExecutorError, ExecutorResult};
+use stepflow_monitoring::{Notification, NotificationService};
+
+/// 将审批事件推送到 webhook
+pub struct WebhookApprovalNotifier {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl WebhookApprovalNotifier {
+    pub fn new(url: String) -> Self {
+        Self {
+            url,
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl ApprovalNotifier for WebhookApprovalNotifier {
+    async fn notify(&self, event: &ApprovalEvent) -> ExecutorResult<()> {
+        let response = self.client
+            .post(&self.url)
+            .header(reqwest::header::CONTENT_TYPE, CLOUDEVENTS_CONTENT_TYPE)
+            .json(&CloudEvent::new(event.clone()))
+            .timeout(Duration::from_secs(10))
+            .send()
+            .await
+            .map_err(|e| ExecutorError::InternalError(format!("Webhook {} failed: {}", self.url, e)))?;
+
+        if !response.status().is_success() {
+            return Err(ExecutorError::InternalError(format!(
+                "Webhook {} returned {}",
+                self.url,
+                response.status()
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// 将审批事件发送到租户订阅了该事件的通知渠道
+pub struct ChannelApprovalNotifier {
+    notifications: Arc<NotificationService>,
+}
+
+impl ChannelApprovalNotifier {
+    pub fn new(notifications: Arc<NotificationService>) -> Self {
+        Self { notifications }
+    }
+}
+
+#[async_trait]
+impl ApprovalNotifier for ChannelApprovalNotifier {
+    async fn notify(&self, event: &ApprovalEvent) -> ExecutorResult<()> {
+        let (subject, body) = match event.event.as_str() {
+            "approval.requested" => (
+                "Approval requested: {{approval.title}}",
+                "Workflow {{approval.workflow_id}} is waiting at step {{approval.step_id}}.",
+            ),
+            _ => (
+                "Approval {{approval.status}}: {{approval.title}}",
+                "Workflow {{approval.workflow_id}}, step {{approval.step_id}}, decided by {{approval.decided_by}}.",
+            ),
+        };
+        let data = serde_json::to_value(event).map_err(|e| ExecutorError::InternalError(e.to_string()))?;
+        let notification = Notification::new(
+            event.event.clone(),
+            TenantId::from_string(event.approval.tenant_id.clone()),
+            subject,
+            body,
+            data,
+        );
+        self.notifications
+            .notify(&notification)
+            .await
+            .map_err(|e| ExecutorError::InternalError(format!("Approval notification failed: {}", e)))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,389 @@
+//! LDAP / Active Directory 用户同步
+//!
+//! 按租户配置的目录服务把用户同步为租户用户，角色由组映射决定。由目录管理的用户
+//! 会记录其 DN；目录条目与本地用户（用户名或邮箱相同）冲突时，按冲突策略跳过或接管。
+//! 同步可以先以 dry-run 方式预览变更，也可以通过 [`DirectorySyncService::start`] 定期执行。
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use chrono::Utc;
+use ldap3::adapters::{Adapter, EntriesOnly, PagedResults};
+use ldap3::{LdapConnAsync, Scope, SearchEntry};
+use serde::{Deserialize, Serialize};
+use stepflow_core::{TenantId, UserId, UserInfo, UserRole};
+use stepflow_database::{
+    utils::generate_token, DirectoryConfigRecord, DirectoryConflictPolicy, DirectoryRepository, DirectorySyncRun,
+    SqliteDatabase, UserRepository,
+};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+use crate::errors::{ApiError, ApiResult};
+
+/// 目录中的一个用户条目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectoryEntry {
+    pub dn: String,
+    pub username: Option<String>,
+    pub email: Option<String>,
+    pub groups: Vec<String>,
+}
+
+/// 目录数据源
+#[async_trait]
+pub trait DirectorySource: Send + Sync {
+    /// 读取符合过滤条件的全部用户条目
+    async fn fetch_entries(&self, config: &DirectoryConfigRecord) -> ApiResult<Vec<DirectoryEntry>>;
+}
+
+/// 基于 LDAP 协议的目录数据源（同时适用于 Active Directory）
+#[derive(Debug, Default)]
+pub struct LdapDirectorySource;
+
+/// 每页读取的条目数，Active Directory 默认单次最多返回 1000 条
+const LDAP_PAGE_SIZE: i32 = 500;
+
+#[async_trait]
+impl DirectorySource for LdapDirectorySource {
+    async fn fetch_entries(&self, config: &DirectoryConfigRecord) -> ApiResult<Vec<DirectoryEntry>> {
+        let ldap_error = |e: ldap3::LdapError| ApiError::ServiceUnavailable(format!("LDAP error: {}", e));
+
+        let (conn, mut ldap) = LdapConnAsync::new(&config.url).await.map_err(ldap_error)?;
+        ldap3::drive!(conn);
+        ldap.simple_bind(&config.bind_dn, &config.bind_password)
+            .await
+            .and_then(|result| result.success())
+            .map_err(ldap_error)?;
+
+        let attributes = vec![
+            config.username_attribute.as_str(),
+            config.email_attribute.as_str(),
+            config.group_attribute.as_str(),
+        ];
+        let adapters: Vec<Box<dyn Adapter<_, _>>> = vec![
+            Box::new(EntriesOnly::new()),
+            Box::new(PagedResults::new(LDAP_PAGE_SIZE)),
+        ];
+        let mut search = ldap
+            .streaming_search_with(adapters, &config.base_dn, Scope::Subtree, &config.user_filter, attributes)
+            .await
+            .map_err(ldap_error)?;
+
+        let mut entries = Vec::new();
+        while let Some(entry) = search.next().await.map_err(ldap_error)? {
+            let entry = SearchEntry::construct(entry);
+            // 属性名大小写以服务器返回为准
+            let values = |name: &str| {
+                entry.attrs.iter()
+                    .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                    .map(|(_, values)| values.clone())
+                    .unwrap_or_default()
+            };
+            entries.push(DirectoryEntry {
+                username: values(&config.username_attribute).into_iter().next(),
+                email: values(&config.email_attribute).into_iter().next(),
+                groups: values(&config.group_attribute),
+                dn: entry.dn,
+            });
+        }
+        search.finish().await.success().map_err(ldap_error)?;
+        let _ = ldap.unbind().await;
+
+        Ok(entries)
+    }
+}
+
+/// 同步计划中的一项变更
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum DirectorySyncChange {
+    /// 创建目录用户
+    Create { dn: String, username: String, email: String, role: UserRole },
+    /// 更新已由目录管理的用户
+    Update { user_id: UserId, username: String, email: Option<String>, role: Option<UserRole> },
+    /// 接管同名的本地用户
+    Adopt { user_id: UserId, username: String, dn: String, role: UserRole },
+    /// 删除目录中已不存在的用户
+    Remove { user_id: UserId, username: String },
+    /// 无法同步的条目
+    Conflict { dn: String, reason: String },
+}
+
+/// 同步结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectorySyncReport {
+    pub run: DirectorySyncRun,
+    pub changes: Vec<DirectorySyncChange>,
+}
+
+/// 目录同步服务
+pub struct DirectorySyncService {
+    db: SqliteDatabase,
+    source: Arc<dyn DirectorySource>,
+}
+
+impl DirectorySyncService {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self::with_source(db, Arc::new(LdapDirectorySource))
+    }
+
+    /// 使用自定义数据源（例如测试或非 LDAP 目录）
+    pub fn with_source(db: SqliteDatabase, source: Arc<dyn DirectorySource>) -> Self {
+        Self { db, source }
+    }
+
+    /// 同步一个租户；`dry_run` 为 true 时只生成变更计划
+    pub async fn sync_tenant(&self, tenant_id: &TenantId, dry_run: bool) -> ApiResult<DirectorySyncReport> {
+        let config = DirectoryRepository::new(self.db.clone())
+            .get_config(tenant_id)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("Directory sync is not configured for this tenant".to_string()))?;
+        self.sync(&config, dry_run).await
+    }
+
+    /// 按给定配置同步，并记录本次同步结果
+    pub async fn sync(&self, config: &DirectoryConfigRecord, dry_run: bool) -> ApiResult<DirectorySyncReport> {
+        let started_at = Utc::now();
+        let outcome = self.run(config, dry_run).await;
+
+        let changes = outcome.as_ref().map(|changes| changes.as_slice()).unwrap_or_default();
+        let count = |matches: fn(&DirectorySyncChange) -> bool| changes.iter().filter(|c| matches(c)).count() as u64;
+        let run = DirectorySyncRun {
+            id: uuid::Uuid::new_v4().to_string(),
+            tenant_id: config.tenant_id.clone(),
+            dry_run,
+            created: count(|c| matches!(c, DirectorySyncChange::Create { .. })),
+            updated: count(|c| matches!(c, DirectorySyncChange::Update { .. })),
+            adopted: count(|c| matches!(c, DirectorySyncChange::Adopt { .. })),
+            removed: count(|c| matches!(c, DirectorySyncChange::Remove { .. })),
+            conflicts: count(|c| matches!(c, DirectorySyncChange::Conflict { .. })),
+            error: outcome.as_ref().err().map(|e| e.to_string()),
+            started_at,
+            finished_at: Utc::now(),
+        };
+        DirectoryRepository::new(self.db.clone()).record_run(&run).await?;
+
+        Ok(DirectorySyncReport {
+            run,
+            changes: outcome?,
+        })
+    }
+
+    /// 同步所有启用了目录同步的租户
+    pub async fn sync_all(&self) -> ApiResult<Vec<DirectorySyncReport>> {
+        let configs = DirectoryRepository::new(self.db.clone()).list_enabled_configs().await?;
+        let mut reports = Vec::with_capacity(configs.len());
+        for config in configs {
+            match self.sync(&config, false).await {
+                Ok(report) => reports.push(report),
+                Err(e) => warn!("Directory sync for tenant {} failed: {}", config.tenant_id.as_str(), e),
+            }
+        }
+        Ok(reports)
+    }
+
+    /// 在后台定期同步所有租户
+    pub fn start(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                match self.sync_all().await {
+                    Ok(reports) => {
+                        for report in reports {
+                            info!(
+                                "Directory sync for tenant {}: {} created, {} updated, {} adopted, {} removed, {} conflicts",
+                                report.run.tenant_id.as_str(), report.run.created, report.run.updated,
+                                report.run.adopted, report.run.removed, report.run.conflicts,
+                            );
+                        }
+                    }
+                    Err(e) => warn!("Directory sync failed: {}", e),
+                }
+            }
+        })
+    }
+
+    async fn run(&self, config: &DirectoryConfigRecord, dry_run: bool) -> ApiResult<Vec<DirectorySyncChange>> {
+        let entries = self.source.fetch_entries(config).await?;
+        let changes = self.plan(config, &entries).await?;
+        if !dry_run {
+            self.apply(config, &changes).await?;
+        }
+        Ok(changes)
+    }
+
+    async fn plan(
+        &self,
+        config: &DirectoryConfigRecord,
+        entries: &[DirectoryEntry],
+    ) -> ApiResult<Vec<DirectorySyncChange>> {
+        let users = UserRepository::new(self.db.clone());
+        let managed = DirectoryRepository::new(self.db.clone()).managed_users(&config.tenant_id).await?;
+        let managed_by_dn: HashMap<String, &UserId> = managed.iter()
+            .map(|(user_id, dn)| (dn.to_lowercase(), user_id))
+            .collect();
+
+        let mut changes = Vec::new();
+        let mut seen = Vec::new();
+        for entry in entries {
+            let conflict = |reason: &str| DirectorySyncChange::Conflict {
+                dn: entry.dn.clone(),
+                reason: reason.to_string(),
+            };
+            let (Some(username), Some(email)) = (entry.username.as_deref(), entry.email.as_deref()) else {
+                changes.push(conflict("Entry has no username or email"));
+                continue;
+            };
+            let role = role_for_groups(config, &entry.groups);
+
+            if let Some(user_id) = managed_by_dn.get(&entry.dn.to_lowercase()) {
+                seen.push((*user_id).clone());
+                let Some(user) = users.get_user(user_id).await? else {
+                    continue;
+                };
+                let email_change = (user.email != email).then(|| email.to_string());
+                let role_change = (user.role != role).then_some(role);
+                if email_change.is_some() || role_change.is_some() {
+                    changes.push(DirectorySyncChange::Update {
+                        user_id: user.id,
+                        username: user.username,
+                        email: email_change,
+                        role: role_change,
+                    });
+                }
+                continue;
+            }
+
+            let existing = match users.get_user_by_username(username).await? {
+                Some(user) => Some(user),
+                None => users.get_user_by_email(email).await?,
+            };
+            match existing {
+                None => changes.push(DirectorySyncChange::Create {
+                    dn: entry.dn.clone(),
+                    username: username.to_string(),
+                    email: email.to_string(),
+                    role,
+                }),
+                Some(user) if user.tenant_id != config.tenant_id => {
+                    changes.push(conflict("A user with this username or email belongs to another tenant"));
+                }
+                Some(user) if managed.contains_key(&user.id) => {
+                    changes.push(conflict("The matching user is managed by another directory entry"));
+                }
+                Some(user) => match config.conflict_policy {
+                    DirectoryConflictPolicy::Skip => {
+                        changes.push(conflict("A local user with this username or email already exists"));
+                    }
+                    DirectoryConflictPolicy::Adopt => {
+                        seen.push(user.id.clone());
+                        changes.push(DirectorySyncChange::Adopt {
+                            user_id: user.id,
+                            username: user.username,
+                            dn: entry.dn.clone(),
+                            role,
+                        });
+                    }
+                },
+            }
+        }
+
+        if config.remove_missing {
+            for user_id in managed.keys().filter(|user_id| !seen.contains(user_id)) {
+                if let Some(user) = users.get_user(user_id).await? {
+                    changes.push(DirectorySyncChange::Remove {
+                        user_id: user.id,
+                        username: user.username,
+                    });
+                }
+            }
+        }
+
+        Ok(changes)
+    }
+
+    async fn apply(&self, config: &DirectoryConfigRecord, changes: &[DirectorySyncChange]) -> ApiResult<()> {
+        let users = UserRepository::new(self.db.clone());
+        let directory = DirectoryRepository::new(self.db.clone());
+
+        for change in changes {
+            match change {
+                DirectorySyncChange::Create { dn, username, email, role } => {
+                    let now = Utc::now();
+                    let user = UserInfo {
+                        id: UserId::new(),
+                        username: username.clone(),
+                        email: email.clone(),
+                        role: role.clone(),
+                        tenant_id: config.tenant_id.clone(),
+                        settings: HashMap::new(),
+                        email_verified: true,
+                        created_at: now,
+                        updated_at: now,
+                    };
+                    // 目录用户不使用本地密码登录
+                    users.register_user(&user, &generate_token()).await?;
+                    directory.set_managed(&user.id, Some(dn)).await?;
+                }
+                DirectorySyncChange::Update { user_id, email, role, .. } => {
+                    if let Some(mut user) = users.get_user(user_id).await? {
+                        if let Some(email) = email {
+                            user.email = email.clone();
+                        }
+                        if let Some(role) = role {
+                            user.role = role.clone();
+                        }
+                        user.updated_at = Utc::now();
+                        users.update_user(user_id, &user, None).await?;
+                    }
+                }
+                DirectorySyncChange::Adopt { user_id, dn, role, .. } => {
+                    if let Some(mut user) = users.get_user(user_id).await? {
+                        user.role = role.clone();
+                        user.updated_at = Utc::now();
+                        users.update_user(user_id, &user, None).await?;
+                    }
+                    directory.set_managed(user_id, Some(dn)).await?;
+                }
+                DirectorySyncChange::Remove { user_id, .. } => {
+                    users.delete_user(user_id).await?;
+                }
+                DirectorySyncChange::Conflict { .. } => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 按组映射确定角色，多个组匹配时取权限最高的角色
+pub fn role_for_groups(config: &DirectoryConfigRecord, groups: &[String]) -> UserRole {
+    let mapping: HashMap<String, &UserRole> = config.group_role_mapping.iter()
+        .map(|(group, role)| (group.to_lowercase(), role))
+        .collect();
+
+    groups.iter()
+        .filter_map(|group| {
+            let group = group.to_lowercase();
+            mapping.get(&group).or_else(|| mapping.get(group_cn(&group)))
+        })
+        .max_by_key(|role| role_rank(role))
+        .map(|role| (*role).clone())
+        .unwrap_or_else(|| config.default_role.clone())
+}
+
+/// 组 DN 的第一个 RDN 值，例如 `cn=admins,ou=groups,dc=example` 得到 `admins`
+fn group_cn(dn: &str) -> &str {
+    let rdn = dn.split(',').next().unwrap_or(dn);
+    rdn.split_once('=').map(|(_, value)| value.trim()).unwrap_or(rdn)
+}
+
+fn role_rank(role: &UserRole) -> u8 {
+    match role {
+        UserRole::Admin => 3,
+        UserRole::Custom(_) => 2,
+        UserRole::User => 1,
+        UserRole::Guest => 0,
+    }
+}
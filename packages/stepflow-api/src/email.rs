@@ -0,0 +1,177 @@
+//! 邮件发送
+//!
+//! 邀请和邮箱验证等流程通过 [`EmailSender`] 发送邮件。默认使用
+//! [`LogEmailSender`] 仅记录日志，生产环境可配置 [`SmtpEmailSender`]。
+//! 通知渠道的邮件经 [`EmailNotificationTransport`] 复用同一个发送器。
+
+use std::sync::Arc;
+use async_trait::async_trait;
+use lettre::{
+    message::Mailbox,
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use stepflow_core::MonitoringError;
+use stepflow_monitoring::{AlertResult, EmailTransport};
+use tracing::info;
+use crate::errors::{ApiError, ApiResult};
+
+/// 邮件内容
+#[derive(Debug, Clone)]
+pub struct EmailMessage {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// 邮件发送器特征
+#[async_trait]
+pub trait EmailSender: Send + Sync {
+    /// 发送邮件
+    async fn send(&self, message: &EmailMessage) -> ApiResult<()>;
+}
+
+/// 仅记录日志的邮件发送器（未配置 SMTP 时使用）
+#[derive(Debug, Default)]
+pub struct LogEmailSender;
+
+#[async_trait]
+impl EmailSender for LogEmailSender {
+    async fn send(&self, message: &EmailMessage) -> ApiResult<()> {
+        info!("Email to {}: {}\n{}", message.to, message.subject, message.body);
+        Ok(())
+    }
+}
+
+/// 通过 [`EmailSender`] 发送通知渠道的邮件
+pub struct EmailNotificationTransport {
+    sender: Arc<dyn EmailSender>,
+}
+
+impl EmailNotificationTransport {
+    pub fn new(sender: Arc<dyn EmailSender>) -> Self {
+        Self { sender }
+    }
+}
+
+#[async_trait]
+impl EmailTransport for EmailNotificationTransport {
+    async fn send_email(&self, to: &str, subject: &str, body: &str) -> AlertResult<()> {
+        let message = EmailMessage {
+            to: to.to_string(),
+            subject: subject.to_string(),
+            body: body.to_string(),
+        };
+        self.sender.send(&message).await.map_err(|e| MonitoringError::ExportFailed(e.to_string()))
+    }
+}
+
+/// SMTP 连接加密方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmtpSecurity {
+    /// 不加密（仅用于本地测试）
+    None,
+    /// 明文连接后升级为 TLS
+    StartTls,
+    /// 直接使用 TLS
+    Tls,
+}
+
+/// SMTP 配置
+#[derive(Debug, Clone)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// 发件人，例如 `Stepflow <noreply@example.com>`
+    pub from: String,
+    pub security: SmtpSecurity,
+}
+
+impl Default for SmtpConfig {
+    fn default() -> Self {
+        Self {
+            host: "localhost".to_string(),
+            port: 587,
+            username: None,
+            password: None,
+            from: "Stepflow <noreply@localhost>".to_string(),
+            security: SmtpSecurity::StartTls,
+        }
+    }
+}
+
+/// 基于 SMTP 的邮件发送器
+pub struct SmtpEmailSender {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpEmailSender {
+    pub fn new(config: SmtpConfig) -> ApiResult<Self> {
+        let from = config.from.parse::<Mailbox>()
+            .map_err(|e| ApiError::InternalServerError(format!("Invalid sender address: {}", e)))?;
+
+        let builder = match config.security {
+            SmtpSecurity::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host),
+            SmtpSecurity::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)
+                .map_err(|e| ApiError::InternalServerError(format!("Invalid SMTP relay: {}", e)))?,
+            SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host)
+                .map_err(|e| ApiError::InternalServerError(format!("Invalid SMTP relay: {}", e)))?,
+        };
+        let builder = builder.port(config.port);
+        let builder = match (config.username, config.password) {
+            (Some(username), Some(password)) => builder.credentials(Credentials::new(username, password)),
+            _ => builder,
+        };
+
+        Ok(Self {
+            transport: builder.build(),
+            from,
+        })
+    }
+}
+
+#[async_trait]
+impl EmailSender for SmtpEmailSender {
+    async fn send(&self, message: &EmailMessage) -> ApiResult<()> {
+        let to = message.to.parse::<Mailbox>()
+            .map_err(|e| ApiError::BadRequest(format!("Invalid recipient address: {}", e)))?;
+        let email = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(message.subject.clone())
+            .body(message.body.clone())
+            .map_err(|e| ApiError::InternalServerError(format!("Failed to build email: {}", e)))?;
+
+        self.transport.send(email).await
+            .map_err(|e| ApiError::ServiceUnavailable(format!("Failed to send email: {}", e)))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_smtp_sender_requires_valid_sender_address() {
+        let invalid = SmtpConfig { from: "not an address".to_string(), ..SmtpConfig::default() };
+        assert!(SmtpEmailSender::new(invalid).is_err());
+        let local = SmtpConfig { security: SmtpSecurity::None, ..SmtpConfig::default() };
+        assert!(SmtpEmailSender::new(local).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_smtp_sender_rejects_invalid_recipient() {
+        let sender = SmtpEmailSender::new(SmtpConfig { security: SmtpSecurity::None, ..SmtpConfig::default() }).unwrap();
+        let message = EmailMessage {
+            to: "nobody".to_string(),
+            subject: "Invitation".to_string(),
+            body: "Welcome".to_string(),
+        };
+        assert!(matches!(sender.send(&message).await, Err(ApiError::BadRequest(_))));
+        assert!(LogEmailSender.send(&message).await.is_ok());
+    }
+}
@@ -4,7 +4,7 @@ use axum::{
     Json,
 };
 use serde_json::json;
-use stepflow_core::errors::{StepflowError, DatabaseError, MonitoringError};
+use stepflow_core::errors::{DatabaseError, ErrorCode, MonitoringError, StepflowError};
 use stepflow_executor::errors::ExecutorError;
 use stepflow_registry::errors::RegistryError;
 use stepflow_sandbox::errors::SandboxError;
@@ -94,12 +94,12 @@ impl ApiError {
             ApiError::GatewayTimeout => StatusCode::GATEWAY_TIMEOUT,
             ApiError::ValidationError(_) => StatusCode::BAD_REQUEST,
             ApiError::SerializationError(_) => StatusCode::BAD_REQUEST,
-            ApiError::DatabaseError(_) => StatusCode::INTERNAL_SERVER_ERROR,
-            ApiError::RegistryError(_) => StatusCode::INTERNAL_SERVER_ERROR,
-            ApiError::ExecutorError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::DatabaseError(e) => catalog_status(e.code()),
+            ApiError::RegistryError(e) => catalog_status(e.code()),
+            ApiError::ExecutorError(e) => catalog_status(e.code()),
             ApiError::SandboxError(_) => StatusCode::INTERNAL_SERVER_ERROR,
             ApiError::MonitoringError(_) => StatusCode::INTERNAL_SERVER_ERROR,
-            ApiError::StepflowError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::StepflowError(e) => catalog_status(e.code()),
             ApiError::JwtError(_) => StatusCode::UNAUTHORIZED,
             ApiError::JsonError(_) => StatusCode::BAD_REQUEST,
         }
@@ -132,6 +132,67 @@ impl ApiError {
         }
     }
     
+    /// 错误目录中的错误代码，跨 HTTP 与 JSON-RPC 保持一致
+    pub fn catalog_code(&self) -> ErrorCode {
+        match self {
+            ApiError::BadRequest(_) | ApiError::SerializationError(_) | ApiError::JsonError(_) => ErrorCode::InvalidInput,
+            ApiError::Unauthorized(_) | ApiError::JwtError(_) => ErrorCode::Unauthenticated,
+            ApiError::Forbidden(_) => ErrorCode::PermissionDenied,
+            ApiError::NotFound(_) => ErrorCode::NotFound,
+            ApiError::MethodNotAllowed(_) => ErrorCode::NotImplemented,
+            ApiError::Conflict(_) => ErrorCode::Conflict,
+            ApiError::UnprocessableEntity(_) => ErrorCode::ValidationFailed,
+            ApiError::ValidationError(_) => ErrorCode::InvalidInput,
+            ApiError::RateLimitExceeded => ErrorCode::RateLimited,
+            ApiError::ServiceUnavailable(_) => ErrorCode::Unavailable,
+            ApiError::GatewayTimeout => ErrorCode::Timeout,
+            ApiError::DatabaseError(e) => e.code(),
+            ApiError::RegistryError(e) => e.code(),
+            ApiError::ExecutorError(e) => e.code(),
+            ApiError::StepflowError(e) => e.code(),
+            ApiError::InternalServerError(_) | ApiError::SandboxError(_) | ApiError::MonitoringError(_) => {
+                ErrorCode::Internal
+            }
+        }
+    }
+
+    /// 稍后重试同一请求是否可能成功
+    pub fn retryable(&self) -> bool {
+        match self {
+            ApiError::DatabaseError(e) => e.retryable(),
+            ApiError::RegistryError(e) => e.retryable(),
+            ApiError::ExecutorError(e) => e.retryable(),
+            ApiError::StepflowError(e) => e.retryable(),
+            _ => self.catalog_code().retryable(),
+        }
+    }
+
+    /// 错误详情，即错误信息中代码标题之后的部分
+    pub fn detail(&self) -> Option<String> {
+        match self {
+            ApiError::BadRequest(detail)
+            | ApiError::Unauthorized(detail)
+            | ApiError::Forbidden(detail)
+            | ApiError::NotFound(detail)
+            | ApiError::MethodNotAllowed(detail)
+            | ApiError::Conflict(detail)
+            | ApiError::UnprocessableEntity(detail)
+            | ApiError::InternalServerError(detail)
+            | ApiError::ServiceUnavailable(detail)
+            | ApiError::ValidationError(detail)
+            | ApiError::SerializationError(detail) => Some(detail.clone()),
+            ApiError::RateLimitExceeded | ApiError::GatewayTimeout => None,
+            ApiError::DatabaseError(e) => Some(e.to_string()),
+            ApiError::RegistryError(e) => Some(e.to_string()),
+            ApiError::ExecutorError(e) => Some(e.to_string()),
+            ApiError::SandboxError(e) => Some(e.to_string()),
+            ApiError::MonitoringError(e) => Some(e.to_string()),
+            ApiError::StepflowError(e) => Some(e.to_string()),
+            ApiError::JwtError(e) => Some(e.to_string()),
+            ApiError::JsonError(e) => Some(e.to_string()),
+        }
+    }
+
     /// 是否应该记录错误
     pub fn should_log(&self) -> bool {
         match self {
@@ -158,18 +219,39 @@ impl IntoResponse for ApiError {
         let error_code = self.error_code();
         let message = self.to_string();
         
-        let body = json!({
+        let mut body = json!({
             "error": {
                 "code": error_code,
+                "reason": self.catalog_code(),
+                "retryable": self.retryable(),
                 "message": message,
                 "timestamp": chrono::Utc::now().to_rfc3339(),
             }
         });
+        // 模板错误附带参数名和出错表达式的位置
+        if let ApiError::ExecutorError(ExecutorError::TemplateError(template_error)) = &self {
+            body["error"]["details"] = json!(template_error);
+        }
         
-        (status_code, Json(body)).into_response()
+        let localizable = LocalizableError { code: error_code, detail: self.detail() };
+        let mut response = (status_code, Json(body)).into_response();
+        response.extensions_mut().insert(localizable);
+        response
     }
 }
 
+/// 按错误目录映射 HTTP 状态码
+fn catalog_status(code: ErrorCode) -> StatusCode {
+    StatusCode::from_u16(code.http_status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// 错误响应携带的错误代码与详情，供本地化中间件按请求语言重写错误信息
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalizableError {
+    pub code: &'static str,
+    pub detail: Option<String>,
+}
+
 /// 中间件错误类型
 #[derive(Debug, Error)]
 pub enum MiddlewareError {
@@ -257,6 +339,13 @@ impl From<ValidationError> for ApiError {
     }
 }
 
+/// 核心类型的校验错误，例如格式不合法的工具 ID 或执行 ID
+impl From<stepflow_core::ValidationError> for ApiError {
+    fn from(error: stepflow_core::ValidationError) -> Self {
+        ApiError::ValidationError(error.to_string())
+    }
+}
+
 /// 认证错误类型
 #[derive(Debug, Error)]
 pub enum AuthError {
@@ -0,0 +1,67 @@
+//! 请求级功能开关
+//!
+//! 处理器通过 [`RequestFlags`] 提取器按当前用户与租户判断功能开关，无需自行拼装
+//! 评估上下文。未认证的请求只命中全量开启的开关。
+
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+
+use async_trait::async_trait;
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use stepflow_core::{Flag, FlagContext, Flags};
+
+use crate::server::AppState;
+use crate::types::UserContext;
+
+/// 绑定到当前用户与租户的功能开关
+#[derive(Debug, Clone)]
+pub struct RequestFlags {
+    flags: Flags,
+    context: FlagContext,
+}
+
+impl RequestFlags {
+    pub fn new(flags: Flags, context: FlagContext) -> Self {
+        Self { flags, context }
+    }
+
+    /// 开关对当前请求是否开启，未定义的开关视为关闭
+    pub fn is_enabled(&self, flag: Flag) -> bool {
+        self.flags.is_enabled(flag, &self.context)
+    }
+
+    pub fn is_enabled_by_name(&self, name: &str) -> bool {
+        self.flags.is_enabled_by_name(name, &self.context)
+    }
+
+    /// 所有已定义开关对当前请求的取值，按名称排序
+    pub fn evaluate_all(&self) -> BTreeMap<String, bool> {
+        self.flags
+            .definitions()
+            .into_iter()
+            .map(|definition| {
+                let enabled = definition.evaluate(&self.context);
+                (definition.name, enabled)
+            })
+            .collect()
+    }
+
+    pub fn context(&self) -> &FlagContext {
+        &self.context
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for RequestFlags {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let context = parts
+            .extensions
+            .get::<UserContext>()
+            .map(|user| FlagContext::new(user.tenant_id.clone(), Some(user.user_id.as_str().to_string())))
+            .unwrap_or_default();
+        Ok(Self::new(state.flags.clone(), context))
+    }
+}
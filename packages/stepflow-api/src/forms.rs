@@ -0,0 +1,726 @@
+//! 工具参数表单元数据
+//!
+//! 把工具输入参数的 JSON Schema 转换为前端渲染表单所需的元数据：字段顺序、标签、
+//! 枚举选项、默认值、敏感字段以及字段间的依赖关系。Schema 内的 `$ref`
+//! （`#/definitions`、`#/$defs`、`#/components/schemas` 等）在生成时解析，`allOf` 合并为一个对象；
+//! `oneOf` / `anyOf` 的分支都是常量时展开为选项，都是带同一判别字段的对象时展开为按判别值显示的字段，
+//! 其余情况退化为 JSON 输入框。嵌套对象的字段以 `.` 连接路径平铺。
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use stepflow_core::ToolId;
+
+/// 嵌套对象展开的最大深度，更深的对象（含递归 schema）作为 JSON 输入
+const MAX_DEPTH: usize = 8;
+
+/// `$ref` 链的最大长度
+const MAX_REF_CHAIN: usize = 16;
+
+/// 作为字段约束原样返回的关键字
+const CONSTRAINT_KEYWORDS: &[&str] = &[
+    "minimum", "maximum", "exclusiveMinimum", "exclusiveMaximum", "multipleOf",
+    "minLength", "maxLength", "pattern", "format", "minItems", "maxItems", "uniqueItems",
+];
+
+/// 工具参数表单
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolForm {
+    pub tool_id: ToolId,
+    pub version: String,
+    /// 按显示顺序排列的字段
+    pub fields: Vec<FormField>,
+}
+
+/// 表单控件
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FormWidget {
+    Text,
+    Password,
+    Number,
+    Checkbox,
+    Select,
+    MultiSelect,
+    Date,
+    DateTime,
+    File,
+    /// 无法展开为具体控件的值，以 JSON 文本输入
+    Json,
+}
+
+/// 选择控件的选项
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FormOption {
+    pub value: Value,
+    pub label: String,
+}
+
+/// 依赖生效的条件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DependencyCondition {
+    /// 被依赖字段已填写
+    Present,
+    /// 被依赖字段的值为其中之一
+    In { values: Vec<Value> },
+}
+
+/// 依赖生效时对字段的影响
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyEffect {
+    /// 条件满足时才显示
+    Visible,
+    /// 条件满足时必填
+    Required,
+}
+
+/// 字段对另一个字段的依赖
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldDependency {
+    /// 被依赖字段的路径
+    pub field: String,
+    pub condition: DependencyCondition,
+    pub effect: DependencyEffect,
+}
+
+/// 表单字段
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormField {
+    /// 字段路径，嵌套对象的字段以 `.` 连接，如 `auth.token`
+    pub name: String,
+    pub label: String,
+    pub description: Option<String>,
+    /// JSON Schema 类型
+    pub field_type: String,
+    pub widget: FormWidget,
+    /// 无条件必填；条件必填见 `depends_on`
+    pub required: bool,
+    pub read_only: bool,
+    /// 密钥类字段，前端应遮盖输入且不回显
+    pub secret: bool,
+    pub default: Option<Value>,
+    pub options: Vec<FormOption>,
+    /// 长度、范围、格式等校验约束，键为 JSON Schema 关键字
+    pub constraints: Map<String, Value>,
+    pub depends_on: Vec<FieldDependency>,
+}
+
+/// 由输入参数的 JSON Schema 生成表单字段
+pub fn form_fields(schema: &Value) -> Vec<FormField> {
+    let mut builder = FormBuilder { root: schema, fields: Vec::new(), required_dependencies: Vec::new() };
+    builder.node(schema, "", true, &[], 0);
+
+    let FormBuilder { mut fields, required_dependencies, .. } = builder;
+    for (name, dependency) in required_dependencies {
+        if let Some(field) = fields.iter_mut().find(|field| field.name == name) {
+            if !field.required && !field.depends_on.contains(&dependency) {
+                field.depends_on.push(dependency);
+            }
+        }
+    }
+    fields
+}
+
+struct FormBuilder<'a> {
+    root: &'a Value,
+    fields: Vec<FormField>,
+    /// 条件必填关系，字段全部生成后再挂到对应字段上
+    required_dependencies: Vec<(String, FieldDependency)>,
+}
+
+impl FormBuilder<'_> {
+    /// 展开一个 schema 节点：对象展开为子字段，组合类型尽量展开，其余生成单个字段
+    fn node(&mut self, schema: &Value, name: &str, required: bool, conditions: &[FieldDependency], depth: usize) {
+        let schema = self.normalize(schema);
+
+        if let Some(branches) = variants(&schema) {
+            let branches: Vec<Value> = branches.iter()
+                .map(|branch| self.normalize(branch))
+                .filter(|branch| branch.get("type").and_then(Value::as_str) != Some("null"))
+                .collect();
+            // 只有一个非 null 分支时即为可空的该类型
+            if let [only] = branches.as_slice() {
+                let mut merged = schema.clone();
+                if let Some(object) = merged.as_object_mut() {
+                    object.remove("oneOf");
+                    object.remove("anyOf");
+                }
+                merge_schema(&mut merged, only);
+                self.node(&merged, name, required, conditions, depth);
+                return;
+            }
+            if let Some(options) = const_options(&branches) {
+                let mut field = self.leaf(&schema, name, required, conditions);
+                field.widget = FormWidget::Select;
+                field.field_type = options.first().map_or("string", |option| json_type(&option.value)).to_string();
+                field.options = options;
+                self.push(field);
+                return;
+            }
+            if depth < MAX_DEPTH {
+                if let Some(discriminator) = discriminator(&branches) {
+                    self.flatten_variants(&schema, &branches, &discriminator, name, required, conditions, depth);
+                    return;
+                }
+            }
+        } else if depth < MAX_DEPTH && schema.get("properties").is_some_and(Value::is_object) {
+            self.object(&schema, name, required, conditions, depth);
+            return;
+        }
+
+        let field = self.leaf(&schema, name, required, conditions);
+        self.push(field);
+    }
+
+    /// 展开对象的属性及属性间依赖
+    fn object(&mut self, schema: &Value, name: &str, required: bool, conditions: &[FieldDependency], depth: usize) {
+        let required_keys = string_list(schema.get("required"));
+        for (key, property) in ordered_properties(schema, |schema| self.normalize(schema)) {
+            let child_required = required && required_keys.contains(&key);
+            self.node(&property, &join(name, &key), child_required, conditions, depth + 1);
+        }
+
+        // dependentRequired，以及 draft-04 `dependencies` 的数组和 schema 两种形式
+        let dependencies = schema.get("dependentRequired").into_iter()
+            .chain(schema.get("dependencies"))
+            .chain(schema.get("dependentSchemas"))
+            .filter_map(Value::as_object)
+            .flatten();
+        for (trigger, dependency) in dependencies {
+            let trigger = join(name, trigger);
+            if let Some(keys) = dependency.as_array() {
+                for key in keys.iter().filter_map(Value::as_str) {
+                    self.require_when(join(name, key), &trigger, DependencyCondition::Present);
+                }
+            } else {
+                let condition = visible_when(&trigger, DependencyCondition::Present);
+                self.conditional(dependency, name, &trigger, DependencyCondition::Present, &condition, conditions, depth);
+            }
+        }
+
+        // if 只支持按常量判断：`{"properties": {"mode": {"const": "advanced"}}}`
+        let Some(then) = schema.get("then") else { return };
+        let Some(condition) = schema.get("if").and_then(|schema| schema.get("properties")).and_then(Value::as_object) else { return };
+        if let [(trigger, value)] = condition.iter().collect::<Vec<_>>().as_slice() {
+            if let Some(values) = const_values(&self.normalize(value)) {
+                let trigger = join(name, trigger);
+                let visible = visible_when(&trigger, DependencyCondition::In { values: values.clone() });
+                self.conditional(then, name, &trigger, DependencyCondition::In { values }, &visible, conditions, depth);
+            }
+        }
+    }
+
+    /// 条件满足时生效的子 schema：新属性按条件显示，已有属性按条件必填
+    #[allow(clippy::too_many_arguments)]
+    fn conditional(
+        &mut self,
+        schema: &Value,
+        name: &str,
+        trigger: &str,
+        condition: DependencyCondition,
+        visible: &FieldDependency,
+        conditions: &[FieldDependency],
+        depth: usize,
+    ) {
+        let schema = self.normalize(schema);
+        let required_keys = string_list(schema.get("required"));
+        let mut scoped = conditions.to_vec();
+        scoped.push(visible.clone());
+        for (key, property) in ordered_properties(&schema, |schema| self.normalize(schema)) {
+            let path = join(name, &key);
+            if !self.fields.iter().any(|field| field.name == path) {
+                self.node(&property, &path, false, &scoped, depth + 1);
+            }
+        }
+        for key in required_keys {
+            self.require_when(join(name, &key), trigger, condition.clone());
+        }
+    }
+
+    /// 按判别字段展开对象分支：判别字段生成选择控件，各分支的其余字段按判别值显示
+    #[allow(clippy::too_many_arguments)]
+    fn flatten_variants(
+        &mut self,
+        schema: &Value,
+        branches: &[Value],
+        discriminator: &str,
+        name: &str,
+        required: bool,
+        conditions: &[FieldDependency],
+        depth: usize,
+    ) {
+        if schema.get("properties").is_some_and(Value::is_object) {
+            self.object(schema, name, required, conditions, depth);
+        }
+
+        let discriminator_path = join(name, discriminator);
+        let values: Vec<Value> = branches.iter()
+            .map(|branch| {
+                branch.pointer(&format!("/properties/{}", escape_pointer(discriminator)))
+                    .map(|property| self.normalize(property))
+                    .and_then(|property| const_values(&property))
+                    .and_then(|values| values.into_iter().next())
+                    .unwrap_or(Value::Null)
+            })
+            .collect();
+
+        // 判别字段排在分支字段之前
+        let mut field = self.leaf(&Value::Null, &discriminator_path, required, conditions);
+        field.widget = FormWidget::Select;
+        field.field_type = values.first().map_or("string", json_type).to_string();
+        field.options = branches.iter().zip(&values)
+            .map(|(branch, value)| FormOption {
+                value: value.clone(),
+                label: branch.get("title").and_then(Value::as_str).map(str::to_string)
+                    .unwrap_or_else(|| option_label(value)),
+            })
+            .collect();
+        self.push(field);
+
+        for (branch, value) in branches.iter().zip(values) {
+            let mut branch = branch.clone();
+            if let Some(properties) = branch.get_mut("properties").and_then(Value::as_object_mut) {
+                properties.remove(discriminator);
+            }
+            let mut scoped = conditions.to_vec();
+            scoped.push(visible_when(&discriminator_path, DependencyCondition::In { values: vec![value] }));
+            self.object(&branch, name, required, &scoped, depth);
+        }
+    }
+
+    /// 生成单个字段
+    fn leaf(&self, schema: &Value, name: &str, required: bool, conditions: &[FieldDependency]) -> FormField {
+        let key = name.rsplit('.').next().unwrap_or(name);
+        let field_type = schema_type(schema).to_string();
+        let secret = schema.get("writeOnly").and_then(Value::as_bool).unwrap_or(false)
+            || schema.get("format").and_then(Value::as_str) == Some("password");
+
+        let enum_schema = if field_type == "array" {
+            schema.get("items").map(|items| self.normalize(items)).unwrap_or(Value::Null)
+        } else {
+            schema.clone()
+        };
+        let options = enum_options(&enum_schema);
+
+        let widget = if secret {
+            FormWidget::Password
+        } else if !options.is_empty() {
+            if field_type == "array" { FormWidget::MultiSelect } else { FormWidget::Select }
+        } else {
+            match (field_type.as_str(), schema.get("format").and_then(Value::as_str)) {
+                ("boolean", _) => FormWidget::Checkbox,
+                ("integer" | "number", _) => FormWidget::Number,
+                ("string", Some("date")) => FormWidget::Date,
+                ("string", Some("date-time")) => FormWidget::DateTime,
+                ("string", Some("binary")) => FormWidget::File,
+                ("string", _) => FormWidget::Text,
+                _ => FormWidget::Json,
+            }
+        };
+
+        let constraints = CONSTRAINT_KEYWORDS.iter()
+            .filter_map(|keyword| schema.get(*keyword).map(|value| (keyword.to_string(), value.clone())))
+            .collect();
+
+        FormField {
+            name: name.to_string(),
+            label: schema.get("title").and_then(Value::as_str).map(str::to_string)
+                .unwrap_or_else(|| humanize(if key.is_empty() { "value" } else { key })),
+            description: schema.get("description").and_then(Value::as_str).map(str::to_string),
+            field_type,
+            widget,
+            required,
+            read_only: schema.get("readOnly").and_then(Value::as_bool).unwrap_or(false),
+            secret,
+            default: schema.get("default").cloned(),
+            options,
+            constraints,
+            depends_on: conditions.to_vec(),
+        }
+    }
+
+    /// 添加字段；同名字段（多个分支共有）合并显示条件
+    fn push(&mut self, field: FormField) {
+        let Some(existing) = self.fields.iter_mut().find(|existing| existing.name == field.name) else {
+            self.fields.push(field);
+            return;
+        };
+        existing.required &= field.required;
+        existing.depends_on.retain(|dependency| {
+            dependency.effect != DependencyEffect::Visible
+                || field.depends_on.iter().any(|other| other.effect == DependencyEffect::Visible && other.field == dependency.field)
+        });
+        for dependency in existing.depends_on.iter_mut() {
+            let other = field.depends_on.iter().find(|other| other.effect == DependencyEffect::Visible && other.field == dependency.field);
+            if let (Some(other), DependencyCondition::In { values }) = (other, &mut dependency.condition) {
+                match &other.condition {
+                    DependencyCondition::In { values: more } => {
+                        values.extend(more.iter().filter(|value| !values.contains(value)).cloned().collect::<Vec<_>>());
+                    }
+                    DependencyCondition::Present => dependency.condition = DependencyCondition::Present,
+                }
+            }
+        }
+    }
+
+    fn require_when(&mut self, name: String, trigger: &str, condition: DependencyCondition) {
+        self.required_dependencies.push((name, FieldDependency {
+            field: trigger.to_string(),
+            condition,
+            effect: DependencyEffect::Required,
+        }));
+    }
+
+    /// 解析 `$ref` 并合并 `allOf`，与 `$ref` 并列的关键字优先
+    fn normalize(&self, schema: &Value) -> Value {
+        let mut schema = schema.clone();
+        for _ in 0..MAX_REF_CHAIN {
+            let Some(target) = schema.get("$ref").and_then(Value::as_str)
+                .and_then(|reference| reference.strip_prefix('#'))
+                .and_then(|pointer| self.root.pointer(pointer))
+            else {
+                break;
+            };
+            let mut resolved = target.clone();
+            if let (Some(resolved), Value::Object(siblings)) = (resolved.as_object_mut(), &schema) {
+                for (key, value) in siblings.iter().filter(|(key, _)| key.as_str() != "$ref") {
+                    resolved.insert(key.clone(), value.clone());
+                }
+            }
+            schema = resolved;
+        }
+
+        let Some(parts) = schema.as_object_mut().and_then(|object| object.remove("allOf")) else {
+            return schema;
+        };
+        for part in parts.as_array().into_iter().flatten() {
+            merge_schema(&mut schema, &self.normalize(part));
+        }
+        schema
+    }
+}
+
+/// 把 `part` 合并进 `schema`：属性和必填项取并集，其余关键字已有的优先
+fn merge_schema(schema: &mut Value, part: &Value) {
+    let (Some(schema), Some(part)) = (schema.as_object_mut(), part.as_object()) else { return };
+    for (key, value) in part {
+        match (key.as_str(), schema.get_mut(key)) {
+            ("properties", Some(Value::Object(properties))) => {
+                for (name, property) in value.as_object().into_iter().flatten() {
+                    properties.entry(name.clone()).or_insert_with(|| property.clone());
+                }
+            }
+            ("required", Some(Value::Array(required))) => {
+                for name in value.as_array().into_iter().flatten() {
+                    if !required.contains(name) {
+                        required.push(name.clone());
+                    }
+                }
+            }
+            (_, Some(_)) => {}
+            (_, None) => {
+                schema.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+/// 按 `x-order` / `propertyOrder` 排序的属性，未指定顺序的排在后面
+fn ordered_properties(schema: &Value, normalize: impl Fn(&Value) -> Value) -> Vec<(String, Value)> {
+    let mut properties: Vec<(String, Value)> = schema.get("properties").and_then(Value::as_object)
+        .into_iter()
+        .flatten()
+        .map(|(key, property)| (key.clone(), normalize(property)))
+        .collect();
+    properties.sort_by_key(|(_, property)| {
+        property.get("x-order").or_else(|| property.get("propertyOrder"))
+            .and_then(Value::as_i64)
+            .unwrap_or(i64::MAX)
+    });
+    properties
+}
+
+fn variants(schema: &Value) -> Option<&Vec<Value>> {
+    schema.get("oneOf").or_else(|| schema.get("anyOf"))
+        .and_then(Value::as_array)
+        .filter(|branches| !branches.is_empty())
+}
+
+/// 分支都是常量时转为选项
+fn const_options(branches: &[Value]) -> Option<Vec<FormOption>> {
+    branches.iter()
+        .map(|branch| {
+            let values = const_values(branch)?;
+            let [value] = values.as_slice() else { return None };
+            let label = branch.get("title").and_then(Value::as_str).map(str::to_string)
+                .unwrap_or_else(|| option_label(value));
+            Some(FormOption { value: value.clone(), label })
+        })
+        .collect()
+}
+
+/// 所有分支都有、且在各分支中为常量的属性
+fn discriminator(branches: &[Value]) -> Option<String> {
+    let first = branches.first()?.get("properties")?.as_object()?;
+    first.keys()
+        .find(|key| branches.iter().all(|branch| {
+            branch.get("properties").and_then(|properties| properties.get(key.as_str()))
+                .and_then(const_values)
+                .is_some_and(|values| values.len() == 1)
+        }))
+        .cloned()
+}
+
+/// `const` 或 `enum` 的取值
+fn const_values(schema: &Value) -> Option<Vec<Value>> {
+    schema.get("const").map(|value| vec![value.clone()])
+        .or_else(|| schema.get("enum").and_then(Value::as_array).cloned())
+}
+
+fn enum_options(schema: &Value) -> Vec<FormOption> {
+    let Some(values) = schema.get("enum").and_then(Value::as_array) else {
+        return Vec::new();
+    };
+    let names = ["x-enum-names", "x-enumNames", "enumNames"].iter()
+        .find_map(|key| schema.get(*key))
+        .and_then(Value::as_array);
+    values.iter()
+        .enumerate()
+        .filter(|(_, value)| !value.is_null())
+        .map(|(index, value)| FormOption {
+            value: value.clone(),
+            label: names.and_then(|names| names.get(index)).and_then(Value::as_str).map(str::to_string)
+                .unwrap_or_else(|| option_label(value)),
+        })
+        .collect()
+}
+
+fn visible_when(field: &str, condition: DependencyCondition) -> FieldDependency {
+    FieldDependency { field: field.to_string(), condition, effect: DependencyEffect::Visible }
+}
+
+/// 字段类型：取 `type` 中第一个非 null 的类型，缺省时按枚举值或结构推断
+fn schema_type(schema: &Value) -> &str {
+    let declared = match schema.get("type") {
+        Some(Value::String(kind)) => Some(kind.as_str()),
+        Some(Value::Array(kinds)) => kinds.iter().filter_map(Value::as_str).find(|kind| *kind != "null"),
+        _ => None,
+    };
+    declared
+        .or_else(|| schema.get("enum").and_then(Value::as_array).and_then(|values| values.first()).map(json_type))
+        .or_else(|| schema.get("const").map(json_type))
+        .or_else(|| schema.get("properties").map(|_| "object"))
+        .or_else(|| schema.get("items").map(|_| "array"))
+        .unwrap_or("string")
+}
+
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(number) if number.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn option_label(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn string_list(value: Option<&Value>) -> Vec<String> {
+    value.and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .map(str::to_string)
+        .collect()
+}
+
+fn join(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() { key.to_string() } else { format!("{}.{}", prefix, key) }
+}
+
+fn escape_pointer(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// 由属性名生成标签：`max_retries` / `maxRetries` → `Max retries`
+fn humanize(key: &str) -> String {
+    let mut words = String::new();
+    let mut previous_lower = false;
+    for ch in key.chars() {
+        if ch == '_' || ch == '-' || ch == ' ' {
+            if !words.ends_with(' ') && !words.is_empty() {
+                words.push(' ');
+            }
+            previous_lower = false;
+            continue;
+        }
+        if ch.is_uppercase() && previous_lower {
+            words.push(' ');
+        }
+        previous_lower = ch.is_lowercase() || ch.is_ascii_digit();
+        words.extend(ch.to_lowercase());
+    }
+    let mut chars = words.trim_end().chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn field<'a>(fields: &'a [FormField], name: &str) -> &'a FormField {
+        fields.iter().find(|field| field.name == name).unwrap_or_else(|| panic!("no field {}", name))
+    }
+
+    #[test]
+    fn test_fields_follow_declared_order_and_resolve_refs() {
+        let schema = json!({
+            "type": "object",
+            "required": ["region", "auth"],
+            "properties": {
+                "maxRetries": { "type": "integer", "default": 3, "minimum": 0, "x-order": 2 },
+                "region": { "$ref": "#/$defs/Region", "x-order": 1 },
+                "auth": {
+                    "type": "object",
+                    "required": ["token"],
+                    "properties": {
+                        "token": { "type": "string", "writeOnly": true },
+                        "user": { "type": "string" }
+                    }
+                }
+            },
+            "$defs": {
+                "Region": { "type": "string", "enum": ["eu", "us"], "x-enum-names": ["Europe", "United States"] }
+            }
+        });
+
+        let fields = form_fields(&schema);
+        let names: Vec<&str> = fields.iter().map(|field| field.name.as_str()).collect();
+        assert_eq!(names, ["region", "maxRetries", "auth.token", "auth.user"]);
+
+        let region = field(&fields, "region");
+        assert_eq!(region.widget, FormWidget::Select);
+        assert!(region.required);
+        assert_eq!(region.options[1], FormOption { value: json!("us"), label: "United States".to_string() });
+
+        let retries = field(&fields, "maxRetries");
+        assert_eq!(retries.label, "Max retries");
+        assert_eq!(retries.default, Some(json!(3)));
+        assert_eq!(retries.constraints["minimum"], json!(0));
+
+        let token = field(&fields, "auth.token");
+        assert!(token.secret && token.required);
+        assert_eq!(token.widget, FormWidget::Password);
+        assert!(!field(&fields, "auth.user").required);
+    }
+
+    #[test]
+    fn test_const_one_of_becomes_options() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "level": { "oneOf": [{ "const": 1, "title": "Low" }, { "const": 2, "title": "High" }] },
+                "tags": { "type": "array", "items": { "enum": ["a", "b"] } }
+            }
+        });
+
+        let fields = form_fields(&schema);
+        let level = field(&fields, "level");
+        assert_eq!(level.field_type, "integer");
+        assert_eq!(level.options.iter().map(|option| option.label.as_str()).collect::<Vec<_>>(), ["Low", "High"]);
+        assert_eq!(field(&fields, "tags").widget, FormWidget::MultiSelect);
+    }
+
+    #[test]
+    fn test_one_of_with_discriminator_is_flattened() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "auth": {
+                    "oneOf": [
+                        {
+                            "title": "API key",
+                            "properties": { "kind": { "const": "api_key" }, "key": { "type": "string", "format": "password" } },
+                            "required": ["kind", "key"]
+                        },
+                        {
+                            "properties": {
+                                "kind": { "const": "basic" },
+                                "username": { "type": "string" },
+                                "password": { "type": "string", "format": "password" }
+                            },
+                            "required": ["kind", "username"]
+                        }
+                    ]
+                }
+            }
+        });
+
+        let fields = form_fields(&schema);
+        assert_eq!(fields[0].name, "auth.kind");
+        assert_eq!(fields[0].options[0].label, "API key");
+        assert_eq!(fields[0].options[1].label, "basic");
+
+        let key = field(&fields, "auth.key");
+        assert!(key.secret);
+        assert_eq!(key.depends_on, vec![FieldDependency {
+            field: "auth.kind".to_string(),
+            condition: DependencyCondition::In { values: vec![json!("api_key")] },
+            effect: DependencyEffect::Visible,
+        }]);
+        assert_eq!(field(&fields, "auth.password").depends_on[0].condition, DependencyCondition::In { values: vec![json!("basic")] });
+    }
+
+    #[test]
+    fn test_dependencies_between_fields() {
+        let schema = json!({
+            "type": "object",
+            "allOf": [{ "properties": { "mode": { "enum": ["simple", "advanced"] } } }],
+            "properties": {
+                "proxy_host": { "type": "string" },
+                "proxy_port": { "type": "integer" }
+            },
+            "dependentRequired": { "proxy_host": ["proxy_port"] },
+            "if": { "properties": { "mode": { "const": "advanced" } } },
+            "then": { "properties": { "batch_size": { "type": "integer" } }, "required": ["proxy_host"] }
+        });
+
+        let fields = form_fields(&schema);
+        assert!(fields.iter().any(|field| field.name == "mode"));
+        assert_eq!(field(&fields, "proxy_port").depends_on, vec![FieldDependency {
+            field: "proxy_host".to_string(),
+            condition: DependencyCondition::Present,
+            effect: DependencyEffect::Required,
+        }]);
+        assert_eq!(field(&fields, "batch_size").depends_on[0].effect, DependencyEffect::Visible);
+        assert_eq!(field(&fields, "proxy_host").depends_on[0].condition, DependencyCondition::In { values: vec![json!("advanced")] });
+    }
+
+    #[test]
+    fn test_recursive_schema_stops_at_max_depth() {
+        let schema = json!({
+            "$ref": "#/definitions/Node",
+            "definitions": {
+                "Node": { "type": "object", "properties": { "child": { "$ref": "#/definitions/Node" } } }
+            }
+        });
+
+        let fields = form_fields(&schema);
+        assert_eq!(fields.len(), 1);
+        assert_eq!(fields[0].widget, FormWidget::Json);
+        assert_eq!(fields[0].name.split('.').count(), MAX_DEPTH);
+    }
+}
@@ -1,4 +1,8 @@
-use async_graphql::{EmptySubscription, Object, Result, Schema};
+use std::collections::HashSet;
+use async_graphql::{Context, EmptySubscription, Object, Result, Schema};
+use stepflow_core::ToolId;
+use crate::graphql::types::ToolObject;
+use crate::types::UserContext;
 
 #[derive(Default)]
 pub struct QueryRoot;
@@ -9,8 +13,35 @@ impl QueryRoot {
         Ok("Tool query not implemented yet".to_string())
     }
 
-    async fn tools(&self) -> Result<Vec<String>, async_graphql::Error> {
-        Ok(vec!["Tools query not implemented yet".to_string()])
+    /// 列出工具，已认证用户的收藏工具排在最前面
+    async fn tools(&self, ctx: &Context<'_>) -> Result<Vec<ToolObject>, async_graphql::Error> {
+        let state = &ctx.data::<GraphQLContext>()?.app_state;
+
+        let Ok(user) = ctx.data::<UserContext>() else {
+            let tools = state.registry.list_tools().await?;
+            return Ok(tools.into_iter().map(|t| ToolObject::from_tool_info(t, false)).collect());
+        };
+
+        let favorites = state.registry.list_favorites(&user.user_id).await?;
+        let favorite_ids: HashSet<ToolId> = favorites.iter().map(|t| t.id.clone()).collect();
+        let others = state.registry.list_tools().await?
+            .into_iter()
+            .filter(|t| !favorite_ids.contains(&t.id));
+
+        Ok(favorites
+            .into_iter()
+            .map(|t| ToolObject::from_tool_info(t, true))
+            .chain(others.map(|t| ToolObject::from_tool_info(t, false)))
+            .collect())
+    }
+
+    /// 当前用户收藏的工具
+    async fn favorite_tools(&self, ctx: &Context<'_>) -> Result<Vec<ToolObject>, async_graphql::Error> {
+        let state = &ctx.data::<GraphQLContext>()?.app_state;
+        let user = ctx.data::<UserContext>()?;
+
+        let tools = state.registry.list_favorites(&user.user_id).await?;
+        Ok(tools.into_iter().map(|t| ToolObject::from_tool_info(t, true)).collect())
     }
 
     async fn execution(&self, _id: String) -> Result<String, async_graphql::Error> {
@@ -30,6 +61,24 @@ impl MutationRoot {
     async fn execute_tool(&self, _tool_id: String, _input: String) -> Result<String, async_graphql::Error> {
         Ok("Execute tool mutation not implemented yet".to_string())
     }
+
+    /// 收藏工具
+    async fn favorite_tool(&self, ctx: &Context<'_>, tool_id: String) -> Result<bool, async_graphql::Error> {
+        let state = &ctx.data::<GraphQLContext>()?.app_state;
+        let user = ctx.data::<UserContext>()?;
+
+        state.registry.add_favorite(&user.user_id, &ToolId::parse(tool_id)?).await?;
+        Ok(true)
+    }
+
+    /// 取消收藏工具
+    async fn unfavorite_tool(&self, ctx: &Context<'_>, tool_id: String) -> Result<bool, async_graphql::Error> {
+        let state = &ctx.data::<GraphQLContext>()?.app_state;
+        let user = ctx.data::<UserContext>()?;
+
+        state.registry.remove_favorite(&user.user_id, &ToolId::parse(tool_id)?).await?;
+        Ok(true)
+    }
 }
 
 pub type StepflowSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
@@ -40,7 +89,14 @@ pub fn create_schema() -> StepflowSchema {
         .finish()
 }
 
+/// Create GraphQL schema bound to the application state
+pub fn create_schema_with_state(app_state: crate::server::AppState) -> StepflowSchema {
+    Schema::build(QueryRoot, MutationRoot, EmptySubscription)
+        .data(GraphQLContext { app_state })
+        .finish()
+}
+
 /// GraphQL context
 pub struct GraphQLContext {
     pub app_state: crate::server::AppState,
-} 
\ No newline at end of file
+}
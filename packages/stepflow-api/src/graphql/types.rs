@@ -1,2 +1,36 @@
+use async_graphql::SimpleObject;
+use stepflow_core::ToolInfo;
+
 // GraphQL 类型占位符
-pub struct GraphQLTypes; 
\ No newline at end of file
+pub struct GraphQLTypes;
+
+/// GraphQL 工具对象
+#[derive(Debug, Clone, SimpleObject)]
+pub struct ToolObject {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub version: String,
+    pub tool_type: String,
+    pub status: String,
+    pub author: String,
+    pub tags: Vec<String>,
+    pub is_favorite: bool,
+}
+
+impl ToolObject {
+    /// 从工具信息构建，并标记是否为当前用户收藏
+    pub fn from_tool_info(tool: ToolInfo, is_favorite: bool) -> Self {
+        Self {
+            id: tool.id.to_string(),
+            name: tool.name,
+            description: tool.description,
+            version: tool.version.to_string(),
+            tool_type: tool.tool_type.to_string(),
+            status: tool.status.to_string(),
+            author: tool.author,
+            tags: tool.tags,
+            is_favorite,
+        }
+    }
+}
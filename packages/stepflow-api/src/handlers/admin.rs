@@ -1,2 +1,1273 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use stepflow_core::calendar::MAX_PREVIEW;
+use stepflow_core::{AclRules, AuditEvent, FlagDefinition, StepflowError, TenantId, TenantInfo, ToolId, UserId};
+use stepflow_database::{
+    DirectoryConfigRecord, DirectoryConflictPolicy, DirectoryRepository, ExecutionRepository, FeatureFlagRepository, InvitationRecord, InvitationRepository, JobRun,
+    JobScheduler, OidcProviderRecord, OidcRepository, OperationalModeRecord, OperationalModeRepository, RewrapReport,
+    SecurityEventRepository, SessionRepository, SlowQueryRepository, TenantDataKeyInfo, TenantKeyRepository, TenantRepository,
+    ToolRepository, UserRepository,
+};
+use stepflow_monitoring::{
+    AlertManager, AlertRule, AnomalyDetector, LoggingHandle, LoggingSettings, NotificationChannel, NotificationDelivery,
+    PayloadMetrics, SlowExecutionLog,
+};
+use stepflow_registry::{ContentStoreStats, GcReport, RegistryGc, RegistryGcReport};
+use tracing::info;
+use crate::directory::{DirectorySyncReport, DirectorySyncService};
+use crate::email::EmailMessage;
+use crate::errors::ApiError;
+use crate::models::requests::{
+    AdminOverviewParams, CreateAlertRuleRequest, CreateInvitationRequest, CreateOidcProviderRequest, DirectorySyncRequest, ExportTenantRequest,
+    CreateNotificationChannelRequest, ListAlertsParams, ListNotificationDeliveriesParams, SetFeatureFlagRequest, SetOperationalModeRequest, UpdateLoggingRequest,
+    ListAnomaliesParams, ListDirectorySyncRunsParams, ListSlowExecutionsParams, ListSlowQueriesParams,
+    PayloadMetricsParams, PreviewScheduleRequest, RegistryGcRequest, SaveDirectoryConfigRequest,
+    TwoFactorPolicyRequest,
+};
+use crate::models::responses::{
+    AdminOverviewResponse, CacheHitRate, DatabaseOverview, EncryptionKeysResponse, ExecutionOverview, ToolOverview, WorkerOverview, ListAlertRulesResponse, ListFeatureFlagsResponse, ListNotificationChannelsResponse,
+    ListNotificationDeliveriesResponse, ListAlertsResponse, ListAnomaliesResponse,
+    ListDirectorySyncRunsResponse, ListInvitationsResponse, ListJobsResponse, ListOidcProvidersResponse,
+    ListSessionsResponse, ListSlowExecutionsResponse, ListSlowQueriesResponse, PayloadMetricsResponse,
+    PreviewScheduleResponse, ScheduleFireTime, SlowQuerySummaryResponse,
+};
+use crate::server::AppState;
+use crate::tenant_bundle::{TenantBundle, TenantBundleService, TenantExportOptions, TenantImportReport};
+use crate::two_factor::REQUIRE_ADMIN_2FA_SETTING;
+use crate::middleware::ClientAddr;
+use crate::types::{CorsConfig, UserContext, CORS_POLICY_SETTING, NETWORK_ACL_SETTING};
+use crate::user_data::{archive_signing_key, UserDataService};
+use super::{parse_role, require_admin, require_system_admin, require_tenant, user_agent};
+
 // 管理处理器占位符
-pub struct AdminHandler; 
\ No newline at end of file
+pub struct AdminHandler;
+
+/// 列出告警规则
+pub async fn list_alert_rules(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+) -> Result<Json<ListAlertRulesResponse>, ApiError> {
+    require_admin(&user)?;
+    let tenant_id = require_tenant(&user)?;
+    let rules = AlertManager::new(state.db.clone()).list_rules(&tenant_id).await?;
+    Ok(Json(ListAlertRulesResponse { rules }))
+}
+
+/// 创建告警规则
+///
+/// 例如工具失败率在 15 分钟窗口内超过 10%，或排队等待 p95 超过 30 秒。
+pub async fn create_alert_rule(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Json(request): Json<CreateAlertRuleRequest>,
+) -> Result<Json<AlertRule>, ApiError> {
+    require_admin(&user)?;
+    let tenant_id = require_tenant(&user)?;
+    if request.name.trim().is_empty() {
+        return Err(ApiError::BadRequest("Alert rule name is required".to_string()));
+    }
+
+    let mut rule = AlertRule::new(
+        tenant_id,
+        request.name,
+        request.metric,
+        request.comparison,
+        request.threshold,
+        Duration::from_secs(request.window_secs),
+    )
+    .with_min_samples(request.min_samples.unwrap_or(1));
+    if let Some(tool_id) = request.tool_id {
+        rule = rule.for_tool(ToolId::parse(tool_id)?);
+    }
+    if let Some(url) = request.webhook_url {
+        rule = rule.with_webhook(url);
+    }
+
+    let rule = AlertManager::new(state.db.clone()).create_rule(rule).await?;
+    Ok(Json(rule))
+}
+
+/// 删除告警规则及其告警记录
+pub async fn delete_alert_rule(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(rule_id): Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    require_admin(&user)?;
+    let tenant_id = require_tenant(&user)?;
+    if !AlertManager::new(state.db.clone()).delete_rule(&tenant_id, &rule_id).await? {
+        return Err(ApiError::NotFound(format!("Alert rule {} not found", rule_id)));
+    }
+
+    Ok(Json(serde_json::json!({
+        "rule_id": rule_id,
+        "message": "Alert rule removed"
+    })))
+}
+
+/// 列出告警（`?firing=true` 仅返回未恢复的告警）
+pub async fn list_alerts(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Query(params): Query<ListAlertsParams>,
+) -> Result<Json<ListAlertsResponse>, ApiError> {
+    require_admin(&user)?;
+    let tenant_id = require_tenant(&user)?;
+    let alerts = AlertManager::new(state.db.clone())
+        .list_alerts(&tenant_id, params.firing.unwrap_or(false))
+        .await?;
+    Ok(Json(ListAlertsResponse { alerts }))
+}
+
+/// 列出租户的通知渠道
+pub async fn list_notification_channels(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+) -> Result<Json<ListNotificationChannelsResponse>, ApiError> {
+    require_admin(&user)?;
+    let tenant_id = require_tenant(&user)?;
+    let channels = state.notification_service().list_channels(&tenant_id).await?;
+    Ok(Json(ListNotificationChannelsResponse { channels }))
+}
+
+/// 创建通知渠道（Slack、邮件或 webhook），告警与审批等事件按订阅发送到该渠道
+pub async fn create_notification_channel(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Json(request): Json<CreateNotificationChannelRequest>,
+) -> Result<Json<NotificationChannel>, ApiError> {
+    require_admin(&user)?;
+    let tenant_id = require_tenant(&user)?;
+    let channel = NotificationChannel::new(tenant_id, request.name, request.target)
+        .with_events(request.events)
+        .with_templates(request.subject_template, request.body_template);
+    channel.validate()?;
+
+    let channel = state.notification_service().create_channel(channel).await?;
+    Ok(Json(channel))
+}
+
+/// 删除通知渠道及其投递记录
+pub async fn delete_notification_channel(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(channel_id): Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    require_admin(&user)?;
+    let tenant_id = require_tenant(&user)?;
+    if !state.notification_service().delete_channel(&tenant_id, &channel_id).await? {
+        return Err(ApiError::NotFound(format!("Notification channel {} not found", channel_id)));
+    }
+
+    Ok(Json(serde_json::json!({
+        "channel_id": channel_id,
+        "message": "Notification channel removed"
+    })))
+}
+
+/// 向通知渠道发送测试消息，返回投递结果
+pub async fn test_notification_channel(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(channel_id): Path<String>,
+) -> Result<Json<NotificationDelivery>, ApiError> {
+    require_admin(&user)?;
+    let tenant_id = require_tenant(&user)?;
+    let delivery = state.notification_service()
+        .send_test(&tenant_id, &channel_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Notification channel {} not found", channel_id)))?;
+    Ok(Json(delivery))
+}
+
+/// 列出通知投递记录（最新在前，可按 `?channel_id=` 过滤，默认 100 条）
+pub async fn list_notification_deliveries(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Query(params): Query<ListNotificationDeliveriesParams>,
+) -> Result<Json<ListNotificationDeliveriesResponse>, ApiError> {
+    require_admin(&user)?;
+    let tenant_id = require_tenant(&user)?;
+    let deliveries = state.notification_service()
+        .list_deliveries(&tenant_id, params.channel_id.as_deref(), params.limit.unwrap_or(100).min(1000))
+        .await?;
+    Ok(Json(ListNotificationDeliveriesResponse { deliveries }))
+}
+
+/// 列出当前租户的执行异常（最新在前，可按 `?tool_id=` 过滤）
+pub async fn list_anomalies(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Query(params): Query<ListAnomaliesParams>,
+) -> Result<Json<ListAnomaliesResponse>, ApiError> {
+    require_admin(&user)?;
+    let tenant_id = require_tenant(&user)?;
+    let tool_id = params.tool_id.map(ToolId::parse).transpose()?;
+    let limit = params.limit.unwrap_or(100).min(1000);
+    let anomalies = AnomalyDetector::new(state.db.clone())
+        .list_anomalies(tenant_id.as_str(), tool_id.as_ref(), limit)
+        .await?;
+    Ok(Json(ListAnomaliesResponse { anomalies }))
+}
+
+/// 按工具汇总的执行载荷指标（输入输出大小、数组长度、schema 警告与增长比），
+/// 平均输入最大的工具在前，可按 `?tool_id=` 过滤
+pub async fn get_payload_metrics(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Query(params): Query<PayloadMetricsParams>,
+) -> Result<Json<PayloadMetricsResponse>, ApiError> {
+    require_admin(&user)?;
+    let tenant_id = require_tenant(&user)?;
+    let window_secs = params.window_secs.unwrap_or(24 * 60 * 60);
+    if window_secs == 0 {
+        return Err(ApiError::ValidationError("window_secs must be positive".to_string()));
+    }
+    let tool_id = params.tool_id.map(ToolId::parse).transpose()?;
+    let tools = PayloadMetrics::new(state.db.clone())
+        .summaries(tenant_id.as_str(), tool_id.as_ref(), Duration::from_secs(window_secs))
+        .await?;
+    Ok(Json(PayloadMetricsResponse { window_secs, tools }))
+}
+
+/// 列出慢查询（最新在前），含执行计划，不含绑定参数
+pub async fn list_slow_queries(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Query(params): Query<ListSlowQueriesParams>,
+) -> Result<Json<ListSlowQueriesResponse>, ApiError> {
+    require_admin(&user)?;
+    let limit = params.limit.unwrap_or(100).min(1000);
+    let queries = SlowQueryRepository::new(state.db.as_ref().clone()).list(limit).await?;
+    Ok(Json(ListSlowQueriesResponse { queries }))
+}
+
+/// 按语句汇总慢查询，累计耗时最长的在前
+pub async fn summarize_slow_queries(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Query(params): Query<ListSlowQueriesParams>,
+) -> Result<Json<SlowQuerySummaryResponse>, ApiError> {
+    require_admin(&user)?;
+    let limit = params.limit.unwrap_or(50).min(1000);
+    let statements = SlowQueryRepository::new(state.db.as_ref().clone()).summaries(limit).await?;
+    Ok(Json(SlowQuerySummaryResponse { statements }))
+}
+
+/// 列出当前租户明显慢于工具基线的执行（最新在前，可按 `?tool_id=` 过滤）
+pub async fn list_slow_executions(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Query(params): Query<ListSlowExecutionsParams>,
+) -> Result<Json<ListSlowExecutionsResponse>, ApiError> {
+    require_admin(&user)?;
+    let tenant_id = require_tenant(&user)?;
+    let tool_id = params.tool_id.map(ToolId::parse).transpose()?;
+    let limit = params.limit.unwrap_or(100).min(1000);
+    let executions = SlowExecutionLog::new(state.db.clone())
+        .list(tenant_id.as_str(), tool_id.as_ref(), limit)
+        .await?;
+    Ok(Json(ListSlowExecutionsResponse { executions }))
+}
+
+/// 内容寻址存储的去重统计
+pub async fn get_storage_stats(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+) -> Result<Json<ContentStoreStats>, ApiError> {
+    require_admin(&user)?;
+    Ok(Json(state.content_store.stats()?))
+}
+
+/// 回收内容寻址存储中未被引用的内容
+pub async fn collect_storage_garbage(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+) -> Result<Json<GcReport>, ApiError> {
+    require_system_admin(&state.config.auth_config, &user)?;
+    Ok(Json(state.content_store.gc()?))
+}
+
+/// 回收注册表中旧版本与已删除工具的数据
+///
+/// 默认只返回将被回收的行数报告；`dry_run` 为 `false` 时才删除。
+pub async fn collect_registry_garbage(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Json(request): Json<RegistryGcRequest>,
+) -> Result<Json<RegistryGcReport>, ApiError> {
+    require_system_admin(&state.config.auth_config, &user)?;
+    let mut policy = state.gc_policy.clone();
+    if let Some(keep_versions) = request.keep_versions {
+        if keep_versions == 0 {
+            return Err(ApiError::BadRequest("keep_versions must be at least 1".to_string()));
+        }
+        policy.keep_versions = keep_versions;
+    }
+
+    let report = RegistryGc::new(state.db.clone(), policy).run(request.dry_run).await?;
+    if !request.dry_run {
+        info!("User {} collected {} registry row(s)", user.user_id.as_str(), report.total_rows);
+    }
+    Ok(Json(report))
+}
+
+/// 执行统计窗口上限：30 天
+const MAX_OVERVIEW_HOURS: u32 = 30 * 24;
+
+/// 管理概览：工具、执行、队列、执行器、数据库与缓存的关键统计
+pub async fn get_admin_overview(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Query(params): Query<AdminOverviewParams>,
+) -> Result<Json<AdminOverviewResponse>, ApiError> {
+    require_admin(&user)?;
+    let hours = params.hours.unwrap_or(24).clamp(1, MAX_OVERVIEW_HOURS);
+    let since = chrono::Utc::now() - chrono::Duration::hours(hours as i64);
+    let tools = ToolRepository::new(state.db.as_ref().clone());
+    let executions = ExecutionRepository::new(state.db.as_ref().clone());
+
+    let by_type_and_status = tools.count_by_type_and_status().await?;
+    let buckets = executions.status_histogram(since, params.bucket).await?;
+    let mut by_status = std::collections::BTreeMap::new();
+    for bucket in &buckets {
+        *by_status.entry(bucket.status.clone()).or_insert(0) += bucket.count;
+    }
+    let top_failing_tools = executions.top_failing_tools(since, params.top.unwrap_or(10).min(100)).await?;
+
+    // 执行器不可用时概览仍然返回，只标记为不健康
+    let healthy = state.executor.health_check().await.unwrap_or(false);
+    let load = state.executor.load_status().await.unwrap_or(None);
+
+    let statement_cache = state.db.statement_cache_stats();
+    let mut caches = vec![CacheHitRate::new("statements", statement_cache.hits, statement_cache.misses)];
+    if let Some(stats) = state.registry.cache_stats().await {
+        caches.push(CacheHitRate::new("registry", stats.hits, stats.misses));
+    }
+
+    Ok(Json(AdminOverviewResponse {
+        generated_at: chrono::Utc::now(),
+        tools: ToolOverview { total: by_type_and_status.iter().map(|c| c.count).sum(), by_type_and_status },
+        executions: ExecutionOverview {
+            since,
+            bucket: params.bucket,
+            total: by_status.values().sum(),
+            by_status,
+            buckets,
+            top_failing_tools,
+        },
+        queue: executions.queue_depth().await?,
+        workers: WorkerOverview { healthy, load },
+        database: DatabaseOverview { size_bytes: state.db.size_bytes().await?, statement_cache },
+        caches,
+    }))
+}
+
+/// 未配置主密钥时密钥管理接口不可用
+fn tenant_keys(state: &AppState) -> Result<TenantKeyRepository, ApiError> {
+    if state.db.keyring().is_none() {
+        return Err(ApiError::ServiceUnavailable("Encryption is not configured".to_string()));
+    }
+    Ok(TenantKeyRepository::new(state.db.as_ref().clone()))
+}
+
+/// 查看主密钥轮换状态与当前租户的数据密钥（不含密钥内容）
+pub async fn get_encryption_keys(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+) -> Result<Json<EncryptionKeysResponse>, ApiError> {
+    require_admin(&user)?;
+    let tenant_id = require_tenant(&user)?;
+    let keys = TenantKeyRepository::new(state.db.as_ref().clone());
+    Ok(Json(EncryptionKeysResponse {
+        status: keys.status().await?,
+        keys: keys.list_keys(&tenant_id).await?,
+    }))
+}
+
+/// 轮换当前租户的数据密钥；旧版本转为停用，仍可解密已有数据
+pub async fn rotate_tenant_key(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+) -> Result<Json<TenantDataKeyInfo>, ApiError> {
+    require_admin(&user)?;
+    let tenant_id = require_tenant(&user)?;
+    let key = tenant_keys(&state)?.rotate(&tenant_id).await?;
+    info!("User {} rotated the data key of tenant {}", user.user_id.as_str(), tenant_id.as_str());
+    Ok(Json(key))
+}
+
+/// 更换主密钥后，用当前主密钥重新包装全部数据密钥，无需重新加密数据
+pub async fn rewrap_data_keys(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+) -> Result<Json<RewrapReport>, ApiError> {
+    require_system_admin(&state.config.auth_config, &user)?;
+    Ok(Json(tenant_keys(&state)?.rewrap_all().await?))
+}
+
+fn logging_handle(state: &AppState) -> Result<&LoggingHandle, ApiError> {
+    state.logging
+        .as_ref()
+        .ok_or_else(|| ApiError::ServiceUnavailable("Runtime logging configuration is not enabled".to_string()))
+}
+
+fn job_scheduler(state: &AppState) -> Result<&JobScheduler, ApiError> {
+    state.jobs
+        .as_deref()
+        .ok_or_else(|| ApiError::ServiceUnavailable("Background jobs are not enabled".to_string()))
+}
+
+/// 列出后台任务及其下次运行时间和上次运行结果
+pub async fn list_jobs(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+) -> Result<Json<ListJobsResponse>, ApiError> {
+    require_admin(&user)?;
+    let jobs = job_scheduler(&state)?.list().await?;
+    Ok(Json(ListJobsResponse { jobs }))
+}
+
+/// 立即运行一次后台任务并返回结果，不影响下次计划运行时间
+pub async fn run_job(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(name): Path<String>,
+) -> Result<Json<JobRun>, ApiError> {
+    require_system_admin(&state.config.auth_config, &user)?;
+    let scheduler = job_scheduler(&state)?;
+    if !scheduler.contains(&name) {
+        return Err(ApiError::NotFound(format!("Background job {} not found", name)));
+    }
+    info!("Background job {} triggered by {}", name, user.user_id);
+    match scheduler.trigger(&name).await {
+        Ok(run) => Ok(Json(run)),
+        Err(StepflowError::Conflict(message)) => Err(ApiError::Conflict(message)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// 预览日历计划接下来的触发时间，用于在写入 `job_schedules` 配置前核对时区、工作日与停机窗口
+pub async fn preview_job_schedule(
+    Extension(user): Extension<UserContext>,
+    Json(request): Json<PreviewScheduleRequest>,
+) -> Result<Json<PreviewScheduleResponse>, ApiError> {
+    require_admin(&user)?;
+    request.schedule.validate().map_err(ApiError::ValidationError)?;
+    let count = request.count.unwrap_or(5);
+    if count == 0 || count > MAX_PREVIEW {
+        return Err(ApiError::ValidationError(format!("count must be between 1 and {}", MAX_PREVIEW)));
+    }
+    let schedule = &request.schedule;
+    let fire_times = schedule
+        .preview(request.after.unwrap_or_else(chrono::Utc::now), count)
+        .into_iter()
+        .map(|at| ScheduleFireTime { at, local: at.with_timezone(&schedule.timezone).to_rfc3339() })
+        .collect();
+    Ok(Json(PreviewScheduleResponse { timezone: schedule.timezone.name().to_string(), fire_times }))
+}
+
+/// 查看当前日志级别与采样规则
+pub async fn get_logging(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+) -> Result<Json<LoggingSettings>, ApiError> {
+    require_admin(&user)?;
+    Ok(Json(logging_handle(&state)?.settings()))
+}
+
+/// 运行时调整日志级别与采样规则，无需重新部署
+pub async fn update_logging(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Json(request): Json<UpdateLoggingRequest>,
+) -> Result<Json<LoggingSettings>, ApiError> {
+    require_system_admin(&state.config.auth_config, &user)?;
+    let handle = logging_handle(&state)?;
+    let invalid = |e: stepflow_core::MonitoringError| ApiError::BadRequest(e.to_string());
+
+    // 先校验采样规则，避免级别已生效而采样规则无效
+    if let Some(sampling) = &request.sampling {
+        let previous = handle.sampling();
+        handle.set_sampling(sampling).map_err(invalid)?;
+        if let Some(level) = &request.level {
+            if let Err(e) = handle.set_levels(level, &request.modules) {
+                handle.set_sampling(&previous).map_err(invalid)?;
+                return Err(invalid(e));
+            }
+        }
+    } else if let Some(level) = &request.level {
+        handle.set_levels(level, &request.modules).map_err(invalid)?;
+    }
+
+    let settings = handle.settings();
+    info!(
+        "Logging reconfigured by {}: directives={}, sampling rules={}",
+        user.user_id, settings.directives, settings.sampling.len()
+    );
+    Ok(Json(settings))
+}
+
+/// 导入租户包时携带口令的请求头
+pub const BUNDLE_PASSPHRASE_HEADER: &str = "x-stepflow-bundle-passphrase";
+
+/// 导出当前租户的状态包（`tar.zst`）
+pub async fn export_tenant(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(tenant_id): Path<String>,
+    Json(request): Json<ExportTenantRequest>,
+) -> Result<Response, ApiError> {
+    require_admin(&user)?;
+    let own_tenant = require_tenant(&user)?;
+    if own_tenant.as_str() != tenant_id {
+        return Err(ApiError::Forbidden("Only the current tenant can be exported".to_string()));
+    }
+
+    let mut options = TenantExportOptions::new(request.passphrase);
+    if let Some(days) = request.execution_history_days {
+        options.execution_history = chrono::Duration::days(days as i64);
+    }
+    let bundle = TenantBundleService::new(state.db.clone()).export(&own_tenant, &options).await?;
+    let disposition = format!("attachment; filename=\"{}.tar.zst\"", tenant_id);
+    Ok(([(header::CONTENT_TYPE, "application/zstd".to_string()), (header::CONTENT_DISPOSITION, disposition)], bundle)
+        .into_response())
+}
+
+/// 导入租户状态包，口令通过 `x-stepflow-bundle-passphrase` 请求头提供
+pub async fn import_tenant(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<TenantImportReport>, ApiError> {
+    require_admin(&user)?;
+    let tenant_id = require_tenant(&user)?;
+    let passphrase = headers
+        .get(BUNDLE_PASSPHRASE_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| ApiError::BadRequest(format!("{} header is required", BUNDLE_PASSPHRASE_HEADER)))?;
+
+    let bundle = TenantBundle::open(&body)?;
+    if bundle.manifest().tenant_id != tenant_id.as_str() {
+        return Err(ApiError::Forbidden("Bundle belongs to another tenant".to_string()));
+    }
+    let report = TenantBundleService::new(state.db.clone()).import(&bundle, passphrase).await?;
+    Ok(Json(report))
+}
+
+/// 邀请用户加入当前租户，邀请令牌通过邮件发送
+pub async fn create_invitation(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Json(request): Json<CreateInvitationRequest>,
+) -> Result<Json<InvitationRecord>, ApiError> {
+    require_admin(&user)?;
+    let tenant_id = require_tenant(&user)?;
+    let email = request.email.trim();
+    if !email.contains('@') {
+        return Err(ApiError::BadRequest("A valid email address is required".to_string()));
+    }
+    let role = parse_role(request.role.as_deref().unwrap_or("user"))?;
+
+    let ttl = chrono::Duration::from_std(state.config.auth_config.invitation_expiration)
+        .map_err(|e| ApiError::InternalServerError(e.to_string()))?;
+    let repository = InvitationRepository::new(state.db.as_ref().clone());
+    let (invitation, token) = repository
+        .create_invitation(email, role, &tenant_id, Some(&user.user_id), ttl)
+        .await?;
+
+    let message = EmailMessage {
+        to: invitation.email.clone(),
+        subject: "You have been invited to Stepflow".to_string(),
+        body: format!(
+            "You have been invited to join Stepflow.\n\n\
+             Accept the invitation by sending this token with your username and password \
+             to POST /api/v1/auth/accept-invite:\n\n{}\n\nThe invitation expires at {}.",
+            token,
+            invitation.expires_at.to_rfc3339(),
+        ),
+    };
+    if let Err(e) = state.email_sender.send(&message).await {
+        // 邀请邮件未送达时撤销邀请，避免留下无法使用的记录
+        repository.revoke_invitation(&tenant_id, &invitation.id).await?;
+        return Err(e);
+    }
+
+    Ok(Json(invitation))
+}
+
+/// 列出当前租户的邀请
+pub async fn list_invitations(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+) -> Result<Json<ListInvitationsResponse>, ApiError> {
+    require_admin(&user)?;
+    let tenant_id = require_tenant(&user)?;
+    let invitations = InvitationRepository::new(state.db.as_ref().clone())
+        .list_invitations(&tenant_id)
+        .await?;
+    Ok(Json(ListInvitationsResponse { invitations }))
+}
+
+/// 撤销尚未接受的邀请
+pub async fn revoke_invitation(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(invitation_id): Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    require_admin(&user)?;
+    let tenant_id = require_tenant(&user)?;
+    if !InvitationRepository::new(state.db.as_ref().clone())
+        .revoke_invitation(&tenant_id, &invitation_id)
+        .await?
+    {
+        return Err(ApiError::NotFound(format!("Pending invitation {} not found", invitation_id)));
+    }
+
+    Ok(Json(serde_json::json!({
+        "invitation_id": invitation_id,
+        "message": "Invitation revoked"
+    })))
+}
+
+/// 设置租户的双因素认证策略（是否要求管理员启用）
+pub async fn set_two_factor_policy(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Json(request): Json<TwoFactorPolicyRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    require_admin(&user)?;
+    let tenant_id = require_tenant(&user)?;
+    let repository = TenantRepository::new(state.db.as_ref().clone());
+    let mut tenant = repository.get_tenant(&tenant_id).await?
+        .ok_or_else(|| ApiError::NotFound(format!("Tenant {} not found", tenant_id.as_str())))?;
+
+    tenant.settings.insert(
+        REQUIRE_ADMIN_2FA_SETTING.to_string(),
+        serde_json::Value::Bool(request.require_for_admins),
+    );
+    tenant.updated_at = chrono::Utc::now();
+    repository.update_tenant(&tenant_id, &tenant).await?;
+
+    Ok(Json(serde_json::json!({
+        "tenant_id": tenant_id.as_str(),
+        "require_for_admins": request.require_for_admins
+    })))
+}
+
+/// 获取租户当前生效的 CORS 策略
+pub async fn get_cors_policy(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let tenant = admin_tenant(&state, &user).await?;
+    let custom = tenant.settings.get(CORS_POLICY_SETTING)
+        .map(|setting| serde_json::from_value::<CorsConfig>(setting.clone()))
+        .transpose()?;
+
+    Ok(Json(serde_json::json!({
+        "tenant_id": tenant.id.as_str(),
+        "domain": tenant.domain,
+        "custom": custom.is_some(),
+        "policy": custom.unwrap_or_else(|| state.config.cors_config.clone())
+    })))
+}
+
+/// 保存租户的 CORS 策略，对指向租户域名的请求生效
+pub async fn set_cors_policy(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Json(policy): Json<CorsConfig>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    if !state.config.cors_config.allow_tenant_overrides {
+        return Err(ApiError::Forbidden("Tenant CORS policies are disabled on this deployment".to_string()));
+    }
+    let violations = policy.violations();
+    if !violations.is_empty() {
+        return Err(ApiError::ValidationError(format!("Invalid CORS policy: {}", violations.join("; "))));
+    }
+
+    let mut tenant = admin_tenant(&state, &user).await?;
+    tenant.settings.insert(CORS_POLICY_SETTING.to_string(), serde_json::to_value(&policy)?);
+    tenant.updated_at = chrono::Utc::now();
+    TenantRepository::new(state.db.as_ref().clone()).update_tenant(&tenant.id, &tenant).await?;
+    state.cors_policies.clear();
+
+    Ok(Json(serde_json::json!({
+        "tenant_id": tenant.id.as_str(),
+        "domain": tenant.domain,
+        "policy": policy
+    })))
+}
+
+/// 删除租户的 CORS 策略，恢复使用部署级策略
+pub async fn delete_cors_policy(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let mut tenant = admin_tenant(&state, &user).await?;
+    if tenant.settings.remove(CORS_POLICY_SETTING).is_some() {
+        tenant.updated_at = chrono::Utc::now();
+        TenantRepository::new(state.db.as_ref().clone()).update_tenant(&tenant.id, &tenant).await?;
+        state.cors_policies.clear();
+    }
+
+    Ok(Json(serde_json::json!({
+        "tenant_id": tenant.id.as_str(),
+        "message": "CORS policy reset to deployment default"
+    })))
+}
+
+/// 查看租户的网络访问控制列表和拒绝计数
+pub async fn get_network_acl(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let tenant = admin_tenant(&state, &user).await?;
+    let rules = state.network_acl.tenant_rules(tenant.id.as_str()).unwrap_or_default();
+
+    Ok(Json(serde_json::json!({
+        "tenant_id": tenant.id.as_str(),
+        "domain": tenant.domain,
+        "rules": rules,
+        "metrics": state.network_acl.metrics()
+    })))
+}
+
+/// 保存租户的网络访问控制列表，立即生效
+///
+/// 会把当前请求的客户端地址拒之门外的列表不予保存，避免管理员锁死自己。
+pub async fn set_network_acl(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    client: Option<Extension<ClientAddr>>,
+    Json(rules): Json<AclRules>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let mut tenant = admin_tenant(&state, &user).await?;
+    if let Some(Extension(ClientAddr(ip))) = client {
+        if !rules.permits(ip) {
+            return Err(ApiError::ValidationError(format!(
+                "Network ACL would block the current client address {}",
+                ip
+            )));
+        }
+    }
+
+    if rules.is_empty() {
+        tenant.settings.remove(NETWORK_ACL_SETTING);
+    } else {
+        tenant.settings.insert(NETWORK_ACL_SETTING.to_string(), serde_json::to_value(&rules)?);
+    }
+    tenant.updated_at = chrono::Utc::now();
+    TenantRepository::new(state.db.as_ref().clone()).update_tenant(&tenant.id, &tenant).await?;
+    state.network_acl.set_tenant_rules(tenant.id.as_str(), tenant.domain.as_deref(), rules.clone());
+
+    Ok(Json(serde_json::json!({
+        "tenant_id": tenant.id.as_str(),
+        "domain": tenant.domain,
+        "rules": rules
+    })))
+}
+
+/// 删除租户的网络访问控制列表，只保留全局列表
+pub async fn delete_network_acl(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let mut tenant = admin_tenant(&state, &user).await?;
+    if tenant.settings.remove(NETWORK_ACL_SETTING).is_some() {
+        tenant.updated_at = chrono::Utc::now();
+        TenantRepository::new(state.db.as_ref().clone()).update_tenant(&tenant.id, &tenant).await?;
+    }
+    state.network_acl.set_tenant_rules(tenant.id.as_str(), None, AclRules::default());
+
+    Ok(Json(serde_json::json!({
+        "tenant_id": tenant.id.as_str(),
+        "message": "Network ACL removed"
+    })))
+}
+
+/// 查看系统运行模式
+pub async fn get_operational_mode(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+) -> Result<Json<OperationalModeRecord>, ApiError> {
+    require_admin(&user)?;
+    Ok(Json(OperationalModeRepository::new(state.db.as_ref().clone()).get().await?))
+}
+
+/// 切换系统运行模式（正常 / 维护 / 只读），持久化后对所有实例生效，重启后保持
+pub async fn set_operational_mode(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    client: Option<Extension<ClientAddr>>,
+    headers: HeaderMap,
+    Json(request): Json<SetOperationalModeRequest>,
+) -> Result<Json<OperationalModeRecord>, ApiError> {
+    require_system_admin(&state.config.auth_config, &user)?;
+    let message = request.message.as_deref().map(str::trim).filter(|message| !message.is_empty());
+    let record = OperationalModeRepository::new(state.db.as_ref().clone())
+        .set(request.mode, message, Some(user.user_id.as_str()))
+        .await?;
+    state.operational_mode.set(record.clone());
+
+    let event = AuditEvent {
+        event_id: uuid::Uuid::new_v4().to_string(),
+        event_type: "operational_mode_changed".to_string(),
+        user_id: Some(user.user_id.clone()),
+        tenant_id: user.tenant_id.clone().map(TenantId::from_string),
+        resource_type: "system".to_string(),
+        resource_id: "operational_mode".to_string(),
+        action: record.mode.as_str().to_string(),
+        details: HashMap::from([("message".to_string(), serde_json::json!(record.message))]),
+        ip_address: client.map(|Extension(client)| client.to_string()),
+        user_agent: user_agent(&headers),
+        timestamp: chrono::Utc::now(),
+        success: true,
+        error_message: None,
+    };
+    SecurityEventRepository::new(state.db.as_ref().clone()).record_event(&event).await?;
+    info!("Operational mode set to {} by {}", record.mode.as_str(), user.user_id);
+    Ok(Json(record))
+}
+
+/// 列出功能开关（配置文件中的默认值与运行时设置合并后的结果）
+pub async fn list_feature_flags(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+) -> Result<Json<ListFeatureFlagsResponse>, ApiError> {
+    require_admin(&user)?;
+    Ok(Json(ListFeatureFlagsResponse { flags: state.flags.definitions() }))
+}
+
+/// 设置功能开关，持久化后立即对本实例生效，覆盖配置文件中的同名开关
+pub async fn set_feature_flag(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    client: Option<Extension<ClientAddr>>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+    Json(request): Json<SetFeatureFlagRequest>,
+) -> Result<Json<FlagDefinition>, ApiError> {
+    require_admin(&user)?;
+    let definition = FlagDefinition {
+        name,
+        description: request.description,
+        enabled: request.enabled,
+        rollout_percentage: request.rollout_percentage,
+        tenants: request.tenants,
+        users: request.users,
+        updated_by: None,
+        updated_at: None,
+    };
+    definition.validate()?;
+    require_flag_admin(&state, &user, state.flags.get(&definition.name).iter().chain([&definition]))?;
+    let stored = FeatureFlagRepository::new(state.db.as_ref().clone())
+        .upsert(&definition, Some(user.user_id.as_str()))
+        .await?;
+    state.flags.set(stored.clone());
+
+    record_flag_event(&state, &user, client, &headers, &stored.name, "set", serde_json::to_value(&stored)?).await?;
+    info!("Feature flag {} set by {}", stored.name, user.user_id);
+    Ok(Json(stored))
+}
+
+/// 删除运行时设置的功能开关，配置文件中的同名开关重新生效
+pub async fn delete_feature_flag(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    client: Option<Extension<ClientAddr>>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    require_flag_admin(&state, &user, state.flags.get(&name).iter())?;
+    if !FeatureFlagRepository::new(state.db.as_ref().clone()).delete(&name).await? {
+        return Err(ApiError::NotFound(format!("Feature flag {} not found", name)));
+    }
+    state.flags.reset(&name);
+
+    record_flag_event(&state, &user, client, &headers, &name, "delete", serde_json::Value::Null).await?;
+    info!("Feature flag {} deleted by {}", name, user.user_id);
+    Ok(Json(serde_json::json!({
+        "name": name,
+        "message": "Feature flag deleted"
+    })))
+}
+
+/// 要求当前用户可以修改给出的功能开关
+///
+/// 开关对整个部署生效。租户管理员只能修改仅作用于本租户的开关，修改前后的定义都须如此；
+/// 带灰度比例、用户覆盖或其它租户覆盖的开关需要系统管理员。
+fn require_flag_admin<'a>(
+    state: &AppState,
+    user: &UserContext,
+    definitions: impl IntoIterator<Item = &'a FlagDefinition>,
+) -> Result<(), ApiError> {
+    require_admin(user)?;
+    let tenant_id = require_tenant(user)?;
+    if definitions.into_iter().all(|definition| definition.is_scoped_to(tenant_id.as_str())) {
+        return Ok(());
+    }
+    require_system_admin(&state.config.auth_config, user)
+}
+
+async fn record_flag_event(
+    state: &AppState,
+    user: &UserContext,
+    client: Option<Extension<ClientAddr>>,
+    headers: &HeaderMap,
+    name: &str,
+    action: &str,
+    flag: serde_json::Value,
+) -> Result<(), ApiError> {
+    let event = AuditEvent {
+        event_id: uuid::Uuid::new_v4().to_string(),
+        event_type: "feature_flag_changed".to_string(),
+        user_id: Some(user.user_id.clone()),
+        tenant_id: user.tenant_id.clone().map(TenantId::from_string),
+        resource_type: "feature_flag".to_string(),
+        resource_id: name.to_string(),
+        action: action.to_string(),
+        details: HashMap::from([("flag".to_string(), flag)]),
+        ip_address: client.map(|Extension(client)| client.to_string()),
+        user_agent: user_agent(headers),
+        timestamp: chrono::Utc::now(),
+        success: true,
+        error_message: None,
+    };
+    SecurityEventRepository::new(state.db.as_ref().clone()).record_event(&event).await?;
+    Ok(())
+}
+
+/// 校验管理员身份并加载其所在租户
+async fn admin_tenant(state: &AppState, user: &UserContext) -> Result<TenantInfo, ApiError> {
+    require_admin(user)?;
+    let tenant_id = require_tenant(user)?;
+    TenantRepository::new(state.db.as_ref().clone())
+        .get_tenant(&tenant_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Tenant {} not found", tenant_id.as_str())))
+}
+
+/// 列出租户的 OIDC 提供商
+pub async fn list_oidc_providers(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+) -> Result<Json<ListOidcProvidersResponse>, ApiError> {
+    require_admin(&user)?;
+    let tenant_id = require_tenant(&user)?;
+    let providers = OidcRepository::new(state.db.as_ref().clone()).list_providers(&tenant_id).await?;
+    Ok(Json(ListOidcProvidersResponse { providers }))
+}
+
+/// 为租户添加 OIDC 提供商
+pub async fn create_oidc_provider(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Json(request): Json<CreateOidcProviderRequest>,
+) -> Result<Json<OidcProviderRecord>, ApiError> {
+    require_admin(&user)?;
+    let tenant_id = require_tenant(&user)?;
+    if request.name.trim().is_empty() || request.client_id.trim().is_empty() {
+        return Err(ApiError::ValidationError("Provider name and client ID are required".to_string()));
+    }
+    let issuer = url::Url::parse(request.issuer.trim())
+        .map_err(|e| ApiError::ValidationError(format!("Invalid issuer URL: {}", e)))?;
+    if !matches!(issuer.scheme(), "https" | "http") {
+        return Err(ApiError::ValidationError("Issuer must be an HTTP(S) URL".to_string()));
+    }
+
+    let mut scopes = request.scopes.unwrap_or_else(|| {
+        vec!["openid".to_string(), "email".to_string(), "profile".to_string()]
+    });
+    if !scopes.iter().any(|scope| scope == "openid") {
+        scopes.insert(0, "openid".to_string());
+    }
+    let role_mapping = request.role_mapping
+        .into_iter()
+        .map(|(value, role)| Ok((value, parse_role(&role)?)))
+        .collect::<Result<_, ApiError>>()?;
+
+    let now = chrono::Utc::now();
+    let provider = OidcProviderRecord {
+        id: uuid::Uuid::new_v4().to_string(),
+        tenant_id,
+        name: request.name.trim().to_string(),
+        issuer: request.issuer.trim().trim_end_matches('/').to_string(),
+        client_id: request.client_id.trim().to_string(),
+        client_secret: request.client_secret,
+        scopes,
+        role_claim: request.role_claim.filter(|claim| !claim.is_empty()),
+        role_mapping,
+        default_role: parse_role(request.default_role.as_deref().unwrap_or("user"))?,
+        enabled: true,
+        created_at: now,
+        updated_at: now,
+    };
+    OidcRepository::new(state.db.as_ref().clone()).create_provider(&provider).await?;
+    Ok(Json(provider))
+}
+
+/// 删除 OIDC 提供商（同时解除通过它关联的身份）
+pub async fn delete_oidc_provider(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(provider_id): Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    require_admin(&user)?;
+    let tenant_id = require_tenant(&user)?;
+    if !OidcRepository::new(state.db.as_ref().clone())
+        .delete_provider(&tenant_id, &provider_id)
+        .await?
+    {
+        return Err(ApiError::NotFound(format!("OIDC provider {} not found", provider_id)));
+    }
+
+    Ok(Json(serde_json::json!({
+        "provider_id": provider_id,
+        "message": "OIDC provider deleted"
+    })))
+}
+
+/// 查看租户的目录同步配置（不包含绑定密码）
+pub async fn get_directory_config(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+) -> Result<Json<DirectoryConfigRecord>, ApiError> {
+    require_admin(&user)?;
+    let tenant_id = require_tenant(&user)?;
+    let config = DirectoryRepository::new(state.db.as_ref().clone())
+        .get_config(&tenant_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Directory sync is not configured for this tenant".to_string()))?;
+    Ok(Json(config))
+}
+
+/// 保存租户的目录同步配置
+pub async fn save_directory_config(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Json(request): Json<SaveDirectoryConfigRequest>,
+) -> Result<Json<DirectoryConfigRecord>, ApiError> {
+    require_admin(&user)?;
+    let tenant_id = require_tenant(&user)?;
+    let url = url::Url::parse(request.url.trim())
+        .map_err(|e| ApiError::ValidationError(format!("Invalid directory URL: {}", e)))?;
+    if !matches!(url.scheme(), "ldap" | "ldaps") {
+        return Err(ApiError::ValidationError("Directory URL must use ldap:// or ldaps://".to_string()));
+    }
+    if request.base_dn.trim().is_empty() {
+        return Err(ApiError::ValidationError("Base DN is required".to_string()));
+    }
+
+    let repository = DirectoryRepository::new(state.db.as_ref().clone());
+    let existing = repository.get_config(&tenant_id).await?;
+    let bind_password = match (request.bind_password, &existing) {
+        (Some(password), _) => password,
+        (None, Some(existing)) => existing.bind_password.clone(),
+        (None, None) => return Err(ApiError::ValidationError("Bind password is required".to_string())),
+    };
+    let conflict_policy = match request.conflict_policy.as_deref() {
+        None => DirectoryConflictPolicy::Skip,
+        Some(policy) => DirectoryConflictPolicy::parse(policy)
+            .ok_or_else(|| ApiError::BadRequest(format!("Unknown conflict policy '{}'", policy)))?,
+    };
+    let group_role_mapping = request.group_role_mapping
+        .into_iter()
+        .map(|(group, role)| Ok((group, parse_role(&role)?)))
+        .collect::<Result<_, ApiError>>()?;
+
+    let now = chrono::Utc::now();
+    let config = DirectoryConfigRecord {
+        tenant_id,
+        url: request.url.trim().to_string(),
+        bind_dn: request.bind_dn,
+        bind_password,
+        base_dn: request.base_dn.trim().to_string(),
+        user_filter: request.user_filter.unwrap_or_else(|| "(objectClass=person)".to_string()),
+        username_attribute: request.username_attribute.unwrap_or_else(|| "uid".to_string()),
+        email_attribute: request.email_attribute.unwrap_or_else(|| "mail".to_string()),
+        group_attribute: request.group_attribute.unwrap_or_else(|| "memberOf".to_string()),
+        group_role_mapping,
+        default_role: parse_role(request.default_role.as_deref().unwrap_or("user"))?,
+        conflict_policy,
+        remove_missing: request.remove_missing,
+        enabled: request.enabled.unwrap_or(true),
+        created_at: existing.map(|existing| existing.created_at).unwrap_or(now),
+        updated_at: now,
+    };
+    repository.save_config(&config).await?;
+    Ok(Json(config))
+}
+
+/// 删除租户的目录同步配置（已同步的用户保留）
+pub async fn delete_directory_config(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    require_admin(&user)?;
+    let tenant_id = require_tenant(&user)?;
+    if !DirectoryRepository::new(state.db.as_ref().clone()).delete_config(&tenant_id).await? {
+        return Err(ApiError::NotFound("Directory sync is not configured for this tenant".to_string()));
+    }
+    Ok(Json(serde_json::json!({
+        "message": "Directory sync configuration deleted"
+    })))
+}
+
+/// 手动触发目录同步，`dry_run` 为 true 时只返回变更计划
+pub async fn sync_directory(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Json(request): Json<DirectorySyncRequest>,
+) -> Result<Json<DirectorySyncReport>, ApiError> {
+    require_admin(&user)?;
+    let tenant_id = require_tenant(&user)?;
+    let report = DirectorySyncService::new(state.db.as_ref().clone())
+        .sync_tenant(&tenant_id, request.dry_run)
+        .await?;
+    Ok(Json(report))
+}
+
+/// 列出目录同步历史
+pub async fn list_directory_sync_runs(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Query(params): Query<ListDirectorySyncRunsParams>,
+) -> Result<Json<ListDirectorySyncRunsResponse>, ApiError> {
+    require_admin(&user)?;
+    let tenant_id = require_tenant(&user)?;
+    let limit = params.limit.unwrap_or(20).min(200);
+    let runs = DirectoryRepository::new(state.db.as_ref().clone()).list_runs(&tenant_id, limit).await?;
+    Ok(Json(ListDirectorySyncRunsResponse { runs }))
+}
+
+/// 列出租户内某个用户的活跃会话
+pub async fn list_user_sessions(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(user_id): Path<String>,
+) -> Result<Json<ListSessionsResponse>, ApiError> {
+    let target = tenant_user(&state, &user, &user_id).await?;
+    let sessions = SessionRepository::new(state.db.as_ref().clone())
+        .list_active_sessions(&target)
+        .await?;
+    Ok(Json(ListSessionsResponse::new(sessions, &user.session_id)))
+}
+
+/// 注销租户内某个用户的全部会话
+pub async fn revoke_user_sessions(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(user_id): Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let target = tenant_user(&state, &user, &user_id).await?;
+    let revoked = SessionRepository::new(state.db.as_ref().clone())
+        .revoke_user_sessions(&target, None)
+        .await?;
+    Ok(Json(serde_json::json!({
+        "user_id": user_id,
+        "revoked": revoked
+    })))
+}
+
+/// 注销租户内某个用户的指定会话
+pub async fn revoke_user_session(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path((user_id, session_id)): Path<(String, String)>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let target = tenant_user(&state, &user, &user_id).await?;
+    if !SessionRepository::new(state.db.as_ref().clone())
+        .revoke_session(&target, &session_id)
+        .await?
+    {
+        return Err(ApiError::NotFound(format!("Active session {} not found", session_id)));
+    }
+    Ok(Json(serde_json::json!({
+        "session_id": session_id,
+        "message": "Session revoked"
+    })))
+}
+
+/// 用户数据合规操作
+///
+/// `POST /api/v1/admin/users/{id}:export` 返回签名的用户数据归档，`{id}:erase` 擦除用户的
+/// 个人数据。两种操作都会写入审计日志。
+pub async fn user_data_action(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    client: Option<Extension<ClientAddr>>,
+    headers: HeaderMap,
+    Path(target): Path<String>,
+) -> Result<Response, ApiError> {
+    let Some((user_id, action)) = target.rsplit_once(':').filter(|(_, action)| matches!(*action, "export" | "erase"))
+    else {
+        return Err(ApiError::NotFound(format!("Unknown user operation '{}'", target)));
+    };
+    let target_id = tenant_user(&state, &user, user_id).await?;
+    let tenant_id = require_tenant(&user)?;
+    let service = UserDataService::new(state.db.clone());
+
+    let (response, details) = if action == "export" {
+        let archive = service
+            .export(&tenant_id, &target_id, &user.user_id, &archive_signing_key(state.config.auth_config.jwt_secret.as_bytes()))
+            .await?;
+        let details = HashMap::from([("bytes".to_string(), serde_json::json!(archive.len()))]);
+        let disposition = format!("attachment; filename=\"user-{}.tar.zst\"", target_id.as_str());
+        let response = ([(header::CONTENT_TYPE, "application/zstd".to_string()), (header::CONTENT_DISPOSITION, disposition)], archive)
+            .into_response();
+        (response, details)
+    } else {
+        if target_id == user.user_id {
+            return Err(ApiError::BadRequest("Administrators cannot erase their own account".to_string()));
+        }
+        let report = service.erase(&tenant_id, &target_id).await?;
+        let details = report.sections
+            .iter()
+            .map(|section| (section.name.clone(), serde_json::json!(section.rows)))
+            .collect();
+        (Json(report).into_response(), details)
+    };
+
+    // 合规操作必须留痕，审计写入失败时整个请求失败
+    let event = AuditEvent {
+        event_id: uuid::Uuid::new_v4().to_string(),
+        event_type: format!("user_data_{}", if action == "export" { "exported" } else { "erased" }),
+        user_id: Some(user.user_id.clone()),
+        tenant_id: Some(tenant_id),
+        resource_type: "user".to_string(),
+        resource_id: target_id.as_str().to_string(),
+        action: action.to_string(),
+        details,
+        ip_address: client.map(|Extension(client)| client.to_string()),
+        user_agent: user_agent(&headers),
+        timestamp: chrono::Utc::now(),
+        success: true,
+        error_message: None,
+    };
+    SecurityEventRepository::new(state.db.as_ref().clone()).record_event(&event).await?;
+    info!("User {} ran {} on data of user {}", user.user_id.as_str(), action, target_id.as_str());
+    Ok(response)
+}
+
+/// 校验管理员身份，并确认目标用户属于管理员所在租户
+async fn tenant_user(state: &AppState, admin: &UserContext, user_id: &str) -> Result<UserId, ApiError> {
+    require_admin(admin)?;
+    let tenant_id = require_tenant(admin)?;
+    let user_id = UserId::from_string(user_id.to_string());
+    UserRepository::new(state.db.as_ref().clone())
+        .get_user(&user_id)
+        .await?
+        .filter(|user| user.tenant_id == tenant_id)
+        .map(|user| user.id)
+        .ok_or_else(|| ApiError::NotFound(format!("User {} not found", user_id.as_str())))
+}
@@ -0,0 +1,95 @@
+use axum::{
+    extract::{Path, Query, State},
+    Extension, Json,
+};
+use stepflow_executor::{ApprovalDecision, WorkflowApproval};
+use crate::errors::ApiError;
+use crate::models::requests::{ApprovalDecisionRequest, ListApprovalsParams};
+use crate::models::responses::{ApprovalDecisionResponse, ListApprovalsResponse};
+use crate::server::AppState;
+use crate::types::UserContext;
+use super::require_tenant;
+
+/// 列出当前租户的工作流审批任务
+///
+/// 可按状态过滤（pending/approved/rejected/expired）。逾期的待审批任务由后台清理任务标记为过期。
+pub async fn list_approvals(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Query(params): Query<ListApprovalsParams>,
+) -> Result<Json<ListApprovalsResponse>, ApiError> {
+    let tenant_id = require_tenant(&user)?;
+    let approvals = state.workflow_engine
+        .list_approvals(tenant_id.as_str(), params.status)
+        .await?;
+
+    Ok(Json(ListApprovalsResponse {
+        total_count: approvals.len(),
+        approvals,
+    }))
+}
+
+/// 获取审批任务
+pub async fn get_approval(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(approval_id): Path<String>,
+) -> Result<Json<WorkflowApproval>, ApiError> {
+    Ok(Json(find_approval(&state, &user, &approval_id).await?))
+}
+
+/// 批准审批任务，工作流在后台恢复执行
+pub async fn approve_approval(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(approval_id): Path<String>,
+    request: Option<Json<ApprovalDecisionRequest>>,
+) -> Result<Json<ApprovalDecisionResponse>, ApiError> {
+    decide(state, user, approval_id, true, request.map(|Json(r)| r).unwrap_or_default()).await
+}
+
+/// 拒绝审批任务；工作流进入拒绝分支，没有拒绝分支时运行失败
+pub async fn reject_approval(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(approval_id): Path<String>,
+    request: Option<Json<ApprovalDecisionRequest>>,
+) -> Result<Json<ApprovalDecisionResponse>, ApiError> {
+    decide(state, user, approval_id, false, request.map(|Json(r)| r).unwrap_or_default()).await
+}
+
+async fn decide(
+    state: AppState,
+    user: UserContext,
+    approval_id: String,
+    approved: bool,
+    request: ApprovalDecisionRequest,
+) -> Result<Json<ApprovalDecisionResponse>, ApiError> {
+    find_approval(&state, &user, &approval_id).await?;
+
+    // 运行在后台恢复，这里只返回其当前状态
+    let outcome = state.workflow_engine
+        .decide(&approval_id, ApprovalDecision {
+            approved,
+            user_id: user.user_id.to_string(),
+            roles: user.roles.clone(),
+            comment: request.comment,
+        })
+        .await?;
+    let run = state.workflow_engine
+        .get_run(&outcome.approval.run_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Workflow run {} not found", outcome.approval.run_id)))?;
+
+    Ok(Json(ApprovalDecisionResponse { approval: outcome.approval, run }))
+}
+
+/// 查找当前租户的审批任务
+async fn find_approval(state: &AppState, user: &UserContext, approval_id: &str) -> Result<WorkflowApproval, ApiError> {
+    let tenant_id = require_tenant(user)?;
+    state.workflow_engine
+        .get_approval(approval_id)
+        .await?
+        .filter(|approval| approval.tenant_id == tenant_id.as_str())
+        .ok_or_else(|| ApiError::NotFound(format!("Approval {} not found", approval_id)))
+}
@@ -1,2 +1,384 @@
+use std::collections::HashMap;
+use axum::{
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    Extension, Json,
+};
+use chrono::Utc;
+use stepflow_core::{AuditEvent, UserId, UserInfo};
+use stepflow_database::{
+    utils::generate_token, InvitationRepository, OidcLoginState, OidcProviderRecord, OidcRepository,
+    SecurityEventRepository, SessionRepository, UserRepository,
+};
+use tracing::{info, warn};
+use crate::errors::ApiError;
+use crate::middleware::ClientAddr;
+use crate::models::requests::{AcceptInvitationRequest, LoginRequest, OidcCallbackParams, RefreshTokenRequest, VerifyEmailRequest};
+use crate::models::responses::{
+    LoginResponse, OidcAuthorizeResponse, RefreshTokenResponse, RegisterUserResponse, UserResponse,
+};
+use crate::oidc::{map_role, OidcClaims};
+use crate::server::AppState;
+use crate::two_factor::verify_second_factor;
+use crate::types::UserContext;
+use super::{check_password_policy, user_agent};
+
 // 认证处理器占位符
-pub struct AuthHandler; 
\ No newline at end of file
+pub struct AuthHandler;
+
+/// 用户名密码登录
+///
+/// 连续失败达到 `max_failed_logins` 次后账户被临时锁定，并记录安全事件。
+/// 已启用双因素认证的用户还需在 `otp_code` 中提供 TOTP 验证码或恢复码，
+/// 验证码错误同样计入失败次数。
+pub async fn login(
+    State(state): State<AppState>,
+    client: Option<Extension<ClientAddr>>,
+    headers: HeaderMap,
+    Json(request): Json<LoginRequest>,
+) -> Result<Json<LoginResponse>, ApiError> {
+    let invalid_credentials = || ApiError::Unauthorized("Invalid username or password".to_string());
+    let client = client.map(|Extension(client)| client);
+    let repository = UserRepository::new(state.db.as_ref().clone());
+
+    let user = repository.get_user_by_username(&request.username).await?
+        .ok_or_else(invalid_credentials)?;
+
+    let failures = repository.get_login_failures(&user.id).await?;
+    if let Some(locked_until) = failures.filter(|f| f.is_locked()).and_then(|f| f.locked_until) {
+        return Err(ApiError::Forbidden(format!(
+            "Account is temporarily locked until {}",
+            locked_until.to_rfc3339()
+        )));
+    }
+
+    if !repository.verify_user_password(&user.id, &request.password).await? {
+        register_failed_login(&state, &repository, &user, client, &headers).await?;
+        return Err(invalid_credentials());
+    }
+
+    if let Some(enrollment) = repository.get_totp(&user.id).await?.filter(|totp| totp.enabled) {
+        let code = request.otp_code.as_deref()
+            .filter(|code| !code.trim().is_empty())
+            .ok_or_else(|| ApiError::Unauthorized("Two-factor authentication code required".to_string()))?;
+        if !verify_second_factor(state.db.as_ref(), &user.id, &user.username, &enrollment, code).await? {
+            register_failed_login(&state, &repository, &user, client, &headers).await?;
+            return Err(ApiError::Unauthorized("Invalid two-factor authentication code".to_string()));
+        }
+    }
+
+    repository.clear_login_failures(&user.id).await?;
+    Ok(Json(issue_login_tokens(&state, user, client, &headers).await?))
+}
+
+/// 为登录成功的用户创建会话并签发访问令牌和刷新令牌
+async fn issue_login_tokens(
+    state: &AppState,
+    user: UserInfo,
+    client: Option<ClientAddr>,
+    headers: &HeaderMap,
+) -> Result<LoginResponse, ApiError> {
+    let auth_config = &state.config.auth_config;
+    let session_ttl = chrono::Duration::from_std(auth_config.jwt_refresh_expiration)
+        .map_err(|e| ApiError::InternalServerError(e.to_string()))?;
+    let session = SessionRepository::new(state.db.as_ref().clone())
+        .create_session(&user, user_agent(headers).as_deref(), client.map(|client| client.to_string()).as_deref(), session_ttl)
+        .await?;
+
+    let now = Utc::now();
+    let mut context = UserContext {
+        user_id: user.id.clone(),
+        tenant_id: Some(user.tenant_id.as_str().to_string()),
+        roles: vec![user.role.to_string()],
+        permissions: Vec::new(),
+        session_id: session.id,
+        expires_at: now + chrono::Duration::from_std(auth_config.jwt_expiration)
+            .map_err(|e| ApiError::InternalServerError(e.to_string()))?,
+    };
+    let access_token = state.auth_service.generate_jwt_token(&context).await?;
+    let expires_at = context.expires_at;
+    context.expires_at = session.expires_at;
+    let refresh_token = state.auth_service.generate_refresh_token(&context).await?;
+
+    Ok(LoginResponse {
+        user: UserResponse::from(user),
+        access_token,
+        refresh_token,
+        expires_at,
+        token_type: "Bearer".to_string(),
+    })
+}
+
+/// 使用刷新令牌换取新的访问令牌
+///
+/// 刷新令牌所属的会话必须仍然有效；刷新令牌本身原样返回，随会话一起过期。
+pub async fn refresh_token(
+    State(state): State<AppState>,
+    Json(request): Json<RefreshTokenRequest>,
+) -> Result<Json<RefreshTokenResponse>, ApiError> {
+    let access_token = state.auth_service.refresh_jwt_token(&request.refresh_token).await?;
+    let expires_at = state.auth_service.validate_jwt_token(&access_token).await?.expires_at;
+
+    Ok(Json(RefreshTokenResponse {
+        access_token,
+        refresh_token: request.refresh_token,
+        expires_at,
+        token_type: "Bearer".to_string(),
+    }))
+}
+
+/// 接受邀请并设置密码，创建用户
+pub async fn accept_invitation(
+    State(state): State<AppState>,
+    Json(request): Json<AcceptInvitationRequest>,
+) -> Result<Json<RegisterUserResponse>, ApiError> {
+    if request.username.trim().is_empty() {
+        return Err(ApiError::BadRequest("Username is required".to_string()));
+    }
+    check_password_policy(&state.config.auth_config.password_policy, &request.password)?;
+
+    let user = InvitationRepository::new(state.db.as_ref().clone())
+        .accept_invitation(&request.token, request.username.trim(), &request.password)
+        .await?;
+
+    Ok(Json(RegisterUserResponse {
+        user: UserResponse::from(user),
+        message: "Invitation accepted".to_string(),
+    }))
+}
+
+/// 使用邮件中的令牌完成邮箱验证
+pub async fn verify_email(
+    State(state): State<AppState>,
+    Json(request): Json<VerifyEmailRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let user_id = UserRepository::new(state.db.as_ref().clone())
+        .verify_email(&request.token)
+        .await?
+        .ok_or_else(|| ApiError::BadRequest("Invalid or expired verification token".to_string()))?;
+
+    Ok(Json(serde_json::json!({
+        "user_id": user_id,
+        "email_verified": true,
+        "message": "Email address verified"
+    })))
+}
+
+/// 发起 OIDC 登录，返回提供商的授权地址
+pub async fn oidc_authorize(
+    State(state): State<AppState>,
+    Path(provider_id): Path<String>,
+) -> Result<Json<OidcAuthorizeResponse>, ApiError> {
+    let provider = enabled_oidc_provider(&state, &provider_id).await?;
+    Ok(Json(start_oidc_login(&state, &provider, None).await?))
+}
+
+/// 创建一次待完成的 OIDC 登录并构造授权地址
+///
+/// `link_user_id` 不为空时，回调会把提供商身份关联到该用户而不是按身份查找用户。
+pub(crate) async fn start_oidc_login(
+    state: &AppState,
+    provider: &OidcProviderRecord,
+    link_user_id: Option<UserId>,
+) -> Result<OidcAuthorizeResponse, ApiError> {
+    let auth_config = &state.config.auth_config;
+    let redirect_uri = auth_config.oidc_redirect_uri.clone()
+        .ok_or_else(|| ApiError::ServiceUnavailable("OIDC redirect URI is not configured".to_string()))?;
+    let metadata = state.oidc_client.discover(&provider.issuer).await?;
+
+    let login = OidcLoginState {
+        state: generate_token(),
+        provider_id: provider.id.clone(),
+        nonce: generate_token(),
+        code_verifier: generate_token(),
+        redirect_uri,
+        link_user_id,
+        expires_at: Utc::now() + chrono::Duration::from_std(auth_config.oidc_login_expiration)
+            .map_err(|e| ApiError::InternalServerError(e.to_string()))?,
+    };
+    let authorization_url = state.oidc_client.authorization_url(
+        &metadata,
+        provider,
+        &login.redirect_uri,
+        &login.state,
+        &login.nonce,
+        &login.code_verifier,
+    )?;
+    OidcRepository::new(state.db.as_ref().clone()).create_login_state(&login).await?;
+
+    Ok(OidcAuthorizeResponse {
+        authorization_url,
+        state: login.state,
+    })
+}
+
+/// OIDC 回调：用授权码换取 ID Token，找到（或关联、创建）对应用户并签发令牌
+pub async fn oidc_callback(
+    State(state): State<AppState>,
+    client: Option<Extension<ClientAddr>>,
+    headers: HeaderMap,
+    Query(params): Query<OidcCallbackParams>,
+) -> Result<Json<LoginResponse>, ApiError> {
+    let oidc = OidcRepository::new(state.db.as_ref().clone());
+    let login = oidc.take_login_state(&params.state).await?
+        .ok_or_else(|| ApiError::BadRequest("Invalid or expired OIDC login state".to_string()))?;
+    if let Some(error) = params.error {
+        return Err(ApiError::Unauthorized(format!(
+            "OIDC login failed: {}",
+            params.error_description.unwrap_or(error)
+        )));
+    }
+    let code = params.code
+        .ok_or_else(|| ApiError::BadRequest("Missing authorization code".to_string()))?;
+
+    let provider = enabled_oidc_provider(&state, &login.provider_id).await?;
+    let metadata = state.oidc_client.discover(&provider.issuer).await?;
+    let id_token = state.oidc_client
+        .exchange_code(&metadata, &provider, &code, &login.redirect_uri, &login.code_verifier)
+        .await?;
+    let claims = state.oidc_client
+        .verify_id_token(&metadata, &provider, &id_token, &login.nonce)
+        .await?;
+
+    let user = resolve_oidc_user(&state, &provider, &claims, login.link_user_id).await?;
+    oidc.touch_identity(&provider.id, &claims.sub).await?;
+    Ok(Json(issue_login_tokens(&state, user, client.map(|Extension(client)| client), &headers).await?))
+}
+
+async fn enabled_oidc_provider(state: &AppState, provider_id: &str) -> Result<OidcProviderRecord, ApiError> {
+    OidcRepository::new(state.db.as_ref().clone())
+        .get_provider(provider_id)
+        .await?
+        .filter(|provider| provider.enabled)
+        .ok_or_else(|| ApiError::NotFound(format!("OIDC provider {} not found", provider_id)))
+}
+
+/// 确定 OIDC 身份对应的本地用户
+///
+/// 依次尝试：显式关联请求、已关联的身份、同租户中邮箱一致的用户（要求提供商已验证邮箱），
+/// 都不满足时按角色映射自动创建用户。
+async fn resolve_oidc_user(
+    state: &AppState,
+    provider: &OidcProviderRecord,
+    claims: &OidcClaims,
+    link_user_id: Option<UserId>,
+) -> Result<UserInfo, ApiError> {
+    let users = UserRepository::new(state.db.as_ref().clone());
+    let oidc = OidcRepository::new(state.db.as_ref().clone());
+    let email = claims.email.as_deref().map(str::trim).filter(|email| !email.is_empty());
+    if let Some(user_id) = link_user_id {
+        let user = find_user(&users, &user_id).await?;
+        oidc.link_identity(&provider.id, &claims.sub, &user.id, email).await?;
+        info!("Linked OIDC identity {} at {} to user {}", claims.sub, provider.name, user.username);
+        return Ok(user);
+    }
+
+    if let Some(identity) = oidc.find_identity(&provider.id, &claims.sub).await? {
+        return find_user(&users, &identity.user_id).await;
+    }
+
+    let email = email
+        .ok_or_else(|| ApiError::BadRequest("OIDC provider did not supply an email address".to_string()))?;
+    if let Some(existing) = users.get_user_by_email(email).await? {
+        if existing.tenant_id != provider.tenant_id || claims.email_verified != Some(true) {
+            return Err(ApiError::Conflict(
+                "An account with this email already exists; sign in and link the identity instead".to_string(),
+            ));
+        }
+        oidc.link_identity(&provider.id, &claims.sub, &existing.id, Some(email)).await?;
+        info!("Linked OIDC identity {} at {} to existing user {}", claims.sub, provider.name, existing.username);
+        return Ok(existing);
+    }
+
+    let base = claims.preferred_username.as_deref()
+        .or_else(|| email.split('@').next())
+        .filter(|name| !name.is_empty())
+        .unwrap_or(&claims.sub)
+        .to_string();
+    let username = if users.get_user_by_username(&base).await?.is_some() {
+        format!("{}-{}", base, &uuid::Uuid::new_v4().simple().to_string()[..6])
+    } else {
+        base
+    };
+
+    let now = Utc::now();
+    let user = UserInfo {
+        id: UserId::new(),
+        username,
+        email: email.to_string(),
+        role: map_role(provider, claims),
+        tenant_id: provider.tenant_id.clone(),
+        settings: HashMap::new(),
+        email_verified: claims.email_verified.unwrap_or(false),
+        created_at: now,
+        updated_at: now,
+    };
+    // 仅通过 SSO 登录，本地密码为随机值
+    users.register_user(&user, &generate_token()).await?;
+    oidc.link_identity(&provider.id, &claims.sub, &user.id, Some(email)).await?;
+    info!("Provisioned user {} from OIDC provider {}", user.username, provider.name);
+    Ok(user)
+}
+
+async fn find_user(users: &UserRepository, user_id: &UserId) -> Result<UserInfo, ApiError> {
+    users.get_user(user_id).await?
+        .ok_or_else(|| ApiError::NotFound(format!("User {} not found", user_id.as_str())))
+}
+
+/// 记录一次登录失败，达到阈值时锁定账户
+async fn register_failed_login(
+    state: &AppState,
+    repository: &UserRepository,
+    user: &UserInfo,
+    client: Option<ClientAddr>,
+    headers: &HeaderMap,
+) -> Result<(), ApiError> {
+    let auth_config = &state.config.auth_config;
+    if auth_config.max_failed_logins == 0 {
+        return Ok(());
+    }
+
+    let lockout = chrono::Duration::from_std(auth_config.lockout_duration)
+        .map_err(|e| ApiError::InternalServerError(e.to_string()))?;
+    let failures = repository
+        .record_failed_login(&user.id, auth_config.max_failed_logins, lockout)
+        .await?;
+    if failures.is_locked() {
+        warn!("Account {} locked after {} failed logins", user.username, auth_config.max_failed_logins);
+        record_security_event(state, user, client, headers, "account_locked", HashMap::from([
+            ("failed_attempts".to_string(), serde_json::json!(auth_config.max_failed_logins)),
+            ("locked_until".to_string(), serde_json::json!(failures.locked_until)),
+        ])).await;
+    }
+    Ok(())
+}
+
+/// 记录安全事件；写入失败只记录日志，不影响请求本身
+async fn record_security_event(
+    state: &AppState,
+    user: &UserInfo,
+    client: Option<ClientAddr>,
+    headers: &HeaderMap,
+    event_type: &str,
+    details: HashMap<String, serde_json::Value>,
+) {
+    let event = AuditEvent {
+        event_id: uuid::Uuid::new_v4().to_string(),
+        event_type: event_type.to_string(),
+        user_id: Some(user.id.clone()),
+        tenant_id: Some(user.tenant_id.clone()),
+        resource_type: "user".to_string(),
+        resource_id: user.id.as_str().to_string(),
+        action: "login".to_string(),
+        details,
+        ip_address: client.map(|client| client.to_string()),
+        user_agent: user_agent(headers),
+        timestamp: Utc::now(),
+        success: false,
+        error_message: None,
+    };
+
+    if let Err(e) = SecurityEventRepository::new(state.db.as_ref().clone()).record_event(&event).await {
+        warn!("Failed to record security event {}: {}", event_type, e);
+    }
+}
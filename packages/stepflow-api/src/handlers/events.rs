@@ -0,0 +1,100 @@
+use axum::{
+    extract::{Path, State},
+    Extension, Json,
+};
+use stepflow_database::EventSchemaRecord;
+use stepflow_registry::{CompatibilityReport, EventSchemaService};
+use crate::errors::ApiError;
+use crate::models::requests::{CheckEventSchemaRequest, PublishEventSchemaRequest};
+use crate::models::responses::ListEventSchemasResponse;
+use crate::server::AppState;
+use crate::types::UserContext;
+use super::require_admin;
+
+/// 列出所有事件 Schema 的最新版本
+pub async fn list_event_schemas(
+    State(state): State<AppState>,
+) -> Result<Json<ListEventSchemasResponse>, ApiError> {
+    let schemas = EventSchemaService::new(state.db.clone()).list_schemas().await?;
+    Ok(Json(ListEventSchemasResponse {
+        total_count: schemas.len(),
+        schemas,
+    }))
+}
+
+/// 列出事件 Schema 的所有版本，从旧到新
+pub async fn list_event_schema_versions(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<ListEventSchemasResponse>, ApiError> {
+    let schemas = EventSchemaService::new(state.db.clone()).list_versions(&name).await?;
+    if schemas.is_empty() {
+        return Err(ApiError::NotFound(format!("Event schema {} not found", name)));
+    }
+    Ok(Json(ListEventSchemasResponse {
+        total_count: schemas.len(),
+        schemas,
+    }))
+}
+
+/// 获取事件 Schema 的最新版本
+pub async fn get_latest_event_schema(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<EventSchemaRecord>, ApiError> {
+    EventSchemaService::new(state.db.clone())
+        .latest_schema(&name)
+        .await?
+        .map(Json)
+        .ok_or_else(|| ApiError::NotFound(format!("Event schema {} not found", name)))
+}
+
+/// 获取事件 Schema 的指定版本
+///
+/// 消费者可从事件的 `dataschema`（`urn:stepflow:event:<name>:v<version>`）得到名称与版本。
+pub async fn get_event_schema(
+    State(state): State<AppState>,
+    Path((name, version)): Path<(String, u32)>,
+) -> Result<Json<EventSchemaRecord>, ApiError> {
+    EventSchemaService::new(state.db.clone())
+        .get_schema(&name, version)
+        .await?
+        .map(Json)
+        .ok_or_else(|| ApiError::NotFound(format!("Version {} of event schema {} not found", version, name)))
+}
+
+/// 发布事件 Schema 的新版本（仅管理员）
+///
+/// 新版本需与最新版本满足兼容性要求，否则返回 409 及不兼容的原因；与最新版本相同的 Schema 直接返回最新版本。
+pub async fn publish_event_schema(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(name): Path<String>,
+    Json(request): Json<PublishEventSchemaRequest>,
+) -> Result<Json<EventSchemaRecord>, ApiError> {
+    require_admin(&user)?;
+
+    let published = EventSchemaService::new(state.db.clone())
+        .publish_schema(
+            &name,
+            request.schema,
+            request.compatibility,
+            request.description.as_deref(),
+            Some(user.user_id.as_str()),
+        )
+        .await?;
+
+    Ok(Json(published))
+}
+
+/// 检查 Schema 与最新版本的兼容性，不发布
+pub async fn check_event_schema(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(request): Json<CheckEventSchemaRequest>,
+) -> Result<Json<CompatibilityReport>, ApiError> {
+    let report = EventSchemaService::new(state.db.clone())
+        .check_schema(&name, &request.schema, request.compatibility)
+        .await?;
+    Ok(Json(report))
+}
@@ -1,2 +1,484 @@
+use std::convert::Infallible;
+use std::time::{Duration, Instant};
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    Extension, Json,
+};
+use futures::Stream;
+use stepflow_core::{ExecutionFilter, ExecutionId, ExecutionStatus, TenantId, ToolId};
+use stepflow_database::{SavedViewRecord, SavedViewRepository, ToolPresetRepository};
+use stepflow_executor::{
+    parse_label, ExecutionAnnotations, ExecutionEstimate, ExecutionNote, ExecutionOptions, ExecutionRequest, ExecutionState,
+    ExecutionTimeline, QueuePosition, RunReport,
+};
+use crate::errors::ApiError;
+use crate::middleware::ensure_accepting_executions;
+use crate::models::requests::{
+    AddExecutionNoteRequest, EstimateExecutionRequest, ExecutionViewFilter, LabelExecutionRequest, ReportParams, RunSyncRequest,
+    SaveExecutionViewRequest,
+};
+use crate::models::responses::{
+    ExecutionTimelineResponse, ListConcurrencyGroupsResponse, ListExecutionViewsResponse, ListExecutionsResponse,
+    QueuePositionResponse, RunSyncProgress, RunSyncResponse,
+};
+use crate::server::AppState;
+use crate::types::{PaginationInfo, PaginationParams, RequestTrace, UserContext};
+use super::{report_response, require_tenant};
+
 // 执行处理器占位符
-pub struct ExecutionsHandler; 
\ No newline at end of file
+pub struct ExecutionsHandler;
+
+/// 同步执行的默认等待时间
+const RUN_SYNC_DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 同步执行允许的最长等待时间
+const RUN_SYNC_MAX_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// 同步执行的状态轮询间隔
+const RUN_SYNC_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 状态不变时 `progress` 事件的推送间隔
+const RUN_SYNC_PROGRESS_INTERVAL: Duration = Duration::from_secs(5);
+
+/// 获取当前租户的执行时间线，其他租户的执行视为不存在
+async fn tenant_execution(state: &AppState, tenant_id: &TenantId, execution_id: &ExecutionId) -> Result<ExecutionTimeline, ApiError> {
+    state.executor
+        .get_execution_timeline(execution_id)
+        .await?
+        .filter(|timeline| timeline.tenant_id.as_deref().is_none_or(|t| t == tenant_id.as_str()))
+        .ok_or_else(|| ApiError::NotFound(format!("Execution {} not found", execution_id)))
+}
+
+/// 列出当前租户的执行
+///
+/// 支持 `tool_id`、`status`、可重复的 `label=key:value` 以及 `view`（已保存视图的 ID）。
+/// 视图中的条件与查询参数合并，查询参数优先。
+pub async fn list_executions(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Query(params): Query<PaginationParams>,
+    Query(query): Query<Vec<(String, String)>>,
+) -> Result<Json<ListExecutionsResponse>, ApiError> {
+    let tenant_id = require_tenant(&user)?;
+
+    let mut filter = ExecutionViewFilter::default();
+    if let Some((_, view_id)) = query.iter().find(|(name, _)| name == "view") {
+        let view = SavedViewRepository::new(state.db.as_ref().clone())
+            .get_view(&tenant_id, &user.user_id, view_id)
+            .await?
+            .ok_or_else(|| ApiError::NotFound(format!("View {} not found", view_id)))?;
+        filter = serde_json::from_value(view.filter)?;
+    }
+    for (name, value) in &query {
+        match name.as_str() {
+            "tool_id" => filter.tool_id = Some(ToolId::parse(value.as_str())?),
+            "status" => {
+                let status: ExecutionStatus = serde_json::from_value(serde_json::Value::String(value.clone()))
+                    .map_err(|_| ApiError::BadRequest(format!("Unknown execution status: {}", value)))?;
+                filter.status = Some(status);
+            }
+            "label" => {
+                let (key, value) = parse_label(value)?;
+                filter.labels.insert(key, value);
+            }
+            _ => {}
+        }
+    }
+
+    let executions = state.executor
+        .list_executions(Some(ExecutionFilter {
+            tool_id: filter.tool_id,
+            tenant_id: Some(tenant_id),
+            status: filter.status,
+            labels: filter.labels,
+            ..ExecutionFilter::default()
+        }))
+        .await?;
+
+    let page = params.page.unwrap_or(1).max(1);
+    let page_size = params.page_size.unwrap_or(20).max(1);
+    let pagination = PaginationInfo::new(page, page_size, executions.len());
+    let executions = executions
+        .into_iter()
+        .skip((page - 1) * page_size)
+        .take(page_size)
+        .collect();
+
+    Ok(Json(ListExecutionsResponse { executions, pagination }))
+}
+
+/// 预估执行的耗时与资源成本
+///
+/// 基于该工具版本近期的执行记录（不足时使用工具基线）以及当前队列状况，
+/// 返回预计耗时、排队等待、成本和建议（同步执行、异步提交或延后）。不会创建执行。
+pub async fn estimate_execution(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    trace: Option<Extension<RequestTrace>>,
+    Json(request): Json<EstimateExecutionRequest>,
+) -> Result<Json<ExecutionEstimate>, ApiError> {
+    require_tenant(&user)?;
+    let trace = trace
+        .map(|Extension(trace)| trace)
+        .unwrap_or_else(|| RequestTrace::from_headers(None, None));
+
+    let mut options = ExecutionOptions::default();
+    options.resource_limits.memory_limit = request.memory_limit;
+    options.resource_limits.cpu_limit = request.cpu_limit;
+    let request = ExecutionRequest::builder()
+        .tool_id(request.tool_id)
+        .version(request.version)
+        .parameters(request.parameters)
+        .context(trace.execution_context(&user))
+        .options(options)
+        .build()?;
+
+    Ok(Json(state.executor.estimate_execution(&request).await?))
+}
+
+/// 同步执行工具
+///
+/// 提交执行后保持连接直到执行结束或达到 `timeout_ms`。结束时返回 200 和执行结果；
+/// 超时则返回 202 和执行 ID，调用方改为轮询。请求头 `Accept: text/event-stream`
+/// 时以 SSE 返回：等待期间推送 `progress` 事件，最后推送 `result` 或 `timeout` 事件。
+pub async fn run_execution_sync(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    trace: Option<Extension<RequestTrace>>,
+    headers: HeaderMap,
+    Json(request): Json<RunSyncRequest>,
+) -> Result<Response, ApiError> {
+    require_tenant(&user)?;
+    ensure_accepting_executions(&state).await?;
+    let trace = trace
+        .map(|Extension(trace)| trace)
+        .unwrap_or_else(|| RequestTrace::from_headers(None, None));
+    let timeout = request.timeout_ms
+        .map(Duration::from_millis)
+        .unwrap_or(RUN_SYNC_DEFAULT_TIMEOUT)
+        .min(RUN_SYNC_MAX_TIMEOUT);
+
+    let parameters = match &request.preset {
+        Some(preset) => {
+            let tenant_id = require_tenant(&user)?;
+            let mut parameters = ToolPresetRepository::new(state.db.as_ref().clone())
+                .resolve(&tenant_id, &request.tool_id, &user.user_id, preset)
+                .await?
+                .ok_or_else(|| ApiError::ValidationError(format!("Preset {} not found for tool {}", preset, request.tool_id)))?;
+            parameters.extend(request.parameters);
+            parameters
+        }
+        None => request.parameters,
+    };
+
+    let mut context = trace.execution_context(&user);
+    context.labels = request.labels;
+    context.note = request.note;
+    let execution_request = ExecutionRequest::builder()
+        .tool_id(request.tool_id)
+        .version(request.version)
+        .parameters(parameters)
+        .context(context)
+        .options(ExecutionOptions {
+            concurrency: request.concurrency,
+            ..ExecutionOptions::default()
+        })
+        .build()?;
+    let execution_id = state.executor.execute_tool_async(execution_request).await?;
+    let run = SyncRun::new(state, execution_id, timeout);
+
+    let wants_events = headers
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|accept| accept.contains("text/event-stream"));
+    if wants_events {
+        return Ok(Sse::new(run.into_events()).keep_alive(KeepAlive::default()).into_response());
+    }
+
+    let response = run.wait().await?;
+    let status = if response.completed { StatusCode::OK } else { StatusCode::ACCEPTED };
+    Ok((status, Json(response)).into_response())
+}
+
+/// 等待中的同步执行
+struct SyncRun {
+    state: AppState,
+    execution_id: ExecutionId,
+    started: Instant,
+    deadline: Instant,
+    last_state: Option<ExecutionState>,
+    queue_position: Option<QueuePosition>,
+}
+
+impl SyncRun {
+    fn new(state: AppState, execution_id: ExecutionId, timeout: Duration) -> Self {
+        let started = Instant::now();
+        Self { state, execution_id, started, deadline: started + timeout, last_state: None, queue_position: None }
+    }
+
+    fn elapsed_ms(&self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
+
+    /// 检查一次执行状态，执行结束或已超时时返回响应
+    async fn poll(&mut self) -> Result<Option<RunSyncResponse>, ApiError> {
+        let timeline = self.state.executor.get_execution_timeline(&self.execution_id).await?;
+        let last_event = timeline.and_then(|timeline| timeline.events.last().cloned());
+        self.last_state = last_event.as_ref().map(|event| event.state);
+        self.queue_position = self.state.executor.get_queue_position(&self.execution_id).await?;
+
+        if let Some(event) = last_event.filter(|event| event.state.is_terminal()) {
+            let result = match event.state {
+                ExecutionState::Completed => self.state.executor.get_execution_result(&self.execution_id).await.ok(),
+                _ => None,
+            };
+            return Ok(Some(self.response(true, result, event.detail)));
+        }
+        if Instant::now() >= self.deadline {
+            return Ok(Some(self.response(false, None, None)));
+        }
+        Ok(None)
+    }
+
+    fn response(&self, completed: bool, result: Option<stepflow_core::ExecutionResult>, error: Option<String>) -> RunSyncResponse {
+        RunSyncResponse {
+            execution_id: self.execution_id.clone(),
+            completed,
+            state: self.last_state,
+            result,
+            error,
+            elapsed_ms: self.elapsed_ms(),
+        }
+    }
+
+    /// 等待执行结束或超时
+    async fn wait(mut self) -> Result<RunSyncResponse, ApiError> {
+        loop {
+            if let Some(response) = self.poll().await? {
+                return Ok(response);
+            }
+            tokio::time::sleep(RUN_SYNC_POLL_INTERVAL).await;
+        }
+    }
+
+    /// 以 SSE 事件流等待：状态变化或每隔一段时间推送 `progress`，最后推送 `result` 或 `timeout`
+    fn into_events(self) -> impl Stream<Item = Result<Event, Infallible>> {
+        futures::stream::unfold(Some((self, None::<Instant>)), |step| async move {
+            let (mut run, mut progress_at) = step?;
+            loop {
+                if progress_at.is_some() {
+                    tokio::time::sleep(RUN_SYNC_POLL_INTERVAL).await;
+                }
+                let previous = (run.last_state, run.queue_position.clone());
+                match run.poll().await {
+                    Ok(Some(response)) => {
+                        let name = if response.completed { "result" } else { "timeout" };
+                        return Some((Ok(sse_event(name, &response)), None));
+                    }
+                    Ok(None) => {
+                        let due = progress_at.is_none_or(|at| at.elapsed() >= RUN_SYNC_PROGRESS_INTERVAL);
+                        if due || (run.last_state, run.queue_position.clone()) != previous {
+                            let progress = RunSyncProgress {
+                                execution_id: run.execution_id.clone(),
+                                state: run.last_state,
+                                queue_position: run.queue_position.clone(),
+                                elapsed_ms: run.elapsed_ms(),
+                            };
+                            progress_at = Some(Instant::now());
+                            return Some((Ok(sse_event("progress", &progress)), Some((run, progress_at))));
+                        }
+                    }
+                    Err(e) => return Some((Ok(Event::default().event("error").data(e.to_string())), None)),
+                }
+            }
+        })
+    }
+}
+
+/// 构建 JSON 数据的 SSE 事件
+fn sse_event(name: &str, data: &impl serde::Serialize) -> Event {
+    Event::default()
+        .event(name)
+        .json_data(data)
+        .unwrap_or_else(|e| Event::default().event("error").data(e.to_string()))
+}
+
+/// 获取执行的状态迁移时间线
+///
+/// 返回 queued → scheduled → running → completed 等每次状态变更及其时间戳，
+/// 用于排查调度延迟。仅能查看当前租户的执行。
+pub async fn get_execution_timeline(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(execution_id): Path<String>,
+) -> Result<Json<ExecutionTimelineResponse>, ApiError> {
+    let tenant_id = require_tenant(&user)?;
+    let execution_id = ExecutionId::parse(execution_id)?;
+
+    let timeline = tenant_execution(&state, &tenant_id, &execution_id).await?;
+
+    Ok(Json(timeline.into()))
+}
+
+/// 查询执行在并发组中的排队位置
+///
+/// 位置从 1 开始，1 表示组内有空位时下一个运行。仅能查看当前租户的执行。
+pub async fn get_execution_queue_position(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(execution_id): Path<String>,
+) -> Result<Json<QueuePositionResponse>, ApiError> {
+    let tenant_id = require_tenant(&user)?;
+    let execution_id = ExecutionId::parse(execution_id)?;
+    tenant_execution(&state, &tenant_id, &execution_id).await?;
+
+    let queue_position = state.executor.get_queue_position(&execution_id).await?;
+    Ok(Json(QueuePositionResponse { execution_id, queue_position }))
+}
+
+/// 列出当前租户有执行在运行或排队的并发组
+pub async fn list_concurrency_groups(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+) -> Result<Json<ListConcurrencyGroupsResponse>, ApiError> {
+    let tenant_id = require_tenant(&user)?;
+    let groups = state.executor.list_concurrency_groups(&tenant_id).await?;
+    Ok(Json(ListConcurrencyGroupsResponse { groups }))
+}
+
+/// 导出执行报告（`format=html|pdf`，默认 HTML）
+///
+/// 包含脱敏后的输入、各阶段耗时、日志摘录、资源使用与输出，用于审计和事故复盘。
+/// 执行未结束时报告只包含已记录的阶段。
+pub async fn get_execution_report(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(execution_id): Path<String>,
+    Query(params): Query<ReportParams>,
+) -> Result<Response, ApiError> {
+    let tenant_id = require_tenant(&user)?;
+    let execution_id = ExecutionId::parse(execution_id)?;
+    let timeline = tenant_execution(&state, &tenant_id, &execution_id).await?;
+    // 未结束的执行还没有结果
+    let result = state.executor.get_execution_result(&execution_id).await.ok();
+
+    Ok(report_response(&RunReport::for_execution(&timeline, result.as_ref()), params.format))
+}
+
+/// 获取执行的标签和备注
+pub async fn get_execution_annotations(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(execution_id): Path<String>,
+) -> Result<Json<ExecutionAnnotations>, ApiError> {
+    let tenant_id = require_tenant(&user)?;
+    let execution_id = ExecutionId::parse(execution_id)?;
+    tenant_execution(&state, &tenant_id, &execution_id).await?;
+
+    Ok(Json(state.executor.get_execution_annotations(&execution_id).await?))
+}
+
+/// 设置执行标签，返回设置后的标签和备注
+pub async fn label_execution(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(execution_id): Path<String>,
+    Json(request): Json<LabelExecutionRequest>,
+) -> Result<Json<ExecutionAnnotations>, ApiError> {
+    let tenant_id = require_tenant(&user)?;
+    let execution_id = ExecutionId::parse(execution_id)?;
+    tenant_execution(&state, &tenant_id, &execution_id).await?;
+
+    state.executor.label_execution(&execution_id, request.labels).await?;
+    Ok(Json(state.executor.get_execution_annotations(&execution_id).await?))
+}
+
+/// 移除执行标签
+pub async fn remove_execution_label(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path((execution_id, key)): Path<(String, String)>,
+) -> Result<Json<ExecutionAnnotations>, ApiError> {
+    let tenant_id = require_tenant(&user)?;
+    let execution_id = ExecutionId::parse(execution_id)?;
+    tenant_execution(&state, &tenant_id, &execution_id).await?;
+
+    if !state.executor.remove_execution_label(&execution_id, &key).await? {
+        return Err(ApiError::NotFound(format!("Execution {} has no label {}", execution_id, key)));
+    }
+    Ok(Json(state.executor.get_execution_annotations(&execution_id).await?))
+}
+
+/// 为执行添加备注，作者为当前用户
+pub async fn add_execution_note(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(execution_id): Path<String>,
+    Json(request): Json<AddExecutionNoteRequest>,
+) -> Result<Json<ExecutionNote>, ApiError> {
+    let tenant_id = require_tenant(&user)?;
+    let execution_id = ExecutionId::parse(execution_id)?;
+    tenant_execution(&state, &tenant_id, &execution_id).await?;
+
+    let note = state.executor
+        .add_execution_note(&execution_id, user.user_id.as_str(), &request.body)
+        .await?;
+    Ok(Json(note))
+}
+
+/// 列出当前用户保存的执行视图
+pub async fn list_execution_views(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+) -> Result<Json<ListExecutionViewsResponse>, ApiError> {
+    let tenant_id = require_tenant(&user)?;
+    let views = SavedViewRepository::new(state.db.as_ref().clone())
+        .list_views(&tenant_id, &user.user_id)
+        .await?;
+    Ok(Json(ListExecutionViewsResponse { views }))
+}
+
+/// 保存执行视图，同名视图会被替换
+pub async fn save_execution_view(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Json(request): Json<SaveExecutionViewRequest>,
+) -> Result<Json<SavedViewRecord>, ApiError> {
+    let tenant_id = require_tenant(&user)?;
+    let name = request.name.trim();
+    if name.is_empty() || name.len() > 100 {
+        return Err(ApiError::ValidationError("View name must be 1-100 characters".to_string()));
+    }
+    for (key, value) in &request.filter.labels {
+        stepflow_executor::annotations::validate_label(key, value)?;
+    }
+
+    let view = SavedViewRepository::new(state.db.as_ref().clone())
+        .save_view(&tenant_id, &user.user_id, name, &serde_json::to_value(&request.filter)?)
+        .await?;
+    Ok(Json(view))
+}
+
+/// 删除当前用户的执行视图
+pub async fn delete_execution_view(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(view_id): Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let tenant_id = require_tenant(&user)?;
+    let deleted = SavedViewRepository::new(state.db.as_ref().clone())
+        .delete_view(&tenant_id, &user.user_id, &view_id)
+        .await?;
+    if !deleted {
+        return Err(ApiError::NotFound(format!("View {} not found", view_id)));
+    }
+    Ok(Json(serde_json::json!({
+        "view_id": view_id,
+        "message": "View removed"
+    })))
+}
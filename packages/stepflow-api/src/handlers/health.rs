@@ -7,6 +7,8 @@ pub async fn health_check() -> Result<Json<HealthResponse>, ApiError> {
     Ok(Json(HealthResponse {
         status: "healthy".to_string(),
         message: "API is running".to_string(),
+        mode: None,
+        leadership: None,
         timestamp: chrono::Utc::now(),
     }))
 }
@@ -95,6 +97,8 @@ pub async fn detailed_health(
     Ok(Json(DetailedHealthResponse {
         status: overall_status,
         services,
+        mode: None,
+        leadership: None,
         timestamp: chrono::Utc::now(),
     }))
 }
@@ -107,6 +111,8 @@ pub async fn readiness_check(
     Ok(Json(HealthResponse {
         status: "ready".to_string(),
         message: "Service is ready to accept requests".to_string(),
+        mode: None,
+        leadership: None,
         timestamp: chrono::Utc::now(),
     }))
 }
@@ -116,6 +122,8 @@ pub async fn liveness_check() -> Result<Json<HealthResponse>, ApiError> {
     Ok(Json(HealthResponse {
         status: "alive".to_string(),
         message: "Service is alive".to_string(),
+        mode: None,
+        leadership: None,
         timestamp: chrono::Utc::now(),
     }))
 } 
\ No newline at end of file
@@ -0,0 +1,168 @@
+use axum::{
+    extract::{Path, State},
+    Extension, Json,
+};
+use stepflow_core::TenantId;
+use stepflow_registry::{ListingVisibility, MarketplaceService, ToolListing, ToolSubscription};
+use crate::errors::ApiError;
+use crate::models::requests::PublishToolRequest;
+use crate::models::responses::{
+    MarketplaceCatalogResponse, MarketplaceSubscriptionsResponse, PublishUpdateResponse,
+};
+use crate::server::AppState;
+use crate::types::UserContext;
+use super::{require_admin, require_tenant};
+
+/// 共享目录服务，写操作会使注册表的工具缓存失效
+fn marketplace(state: &AppState) -> MarketplaceService {
+    MarketplaceService::new(state.db.clone()).with_cache(state.registry.tool_cache())
+}
+
+/// 发布工具到共享目录
+///
+/// 需要管理员角色，且只能发布作者为本租户的工具。
+pub async fn publish_marketplace_tool(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Json(request): Json<PublishToolRequest>,
+) -> Result<Json<ToolListing>, ApiError> {
+    require_admin(&user)?;
+    let tenant_id = require_tenant(&user)?;
+    let visibility = if request.target_tenants.is_empty() {
+        ListingVisibility::Global
+    } else {
+        ListingVisibility::Tenants(
+            request.target_tenants.into_iter().map(TenantId::from_string).collect()
+        )
+    };
+
+    let listing = marketplace(&state)
+        .publish_tool(&tenant_id, &request.tool_id, visibility)
+        .await?;
+
+    Ok(Json(listing))
+}
+
+/// 撤回已发布的工具
+pub async fn withdraw_marketplace_listing(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(listing_id): Path<String>,
+) -> Result<Json<ToolListing>, ApiError> {
+    require_admin(&user)?;
+    let tenant_id = require_tenant(&user)?;
+    let listing = marketplace(&state)
+        .withdraw_listing(&tenant_id, &listing_id)
+        .await?;
+
+    Ok(Json(listing))
+}
+
+/// 将工具的最新版本推送给订阅者（需订阅方审批）
+pub async fn publish_marketplace_update(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(listing_id): Path<String>,
+) -> Result<Json<PublishUpdateResponse>, ApiError> {
+    require_admin(&user)?;
+    let tenant_id = require_tenant(&user)?;
+    let notified_subscriptions = marketplace(&state)
+        .publish_update(&tenant_id, &listing_id)
+        .await?;
+
+    Ok(Json(PublishUpdateResponse {
+        listing_id,
+        notified_subscriptions,
+    }))
+}
+
+/// 列出当前租户可见的共享工具
+pub async fn list_marketplace_catalog(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+) -> Result<Json<MarketplaceCatalogResponse>, ApiError> {
+    let tenant_id = require_tenant(&user)?;
+    let listings = marketplace(&state)
+        .list_catalog(&tenant_id)
+        .await?;
+
+    Ok(Json(MarketplaceCatalogResponse {
+        total_count: listings.len(),
+        listings,
+    }))
+}
+
+/// 订阅共享工具（创建关联条目而非副本）
+pub async fn subscribe_marketplace_listing(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(listing_id): Path<String>,
+) -> Result<Json<ToolSubscription>, ApiError> {
+    let tenant_id = require_tenant(&user)?;
+    let subscription = marketplace(&state)
+        .subscribe(&tenant_id, &listing_id)
+        .await?;
+
+    Ok(Json(subscription))
+}
+
+/// 列出当前租户的订阅
+pub async fn list_marketplace_subscriptions(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+) -> Result<Json<MarketplaceSubscriptionsResponse>, ApiError> {
+    let tenant_id = require_tenant(&user)?;
+    let subscriptions = marketplace(&state)
+        .list_subscriptions(&tenant_id)
+        .await?;
+
+    Ok(Json(MarketplaceSubscriptionsResponse {
+        total_count: subscriptions.len(),
+        subscriptions,
+    }))
+}
+
+/// 取消订阅
+pub async fn unsubscribe_marketplace_subscription(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(subscription_id): Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let tenant_id = require_tenant(&user)?;
+    marketplace(&state)
+        .unsubscribe(&tenant_id, &subscription_id)
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "subscription_id": subscription_id,
+        "message": "Subscription removed"
+    })))
+}
+
+/// 批准待处理的更新
+pub async fn approve_marketplace_update(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(subscription_id): Path<String>,
+) -> Result<Json<ToolSubscription>, ApiError> {
+    let tenant_id = require_tenant(&user)?;
+    let subscription = marketplace(&state)
+        .approve_update(&tenant_id, &subscription_id)
+        .await?;
+
+    Ok(Json(subscription))
+}
+
+/// 拒绝待处理的更新
+pub async fn reject_marketplace_update(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(subscription_id): Path<String>,
+) -> Result<Json<ToolSubscription>, ApiError> {
+    let tenant_id = require_tenant(&user)?;
+    let subscription = marketplace(&state)
+        .reject_update(&tenant_id, &subscription_id)
+        .await?;
+
+    Ok(Json(subscription))
+}
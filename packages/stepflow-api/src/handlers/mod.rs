@@ -4,10 +4,159 @@ pub mod registry;
 pub mod admin;
 pub mod auth;
 pub mod health;
+pub mod users;
+pub mod marketplace;
+pub mod approvals;
+pub mod workflows;
+pub mod tool_sessions;
+pub mod events;
 
 pub use tools::*;
 pub use executions::*;
 pub use registry::*;
 pub use admin::*;
 pub use auth::*;
-pub use health::*; 
\ No newline at end of file
+pub use health::*;
+pub use users::*;
+pub use marketplace::*;
+pub use approvals::*;
+pub use workflows::*;
+pub use tool_sessions::*;
+pub use events::*;
+
+use axum::http::{header, HeaderMap};
+use axum::response::{IntoResponse, Response};
+use stepflow_core::{TenantId, UserRole};
+use stepflow_executor::{ReportSubject, RunReport};
+use crate::errors::ApiError;
+use crate::models::requests::ReportFormat;
+use crate::types::{AuthConfig, PasswordPolicy, UserContext};
+
+/// 客户端 User-Agent
+pub(crate) fn user_agent(headers: &HeaderMap) -> Option<String> {
+    headers.get(header::USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string())
+}
+
+/// 从用户上下文中获取租户 ID
+pub(crate) fn require_tenant(user: &UserContext) -> Result<TenantId, ApiError> {
+    user.tenant_id
+        .clone()
+        .map(TenantId::from_string)
+        .ok_or_else(|| ApiError::BadRequest(
+            "Tenant ID is required for multi-tenant operations".to_string()
+        ))
+}
+
+/// 要求当前用户具有管理员角色
+pub(crate) fn require_admin(user: &UserContext) -> Result<(), ApiError> {
+    if user.roles.iter().any(|role| role == "admin") {
+        Ok(())
+    } else {
+        Err(ApiError::Forbidden("Admin role is required".to_string()))
+    }
+}
+
+/// 要求当前用户是系统租户的管理员
+///
+/// 运行模式、日志、后台任务等部署级设置影响所有租户，普通租户的管理员无权修改；
+/// 未配置 `system_tenant` 时这些接口一律拒绝，只能通过 `stepflow-admin` 操作。
+pub(crate) fn require_system_admin(config: &AuthConfig, user: &UserContext) -> Result<(), ApiError> {
+    require_admin(user)?;
+    match (&config.system_tenant, &user.tenant_id) {
+        (Some(system), Some(tenant)) if system == tenant => Ok(()),
+        _ => Err(ApiError::Forbidden("Deployment settings can only be changed by system administrators".to_string())),
+    }
+}
+
+/// 解析请求中的角色名（admin/user/guest）
+pub(crate) fn parse_role(role: &str) -> Result<UserRole, ApiError> {
+    match role {
+        "admin" => Ok(UserRole::Admin),
+        "user" => Ok(UserRole::User),
+        "guest" => Ok(UserRole::Guest),
+        other => Err(ApiError::BadRequest(format!("Unknown role '{}'", other))),
+    }
+}
+
+/// 按请求的格式输出运行报告，PDF 作为附件下载
+pub(crate) fn report_response(report: &RunReport, format: ReportFormat) -> Response {
+    match format {
+        ReportFormat::Html => ([(header::CONTENT_TYPE, "text/html; charset=utf-8".to_string())], report.to_html()).into_response(),
+        ReportFormat::Pdf => {
+            let subject = match report.subject {
+                ReportSubject::Execution => "execution",
+                ReportSubject::WorkflowRun => "workflow-run",
+            };
+            let disposition = format!("attachment; filename=\"{}-{}.pdf\"", subject, report.id);
+            ([(header::CONTENT_TYPE, "application/pdf".to_string()), (header::CONTENT_DISPOSITION, disposition)], report.to_pdf())
+                .into_response()
+        }
+    }
+}
+
+/// 按配置的密码策略校验新密码
+pub(crate) fn check_password_policy(policy: &PasswordPolicy, password: &str) -> Result<(), ApiError> {
+    let violations = policy.violations(password);
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(ApiError::ValidationError(format!("Password {}", violations.join(", "))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+    use stepflow_core::UserId;
+
+    fn context(tenant_id: Option<&str>, roles: &[&str]) -> UserContext {
+        UserContext {
+            user_id: UserId::new(),
+            tenant_id: tenant_id.map(str::to_string),
+            roles: roles.iter().map(|role| role.to_string()).collect(),
+            permissions: Vec::new(),
+            session_id: "session".to_string(),
+            expires_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_client_metadata_from_headers() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(user_agent(&headers), None);
+
+        headers.insert(header::USER_AGENT, HeaderValue::from_static("curl/8.0"));
+        assert_eq!(user_agent(&headers).as_deref(), Some("curl/8.0"));
+    }
+
+    #[test]
+    fn test_tenant_and_role_checks() {
+        let admin = context(Some("tenant-a"), &["user", "admin"]);
+        assert_eq!(require_tenant(&admin).unwrap().as_str(), "tenant-a");
+        assert!(require_admin(&admin).is_ok());
+
+        let user = context(None, &["user"]);
+        assert!(matches!(require_tenant(&user), Err(ApiError::BadRequest(_))));
+        assert!(matches!(require_admin(&user), Err(ApiError::Forbidden(_))));
+
+        assert_eq!(parse_role("guest").unwrap(), UserRole::Guest);
+        assert!(matches!(parse_role("root"), Err(ApiError::BadRequest(_))));
+    }
+
+    #[test]
+    fn test_check_password_policy() {
+        let policy = PasswordPolicy::default();
+        assert!(check_password_policy(&policy, "Correct7Horse").is_ok());
+        match check_password_policy(&policy, "password") {
+            Err(ApiError::ValidationError(message)) => {
+                assert!(message.starts_with("Password "));
+                assert!(message.contains("is too common"));
+                assert!(message.contains("uppercase"));
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use std::convert::Infallible;
+use axum::{
+    extract::{Path, State},
+    response::sse::{Event, KeepAlive, Sse},
+    Extension, Json,
+};
+use futures::Stream;
+use stepflow_database::TenantRepository;
+use stepflow_executor::EnvironmentRules;
+use stepflow_registry::ToolConfigService;
+use tokio::sync::broadcast::error::RecvError;
+use crate::errors::ApiError;
+use crate::middleware::ensure_accepting_executions;
+use crate::models::requests::{CreateToolSessionRequest, ToolSessionInputRequest};
+use crate::models::responses::ListToolSessionsResponse;
+use crate::server::AppState;
+use crate::tool_sessions::{ToolSessionEvent, ToolSessionInfo, ToolSessionOutput};
+use crate::types::UserContext;
+use super::{require_admin, require_tenant};
+
+/// 获取当前用户可以操作的会话：自己打开的会话，管理员可以操作租户内所有会话
+async fn owned_session(state: &AppState, user: &UserContext, session_id: &str) -> Result<ToolSessionInfo, ApiError> {
+    let tenant_id = require_tenant(user)?;
+    let session = state.tool_sessions.get(&tenant_id, session_id).await?;
+    if session.user_id != user.user_id && require_admin(user).is_err() {
+        return Err(ApiError::NotFound(format!("ToolSession {} not found", session_id)));
+    }
+    Ok(session)
+}
+
+/// 打开交互式会话
+///
+/// 为工具创建长期存活的沙箱，之后通过 `input` 接口依次发送输入。会话命令取自工具定义，
+/// 请求只能追加工具允许的参数；未定义会话命令的工具返回 400。租户打开的会话数达到配额时
+/// 返回 409；会话空闲超过配置的时长后自动关闭。沙箱环境由工具默认值、租户工具配置和请求中的
+/// 环境变量依次覆盖合并而成，合并结果按与执行相同的环境变量策略检查，违反策略时返回 400；
+/// 之后注入 `STEPFLOW_TENANT` 和 `STEPFLOW_SESSION_ID`。
+pub async fn create_tool_session(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Json(request): Json<CreateToolSessionRequest>,
+) -> Result<Json<ToolSessionInfo>, ApiError> {
+    let tenant_id = require_tenant(&user)?;
+    ensure_accepting_executions(&state).await?;
+    let tool = state.registry.get_tool(&request.tool_id).await?;
+    let command = tool.session
+        .as_ref()
+        .ok_or_else(|| ApiError::BadRequest(format!("Tool {} cannot be opened as a session", request.tool_id)))?
+        .with_args(&request.args)
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    let environment = ToolConfigService::new(state.db.clone())
+        .preview_environment(&tenant_id, &request.tool_id, &request.environment)
+        .await?
+        .to_map();
+    let tenant = TenantRepository::new(state.db.as_ref().clone()).get_tenant(&tenant_id).await?;
+    let tenant_rules = match &tenant {
+        Some(tenant) => EnvironmentRules::from_tenant(tenant)?,
+        None => None,
+    };
+    let session_id = uuid::Uuid::new_v4().to_string();
+    let environment = state.environment_policy
+        .apply_session(&environment, tenant_rules.as_ref(), &session_id, tenant_id.as_str())?;
+
+    let session = state.tool_sessions
+        .create(session_id, &tenant_id, &user.user_id, request.tool_id, command, environment)
+        .await?;
+    Ok(Json(session))
+}
+
+/// 列出当前用户打开的会话，管理员可以看到租户内所有会话
+pub async fn list_tool_sessions(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+) -> Result<Json<ListToolSessionsResponse>, ApiError> {
+    let tenant_id = require_tenant(&user)?;
+    let owner = require_admin(&user).is_err().then_some(&user.user_id);
+    Ok(Json(ListToolSessionsResponse { sessions: state.tool_sessions.list(&tenant_id, owner).await }))
+}
+
+/// 获取会话
+pub async fn get_tool_session(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(session_id): Path<String>,
+) -> Result<Json<ToolSessionInfo>, ApiError> {
+    Ok(Json(owned_session(&state, &user, &session_id).await?))
+}
+
+/// 向会话发送一次输入，等待执行完成后返回输出
+///
+/// 同一会话的输入按到达顺序依次执行；输出同时推送给 `events` 的订阅者。
+pub async fn send_tool_session_input(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(session_id): Path<String>,
+    Json(request): Json<ToolSessionInputRequest>,
+) -> Result<Json<ToolSessionOutput>, ApiError> {
+    let session = owned_session(&state, &user, &session_id).await?;
+    ensure_accepting_executions(&state).await?;
+    Ok(Json(state.tool_sessions.send(&session.tenant_id, &session_id, request.input).await?))
+}
+
+/// 以 SSE 推送会话事件：每次输入的 `output`，会话关闭时推送 `closed` 并结束
+pub async fn stream_tool_session_events(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(session_id): Path<String>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    let session = owned_session(&state, &user, &session_id).await?;
+    let receiver = state.tool_sessions.subscribe(&session.tenant_id, &session_id).await?;
+
+    let events = futures::stream::unfold(Some(receiver), |receiver| async move {
+        let mut receiver = receiver?;
+        loop {
+            match receiver.recv().await {
+                Ok(event) => {
+                    let (name, done) = match &event {
+                        ToolSessionEvent::Output(_) => ("output", false),
+                        ToolSessionEvent::Closed { .. } => ("closed", true),
+                    };
+                    let event = Event::default()
+                        .event(name)
+                        .json_data(&event)
+                        .unwrap_or_else(|e| Event::default().event("error").data(e.to_string()));
+                    return Some((Ok(event), (!done).then_some(receiver)));
+                }
+                // 订阅者处理过慢时跳过丢失的事件
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+/// 关闭会话并销毁其沙箱
+pub async fn close_tool_session(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(session_id): Path<String>,
+) -> Result<Json<ToolSessionInfo>, ApiError> {
+    let session = owned_session(&state, &user, &session_id).await?;
+    Ok(Json(state.tool_sessions.close(&session.tenant_id, &session_id).await?))
+}
@@ -1,2 +1,547 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use std::sync::Arc;
+use chrono::Utc;
+use stepflow_core::{ToolConfig, ToolId};
+use stepflow_database::{ToolDocsRecord, ToolPresetRecord, ToolPresetRepository};
+use stepflow_executor::{RolloutManager, SqliteExecutionStore, TestTrigger, ToolRollout, ToolTestRun, ToolTestRunner};
+use stepflow_openapi::extension_descriptor;
+use stepflow_registry::{ChangeFeed, RegistryError, ToolConfigService, ToolDocsService};
+use crate::errors::ApiError;
+use crate::forms::{form_fields, ToolForm};
+use crate::i18n::AcceptLanguage;
+use crate::models::requests::{
+    PreviewToolEnvironmentRequest, RolloutDecisionRequest, SaveToolConfigRequest, SaveToolDocsRequest, SaveToolPresetRequest, StartToolRolloutRequest,
+    TestToolRequest, ToolChangesParams, ToolDocsFormat, ToolDocsParams, ToolEnvironmentParams, ToolTestRunsParams,
+    UpdateToolPresetRequest, UpdateToolRolloutRequest,
+};
+use crate::models::responses::{
+    ListToolPresetsResponse, ListToolsResponse, ToolChangesResponse, ToolConfigResponse, ToolEnvironmentResponse, ToolResponse,
+};
+use crate::server::AppState;
+use crate::types::{PaginationInfo, PaginationParams, UserContext};
+use super::{require_admin, require_tenant};
+
 // 工具处理器占位符
-pub struct ToolsHandler; 
\ No newline at end of file
+pub struct ToolsHandler;
+
+/// 列出工具
+///
+/// 已认证用户的收藏工具排在最前面。指定 `environment` 时只返回该环境
+/// 以及未标记环境的工具。名称与描述按 `Accept-Language` 本地化。
+pub async fn list_tools(
+    State(state): State<AppState>,
+    user: Option<Extension<UserContext>>,
+    Query(params): Query<PaginationParams>,
+    Query(scope): Query<ToolEnvironmentParams>,
+    headers: HeaderMap,
+) -> Result<Json<ListToolsResponse>, ApiError> {
+    let mut tools = match user {
+        Some(Extension(user)) => state.registry.discover_tools_for_user(&user.user_id).await?,
+        None => state.registry.list_tools().await?,
+    };
+    if let Some(environment) = scope.environment {
+        tools.retain(|tool| tool.environment_label.is_none_or(|label| label == environment));
+    }
+
+    let languages = AcceptLanguage::from_headers(&headers);
+    let page = params.page.unwrap_or(1).max(1);
+    let page_size = params.page_size.unwrap_or(20).max(1);
+    let pagination = PaginationInfo::new(page, page_size, tools.len());
+
+    let tools = tools
+        .into_iter()
+        .skip((page - 1) * page_size)
+        .take(page_size)
+        .map(|tool| ToolResponse::localized(tool, languages.languages()))
+        .collect();
+
+    Ok(Json(ListToolsResponse { tools, pagination }))
+}
+
+/// 按序列号增量拉取工具变更（创建、更新、删除、新增版本）
+///
+/// 客户端保存响应中的 `next_since`，下次请求时作为 `since` 传入。
+pub async fn list_tool_changes(
+    State(state): State<AppState>,
+    Query(params): Query<ToolChangesParams>,
+) -> Result<Json<ToolChangesResponse>, ApiError> {
+    let page = ChangeFeed::new(state.db.clone())
+        .changes_since(params.since.unwrap_or(0), params.limit)
+        .await?;
+
+    Ok(Json(page.into()))
+}
+
+/// 获取当前租户的工具配置
+pub async fn get_tool_config(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(tool_id): Path<String>,
+) -> Result<Json<ToolConfigResponse>, ApiError> {
+    let tenant_id = require_tenant(&user)?;
+    let tool_id = ToolId::parse(tool_id)?;
+
+    let config = ToolConfigService::new(state.db.clone())
+        .get_config(&tenant_id, &tool_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("No configuration for tool {}", tool_id)))?;
+
+    Ok(Json(config.into()))
+}
+
+/// 保存当前租户的工具配置
+///
+/// 配置会按工具的 configuration_schema 校验并填充默认值；
+/// `${VAR}` 形式的环境变量引用在执行时解析。
+pub async fn save_tool_config(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(tool_id): Path<String>,
+    Json(request): Json<SaveToolConfigRequest>,
+) -> Result<Json<ToolConfigResponse>, ApiError> {
+    let tenant_id = require_tenant(&user)?;
+    let config = ToolConfig {
+        tool_id: ToolId::parse(tool_id)?,
+        configuration: request.configuration,
+        environment: request.environment,
+        secrets: request.secrets,
+        timeout: request.timeout,
+        retries: request.retries,
+        enabled: request.enabled,
+    };
+
+    let saved = ToolConfigService::new(state.db.clone())
+        .save_config(&tenant_id, config)
+        .await?;
+
+    Ok(Json(saved.into()))
+}
+
+/// 预览工具的执行环境
+///
+/// 优先级：执行请求 > 租户工具配置 > 工具默认值。引用密钥的变量以掩码返回；
+/// 执行器注入的变量（如 `STEPFLOW_EXECUTION_ID`）不在预览中。
+pub async fn preview_tool_environment(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(tool_id): Path<String>,
+    Json(request): Json<PreviewToolEnvironmentRequest>,
+) -> Result<Json<ToolEnvironmentResponse>, ApiError> {
+    let tenant_id = require_tenant(&user)?;
+    let tool_id = ToolId::parse(tool_id)?;
+
+    let environment = ToolConfigService::new(state.db.clone())
+        .preview_environment(&tenant_id, &tool_id, &request.environment)
+        .await?
+        .redacted();
+
+    Ok(Json(ToolEnvironmentResponse { tool_id, variables: environment.variables }))
+}
+
+/// 删除当前租户的工具配置
+pub async fn delete_tool_config(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(tool_id): Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let tenant_id = require_tenant(&user)?;
+    let tool_id = ToolId::parse(tool_id)?;
+
+    ToolConfigService::new(state.db.clone())
+        .delete_config(&tenant_id, &tool_id)
+        .await
+        .map_err(|e| match e {
+            RegistryError::ConfigurationError(message) => ApiError::NotFound(message),
+            other => other.into(),
+        })?;
+
+    Ok(Json(serde_json::json!({
+        "tool_id": tool_id,
+        "message": "Tool configuration removed"
+    })))
+}
+
+/// 获取工具参数表单元数据
+///
+/// 由工具输入参数的 JSON Schema 生成字段顺序、标签、选项、默认值和字段依赖，
+/// 供前端渲染参数表单。
+pub async fn get_tool_form(
+    State(state): State<AppState>,
+    Path(tool_id): Path<String>,
+) -> Result<Json<ToolForm>, ApiError> {
+    let tool_id = ToolId::parse(tool_id)?;
+
+    let tool = state.registry.get_tool(&tool_id).await?;
+    let schema = tool.input_schema
+        .ok_or_else(|| ApiError::NotFound(format!("Tool {} declares no input schema", tool_id)))?;
+
+    Ok(Json(ToolForm {
+        tool_id,
+        version: tool.version.to_string(),
+        fields: form_fields(&schema),
+    }))
+}
+
+fn preset_repository(state: &AppState) -> ToolPresetRepository {
+    ToolPresetRepository::new(state.db.as_ref().clone())
+}
+
+fn validate_preset_name(name: &str) -> Result<&str, ApiError> {
+    let name = name.trim();
+    if name.is_empty() || name.len() > 100 {
+        return Err(ApiError::ValidationError("Preset name must be 1-100 characters".to_string()));
+    }
+    Ok(name)
+}
+
+/// 当前用户可见的预设：租户共享的预设，或自己的私有预设
+async fn visible_preset(state: &AppState, user: &UserContext, tool_id: &ToolId, preset_id: &str) -> Result<ToolPresetRecord, ApiError> {
+    let tenant_id = require_tenant(user)?;
+    preset_repository(state)
+        .get_preset(&tenant_id, preset_id)
+        .await?
+        .filter(|preset| &preset.tool_id == tool_id)
+        .filter(|preset| preset.user_id.as_ref().is_none_or(|owner| owner == &user.user_id))
+        .ok_or_else(|| ApiError::NotFound(format!("Preset {} not found", preset_id)))
+}
+
+/// 修改预设的权限：共享预设需要管理员，私有预设只有本人可以修改
+fn require_preset_owner(user: &UserContext, preset: &ToolPresetRecord) -> Result<(), ApiError> {
+    match &preset.user_id {
+        None => require_admin(user),
+        Some(_) => Ok(()),
+    }
+}
+
+/// 列出工具的参数预设：租户共享的预设和当前用户的私有预设
+pub async fn list_tool_presets(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(tool_id): Path<String>,
+) -> Result<Json<ListToolPresetsResponse>, ApiError> {
+    let tenant_id = require_tenant(&user)?;
+    let tool_id = ToolId::parse(tool_id)?;
+    let presets = preset_repository(&state).list_presets(&tenant_id, &tool_id, &user.user_id).await?;
+    Ok(Json(ListToolPresetsResponse { presets }))
+}
+
+/// 保存工具参数预设，同名且同归属的预设会被替换
+///
+/// 提交执行时通过 `preset` 引用：租户共享预设、同名的个人预设、请求参数依次覆盖。
+pub async fn save_tool_preset(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(tool_id): Path<String>,
+    Json(request): Json<SaveToolPresetRequest>,
+) -> Result<Json<ToolPresetRecord>, ApiError> {
+    let tenant_id = require_tenant(&user)?;
+    if request.shared {
+        require_admin(&user)?;
+    }
+    let name = validate_preset_name(&request.name)?;
+    let tool_id = ToolId::parse(tool_id)?;
+    state.registry.get_tool(&tool_id).await?;
+
+    let owner = (!request.shared).then_some(&user.user_id);
+    let preset = preset_repository(&state)
+        .save_preset(&tenant_id, &tool_id, owner, name, request.description.as_deref(), &request.parameters, &user.user_id)
+        .await?;
+    Ok(Json(preset))
+}
+
+/// 获取工具参数预设
+pub async fn get_tool_preset(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path((tool_id, preset_id)): Path<(String, String)>,
+) -> Result<Json<ToolPresetRecord>, ApiError> {
+    let tool_id = ToolId::parse(tool_id)?;
+    Ok(Json(visible_preset(&state, &user, &tool_id, &preset_id).await?))
+}
+
+/// 更新工具参数预设的名称、描述与参数
+pub async fn update_tool_preset(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path((tool_id, preset_id)): Path<(String, String)>,
+    Json(request): Json<UpdateToolPresetRequest>,
+) -> Result<Json<ToolPresetRecord>, ApiError> {
+    let tenant_id = require_tenant(&user)?;
+    let tool_id = ToolId::parse(tool_id)?;
+    let preset = visible_preset(&state, &user, &tool_id, &preset_id).await?;
+    require_preset_owner(&user, &preset)?;
+    let name = validate_preset_name(&request.name)?;
+
+    let repository = preset_repository(&state);
+    let owner = preset.user_id.as_ref().unwrap_or(&user.user_id);
+    let taken = repository.list_presets(&tenant_id, &tool_id, owner).await?
+        .into_iter()
+        .any(|other| other.id != preset.id && other.name == name && other.user_id == preset.user_id);
+    if taken {
+        return Err(ApiError::Conflict(format!("Preset {} already exists", name)));
+    }
+    let preset = repository
+        .update_preset(&tenant_id, &preset_id, name, request.description.as_deref(), &request.parameters)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Preset {} not found", preset_id)))?;
+    Ok(Json(preset))
+}
+
+/// 删除工具参数预设
+pub async fn delete_tool_preset(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path((tool_id, preset_id)): Path<(String, String)>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let tenant_id = require_tenant(&user)?;
+    let tool_id = ToolId::parse(tool_id)?;
+    let preset = visible_preset(&state, &user, &tool_id, &preset_id).await?;
+    require_preset_owner(&user, &preset)?;
+
+    preset_repository(&state).delete_preset(&tenant_id, &preset_id).await?;
+    Ok(Json(serde_json::json!({
+        "preset_id": preset_id,
+        "message": "Preset removed"
+    })))
+}
+
+fn rollout_manager(state: &AppState) -> RolloutManager {
+    RolloutManager::new(Arc::new(SqliteExecutionStore::new(state.db.clone())), state.registry.clone())
+}
+
+/// 获取工具最近一次灰度发布（含已结束的）
+pub async fn get_tool_rollout(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(tool_id): Path<String>,
+) -> Result<Json<ToolRollout>, ApiError> {
+    require_admin(&user)?;
+    let tool_id = ToolId::parse(tool_id)?;
+
+    let rollout = rollout_manager(&state)
+        .get(&tool_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("No rollout for tool {}", tool_id)))?;
+
+    Ok(Json(rollout))
+}
+
+/// 启动灰度发布：按比例将执行路由到候选版本
+///
+/// 候选版本样本足够后与稳定版本比较成功率和耗时，
+/// 超出阈值自动回滚，持续达标则自动晋升为稳定版本。
+pub async fn start_tool_rollout(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(tool_id): Path<String>,
+    Json(request): Json<StartToolRolloutRequest>,
+) -> Result<Json<ToolRollout>, ApiError> {
+    require_admin(&user)?;
+    let tool_id = ToolId::parse(tool_id)?;
+
+    let stable = state.registry.get_tool(&tool_id).await?;
+    let mut candidate = stable;
+    candidate.version = request.version;
+    if let Some(description) = request.description {
+        candidate.description = description;
+    }
+    candidate.documentation = request.documentation.or(candidate.documentation);
+    candidate.configuration_schema = request.configuration_schema.or(candidate.configuration_schema);
+    candidate.updated_at = Utc::now();
+
+    let rollout = rollout_manager(&state)
+        .start(&tool_id, candidate, request.percentage, request.thresholds)
+        .await?;
+
+    // 候选版本的示例测试通过前不会自动晋升
+    if !rollout.candidate.examples.is_empty() {
+        let runner = test_runner(&state);
+        let candidate = rollout.candidate.clone();
+        let context = test_context(&user);
+        tokio::spawn(async move {
+            if let Err(e) = runner.run(&candidate, TestTrigger::Promotion, context).await {
+                tracing::warn!("Failed to test rollout candidate of tool {}: {}", candidate.id, e);
+            }
+        });
+    }
+
+    Ok(Json(rollout))
+}
+
+/// 调整进行中灰度发布的候选版本比例
+pub async fn update_tool_rollout(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(tool_id): Path<String>,
+    Json(request): Json<UpdateToolRolloutRequest>,
+) -> Result<Json<ToolRollout>, ApiError> {
+    require_admin(&user)?;
+    let tool_id = ToolId::parse(tool_id)?;
+
+    let rollout = rollout_manager(&state)
+        .set_percentage(&tool_id, request.percentage)
+        .await?;
+
+    Ok(Json(rollout))
+}
+
+/// 手动将候选版本晋升为稳定版本
+///
+/// 候选版本有示例时先运行示例测试，未全部通过则拒绝晋升。
+pub async fn promote_tool_rollout(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(tool_id): Path<String>,
+    Json(request): Json<RolloutDecisionRequest>,
+) -> Result<Json<ToolRollout>, ApiError> {
+    require_admin(&user)?;
+    let tool_id = ToolId::parse(tool_id)?;
+    let reason = request.reason.unwrap_or_else(|| format!("promoted manually by {}", user.user_id));
+
+    let manager = rollout_manager(&state);
+    let active = manager
+        .active(&tool_id)
+        .await?
+        .ok_or_else(|| ApiError::Conflict(format!("Tool {} has no rollout in progress", tool_id)))?;
+    if !active.candidate.examples.is_empty() {
+        test_runner(&state)
+            .run(&active.candidate, TestTrigger::Promotion, test_context(&user))
+            .await?;
+    }
+    let rollout = manager.promote(&tool_id, &reason).await?;
+
+    Ok(Json(rollout))
+}
+
+/// 手动回滚灰度发布，所有执行回到稳定版本
+pub async fn rollback_tool_rollout(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(tool_id): Path<String>,
+    Json(request): Json<RolloutDecisionRequest>,
+) -> Result<Json<ToolRollout>, ApiError> {
+    require_admin(&user)?;
+    let tool_id = ToolId::parse(tool_id)?;
+    let reason = request.reason.unwrap_or_else(|| format!("rolled back manually by {}", user.user_id));
+
+    let rollout = rollout_manager(&state).rollback(&tool_id, &reason).await?;
+
+    Ok(Json(rollout))
+}
+
+fn test_runner(state: &AppState) -> ToolTestRunner {
+    ToolTestRunner::new(state.executor.clone(), Arc::new(SqliteExecutionStore::new(state.db.clone())))
+}
+
+/// 示例测试的执行上下文：以当前用户身份执行，没有租户的管理员使用系统租户
+fn test_context(user: &UserContext) -> stepflow_executor::ExecutionContext {
+    let mut context = ToolTestRunner::system_context(user.tenant_id.as_deref().unwrap_or("system"));
+    context.user_id = user.user_id.to_string();
+    context.session_id = user.session_id.clone();
+    context
+}
+
+/// 运行工具示例测试
+///
+/// 逐个执行工具示例，按示例的匹配方式（exact / subset / schema）比较输出，
+/// 结果按版本保存。未指定版本时测试稳定版本。
+pub async fn test_tool(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(tool_id): Path<String>,
+    Json(request): Json<TestToolRequest>,
+) -> Result<Json<ToolTestRun>, ApiError> {
+    require_admin(&user)?;
+    let tool_id = ToolId::parse(tool_id)?;
+
+    let stable = state.registry.get_tool(&tool_id).await?;
+    let tool = match request.version {
+        Some(version) if version != stable.version => rollout_manager(&state)
+            .active(&tool_id)
+            .await?
+            .map(|rollout| rollout.candidate)
+            .filter(|candidate| candidate.version == version)
+            .ok_or_else(|| ApiError::NotFound(format!("Tool {} has no version {} to test", tool_id, version)))?,
+        _ => stable,
+    };
+    if tool.examples.is_empty() {
+        return Err(ApiError::BadRequest(format!("Tool {} {} has no examples", tool_id, tool.version)));
+    }
+
+    let run = test_runner(&state).run(&tool, TestTrigger::Manual, test_context(&user)).await?;
+
+    Ok(Json(run))
+}
+
+/// 列出工具的示例测试记录，最新的在前
+pub async fn list_tool_tests(
+    State(state): State<AppState>,
+    Path(tool_id): Path<String>,
+    Query(params): Query<ToolTestRunsParams>,
+) -> Result<Json<Vec<ToolTestRun>>, ApiError> {
+    let tool_id = ToolId::parse(tool_id)?;
+
+    let runs = test_runner(&state)
+        .list(&tool_id, params.limit.unwrap_or(20).clamp(1, 100))
+        .await?;
+
+    Ok(Json(runs))
+}
+
+/// 获取工具文档
+///
+/// Markdown 文档渲染为清理后的 HTML，默认直接返回 HTML 页面片段；
+/// `format=json` 时返回 HTML 及可运行的示例名称。未指定版本时返回工具当前版本的文档。
+pub async fn get_tool_docs(
+    State(state): State<AppState>,
+    Path(tool_id): Path<String>,
+    Query(params): Query<ToolDocsParams>,
+) -> Result<Response, ApiError> {
+    let tool_id = ToolId::parse(tool_id)?;
+
+    let docs = ToolDocsService::new(state.db.clone())
+        .render_docs(&tool_id, params.version.as_deref())
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Tool {} has no documentation", tool_id)))?;
+
+    Ok(match params.format {
+        ToolDocsFormat::Html => ([(header::CONTENT_TYPE, "text/html; charset=utf-8".to_string())], docs.html).into_response(),
+        ToolDocsFormat::Json => Json(docs).into_response(),
+    })
+}
+
+/// 保存工具文档（仅管理员）
+///
+/// 同一版本的文档会被覆盖，未指定版本时保存为工具当前版本的文档。
+pub async fn save_tool_docs(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(tool_id): Path<String>,
+    Json(request): Json<SaveToolDocsRequest>,
+) -> Result<Json<ToolDocsRecord>, ApiError> {
+    require_admin(&user)?;
+    let tool_id = ToolId::parse(tool_id)?;
+
+    let saved = ToolDocsService::new(state.db.clone())
+        .save_docs(&tool_id, request.version.as_deref(), &request.markdown, Some(user.user_id.as_str()))
+        .await?;
+
+    Ok(Json(saved))
+}
+
+/// 获取 OpenAPI 生成工具支持的 `x-stepflow-*` 扩展描述
+///
+/// 返回每个扩展的名称、说明和 JSON Schema，供编辑器和规范校验工具使用。
+pub async fn openapi_extension_descriptor() -> Json<serde_json::Value> {
+    Json(extension_descriptor())
+}
+
+/// 获取 Stepflow API 自身的 OpenAPI 文档，用于生成客户端 SDK
+pub async fn api_spec_document() -> Json<serde_json::Value> {
+    Json(crate::spec::api_spec())
+}
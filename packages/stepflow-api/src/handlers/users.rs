@@ -0,0 +1,332 @@
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+    Extension, Json,
+};
+use stepflow_core::{TenantId, ToolId, UserInfo};
+use stepflow_database::{OidcRepository, SessionRepository, UserRepository};
+use crate::email::EmailMessage;
+use crate::errors::ApiError;
+use crate::flags::RequestFlags;
+use crate::i18n::AcceptLanguage;
+use crate::models::requests::{ChangePasswordRequest, TwoFactorCodeRequest};
+use crate::models::responses::{
+    FavoriteToolResponse, FeatureFlagValuesResponse, ListFavoritesResponse, ListOidcIdentitiesResponse, ListSessionsResponse, OidcAuthorizeResponse,
+    RecoveryCodesResponse, ToolResponse, TotpSetupResponse, TwoFactorStatusResponse,
+};
+use crate::server::AppState;
+use crate::two_factor::{
+    accept_totp, generate_recovery_codes, generate_totp_secret, otpauth_uri, tenant_requires_admin_2fa,
+    verify_second_factor,
+};
+use crate::types::UserContext;
+use super::auth::start_oidc_login;
+use super::{check_password_policy, require_tenant};
+
+/// 收藏工具
+pub async fn add_favorite_tool(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(tool_id): Path<String>,
+) -> Result<Json<FavoriteToolResponse>, ApiError> {
+    let tool_id = ToolId::parse(tool_id)?;
+    state.registry.add_favorite(&user.user_id, &tool_id).await?;
+
+    Ok(Json(FavoriteToolResponse {
+        tool_id,
+        favorited: true,
+        message: "Tool added to favorites".to_string(),
+    }))
+}
+
+/// 取消收藏工具
+pub async fn remove_favorite_tool(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(tool_id): Path<String>,
+) -> Result<Json<FavoriteToolResponse>, ApiError> {
+    let tool_id = ToolId::parse(tool_id)?;
+    state.registry.remove_favorite(&user.user_id, &tool_id).await?;
+
+    Ok(Json(FavoriteToolResponse {
+        tool_id,
+        favorited: false,
+        message: "Tool removed from favorites".to_string(),
+    }))
+}
+
+/// 当前用户的功能开关取值，供前端按开关显示功能
+pub async fn feature_flag_values(flags: RequestFlags) -> Json<FeatureFlagValuesResponse> {
+    Json(FeatureFlagValuesResponse { flags: flags.evaluate_all() })
+}
+
+/// 列出当前用户收藏的工具，名称与描述按 `Accept-Language` 本地化
+pub async fn list_favorite_tools(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    headers: HeaderMap,
+) -> Result<Json<ListFavoritesResponse>, ApiError> {
+    let languages = AcceptLanguage::from_headers(&headers);
+    let tools: Vec<ToolResponse> = state.registry.list_favorites(&user.user_id).await?
+        .into_iter()
+        .map(|tool| ToolResponse::localized(tool, languages.languages()))
+        .collect();
+
+    Ok(Json(ListFavoritesResponse {
+        total_count: tools.len(),
+        tools,
+    }))
+}
+
+/// 向当前用户的邮箱发送验证邮件
+pub async fn request_email_verification(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let repository = UserRepository::new(state.db.as_ref().clone());
+    let info = repository.get_user(&user.user_id).await?
+        .ok_or_else(|| ApiError::NotFound(format!("User {} not found", user.user_id)))?;
+    if info.email_verified {
+        return Ok(Json(serde_json::json!({
+            "email_verified": true,
+            "message": "Email address is already verified"
+        })));
+    }
+
+    let ttl = chrono::Duration::from_std(state.config.auth_config.email_verification_expiration)
+        .map_err(|e| ApiError::InternalServerError(e.to_string()))?;
+    let token = repository.create_email_verification(&info.id, ttl).await?;
+    state.email_sender.send(&EmailMessage {
+        to: info.email.clone(),
+        subject: "Verify your Stepflow email address".to_string(),
+        body: format!(
+            "Confirm your email address by sending this token to POST /api/v1/auth/verify-email:\n\n{}",
+            token,
+        ),
+    }).await?;
+
+    Ok(Json(serde_json::json!({
+        "email_verified": false,
+        "message": "Verification email sent"
+    })))
+}
+
+/// 修改当前用户密码（新密码需满足密码策略）
+pub async fn change_password(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Json(request): Json<ChangePasswordRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let repository = UserRepository::new(state.db.as_ref().clone());
+    if !repository.verify_user_password(&user.user_id, &request.current_password).await? {
+        return Err(ApiError::Unauthorized("Current password is incorrect".to_string()));
+    }
+    if request.new_password == request.current_password {
+        return Err(ApiError::ValidationError("New password must differ from the current password".to_string()));
+    }
+    check_password_policy(&state.config.auth_config.password_policy, &request.new_password)?;
+
+    repository.change_user_password(&user.user_id, &request.new_password).await?;
+    // 修改密码后注销其他设备上的会话
+    let current_session = Some(user.session_id.as_str()).filter(|id| !id.is_empty());
+    SessionRepository::new(state.db.as_ref().clone())
+        .revoke_user_sessions(&user.user_id, current_session)
+        .await?;
+    Ok(Json(serde_json::json!({
+        "message": "Password changed"
+    })))
+}
+
+/// 查询当前用户的双因素认证状态
+pub async fn two_factor_status(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+) -> Result<Json<TwoFactorStatusResponse>, ApiError> {
+    let repository = UserRepository::new(state.db.as_ref().clone());
+    let enrollment = repository.get_totp(&user.user_id).await?;
+    let required = match user.tenant_id.clone() {
+        Some(tenant_id) if user.roles.iter().any(|role| role == "admin") => {
+            tenant_requires_admin_2fa(state.db.as_ref(), &TenantId::from_string(tenant_id)).await?
+        }
+        _ => false,
+    };
+
+    Ok(Json(TwoFactorStatusResponse {
+        enabled: enrollment.as_ref().is_some_and(|totp| totp.enabled),
+        pending: enrollment.as_ref().is_some_and(|totp| !totp.enabled),
+        recovery_codes_remaining: repository.remaining_recovery_codes(&user.user_id).await?,
+        required,
+    }))
+}
+
+/// 生成 TOTP 密钥，返回 otpauth URI 供身份验证器扫码
+///
+/// 密钥在调用 [`enable_two_factor`] 确认前不会生效；重复调用会替换未确认的密钥。
+pub async fn setup_two_factor(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+) -> Result<Json<TotpSetupResponse>, ApiError> {
+    let repository = UserRepository::new(state.db.as_ref().clone());
+    let account = current_user(&repository, &user).await?;
+    if repository.get_totp(&user.user_id).await?.is_some_and(|totp| totp.enabled) {
+        return Err(ApiError::Conflict("Two-factor authentication is already enabled".to_string()));
+    }
+
+    let secret = generate_totp_secret();
+    let otpauth_uri = otpauth_uri(&secret, &account.username)?;
+    repository.set_pending_totp_secret(&user.user_id, &secret).await?;
+
+    Ok(Json(TotpSetupResponse {
+        secret,
+        qr_payload: otpauth_uri.clone(),
+        otpauth_uri,
+    }))
+}
+
+/// 提交验证码确认绑定，启用双因素认证并返回恢复码
+pub async fn enable_two_factor(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Json(request): Json<TwoFactorCodeRequest>,
+) -> Result<Json<RecoveryCodesResponse>, ApiError> {
+    let repository = UserRepository::new(state.db.as_ref().clone());
+    let account = current_user(&repository, &user).await?;
+    let enrollment = repository.get_totp(&user.user_id).await?
+        .ok_or_else(|| ApiError::BadRequest("Two-factor setup has not been started".to_string()))?;
+    if enrollment.enabled {
+        return Err(ApiError::Conflict("Two-factor authentication is already enabled".to_string()));
+    }
+    if !accept_totp(state.db.as_ref(), &user.user_id, &account.username, &enrollment.secret, &request.code).await? {
+        return Err(ApiError::BadRequest("Invalid two-factor authentication code".to_string()));
+    }
+
+    let recovery_codes = generate_recovery_codes();
+    repository.enable_totp(&user.user_id).await?;
+    repository.replace_recovery_codes(&user.user_id, &recovery_codes).await?;
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
+}
+
+/// 关闭双因素认证（需要提供验证码或恢复码）
+pub async fn disable_two_factor(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Json(request): Json<TwoFactorCodeRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let repository = UserRepository::new(state.db.as_ref().clone());
+    check_second_factor(&state, &repository, &user, &request.code).await?;
+
+    repository.disable_totp(&user.user_id).await?;
+    Ok(Json(serde_json::json!({
+        "message": "Two-factor authentication disabled"
+    })))
+}
+
+/// 重新生成恢复码，旧恢复码全部失效（需要提供验证码或恢复码）
+pub async fn regenerate_recovery_codes(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Json(request): Json<TwoFactorCodeRequest>,
+) -> Result<Json<RecoveryCodesResponse>, ApiError> {
+    let repository = UserRepository::new(state.db.as_ref().clone());
+    check_second_factor(&state, &repository, &user, &request.code).await?;
+
+    let recovery_codes = generate_recovery_codes();
+    repository.replace_recovery_codes(&user.user_id, &recovery_codes).await?;
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
+}
+
+async fn current_user(repository: &UserRepository, user: &UserContext) -> Result<UserInfo, ApiError> {
+    repository.get_user(&user.user_id).await?
+        .ok_or_else(|| ApiError::NotFound(format!("User {} not found", user.user_id.as_str())))
+}
+
+/// 要求用户已启用双因素认证并提供有效的验证码或恢复码
+async fn check_second_factor(
+    state: &AppState,
+    repository: &UserRepository,
+    user: &UserContext,
+    code: &str,
+) -> Result<(), ApiError> {
+    let account = current_user(repository, user).await?;
+    let enrollment = repository.get_totp(&user.user_id).await?
+        .filter(|totp| totp.enabled)
+        .ok_or_else(|| ApiError::BadRequest("Two-factor authentication is not enabled".to_string()))?;
+    if !verify_second_factor(state.db.as_ref(), &user.user_id, &account.username, &enrollment, code).await? {
+        return Err(ApiError::Unauthorized("Invalid two-factor authentication code".to_string()));
+    }
+    Ok(())
+}
+
+/// 发起 OIDC 身份关联，返回提供商的授权地址；完成授权后回调会把身份关联到当前用户
+pub async fn link_oidc_identity(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(provider_id): Path<String>,
+) -> Result<Json<OidcAuthorizeResponse>, ApiError> {
+    let tenant_id = require_tenant(&user)?;
+    let provider = OidcRepository::new(state.db.as_ref().clone())
+        .get_provider(&provider_id)
+        .await?
+        .filter(|provider| provider.enabled && provider.tenant_id == tenant_id)
+        .ok_or_else(|| ApiError::NotFound(format!("OIDC provider {} not found", provider_id)))?;
+
+    Ok(Json(start_oidc_login(&state, &provider, Some(user.user_id.clone())).await?))
+}
+
+/// 列出当前用户关联的 OIDC 身份
+pub async fn list_oidc_identities(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+) -> Result<Json<ListOidcIdentitiesResponse>, ApiError> {
+    let identities = OidcRepository::new(state.db.as_ref().clone()).list_identities(&user.user_id).await?;
+    Ok(Json(ListOidcIdentitiesResponse { identities }))
+}
+
+/// 解除当前用户在某个提供商的身份关联
+pub async fn unlink_oidc_identity(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(provider_id): Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    if !OidcRepository::new(state.db.as_ref().clone())
+        .unlink_identity(&user.user_id, &provider_id)
+        .await?
+    {
+        return Err(ApiError::NotFound(format!("No identity linked for provider {}", provider_id)));
+    }
+
+    Ok(Json(serde_json::json!({
+        "provider_id": provider_id,
+        "message": "OIDC identity unlinked"
+    })))
+}
+
+/// 列出当前用户的活跃会话
+pub async fn list_sessions(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+) -> Result<Json<ListSessionsResponse>, ApiError> {
+    let sessions = SessionRepository::new(state.db.as_ref().clone())
+        .list_active_sessions(&user.user_id)
+        .await?;
+    Ok(Json(ListSessionsResponse::new(sessions, &user.session_id)))
+}
+
+/// 注销当前用户的某个会话
+pub async fn revoke_session(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(session_id): Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    if !SessionRepository::new(state.db.as_ref().clone())
+        .revoke_session(&user.user_id, &session_id)
+        .await?
+    {
+        return Err(ApiError::NotFound(format!("Active session {} not found", session_id)));
+    }
+
+    Ok(Json(serde_json::json!({
+        "session_id": session_id,
+        "message": "Session revoked"
+    })))
+}
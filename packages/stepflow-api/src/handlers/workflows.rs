@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+use axum::{
+    extract::{Path, Query, State},
+    response::Response,
+    Extension, Json,
+};
+use stepflow_core::ExecutionId;
+use stepflow_executor::{
+    DryRunReport, IssueSeverity, PublishResult, RunReport, StepExecution, WorkflowDefinition, WorkflowRun, WorkflowValidator,
+    WorkflowVersion,
+};
+use crate::errors::ApiError;
+use crate::middleware::ensure_accepting_executions;
+use crate::models::requests::{
+    DryRunWorkflowRequest, PublishWorkflowVersionRequest, ReportParams, StartWorkflowRunRequest, UpdateMigrationPolicyRequest,
+};
+use crate::models::responses::{ListWorkflowVersionsResponse, MigrationPolicyResponse, WorkflowValidationResponse};
+use crate::server::AppState;
+use crate::types::{RequestTrace, UserContext};
+use super::{report_response, require_admin, require_tenant};
+
+/// 校验工作流定义
+///
+/// 检查步骤 ID、引用的工具是否存在且可用、模板语法以及步骤间的引用（含循环引用），不执行任何步骤。
+pub async fn validate_workflow(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Json(definition): Json<WorkflowDefinition>,
+) -> Result<Json<WorkflowValidationResponse>, ApiError> {
+    require_tenant(&user)?;
+    let issues = WorkflowValidator::new(state.registry.clone())
+        .validate(&definition)
+        .await;
+
+    Ok(Json(WorkflowValidationResponse {
+        valid: !issues.iter().any(|issue| issue.severity == IssueSeverity::Error),
+        issues,
+    }))
+}
+
+/// 试运行工作流
+///
+/// 使用模拟输出推演各步骤，返回执行计划（选中的分支、foreach 展开的迭代、各工具收到的参数）和发现的问题。
+/// 未指定上下文时使用当前用户和请求的上下文。
+pub async fn dry_run_workflow(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    trace: Option<Extension<RequestTrace>>,
+    Json(mut request): Json<DryRunWorkflowRequest>,
+) -> Result<Json<DryRunReport>, ApiError> {
+    require_tenant(&user)?;
+    if request.options.context.is_none() {
+        request.options.context = trace.map(|Extension(trace)| trace.execution_context(&user));
+    }
+
+    let report = WorkflowValidator::new(state.registry.clone())
+        .dry_run(&request.definition, &request.options)
+        .await;
+
+    Ok(Json(report))
+}
+
+/// 发布工作流新版本（需要管理员角色）
+///
+/// 新运行使用最新版本，进行中的运行保留原版本。发布破坏性变更时，按工作流的迁移策略
+/// 处理旧版本的进行中运行：跑完之前不启动新运行（drain）、取消（cancel）或在下一个顶层步骤前
+/// 迁移到新版本（migrate）。
+pub async fn publish_workflow_version(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(workflow_id): Path<String>,
+    Json(request): Json<PublishWorkflowVersionRequest>,
+) -> Result<Json<PublishResult>, ApiError> {
+    let tenant_id = require_tenant(&user)?;
+    require_admin(&user)?;
+    if request.definition.id != workflow_id {
+        return Err(ApiError::BadRequest(format!(
+            "Definition id '{}' does not match workflow '{}'", request.definition.id, workflow_id
+        )));
+    }
+
+    let result = state.workflow_engine
+        .publish(tenant_id.as_str(), &request.definition, request.breaking, Some(user.user_id.as_str()))
+        .await?;
+    Ok(Json(result))
+}
+
+/// 列出工作流的已发布版本，最新的在前
+pub async fn list_workflow_versions(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(workflow_id): Path<String>,
+) -> Result<Json<ListWorkflowVersionsResponse>, ApiError> {
+    let tenant_id = require_tenant(&user)?;
+    let versions = state.workflow_engine
+        .list_versions(tenant_id.as_str(), &workflow_id)
+        .await?;
+
+    Ok(Json(ListWorkflowVersionsResponse {
+        total_count: versions.len(),
+        versions,
+    }))
+}
+
+/// 获取工作流的指定版本
+pub async fn get_workflow_version(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path((workflow_id, version)): Path<(String, u32)>,
+) -> Result<Json<WorkflowVersion>, ApiError> {
+    let tenant_id = require_tenant(&user)?;
+    state.workflow_engine
+        .get_version(tenant_id.as_str(), &workflow_id, Some(version))
+        .await?
+        .map(Json)
+        .ok_or_else(|| ApiError::NotFound(format!("Workflow {} version {} not found", workflow_id, version)))
+}
+
+/// 获取工作流的迁移策略；未配置时为 drain
+pub async fn get_migration_policy(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(workflow_id): Path<String>,
+) -> Result<Json<MigrationPolicyResponse>, ApiError> {
+    let tenant_id = require_tenant(&user)?;
+    let policy = state.workflow_engine
+        .migration_policy(tenant_id.as_str(), &workflow_id)
+        .await?;
+
+    Ok(Json(MigrationPolicyResponse { workflow_id, policy }))
+}
+
+/// 设置工作流的迁移策略（需要管理员角色）
+pub async fn update_migration_policy(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(workflow_id): Path<String>,
+    Json(request): Json<UpdateMigrationPolicyRequest>,
+) -> Result<Json<MigrationPolicyResponse>, ApiError> {
+    let tenant_id = require_tenant(&user)?;
+    require_admin(&user)?;
+    state.workflow_engine
+        .set_migration_policy(tenant_id.as_str(), &workflow_id, request.policy)
+        .await?;
+
+    Ok(Json(MigrationPolicyResponse { workflow_id, policy: request.policy }))
+}
+
+/// 以最新发布的版本启动工作流运行
+///
+/// 请求体中的标签和备注附加到运行中的每个步骤执行。
+pub async fn start_workflow_run(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    trace: Option<Extension<RequestTrace>>,
+    Path(workflow_id): Path<String>,
+    request: Option<Json<StartWorkflowRunRequest>>,
+) -> Result<Json<WorkflowRun>, ApiError> {
+    require_tenant(&user)?;
+    ensure_accepting_executions(&state).await?;
+    let trace = trace
+        .map(|Extension(trace)| trace)
+        .unwrap_or_else(|| RequestTrace::from_headers(None, None));
+    let Json(request) = request.unwrap_or_default();
+
+    let mut context = trace.execution_context(&user);
+    context.labels = request.labels;
+    context.note = request.note;
+    let run = state.workflow_engine
+        .start_latest(&workflow_id, context)
+        .await?;
+    Ok(Json(run))
+}
+
+/// 获取工作流运行
+pub async fn get_workflow_run(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(run_id): Path<String>,
+) -> Result<Json<WorkflowRun>, ApiError> {
+    Ok(Json(find_run(&state, &user, &run_id).await?))
+}
+
+/// 导出工作流运行报告（`format=html|pdf`，默认 HTML）
+///
+/// 每个步骤的耗时取自其执行的时间线，日志与资源使用汇总自各步骤的执行结果。
+pub async fn get_workflow_run_report(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(run_id): Path<String>,
+    Query(params): Query<ReportParams>,
+) -> Result<Response, ApiError> {
+    let run = find_run(&state, &user, &run_id).await?;
+    let inputs = state.workflow_engine.get_run_inputs(&run_id).await?;
+    let mut executions = HashMap::new();
+    for execution_id in run.steps.iter().filter_map(|step| step.execution_id.as_ref()) {
+        let id = ExecutionId::from_string(execution_id.clone());
+        let execution = StepExecution {
+            timeline: state.executor.get_execution_timeline(&id).await?,
+            result: state.executor.get_execution_result(&id).await.ok(),
+        };
+        executions.insert(execution_id.clone(), execution);
+    }
+
+    Ok(report_response(&RunReport::for_workflow(&run, inputs, &executions), params.format))
+}
+
+/// 取消进行中或等待审批的工作流运行，其待处理审批一并取消
+pub async fn cancel_workflow_run(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserContext>,
+    Path(run_id): Path<String>,
+) -> Result<Json<WorkflowRun>, ApiError> {
+    find_run(&state, &user, &run_id).await?;
+    Ok(Json(state.workflow_engine.cancel(&run_id).await?))
+}
+
+/// 查找当前租户的工作流运行
+async fn find_run(state: &AppState, user: &UserContext, run_id: &str) -> Result<WorkflowRun, ApiError> {
+    let tenant_id = require_tenant(user)?;
+    state.workflow_engine
+        .get_run(run_id)
+        .await?
+        .filter(|run| run.tenant_id == tenant_id.as_str())
+        .ok_or_else(|| ApiError::NotFound(format!("Workflow run {} not found", run_id)))
+}
@@ -0,0 +1,152 @@
+//! 本地化
+//!
+//! 按 `Accept-Language` 协商响应语言：错误信息使用内置的错误目录（至少包含英文与中文），
+//! 工具名称与描述使用工具自带的 [`ToolLocalization`](stepflow_core::ToolLocalization)，
+//! 缺少对应语言时回退到默认文本。
+
+use axum::http::{header::ACCEPT_LANGUAGE, HeaderMap};
+use serde::{Deserialize, Serialize};
+
+/// 错误目录支持的语言
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    En,
+    Zh,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 2] = [Locale::En, Locale::Zh];
+
+    /// 语言标签，用于 `Content-Language`
+    pub fn tag(&self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::Zh => "zh",
+        }
+    }
+
+    /// 按主语言子标签匹配，`zh-CN`、`zh-Hant` 都对应中文
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let primary = tag.split(['-', '_']).next().unwrap_or_default();
+        Self::ALL.into_iter().find(|locale| locale.tag().eq_ignore_ascii_case(primary))
+    }
+}
+
+/// 按偏好排序的 `Accept-Language` 语言标签
+///
+/// 权重相同的标签保持请求中的顺序；`q=0` 的标签与通配符 `*` 被忽略。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AcceptLanguage(Vec<String>);
+
+impl AcceptLanguage {
+    pub fn parse(header: &str) -> Self {
+        let mut ranges: Vec<(String, f32)> = header
+            .split(',')
+            .filter_map(|range| {
+                let mut parts = range.split(';');
+                let tag = parts.next()?.trim();
+                let quality = parts
+                    .filter_map(|param| param.trim().strip_prefix("q="))
+                    .find_map(|q| q.trim().parse::<f32>().ok())
+                    .unwrap_or(1.0);
+                (!tag.is_empty() && tag != "*" && quality > 0.0).then(|| (tag.to_string(), quality))
+            })
+            .collect();
+        ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+        Self(ranges.into_iter().map(|(tag, _)| tag).collect())
+    }
+
+    /// 从请求头读取，没有 `Accept-Language` 时为空
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        headers
+            .get(ACCEPT_LANGUAGE)
+            .and_then(|value| value.to_str().ok())
+            .map(Self::parse)
+            .unwrap_or_default()
+    }
+
+    pub fn languages(&self) -> &[String] {
+        &self.0
+    }
+
+    /// 错误目录中最符合偏好的语言，都不支持时为英文
+    pub fn locale(&self) -> Locale {
+        self.0.iter().find_map(|tag| Locale::from_tag(tag)).unwrap_or_default()
+    }
+}
+
+/// 错误目录：错误代码及各语言的标题
+const ERROR_CATALOG: &[(&str, &str, &str)] = &[
+    ("BAD_REQUEST", "Bad request", "请求无效"),
+    ("UNAUTHORIZED", "Unauthorized", "未认证"),
+    ("FORBIDDEN", "Forbidden", "无权访问"),
+    ("NOT_FOUND", "Not found", "资源不存在"),
+    ("METHOD_NOT_ALLOWED", "Method not allowed", "不支持该请求方法"),
+    ("CONFLICT", "Conflict", "资源冲突"),
+    ("UNPROCESSABLE_ENTITY", "Unprocessable entity", "无法处理的请求内容"),
+    ("RATE_LIMIT_EXCEEDED", "Rate limit exceeded", "请求过于频繁"),
+    ("INTERNAL_SERVER_ERROR", "Internal server error", "服务器内部错误"),
+    ("SERVICE_UNAVAILABLE", "Service unavailable", "服务暂不可用"),
+    ("GATEWAY_TIMEOUT", "Gateway timeout", "网关超时"),
+    ("VALIDATION_ERROR", "Validation error", "校验失败"),
+    ("SERIALIZATION_ERROR", "Serialization error", "序列化失败"),
+    ("DATABASE_ERROR", "Database error", "数据库错误"),
+    ("REGISTRY_ERROR", "Registry error", "工具注册表错误"),
+    ("EXECUTOR_ERROR", "Executor error", "执行器错误"),
+    ("SANDBOX_ERROR", "Sandbox error", "沙箱错误"),
+    ("MONITORING_ERROR", "Monitoring error", "监控错误"),
+    ("STEPFLOW_ERROR", "Stepflow error", "系统错误"),
+    ("JWT_ERROR", "JWT error", "令牌无效"),
+    ("JSON_ERROR", "JSON error", "JSON 格式错误"),
+];
+
+/// 错误代码在指定语言下的标题，目录中没有该代码时为 `None`
+pub fn error_title(code: &str, locale: Locale) -> Option<&'static str> {
+    ERROR_CATALOG.iter().find(|(key, _, _)| *key == code).map(|(_, en, zh)| match locale {
+        Locale::En => *en,
+        Locale::Zh => *zh,
+    })
+}
+
+/// 本地化的错误信息：翻译后的标题加上原始详情
+///
+/// 详情来自具体的出错位置，保持原文。
+pub fn localize_error(code: &str, detail: Option<&str>, locale: Locale) -> Option<String> {
+    let title = error_title(code, locale)?;
+    let separator = match locale {
+        Locale::En => ": ",
+        Locale::Zh => "：",
+    };
+    Some(match detail {
+        Some(detail) => format!("{}{}{}", title, separator, detail),
+        None => title.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accept_language_negotiation() {
+        let languages = AcceptLanguage::parse("fr;q=0.9, zh-CN;q=0.8, en;q=0.5, de;q=0");
+        assert_eq!(languages.languages(), ["fr", "zh-CN", "en"]);
+        assert_eq!(languages.locale(), Locale::Zh);
+
+        assert_eq!(AcceptLanguage::parse("en-US,zh;q=0.9").locale(), Locale::En);
+        assert_eq!(AcceptLanguage::parse("*").locale(), Locale::En);
+        assert_eq!(AcceptLanguage::default().locale(), Locale::En);
+    }
+
+    #[test]
+    fn test_error_catalog() {
+        assert_eq!(
+            localize_error("NOT_FOUND", Some("Tool abc"), Locale::Zh).as_deref(),
+            Some("资源不存在：Tool abc"),
+        );
+        assert_eq!(localize_error("RATE_LIMIT_EXCEEDED", None, Locale::En).as_deref(), Some("Rate limit exceeded"));
+        assert_eq!(localize_error("UNKNOWN", None, Locale::Zh), None);
+    }
+}
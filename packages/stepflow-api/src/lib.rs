@@ -11,6 +11,20 @@ pub mod middleware;
 pub mod routes;
 pub mod handlers;
 pub mod graphql;
+pub mod email;
+pub mod two_factor;
+pub mod oidc;
+pub mod directory;
+pub mod approvals;
+pub mod tenant_bundle;
+pub mod user_data;
+pub mod spec;
+pub mod forms;
+pub mod i18n;
+pub mod tool_sessions;
+pub mod flags;
+pub mod services;
+pub mod app;
 
 // Re-export commonly used types and traits
 pub use errors::*;
@@ -22,12 +36,57 @@ pub use models::*;
 pub use middleware::*;
 
 // Re-export routes and handlers
-pub use routes::*;
-pub use handlers::*;
+pub use routes::{
+    AdminRouter, ApprovalsRouter, AuthRouter, EventsRouter, ExecutionsRouter, MarketplaceRouter,
+    RegistryRouter, ToolSessionsRouter, ToolsRouter, UsersRouter, WorkflowsRouter,
+};
+pub use handlers::{AdminHandler, AuthHandler, ExecutionsHandler, RegistryHandler, ToolsHandler};
 
 // Re-export GraphQL
 pub use graphql::*;
 
+// Re-export email
+pub use email::*;
+
+// Re-export two-factor authentication
+pub use two_factor::*;
+
+// Re-export OIDC
+pub use oidc::*;
+
+// Re-export directory sync
+pub use directory::*;
+
+// Re-export approval notifications
+pub use approvals::*;
+
+// Re-export tenant import/export
+pub use tenant_bundle::*;
+
+// Re-export user data compliance operations
+pub use user_data::*;
+
+// Re-export the API's own OpenAPI document
+pub use spec::api_spec;
+
+// Re-export tool form metadata
+pub use forms::{form_fields, ToolForm};
+
+// Re-export localization
+pub use i18n::{AcceptLanguage, Locale};
+
+// Re-export interactive sessions
+pub use tool_sessions::{ToolSessionConfig, ToolSessionEvent, ToolSessionInfo, ToolSessionManager, ToolSessionOutput};
+
+// Re-export request-scoped feature flags
+pub use flags::RequestFlags;
+
+// Re-export default service implementations
+pub use services::*;
+
+// Re-export application assembly
+pub use app::build_app;
+
 /// API 包版本
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
@@ -1,5 +1,7 @@
 use crate::errors::{ApiError, ApiResult, AuthError, AuthResult};
-use crate::server::{AuthService, Middleware};
+use crate::middleware::ClientAddr;
+use crate::server::{AppState, AuthService, Middleware};
+use crate::two_factor::tenant_requires_admin_2fa;
 use crate::types::{HttpRequest, HttpResponse, JwtClaims, UserContext};
 use async_trait::async_trait;
 use axum::{
@@ -8,8 +10,8 @@ use axum::{
     middleware::Next,
     response::Response,
 };
-use jsonwebtoken::{decode, DecodingKey, Validation};
-use stepflow_core::UserId;
+use stepflow_core::{TenantId, UserId};
+use stepflow_database::{SessionRepository, UserRepository};
 use std::sync::Arc;
 use tracing::{debug, error, info, warn};
 
@@ -31,6 +33,9 @@ impl AuthMiddleware {
                 "/api/v1/auth/register".to_string(),
                 "/api/v1/auth/refresh".to_string(),
                 "/api/v1/auth/forgot-password".to_string(),
+                "/api/v1/auth/accept-invite".to_string(),
+                "/api/v1/auth/verify-email".to_string(),
+                "/api/v1/auth/oidc".to_string(),
             ],
         }
     }
@@ -42,13 +47,8 @@ impl AuthMiddleware {
     
     /// 验证 JWT 令牌
     async fn validate_jwt_token(&self, token: &str) -> AuthResult<UserContext> {
-        let decoding_key = DecodingKey::from_secret(self.jwt_secret.as_ref());
-        let validation = Validation::default();
-        
-        match decode::<JwtClaims>(token, &decoding_key, &validation) {
-            Ok(token_data) => {
-                let claims = token_data.claims;
-                
+        match JwtClaims::decode_access(token, &self.jwt_secret) {
+            Ok(claims) => {
                 // 检查令牌是否过期
                 let now = chrono::Utc::now().timestamp();
                 if claims.exp < now {
@@ -225,14 +225,8 @@ pub async fn jwt_auth(
 
     let token = &auth_header[7..]; // Remove "Bearer " prefix
 
-    let token_data = decode::<JwtClaims>(
-        token,
-        &DecodingKey::from_secret(secret.as_ref()),
-        &Validation::default(),
-    )
-    .map_err(|e| ApiError::Unauthorized(format!("Invalid token: {}", e)))?;
-
-    let claims = token_data.claims;
+    let claims = JwtClaims::decode_access(token, &secret)
+        .map_err(|e| ApiError::Unauthorized(format!("Invalid token: {}", e)))?;
 
     // 验证令牌是否过期
     let now = chrono::Utc::now().timestamp();
@@ -241,14 +235,7 @@ pub async fn jwt_auth(
     }
 
     // 创建用户上下文
-    let user_context = UserContext {
-        user_id: UserId::from_string(claims.user_id),
-        tenant_id: claims.tenant_id,
-        roles: claims.roles,
-        permissions: claims.permissions,
-        session_id: "".to_string(), // TODO: 从令牌中获取会话ID
-        expires_at: chrono::DateTime::from_timestamp(claims.exp, 0).unwrap_or_else(chrono::Utc::now),
-    };
+    let user_context = UserContext::from(claims);
 
     // 将用户上下文添加到请求扩展中
     request.extensions_mut().insert(user_context);
@@ -256,6 +243,110 @@ pub async fn jwt_auth(
     Ok(next.run(request).await)
 }
 
+/// 邮箱验证策略中间件
+///
+/// 当 `auth_config.require_verified_email` 开启时，拒绝尚未验证邮箱的用户。
+/// 需要挂载在 JWT 认证中间件之后；验证邮件相关的接口不应放在该中间件之后。
+pub async fn require_verified_email(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    if !state.config.auth_config.require_verified_email {
+        return Ok(next.run(request).await);
+    }
+
+    let user_id = request.extensions()
+        .get::<UserContext>()
+        .map(|user| user.user_id.clone())
+        .ok_or_else(|| ApiError::Unauthorized("User not authenticated".to_string()))?;
+
+    let verified = UserRepository::new(state.db.as_ref().clone())
+        .get_user(&user_id)
+        .await?
+        .is_some_and(|user| user.email_verified);
+    if !verified {
+        return Err(ApiError::Forbidden("Email address has not been verified".to_string()));
+    }
+
+    Ok(next.run(request).await)
+}
+
+/// 会话状态中间件
+///
+/// 拒绝已撤销或已过期会话的令牌，并记录会话的最近活动时间和 IP。未携带会话 ID 的
+/// 凭证（如 API 密钥）直接放行。需要挂载在 JWT 认证中间件之后。
+pub async fn require_active_session(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let user = request.extensions()
+        .get::<UserContext>()
+        .cloned()
+        .ok_or_else(|| ApiError::Unauthorized("User not authenticated".to_string()))?;
+    if user.session_id.is_empty() {
+        return Ok(next.run(request).await);
+    }
+
+    let repository = SessionRepository::new(state.db.as_ref().clone());
+    let active = repository.get_session(&user.session_id)
+        .await?
+        .is_some_and(|session| session.user_id == user.user_id && session.is_active());
+    if !active {
+        return Err(ApiError::Unauthorized("Session has been revoked or has expired".to_string()));
+    }
+
+    let ip_address = request.extensions().get::<ClientAddr>().map(ToString::to_string);
+    repository.touch_session(
+        &user.session_id,
+        ip_address.as_deref(),
+        chrono::Duration::seconds(SESSION_ACTIVITY_INTERVAL_SECS),
+    ).await?;
+
+    Ok(next.run(request).await)
+}
+
+/// 会话活动时间的最小记录间隔（秒），避免每个请求都写库
+const SESSION_ACTIVITY_INTERVAL_SECS: i64 = 60;
+
+/// 管理员双因素认证策略中间件
+///
+/// 租户开启 `require_2fa_for_admins` 后，未启用双因素认证的管理员只能访问
+/// `/api/v1/users/me/2fa` 下的接口完成绑定。需要挂载在 JWT 认证中间件之后。
+pub async fn require_two_factor(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    if request.uri().path().starts_with("/api/v1/users/me/2fa") {
+        return Ok(next.run(request).await);
+    }
+
+    let user = request.extensions()
+        .get::<UserContext>()
+        .cloned()
+        .ok_or_else(|| ApiError::Unauthorized("User not authenticated".to_string()))?;
+    let tenant_id = match user.tenant_id {
+        Some(tenant_id) if user.roles.iter().any(|role| role == "admin") => TenantId::from_string(tenant_id),
+        _ => return Ok(next.run(request).await),
+    };
+
+    if tenant_requires_admin_2fa(state.db.as_ref(), &tenant_id).await? {
+        let enrolled = UserRepository::new(state.db.as_ref().clone())
+            .get_totp(&user.user_id)
+            .await?
+            .is_some_and(|totp| totp.enabled);
+        if !enrolled {
+            return Err(ApiError::Forbidden(
+                "Two-factor authentication is required for administrators of this tenant".to_string(),
+            ));
+        }
+    }
+
+    Ok(next.run(request).await)
+}
+
 /// 权限检查中间件
 pub struct PermissionMiddleware {
     auth_service: Arc<dyn AuthService>,
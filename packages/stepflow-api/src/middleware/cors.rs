@@ -1,8 +1,303 @@
-// CORS 中间件占位符
-pub struct CorsMiddleware;
+use crate::server::AppState;
+use crate::types::{CorsConfig, CORS_POLICY_SETTING};
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+use stepflow_core::StepflowResult;
+use stepflow_database::TenantRepository;
+use tracing::warn;
 
-impl CorsMiddleware {
-    pub fn new() -> Self {
-        Self
+/// CORS 中间件
+///
+/// 默认使用部署级的 `cors_config`；请求的 Host 指向某个租户域名且该租户在设置中保存了
+/// 有效策略时，改用租户策略。预检请求直接在此应答，不再进入后续处理器。
+pub async fn cors(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let origin = request.headers().get(header::ORIGIN).and_then(|v| v.to_str().ok()).map(str::to_string);
+    let origin = match origin {
+        Some(origin) if state.config.enable_cors => origin,
+        _ => return next.run(request).await,
+    };
+
+    let policy = resolve_cors_policy(&state, request.headers()).await;
+    let allowed = policy.allows_origin(&origin);
+
+    let is_preflight = request.method() == Method::OPTIONS
+        && request.headers().contains_key(header::ACCESS_CONTROL_REQUEST_METHOD);
+    if is_preflight {
+        let mut response = StatusCode::NO_CONTENT.into_response();
+        if allowed && preflight_allowed(&policy, request.headers()) {
+            let headers = response.headers_mut();
+            insert_origin_headers(headers, &policy, &origin);
+            let methods = policy.allow_methods.iter().map(|m| m.to_string()).collect::<Vec<_>>();
+            insert_list(headers, header::ACCESS_CONTROL_ALLOW_METHODS, &methods);
+            insert_list(headers, header::ACCESS_CONTROL_ALLOW_HEADERS, &policy.allow_headers);
+            if let Some(max_age) = policy.max_age {
+                headers.insert(header::ACCESS_CONTROL_MAX_AGE, HeaderValue::from(max_age.as_secs()));
+            }
+        }
+        append_vary(response.headers_mut(), "Origin, Access-Control-Request-Method, Access-Control-Request-Headers");
+        return response;
+    }
+
+    let mut response = next.run(request).await;
+    if allowed {
+        let headers = response.headers_mut();
+        insert_origin_headers(headers, &policy, &origin);
+        insert_list(headers, header::ACCESS_CONTROL_EXPOSE_HEADERS, &policy.expose_headers);
+    }
+    append_vary(response.headers_mut(), "Origin");
+    response
+}
+
+/// 按主机名缓存的租户 CORS 策略
+///
+/// 每个跨域请求都要按 Host 查找租户，查找结果（包括“该主机没有租户策略”）缓存 `ttl`。
+/// 管理员修改策略时清空缓存，其它变更（如租户域名）最多延迟 `ttl` 生效。
+/// Host 由客户端决定，条目数达到上限时先清理过期条目，仍然满则整体清空。
+#[derive(Debug)]
+pub struct CorsPolicyCache {
+    ttl: Duration,
+    max_entries: usize,
+    entries: RwLock<HashMap<String, (Instant, Option<CorsConfig>)>>,
+}
+
+impl Default for CorsPolicyCache {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(60), 1024)
+    }
+}
+
+impl CorsPolicyCache {
+    pub fn new(ttl: Duration, max_entries: usize) -> Self {
+        Self {
+            ttl,
+            max_entries: max_entries.max(1),
+            entries: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// 缓存的查找结果：外层 `None` 表示未缓存，内层 `None` 表示该主机没有有效的租户策略
+    pub fn get(&self, host: &str) -> Option<Option<CorsConfig>> {
+        let entries = self.entries.read().unwrap_or_else(|e| e.into_inner());
+        entries.get(host)
+            .filter(|(cached_at, _)| cached_at.elapsed() < self.ttl)
+            .map(|(_, policy)| policy.clone())
+    }
+
+    /// 缓存主机的查找结果
+    pub fn insert(&self, host: String, policy: Option<CorsConfig>) {
+        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+        if entries.len() >= self.max_entries && !entries.contains_key(&host) {
+            entries.retain(|_, (cached_at, _)| cached_at.elapsed() < self.ttl);
+            if entries.len() >= self.max_entries {
+                entries.clear();
+            }
+        }
+        entries.insert(host, (Instant::now(), policy));
+    }
+
+    /// 清空缓存，在租户策略变更后调用
+    pub fn clear(&self) {
+        self.entries.write().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+}
+
+/// 确定请求适用的 CORS 策略
+///
+/// 查找失败或租户策略无效时回退到部署级策略，不影响请求本身；查找失败不缓存。
+pub async fn resolve_cors_policy(state: &AppState, headers: &HeaderMap) -> CorsConfig {
+    let deployment = &state.config.cors_config;
+    if !deployment.allow_tenant_overrides {
+        return deployment.clone();
+    }
+    let Some(host) = request_host(headers) else {
+        return deployment.clone();
+    };
+
+    let policy = match state.cors_policies.get(&host) {
+        Some(policy) => policy,
+        None => match tenant_cors_policy(state, &host).await {
+            Ok(policy) => {
+                state.cors_policies.insert(host, policy.clone());
+                policy
+            }
+            Err(e) => {
+                warn!("Failed to look up tenant for host {}: {}", host, e);
+                None
+            }
+        },
+    };
+    policy.unwrap_or_else(|| deployment.clone())
+}
+
+/// 主机名对应租户保存的有效策略；没有租户、没有策略或策略无效时为 `None`
+async fn tenant_cors_policy(state: &AppState, host: &str) -> StepflowResult<Option<CorsConfig>> {
+    let tenant = TenantRepository::new(state.db.as_ref().clone()).get_tenant_by_domain(host).await?;
+    let Some(setting) = tenant.as_ref().and_then(|tenant| tenant.settings.get(CORS_POLICY_SETTING)) else {
+        return Ok(None);
+    };
+
+    Ok(match serde_json::from_value::<CorsConfig>(setting.clone()) {
+        Ok(policy) if policy.violations().is_empty() => Some(policy),
+        Ok(policy) => {
+            warn!("Ignoring invalid CORS policy for host {}: {}", host, policy.violations().join("; "));
+            None
+        }
+        Err(e) => {
+            warn!("Ignoring malformed CORS policy for host {}: {}", host, e);
+            None
+        }
+    })
+}
+
+/// 请求的主机名（小写，不含端口）
+pub(crate) fn request_host(headers: &HeaderMap) -> Option<String> {
+    let host = headers.get(header::HOST)?.to_str().ok()?;
+    let host = host.rsplit_once(':').map_or(host, |(host, port)| {
+        if port.chars().all(|c| c.is_ascii_digit()) { host } else { "" }
+    });
+    (!host.is_empty()).then(|| host.to_ascii_lowercase())
+}
+
+/// 预检请求的方法和请求头是否都在策略允许范围内
+fn preflight_allowed(policy: &CorsConfig, headers: &HeaderMap) -> bool {
+    let method_allowed = headers
+        .get(header::ACCESS_CONTROL_REQUEST_METHOD)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|method| policy.allows_method(method.trim()));
+    let headers_allowed = headers
+        .get(header::ACCESS_CONTROL_REQUEST_HEADERS)
+        .and_then(|v| v.to_str().ok())
+        .is_none_or(|requested| {
+            requested
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .all(|name| policy.allows_header(name))
+        });
+    method_allowed && headers_allowed
+}
+
+fn insert_origin_headers(headers: &mut HeaderMap, policy: &CorsConfig, origin: &str) {
+    // 仅在允许任意来源且不携带凭证时返回 `*`，其余情况回显具体来源
+    let allow_origin = if policy.allow_origins.iter().any(|o| o == "*") && !policy.allow_credentials {
+        HeaderValue::from_static("*")
+    } else {
+        match HeaderValue::from_str(origin) {
+            Ok(value) => value,
+            Err(_) => return,
+        }
+    };
+    headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, allow_origin);
+    if policy.allow_credentials {
+        headers.insert(header::ACCESS_CONTROL_ALLOW_CREDENTIALS, HeaderValue::from_static("true"));
+    }
+}
+
+fn insert_list(headers: &mut HeaderMap, name: header::HeaderName, values: &[String]) {
+    if values.is_empty() {
+        return;
+    }
+    if let Ok(value) = HeaderValue::from_str(&values.join(", ")) {
+        headers.insert(name, value);
+    }
+}
+
+fn append_vary(headers: &mut HeaderMap, value: &'static str) {
+    headers.append(header::VARY, HeaderValue::from_static(value));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preflight(method: &str, requested_headers: Option<&str>) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCESS_CONTROL_REQUEST_METHOD, HeaderValue::from_str(method).unwrap());
+        if let Some(requested) = requested_headers {
+            headers.insert(header::ACCESS_CONTROL_REQUEST_HEADERS, HeaderValue::from_str(requested).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn test_preflight_allowed() {
+        let policy = CorsConfig {
+            allow_headers: vec!["Content-Type".to_string(), "Authorization".to_string()],
+            ..CorsConfig::default()
+        };
+
+        assert!(preflight_allowed(&policy, &preflight("POST", None)));
+        assert!(preflight_allowed(&policy, &preflight("post", Some("content-type, AUTHORIZATION"))));
+        assert!(preflight_allowed(&policy, &preflight("GET", Some(" , "))));
+        assert!(!preflight_allowed(&policy, &preflight("POST", Some("Content-Type, X-Custom"))));
+        assert!(!preflight_allowed(&policy, &preflight("TRACE", None)));
+        assert!(!preflight_allowed(&policy, &HeaderMap::new()));
+
+        let any_header = CorsConfig {
+            allow_headers: vec!["*".to_string()],
+            ..CorsConfig::default()
+        };
+        assert!(preflight_allowed(&any_header, &preflight("PUT", Some("X-Custom"))));
+    }
+
+    #[test]
+    fn test_request_host() {
+        let host = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::HOST, HeaderValue::from_str(value).unwrap());
+            request_host(&headers)
+        };
+        assert_eq!(host("App.Example.com:8080").as_deref(), Some("app.example.com"));
+        assert_eq!(host("example.com").as_deref(), Some("example.com"));
+        assert_eq!(host(":8080"), None);
+        assert_eq!(request_host(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn test_origin_headers_never_combine_wildcard_with_credentials() {
+        let mut headers = HeaderMap::new();
+        insert_origin_headers(&mut headers, &CorsConfig::default(), "https://app.example.com");
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+        assert!(!headers.contains_key(header::ACCESS_CONTROL_ALLOW_CREDENTIALS));
+
+        let credentials = CorsConfig {
+            allow_origins: vec!["https://*.example.com".to_string()],
+            allow_credentials: true,
+            ..CorsConfig::default()
+        };
+        let mut headers = HeaderMap::new();
+        insert_origin_headers(&mut headers, &credentials, "https://app.example.com");
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], "https://app.example.com");
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_CREDENTIALS], "true");
+    }
+
+    #[test]
+    fn test_policy_cache() {
+        let cache = CorsPolicyCache::new(Duration::from_secs(60), 2);
+        assert!(cache.get("a.example.com").is_none());
+
+        cache.insert("a.example.com".to_string(), None);
+        cache.insert("b.example.com".to_string(), Some(CorsConfig::default()));
+        assert_eq!(cache.get("a.example.com"), Some(None));
+        assert!(cache.get("b.example.com").unwrap().is_some());
+
+        // Full of live entries: a new host starts a fresh cache
+        cache.insert("c.example.com".to_string(), None);
+        assert!(cache.get("a.example.com").is_none());
+        assert_eq!(cache.get("c.example.com"), Some(None));
+
+        cache.clear();
+        assert!(cache.get("c.example.com").is_none());
+
+        let expired = CorsPolicyCache::new(Duration::ZERO, 4);
+        expired.insert("a.example.com".to_string(), None);
+        assert!(expired.get("a.example.com").is_none());
     }
-} 
\ No newline at end of file
+}
@@ -0,0 +1,53 @@
+use crate::errors::LocalizableError;
+use crate::i18n::{localize_error, AcceptLanguage, Locale};
+use axum::{
+    body::{to_bytes, Body},
+    extract::Request,
+    http::{
+        header::{CONTENT_LANGUAGE, CONTENT_LENGTH, VARY},
+        HeaderValue,
+    },
+    middleware::Next,
+    response::Response,
+};
+use tracing::warn;
+
+/// 错误响应体的大小上限，超出时保持原样
+const MAX_ERROR_BODY: usize = 64 * 1024;
+
+/// 错误信息本地化中间件
+///
+/// 按 `Accept-Language` 选出错误目录支持的语言，重写 [`ApiError`](crate::errors::ApiError)
+/// 响应中的 `error.message`，并设置 `Content-Language`。英文请求与非错误响应不做改动。
+pub async fn localize_errors(request: Request, next: Next) -> Response {
+    let locale = AcceptLanguage::from_headers(request.headers()).locale();
+    let response = next.run(request).await;
+    if locale == Locale::En {
+        return response;
+    }
+    let Some(error) = response.extensions().get::<LocalizableError>().cloned() else {
+        return response;
+    };
+    let Some(message) = localize_error(error.code, error.detail.as_deref(), locale) else {
+        return response;
+    };
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, MAX_ERROR_BODY).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            warn!("Failed to read error response for localization: {}", e);
+            return Response::from_parts(parts, Body::empty());
+        }
+    };
+    let mut body: serde_json::Value = match serde_json::from_slice(&bytes) {
+        Ok(body) => body,
+        Err(_) => return Response::from_parts(parts, Body::from(bytes)),
+    };
+    body["error"]["message"] = serde_json::Value::String(message);
+
+    parts.headers.remove(CONTENT_LENGTH);
+    parts.headers.insert(CONTENT_LANGUAGE, HeaderValue::from_static(locale.tag()));
+    parts.headers.append(VARY, HeaderValue::from_static("accept-language"));
+    Response::from_parts(parts, Body::from(body.to_string()))
+}
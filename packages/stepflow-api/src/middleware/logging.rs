@@ -1,8 +1,50 @@
-// 日志中间件占位符
-pub struct LoggingMiddleware;
+use crate::types::{RequestTrace, REQUEST_ID_HEADER, TRACEPARENT_HEADER};
+use axum::{
+    extract::Request,
+    http::HeaderValue,
+    middleware::Next,
+    response::Response,
+};
+use tracing::{info, info_span, Instrument};
 
-impl LoggingMiddleware {
-    pub fn new() -> Self {
-        Self
+/// 请求追踪中间件
+///
+/// 沿用或生成 `X-Request-Id` 与 `traceparent`，将 [`RequestTrace`] 放入请求扩展供处理器使用
+/// （如 [`RequestTrace::execution_context`]），并在响应中回传这两个头。后续处理在带有
+/// `request_id` 和 `trace_id` 字段的 span 中运行，因此期间输出的日志都会带上这两个 ID。
+/// 应挂载在最外层，使认证失败等错误响应同样带有追踪头。
+pub async fn request_tracing(mut request: Request, next: Next) -> Response {
+    // 请求体不是 Sync，借用请求的闭包不能跨越 await 存活
+    let trace = {
+        let header = |name: &str| request.headers().get(name).and_then(|v| v.to_str().ok());
+        RequestTrace::from_headers(header(REQUEST_ID_HEADER), header(TRACEPARENT_HEADER))
+    };
+    request.extensions_mut().insert(trace.clone());
+
+    let span = info_span!(
+        "request",
+        request_id = %trace.request_id,
+        trace_id = %trace.trace_id,
+        span_id = %trace.span_id,
+        method = %request.method(),
+        path = %request.uri().path(),
+    );
+    let start = std::time::Instant::now();
+    let mut response = next.run(request).instrument(span.clone()).await;
+    span.in_scope(|| {
+        info!(
+            status = response.status().as_u16(),
+            duration_ms = start.elapsed().as_millis() as u64,
+            "Request completed"
+        );
+    });
+
+    let headers = response.headers_mut();
+    if let Ok(value) = HeaderValue::from_str(&trace.request_id) {
+        headers.insert(REQUEST_ID_HEADER, value);
+    }
+    if let Ok(value) = HeaderValue::from_str(&trace.traceparent()) {
+        headers.insert(TRACEPARENT_HEADER, value);
     }
-} 
\ No newline at end of file
+    response
+}
@@ -4,10 +4,16 @@ pub mod rate_limit;
 pub mod logging;
 pub mod validation;
 pub mod metrics;
+pub mod network_acl;
+pub mod operational_mode;
+pub mod localization;
 
 pub use auth::*;
 pub use cors::*;
 pub use rate_limit::*;
 pub use logging::*;
 pub use validation::*;
-pub use metrics::*; 
\ No newline at end of file
+pub use metrics::*;
+pub use network_acl::*;
+pub use operational_mode::*;
+pub use localization::*; 
\ No newline at end of file
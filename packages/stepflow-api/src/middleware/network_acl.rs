@@ -0,0 +1,96 @@
+use crate::errors::ApiError;
+use crate::middleware::cors::request_host;
+use crate::server::AppState;
+use crate::types::{UserContext, NETWORK_ACL_SETTING};
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use std::net::{IpAddr, SocketAddr};
+use stepflow_core::{AclDecision, AclRules, NetworkAcl, StepflowResult};
+use stepflow_database::{SqliteDatabase, TenantRepository};
+use tracing::warn;
+
+/// 经访问控制列表确认的客户端地址
+///
+/// 由 [`network_acl`] 写入请求扩展；经可信代理转发时为 `X-Forwarded-For` 中的客户端地址。
+///
+/// 会话和审计记录中的客户端地址都取自这里，不直接读取可被客户端伪造的转发头。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientAddr(pub IpAddr);
+
+impl std::fmt::Display for ClientAddr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// 网络访问控制中间件
+///
+/// 在认证之前按全局列表检查客户端地址；请求的 Host 是某个设置了列表的租户域名时，
+/// 同时检查该租户的列表。服务需以 `into_make_service_with_connect_info::<SocketAddr>()`
+/// 启动，拿不到对端地址时只要配置了任何列表就拒绝请求。
+pub async fn network_acl(State(state): State<AppState>, mut request: Request, next: Next) -> Result<Response, ApiError> {
+    let acl = &state.network_acl;
+    let Some(ConnectInfo(peer)) = request.extensions().get::<ConnectInfo<SocketAddr>>().copied() else {
+        if acl.is_empty() {
+            return Ok(next.run(request).await);
+        }
+        warn!("Rejecting request without peer address while a network ACL is configured");
+        return Err(ApiError::Forbidden("Client address is not allowed".to_string()));
+    };
+
+    let forwarded_for = request.headers().get("x-forwarded-for").and_then(|v| v.to_str().ok());
+    let ip = acl.client_ip(peer.ip(), forwarded_for);
+    let tenant_id = request_host(request.headers()).and_then(|host| acl.tenant_for_host(&host));
+    reject_denied(acl, ip, tenant_id.as_deref())?;
+
+    request.extensions_mut().insert(ClientAddr(ip));
+    Ok(next.run(request).await)
+}
+
+/// 租户网络访问控制中间件
+///
+/// 按令牌中的租户再次检查客户端地址，覆盖未通过租户域名访问的请求。
+/// 需要挂载在 JWT 认证中间件之后。
+pub async fn tenant_network_acl(State(state): State<AppState>, request: Request, next: Next) -> Result<Response, ApiError> {
+    let tenant_id = request.extensions().get::<UserContext>().and_then(|user| user.tenant_id.clone());
+    let client = request.extensions().get::<ClientAddr>().copied();
+    if let (Some(tenant_id), Some(ClientAddr(ip))) = (tenant_id, client) {
+        if state.network_acl.tenant_rules(&tenant_id).is_some() {
+            reject_denied(&state.network_acl, ip, Some(&tenant_id))?;
+        }
+    }
+    Ok(next.run(request).await)
+}
+
+fn reject_denied(acl: &NetworkAcl, ip: IpAddr, tenant_id: Option<&str>) -> Result<(), ApiError> {
+    match acl.check(ip, tenant_id) {
+        AclDecision::Allow => Ok(()),
+        AclDecision::Deny(scope) => {
+            warn!("Rejected request from {} by {:?} network ACL", ip, scope);
+            Err(ApiError::Forbidden("Client address is not allowed".to_string()))
+        }
+    }
+}
+
+/// 从租户设置加载各租户的访问控制列表，在服务启动时调用
+///
+/// 设置无法解析的租户会被跳过并记录警告，不影响其它租户。
+pub async fn load_tenant_network_acls(db: &SqliteDatabase, acl: &NetworkAcl) -> StepflowResult<usize> {
+    let mut loaded = 0;
+    for tenant in TenantRepository::new(db.clone()).list_tenants(None).await? {
+        let Some(setting) = tenant.settings.get(NETWORK_ACL_SETTING) else {
+            continue;
+        };
+        match serde_json::from_value::<AclRules>(setting.clone()) {
+            Ok(rules) => {
+                acl.set_tenant_rules(tenant.id.as_str(), tenant.domain.as_deref(), rules);
+                loaded += 1;
+            }
+            Err(e) => warn!("Ignoring malformed network ACL for tenant {}: {}", tenant.id.as_str(), e),
+        }
+    }
+    Ok(loaded)
+}
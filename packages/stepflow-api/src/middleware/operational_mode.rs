@@ -0,0 +1,128 @@
+use crate::errors::ApiError;
+use crate::server::AppState;
+use axum::{
+    extract::{Request, State},
+    http::Method,
+    middleware::Next,
+    response::Response,
+};
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+use stepflow_database::{OperationalMode, OperationalModeRecord, OperationalModeRepository};
+use tracing::warn;
+
+/// 只读模式下仍然放行的写请求：登录与刷新令牌、切换运行模式本身，以及不落库的校验与预估接口
+const READ_ONLY_EXEMPT_PATHS: &[&str] = &[
+    "/api/v1/auth/login",
+    "/api/v1/auth/refresh",
+    "/api/v1/admin/system/mode",
+    "/api/v1/executions/estimate",
+    "/api/v1/workflows/validate",
+    "/api/v1/workflows/dry-run",
+];
+
+/// 只读模式下拒绝的读请求：OIDC 回调会创建用户、关联身份并建立会话
+const READ_ONLY_WRITING_GETS: &[&str] = &["/api/v1/auth/oidc/callback"];
+
+/// 运行模式缓存
+///
+/// 运行模式保存在数据库中，管理命令行工具或其它实例修改后最多延迟 `ttl` 生效；
+/// 通过本实例的管理接口修改时立即生效。
+#[derive(Debug)]
+pub struct OperationalModeCache {
+    ttl: Duration,
+    current: RwLock<Option<(Instant, OperationalModeRecord)>>,
+}
+
+impl Default for OperationalModeCache {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(5))
+    }
+}
+
+impl OperationalModeCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self { ttl, current: RwLock::new(None) }
+    }
+
+    /// 未过期的缓存值
+    pub fn get(&self) -> Option<OperationalModeRecord> {
+        let current = self.current.read().unwrap_or_else(|e| e.into_inner());
+        current.as_ref()
+            .filter(|(cached_at, _)| cached_at.elapsed() < self.ttl)
+            .map(|(_, record)| record.clone())
+    }
+
+    /// 最近一次读取到的值，不论是否过期
+    fn last_known(&self) -> Option<OperationalModeRecord> {
+        let current = self.current.read().unwrap_or_else(|e| e.into_inner());
+        current.as_ref().map(|(_, record)| record.clone())
+    }
+
+    pub fn set(&self, record: OperationalModeRecord) {
+        *self.current.write().unwrap_or_else(|e| e.into_inner()) = Some((Instant::now(), record));
+    }
+}
+
+/// 当前运行模式
+///
+/// 读取数据库失败时沿用最近一次读取到的模式，从未读取成功则视为正常模式，不影响请求本身。
+pub async fn current_operational_mode(state: &AppState) -> OperationalModeRecord {
+    if let Some(record) = state.operational_mode.get() {
+        return record;
+    }
+    match OperationalModeRepository::new(state.db.as_ref().clone()).get().await {
+        Ok(record) => {
+            state.operational_mode.set(record.clone());
+            record
+        }
+        Err(e) => {
+            warn!("Failed to load operational mode: {}", e);
+            state.operational_mode.last_known().unwrap_or_default()
+        }
+    }
+}
+
+/// 只读模式中间件
+///
+/// 只读模式下拒绝除 [`READ_ONLY_EXEMPT_PATHS`] 以外的所有写请求；读请求不查询运行模式，
+/// 但 [`READ_ONLY_WRITING_GETS`] 中会落库的 GET 请求按写请求处理。
+pub async fn read_only_guard(State(state): State<AppState>, request: Request, next: Next) -> Result<Response, ApiError> {
+    let path = request.uri().path();
+    let safe = matches!(*request.method(), Method::GET | Method::HEAD | Method::OPTIONS)
+        && !READ_ONLY_WRITING_GETS.contains(&path);
+    if safe || READ_ONLY_EXEMPT_PATHS.contains(&path) {
+        return Ok(next.run(request).await);
+    }
+
+    let record = current_operational_mode(&state).await;
+    if !record.mode.accepts_writes() {
+        return Err(ApiError::ServiceUnavailable(record.message.unwrap_or_else(|| {
+            "Stepflow is in read-only mode; changes are temporarily disabled".to_string()
+        })));
+    }
+    Ok(next.run(request).await)
+}
+
+/// 当前运行模式不接受新执行时返回拒绝原因
+///
+/// HTTP 处理器通过 [`ensure_accepting_executions`] 使用；RPC 等其它入口据此拒绝发起执行的调用。
+pub async fn execution_pause_reason(state: &AppState) -> Option<String> {
+    let record = current_operational_mode(state).await;
+    if record.mode.accepts_executions() {
+        return None;
+    }
+    let default_message = match record.mode {
+        OperationalMode::ReadOnly => "Stepflow is in read-only mode; new executions are not accepted",
+        _ => "Stepflow is undergoing maintenance; new executions are paused, please try again later",
+    };
+    Some(record.message.unwrap_or_else(|| default_message.to_string()))
+}
+
+/// 维护模式与只读模式下拒绝发起新的执行，在发起执行的处理器开头调用
+pub(crate) async fn ensure_accepting_executions(state: &AppState) -> Result<(), ApiError> {
+    match execution_pause_reason(state).await {
+        Some(reason) => Err(ApiError::ServiceUnavailable(reason)),
+        None => Ok(()),
+    }
+}
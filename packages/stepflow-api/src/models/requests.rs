@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
-use stepflow_core::{ToolId, UserId};
+use stepflow_core::{ToolId, ToolVersion, UserId};
 use crate::types::{FilterParams, PaginationParams};
 use std::collections::HashMap;
 
@@ -50,6 +50,13 @@ pub struct ListToolsParams {
     pub status: Option<String>,
 }
 
+/// 按环境过滤工具列表的查询参数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ToolEnvironmentParams {
+    /// 仅返回该环境的工具以及未标记环境的工具
+    pub environment: Option<stepflow_core::EnvironmentLabel>,
+}
+
 /// 执行工具请求
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExecuteToolRequest {
@@ -160,6 +167,8 @@ pub struct LoginRequest {
     pub username: String,
     pub password: String,
     pub remember_me: Option<bool>,
+    /// 已启用双因素认证时必填：TOTP 验证码或恢复码
+    pub otp_code: Option<String>,
 }
 
 /// 用户注册请求
@@ -173,6 +182,126 @@ pub struct RegisterUserRequest {
     pub organization: Option<String>,
 }
 
+/// 创建用户邀请请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateInvitationRequest {
+    pub email: String,
+    /// 受邀用户角色（admin/user/guest），默认 user
+    pub role: Option<String>,
+}
+
+/// 接受邀请请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AcceptInvitationRequest {
+    pub token: String,
+    pub username: String,
+    pub password: String,
+}
+
+/// 邮箱验证请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifyEmailRequest {
+    pub token: String,
+}
+
+/// 创建 OIDC 提供商请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateOidcProviderRequest {
+    pub name: String,
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: String,
+    /// 默认 `openid email profile`
+    pub scopes: Option<Vec<String>>,
+    /// 承载用户组或角色的声明，例如 `groups`
+    pub role_claim: Option<String>,
+    /// 声明值到角色（admin/user/guest）的映射
+    #[serde(default)]
+    pub role_mapping: HashMap<String, String>,
+    /// 无映射匹配时的角色，默认 user
+    pub default_role: Option<String>,
+}
+
+/// 保存目录同步配置请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveDirectoryConfigRequest {
+    /// 例如 `ldaps://dc.example.com:636`
+    pub url: String,
+    pub bind_dn: String,
+    /// 为空时沿用已保存的密码
+    pub bind_password: Option<String>,
+    pub base_dn: String,
+    /// 默认 `(objectClass=person)`
+    pub user_filter: Option<String>,
+    /// 默认 `uid`，Active Directory 通常为 `sAMAccountName`
+    pub username_attribute: Option<String>,
+    /// 默认 `mail`
+    pub email_attribute: Option<String>,
+    /// 默认 `memberOf`
+    pub group_attribute: Option<String>,
+    /// 组 DN 或 CN 到角色（admin/user/guest）的映射
+    #[serde(default)]
+    pub group_role_mapping: HashMap<String, String>,
+    /// 无映射匹配时的角色，默认 user
+    pub default_role: Option<String>,
+    /// 与本地用户冲突时的处理方式（skip/adopt），默认 skip
+    pub conflict_policy: Option<String>,
+    #[serde(default)]
+    pub remove_missing: bool,
+    pub enabled: Option<bool>,
+}
+
+/// 手动触发目录同步请求
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DirectorySyncRequest {
+    /// 只预览变更，不写入
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// 运行报告格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    #[default]
+    Html,
+    Pdf,
+}
+
+/// 运行报告查询参数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReportParams {
+    #[serde(default)]
+    pub format: ReportFormat,
+}
+
+/// 目录同步历史查询参数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListDirectorySyncRunsParams {
+    pub limit: Option<usize>,
+}
+
+/// OIDC 回调参数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OidcCallbackParams {
+    pub state: String,
+    pub code: Option<String>,
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
+
+/// 双因素验证码请求（TOTP 验证码或恢复码）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TwoFactorCodeRequest {
+    pub code: String,
+}
+
+/// 租户双因素认证策略请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TwoFactorPolicyRequest {
+    pub require_for_admins: bool,
+}
+
 /// 刷新令牌请求
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RefreshTokenRequest {
@@ -411,4 +540,430 @@ pub enum CacheOperationType {
     Delete,
     Clear,
     Stats,
-} 
\ No newline at end of file
+}
+
+/// 发布工具到市场请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublishToolRequest {
+    pub tool_id: ToolId,
+    /// 为空表示全局可见，否则仅对指定租户可见
+    #[serde(default)]
+    pub target_tenants: Vec<String>,
+}
+
+/// 保存租户工具配置请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveToolConfigRequest {
+    #[serde(default)]
+    pub configuration: HashMap<String, serde_json::Value>,
+    #[serde(default)]
+    pub environment: HashMap<String, String>,
+    #[serde(default)]
+    pub secrets: HashMap<String, String>,
+    pub timeout: Option<u64>,
+    pub retries: Option<u32>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+/// 预览工具执行环境请求
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PreviewToolEnvironmentRequest {
+    /// 执行请求将携带的环境变量
+    #[serde(default)]
+    pub environment: HashMap<String, String>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// 工具变更流查询参数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ToolChangesParams {
+    /// 仅返回序列号大于该值的变更
+    pub since: Option<u64>,
+    pub limit: Option<usize>,
+}
+
+/// 创建告警规则请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateAlertRuleRequest {
+    pub name: String,
+    pub metric: stepflow_monitoring::AlertMetric,
+    pub comparison: stepflow_monitoring::Comparison,
+    pub threshold: f64,
+    /// 统计窗口（秒）
+    pub window_secs: u64,
+    pub tool_id: Option<String>,
+    pub min_samples: Option<u64>,
+    pub webhook_url: Option<String>,
+}
+
+/// 创建通知渠道请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateNotificationChannelRequest {
+    pub name: String,
+    /// 渠道类型及目标，例如 `{"type": "slack", "webhook_url": "..."}`
+    pub target: stepflow_monitoring::ChannelTarget,
+    /// 订阅的事件类型，支持 `alert.*` 形式的前缀，缺省时接收全部事件
+    #[serde(default)]
+    pub events: Vec<String>,
+    /// 标题模板，`{{rule.name}}` 等占位符取自事件数据
+    pub subject_template: Option<String>,
+    /// 正文模板
+    pub body_template: Option<String>,
+}
+
+/// 通知投递记录查询参数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ListNotificationDeliveriesParams {
+    pub channel_id: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// 告警列表查询参数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ListAlertsParams {
+    pub firing: Option<bool>,
+}
+
+/// 异常列表查询参数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ListAnomaliesParams {
+    pub tool_id: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// 工具载荷指标查询参数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PayloadMetricsParams {
+    pub tool_id: Option<String>,
+    /// 统计窗口（秒），默认 24 小时
+    pub window_secs: Option<u64>,
+}
+
+/// 管理概览查询参数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AdminOverviewParams {
+    /// 执行统计窗口（小时），默认 24 小时，最多 30 天
+    pub hours: Option<u32>,
+    /// 执行直方图的时间粒度，默认按小时
+    #[serde(default)]
+    pub bucket: stepflow_database::TimeBucket,
+    /// 失败最多的工具数量，默认 10
+    pub top: Option<usize>,
+}
+
+/// 慢查询列表查询参数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ListSlowQueriesParams {
+    pub limit: Option<usize>,
+}
+
+/// 慢执行列表查询参数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ListSlowExecutionsParams {
+    pub tool_id: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// 审批列表查询参数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ListApprovalsParams {
+    pub status: Option<stepflow_executor::ApprovalStatus>,
+}
+
+/// 审批决定请求
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ApprovalDecisionRequest {
+    pub comment: Option<String>,
+}
+
+/// 工作流试运行请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DryRunWorkflowRequest {
+    pub definition: stepflow_executor::WorkflowDefinition,
+    #[serde(flatten)]
+    pub options: stepflow_executor::DryRunOptions,
+}
+
+/// 发布工作流版本请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublishWorkflowVersionRequest {
+    pub definition: stepflow_executor::WorkflowDefinition,
+    /// 是否为破坏性变更；为 true 时按工作流的迁移策略处理进行中的运行
+    #[serde(default)]
+    pub breaking: bool,
+}
+
+/// 更新工作流迁移策略请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateMigrationPolicyRequest {
+    pub policy: stepflow_executor::MigrationPolicy,
+}
+
+/// 启动工具灰度发布请求
+///
+/// 候选版本以当前稳定版本为基础，覆盖请求中给出的字段。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StartToolRolloutRequest {
+    pub version: stepflow_core::ToolVersion,
+    pub description: Option<String>,
+    pub documentation: Option<String>,
+    pub configuration_schema: Option<serde_json::Value>,
+    /// 路由到候选版本的执行比例（0-100）
+    pub percentage: u8,
+    /// 自动晋升与回滚阈值，缺省时使用默认值
+    #[serde(default)]
+    pub thresholds: stepflow_executor::RolloutThresholds,
+}
+
+/// 调整灰度比例请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateToolRolloutRequest {
+    pub percentage: u8,
+}
+
+/// 手动晋升或回滚灰度发布请求
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RolloutDecisionRequest {
+    pub reason: Option<String>,
+}
+
+/// 运行工具示例测试请求
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TestToolRequest {
+    /// 要测试的版本，默认为稳定版本；也可以是进行中灰度发布的候选版本
+    pub version: Option<stepflow_core::ToolVersion>,
+}
+
+/// 工具文档输出格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolDocsFormat {
+    #[default]
+    Html,
+    Json,
+}
+
+/// 工具文档查询参数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ToolDocsParams {
+    /// 文档版本，缺省为工具当前版本
+    pub version: Option<String>,
+    #[serde(default)]
+    pub format: ToolDocsFormat,
+}
+
+/// 保存工具文档请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveToolDocsRequest {
+    /// 文档版本，缺省为工具当前版本
+    pub version: Option<String>,
+    /// Markdown 文档，可用 `example <名称>` 代码块嵌入工具示例
+    pub markdown: String,
+}
+
+/// 工具示例测试记录查询参数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ToolTestRunsParams {
+    pub limit: Option<usize>,
+}
+
+/// 注册表垃圾回收请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistryGcRequest {
+    /// 只统计将被回收的行，默认开启；需要显式传入 `false` 才会删除
+    #[serde(default = "default_enabled")]
+    pub dry_run: bool,
+    /// 覆盖策略中每个工具保留的版本数
+    pub keep_versions: Option<usize>,
+}
+
+/// 导出租户状态请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportTenantRequest {
+    /// 加密包内密钥的口令，导入时需要提供同一口令
+    pub passphrase: String,
+    /// 导出最近多少天的执行元数据，默认 30 天
+    pub execution_history_days: Option<u32>,
+}
+
+/// 运行时日志配置请求，未给出的部分保持不变
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateLoggingRequest {
+    /// 默认日志级别；给出时与 `modules` 一起替换当前级别设置
+    pub level: Option<String>,
+    /// 按模块覆盖的日志级别，例如 `stepflow_rpc = "debug"`
+    #[serde(default)]
+    pub modules: std::collections::BTreeMap<String, String>,
+    /// 采样规则，给出时替换当前规则，空列表表示不采样
+    pub sampling: Option<Vec<stepflow_monitoring::LogSamplingRule>>,
+}
+
+/// 切换系统运行模式请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetOperationalModeRequest {
+    pub mode: stepflow_database::OperationalMode,
+    /// 被拒绝的请求收到的提示，缺省时使用内置提示
+    pub message: Option<String>,
+}
+
+/// 执行列表筛选条件，可保存为视图
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExecutionViewFilter {
+    pub tool_id: Option<ToolId>,
+    pub status: Option<stepflow_core::ExecutionStatus>,
+    /// 执行必须带有的全部标签
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+}
+
+/// 保存执行视图请求，同名视图会被替换
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveExecutionViewRequest {
+    pub name: String,
+    pub filter: ExecutionViewFilter,
+}
+
+/// 设置执行标签请求，已有的同名标签会被覆盖
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LabelExecutionRequest {
+    pub labels: HashMap<String, String>,
+}
+
+/// 添加执行备注请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddExecutionNoteRequest {
+    pub body: String,
+}
+
+/// 启动工作流运行请求，请求体可省略
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StartWorkflowRunRequest {
+    /// 附加到运行中每个步骤执行的标签
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    /// 附加到运行中每个步骤执行的备注
+    pub note: Option<String>,
+}
+
+/// 执行预估请求，与实际执行的参数相同但不会运行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EstimateExecutionRequest {
+    pub tool_id: ToolId,
+    /// 省略时按工具当前版本预估
+    pub version: Option<ToolVersion>,
+    #[serde(default)]
+    pub parameters: HashMap<String, serde_json::Value>,
+    /// 预计内存上限（字节），无历史内存数据时用于计算成本
+    pub memory_limit: Option<u64>,
+    /// 预计占用的 CPU 核数，默认 1
+    pub cpu_limit: Option<f64>,
+}
+
+/// 同步执行请求，等待执行完成或超时后返回
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunSyncRequest {
+    pub tool_id: ToolId,
+    /// 省略时使用工具当前版本
+    pub version: Option<ToolVersion>,
+    #[serde(default)]
+    pub parameters: HashMap<String, serde_json::Value>,
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    pub note: Option<String>,
+    /// 等待完成的最长时间（毫秒），默认 30 秒，最多 5 分钟
+    pub timeout_ms: Option<u64>,
+    /// 并发组：同一租户下渲染出相同键的执行最多同时运行 `max_concurrency` 个，其余按提交顺序排队
+    #[serde(default)]
+    pub concurrency: Option<stepflow_executor::ConcurrencyGroup>,
+    /// 参数预设名称；预设参数作为默认值，`parameters` 中的同名参数优先
+    pub preset: Option<String>,
+}
+
+/// 打开交互式会话请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateToolSessionRequest {
+    pub tool_id: ToolId,
+    /// 插入在工具会话程序之后的额外参数，只能使用工具允许的参数
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// 会话沙箱的环境变量
+    #[serde(default)]
+    pub environment: HashMap<String, String>,
+}
+
+/// 会话输入请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolSessionInputRequest {
+    pub input: String,
+}
+
+/// 保存工具参数预设请求，同名（且同归属）的预设会被替换
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveToolPresetRequest {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub parameters: HashMap<String, serde_json::Value>,
+    /// 共享给整个租户（需要管理员角色），默认仅自己可见
+    #[serde(default)]
+    pub shared: bool,
+}
+
+/// 更新工具参数预设请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateToolPresetRequest {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub parameters: HashMap<String, serde_json::Value>,
+}
+
+/// 日历计划预览请求，返回接下来的触发时间以便核对配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreviewScheduleRequest {
+    pub schedule: stepflow_core::CalendarSchedule,
+    /// 返回的触发次数，默认 5，最多 100
+    pub count: Option<usize>,
+    /// 从该时间之后开始计算，默认当前时间
+    pub after: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// 设置功能开关请求，开关名称取自路径
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SetFeatureFlagRequest {
+    pub description: Option<String>,
+    /// 总开关，关闭后对所有人关闭（包括单独开启的租户与用户）
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 按租户灰度的比例，0 到 100
+    #[serde(default)]
+    pub rollout_percentage: u8,
+    /// 单独开启或关闭的租户
+    #[serde(default)]
+    pub tenants: std::collections::BTreeMap<String, bool>,
+    /// 单独开启或关闭的用户，优先于租户设置
+    #[serde(default)]
+    pub users: std::collections::BTreeMap<String, bool>,
+}
+
+/// 发布事件 Schema 请求，Schema 名称取自路径
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublishEventSchemaRequest {
+    /// 事件负载的 JSON Schema
+    pub schema: serde_json::Value,
+    /// 与上一版本的兼容性要求，缺省沿用上一版本的要求，新 Schema 为 backward
+    pub compatibility: Option<stepflow_registry::CompatibilityMode>,
+    pub description: Option<String>,
+}
+
+/// 事件 Schema 兼容性检查请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckEventSchemaRequest {
+    pub schema: serde_json::Value,
+    pub compatibility: Option<stepflow_registry::CompatibilityMode>,
+}
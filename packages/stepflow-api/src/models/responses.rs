@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
-use stepflow_core::{ToolId, ExecutionId, UserId};
+use stepflow_core::{ToolId, ToolInfo, ExecutionId, UserId};
 use std::collections::HashMap;
 use crate::types::PaginationInfo;
 
@@ -50,6 +50,44 @@ pub struct ToolResponse {
     pub metadata: HashMap<String, String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// 工具所属环境（dev/staging/prod），为空表示所有环境可用
+    pub environment_label: Option<stepflow_core::EnvironmentLabel>,
+    /// 执行该工具所需（capabilities）及偏好（preferred）的 worker 能力
+    pub worker_requirements: stepflow_core::ToolRequirements,
+}
+
+impl From<ToolInfo> for ToolResponse {
+    fn from(tool: ToolInfo) -> Self {
+        Self {
+            id: tool.id,
+            name: tool.name,
+            description: tool.description,
+            tool_type: tool.tool_type.to_string(),
+            version: tool.version.to_string(),
+            author: tool.author,
+            license: String::new(),
+            repository: tool.repository,
+            documentation: tool.documentation,
+            tags: tool.tags,
+            status: tool.status.to_string(),
+            configuration: tool.configuration_schema.unwrap_or(serde_json::Value::Null),
+            requirements: HashMap::new(),
+            metadata: HashMap::new(),
+            created_at: tool.created_at,
+            updated_at: tool.updated_at,
+            environment_label: tool.environment_label,
+            worker_requirements: tool.requirements,
+        }
+    }
+}
+
+impl ToolResponse {
+    /// 使用 `languages` 中第一个有翻译的语言的名称与描述，没有翻译时使用默认文本
+    pub fn localized(tool: ToolInfo, languages: &[String]) -> Self {
+        let (name, description) = tool.localized(languages);
+        let (name, description) = (name.to_string(), description.to_string());
+        Self { name, description, ..Self::from(tool) }
+    }
 }
 
 /// 注册工具响应
@@ -86,6 +124,21 @@ pub struct GetToolResponse {
     pub tool: ToolResponse,
 }
 
+/// 收藏工具响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FavoriteToolResponse {
+    pub tool_id: ToolId,
+    pub favorited: bool,
+    pub message: String,
+}
+
+/// 收藏工具列表响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListFavoritesResponse {
+    pub tools: Vec<ToolResponse>,
+    pub total_count: usize,
+}
+
 /// 执行响应
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExecutionResponse {
@@ -199,7 +252,7 @@ pub struct GetExecutionResponse {
 /// 列出执行响应
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ListExecutionsResponse {
-    pub executions: Vec<ExecutionResponse>,
+    pub executions: Vec<stepflow_executor::ExecutionInfo>,
     pub pagination: PaginationInfo,
 }
 
@@ -257,11 +310,32 @@ pub struct UserResponse {
     pub roles: Vec<String>,
     pub permissions: Vec<String>,
     pub is_active: bool,
+    pub email_verified: bool,
     pub last_login_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+impl From<stepflow_core::UserInfo> for UserResponse {
+    fn from(user: stepflow_core::UserInfo) -> Self {
+        Self {
+            id: user.id,
+            username: user.username,
+            email: user.email,
+            first_name: None,
+            last_name: None,
+            organization: None,
+            roles: vec![user.role.to_string()],
+            permissions: Vec::new(),
+            is_active: true,
+            email_verified: user.email_verified,
+            last_login_at: None,
+            created_at: user.created_at,
+            updated_at: user.updated_at,
+        }
+    }
+}
+
 /// 登录响应
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LoginResponse {
@@ -279,6 +353,100 @@ pub struct RegisterUserResponse {
     pub message: String,
 }
 
+/// 邀请列表响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListInvitationsResponse {
+    pub invitations: Vec<stepflow_database::InvitationRecord>,
+}
+
+/// OIDC 授权跳转响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OidcAuthorizeResponse {
+    pub authorization_url: String,
+    pub state: String,
+}
+
+/// 加密状态与租户数据密钥列表响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptionKeysResponse {
+    pub status: stepflow_database::EncryptionStatus,
+    pub keys: Vec<stepflow_database::TenantDataKeyInfo>,
+}
+
+/// OIDC 提供商列表响应（不包含客户端密钥）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListOidcProvidersResponse {
+    pub providers: Vec<stepflow_database::OidcProviderRecord>,
+}
+
+/// 已关联的 OIDC 身份列表响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListOidcIdentitiesResponse {
+    pub identities: Vec<stepflow_database::OidcIdentityRecord>,
+}
+
+/// 目录同步历史响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListDirectorySyncRunsResponse {
+    pub runs: Vec<stepflow_database::DirectorySyncRun>,
+}
+
+/// 会话响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionResponse {
+    #[serde(flatten)]
+    pub session: stepflow_database::SessionRecord,
+    /// 是否为发起本次请求的会话
+    pub current: bool,
+}
+
+/// 会话列表响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListSessionsResponse {
+    pub sessions: Vec<SessionResponse>,
+}
+
+impl ListSessionsResponse {
+    pub fn new(sessions: Vec<stepflow_database::SessionRecord>, current_session_id: &str) -> Self {
+        Self {
+            sessions: sessions
+                .into_iter()
+                .map(|session| SessionResponse {
+                    current: session.id == current_session_id,
+                    session,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// TOTP 密钥生成响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TotpSetupResponse {
+    /// Base32 密钥，供无法扫码时手动输入
+    pub secret: String,
+    pub otpauth_uri: String,
+    /// 需编码为二维码的内容
+    pub qr_payload: String,
+}
+
+/// 恢复码响应（恢复码只在生成时返回一次）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecoveryCodesResponse {
+    pub recovery_codes: Vec<String>,
+}
+
+/// 双因素认证状态响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TwoFactorStatusResponse {
+    pub enabled: bool,
+    /// 已生成密钥但尚未确认
+    pub pending: bool,
+    pub recovery_codes_remaining: usize,
+    /// 租户策略是否要求当前用户启用
+    pub required: bool,
+}
+
 /// 刷新令牌响应
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RefreshTokenResponse {
@@ -329,6 +497,12 @@ pub struct ServerStatusResponse {
 pub struct HealthResponse {
     pub status: String,
     pub message: String,
+    /// 系统运行模式（正常 / 维护 / 只读）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<stepflow_database::OperationalMode>,
+    /// 多实例部署时本实例的领导者状态
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub leadership: Option<stepflow_database::LeadershipStatus>,
     pub timestamp: DateTime<Utc>,
 }
 
@@ -337,6 +511,12 @@ pub struct HealthResponse {
 pub struct DetailedHealthResponse {
     pub status: String,
     pub services: std::collections::HashMap<String, ServiceHealth>,
+    /// 系统运行模式（正常 / 维护 / 只读）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<stepflow_database::OperationalMode>,
+    /// 多实例部署时本实例的领导者状态
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub leadership: Option<stepflow_database::LeadershipStatus>,
     pub timestamp: DateTime<Utc>,
 }
 
@@ -554,4 +734,359 @@ pub struct SuccessResponse {
 pub struct ErrorResponse {
     pub error: String,
     pub timestamp: DateTime<Utc>,
-} 
\ No newline at end of file
+}
+
+/// 市场目录响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketplaceCatalogResponse {
+    pub listings: Vec<stepflow_registry::ToolListing>,
+    pub total_count: usize,
+}
+
+/// 市场订阅列表响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketplaceSubscriptionsResponse {
+    pub subscriptions: Vec<stepflow_registry::ToolSubscription>,
+    pub total_count: usize,
+}
+
+/// 发布更新响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublishUpdateResponse {
+    pub listing_id: String,
+    pub notified_subscriptions: usize,
+}
+
+/// 租户工具配置响应（不返回密钥值）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolConfigResponse {
+    pub tool_id: ToolId,
+    pub configuration: HashMap<String, serde_json::Value>,
+    pub environment: HashMap<String, String>,
+    pub secret_keys: Vec<String>,
+    pub timeout: Option<u64>,
+    pub retries: Option<u32>,
+    pub enabled: bool,
+}
+
+impl From<stepflow_core::ToolConfig> for ToolConfigResponse {
+    fn from(config: stepflow_core::ToolConfig) -> Self {
+        let mut secret_keys: Vec<String> = config.secrets.into_keys().collect();
+        secret_keys.sort();
+
+        Self {
+            tool_id: config.tool_id,
+            configuration: config.configuration,
+            environment: config.environment,
+            secret_keys,
+            timeout: config.timeout,
+            retries: config.retries,
+            enabled: config.enabled,
+        }
+    }
+}
+
+/// 工具执行环境预览响应（密钥值以掩码代替）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolEnvironmentResponse {
+    pub tool_id: ToolId,
+    pub variables: Vec<stepflow_registry::EnvironmentVariable>,
+}
+
+/// 工具变更流响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolChangesResponse {
+    pub changes: Vec<stepflow_registry::ToolChange>,
+    /// 下一页请求应携带的 since 值
+    pub next_since: u64,
+    pub latest_sequence: u64,
+    pub has_more: bool,
+}
+
+impl From<stepflow_registry::ChangePage> for ToolChangesResponse {
+    fn from(page: stepflow_registry::ChangePage) -> Self {
+        Self {
+            changes: page.changes,
+            next_since: page.next_since,
+            latest_sequence: page.latest_sequence,
+            has_more: page.has_more,
+        }
+    }
+}
+
+/// 执行状态迁移时间线响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionTimelineResponse {
+    pub execution_id: ExecutionId,
+    pub current_state: Option<stepflow_executor::ExecutionState>,
+    pub events: Vec<stepflow_executor::ExecutionEvent>,
+    /// 从排队到开始运行的等待时间（毫秒）
+    pub queue_wait_ms: Option<i64>,
+    pub run_duration_ms: Option<i64>,
+    pub total_duration_ms: Option<i64>,
+}
+
+impl From<stepflow_executor::ExecutionTimeline> for ExecutionTimelineResponse {
+    fn from(timeline: stepflow_executor::ExecutionTimeline) -> Self {
+        Self {
+            current_state: timeline.current_state(),
+            execution_id: timeline.execution_id,
+            events: timeline.events,
+            queue_wait_ms: timeline.queue_wait_ms,
+            run_duration_ms: timeline.run_duration_ms,
+            total_duration_ms: timeline.total_duration_ms,
+        }
+    }
+}
+
+/// 已保存的执行视图列表响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListExecutionViewsResponse {
+    pub views: Vec<stepflow_database::SavedViewRecord>,
+}
+
+/// 工具参数预设列表响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListToolPresetsResponse {
+    pub presets: Vec<stepflow_database::ToolPresetRecord>,
+}
+
+/// 同步执行进度，以 `progress` 事件推送
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunSyncProgress {
+    pub execution_id: ExecutionId,
+    pub state: Option<stepflow_executor::ExecutionState>,
+    /// 在并发组中排队时的位置
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub queue_position: Option<stepflow_executor::QueuePosition>,
+    pub elapsed_ms: u64,
+}
+
+/// 同步执行响应
+///
+/// 超时前未结束时 `completed` 为 false，调用方可凭 `execution_id` 继续查询。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunSyncResponse {
+    pub execution_id: ExecutionId,
+    pub completed: bool,
+    /// 最近记录的执行状态
+    pub state: Option<stepflow_executor::ExecutionState>,
+    pub result: Option<stepflow_core::ExecutionResult>,
+    /// 执行失败时的错误信息
+    pub error: Option<String>,
+    pub elapsed_ms: u64,
+}
+
+/// 执行在并发组中的排队位置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuePositionResponse {
+    pub execution_id: ExecutionId,
+    /// 未在排队（已开始运行、已结束或未声明并发组）时为空
+    pub queue_position: Option<stepflow_executor::QueuePosition>,
+}
+
+/// 当前租户的并发组列表响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListConcurrencyGroupsResponse {
+    pub groups: Vec<stepflow_executor::ConcurrencyGroupStatus>,
+}
+
+/// 交互式会话列表响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListToolSessionsResponse {
+    pub sessions: Vec<crate::tool_sessions::ToolSessionInfo>,
+}
+
+/// 后台任务列表响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListJobsResponse {
+    pub jobs: Vec<stepflow_database::JobInfo>,
+}
+
+/// 日历计划预览响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreviewScheduleResponse {
+    pub timezone: String,
+    pub fire_times: Vec<ScheduleFireTime>,
+}
+
+/// 一次触发时间，同时给出 UTC 与计划时区的本地时间
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleFireTime {
+    pub at: chrono::DateTime<chrono::Utc>,
+    /// 计划时区的本地时间（RFC 3339，带偏移）
+    pub local: String,
+}
+
+/// 告警规则列表响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListAlertRulesResponse {
+    pub rules: Vec<stepflow_monitoring::AlertRule>,
+}
+
+/// 通知渠道列表响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListNotificationChannelsResponse {
+    pub channels: Vec<stepflow_monitoring::NotificationChannel>,
+}
+
+/// 通知投递记录响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListNotificationDeliveriesResponse {
+    pub deliveries: Vec<stepflow_monitoring::NotificationDelivery>,
+}
+
+/// 告警列表响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListAlertsResponse {
+    pub alerts: Vec<stepflow_monitoring::Alert>,
+}
+
+/// 执行异常列表响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListAnomaliesResponse {
+    pub anomalies: Vec<stepflow_monitoring::Anomaly>,
+}
+
+/// 工具载荷指标响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PayloadMetricsResponse {
+    pub window_secs: u64,
+    pub tools: Vec<stepflow_monitoring::ToolPayloadSummary>,
+}
+
+/// 管理概览响应：一次请求汇总系统的关键统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminOverviewResponse {
+    pub generated_at: DateTime<Utc>,
+    pub tools: ToolOverview,
+    pub executions: ExecutionOverview,
+    pub queue: stepflow_database::QueueDepth,
+    pub workers: WorkerOverview,
+    pub database: DatabaseOverview,
+    pub caches: Vec<CacheHitRate>,
+}
+
+/// 按类型与状态统计的工具数量
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolOverview {
+    pub total: u64,
+    pub by_type_and_status: Vec<stepflow_database::ToolCount>,
+}
+
+/// 统计窗口内的执行情况
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionOverview {
+    pub since: DateTime<Utc>,
+    pub bucket: stepflow_database::TimeBucket,
+    pub total: u64,
+    pub by_status: std::collections::BTreeMap<String, u64>,
+    pub buckets: Vec<stepflow_database::ExecutionBucket>,
+    pub top_failing_tools: Vec<stepflow_database::ToolFailureStats>,
+}
+
+/// 执行器健康状况与负载
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerOverview {
+    pub healthy: bool,
+    /// 执行器不提供负载信息时为空
+    pub load: Option<stepflow_executor::ExecutorLoad>,
+}
+
+/// 数据库大小与语句缓存
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseOverview {
+    pub size_bytes: u64,
+    pub statement_cache: stepflow_database::StatementCacheStats,
+}
+
+/// 缓存命中率
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheHitRate {
+    pub name: String,
+    pub hits: u64,
+    pub misses: u64,
+    pub hit_rate: f64,
+}
+
+impl CacheHitRate {
+    pub fn new(name: &str, hits: u64, misses: u64) -> Self {
+        let lookups = hits + misses;
+        let hit_rate = if lookups == 0 { 0.0 } else { hits as f64 / lookups as f64 };
+        Self { name: name.to_string(), hits, misses, hit_rate }
+    }
+}
+
+/// 慢查询列表响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListSlowQueriesResponse {
+    pub queries: Vec<stepflow_database::SlowQuery>,
+}
+
+/// 按语句汇总的慢查询响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlowQuerySummaryResponse {
+    pub statements: Vec<stepflow_database::SlowQuerySummary>,
+}
+
+/// 慢执行列表响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListSlowExecutionsResponse {
+    pub executions: Vec<stepflow_monitoring::SlowExecution>,
+}
+
+/// 审批列表响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListApprovalsResponse {
+    pub approvals: Vec<stepflow_executor::WorkflowApproval>,
+    pub total_count: usize,
+}
+
+/// 事件 Schema 列表响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListEventSchemasResponse {
+    pub schemas: Vec<stepflow_database::EventSchemaRecord>,
+    pub total_count: usize,
+}
+
+/// 审批决定响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalDecisionResponse {
+    pub approval: stepflow_executor::WorkflowApproval,
+    /// 工作流运行的当前状态（在后台恢复执行）
+    pub run: stepflow_executor::WorkflowRun,
+}
+
+/// 工作流校验响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowValidationResponse {
+    /// 是否没有错误级别的问题
+    pub valid: bool,
+    pub issues: Vec<stepflow_executor::ValidationIssue>,
+}
+
+/// 工作流版本列表响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListWorkflowVersionsResponse {
+    pub versions: Vec<stepflow_executor::WorkflowVersion>,
+    pub total_count: usize,
+}
+
+/// 工作流迁移策略响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationPolicyResponse {
+    pub workflow_id: String,
+    pub policy: stepflow_executor::MigrationPolicy,
+}
+
+/// 功能开关列表响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListFeatureFlagsResponse {
+    pub flags: Vec<stepflow_core::FlagDefinition>,
+}
+
+/// 当前用户的功能开关取值
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeatureFlagValuesResponse {
+    pub flags: std::collections::BTreeMap<String, bool>,
+}
@@ -0,0 +1,500 @@
+//! OpenID Connect 单点登录
+//!
+//! 每个租户可配置多个 OIDC 提供商，登录使用授权码流程（PKCE + nonce）。
+//! ID Token 通过提供商发布的 JWKS 校验签名，并要求 `iss`、`aud` 和 `nonce` 匹配。
+//! 签名算法由 JWKS 中的密钥确定，不信任 ID Token 头部声明的算法。
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use base64::Engine;
+use jsonwebtoken::{
+    decode, decode_header,
+    jwk::{AlgorithmParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm},
+    Algorithm, DecodingKey, Validation,
+};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use stepflow_core::UserRole;
+use stepflow_database::OidcProviderRecord;
+use crate::errors::{ApiError, ApiResult};
+
+/// 提供商发现文档（`/.well-known/openid-configuration`）中用到的字段
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+/// ID Token 中的声明
+#[derive(Debug, Clone, Deserialize)]
+pub struct OidcClaims {
+    pub sub: String,
+    pub email: Option<String>,
+    pub email_verified: Option<bool>,
+    pub name: Option<String>,
+    pub preferred_username: Option<String>,
+    pub nonce: Option<String>,
+    /// 其余声明，用于角色映射
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: Option<String>,
+}
+
+/// OIDC 客户端
+#[derive(Debug, Clone, Default)]
+pub struct OidcClient {
+    http: reqwest::Client,
+    /// 按签发方缓存的 JWKS
+    jwks: Arc<RwLock<HashMap<String, JwkSet>>>,
+}
+
+impl OidcClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 获取提供商发现文档
+    pub async fn discover(&self, issuer: &str) -> ApiResult<ProviderMetadata> {
+        let issuer = issuer.trim_end_matches('/');
+        let url = format!("{}/.well-known/openid-configuration", issuer);
+        let metadata: ProviderMetadata = self.get_json(&url).await?;
+        if metadata.issuer.trim_end_matches('/') != issuer {
+            return Err(ApiError::ServiceUnavailable(format!(
+                "OIDC issuer mismatch: expected {}, got {}",
+                issuer, metadata.issuer
+            )));
+        }
+        Ok(metadata)
+    }
+
+    /// 构造跳转到提供商的授权地址
+    pub fn authorization_url(
+        &self,
+        metadata: &ProviderMetadata,
+        provider: &OidcProviderRecord,
+        redirect_uri: &str,
+        state: &str,
+        nonce: &str,
+        code_verifier: &str,
+    ) -> ApiResult<String> {
+        let mut url = url::Url::parse(&metadata.authorization_endpoint)
+            .map_err(|e| ApiError::ServiceUnavailable(format!("Invalid authorization endpoint: {}", e)))?;
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &provider.client_id)
+            .append_pair("redirect_uri", redirect_uri)
+            .append_pair("scope", &provider.scopes.join(" "))
+            .append_pair("state", state)
+            .append_pair("nonce", nonce)
+            .append_pair("code_challenge", &pkce_challenge(code_verifier))
+            .append_pair("code_challenge_method", "S256");
+        Ok(url.into())
+    }
+
+    /// 使用授权码换取 ID Token
+    pub async fn exchange_code(
+        &self,
+        metadata: &ProviderMetadata,
+        provider: &OidcProviderRecord,
+        code: &str,
+        redirect_uri: &str,
+        code_verifier: &str,
+    ) -> ApiResult<String> {
+        let response = self.http
+            .post(&metadata.token_endpoint)
+            .form(&[
+                ("grant_type", "authorization_code"),
+                ("code", code),
+                ("redirect_uri", redirect_uri),
+                ("client_id", provider.client_id.as_str()),
+                ("client_secret", provider.client_secret.as_str()),
+                ("code_verifier", code_verifier),
+            ])
+            .send()
+            .await
+            .map_err(|e| ApiError::ServiceUnavailable(format!("OIDC token request failed: {}", e)))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(ApiError::Unauthorized(format!("OIDC token request rejected ({}): {}", status, body)));
+        }
+
+        let tokens: TokenResponse = response.json().await
+            .map_err(|e| ApiError::ServiceUnavailable(format!("Invalid OIDC token response: {}", e)))?;
+        tokens.id_token
+            .ok_or_else(|| ApiError::Unauthorized("OIDC provider did not return an ID token".to_string()))
+    }
+
+    /// 校验 ID Token 的签名、签发方、受众和 nonce
+    pub async fn verify_id_token(
+        &self,
+        metadata: &ProviderMetadata,
+        provider: &OidcProviderRecord,
+        id_token: &str,
+        nonce: &str,
+    ) -> ApiResult<OidcClaims> {
+        let header = decode_header(id_token)
+            .map_err(|e| ApiError::Unauthorized(format!("Invalid ID token: {}", e)))?;
+        let jwk = self.signing_key(metadata, header.kid.as_deref()).await?;
+        let algorithm = key_algorithm(&jwk)?;
+        let key = DecodingKey::from_jwk(&jwk)
+            .map_err(|e| ApiError::Unauthorized(format!("Unsupported ID token key: {}", e)))?;
+
+        // 头部声明的算法与密钥算法不一致时校验失败
+        let mut validation = Validation::new(algorithm);
+        validation.set_audience(&[&provider.client_id]);
+        validation.set_issuer(&[&metadata.issuer]);
+        let claims = decode::<OidcClaims>(id_token, &key, &validation)
+            .map_err(|e| ApiError::Unauthorized(format!("Invalid ID token: {}", e)))?
+            .claims;
+
+        if claims.nonce.as_deref() != Some(nonce) {
+            return Err(ApiError::Unauthorized("ID token nonce mismatch".to_string()));
+        }
+        Ok(claims)
+    }
+
+    /// 查找 ID Token 的签名密钥
+    ///
+    /// JWKS 按签发方缓存；缓存中找不到对应的 `kid` 时（例如提供商轮换了密钥）重新获取。
+    async fn signing_key(&self, metadata: &ProviderMetadata, kid: Option<&str>) -> ApiResult<Jwk> {
+        let cached = self.jwks.read().unwrap_or_else(|e| e.into_inner()).get(&metadata.issuer).cloned();
+        if let Some(jwk) = cached.and_then(|jwks| select_key(&jwks, kid)) {
+            return Ok(jwk);
+        }
+
+        let jwks: JwkSet = self.get_json(&metadata.jwks_uri).await?;
+        let jwk = select_key(&jwks, kid);
+        self.jwks.write().unwrap_or_else(|e| e.into_inner()).insert(metadata.issuer.clone(), jwks);
+        jwk.ok_or_else(|| ApiError::Unauthorized("No matching key for ID token".to_string()))
+    }
+
+    async fn get_json<T: serde::de::DeserializeOwned>(&self, url: &str) -> ApiResult<T> {
+        self.http
+            .get(url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| ApiError::ServiceUnavailable(format!("OIDC request to {} failed: {}", url, e)))?
+            .json()
+            .await
+            .map_err(|e| ApiError::ServiceUnavailable(format!("Invalid OIDC response from {}: {}", url, e)))
+    }
+}
+
+/// 按 `kid` 选择密钥；ID Token 未指定 `kid` 时仅在 JWKS 只有一个密钥时使用该密钥
+fn select_key(jwks: &JwkSet, kid: Option<&str>) -> Option<Jwk> {
+    match kid {
+        Some(kid) => jwks.find(kid),
+        None if jwks.keys.len() == 1 => jwks.keys.first(),
+        None => None,
+    }
+    .cloned()
+}
+
+/// 确定密钥的签名算法
+///
+/// 优先使用 JWK 声明的 `alg`；未声明时按密钥类型确定（RSA 密钥使用 OIDC 默认的 RS256）。
+/// 对称密钥必须显式声明算法。
+fn key_algorithm(jwk: &Jwk) -> ApiResult<Algorithm> {
+    let unsupported = || ApiError::Unauthorized("Unsupported ID token signing algorithm".to_string());
+    match jwk.common.key_algorithm {
+        Some(KeyAlgorithm::HS256) => Ok(Algorithm::HS256),
+        Some(KeyAlgorithm::HS384) => Ok(Algorithm::HS384),
+        Some(KeyAlgorithm::HS512) => Ok(Algorithm::HS512),
+        Some(KeyAlgorithm::ES256) => Ok(Algorithm::ES256),
+        Some(KeyAlgorithm::ES384) => Ok(Algorithm::ES384),
+        Some(KeyAlgorithm::RS256) => Ok(Algorithm::RS256),
+        Some(KeyAlgorithm::RS384) => Ok(Algorithm::RS384),
+        Some(KeyAlgorithm::RS512) => Ok(Algorithm::RS512),
+        Some(KeyAlgorithm::PS256) => Ok(Algorithm::PS256),
+        Some(KeyAlgorithm::PS384) => Ok(Algorithm::PS384),
+        Some(KeyAlgorithm::PS512) => Ok(Algorithm::PS512),
+        Some(KeyAlgorithm::EdDSA) => Ok(Algorithm::EdDSA),
+        Some(_) => Err(unsupported()),
+        None => match &jwk.algorithm {
+            AlgorithmParameters::RSA(_) => Ok(Algorithm::RS256),
+            AlgorithmParameters::EllipticCurve(params) => match params.curve {
+                EllipticCurve::P256 => Ok(Algorithm::ES256),
+                EllipticCurve::P384 => Ok(Algorithm::ES384),
+                _ => Err(unsupported()),
+            },
+            AlgorithmParameters::OctetKeyPair(_) => Ok(Algorithm::EdDSA),
+            AlgorithmParameters::OctetKey(_) => Err(unsupported()),
+        },
+    }
+}
+
+/// 计算 PKCE S256 code challenge
+pub fn pkce_challenge(code_verifier: &str) -> String {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
+
+/// 按提供商的角色映射从声明中确定用户角色
+///
+/// 角色声明可以是字符串或字符串数组，取第一个有映射的值；都没有映射时使用默认角色。
+pub fn map_role(provider: &OidcProviderRecord, claims: &OidcClaims) -> UserRole {
+    let values = provider.role_claim
+        .as_deref()
+        .and_then(|claim| claims.extra.get(claim))
+        .map(|value| match value {
+            serde_json::Value::String(value) => vec![value.as_str()],
+            serde_json::Value::Array(values) => values.iter().filter_map(|v| v.as_str()).collect(),
+            _ => Vec::new(),
+        })
+        .unwrap_or_default();
+
+    values.into_iter()
+        .find_map(|value| provider.role_mapping.get(value))
+        .cloned()
+        .unwrap_or_else(|| provider.default_role.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use stepflow_core::TenantId;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const KEY: &[u8] = b"an-oidc-test-signing-key-of-32-bytes";
+
+    fn provider(issuer: &str) -> OidcProviderRecord {
+        OidcProviderRecord {
+            id: "provider".to_string(),
+            tenant_id: TenantId::new(),
+            name: "Test IdP".to_string(),
+            issuer: issuer.to_string(),
+            client_id: "stepflow".to_string(),
+            client_secret: "secret".to_string(),
+            scopes: vec!["openid".to_string(), "email".to_string()],
+            role_claim: Some("groups".to_string()),
+            role_mapping: HashMap::from([("ops".to_string(), UserRole::Admin)]),
+            default_role: UserRole::User,
+            enabled: true,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn claims(groups: serde_json::Value) -> OidcClaims {
+        let mut claims = serde_json::json!({ "sub": "user-1" });
+        if !groups.is_null() {
+            claims["groups"] = groups;
+        }
+        serde_json::from_value(claims).unwrap()
+    }
+
+    async fn identity_provider() -> (MockServer, ProviderMetadata) {
+        let server = MockServer::start().await;
+        let metadata = serde_json::json!({
+            "issuer": server.uri(),
+            "authorization_endpoint": format!("{}/authorize", server.uri()),
+            "token_endpoint": format!("{}/token", server.uri()),
+            "jwks_uri": format!("{}/jwks", server.uri()),
+        });
+        Mock::given(method("GET"))
+            .and(path("/.well-known/openid-configuration"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(&metadata))
+            .mount(&server)
+            .await;
+        (server, serde_json::from_value(metadata).unwrap())
+    }
+
+    fn jwk(kid: &str, alg: Option<&str>) -> serde_json::Value {
+        let mut jwk = serde_json::json!({
+            "kty": "oct",
+            "kid": kid,
+            "k": base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(KEY),
+        });
+        if let Some(alg) = alg {
+            jwk["alg"] = serde_json::Value::String(alg.to_string());
+        }
+        jwk
+    }
+
+    /// 发布 JWKS，`times` 为可被获取的次数
+    async fn publish_jwks(server: &MockServer, keys: Vec<serde_json::Value>, times: u64) {
+        Mock::given(method("GET"))
+            .and(path("/jwks"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "keys": keys })))
+            .up_to_n_times(times)
+            .expect(times)
+            .mount(server)
+            .await;
+    }
+
+    fn id_token(issuer: &str, audience: &str, nonce: &str) -> String {
+        signed_token(issuer, audience, nonce, "key-1", Algorithm::HS256)
+    }
+
+    fn signed_token(issuer: &str, audience: &str, nonce: &str, kid: &str, algorithm: Algorithm) -> String {
+        let mut header = Header::new(algorithm);
+        header.kid = Some(kid.to_string());
+        let claims = serde_json::json!({
+            "iss": issuer,
+            "aud": audience,
+            "sub": "user-1",
+            "email": "user@example.com",
+            "nonce": nonce,
+            "groups": ["ops"],
+            "exp": Utc::now().timestamp() + 300,
+        });
+        encode(&header, &claims, &EncodingKey::from_secret(KEY)).unwrap()
+    }
+
+    #[test]
+    fn test_pkce_challenge() {
+        // RFC 7636 附录 B 的示例
+        assert_eq!(
+            pkce_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+
+    #[test]
+    fn test_map_role() {
+        let provider = provider("https://idp.example.com");
+        assert_eq!(map_role(&provider, &claims(serde_json::json!(["dev", "ops"]))), UserRole::Admin);
+        assert_eq!(map_role(&provider, &claims(serde_json::json!("ops"))), UserRole::Admin);
+        assert_eq!(map_role(&provider, &claims(serde_json::json!(["dev"]))), UserRole::User);
+        assert_eq!(map_role(&provider, &claims(serde_json::json!(1))), UserRole::User);
+        assert_eq!(map_role(&provider, &claims(serde_json::Value::Null)), UserRole::User);
+    }
+
+    #[tokio::test]
+    async fn test_discover_and_authorization_url() {
+        let (server, _) = identity_provider().await;
+        let client = OidcClient::new();
+        let metadata = client.discover(&format!("{}/", server.uri())).await.unwrap();
+        assert_eq!(metadata.token_endpoint, format!("{}/token", server.uri()));
+        assert!(client.discover("http://127.0.0.1:1").await.is_err());
+
+        let url = client
+            .authorization_url(&metadata, &provider(&server.uri()), "https://app/callback", "st", "nc", "verifier")
+            .unwrap();
+        let url = url::Url::parse(&url).unwrap();
+        let query: HashMap<_, _> = url.query_pairs().into_owned().collect();
+        assert_eq!(query["client_id"], "stepflow");
+        assert_eq!(query["scope"], "openid email");
+        assert_eq!(query["state"], "st");
+        assert_eq!(query["nonce"], "nc");
+        assert_eq!(query["code_challenge"], pkce_challenge("verifier"));
+    }
+
+    #[tokio::test]
+    async fn test_discover_rejects_issuer_mismatch() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/.well-known/openid-configuration"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "issuer": "https://other.example.com",
+                "authorization_endpoint": "https://other.example.com/authorize",
+                "token_endpoint": "https://other.example.com/token",
+                "jwks_uri": "https://other.example.com/jwks",
+            })))
+            .mount(&server)
+            .await;
+        assert!(OidcClient::new().discover(&server.uri()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_verify_id_token() {
+        let (server, metadata) = identity_provider().await;
+        publish_jwks(&server, vec![jwk("key-1", Some("HS256"))], 1).await;
+        let client = OidcClient::new();
+        let provider = provider(&server.uri());
+
+        let claims = client
+            .verify_id_token(&metadata, &provider, &id_token(&server.uri(), "stepflow", "n-1"), "n-1")
+            .await
+            .unwrap();
+        assert_eq!(claims.sub, "user-1");
+        assert_eq!(claims.email.as_deref(), Some("user@example.com"));
+        assert_eq!(map_role(&provider, &claims), UserRole::Admin);
+
+        for (token, nonce) in [
+            (id_token(&server.uri(), "stepflow", "n-1"), "n-2"),
+            (id_token(&server.uri(), "another-client", "n-1"), "n-1"),
+            (id_token("https://evil.example.com", "stepflow", "n-1"), "n-1"),
+            ("not-a-token".to_string(), "n-1"),
+        ] {
+            let result = client.verify_id_token(&metadata, &provider, &token, nonce).await;
+            assert!(matches!(result, Err(ApiError::Unauthorized(_))));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_signing_algorithm_is_pinned_by_key() {
+        let (server, metadata) = identity_provider().await;
+        publish_jwks(&server, vec![jwk("key-1", Some("HS256")), jwk("no-alg", None)], 1).await;
+        let client = OidcClient::new();
+        let provider = provider(&server.uri());
+
+        // 同一密钥但头部声明了其他算法
+        let token = signed_token(&server.uri(), "stepflow", "n-1", "key-1", Algorithm::HS512);
+        assert!(client.verify_id_token(&metadata, &provider, &token, "n-1").await.is_err());
+        // 对称密钥未声明算法时不接受
+        let token = signed_token(&server.uri(), "stepflow", "n-1", "no-alg", Algorithm::HS256);
+        assert!(client.verify_id_token(&metadata, &provider, &token, "n-1").await.is_err());
+
+        let rsa: Jwk = serde_json::from_value(serde_json::json!({
+            "kty": "RSA", "kid": "rsa", "n": "AQAB", "e": "AQAB",
+        })).unwrap();
+        assert_eq!(key_algorithm(&rsa).unwrap(), Algorithm::RS256);
+        let ec: Jwk = serde_json::from_value(serde_json::json!({
+            "kty": "EC", "kid": "ec", "crv": "P-384", "x": "AQAB", "y": "AQAB",
+        })).unwrap();
+        assert_eq!(key_algorithm(&ec).unwrap(), Algorithm::ES384);
+    }
+
+    #[tokio::test]
+    async fn test_jwks_cached_and_refetched_on_unknown_kid() {
+        let (server, metadata) = identity_provider().await;
+        let client = OidcClient::new();
+        let provider = provider(&server.uri());
+
+        publish_jwks(&server, vec![jwk("key-1", Some("HS256"))], 1).await;
+        for _ in 0..3 {
+            client.verify_id_token(&metadata, &provider, &id_token(&server.uri(), "stepflow", "n"), "n").await.unwrap();
+        }
+
+        // 提供商轮换密钥后，未知的 kid 触发重新获取
+        publish_jwks(&server, vec![jwk("key-1", Some("HS256")), jwk("key-2", Some("HS256"))], 1).await;
+        let rotated = signed_token(&server.uri(), "stepflow", "n", "key-2", Algorithm::HS256);
+        client.verify_id_token(&metadata, &provider, &rotated, "n").await.unwrap();
+        client.verify_id_token(&metadata, &provider, &rotated, "n").await.unwrap();
+        server.verify().await;
+    }
+
+    #[tokio::test]
+    async fn test_exchange_code() {
+        let (server, metadata) = identity_provider().await;
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .and(wiremock::matchers::body_string_contains("code=good"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "id_token": "token" })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .respond_with(ResponseTemplate::new(400).set_body_string("invalid_grant"))
+            .mount(&server)
+            .await;
+
+        let client = OidcClient::new();
+        let provider = provider(&server.uri());
+        let token = client.exchange_code(&metadata, &provider, "good", "https://app/cb", "verifier").await.unwrap();
+        assert_eq!(token, "token");
+        let rejected = client.exchange_code(&metadata, &provider, "bad", "https://app/cb", "verifier").await;
+        assert!(matches!(rejected, Err(ApiError::Unauthorized(message)) if message.contains("invalid_grant")));
+    }
+}
@@ -1,2 +1,95 @@
-// 管理路由占位符
-pub struct AdminRouter; 
\ No newline at end of file
+use axum::{
+    extract::DefaultBodyLimit,
+    routing::{delete, get, post, put},
+    Router,
+};
+use crate::handlers::admin::{
+    collect_registry_garbage, collect_storage_garbage, create_alert_rule, get_admin_overview, create_notification_channel, delete_feature_flag,
+    delete_notification_channel, list_notification_channels, list_notification_deliveries, test_notification_channel, list_feature_flags, set_feature_flag, create_invitation, create_oidc_provider, delete_alert_rule,
+    delete_cors_policy, delete_directory_config, delete_network_acl, delete_oidc_provider, export_tenant,
+    get_cors_policy, get_directory_config, get_encryption_keys, get_logging, get_network_acl, get_operational_mode,
+    get_payload_metrics, get_storage_stats, import_tenant, list_alert_rules, list_alerts, list_anomalies, list_directory_sync_runs,
+    list_invitations, list_jobs, list_oidc_providers, list_user_sessions, preview_job_schedule, revoke_invitation, revoke_user_session,
+    list_slow_executions, list_slow_queries, summarize_slow_queries,
+    revoke_user_sessions, rewrap_data_keys, rotate_tenant_key, run_job, save_directory_config, set_cors_policy,
+    set_network_acl, set_operational_mode, set_two_factor_policy, sync_directory, update_logging, user_data_action,
+};
+use crate::server::AppState;
+
+/// 租户包导入请求体上限
+const TENANT_BUNDLE_BODY_LIMIT: usize = 256 * 1024 * 1024;
+
+// 管理路由
+#[derive(Default)]
+pub struct AdminRouter;
+
+impl AdminRouter {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// 构建管理路由（需要在 JWT 认证中间件之后挂载，处理器内校验管理员角色）
+    pub fn router(&self) -> Router<AppState> {
+        Router::new()
+            .route("/api/v1/admin/alerts", get(list_alerts))
+            .route("/api/v1/admin/alerts/rules", get(list_alert_rules).post(create_alert_rule))
+            .route("/api/v1/admin/alerts/rules/:rule_id", delete(delete_alert_rule))
+            .route(
+                "/api/v1/admin/notifications/channels",
+                get(list_notification_channels).post(create_notification_channel),
+            )
+            .route("/api/v1/admin/notifications/channels/:channel_id", delete(delete_notification_channel))
+            .route("/api/v1/admin/notifications/channels/:channel_id/test", post(test_notification_channel))
+            .route("/api/v1/admin/notifications/deliveries", get(list_notification_deliveries))
+            .route("/api/v1/admin/anomalies", get(list_anomalies))
+            .route("/api/v1/admin/payload-metrics", get(get_payload_metrics))
+            .route("/api/v1/admin/slow-queries", get(list_slow_queries))
+            .route("/api/v1/admin/slow-queries/summary", get(summarize_slow_queries))
+            .route("/api/v1/admin/slow-executions", get(list_slow_executions))
+            .route("/api/v1/admin/logging", get(get_logging).put(update_logging))
+            .route("/api/v1/admin/system/mode", get(get_operational_mode).put(set_operational_mode))
+            .route("/api/v1/admin/flags", get(list_feature_flags))
+            .route("/api/v1/admin/flags/:name", put(set_feature_flag).delete(delete_feature_flag))
+            .route("/api/v1/admin/jobs", get(list_jobs))
+            .route("/api/v1/admin/jobs/schedule-preview", post(preview_job_schedule))
+            .route("/api/v1/admin/jobs/:name/run", post(run_job))
+            .route("/api/v1/admin/storage/stats", get(get_storage_stats))
+            .route("/api/v1/admin/storage/gc", post(collect_storage_garbage))
+            .route("/api/v1/admin/overview", get(get_admin_overview))
+            .route("/api/v1/admin/registry/gc", post(collect_registry_garbage))
+            .route("/api/v1/admin/tenants/:tenant_id/export", post(export_tenant))
+            .route(
+                "/api/v1/admin/tenants/import",
+                post(import_tenant).layer(DefaultBodyLimit::max(TENANT_BUNDLE_BODY_LIMIT)),
+            )
+            .route("/api/v1/admin/invitations", get(list_invitations).post(create_invitation))
+            .route("/api/v1/admin/invitations/:invitation_id", delete(revoke_invitation))
+            .route("/api/v1/admin/security/two-factor", put(set_two_factor_policy))
+            .route("/api/v1/admin/security/encryption", get(get_encryption_keys))
+            .route("/api/v1/admin/security/encryption/rotate", post(rotate_tenant_key))
+            .route("/api/v1/admin/security/encryption/rewrap", post(rewrap_data_keys))
+            .route(
+                "/api/v1/admin/security/cors",
+                get(get_cors_policy).put(set_cors_policy).delete(delete_cors_policy),
+            )
+            .route(
+                "/api/v1/admin/security/network",
+                get(get_network_acl).put(set_network_acl).delete(delete_network_acl),
+            )
+            .route("/api/v1/admin/oidc/providers", get(list_oidc_providers).post(create_oidc_provider))
+            .route("/api/v1/admin/oidc/providers/:provider_id", delete(delete_oidc_provider))
+            .route(
+                "/api/v1/admin/directory",
+                get(get_directory_config).put(save_directory_config).delete(delete_directory_config),
+            )
+            .route("/api/v1/admin/directory/sync", post(sync_directory))
+            .route("/api/v1/admin/directory/sync/runs", get(list_directory_sync_runs))
+            // `{id}:export` / `{id}:erase`，操作名由处理器解析
+            .route("/api/v1/admin/users/:user_id", post(user_data_action))
+            .route(
+                "/api/v1/admin/users/:user_id/sessions",
+                get(list_user_sessions).delete(revoke_user_sessions),
+            )
+            .route("/api/v1/admin/users/:user_id/sessions/:session_id", delete(revoke_user_session))
+    }
+}
@@ -0,0 +1,22 @@
+use axum::{routing::{get, post}, Router};
+use crate::handlers::approvals::{approve_approval, get_approval, list_approvals, reject_approval};
+use crate::server::AppState;
+
+// 审批路由
+#[derive(Default)]
+pub struct ApprovalsRouter;
+
+impl ApprovalsRouter {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// 构建审批路由（需要在 JWT 认证中间件之后挂载）
+    pub fn router(&self) -> Router<AppState> {
+        Router::new()
+            .route("/api/v1/approvals", get(list_approvals))
+            .route("/api/v1/approvals/:approval_id", get(get_approval))
+            .route("/api/v1/approvals/:approval_id/approve", post(approve_approval))
+            .route("/api/v1/approvals/:approval_id/reject", post(reject_approval))
+    }
+}
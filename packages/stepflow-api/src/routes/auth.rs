@@ -1,2 +1,27 @@
-// 认证路由占位符
-pub struct AuthRouter; 
\ No newline at end of file
+use axum::{
+    routing::{get, post},
+    Router,
+};
+use crate::handlers::auth::{accept_invitation, login, oidc_authorize, oidc_callback, refresh_token, verify_email};
+use crate::server::AppState;
+
+// 认证路由
+#[derive(Default)]
+pub struct AuthRouter;
+
+impl AuthRouter {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// 构建认证路由（无需登录，可挂载在 JWT 认证中间件之前）
+    pub fn router(&self) -> Router<AppState> {
+        Router::new()
+            .route("/api/v1/auth/login", post(login))
+            .route("/api/v1/auth/refresh", post(refresh_token))
+            .route("/api/v1/auth/accept-invite", post(accept_invitation))
+            .route("/api/v1/auth/verify-email", post(verify_email))
+            .route("/api/v1/auth/oidc/callback", get(oidc_callback))
+            .route("/api/v1/auth/oidc/:provider_id/authorize", get(oidc_authorize))
+    }
+}
@@ -0,0 +1,26 @@
+use axum::{routing::{get, post}, Router};
+use crate::handlers::events::{
+    check_event_schema, get_event_schema, get_latest_event_schema, list_event_schema_versions, list_event_schemas,
+    publish_event_schema,
+};
+use crate::server::AppState;
+
+// 事件 Schema 路由
+#[derive(Default)]
+pub struct EventsRouter;
+
+impl EventsRouter {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// 构建事件 Schema 路由（需要在 JWT 认证中间件之后挂载，发布时在处理器内校验管理员角色）
+    pub fn router(&self) -> Router<AppState> {
+        Router::new()
+            .route("/api/v1/events/schemas", get(list_event_schemas))
+            .route("/api/v1/events/schemas/:name", get(list_event_schema_versions).post(publish_event_schema))
+            .route("/api/v1/events/schemas/:name/latest", get(get_latest_event_schema))
+            .route("/api/v1/events/schemas/:name/versions/:version", get(get_event_schema))
+            .route("/api/v1/events/schemas/:name/compatibility", post(check_event_schema))
+    }
+}
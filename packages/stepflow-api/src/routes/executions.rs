@@ -1,2 +1,38 @@
-// 执行路由占位符
-pub struct ExecutionsRouter; 
\ No newline at end of file
+use axum::{
+    routing::{delete, get, post, put},
+    Router,
+};
+use crate::handlers::executions::{
+    add_execution_note, delete_execution_view, estimate_execution, get_execution_annotations, get_execution_queue_position,
+    get_execution_report, get_execution_timeline, label_execution, list_concurrency_groups,
+    list_execution_views, list_executions, remove_execution_label, run_execution_sync, save_execution_view,
+};
+use crate::server::AppState;
+
+// 执行路由
+#[derive(Default)]
+pub struct ExecutionsRouter;
+
+impl ExecutionsRouter {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// 构建执行路由（需要在 JWT 认证中间件之后挂载）
+    pub fn router(&self) -> Router<AppState> {
+        Router::new()
+            .route("/api/v1/executions", get(list_executions))
+            .route("/api/v1/executions/estimate", post(estimate_execution))
+            .route("/api/v1/executions/run-sync", post(run_execution_sync))
+            .route("/api/v1/executions/concurrency-groups", get(list_concurrency_groups))
+            .route("/api/v1/executions/:execution_id/queue-position", get(get_execution_queue_position))
+            .route("/api/v1/executions/:execution_id/timeline", get(get_execution_timeline))
+            .route("/api/v1/executions/:execution_id/annotations", get(get_execution_annotations))
+            .route("/api/v1/executions/:execution_id/report", get(get_execution_report))
+            .route("/api/v1/executions/:execution_id/labels", put(label_execution))
+            .route("/api/v1/executions/:execution_id/labels/:key", delete(remove_execution_label))
+            .route("/api/v1/executions/:execution_id/notes", post(add_execution_note))
+            .route("/api/v1/execution-views", get(list_execution_views).post(save_execution_view))
+            .route("/api/v1/execution-views/:view_id", delete(delete_execution_view))
+    }
+}
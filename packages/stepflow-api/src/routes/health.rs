@@ -10,6 +10,8 @@ pub async fn health_check(
     let response = HealthResponse {
         status: "healthy".to_string(),
         message: "API is running".to_string(),
+        mode: None,
+        leadership: None,
         timestamp: chrono::Utc::now(),
     };
     Ok(Json(response))
@@ -38,11 +40,18 @@ pub async fn detailed_health_check(
     };
     services.insert("database".to_string(), db_health);
     
-    // 检查注册表
+    // 检查注册表（附带工具缓存统计）
+    let registry_message = match state.registry.cache_stats().await {
+        Some(stats) => format!(
+            "Registry is healthy (cache: {} hits, {} misses, {}/{} entries)",
+            stats.hits, stats.misses, stats.size, stats.max_size
+        ),
+        None => "Registry is healthy".to_string(),
+    };
     let registry_health = match state.registry.health_check().await {
         Ok(_) => ServiceHealth {
             status: "healthy".to_string(),
-            message: "Registry is healthy".to_string(),
+            message: registry_message,
             duration: 0,
             timestamp: chrono::Utc::now(),
         },
@@ -98,6 +107,8 @@ pub async fn detailed_health_check(
     let response = DetailedHealthResponse {
         status: overall_status,
         services,
+        mode: None,
+        leadership: None,
         timestamp: chrono::Utc::now(),
     };
     Ok(Json(response))
@@ -110,6 +121,8 @@ pub async fn readiness_check(
     let response = HealthResponse {
         status: "ready".to_string(),
         message: "Service is ready".to_string(),
+        mode: None,
+        leadership: None,
         timestamp: chrono::Utc::now(),
     };
     Ok(Json(response))
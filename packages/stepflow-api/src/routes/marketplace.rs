@@ -0,0 +1,35 @@
+use axum::{
+    routing::{delete, get, post},
+    Router,
+};
+use crate::handlers::marketplace::{
+    approve_marketplace_update, list_marketplace_catalog, list_marketplace_subscriptions,
+    publish_marketplace_tool, publish_marketplace_update, reject_marketplace_update,
+    subscribe_marketplace_listing, unsubscribe_marketplace_subscription,
+    withdraw_marketplace_listing,
+};
+use crate::server::AppState;
+
+// 工具市场路由
+#[derive(Default)]
+pub struct MarketplaceRouter;
+
+impl MarketplaceRouter {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// 构建工具市场路由（需要在 JWT 认证中间件之后挂载）
+    pub fn router(&self) -> Router<AppState> {
+        Router::new()
+            .route("/api/v1/marketplace/catalog", get(list_marketplace_catalog))
+            .route("/api/v1/marketplace/listings", post(publish_marketplace_tool))
+            .route("/api/v1/marketplace/listings/:listing_id", delete(withdraw_marketplace_listing))
+            .route("/api/v1/marketplace/listings/:listing_id/updates", post(publish_marketplace_update))
+            .route("/api/v1/marketplace/listings/:listing_id/subscribe", post(subscribe_marketplace_listing))
+            .route("/api/v1/marketplace/subscriptions", get(list_marketplace_subscriptions))
+            .route("/api/v1/marketplace/subscriptions/:subscription_id", delete(unsubscribe_marketplace_subscription))
+            .route("/api/v1/marketplace/subscriptions/:subscription_id/approve", post(approve_marketplace_update))
+            .route("/api/v1/marketplace/subscriptions/:subscription_id/reject", post(reject_marketplace_update))
+    }
+}
@@ -4,10 +4,22 @@ pub mod registry;
 pub mod admin;
 pub mod auth;
 pub mod health;
+pub mod users;
+pub mod marketplace;
+pub mod approvals;
+pub mod workflows;
+pub mod tool_sessions;
+pub mod events;
 
 pub use tools::*;
 pub use executions::*;
 pub use registry::*;
 pub use admin::*;
 pub use auth::*;
-pub use health::*; 
\ No newline at end of file
+pub use health::*;
+pub use users::*;
+pub use marketplace::*;
+pub use approvals::*;
+pub use workflows::*;
+pub use tool_sessions::*;
+pub use events::*; 
\ No newline at end of file
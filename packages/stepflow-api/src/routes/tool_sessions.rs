@@ -0,0 +1,22 @@
+use axum::{routing::{get, post}, Router};
+use crate::handlers::tool_sessions::{close_tool_session, create_tool_session, get_tool_session, list_tool_sessions, send_tool_session_input, stream_tool_session_events};
+use crate::server::AppState;
+
+// 交互式会话路由
+#[derive(Default)]
+pub struct ToolSessionsRouter;
+
+impl ToolSessionsRouter {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// 构建会话路由（需要在 JWT 认证中间件之后挂载）
+    pub fn router(&self) -> Router<AppState> {
+        Router::new()
+            .route("/api/v1/tool-sessions", get(list_tool_sessions).post(create_tool_session))
+            .route("/api/v1/tool-sessions/:session_id", get(get_tool_session).delete(close_tool_session))
+            .route("/api/v1/tool-sessions/:session_id/input", post(send_tool_session_input))
+            .route("/api/v1/tool-sessions/:session_id/events", get(stream_tool_session_events))
+    }
+}
@@ -1,8 +1,46 @@
-// 工具路由占位符
+use axum::{routing::{get, post}, Router};
+use crate::handlers::tools::{
+    api_spec_document, delete_tool_config, delete_tool_preset, get_tool_config, get_tool_docs, get_tool_form,
+    get_tool_preset, get_tool_rollout, list_tool_changes, list_tool_presets, list_tool_tests, list_tools,
+    openapi_extension_descriptor, preview_tool_environment, promote_tool_rollout, rollback_tool_rollout, save_tool_config, save_tool_docs,
+    save_tool_preset, start_tool_rollout, test_tool, update_tool_preset, update_tool_rollout,
+};
+use crate::server::AppState;
+
+// 工具路由
 pub struct ToolsRouter;
 
 impl ToolsRouter {
     pub fn new() -> Self {
         Self
     }
-} 
\ No newline at end of file
+
+    /// 构建工具路由
+    pub fn router(&self) -> Router<AppState> {
+        Router::new()
+            .route("/api/v1/tools", get(list_tools))
+            .route("/api/v1/tools/changes", get(list_tool_changes))
+            .route("/api/v1/openapi/extensions", get(openapi_extension_descriptor))
+            .route("/api/v1/openapi.json", get(api_spec_document))
+            .route(
+                "/api/v1/tools/:tool_id/config",
+                get(get_tool_config).put(save_tool_config).delete(delete_tool_config),
+            )
+            .route("/api/v1/tools/:tool_id/config/environment", post(preview_tool_environment))
+            .route(
+                "/api/v1/tools/:tool_id/rollout",
+                get(get_tool_rollout).post(start_tool_rollout).put(update_tool_rollout),
+            )
+            .route("/api/v1/tools/:tool_id/rollout/promote", post(promote_tool_rollout))
+            .route("/api/v1/tools/:tool_id/rollout/rollback", post(rollback_tool_rollout))
+            .route("/api/v1/tools/:tool_id/form", get(get_tool_form))
+            .route("/api/v1/tools/:tool_id/docs", get(get_tool_docs).put(save_tool_docs))
+            .route("/api/v1/tools/:tool_id/test", post(test_tool))
+            .route("/api/v1/tools/:tool_id/tests", get(list_tool_tests))
+            .route("/api/v1/tools/:tool_id/presets", get(list_tool_presets).post(save_tool_preset))
+            .route(
+                "/api/v1/tools/:tool_id/presets/:preset_id",
+                get(get_tool_preset).put(update_tool_preset).delete(delete_tool_preset),
+            )
+    }
+}
@@ -0,0 +1,43 @@
+use axum::{
+    routing::{delete, get, post, put},
+    Router,
+};
+use crate::handlers::users::{
+    add_favorite_tool, change_password, disable_two_factor, enable_two_factor, feature_flag_values, link_oidc_identity,
+    list_favorite_tools, list_oidc_identities, list_sessions, regenerate_recovery_codes, remove_favorite_tool,
+    request_email_verification, revoke_session, setup_two_factor, two_factor_status, unlink_oidc_identity,
+};
+use crate::server::AppState;
+
+// 用户路由
+#[derive(Default)]
+pub struct UsersRouter;
+
+impl UsersRouter {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// 构建用户路由（需要在 JWT 认证中间件之后挂载）
+    pub fn router(&self) -> Router<AppState> {
+        Router::new()
+            .route("/api/v1/users/me/favorites", get(list_favorite_tools))
+            .route("/api/v1/users/me/flags", get(feature_flag_values))
+            .route(
+                "/api/v1/users/me/favorites/:tool_id",
+                put(add_favorite_tool).delete(remove_favorite_tool),
+            )
+            .route("/api/v1/users/me/email-verification", post(request_email_verification))
+            .route("/api/v1/users/me/password", put(change_password))
+            .route("/api/v1/users/me/2fa", get(two_factor_status))
+            .route("/api/v1/users/me/2fa/setup", post(setup_two_factor))
+            .route("/api/v1/users/me/2fa/enable", post(enable_two_factor))
+            .route("/api/v1/users/me/2fa/disable", post(disable_two_factor))
+            .route("/api/v1/users/me/2fa/recovery-codes", post(regenerate_recovery_codes))
+            .route("/api/v1/users/me/sessions", get(list_sessions))
+            .route("/api/v1/users/me/sessions/:session_id", delete(revoke_session))
+            .route("/api/v1/users/me/oidc/identities", get(list_oidc_identities))
+            .route("/api/v1/users/me/oidc/identities/:provider_id", delete(unlink_oidc_identity))
+            .route("/api/v1/users/me/oidc/:provider_id/link", post(link_oidc_identity))
+    }
+}
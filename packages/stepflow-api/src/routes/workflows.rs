@@ -0,0 +1,33 @@
+use axum::{
+    routing::{get, post},
+    Router,
+};
+use crate::handlers::workflows::{
+    cancel_workflow_run, dry_run_workflow, get_migration_policy, get_workflow_run, get_workflow_run_report, get_workflow_version,
+    list_workflow_versions, publish_workflow_version, start_workflow_run, update_migration_policy, validate_workflow,
+};
+use crate::server::AppState;
+
+// 工作流路由
+#[derive(Default)]
+pub struct WorkflowsRouter;
+
+impl WorkflowsRouter {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// 构建工作流路由（需要在 JWT 认证中间件之后挂载）
+    pub fn router(&self) -> Router<AppState> {
+        Router::new()
+            .route("/api/v1/workflows/validate", post(validate_workflow))
+            .route("/api/v1/workflows/dry-run", post(dry_run_workflow))
+            .route("/api/v1/workflows/runs/:run_id", get(get_workflow_run))
+            .route("/api/v1/workflows/runs/:run_id/report", get(get_workflow_run_report))
+            .route("/api/v1/workflows/runs/:run_id/cancel", post(cancel_workflow_run))
+            .route("/api/v1/workflows/:workflow_id/versions", get(list_workflow_versions).post(publish_workflow_version))
+            .route("/api/v1/workflows/:workflow_id/versions/:version", get(get_workflow_version))
+            .route("/api/v1/workflows/:workflow_id/migration-policy", get(get_migration_policy).put(update_migration_policy))
+            .route("/api/v1/workflows/:workflow_id/runs", post(start_workflow_run))
+    }
+}
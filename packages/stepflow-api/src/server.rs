@@ -1,12 +1,21 @@
+use crate::email::{EmailNotificationTransport, EmailSender, LogEmailSender};
+use crate::middleware::{CorsPolicyCache, OperationalModeCache};
+use crate::oidc::OidcClient;
+use crate::tool_sessions::{ToolSessionConfig, ToolSessionManager};
+use crate::services::{
+    BasicValidationService, InMemoryCacheService, InMemoryRateLimitService, JwtAuthService, RequestMetricsService,
+};
 use crate::errors::{ApiError, ApiResult};
 use crate::types::{
     ApiMetrics, HealthStatus, HttpRequest, HttpResponse, ServerConfig, ServerStatus, UserContext,
 };
 use async_trait::async_trait;
 use std::sync::Arc;
-use stepflow_database::SqliteDatabase;
-use stepflow_executor::Executor;
-use stepflow_registry::Registry;
+use stepflow_core::{Flags, GcPolicy, NetworkAcl};
+use stepflow_database::{JobScheduler, LeaderElection, SqliteDatabase};
+use stepflow_executor::{EnvironmentPolicy, Executor, WorkflowEngine};
+use stepflow_monitoring::{LoggingHandle, NotificationService};
+use stepflow_registry::{ContentStore, Registry, ToolPackageStore};
 use stepflow_sandbox::Sandbox;
 
 /// API 服务器特征
@@ -97,6 +106,9 @@ pub trait AuthService: Send + Sync {
     
     /// 生成 JWT 令牌
     async fn generate_jwt_token(&self, user_context: &UserContext) -> ApiResult<String>;
+
+    /// 生成刷新令牌，只能用于换取新的访问令牌
+    async fn generate_refresh_token(&self, user_context: &UserContext) -> ApiResult<String>;
     
     /// 刷新 JWT 令牌
     async fn refresh_jwt_token(&self, refresh_token: &str) -> ApiResult<String>;
@@ -263,6 +275,33 @@ pub struct AppState {
     pub monitoring_service: Arc<dyn MonitoringService>,
     pub validation_service: Arc<dyn ValidationService>,
     pub cache_service: Arc<dyn CacheService>,
+    pub email_sender: Arc<dyn EmailSender>,
+    pub workflow_engine: Arc<WorkflowEngine>,
+    pub oidc_client: OidcClient,
+    /// 租户域名的 CORS 策略缓存
+    pub cors_policies: Arc<CorsPolicyCache>,
+    /// 运行模式（维护 / 只读）缓存
+    pub operational_mode: Arc<OperationalModeCache>,
+    /// 工具包与制品共享的内容寻址存储
+    pub content_store: Arc<ContentStore>,
+    /// 按工具版本保存的工具包
+    pub tool_packages: Arc<ToolPackageStore>,
+    /// 运行时调整日志级别与采样的句柄，未设置时管理接口不可用
+    pub logging: Option<LoggingHandle>,
+    /// 客户端地址访问控制列表，可与 RPC 服务端共享
+    pub network_acl: Arc<NetworkAcl>,
+    /// 内部后台任务调度器，未设置时后台任务管理接口不可用
+    pub jobs: Option<Arc<JobScheduler>>,
+    /// 多实例部署的领导者选举，设置后健康检查中附带领导者状态
+    pub leader: Option<Arc<LeaderElection>>,
+    /// 交互式执行会话
+    pub tool_sessions: Arc<ToolSessionManager>,
+    /// 功能开关，与执行器共享同一份定义
+    pub flags: Flags,
+    /// 环境变量策略，应与执行器使用同一份策略；交互式会话的环境按它检查
+    pub environment_policy: Arc<EnvironmentPolicy>,
+    /// 注册表垃圾回收策略，管理接口手动回收时使用
+    pub gc_policy: GcPolicy,
     pub config: ServerConfig,
 }
 
@@ -279,7 +318,10 @@ impl AppState {
         cache_service: Arc<dyn CacheService>,
         config: ServerConfig,
     ) -> Self {
+        let content_store = Arc::new(ContentStore::new());
         Self {
+            workflow_engine: Arc::new(WorkflowEngine::new(executor.clone(), db.clone())),
+            tool_sessions: Arc::new(ToolSessionManager::new(sandbox.clone(), ToolSessionConfig::default())),
             db,
             registry,
             executor,
@@ -289,7 +331,136 @@ impl AppState {
             monitoring_service,
             validation_service,
             cache_service,
+            email_sender: Arc::new(LogEmailSender),
+            oidc_client: OidcClient::new(),
+            cors_policies: Arc::new(CorsPolicyCache::default()),
+            operational_mode: Arc::new(OperationalModeCache::default()),
+            tool_packages: Arc::new(ToolPackageStore::new(content_store.clone())),
+            content_store,
+            logging: None,
+            network_acl: Arc::new(NetworkAcl::default()),
+            jobs: None,
+            leader: None,
+            flags: Flags::default(),
+            environment_policy: Arc::new(EnvironmentPolicy::default()),
+            gc_policy: GcPolicy::default(),
+            config,
+        }
+    }
+    
+    /// 使用默认服务实现创建应用状态：JWT 认证，进程内的速率限制、请求指标与缓存
+    pub fn with_default_services(
+        db: Arc<SqliteDatabase>,
+        registry: Arc<dyn Registry>,
+        executor: Arc<dyn Executor>,
+        sandbox: Arc<dyn Sandbox>,
+        config: ServerConfig,
+    ) -> Self {
+        let auth_service = Arc::new(JwtAuthService::new(db.clone(), config.auth_config.clone()));
+        Self::new(
+            db,
+            registry,
+            executor,
+            sandbox,
+            auth_service,
+            Arc::new(InMemoryRateLimitService::new()),
+            Arc::new(RequestMetricsService::new()),
+            Arc::new(BasicValidationService::new()),
+            Arc::new(InMemoryCacheService::new()),
             config,
+        )
+    }
+
+    /// 设置速率限制服务（例如多实例共享的 Redis 实现）
+    pub fn with_rate_limit_service(mut self, rate_limit_service: Arc<dyn RateLimitService>) -> Self {
+        self.rate_limit_service = rate_limit_service;
+        self
+    }
+
+    /// 设置邮件发送器（默认仅记录日志）
+    pub fn with_email_sender(mut self, email_sender: Arc<dyn EmailSender>) -> Self {
+        self.email_sender = email_sender;
+        self
+    }
+
+    /// 设置内容寻址存储（例如与 OpenAPI 制品存储共享），工具包改存到该存储中
+    pub fn with_content_store(mut self, content_store: Arc<ContentStore>) -> Self {
+        self.tool_packages = Arc::new(ToolPackageStore::new(content_store.clone()));
+        self.content_store = content_store;
+        self
+    }
+
+    /// 设置日志句柄，启用运行时日志配置接口
+    pub fn with_logging_handle(mut self, logging: LoggingHandle) -> Self {
+        self.logging = Some(logging);
+        self
+    }
+
+    /// 设置后台任务调度器，启用后台任务管理接口
+    pub fn with_job_scheduler(mut self, jobs: Arc<JobScheduler>) -> Self {
+        self.jobs = Some(jobs);
+        self
+    }
+
+    /// 设置领导者选举，健康检查中附带本实例是否为领导者
+    pub fn with_leader_election(mut self, leader: Arc<LeaderElection>) -> Self {
+        self.leader = Some(leader);
+        self
+    }
+
+    /// 设置网络访问控制列表（例如与 RPC 服务端共享的列表）
+    pub fn with_network_acl(mut self, network_acl: Arc<NetworkAcl>) -> Self {
+        self.network_acl = network_acl;
+        self
+    }
+
+    /// 设置会话管理器（例如调整了配额与空闲超时的管理器）
+    pub fn with_tool_session_manager(mut self, tool_sessions: Arc<ToolSessionManager>) -> Self {
+        self.tool_sessions = tool_sessions;
+        self
+    }
+
+    /// 设置工作流引擎（例如配置了审批通知的引擎）
+    pub fn with_workflow_engine(mut self, workflow_engine: Arc<WorkflowEngine>) -> Self {
+        self.workflow_engine = workflow_engine;
+        self
+    }
+
+    /// 租户通知渠道服务，邮件渠道通过当前的邮件发送器发送
+    ///
+    /// 可作为告警通知器（[`stepflow_monitoring::AlertManager::with_notifier`]），或经
+    /// [`crate::approvals::ChannelApprovalNotifier`] 接收审批事件。
+    pub fn notification_service(&self) -> NotificationService {
+        NotificationService::new(self.db.clone())
+            .with_email_transport(Arc::new(EmailNotificationTransport::new(self.email_sender.clone())))
+    }
+
+    /// 设置功能开关（例如运行时加载的、与执行器共享的开关）
+    pub fn with_flags(mut self, flags: Flags) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// 设置环境变量策略（通常与执行器的 `with_environment_policy` 使用同一份策略）
+    pub fn with_environment_policy(mut self, environment_policy: Arc<EnvironmentPolicy>) -> Self {
+        self.environment_policy = environment_policy;
+        self
+    }
+
+    /// 设置注册表垃圾回收策略，通常与后台回收任务使用同一配置
+    pub fn with_gc_policy(mut self, gc_policy: GcPolicy) -> Self {
+        self.gc_policy = gc_policy;
+        self
+    }
+}
+
+impl From<&AppState> for crate::models::responses::AppState {
+    fn from(state: &AppState) -> Self {
+        Self {
+            registry: state.registry.clone(),
+            executor: state.executor.clone(),
+            sandbox: state.sandbox.clone(),
+            database: state.db.clone(),
         }
     }
 }
@@ -0,0 +1,623 @@
+//! 服务特征的默认实现
+//!
+//! 单实例部署使用的实现：认证基于 JWT 与数据库中的用户和会话，速率限制、缓存与请求指标
+//! 保存在进程内存中。多实例部署可替换为共享存储的实现，例如启用 `redis` 特性后的
+//! [`RedisRateLimitService`]。
+
+use crate::errors::{ApiError, ApiResult};
+use crate::server::{AuthService, CacheService, MonitoringService, RateLimitService, ValidationService};
+use crate::types::{
+    ApiMetrics, AuthConfig, HealthCheck, HealthStatus, HealthStatusType, HttpRequest, HttpResponse, JwtClaims,
+    TokenType, UserContext, JWT_AUDIENCE, JWT_ISSUER,
+};
+use async_trait::async_trait;
+use chrono::Utc;
+use jsonwebtoken::{encode, EncodingKey, Header};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use stepflow_core::UserId;
+use stepflow_database::{SessionRepository, SqliteDatabase, UserRepository};
+
+/// 基于 JWT 的认证服务
+///
+/// 令牌使用 `auth_config.jwt_secret` 签名，会话 ID 写入 `sid` 声明，撤销令牌即撤销其会话。
+pub struct JwtAuthService {
+    db: Arc<SqliteDatabase>,
+    config: AuthConfig,
+}
+
+impl JwtAuthService {
+    pub fn new(db: Arc<SqliteDatabase>, config: AuthConfig) -> Self {
+        Self { db, config }
+    }
+
+    fn sessions(&self) -> SessionRepository {
+        SessionRepository::new(self.db.as_ref().clone())
+    }
+
+    /// 校验刷新令牌，并要求其会话仍然有效
+    async fn validate_refresh_token(&self, token: &str) -> ApiResult<UserContext> {
+        let claims = JwtClaims::decode(token, &self.config.jwt_secret)
+            .map_err(|e| ApiError::Unauthorized(format!("Invalid token: {}", e)))?;
+        if claims.typ != TokenType::Refresh {
+            return Err(ApiError::Unauthorized("Not a refresh token".to_string()));
+        }
+        let user = UserContext::from(claims);
+        if user.session_id.is_empty() {
+            return Err(ApiError::Unauthorized("Refresh token is not bound to a session".to_string()));
+        }
+        let active = self.sessions()
+            .get_session(&user.session_id)
+            .await?
+            .is_some_and(|session| session.user_id == user.user_id && session.is_active());
+        if !active {
+            return Err(ApiError::Unauthorized("Session has been revoked or has expired".to_string()));
+        }
+        Ok(user)
+    }
+
+    fn sign(&self, user_context: &UserContext, typ: TokenType) -> ApiResult<String> {
+        let claims = JwtClaims {
+            sub: user_context.user_id.as_str().to_string(),
+            iat: Utc::now().timestamp(),
+            exp: user_context.expires_at.timestamp(),
+            aud: JWT_AUDIENCE.to_string(),
+            iss: JWT_ISSUER.to_string(),
+            user_id: user_context.user_id.as_str().to_string(),
+            tenant_id: user_context.tenant_id.clone(),
+            roles: user_context.roles.clone(),
+            permissions: user_context.permissions.clone(),
+            sid: (!user_context.session_id.is_empty()).then(|| user_context.session_id.clone()),
+            typ,
+        };
+        encode(&Header::default(), &claims, &EncodingKey::from_secret(self.config.jwt_secret.as_ref()))
+            .map_err(|e| ApiError::InternalServerError(format!("Failed to sign token: {}", e)))
+    }
+}
+
+#[async_trait]
+impl AuthService for JwtAuthService {
+    async fn validate_jwt_token(&self, token: &str) -> ApiResult<UserContext> {
+        let claims = JwtClaims::decode_access(token, &self.config.jwt_secret)
+            .map_err(|e| ApiError::Unauthorized(format!("Invalid token: {}", e)))?;
+        Ok(UserContext::from(claims))
+    }
+
+    async fn validate_api_key(&self, _key: &str) -> ApiResult<UserContext> {
+        Err(ApiError::Unauthorized("API keys are not supported".to_string()))
+    }
+
+    async fn generate_jwt_token(&self, user_context: &UserContext) -> ApiResult<String> {
+        self.sign(user_context, TokenType::Access)
+    }
+
+    async fn generate_refresh_token(&self, user_context: &UserContext) -> ApiResult<String> {
+        self.sign(user_context, TokenType::Refresh)
+    }
+
+    async fn refresh_jwt_token(&self, refresh_token: &str) -> ApiResult<String> {
+        let mut user = self.validate_refresh_token(refresh_token).await?;
+        user.expires_at = Utc::now() + chrono::Duration::from_std(self.config.jwt_expiration)
+            .map_err(|e| ApiError::InternalServerError(e.to_string()))?;
+        self.generate_jwt_token(&user).await
+    }
+
+    async fn revoke_token(&self, token: &str) -> ApiResult<()> {
+        let user = self.validate_jwt_token(token).await?;
+        if !user.session_id.is_empty() {
+            self.sessions().revoke_session(&user.user_id, &user.session_id).await?;
+        }
+        Ok(())
+    }
+
+    async fn check_permission(&self, user_context: &UserContext, permission: &str) -> ApiResult<bool> {
+        Ok(user_context.roles.iter().any(|role| role == "admin")
+            || user_context.permissions.iter().any(|p| p == permission))
+    }
+
+    async fn get_user_roles(&self, user_id: &str) -> ApiResult<Vec<String>> {
+        let user = UserRepository::new(self.db.as_ref().clone())
+            .get_user(&UserId::from_string(user_id.to_string()))
+            .await?;
+        Ok(user.map(|user| vec![user.role.to_string()]).unwrap_or_default())
+    }
+
+    async fn get_user_permissions(&self, _user_id: &str) -> ApiResult<Vec<String>> {
+        // 权限目前完全由角色决定
+        Ok(Vec::new())
+    }
+}
+
+/// 进程内的滑动窗口速率限制
+#[derive(Default)]
+pub struct InMemoryRateLimitService {
+    requests: Mutex<HashMap<String, VecDeque<Instant>>>,
+}
+
+impl InMemoryRateLimitService {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 丢弃窗口外的记录后，对 `key` 的请求时间序列执行 `f`
+    fn with_window<T>(&self, key: &str, window: Duration, f: impl FnOnce(&mut VecDeque<Instant>) -> T) -> T {
+        let mut requests = self.requests.lock().unwrap();
+        let timestamps = requests.entry(key.to_string()).or_default();
+        let now = Instant::now();
+        while timestamps.front().is_some_and(|t| now.duration_since(*t) >= window) {
+            timestamps.pop_front();
+        }
+        f(timestamps)
+    }
+}
+
+#[async_trait]
+impl RateLimitService for InMemoryRateLimitService {
+    async fn check_rate_limit(&self, key: &str, limit: usize, window: Duration) -> ApiResult<bool> {
+        Ok(self.with_window(key, window, |timestamps| {
+            if timestamps.len() >= limit {
+                return false;
+            }
+            timestamps.push_back(Instant::now());
+            true
+        }))
+    }
+
+    async fn get_remaining_requests(&self, key: &str, limit: usize, window: Duration) -> ApiResult<usize> {
+        Ok(self.with_window(key, window, |timestamps| limit.saturating_sub(timestamps.len())))
+    }
+
+    async fn get_reset_time(&self, key: &str, window: Duration) -> ApiResult<SystemTime> {
+        let remaining = self.with_window(key, window, |timestamps| {
+            timestamps.front().map(|oldest| window.saturating_sub(oldest.elapsed())).unwrap_or_default()
+        });
+        Ok(SystemTime::now() + remaining)
+    }
+
+    async fn reset_rate_limit(&self, key: &str) -> ApiResult<()> {
+        self.requests.lock().unwrap().remove(key);
+        Ok(())
+    }
+
+    async fn get_current_requests(&self, key: &str, window: Duration) -> ApiResult<usize> {
+        Ok(self.with_window(key, window, |timestamps| timestamps.len()))
+    }
+}
+
+/// 多实例共享的滑动窗口速率限制
+///
+/// 每个限流键对应一个 Redis 有序集合 `{prefix}:ratelimit:{key}`，成员为请求时间；
+/// 检查与记录在同一个 Lua 脚本中完成，多个实例并发请求时计数仍然准确。
+#[cfg(feature = "redis")]
+pub struct RedisRateLimitService {
+    connection: redis::aio::ConnectionManager,
+    prefix: String,
+    check: redis::Script,
+}
+
+/// 清理窗口外的记录，未达上限时记录本次请求；返回是否放行
+#[cfg(feature = "redis")]
+const RATE_LIMIT_CHECK: &str = r#"
+    redis.call('ZREMRANGEBYSCORE', KEYS[1], '-inf', ARGV[1] - ARGV[2])
+    if redis.call('ZCARD', KEYS[1]) >= tonumber(ARGV[3]) then
+        return 0
+    end
+    redis.call('ZADD', KEYS[1], ARGV[1], ARGV[4])
+    redis.call('PEXPIRE', KEYS[1], ARGV[2])
+    return 1
+"#;
+
+#[cfg(feature = "redis")]
+impl RedisRateLimitService {
+    /// 连接 `url`，限流键保存在 `{prefix}:ratelimit:` 下
+    pub async fn connect(url: &str, prefix: &str) -> ApiResult<Self> {
+        let client = redis::Client::open(url).map_err(redis_error)?;
+        let connection = client.get_connection_manager().await.map_err(redis_error)?;
+        Ok(Self::new(connection, prefix))
+    }
+
+    pub fn new(connection: redis::aio::ConnectionManager, prefix: &str) -> Self {
+        Self {
+            connection,
+            prefix: format!("{}:ratelimit:", prefix),
+            check: redis::Script::new(RATE_LIMIT_CHECK),
+        }
+    }
+
+    fn key(&self, key: &str) -> String {
+        format!("{}{}", self.prefix, key)
+    }
+
+    fn now_ms() -> i64 {
+        Utc::now().timestamp_millis()
+    }
+
+    /// 窗口内的请求数
+    async fn count(&self, key: &str, window: Duration) -> ApiResult<usize> {
+        let since = Self::now_ms() - window.as_millis() as i64;
+        redis::cmd("ZCOUNT")
+            .arg(self.key(key))
+            .arg(format!("({}", since))
+            .arg("+inf")
+            .query_async(&mut self.connection.clone())
+            .await
+            .map_err(redis_error)
+    }
+}
+
+#[cfg(feature = "redis")]
+fn redis_error(e: redis::RedisError) -> ApiError {
+    ApiError::InternalServerError(format!("Redis error: {}", e))
+}
+
+#[cfg(feature = "redis")]
+#[async_trait]
+impl RateLimitService for RedisRateLimitService {
+    async fn check_rate_limit(&self, key: &str, limit: usize, window: Duration) -> ApiResult<bool> {
+        let allowed: i64 = self.check
+            .key(self.key(key))
+            .arg(Self::now_ms())
+            .arg(window.as_millis().max(1) as i64)
+            .arg(limit)
+            .arg(uuid::Uuid::new_v4().to_string())
+            .invoke_async(&mut self.connection.clone())
+            .await
+            .map_err(redis_error)?;
+        Ok(allowed == 1)
+    }
+
+    async fn get_remaining_requests(&self, key: &str, limit: usize, window: Duration) -> ApiResult<usize> {
+        Ok(limit.saturating_sub(self.count(key, window).await?))
+    }
+
+    async fn get_reset_time(&self, key: &str, window: Duration) -> ApiResult<SystemTime> {
+        let oldest: Vec<(String, i64)> = redis::cmd("ZRANGEBYSCORE")
+            .arg(self.key(key))
+            .arg(format!("({}", Self::now_ms() - window.as_millis() as i64))
+            .arg("+inf")
+            .arg("WITHSCORES")
+            .arg("LIMIT")
+            .arg(0)
+            .arg(1)
+            .query_async(&mut self.connection.clone())
+            .await
+            .map_err(redis_error)?;
+        let remaining = oldest.first()
+            .map(|(_, at)| window.saturating_sub(Duration::from_millis((Self::now_ms() - at).max(0) as u64)))
+            .unwrap_or_default();
+        Ok(SystemTime::now() + remaining)
+    }
+
+    async fn reset_rate_limit(&self, key: &str) -> ApiResult<()> {
+        redis::cmd("DEL")
+            .arg(self.key(key))
+            .query_async::<_, ()>(&mut self.connection.clone())
+            .await
+            .map_err(redis_error)
+    }
+
+    async fn get_current_requests(&self, key: &str, window: Duration) -> ApiResult<usize> {
+        self.count(key, window).await
+    }
+}
+
+/// 进程内的请求计数与耗时统计
+pub struct RequestMetricsService {
+    started_at: Instant,
+    total_requests: AtomicU64,
+    failed_requests: AtomicU64,
+    total_duration_us: AtomicU64,
+    custom: Mutex<HashMap<String, f64>>,
+}
+
+impl Default for RequestMetricsService {
+    fn default() -> Self {
+        Self {
+            started_at: Instant::now(),
+            total_requests: AtomicU64::new(0),
+            failed_requests: AtomicU64::new(0),
+            total_duration_us: AtomicU64::new(0),
+            custom: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl RequestMetricsService {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 自定义指标的当前值，计数器为累计值，其余为最近一次记录的值
+    pub fn custom_metric(&self, name: &str) -> Option<f64> {
+        self.custom.lock().unwrap().get(name).copied()
+    }
+}
+
+#[async_trait]
+impl MonitoringService for RequestMetricsService {
+    async fn record_request(&self, _request: &HttpRequest, response: &HttpResponse, duration: Duration) -> ApiResult<()> {
+        self.total_requests.fetch_add(1, Ordering::Relaxed);
+        if response.status_code >= 500 {
+            self.failed_requests.fetch_add(1, Ordering::Relaxed);
+        }
+        self.total_duration_us.fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+        Ok(())
+    }
+
+    async fn record_error(&self, _request: &HttpRequest, _error: &ApiError) -> ApiResult<()> {
+        self.failed_requests.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    async fn get_metrics(&self) -> ApiResult<ApiMetrics> {
+        let total = self.total_requests.load(Ordering::Relaxed);
+        let failed = self.failed_requests.load(Ordering::Relaxed).min(total);
+        let uptime = self.started_at.elapsed().as_secs_f64();
+        Ok(ApiMetrics {
+            total_requests: total,
+            successful_requests: total - failed,
+            failed_requests: failed,
+            average_response_time: Duration::from_micros(self.total_duration_us.load(Ordering::Relaxed) / total.max(1)),
+            requests_per_second: if uptime > 0.0 { total as f64 / uptime } else { 0.0 },
+            error_rate: failed as f64 / total.max(1) as f64,
+            active_connections: 0,
+            memory_usage: 0,
+            cpu_usage: 0.0,
+            timestamp: Utc::now(),
+        })
+    }
+
+    async fn get_health_status(&self) -> ApiResult<HealthStatus> {
+        let mut checks = HashMap::new();
+        checks.insert("api".to_string(), HealthCheck {
+            status: HealthStatusType::Healthy,
+            message: "API is running".to_string(),
+            duration: Duration::ZERO,
+            timestamp: Utc::now(),
+        });
+        Ok(HealthStatus {
+            status: HealthStatusType::Healthy,
+            checks,
+            timestamp: Utc::now(),
+        })
+    }
+
+    async fn record_custom_metric(&self, name: &str, value: f64, _tags: Option<HashMap<String, String>>) -> ApiResult<()> {
+        self.custom.lock().unwrap().insert(name.to_string(), value);
+        Ok(())
+    }
+
+    async fn increment_counter(&self, name: &str, _tags: Option<HashMap<String, String>>) -> ApiResult<()> {
+        *self.custom.lock().unwrap().entry(name.to_string()).or_insert(0.0) += 1.0;
+        Ok(())
+    }
+
+    async fn record_histogram(&self, name: &str, value: f64, tags: Option<HashMap<String, String>>) -> ApiResult<()> {
+        self.record_custom_metric(name, value, tags).await
+    }
+}
+
+/// 基本的请求与字段校验
+#[derive(Default)]
+pub struct BasicValidationService;
+
+impl BasicValidationService {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+fn require_non_empty(field_name: &str, value: &str) -> ApiResult<()> {
+    if value.trim().is_empty() {
+        return Err(ApiError::ValidationError(format!("{} is required", field_name)));
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl ValidationService for BasicValidationService {
+    async fn validate_request(&self, request: &HttpRequest) -> ApiResult<()> {
+        if !request.path.starts_with('/') {
+            return Err(ApiError::ValidationError(format!("Invalid request path: {}", request.path)));
+        }
+        Ok(())
+    }
+
+    async fn validate_response(&self, _response: &HttpResponse) -> ApiResult<()> {
+        Ok(())
+    }
+
+    async fn validate_json(&self, data: &serde_json::Value, schema: &str) -> ApiResult<()> {
+        let schema: serde_json::Value = serde_json::from_str(schema)
+            .map_err(|e| ApiError::ValidationError(format!("Invalid schema: {}", e)))?;
+        let required = schema.get("required").and_then(|r| r.as_array()).into_iter().flatten();
+        for field in required.filter_map(|f| f.as_str()) {
+            if data.get(field).is_none() {
+                return Err(ApiError::ValidationError(format!("Missing required field: {}", field)));
+            }
+        }
+        Ok(())
+    }
+
+    async fn validate_field(&self, field_name: &str, value: &str, rules: &[&str]) -> ApiResult<()> {
+        for rule in rules {
+            match rule.split_once(':') {
+                None if *rule == "required" => require_non_empty(field_name, value)?,
+                Some(("max", max)) if value.chars().count() > max.parse().unwrap_or(usize::MAX) => {
+                    return Err(ApiError::ValidationError(format!("{} must be at most {} characters", field_name, max)));
+                }
+                Some(("min", min)) if value.chars().count() < min.parse().unwrap_or(0) => {
+                    return Err(ApiError::ValidationError(format!("{} must be at least {} characters", field_name, min)));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    async fn validate_tool_name(&self, name: &str) -> ApiResult<()> {
+        require_non_empty("Tool name", name)?;
+        if !name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')) {
+            return Err(ApiError::ValidationError(format!("Invalid tool name: {}", name)));
+        }
+        Ok(())
+    }
+
+    async fn validate_tool_type(&self, tool_type: &str) -> ApiResult<()> {
+        require_non_empty("Tool type", tool_type)
+    }
+
+    async fn validate_version(&self, version: &str) -> ApiResult<()> {
+        // 形如 `1.2.3`，可带 `-pre` 与 `+build` 后缀
+        let core = version.split(['-', '+']).next().unwrap_or_default();
+        let parts: Vec<&str> = core.split('.').collect();
+        if parts.len() != 3 || parts.iter().any(|part| part.parse::<u32>().is_err()) {
+            return Err(ApiError::ValidationError(format!("Invalid version: {}", version)));
+        }
+        Ok(())
+    }
+
+    async fn validate_user_id(&self, user_id: &str) -> ApiResult<()> {
+        require_non_empty("User ID", user_id)
+    }
+
+    async fn validate_execution_id(&self, execution_id: &str) -> ApiResult<()> {
+        require_non_empty("Execution ID", execution_id)
+    }
+}
+
+/// 缓存条目：值与过期时间
+type CacheEntries = HashMap<String, (Vec<u8>, Option<Instant>)>;
+
+/// 进程内的键值缓存
+#[derive(Default)]
+pub struct InMemoryCacheService {
+    entries: Mutex<CacheEntries>,
+}
+
+impl InMemoryCacheService {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 在移除过期条目后访问缓存
+    fn live_entries<T>(&self, f: impl FnOnce(&mut CacheEntries) -> T) -> T {
+        let mut entries = self.entries.lock().unwrap();
+        let now = Instant::now();
+        entries.retain(|_, (_, expires_at)| expires_at.is_none_or(|at| at > now));
+        f(&mut entries)
+    }
+}
+
+#[async_trait]
+impl CacheService for InMemoryCacheService {
+    async fn get(&self, key: &str) -> ApiResult<Option<Vec<u8>>> {
+        Ok(self.live_entries(|entries| entries.get(key).map(|(value, _)| value.clone())))
+    }
+
+    async fn set(&self, key: &str, value: Vec<u8>, ttl: Option<Duration>) -> ApiResult<()> {
+        let expires_at = ttl.map(|ttl| Instant::now() + ttl);
+        self.live_entries(|entries| entries.insert(key.to_string(), (value, expires_at)));
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> ApiResult<()> {
+        self.live_entries(|entries| entries.remove(key));
+        Ok(())
+    }
+
+    async fn exists(&self, key: &str) -> ApiResult<bool> {
+        Ok(self.live_entries(|entries| entries.contains_key(key)))
+    }
+
+    async fn expire(&self, key: &str, ttl: Duration) -> ApiResult<()> {
+        self.live_entries(|entries| {
+            if let Some((_, expires_at)) = entries.get_mut(key) {
+                *expires_at = Some(Instant::now() + ttl);
+            }
+        });
+        Ok(())
+    }
+
+    async fn ttl(&self, key: &str) -> ApiResult<Option<Duration>> {
+        Ok(self.live_entries(|entries| {
+            entries.get(key)
+                .and_then(|(_, expires_at)| *expires_at)
+                .map(|at| at.saturating_duration_since(Instant::now()))
+        }))
+    }
+
+    async fn clear(&self) -> ApiResult<()> {
+        self.entries.lock().unwrap().clear();
+        Ok(())
+    }
+
+    async fn stats(&self) -> ApiResult<HashMap<String, String>> {
+        let entries = self.live_entries(|entries| entries.len());
+        Ok(HashMap::from([("entries".to_string(), entries.to_string())]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stepflow_database::MigrationManager;
+
+    async fn auth_service() -> JwtAuthService {
+        let db = Arc::new(SqliteDatabase::new("sqlite::memory:").await.unwrap());
+        MigrationManager::run_migrations(&db).await.unwrap();
+        JwtAuthService::new(db, AuthConfig::default())
+    }
+
+    #[tokio::test]
+    async fn test_jwt_round_trip() {
+        let service = auth_service().await;
+        let user = UserContext {
+            user_id: UserId::from_string("user-1".to_string()),
+            tenant_id: Some("tenant-1".to_string()),
+            roles: vec!["user".to_string()],
+            permissions: Vec::new(),
+            session_id: String::new(),
+            expires_at: Utc::now() + chrono::Duration::minutes(5),
+        };
+
+        let token = service.generate_jwt_token(&user).await.unwrap();
+        let decoded = service.validate_jwt_token(&token).await.unwrap();
+        assert_eq!(decoded.user_id, user.user_id);
+        assert_eq!(decoded.tenant_id, user.tenant_id);
+        assert_eq!(decoded.roles, user.roles);
+
+        let other = JwtAuthService::new(service.db.clone(), AuthConfig {
+            jwt_secret: "another-secret".to_string(),
+            ..AuthConfig::default()
+        });
+        assert!(other.validate_jwt_token(&token).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_window() {
+        let service = InMemoryRateLimitService::new();
+        let window = Duration::from_secs(60);
+        assert!(service.check_rate_limit("client", 2, window).await.unwrap());
+        assert!(service.check_rate_limit("client", 2, window).await.unwrap());
+        assert!(!service.check_rate_limit("client", 2, window).await.unwrap());
+        assert_eq!(service.get_remaining_requests("client", 2, window).await.unwrap(), 0);
+        assert!(service.check_rate_limit("other", 2, window).await.unwrap());
+
+        service.reset_rate_limit("client").await.unwrap();
+        assert_eq!(service.get_current_requests("client", window).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_cache_expiry() {
+        let cache = InMemoryCacheService::new();
+        cache.set("kept", b"a".to_vec(), None).await.unwrap();
+        cache.set("expired", b"b".to_vec(), Some(Duration::ZERO)).await.unwrap();
+
+        assert_eq!(cache.get("kept").await.unwrap(), Some(b"a".to_vec()));
+        assert!(!cache.exists("expired").await.unwrap());
+        assert_eq!(cache.ttl("kept").await.unwrap(), None);
+    }
+}
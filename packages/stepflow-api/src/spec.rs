@@ -0,0 +1,938 @@
+//! Stepflow API 自描述规范
+//!
+//! 以 OpenAPI 3.0 文档描述面向集成方的 REST 接口（登录、工具、执行、工作流运行），
+//! 通过 `GET /api/v1/openapi.json` 提供，并作为 `stepflow-admin generate-sdk`
+//! 生成 Rust / TypeScript 客户端 SDK 的输入。新增或修改这些接口时需同步更新此文档。
+
+use serde_json::{json, Value};
+
+/// 引用组件 Schema
+fn schema_ref(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{}", name) })
+}
+
+/// JSON 请求体
+fn json_body(schema: &str, required: bool) -> Value {
+    json!({
+        "required": required,
+        "content": { "application/json": { "schema": schema_ref(schema) } }
+    })
+}
+
+/// 返回 JSON 的成功响应
+fn ok(schema: &str) -> Value {
+    json!({
+        "200": { "description": "OK", "content": { "application/json": { "schema": schema_ref(schema) } } },
+        "default": { "description": "Error", "content": { "application/json": { "schema": schema_ref("ErrorResponse") } } }
+    })
+}
+
+/// 运行报告的成功响应，HTML 页面或 PDF 文件
+fn report() -> Value {
+    json!({
+        "200": {
+            "description": "OK",
+            "content": {
+                "text/html": { "schema": { "type": "string" } },
+                "application/pdf": { "schema": { "type": "string", "format": "binary" } }
+            }
+        },
+        "default": { "description": "Error", "content": { "application/json": { "schema": schema_ref("ErrorResponse") } } }
+    })
+}
+
+fn report_format_param() -> Value {
+    query_param("format", json!({ "type": "string", "enum": ["html", "pdf"] }), "报告格式，默认 html")
+}
+
+fn path_param(name: &str) -> Value {
+    json!({ "name": name, "in": "path", "required": true, "schema": { "type": "string" } })
+}
+
+fn query_param(name: &str, schema: Value, description: &str) -> Value {
+    json!({ "name": name, "in": "query", "required": false, "schema": schema, "description": description })
+}
+
+/// `Accept-Language` 请求头，决定错误信息与工具名称、描述的语言
+fn accept_language_param() -> Value {
+    json!({
+        "name": "Accept-Language", "in": "header", "required": false, "schema": { "type": "string" },
+        "description": "偏好的语言，例如 zh-CN,en;q=0.8；缺少翻译时使用默认文本"
+    })
+}
+
+fn pagination_params() -> [Value; 2] {
+    [
+        query_param("page", json!({ "type": "integer" }), "页码，从 1 开始"),
+        query_param("page_size", json!({ "type": "integer" }), "每页条数，默认 20"),
+    ]
+}
+
+fn string_map() -> Value {
+    json!({ "type": "object", "additionalProperties": { "type": "string" } })
+}
+
+/// 生成 Stepflow API 的 OpenAPI 文档
+pub fn api_spec() -> Value {
+    let [page, page_size] = pagination_params();
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Stepflow API",
+            "version": crate::VERSION,
+            "description": crate::DESCRIPTION
+        },
+        "security": [{ "bearerAuth": [] }, { "apiKey": [] }],
+        "paths": {
+            "/api/v1/auth/login": {
+                "post": {
+                    "operationId": "login",
+                    "summary": "Log in with username and password",
+                    "tags": ["auth"],
+                    "security": [],
+                    "requestBody": json_body("LoginRequest", true),
+                    "responses": ok("LoginResponse")
+                }
+            },
+            "/api/v1/auth/refresh": {
+                "post": {
+                    "operationId": "refreshToken",
+                    "summary": "Exchange a refresh token for a new access token",
+                    "tags": ["auth"],
+                    "security": [],
+                    "requestBody": json_body("RefreshTokenRequest", true),
+                    "responses": ok("RefreshTokenResponse")
+                }
+            },
+            "/api/v1/tools": {
+                "get": {
+                    "operationId": "listTools",
+                    "summary": "List the tools visible to the caller",
+                    "tags": ["tools"],
+                    "parameters": [page, page_size, accept_language_param()],
+                    "responses": ok("ListToolsResponse")
+                }
+            },
+            "/api/v1/tools/{tool_id}/form": {
+                "get": {
+                    "operationId": "getToolForm",
+                    "summary": "Get UI form metadata for the input parameters of a tool",
+                    "tags": ["tools"],
+                    "parameters": [path_param("tool_id")],
+                    "responses": ok("ToolForm")
+                }
+            },
+            "/api/v1/tools/{tool_id}/presets": {
+                "get": {
+                    "operationId": "listToolPresets",
+                    "summary": "List the parameter presets of a tool shared with the tenant or owned by the caller",
+                    "tags": ["tools"],
+                    "parameters": [path_param("tool_id")],
+                    "responses": ok("ListToolPresetsResponse")
+                },
+                "post": {
+                    "operationId": "saveToolPreset",
+                    "summary": "Save a parameter preset, replacing the preset of the same name and owner",
+                    "tags": ["tools"],
+                    "parameters": [path_param("tool_id")],
+                    "requestBody": json_body("SaveToolPresetRequest", true),
+                    "responses": ok("ToolPreset")
+                }
+            },
+            "/api/v1/tools/{tool_id}/presets/{preset_id}": {
+                "get": {
+                    "operationId": "getToolPreset",
+                    "summary": "Get a parameter preset",
+                    "tags": ["tools"],
+                    "parameters": [path_param("tool_id"), path_param("preset_id")],
+                    "responses": ok("ToolPreset")
+                },
+                "put": {
+                    "operationId": "updateToolPreset",
+                    "summary": "Update the name, description and parameters of a preset",
+                    "tags": ["tools"],
+                    "parameters": [path_param("tool_id"), path_param("preset_id")],
+                    "requestBody": json_body("UpdateToolPresetRequest", true),
+                    "responses": ok("ToolPreset")
+                },
+                "delete": {
+                    "operationId": "deleteToolPreset",
+                    "summary": "Delete a parameter preset",
+                    "tags": ["tools"],
+                    "parameters": [path_param("tool_id"), path_param("preset_id")],
+                    "responses": ok("DeleteToolPresetResponse")
+                }
+            },
+            "/api/v1/executions": {
+                "get": {
+                    "operationId": "listExecutions",
+                    "summary": "List executions of the current tenant",
+                    "tags": ["executions"],
+                    "parameters": [
+                        page,
+                        page_size,
+                        query_param("tool_id", json!({ "type": "string" }), "只返回该工具的执行"),
+                        query_param("status", json!({ "type": "string" }), "只返回该状态的执行"),
+                        query_param("label", json!({ "type": "string" }), "标签条件，格式为 key:value"),
+                        query_param("view", json!({ "type": "string" }), "已保存视图的 ID")
+                    ],
+                    "responses": ok("ListExecutionsResponse")
+                }
+            },
+            "/api/v1/executions/estimate": {
+                "post": {
+                    "operationId": "estimateExecution",
+                    "summary": "Estimate duration and cost of an execution without running it",
+                    "tags": ["executions"],
+                    "requestBody": json_body("EstimateExecutionRequest", true),
+                    "responses": ok("ExecutionEstimate")
+                }
+            },
+            "/api/v1/executions/run-sync": {
+                "post": {
+                    "operationId": "runExecutionSync",
+                    "summary": "Run a tool and wait for the result or the timeout",
+                    "tags": ["executions"],
+                    "requestBody": json_body("RunSyncRequest", true),
+                    "responses": {
+                        "200": {
+                            "description": "Execution finished; with Accept: text/event-stream, progress events followed by a result or timeout event",
+                            "content": {
+                                "application/json": { "schema": schema_ref("RunSyncResponse") },
+                                "text/event-stream": { "schema": { "type": "string" } }
+                            }
+                        },
+                        "202": {
+                            "description": "Timeout elapsed before the execution finished",
+                            "content": { "application/json": { "schema": schema_ref("RunSyncResponse") } }
+                        },
+                        "default": { "description": "Error", "content": { "application/json": { "schema": schema_ref("ErrorResponse") } } }
+                    }
+                }
+            },
+            "/api/v1/executions/concurrency-groups": {
+                "get": {
+                    "operationId": "listConcurrencyGroups",
+                    "summary": "List concurrency groups of the tenant with running or waiting executions",
+                    "tags": ["executions"],
+                    "responses": ok("ListConcurrencyGroupsResponse")
+                }
+            },
+            "/api/v1/executions/{execution_id}/queue-position": {
+                "get": {
+                    "operationId": "getExecutionQueuePosition",
+                    "summary": "Get the position of an execution waiting for its concurrency group",
+                    "tags": ["executions"],
+                    "parameters": [path_param("execution_id")],
+                    "responses": ok("QueuePositionResponse")
+                }
+            },
+            "/api/v1/executions/{execution_id}/timeline": {
+                "get": {
+                    "operationId": "getExecutionTimeline",
+                    "summary": "Get the state transitions of an execution",
+                    "tags": ["executions"],
+                    "parameters": [path_param("execution_id")],
+                    "responses": ok("ExecutionTimeline")
+                }
+            },
+            "/api/v1/executions/{execution_id}/report": {
+                "get": {
+                    "operationId": "getExecutionReport",
+                    "summary": "Export a report of an execution",
+                    "tags": ["executions"],
+                    "parameters": [path_param("execution_id"), report_format_param()],
+                    "responses": report()
+                }
+            },
+            "/api/v1/executions/{execution_id}/annotations": {
+                "get": {
+                    "operationId": "getExecutionAnnotations",
+                    "summary": "Get the labels and notes of an execution",
+                    "tags": ["executions"],
+                    "parameters": [path_param("execution_id")],
+                    "responses": ok("ExecutionAnnotations")
+                }
+            },
+            "/api/v1/executions/{execution_id}/labels": {
+                "put": {
+                    "operationId": "labelExecution",
+                    "summary": "Set labels of an execution",
+                    "tags": ["executions"],
+                    "parameters": [path_param("execution_id")],
+                    "requestBody": json_body("LabelExecutionRequest", true),
+                    "responses": ok("ExecutionAnnotations")
+                }
+            },
+            "/api/v1/executions/{execution_id}/labels/{key}": {
+                "delete": {
+                    "operationId": "removeExecutionLabel",
+                    "summary": "Remove a label of an execution",
+                    "tags": ["executions"],
+                    "parameters": [path_param("execution_id"), path_param("key")],
+                    "responses": ok("ExecutionAnnotations")
+                }
+            },
+            "/api/v1/executions/{execution_id}/notes": {
+                "post": {
+                    "operationId": "addExecutionNote",
+                    "summary": "Add a note to an execution",
+                    "tags": ["executions"],
+                    "parameters": [path_param("execution_id")],
+                    "requestBody": json_body("AddExecutionNoteRequest", true),
+                    "responses": ok("ExecutionNote")
+                }
+            },
+            "/api/v1/tool-sessions": {
+                "get": {
+                    "operationId": "listToolSessions",
+                    "summary": "List the caller's open interactive sessions; admins see the whole tenant",
+                    "tags": ["executions"],
+                    "responses": ok("ListToolSessionsResponse")
+                },
+                "post": {
+                    "operationId": "createToolSession",
+                    "summary": "Open an interactive session backed by a long-lived sandbox",
+                    "tags": ["executions"],
+                    "requestBody": json_body("CreateToolSessionRequest", true),
+                    "responses": ok("ToolSession")
+                }
+            },
+            "/api/v1/tool-sessions/{session_id}": {
+                "get": {
+                    "operationId": "getToolSession",
+                    "tags": ["executions"],
+                    "parameters": [path_param("session_id")],
+                    "responses": ok("ToolSession")
+                },
+                "delete": {
+                    "operationId": "closeToolSession",
+                    "summary": "Close a session and destroy its sandbox",
+                    "tags": ["executions"],
+                    "parameters": [path_param("session_id")],
+                    "responses": ok("ToolSession")
+                }
+            },
+            "/api/v1/tool-sessions/{session_id}/input": {
+                "post": {
+                    "operationId": "sendToolSessionInput",
+                    "summary": "Run one input in the session and return its output; inputs run in arrival order",
+                    "tags": ["executions"],
+                    "parameters": [path_param("session_id")],
+                    "requestBody": json_body("ToolSessionInputRequest", true),
+                    "responses": ok("ToolSessionOutput")
+                }
+            },
+            "/api/v1/tool-sessions/{session_id}/events": {
+                "get": {
+                    "operationId": "streamToolSessionEvents",
+                    "summary": "Stream the session's outputs as server-sent events until it closes",
+                    "tags": ["executions"],
+                    "parameters": [path_param("session_id")],
+                    "responses": {
+                        "200": {
+                            "description": "output events, then a closed event",
+                            "content": { "text/event-stream": { "schema": { "type": "string" } } }
+                        },
+                        "default": { "description": "Error", "content": { "application/json": { "schema": schema_ref("ErrorResponse") } } }
+                    }
+                }
+            },
+            "/api/v1/workflows/{workflow_id}/runs": {
+                "post": {
+                    "operationId": "startWorkflowRun",
+                    "summary": "Start a run of the latest published workflow version",
+                    "tags": ["workflows"],
+                    "parameters": [path_param("workflow_id")],
+                    "requestBody": json_body("StartWorkflowRunRequest", false),
+                    "responses": ok("WorkflowRun")
+                }
+            },
+            "/api/v1/workflows/runs/{run_id}": {
+                "get": {
+                    "operationId": "getWorkflowRun",
+                    "summary": "Get a workflow run",
+                    "tags": ["workflows"],
+                    "parameters": [path_param("run_id")],
+                    "responses": ok("WorkflowRun")
+                }
+            },
+            "/api/v1/workflows/runs/{run_id}/report": {
+                "get": {
+                    "operationId": "getWorkflowRunReport",
+                    "summary": "Export a report of a workflow run",
+                    "tags": ["workflows"],
+                    "parameters": [path_param("run_id"), report_format_param()],
+                    "responses": report()
+                }
+            },
+            "/api/v1/workflows/runs/{run_id}/cancel": {
+                "post": {
+                    "operationId": "cancelWorkflowRun",
+                    "summary": "Cancel a workflow run",
+                    "tags": ["workflows"],
+                    "parameters": [path_param("run_id")],
+                    "responses": ok("WorkflowRun")
+                }
+            }
+        },
+        "components": {
+            "securitySchemes": {
+                "bearerAuth": { "type": "http", "scheme": "bearer", "bearerFormat": "JWT" },
+                "apiKey": { "type": "apiKey", "in": "header", "name": "x-api-key" }
+            },
+            "schemas": schemas()
+        }
+    })
+}
+
+fn schemas() -> Value {
+    let schemas = [
+        ("ErrorResponse", json!({
+            "type": "object",
+            "required": ["error"],
+            "properties": { "error": schema_ref("ErrorDetail") }
+        })),
+        ("ErrorDetail", json!({
+            "type": "object",
+            "required": ["code", "message"],
+            "properties": {
+                "code": { "type": "string" },
+                "message": { "type": "string" },
+                "timestamp": { "type": "string" },
+                "details": {}
+            }
+        })),
+        ("LoginRequest", json!({
+            "type": "object",
+            "required": ["username", "password"],
+            "properties": {
+                "username": { "type": "string" },
+                "password": { "type": "string" },
+                "remember_me": { "type": "boolean" },
+                "otp_code": { "type": "string", "description": "TOTP or recovery code when two-factor authentication is enabled" }
+            }
+        })),
+        ("LoginResponse", json!({
+            "type": "object",
+            "required": ["user", "access_token", "refresh_token", "expires_at", "token_type"],
+            "properties": {
+                "user": schema_ref("User"),
+                "access_token": { "type": "string" },
+                "refresh_token": { "type": "string" },
+                "expires_at": { "type": "string" },
+                "token_type": { "type": "string" }
+            }
+        })),
+        ("RefreshTokenRequest", json!({
+            "type": "object",
+            "required": ["refresh_token"],
+            "properties": { "refresh_token": { "type": "string" } }
+        })),
+        ("RefreshTokenResponse", json!({
+            "type": "object",
+            "required": ["access_token", "refresh_token", "expires_at", "token_type"],
+            "properties": {
+                "access_token": { "type": "string" },
+                "refresh_token": { "type": "string" },
+                "expires_at": { "type": "string" },
+                "token_type": { "type": "string" }
+            }
+        })),
+        ("User", json!({
+            "type": "object",
+            "required": ["id", "username", "email", "roles", "permissions"],
+            "properties": {
+                "id": { "type": "string" },
+                "username": { "type": "string" },
+                "email": { "type": "string" },
+                "roles": { "type": "array", "items": { "type": "string" } },
+                "permissions": { "type": "array", "items": { "type": "string" } }
+            }
+        })),
+        ("Pagination", json!({
+            "type": "object",
+            "required": ["page", "page_size", "total_items", "total_pages", "has_next", "has_previous"],
+            "properties": {
+                "page": { "type": "integer" },
+                "page_size": { "type": "integer" },
+                "total_items": { "type": "integer" },
+                "total_pages": { "type": "integer" },
+                "has_next": { "type": "boolean" },
+                "has_previous": { "type": "boolean" }
+            }
+        })),
+        ("Tool", json!({
+            "type": "object",
+            "required": ["id", "name", "description", "tool_type", "version", "tags", "status"],
+            "properties": {
+                "id": { "type": "string" },
+                "name": { "type": "string" },
+                "description": { "type": "string" },
+                "tool_type": { "type": "string" },
+                "version": { "type": "string" },
+                "tags": { "type": "array", "items": { "type": "string" } },
+                "status": { "type": "string" },
+                "configuration": {}
+            }
+        })),
+        ("ListToolsResponse", json!({
+            "type": "object",
+            "required": ["tools", "pagination"],
+            "properties": {
+                "tools": { "type": "array", "items": schema_ref("Tool") },
+                "pagination": schema_ref("Pagination")
+            }
+        })),
+        ("ToolPreset", json!({
+            "type": "object",
+            "required": ["id", "tenant_id", "tool_id", "name", "parameters", "created_by", "created_at", "updated_at"],
+            "properties": {
+                "id": { "type": "string" },
+                "tenant_id": { "type": "string" },
+                "tool_id": { "type": "string" },
+                "user_id": { "type": "string", "nullable": true, "description": "Owner of a private preset; null when shared with the tenant" },
+                "name": { "type": "string" },
+                "description": { "type": "string", "nullable": true },
+                "parameters": { "type": "object", "additionalProperties": {} },
+                "created_by": { "type": "string" },
+                "created_at": { "type": "string", "format": "date-time" },
+                "updated_at": { "type": "string", "format": "date-time" }
+            }
+        })),
+        ("ListToolPresetsResponse", json!({
+            "type": "object",
+            "required": ["presets"],
+            "properties": {
+                "presets": { "type": "array", "items": schema_ref("ToolPreset") }
+            }
+        })),
+        ("SaveToolPresetRequest", json!({
+            "type": "object",
+            "required": ["name"],
+            "properties": {
+                "name": { "type": "string" },
+                "description": { "type": "string" },
+                "parameters": { "type": "object", "additionalProperties": {} },
+                "shared": { "type": "boolean", "description": "Share with the tenant (admin only); private to the caller by default" }
+            }
+        })),
+        ("UpdateToolPresetRequest", json!({
+            "type": "object",
+            "required": ["name"],
+            "properties": {
+                "name": { "type": "string" },
+                "description": { "type": "string" },
+                "parameters": { "type": "object", "additionalProperties": {} }
+            }
+        })),
+        ("DeleteToolPresetResponse", json!({
+            "type": "object",
+            "required": ["preset_id", "message"],
+            "properties": {
+                "preset_id": { "type": "string" },
+                "message": { "type": "string" }
+            }
+        })),
+        ("ToolForm", json!({
+            "type": "object",
+            "required": ["tool_id", "version", "fields"],
+            "properties": {
+                "tool_id": { "type": "string" },
+                "version": { "type": "string" },
+                "fields": { "type": "array", "items": schema_ref("FormField") }
+            }
+        })),
+        ("FormField", json!({
+            "type": "object",
+            "required": ["name", "label", "field_type", "widget", "required", "read_only", "secret", "options", "constraints", "depends_on"],
+            "properties": {
+                "name": { "type": "string", "description": "Field path, nested fields joined with '.'" },
+                "label": { "type": "string" },
+                "description": { "type": "string", "nullable": true },
+                "field_type": { "type": "string" },
+                "widget": {
+                    "type": "string",
+                    "enum": ["text", "password", "number", "checkbox", "select", "multi_select", "date", "date_time", "file", "json"]
+                },
+                "required": { "type": "boolean" },
+                "read_only": { "type": "boolean" },
+                "secret": { "type": "boolean" },
+                "default": {},
+                "options": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["value", "label"],
+                        "properties": { "value": {}, "label": { "type": "string" } }
+                    }
+                },
+                "constraints": { "type": "object" },
+                "depends_on": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["field", "condition", "effect"],
+                        "properties": {
+                            "field": { "type": "string" },
+                            "condition": {
+                                "type": "object",
+                                "required": ["kind"],
+                                "properties": {
+                                    "kind": { "type": "string", "enum": ["present", "in"] },
+                                    "values": { "type": "array", "items": {} }
+                                }
+                            },
+                            "effect": { "type": "string", "enum": ["visible", "required"] }
+                        }
+                    }
+                }
+            }
+        })),
+        ("ExecutionInfo", json!({
+            "type": "object",
+            "required": ["execution_id", "tool_id", "status", "created_at", "user_id", "tenant_id"],
+            "properties": {
+                "execution_id": { "type": "string" },
+                "tool_id": { "type": "string" },
+                "status": { "type": "string" },
+                "created_at": { "type": "string" },
+                "started_at": { "type": "string", "nullable": true },
+                "completed_at": { "type": "string", "nullable": true },
+                "user_id": { "type": "string" },
+                "tenant_id": { "type": "string" },
+                "labels": string_map()
+            }
+        })),
+        ("ListExecutionsResponse", json!({
+            "type": "object",
+            "required": ["executions", "pagination"],
+            "properties": {
+                "executions": { "type": "array", "items": schema_ref("ExecutionInfo") },
+                "pagination": schema_ref("Pagination")
+            }
+        })),
+        ("ToolVersion", json!({
+            "type": "object",
+            "required": ["major", "minor", "patch"],
+            "properties": {
+                "major": { "type": "integer" },
+                "minor": { "type": "integer" },
+                "patch": { "type": "integer" },
+                "pre_release": { "type": "string", "nullable": true },
+                "build": { "type": "string", "nullable": true }
+            }
+        })),
+        ("EstimateExecutionRequest", json!({
+            "type": "object",
+            "required": ["tool_id"],
+            "properties": {
+                "tool_id": { "type": "string" },
+                "version": schema_ref("ToolVersion"),
+                "parameters": { "type": "object", "additionalProperties": {} },
+                "memory_limit": { "type": "integer", "description": "Expected memory limit in bytes" },
+                "cpu_limit": { "type": "number", "description": "Expected CPU cores, 1 by default" }
+            }
+        })),
+        ("ExecutionEstimate", json!({
+            "type": "object",
+            "required": [
+                "tool_id", "tool_version", "basis", "samples", "expected_duration_ms", "p90_duration_ms",
+                "failure_rate", "expected_queue_wait_ms", "expected_total_ms", "resources", "queue",
+                "recommendation", "estimated_at"
+            ],
+            "properties": {
+                "tool_id": { "type": "string" },
+                "tool_version": { "type": "string" },
+                "basis": { "type": "string", "enum": ["tool_version", "tool", "no_history"] },
+                "samples": { "type": "integer" },
+                "expected_duration_ms": { "type": "number" },
+                "p90_duration_ms": { "type": "number" },
+                "failure_rate": { "type": "number" },
+                "expected_queue_wait_ms": { "type": "number" },
+                "expected_total_ms": { "type": "number" },
+                "resources": schema_ref("ResourceEstimate"),
+                "queue": schema_ref("QueueConditions"),
+                "recommendation": schema_ref("Recommendation"),
+                "estimated_at": { "type": "string" }
+            }
+        })),
+        ("ResourceEstimate", json!({
+            "type": "object",
+            "required": ["cpu_seconds", "cost_units"],
+            "properties": {
+                "cpu_seconds": { "type": "number" },
+                "memory_bytes": { "type": "number", "nullable": true },
+                "cost_units": { "type": "number" }
+            }
+        })),
+        ("QueueConditions", json!({
+            "type": "object",
+            "required": ["pending_tasks", "running_tasks", "total_capacity", "idle_workers", "total_workers"],
+            "properties": {
+                "pending_tasks": { "type": "integer" },
+                "running_tasks": { "type": "integer" },
+                "total_capacity": { "type": "integer" },
+                "idle_workers": { "type": "integer" },
+                "total_workers": { "type": "integer" }
+            }
+        })),
+        ("Recommendation", json!({ "type": "string", "enum": ["run_sync", "run_async", "defer"] })),
+        ("ExecutionState", json!({
+            "type": "string",
+            "enum": ["queued", "scheduled", "running", "retrying", "completed", "failed", "cancelled", "timed_out"]
+        })),
+        ("RunSyncRequest", json!({
+            "type": "object",
+            "required": ["tool_id"],
+            "properties": {
+                "tool_id": { "type": "string" },
+                "version": schema_ref("ToolVersion"),
+                "parameters": { "type": "object", "additionalProperties": {} },
+                "labels": string_map(),
+                "note": { "type": "string" },
+                "timeout_ms": { "type": "integer", "description": "Longest wait in milliseconds; 30 seconds by default, at most 5 minutes" },
+                "concurrency": schema_ref("ConcurrencyGroup"),
+                "preset": { "type": "string", "description": "Parameter preset supplying defaults; request parameters take precedence" }
+            }
+        })),
+        ("ConcurrencyGroup", json!({
+            "type": "object",
+            "required": ["key"],
+            "properties": {
+                "key": { "type": "string", "description": "Group key template, e.g. deploy-{{ params.environment }}" },
+                "max_concurrency": { "type": "integer", "minimum": 1, "default": 1 },
+                "timeout_ms": { "type": "integer", "description": "Longest wait for a slot; 10 minutes by default" }
+            }
+        })),
+        ("QueuePosition", json!({
+            "type": "object",
+            "required": ["key", "position", "running", "max_concurrency"],
+            "properties": {
+                "key": { "type": "string" },
+                "position": { "type": "integer", "description": "1 for the execution that runs next" },
+                "running": { "type": "integer" },
+                "max_concurrency": { "type": "integer" }
+            }
+        })),
+        ("QueuePositionResponse", json!({
+            "type": "object",
+            "required": ["execution_id"],
+            "properties": {
+                "execution_id": { "type": "string" },
+                "queue_position": schema_ref("QueuePosition")
+            }
+        })),
+        ("ListConcurrencyGroupsResponse", json!({
+            "type": "object",
+            "required": ["groups"],
+            "properties": {
+                "groups": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["key", "max_concurrency", "running", "waiting"],
+                        "properties": {
+                            "key": { "type": "string" },
+                            "max_concurrency": { "type": "integer" },
+                            "running": { "type": "array", "items": { "type": "string" } },
+                            "waiting": { "type": "array", "items": { "type": "string" } }
+                        }
+                    }
+                }
+            }
+        })),
+        ("RunSyncProgress", json!({
+            "type": "object",
+            "required": ["execution_id", "elapsed_ms"],
+            "properties": {
+                "execution_id": { "type": "string" },
+                "state": schema_ref("ExecutionState"),
+                "queue_position": schema_ref("QueuePosition"),
+                "elapsed_ms": { "type": "integer" }
+            }
+        })),
+        ("CreateToolSessionRequest", json!({
+            "type": "object",
+            "required": ["tool_id"],
+            "properties": {
+                "tool_id": { "type": "string" },
+                "args": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Extra arguments inserted after the tool's session program; only arguments the tool allows"
+                },
+                "environment": string_map()
+            }
+        })),
+        ("ToolSession", json!({
+            "type": "object",
+            "required": ["id", "tenant_id", "tool_id", "user_id", "command", "inputs", "created_at", "last_active_at", "idle_timeout_secs"],
+            "properties": {
+                "id": { "type": "string" },
+                "tenant_id": { "type": "string" },
+                "tool_id": { "type": "string" },
+                "user_id": { "type": "string" },
+                "command": { "type": "array", "items": { "type": "string" } },
+                "inputs": { "type": "integer" },
+                "created_at": { "type": "string", "format": "date-time" },
+                "last_active_at": { "type": "string", "format": "date-time" },
+                "idle_timeout_secs": { "type": "integer" }
+            }
+        })),
+        ("ListToolSessionsResponse", json!({
+            "type": "object",
+            "required": ["sessions"],
+            "properties": {
+                "sessions": { "type": "array", "items": schema_ref("ToolSession") }
+            }
+        })),
+        ("ToolSessionInputRequest", json!({
+            "type": "object",
+            "required": ["input"],
+            "properties": {
+                "input": { "type": "string" }
+            }
+        })),
+        ("ToolSessionOutput", json!({
+            "type": "object",
+            "required": ["session_id", "sequence", "exit_code", "stdout", "stderr", "duration_ms"],
+            "properties": {
+                "session_id": { "type": "string" },
+                "sequence": { "type": "integer" },
+                "exit_code": { "type": "integer" },
+                "stdout": { "type": "string" },
+                "stderr": { "type": "string" },
+                "duration_ms": { "type": "integer" }
+            }
+        })),
+        ("RunSyncResponse", json!({
+            "type": "object",
+            "required": ["execution_id", "completed", "elapsed_ms"],
+            "properties": {
+                "execution_id": { "type": "string" },
+                "completed": { "type": "boolean" },
+                "state": schema_ref("ExecutionState"),
+                "result": schema_ref("ExecutionResult"),
+                "error": { "type": "string", "nullable": true },
+                "elapsed_ms": { "type": "integer" }
+            }
+        })),
+        ("ExecutionResult", json!({
+            "type": "object",
+            "required": ["success", "logs", "metrics", "metadata"],
+            "properties": {
+                "success": { "type": "boolean" },
+                "output": {},
+                "error": { "type": "string", "nullable": true },
+                "logs": { "type": "array", "items": { "type": "object", "additionalProperties": {} } },
+                "metrics": { "type": "object", "additionalProperties": { "type": "number" } },
+                "metadata": { "type": "object", "additionalProperties": {} }
+            }
+        })),
+        ("ExecutionEvent", json!({
+            "type": "object",
+            "required": ["state", "occurred_at"],
+            "properties": {
+                "state": schema_ref("ExecutionState"),
+                "worker_id": { "type": "string", "nullable": true },
+                "detail": { "type": "string", "nullable": true },
+                "occurred_at": { "type": "string" }
+            }
+        })),
+        ("ExecutionTimeline", json!({
+            "type": "object",
+            "required": ["execution_id", "events"],
+            "properties": {
+                "execution_id": { "type": "string" },
+                "current_state": schema_ref("ExecutionState"),
+                "events": { "type": "array", "items": schema_ref("ExecutionEvent") },
+                "queue_wait_ms": { "type": "integer", "nullable": true },
+                "run_duration_ms": { "type": "integer", "nullable": true },
+                "total_duration_ms": { "type": "integer", "nullable": true }
+            }
+        })),
+        ("ExecutionNote", json!({
+            "type": "object",
+            "required": ["id", "execution_id", "author", "body", "created_at"],
+            "properties": {
+                "id": { "type": "string" },
+                "execution_id": { "type": "string" },
+                "author": { "type": "string" },
+                "body": { "type": "string" },
+                "created_at": { "type": "string" }
+            }
+        })),
+        ("ExecutionAnnotations", json!({
+            "type": "object",
+            "required": ["labels", "notes"],
+            "properties": {
+                "labels": string_map(),
+                "notes": { "type": "array", "items": schema_ref("ExecutionNote") }
+            }
+        })),
+        ("LabelExecutionRequest", json!({
+            "type": "object",
+            "required": ["labels"],
+            "properties": { "labels": string_map() }
+        })),
+        ("AddExecutionNoteRequest", json!({
+            "type": "object",
+            "required": ["body"],
+            "properties": { "body": { "type": "string" } }
+        })),
+        ("StartWorkflowRunRequest", json!({
+            "type": "object",
+            "properties": {
+                "labels": string_map(),
+                "note": { "type": "string" }
+            }
+        })),
+        ("WorkflowRun", json!({
+            "type": "object",
+            "required": ["id", "workflow_id", "tenant_id", "status", "steps", "created_at", "updated_at"],
+            "properties": {
+                "id": { "type": "string" },
+                "workflow_id": { "type": "string" },
+                "tenant_id": { "type": "string" },
+                "version": { "type": "integer", "nullable": true },
+                "status": { "type": "string" },
+                "output": {},
+                "error": { "type": "string", "nullable": true },
+                "steps": { "type": "array", "items": { "type": "object", "additionalProperties": {} } },
+                "created_at": { "type": "string" },
+                "updated_at": { "type": "string" }
+            }
+        })),
+    ];
+    Value::Object(schemas.into_iter().map(|(name, schema)| (name.to_string(), schema)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stepflow_openapi::{SdkGenerator, SdkLanguage, SdkOptions};
+
+    #[test]
+    fn test_spec_references_resolve() {
+        let spec = api_spec();
+        let text = spec.to_string();
+        let schemas = spec["components"]["schemas"].as_object().unwrap();
+        for reference in text.split("#/components/schemas/").skip(1) {
+            let name = &reference[..reference.find('"').unwrap()];
+            assert!(schemas.contains_key(name), "unresolved schema {}", name);
+        }
+    }
+
+    #[test]
+    fn test_spec_generates_sdks() {
+        let spec = api_spec();
+        let rust = SdkGenerator::new(SdkOptions::new(SdkLanguage::Rust, "stepflow-client")).generate(&spec).unwrap();
+        let lib = &rust.iter().find(|file| file.path == "src/lib.rs").unwrap().contents;
+        assert!(lib.contains("pub async fn run_execution_sync_stream("));
+        assert!(lib.contains("pub async fn sign_in("));
+
+        let typescript = SdkGenerator::new(SdkOptions::new(SdkLanguage::TypeScript, "@stepflow/client")).generate(&spec).unwrap();
+        assert!(typescript[1].contents.contains("async *runExecutionSyncStream(body: RunSyncRequest)"));
+    }
+}
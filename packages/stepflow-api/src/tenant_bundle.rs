@@ -0,0 +1,738 @@
+//! 租户状态导入导出（灾难恢复）
+//!
+//! 把一个租户的用户、工具及其文档、工具配置、工作流版本和近期执行元数据序列化为可移植的
+//! `tar.zst` 包。包内 `manifest.json` 记录包格式版本、数据库迁移版本以及每个分区文件的
+//! SHA-256 校验和，导入前逐一校验。工具配置中的密钥先用租户数据密钥解密，再用导出口令
+//! 派生的密钥重新加密；导入时用同一口令解密后以目标库的租户数据密钥加密保存，包本身不含
+//! 可直接使用的密钥。
+//!
+//! 导入只写入该租户自己的行：ID、用户名或邮箱已被其他租户使用的行会使整个导入被拒绝，
+//! 已有行按主键更新且只更新属于该租户的行。工具及其文档属于全局目录，只随包导出供参考，
+//! 导入时跳过，需要先通过注册表注册。
+
+use std::collections::{BTreeSet, HashMap};
+use std::io::Read;
+use std::sync::Arc;
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use argon2::Argon2;
+use base64::Engine;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use stepflow_core::{Database, TenantId};
+use stepflow_database::{utils::param, MigrationManager, SqliteDatabase, TenantKeyRepository};
+use tracing::info;
+use crate::errors::{ApiError, ApiResult};
+
+/// 当前包格式版本
+pub const TENANT_BUNDLE_SCHEMA_VERSION: u32 = 1;
+
+/// 包内清单文件名
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// 解压后包的最大字节数
+const MAX_BUNDLE_BYTES: u64 = 512 * 1024 * 1024;
+
+/// 导入时每批插入的行数
+const IMPORT_CHUNK_SIZE: usize = 100;
+
+/// 密钥加密算法标识
+const SECRET_ALGORITHM: &str = "argon2id+aes-256-gcm";
+
+/// 包中的一个分区，对应一张表中属于该租户的行
+struct BundleSection {
+    name: &'static str,
+    table: &'static str,
+    /// 选出租户数据的条件，`?1` 为租户 ID，`?2` 为执行历史起始时间
+    filter: &'static str,
+    /// 条件是否使用执行历史起始时间
+    uses_cutoff: bool,
+    /// 标识行所属租户的列，导入时校验；全局数据（工具）为 `None`，这类分区不导入
+    tenant_column: Option<&'static str>,
+    /// 主键列，导入时按主键更新已有行
+    key: &'static [&'static str],
+    /// 全库唯一、可能与其他租户冲突的列，导入前检查
+    unique_columns: &'static [&'static str],
+    /// 导出时不包含的列
+    excluded_columns: &'static [&'static str],
+    /// 需要重新加密的列
+    secret_column: Option<&'static str>,
+}
+
+/// 分区按导入顺序排列，被引用的表在前
+const SECTIONS: &[BundleSection] = &[
+    BundleSection {
+        name: "tenant",
+        table: "tenants",
+        filter: "id = ?1",
+        uses_cutoff: false,
+        tenant_column: Some("id"),
+        key: &["id"],
+        unique_columns: &[],
+        excluded_columns: &[],
+        secret_column: None,
+    },
+    BundleSection {
+        name: "users",
+        table: "users",
+        filter: "tenant_id = ?1",
+        uses_cutoff: false,
+        tenant_column: Some("tenant_id"),
+        key: &["id"],
+        unique_columns: &["id", "username", "email"],
+        excluded_columns: &[],
+        secret_column: None,
+    },
+    BundleSection {
+        name: "tools",
+        table: "tools",
+        filter: "id IN (SELECT tool_id FROM tool_configs WHERE tenant_id = ?1 \
+                 UNION SELECT tool_id FROM executions WHERE tenant_id = ?1 AND created_at >= ?2)",
+        uses_cutoff: true,
+        tenant_column: None,
+        key: &["id"],
+        unique_columns: &[],
+        excluded_columns: &[],
+        secret_column: None,
+    },
+    BundleSection {
+        name: "tool_docs",
+        table: "tool_docs",
+        filter: "tool_id IN (SELECT tool_id FROM tool_configs WHERE tenant_id = ?1 \
+                 UNION SELECT tool_id FROM executions WHERE tenant_id = ?1 AND created_at >= ?2)",
+        uses_cutoff: true,
+        tenant_column: None,
+        key: &["tool_id", "version"],
+        unique_columns: &[],
+        excluded_columns: &[],
+        secret_column: None,
+    },
+    BundleSection {
+        name: "tool_configs",
+        table: "tool_configs",
+        filter: "tenant_id = ?1",
+        uses_cutoff: false,
+        tenant_column: Some("tenant_id"),
+        key: &["tenant_id", "tool_id"],
+        unique_columns: &[],
+        excluded_columns: &[],
+        secret_column: Some("secrets"),
+    },
+    BundleSection {
+        name: "workflow_versions",
+        table: "workflow_versions",
+        filter: "tenant_id = ?1",
+        uses_cutoff: false,
+        tenant_column: Some("tenant_id"),
+        key: &["tenant_id", "workflow_id", "version"],
+        unique_columns: &[],
+        excluded_columns: &[],
+        secret_column: None,
+    },
+    BundleSection {
+        name: "workflow_migration_policies",
+        table: "workflow_migration_policies",
+        filter: "tenant_id = ?1",
+        uses_cutoff: false,
+        tenant_column: Some("tenant_id"),
+        key: &["tenant_id", "workflow_id"],
+        unique_columns: &[],
+        excluded_columns: &[],
+        secret_column: None,
+    },
+    BundleSection {
+        name: "executions",
+        table: "executions",
+        filter: "tenant_id = ?1 AND created_at >= ?2",
+        uses_cutoff: true,
+        tenant_column: Some("tenant_id"),
+        key: &["id"],
+        unique_columns: &["id"],
+        excluded_columns: &["result"],
+        secret_column: None,
+    },
+];
+
+/// 导出选项
+#[derive(Debug, Clone)]
+pub struct TenantExportOptions {
+    /// 加密密钥用的口令，导入时需要提供同一口令
+    pub passphrase: String,
+    /// 导出多久以内的执行元数据
+    pub execution_history: Duration,
+}
+
+impl TenantExportOptions {
+    pub fn new(passphrase: impl Into<String>) -> Self {
+        Self {
+            passphrase: passphrase.into(),
+            execution_history: Duration::days(30),
+        }
+    }
+}
+
+/// 密钥加密参数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleSecretEncryption {
+    pub algorithm: String,
+    /// 口令派生密钥用的盐（base64）
+    pub salt: String,
+}
+
+/// 分区文件信息
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleSectionInfo {
+    pub name: String,
+    pub file: String,
+    pub rows: usize,
+    /// 文件内容的 SHA-256（十六进制）
+    pub sha256: String,
+}
+
+/// 包清单
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TenantBundleManifest {
+    pub schema_version: u32,
+    /// 导出时数据库的迁移版本
+    pub database_version: u32,
+    pub tenant_id: String,
+    pub exported_at: DateTime<Utc>,
+    pub secrets: BundleSecretEncryption,
+    pub sections: Vec<BundleSectionInfo>,
+}
+
+/// 已通过格式与校验和检查的租户包
+#[derive(Debug, Clone)]
+pub struct TenantBundle {
+    manifest: TenantBundleManifest,
+    files: HashMap<String, Vec<u8>>,
+}
+
+impl TenantBundle {
+    /// 解压并校验包：格式版本、数据库版本和每个分区的校验和
+    pub fn open(bytes: &[u8]) -> ApiResult<Self> {
+        let invalid = |message: String| ApiError::BadRequest(format!("Invalid tenant bundle: {}", message));
+
+        let files = unpack(bytes).map_err(invalid)?;
+        let manifest: TenantBundleManifest = files
+            .get(MANIFEST_FILE)
+            .ok_or_else(|| invalid(format!("missing {}", MANIFEST_FILE)))
+            .and_then(|data| serde_json::from_slice(data).map_err(|e| invalid(e.to_string())))?;
+        if manifest.schema_version > TENANT_BUNDLE_SCHEMA_VERSION {
+            return Err(invalid(format!(
+                "schema version {} is newer than supported version {}",
+                manifest.schema_version, TENANT_BUNDLE_SCHEMA_VERSION
+            )));
+        }
+        if manifest.database_version > MigrationManager::latest_version() {
+            return Err(invalid(format!(
+                "exported from database version {}, this server is at {}",
+                manifest.database_version, MigrationManager::latest_version()
+            )));
+        }
+        for section in &manifest.sections {
+            let data = files.get(&section.file).ok_or_else(|| invalid(format!("missing {}", section.file)))?;
+            if sha256_hex(data) != section.sha256 {
+                return Err(invalid(format!("checksum mismatch for {}", section.file)));
+            }
+        }
+
+        Ok(Self { manifest, files })
+    }
+
+    pub fn manifest(&self) -> &TenantBundleManifest {
+        &self.manifest
+    }
+
+    fn section_rows(&self, name: &str) -> ApiResult<Option<Vec<Map<String, Value>>>> {
+        let Some(info) = self.manifest.sections.iter().find(|section| section.name == name) else {
+            return Ok(None);
+        };
+        let rows = serde_json::from_slice(&self.files[&info.file])
+            .map_err(|e| ApiError::BadRequest(format!("Invalid tenant bundle section {}: {}", name, e)))?;
+        Ok(Some(rows))
+    }
+}
+
+/// 单个分区的导入结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SectionImportReport {
+    pub name: String,
+    pub imported: usize,
+    /// 未导入的全局数据行数
+    #[serde(default)]
+    pub skipped: usize,
+    /// 插入失败的行及原因
+    pub failures: Vec<String>,
+}
+
+/// 导入结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TenantImportReport {
+    pub tenant_id: String,
+    pub schema_version: u32,
+    pub sections: Vec<SectionImportReport>,
+}
+
+/// 租户状态导入导出服务
+pub struct TenantBundleService {
+    db: Arc<SqliteDatabase>,
+}
+
+impl TenantBundleService {
+    pub fn new(db: Arc<SqliteDatabase>) -> Self {
+        Self { db }
+    }
+
+    /// 导出租户状态为 `tar.zst` 包
+    pub async fn export(&self, tenant_id: &TenantId, options: &TenantExportOptions) -> ApiResult<Vec<u8>> {
+        let cutoff = (Utc::now() - options.execution_history).to_rfc3339();
+        let salt: [u8; 16] = rand::random();
+        let cipher = SecretCipher::derive(&options.passphrase, &salt)?;
+        let tenant_keys = TenantKeyRepository::new(self.db.as_ref().clone());
+
+        let mut sections = Vec::new();
+        let mut files = Vec::new();
+        for section in SECTIONS {
+            let mut params = vec![param::text(tenant_id.as_str())];
+            if section.uses_cutoff {
+                params.push(param::text(&cutoff));
+            }
+            let sql = format!("SELECT * FROM {} WHERE {}", section.table, section.filter);
+            let result = self.db.execute(&sql, &params).await?;
+
+            let mut rows = Vec::with_capacity(result.rows.len());
+            for row in result.rows {
+                let mut row: Map<String, Value> = row
+                    .into_iter()
+                    .filter(|(column, _)| !section.excluded_columns.contains(&column.as_str()))
+                    .collect();
+                if let Some(Value::String(secret)) = section.secret_column.and_then(|column| row.get_mut(column)) {
+                    *secret = cipher.encrypt(&tenant_keys.decrypt(tenant_id, secret).await?)?;
+                }
+                rows.push(row);
+            }
+            if section.name == "tenant" && rows.is_empty() {
+                return Err(ApiError::NotFound(format!("Tenant {} not found", tenant_id.as_str())));
+            }
+
+            let file = format!("{}.json", section.name);
+            let data = serde_json::to_vec(&rows)?;
+            sections.push(BundleSectionInfo {
+                name: section.name.to_string(),
+                file: file.clone(),
+                rows: rows.len(),
+                sha256: sha256_hex(&data),
+            });
+            files.push((file, data));
+        }
+
+        let manifest = TenantBundleManifest {
+            schema_version: TENANT_BUNDLE_SCHEMA_VERSION,
+            database_version: MigrationManager::latest_version(),
+            tenant_id: tenant_id.as_str().to_string(),
+            exported_at: Utc::now(),
+            secrets: BundleSecretEncryption {
+                algorithm: SECRET_ALGORITHM.to_string(),
+                salt: base64::engine::general_purpose::STANDARD.encode(salt),
+            },
+            sections,
+        };
+        files.insert(0, (MANIFEST_FILE.to_string(), serde_json::to_vec_pretty(&manifest)?));
+
+        let bundle = pack(&files, manifest.exported_at)?;
+        info!("Exported tenant {} ({} bytes)", tenant_id.as_str(), bundle.len());
+        Ok(bundle)
+    }
+
+    /// 把包中的租户状态写入数据库
+    ///
+    /// 租户、用户、配置、工作流和执行记录按主键更新该租户已有的行；ID、用户名或邮箱属于
+    /// 其他租户的行使导入整体失败。工具及其文档不导入。
+    pub async fn import(&self, bundle: &TenantBundle, passphrase: &str) -> ApiResult<TenantImportReport> {
+        let manifest = bundle.manifest();
+        if manifest.secrets.algorithm != SECRET_ALGORITHM {
+            return Err(ApiError::BadRequest(format!(
+                "Unsupported secret encryption {}", manifest.secrets.algorithm
+            )));
+        }
+        let salt = base64::engine::general_purpose::STANDARD
+            .decode(&manifest.secrets.salt)
+            .map_err(|e| ApiError::BadRequest(format!("Invalid bundle salt: {}", e)))?;
+        let cipher = SecretCipher::derive(passphrase, &salt)?;
+        let tenant_id = TenantId::from_string(manifest.tenant_id.clone());
+        let tenant_keys = TenantKeyRepository::new(self.db.as_ref().clone());
+
+        // 先整体校验并解密，避免口令错误或包被篡改时只导入一部分
+        let mut prepared = Vec::new();
+        let mut sections = Vec::new();
+        for section in SECTIONS {
+            let Some(mut rows) = bundle.section_rows(section.name)? else {
+                continue;
+            };
+            let Some(tenant_column) = section.tenant_column else {
+                sections.push(SectionImportReport {
+                    name: section.name.to_string(),
+                    imported: 0,
+                    skipped: rows.len(),
+                    failures: Vec::new(),
+                });
+                continue;
+            };
+            for row in &mut rows {
+                if row.get(tenant_column).and_then(Value::as_str) != Some(manifest.tenant_id.as_str()) {
+                    return Err(ApiError::BadRequest(format!(
+                        "Section {} contains rows of another tenant", section.name
+                    )));
+                }
+                self.check_not_taken(section, tenant_column, row, &manifest.tenant_id).await?;
+                if let Some(Value::String(secret)) = section.secret_column.and_then(|column| row.get_mut(column)) {
+                    *secret = tenant_keys.encrypt(&tenant_id, &cipher.decrypt(secret)?).await?;
+                }
+            }
+            section_columns(section, &rows)?;
+            prepared.push((section, tenant_column, rows));
+        }
+
+        for (section, tenant_column, rows) in prepared {
+            let columns = section_columns(section, &rows)?;
+            let mut report = SectionImportReport {
+                name: section.name.to_string(),
+                imported: 0,
+                skipped: 0,
+                failures: Vec::new(),
+            };
+            if !columns.is_empty() {
+                let values: Vec<Vec<Value>> = rows
+                    .iter()
+                    .map(|row| columns.iter().map(|column| row.get(*column).cloned().unwrap_or(Value::Null)).collect())
+                    .collect();
+                let prefix = format!(
+                    "INSERT INTO {} ({})",
+                    section.table,
+                    columns.iter().copied().collect::<Vec<_>>().join(", ")
+                );
+                // 已有行只在属于同一租户时更新，不会像 REPLACE 那样删除冲突的行
+                let updates: Vec<String> = columns
+                    .iter()
+                    .filter(|column| !section.key.contains(column))
+                    .map(|column| format!("{} = excluded.{}", column, column))
+                    .collect();
+                let conflict = if updates.is_empty() {
+                    format!("ON CONFLICT({}) DO NOTHING", section.key.join(", "))
+                } else {
+                    format!(
+                        "ON CONFLICT({}) DO UPDATE SET {} WHERE {}.{} = excluded.{}",
+                        section.key.join(", "),
+                        updates.join(", "),
+                        section.table,
+                        tenant_column,
+                        tenant_column,
+                    )
+                };
+                let result = self.db.insert_batch_on_conflict(&prefix, &conflict, &values, IMPORT_CHUNK_SIZE).await?;
+                report.imported = result.inserted;
+                report.failures = result.failures
+                    .into_iter()
+                    .map(|failure| format!("row {}: {}", failure.index, failure.error))
+                    .collect();
+            }
+            sections.push(report);
+        }
+
+        sections.sort_by_key(|report| SECTIONS.iter().position(|section| section.name == report.name));
+        info!("Imported tenant {} from bundle exported at {}", manifest.tenant_id, manifest.exported_at);
+        Ok(TenantImportReport {
+            tenant_id: manifest.tenant_id.clone(),
+            schema_version: manifest.schema_version,
+            sections,
+        })
+    }
+}
+
+impl TenantBundleService {
+    /// 拒绝 ID、用户名或邮箱已被其他租户使用的行
+    async fn check_not_taken(
+        &self,
+        section: &BundleSection,
+        tenant_column: &str,
+        row: &Map<String, Value>,
+        tenant_id: &str,
+    ) -> ApiResult<()> {
+        for column in section.unique_columns {
+            let Some(value) = row.get(*column).and_then(Value::as_str) else {
+                continue;
+            };
+            let sql = format!(
+                "SELECT 1 FROM {} WHERE {} = ?1 AND ({} IS NULL OR {} != ?2) LIMIT 1",
+                section.table, column, tenant_column, tenant_column
+            );
+            let taken = self.db.execute(&sql, &[param::text(value), param::text(tenant_id)]).await?;
+            if !taken.rows.is_empty() {
+                return Err(ApiError::Forbidden(format!(
+                    "Section {} has a row whose {} {} belongs to another tenant", section.name, column, value
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 由口令派生的密钥加密器
+struct SecretCipher(Aes256Gcm);
+
+impl SecretCipher {
+    fn derive(passphrase: &str, salt: &[u8]) -> ApiResult<Self> {
+        if passphrase.is_empty() {
+            return Err(ApiError::BadRequest("Bundle passphrase is required".to_string()));
+        }
+        let mut key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+            .map_err(|e| ApiError::InternalServerError(format!("Failed to derive bundle key: {}", e)))?;
+        let cipher = Aes256Gcm::new_from_slice(&key)
+            .map_err(|e| ApiError::InternalServerError(format!("Invalid bundle key: {}", e)))?;
+        Ok(Self(cipher))
+    }
+
+    /// 加密为 base64(nonce || 密文)
+    fn encrypt(&self, plaintext: &str) -> ApiResult<String> {
+        let nonce: [u8; 12] = rand::random();
+        let mut sealed = self.0
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_bytes())
+            .map_err(|_| ApiError::InternalServerError("Failed to encrypt secrets".to_string()))?;
+        sealed.splice(0..0, nonce);
+        Ok(base64::engine::general_purpose::STANDARD.encode(sealed))
+    }
+
+    fn decrypt(&self, encoded: &str) -> ApiResult<String> {
+        let wrong_passphrase = || ApiError::BadRequest("Wrong bundle passphrase or corrupted secrets".to_string());
+        let sealed = base64::engine::general_purpose::STANDARD.decode(encoded).map_err(|_| wrong_passphrase())?;
+        if sealed.len() < 12 {
+            return Err(wrong_passphrase());
+        }
+        let (nonce, ciphertext) = sealed.split_at(12);
+        let plaintext = self.0.decrypt(Nonce::from_slice(nonce), ciphertext).map_err(|_| wrong_passphrase())?;
+        String::from_utf8(plaintext).map_err(|_| wrong_passphrase())
+    }
+}
+
+pub(crate) fn sha256_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+/// 分区中出现的列，列名必须是标识符且包含全部主键列
+fn section_columns<'a>(section: &BundleSection, rows: &'a [Map<String, Value>]) -> ApiResult<BTreeSet<&'a str>> {
+    let columns: BTreeSet<&str> = rows.iter().flat_map(|row| row.keys().map(String::as_str)).collect();
+    if let Some(column) = columns.iter().find(|column| !is_identifier(column)) {
+        return Err(ApiError::BadRequest(format!("Invalid column {} in section {}", column, section.name)));
+    }
+    if let Some(column) = section.key.iter().find(|column| !rows.is_empty() && !columns.contains(*column)) {
+        return Err(ApiError::BadRequest(format!("Section {} is missing key column {}", section.name, column)));
+    }
+    Ok(columns)
+}
+
+fn is_identifier(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// 打包为 `tar.zst`
+pub(crate) fn pack(files: &[(String, Vec<u8>)], modified_at: DateTime<Utc>) -> ApiResult<Vec<u8>> {
+    let io_error = |e: std::io::Error| ApiError::InternalServerError(format!("Failed to write tenant bundle: {}", e));
+    let mut builder = tar::Builder::new(Vec::new());
+    for (name, data) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(modified_at.timestamp().max(0) as u64);
+        builder.append_data(&mut header, name, data.as_slice()).map_err(io_error)?;
+    }
+    let archive = builder.into_inner().map_err(io_error)?;
+    zstd::encode_all(archive.as_slice(), 3).map_err(io_error)
+}
+
+/// 解压 `tar.zst`，返回文件名到内容的映射
+pub(crate) fn unpack(bytes: &[u8]) -> Result<HashMap<String, Vec<u8>>, String> {
+    let mut archive = Vec::new();
+    zstd::stream::read::Decoder::new(bytes)
+        .map_err(|e| e.to_string())?
+        .take(MAX_BUNDLE_BYTES + 1)
+        .read_to_end(&mut archive)
+        .map_err(|e| e.to_string())?;
+    if archive.len() as u64 > MAX_BUNDLE_BYTES {
+        return Err(format!("larger than {} bytes", MAX_BUNDLE_BYTES));
+    }
+
+    let mut files = HashMap::new();
+    let mut entries = tar::Archive::new(archive.as_slice());
+    for entry in entries.entries().map_err(|e| e.to_string())? {
+        let mut entry = entry.map_err(|e| e.to_string())?;
+        let path = entry.path().map_err(|e| e.to_string())?.to_string_lossy().into_owned();
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data).map_err(|e| e.to_string())?;
+        files.insert(path, data);
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn setup() -> Arc<SqliteDatabase> {
+        let db = Arc::new(SqliteDatabase::new(":memory:").await.unwrap());
+        MigrationManager::run_migrations(&db).await.unwrap();
+        let now = Utc::now().to_rfc3339();
+        db.execute(
+            "INSERT INTO tenants (id, name, created_at, updated_at) VALUES ('acme', 'Acme', ?1, ?1)",
+            &[param::text(&now)],
+        ).await.unwrap();
+        db.execute(
+            "INSERT INTO users (id, username, email, password_hash, role, tenant_id, created_at, updated_at) \
+             VALUES ('u1', 'alice', 'alice@acme.test', 'hash', 'admin', 'acme', ?1, ?1)",
+            &[param::text(&now)],
+        ).await.unwrap();
+        db.execute(
+            "INSERT INTO tools (id, name, version_major, version_minor, version_patch, tool_type, status, author, created_at, updated_at) \
+             VALUES ('t1', 'Tool', 1, 0, 0, 'python', 'active', 'alice', ?1, ?1)",
+            &[param::text(&now)],
+        ).await.unwrap();
+        db.execute(
+            "INSERT INTO tool_configs (tenant_id, tool_id, configuration, environment, secrets, created_at, updated_at) \
+             VALUES ('acme', 't1', '{}', '{}', '{\"API_KEY\":\"s3cret\"}', ?1, ?1)",
+            &[param::text(&now)],
+        ).await.unwrap();
+        db.execute(
+            "INSERT INTO tool_docs (tool_id, version, markdown, updated_by, updated_at) VALUES ('t1', '1.0.0', '# Tool', 'u1', ?1)",
+            &[param::text(&now)],
+        ).await.unwrap();
+        db
+    }
+
+    #[tokio::test]
+    async fn test_export_import_roundtrip() {
+        let source = setup().await;
+        let bundle = TenantBundleService::new(source)
+            .export(&TenantId::from_string("acme".to_string()), &TenantExportOptions::new("correct horse"))
+            .await
+            .unwrap();
+
+        let opened = TenantBundle::open(&bundle).unwrap();
+        assert_eq!(opened.manifest().tenant_id, "acme");
+        let configs = opened.section_rows("tool_configs").unwrap().unwrap();
+        assert!(!configs[0]["secrets"].as_str().unwrap().contains("s3cret"));
+
+        let target = target_with_tool().await;
+        let service = TenantBundleService::new(target.clone());
+        assert!(matches!(service.import(&opened, "wrong").await, Err(ApiError::BadRequest(_))));
+        assert!(target.execute("SELECT id FROM users WHERE tenant_id = 'acme'", &[]).await.unwrap().rows.is_empty());
+
+        let report = service.import(&opened, "correct horse").await.unwrap();
+        assert!(report.sections.iter().all(|section| section.failures.is_empty()));
+        let tools = report.sections.iter().find(|section| section.name == "tools").unwrap();
+        assert_eq!((tools.imported, tools.skipped), (0, 1));
+        let secrets = target.execute("SELECT secrets FROM tool_configs WHERE tenant_id = 'acme'", &[]).await.unwrap();
+        assert_eq!(secrets.rows[0]["secrets"], Value::String("{\"API_KEY\":\"s3cret\"}".to_string()));
+        assert_eq!(target.execute("SELECT id FROM users WHERE tenant_id = 'acme'", &[]).await.unwrap().rows.len(), 1);
+        // 全局的工具文档不导入
+        assert!(target.execute("SELECT markdown FROM tool_docs", &[]).await.unwrap().rows.is_empty());
+
+        // 再次导入时更新已有行
+        let report = service.import(&opened, "correct horse").await.unwrap();
+        assert!(report.sections.iter().all(|section| section.failures.is_empty()));
+        assert_eq!(target.execute("SELECT id FROM users WHERE tenant_id = 'acme'", &[]).await.unwrap().rows.len(), 1);
+    }
+
+    /// 已注册工具 t1、并有另一个租户 other 及其用户 bob 的目标库
+    async fn target_with_tool() -> Arc<SqliteDatabase> {
+        let db = Arc::new(SqliteDatabase::new(":memory:").await.unwrap());
+        MigrationManager::run_migrations(&db).await.unwrap();
+        let now = Utc::now().to_rfc3339();
+        db.execute(
+            "INSERT INTO tenants (id, name, created_at, updated_at) VALUES ('other', 'Other', ?1, ?1)",
+            &[param::text(&now)],
+        ).await.unwrap();
+        db.execute(
+            "INSERT INTO users (id, username, email, password_hash, role, tenant_id, created_at, updated_at) \
+             VALUES ('u2', 'bob', 'bob@other.test', 'bob-hash', 'admin', 'other', ?1, ?1)",
+            &[param::text(&now)],
+        ).await.unwrap();
+        db.execute(
+            "INSERT INTO tools (id, name, version_major, version_minor, version_patch, tool_type, status, author, created_at, updated_at) \
+             VALUES ('t1', 'Tool', 1, 0, 0, 'python', 'active', 'alice', ?1, ?1)",
+            &[param::text(&now)],
+        ).await.unwrap();
+        db
+    }
+
+    /// 修改包中一个分区的行并重新计算校验和，模拟租户管理员自行构造的包
+    fn rewrite_section(bundle: &[u8], name: &str, edit: impl FnOnce(&mut Vec<Map<String, Value>>)) -> Vec<u8> {
+        let mut files = unpack(bundle).unwrap();
+        let mut manifest: TenantBundleManifest = serde_json::from_slice(&files[MANIFEST_FILE]).unwrap();
+        let info = manifest.sections.iter_mut().find(|section| section.name == name).unwrap();
+        let mut rows: Vec<Map<String, Value>> = serde_json::from_slice(&files[&info.file]).unwrap();
+        edit(&mut rows);
+        let data = serde_json::to_vec(&rows).unwrap();
+        info.rows = rows.len();
+        info.sha256 = sha256_hex(&data);
+        files.insert(info.file.clone(), data);
+        files.insert(MANIFEST_FILE.to_string(), serde_json::to_vec(&manifest).unwrap());
+        pack(&files.into_iter().collect::<Vec<_>>(), manifest.exported_at).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_crafted_bundle_cannot_touch_other_tenants() {
+        let bundle = TenantBundleService::new(setup().await)
+            .export(&TenantId::from_string("acme".to_string()), &TenantExportOptions::new("pw"))
+            .await
+            .unwrap();
+        let target = target_with_tool().await;
+        let service = TenantBundleService::new(target.clone());
+        let bob = || async {
+            target.execute("SELECT tenant_id, password_hash FROM users WHERE username = 'bob'", &[]).await.unwrap().rows
+        };
+
+        // 复用其他租户用户的用户名、邮箱或 ID 都会被拒绝，且不写入任何行
+        for (column, value) in [("username", "bob"), ("email", "bob@other.test"), ("id", "u2")] {
+            let crafted = rewrite_section(&bundle, "users", |rows| {
+                rows[0].insert(column.to_string(), Value::String(value.to_string()));
+                rows[0].insert("password_hash".to_string(), Value::String("attacker".to_string()));
+            });
+            let error = service.import(&TenantBundle::open(&crafted).unwrap(), "pw").await.unwrap_err();
+            assert!(matches!(error, ApiError::Forbidden(_)), "{}: {}", column, error);
+            assert!(target.execute("SELECT id FROM users WHERE tenant_id = 'acme'", &[]).await.unwrap().rows.is_empty());
+        }
+        let rows = bob().await;
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0]["tenant_id"], Value::String("other".to_string()));
+        assert_eq!(rows[0]["password_hash"], Value::String("bob-hash".to_string()));
+
+        // 包中的工具不会写入全局目录
+        let crafted = rewrite_section(&bundle, "tools", |rows| {
+            rows[0].insert("id".to_string(), Value::String("evil".to_string()));
+        });
+        service.import(&TenantBundle::open(&crafted).unwrap(), "pw").await.unwrap();
+        assert!(target.execute("SELECT id FROM tools WHERE id = 'evil'", &[]).await.unwrap().rows.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_tampered_bundle_rejected() {
+        let db = setup().await;
+        let bundle = TenantBundleService::new(db)
+            .export(&TenantId::from_string("acme".to_string()), &TenantExportOptions::new("pw"))
+            .await
+            .unwrap();
+
+        let mut archive = zstd::decode_all(bundle.as_slice()).unwrap();
+        let needle = b"alice@acme.test";
+        let at = archive.windows(needle.len()).position(|window| window == needle).unwrap();
+        archive[at] = b'm';
+        let tampered = zstd::encode_all(archive.as_slice(), 3).unwrap();
+
+        let error = TenantBundle::open(&tampered).unwrap_err();
+        assert!(error.to_string().contains("checksum mismatch"));
+    }
+}
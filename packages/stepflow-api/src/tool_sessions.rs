@@ -0,0 +1,330 @@
+//! 交互式执行会话（与登录会话无关）
+//!
+//! 会话为 REPL 类工具保持一个长期存活的沙箱：每次输入作为会话命令的最后一个参数在同一个
+//! 沙箱中按顺序执行，沙箱内的状态在输入之间保留，输出同时推送给会话的订阅者。会话空闲超时
+//! 后自动关闭，每个租户同时打开的会话数受配额限制。
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use stepflow_core::{TenantId, ToolId, UserId};
+use stepflow_sandbox::{Command, IntegrityVerifier, Sandbox, SandboxConfig, SandboxId};
+use tokio::sync::{broadcast, RwLock};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::errors::{ApiError, ApiResult};
+
+/// 每个会话缓冲的待推送事件数，订阅者落后更多时丢失最早的事件
+const EVENT_BUFFER: usize = 64;
+
+/// 会话配置
+#[derive(Debug, Clone)]
+pub struct ToolSessionConfig {
+    /// 每个租户同时打开的会话数上限
+    pub max_sessions_per_tenant: usize,
+    /// 按租户 ID 覆盖的会话数上限
+    pub tenant_quotas: HashMap<String, usize>,
+    /// 会话无输入多久后自动关闭
+    pub idle_timeout: Duration,
+    /// 会话沙箱的配置
+    pub sandbox_config: SandboxConfig,
+}
+
+impl Default for ToolSessionConfig {
+    fn default() -> Self {
+        Self {
+            max_sessions_per_tenant: 5,
+            tenant_quotas: HashMap::new(),
+            idle_timeout: Duration::from_secs(10 * 60),
+            sandbox_config: SandboxConfig::default(),
+        }
+    }
+}
+
+impl ToolSessionConfig {
+    /// 租户的会话数上限
+    pub fn quota(&self, tenant_id: &str) -> usize {
+        self.tenant_quotas.get(tenant_id).copied().unwrap_or(self.max_sessions_per_tenant)
+    }
+}
+
+/// 会话概况
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolSessionInfo {
+    pub id: String,
+    pub tenant_id: TenantId,
+    pub tool_id: ToolId,
+    pub user_id: UserId,
+    /// 会话命令，每次输入追加为最后一个参数
+    pub command: Vec<String>,
+    /// 已处理的输入数
+    pub inputs: u64,
+    pub created_at: DateTime<Utc>,
+    pub last_active_at: DateTime<Utc>,
+    pub idle_timeout_secs: u64,
+}
+
+/// 一次输入的输出
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolSessionOutput {
+    pub session_id: String,
+    /// 从 1 开始的输入序号
+    pub sequence: u64,
+    pub exit_code: i32,
+    pub stdout: String,
+    pub stderr: String,
+    pub duration_ms: u64,
+}
+
+/// 推送给会话订阅者的事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ToolSessionEvent {
+    Output(ToolSessionOutput),
+    Closed { session_id: String, reason: String },
+}
+
+struct ToolSession {
+    info: Mutex<ToolSessionInfo>,
+    sandbox_id: SandboxId,
+    environment: HashMap<String, String>,
+    last_active: Mutex<Instant>,
+    /// 保证输入按顺序执行
+    turn: tokio::sync::Mutex<()>,
+    events: broadcast::Sender<ToolSessionEvent>,
+}
+
+impl ToolSession {
+    fn info(&self) -> ToolSessionInfo {
+        self.info.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn idle_for(&self) -> Duration {
+        self.last_active.lock().unwrap_or_else(|e| e.into_inner()).elapsed()
+    }
+}
+
+/// 会话管理器
+pub struct ToolSessionManager {
+    sandbox: Arc<dyn Sandbox>,
+    integrity: Option<Arc<IntegrityVerifier>>,
+    config: ToolSessionConfig,
+    sessions: RwLock<HashMap<String, Arc<ToolSession>>>,
+}
+
+impl ToolSessionManager {
+    pub fn new(sandbox: Arc<dyn Sandbox>, config: ToolSessionConfig) -> Self {
+        Self { sandbox, integrity: None, config, sessions: RwLock::new(HashMap::new()) }
+    }
+
+    /// 创建会话沙箱前按工具固定的摘要校验镜像；校验器应与会话使用同一个沙箱
+    pub fn with_integrity_verifier(mut self, integrity: Arc<IntegrityVerifier>) -> Self {
+        self.integrity = Some(integrity);
+        self
+    }
+
+    pub fn config(&self) -> &ToolSessionConfig {
+        &self.config
+    }
+
+    /// 为工具打开会话，创建会话沙箱
+    ///
+    /// `session_id` 由调用方生成，以便在此之前注入会话环境变量。
+    pub async fn create(
+        &self,
+        session_id: String,
+        tenant_id: &TenantId,
+        user_id: &UserId,
+        tool_id: ToolId,
+        command: Vec<String>,
+        environment: HashMap<String, String>,
+    ) -> ApiResult<ToolSessionInfo> {
+        if command.first().is_none_or(|program| program.trim().is_empty()) {
+            return Err(ApiError::ValidationError("ToolSession command must start with a program".to_string()));
+        }
+        self.reap_idle().await;
+        let quota = self.config.quota(tenant_id.as_str());
+        if self.tenant_sessions(tenant_id).await >= quota {
+            return Err(ApiError::Conflict(format!("Tenant already has the maximum of {} open sessions", quota)));
+        }
+
+        let mut sandbox_config = self.config.sandbox_config.clone();
+        sandbox_config.environment.extend(environment.clone());
+        let sandbox_id = match &self.integrity {
+            Some(integrity) => integrity.create_sandbox(tool_id.as_str(), sandbox_config).await?,
+            None => self.sandbox.create_sandbox(sandbox_config).await?,
+        };
+        let now = Utc::now();
+        let info = ToolSessionInfo {
+            id: session_id,
+            tenant_id: tenant_id.clone(),
+            tool_id,
+            user_id: user_id.clone(),
+            command,
+            inputs: 0,
+            created_at: now,
+            last_active_at: now,
+            idle_timeout_secs: self.config.idle_timeout.as_secs(),
+        };
+        let session = Arc::new(ToolSession {
+            info: Mutex::new(info.clone()),
+            sandbox_id,
+            environment,
+            last_active: Mutex::new(Instant::now()),
+            turn: tokio::sync::Mutex::new(()),
+            events: broadcast::channel(EVENT_BUFFER).0,
+        });
+
+        // 并发创建时以写锁下的计数为准
+        let rejected = {
+            let mut sessions = self.sessions.write().await;
+            let open = sessions.values().filter(|session| session.info().tenant_id == *tenant_id).count();
+            if open >= quota {
+                true
+            } else {
+                sessions.insert(info.id.clone(), session.clone());
+                false
+            }
+        };
+        if rejected {
+            self.destroy(&session).await;
+            return Err(ApiError::Conflict(format!("Tenant already has the maximum of {} open sessions", quota)));
+        }
+        info!("Opened session {} for tool {} in tenant {}", info.id, info.tool_id, tenant_id);
+        Ok(info)
+    }
+
+    /// 获取租户的会话，空闲超时的会话视为不存在
+    pub async fn get(&self, tenant_id: &TenantId, session_id: &str) -> ApiResult<ToolSessionInfo> {
+        Ok(self.session(tenant_id, session_id).await?.info())
+    }
+
+    /// 租户的会话，`user_id` 不为空时只返回该用户的会话；按创建时间排序
+    pub async fn list(&self, tenant_id: &TenantId, user_id: Option<&UserId>) -> Vec<ToolSessionInfo> {
+        self.reap_idle().await;
+        let mut sessions: Vec<ToolSessionInfo> = self.sessions.read().await
+            .values()
+            .map(|session| session.info())
+            .filter(|info| info.tenant_id == *tenant_id && user_id.is_none_or(|user_id| info.user_id == *user_id))
+            .collect();
+        sessions.sort_by_key(|s| s.created_at);
+        sessions
+    }
+
+    /// 在会话沙箱中执行一次输入，同一会话的输入依次执行
+    pub async fn send(&self, tenant_id: &TenantId, session_id: &str, input: String) -> ApiResult<ToolSessionOutput> {
+        let session = self.session(tenant_id, session_id).await?;
+        let _turn = session.turn.lock().await;
+        // 等待期间会话可能已被关闭
+        if !self.sessions.read().await.contains_key(session_id) {
+            return Err(ApiError::Conflict(format!("ToolSession {} is closed", session_id)));
+        }
+
+        let mut args = session.info().command;
+        let program = args.remove(0);
+        args.push(input);
+        let mut command = Command::new(program).with_args(args);
+        command.environment = session.environment.clone();
+
+        *session.last_active.lock().unwrap_or_else(|e| e.into_inner()) = Instant::now();
+        let started = Instant::now();
+        let result = self.sandbox.execute_in_sandbox(&session.sandbox_id, command).await;
+        *session.last_active.lock().unwrap_or_else(|e| e.into_inner()) = Instant::now();
+        let result = result?;
+
+        let sequence = {
+            let mut info = session.info.lock().unwrap_or_else(|e| e.into_inner());
+            info.inputs += 1;
+            info.last_active_at = Utc::now();
+            info.inputs
+        };
+        let output = ToolSessionOutput {
+            session_id: session_id.to_string(),
+            sequence,
+            exit_code: result.exit_code,
+            stdout: result.stdout,
+            stderr: result.stderr,
+            duration_ms: started.elapsed().as_millis() as u64,
+        };
+        // 没有订阅者时发送失败，可以忽略
+        let _ = session.events.send(ToolSessionEvent::Output(output.clone()));
+        Ok(output)
+    }
+
+    /// 订阅会话事件：之后每次输入的输出，以及会话关闭
+    pub async fn subscribe(&self, tenant_id: &TenantId, session_id: &str) -> ApiResult<broadcast::Receiver<ToolSessionEvent>> {
+        Ok(self.session(tenant_id, session_id).await?.events.subscribe())
+    }
+
+    /// 关闭会话并销毁其沙箱
+    pub async fn close(&self, tenant_id: &TenantId, session_id: &str) -> ApiResult<ToolSessionInfo> {
+        let session = self.session(tenant_id, session_id).await?;
+        // 等待进行中的输入完成
+        let _turn = session.turn.lock().await;
+        if self.sessions.write().await.remove(session_id).is_none() {
+            return Err(ApiError::NotFound(format!("ToolSession {} not found", session_id)));
+        }
+        self.finish(&session, "closed").await;
+        Ok(session.info())
+    }
+
+    /// 关闭空闲超时的会话，返回关闭的数量
+    pub async fn reap_idle(&self) -> usize {
+        let expired: Vec<Arc<ToolSession>> = {
+            let mut sessions = self.sessions.write().await;
+            let ids: Vec<String> = sessions
+                .iter()
+                // 正在执行输入的会话不算空闲
+                .filter(|(_, session)| session.idle_for() >= self.config.idle_timeout && session.turn.try_lock().is_ok())
+                .map(|(id, _)| id.clone())
+                .collect();
+            ids.iter().filter_map(|id| sessions.remove(id)).collect()
+        };
+        for session in &expired {
+            info!("Closing session {} after {:?} idle", session.info().id, self.config.idle_timeout);
+            self.finish(session, "idle_timeout").await;
+        }
+        expired.len()
+    }
+
+    /// 启动定期关闭空闲会话的任务
+    pub fn spawn_reaper(self: &Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        let manager = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                manager.reap_idle().await;
+            }
+        })
+    }
+
+    async fn session(&self, tenant_id: &TenantId, session_id: &str) -> ApiResult<Arc<ToolSession>> {
+        self.reap_idle().await;
+        self.sessions.read().await
+            .get(session_id)
+            .filter(|session| session.info().tenant_id == *tenant_id)
+            .cloned()
+            .ok_or_else(|| ApiError::NotFound(format!("ToolSession {} not found", session_id)))
+    }
+
+    async fn tenant_sessions(&self, tenant_id: &TenantId) -> usize {
+        self.sessions.read().await.values().filter(|session| session.info().tenant_id == *tenant_id).count()
+    }
+
+    async fn finish(&self, session: &ToolSession, reason: &str) {
+        let session_id = session.info().id;
+        let _ = session.events.send(ToolSessionEvent::Closed { session_id, reason: reason.to_string() });
+        self.destroy(session).await;
+    }
+
+    async fn destroy(&self, session: &ToolSession) {
+        if let Err(e) = self.sandbox.destroy_sandbox(&session.sandbox_id).await {
+            warn!("Failed to destroy session sandbox {}: {}", session.sandbox_id, e);
+        }
+    }
+}
@@ -0,0 +1,223 @@
+//! 基于 TOTP 的双因素认证
+//!
+//! 用户先生成密钥（返回 otpauth URI，供身份验证器扫码），再提交一次验证码
+//! 完成绑定，同时获得一组一次性恢复码。租户可通过设置
+//! [`REQUIRE_ADMIN_2FA_SETTING`] 要求管理员必须启用双因素认证。
+
+use rand::{distributions::Alphanumeric, Rng};
+use stepflow_core::{TenantId, UserId};
+use stepflow_database::{SqliteDatabase, TenantRepository, TotpEnrollment, UserRepository};
+use totp_rs::{Algorithm, Secret, TOTP};
+use crate::errors::{ApiError, ApiResult};
+
+/// otpauth URI 中显示的签发方
+pub const TOTP_ISSUER: &str = "Stepflow";
+
+/// 租户设置项：为 true 时管理员必须启用双因素认证
+pub const REQUIRE_ADMIN_2FA_SETTING: &str = "require_2fa_for_admins";
+
+/// 每次生成的恢复码数量
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+/// 生成新的 TOTP 密钥（Base32 编码）
+pub fn generate_totp_secret() -> String {
+    Secret::generate_secret().to_encoded().to_string()
+}
+
+fn build_totp(secret: &str, account: &str) -> ApiResult<TOTP> {
+    let secret = Secret::Encoded(secret.to_string())
+        .to_bytes()
+        .map_err(|e| ApiError::InternalServerError(format!("Invalid TOTP secret: {:?}", e)))?;
+    // 账户名中不允许出现冒号
+    TOTP::new(Algorithm::SHA1, 6, 1, 30, secret, Some(TOTP_ISSUER.to_string()), account.replace(':', "_"))
+        .map_err(|e| ApiError::InternalServerError(format!("Invalid TOTP parameters: {}", e)))
+}
+
+/// 生成身份验证器使用的 otpauth URI（也是二维码的内容）
+pub fn otpauth_uri(secret: &str, account: &str) -> ApiResult<String> {
+    Ok(build_totp(secret, account)?.get_url())
+}
+
+/// 校验 TOTP 验证码（允许前后各一个步长），返回验证码所属的时间步，不匹配时返回 `None`
+///
+/// 只校验验证码本身；防重放需通过 [`accept_totp`] 记录已使用的时间步。
+pub fn verify_totp(secret: &str, account: &str, code: &str) -> ApiResult<Option<u64>> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| ApiError::InternalServerError(e.to_string()))?
+        .as_secs();
+    Ok(matching_step(&build_totp(secret, account)?, code.trim(), now))
+}
+
+/// 在 `time` 前后的允许窗口内查找验证码对应的时间步（优先匹配最新的时间步）
+fn matching_step(totp: &TOTP, code: &str, time: u64) -> Option<u64> {
+    let current = time / totp.step;
+    let skew = totp.skew as u64;
+    (current.saturating_sub(skew)..=current + skew)
+        .rev()
+        .find(|step| totp.generate(step * totp.step) == code)
+}
+
+/// 校验 TOTP 验证码并记录其时间步
+///
+/// 同一时间步及更早时间步的验证码只能使用一次，已使用过的验证码会被拒绝。
+pub async fn accept_totp(
+    db: &SqliteDatabase,
+    user_id: &UserId,
+    account: &str,
+    secret: &str,
+    code: &str,
+) -> ApiResult<bool> {
+    match verify_totp(secret, account, code)? {
+        Some(step) => Ok(UserRepository::new(db.clone()).record_totp_step(user_id, step).await?),
+        None => Ok(false),
+    }
+}
+
+/// 生成一组形如 `abcde-12345` 的恢复码
+pub fn generate_recovery_codes() -> Vec<String> {
+    let mut rng = rand::thread_rng();
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let raw: String = (&mut rng)
+                .sample_iter(&Alphanumeric)
+                .take(10)
+                .map(|c| char::from(c).to_ascii_lowercase())
+                .collect();
+            format!("{}-{}", &raw[..5], &raw[5..])
+        })
+        .collect()
+}
+
+/// 校验第二因素：6 位数字按 TOTP 校验，其他输入按恢复码校验（两者均只能使用一次）
+pub async fn verify_second_factor(
+    db: &SqliteDatabase,
+    user_id: &UserId,
+    account: &str,
+    enrollment: &TotpEnrollment,
+    code: &str,
+) -> ApiResult<bool> {
+    let code = code.trim();
+    if code.len() == 6 && code.chars().all(|c| c.is_ascii_digit()) {
+        return accept_totp(db, user_id, account, &enrollment.secret, code).await;
+    }
+    Ok(UserRepository::new(db.clone()).consume_recovery_code(user_id, code).await?)
+}
+
+/// 租户是否要求管理员启用双因素认证
+pub async fn tenant_requires_admin_2fa(db: &SqliteDatabase, tenant_id: &TenantId) -> ApiResult<bool> {
+    let tenant = TenantRepository::new(db.clone()).get_tenant(tenant_id).await?;
+    Ok(tenant
+        .and_then(|tenant| tenant.settings.get(REQUIRE_ADMIN_2FA_SETTING).and_then(|v| v.as_bool()))
+        .unwrap_or(false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use chrono::Utc;
+    use stepflow_core::{TenantInfo, UserInfo, UserRole};
+    use stepflow_database::MigrationManager;
+
+    const SECRET: &str = "JBSWY3DPEHPK3PXPJBSWY3DPEHPK3PXP";
+
+    async fn setup(settings: HashMap<String, serde_json::Value>) -> (SqliteDatabase, UserInfo) {
+        let db = SqliteDatabase::new("sqlite::memory:").await.unwrap();
+        MigrationManager::run_migrations(&db).await.unwrap();
+        let tenant_id = TenantId::new();
+        TenantRepository::new(db.clone()).create_tenant(&TenantInfo {
+            id: tenant_id.clone(),
+            name: "2FA Tenant".to_string(),
+            description: String::new(),
+            domain: None,
+            settings,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }).await.unwrap();
+        let user = UserInfo {
+            id: UserId::new(),
+            username: "alice".to_string(),
+            email: "alice@example.com".to_string(),
+            role: UserRole::Admin,
+            tenant_id,
+            settings: HashMap::new(),
+            email_verified: true,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let users = UserRepository::new(db.clone());
+        users.register_user(&user, "Password123").await.unwrap();
+        users.set_pending_totp_secret(&user.id, SECRET).await.unwrap();
+        users.enable_totp(&user.id).await.unwrap();
+        (db, user)
+    }
+
+    fn now() -> u64 {
+        std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs()
+    }
+
+    #[test]
+    fn test_matching_step_allows_one_step_of_skew() {
+        let totp = build_totp(SECRET, "alice").unwrap();
+        let time = 1_700_000_000;
+        let step = time / totp.step;
+        for offset in [-1i64, 0, 1] {
+            let code_step = (step as i64 + offset) as u64;
+            let code = totp.generate(code_step * totp.step);
+            assert_eq!(matching_step(&totp, &code, time), Some(code_step));
+        }
+        assert_eq!(matching_step(&totp, &totp.generate((step - 2) * totp.step), time), None);
+        assert_eq!(matching_step(&totp, &totp.generate((step + 2) * totp.step), time), None);
+        assert_eq!(matching_step(&totp, "abcdef", time), None);
+    }
+
+    #[test]
+    fn test_otpauth_uri_and_recovery_codes() {
+        let uri = otpauth_uri(SECRET, "bob:admin").unwrap();
+        assert!(uri.starts_with("otpauth://totp/Stepflow:bob_admin?"));
+        assert!(uri.contains(&format!("secret={}", SECRET)));
+
+        let codes = generate_recovery_codes();
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+        assert!(codes.iter().all(|code| code.len() == 11 && code.chars().nth(5) == Some('-')));
+    }
+
+    #[tokio::test]
+    async fn test_totp_code_cannot_be_replayed() {
+        let (db, user) = setup(HashMap::new()).await;
+        let enrollment = UserRepository::new(db.clone()).get_totp(&user.id).await.unwrap().unwrap();
+        let totp = build_totp(SECRET, &user.username).unwrap();
+        let previous = totp.generate(now() - totp.step);
+        let current = totp.generate(now());
+
+        assert!(verify_second_factor(&db, &user.id, &user.username, &enrollment, &previous).await.unwrap());
+        assert!(verify_second_factor(&db, &user.id, &user.username, &enrollment, &current).await.unwrap());
+        // 已使用的验证码以及更早时间步的验证码都被拒绝
+        assert!(!verify_second_factor(&db, &user.id, &user.username, &enrollment, &current).await.unwrap());
+        assert!(!verify_second_factor(&db, &user.id, &user.username, &enrollment, &previous).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_recovery_codes_are_single_use() {
+        let (db, user) = setup(HashMap::new()).await;
+        let enrollment = UserRepository::new(db.clone()).get_totp(&user.id).await.unwrap().unwrap();
+        let codes = generate_recovery_codes();
+        UserRepository::new(db.clone()).replace_recovery_codes(&user.id, &codes).await.unwrap();
+
+        assert!(verify_second_factor(&db, &user.id, &user.username, &enrollment, &codes[0]).await.unwrap());
+        assert!(!verify_second_factor(&db, &user.id, &user.username, &enrollment, &codes[0]).await.unwrap());
+        assert!(!verify_second_factor(&db, &user.id, &user.username, &enrollment, "zzzzz-00000").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_tenant_requires_admin_2fa() {
+        let (db, user) = setup(HashMap::from([
+            (REQUIRE_ADMIN_2FA_SETTING.to_string(), serde_json::Value::Bool(true)),
+        ])).await;
+        assert!(tenant_requires_admin_2fa(&db, &user.tenant_id).await.unwrap());
+
+        let (db, user) = setup(HashMap::new()).await;
+        assert!(!tenant_requires_admin_2fa(&db, &user.tenant_id).await.unwrap());
+    }
+}
@@ -253,15 +253,88 @@ impl Default for ServerConfig {
     }
 }
 
+impl ServerConfig {
+    /// 校验配置中不安全的组合，应在服务启动前调用
+    pub fn validate(&self) -> crate::errors::ApiResult<()> {
+        let violations = self.cors_config.violations();
+        if !violations.is_empty() {
+            return Err(crate::errors::ApiError::ValidationError(format!(
+                "Invalid CORS configuration: {}",
+                violations.join("; ")
+            )));
+        }
+        Ok(())
+    }
+}
+
 /// CORS 配置
-#[derive(Debug, Clone)]
+///
+/// 来源支持 `*`（任意来源）以及 `https://*.example.com` 形式的子域名通配。
+/// 租户可在设置的 [`CORS_POLICY_SETTING`] 中保存自己的策略，对指向租户域名的请求生效。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct CorsConfig {
     pub allow_origins: Vec<String>,
     pub allow_methods: Vec<HttpMethod>,
     pub allow_headers: Vec<String>,
     pub expose_headers: Vec<String>,
     pub allow_credentials: bool,
+    /// 预检结果的缓存时长，JSON 中以整数秒表示（`max_age_secs`，也接受 `max_age`）
+    #[serde(rename = "max_age_secs", alias = "max_age", with = "optional_secs")]
     pub max_age: Option<std::time::Duration>,
+    /// 是否允许租户通过设置覆盖部署级策略
+    pub allow_tenant_overrides: bool,
+}
+
+/// 租户设置中保存 CORS 策略的键
+pub const CORS_POLICY_SETTING: &str = "cors_policy";
+
+/// 租户设置中保存网络访问控制列表（[`stepflow_core::AclRules`]）的键
+pub const NETWORK_ACL_SETTING: &str = "network_acl";
+
+/// 预检缓存时长上限（秒），与主流浏览器的上限一致
+pub const MAX_CORS_MAX_AGE_SECS: u64 = 86400;
+
+impl CorsConfig {
+    /// 检查策略，返回所有不安全或无效的配置项；为空表示策略可用
+    pub fn violations(&self) -> Vec<String> {
+        let mut violations = Vec::new();
+        for origin in &self.allow_origins {
+            if let Err(reason) = validate_origin_pattern(origin) {
+                violations.push(format!("origin '{}' {}", origin, reason));
+            }
+        }
+        if self.allow_credentials && self.allow_origins.iter().any(|origin| origin == "*") {
+            violations.push("wildcard origin '*' cannot be combined with credentials".to_string());
+        }
+        if self.allow_credentials && self.allow_headers.iter().any(|header| header == "*") {
+            violations.push("wildcard header '*' cannot be combined with credentials".to_string());
+        }
+        for header in self.allow_headers.iter().chain(&self.expose_headers) {
+            if header != "*" && axum::http::HeaderName::from_bytes(header.as_bytes()).is_err() {
+                violations.push(format!("header '{}' is not a valid header name", header));
+            }
+        }
+        if self.max_age.is_some_and(|max_age| max_age.as_secs() > MAX_CORS_MAX_AGE_SECS) {
+            violations.push(format!("max age must not exceed {} seconds", MAX_CORS_MAX_AGE_SECS));
+        }
+        violations
+    }
+
+    /// 来源是否被允许
+    pub fn allows_origin(&self, origin: &str) -> bool {
+        self.allow_origins.iter().any(|pattern| origin_matches(pattern, origin))
+    }
+
+    /// 方法是否被允许
+    pub fn allows_method(&self, method: &str) -> bool {
+        self.allow_methods.iter().any(|allowed| allowed.to_string().eq_ignore_ascii_case(method))
+    }
+
+    /// 请求头是否被允许（不区分大小写）
+    pub fn allows_header(&self, header: &str) -> bool {
+        self.allow_headers.iter().any(|allowed| allowed == "*" || allowed.eq_ignore_ascii_case(header))
+    }
 }
 
 impl Default for CorsConfig {
@@ -289,9 +362,66 @@ impl Default for CorsConfig {
                 "X-Rate-Limit-Remaining".to_string(),
                 "X-Rate-Limit-Reset".to_string(),
             ],
-            allow_credentials: true,
+            // 任意来源时不允许携带凭证
+            allow_credentials: false,
             max_age: Some(std::time::Duration::from_secs(3600)),
+            allow_tenant_overrides: true,
+        }
+    }
+}
+
+/// 校验来源模式：`*`，或不带路径的 `scheme://host[:port]`，host 可以以 `*.` 开头
+fn validate_origin_pattern(pattern: &str) -> Result<(), &'static str> {
+    if pattern == "*" {
+        return Ok(());
+    }
+    let (scheme, authority) = pattern.split_once("://").ok_or("must include a scheme")?;
+    if scheme != "http" && scheme != "https" {
+        return Err("must use http or https");
+    }
+    if authority.is_empty() || authority.contains(['/', '?', '#', '@']) {
+        return Err("must not contain a path, query or credentials");
+    }
+    let host = authority.rsplit_once(':').map_or(authority, |(host, _)| host);
+    if let Some(suffix) = host.strip_prefix("*.") {
+        // 通配至少要限定到注册域名，如 *.example.com
+        if suffix.contains('*') || suffix.split('.').filter(|label| !label.is_empty()).count() < 2 {
+            return Err("wildcard must cover a subdomain of a registered domain");
         }
+    } else if host.contains('*') {
+        return Err("wildcard is only allowed as the leftmost label");
+    }
+    Ok(())
+}
+
+/// `Option<Duration>` 以整数秒序列化
+mod optional_secs {
+    use std::time::Duration;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error> {
+        value.map(|duration| duration.as_secs()).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Duration>, D::Error> {
+        Ok(Option::<u64>::deserialize(deserializer)?.map(Duration::from_secs))
+    }
+}
+
+/// 来源是否匹配模式（不区分大小写）
+fn origin_matches(pattern: &str, origin: &str) -> bool {
+    if pattern == "*" {
+        return true;
+    }
+    let pattern = pattern.to_ascii_lowercase();
+    let origin = origin.to_ascii_lowercase();
+    match pattern.split_once("://*.") {
+        Some((scheme, suffix)) => origin
+            .strip_prefix(scheme)
+            .and_then(|rest| rest.strip_prefix("://"))
+            .and_then(|host| host.strip_suffix(suffix))
+            .is_some_and(|sub| sub.len() > 1 && sub.ends_with('.') && !sub.contains('/')),
+        None => pattern == origin,
     }
 }
 
@@ -306,6 +436,22 @@ pub struct AuthConfig {
     pub enable_oauth: bool,
     pub oauth_providers: Vec<OAuthProvider>,
     pub password_policy: PasswordPolicy,
+    /// 邀请有效期
+    pub invitation_expiration: std::time::Duration,
+    /// 邮箱验证链接有效期
+    pub email_verification_expiration: std::time::Duration,
+    /// 是否要求用户完成邮箱验证后才能访问受保护的接口
+    pub require_verified_email: bool,
+    /// 连续登录失败多少次后锁定账户（0 表示不限制）
+    pub max_failed_logins: u32,
+    /// 账户锁定时长
+    pub lockout_duration: std::time::Duration,
+    /// OIDC 回调地址，需在提供商处登记并指向 `/api/v1/auth/oidc/callback`
+    pub oidc_redirect_uri: Option<String>,
+    /// OIDC 登录流程（从跳转到回调）的有效期
+    pub oidc_login_expiration: std::time::Duration,
+    /// 系统租户：只有该租户的管理员可以修改影响所有租户的部署级设置
+    pub system_tenant: Option<String>,
 }
 
 impl Default for AuthConfig {
@@ -319,6 +465,14 @@ impl Default for AuthConfig {
             enable_oauth: false,
             oauth_providers: Vec::new(),
             password_policy: PasswordPolicy::default(),
+            invitation_expiration: std::time::Duration::from_secs(86400 * 7),
+            email_verification_expiration: std::time::Duration::from_secs(86400),
+            require_verified_email: false,
+            max_failed_logins: 5,
+            lockout_duration: std::time::Duration::from_secs(15 * 60),
+            oidc_redirect_uri: None,
+            oidc_login_expiration: std::time::Duration::from_secs(10 * 60),
+            system_tenant: None,
         }
     }
 }
@@ -344,6 +498,41 @@ pub struct PasswordPolicy {
     pub require_numbers: bool,
     pub require_symbols: bool,
     pub max_age: Option<std::time::Duration>,
+    /// 禁止使用的常见密码（不区分大小写）
+    pub banned_passwords: Vec<String>,
+}
+
+/// 默认禁止使用的常见密码
+pub const COMMON_PASSWORDS: &[&str] = &[
+    "password", "password1", "password123", "passw0rd", "123456", "12345678", "123456789",
+    "1234567890", "qwerty", "qwerty123", "abc123", "111111", "letmein", "welcome", "welcome1",
+    "admin", "admin123", "iloveyou", "monkey", "dragon", "sunshine", "football", "changeme",
+];
+
+impl PasswordPolicy {
+    /// 检查密码，返回所有不满足的规则；为空表示密码合规
+    pub fn violations(&self, password: &str) -> Vec<String> {
+        let mut violations = Vec::new();
+        if password.chars().count() < self.min_length {
+            violations.push(format!("must be at least {} characters long", self.min_length));
+        }
+        if self.require_uppercase && !password.chars().any(|c| c.is_uppercase()) {
+            violations.push("must contain an uppercase letter".to_string());
+        }
+        if self.require_lowercase && !password.chars().any(|c| c.is_lowercase()) {
+            violations.push("must contain a lowercase letter".to_string());
+        }
+        if self.require_numbers && !password.chars().any(|c| c.is_ascii_digit()) {
+            violations.push("must contain a number".to_string());
+        }
+        if self.require_symbols && !password.chars().any(|c| !c.is_alphanumeric() && !c.is_whitespace()) {
+            violations.push("must contain a symbol".to_string());
+        }
+        if self.banned_passwords.iter().any(|banned| banned.eq_ignore_ascii_case(password)) {
+            violations.push("is too common".to_string());
+        }
+        violations
+    }
 }
 
 impl Default for PasswordPolicy {
@@ -355,6 +544,7 @@ impl Default for PasswordPolicy {
             require_numbers: true,
             require_symbols: false,
             max_age: Some(std::time::Duration::from_secs(86400 * 90)),
+            banned_passwords: COMMON_PASSWORDS.iter().map(|p| p.to_string()).collect(),
         }
     }
 }
@@ -543,6 +733,23 @@ pub struct PaginationInfo {
     pub has_previous: bool,
 }
 
+impl PaginationInfo {
+    /// 根据页码、页大小和总数计算分页信息
+    pub fn new(page: usize, page_size: usize, total_items: usize) -> Self {
+        let page = page.max(1);
+        let page_size = page_size.max(1);
+        let total_pages = total_items.div_ceil(page_size);
+        Self {
+            page,
+            page_size,
+            total_items,
+            total_pages,
+            has_next: page < total_pages,
+            has_previous: page > 1,
+        }
+    }
+}
+
 /// 过滤参数
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FilterParams {
@@ -563,6 +770,104 @@ pub struct UserContext {
     pub expires_at: DateTime<Utc>,
 }
 
+/// 请求追踪信息
+///
+/// 由请求追踪中间件生成并放入请求扩展。`request_id` 来自 `X-Request-Id`（缺省时生成），
+/// `trace_id` 与上游 `traceparent` 保持一致，`span_id` 是本服务为该请求分配的新 span。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestTrace {
+    pub request_id: String,
+    pub trace_id: String,
+    pub span_id: String,
+    pub parent_span_id: Option<String>,
+    pub sampled: bool,
+}
+
+/// 请求 ID 头
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// W3C Trace Context 头
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+
+impl RequestTrace {
+    /// 根据请求头创建追踪信息：沿用合法的 `X-Request-Id` 和 `traceparent`，否则重新生成
+    pub fn from_headers(request_id: Option<&str>, traceparent: Option<&str>) -> Self {
+        let request_id = request_id
+            .map(str::trim)
+            .filter(|id| is_valid_request_id(id))
+            .map(str::to_string)
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        let span_id = format!("{:016x}", rand::random::<u64>().max(1));
+
+        match traceparent.and_then(parse_traceparent) {
+            Some((trace_id, parent_span_id, sampled)) => Self {
+                request_id,
+                trace_id,
+                span_id,
+                parent_span_id: Some(parent_span_id),
+                sampled,
+            },
+            None => Self {
+                request_id,
+                trace_id: uuid::Uuid::new_v4().simple().to_string(),
+                span_id,
+                parent_span_id: None,
+                sampled: true,
+            },
+        }
+    }
+
+    /// 本服务 span 对应的 `traceparent`，用于响应和下游调用
+    pub fn traceparent(&self) -> String {
+        format!("00-{}-{}-{:02x}", self.trace_id, self.span_id, self.sampled as u8)
+    }
+
+    /// 为该请求触发的工具执行创建执行上下文
+    ///
+    /// `request_id` 写入执行记录；`TRACEPARENT` 环境变量传给工具进程，便于其继续传播。
+    pub fn execution_context(&self, user: &UserContext) -> stepflow_executor::ExecutionContext {
+        stepflow_executor::ExecutionContext {
+            user_id: user.user_id.as_str().to_string(),
+            tenant_id: user.tenant_id.clone().unwrap_or_default(),
+            session_id: user.session_id.clone(),
+            request_id: self.request_id.clone(),
+            parent_execution_id: None,
+            environment: HashMap::from([("TRACEPARENT".to_string(), self.traceparent())]),
+            labels: HashMap::new(),
+            note: None,
+        }
+    }
+}
+
+/// 请求 ID 限制为 1-128 个可见 ASCII 字符，避免日志注入
+fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= 128 && id.bytes().all(|b| b.is_ascii_graphic())
+}
+
+/// 解析 `traceparent`，返回 (trace_id, parent_span_id, sampled)
+fn parse_traceparent(value: &str) -> Option<(String, String, bool)> {
+    let mut parts = value.trim().split('-');
+    let version = parts.next()?;
+    let trace_id = parts.next()?;
+    let parent_id = parts.next()?;
+    let flags = parts.next()?;
+    // 版本 00 只能有四段；更高版本允许追加字段
+    if version.len() != 2 || version == "ff" || (version == "00" && parts.next().is_some()) {
+        return None;
+    }
+    let is_hex = |s: &str, len: usize| {
+        s.len() == len && s.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+    };
+    if !is_hex(version, 2) || !is_hex(trace_id, 32) || !is_hex(parent_id, 16) || !is_hex(flags, 2) {
+        return None;
+    }
+    if trace_id.bytes().all(|b| b == b'0') || parent_id.bytes().all(|b| b == b'0') {
+        return None;
+    }
+    let flags = u8::from_str_radix(flags, 16).ok()?;
+    Some((trace_id.to_string(), parent_id.to_string(), flags & 0x01 == 1))
+}
+
 /// JWT 声明
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JwtClaims {
@@ -575,6 +880,62 @@ pub struct JwtClaims {
     pub tenant_id: Option<String>,
     pub roles: Vec<String>,
     pub permissions: Vec<String>,
+    /// 会话 ID，用于会话撤销
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>,
+    /// 令牌用途；刷新令牌只能用于换取新的访问令牌
+    #[serde(default)]
+    pub typ: TokenType,
+}
+
+/// JWT 的用途
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenType {
+    /// 访问令牌，用于调用 API
+    #[default]
+    Access,
+    /// 刷新令牌，只能提交给 `/api/v1/auth/refresh`
+    Refresh,
+}
+
+/// 本服务签发的 JWT 的 `iss`
+pub const JWT_ISSUER: &str = "stepflow";
+
+/// 本服务签发的 JWT 的 `aud`
+pub const JWT_AUDIENCE: &str = "stepflow-api";
+
+impl JwtClaims {
+    /// 校验签名、有效期、签发方与受众并解出声明
+    pub fn decode(token: &str, secret: &str) -> Result<Self, jsonwebtoken::errors::Error> {
+        let mut validation = jsonwebtoken::Validation::default();
+        validation.set_issuer(&[JWT_ISSUER]);
+        validation.set_audience(&[JWT_AUDIENCE]);
+        jsonwebtoken::decode::<Self>(token, &jsonwebtoken::DecodingKey::from_secret(secret.as_ref()), &validation)
+            .map(|data| data.claims)
+    }
+
+    /// 解出访问令牌的声明，拒绝刷新令牌
+    pub fn decode_access(token: &str, secret: &str) -> Result<Self, String> {
+        let claims = Self::decode(token, secret).map_err(|e| e.to_string())?;
+        if claims.typ != TokenType::Access {
+            return Err("refresh tokens cannot be used to access the API".to_string());
+        }
+        Ok(claims)
+    }
+}
+
+impl From<JwtClaims> for UserContext {
+    fn from(claims: JwtClaims) -> Self {
+        Self {
+            user_id: UserId::from_string(claims.user_id),
+            tenant_id: claims.tenant_id,
+            roles: claims.roles,
+            permissions: claims.permissions,
+            session_id: claims.sid.unwrap_or_default(),
+            expires_at: DateTime::from_timestamp(claims.exp, 0).unwrap_or_else(Utc::now),
+        }
+    }
 }
 
 /// API 密钥
@@ -609,4 +970,147 @@ pub struct Session {
 pub type RequestId = String;
 
 /// 租户 ID
-pub type TenantId = String; 
\ No newline at end of file
+pub type TenantId = String; 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn policy(origins: &[&str]) -> CorsConfig {
+        CorsConfig {
+            allow_origins: origins.iter().map(|origin| origin.to_string()).collect(),
+            ..CorsConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_validate_origin_pattern() {
+        for valid in ["*", "https://app.example.com", "http://localhost:3000", "https://*.example.com", "https://*.example.com:8443"] {
+            assert!(validate_origin_pattern(valid).is_ok(), "{}", valid);
+        }
+        for invalid in [
+            "app.example.com",
+            "ftp://example.com",
+            "https://example.com/path",
+            "https://*.com",
+            "https://*.*.example.com",
+            "https://app.*.example.com",
+            "https://",
+        ] {
+            assert!(validate_origin_pattern(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_origin_matches_wildcard_subdomains() {
+        let pattern = "https://*.example.com";
+        assert!(origin_matches(pattern, "https://app.example.com"));
+        assert!(origin_matches(pattern, "https://a.b.example.com"));
+        assert!(origin_matches(pattern, "HTTPS://App.Example.COM"));
+        // The bare domain and look-alike suffixes are not subdomains
+        assert!(!origin_matches(pattern, "https://example.com"));
+        assert!(!origin_matches(pattern, "https://evilexample.com"));
+        assert!(!origin_matches(pattern, "https://example.com.evil.com"));
+        assert!(!origin_matches(pattern, "https://.example.com"));
+    }
+
+    #[test]
+    fn test_origin_matches_scheme_and_port() {
+        assert!(origin_matches("https://app.example.com", "https://app.example.com"));
+        assert!(!origin_matches("https://app.example.com", "http://app.example.com"));
+        assert!(!origin_matches("https://app.example.com", "https://app.example.com:8443"));
+        assert!(!origin_matches("https://*.example.com", "http://app.example.com"));
+        // A wildcard without a port does not match origins with one
+        assert!(!origin_matches("https://*.example.com", "https://app.example.com:8443"));
+        assert!(origin_matches("https://*.example.com:8443", "https://app.example.com:8443"));
+        assert!(origin_matches("*", "null"));
+    }
+
+    #[test]
+    fn test_cors_violations() {
+        assert!(CorsConfig::default().violations().is_empty());
+
+        let mut credentials = policy(&["*"]);
+        credentials.allow_credentials = true;
+        let violations = credentials.violations();
+        assert_eq!(violations.len(), 1);
+        assert!(violations[0].contains("credentials"));
+
+        let mut headers = policy(&["https://app.example.com"]);
+        headers.allow_credentials = true;
+        headers.allow_headers = vec!["*".to_string(), "bad header".to_string()];
+        assert_eq!(headers.violations().len(), 2);
+
+        let mut max_age = policy(&["https://*.example.com"]);
+        max_age.max_age = Some(Duration::from_secs(MAX_CORS_MAX_AGE_SECS + 1));
+        assert_eq!(max_age.violations().len(), 1);
+
+        assert_eq!(policy(&["https://*.com", "example.com"]).violations().len(), 2);
+    }
+
+    #[test]
+    fn test_cors_max_age_serialized_as_seconds() {
+        let json = serde_json::to_value(CorsConfig::default()).unwrap();
+        assert_eq!(json["max_age_secs"], 3600);
+
+        let parsed: CorsConfig = serde_json::from_value(serde_json::json!({"max_age": 600})).unwrap();
+        assert_eq!(parsed.max_age, Some(Duration::from_secs(600)));
+        let parsed: CorsConfig = serde_json::from_value(serde_json::json!({"max_age_secs": null})).unwrap();
+        assert_eq!(parsed.max_age, None);
+    }
+
+    #[test]
+    fn test_password_policy_violations() {
+        let policy = PasswordPolicy::default();
+        assert!(policy.violations("Correct7Horse").is_empty());
+        assert_eq!(policy.violations("short1A").len(), 1);
+        assert_eq!(policy.violations("alllowercase").len(), 2);
+        assert!(policy.violations("Password123").contains(&"is too common".to_string()));
+        assert!(policy.violations("PASSWORD123").contains(&"is too common".to_string()));
+
+        let symbols = PasswordPolicy { require_symbols: true, ..PasswordPolicy::default() };
+        assert_eq!(symbols.violations("Correct7Horse"), vec!["must contain a symbol".to_string()]);
+        assert!(symbols.violations("Correct7Horse!").is_empty());
+    }
+
+    #[test]
+    fn test_request_trace_continues_upstream_trace() {
+        let traceparent = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+        let trace = RequestTrace::from_headers(Some(" req-42 "), Some(traceparent));
+        assert_eq!(trace.request_id, "req-42");
+        assert_eq!(trace.trace_id, "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(trace.parent_span_id.as_deref(), Some("00f067aa0ba902b7"));
+        assert!(trace.sampled);
+        assert_ne!(trace.span_id, "00f067aa0ba902b7");
+        assert_eq!(
+            trace.traceparent(),
+            format!("00-4bf92f3577b34da6a3ce929d0e0e4736-{}-01", trace.span_id)
+        );
+
+        let unsampled = RequestTrace::from_headers(None, Some("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-00"));
+        assert!(!unsampled.sampled);
+        assert!(!unsampled.request_id.is_empty());
+    }
+
+    #[test]
+    fn test_request_trace_replaces_invalid_headers() {
+        for invalid in [
+            "01-4bf92f3577b34da6a3ce929d0e0e4736",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+        ] {
+            let trace = RequestTrace::from_headers(None, Some(invalid));
+            assert!(trace.parent_span_id.is_none(), "{}", invalid);
+            assert_eq!(trace.trace_id.len(), 32);
+        }
+        // 更高版本允许追加字段
+        assert!(parse_traceparent("01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra").is_some());
+
+        let injected = RequestTrace::from_headers(Some("id with spaces\nINFO forged"), None);
+        assert_ne!(injected.request_id, "id with spaces\nINFO forged");
+        assert!(RequestTrace::from_headers(Some(&"x".repeat(129)), None).request_id.len() < 129);
+    }
+}
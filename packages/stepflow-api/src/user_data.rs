@@ -0,0 +1,486 @@
+//! 用户数据合规导出与擦除（GDPR）
+//!
+//! 导出把与一个用户关联的资料、会话、发起的执行、执行备注、审计记录等收集为 `tar.zst`
+//! 归档。`manifest.json` 记录每个分区文件的 SHA-256，`manifest.sig` 是用服务端密钥对清单
+//! 计算的 HMAC-SHA256，[`UserDataArchive::open`] 用同一密钥校验归档未被篡改。签名密钥由
+//! [`archive_signing_key`] 从服务端密钥派生，不直接复用 JWT 签名密钥。
+//!
+//! 擦除删除用户的会话、恢复码、身份关联、收藏和保存的视图，把资料替换为匿名值并清除
+//! 审计记录中的 IP 和 User-Agent。执行、备注和审计记录本身保留，以维持租户数据和审计链
+//! 完整，但只能关联到匿名账户。
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::Sha256;
+use stepflow_core::{Database, TenantId, UserId};
+use stepflow_database::{utils::param, SqliteDatabase};
+use tracing::info;
+use crate::errors::{ApiError, ApiResult};
+use crate::tenant_bundle::{pack, sha256_hex, unpack, BundleSectionInfo};
+
+/// 当前归档格式版本
+pub const USER_DATA_SCHEMA_VERSION: u32 = 1;
+
+/// 归档内清单文件名
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// 归档内清单签名文件名
+const SIGNATURE_FILE: &str = "manifest.sig";
+
+/// 清单签名算法标识
+const SIGNATURE_ALGORITHM: &str = "hmac-sha256";
+
+/// 从服务端密钥派生归档签名密钥时使用的固定标签
+const SIGNING_KEY_LABEL: &[u8] = b"stepflow user-data archive signing key v1";
+
+/// 匿名化后邮箱使用的保留域名
+const ERASED_EMAIL_DOMAIN: &str = "erased.invalid";
+
+/// 归档中的一个分区，`?1` 为用户 ID
+struct UserDataSection {
+    name: &'static str,
+    table: &'static str,
+    filter: &'static str,
+    /// 导出时不包含的列（凭据）
+    excluded_columns: &'static [&'static str],
+}
+
+const SECTIONS: &[UserDataSection] = &[
+    UserDataSection {
+        name: "profile",
+        table: "users",
+        filter: "id = ?1",
+        excluded_columns: &["password_hash", "totp_secret", "totp_last_step"],
+    },
+    UserDataSection {
+        name: "sessions",
+        table: "user_sessions",
+        filter: "user_id = ?1",
+        excluded_columns: &[],
+    },
+    UserDataSection {
+        name: "identities",
+        table: "oidc_identities",
+        filter: "user_id = ?1",
+        excluded_columns: &[],
+    },
+    UserDataSection {
+        name: "executions",
+        table: "executions",
+        filter: "user_id = ?1",
+        excluded_columns: &[],
+    },
+    UserDataSection {
+        name: "execution_notes",
+        table: "execution_notes",
+        filter: "author = ?1",
+        excluded_columns: &[],
+    },
+    UserDataSection {
+        name: "favorites",
+        table: "user_favorites",
+        filter: "user_id = ?1",
+        excluded_columns: &[],
+    },
+    UserDataSection {
+        name: "saved_views",
+        table: "saved_execution_views",
+        filter: "user_id = ?1",
+        excluded_columns: &[],
+    },
+    UserDataSection {
+        name: "audit_events",
+        table: "security_events",
+        filter: "user_id = ?1 OR (resource_type = 'user' AND resource_id = ?1)",
+        excluded_columns: &[],
+    },
+];
+
+/// 擦除时整行删除的数据，按表中的 `user_id` 列匹配
+const ERASED_TABLES: &[(&str, &str)] = &[
+    ("sessions", "user_sessions"),
+    ("recovery_codes", "recovery_codes"),
+    ("identities", "oidc_identities"),
+    ("email_verifications", "email_verifications"),
+    ("login_failures", "login_failures"),
+    ("favorites", "user_favorites"),
+    ("saved_views", "saved_execution_views"),
+];
+
+/// 导出归档清单
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserDataManifest {
+    pub schema_version: u32,
+    pub user_id: String,
+    pub tenant_id: String,
+    /// 执行导出的管理员
+    pub exported_by: String,
+    pub exported_at: DateTime<Utc>,
+    pub signature_algorithm: String,
+    pub sections: Vec<BundleSectionInfo>,
+}
+
+/// 已通过签名与校验和检查的导出归档
+#[derive(Debug, Clone)]
+pub struct UserDataArchive {
+    manifest: UserDataManifest,
+    files: HashMap<String, Vec<u8>>,
+}
+
+impl UserDataArchive {
+    /// 解压归档，用导出时的签名密钥校验清单签名和每个分区的校验和
+    pub fn open(bytes: &[u8], signing_key: &[u8]) -> ApiResult<Self> {
+        let invalid = |message: String| ApiError::BadRequest(format!("Invalid user data archive: {}", message));
+
+        let files = unpack(bytes).map_err(invalid)?;
+        let manifest_data = files
+            .get(MANIFEST_FILE)
+            .ok_or_else(|| invalid(format!("missing {}", MANIFEST_FILE)))?;
+        let signature = files
+            .get(SIGNATURE_FILE)
+            .and_then(|data| hex_decode(std::str::from_utf8(data).ok()?.trim()))
+            .ok_or_else(|| invalid(format!("missing or malformed {}", SIGNATURE_FILE)))?;
+        signer(signing_key)?
+            .chain_update(manifest_data)
+            .verify_slice(&signature)
+            .map_err(|_| invalid("signature mismatch".to_string()))?;
+
+        let manifest: UserDataManifest = serde_json::from_slice(manifest_data).map_err(|e| invalid(e.to_string()))?;
+        if manifest.schema_version > USER_DATA_SCHEMA_VERSION {
+            return Err(invalid(format!(
+                "schema version {} is newer than supported version {}",
+                manifest.schema_version, USER_DATA_SCHEMA_VERSION
+            )));
+        }
+        for section in &manifest.sections {
+            let data = files.get(&section.file).ok_or_else(|| invalid(format!("missing {}", section.file)))?;
+            if sha256_hex(data) != section.sha256 {
+                return Err(invalid(format!("checksum mismatch for {}", section.file)));
+            }
+        }
+
+        Ok(Self { manifest, files })
+    }
+
+    pub fn manifest(&self) -> &UserDataManifest {
+        &self.manifest
+    }
+
+    /// 某个分区的行，归档中没有该分区时返回 `None`
+    pub fn section_rows(&self, name: &str) -> ApiResult<Option<Vec<Map<String, Value>>>> {
+        let Some(info) = self.manifest.sections.iter().find(|section| section.name == name) else {
+            return Ok(None);
+        };
+        let rows = serde_json::from_slice(&self.files[&info.file])
+            .map_err(|e| ApiError::BadRequest(format!("Invalid user data archive section {}: {}", name, e)))?;
+        Ok(Some(rows))
+    }
+}
+
+/// 单个分区的擦除结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErasedSection {
+    pub name: String,
+    /// 删除或匿名化的行数
+    pub rows: u64,
+}
+
+/// 擦除结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserErasureReport {
+    pub user_id: String,
+    pub tenant_id: String,
+    pub erased_at: DateTime<Utc>,
+    pub sections: Vec<ErasedSection>,
+}
+
+/// 用户数据导出与擦除服务
+///
+/// 调用方负责确认用户属于 `tenant_id`，并把操作记录到审计日志。
+pub struct UserDataService {
+    db: Arc<SqliteDatabase>,
+}
+
+impl UserDataService {
+    pub fn new(db: Arc<SqliteDatabase>) -> Self {
+        Self { db }
+    }
+
+    /// 导出用户数据为签名的 `tar.zst` 归档
+    pub async fn export(
+        &self,
+        tenant_id: &TenantId,
+        user_id: &UserId,
+        exported_by: &UserId,
+        signing_key: &[u8],
+    ) -> ApiResult<Vec<u8>> {
+        let mut sections = Vec::new();
+        let mut files = Vec::new();
+        for section in SECTIONS {
+            let sql = format!("SELECT * FROM {} WHERE {}", section.table, section.filter);
+            let result = self.db.execute(&sql, &[param::text(user_id.as_str())]).await?;
+            let rows: Vec<Map<String, Value>> = result.rows
+                .into_iter()
+                .map(|row| {
+                    row.into_iter()
+                        .filter(|(column, _)| !section.excluded_columns.contains(&column.as_str()))
+                        .collect()
+                })
+                .collect();
+            if section.name == "profile" && rows.is_empty() {
+                return Err(ApiError::NotFound(format!("User {} not found", user_id.as_str())));
+            }
+
+            let file = format!("{}.json", section.name);
+            let data = serde_json::to_vec_pretty(&rows)?;
+            sections.push(BundleSectionInfo {
+                name: section.name.to_string(),
+                file: file.clone(),
+                rows: rows.len(),
+                sha256: sha256_hex(&data),
+            });
+            files.push((file, data));
+        }
+
+        let manifest = UserDataManifest {
+            schema_version: USER_DATA_SCHEMA_VERSION,
+            user_id: user_id.as_str().to_string(),
+            tenant_id: tenant_id.as_str().to_string(),
+            exported_by: exported_by.as_str().to_string(),
+            exported_at: Utc::now(),
+            signature_algorithm: SIGNATURE_ALGORITHM.to_string(),
+            sections,
+        };
+        let manifest_data = serde_json::to_vec_pretty(&manifest)?;
+        let signature = hex_encode(&signer(signing_key)?.chain_update(&manifest_data).finalize().into_bytes());
+        files.insert(0, (MANIFEST_FILE.to_string(), manifest_data));
+        files.insert(1, (SIGNATURE_FILE.to_string(), signature.into_bytes()));
+
+        let archive = pack(&files, manifest.exported_at)?;
+        info!("Exported data of user {} ({} bytes)", user_id.as_str(), archive.len());
+        Ok(archive)
+    }
+
+    /// 擦除用户的个人数据
+    ///
+    /// 各步骤可重复执行，中途失败后再次调用即可完成擦除。
+    pub async fn erase(&self, tenant_id: &TenantId, user_id: &UserId) -> ApiResult<UserErasureReport> {
+        let id = param::text(user_id.as_str());
+        let mut sections = Vec::new();
+        for (name, table) in ERASED_TABLES {
+            let sql = format!("DELETE FROM {} WHERE user_id = ?1", table);
+            let result = self.db.execute(&sql, std::slice::from_ref(&id)).await?;
+            sections.push(ErasedSection { name: name.to_string(), rows: result.rows_affected });
+        }
+
+        let result = self.db.execute(
+            "UPDATE security_events SET ip_address = NULL, user_agent = NULL \
+             WHERE user_id = ?1 OR (resource_type = 'user' AND resource_id = ?1)",
+            std::slice::from_ref(&id),
+        ).await?;
+        sections.push(ErasedSection { name: "audit_events".to_string(), rows: result.rows_affected });
+
+        let username = format!("erased-{}", user_id.as_str());
+        let email = format!("{}@{}", username, ERASED_EMAIL_DOMAIN);
+        let result = self.db.execute(
+            "UPDATE user_invitations SET email = ?2 WHERE accepted_user_id = ?1",
+            &[id.clone(), param::text(&email)],
+        ).await?;
+        sections.push(ErasedSection { name: "invitations".to_string(), rows: result.rows_affected });
+
+        // 空密码哈希无法通过校验，账户不能再登录
+        let erased_at = Utc::now();
+        let result = self.db.execute(
+            r#"
+                UPDATE users SET username = ?2, email = ?3, password_hash = '', settings = NULL,
+                    email_verified = 0, totp_secret = NULL, totp_enabled = 0, totp_last_step = NULL,
+                    directory_dn = NULL, updated_at = ?4
+                WHERE id = ?1
+            "#,
+            &[id, param::text(&username), param::text(&email), param::timestamp(&erased_at)],
+        ).await?;
+        if result.rows_affected == 0 {
+            return Err(ApiError::NotFound(format!("User {} not found", user_id.as_str())));
+        }
+        sections.push(ErasedSection { name: "profile".to_string(), rows: result.rows_affected });
+
+        info!("Erased personal data of user {}", user_id.as_str());
+        Ok(UserErasureReport {
+            user_id: user_id.as_str().to_string(),
+            tenant_id: tenant_id.as_str().to_string(),
+            erased_at,
+            sections,
+        })
+    }
+}
+
+/// 用 HKDF-SHA256 从服务端密钥派生归档签名密钥
+///
+/// 派生出的密钥与 JWT 签名密钥相互独立，持有归档签名密钥也无法签发令牌。
+pub fn archive_signing_key(secret: &[u8]) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    Hkdf::<Sha256>::new(None, secret)
+        .expand(SIGNING_KEY_LABEL, &mut key)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    key
+}
+
+fn signer(key: &[u8]) -> ApiResult<Hmac<Sha256>> {
+    Hmac::<Sha256>::new_from_slice(key)
+        .map_err(|e| ApiError::InternalServerError(format!("Invalid signing key: {}", e)))
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn hex_decode(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stepflow_database::MigrationManager;
+
+    const KEY: &[u8] = b"signing-key";
+
+    async fn setup() -> Arc<SqliteDatabase> {
+        let db = Arc::new(SqliteDatabase::new(":memory:").await.unwrap());
+        MigrationManager::run_migrations(&db).await.unwrap();
+        let now = Utc::now().to_rfc3339();
+        for sql in [
+            "INSERT INTO tenants (id, name, created_at, updated_at) VALUES ('acme', 'Acme', ?1, ?1)",
+            "INSERT INTO users (id, username, email, password_hash, role, tenant_id, totp_secret, created_at, updated_at) \
+             VALUES ('u1', 'alice', 'alice@acme.test', 'hash', 'user', 'acme', 'TOTPSECRET', ?1, ?1)",
+            "INSERT INTO users (id, username, email, password_hash, role, tenant_id, created_at, updated_at) \
+             VALUES ('u2', 'bob', 'bob@acme.test', 'hash', 'user', 'acme', ?1, ?1)",
+            "INSERT INTO user_sessions (id, user_id, tenant_id, device, ip_address, created_at, last_seen_at, expires_at) \
+             VALUES ('s1', 'u1', 'acme', 'laptop', '10.0.0.1', ?1, ?1, ?1)",
+            "INSERT INTO user_sessions (id, user_id, tenant_id, created_at, last_seen_at, expires_at) \
+             VALUES ('s2', 'u2', 'acme', ?1, ?1, ?1)",
+            "INSERT INTO tools (id, name, version_major, version_minor, version_patch, tool_type, status, author, created_at, updated_at) \
+             VALUES ('t1', 'Tool', 1, 0, 0, 'python', 'active', 'alice', ?1, ?1)",
+            "INSERT INTO executions (id, tool_id, tenant_id, user_id, status, request, started_at, created_at, updated_at) \
+             VALUES ('e1', 't1', 'acme', 'u1', 'completed', '{}', ?1, ?1, ?1)",
+            "INSERT INTO security_events (id, event_type, user_id, tenant_id, resource_type, resource_id, action, ip_address, user_agent, success, created_at) \
+             VALUES ('a1', 'account_locked', 'u1', 'acme', 'user', 'u1', 'login', '10.0.0.1', 'curl', 0, ?1)",
+        ] {
+            db.execute(sql, &[param::text(&now)]).await.unwrap();
+        }
+        db
+    }
+
+    #[tokio::test]
+    async fn test_export_collects_signed_user_data() {
+        let db = setup().await;
+        let service = UserDataService::new(db);
+        let tenant_id = TenantId::from_string("acme".to_string());
+        let archive = service
+            .export(&tenant_id, &UserId::from_string("u1".to_string()), &UserId::from_string("admin".to_string()), KEY)
+            .await
+            .unwrap();
+
+        let opened = UserDataArchive::open(&archive, KEY).unwrap();
+        assert_eq!(opened.manifest().user_id, "u1");
+        let profile = opened.section_rows("profile").unwrap().unwrap();
+        assert_eq!(profile[0]["email"], "alice@acme.test");
+        assert!(!profile[0].contains_key("password_hash"));
+        assert!(!profile[0].contains_key("totp_secret"));
+        let sessions = opened.section_rows("sessions").unwrap().unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0]["id"], "s1");
+        assert_eq!(opened.section_rows("executions").unwrap().unwrap().len(), 1);
+        assert_eq!(opened.section_rows("audit_events").unwrap().unwrap().len(), 1);
+
+        assert!(UserDataArchive::open(&archive, b"other-key").is_err());
+        let missing = service
+            .export(&tenant_id, &UserId::from_string("nobody".to_string()), &UserId::from_string("admin".to_string()), KEY)
+            .await;
+        assert!(matches!(missing, Err(ApiError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_tampered_archive_rejected() {
+        let db = setup().await;
+        let archive = UserDataService::new(db)
+            .export(
+                &TenantId::from_string("acme".to_string()),
+                &UserId::from_string("u1".to_string()),
+                &UserId::from_string("admin".to_string()),
+                KEY,
+            )
+            .await
+            .unwrap();
+
+        // 同时改写分区和清单中的校验和，只有签名能发现
+        let mut files = unpack(&archive).unwrap();
+        let profile = String::from_utf8(files["profile.json"].clone()).unwrap().replace("alice@", "mallory@");
+        let old_sum = sha256_hex(&files["profile.json"]);
+        let manifest = String::from_utf8(files[MANIFEST_FILE].clone()).unwrap().replace(&old_sum, &sha256_hex(profile.as_bytes()));
+        files.insert("profile.json".to_string(), profile.into_bytes());
+        files.insert(MANIFEST_FILE.to_string(), manifest.into_bytes());
+        let tampered = pack(&files.into_iter().collect::<Vec<_>>(), Utc::now()).unwrap();
+
+        let error = UserDataArchive::open(&tampered, KEY).unwrap_err();
+        assert!(error.to_string().contains("signature mismatch"));
+    }
+
+    #[tokio::test]
+    async fn test_signing_key_derived_from_secret() {
+        let secret = b"jwt-secret";
+        let key = archive_signing_key(secret);
+        assert_eq!(key, archive_signing_key(secret));
+        assert_ne!(key, archive_signing_key(b"other-secret"));
+
+        // 用 JWT 密钥本身无法校验用派生密钥签名的归档
+        let archive = UserDataService::new(setup().await)
+            .export(
+                &TenantId::from_string("acme".to_string()),
+                &UserId::from_string("u1".to_string()),
+                &UserId::from_string("admin".to_string()),
+                &key,
+            )
+            .await
+            .unwrap();
+        assert!(UserDataArchive::open(&archive, &key).is_ok());
+        assert!(UserDataArchive::open(&archive, secret).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_erase_anonymizes_user() {
+        let db = setup().await;
+        let report = UserDataService::new(db.clone())
+            .erase(&TenantId::from_string("acme".to_string()), &UserId::from_string("u1".to_string()))
+            .await
+            .unwrap();
+        let rows = |name: &str| report.sections.iter().find(|section| section.name == name).unwrap().rows;
+        assert_eq!(rows("sessions"), 1);
+        assert_eq!(rows("audit_events"), 1);
+
+        let user = db.execute("SELECT * FROM users WHERE id = 'u1'", &[]).await.unwrap();
+        assert_eq!(user.rows[0]["username"], "erased-u1");
+        assert_eq!(user.rows[0]["email"], "erased-u1@erased.invalid");
+        assert_eq!(user.rows[0]["totp_secret"], Value::Null);
+        let audit = db.execute("SELECT ip_address, user_agent FROM security_events", &[]).await.unwrap();
+        assert_eq!(audit.rows[0]["ip_address"], Value::Null);
+        assert_eq!(audit.rows[0]["user_agent"], Value::Null);
+
+        // 执行记录保留，其他用户不受影响
+        assert_eq!(db.execute("SELECT id FROM executions WHERE user_id = 'u1'", &[]).await.unwrap().rows.len(), 1);
+        assert_eq!(db.execute("SELECT id FROM user_sessions", &[]).await.unwrap().rows.len(), 1);
+        let bob = db.execute("SELECT email FROM users WHERE id = 'u2'", &[]).await.unwrap();
+        assert_eq!(bob.rows[0]["email"], "bob@acme.test");
+    }
+}
@@ -0,0 +1,276 @@
+//! Builders for the core structs
+//!
+//! [`ToolInfo`] and [`ToolRequest`] are `#[non_exhaustive]`, so other crates build
+//! them through [`ToolInfo::builder`] and [`ToolRequest::new`]. Optional fields get
+//! defaults, so new fields can be added without breaking callers.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+
+use crate::types::{
+    EnvironmentLabel, ToolExample, ToolId, ToolImage, ToolInfo, ToolLocalization, ToolRequest, ToolRequirements,
+    ToolSessionCommand, ToolStatus, ToolType, ToolVersion,
+};
+use crate::ValidationError;
+
+/// Builder for [`ToolInfo`]
+///
+/// `name` and `tool_type` are required. The ID defaults to a new UUID, the version
+/// to 1.0.0, the status to active and both timestamps to the time of `build`.
+#[derive(Debug, Clone, Default)]
+pub struct ToolInfoBuilder {
+    id: Option<ToolId>,
+    name: Option<String>,
+    description: String,
+    version: Option<ToolVersion>,
+    tool_type: Option<ToolType>,
+    status: Option<ToolStatus>,
+    author: String,
+    repository: Option<String>,
+    documentation: Option<String>,
+    tags: Vec<String>,
+    capabilities: Vec<String>,
+    configuration_schema: Option<serde_json::Value>,
+    examples: Vec<ToolExample>,
+    created_at: Option<DateTime<Utc>>,
+    updated_at: Option<DateTime<Utc>>,
+    environment_label: Option<EnvironmentLabel>,
+    requirements: ToolRequirements,
+    input_schema: Option<serde_json::Value>,
+    localizations: HashMap<String, ToolLocalization>,
+    environment: HashMap<String, String>,
+    session: Option<ToolSessionCommand>,
+    image: Option<ToolImage>,
+}
+
+impl ToolInfo {
+    pub fn builder() -> ToolInfoBuilder {
+        ToolInfoBuilder::default()
+    }
+}
+
+impl ToolInfoBuilder {
+    pub fn id(mut self, id: ToolId) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = description.into();
+        self
+    }
+
+    pub fn version(mut self, version: ToolVersion) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    pub fn tool_type(mut self, tool_type: ToolType) -> Self {
+        self.tool_type = Some(tool_type);
+        self
+    }
+
+    pub fn status(mut self, status: ToolStatus) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    pub fn author(mut self, author: impl Into<String>) -> Self {
+        self.author = author.into();
+        self
+    }
+
+    pub fn repository(mut self, repository: impl Into<Option<String>>) -> Self {
+        self.repository = repository.into();
+        self
+    }
+
+    pub fn documentation(mut self, documentation: impl Into<Option<String>>) -> Self {
+        self.documentation = documentation.into();
+        self
+    }
+
+    /// Add one tag
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    /// Replace the tags
+    pub fn tags<I, S>(mut self, tags: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.tags = tags.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Replace the capabilities
+    pub fn capabilities<I, S>(mut self, capabilities: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.capabilities = capabilities.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set the configuration schema, which must be a JSON object; `null` clears it
+    pub fn configuration_schema(mut self, schema: impl Into<Option<serde_json::Value>>) -> Self {
+        self.configuration_schema = schema.into().filter(|schema| !schema.is_null());
+        self
+    }
+
+    /// Add one example
+    pub fn example(mut self, example: ToolExample) -> Self {
+        self.examples.push(example);
+        self
+    }
+
+    /// Replace the examples
+    pub fn examples(mut self, examples: Vec<ToolExample>) -> Self {
+        self.examples = examples;
+        self
+    }
+
+    pub fn created_at(mut self, created_at: DateTime<Utc>) -> Self {
+        self.created_at = Some(created_at);
+        self
+    }
+
+    /// Defaults to `created_at`; must not be earlier
+    pub fn updated_at(mut self, updated_at: DateTime<Utc>) -> Self {
+        self.updated_at = Some(updated_at);
+        self
+    }
+
+    pub fn environment_label(mut self, label: impl Into<Option<EnvironmentLabel>>) -> Self {
+        self.environment_label = label.into();
+        self
+    }
+
+    pub fn requirements(mut self, requirements: ToolRequirements) -> Self {
+        self.requirements = requirements;
+        self
+    }
+
+    /// Set the input schema, which must be a JSON object; `null` clears it
+    pub fn input_schema(mut self, schema: impl Into<Option<serde_json::Value>>) -> Self {
+        self.input_schema = schema.into().filter(|schema| !schema.is_null());
+        self
+    }
+
+    /// Add the translation for one language tag
+    pub fn localization(mut self, language: impl Into<String>, localization: ToolLocalization) -> Self {
+        self.localizations.insert(language.into(), localization);
+        self
+    }
+
+    /// Replace the translations
+    pub fn localizations(mut self, localizations: HashMap<String, ToolLocalization>) -> Self {
+        self.localizations = localizations;
+        self
+    }
+
+    /// Set a default environment variable
+    pub fn env(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.environment.insert(name.into(), value.into());
+        self
+    }
+
+    /// Replace the default environment variables
+    pub fn environment(mut self, environment: HashMap<String, String>) -> Self {
+        self.environment = environment;
+        self
+    }
+
+    /// Set the interactive session command; `None` clears it
+    pub fn session(mut self, session: impl Into<Option<ToolSessionCommand>>) -> Self {
+        self.session = session.into();
+        self
+    }
+
+    /// Pin the container image by digest; `None` clears it
+    pub fn image(mut self, image: impl Into<Option<ToolImage>>) -> Self {
+        self.image = image.into();
+        self
+    }
+
+    pub fn build(self) -> Result<ToolInfo, ValidationError> {
+        let name = self.name
+            .filter(|name| !name.trim().is_empty())
+            .ok_or_else(|| ValidationError::RequiredFieldMissing("name".to_string()))?;
+        let tool_type = self.tool_type.ok_or_else(|| ValidationError::RequiredFieldMissing("tool_type".to_string()))?;
+        for (field, schema) in [("configuration_schema", &self.configuration_schema), ("input_schema", &self.input_schema)] {
+            if schema.as_ref().is_some_and(|schema| !schema.is_object()) {
+                return Err(ValidationError::InvalidFormat(format!("{} must be a JSON object", field)));
+            }
+        }
+        if self.session.as_ref().is_some_and(|session| session.command.first().is_none_or(|program| program.trim().is_empty())) {
+            return Err(ValidationError::InvalidFormat("session command must start with a program".to_string()));
+        }
+        if let Some(image) = &self.image {
+            image.validate()?;
+        }
+        let created_at = self.created_at.unwrap_or_else(Utc::now);
+        let updated_at = self.updated_at.unwrap_or(created_at);
+        if updated_at < created_at {
+            return Err(ValidationError::InvalidFormat("updated_at is earlier than created_at".to_string()));
+        }
+
+        Ok(ToolInfo {
+            id: self.id.unwrap_or_else(ToolId::new),
+            name,
+            description: self.description,
+            version: self.version.unwrap_or_else(|| ToolVersion::new(1, 0, 0)),
+            tool_type,
+            status: self.status.unwrap_or(ToolStatus::Active),
+            author: self.author,
+            repository: self.repository,
+            documentation: self.documentation,
+            tags: self.tags,
+            capabilities: self.capabilities,
+            configuration_schema: self.configuration_schema,
+            examples: self.examples,
+            created_at,
+            updated_at,
+            environment_label: self.environment_label,
+            requirements: self.requirements,
+            input_schema: self.input_schema,
+            localizations: self.localizations,
+            environment: self.environment,
+            session: self.session,
+            image: self.image,
+        })
+    }
+}
+
+impl ToolRequest {
+    /// Request to run `tool_id` with `input`, without configuration or metadata
+    pub fn new(tool_id: ToolId, input: serde_json::Value) -> Self {
+        Self {
+            tool_id,
+            input,
+            configuration: None,
+            metadata: HashMap::new(),
+        }
+    }
+
+    pub fn with_configuration(mut self, configuration: HashMap<String, serde_json::Value>) -> Self {
+        self.configuration = Some(configuration);
+        self
+    }
+
+    /// Add one metadata entry
+    pub fn with_metadata(mut self, key: impl Into<String>, value: serde_json::Value) -> Self {
+        self.metadata.insert(key.into(), value);
+        self
+    }
+}
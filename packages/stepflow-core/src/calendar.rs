@@ -0,0 +1,122 @@
+//! Calendar-aware schedules
+//!
+//! A [`CalendarSchedule`] evaluates a cron expression in the wall-clock time of an IANA
+//! timezone (`Asia/Shanghai`, `Europe/Berlin`, ...) and drops fire times that fall on a
+//! weekend or holiday when restricted to business days, or inside a blackout window.
+//! Dropped runs are skipped, not postponed: the schedule resumes at its next regular
+//! fire time.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+use crate::cron::CronSchedule;
+
+/// Upper bound on the number of fire times a preview returns
+pub const MAX_PREVIEW: usize = 100;
+
+/// How many candidate fire times [`CalendarSchedule::next_after`] rejects before giving up
+const SEARCH_LIMIT: usize = 10_000;
+
+/// A period during which no runs start, e.g. a maintenance window
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlackoutWindow {
+    pub start: DateTime<Utc>,
+    /// Exclusive
+    pub end: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl BlackoutWindow {
+    pub fn contains(&self, time: DateTime<Utc>) -> bool {
+        self.start <= time && time < self.end
+    }
+}
+
+/// A cron schedule with a timezone, business-day restriction and blackout windows
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CalendarSchedule {
+    pub cron: CronSchedule,
+    /// Timezone the cron fields are evaluated in
+    #[serde(default = "default_timezone")]
+    pub timezone: Tz,
+    /// Only fire Monday to Friday, excluding `holidays`
+    #[serde(default)]
+    pub business_days_only: bool,
+    /// Local dates that are not business days
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub holidays: Vec<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blackouts: Vec<BlackoutWindow>,
+}
+
+fn default_timezone() -> Tz {
+    Tz::UTC
+}
+
+impl CalendarSchedule {
+    /// A schedule that fires whenever `cron` matches in `timezone`
+    pub fn new(cron: CronSchedule, timezone: Tz) -> Self {
+        Self { cron, timezone, business_days_only: false, holidays: Vec::new(), blackouts: Vec::new() }
+    }
+
+    pub fn business_days_only(mut self, holidays: Vec<NaiveDate>) -> Self {
+        self.business_days_only = true;
+        self.holidays = holidays;
+        self
+    }
+
+    pub fn with_blackout(mut self, window: BlackoutWindow) -> Self {
+        self.blackouts.push(window);
+        self
+    }
+
+    /// Check the parts the cron expression and timezone do not already guarantee
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(window) = self.blackouts.iter().find(|window| window.start >= window.end) {
+            return Err(format!("blackout window starting at {} does not end after it starts", window.start));
+        }
+        Ok(())
+    }
+
+    /// First fire time strictly after `after`; `None` when the schedule never fires again
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut cursor = after;
+        for _ in 0..SEARCH_LIMIT {
+            let candidate = self.cron.next_after_in(cursor, &self.timezone)?;
+            let local_date = candidate.with_timezone(&self.timezone).date_naive();
+            if self.business_days_only && !self.is_business_day(local_date) {
+                // Nothing fires for the rest of the local day
+                cursor = self.end_of_day(local_date).unwrap_or(candidate).max(candidate);
+            } else if let Some(window) = self.blackouts.iter().find(|window| window.contains(candidate)) {
+                cursor = (window.end - Duration::minutes(1)).max(candidate);
+            } else {
+                return Some(candidate);
+            }
+        }
+        None
+    }
+
+    /// The next `count` fire times after `after`, at most [`MAX_PREVIEW`]
+    pub fn preview(&self, after: DateTime<Utc>, count: usize) -> Vec<DateTime<Utc>> {
+        let mut times = Vec::new();
+        let mut cursor = after;
+        while times.len() < count.min(MAX_PREVIEW) {
+            let Some(next) = self.next_after(cursor) else { break };
+            times.push(next);
+            cursor = next;
+        }
+        times
+    }
+
+    pub fn is_business_day(&self, date: NaiveDate) -> bool {
+        !matches!(date.weekday(), Weekday::Sat | Weekday::Sun) && !self.holidays.contains(&date)
+    }
+
+    /// Last minute of `date` in the schedule's timezone
+    fn end_of_day(&self, date: NaiveDate) -> Option<DateTime<Utc>> {
+        let last_minute = date.and_hms_opt(23, 59, 0)?;
+        self.timezone.from_local_datetime(&last_minute).earliest().map(|time| time.with_timezone(&Utc))
+    }
+}
@@ -0,0 +1,180 @@
+//! Cron expressions
+//!
+//! Standard five-field expressions (`minute hour day-of-month month day-of-week`), evaluated
+//! in UTC or, with [`CronSchedule::next_after_in`], in the wall-clock time of a timezone. Fields accept `*`, single values, ranges (`1-5`), lists (`1,15`) and steps
+//! (`*/15`, `0-30/10`). Day of week runs from 0 (Sunday) to 6; 7 is also Sunday. As in
+//! classic cron, when both day fields are restricted a time matches if either matches.
+
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, Datelike, Duration, DurationRound, LocalResult, NaiveDateTime, TimeZone, Timelike, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A parsed cron expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    expression: String,
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    day_of_month_restricted: bool,
+    day_of_week_restricted: bool,
+}
+
+/// How far ahead [`CronSchedule::next_after`] searches before giving up, e.g. for `0 0 31 2 *`
+const SEARCH_LIMIT_DAYS: i64 = 366 * 5;
+
+impl CronSchedule {
+    pub fn expression(&self) -> &str {
+        &self.expression
+    }
+
+    /// First matching minute strictly after `after`
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.next_local(after.naive_utc()).map(|time| time.and_utc())
+    }
+
+    /// First matching wall-clock minute in `timezone` strictly after `after`
+    ///
+    /// Local times skipped by a daylight saving change never fire; local times that occur
+    /// twice fire once, at the earlier instant.
+    pub fn next_after_in<Tz: TimeZone>(&self, after: DateTime<Utc>, timezone: &Tz) -> Option<DateTime<Utc>> {
+        let mut local = after.with_timezone(timezone).naive_local();
+        let limit = local + Duration::days(SEARCH_LIMIT_DAYS);
+        while local <= limit {
+            local = self.next_local(local)?;
+            match timezone.from_local_datetime(&local) {
+                LocalResult::Single(time) | LocalResult::Ambiguous(time, _) if time.with_timezone(&Utc) > after => {
+                    return Some(time.with_timezone(&Utc));
+                }
+                _ => continue,
+            }
+        }
+        None
+    }
+
+    /// First matching minute of a wall clock strictly after `after`
+    fn next_local(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let limit = after + Duration::days(SEARCH_LIMIT_DAYS);
+        let mut time = after.duration_trunc(Duration::minutes(1)).ok()? + Duration::minutes(1);
+        while time <= limit {
+            if !bit(self.months, time.month()) {
+                // First minute of the next month
+                let (year, month) = if time.month() == 12 { (time.year() + 1, 1) } else { (time.year(), time.month() + 1) };
+                time = time.with_day(1)?.with_hour(0)?.with_minute(0)?.with_year(year)?.with_month(month)?;
+            } else if !self.day_matches(time) {
+                time = time.with_hour(0)?.with_minute(0)? + Duration::days(1);
+            } else if !bit(self.hours, time.hour()) {
+                time = time.with_minute(0)? + Duration::hours(1);
+            } else if !bit(self.minutes, time.minute()) {
+                time += Duration::minutes(1);
+            } else {
+                return Some(time);
+            }
+        }
+        None
+    }
+
+    fn day_matches(&self, time: NaiveDateTime) -> bool {
+        let day_of_month = bit(self.days_of_month, time.day());
+        let day_of_week = bit(self.days_of_week, time.weekday().num_days_from_sunday());
+        match (self.day_of_month_restricted, self.day_of_week_restricted) {
+            (true, true) => day_of_month || day_of_week,
+            (true, false) => day_of_month,
+            (false, true) => day_of_week,
+            (false, false) => true,
+        }
+    }
+}
+
+fn bit(set: u64, value: u32) -> bool {
+    set & (1 << value) != 0
+}
+
+/// Parse one field into a bit set of the values it matches
+fn parse_field(field: &str, name: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut set = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step.parse().map_err(|_| format!("invalid step '{}' in {} field", step, name))?;
+                if step == 0 {
+                    return Err(format!("step must be positive in {} field", name));
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let value = |s: &str| {
+            s.parse::<u32>()
+                .ok()
+                .filter(|v| (min..=max).contains(v))
+                .ok_or_else(|| format!("{} value '{}' is outside {}-{}", name, s, min, max))
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            range => match range.split_once('-') {
+                Some((start, end)) => (value(start)?, value(end)?),
+                // `5/10` means from 5 to the end of the range
+                None if step > 1 => (value(range)?, max),
+                None => {
+                    let v = value(range)?;
+                    (v, v)
+                }
+            },
+        };
+        if start > end {
+            return Err(format!("range '{}' in {} field is reversed", range, name));
+        }
+        for v in (start..=end).step_by(step as usize) {
+            set |= 1 << v;
+        }
+    }
+    Ok(set)
+}
+
+impl FromStr for CronSchedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        let [minute, hour, day_of_month, month, day_of_week] = fields[..] else {
+            return Err(format!("cron expression '{}' must have 5 fields", s));
+        };
+        let mut days_of_week = parse_field(day_of_week, "day-of-week", 0, 7)?;
+        if bit(days_of_week, 7) {
+            days_of_week = (days_of_week | 1) & !(1 << 7);
+        }
+        Ok(Self {
+            expression: fields.join(" "),
+            minutes: parse_field(minute, "minute", 0, 59)?,
+            hours: parse_field(hour, "hour", 0, 23)?,
+            days_of_month: parse_field(day_of_month, "day-of-month", 1, 31)?,
+            months: parse_field(month, "month", 1, 12)?,
+            days_of_week,
+            day_of_month_restricted: day_of_month != "*",
+            day_of_week_restricted: day_of_week != "*",
+        })
+    }
+}
+
+impl fmt::Display for CronSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.expression)
+    }
+}
+
+impl Serialize for CronSchedule {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.expression)
+    }
+}
+
+impl<'de> Deserialize<'de> for CronSchedule {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
@@ -1,5 +1,6 @@
 //! Error types for Stepflow Tool System
 
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 /// Result type for Stepflow operations
@@ -65,6 +66,12 @@ pub enum StepflowError {
     #[error("Serialization error: {0}")]
     SerializationError(String),
 
+    #[error("Serialization error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
     #[error("Deserialization error: {0}")]
     DeserializationError(String),
 
@@ -108,6 +115,230 @@ pub enum StepflowError {
     Unknown(String),
 }
 
+/// Stable machine-readable error codes shared by every crate
+///
+/// Clients should match on these rather than on messages. Each code maps to one
+/// HTTP status and one JSON-RPC error code; see [`ErrorCode::http_status`] and
+/// [`ErrorCode::json_rpc_code`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    ToolNotFound,
+    ExecutionNotFound,
+    TenantNotFound,
+    UserNotFound,
+    NotFound,
+    AlreadyExists,
+    Conflict,
+    VersionMismatch,
+    InvalidInput,
+    ValidationFailed,
+    PolicyViolation,
+    Unauthenticated,
+    PermissionDenied,
+    RateLimited,
+    ResourceExhausted,
+    Timeout,
+    Unavailable,
+    NetworkError,
+    DatabaseError,
+    ExecutionFailed,
+    Cancelled,
+    InvalidState,
+    NotImplemented,
+    ConfigurationError,
+    Internal,
+}
+
+impl ErrorCode {
+    pub const ALL: &'static [ErrorCode] = &[
+        ErrorCode::ToolNotFound,
+        ErrorCode::ExecutionNotFound,
+        ErrorCode::TenantNotFound,
+        ErrorCode::UserNotFound,
+        ErrorCode::NotFound,
+        ErrorCode::AlreadyExists,
+        ErrorCode::Conflict,
+        ErrorCode::VersionMismatch,
+        ErrorCode::InvalidInput,
+        ErrorCode::ValidationFailed,
+        ErrorCode::PolicyViolation,
+        ErrorCode::Unauthenticated,
+        ErrorCode::PermissionDenied,
+        ErrorCode::RateLimited,
+        ErrorCode::ResourceExhausted,
+        ErrorCode::Timeout,
+        ErrorCode::Unavailable,
+        ErrorCode::NetworkError,
+        ErrorCode::DatabaseError,
+        ErrorCode::ExecutionFailed,
+        ErrorCode::Cancelled,
+        ErrorCode::InvalidState,
+        ErrorCode::NotImplemented,
+        ErrorCode::ConfigurationError,
+        ErrorCode::Internal,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::ToolNotFound => "TOOL_NOT_FOUND",
+            ErrorCode::ExecutionNotFound => "EXECUTION_NOT_FOUND",
+            ErrorCode::TenantNotFound => "TENANT_NOT_FOUND",
+            ErrorCode::UserNotFound => "USER_NOT_FOUND",
+            ErrorCode::NotFound => "NOT_FOUND",
+            ErrorCode::AlreadyExists => "ALREADY_EXISTS",
+            ErrorCode::Conflict => "CONFLICT",
+            ErrorCode::VersionMismatch => "VERSION_MISMATCH",
+            ErrorCode::InvalidInput => "INVALID_INPUT",
+            ErrorCode::ValidationFailed => "VALIDATION_FAILED",
+            ErrorCode::PolicyViolation => "POLICY_VIOLATION",
+            ErrorCode::Unauthenticated => "UNAUTHENTICATED",
+            ErrorCode::PermissionDenied => "PERMISSION_DENIED",
+            ErrorCode::RateLimited => "RATE_LIMITED",
+            ErrorCode::ResourceExhausted => "RESOURCE_EXHAUSTED",
+            ErrorCode::Timeout => "TIMEOUT",
+            ErrorCode::Unavailable => "UNAVAILABLE",
+            ErrorCode::NetworkError => "NETWORK_ERROR",
+            ErrorCode::DatabaseError => "DATABASE_ERROR",
+            ErrorCode::ExecutionFailed => "EXECUTION_FAILED",
+            ErrorCode::Cancelled => "CANCELLED",
+            ErrorCode::InvalidState => "INVALID_STATE",
+            ErrorCode::NotImplemented => "NOT_IMPLEMENTED",
+            ErrorCode::ConfigurationError => "CONFIGURATION_ERROR",
+            ErrorCode::Internal => "INTERNAL",
+        }
+    }
+
+    /// HTTP status for responses carrying this code
+    pub fn http_status(&self) -> u16 {
+        match self {
+            ErrorCode::InvalidInput | ErrorCode::PolicyViolation => 400,
+            ErrorCode::Unauthenticated => 401,
+            ErrorCode::PermissionDenied => 403,
+            ErrorCode::ToolNotFound
+            | ErrorCode::ExecutionNotFound
+            | ErrorCode::TenantNotFound
+            | ErrorCode::UserNotFound
+            | ErrorCode::NotFound => 404,
+            ErrorCode::AlreadyExists
+            | ErrorCode::Conflict
+            | ErrorCode::VersionMismatch
+            | ErrorCode::Cancelled
+            | ErrorCode::InvalidState => 409,
+            ErrorCode::ValidationFailed => 422,
+            ErrorCode::RateLimited => 429,
+            ErrorCode::NotImplemented => 501,
+            ErrorCode::NetworkError => 502,
+            ErrorCode::ResourceExhausted | ErrorCode::Unavailable => 503,
+            ErrorCode::Timeout => 504,
+            ErrorCode::DatabaseError
+            | ErrorCode::ExecutionFailed
+            | ErrorCode::ConfigurationError
+            | ErrorCode::Internal => 500,
+        }
+    }
+
+    /// JSON-RPC error code; domain errors use the server range -32000 to -32099
+    pub fn json_rpc_code(&self) -> i32 {
+        match self {
+            ErrorCode::InvalidInput | ErrorCode::ValidationFailed | ErrorCode::PolicyViolation => -32602,
+            ErrorCode::NotImplemented => -32601,
+            ErrorCode::Unauthenticated => -32001,
+            ErrorCode::PermissionDenied => -32003,
+            ErrorCode::ToolNotFound
+            | ErrorCode::ExecutionNotFound
+            | ErrorCode::TenantNotFound
+            | ErrorCode::UserNotFound
+            | ErrorCode::NotFound => -32004,
+            ErrorCode::RateLimited | ErrorCode::ResourceExhausted => -32005,
+            ErrorCode::Unavailable | ErrorCode::NetworkError => -32006,
+            ErrorCode::Timeout => -32007,
+            ErrorCode::AlreadyExists
+            | ErrorCode::Conflict
+            | ErrorCode::VersionMismatch
+            | ErrorCode::InvalidState => -32009,
+            ErrorCode::ExecutionFailed => -32010,
+            ErrorCode::Cancelled => -32011,
+            ErrorCode::DatabaseError | ErrorCode::ConfigurationError | ErrorCode::Internal => -32603,
+        }
+    }
+
+    /// Whether the same request may succeed if retried later
+    pub fn retryable(&self) -> bool {
+        matches!(
+            self,
+            ErrorCode::RateLimited
+                | ErrorCode::ResourceExhausted
+                | ErrorCode::Timeout
+                | ErrorCode::Unavailable
+                | ErrorCode::NetworkError
+        )
+    }
+
+    /// Whether the message is meant for the caller; internal failures should be
+    /// reported generically and logged instead
+    pub fn user_facing(&self) -> bool {
+        !matches!(
+            self,
+            ErrorCode::DatabaseError | ErrorCode::ConfigurationError | ErrorCode::Internal
+        )
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl StepflowError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            StepflowError::ToolNotFound(_) => ErrorCode::ToolNotFound,
+            StepflowError::ExecutionNotFound(_) => ErrorCode::ExecutionNotFound,
+            StepflowError::TenantNotFound(_) => ErrorCode::TenantNotFound,
+            StepflowError::UserNotFound(_) => ErrorCode::UserNotFound,
+            StepflowError::ToolExecutionFailed(_) | StepflowError::ExecutionFailed(_) => ErrorCode::ExecutionFailed,
+            StepflowError::ToolValidationFailed(_) | StepflowError::ValidationError(_) => ErrorCode::ValidationFailed,
+            StepflowError::InvalidInput(_)
+            | StepflowError::DeserializationError(_)
+            | StepflowError::Json(_) => ErrorCode::InvalidInput,
+            StepflowError::AuthenticationFailed(_) => ErrorCode::Unauthenticated,
+            StepflowError::AuthorizationFailed(_) | StepflowError::PermissionDenied(_) => ErrorCode::PermissionDenied,
+            StepflowError::SecurityViolation(_) => ErrorCode::PolicyViolation,
+            StepflowError::ConfigurationError(_) => ErrorCode::ConfigurationError,
+            StepflowError::DatabaseError(e) => e.code(),
+            StepflowError::NetworkError(_) => ErrorCode::NetworkError,
+            StepflowError::TimeoutError(_) => ErrorCode::Timeout,
+            StepflowError::RateLimitExceeded => ErrorCode::RateLimited,
+            StepflowError::ResourceExhausted(_) => ErrorCode::ResourceExhausted,
+            StepflowError::ResourceNotAvailable(_) | StepflowError::ServiceUnavailable(_) => ErrorCode::Unavailable,
+            StepflowError::NotImplemented(_) | StepflowError::UnsupportedOperation(_) => ErrorCode::NotImplemented,
+            StepflowError::VersionMismatch(_) => ErrorCode::VersionMismatch,
+            StepflowError::Conflict(_) => ErrorCode::Conflict,
+            StepflowError::AlreadyExists(_) => ErrorCode::AlreadyExists,
+            StepflowError::InvalidState(_) => ErrorCode::InvalidState,
+            StepflowError::Cancelled(_) => ErrorCode::Cancelled,
+            StepflowError::SerializationError(_)
+            | StepflowError::Io(_)
+            | StepflowError::DependencyError(_)
+            | StepflowError::InternalError(_)
+            | StepflowError::Unknown(_) => ErrorCode::Internal,
+        }
+    }
+
+    pub fn retryable(&self) -> bool {
+        match self {
+            StepflowError::DatabaseError(e) => e.retryable(),
+            _ => self.code().retryable(),
+        }
+    }
+
+    pub fn user_facing(&self) -> bool {
+        self.code().user_facing()
+    }
+}
+
 /// Database-specific errors
 #[derive(Error, Debug)]
 pub enum DatabaseError {
@@ -142,6 +373,28 @@ pub enum DatabaseError {
     DataCorruption(String),
 }
 
+impl DatabaseError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            DatabaseError::ConstraintViolation(_) => ErrorCode::Conflict,
+            DatabaseError::Timeout(_) => ErrorCode::Timeout,
+            DatabaseError::PoolExhausted => ErrorCode::ResourceExhausted,
+            _ => ErrorCode::DatabaseError,
+        }
+    }
+
+    /// Transient failures that usually clear on their own
+    pub fn retryable(&self) -> bool {
+        matches!(
+            self,
+            DatabaseError::ConnectionFailed(_)
+                | DatabaseError::Deadlock
+                | DatabaseError::Timeout(_)
+                | DatabaseError::PoolExhausted
+        )
+    }
+}
+
 /// Validation errors
 #[derive(Error, Debug)]
 pub enum ValidationError {
@@ -402,18 +655,6 @@ impl From<MonitoringError> for StepflowError {
     }
 }
 
-impl From<serde_json::Error> for StepflowError {
-    fn from(err: serde_json::Error) -> Self {
-        StepflowError::SerializationError(err.to_string())
-    }
-}
-
-impl From<std::io::Error> for StepflowError {
-    fn from(err: std::io::Error) -> Self {
-        StepflowError::InternalError(err.to_string())
-    }
-}
-
 impl From<uuid::Error> for StepflowError {
     fn from(err: uuid::Error) -> Self {
         StepflowError::ValidationError(err.to_string())
@@ -0,0 +1,237 @@
+//! CloudEvents envelope
+//!
+//! Events leaving a component, whether on the RPC event bus, to a webhook or to an
+//! external broker, are wrapped in a [CloudEvents 1.0](https://cloudevents.io) envelope
+//! in structured JSON mode. Payloads implement [`EventData`], which supplies the
+//! envelope attributes: `source` names the producing component, `type` is the payload's
+//! event type under the `io.stepflow.` prefix, and `dataschema` names the payload
+//! schema and its version, e.g. `urn:stepflow:event:execution.state_changed:v1`. A
+//! payload's version is bumped whenever a field is removed, renamed or changes meaning;
+//! adding a field is compatible and keeps the version. The tenant an event belongs to
+//! travels in the `tenantid` extension attribute.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// CloudEvents specification version of every envelope
+pub const CLOUDEVENTS_SPEC_VERSION: &str = "1.0";
+
+/// Content type of an event sent in structured mode, e.g. as a webhook body
+pub const CLOUDEVENTS_CONTENT_TYPE: &str = "application/cloudevents+json";
+
+/// Prefix of every Stepflow event type
+pub const EVENT_TYPE_PREFIX: &str = "io.stepflow.";
+
+const SCHEMA_PREFIX: &str = "urn:stepflow:event:";
+
+/// An event payload
+pub trait EventData: Serialize {
+    /// Producing component, e.g. `/stepflow/registry`
+    const SOURCE: &'static str;
+    /// Name of the payload schema, shared by every event type it is used for
+    const SCHEMA: &'static str;
+    /// Version of the payload schema
+    const SCHEMA_VERSION: u32;
+
+    /// Event type without [`EVENT_TYPE_PREFIX`], e.g. `registry.tool.created`
+    fn event_type(&self) -> String;
+
+    /// Stable event id, so redeliveries can be recognized; a random id is used otherwise
+    fn event_id(&self) -> Option<String> {
+        None
+    }
+
+    /// Resource the event is about
+    fn subject(&self) -> Option<String> {
+        None
+    }
+
+    fn tenant_id(&self) -> Option<String> {
+        None
+    }
+}
+
+/// A CloudEvents 1.0 envelope, `data` always being JSON
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CloudEvent<T = Value> {
+    #[serde(rename = "specversion")]
+    pub spec_version: String,
+    pub id: String,
+    pub source: String,
+    #[serde(rename = "type")]
+    pub event_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    pub time: DateTime<Utc>,
+    #[serde(rename = "datacontenttype")]
+    pub data_content_type: String,
+    #[serde(rename = "dataschema", default, skip_serializing_if = "Option::is_none")]
+    pub data_schema: Option<String>,
+    /// `tenantid` extension attribute
+    #[serde(rename = "tenantid", default, skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<String>,
+    pub data: T,
+}
+
+impl<T: EventData> CloudEvent<T> {
+    /// Wrap `data`, taking the envelope attributes from it; `time` is now
+    pub fn new(data: T) -> Self {
+        Self {
+            spec_version: CLOUDEVENTS_SPEC_VERSION.to_string(),
+            id: data.event_id().unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+            source: T::SOURCE.to_string(),
+            event_type: format!("{}{}", EVENT_TYPE_PREFIX, data.event_type()),
+            subject: data.subject(),
+            time: Utc::now(),
+            data_content_type: "application/json".to_string(),
+            data_schema: Some(format!("{}{}:v{}", SCHEMA_PREFIX, T::SCHEMA, T::SCHEMA_VERSION)),
+            tenant_id: data.tenant_id(),
+            data,
+        }
+    }
+}
+
+impl<T> CloudEvent<T> {
+    /// When the event happened, if not now
+    pub fn with_time(mut self, time: DateTime<Utc>) -> Self {
+        self.time = time;
+        self
+    }
+
+    /// Schema name and version from `dataschema`, e.g. `("alert.notification", 1)`
+    pub fn schema(&self) -> Option<(&str, u32)> {
+        let (name, version) = self.data_schema.as_deref()?.strip_prefix(SCHEMA_PREFIX)?.rsplit_once(":v")?;
+        Some((name, version.parse().ok()?))
+    }
+}
+
+impl<T: Serialize> CloudEvent<T> {
+    /// The same event with its payload as a JSON value
+    pub fn into_json(self) -> CloudEvent<Value> {
+        CloudEvent {
+            spec_version: self.spec_version,
+            id: self.id,
+            source: self.source,
+            event_type: self.event_type,
+            subject: self.subject,
+            time: self.time,
+            data_content_type: self.data_content_type,
+            data_schema: self.data_schema,
+            tenant_id: self.tenant_id,
+            data: serde_json::to_value(&self.data).unwrap_or(Value::Null),
+        }
+    }
+}
+
+/// An execution moved to a new state, as recorded in `execution_events`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionStateChanged {
+    /// Position in the execution event log
+    pub sequence: i64,
+    pub execution_id: String,
+    pub tool_id: Option<String>,
+    pub tenant_id: Option<String>,
+    /// e.g. `queued`, `running`, `completed` or `failed`
+    pub state: String,
+    pub worker_id: Option<String>,
+    pub detail: Option<String>,
+}
+
+impl EventData for ExecutionStateChanged {
+    const SOURCE: &'static str = "/stepflow/executions";
+    const SCHEMA: &'static str = "execution.state_changed";
+    const SCHEMA_VERSION: u32 = 1;
+
+    fn event_type(&self) -> String {
+        format!("execution.{}", self.state)
+    }
+
+    fn event_id(&self) -> Option<String> {
+        Some(format!("execution-{}", self.sequence))
+    }
+
+    fn subject(&self) -> Option<String> {
+        Some(self.execution_id.clone())
+    }
+
+    fn tenant_id(&self) -> Option<String> {
+        self.tenant_id.clone()
+    }
+}
+
+/// A tool was created, updated, deleted or got a new version, as recorded in `registry_changes`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolChanged {
+    /// Position in the registry change feed
+    pub sequence: u64,
+    /// `created`, `updated`, `deleted` or `version_added`
+    pub change_type: String,
+    pub tool_id: String,
+    pub version: String,
+    /// Tool state after the change; `None` for deletions
+    pub tool: Option<Value>,
+    pub changed_at: DateTime<Utc>,
+}
+
+impl EventData for ToolChanged {
+    const SOURCE: &'static str = "/stepflow/registry";
+    const SCHEMA: &'static str = "registry.tool_changed";
+    const SCHEMA_VERSION: u32 = 1;
+
+    fn event_type(&self) -> String {
+        format!("registry.tool.{}", self.change_type)
+    }
+
+    fn event_id(&self) -> Option<String> {
+        Some(format!("registry-{}", self.sequence))
+    }
+
+    fn subject(&self) -> Option<String> {
+        Some(self.tool_id.clone())
+    }
+
+    fn tenant_id(&self) -> Option<String> {
+        self.tool.as_ref()?.get("tenant_id")?.as_str().map(str::to_string)
+    }
+}
+
+/// A security relevant action, as recorded in `security_events`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecorded {
+    pub event_id: String,
+    /// e.g. `login`, `login_failed` or `permission_denied`
+    pub event_type: String,
+    pub user_id: Option<String>,
+    pub tenant_id: Option<String>,
+    pub resource_type: Option<String>,
+    pub resource_id: Option<String>,
+    pub action: Option<String>,
+    pub details: Option<Value>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub success: bool,
+    pub error_message: Option<String>,
+}
+
+impl EventData for AuditRecorded {
+    const SOURCE: &'static str = "/stepflow/audit";
+    const SCHEMA: &'static str = "audit.recorded";
+    const SCHEMA_VERSION: u32 = 1;
+
+    fn event_type(&self) -> String {
+        format!("audit.{}", self.event_type)
+    }
+
+    fn event_id(&self) -> Option<String> {
+        Some(format!("audit-{}", self.event_id))
+    }
+
+    fn subject(&self) -> Option<String> {
+        self.resource_id.clone()
+    }
+
+    fn tenant_id(&self) -> Option<String> {
+        self.tenant_id.clone()
+    }
+}
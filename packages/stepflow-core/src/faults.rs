@@ -0,0 +1,193 @@
+//! Fault injection for resilience tests
+//!
+//! Only compiled with the `fault-injection` feature. A [`FaultController`] is attached to
+//! a component (database, RPC client, HTTP proxy, worker pool) and decides, per call,
+//! whether to add latency, fail the call, or let a write go through and then report a
+//! failure anyway. Decisions come from a seeded generator, so a failing test can be
+//! replayed with the same seed.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// Component a fault is injected into
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FaultTarget {
+    Database,
+    RpcClient,
+    HttpProxy,
+    WorkerPool,
+}
+
+/// Fault probabilities for one target; all probabilities are in `0.0..=1.0`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FaultConfig {
+    /// Probability that the call fails before doing anything
+    pub error_probability: f64,
+    /// Probability that the call is delayed by `latency`
+    pub latency_probability: f64,
+    /// Delay applied when latency is injected
+    pub latency: Duration,
+    /// Probability that the call takes effect but still reports a failure
+    pub partial_write_probability: f64,
+}
+
+impl FaultConfig {
+    /// Fail every call
+    pub fn always_fail() -> Self {
+        Self {
+            error_probability: 1.0,
+            ..Self::default()
+        }
+    }
+
+    /// Fail calls with the given probability
+    pub fn errors(probability: f64) -> Self {
+        Self {
+            error_probability: probability,
+            ..Self::default()
+        }
+    }
+
+    /// Delay calls by `latency` with the given probability
+    pub fn latency(probability: f64, latency: Duration) -> Self {
+        Self {
+            latency_probability: probability,
+            latency,
+            ..Self::default()
+        }
+    }
+
+    /// Apply calls but report failure with the given probability
+    pub fn partial_writes(probability: f64) -> Self {
+        Self {
+            partial_write_probability: probability,
+            ..Self::default()
+        }
+    }
+}
+
+/// Fault chosen for a call, after any injected latency has elapsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// Fail without performing the operation
+    Error,
+    /// Perform the operation, then fail as if its result was lost
+    PartialWrite,
+}
+
+impl Fault {
+    /// Error message used by components when surfacing the fault
+    pub fn message(&self, target: FaultTarget) -> String {
+        match self {
+            Fault::Error => format!("Injected {:?} fault: connection error", target),
+            Fault::PartialWrite => format!("Injected {:?} fault: connection lost after write", target),
+        }
+    }
+}
+
+/// Counters of injected faults for one target
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FaultStats {
+    pub calls: u64,
+    pub errors: u64,
+    pub delays: u64,
+    pub partial_writes: u64,
+}
+
+#[derive(Debug)]
+struct ControllerState {
+    rng: u64,
+    configs: HashMap<FaultTarget, FaultConfig>,
+    stats: HashMap<FaultTarget, FaultStats>,
+}
+
+impl ControllerState {
+    /// xorshift64* in `0.0..1.0`
+    fn roll(&mut self) -> f64 {
+        self.rng ^= self.rng >> 12;
+        self.rng ^= self.rng << 25;
+        self.rng ^= self.rng >> 27;
+        (self.rng.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Shared handle controlling injected faults; clones share configuration and counters
+#[derive(Debug, Clone)]
+pub struct FaultController {
+    state: Arc<Mutex<ControllerState>>,
+}
+
+impl Default for FaultController {
+    fn default() -> Self {
+        Self::new(0x5EED)
+    }
+}
+
+impl FaultController {
+    /// Create a controller with no faults configured; `seed` makes decisions reproducible
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(ControllerState {
+                rng: seed.max(1),
+                configs: HashMap::new(),
+                stats: HashMap::new(),
+            })),
+        }
+    }
+
+    /// Set the fault configuration for `target`
+    pub fn set(&self, target: FaultTarget, config: FaultConfig) {
+        self.state.lock().unwrap().configs.insert(target, config);
+    }
+
+    /// Stop injecting faults into `target`
+    pub fn clear(&self, target: FaultTarget) {
+        self.state.lock().unwrap().configs.remove(&target);
+    }
+
+    /// Stop injecting faults into every target
+    pub fn clear_all(&self) {
+        self.state.lock().unwrap().configs.clear();
+    }
+
+    /// Counters for `target`
+    pub fn stats(&self, target: FaultTarget) -> FaultStats {
+        self.state.lock().unwrap().stats.get(&target).cloned().unwrap_or_default()
+    }
+
+    /// Decide the fault for one call to `target`, sleeping first if latency is injected
+    pub async fn inject(&self, target: FaultTarget) -> Option<Fault> {
+        let (delay, fault) = {
+            let mut state = self.state.lock().unwrap();
+            let config = state.configs.get(&target).cloned().unwrap_or_default();
+            let delay = (state.roll() < config.latency_probability).then_some(config.latency);
+            let fault = if state.roll() < config.error_probability {
+                Some(Fault::Error)
+            } else if state.roll() < config.partial_write_probability {
+                Some(Fault::PartialWrite)
+            } else {
+                None
+            };
+
+            let stats = state.stats.entry(target).or_default();
+            stats.calls += 1;
+            stats.delays += delay.is_some() as u64;
+            match fault {
+                Some(Fault::Error) => stats.errors += 1,
+                Some(Fault::PartialWrite) => stats.partial_writes += 1,
+                None => {}
+            }
+            (delay, fault)
+        };
+
+        if let Some(delay) = delay.filter(|delay| !delay.is_zero()) {
+            tokio::time::sleep(delay).await;
+        }
+        fault
+    }
+}
+
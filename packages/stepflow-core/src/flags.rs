@@ -0,0 +1,255 @@
+//! Feature flags
+//!
+//! Flags let risky features ship dark and be turned on per tenant, per user or for
+//! a percentage of tenants. Definitions come from the configuration file and the
+//! `feature_flags` table, which wins on conflicts and can be changed at runtime.
+//! Evaluation is in-process and never touches storage.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::errors::ValidationError;
+
+/// Flag names known to the code base
+///
+/// Flags can have any name, these are only the ones checked by Stepflow itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Flag(&'static str);
+
+impl Flag {
+    /// Queue scheduling rewrite
+    pub const NEW_SCHEDULER: Flag = Flag("new_scheduler");
+    /// Sandbox runtime rewrite
+    pub const NEW_SANDBOX: Flag = Flag("new_sandbox");
+
+    pub const fn new(name: &'static str) -> Self {
+        Self(name)
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.0
+    }
+}
+
+/// Definition of one flag
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FlagDefinition {
+    pub name: String,
+    pub description: Option<String>,
+    /// Kill switch; a disabled flag is off for everyone, overrides included
+    pub enabled: bool,
+    /// Share of tenants (or users, without a tenant) the flag is on for, 0 to 100
+    pub rollout_percentage: u8,
+    /// Per-tenant overrides, checked before the rollout
+    pub tenants: BTreeMap<String, bool>,
+    /// Per-user overrides, checked before the tenant overrides
+    pub users: BTreeMap<String, bool>,
+    pub updated_by: Option<String>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl Default for FlagDefinition {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            description: None,
+            enabled: true,
+            rollout_percentage: 0,
+            tenants: BTreeMap::new(),
+            users: BTreeMap::new(),
+            updated_by: None,
+            updated_at: None,
+        }
+    }
+}
+
+impl FlagDefinition {
+    /// Enabled flag that is on for nobody until overrides or a rollout are added
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into(), ..Self::default() }
+    }
+
+    pub fn with_rollout(mut self, percentage: u8) -> Self {
+        self.rollout_percentage = percentage;
+        self
+    }
+
+    pub fn with_tenant(mut self, tenant_id: impl Into<String>, on: bool) -> Self {
+        self.tenants.insert(tenant_id.into(), on);
+        self
+    }
+
+    pub fn with_user(mut self, user_id: impl Into<String>, on: bool) -> Self {
+        self.users.insert(user_id.into(), on);
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let valid_name = !self.name.is_empty()
+            && self.name.len() <= 64
+            && self.name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '.');
+        if !valid_name {
+            return Err(ValidationError::InvalidFormat(format!(
+                "flag name '{}' must be 1-64 lowercase letters, digits, '_' or '.'",
+                self.name
+            )));
+        }
+        if self.rollout_percentage > 100 {
+            return Err(ValidationError::ValueOutOfRange(format!(
+                "rollout_percentage of flag {} must be at most 100",
+                self.name
+            )));
+        }
+        Ok(())
+    }
+
+    /// Whether the flag can only be on for `tenant_id`: no rollout, no user
+    /// overrides and no overrides for other tenants
+    pub fn is_scoped_to(&self, tenant_id: &str) -> bool {
+        self.rollout_percentage == 0 && self.users.is_empty() && self.tenants.keys().all(|tenant| tenant == tenant_id)
+    }
+
+    /// Whether the flag is on for `context`
+    ///
+    /// User overrides win over tenant overrides, which win over the rollout. The
+    /// rollout buckets by tenant so a tenant's users all see the same behaviour.
+    pub fn evaluate(&self, context: &FlagContext) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        if let Some(on) = context.user_id.as_ref().and_then(|user| self.users.get(user)) {
+            return *on;
+        }
+        if let Some(on) = context.tenant_id.as_ref().and_then(|tenant| self.tenants.get(tenant)) {
+            return *on;
+        }
+        match self.rollout_percentage {
+            0 => false,
+            100.. => true,
+            percentage => context
+                .rollout_key()
+                .is_some_and(|key| rollout_bucket(&self.name, key) < u32::from(percentage)),
+        }
+    }
+}
+
+/// Who a flag is evaluated for
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FlagContext {
+    pub tenant_id: Option<String>,
+    pub user_id: Option<String>,
+}
+
+impl FlagContext {
+    pub fn new(tenant_id: Option<String>, user_id: Option<String>) -> Self {
+        Self { tenant_id, user_id }
+    }
+
+    pub fn tenant(tenant_id: impl Into<String>) -> Self {
+        Self { tenant_id: Some(tenant_id.into()), user_id: None }
+    }
+
+    fn rollout_key(&self) -> Option<&str> {
+        self.tenant_id.as_deref().or(self.user_id.as_deref())
+    }
+}
+
+/// Stable bucket in 0..100 for `key` under `flag`
+///
+/// FNV-1a keeps buckets identical across processes and releases, so raising the
+/// percentage only ever adds tenants.
+fn rollout_bucket(flag: &str, key: &str) -> u32 {
+    let mut hash: u32 = 0x811c_9dc5;
+    for byte in flag.bytes().chain([b':']).chain(key.bytes()) {
+        hash ^= u32::from(byte);
+        hash = hash.wrapping_mul(0x0100_0193);
+    }
+    hash % 100
+}
+
+/// Shared set of flag definitions
+///
+/// Holds the defaults from the configuration file and, on top of them, the flags
+/// set at runtime. Cloning is cheap and clones see each other's updates, so the
+/// handle given to the executor and the API reflects changes made through the
+/// admin API.
+#[derive(Debug, Clone, Default)]
+pub struct Flags {
+    defaults: Arc<HashMap<String, FlagDefinition>>,
+    definitions: Arc<RwLock<HashMap<String, FlagDefinition>>>,
+}
+
+impl Flags {
+    pub fn new(definitions: impl IntoIterator<Item = FlagDefinition>) -> Self {
+        Self::with_defaults(Vec::new(), definitions)
+    }
+
+    /// Config `defaults` overridden by flags with the same name in `stored`
+    pub fn with_defaults(
+        defaults: impl IntoIterator<Item = FlagDefinition>,
+        stored: impl IntoIterator<Item = FlagDefinition>,
+    ) -> Self {
+        let flags = Self {
+            defaults: Arc::new(defaults.into_iter().map(|definition| (definition.name.clone(), definition)).collect()),
+            definitions: Arc::default(),
+        };
+        flags.replace(stored);
+        flags
+    }
+
+    /// Whether `flag` is on for `context`; unknown flags are off
+    pub fn is_enabled(&self, flag: Flag, context: &FlagContext) -> bool {
+        self.is_enabled_by_name(flag.name(), context)
+    }
+
+    pub fn is_enabled_by_name(&self, name: &str, context: &FlagContext) -> bool {
+        let definitions = self.definitions.read().unwrap_or_else(|e| e.into_inner());
+        definitions.get(name).is_some_and(|definition| definition.evaluate(context))
+    }
+
+    pub fn get(&self, name: &str) -> Option<FlagDefinition> {
+        self.definitions.read().unwrap_or_else(|e| e.into_inner()).get(name).cloned()
+    }
+
+    /// All definitions in effect, sorted by name
+    pub fn definitions(&self) -> Vec<FlagDefinition> {
+        let definitions = self.definitions.read().unwrap_or_else(|e| e.into_inner());
+        let mut definitions: Vec<_> = definitions.values().cloned().collect();
+        definitions.sort_by(|a, b| a.name.cmp(&b.name));
+        definitions
+    }
+
+    /// Add or replace one definition
+    pub fn set(&self, definition: FlagDefinition) {
+        self.definitions
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(definition.name.clone(), definition);
+    }
+
+    /// Drop the runtime definition of `name`, returning the default now in effect
+    pub fn reset(&self, name: &str) -> Option<FlagDefinition> {
+        let mut definitions = self.definitions.write().unwrap_or_else(|e| e.into_inner());
+        match self.defaults.get(name) {
+            Some(default) => {
+                definitions.insert(name.to_string(), default.clone());
+                Some(default.clone())
+            }
+            None => {
+                definitions.remove(name);
+                None
+            }
+        }
+    }
+
+    /// Replace every runtime definition, e.g. after reloading them from storage
+    pub fn replace(&self, stored: impl IntoIterator<Item = FlagDefinition>) {
+        let mut definitions = (*self.defaults).clone();
+        definitions.extend(stored.into_iter().map(|definition| (definition.name.clone(), definition)));
+        *self.definitions.write().unwrap_or_else(|e| e.into_inner()) = definitions;
+    }
+}
@@ -5,28 +5,39 @@
 //! for all other packages in the system.
 
 pub mod types;
+pub mod builders;
 pub mod traits;
 pub mod errors;
 pub mod config;
 pub mod security;
 pub mod monitoring;
 pub mod models;
+pub mod simulated;
+pub mod network_acl;
+pub mod cron;
+pub mod calendar;
+pub mod events;
+pub mod flags;
+#[cfg(feature = "fault-injection")]
+pub mod faults;
 
 // Re-export specific types to avoid conflicts
 pub use types::{
-    ToolId, ToolVersion, ToolType, ToolStatus, ToolInfo, ToolExample, ToolConfig,
+    ToolId, MAX_ID_LEN, ToolVersion, ToolType, ToolStatus, ToolInfo, ToolExample, ExampleMatch, ToolConfig, EnvironmentLabel, ToolRequirements,
+    ToolLocalization, ToolSessionCommand, ToolImage,
     ToolRequest, ToolResponse, ToolStats, ExecutionId, ExecutionStatus, ExecutionResult,
     LogEntry, LogLevel, TenantId, TenantInfo, UserId, UserRole, UserInfo,
     Pagination, Filter, FilterOperator, Sort, SortDirection, Query, Metric, Execution
 };
+pub use builders::ToolInfoBuilder;
 pub use traits::{
     ToolRegistry, ToolExecutor, ExecutionManager, TenantManager, UserManager,
     Authenticator, Authorizer, Monitor, Cache, Database, ToolFilter,
     ExecutionFilter, TenantFilter, UserFilter, Credentials, AuthResult, Permission,
-    Event, MetricFilter, EventFilter, CacheStats, QueryResult, Migration, DatabaseStats
+    Event, MetricFilter, EventFilter, CacheStats, QueryResult, Migration, DatabaseStats, ReadConsistency
 };
 pub use errors::{
-    StepflowError, StepflowResult, DatabaseError, ValidationError, SecurityError,
+    StepflowError, StepflowResult, ErrorCode, DatabaseError, ValidationError, SecurityError,
     NetworkError, ConfigurationError, MonitoringError
 };
 pub use models::{
@@ -35,6 +46,17 @@ pub use models::{
 pub use config::*;
 pub use security::*;
 pub use monitoring::*;
+pub use simulated::SimulatedFaults;
+pub use cron::CronSchedule;
+pub use calendar::{BlackoutWindow, CalendarSchedule};
+pub use events::{
+    AuditRecorded, CloudEvent, EventData, ExecutionStateChanged, ToolChanged, CLOUDEVENTS_CONTENT_TYPE,
+    CLOUDEVENTS_SPEC_VERSION, EVENT_TYPE_PREFIX,
+};
+pub use flags::{Flag, FlagContext, FlagDefinition, Flags};
+pub use network_acl::{AclDecision, AclMetrics, AclRules, AclScope, IpNetwork, NetworkAcl, NetworkAclConfig};
+#[cfg(feature = "fault-injection")]
+pub use faults::{Fault, FaultConfig, FaultController, FaultStats, FaultTarget};
 
 /// Re-export commonly used types for convenience
 pub mod prelude {
@@ -0,0 +1,300 @@
+//! Network access control lists
+//!
+//! CIDR allow and deny lists checked against the client address before a request is
+//! authenticated. A global list applies to every connection; tenants can add their own
+//! list, applied once the tenant of a request is known (from the request host or the
+//! authenticated token). Deny entries always win, and a non-empty allow list admits only
+//! the addresses it contains.
+//!
+//! `X-Forwarded-For` is only honoured when the direct peer is a trusted proxy. The header
+//! is read from the right, skipping trusted proxies, so a client cannot spoof its address
+//! by prepending entries.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// An IPv4 or IPv6 network in CIDR notation; a bare address is a single host
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IpNetwork {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl IpNetwork {
+    /// Network containing `addr`, with the host bits cleared
+    pub fn new(addr: IpAddr, prefix: u8) -> Result<Self, String> {
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        if prefix > max {
+            return Err(format!("prefix /{} is too long for {}", prefix, addr));
+        }
+        let addr = match addr {
+            IpAddr::V4(v4) => IpAddr::from((u32::from(v4) & mask(prefix, 32) as u32).to_be_bytes()),
+            IpAddr::V6(v6) => IpAddr::from((u128::from(v6) & mask(prefix, 128)).to_be_bytes()),
+        };
+        Ok(Self { addr, prefix })
+    }
+
+    pub fn addr(&self) -> IpAddr {
+        self.addr
+    }
+
+    pub fn prefix(&self) -> u8 {
+        self.prefix
+    }
+
+    /// Whether `ip` is in the network; IPv4-mapped IPv6 addresses match IPv4 networks
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                u32::from(ip) & mask(self.prefix, 32) as u32 == u32::from(network)
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => u128::from(ip) & mask(self.prefix, 128) == u128::from(network),
+            _ => false,
+        }
+    }
+}
+
+/// Mask with the top `prefix` of `bits` bits set
+fn mask(prefix: u8, bits: u32) -> u128 {
+    if prefix == 0 {
+        0
+    } else {
+        (u128::MAX << (bits - prefix as u32)) & (u128::MAX >> (128 - bits))
+    }
+}
+
+impl FromStr for IpNetwork {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s, None),
+        };
+        let addr: IpAddr = addr.parse().map_err(|_| format!("invalid network address '{}'", s))?;
+        let prefix = match prefix {
+            Some(prefix) => prefix.parse().map_err(|_| format!("invalid prefix length in '{}'", s))?,
+            None if addr.is_ipv4() => 32,
+            None => 128,
+        };
+        Self::new(addr, prefix)
+    }
+}
+
+impl fmt::Display for IpNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix)
+    }
+}
+
+impl Serialize for IpNetwork {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for IpNetwork {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// One allow list and one deny list
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AclRules {
+    /// When non-empty, only these networks are admitted
+    pub allow: Vec<IpNetwork>,
+    /// Networks that are always rejected
+    pub deny: Vec<IpNetwork>,
+}
+
+impl AclRules {
+    pub fn is_empty(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty()
+    }
+
+    pub fn permits(&self, ip: IpAddr) -> bool {
+        !self.deny.iter().any(|network| network.contains(ip))
+            && (self.allow.is_empty() || self.allow.iter().any(|network| network.contains(ip)))
+    }
+}
+
+/// Deployment-level network ACL settings
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetworkAclConfig {
+    pub allow: Vec<IpNetwork>,
+    pub deny: Vec<IpNetwork>,
+    /// Proxies whose `X-Forwarded-For` header is trusted
+    pub trusted_proxies: Vec<IpNetwork>,
+}
+
+impl NetworkAclConfig {
+    pub fn rules(&self) -> AclRules {
+        AclRules { allow: self.allow.clone(), deny: self.deny.clone() }
+    }
+}
+
+/// List that rejected a connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AclScope {
+    Global,
+    Tenant,
+}
+
+/// Result of checking a client address
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AclDecision {
+    Allow,
+    Deny(AclScope),
+}
+
+impl AclDecision {
+    pub fn is_allowed(&self) -> bool {
+        matches!(self, AclDecision::Allow)
+    }
+}
+
+/// Rejection counters
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AclMetrics {
+    pub rejected_global: u64,
+    pub rejected_tenant: u64,
+    /// Tenants with their own list
+    pub tenant_lists: usize,
+}
+
+/// Runtime network ACL shared by the HTTP API and the RPC server
+///
+/// All lists can be replaced while the server is running.
+#[derive(Debug, Default)]
+pub struct NetworkAcl {
+    global: RwLock<AclRules>,
+    tenants: RwLock<HashMap<String, TenantAcl>>,
+    trusted_proxies: RwLock<Vec<IpNetwork>>,
+    rejected_global: AtomicU64,
+    rejected_tenant: AtomicU64,
+}
+
+#[derive(Debug, Clone)]
+struct TenantAcl {
+    domain: Option<String>,
+    rules: AclRules,
+}
+
+impl NetworkAcl {
+    pub fn new(config: &NetworkAclConfig) -> Self {
+        Self {
+            global: RwLock::new(config.rules()),
+            trusted_proxies: RwLock::new(config.trusted_proxies.clone()),
+            ..Self::default()
+        }
+    }
+
+    /// Whether no list is configured, so every address is admitted
+    pub fn is_empty(&self) -> bool {
+        self.global.read().unwrap_or_else(|e| e.into_inner()).is_empty()
+            && self.tenants.read().unwrap_or_else(|e| e.into_inner()).is_empty()
+    }
+
+    pub fn global_rules(&self) -> AclRules {
+        self.global.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn set_global_rules(&self, rules: AclRules) {
+        *self.global.write().unwrap_or_else(|e| e.into_inner()) = rules;
+    }
+
+    pub fn trusted_proxies(&self) -> Vec<IpNetwork> {
+        self.trusted_proxies.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn set_trusted_proxies(&self, proxies: Vec<IpNetwork>) {
+        *self.trusted_proxies.write().unwrap_or_else(|e| e.into_inner()) = proxies;
+    }
+
+    pub fn tenant_rules(&self, tenant_id: &str) -> Option<AclRules> {
+        let tenants = self.tenants.read().unwrap_or_else(|e| e.into_inner());
+        tenants.get(tenant_id).map(|tenant| tenant.rules.clone())
+    }
+
+    /// Replace a tenant's list; `domain` lets requests to the tenant's host be checked
+    /// before authentication. Empty rules remove the tenant's list.
+    pub fn set_tenant_rules(&self, tenant_id: &str, domain: Option<&str>, rules: AclRules) {
+        let mut tenants = self.tenants.write().unwrap_or_else(|e| e.into_inner());
+        if rules.is_empty() {
+            tenants.remove(tenant_id);
+        } else {
+            let domain = domain.map(str::to_ascii_lowercase);
+            tenants.insert(tenant_id.to_string(), TenantAcl { domain, rules });
+        }
+    }
+
+    /// Tenant with a list whose domain is `host`
+    pub fn tenant_for_host(&self, host: &str) -> Option<String> {
+        let tenants = self.tenants.read().unwrap_or_else(|e| e.into_inner());
+        tenants
+            .iter()
+            .find(|(_, tenant)| tenant.domain.as_deref().is_some_and(|domain| domain.eq_ignore_ascii_case(host)))
+            .map(|(tenant_id, _)| tenant_id.clone())
+    }
+
+    /// Client address of a connection from `peer`
+    ///
+    /// `forwarded_for` is the `X-Forwarded-For` header value. It is ignored unless the
+    /// peer is a trusted proxy; otherwise the right-most address that is not a trusted
+    /// proxy is the client. Parsing stops at the first malformed entry.
+    pub fn client_ip(&self, peer: IpAddr, forwarded_for: Option<&str>) -> IpAddr {
+        let proxies = self.trusted_proxies.read().unwrap_or_else(|e| e.into_inner());
+        let trusted = |ip: IpAddr| proxies.iter().any(|network| network.contains(ip));
+        let Some(forwarded_for) = forwarded_for.filter(|_| trusted(peer)) else {
+            return peer;
+        };
+
+        let mut client = peer;
+        for hop in forwarded_for.rsplit(',') {
+            if !trusted(client) {
+                break;
+            }
+            match hop.trim().parse::<IpAddr>() {
+                Ok(ip) => client = ip,
+                Err(_) => break,
+            }
+        }
+        client
+    }
+
+    /// Check `ip` against the global list and, when the tenant is known, the tenant's
+    /// list; rejections are counted
+    pub fn check(&self, ip: IpAddr, tenant_id: Option<&str>) -> AclDecision {
+        if !self.global.read().unwrap_or_else(|e| e.into_inner()).permits(ip) {
+            self.rejected_global.fetch_add(1, Ordering::Relaxed);
+            return AclDecision::Deny(AclScope::Global);
+        }
+        if let Some(tenant_id) = tenant_id {
+            let tenants = self.tenants.read().unwrap_or_else(|e| e.into_inner());
+            if tenants.get(tenant_id).is_some_and(|tenant| !tenant.rules.permits(ip)) {
+                self.rejected_tenant.fetch_add(1, Ordering::Relaxed);
+                return AclDecision::Deny(AclScope::Tenant);
+            }
+        }
+        AclDecision::Allow
+    }
+
+    pub fn metrics(&self) -> AclMetrics {
+        AclMetrics {
+            rejected_global: self.rejected_global.load(Ordering::Relaxed),
+            rejected_tenant: self.rejected_tenant.load(Ordering::Relaxed),
+            tenant_lists: self.tenants.read().unwrap_or_else(|e| e.into_inner()).len(),
+        }
+    }
+}
@@ -334,4 +334,35 @@ pub struct PolicyResult {
     pub applied_policies: Vec<String>,
     pub actions: Vec<SecurityAction>,
     pub reason: Option<String>,
-} 
\ No newline at end of file
+} 
+/// Key fragments, compared case-insensitively, whose values are credentials
+pub const SENSITIVE_KEYS: &[&str] = &[
+    "password", "passwd", "secret", "token", "api_key", "apikey", "authorization", "credential", "private_key", "cookie",
+];
+
+/// Whether values under `key` are credentials that must not be stored or shown
+pub fn is_sensitive_key(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    SENSITIVE_KEYS.iter().any(|fragment| key.contains(fragment))
+}
+
+/// Copy of `value` with the values of sensitive keys replaced by `placeholder`, at any depth
+pub fn redact_sensitive(value: &serde_json::Value, placeholder: &str) -> serde_json::Value {
+    use serde_json::Value;
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| {
+                    let value = if is_sensitive_key(key) && !value.is_null() {
+                        Value::String(placeholder.to_string())
+                    } else {
+                        redact_sensitive(value, placeholder)
+                    };
+                    (key.clone(), value)
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(|item| redact_sensitive(item, placeholder)).collect()),
+        other => other.clone(),
+    }
+}
@@ -0,0 +1,89 @@
+//! Simulated faults for in-memory backends
+//!
+//! The HashMap-backed registry, result manager, monitoring and execution store
+//! used by unit tests share one [`SimulatedFaults`] handle. Unlike the seeded
+//! `FaultController` behind the `fault-injection` feature this one is always
+//! compiled and fully deterministic: a test names the operation to fail and
+//! how many times.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Deterministic failures and latency for in-memory components
+///
+/// Operations are named after the trait methods of the component, e.g.
+/// `store_result` or `get_tool`. Clones share state, so a test can keep a
+/// handle and change the behaviour while the component is in use.
+#[derive(Clone, Default)]
+pub struct SimulatedFaults {
+    state: Arc<Mutex<FaultState>>,
+}
+
+#[derive(Default)]
+struct FaultState {
+    latency: Duration,
+    fail_next: HashMap<String, u32>,
+    fail_always: HashSet<String>,
+    calls: HashMap<String, u64>,
+}
+
+impl SimulatedFaults {
+    /// Create a handle with no faults
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Delay every operation by `latency`
+    pub fn set_latency(&self, latency: Duration) {
+        self.state.lock().unwrap().latency = latency;
+    }
+
+    /// Fail the next `times` calls of `operation`
+    pub fn fail_next(&self, operation: &str, times: u32) {
+        *self.state.lock().unwrap().fail_next.entry(operation.to_string()).or_insert(0) += times;
+    }
+
+    /// Fail every call of `operation` until [`SimulatedFaults::clear`]
+    pub fn fail_always(&self, operation: &str) {
+        self.state.lock().unwrap().fail_always.insert(operation.to_string());
+    }
+
+    /// Remove all latency and failures
+    pub fn clear(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.latency = Duration::ZERO;
+        state.fail_next.clear();
+        state.fail_always.clear();
+    }
+
+    /// Number of times `operation` was called, including failed calls
+    pub fn calls(&self, operation: &str) -> u64 {
+        self.state.lock().unwrap().calls.get(operation).copied().unwrap_or(0)
+    }
+
+    /// Apply latency and return the injected error for `operation`, if any
+    pub async fn check(&self, operation: &str) -> Result<(), String> {
+        let (latency, fail) = {
+            let mut state = self.state.lock().unwrap();
+            *state.calls.entry(operation.to_string()).or_insert(0) += 1;
+            let mut fail = state.fail_always.contains(operation);
+            if let Some(remaining) = state.fail_next.get_mut(operation) {
+                if *remaining > 0 {
+                    *remaining -= 1;
+                    fail = true;
+                }
+            }
+            (state.latency, fail)
+        };
+
+        if !latency.is_zero() {
+            tokio::time::sleep(latency).await;
+        }
+        if fail {
+            Err(format!("Simulated failure in {}", operation))
+        } else {
+            Ok(())
+        }
+    }
+}
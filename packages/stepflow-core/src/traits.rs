@@ -103,7 +103,7 @@ pub trait ExecutionManager: Send + Sync {
 }
 
 /// Execution filter
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct ExecutionFilter {
     pub tool_id: Option<ToolId>,
     pub tenant_id: Option<TenantId>,
@@ -111,6 +111,8 @@ pub struct ExecutionFilter {
     pub status: Option<ExecutionStatus>,
     pub started_after: Option<chrono::DateTime<chrono::Utc>>,
     pub started_before: Option<chrono::DateTime<chrono::Utc>>,
+    /// Labels an execution must carry, all of them with the given values
+    pub labels: HashMap<String, String>,
 }
 
 /// Tenant manager trait
@@ -317,6 +319,17 @@ pub trait Database: Send + Sync {
     /// Execute query
     async fn execute(&self, query: &str, params: &[serde_json::Value]) -> Result<QueryResult, crate::StepflowError>;
 
+    /// Execute query with a consistency hint for reads; databases without read
+    /// replicas ignore the hint
+    async fn execute_with(
+        &self,
+        query: &str,
+        params: &[serde_json::Value],
+        _consistency: ReadConsistency,
+    ) -> Result<QueryResult, crate::StepflowError> {
+        self.execute(query, params).await
+    }
+
     /// Migrate database
     async fn migrate(&self, migrations: &[Migration]) -> Result<(), crate::StepflowError>;
 
@@ -324,6 +337,19 @@ pub trait Database: Send + Sync {
     async fn get_stats(&self) -> Result<DatabaseStats, crate::StepflowError>;
 }
 
+/// Where a read may be served from when the database has read replicas
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadConsistency {
+    /// Always read from the primary
+    Primary,
+    /// Any replica whose replication lag is within its configured bound
+    Eventual,
+    /// A replica that has applied every write made so far, otherwise the primary
+    #[default]
+    ReadYourWrites,
+}
+
 /// Query result
 #[derive(Debug, Clone)]
 pub struct QueryResult {
@@ -338,6 +364,8 @@ pub struct Migration {
     pub version: u32,
     pub name: String,
     pub sql: String,
+    /// SQL that reverts `sql`; `None` for irreversible migrations
+    pub down_sql: Option<String>,
 }
 
 /// Database statistics
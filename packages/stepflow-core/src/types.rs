@@ -4,8 +4,40 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use chrono::{DateTime, Utc};
 
+/// Longest identifier accepted by [`ToolId::parse`] and [`ExecutionId::parse`]
+pub const MAX_ID_LEN: usize = 256;
+
+/// Characters allowed after the first one of an identifier
+const ID_SEPARATORS: &[char] = &['-', '_', '.', ':', '@', '/', '+'];
+
+/// Check the identifier format shared by tools and executions
+///
+/// An identifier is 1 to [`MAX_ID_LEN`] ASCII characters: it starts with a letter or
+/// digit, followed by letters, digits and `- _ . : @ / +`. UUIDs, slugs such as
+/// `python-calculator` and resource names such as `tool:ns:name@1.0.0` all qualify.
+fn validate_id(kind: &str, value: &str) -> Result<(), crate::ValidationError> {
+    let invalid = |reason: &str| Err(crate::ValidationError::InvalidFormat(format!("{} {:?}: {}", kind, value, reason)));
+    if value.is_empty() {
+        return invalid("must not be empty");
+    }
+    if value.len() > MAX_ID_LEN {
+        return invalid(&format!("longer than {} characters", MAX_ID_LEN));
+    }
+    if !value.starts_with(|c: char| c.is_ascii_alphanumeric()) {
+        return invalid("must start with a letter or digit");
+    }
+    if let Some(c) = value.chars().find(|c| !c.is_ascii_alphanumeric() && !ID_SEPARATORS.contains(c)) {
+        return invalid(&format!("contains {:?}", c));
+    }
+    Ok(())
+}
+
 /// Unique identifier for a tool
+///
+/// Deserializing and parsing validate the format; [`ToolId::from_string`] does not
+/// and is meant for values that were validated before they were stored.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
 pub struct ToolId(String);
 
 impl ToolId {
@@ -14,7 +46,14 @@ impl ToolId {
         Self(uuid::Uuid::new_v4().to_string())
     }
 
-    /// Create from string
+    /// Parse and validate a tool ID
+    pub fn parse(s: impl Into<String>) -> Result<Self, crate::ValidationError> {
+        let s = s.into();
+        validate_id("tool ID", &s)?;
+        Ok(Self(s))
+    }
+
+    /// Create from a trusted string without validation
     pub fn from_string(s: String) -> Self {
         Self(s)
     }
@@ -23,6 +62,11 @@ impl ToolId {
     pub fn as_str(&self) -> &str {
         &self.0
     }
+
+    /// Consume into the inner string
+    pub fn into_string(self) -> String {
+        self.0
+    }
 }
 
 impl std::fmt::Display for ToolId {
@@ -31,9 +75,25 @@ impl std::fmt::Display for ToolId {
     }
 }
 
-impl From<String> for ToolId {
-    fn from(s: String) -> Self {
-        Self(s)
+impl std::str::FromStr for ToolId {
+    type Err = crate::ValidationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl TryFrom<String> for ToolId {
+    type Error = crate::ValidationError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Self::parse(s)
+    }
+}
+
+impl From<ToolId> for String {
+    fn from(id: ToolId) -> Self {
+        id.0
     }
 }
 
@@ -131,8 +191,78 @@ impl std::fmt::Display for ToolStatus {
     }
 }
 
+/// Deployment environment a tool, execution or worker is labeled with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EnvironmentLabel {
+    Dev,
+    Staging,
+    Prod,
+}
+
+impl EnvironmentLabel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EnvironmentLabel::Dev => "dev",
+            EnvironmentLabel::Staging => "staging",
+            EnvironmentLabel::Prod => "prod",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "dev" => Some(EnvironmentLabel::Dev),
+            "staging" => Some(EnvironmentLabel::Staging),
+            "prod" => Some(EnvironmentLabel::Prod),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for EnvironmentLabel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Worker capabilities a tool needs to run
+///
+/// Capabilities are opaque strings such as `python3.11`, `gpu`, `docker` or
+/// `region=eu`, matched exactly against what workers advertise.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolRequirements {
+    /// Capabilities a worker must have
+    pub capabilities: Vec<String>,
+    /// Capabilities a worker should have; workers with more of them are preferred
+    pub preferred: Vec<String>,
+}
+
+impl ToolRequirements {
+    pub fn is_empty(&self) -> bool {
+        self.capabilities.is_empty() && self.preferred.is_empty()
+    }
+
+    /// Add the capabilities of `other` that are not listed yet
+    pub fn merge(&mut self, other: &ToolRequirements) {
+        for capability in &other.capabilities {
+            if !self.capabilities.contains(capability) {
+                self.capabilities.push(capability.clone());
+            }
+        }
+        for capability in &other.preferred {
+            if !self.preferred.contains(capability) {
+                self.preferred.push(capability.clone());
+            }
+        }
+    }
+}
+
 /// Tool information
+///
+/// Built with [`ToolInfo::builder`] outside this crate.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct ToolInfo {
     pub id: ToolId,
     pub name: String,
@@ -149,6 +279,111 @@ pub struct ToolInfo {
     pub examples: Vec<ToolExample>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Environment the tool is deployed to; unlabeled tools can run in any environment
+    #[serde(default)]
+    pub environment_label: Option<EnvironmentLabel>,
+    /// Worker capabilities executions of the tool need
+    #[serde(default)]
+    pub requirements: ToolRequirements,
+    /// JSON schema of the tool's input parameters
+    #[serde(default)]
+    pub input_schema: Option<serde_json::Value>,
+    /// Translated names and descriptions keyed by language tag, e.g. `zh` or `zh-TW`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub localizations: HashMap<String, ToolLocalization>,
+    /// Default environment variables of executions; tenant tool configuration
+    /// and the execution request override them
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub environment: HashMap<String, String>,
+    /// How the tool runs as an interactive session; tools without one can't be opened as sessions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<ToolSessionCommand>,
+    /// Container image of this tool version, pinned to a digest; executions
+    /// are rejected when the local image doesn't match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<ToolImage>,
+}
+
+impl ToolInfo {
+    /// Name and description for the first of `languages` the tool is translated into
+    ///
+    /// Each language tag is tried as given and then without its region (`zh-CN` falls
+    /// back to `zh`); untranslated fields keep the default name and description.
+    pub fn localized(&self, languages: &[String]) -> (&str, &str) {
+        let localization = languages.iter().find_map(|tag| {
+            let tag = tag.to_ascii_lowercase();
+            let primary = tag.split('-').next().unwrap_or_default();
+            self.localizations.iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(&tag))
+                .or_else(|| self.localizations.iter().find(|(key, _)| key.eq_ignore_ascii_case(primary)))
+                .map(|(_, localization)| localization)
+        });
+        let name = localization.and_then(|l| l.name.as_deref()).unwrap_or(&self.name);
+        let description = localization.and_then(|l| l.description.as_deref()).unwrap_or(&self.description);
+        (name, description)
+    }
+}
+
+/// Container image a tool version runs in
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolImage {
+    /// Image reference, e.g. `tools/python:3.11`
+    pub image: String,
+    /// Content digest of the image, `sha256:<hex>`
+    pub digest: String,
+}
+
+impl ToolImage {
+    pub fn new(image: impl Into<String>, digest: impl Into<String>) -> Self {
+        Self { image: image.into(), digest: digest.into() }
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), crate::ValidationError> {
+        let hex = self.digest.strip_prefix("sha256:").unwrap_or_default();
+        if self.image.trim().is_empty() {
+            return Err(crate::ValidationError::RequiredFieldMissing("image".to_string()));
+        }
+        if hex.len() != 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(crate::ValidationError::InvalidFormat(format!(
+                "image digest must be sha256:<64 hex digits>, got {:?}", self.digest
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Command a tool runs in an interactive session
+///
+/// Each session input is appended as the last argument. Clients may add only
+/// the listed extra arguments, which are inserted right after the program.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolSessionCommand {
+    /// Program and leading arguments, e.g. `["python3", "-c"]`
+    pub command: Vec<String>,
+    /// Extra arguments clients may add, matched exactly
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_args: Vec<String>,
+}
+
+impl ToolSessionCommand {
+    /// The session command with the client's `args`, rejecting arguments the tool doesn't allow
+    pub fn with_args(&self, args: &[String]) -> Result<Vec<String>, crate::ValidationError> {
+        if let Some(arg) = args.iter().find(|arg| !self.allowed_args.contains(arg)) {
+            return Err(crate::ValidationError::InvalidFormat(format!("session argument {:?} is not allowed by the tool", arg)));
+        }
+        let (program, leading) = self.command.split_first()
+            .ok_or_else(|| crate::ValidationError::RequiredFieldMissing("session command".to_string()))?;
+        Ok(std::iter::once(program).chain(args).chain(leading).cloned().collect())
+    }
+}
+
+/// Name and description of a tool in one language
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolLocalization {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
 }
 
 /// Tool example
@@ -158,6 +393,22 @@ pub struct ToolExample {
     pub description: String,
     pub input: serde_json::Value,
     pub output: serde_json::Value,
+    /// How `output` is compared with the actual output when the example is run as a test
+    #[serde(default)]
+    pub match_mode: ExampleMatch,
+}
+
+/// How an example's expected output is compared with the actual output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExampleMatch {
+    /// The outputs must be equal
+    #[default]
+    Exact,
+    /// Every field of the expected output must be present with the same value
+    Subset,
+    /// The expected output is a JSON schema the actual output must satisfy
+    Schema,
 }
 
 /// Tool configuration
@@ -173,7 +424,10 @@ pub struct ToolConfig {
 }
 
 /// Tool execution request
+///
+/// Built with [`ToolRequest::new`] outside this crate.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct ToolRequest {
     pub tool_id: ToolId,
     pub input: serde_json::Value,
@@ -211,7 +465,10 @@ pub trait Tool: Send + Sync {
 }
 
 /// Unique identifier for an execution
+///
+/// Validated like [`ToolId`].
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
 pub struct ExecutionId(String);
 
 impl ExecutionId {
@@ -220,7 +477,14 @@ impl ExecutionId {
         Self(uuid::Uuid::new_v4().to_string())
     }
 
-    /// Create from string
+    /// Parse and validate an execution ID
+    pub fn parse(s: impl Into<String>) -> Result<Self, crate::ValidationError> {
+        let s = s.into();
+        validate_id("execution ID", &s)?;
+        Ok(Self(s))
+    }
+
+    /// Create from a trusted string without validation
     pub fn from_string(s: String) -> Self {
         Self(s)
     }
@@ -229,6 +493,11 @@ impl ExecutionId {
     pub fn as_str(&self) -> &str {
         &self.0
     }
+
+    /// Consume into the inner string
+    pub fn into_string(self) -> String {
+        self.0
+    }
 }
 
 impl std::fmt::Display for ExecutionId {
@@ -237,6 +506,28 @@ impl std::fmt::Display for ExecutionId {
     }
 }
 
+impl std::str::FromStr for ExecutionId {
+    type Err = crate::ValidationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl TryFrom<String> for ExecutionId {
+    type Error = crate::ValidationError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Self::parse(s)
+    }
+}
+
+impl From<ExecutionId> for String {
+    fn from(id: ExecutionId) -> Self {
+        id.0
+    }
+}
+
 /// Execution status enumeration
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ExecutionStatus {
@@ -417,6 +708,9 @@ pub struct UserInfo {
     pub role: UserRole,
     pub tenant_id: TenantId,
     pub settings: HashMap<String, serde_json::Value>,
+    /// Whether the user has confirmed ownership of `email`
+    #[serde(default)]
+    pub email_verified: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
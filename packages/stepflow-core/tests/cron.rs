@@ -0,0 +1,83 @@
+use chrono::{DateTime, Utc};
+use chrono_tz::{America, Asia};
+use stepflow_core::{BlackoutWindow, CalendarSchedule, CronSchedule};
+
+fn at(s: &str) -> DateTime<Utc> {
+    s.parse().unwrap()
+}
+
+fn next(expression: &str, after: &str) -> Option<DateTime<Utc>> {
+    expression.parse::<CronSchedule>().unwrap().next_after(at(after))
+}
+
+#[test]
+fn test_cron_next_after() {
+    assert_eq!(next("* * * * *", "2026-03-01T10:15:30Z"), Some(at("2026-03-01T10:16:00Z")));
+    assert_eq!(next("*/15 * * * *", "2026-03-01T10:15:00Z"), Some(at("2026-03-01T10:30:00Z")));
+    assert_eq!(next("0 * * * *", "2026-03-01T23:59:00Z"), Some(at("2026-03-02T00:00:00Z")));
+    assert_eq!(next("30 2 * * *", "2026-03-01T03:00:00Z"), Some(at("2026-03-02T02:30:00Z")));
+    // Month and year rollover
+    assert_eq!(next("0 0 1 1 *", "2026-03-01T00:00:00Z"), Some(at("2027-01-01T00:00:00Z")));
+    assert_eq!(next("0 0 29 2 *", "2026-03-01T00:00:00Z"), Some(at("2028-02-29T00:00:00Z")));
+    // Weekdays: 2026-03-01 is a Sunday
+    assert_eq!(next("0 9 * * 1-5", "2026-03-01T00:00:00Z"), Some(at("2026-03-02T09:00:00Z")));
+    assert_eq!(next("0 9 * * 7", "2026-03-02T00:00:00Z"), Some(at("2026-03-08T09:00:00Z")));
+    // Both day fields restricted: either matches
+    assert_eq!(next("0 0 15 * 1", "2026-03-01T00:00:00Z"), Some(at("2026-03-02T00:00:00Z")));
+    // Never matches
+    assert_eq!(next("0 0 31 2 *", "2026-03-01T00:00:00Z"), None);
+}
+
+#[test]
+fn test_cron_parse_errors() {
+    for expression in ["* * * *", "60 * * * *", "* 24 * * *", "* * 0 * *", "*/0 * * * *", "5-1 * * * *", "a * * * *"] {
+        assert!(expression.parse::<CronSchedule>().is_err(), "{} should be rejected", expression);
+    }
+    let schedule: CronSchedule = " 0   */6 * * * ".parse().unwrap();
+    assert_eq!(schedule.to_string(), "0 */6 * * *");
+    let json = serde_json::to_string(&schedule).unwrap();
+    assert_eq!(json, "\"0 */6 * * *\"");
+    assert_eq!(serde_json::from_str::<CronSchedule>(&json).unwrap(), schedule);
+}
+
+#[test]
+fn test_cron_in_timezone() {
+    let schedule: CronSchedule = "0 9 * * *".parse().unwrap();
+    // 09:00 in Shanghai (UTC+8) is 01:00 UTC
+    assert_eq!(schedule.next_after_in(at("2026-03-01T02:00:00Z"), &Asia::Shanghai), Some(at("2026-03-02T01:00:00Z")));
+    assert_eq!(schedule.next_after_in(at("2026-03-01T00:00:00Z"), &Asia::Shanghai), Some(at("2026-03-01T01:00:00Z")));
+
+    // New York springs forward on 2026-03-08: 02:30 does not exist that day
+    let schedule: CronSchedule = "30 2 * * *".parse().unwrap();
+    assert_eq!(schedule.next_after_in(at("2026-03-08T00:00:00Z"), &America::New_York), Some(at("2026-03-09T06:30:00Z")));
+    // and falls back on 2026-11-01: 01:30 happens twice and fires once, at the first
+    let schedule: CronSchedule = "30 1 * * *".parse().unwrap();
+    assert_eq!(schedule.next_after_in(at("2026-11-01T04:00:00Z"), &America::New_York), Some(at("2026-11-01T05:30:00Z")));
+    assert_eq!(schedule.next_after_in(at("2026-11-01T05:30:00Z"), &America::New_York), Some(at("2026-11-02T06:30:00Z")));
+}
+
+#[test]
+fn test_calendar_schedule() {
+    // 2026-03-06 is a Friday
+    let daily = CalendarSchedule::new("0 9 * * *".parse().unwrap(), Asia::Shanghai);
+    let business = daily.clone().business_days_only(vec!["2026-03-09".parse().unwrap()]);
+    assert_eq!(
+        business.preview(at("2026-03-05T12:00:00Z"), 3),
+        vec![at("2026-03-06T01:00:00Z"), at("2026-03-10T01:00:00Z"), at("2026-03-11T01:00:00Z")],
+    );
+
+    let with_blackout = daily.with_blackout(BlackoutWindow {
+        start: at("2026-03-06T00:00:00Z"),
+        end: at("2026-03-08T00:00:00Z"),
+        reason: Some("datacenter move".to_string()),
+    });
+    assert!(with_blackout.validate().is_ok());
+    assert_eq!(
+        with_blackout.preview(at("2026-03-05T12:00:00Z"), 2),
+        vec![at("2026-03-08T01:00:00Z"), at("2026-03-09T01:00:00Z")],
+    );
+
+    let never: CalendarSchedule = serde_json::from_value(serde_json::json!({"cron": "0 0 31 2 *"})).unwrap();
+    assert_eq!(never.timezone, chrono_tz::UTC);
+    assert!(never.preview(at("2026-03-01T00:00:00Z"), 5).is_empty());
+}
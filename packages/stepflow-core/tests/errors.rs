@@ -117,7 +117,8 @@ fn test_error_conversions() {
 fn test_serde_json_error_conversion() {
     let json_error = serde_json::from_str::<serde_json::Value>("invalid json").unwrap_err();
     let stepflow_error: StepflowError = json_error.into();
-    assert!(matches!(stepflow_error, StepflowError::SerializationError(_)));
+    assert!(matches!(stepflow_error, StepflowError::Json(_)));
+    assert!(std::error::Error::source(&stepflow_error).is_some());
 }
 
 #[test]
@@ -125,7 +126,8 @@ fn test_io_error_conversion() {
     use std::io;
     let io_error = io::Error::new(io::ErrorKind::NotFound, "file not found");
     let stepflow_error: StepflowError = io_error.into();
-    assert!(matches!(stepflow_error, StepflowError::InternalError(_)));
+    assert!(matches!(stepflow_error, StepflowError::Io(_)));
+    assert_eq!(std::error::Error::source(&stepflow_error).unwrap().to_string(), "file not found");
 }
 
 #[test]
@@ -140,4 +142,29 @@ fn test_chrono_parse_error_conversion() {
     let chrono_error = chrono::DateTime::parse_from_rfc3339("invalid-date").unwrap_err();
     let stepflow_error: StepflowError = chrono_error.into();
     assert!(matches!(stepflow_error, StepflowError::ValidationError(_)));
-} 
\ No newline at end of file
+}
+
+#[test]
+fn test_error_code_catalog() {
+    let codes: std::collections::HashSet<_> = ErrorCode::ALL.iter().map(|code| code.as_str()).collect();
+    assert_eq!(codes.len(), ErrorCode::ALL.len());
+    for code in ErrorCode::ALL {
+        assert_eq!(serde_json::to_value(code).unwrap(), code.as_str());
+        assert!((400..600).contains(&code.http_status()));
+        let rpc = code.json_rpc_code();
+        assert!((-32099..=-32000).contains(&rpc) || (-32603..=-32600).contains(&rpc));
+    }
+
+    let error = StepflowError::ToolNotFound("echo".to_string());
+    assert_eq!(error.code(), ErrorCode::ToolNotFound);
+    assert_eq!(error.code().http_status(), 404);
+    assert!(!error.retryable());
+    assert!(error.user_facing());
+
+    let error = StepflowError::from(DatabaseError::Deadlock);
+    assert_eq!(error.code(), ErrorCode::DatabaseError);
+    assert!(error.retryable());
+    assert!(!error.user_facing());
+    assert!(StepflowError::ServiceUnavailable("draining".to_string()).retryable());
+    assert!(!StepflowError::from(DatabaseError::QueryFailed("syntax".to_string())).retryable());
+}
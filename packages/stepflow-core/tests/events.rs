@@ -0,0 +1,70 @@
+use serde_json::json;
+use stepflow_core::{CloudEvent, ExecutionStateChanged, ToolChanged};
+
+fn execution_event() -> ExecutionStateChanged {
+    ExecutionStateChanged {
+        sequence: 42,
+        execution_id: "exec-1".to_string(),
+        tool_id: Some("tool-1".to_string()),
+        tenant_id: Some("acme".to_string()),
+        state: "completed".to_string(),
+        worker_id: None,
+        detail: None,
+    }
+}
+
+#[test]
+fn test_cloud_event_envelope() {
+    let time = "2026-03-01T10:15:30Z".parse().unwrap();
+    let event = CloudEvent::new(execution_event()).with_time(time);
+    let value = serde_json::to_value(&event).unwrap();
+
+    assert_eq!(value["specversion"], "1.0");
+    assert_eq!(value["id"], "execution-42");
+    assert_eq!(value["source"], "/stepflow/executions");
+    assert_eq!(value["type"], "io.stepflow.execution.completed");
+    assert_eq!(value["subject"], "exec-1");
+    assert_eq!(value["time"], "2026-03-01T10:15:30Z");
+    assert_eq!(value["datacontenttype"], "application/json");
+    assert_eq!(value["dataschema"], "urn:stepflow:event:execution.state_changed:v1");
+    assert_eq!(value["tenantid"], "acme");
+    assert_eq!(value["data"]["state"], "completed");
+    assert_eq!(event.schema(), Some(("execution.state_changed", 1)));
+
+    // Round-trips typed and untyped
+    let typed: CloudEvent<ExecutionStateChanged> = serde_json::from_value(value.clone()).unwrap();
+    assert_eq!(typed, event);
+    let untyped: CloudEvent = serde_json::from_value(value).unwrap();
+    assert_eq!(untyped, event.into_json());
+}
+
+#[test]
+fn test_cloud_event_optional_attributes() {
+    let event = CloudEvent::new(ToolChanged {
+        sequence: 7,
+        change_type: "deleted".to_string(),
+        tool_id: "tool-1".to_string(),
+        version: "1.0.0".to_string(),
+        tool: None,
+        changed_at: chrono::Utc::now(),
+    });
+    assert_eq!(event.id, "registry-7");
+    assert_eq!(event.event_type, "io.stepflow.registry.tool.deleted");
+
+    // Attributes without a value are left out rather than sent as null
+    let value = serde_json::to_value(&event).unwrap();
+    assert!(value.get("tenantid").is_none());
+
+    // Events from other producers parse without the optional attributes
+    let foreign: CloudEvent = serde_json::from_value(json!({
+        "specversion": "1.0",
+        "id": "1",
+        "source": "/elsewhere",
+        "type": "com.example.ping",
+        "time": "2026-03-01T10:15:30Z",
+        "datacontenttype": "application/json",
+        "data": {}
+    })).unwrap();
+    assert_eq!(foreign.schema(), None);
+    assert_eq!(foreign.subject, None);
+}
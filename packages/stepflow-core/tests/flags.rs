@@ -0,0 +1,88 @@
+use stepflow_core::{Flag, FlagContext, FlagDefinition, Flags};
+
+fn context(tenant: &str, user: &str) -> FlagContext {
+    FlagContext::new(Some(tenant.to_string()), Some(user.to_string()))
+}
+
+#[test]
+fn test_flag_overrides() {
+    let flags = Flags::new([FlagDefinition::new("new_scheduler")
+        .with_tenant("acme", true)
+        .with_user("mallory", false)]);
+
+    assert!(flags.is_enabled(Flag::NEW_SCHEDULER, &context("acme", "alice")));
+    assert!(!flags.is_enabled(Flag::NEW_SCHEDULER, &context("acme", "mallory")));
+    assert!(!flags.is_enabled(Flag::NEW_SCHEDULER, &context("globex", "alice")));
+    assert!(!flags.is_enabled(Flag::NEW_SANDBOX, &context("acme", "alice")));
+
+    // The kill switch beats every override
+    let mut definition = flags.get("new_scheduler").unwrap();
+    definition.enabled = false;
+    flags.clone().set(definition);
+    assert!(!flags.is_enabled(Flag::NEW_SCHEDULER, &context("acme", "alice")));
+}
+
+#[test]
+fn test_flag_percentage_rollout() {
+    let tenants: Vec<String> = (0..1000).map(|i| format!("tenant-{}", i)).collect();
+    let enabled = |flags: &Flags| {
+        tenants.iter()
+            .filter(|tenant| flags.is_enabled_by_name("new_sandbox", &FlagContext::tenant(tenant.as_str())))
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+
+    let flags = Flags::new([FlagDefinition::new("new_sandbox").with_rollout(20)]);
+    let at_20 = enabled(&flags);
+    assert!((150..250).contains(&at_20.len()), "{} tenants enabled", at_20.len());
+
+    // Raising the percentage only adds tenants, and all users of a tenant agree
+    flags.set(FlagDefinition::new("new_sandbox").with_rollout(50));
+    let at_50 = enabled(&flags);
+    assert!(at_20.iter().all(|tenant| at_50.contains(tenant)));
+    let tenant = &at_20[0];
+    assert!(flags.is_enabled_by_name("new_sandbox", &context(tenant, "someone-else")));
+
+    flags.set(FlagDefinition::new("new_sandbox").with_rollout(100));
+    assert_eq!(enabled(&flags).len(), tenants.len());
+    assert!(flags.is_enabled_by_name("new_sandbox", &FlagContext::default()));
+}
+
+#[test]
+fn test_stored_flags_override_defaults() {
+    let flags = Flags::with_defaults(
+        [FlagDefinition::new("new_scheduler").with_rollout(100), FlagDefinition::new("new_sandbox")],
+        [FlagDefinition::new("new_scheduler").with_tenant("acme", false)],
+    );
+    assert!(!flags.is_enabled(Flag::NEW_SCHEDULER, &context("acme", "alice")));
+    assert_eq!(flags.definitions().len(), 2);
+
+    // Resetting falls back to the config default, or drops flags without one
+    assert!(flags.reset("new_scheduler").is_some());
+    assert!(flags.is_enabled(Flag::NEW_SCHEDULER, &context("acme", "alice")));
+    flags.set(FlagDefinition::new("runtime_only"));
+    assert!(flags.reset("runtime_only").is_none());
+    assert!(flags.get("runtime_only").is_none());
+
+    // Reloading keeps the defaults underneath
+    flags.replace([FlagDefinition::new("new_sandbox").with_rollout(100)]);
+    assert!(flags.is_enabled(Flag::NEW_SANDBOX, &context("acme", "alice")));
+    assert!(flags.is_enabled(Flag::NEW_SCHEDULER, &context("acme", "alice")));
+}
+
+#[test]
+fn test_flag_validation() {
+    assert!(FlagDefinition::new("new_scheduler").validate().is_ok());
+    assert!(FlagDefinition::new("New Scheduler").validate().is_err());
+    assert!(FlagDefinition::new("").validate().is_err());
+    assert!(FlagDefinition::new("x").with_rollout(101).validate().is_err());
+}
+
+#[test]
+fn test_flag_tenant_scope() {
+    assert!(FlagDefinition::new("new_scheduler").is_scoped_to("acme"));
+    assert!(FlagDefinition::new("new_scheduler").with_tenant("acme", true).is_scoped_to("acme"));
+    assert!(!FlagDefinition::new("new_scheduler").with_tenant("globex", true).is_scoped_to("acme"));
+    assert!(!FlagDefinition::new("new_scheduler").with_rollout(10).is_scoped_to("acme"));
+    assert!(!FlagDefinition::new("new_scheduler").with_user("alice", true).is_scoped_to("acme"));
+}
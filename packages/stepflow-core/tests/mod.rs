@@ -5,5 +5,10 @@ mod models;
 mod errors;
 mod config;
 mod traits;
+mod network_acl;
+mod cron;
+mod events;
 // mod security;
-// mod monitoring; 
\ No newline at end of file
+// mod monitoring;
+mod flags;
+
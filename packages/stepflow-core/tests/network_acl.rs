@@ -0,0 +1,88 @@
+use stepflow_core::{AclDecision, AclRules, AclScope, IpNetwork, NetworkAcl, NetworkAclConfig};
+use std::net::IpAddr;
+
+fn ip(s: &str) -> IpAddr {
+    s.parse().unwrap()
+}
+
+fn networks(list: &[&str]) -> Vec<IpNetwork> {
+    list.iter().map(|s| s.parse().unwrap()).collect()
+}
+
+#[test]
+fn test_ip_network_parse_and_contains() {
+    let network: IpNetwork = "10.1.2.3/8".parse().unwrap();
+    assert_eq!(network.to_string(), "10.0.0.0/8");
+    assert!(network.contains(ip("10.255.0.1")));
+    assert!(!network.contains(ip("11.0.0.1")));
+    assert!(network.contains(ip("::ffff:10.0.0.1")));
+
+    let host: IpNetwork = "192.168.1.10".parse().unwrap();
+    assert_eq!(host.prefix(), 32);
+    assert!(host.contains(ip("192.168.1.10")));
+    assert!(!host.contains(ip("192.168.1.11")));
+
+    let v6: IpNetwork = "2001:db8::/32".parse().unwrap();
+    assert!(v6.contains(ip("2001:db8:1::1")));
+    assert!(!v6.contains(ip("10.0.0.1")));
+
+    let any: IpNetwork = "0.0.0.0/0".parse().unwrap();
+    assert!(any.contains(ip("203.0.113.9")));
+
+    assert!("10.0.0.0/33".parse::<IpNetwork>().is_err());
+    assert!("10.0.0/8".parse::<IpNetwork>().is_err());
+    assert!("10.0.0.0/x".parse::<IpNetwork>().is_err());
+}
+
+#[test]
+fn test_acl_rules_deny_wins() {
+    let rules = AclRules { allow: networks(&["10.0.0.0/8"]), deny: networks(&["10.0.0.66"]) };
+    assert!(rules.permits(ip("10.1.1.1")));
+    assert!(!rules.permits(ip("10.0.0.66")));
+    assert!(!rules.permits(ip("192.168.0.1")));
+    assert!(AclRules::default().permits(ip("192.168.0.1")));
+
+    let config: NetworkAclConfig = serde_json::from_str(r#"{"deny": ["198.51.100.0/24"], "trusted_proxies": ["127.0.0.1"]}"#).unwrap();
+    assert!(!config.rules().permits(ip("198.51.100.7")));
+    assert!(serde_json::from_str::<NetworkAclConfig>(r#"{"deny": ["not-an-ip"]}"#).is_err());
+}
+
+#[test]
+fn test_global_and_tenant_lists() {
+    let acl = NetworkAcl::new(&NetworkAclConfig { deny: networks(&["203.0.113.0/24"]), ..Default::default() });
+    acl.set_tenant_rules("acme", Some("Acme.Example.com"), AclRules { allow: networks(&["10.0.0.0/8"]), deny: vec![] });
+
+    assert_eq!(acl.check(ip("203.0.113.5"), None), AclDecision::Deny(AclScope::Global));
+    assert_eq!(acl.check(ip("192.168.0.1"), None), AclDecision::Allow);
+    assert_eq!(acl.check(ip("192.168.0.1"), Some("acme")), AclDecision::Deny(AclScope::Tenant));
+    assert_eq!(acl.check(ip("10.0.0.1"), Some("acme")), AclDecision::Allow);
+    assert_eq!(acl.check(ip("192.168.0.1"), Some("other")), AclDecision::Allow);
+    assert_eq!(acl.tenant_for_host("acme.example.com").as_deref(), Some("acme"));
+
+    let metrics = acl.metrics();
+    assert_eq!((metrics.rejected_global, metrics.rejected_tenant, metrics.tenant_lists), (1, 1, 1));
+
+    // Lists can be replaced at runtime
+    acl.set_global_rules(AclRules::default());
+    assert!(acl.check(ip("203.0.113.5"), None).is_allowed());
+    acl.set_tenant_rules("acme", None, AclRules::default());
+    assert!(acl.tenant_rules("acme").is_none());
+    assert!(acl.check(ip("192.168.0.1"), Some("acme")).is_allowed());
+}
+
+#[test]
+fn test_client_ip_only_trusts_configured_proxies() {
+    let acl = NetworkAcl::new(&NetworkAclConfig {
+        trusted_proxies: networks(&["10.0.0.0/24"]),
+        ..Default::default()
+    });
+
+    // Untrusted peers cannot set their address through the header
+    assert_eq!(acl.client_ip(ip("198.51.100.1"), Some("1.2.3.4")), ip("198.51.100.1"));
+    // The right-most untrusted hop is the client, spoofed entries to its left are ignored
+    assert_eq!(acl.client_ip(ip("10.0.0.1"), Some("1.2.3.4, 203.0.113.7, 10.0.0.2")), ip("203.0.113.7"));
+    assert_eq!(acl.client_ip(ip("10.0.0.1"), Some("203.0.113.7")), ip("203.0.113.7"));
+    // Malformed entries stop the walk at the last trusted hop
+    assert_eq!(acl.client_ip(ip("10.0.0.1"), Some("203.0.113.7, garbage")), ip("10.0.0.1"));
+    assert_eq!(acl.client_ip(ip("10.0.0.1"), None), ip("10.0.0.1"));
+}
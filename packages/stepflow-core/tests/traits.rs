@@ -18,23 +18,15 @@ pub struct MockTool {
 #[async_trait]
 impl Tool for MockTool {
     async fn get_info(&self) -> Result<ToolInfo, StepflowError> {
-        Ok(ToolInfo {
-            id: self.id.clone(),
-            name: self.name.clone(),
-            description: self.description.clone(),
-            version: ToolVersion::new(1, 0, 0),
-            tool_type: ToolType::Custom("mock".to_string()),
-            status: ToolStatus::Active,
-            author: "test".to_string(),
-            repository: None,
-            documentation: None,
-            tags: vec!["test".to_string()],
-            capabilities: vec!["mock".to_string()],
-            configuration_schema: None,
-            examples: vec![],
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-        })
+        Ok(ToolInfo::builder()
+            .id(self.id.clone())
+            .name(self.name.clone())
+            .description(self.description.clone())
+            .tool_type(ToolType::Custom("mock".to_string()))
+            .author("test")
+            .tags(["test"])
+            .capabilities(["mock"])
+            .build()?)
     }
 
     async fn execute(&self, _request: ToolRequest) -> Result<ToolResponse, StepflowError> {
@@ -125,12 +117,7 @@ async fn test_mock_tool() {
     assert_eq!(info.name, "Mock Tool");
     assert_eq!(info.tool_type, ToolType::Custom("mock".to_string()));
 
-    let request = ToolRequest {
-        tool_id: tool.id.clone(),
-        input: serde_json::json!({}),
-        configuration: None,
-        metadata: HashMap::new(),
-    };
+    let request = ToolRequest::new(tool.id.clone(), serde_json::json!({}));
     let response = tool.execute(request).await.unwrap();
     assert!(response.success);
     assert!(response.output.is_some());
@@ -140,12 +127,7 @@ async fn test_mock_tool() {
 async fn test_mock_tool_executor() {
     let executor = MockToolExecutor;
     
-    let request = ToolRequest {
-        tool_id: ToolId::from_string("test-tool".to_string()),
-        input: serde_json::json!({"test": "data"}),
-        configuration: None,
-        metadata: HashMap::new(),
-    };
+    let request = ToolRequest::new(ToolId::from_string("test-tool".to_string()), serde_json::json!({"test": "data"}));
 
     let response = executor.execute_tool(request).await.unwrap();
     assert!(response.success);
@@ -178,6 +160,7 @@ async fn test_execution_filter() {
         status: Some(ExecutionStatus::Pending),
         started_after: Some(Utc::now()),
         started_before: Some(Utc::now()),
+        labels: HashMap::new(),
     };
 
     assert!(filter.tool_id.is_some());
@@ -1,7 +1,6 @@
 //! Tests for types module
 
 use stepflow_core::types::*;
-use chrono::Utc;
 
 #[tokio::test]
 async fn test_tool_id() {
@@ -15,6 +14,29 @@ async fn test_tool_id() {
     assert_eq!(id3.as_str(), "test-id");
 }
 
+#[test]
+fn test_tool_id_validation() {
+    for valid in ["python-calculator", "tool:ns:name@1.0.0", "tool:ns:name/1.0.0", "a", &uuid::Uuid::new_v4().to_string()] {
+        let id: ToolId = valid.parse().unwrap();
+        assert_eq!(id.to_string(), valid);
+    }
+    let longest = "a".repeat(MAX_ID_LEN);
+    assert!(ToolId::parse(longest.as_str()).is_ok());
+
+    for invalid in ["", "-leading", "has space", "quote'", "semi;colon", "naïve", &"a".repeat(MAX_ID_LEN + 1)] {
+        assert!(ToolId::parse(invalid).is_err(), "{:?} should be rejected", invalid);
+    }
+    let error = ToolId::parse("bad id").unwrap_err().to_string();
+    assert!(error.contains("tool ID") && error.contains("' '"), "{}", error);
+
+    // Serde goes through the same validation, the wire format stays a plain string
+    let id = ToolId::parse("echo").unwrap();
+    assert_eq!(serde_json::to_value(&id).unwrap(), serde_json::json!("echo"));
+    assert_eq!(serde_json::from_value::<ToolId>(serde_json::json!("echo")).unwrap(), id);
+    assert!(serde_json::from_value::<ToolId>(serde_json::json!("drop table;")).is_err());
+    assert_eq!(String::from(id), "echo");
+}
+
 #[tokio::test]
 async fn test_tool_version() {
     let version = ToolVersion::new(1, 2, 3);
@@ -42,29 +64,115 @@ async fn test_tool_status() {
 
 #[tokio::test]
 async fn test_tool_info() {
-    let info = ToolInfo {
-        id: ToolId::new(),
-        name: "test-tool".to_string(),
-        description: "A test tool".to_string(),
-        version: ToolVersion::new(1, 0, 0),
-        tool_type: ToolType::Python,
-        status: ToolStatus::Active,
-        author: "test-author".to_string(),
-        repository: Some("https://github.com/test/tool".to_string()),
-        documentation: Some("https://docs.test.com".to_string()),
-        tags: vec!["test".to_string(), "example".to_string()],
-        capabilities: vec!["process".to_string()],
-        configuration_schema: None,
-        examples: vec![],
-        created_at: Utc::now(),
-        updated_at: Utc::now(),
-    };
+    let info = ToolInfo::builder()
+        .name("test-tool")
+        .description("A test tool")
+        .tool_type(ToolType::Python)
+        .author("test-author")
+        .repository("https://github.com/test/tool".to_string())
+        .documentation("https://docs.test.com".to_string())
+        .tags(["test", "example"])
+        .capabilities(["process"])
+        .build()
+        .unwrap();
     
     assert_eq!(info.name, "test-tool");
     assert_eq!(info.tool_type, ToolType::Python);
     assert_eq!(info.status, ToolStatus::Active);
 }
 
+#[test]
+fn test_tool_info_builder_validation() {
+    let info = ToolInfo::builder().name("echo").tool_type(ToolType::Python).build().unwrap();
+    assert_eq!(info.version, ToolVersion::new(1, 0, 0));
+    assert_eq!(info.updated_at, info.created_at);
+    assert!(info.configuration_schema.is_none());
+
+    assert!(matches!(
+        ToolInfo::builder().name(" ").tool_type(ToolType::Python).build(),
+        Err(stepflow_core::ValidationError::RequiredFieldMissing(field)) if field == "name"
+    ));
+    assert!(matches!(
+        ToolInfo::builder().name("echo").build(),
+        Err(stepflow_core::ValidationError::RequiredFieldMissing(field)) if field == "tool_type"
+    ));
+    assert!(ToolInfo::builder()
+        .name("echo")
+        .tool_type(ToolType::Python)
+        .input_schema(serde_json::json!("string"))
+        .build()
+        .is_err());
+    let created_at = chrono::Utc::now();
+    assert!(ToolInfo::builder()
+        .name("echo")
+        .tool_type(ToolType::Python)
+        .created_at(created_at)
+        .updated_at(created_at - chrono::Duration::seconds(1))
+        .build()
+        .is_err());
+
+    let request = ToolRequest::new(info.id.clone(), serde_json::json!({"text": "hi"}))
+        .with_metadata("trace", serde_json::json!("abc"));
+    assert_eq!(request.tool_id, info.id);
+    assert!(request.configuration.is_none());
+    assert_eq!(request.metadata["trace"], "abc");
+}
+
+#[test]
+fn test_tool_session_command() {
+    let session = ToolSessionCommand {
+        command: vec!["python3".to_string(), "-c".to_string()],
+        allowed_args: vec!["-u".to_string()],
+    };
+    assert_eq!(session.with_args(&[]).unwrap(), ["python3", "-c"]);
+    assert_eq!(session.with_args(&["-u".to_string()]).unwrap(), ["python3", "-u", "-c"]);
+    assert!(session.with_args(&["-m".to_string()]).is_err());
+
+    assert!(ToolInfo::builder()
+        .name("repl")
+        .tool_type(ToolType::Python)
+        .session(ToolSessionCommand::default())
+        .build()
+        .is_err());
+}
+
+#[test]
+fn test_tool_image_digest() {
+    let digest = format!("sha256:{}", "ab".repeat(32));
+    let info = ToolInfo::builder()
+        .name("python")
+        .tool_type(ToolType::Python)
+        .image(ToolImage::new("tools/python:3.11", digest.clone()))
+        .build()
+        .unwrap();
+    assert_eq!(info.image.unwrap().digest, digest);
+
+    for image in [ToolImage::new("tools/python:3.11", "sha256:abc"), ToolImage::new("", digest)] {
+        assert!(ToolInfo::builder().name("python").tool_type(ToolType::Python).image(image).build().is_err());
+    }
+}
+
+#[test]
+fn test_tool_info_localized() {
+    let mut info: ToolInfo = serde_json::from_value(serde_json::json!({
+        "id": "echo", "name": "Echo", "description": "Returns its input",
+        "version": {"major": 1, "minor": 0, "patch": 0, "pre_release": null, "build": null},
+        "tool_type": "Python", "status": "Active", "author": "stepflow",
+        "repository": null, "documentation": null, "tags": [], "capabilities": [],
+        "configuration_schema": null, "examples": [],
+        "created_at": "2024-01-01T00:00:00Z", "updated_at": "2024-01-01T00:00:00Z",
+    })).unwrap();
+    let english = vec!["en".to_string()];
+    assert_eq!(info.localized(&english), ("Echo", "Returns its input"));
+
+    info.localizations.insert("zh".to_string(), ToolLocalization { name: Some("回显".to_string()), description: None });
+    info.localizations.insert("zh-TW".to_string(), ToolLocalization { name: Some("迴響".to_string()), description: None });
+    let languages = |tags: &[&str]| tags.iter().map(|tag| tag.to_string()).collect::<Vec<_>>();
+    assert_eq!(info.localized(&languages(&["zh-CN"])), ("回显", "Returns its input"));
+    assert_eq!(info.localized(&languages(&["ZH-tw"])), ("迴響", "Returns its input"));
+    assert_eq!(info.localized(&languages(&["fr", "zh"])), ("回显", "Returns its input"));
+}
+
 #[tokio::test]
 async fn test_execution_id() {
     let id1 = ExecutionId::new();
@@ -74,6 +182,11 @@ async fn test_execution_id() {
     
     let id3 = ExecutionId::from_string("exec-123".to_string());
     assert_eq!(id3.as_str(), "exec-123");
+
+    let parsed: ExecutionId = id1.to_string().parse().unwrap();
+    assert_eq!(parsed, id1);
+    assert!(ExecutionId::try_from("exec 1".to_string()).is_err());
+    assert!(serde_json::from_value::<ExecutionId>(serde_json::json!("")).is_err());
 }
 
 #[tokio::test]
@@ -0,0 +1,62 @@
+//! Database operation microbenchmarks
+//!
+//! Run with `cargo bench -p stepflow-database`.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use stepflow_core::{ToolId, ToolInfo, ToolType};
+use stepflow_database::{MigrationManager, SqliteDatabase, ToolRepository};
+
+fn tool(id: usize) -> ToolInfo {
+    ToolInfo::builder()
+        .id(ToolId::from_string(format!("bench-tool-{}", id)))
+        .name(format!("Bench Tool {}", id))
+        .description("Tool used by database benchmarks")
+        .tool_type(ToolType::Python)
+        .author("stepflow")
+        .tags(["bench"])
+        .build()
+        .unwrap()
+}
+
+fn database_benchmarks(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let repository = runtime.block_on(async {
+        let database = SqliteDatabase::new("sqlite::memory:").await.unwrap();
+        MigrationManager::run_migrations(&database).await.unwrap();
+        let repository = ToolRepository::new(database);
+        let tools: Vec<ToolInfo> = (0..1_000).map(tool).collect();
+        repository.create_tools(&tools).await.unwrap();
+        repository
+    });
+
+    let mut next_id = 1_000;
+    c.bench_function("db_insert_tool", |b| {
+        b.iter_batched(
+            || {
+                next_id += 1;
+                tool(next_id)
+            },
+            |tool| runtime.block_on(repository.create_tool(&tool)).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+
+    let id = ToolId::from_string("bench-tool-500".to_string());
+    c.bench_function("db_get_tool", |b| {
+        b.iter(|| runtime.block_on(repository.get_tool(&id)).unwrap())
+    });
+
+    c.bench_function("db_insert_batch_100", |b| {
+        b.iter_batched(
+            || {
+                next_id += 100;
+                (next_id - 100..next_id).map(tool).collect::<Vec<_>>()
+            },
+            |tools| runtime.block_on(repository.create_tools(&tools)).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+criterion_group!(benches, database_benchmarks);
+criterion_main!(benches);
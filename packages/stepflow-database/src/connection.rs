@@ -1,12 +1,17 @@
 //! Database connection management
 
-use sqlx::{Sqlite, SqlitePool, Row, Column, TypeInfo};
-use stepflow_core::{Database, QueryResult, Migration, DatabaseStats, StepflowError, StepflowResult};
-use tracing::{debug, info, error};
+use sqlx::{Sqlite, SqliteConnection, SqlitePool, Row, Column, TypeInfo, ValueRef};
+use sqlx::pool::PoolConnection;
+use sqlx::Connection as _;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous};
+use stepflow_core::{Database, QueryResult, Migration, DatabaseStats, ReadConsistency, StepflowError, StepflowResult};
+use tracing::{debug, info, error, warn};
+use std::str::FromStr;
 use std::time::{Duration, Instant};
 use base64::Engine;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
+use crate::encryption::MasterKeyring;
 
 /// SQLite database connection configuration
 #[derive(Debug, Clone)]
@@ -17,6 +22,44 @@ pub struct DatabaseConfig {
     pub connection_timeout: Duration,
     pub idle_timeout: Duration,
     pub max_lifetime: Duration,
+    /// Use write-ahead logging so readers don't block the writer (ignored for in-memory databases)
+    pub wal: bool,
+    /// How long a statement waits on a locked database before failing
+    pub busy_timeout: Duration,
+    /// Funnel all writes through a single dedicated writer connection
+    pub serialize_writes: bool,
+    /// Prepared statements kept per connection, keyed by SQL text
+    pub statement_cache_capacity: usize,
+    /// Read replicas; writes always go to the primary at `url`
+    pub replicas: Vec<ReplicaConfig>,
+    /// Consistency used by [`Database::execute`] for reads
+    pub read_consistency: ReadConsistency,
+    /// How often a replica's replication position is re-read
+    pub replica_check_interval: Duration,
+    /// Statements running longer than this count as slow
+    pub slow_query_threshold: Duration,
+    /// Capture slow statements and their query plans in the slow query log
+    pub capture_slow_queries: bool,
+}
+
+/// Read replica configuration
+#[derive(Debug, Clone)]
+pub struct ReplicaConfig {
+    pub url: String,
+    pub max_connections: u32,
+    /// Lag beyond which the replica stops serving eventually consistent reads
+    pub max_lag: Duration,
+}
+
+impl ReplicaConfig {
+    /// Create a replica configuration with default pool size and lag bound
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            max_connections: 5,
+            max_lag: Duration::from_secs(5),
+        }
+    }
 }
 
 impl Default for DatabaseConfig {
@@ -28,16 +71,147 @@ impl Default for DatabaseConfig {
             connection_timeout: Duration::from_secs(30),
             idle_timeout: Duration::from_secs(600),
             max_lifetime: Duration::from_secs(1800),
+            wal: true,
+            busy_timeout: Duration::from_secs(5),
+            serialize_writes: true,
+            statement_cache_capacity: 100,
+            replicas: Vec::new(),
+            read_consistency: ReadConsistency::default(),
+            replica_check_interval: Duration::from_secs(1),
+            slow_query_threshold: Duration::from_secs(1),
+            capture_slow_queries: true,
         }
     }
 }
 
+impl DatabaseConfig {
+    /// Whether the URL points at an in-memory database
+    pub fn is_in_memory(&self) -> bool {
+        self.url.contains(":memory:") || self.url.contains("mode=memory")
+    }
+}
+
 /// SQLite database connection manager
+///
+/// Reads run in parallel on the main pool. When `serialize_writes` is enabled, every
+/// write goes through a dedicated single-connection writer pool, so concurrent writers
+/// queue inside the process instead of racing for SQLite's write lock.
+///
+/// With read replicas configured, each write also advances a position counter in the
+/// `replication_state` table. Replicas report the position they have applied, which
+/// decides whether they may serve a read under its [`ReadConsistency`].
 #[derive(Clone)]
 pub struct SqliteDatabase {
     pool: SqlitePool,
+    writer: Option<SqlitePool>,
     config: DatabaseConfig,
     stats: Arc<DatabaseStatsTracker>,
+    statement_cache: Arc<StatementCacheTracker>,
+    replicas: Arc<[Replica]>,
+    write_position: Arc<AtomicU64>,
+    next_replica: Arc<AtomicUsize>,
+    keyring: Option<Arc<MasterKeyring>>,
+    #[cfg(feature = "fault-injection")]
+    faults: Option<stepflow_core::FaultController>,
+}
+
+/// A read replica and the replication position last read from it
+struct Replica {
+    config: ReplicaConfig,
+    pool: SqlitePool,
+    state: std::sync::Mutex<ReplicaState>,
+    reads: AtomicU64,
+}
+
+#[derive(Debug, Default)]
+struct ReplicaState {
+    position: u64,
+    heartbeat: Option<chrono::DateTime<chrono::Utc>>,
+    checked_at: Option<Instant>,
+    healthy: bool,
+}
+
+impl Replica {
+    /// Re-read the replicated position; an unreachable replica is marked unhealthy
+    async fn refresh(&self) {
+        let result = sqlx::query("SELECT position, updated_at FROM replication_state WHERE id = 1")
+            .fetch_optional(&self.pool)
+            .await;
+
+        let mut state = self.state.lock().unwrap();
+        state.checked_at = Some(Instant::now());
+        match result {
+            Ok(row) => {
+                state.healthy = true;
+                state.position = row.as_ref().and_then(|row| row.try_get::<i64, _>(0).ok()).unwrap_or(0) as u64;
+                state.heartbeat = row
+                    .as_ref()
+                    .and_then(|row| row.try_get::<String, _>(1).ok())
+                    .and_then(|t| chrono::DateTime::parse_from_rfc3339(&t).ok())
+                    .map(|t| t.with_timezone(&chrono::Utc));
+            }
+            Err(e) => {
+                if state.healthy {
+                    warn!("Read replica {} unavailable: {}", self.config.url, e);
+                }
+                state.healthy = false;
+            }
+        }
+    }
+
+    fn needs_refresh(&self, interval: Duration, target: u64, consistency: ReadConsistency) -> bool {
+        let state = self.state.lock().unwrap();
+        let stale = state.checked_at.is_none_or(|checked_at| checked_at.elapsed() >= interval);
+        stale || (consistency == ReadConsistency::ReadYourWrites && state.position < target)
+    }
+
+    /// Replication lag relative to the primary's position, `None` when unknown
+    ///
+    /// A replica that is behind has seen nothing newer than its last heartbeat, so
+    /// the lag is the age of that heartbeat.
+    fn lag(&self, primary_position: u64) -> Option<Duration> {
+        let state = self.state.lock().unwrap();
+        if !state.healthy {
+            return None;
+        }
+        if state.position >= primary_position {
+            return Some(Duration::ZERO);
+        }
+        state.heartbeat.map(|heartbeat| (chrono::Utc::now() - heartbeat).to_std().unwrap_or_default())
+    }
+
+    fn can_serve(&self, consistency: ReadConsistency, primary_position: u64) -> bool {
+        match consistency {
+            ReadConsistency::Primary => false,
+            ReadConsistency::Eventual => self.lag(primary_position).is_some_and(|lag| lag <= self.config.max_lag),
+            ReadConsistency::ReadYourWrites => self.lag(primary_position) == Some(Duration::ZERO),
+        }
+    }
+
+    fn stats(&self, primary_position: u64) -> ReplicaStats {
+        let lag = self.lag(primary_position);
+        let state = self.state.lock().unwrap();
+        ReplicaStats {
+            url: self.config.url.clone(),
+            position: state.position,
+            lag,
+            healthy: state.healthy,
+            reads: self.reads.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Read replica metrics
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ReplicaStats {
+    pub url: String,
+    /// Replication position last read from the replica
+    pub position: u64,
+    /// Lag behind the primary, `None` when unknown
+    pub lag: Option<Duration>,
+    pub healthy: bool,
+    /// Reads served by this replica
+    pub reads: u64,
 }
 
 /// Database statistics tracker
@@ -47,6 +221,125 @@ pub struct DatabaseStatsTracker {
     pub slow_queries: AtomicU64,
     pub errors: AtomicU64,
     pub slow_query_threshold: Duration,
+    pub total_writes: AtomicU64,
+    pub queued_writes: AtomicU64,
+    pub write_wait_micros: AtomicU64,
+    pub max_write_wait_micros: AtomicU64,
+    pub lock_errors: AtomicU64,
+    /// Reads that fell back to the primary because no replica could serve them
+    pub replica_fallbacks: AtomicU64,
+}
+
+/// Prepared statement cache metrics
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct StatementCacheStats {
+    /// Statements each connection keeps prepared
+    pub capacity: usize,
+    /// Queries that reused a statement already prepared on their connection
+    pub hits: u64,
+    /// Queries that had to prepare their statement
+    pub misses: u64,
+    /// Queries on a full cache, where a hit and an evicting miss look the same
+    pub unsampled: u64,
+    /// Largest number of statements seen cached on one connection
+    pub largest_cache: usize,
+}
+
+impl StatementCacheStats {
+    /// Fraction of sampled queries that reused a prepared statement
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 { 0.0 } else { self.hits as f64 / total as f64 }
+    }
+}
+
+/// Counts statement cache hits from the size of the connection's own cache
+///
+/// sqlx exposes only how many statements a connection has cached, so the size is
+/// sampled before and after each query on the connection that ran it: growth means
+/// the statement was prepared, no change on a cache with room means it was reused.
+#[derive(Debug)]
+struct StatementCacheTracker {
+    capacity: usize,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    unsampled: AtomicU64,
+    largest_cache: AtomicUsize,
+}
+
+impl StatementCacheTracker {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            unsampled: AtomicU64::new(0),
+            largest_cache: AtomicUsize::new(0),
+        }
+    }
+
+    fn record(&self, cached_before: usize, cached_after: usize) {
+        self.largest_cache.fetch_max(cached_after, Ordering::Relaxed);
+        let counter = if cached_after > cached_before || self.capacity == 0 {
+            &self.misses
+        } else if cached_before < self.capacity {
+            &self.hits
+        } else {
+            &self.unsampled
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn stats(&self) -> StatementCacheStats {
+        StatementCacheStats {
+            capacity: self.capacity,
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            unsampled: self.unsampled.load(Ordering::Relaxed),
+            largest_cache: self.largest_cache.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// SQLite's default limit on bind parameters per statement
+const MAX_BIND_PARAMS: usize = 999;
+
+/// Outcome of a batch insert
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct BatchInsertReport {
+    /// Rows written
+    pub inserted: usize,
+    /// Rows that could not be written, by position in the input
+    pub failures: Vec<BatchFailure>,
+}
+
+impl BatchInsertReport {
+    /// Whether every row was written
+    pub fn is_complete(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// A row rejected by a batch insert
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct BatchFailure {
+    pub index: usize,
+    pub error: String,
+}
+
+/// Write contention metrics
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ContentionStats {
+    /// Writes executed so far
+    pub total_writes: u64,
+    /// Writes currently waiting for the writer connection
+    pub queued_writes: u64,
+    /// Total time writes spent waiting for the writer connection
+    pub total_write_wait: Duration,
+    /// Longest single wait for the writer connection
+    pub max_write_wait: Duration,
+    /// Statements that failed with `database is locked` / `database table is locked`
+    pub lock_errors: u64,
 }
 
 impl SqliteDatabase {
@@ -61,21 +354,147 @@ impl SqliteDatabase {
 
     /// Create a new SQLite database connection with custom configuration
     pub async fn with_config(config: DatabaseConfig) -> Result<Self, StepflowError> {
-        let pool = SqlitePool::connect(&config.url)
+        let connection_failed = |e: sqlx::Error| StepflowError::DatabaseError(stepflow_core::DatabaseError::ConnectionFailed(
+            format!("Failed to connect to database: {}", e)
+        ));
+
+        // Parse once: every in-memory URL parse yields a distinct database, and the
+        // reader and writer pools must share the same one
+        let options = SqliteConnectOptions::from_str(&config.url)
+            .map_err(connection_failed)?
+            .busy_timeout(config.busy_timeout)
+            .statement_cache_capacity(config.statement_cache_capacity);
+
+        // The writer connects first so WAL, which needs an exclusive lock to enable, is
+        // in place before any reader opens the file
+        let writer = if config.serialize_writes {
+            let mut writer_options = options.clone();
+            if config.wal && !config.is_in_memory() {
+                writer_options = writer_options
+                    .journal_mode(SqliteJournalMode::Wal)
+                    .synchronous(SqliteSynchronous::Normal);
+            }
+            let writer = SqlitePoolOptions::new()
+                .max_connections(1)
+                .min_connections(1)
+                .acquire_timeout(config.connection_timeout)
+                .idle_timeout(None)
+                .max_lifetime(None)
+                .connect_with(writer_options)
+                .await
+                .map_err(connection_failed)?;
+            Some(writer)
+        } else {
+            None
+        };
+
+        let mut reader_options = options;
+        if config.wal && !config.is_in_memory() && writer.is_none() {
+            reader_options = reader_options
+                .journal_mode(SqliteJournalMode::Wal)
+                .synchronous(SqliteSynchronous::Normal);
+        }
+        let pool = SqlitePoolOptions::new()
+            .max_connections(config.max_connections)
+            .min_connections(config.min_connections)
+            .acquire_timeout(config.connection_timeout)
+            .idle_timeout(config.idle_timeout)
+            .max_lifetime(config.max_lifetime)
+            .connect_with(reader_options)
             .await
-            .map_err(|e| StepflowError::DatabaseError(stepflow_core::DatabaseError::ConnectionFailed(
-                format!("Failed to connect to database: {}", e)
-            )))?;
+            .map_err(connection_failed)?;
 
         let stats = Arc::new(DatabaseStatsTracker {
             total_queries: AtomicU64::new(0),
             slow_queries: AtomicU64::new(0),
             errors: AtomicU64::new(0),
-            slow_query_threshold: Duration::from_millis(1000), // 1 second threshold
+            slow_query_threshold: config.slow_query_threshold,
+            total_writes: AtomicU64::new(0),
+            queued_writes: AtomicU64::new(0),
+            write_wait_micros: AtomicU64::new(0),
+            max_write_wait_micros: AtomicU64::new(0),
+            lock_errors: AtomicU64::new(0),
+            replica_fallbacks: AtomicU64::new(0),
         });
 
-        info!("Connected to SQLite database with {} max connections", config.max_connections);
-        Ok(Self { pool, config, stats })
+        let mut replicas = Vec::with_capacity(config.replicas.len());
+        for replica in &config.replicas {
+            let options = SqliteConnectOptions::from_str(&replica.url)
+                .map_err(connection_failed)?
+                .read_only(true)
+                .busy_timeout(config.busy_timeout)
+                .statement_cache_capacity(config.statement_cache_capacity);
+            let pool = SqlitePoolOptions::new()
+                .max_connections(replica.max_connections)
+                .min_connections(0)
+                .acquire_timeout(config.connection_timeout)
+                .idle_timeout(config.idle_timeout)
+                .max_lifetime(config.max_lifetime)
+                .connect_lazy_with(options);
+            replicas.push(Replica {
+                config: replica.clone(),
+                pool,
+                state: std::sync::Mutex::new(ReplicaState::default()),
+                reads: AtomicU64::new(0),
+            });
+        }
+
+        // Resume from the recorded position so replicas that lag behind writes made
+        // before a restart are still recognised as behind
+        let write_position = if replicas.is_empty() {
+            0
+        } else {
+            sqlx::query("SELECT position FROM replication_state WHERE id = 1")
+                .fetch_optional(&pool)
+                .await
+                .ok()
+                .flatten()
+                .and_then(|row| row.try_get::<i64, _>(0).ok())
+                .unwrap_or(0) as u64
+        };
+
+        info!(
+            "Connected to SQLite database with {} max connections (wal: {}, serialized writes: {})",
+            config.max_connections, config.wal, config.serialize_writes
+        );
+        let statement_cache = Arc::new(StatementCacheTracker::new(config.statement_cache_capacity));
+        Ok(Self {
+            pool,
+            writer,
+            config,
+            stats,
+            statement_cache,
+            replicas: replicas.into(),
+            write_position: Arc::new(AtomicU64::new(write_position)),
+            next_replica: Arc::new(AtomicUsize::new(0)),
+            keyring: None,
+            #[cfg(feature = "fault-injection")]
+            faults: None,
+        })
+    }
+
+    /// Encrypt tenant secrets and credentials with data keys wrapped by `keyring`
+    ///
+    /// See [`TenantKeyRepository`](crate::TenantKeyRepository). Without a keyring
+    /// they are stored in plaintext.
+    pub fn with_encryption(mut self, keyring: Arc<MasterKeyring>) -> Self {
+        self.keyring = Some(keyring);
+        self
+    }
+
+    /// The master keyring, `None` when encryption is not configured
+    pub fn keyring(&self) -> Option<&Arc<MasterKeyring>> {
+        self.keyring.as_ref()
+    }
+
+    /// Inject faults into queries run through [`Database::execute`]
+    ///
+    /// A partial write runs the query and then reports it as failed; reads treat it as
+    /// a plain error.
+    #[cfg(feature = "fault-injection")]
+    pub fn with_fault_controller(mut self, controller: stepflow_core::FaultController) -> Self {
+        self.faults = Some(controller);
+        self
     }
 
     /// Get the connection pool
@@ -83,11 +502,122 @@ impl SqliteDatabase {
         &self.pool
     }
 
+    /// Get the pool used for writes: the dedicated writer when writes are serialized
+    pub fn writer_pool(&self) -> &SqlitePool {
+        self.writer.as_ref().unwrap_or(&self.pool)
+    }
+
     /// Get the database configuration
     pub fn config(&self) -> &DatabaseConfig {
         &self.config
     }
 
+    /// Get write contention metrics
+    pub fn contention_stats(&self) -> ContentionStats {
+        ContentionStats {
+            total_writes: self.stats.total_writes.load(Ordering::Relaxed),
+            queued_writes: self.stats.queued_writes.load(Ordering::Relaxed),
+            total_write_wait: Duration::from_micros(self.stats.write_wait_micros.load(Ordering::Relaxed)),
+            max_write_wait: Duration::from_micros(self.stats.max_write_wait_micros.load(Ordering::Relaxed)),
+            lock_errors: self.stats.lock_errors.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Get prepared statement cache metrics
+    pub fn statement_cache_stats(&self) -> StatementCacheStats {
+        self.statement_cache.stats()
+    }
+
+    /// Replication position of the latest write made through this database
+    pub fn write_position(&self) -> u64 {
+        self.write_position.load(Ordering::Acquire)
+    }
+
+    /// Get read replica metrics, in configuration order
+    pub fn replica_stats(&self) -> Vec<ReplicaStats> {
+        let position = self.write_position();
+        self.replicas.iter().map(|replica| replica.stats(position)).collect()
+    }
+
+    /// Reads that fell back to the primary because no replica could serve them
+    pub fn replica_fallbacks(&self) -> u64 {
+        self.stats.replica_fallbacks.load(Ordering::Relaxed)
+    }
+
+    /// Pick the pool for a read: replicas in round-robin order, the primary when
+    /// none of them satisfies `consistency`
+    async fn read_pool(&self, consistency: ReadConsistency) -> &SqlitePool {
+        if self.replicas.is_empty() || consistency == ReadConsistency::Primary {
+            return &self.pool;
+        }
+
+        let target = self.write_position();
+        let start = self.next_replica.fetch_add(1, Ordering::Relaxed);
+        for offset in 0..self.replicas.len() {
+            let replica = &self.replicas[(start + offset) % self.replicas.len()];
+            if replica.needs_refresh(self.config.replica_check_interval, target, consistency) {
+                replica.refresh().await;
+            }
+            if replica.can_serve(consistency, target) {
+                replica.reads.fetch_add(1, Ordering::Relaxed);
+                return &replica.pool;
+            }
+        }
+
+        self.stats.replica_fallbacks.fetch_add(1, Ordering::Relaxed);
+        &self.pool
+    }
+
+    /// Record a completed write in `replication_state` so replicas can report how far
+    /// they have caught up. Only done when replicas are configured.
+    pub(crate) async fn advance_write_position(&self, connection: &mut SqliteConnection) {
+        if self.replicas.is_empty() {
+            return;
+        }
+        let result = sqlx::query(
+            "INSERT INTO replication_state (id, position, updated_at) VALUES (1, 1, ?) \
+             ON CONFLICT(id) DO UPDATE SET position = position + 1, updated_at = excluded.updated_at \
+             RETURNING position",
+        )
+        .bind(chrono::Utc::now().to_rfc3339())
+        .fetch_one(connection)
+        .await;
+
+        match result.and_then(|row| row.try_get::<i64, _>(0)) {
+            Ok(position) => {
+                self.write_position.fetch_max(position as u64, Ordering::AcqRel);
+            }
+            // The table only exists once migrations have run
+            Err(e) => debug!("Replication position not recorded: {}", e),
+        }
+    }
+
+    /// Acquire a connection for writing, recording how long the write had to queue
+    pub(crate) async fn acquire_writer(&self) -> StepflowResult<PoolConnection<Sqlite>> {
+        self.stats.queued_writes.fetch_add(1, Ordering::Relaxed);
+        let start = Instant::now();
+        let connection = self.writer_pool().acquire().await;
+        self.stats.queued_writes.fetch_sub(1, Ordering::Relaxed);
+
+        let waited = start.elapsed().as_micros() as u64;
+        self.stats.total_writes.fetch_add(1, Ordering::Relaxed);
+        self.stats.write_wait_micros.fetch_add(waited, Ordering::Relaxed);
+        self.stats.max_write_wait_micros.fetch_max(waited, Ordering::Relaxed);
+
+        connection.map_err(|e| StepflowError::DatabaseError(stepflow_core::DatabaseError::ConnectionFailed(
+            format!("Failed to acquire writer connection: {}", e)
+        )))
+    }
+
+    /// Count lock contention failures so they show up in the contention metrics
+    fn track_lock_error(&self, error: &sqlx::Error) {
+        let message = error.to_string();
+        if message.contains("database is locked") || message.contains("database table is locked") {
+            self.stats.lock_errors.fetch_add(1, Ordering::Relaxed);
+            warn!("SQLite lock contention: {}", message);
+        }
+    }
+
     /// Check if the database connection is healthy
     pub async fn health_check(&self) -> Result<bool, StepflowError> {
         match sqlx::query("SELECT 1").execute(&self.pool).await {
@@ -114,10 +644,25 @@ impl SqliteDatabase {
         })
     }
 
+    /// Size of the database file in bytes, including free pages
+    pub async fn size_bytes(&self) -> Result<u64, StepflowError> {
+        let row = sqlx::query("SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()")
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| StepflowError::DatabaseError(stepflow_core::DatabaseError::QueryFailed(e.to_string())))?;
+        Ok(row.try_get::<i64, _>(0).unwrap_or(0).max(0) as u64)
+    }
+
     /// Close the database connection
     pub async fn close(&self) -> Result<(), StepflowError> {
         info!("Closing database connection pool");
         self.pool.close().await;
+        if let Some(writer) = &self.writer {
+            writer.close().await;
+        }
+        for replica in self.replicas.iter() {
+            replica.pool.close().await;
+        }
         Ok(())
     }
 }
@@ -133,6 +678,15 @@ pub struct PoolStats {
 #[async_trait::async_trait]
 impl Database for SqliteDatabase {
     async fn execute(&self, query: &str, params: &[serde_json::Value]) -> StepflowResult<QueryResult> {
+        self.execute_with(query, params, self.config.read_consistency).await
+    }
+
+    async fn execute_with(
+        &self,
+        query: &str,
+        params: &[serde_json::Value],
+        consistency: ReadConsistency,
+    ) -> StepflowResult<QueryResult> {
         debug!("Executing query: {}", query);
         
         let start_time = Instant::now();
@@ -141,10 +695,23 @@ impl Database for SqliteDatabase {
         // 检查查询类型
         let query_type = query.trim().to_lowercase();
         let is_select = query_type.starts_with("select");
+
+        #[cfg(feature = "fault-injection")]
+        let fault = match &self.faults {
+            Some(faults) => faults.inject(stepflow_core::FaultTarget::Database).await,
+            None => None,
+        };
+        #[cfg(feature = "fault-injection")]
+        if let Some(fault) = fault.filter(|fault| is_select || *fault == stepflow_core::Fault::Error) {
+            self.stats.errors.fetch_add(1, Ordering::Relaxed);
+            return Err(StepflowError::DatabaseError(stepflow_core::DatabaseError::ConnectionFailed(
+                fault.message(stepflow_core::FaultTarget::Database),
+            )));
+        }
         
         let result = if is_select {
             // 处理SELECT查询
-            self.execute_select_query(query, params).await
+            self.execute_select_query(query, params, consistency).await
         } else {
             // 处理INSERT/UPDATE/DELETE查询
             self.execute_modify_query(query, params).await
@@ -156,8 +723,22 @@ impl Database for SqliteDatabase {
         if duration > self.stats.slow_query_threshold {
             self.stats.slow_queries.fetch_add(1, Ordering::Relaxed);
             debug!("Slow query detected: {} (took {:?})", query, duration);
+            // Captured in the background: the writer may be held by a transaction
+            // waiting on this very query
+            if self.config.capture_slow_queries {
+                let (database, query, params, success) = (self.clone(), query.to_string(), params.to_vec(), result.is_ok());
+                tokio::spawn(async move { database.capture_slow_query(&query, &params, duration, success).await });
+            }
         }
 
+        #[cfg(feature = "fault-injection")]
+        let result = match (result, fault) {
+            (Ok(_), Some(fault)) => Err(StepflowError::DatabaseError(stepflow_core::DatabaseError::ConnectionFailed(
+                fault.message(stepflow_core::FaultTarget::Database),
+            ))),
+            (result, _) => result,
+        };
+
         // 跟踪错误
         if result.is_err() {
             self.stats.errors.fetch_add(1, Ordering::Relaxed);
@@ -171,7 +752,7 @@ impl Database for SqliteDatabase {
             debug!("Running migration: {}", migration.name);
             
             sqlx::query(&migration.sql)
-                .execute(&self.pool)
+                .execute(self.writer_pool())
                 .await
                 .map_err(|e| StepflowError::DatabaseError(stepflow_core::DatabaseError::MigrationFailed(
                     format!("Migration {} failed: {}", migration.name, e)
@@ -195,34 +776,54 @@ impl Database for SqliteDatabase {
     }
 }
 
-impl SqliteDatabase {
-    /// Execute a SELECT query and return rows
-    async fn execute_select_query(&self, query: &str, params: &[serde_json::Value]) -> StepflowResult<QueryResult> {
-        let mut query_builder = sqlx::query(query);
-        
-        // 绑定参数 - 直接使用值而不是序列化
-        for param in params {
-            match param {
-                serde_json::Value::String(s) => query_builder = query_builder.bind(s),
-                serde_json::Value::Number(n) => {
-                    if let Some(i) = n.as_i64() {
-                        query_builder = query_builder.bind(i);
-                    } else if let Some(f) = n.as_f64() {
-                        query_builder = query_builder.bind(f);
-                    } else {
-                        query_builder = query_builder.bind(n.to_string());
-                    }
+/// Bind JSON parameters to a query using their natural SQLite types
+pub(crate) fn bind_params<'q>(
+    mut query_builder: sqlx::query::Query<'q, Sqlite, sqlx::sqlite::SqliteArguments<'q>>,
+    params: &'q [serde_json::Value],
+) -> StepflowResult<sqlx::query::Query<'q, Sqlite, sqlx::sqlite::SqliteArguments<'q>>> {
+    // 绑定参数 - 直接使用值而不是序列化
+    for param in params {
+        match param {
+            serde_json::Value::String(s) => query_builder = query_builder.bind(s),
+            serde_json::Value::Number(n) => {
+                if let Some(i) = n.as_i64() {
+                    query_builder = query_builder.bind(i);
+                } else if let Some(f) = n.as_f64() {
+                    query_builder = query_builder.bind(f);
+                } else {
+                    query_builder = query_builder.bind(n.to_string());
                 }
-                serde_json::Value::Bool(b) => query_builder = query_builder.bind(b),
-                serde_json::Value::Null => query_builder = query_builder.bind(None::<String>),
-                _ => query_builder = query_builder.bind(serde_json::to_string(param)?),
             }
+            serde_json::Value::Bool(b) => query_builder = query_builder.bind(b),
+            serde_json::Value::Null => query_builder = query_builder.bind(None::<String>),
+            _ => query_builder = query_builder.bind(serde_json::to_string(param)?),
         }
+    }
+    Ok(query_builder)
+}
+
+impl SqliteDatabase {
+    /// Execute a SELECT query and return rows
+    async fn execute_select_query(
+        &self,
+        query: &str,
+        params: &[serde_json::Value],
+        consistency: ReadConsistency,
+    ) -> StepflowResult<QueryResult> {
+        let query_builder = bind_params(sqlx::query(query), params)?;
 
-        let rows = query_builder.fetch_all(&self.pool).await
-            .map_err(|e| StepflowError::DatabaseError(stepflow_core::DatabaseError::QueryFailed(
+        let pool = self.read_pool(consistency).await;
+        let query_failed = |e: sqlx::Error| {
+            self.track_lock_error(&e);
+            StepflowError::DatabaseError(stepflow_core::DatabaseError::QueryFailed(
                 format!("Query execution failed: {}", e)
-            )))?;
+            ))
+        };
+        let mut connection = pool.acquire().await.map_err(query_failed)?;
+        let cached_before = connection.cached_statements_size();
+        let rows = query_builder.fetch_all(&mut *connection).await.map_err(query_failed)?;
+        self.statement_cache.record(cached_before, connection.cached_statements_size());
+        drop(connection);
 
         // 将行转换为HashMap<String, serde_json::Value>
         let mut result_rows = Vec::new();
@@ -233,6 +834,12 @@ impl SqliteDatabase {
             let columns = row.columns();
             for (i, column) in columns.iter().enumerate() {
                 let column_name = column.name().to_string();
+
+                // sqlx 会把 NULL 解码成空字符串，先单独判断
+                if row.try_get_raw(i).map_or(true, |value| value.is_null()) {
+                    row_map.insert(column_name, serde_json::Value::Null);
+                    continue;
+                }
                 
                 // 根据列类型获取值
                 let value = match column.type_info().name() {
@@ -257,9 +864,17 @@ impl SqliteDatabase {
                             .unwrap_or(serde_json::Value::Null)
                     }
                     _ => {
-                        // 尝试作为字符串获取
-                        let val: Option<String> = row.try_get(i).ok();
-                        val.map(serde_json::Value::String).unwrap_or(serde_json::Value::Null)
+                        // 表达式列（如聚合）没有声明类型，依次尝试字符串、整数、浮点数
+                        if let Ok(val) = row.try_get::<String, _>(i) {
+                            serde_json::Value::String(val)
+                        } else if let Ok(val) = row.try_get::<i64, _>(i) {
+                            serde_json::Value::Number(serde_json::Number::from(val))
+                        } else {
+                            row.try_get::<f64, _>(i).ok()
+                                .and_then(serde_json::Number::from_f64)
+                                .map(serde_json::Value::Number)
+                                .unwrap_or(serde_json::Value::Null)
+                        }
                     }
                 };
                 
@@ -278,31 +893,19 @@ impl SqliteDatabase {
 
     /// Execute a modify query (INSERT/UPDATE/DELETE)
     async fn execute_modify_query(&self, query: &str, params: &[serde_json::Value]) -> StepflowResult<QueryResult> {
-        let mut query_builder = sqlx::query(query);
-        
-        // 绑定参数 - 直接使用值而不是序列化
-        for param in params {
-            match param {
-                serde_json::Value::String(s) => query_builder = query_builder.bind(s),
-                serde_json::Value::Number(n) => {
-                    if let Some(i) = n.as_i64() {
-                        query_builder = query_builder.bind(i);
-                    } else if let Some(f) = n.as_f64() {
-                        query_builder = query_builder.bind(f);
-                    } else {
-                        query_builder = query_builder.bind(n.to_string());
-                    }
-                }
-                serde_json::Value::Bool(b) => query_builder = query_builder.bind(b),
-                serde_json::Value::Null => query_builder = query_builder.bind(None::<String>),
-                _ => query_builder = query_builder.bind(serde_json::to_string(param)?),
-            }
-        }
+        let query_builder = bind_params(sqlx::query(query), params)?;
 
-        let result = query_builder.execute(&self.pool).await
-            .map_err(|e| StepflowError::DatabaseError(stepflow_core::DatabaseError::QueryFailed(
-                format!("Query execution failed: {}", e)
-            )))?;
+        let mut connection = self.acquire_writer().await?;
+        let cached_before = connection.cached_statements_size();
+        let result = query_builder.execute(&mut *connection).await
+            .map_err(|e| {
+                self.track_lock_error(&e);
+                StepflowError::DatabaseError(stepflow_core::DatabaseError::QueryFailed(
+                    format!("Query execution failed: {}", e)
+                ))
+            })?;
+        self.statement_cache.record(cached_before, connection.cached_statements_size());
+        self.advance_write_position(&mut connection).await;
 
         Ok(QueryResult {
             rows_affected: result.rows_affected(),
@@ -311,13 +914,109 @@ impl SqliteDatabase {
         })
     }
 
+    /// Insert many rows with multi-row `INSERT` statements inside one transaction
+    ///
+    /// `insert_prefix` is everything up to `VALUES`, e.g. `INSERT INTO logs (a, b)`.
+    /// Rows are written in chunks of at most `chunk_size` rows (fewer when needed to
+    /// stay under SQLite's bind parameter limit). A chunk that fails is rolled back to
+    /// its savepoint and retried row by row, so one bad row doesn't sink the batch;
+    /// failed rows are reported by index.
+    pub async fn insert_batch(
+        &self,
+        insert_prefix: &str,
+        rows: &[Vec<serde_json::Value>],
+        chunk_size: usize,
+    ) -> StepflowResult<BatchInsertReport> {
+        self.insert_batch_on_conflict(insert_prefix, "", rows, chunk_size).await
+    }
+
+    /// [`insert_batch`](Self::insert_batch) with an upsert clause after the values,
+    /// e.g. `ON CONFLICT(id) DO UPDATE SET name = excluded.name`
+    ///
+    /// Only rows actually written count as inserted; a `DO UPDATE ... WHERE` that
+    /// doesn't match leaves the existing row alone without reporting a failure.
+    pub async fn insert_batch_on_conflict(
+        &self,
+        insert_prefix: &str,
+        conflict_clause: &str,
+        rows: &[Vec<serde_json::Value>],
+        chunk_size: usize,
+    ) -> StepflowResult<BatchInsertReport> {
+        let mut report = BatchInsertReport::default();
+        let Some(columns) = rows.first().map(Vec::len).filter(|columns| *columns > 0) else {
+            return Ok(report);
+        };
+        if let Some(index) = rows.iter().position(|row| row.len() != columns) {
+            return Err(StepflowError::DatabaseError(stepflow_core::DatabaseError::QueryFailed(
+                format!("Row {} has {} values, expected {}", index, rows[index].len(), columns)
+            )));
+        }
+
+        let rows_per_chunk = chunk_size.clamp(1, (MAX_BIND_PARAMS / columns).max(1));
+        let row_placeholder = format!("({})", vec!["?"; columns].join(", "));
+        let transaction_failed = |e: sqlx::Error| StepflowError::DatabaseError(stepflow_core::DatabaseError::TransactionFailed(
+            format!("Batch insert failed: {}", e)
+        ));
+
+        let mut connection = self.acquire_writer().await?;
+        let mut transaction = sqlx::Connection::begin(&mut *connection).await.map_err(transaction_failed)?;
+
+        for (chunk_index, chunk) in rows.chunks(rows_per_chunk).enumerate() {
+            let offset = chunk_index * rows_per_chunk;
+            let sql = format!(
+                "{} VALUES {} {}",
+                insert_prefix,
+                vec![row_placeholder.as_str(); chunk.len()].join(", "),
+                conflict_clause,
+            );
+            let params: Vec<serde_json::Value> = chunk.iter().flatten().cloned().collect();
+
+            sqlx::query("SAVEPOINT batch_chunk").execute(&mut *transaction).await.map_err(transaction_failed)?;
+            match bind_params(sqlx::query(&sql), &params)?.execute(&mut *transaction).await {
+                Ok(result) => {
+                    report.inserted += result.rows_affected() as usize;
+                    sqlx::query("RELEASE batch_chunk").execute(&mut *transaction).await.map_err(transaction_failed)?;
+                    continue;
+                }
+                Err(e) => {
+                    self.track_lock_error(&e);
+                    debug!("Batch chunk at row {} failed, retrying row by row: {}", offset, e);
+                    sqlx::query("ROLLBACK TO batch_chunk").execute(&mut *transaction).await.map_err(transaction_failed)?;
+                }
+            }
+
+            // Find the offending rows; each row gets its own savepoint
+            let single_sql = format!("{} VALUES {} {}", insert_prefix, row_placeholder, conflict_clause);
+            for (row_index, row) in chunk.iter().enumerate() {
+                sqlx::query("SAVEPOINT batch_row").execute(&mut *transaction).await.map_err(transaction_failed)?;
+                match bind_params(sqlx::query(&single_sql), row)?.execute(&mut *transaction).await {
+                    Ok(result) => {
+                        report.inserted += result.rows_affected() as usize;
+                        sqlx::query("RELEASE batch_row").execute(&mut *transaction).await.map_err(transaction_failed)?;
+                    }
+                    Err(e) => {
+                        sqlx::query("ROLLBACK TO batch_row").execute(&mut *transaction).await.map_err(transaction_failed)?;
+                        sqlx::query("RELEASE batch_row").execute(&mut *transaction).await.map_err(transaction_failed)?;
+                        report.failures.push(BatchFailure { index: offset + row_index, error: e.to_string() });
+                    }
+                }
+            }
+            sqlx::query("RELEASE batch_chunk").execute(&mut *transaction).await.map_err(transaction_failed)?;
+        }
+
+        transaction.commit().await.map_err(transaction_failed)?;
+        self.advance_write_position(&mut connection).await;
+        Ok(report)
+    }
+
     /// Execute a transaction with a callback
     pub async fn execute_transaction<F, Fut>(&self, callback: F) -> StepflowResult<()>
     where
         F: FnOnce(&mut sqlx::Transaction<'_, Sqlite>) -> Fut + Send,
         Fut: std::future::Future<Output = StepflowResult<()>> + Send,
     {
-        let mut transaction = self.pool.begin().await
+        let mut connection = self.acquire_writer().await?;
+        let mut transaction = sqlx::Connection::begin(&mut *connection).await
             .map_err(|e| StepflowError::DatabaseError(stepflow_core::DatabaseError::TransactionFailed(
                 format!("Failed to begin transaction: {}", e)
             )))?;
@@ -328,6 +1027,7 @@ impl SqliteDatabase {
             .map_err(|e| StepflowError::DatabaseError(stepflow_core::DatabaseError::TransactionFailed(
                 format!("Failed to commit transaction: {}", e)
             )))?;
+        self.advance_write_position(&mut connection).await;
         
         Ok(result)
     }
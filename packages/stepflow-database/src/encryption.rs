@@ -0,0 +1,452 @@
+//! Envelope encryption of sensitive tenant data
+//!
+//! Every tenant has a data key that encrypts its secrets and credentials. Data
+//! keys are stored wrapped by a master key, which is supplied by configuration
+//! and never written to the database. Rotating the master key re-wraps the data
+//! keys without touching the data they encrypt; rotating a tenant's data key
+//! retires the old version, which keeps decrypting values written with it.
+//!
+//! Encrypted values are stored as `enc:v1:<key version>:<base64(nonce || ciphertext)>`.
+//! Values without that prefix are plaintext written before encryption was
+//! enabled and are returned unchanged.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use rand_core::{OsRng, RngCore};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use stepflow_core::{Database, StepflowError, StepflowResult, TenantId};
+use tracing::info;
+
+use crate::utils::param;
+use crate::SqliteDatabase;
+
+/// Prefix of values encrypted with a tenant data key
+pub const ENVELOPE_PREFIX: &str = "enc:v1:";
+
+const NONCE_LEN: usize = 12;
+
+fn encryption_error(message: impl Into<String>) -> StepflowError {
+    StepflowError::SecurityViolation(message.into())
+}
+
+/// Seal `plaintext` as nonce || ciphertext, authenticating `aad`
+fn seal(cipher: &Aes256Gcm, plaintext: &[u8], aad: &[u8]) -> StepflowResult<Vec<u8>> {
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+    let mut sealed = cipher
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg: plaintext, aad })
+        .map_err(|_| encryption_error("Encryption failed"))?;
+    sealed.splice(0..0, nonce);
+    Ok(sealed)
+}
+
+fn open(cipher: &Aes256Gcm, sealed: &[u8], aad: &[u8]) -> StepflowResult<Vec<u8>> {
+    if sealed.len() < NONCE_LEN {
+        return Err(encryption_error("Encrypted value is truncated"));
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    cipher
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad })
+        .map_err(|_| encryption_error("Decryption failed: wrong key or corrupted value"))
+}
+
+fn decode_base64(encoded: &str) -> StepflowResult<Vec<u8>> {
+    base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|e| encryption_error(format!("Invalid encrypted value: {}", e)))
+}
+
+/// A 256-bit key-encryption key
+pub struct MasterKey {
+    id: String,
+    cipher: Aes256Gcm,
+}
+
+impl MasterKey {
+    /// Use 32 raw bytes as a master key
+    ///
+    /// The key ID is derived from the key, so the same key always has the same ID.
+    pub fn from_bytes(bytes: &[u8]) -> StepflowResult<Self> {
+        let cipher = Aes256Gcm::new_from_slice(bytes)
+            .map_err(|_| StepflowError::ConfigurationError("Master key must be 32 bytes".to_string()))?;
+        let digest = Sha256::digest(bytes);
+        let id = digest[..8].iter().map(|b| format!("{:02x}", b)).collect();
+        Ok(Self { id, cipher })
+    }
+
+    /// Decode a base64 encoded 32-byte master key
+    pub fn from_base64(encoded: &str) -> StepflowResult<Self> {
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(encoded.trim())
+            .map_err(|e| StepflowError::ConfigurationError(format!("Invalid master key: {}", e)))?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Generate a random master key, returned base64 encoded
+    pub fn generate() -> String {
+        let mut bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut bytes);
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl std::fmt::Debug for MasterKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MasterKey").field("id", &self.id).finish_non_exhaustive()
+    }
+}
+
+/// Master keys known to the process
+///
+/// The current key wraps new data keys and re-wrapped ones; previous keys are
+/// only used to unwrap data keys that have not been re-wrapped yet.
+pub struct MasterKeyring {
+    current: MasterKey,
+    previous: Vec<MasterKey>,
+    /// Unwrapped data keys by tenant and version
+    data_keys: Mutex<HashMap<(String, u32), Arc<Aes256Gcm>>>,
+}
+
+impl std::fmt::Debug for MasterKeyring {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MasterKeyring")
+            .field("current", &self.current)
+            .field("previous", &self.previous)
+            .finish_non_exhaustive()
+    }
+}
+
+impl MasterKeyring {
+    pub fn new(current: MasterKey) -> Self {
+        Self {
+            current,
+            previous: Vec::new(),
+            data_keys: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Keep a retired master key for unwrapping until its data keys are re-wrapped
+    pub fn with_previous(mut self, key: MasterKey) -> Self {
+        if key.id != self.current.id {
+            self.previous.push(key);
+        }
+        self
+    }
+
+    pub fn current_key_id(&self) -> &str {
+        &self.current.id
+    }
+
+    pub fn previous_key_ids(&self) -> Vec<String> {
+        self.previous.iter().map(|key| key.id.clone()).collect()
+    }
+
+    fn key(&self, id: &str) -> Option<&MasterKey> {
+        std::iter::once(&self.current).chain(&self.previous).find(|key| key.id == id)
+    }
+}
+
+/// Lifecycle state of a tenant data key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DataKeyStatus {
+    /// Encrypts new values
+    Active,
+    /// Only decrypts values written while it was active
+    Retired,
+}
+
+impl DataKeyStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DataKeyStatus::Active => "active",
+            DataKeyStatus::Retired => "retired",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "active" => Some(DataKeyStatus::Active),
+            "retired" => Some(DataKeyStatus::Retired),
+            _ => None,
+        }
+    }
+}
+
+/// Metadata of a tenant data key; the key material is never exposed
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TenantDataKeyInfo {
+    pub tenant_id: TenantId,
+    pub version: u32,
+    pub status: DataKeyStatus,
+    /// Master key the data key is currently wrapped with
+    pub master_key_id: String,
+    pub created_at: DateTime<Utc>,
+    /// Last time the key was re-wrapped with a new master key
+    pub rewrapped_at: Option<DateTime<Utc>>,
+    pub retired_at: Option<DateTime<Utc>>,
+}
+
+/// Master key rotation status
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct EncryptionStatus {
+    /// Whether a master key is configured; without one values are stored in plaintext
+    pub enabled: bool,
+    pub master_key_id: Option<String>,
+    pub previous_master_key_ids: Vec<String>,
+    pub data_keys: u64,
+    /// Data keys still wrapped with a master key other than the current one
+    pub pending_rewrap: u64,
+}
+
+/// Outcome of re-wrapping data keys with the current master key
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct RewrapReport {
+    pub rewrapped: u64,
+    /// Keys wrapped with a master key that is not configured, as `tenant/version`
+    pub unknown_master_key: Vec<String>,
+}
+
+/// Helper function to convert database row to TenantDataKeyInfo
+fn row_to_data_key_info(row: &HashMap<String, Value>) -> Option<TenantDataKeyInfo> {
+    let timestamp = |key: &str| row.get(key).and_then(|v| v.as_str()).and_then(|s| s.parse().ok());
+    Some(TenantDataKeyInfo {
+        tenant_id: TenantId::from_string(row.get("tenant_id")?.as_str()?.to_string()),
+        version: row.get("version")?.as_u64()? as u32,
+        status: DataKeyStatus::parse(row.get("status")?.as_str()?)?,
+        master_key_id: row.get("master_key_id")?.as_str()?.to_string(),
+        created_at: timestamp("created_at")?,
+        rewrapped_at: timestamp("rewrapped_at"),
+        retired_at: timestamp("retired_at"),
+    })
+}
+
+/// Associated data binding a wrapped data key to its row
+fn wrap_aad(tenant_id: &str, version: u32) -> Vec<u8> {
+    format!("{}:{}", tenant_id, version).into_bytes()
+}
+
+/// Tenant data keys and the values encrypted with them
+///
+/// Uses the master keyring attached with [`SqliteDatabase::with_encryption`].
+/// Without one, [`encrypt`](Self::encrypt) stores values unchanged.
+pub struct TenantKeyRepository {
+    database: SqliteDatabase,
+}
+
+impl TenantKeyRepository {
+    /// Create a new tenant key repository
+    pub fn new(database: SqliteDatabase) -> Self {
+        Self { database }
+    }
+
+    fn keyring(&self) -> StepflowResult<&Arc<MasterKeyring>> {
+        self.database
+            .keyring()
+            .ok_or_else(|| StepflowError::ConfigurationError("No master encryption key is configured".to_string()))
+    }
+
+    /// Encrypt a value with the tenant's active data key, creating it on first use
+    pub async fn encrypt(&self, tenant_id: &TenantId, plaintext: &str) -> StepflowResult<String> {
+        if self.database.keyring().is_none() {
+            return Ok(plaintext.to_string());
+        }
+        let version = self.active_version(tenant_id).await?;
+        let cipher = self.data_key(tenant_id, version).await?;
+        let sealed = seal(&cipher, plaintext.as_bytes(), tenant_id.as_str().as_bytes())?;
+        Ok(format!(
+            "{}{}:{}",
+            ENVELOPE_PREFIX,
+            version,
+            base64::engine::general_purpose::STANDARD.encode(sealed)
+        ))
+    }
+
+    /// Decrypt a value written by [`encrypt`](Self::encrypt); plaintext values are returned as is
+    pub async fn decrypt(&self, tenant_id: &TenantId, value: &str) -> StepflowResult<String> {
+        let Some(envelope) = value.strip_prefix(ENVELOPE_PREFIX) else {
+            return Ok(value.to_string());
+        };
+        let (version, encoded) = envelope
+            .split_once(':')
+            .and_then(|(version, encoded)| Some((version.parse::<u32>().ok()?, encoded)))
+            .ok_or_else(|| encryption_error("Malformed encrypted value"))?;
+        let cipher = self.data_key(tenant_id, version).await?;
+        let plaintext = open(&cipher, &decode_base64(encoded)?, tenant_id.as_str().as_bytes())?;
+        String::from_utf8(plaintext).map_err(|_| encryption_error("Decrypted value is not UTF-8"))
+    }
+
+    /// Version of the tenant's active data key, creating the first one if needed
+    async fn active_version(&self, tenant_id: &TenantId) -> StepflowResult<u32> {
+        if let Some(key) = self.active_key(tenant_id).await? {
+            return Ok(key.version);
+        }
+        // Concurrent callers race on the unique active index; the loser reads the winner's key
+        self.insert_key(tenant_id).await?;
+        self.active_key(tenant_id)
+            .await?
+            .map(|key| key.version)
+            .ok_or_else(|| StepflowError::InternalError("Failed to create tenant data key".to_string()))
+    }
+
+    async fn active_key(&self, tenant_id: &TenantId) -> StepflowResult<Option<TenantDataKeyInfo>> {
+        let result = self.database.execute(
+            "SELECT * FROM tenant_data_keys WHERE tenant_id = ? AND status = 'active'",
+            &[param::text(tenant_id.as_str())],
+        ).await?;
+        Ok(result.rows.first().and_then(row_to_data_key_info))
+    }
+
+    /// Generate a data key as the tenant's next version, unless one is already active
+    async fn insert_key(&self, tenant_id: &TenantId) -> StepflowResult<()> {
+        let keyring = self.keyring()?;
+        // Computed columns come back untyped, so read the latest version itself
+        let latest = self.database.execute(
+            "SELECT version FROM tenant_data_keys WHERE tenant_id = ? ORDER BY version DESC LIMIT 1",
+            &[param::text(tenant_id.as_str())],
+        ).await?;
+        let version = latest.rows.first().and_then(|row| row.get("version")?.as_u64()).unwrap_or(0) as u32 + 1;
+
+        let mut key = [0u8; 32];
+        OsRng.fill_bytes(&mut key);
+        let wrapped = seal(&keyring.current.cipher, &key, &wrap_aad(tenant_id.as_str(), version))?;
+        self.database.execute(
+            r#"
+                INSERT OR IGNORE INTO tenant_data_keys (tenant_id, version, wrapped_key, master_key_id, status, created_at)
+                VALUES (?, ?, ?, ?, 'active', ?)
+            "#,
+            &[
+                param::text(tenant_id.as_str()),
+                param::int(version),
+                param::text(base64::engine::general_purpose::STANDARD.encode(wrapped)),
+                param::text(keyring.current_key_id()),
+                param::timestamp(&Utc::now()),
+            ],
+        ).await?;
+        Ok(())
+    }
+
+    /// Unwrapped data key of a tenant version, cached after the first use
+    async fn data_key(&self, tenant_id: &TenantId, version: u32) -> StepflowResult<Arc<Aes256Gcm>> {
+        let keyring = self.keyring()?;
+        let cache_key = (tenant_id.as_str().to_string(), version);
+        if let Some(cipher) = keyring.data_keys.lock().unwrap().get(&cache_key) {
+            return Ok(cipher.clone());
+        }
+
+        let result = self.database.execute(
+            "SELECT wrapped_key, master_key_id FROM tenant_data_keys WHERE tenant_id = ? AND version = ?",
+            &[param::text(tenant_id.as_str()), param::int(version)],
+        ).await?;
+        let row = result.rows.first().ok_or_else(|| {
+            encryption_error(format!("Data key {} of tenant {} not found", version, tenant_id.as_str()))
+        })?;
+        let text = |key: &str| row.get(key).and_then(|v| v.as_str()).unwrap_or_default();
+        let master = keyring.key(text("master_key_id")).ok_or_else(|| {
+            encryption_error(format!("Master key {} is not configured", text("master_key_id")))
+        })?;
+        let key = open(&master.cipher, &decode_base64(text("wrapped_key"))?, &wrap_aad(tenant_id.as_str(), version))?;
+        let cipher = Arc::new(
+            Aes256Gcm::new_from_slice(&key).map_err(|_| encryption_error("Invalid data key length"))?,
+        );
+        keyring.data_keys.lock().unwrap().insert(cache_key, cipher.clone());
+        Ok(cipher)
+    }
+
+    /// Retire the tenant's active data key and create a new one
+    ///
+    /// Values encrypted with the retired key stay readable.
+    pub async fn rotate(&self, tenant_id: &TenantId) -> StepflowResult<TenantDataKeyInfo> {
+        self.keyring()?;
+        self.database.execute(
+            "UPDATE tenant_data_keys SET status = 'retired', retired_at = ? WHERE tenant_id = ? AND status = 'active'",
+            &[param::timestamp(&Utc::now()), param::text(tenant_id.as_str())],
+        ).await?;
+        self.insert_key(tenant_id).await?;
+        let key = self.active_key(tenant_id)
+            .await?
+            .ok_or_else(|| StepflowError::InternalError("Failed to create tenant data key".to_string()))?;
+        info!("Rotated data key of tenant {} to version {}", tenant_id.as_str(), key.version);
+        Ok(key)
+    }
+
+    /// Re-wrap every data key not wrapped with the current master key
+    ///
+    /// Only the wrapped keys change; data encrypted with them is left as is.
+    pub async fn rewrap_all(&self) -> StepflowResult<RewrapReport> {
+        let keyring = self.keyring()?;
+        let result = self.database.execute(
+            "SELECT * FROM tenant_data_keys WHERE master_key_id != ?",
+            &[param::text(keyring.current_key_id())],
+        ).await?;
+
+        let mut report = RewrapReport::default();
+        for row in &result.rows {
+            let Some(info) = row_to_data_key_info(row) else {
+                continue;
+            };
+            let aad = wrap_aad(info.tenant_id.as_str(), info.version);
+            let Some(master) = keyring.key(&info.master_key_id) else {
+                report.unknown_master_key.push(format!("{}/{}", info.tenant_id.as_str(), info.version));
+                continue;
+            };
+            let wrapped = row.get("wrapped_key").and_then(|v| v.as_str()).unwrap_or_default();
+            let key = open(&master.cipher, &decode_base64(wrapped)?, &aad)?;
+            let rewrapped = seal(&keyring.current.cipher, &key, &aad)?;
+            self.database.execute(
+                r#"
+                    UPDATE tenant_data_keys SET wrapped_key = ?, master_key_id = ?, rewrapped_at = ?
+                    WHERE tenant_id = ? AND version = ? AND master_key_id = ?
+                "#,
+                &[
+                    param::text(base64::engine::general_purpose::STANDARD.encode(rewrapped)),
+                    param::text(keyring.current_key_id()),
+                    param::timestamp(&Utc::now()),
+                    param::text(info.tenant_id.as_str()),
+                    param::int(info.version),
+                    param::text(&info.master_key_id),
+                ],
+            ).await?;
+            report.rewrapped += 1;
+        }
+        info!("Re-wrapped {} data keys with master key {}", report.rewrapped, keyring.current_key_id());
+        Ok(report)
+    }
+
+    /// Data keys of a tenant, newest first
+    pub async fn list_keys(&self, tenant_id: &TenantId) -> StepflowResult<Vec<TenantDataKeyInfo>> {
+        let result = self.database.execute(
+            "SELECT * FROM tenant_data_keys WHERE tenant_id = ? ORDER BY version DESC",
+            &[param::text(tenant_id.as_str())],
+        ).await?;
+        Ok(result.rows.iter().filter_map(row_to_data_key_info).collect())
+    }
+
+    /// Master key in use and how many data keys still need re-wrapping
+    pub async fn status(&self) -> StepflowResult<EncryptionStatus> {
+        let keyring = self.database.keyring();
+        let current = keyring.map(|keyring| keyring.current_key_id().to_string());
+        let result = self.database.execute("SELECT master_key_id FROM tenant_data_keys", &[]).await?;
+        let pending = result.rows
+            .iter()
+            .filter(|row| row.get("master_key_id").and_then(|v| v.as_str()) != current.as_deref())
+            .count();
+        Ok(EncryptionStatus {
+            enabled: keyring.is_some(),
+            master_key_id: current,
+            previous_master_key_ids: keyring.map(|keyring| keyring.previous_key_ids()).unwrap_or_default(),
+            data_keys: result.rows.len() as u64,
+            pending_rewrap: pending as u64,
+        })
+    }
+}
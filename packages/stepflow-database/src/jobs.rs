@@ -0,0 +1,423 @@
+//! Internal background jobs
+//!
+//! Periodic maintenance work (retention, rollups, retries, ...) is registered with a
+//! [`JobScheduler`] under a unique name and an interval, cron or calendar trigger. Calendar
+//! triggers evaluate cron in a timezone and skip weekends, holidays and blackout windows;
+//! [`JobScheduler::with_trigger_overrides`] replaces the built-in trigger of a job, e.g.
+//! from the `job_schedules` configuration. The schedule and
+//! the outcome of the last run live in the `background_jobs` table, so they survive
+//! restarts and are shared by every instance using the same database.
+//!
+//! Each run is guarded by a lease on the job's row: an instance only runs a job after
+//! atomically taking the lease, and renews it while the job is running. When several
+//! instances are running, each job therefore runs on one of them at a time; if that
+//! instance dies the lease expires and another instance picks the job up.
+//!
+//! With a [`LeaderElection`] attached, only the leader checks for due jobs, so schedules
+//! are not raced at all; manual runs from the admin API still work on any instance.
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use stepflow_core::{CalendarSchedule, CronSchedule, Database, StepflowError, StepflowResult};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::utils::param;
+use crate::{LeaderElection, SqliteDatabase};
+
+/// Work done by a background job
+#[async_trait]
+pub trait Job: Send + Sync {
+    /// Run the job once, returning a short summary for the run history
+    async fn run(&self) -> StepflowResult<String>;
+}
+
+/// [`Job`] backed by an async closure
+struct FnJob<F>(F);
+
+#[async_trait]
+impl<F, Fut> Job for FnJob<F>
+where
+    F: Fn() -> Fut + Send + Sync,
+    Fut: Future<Output = StepflowResult<String>> + Send,
+{
+    async fn run(&self) -> StepflowResult<String> {
+        (self.0)().await
+    }
+}
+
+/// When a job runs
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobTrigger {
+    /// Every `n` seconds, counted from the end of the previous run
+    IntervalSecs(u64),
+    Cron(CronSchedule),
+    /// Cron in a timezone, restricted to business days and outside blackout windows
+    Calendar(CalendarSchedule),
+}
+
+impl JobTrigger {
+    pub fn interval(interval: Duration) -> Self {
+        JobTrigger::IntervalSecs(interval.as_secs().max(1))
+    }
+
+    /// Next run after `after`; `None` when a cron expression never matches again
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            JobTrigger::IntervalSecs(secs) => Some(after + chrono::Duration::seconds(*secs as i64)),
+            JobTrigger::Cron(schedule) => schedule.next_after(after),
+            JobTrigger::Calendar(schedule) => schedule.next_after(after),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobRunStatus {
+    Succeeded,
+    Failed,
+}
+
+impl JobRunStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobRunStatus::Succeeded => "succeeded",
+            JobRunStatus::Failed => "failed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "succeeded" => Some(JobRunStatus::Succeeded),
+            "failed" => Some(JobRunStatus::Failed),
+            _ => None,
+        }
+    }
+}
+
+/// Outcome of one run of a job
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct JobRun {
+    pub job: String,
+    pub status: JobRunStatus,
+    /// Summary returned by the job, or the error
+    pub message: String,
+    /// Instance that ran the job
+    pub instance_id: String,
+    /// Started from the admin API rather than by the trigger
+    pub manual: bool,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+}
+
+/// A registered job and its persisted state
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct JobInfo {
+    pub name: String,
+    pub description: String,
+    pub trigger: JobTrigger,
+    pub next_run_at: Option<DateTime<Utc>>,
+    /// Instance currently holding the job's lease, if it is running
+    pub running_on: Option<String>,
+    pub last_run: Option<JobRun>,
+}
+
+#[derive(Debug, Clone)]
+pub struct JobSchedulerConfig {
+    /// Identifies this instance in leases and run history
+    pub instance_id: String,
+    /// How often due jobs are checked
+    pub poll_interval: Duration,
+    /// How long a lease is held without renewal; renewed every third of this while a job runs
+    pub lease_duration: Duration,
+}
+
+impl Default for JobSchedulerConfig {
+    fn default() -> Self {
+        Self {
+            instance_id: uuid::Uuid::new_v4().to_string(),
+            poll_interval: Duration::from_secs(5),
+            lease_duration: Duration::from_secs(60),
+        }
+    }
+}
+
+struct RegisteredJob {
+    description: String,
+    trigger: JobTrigger,
+    job: Arc<dyn Job>,
+}
+
+/// Runs registered jobs on their triggers; see the module documentation
+pub struct JobScheduler {
+    database: SqliteDatabase,
+    config: JobSchedulerConfig,
+    jobs: RwLock<BTreeMap<String, Arc<RegisteredJob>>>,
+    leader: Option<Arc<LeaderElection>>,
+    overrides: BTreeMap<String, JobTrigger>,
+}
+
+impl JobScheduler {
+    pub fn new(database: SqliteDatabase) -> Self {
+        Self::with_config(database, JobSchedulerConfig::default())
+    }
+
+    pub fn with_config(database: SqliteDatabase, config: JobSchedulerConfig) -> Self {
+        Self { database, config, jobs: RwLock::new(BTreeMap::new()), leader: None, overrides: BTreeMap::new() }
+    }
+
+    /// Use these triggers instead of the ones jobs are registered with, keyed by job name
+    pub fn with_trigger_overrides(mut self, overrides: BTreeMap<String, JobTrigger>) -> Self {
+        self.overrides = overrides;
+        self
+    }
+
+    /// Only run scheduled jobs while `leader` holds the leadership
+    pub fn with_leader_election(mut self, leader: Arc<LeaderElection>) -> Self {
+        self.leader = Some(leader);
+        self
+    }
+
+    pub fn instance_id(&self) -> &str {
+        &self.config.instance_id
+    }
+
+    /// Register a job; its first run is scheduled from now unless the database already
+    /// has a schedule for it from an earlier start
+    pub async fn register(
+        &self,
+        name: &str,
+        description: &str,
+        trigger: JobTrigger,
+        job: Arc<dyn Job>,
+    ) -> StepflowResult<()> {
+        let overridden = self.overrides.get(name).cloned();
+        let now = Utc::now();
+        // An override may have changed since the stored next run was computed, so it is
+        // always recomputed; built-in triggers keep their schedule across restarts
+        let sql = if overridden.is_some() {
+            r#"
+                INSERT INTO background_jobs (name, next_run_at, updated_at)
+                VALUES (?, ?, ?)
+                ON CONFLICT(name) DO UPDATE SET next_run_at = excluded.next_run_at, updated_at = excluded.updated_at
+            "#
+        } else {
+            r#"
+                INSERT OR IGNORE INTO background_jobs (name, next_run_at, updated_at)
+                VALUES (?, ?, ?)
+            "#
+        };
+        let trigger = overridden.unwrap_or(trigger);
+        let next_run_at = trigger.next_after(now);
+        let params = vec![param::text(name), optional_timestamp(next_run_at), param::timestamp(&now)];
+        self.database.execute(sql, &params).await?;
+
+        let registered = RegisteredJob { description: description.to_string(), trigger, job };
+        self.jobs.write().unwrap_or_else(|e| e.into_inner()).insert(name.to_string(), Arc::new(registered));
+        Ok(())
+    }
+
+    /// Register an async closure as a job
+    pub async fn register_fn<F, Fut>(&self, name: &str, description: &str, trigger: JobTrigger, job: F) -> StepflowResult<()>
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = StepflowResult<String>> + Send + 'static,
+    {
+        self.register(name, description, trigger, Arc::new(FnJob(job))).await
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.jobs.read().unwrap_or_else(|e| e.into_inner()).contains_key(name)
+    }
+
+    /// Registered jobs with their schedule and last run
+    pub async fn list(&self) -> StepflowResult<Vec<JobInfo>> {
+        let jobs = self.jobs.read().unwrap_or_else(|e| e.into_inner()).clone();
+        let result = self.database.execute("SELECT * FROM background_jobs", &[]).await?;
+        let rows: BTreeMap<String, &std::collections::HashMap<String, Value>> = result.rows.iter()
+            .filter_map(|row| Some((row.get("name")?.as_str()?.to_string(), row)))
+            .collect();
+        let now = Utc::now();
+
+        Ok(jobs.into_iter().map(|(name, job)| {
+            let row = rows.get(&name);
+            let text = |key: &str| row.and_then(|row| row.get(key)).and_then(|v| v.as_str()).map(str::to_string);
+            let time = |key: &str| text(key).and_then(|s| s.parse::<DateTime<Utc>>().ok());
+            let running_on = text("lease_owner").filter(|_| time("lease_until").is_some_and(|until| until > now));
+            let last_run = match (time("last_started_at"), time("last_finished_at")) {
+                (Some(started_at), Some(finished_at)) => Some(JobRun {
+                    job: name.clone(),
+                    status: text("last_status").and_then(|s| JobRunStatus::parse(&s)).unwrap_or(JobRunStatus::Failed),
+                    message: text("last_message").unwrap_or_default(),
+                    instance_id: text("last_instance").unwrap_or_default(),
+                    manual: row.and_then(|row| row.get("last_manual")).and_then(|v| v.as_i64()) == Some(1),
+                    started_at,
+                    finished_at,
+                }),
+                _ => None,
+            };
+            JobInfo {
+                name: name.clone(),
+                description: job.description.clone(),
+                trigger: job.trigger.clone(),
+                next_run_at: time("next_run_at"),
+                running_on,
+                last_run,
+            }
+        }).collect())
+    }
+
+    /// Run every job that is due and not running elsewhere
+    pub async fn run_due(&self) -> StepflowResult<Vec<JobRun>> {
+        let jobs = self.jobs.read().unwrap_or_else(|e| e.into_inner()).clone();
+        let mut runs = Vec::new();
+        for (name, job) in jobs {
+            if self.acquire(&name, true).await? {
+                runs.push(self.execute(&name, &job, false).await?);
+            }
+        }
+        Ok(runs)
+    }
+
+    /// Run a job now, outside its schedule; the next scheduled run is unchanged
+    ///
+    /// Fails with [`StepflowError::Conflict`] when the job is already running.
+    pub async fn trigger(&self, name: &str) -> StepflowResult<JobRun> {
+        let job = self.jobs.read().unwrap_or_else(|e| e.into_inner()).get(name).cloned()
+            .ok_or_else(|| StepflowError::ResourceNotAvailable(format!("Unknown background job '{}'", name)))?;
+        if !self.acquire(name, false).await? {
+            return Err(StepflowError::Conflict(format!("Background job '{}' is already running", name)));
+        }
+        self.execute(name, &job, true).await
+    }
+
+    /// Check for due jobs every `poll_interval` in the background
+    pub fn start(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(self.config.poll_interval);
+            loop {
+                ticker.tick().await;
+                if self.leader.as_ref().is_some_and(|leader| !leader.is_leader()) {
+                    continue;
+                }
+                if let Err(e) = self.run_due().await {
+                    warn!("Background job scheduling failed: {}", e);
+                }
+            }
+        })
+    }
+
+    /// Take the job's lease; scheduled runs also require the job to be due
+    async fn acquire(&self, name: &str, due_only: bool) -> StepflowResult<bool> {
+        let now = Utc::now();
+        let sql = format!(
+            "UPDATE background_jobs SET lease_owner = ?, lease_until = ?, updated_at = ? \
+             WHERE name = ? AND (lease_until IS NULL OR lease_until < ?){}",
+            if due_only { " AND next_run_at IS NOT NULL AND next_run_at <= ?" } else { "" },
+        );
+        let mut params = vec![
+            param::text(self.instance_id()),
+            param::timestamp(&self.lease_until(now)),
+            param::timestamp(&now),
+            param::text(name),
+            param::timestamp(&now),
+        ];
+        if due_only {
+            params.push(param::timestamp(&now));
+        }
+        Ok(self.database.execute(&sql, &params).await?.rows_affected == 1)
+    }
+
+    async fn renew(&self, name: &str) -> StepflowResult<()> {
+        let sql = "UPDATE background_jobs SET lease_until = ? WHERE name = ? AND lease_owner = ?";
+        let params = vec![
+            param::timestamp(&self.lease_until(Utc::now())),
+            param::text(name),
+            param::text(self.instance_id()),
+        ];
+        self.database.execute(sql, &params).await?;
+        Ok(())
+    }
+
+    fn lease_until(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        now + chrono::Duration::from_std(self.config.lease_duration).unwrap_or(chrono::Duration::seconds(60))
+    }
+
+    /// Run a job whose lease is held, renewing the lease until it finishes, then record
+    /// the outcome and release the lease
+    async fn execute(&self, name: &str, job: &RegisteredJob, manual: bool) -> StepflowResult<JobRun> {
+        let started_at = Utc::now();
+        let clock = Instant::now();
+        let mut renewal = tokio::time::interval((self.config.lease_duration / 3).max(Duration::from_millis(10)));
+        renewal.tick().await;
+        let result = {
+            let run = job.job.run();
+            tokio::pin!(run);
+            loop {
+                tokio::select! {
+                    result = &mut run => break result,
+                    _ = renewal.tick() => {
+                        if let Err(e) = self.renew(name).await {
+                            warn!("Failed to renew lease of background job {}: {}", name, e);
+                        }
+                    }
+                }
+            }
+        };
+        let finished_at = Utc::now();
+
+        let (status, message) = match result {
+            Ok(summary) => {
+                info!("Background job {} finished in {:?}: {}", name, clock.elapsed(), summary);
+                (JobRunStatus::Succeeded, summary)
+            }
+            Err(e) => {
+                warn!("Background job {} failed after {:?}: {}", name, clock.elapsed(), e);
+                (JobRunStatus::Failed, e.to_string())
+            }
+        };
+        let run = JobRun {
+            job: name.to_string(),
+            status,
+            message,
+            instance_id: self.instance_id().to_string(),
+            manual,
+            started_at,
+            finished_at,
+        };
+
+        let next_run = if manual { "" } else { ", next_run_at = ?" };
+        let sql = format!(
+            "UPDATE background_jobs SET lease_owner = NULL, lease_until = NULL, last_started_at = ?, \
+             last_finished_at = ?, last_status = ?, last_message = ?, last_instance = ?, last_manual = ?, \
+             updated_at = ?{} WHERE name = ?",
+            next_run,
+        );
+        let mut params = vec![
+            param::timestamp(&run.started_at),
+            param::timestamp(&run.finished_at),
+            param::text(run.status.as_str()),
+            param::text(run.message.as_str()),
+            param::text(run.instance_id.as_str()),
+            param::flag(manual),
+            param::timestamp(&finished_at),
+        ];
+        if !manual {
+            params.push(optional_timestamp(job.trigger.next_after(finished_at)));
+        }
+        params.push(param::text(name));
+        self.database.execute(&sql, &params).await?;
+        Ok(run)
+    }
+}
+
+fn optional_timestamp(value: Option<DateTime<Utc>>) -> Value {
+    value.map_or(Value::Null, |value| param::timestamp(&value))
+}
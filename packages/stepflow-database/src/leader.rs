@@ -0,0 +1,187 @@
+//! Leader election for multi-instance deployments
+//!
+//! Instances sharing a database campaign for a named lease in the `leader_leases`
+//! table. The holder renews the lease every `renew_interval`; when it stops renewing
+//! (crash, network partition, shutdown) another instance takes the lease over once it
+//! has expired, bumping the term. Work that must only happen once per deployment, such
+//! as the [`JobScheduler`](crate::JobScheduler) loop, checks [`LeaderElection::is_leader`]
+//! before running.
+//!
+//! An instance considers itself leader only until its own lease would expire, measured
+//! from the start of the last successful renewal, so a leader that cannot reach the
+//! database steps down before anyone else can take over. Expiry timestamps are compared
+//! against each instance's wall clock; keep clock skew well below the lease duration.
+
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use stepflow_core::{Database, StepflowResult};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::utils::param;
+use crate::SqliteDatabase;
+
+#[derive(Debug, Clone)]
+pub struct LeaderElectionConfig {
+    /// Name of the lease; instances campaigning for the same name elect one leader
+    pub name: String,
+    /// Identifies this instance as lease holder
+    pub instance_id: String,
+    /// How long the lease is valid without renewal
+    pub lease_duration: Duration,
+    /// How often the lease is renewed or, when not leader, campaigned for
+    pub renew_interval: Duration,
+}
+
+impl Default for LeaderElectionConfig {
+    fn default() -> Self {
+        Self {
+            name: "primary".to_string(),
+            instance_id: uuid::Uuid::new_v4().to_string(),
+            lease_duration: Duration::from_secs(15),
+            renew_interval: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Leadership as last observed by this instance
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LeadershipStatus {
+    pub instance_id: String,
+    pub is_leader: bool,
+    /// Current lease holder, if any instance holds the lease
+    pub leader: Option<String>,
+    /// Incremented every time the lease changes hands
+    pub term: Option<i64>,
+    pub lease_expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Default)]
+struct Observed {
+    /// Local deadline of our own lease; leader while in the future
+    leader_until: Option<Instant>,
+    holder: Option<String>,
+    term: Option<i64>,
+    expires_at: Option<DateTime<Utc>>,
+}
+
+/// Database lease based leader election; see the module documentation
+pub struct LeaderElection {
+    database: SqliteDatabase,
+    config: LeaderElectionConfig,
+    observed: RwLock<Observed>,
+}
+
+impl LeaderElection {
+    pub fn new(database: SqliteDatabase) -> Self {
+        Self::with_config(database, LeaderElectionConfig::default())
+    }
+
+    pub fn with_config(database: SqliteDatabase, config: LeaderElectionConfig) -> Self {
+        Self { database, config, observed: RwLock::new(Observed::default()) }
+    }
+
+    pub fn instance_id(&self) -> &str {
+        &self.config.instance_id
+    }
+
+    /// Whether this instance holds an unexpired lease
+    pub fn is_leader(&self) -> bool {
+        let observed = self.observed.read().unwrap_or_else(|e| e.into_inner());
+        observed.leader_until.is_some_and(|until| until > Instant::now())
+    }
+
+    /// Leadership as of the last campaign, without querying the database
+    pub fn status(&self) -> LeadershipStatus {
+        let is_leader = self.is_leader();
+        let observed = self.observed.read().unwrap_or_else(|e| e.into_inner());
+        LeadershipStatus {
+            instance_id: self.instance_id().to_string(),
+            is_leader,
+            leader: observed.holder.clone(),
+            term: observed.term,
+            lease_expires_at: observed.expires_at,
+        }
+    }
+
+    /// Take the lease if it is free or expired, or renew it if already held
+    ///
+    /// Returns whether this instance is leader afterwards. On error the previous
+    /// leadership stands until its local deadline passes.
+    pub async fn campaign(&self) -> StepflowResult<bool> {
+        let started = Instant::now();
+        let now = Utc::now();
+        let expires_at = now + chrono::Duration::from_std(self.config.lease_duration).unwrap_or(chrono::Duration::seconds(15));
+        // SQLite evaluates every SET expression against the old row, so `holder` in the
+        // CASEs is the previous holder
+        let sql = r#"
+            INSERT INTO leader_leases (name, holder, term, acquired_at, expires_at)
+            VALUES (?, ?, 1, ?, ?)
+            ON CONFLICT(name) DO UPDATE SET
+                term = CASE WHEN leader_leases.holder = excluded.holder THEN leader_leases.term ELSE leader_leases.term + 1 END,
+                acquired_at = CASE WHEN leader_leases.holder = excluded.holder THEN leader_leases.acquired_at ELSE excluded.acquired_at END,
+                holder = excluded.holder,
+                expires_at = excluded.expires_at
+            WHERE leader_leases.holder = excluded.holder OR leader_leases.expires_at < excluded.acquired_at
+        "#;
+        let params = vec![
+            param::text(self.config.name.as_str()),
+            param::text(self.instance_id()),
+            param::timestamp(&now),
+            param::timestamp(&expires_at),
+        ];
+        let elected = self.database.execute(sql, &params).await?.rows_affected == 1;
+
+        let result = self.database.execute(
+            "SELECT holder, term, expires_at FROM leader_leases WHERE name = ?",
+            &[param::text(self.config.name.as_str())],
+        ).await?;
+        let row = result.rows.first();
+        let text = |key: &str| row.and_then(|row| row.get(key)).and_then(|v| v.as_str()).map(str::to_string);
+
+        let mut observed = self.observed.write().unwrap_or_else(|e| e.into_inner());
+        observed.leader_until = elected.then(|| started + self.config.lease_duration);
+        observed.expires_at = text("expires_at").and_then(|s| s.parse().ok());
+        // An expired lease has no holder, whoever wrote it last
+        observed.holder = text("holder").filter(|_| observed.expires_at.is_some_and(|at| at >= now));
+        observed.term = row.and_then(|row| row.get("term")).and_then(|v| v.as_i64());
+        Ok(elected)
+    }
+
+    /// Give up the lease so another instance can take over without waiting for it to expire
+    pub async fn resign(&self) -> StepflowResult<()> {
+        let sql = "UPDATE leader_leases SET expires_at = ? WHERE name = ? AND holder = ?";
+        let params = vec![
+            param::timestamp(&(Utc::now() - chrono::Duration::seconds(1))),
+            param::text(self.config.name.as_str()),
+            param::text(self.instance_id()),
+        ];
+        self.database.execute(sql, &params).await?;
+        *self.observed.write().unwrap_or_else(|e| e.into_inner()) = Observed::default();
+        Ok(())
+    }
+
+    /// Campaign every `renew_interval` in the background, logging leadership changes
+    pub fn start(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(self.config.renew_interval);
+            let mut was_leader = false;
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.campaign().await {
+                    warn!("Leader election for {} failed: {}", self.config.name, e);
+                }
+                let is_leader = self.is_leader();
+                match (was_leader, is_leader) {
+                    (false, true) => info!("Instance {} became leader of {}", self.instance_id(), self.config.name),
+                    (true, false) => warn!("Instance {} lost leadership of {}", self.instance_id(), self.config.name),
+                    _ => {}
+                }
+                was_leader = is_leader;
+            }
+        })
+    }
+}
@@ -4,15 +4,27 @@
 //! for the Stepflow Tool System.
 
 pub mod connection;
+pub mod encryption;
+pub mod jobs;
+pub mod leader;
 pub mod migrations;
 pub mod repositories;
 pub mod models;
+pub mod online;
+pub mod seed;
+pub mod slow_log;
 pub mod utils;
 
 pub use connection::*;
+pub use encryption::*;
+pub use jobs::*;
+pub use leader::*;
 pub use migrations::*;
 pub use repositories::*;
 pub use models::*; 
+pub use online::*;
+pub use seed::*;
+pub use slow_log::*;
 
 #[cfg(test)]
 mod tests {
@@ -36,29 +48,37 @@ mod tests {
         assert!(database.health_check().await.unwrap());
     }
 
+    #[tokio::test]
+    async fn test_null_and_empty_text_are_distinct() {
+        let database = create_test_database().await.unwrap();
+        let result = database.execute("SELECT NULL AS missing, '' AS empty, 'x' AS present", &[]).await.unwrap();
+        let row = &result.rows[0];
+
+        assert_eq!(row["missing"], serde_json::Value::Null);
+        assert_eq!(row["empty"], serde_json::json!(""));
+        assert_eq!(row["present"], serde_json::json!("x"));
+    }
+
     #[tokio::test]
     async fn test_tool_repository() {
         let database = create_test_database().await.unwrap();
         let tool_repo = ToolRepository::new(database);
 
         // 创建测试工具
-        let tool_info = ToolInfo {
-            id: ToolId::new(),
-            name: "test-tool".to_string(),
-            description: "Test tool description".to_string(),
-            version: ToolVersion::new(1, 0, 0),
-            tool_type: ToolType::Custom("test".to_string()),
-            status: ToolStatus::Active,
-            author: "test-author".to_string(),
-            repository: Some("https://github.com/test/test".to_string()),
-            documentation: None,
-            tags: vec!["test".to_string()],
-            capabilities: vec!["test-capability".to_string()],
-            configuration_schema: None,
-            examples: vec![],
-            created_at: chrono::Utc::now(),
-            updated_at: chrono::Utc::now(),
-        };
+        let tool_info = ToolInfo::builder()
+            .name("test-tool")
+            .description("Test tool description")
+            .tool_type(ToolType::Custom("test".to_string()))
+            .author("test-author")
+            .repository("https://github.com/test/test".to_string())
+            .tags(["test"])
+            .capabilities(["test-capability"])
+            .localization("zh", ToolLocalization {
+                name: Some("测试工具".to_string()),
+                description: None,
+            })
+            .build()
+            .unwrap();
 
         // 测试创建工具
         tool_repo.create_tool(&tool_info).await.unwrap();
@@ -73,6 +93,10 @@ mod tests {
         let retrieved_tool = retrieved_tool.unwrap();
         assert_eq!(retrieved_tool.name, tool_info.name);
         assert_eq!(retrieved_tool.description, tool_info.description);
+        assert_eq!(retrieved_tool.repository, tool_info.repository);
+        assert_eq!(retrieved_tool.documentation, None);
+        assert_eq!(retrieved_tool.version.pre_release, None);
+        assert_eq!(retrieved_tool.localizations, tool_info.localizations);
 
         // 测试列出工具
         let tools = tool_repo.list_tools(None).await.unwrap();
@@ -81,6 +105,24 @@ mod tests {
         // 测试搜索工具
         let search_results = tool_repo.search_tools("test").await.unwrap();
         assert_eq!(search_results.len(), 1);
+
+        // 测试环境标签
+        let mut staged = retrieved_tool;
+        staged.environment_label = Some(EnvironmentLabel::Staging);
+        tool_repo.update_tool(&staged.id, &staged).await.unwrap();
+        let retrieved_tool = tool_repo.get_tool(&tool_info.id).await.unwrap().unwrap();
+        assert_eq!(retrieved_tool.environment_label, Some(EnvironmentLabel::Staging));
+
+        // 测试 worker 能力要求
+        let requirements = ToolRequirements {
+            capabilities: vec!["gpu".to_string()],
+            preferred: vec!["region=eu".to_string()],
+        };
+        let mut gpu_tool = retrieved_tool;
+        gpu_tool.requirements = requirements.clone();
+        tool_repo.update_tool(&gpu_tool.id, &gpu_tool).await.unwrap();
+        let retrieved_tool = tool_repo.get_tool(&tool_info.id).await.unwrap().unwrap();
+        assert_eq!(retrieved_tool.requirements, requirements);
     }
 
     #[tokio::test]
@@ -102,6 +144,11 @@ mod tests {
         };
         tenant_repo.create_tenant(&tenant_info).await.unwrap();
 
+        // 按域名查找租户
+        let by_domain = tenant_repo.get_tenant_by_domain("Test.Example.com").await.unwrap();
+        assert_eq!(by_domain.map(|t| t.id), Some(tenant_id.clone()));
+        assert!(tenant_repo.get_tenant_by_domain("other.example.com").await.unwrap().is_none());
+
         // 创建测试用户
         let user_info = UserInfo {
             id: UserId::new(),
@@ -110,6 +157,7 @@ mod tests {
             role: UserRole::User,
             tenant_id: tenant_id.clone(),
             settings: HashMap::new(),
+            email_verified: false,
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
         };
@@ -137,6 +185,489 @@ mod tests {
         assert!(user_by_username.is_some());
     }
 
+    #[tokio::test]
+    async fn test_favorite_repository() {
+        let database = create_test_database().await.unwrap();
+        let tool_repo = ToolRepository::new(database.clone());
+        let favorite_repo = FavoriteRepository::new(database);
+
+        let tool_info = ToolInfo::builder()
+            .name("favorite-tool")
+            .description("Tool used for favorites")
+            .tool_type(ToolType::Python)
+            .author("test-author")
+            .build()
+            .unwrap();
+        tool_repo.create_tool(&tool_info).await.unwrap();
+
+        let user_id = UserId::new();
+
+        // 重复收藏应当是幂等的
+        favorite_repo.add_favorite(&user_id, &tool_info.id).await.unwrap();
+        favorite_repo.add_favorite(&user_id, &tool_info.id).await.unwrap();
+        assert!(favorite_repo.is_favorite(&user_id, &tool_info.id).await.unwrap());
+
+        let favorites = favorite_repo.list_favorite_tools(&user_id).await.unwrap();
+        assert_eq!(favorites.len(), 1);
+        assert_eq!(favorites[0].id, tool_info.id);
+
+        // 其他用户不受影响
+        let other_user = UserId::new();
+        assert!(favorite_repo.list_favorite_ids(&other_user).await.unwrap().is_empty());
+
+        assert!(favorite_repo.remove_favorite(&user_id, &tool_info.id).await.unwrap());
+        assert!(!favorite_repo.remove_favorite(&user_id, &tool_info.id).await.unwrap());
+        assert!(favorite_repo.list_favorite_ids(&user_id).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_invitation_repository() {
+        let database = create_test_database().await.unwrap();
+        let tenant_repo = TenantRepository::new(database.clone());
+        let user_repo = UserRepository::new(database.clone());
+        let invitation_repo = InvitationRepository::new(database);
+
+        let tenant_id = TenantId::new();
+        tenant_repo.create_tenant(&TenantInfo {
+            id: tenant_id.clone(),
+            name: "Invite Tenant".to_string(),
+            description: String::new(),
+            domain: None,
+            settings: HashMap::new(),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }).await.unwrap();
+
+        let (invitation, token) = invitation_repo
+            .create_invitation("new@example.com", UserRole::Admin, &tenant_id, None, chrono::Duration::days(1))
+            .await
+            .unwrap();
+        assert!(invitation.is_pending());
+        assert!(invitation_repo.accept_invitation("bogus", "newuser", "password123").await.is_err());
+
+        let user = invitation_repo.accept_invitation(&token, "newuser", "password123").await.unwrap();
+        assert_eq!(user.email, "new@example.com");
+        assert_eq!(user.role, UserRole::Admin);
+        assert!(user_repo.get_user(&user.id).await.unwrap().unwrap().email_verified);
+        assert!(user_repo.verify_user_password(&user.id, "password123").await.unwrap());
+
+        // Invitations are single use
+        assert!(invitation_repo.accept_invitation(&token, "other", "password123").await.is_err());
+        let listed = invitation_repo.list_invitations(&tenant_id).await.unwrap();
+        assert_eq!(listed[0].accepted_user_id.as_ref(), Some(&user.id));
+
+        // Expired invitations cannot be accepted
+        let (_, expired) = invitation_repo
+            .create_invitation("late@example.com", UserRole::User, &tenant_id, None, chrono::Duration::seconds(-1))
+            .await
+            .unwrap();
+        assert!(invitation_repo.accept_invitation(&expired, "late", "password123").await.is_err());
+
+        // Email verification
+        user_repo.set_email_verified(&user.id, false).await.unwrap();
+        let verification = user_repo.create_email_verification(&user.id, chrono::Duration::hours(1)).await.unwrap();
+        assert_eq!(user_repo.verify_email(&verification).await.unwrap(), Some(user.id.clone()));
+        assert!(user_repo.get_user(&user.id).await.unwrap().unwrap().email_verified);
+        assert_eq!(user_repo.verify_email(&verification).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_login_lockout() {
+        let database = create_test_database().await.unwrap();
+        let tenant_repo = TenantRepository::new(database.clone());
+        let user_repo = UserRepository::new(database.clone());
+        let event_repo = SecurityEventRepository::new(database);
+
+        let tenant_id = TenantId::new();
+        tenant_repo.create_tenant(&TenantInfo {
+            id: tenant_id.clone(),
+            name: "Lockout Tenant".to_string(),
+            description: String::new(),
+            domain: None,
+            settings: HashMap::new(),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }).await.unwrap();
+        let user = UserInfo {
+            id: UserId::new(),
+            username: "locked".to_string(),
+            email: "locked@example.com".to_string(),
+            role: UserRole::User,
+            tenant_id: tenant_id.clone(),
+            settings: HashMap::new(),
+            email_verified: true,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        };
+        user_repo.register_user(&user, "Password123").await.unwrap();
+
+        let lockout = chrono::Duration::minutes(15);
+        for attempt in 1..3 {
+            let state = user_repo.record_failed_login(&user.id, 3, lockout).await.unwrap();
+            assert_eq!(state.failed_attempts, attempt);
+            assert!(!state.is_locked());
+        }
+        let state = user_repo.record_failed_login(&user.id, 3, lockout).await.unwrap();
+        assert!(state.is_locked());
+        assert!(user_repo.get_login_failures(&user.id).await.unwrap().unwrap().is_locked());
+
+        // An expired lockout starts a fresh window
+        user_repo.clear_login_failures(&user.id).await.unwrap();
+        for _ in 0..3 {
+            user_repo.record_failed_login(&user.id, 3, chrono::Duration::seconds(-1)).await.unwrap();
+        }
+        let state = user_repo.record_failed_login(&user.id, 3, lockout).await.unwrap();
+        assert_eq!(state.failed_attempts, 1);
+
+        user_repo.clear_login_failures(&user.id).await.unwrap();
+        assert!(user_repo.get_login_failures(&user.id).await.unwrap().is_none());
+
+        event_repo.record_event(&AuditEvent {
+            event_id: uuid::Uuid::new_v4().to_string(),
+            event_type: "account_locked".to_string(),
+            user_id: Some(user.id.clone()),
+            tenant_id: Some(tenant_id),
+            resource_type: "user".to_string(),
+            resource_id: user.id.as_str().to_string(),
+            action: "login".to_string(),
+            details: HashMap::from([("failed_attempts".to_string(), serde_json::json!(3))]),
+            ip_address: None,
+            user_agent: None,
+            timestamp: chrono::Utc::now(),
+            success: false,
+            error_message: None,
+        }).await.unwrap();
+        let events = event_repo.list_events(&AuditFilter {
+            event_type: Some("account_locked".to_string()),
+            user_id: Some(user.id.clone()),
+            tenant_id: None,
+            resource_type: None,
+            resource_id: None,
+            action: None,
+            start_time: None,
+            end_time: None,
+            success: None,
+        }, 10).await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].details["failed_attempts"], serde_json::json!(3));
+        assert!(!events[0].success);
+    }
+
+    #[tokio::test]
+    async fn test_two_factor_enrollment() {
+        let database = create_test_database().await.unwrap();
+        let tenant_repo = TenantRepository::new(database.clone());
+        let user_repo = UserRepository::new(database);
+
+        let tenant_id = TenantId::new();
+        tenant_repo.create_tenant(&TenantInfo {
+            id: tenant_id.clone(),
+            name: "2FA Tenant".to_string(),
+            description: String::new(),
+            domain: None,
+            settings: HashMap::new(),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }).await.unwrap();
+        let user = UserInfo {
+            id: UserId::new(),
+            username: "mfa".to_string(),
+            email: "mfa@example.com".to_string(),
+            role: UserRole::Admin,
+            tenant_id,
+            settings: HashMap::new(),
+            email_verified: true,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        };
+        user_repo.register_user(&user, "Password123").await.unwrap();
+
+        assert!(user_repo.get_totp(&user.id).await.unwrap().is_none());
+        assert!(user_repo.enable_totp(&user.id).await.is_err());
+
+        user_repo.set_pending_totp_secret(&user.id, "JBSWY3DPEHPK3PXP").await.unwrap();
+        let pending = user_repo.get_totp(&user.id).await.unwrap().unwrap();
+        assert_eq!(pending.secret, "JBSWY3DPEHPK3PXP");
+        assert!(!pending.enabled);
+
+        user_repo.enable_totp(&user.id).await.unwrap();
+        assert!(user_repo.get_totp(&user.id).await.unwrap().unwrap().enabled);
+
+        let codes = vec!["aaaaa-11111".to_string(), "bbbbb-22222".to_string()];
+        user_repo.replace_recovery_codes(&user.id, &codes).await.unwrap();
+        assert_eq!(user_repo.remaining_recovery_codes(&user.id).await.unwrap(), 2);
+        assert!(user_repo.consume_recovery_code(&user.id, "aaaaa-11111").await.unwrap());
+        assert!(!user_repo.consume_recovery_code(&user.id, "aaaaa-11111").await.unwrap());
+        assert!(!user_repo.consume_recovery_code(&user.id, "unknown").await.unwrap());
+        assert_eq!(user_repo.remaining_recovery_codes(&user.id).await.unwrap(), 1);
+
+        // Each time step is accepted once, earlier steps are replays
+        assert!(user_repo.record_totp_step(&user.id, 100).await.unwrap());
+        assert!(!user_repo.record_totp_step(&user.id, 100).await.unwrap());
+        assert!(!user_repo.record_totp_step(&user.id, 99).await.unwrap());
+        assert!(user_repo.record_totp_step(&user.id, 101).await.unwrap());
+
+        user_repo.disable_totp(&user.id).await.unwrap();
+        assert!(user_repo.get_totp(&user.id).await.unwrap().is_none());
+        assert_eq!(user_repo.remaining_recovery_codes(&user.id).await.unwrap(), 0);
+        // A new enrollment starts without a recorded step
+        user_repo.set_pending_totp_secret(&user.id, "JBSWY3DPEHPK3PXP").await.unwrap();
+        assert!(user_repo.record_totp_step(&user.id, 50).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_oidc_repository() {
+        let database = create_test_database().await.unwrap();
+        let tenant_repo = TenantRepository::new(database.clone());
+        let user_repo = UserRepository::new(database.clone());
+        let oidc_repo = OidcRepository::new(database);
+
+        let tenant_id = TenantId::new();
+        tenant_repo.create_tenant(&TenantInfo {
+            id: tenant_id.clone(),
+            name: "SSO Tenant".to_string(),
+            description: String::new(),
+            domain: None,
+            settings: HashMap::new(),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }).await.unwrap();
+        let user = UserInfo {
+            id: UserId::new(),
+            username: "sso".to_string(),
+            email: "sso@example.com".to_string(),
+            role: UserRole::User,
+            tenant_id: tenant_id.clone(),
+            settings: HashMap::new(),
+            email_verified: true,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        };
+        user_repo.register_user(&user, "Password123").await.unwrap();
+
+        let provider = OidcProviderRecord {
+            id: "provider-1".to_string(),
+            tenant_id: tenant_id.clone(),
+            name: "corp".to_string(),
+            issuer: "https://idp.example.com".to_string(),
+            client_id: "client".to_string(),
+            client_secret: "secret".to_string(),
+            scopes: vec!["openid".to_string(), "email".to_string()],
+            role_claim: None,
+            role_mapping: HashMap::from([("stepflow-admins".to_string(), UserRole::Admin)]),
+            default_role: UserRole::User,
+            enabled: true,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        };
+        oidc_repo.create_provider(&provider).await.unwrap();
+        let stored = oidc_repo.get_provider("provider-1").await.unwrap().unwrap();
+        assert_eq!(stored.client_secret, "secret");
+        assert_eq!(stored.role_claim, None);
+        assert_eq!(stored.role_mapping.get("stepflow-admins"), Some(&UserRole::Admin));
+        assert_eq!(oidc_repo.list_providers(&tenant_id).await.unwrap().len(), 1);
+
+        // Login states are single use and expire
+        let login = OidcLoginState {
+            state: "state-1".to_string(),
+            provider_id: provider.id.clone(),
+            nonce: "nonce".to_string(),
+            code_verifier: "verifier".to_string(),
+            redirect_uri: "https://app.example.com/callback".to_string(),
+            link_user_id: Some(user.id.clone()),
+            expires_at: chrono::Utc::now() + chrono::Duration::minutes(5),
+        };
+        oidc_repo.create_login_state(&login).await.unwrap();
+        let taken = oidc_repo.take_login_state("state-1").await.unwrap().unwrap();
+        assert_eq!(taken.link_user_id, Some(user.id.clone()));
+        assert!(oidc_repo.take_login_state("state-1").await.unwrap().is_none());
+        oidc_repo.create_login_state(&OidcLoginState {
+            state: "state-2".to_string(),
+            expires_at: chrono::Utc::now() - chrono::Duration::seconds(1),
+            ..login
+        }).await.unwrap();
+        assert!(oidc_repo.take_login_state("state-2").await.unwrap().is_none());
+
+        // Identities
+        oidc_repo.link_identity(&provider.id, "sub-1", &user.id, Some("sso@example.com")).await.unwrap();
+        assert!(oidc_repo.link_identity(&provider.id, "sub-2", &user.id, None).await.is_err());
+        let identity = oidc_repo.find_identity(&provider.id, "sub-1").await.unwrap().unwrap();
+        assert_eq!(identity.user_id, user.id);
+        assert!(identity.last_login_at.is_none());
+        oidc_repo.touch_identity(&provider.id, "sub-1").await.unwrap();
+        assert!(oidc_repo.find_identity(&provider.id, "sub-1").await.unwrap().unwrap().last_login_at.is_some());
+        assert_eq!(oidc_repo.list_identities(&user.id).await.unwrap().len(), 1);
+
+        assert!(oidc_repo.unlink_identity(&user.id, &provider.id).await.unwrap());
+        assert!(!oidc_repo.unlink_identity(&user.id, &provider.id).await.unwrap());
+        assert!(oidc_repo.delete_provider(&tenant_id, &provider.id).await.unwrap());
+        assert!(oidc_repo.get_provider(&provider.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_directory_repository() {
+        let database = create_test_database().await.unwrap();
+        let tenant_repo = TenantRepository::new(database.clone());
+        let user_repo = UserRepository::new(database.clone());
+        let directory_repo = DirectoryRepository::new(database);
+
+        let tenant_id = TenantId::new();
+        tenant_repo.create_tenant(&TenantInfo {
+            id: tenant_id.clone(),
+            name: "Directory Tenant".to_string(),
+            description: String::new(),
+            domain: None,
+            settings: HashMap::new(),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }).await.unwrap();
+
+        let config = DirectoryConfigRecord {
+            tenant_id: tenant_id.clone(),
+            url: "ldap://localhost:389".to_string(),
+            bind_dn: "cn=admin,dc=example,dc=com".to_string(),
+            bind_password: "secret".to_string(),
+            base_dn: "dc=example,dc=com".to_string(),
+            user_filter: "(objectClass=person)".to_string(),
+            username_attribute: "uid".to_string(),
+            email_attribute: "mail".to_string(),
+            group_attribute: "memberOf".to_string(),
+            group_role_mapping: HashMap::from([("admins".to_string(), UserRole::Admin)]),
+            default_role: UserRole::User,
+            conflict_policy: DirectoryConflictPolicy::Skip,
+            remove_missing: false,
+            enabled: true,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        };
+        directory_repo.save_config(&config).await.unwrap();
+        directory_repo.save_config(&DirectoryConfigRecord {
+            conflict_policy: DirectoryConflictPolicy::Adopt,
+            ..config.clone()
+        }).await.unwrap();
+        let stored = directory_repo.get_config(&tenant_id).await.unwrap().unwrap();
+        assert_eq!(stored.conflict_policy, DirectoryConflictPolicy::Adopt);
+        assert_eq!(stored.group_role_mapping.get("admins"), Some(&UserRole::Admin));
+        assert_eq!(directory_repo.list_enabled_configs().await.unwrap().len(), 1);
+
+        // Users become directory-managed and can be released again
+        let user = UserInfo {
+            id: UserId::new(),
+            username: "ldap-user".to_string(),
+            email: "ldap@example.com".to_string(),
+            role: UserRole::User,
+            tenant_id: tenant_id.clone(),
+            settings: HashMap::new(),
+            email_verified: true,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        };
+        user_repo.register_user(&user, "Password123").await.unwrap();
+        assert!(directory_repo.managed_users(&tenant_id).await.unwrap().is_empty());
+        directory_repo.set_managed(&user.id, Some("uid=ldap-user,dc=example,dc=com")).await.unwrap();
+        let managed = directory_repo.managed_users(&tenant_id).await.unwrap();
+        assert_eq!(managed.get(&user.id).map(String::as_str), Some("uid=ldap-user,dc=example,dc=com"));
+        directory_repo.set_managed(&user.id, None).await.unwrap();
+        assert!(directory_repo.managed_users(&tenant_id).await.unwrap().is_empty());
+
+        let run = DirectorySyncRun {
+            id: "run-1".to_string(),
+            tenant_id: tenant_id.clone(),
+            dry_run: true,
+            created: 2,
+            updated: 1,
+            adopted: 0,
+            removed: 0,
+            conflicts: 1,
+            error: None,
+            started_at: chrono::Utc::now(),
+            finished_at: chrono::Utc::now(),
+        };
+        directory_repo.record_run(&run).await.unwrap();
+        let runs = directory_repo.list_runs(&tenant_id, 10).await.unwrap();
+        assert_eq!(runs.len(), 1);
+        assert!(runs[0].dry_run);
+        assert_eq!((runs[0].created, runs[0].conflicts), (2, 1));
+        assert!(runs[0].error.is_none());
+
+        assert!(directory_repo.delete_config(&tenant_id).await.unwrap());
+        assert!(directory_repo.get_config(&tenant_id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_session_repository() {
+        let database = create_test_database().await.unwrap();
+        let tenant_repo = TenantRepository::new(database.clone());
+        let user_repo = UserRepository::new(database.clone());
+        let session_repo = SessionRepository::new(database);
+
+        let tenant_id = TenantId::new();
+        tenant_repo.create_tenant(&TenantInfo {
+            id: tenant_id.clone(),
+            name: "Session Tenant".to_string(),
+            description: String::new(),
+            domain: None,
+            settings: HashMap::new(),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }).await.unwrap();
+        let user = UserInfo {
+            id: UserId::new(),
+            username: "session".to_string(),
+            email: "session@example.com".to_string(),
+            role: UserRole::User,
+            tenant_id,
+            settings: HashMap::new(),
+            email_verified: true,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        };
+        user_repo.register_user(&user, "Password123").await.unwrap();
+
+        let laptop = session_repo
+            .create_session(&user, Some("Firefox"), Some("10.0.0.1"), chrono::Duration::days(7))
+            .await
+            .unwrap();
+        let phone = session_repo
+            .create_session(&user, None, None, chrono::Duration::days(7))
+            .await
+            .unwrap();
+        let expired = session_repo
+            .create_session(&user, None, None, chrono::Duration::seconds(-1))
+            .await
+            .unwrap();
+        assert!(!expired.is_active());
+        assert_eq!(session_repo.list_active_sessions(&user.id).await.unwrap().len(), 2);
+
+        // Activity updates are throttled
+        session_repo.touch_session(&phone.id, Some("10.0.0.2"), chrono::Duration::minutes(1)).await.unwrap();
+        let stored = session_repo.get_session(&phone.id).await.unwrap().unwrap();
+        assert_eq!(stored.ip_address, None);
+        assert_eq!(stored.device, None);
+        session_repo.touch_session(&phone.id, Some("10.0.0.2"), chrono::Duration::zero()).await.unwrap();
+        let stored = session_repo.get_session(&phone.id).await.unwrap().unwrap();
+        assert_eq!(stored.ip_address.as_deref(), Some("10.0.0.2"));
+        assert!(stored.last_seen_at > phone.last_seen_at);
+
+        assert!(session_repo.revoke_session(&user.id, &laptop.id).await.unwrap());
+        assert!(!session_repo.revoke_session(&user.id, &laptop.id).await.unwrap());
+        assert!(!session_repo.get_session(&laptop.id).await.unwrap().unwrap().is_active());
+
+        let other = session_repo
+            .create_session(&user, None, None, chrono::Duration::days(7))
+            .await
+            .unwrap();
+        assert_eq!(session_repo.revoke_user_sessions(&user.id, Some(&phone.id)).await.unwrap(), 2);
+        let active = session_repo.list_active_sessions(&user.id).await.unwrap();
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].id, phone.id);
+        assert!(!session_repo.get_session(&other.id).await.unwrap().unwrap().is_active());
+
+        let purged = session_repo.purge_sessions(chrono::Utc::now() + chrono::Duration::seconds(1)).await.unwrap();
+        assert_eq!(purged, 3);
+        assert!(session_repo.get_session(&phone.id).await.unwrap().is_some());
+    }
+
     #[tokio::test]
     async fn test_database_stats() {
         let database = create_test_database().await.unwrap();
@@ -162,4 +693,934 @@ mod tests {
         let history = MigrationManager::get_migration_history(&database).await.unwrap();
         assert!(!history.is_empty());
     }
-} 
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_seed_fixture() {
+        let database = create_test_database().await.unwrap();
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/dev.json");
+
+        // Fixtures only load into the environments they list
+        assert!(MigrationManager::seed(&database, path, FALLBACK_ENVIRONMENT).await.is_err());
+        let report = MigrationManager::seed(&database, path, "development").await.unwrap();
+        assert_eq!(report, SeedReport { tenants: 1, users: 2, tools: 2, executions: 1 });
+
+        // Seeding again upserts instead of duplicating
+        let fixture = SeedFixture::from_file(path).unwrap();
+        fixture.apply(&database, "test").await.unwrap();
+        assert_eq!(TenantRepository::new(database.clone()).list_tenants(None).await.unwrap().len(), 1);
+        assert_eq!(ToolRepository::new(database.clone()).list_tools(None).await.unwrap().len(), 2);
+
+        let user_repo = UserRepository::new(database.clone());
+        let admin = user_repo.get_user_by_username("admin").await.unwrap().unwrap();
+        assert_eq!(admin.role, UserRole::Admin);
+        assert!(admin.email_verified);
+        assert!(user_repo.verify_user_password(&admin.id, "admin-password").await.unwrap());
+
+        let tool = ToolRepository::new(database.clone())
+            .get_tool(&ToolId::from_string("tool-dev-petstore".to_string()))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(tool.version, ToolVersion::new(2, 1, 0));
+        assert_eq!(tool.tool_type, ToolType::OpenAPI);
+
+        let execution = ExecutionRepository::new(database.clone())
+            .get_execution("execution-dev-echo")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(execution.tool_id.as_str(), "tool-dev-echo");
+        assert_eq!(execution.status, "completed");
+
+        // Production is off limits unless the fixture opts in
+        assert!(fixture.apply(&database, "production").await.is_err());
+        let opted_in = SeedFixture { environments: vec!["production".to_string()], ..Default::default() };
+        assert!(opted_in.apply(&database, "production").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_reversible_migrations() {
+        let database = create_test_database().await.unwrap();
+        let latest = MigrationManager::latest_version();
+        let user_columns = |database: SqliteDatabase| async move {
+            database
+                .execute("SELECT name FROM pragma_table_info('users')", &[])
+                .await
+                .unwrap()
+                .rows
+                .iter()
+                .filter_map(|row| row.get("name").and_then(|v| v.as_str()).map(str::to_string))
+                .collect::<Vec<_>>()
+        };
+
+        // A dry run only reports the plan
+        let plan = MigrationManager::migrate_to(&database, 18, true).await.unwrap();
+        assert_eq!(plan.steps.len() as u32, latest - 18);
+        assert!(plan.steps.iter().all(|step| step.direction == MigrationDirection::Down));
+        assert_eq!(plan.steps[0].version, latest);
+        assert!(plan.to_string().contains("DROP TABLE IF EXISTS user_sessions;"));
+        assert!(user_columns(database.clone()).await.contains(&"email_verified".to_string()));
+
+        MigrationManager::migrate_to(&database, 18, false).await.unwrap();
+        assert!(!user_columns(database.clone()).await.contains(&"email_verified".to_string()));
+        let history = MigrationManager::get_migration_history(&database).await.unwrap();
+        assert_eq!(history.last().unwrap().version, 18);
+        assert!(history.iter().all(|record| record.checksum.is_some()));
+
+        // Migrations without down SQL block the rollback before anything runs
+        assert!(MigrationManager::migrate_to(&database, 9, false).await.is_err());
+        assert_eq!(MigrationManager::get_migration_history(&database).await.unwrap().len(), 18);
+
+        MigrationManager::run_migrations(&database).await.unwrap();
+        assert_eq!(MigrationManager::get_migration_status(&database).await.unwrap(), MigrationStatus::Completed);
+
+        // Drift in an applied migration stops further migrations
+        database
+            .execute("UPDATE migrations SET checksum = 'edited' WHERE version = 3", &[])
+            .await
+            .unwrap();
+        assert!(MigrationManager::verify_checksums(&database).await.is_err());
+        assert!(MigrationManager::migrate_to(&database, 20, false).await.is_err());
+        assert_eq!(MigrationManager::get_migration_history(&database).await.unwrap().len() as u32, latest);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_writes_use_wal_and_writer() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = format!("sqlite://{}?mode=rwc", dir.path().join("stepflow.db").display());
+        let database = SqliteDatabase::new(&url).await.unwrap();
+        MigrationManager::run_migrations(&database).await.unwrap();
+
+        let journal_mode: String = sqlx::query_scalar("PRAGMA journal_mode")
+            .fetch_one(database.pool())
+            .await
+            .unwrap();
+        assert_eq!(journal_mode, "wal");
+
+        let writes = (0..50).map(|i| {
+            let database = database.clone();
+            tokio::spawn(async move {
+                let tenant = TenantInfo {
+                    id: TenantId::new(),
+                    name: format!("tenant-{}", i),
+                    description: String::new(),
+                    domain: None,
+                    settings: HashMap::new(),
+                    created_at: chrono::Utc::now(),
+                    updated_at: chrono::Utc::now(),
+                };
+                TenantRepository::new(database.clone()).create_tenant(&tenant).await?;
+                TenantRepository::new(database).list_tenants(None).await.map(|_| ())
+            })
+        });
+        for write in futures::future::join_all(writes).await {
+            write.unwrap().unwrap();
+        }
+
+        let tenants = TenantRepository::new(database.clone()).list_tenants(None).await.unwrap();
+        assert_eq!(tenants.len(), 50);
+        let contention = database.contention_stats();
+        assert!(contention.total_writes >= 50);
+        assert_eq!(contention.queued_writes, 0);
+        assert_eq!(contention.lock_errors, 0);
+    }
+
+    #[tokio::test]
+    async fn test_slow_queries_captured_with_plan() {
+        let database = SqliteDatabase::with_config(connection::DatabaseConfig {
+            url: "sqlite::memory:".to_string(),
+            slow_query_threshold: std::time::Duration::ZERO,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        MigrationManager::run_migrations(&database).await.unwrap();
+        let slow_queries = SlowQueryRepository::new(database.clone());
+
+        for _ in 0..2 {
+            database.execute(
+                "SELECT id FROM users\n  WHERE username = ?",
+                &[serde_json::Value::String("secret-name".to_string())],
+            ).await.unwrap();
+        }
+
+        // Captures are written in the background
+        let statement = "SELECT id FROM users WHERE username = ?";
+        let mut captured = Vec::new();
+        for _ in 0..50 {
+            captured = slow_queries.list(1000).await.unwrap();
+            captured.retain(|query| query.statement == statement);
+            if captured.len() == 2 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert_eq!(captured.len(), 2);
+        assert!(captured[0].success);
+        assert!(captured[0].plan.as_deref().unwrap().contains("users"));
+
+        // Bound parameters are never stored, and the log does not capture itself
+        let all = slow_queries.list(1000).await.unwrap();
+        assert!(all.iter().all(|query| !query.statement.contains("slow_queries")));
+        let raw = database.execute("SELECT statement, plan FROM slow_queries", &[]).await.unwrap();
+        assert!(raw.rows.iter().all(|row| !row.values().any(|value| value.as_str().is_some_and(|v| v.contains("secret-name")))));
+
+        let summary = slow_queries.summaries(1000).await.unwrap();
+        let users = summary.iter().find(|summary| summary.statement == statement).unwrap();
+        assert_eq!(users.occurrences, 2);
+        assert!(users.plan.is_some());
+
+        assert!(slow_queries.purge(chrono::Utc::now() + chrono::Duration::seconds(1)).await.unwrap() >= 2);
+        assert!(slow_queries.list(1000).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_online_table_rebuild() {
+        let database = SqliteDatabase::new("sqlite::memory:").await.unwrap();
+        database.execute(
+            "CREATE TABLE widgets (id TEXT PRIMARY KEY, name TEXT NOT NULL, size INTEGER)",
+            &[],
+        ).await.unwrap();
+        for i in 0..25 {
+            database.execute(
+                "INSERT INTO widgets (id, name, size) VALUES (?, ?, ?)",
+                &[utils::param::text(format!("w{}", i)), utils::param::text(format!("widget {}", i)), utils::param::int(i)],
+            ).await.unwrap();
+        }
+
+        let rebuild = OnlineRebuild::new(
+            "widgets",
+            "id",
+            "id TEXT PRIMARY KEY, name TEXT NOT NULL, size INTEGER, tenant_id TEXT NOT NULL DEFAULT 'default', label TEXT",
+        )
+        .compute("label", "upper(name)")
+        .index("CREATE INDEX idx_widgets_tenant ON widgets(tenant_id)")
+        .batch_size(10)
+        .batch_pause(std::time::Duration::from_millis(50));
+
+        // Writes made during the backfill are mirrored into the shadow table
+        let writer = database.clone();
+        let mut seen = Vec::new();
+        let mut concurrent = None;
+        let progress = MigrationManager::rebuild_table_online(&database, &rebuild, |progress| {
+            seen.push((progress.phase, progress.rows_copied));
+            if progress.phase == RebuildPhase::Backfilling && progress.rows_copied == 10 && concurrent.is_none() {
+                let writer = writer.clone();
+                concurrent = Some(tokio::spawn(async move {
+                    writer.execute("INSERT INTO widgets (id, name, size) VALUES ('w99', 'late', 1)", &[]).await.unwrap();
+                    writer.execute("UPDATE widgets SET name = 'renamed' WHERE id = 'w20'", &[]).await.unwrap();
+                    writer.execute("DELETE FROM widgets WHERE id = 'w0'", &[]).await.unwrap();
+                }));
+            }
+        })
+        .await
+        .unwrap();
+        concurrent.unwrap().await.unwrap();
+
+        assert_eq!(progress.phase, RebuildPhase::Completed);
+        assert_eq!(progress.total_rows, 25);
+        assert_eq!(progress.fraction(), 1.0);
+        assert_eq!(seen.first(), Some(&(RebuildPhase::Backfilling, 0)));
+        assert!(seen.contains(&(RebuildPhase::Swapping, progress.rows_copied)));
+
+        let rows = database.execute("SELECT id, name, tenant_id, label FROM widgets ORDER BY id", &[]).await.unwrap().rows;
+        assert_eq!(rows.len(), 25);
+        let widget = |id: &str| rows.iter().find(|row| row["id"] == id).cloned();
+        assert!(widget("w0").is_none());
+        assert_eq!(widget("w99").unwrap()["label"], "LATE");
+        assert_eq!(widget("w20").unwrap()["name"], "renamed");
+        assert_eq!(widget("w20").unwrap()["label"], "RENAMED");
+        assert_eq!(widget("w5").unwrap()["tenant_id"], "default");
+
+        // The triggers and the shadow table are gone, the new index exists
+        let objects = database.execute(
+            "SELECT type, name FROM sqlite_master WHERE name LIKE '%online%' OR name = 'idx_widgets_tenant'",
+            &[],
+        ).await.unwrap().rows;
+        let names: Vec<&str> = objects.iter().filter_map(|row| row["name"].as_str()).collect();
+        assert!(names.contains(&"idx_widgets_tenant"));
+        assert!(!names.iter().any(|name| name.starts_with("_online_")));
+
+        let status = MigrationManager::online_rebuilds(&database).await.unwrap();
+        assert_eq!(status.len(), 1);
+        assert_eq!(status[0].phase, RebuildPhase::Completed);
+
+        // Rebuilding a table that does not exist fails before touching anything
+        let missing = OnlineRebuild::new("gadgets", "id", "id TEXT PRIMARY KEY");
+        assert!(MigrationManager::rebuild_table_online(&database, &missing, |_| {}).await.is_err());
+        let invalid = OnlineRebuild::new("bad name", "id", "id TEXT");
+        assert!(MigrationManager::rebuild_table_online(&database, &invalid, |_| {}).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_statement_cache_stats() {
+        let database = SqliteDatabase::with_config(connection::DatabaseConfig {
+            url: "sqlite::memory:".to_string(),
+            max_connections: 1,
+            statement_cache_capacity: 3,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        let baseline = database.statement_cache_stats();
+
+        for sql in ["SELECT 11", "SELECT 11", "SELECT 12", "SELECT 13", "SELECT 11"] {
+            database.execute(sql, &[]).await.unwrap();
+        }
+
+        // The cache is full by the last query, so its hit can't be told from an evicting miss
+        let stats = database.statement_cache_stats();
+        assert_eq!(stats.capacity, 3);
+        assert_eq!(stats.hits - baseline.hits, 1);
+        assert_eq!(stats.misses - baseline.misses, 3);
+        assert_eq!(stats.unsampled - baseline.unsampled, 1);
+        assert_eq!(stats.largest_cache, 3);
+
+        let connection = database.pool().acquire().await.unwrap();
+        assert_eq!(sqlx::Connection::cached_statements_size(&*connection), 3);
+    }
+
+    #[tokio::test]
+    async fn test_batch_inserts() {
+        let database = create_test_database().await.unwrap();
+        let tool_repo = ToolRepository::new(database.clone());
+
+        let tools = (0..5)
+            .map(|i| ToolInfo::builder()
+                .name(format!("batch-tool-{}", i))
+                .description("Batch tool")
+                .tool_type(ToolType::Custom("test".to_string()))
+                .author("test-author")
+                .build()
+                .unwrap())
+            .collect::<Vec<_>>();
+
+        // A duplicate id fails on its own without rolling back the rest
+        let mut batch = tools.clone();
+        batch.insert(3, tools[1].clone());
+        let report = tool_repo.create_tools(&batch).await.unwrap();
+        assert_eq!(report.inserted, 5);
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.failures[0].index, 3);
+        assert!(!report.is_complete());
+        assert_eq!(tool_repo.list_tools(None).await.unwrap().len(), 5);
+
+        let logs = (0..1200)
+            .map(|i| {
+                let entry = LogEntry {
+                    level: LogLevel::Info,
+                    message: format!("line {}", i),
+                    timestamp: chrono::Utc::now(),
+                    source: "test".to_string(),
+                    metadata: HashMap::new(),
+                };
+                ("exec-1".to_string(), entry)
+            })
+            .collect::<Vec<_>>();
+        let report = LogRepository::new(database.clone()).insert_logs_batch(&logs).await.unwrap();
+        assert!(report.is_complete());
+        assert_eq!(report.inserted, 1200);
+
+        let rows = database
+            .execute("SELECT id FROM logs WHERE execution_id = ?", &[serde_json::Value::String("exec-1".to_string())])
+            .await
+            .unwrap();
+        assert_eq!(rows.rows.len(), 1200);
+    }
+
+    #[tokio::test]
+    async fn test_saved_views() {
+        let database = create_test_database().await.unwrap();
+        let views = SavedViewRepository::new(database);
+        let tenant = TenantId::from_string("tenant-1".to_string());
+        let user = UserId::from_string("user-1".to_string());
+        let other = UserId::from_string("user-2".to_string());
+
+        let saved = views
+            .save_view(&tenant, &user, "payments", &serde_json::json!({"labels": {"team": "payments"}}))
+            .await
+            .unwrap();
+
+        // Saving under the same name replaces the filter but keeps the view
+        let replaced = views
+            .save_view(&tenant, &user, "payments", &serde_json::json!({"status": "Failed"}))
+            .await
+            .unwrap();
+        assert_eq!(replaced.id, saved.id);
+        assert_eq!(replaced.filter, serde_json::json!({"status": "Failed"}));
+        assert_eq!(views.list_views(&tenant, &user).await.unwrap().len(), 1);
+
+        // Views are private to their owner
+        assert!(views.list_views(&tenant, &other).await.unwrap().is_empty());
+        assert!(views.get_view(&tenant, &other, &saved.id).await.unwrap().is_none());
+        assert!(!views.delete_view(&tenant, &other, &saved.id).await.unwrap());
+
+        assert!(views.delete_view(&tenant, &user, &saved.id).await.unwrap());
+        assert!(views.get_view(&tenant, &user, &saved.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_tool_presets() {
+        let database = create_test_database().await.unwrap();
+        let presets = ToolPresetRepository::new(database);
+        let tenant = TenantId::from_string("tenant-1".to_string());
+        let tool = ToolId::from_string("deploy".to_string());
+        let admin = UserId::from_string("admin".to_string());
+        let user = UserId::from_string("user-1".to_string());
+        let other = UserId::from_string("user-2".to_string());
+        let params = |value: serde_json::Value| serde_json::from_value::<HashMap<String, serde_json::Value>>(value).unwrap();
+
+        let shared = presets
+            .save_preset(&tenant, &tool, None, "prod-eu", Some("EU defaults"), &params(serde_json::json!({"region": "eu-west-1", "replicas": 3})), &admin)
+            .await
+            .unwrap();
+        assert!(shared.user_id.is_none());
+        let own = presets
+            .save_preset(&tenant, &tool, Some(&user), "prod-eu", None, &params(serde_json::json!({"replicas": 5})), &user)
+            .await
+            .unwrap();
+        assert_eq!(own.user_id.as_ref(), Some(&user));
+
+        // The user's preset overrides single values of the shared one
+        let resolved = presets.resolve(&tenant, &tool, &user, "prod-eu").await.unwrap().unwrap();
+        assert_eq!(resolved, params(serde_json::json!({"region": "eu-west-1", "replicas": 5})));
+        let resolved = presets.resolve(&tenant, &tool, &other, "prod-eu").await.unwrap().unwrap();
+        assert_eq!(resolved, params(serde_json::json!({"region": "eu-west-1", "replicas": 3})));
+        assert!(presets.resolve(&tenant, &tool, &user, "missing").await.unwrap().is_none());
+
+        // Private presets are only listed for their owner
+        assert_eq!(presets.list_presets(&tenant, &tool, &user).await.unwrap().len(), 2);
+        assert_eq!(presets.list_presets(&tenant, &tool, &other).await.unwrap().len(), 1);
+
+        // Saving again under the same name and owner replaces the parameters
+        let replaced = presets
+            .save_preset(&tenant, &tool, None, "prod-eu", None, &params(serde_json::json!({"region": "eu-central-1"})), &admin)
+            .await
+            .unwrap();
+        assert_eq!(replaced.id, shared.id);
+        let updated = presets
+            .update_preset(&tenant, &own.id, "prod-eu-large", None, &params(serde_json::json!({"replicas": 9})))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.name, "prod-eu-large");
+        assert!(presets.update_preset(&tenant, "missing", "x", None, &HashMap::new()).await.unwrap().is_none());
+
+        let other_tenant = TenantId::from_string("tenant-2".to_string());
+        assert!(presets.get_preset(&other_tenant, &shared.id).await.unwrap().is_none());
+        assert!(presets.delete_preset(&tenant, &shared.id).await.unwrap());
+        assert!(presets.get_preset(&tenant, &shared.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_feature_flags() {
+        let database = create_test_database().await.unwrap();
+        let flags = FeatureFlagRepository::new(database);
+
+        let stored = flags
+            .upsert(&FlagDefinition::new("new_scheduler").with_rollout(20).with_tenant("tenant-1", true), Some("admin"))
+            .await
+            .unwrap();
+        assert_eq!(stored.updated_by.as_deref(), Some("admin"));
+        let loaded = flags.get("new_scheduler").await.unwrap().unwrap();
+        assert_eq!(loaded.rollout_percentage, 20);
+        assert_eq!(loaded.tenants.get("tenant-1"), Some(&true));
+        assert!(loaded.updated_at.is_some());
+
+        // Upserting replaces the stored definition
+        flags.upsert(&FlagDefinition::new("new_scheduler").with_rollout(50), None).await.unwrap();
+        let loaded = flags.get("new_scheduler").await.unwrap().unwrap();
+        assert_eq!(loaded.rollout_percentage, 50);
+        assert!(loaded.tenants.is_empty());
+        assert!(flags.upsert(&FlagDefinition::new("Bad Name"), None).await.is_err());
+        assert_eq!(flags.list().await.unwrap().len(), 1);
+
+        assert!(flags.delete("new_scheduler").await.unwrap());
+        assert!(!flags.delete("new_scheduler").await.unwrap());
+        assert!(flags.list().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_tool_docs() {
+        let database = create_test_database().await.unwrap();
+        let docs = ToolDocsRepository::new(database);
+        let tool_id = ToolId::from_string("docs-tool".to_string());
+
+        docs.save_docs(&tool_id, "1.0.0", "# v1", Some("admin")).await.unwrap();
+        docs.save_docs(&tool_id, "2.0.0", "# v2", None).await.unwrap();
+        let saved = docs.save_docs(&tool_id, "1.0.0", "# v1 updated", None).await.unwrap();
+
+        let loaded = docs.get_docs(&tool_id, "1.0.0").await.unwrap().unwrap();
+        assert_eq!(loaded.markdown, "# v1 updated");
+        assert_eq!(loaded.updated_by, None);
+        assert_eq!(loaded.updated_at, saved.updated_at);
+        assert_eq!(docs.latest_docs(&tool_id).await.unwrap().unwrap().version, "1.0.0");
+        assert_eq!(docs.list_versions(&tool_id).await.unwrap(), vec!["1.0.0", "2.0.0"]);
+
+        assert!(docs.delete_docs(&tool_id, "1.0.0").await.unwrap());
+        assert!(!docs.delete_docs(&tool_id, "1.0.0").await.unwrap());
+        assert!(docs.get_docs(&tool_id, "1.0.0").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_overview_statistics() {
+        let database = create_test_database().await.unwrap();
+        let tools = ToolRepository::new(database.clone());
+        let executions = ExecutionRepository::new(database.clone());
+        let flaky = ToolInfo::builder().name("flaky").tool_type(ToolType::Python).build().unwrap();
+        let steady = ToolInfo::builder().name("steady").tool_type(ToolType::Python).build().unwrap();
+        tools.create_tool(&flaky).await.unwrap();
+        tools.create_tool(&steady).await.unwrap();
+        let tenant_id = TenantId::new();
+        TenantRepository::new(database.clone()).create_tenant(&TenantInfo {
+            id: tenant_id.clone(),
+            name: "Overview Tenant".to_string(),
+            description: String::new(),
+            domain: None,
+            settings: HashMap::new(),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }).await.unwrap();
+        let user = UserInfo {
+            id: UserId::new(),
+            username: "overview".to_string(),
+            email: "overview@example.com".to_string(),
+            role: UserRole::User,
+            tenant_id: tenant_id.clone(),
+            settings: HashMap::new(),
+            email_verified: true,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        };
+        UserRepository::new(database.clone()).register_user(&user, "password123").await.unwrap();
+
+        let now = chrono::Utc::now();
+        for (index, (tool, status)) in [(&flaky, "failed"), (&flaky, "failed"), (&flaky, "completed"), (&steady, "completed"), (&steady, "pending")]
+            .into_iter()
+            .enumerate()
+        {
+            executions.create_execution(&ExecutionRecord {
+                id: format!("execution-{}", index),
+                tool_id: tool.id.clone(),
+                tenant_id: tenant_id.clone(),
+                user_id: user.id.clone(),
+                status: status.to_string(),
+                request: serde_json::json!({}),
+                result: None,
+                started_at: now,
+                completed_at: None,
+                created_at: now,
+                updated_at: now,
+            }).await.unwrap();
+        }
+
+        let counts = tools.count_by_type_and_status().await.unwrap();
+        assert_eq!(counts.iter().map(|c| c.count).sum::<u64>(), 2);
+
+        let since = now - chrono::Duration::hours(1);
+        let histogram = executions.status_histogram(since, TimeBucket::Hour).await.unwrap();
+        assert_eq!(histogram.iter().map(|b| b.count).sum::<u64>(), 5);
+        let failed = histogram.iter().find(|b| b.status == "failed").unwrap();
+        assert_eq!(failed.count, 2);
+        assert!(failed.bucket_start <= now && now - failed.bucket_start < chrono::Duration::hours(1));
+        let days = executions.status_histogram(since, TimeBucket::Day).await.unwrap();
+        assert!(days.iter().all(|b| b.bucket_start.format("%H:%M:%S").to_string() == "00:00:00"));
+
+        let failing = executions.top_failing_tools(since, 10).await.unwrap();
+        assert_eq!(failing.len(), 1);
+        assert_eq!(failing[0].tool_id, flaky.id);
+        assert_eq!((failing[0].executions, failing[0].failures), (3, 2));
+        assert!(executions.top_failing_tools(now + chrono::Duration::hours(1), 10).await.unwrap().is_empty());
+
+        let depth = executions.queue_depth().await.unwrap();
+        assert_eq!(depth.pending_executions, 1);
+        assert_eq!(depth.queued_tasks, 0);
+        assert!(database.size_bytes().await.unwrap() > 0);
+    }
+
+    #[tokio::test]
+    async fn test_event_schemas() {
+        let database = create_test_database().await.unwrap();
+        let schemas = EventSchemaRepository::new(database);
+        let record = |name: &str, version: u32| EventSchemaRecord {
+            name: name.to_string(),
+            version,
+            json_schema: serde_json::json!({"type": "object", "title": format!("{} v{}", name, version)}),
+            compatibility: "backward".to_string(),
+            description: None,
+            created_by: Some("admin".to_string()),
+            created_at: chrono::Utc::now(),
+        };
+
+        schemas.insert_schema(&record("tool.changed", 1)).await.unwrap();
+        schemas.insert_schema(&record("tool.changed", 2)).await.unwrap();
+        schemas.insert_schema(&record("audit.recorded", 1)).await.unwrap();
+        assert!(schemas.insert_schema(&record("tool.changed", 2)).await.is_err());
+
+        let loaded = schemas.get_schema("tool.changed", 1).await.unwrap().unwrap();
+        assert_eq!(loaded.json_schema["title"], "tool.changed v1");
+        assert_eq!(loaded.created_by.as_deref(), Some("admin"));
+        assert_eq!(schemas.latest_schema("tool.changed").await.unwrap().unwrap().version, 2);
+        assert_eq!(schemas.list_versions("tool.changed").await.unwrap().len(), 2);
+        assert!(schemas.get_schema("tool.changed", 3).await.unwrap().is_none());
+
+        let latest: Vec<(String, u32)> = schemas.list_latest().await.unwrap()
+            .into_iter()
+            .map(|record| (record.name, record.version))
+            .collect();
+        assert_eq!(latest, vec![("audit.recorded".to_string(), 1), ("tool.changed".to_string(), 2)]);
+    }
+
+    #[tokio::test]
+    async fn test_read_replica_routing() {
+        let dir = tempfile::tempdir().unwrap();
+        let primary_url = format!("sqlite://{}?mode=rwc", dir.path().join("primary.db").display());
+        let replica_url = format!("sqlite://{}?mode=rwc", dir.path().join("replica.db").display());
+
+        // Stands in for the replication process writing to the replica
+        let upstream = SqliteDatabase::new(&replica_url).await.unwrap();
+        MigrationManager::run_migrations(&upstream).await.unwrap();
+        upstream
+            .execute(
+                "INSERT INTO replication_state (id, position, updated_at) VALUES (1, 0, ?)",
+                &[serde_json::Value::String(chrono::Utc::now().to_rfc3339())],
+            )
+            .await
+            .unwrap();
+
+        let database = SqliteDatabase::with_config(connection::DatabaseConfig {
+            url: primary_url,
+            replicas: vec![ReplicaConfig {
+                max_lag: std::time::Duration::from_secs(60),
+                ..ReplicaConfig::new(replica_url)
+            }],
+            replica_check_interval: std::time::Duration::ZERO,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        MigrationManager::run_migrations(&database).await.unwrap();
+
+        let tool_repo = ToolRepository::new(database.clone());
+        let tool_info = ToolInfo::builder()
+            .name("replicated-tool")
+            .description("Replicated tool")
+            .tool_type(ToolType::Custom("test".to_string()))
+            .author("test-author")
+            .build()
+            .unwrap();
+        tool_repo.create_tool(&tool_info).await.unwrap();
+        let position = database.write_position();
+        assert!(position > 0);
+
+        // Search tolerates lag and reads the stale replica; read-your-writes falls back
+        assert!(tool_repo.search_tools("replicated").await.unwrap().is_empty());
+        assert!(tool_repo.get_tool(&tool_info.id).await.unwrap().is_some());
+        assert_eq!(database.replica_fallbacks(), 1);
+        assert!(database.replica_stats()[0].lag.unwrap() > std::time::Duration::ZERO);
+
+        // Once the replica catches up it serves read-your-writes reads too
+        ToolRepository::new(upstream.clone()).create_tool(&tool_info).await.unwrap();
+        upstream
+            .execute(
+                "UPDATE replication_state SET position = ?, updated_at = ? WHERE id = 1",
+                &[serde_json::Value::from(position), serde_json::Value::String(chrono::Utc::now().to_rfc3339())],
+            )
+            .await
+            .unwrap();
+        assert!(tool_repo.get_tool(&tool_info.id).await.unwrap().is_some());
+        assert_eq!(database.replica_fallbacks(), 1);
+
+        let stats = &database.replica_stats()[0];
+        assert!(stats.healthy);
+        assert_eq!(stats.position, position);
+        assert_eq!(stats.lag, Some(std::time::Duration::ZERO));
+        assert_eq!(stats.reads, 2);
+
+        database
+            .execute_with("SELECT * FROM tools", &[], ReadConsistency::Primary)
+            .await
+            .unwrap();
+        assert_eq!(database.replica_stats()[0].reads, 2);
+    }
+
+    #[cfg(feature = "fault-injection")]
+    #[tokio::test]
+    async fn test_fault_injection() {
+        let faults = FaultController::new(7);
+        let database = create_test_database().await.unwrap().with_fault_controller(faults.clone());
+        database
+            .execute("CREATE TABLE chaos (id INTEGER PRIMARY KEY, value TEXT)", &[])
+            .await
+            .unwrap();
+
+        // Errors fail before the write
+        faults.set(FaultTarget::Database, FaultConfig::always_fail());
+        let insert = "INSERT INTO chaos (value) VALUES (?)";
+        assert!(database.execute(insert, &[serde_json::json!("a")]).await.is_err());
+
+        // Partial writes apply the write and still report failure
+        faults.set(FaultTarget::Database, FaultConfig::partial_writes(1.0));
+        assert!(database.execute(insert, &[serde_json::json!("b")]).await.is_err());
+        faults.clear(FaultTarget::Database);
+        let rows = database.execute("SELECT value FROM chaos", &[]).await.unwrap();
+        assert_eq!(rows.rows.len(), 1);
+
+        let stats = faults.stats(FaultTarget::Database);
+        assert_eq!((stats.errors, stats.partial_writes), (1, 1));
+
+        // Probabilistic faults replay identically for the same seed
+        let replay = |seed| async move {
+            let faults = FaultController::new(seed);
+            faults.set(FaultTarget::Database, FaultConfig::errors(0.5));
+            let mut decisions = Vec::new();
+            for _ in 0..32 {
+                decisions.push(faults.inject(FaultTarget::Database).await);
+            }
+            decisions
+        };
+        let decisions = replay(11).await;
+        assert_eq!(decisions, replay(11).await);
+        assert!(decisions.contains(&Some(Fault::Error)) && decisions.contains(&None));
+    }
+
+    #[tokio::test]
+    async fn test_tenant_secrets_encrypted_at_rest() {
+        let master = MasterKey::from_base64(&MasterKey::generate()).unwrap();
+        let database = create_test_database().await.unwrap()
+            .with_encryption(std::sync::Arc::new(MasterKeyring::new(master)));
+        let tenant_id = TenantId::new();
+        TenantRepository::new(database.clone()).create_tenant(&TenantInfo {
+            id: tenant_id.clone(),
+            name: "Encrypted Tenant".to_string(),
+            description: String::new(),
+            domain: None,
+            settings: HashMap::new(),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }).await.unwrap();
+
+        let oidc_repo = OidcRepository::new(database.clone());
+        oidc_repo.create_provider(&OidcProviderRecord {
+            id: "provider-1".to_string(),
+            tenant_id: tenant_id.clone(),
+            name: "corp".to_string(),
+            issuer: "https://idp.example.com".to_string(),
+            client_id: "client".to_string(),
+            client_secret: "client-secret".to_string(),
+            scopes: vec!["openid".to_string()],
+            role_claim: None,
+            role_mapping: HashMap::new(),
+            default_role: UserRole::User,
+            enabled: true,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }).await.unwrap();
+
+        let raw = database.execute("SELECT client_secret FROM oidc_providers", &[]).await.unwrap();
+        let stored = raw.rows[0]["client_secret"].as_str().unwrap().to_string();
+        assert!(stored.starts_with(ENVELOPE_PREFIX));
+        assert!(!stored.contains("client-secret"));
+        assert_eq!(oidc_repo.get_provider("provider-1").await.unwrap().unwrap().client_secret, "client-secret");
+
+        // Ciphertext is bound to its tenant, and plaintext written before encryption still reads
+        let keys = TenantKeyRepository::new(database.clone());
+        assert!(keys.decrypt(&TenantId::new(), &stored).await.is_err());
+        assert_eq!(keys.decrypt(&tenant_id, "legacy").await.unwrap(), "legacy");
+    }
+
+    #[tokio::test]
+    async fn test_data_key_and_master_key_rotation() {
+        let old_master = MasterKey::generate();
+        let new_master = MasterKey::generate();
+        let keyring = |current: &str, previous: Option<&str>| {
+            let mut keyring = MasterKeyring::new(MasterKey::from_base64(current).unwrap());
+            if let Some(previous) = previous {
+                keyring = keyring.with_previous(MasterKey::from_base64(previous).unwrap());
+            }
+            std::sync::Arc::new(keyring)
+        };
+        let database = create_test_database().await.unwrap();
+        let tenant_id = TenantId::new();
+
+        let keys = TenantKeyRepository::new(database.clone().with_encryption(keyring(&old_master, None)));
+        let first = keys.encrypt(&tenant_id, "first").await.unwrap();
+
+        // Rotating the data key retires version 1, which still decrypts
+        let rotated = keys.rotate(&tenant_id).await.unwrap();
+        assert_eq!((rotated.version, rotated.status), (2, DataKeyStatus::Active));
+        let second = keys.encrypt(&tenant_id, "second").await.unwrap();
+        assert!(second.starts_with(&format!("{}2:", ENVELOPE_PREFIX)));
+        assert_eq!(keys.decrypt(&tenant_id, &first).await.unwrap(), "first");
+        let listed = keys.list_keys(&tenant_id).await.unwrap();
+        assert_eq!(listed.iter().map(|key| key.status).collect::<Vec<_>>(), vec![DataKeyStatus::Active, DataKeyStatus::Retired]);
+
+        // A new master key reads old data keys until they are re-wrapped
+        let keys = TenantKeyRepository::new(database.clone().with_encryption(keyring(&new_master, Some(&old_master))));
+        assert_eq!(keys.status().await.unwrap().pending_rewrap, 2);
+        assert_eq!(keys.decrypt(&tenant_id, &first).await.unwrap(), "first");
+        let report = keys.rewrap_all().await.unwrap();
+        assert_eq!(report.rewrapped, 2);
+        assert!(report.unknown_master_key.is_empty());
+        assert_eq!(keys.status().await.unwrap().pending_rewrap, 0);
+
+        // After re-wrapping, the old master key is no longer needed and the data is unchanged
+        let keys = TenantKeyRepository::new(database.clone().with_encryption(keyring(&new_master, None)));
+        assert_eq!(keys.decrypt(&tenant_id, &first).await.unwrap(), "first");
+        assert_eq!(keys.decrypt(&tenant_id, &second).await.unwrap(), "second");
+
+        // Without the right master key nothing decrypts
+        let keys = TenantKeyRepository::new(database.with_encryption(keyring(&old_master, None)));
+        assert!(keys.decrypt(&tenant_id, &first).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_operational_mode_persisted() {
+        let database = create_test_database().await.unwrap();
+        let repository = OperationalModeRepository::new(database.clone());
+
+        let record = repository.get().await.unwrap();
+        assert_eq!(record.mode, OperationalMode::Normal);
+        assert!(record.updated_at.is_none());
+
+        repository.set(OperationalMode::Maintenance, Some("Upgrading storage"), Some("admin")).await.unwrap();
+        let record = OperationalModeRepository::new(database.clone()).get().await.unwrap();
+        assert_eq!(record.mode, OperationalMode::Maintenance);
+        assert_eq!(record.message.as_deref(), Some("Upgrading storage"));
+        assert_eq!(record.updated_by.as_deref(), Some("admin"));
+        assert!(!record.mode.accepts_executions());
+        assert!(record.mode.accepts_writes());
+
+        repository.set(OperationalMode::ReadOnly, None, None).await.unwrap();
+        let record = repository.get().await.unwrap();
+        assert_eq!(record.mode, OperationalMode::ReadOnly);
+        assert!(record.message.is_none());
+        assert!(!record.mode.accepts_writes());
+    }
+
+    #[tokio::test]
+    async fn test_background_jobs_run_once_across_instances() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let database = create_test_database().await.unwrap();
+        let runs = Arc::new(AtomicUsize::new(0));
+        let scheduler = |instance: &str| JobScheduler::with_config(database.clone(), JobSchedulerConfig {
+            instance_id: instance.to_string(),
+            ..JobSchedulerConfig::default()
+        });
+        let first = scheduler("first");
+        let second = scheduler("second");
+        for scheduler in [&first, &second] {
+            let runs = runs.clone();
+            scheduler.register_fn("count", "Counts runs", JobTrigger::interval(std::time::Duration::from_secs(3600)), move || {
+                let runs = runs.clone();
+                async move { Ok(format!("run {}", runs.fetch_add(1, Ordering::SeqCst) + 1)) }
+            }).await.unwrap();
+        }
+
+        // Not due until an hour after registration
+        assert!(first.run_due().await.unwrap().is_empty());
+        database.execute("UPDATE background_jobs SET next_run_at = ?", &[serde_json::json!(chrono::Utc::now().to_rfc3339())]).await.unwrap();
+        let (a, b) = tokio::join!(first.run_due(), second.run_due());
+        assert_eq!(a.unwrap().len() + b.unwrap().len(), 1);
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+        // A job whose lease is held elsewhere is not started
+        database.execute(
+            "UPDATE background_jobs SET lease_owner = 'other', lease_until = ?",
+            &[serde_json::json!((chrono::Utc::now() + chrono::Duration::minutes(5)).to_rfc3339())],
+        ).await.unwrap();
+        assert!(matches!(second.trigger("count").await, Err(StepflowError::Conflict(_))));
+        assert_eq!(first.list().await.unwrap()[0].running_on.as_deref(), Some("other"));
+        database.execute("UPDATE background_jobs SET lease_owner = NULL, lease_until = NULL", &[]).await.unwrap();
+
+        let next_run_at = first.list().await.unwrap()[0].next_run_at;
+        let run = second.trigger("count").await.unwrap();
+        assert!(run.manual);
+        assert_eq!(run.message, "run 2");
+
+        let jobs = first.list().await.unwrap();
+        assert_eq!(jobs.len(), 1);
+        let last_run = jobs[0].last_run.as_ref().unwrap();
+        assert_eq!(last_run.status, JobRunStatus::Succeeded);
+        assert_eq!(last_run.instance_id, "second");
+        assert!(last_run.manual);
+        assert_eq!(jobs[0].next_run_at, next_run_at);
+        assert!(jobs[0].running_on.is_none());
+        assert!(matches!(first.trigger("missing").await, Err(StepflowError::ResourceNotAvailable(_))));
+    }
+
+    #[tokio::test]
+    async fn test_background_job_trigger_overrides() {
+        use stepflow_core::CalendarSchedule;
+
+        let database = create_test_database().await.unwrap();
+        JobScheduler::new(database.clone())
+            .register_fn("report", "Daily report", JobTrigger::interval(std::time::Duration::from_secs(60)), || async { Ok(String::new()) })
+            .await
+            .unwrap();
+
+        // Daily at 09:00 Shanghai time on business days, with the next three days blacked out
+        let now = chrono::Utc::now();
+        let schedule: CalendarSchedule = serde_json::from_value(serde_json::json!({
+            "cron": "0 9 * * *",
+            "timezone": "Asia/Shanghai",
+            "business_days_only": true,
+            "blackouts": [{"start": now, "end": now + chrono::Duration::days(3)}],
+        })).unwrap();
+        let expected = schedule.next_after(now).unwrap();
+        let scheduler = JobScheduler::new(database.clone())
+            .with_trigger_overrides([("report".to_string(), JobTrigger::Calendar(schedule))].into());
+        scheduler.register_fn("report", "Daily report", JobTrigger::interval(std::time::Duration::from_secs(60)), || async { Ok(String::new()) })
+            .await
+            .unwrap();
+
+        // The override replaces the schedule stored by the earlier registration
+        let next_run_at = scheduler.list().await.unwrap()[0].next_run_at.unwrap();
+        assert_eq!(next_run_at, expected);
+        assert!(next_run_at >= now + chrono::Duration::days(3));
+        assert_eq!(next_run_at.format("%H:%M").to_string(), "01:00");
+    }
+
+    #[tokio::test]
+    async fn test_leader_election_failover() {
+        let database = create_test_database().await.unwrap();
+        let election = |instance: &str| LeaderElection::with_config(database.clone(), LeaderElectionConfig {
+            instance_id: instance.to_string(),
+            ..LeaderElectionConfig::default()
+        });
+        let first = election("first");
+        let second = election("second");
+
+        assert!(first.campaign().await.unwrap());
+        assert!(!second.campaign().await.unwrap());
+        assert!(first.is_leader());
+        assert!(!second.is_leader());
+        let status = second.status();
+        assert_eq!(status.leader.as_deref(), Some("first"));
+        assert_eq!(status.term, Some(1));
+
+        // Renewing keeps the term
+        assert!(first.campaign().await.unwrap());
+        assert_eq!(first.status().term, Some(1));
+
+        // An expired lease is taken over in a new term
+        database.execute(
+            "UPDATE leader_leases SET expires_at = ?",
+            &[serde_json::json!((chrono::Utc::now() - chrono::Duration::seconds(5)).to_rfc3339())],
+        ).await.unwrap();
+        assert!(second.campaign().await.unwrap());
+        assert!(!first.campaign().await.unwrap());
+        assert!(!first.is_leader());
+        let status = first.status();
+        assert_eq!(status.leader.as_deref(), Some("second"));
+        assert_eq!(status.term, Some(2));
+
+        // Resigning hands over immediately
+        second.resign().await.unwrap();
+        assert!(!second.is_leader());
+        assert!(first.campaign().await.unwrap());
+        assert_eq!(first.status().term, Some(3));
+    }
+}
@@ -1,8 +1,13 @@
 //! Database migrations
 
-use stepflow_core::{Migration, StepflowResult, Database};
+use stepflow_core::{Migration, StepflowError, StepflowResult, Database, DatabaseError, ReadConsistency};
 use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use crate::seed::{SeedFixture, SeedReport};
 use crate::SqliteDatabase;
+use crate::utils::hash_token;
 use tracing::{debug, info, error};
 
 /// Migration status
@@ -20,6 +25,62 @@ pub struct MigrationRecord {
     pub version: u32,
     pub name: String,
     pub applied_at: String,
+    /// SHA-256 of the SQL that was applied; `None` for migrations applied before checksums were recorded
+    pub checksum: Option<String>,
+}
+
+/// Direction of a planned migration step
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationDirection {
+    Up,
+    Down,
+}
+
+/// A single step of a migration plan
+#[derive(Debug, Clone)]
+pub struct MigrationStep {
+    pub version: u32,
+    pub name: String,
+    pub direction: MigrationDirection,
+    /// SQL the step runs
+    pub sql: String,
+}
+
+/// Ordered steps that bring the schema to `target`
+#[derive(Debug, Clone)]
+pub struct MigrationPlan {
+    pub target: u32,
+    pub steps: Vec<MigrationStep>,
+}
+
+impl MigrationPlan {
+    /// Whether the schema is already at the target version
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+}
+
+impl fmt::Display for MigrationPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.steps.is_empty() {
+            return writeln!(f, "-- schema is at version {}, nothing to do", self.target);
+        }
+        for step in &self.steps {
+            let direction = match step.direction {
+                MigrationDirection::Up => "up",
+                MigrationDirection::Down => "down",
+            };
+            writeln!(f, "-- {} {} ({})", direction, step.version, step.name)?;
+            // Strip the indentation the SQL has in source
+            let lines: Vec<&str> = step.sql.lines().filter(|line| !line.trim().is_empty()).collect();
+            let indent = lines.iter().map(|line| line.len() - line.trim_start().len()).min().unwrap_or(0);
+            for line in lines {
+                writeln!(f, "{}", line[indent..].trim_end())?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
 }
 
 /// Migration manager for SQLite database
@@ -36,50 +97,72 @@ impl MigrationManager {
     /// Run migrations
     pub async fn run_migrations(database: &SqliteDatabase) -> StepflowResult<()> {
         info!("Starting database migrations");
-        
-        // First, ensure migrations table exists
-        Self::ensure_migrations_table(database).await?;
-        
-        let migrations = Self::get_migrations();
-        let applied_migrations = Self::get_applied_migrations(database).await?;
-        
-        let mut applied_count = 0;
-        for migration in migrations {
-            if !applied_migrations.contains(&migration.version) {
-                info!("Applying migration: {} (version {})", migration.name, migration.version);
-                
-                // Record migration start
-                Self::record_migration_start(database, &migration).await?;
-                
-                // Apply migration
-                match database.migrate(&[migration.clone()]).await {
-                    Ok(_) => {
-                        // Record successful migration
-                        Self::record_migration_success(database, &migration).await?;
-                        applied_count += 1;
-                        info!("Successfully applied migration: {}", migration.name);
-                    }
-                    Err(e) => {
-                        // Record failed migration
-                        Self::record_migration_failure(database, &migration, &e.to_string()).await?;
-                        error!("Failed to apply migration {}: {}", migration.name, e);
-                        return Err(e);
-                    }
-                }
-            } else {
-                debug!("Migration {} (version {}) already applied", migration.name, migration.version);
-            }
-        }
-        
-        if applied_count > 0 {
-            info!("Applied {} new migrations", applied_count);
-        } else {
+        let plan = Self::migrate_to(database, Self::latest_version(), false).await?;
+
+        if plan.is_empty() {
             info!("No new migrations to apply");
+        } else {
+            info!("Applied {} new migrations", plan.steps.len());
         }
-        
         Ok(())
     }
 
+    /// Bring the schema to `target`, applying pending migrations up to it and
+    /// reverting applied migrations above it
+    ///
+    /// Applied migrations are checked against their checksums first, and nothing
+    /// runs if any of them drifted. With `dry_run` the plan is returned without
+    /// touching the database.
+    pub async fn migrate_to(database: &SqliteDatabase, target: u32, dry_run: bool) -> StepflowResult<MigrationPlan> {
+        if !dry_run {
+            Self::ensure_migrations_table(database).await?;
+        }
+        let applied = Self::get_applied_records(database).await?;
+        Self::verify_records(&applied)?;
+        let plan = Self::plan(&applied, target)?;
+        if dry_run {
+            return Ok(plan);
+        }
+        Self::backfill_checksums(database, &applied).await?;
+
+        let migrations = Self::get_migrations();
+        for step in &plan.steps {
+            let Some(migration) = migrations.iter().find(|m| m.version == step.version) else {
+                continue;
+            };
+            match step.direction {
+                MigrationDirection::Up => Self::apply_migration(database, migration).await?,
+                MigrationDirection::Down => Self::revert_migration(database, migration, &step.sql).await?,
+            }
+        }
+        Ok(plan)
+    }
+
+    /// Fail if any applied migration no longer matches its definition
+    pub async fn verify_checksums(database: &SqliteDatabase) -> StepflowResult<()> {
+        let applied = Self::get_applied_records(database).await?;
+        Self::verify_records(&applied)
+    }
+
+    /// Version of the newest known migration
+    pub fn latest_version() -> u32 {
+        Self::get_migrations().iter().map(|m| m.version).max().unwrap_or(0)
+    }
+
+    /// SHA-256 of a migration's SQL, recorded when it is applied
+    pub fn checksum(migration: &Migration) -> String {
+        hash_token(&migration.sql)
+    }
+
+    /// Load a fixture file into the database for the given environment
+    ///
+    /// See [`crate::seed`]; fails without writing anything when the fixture doesn't
+    /// allow the environment. Callers without an explicit environment should pass
+    /// [`crate::seed::current_environment`], which treats an unset `STEPFLOW_ENV` as production.
+    pub async fn seed(database: &SqliteDatabase, path: impl AsRef<Path>, environment: &str) -> StepflowResult<SeedReport> {
+        SeedFixture::from_file(path)?.apply(database, environment).await
+    }
+
     /// Check if migrations are needed
     pub async fn check_migrations_needed(database: &SqliteDatabase) -> StepflowResult<bool> {
         let migrations = Self::get_migrations();
@@ -110,33 +193,9 @@ impl MigrationManager {
 
     /// Get migration history
     pub async fn get_migration_history(database: &SqliteDatabase) -> StepflowResult<Vec<MigrationRecord>> {
-        let sql = "SELECT version, name, applied_at FROM migrations ORDER BY version";
-        let result = database.execute(sql, &[]).await?;
-        
-        let mut records = Vec::new();
-        for row in result.rows {
-            let version = row.get("version")
-                .and_then(|v| v.as_i64())
-                .unwrap_or(0) as u32;
-            
-            let name = row.get("name")
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string();
-            
-            let applied_at = row.get("applied_at")
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string();
-            
-            records.push(MigrationRecord {
-                version,
-                name,
-                applied_at,
-            });
-        }
-        
-        Ok(records)
+        let sql = "SELECT * FROM migrations ORDER BY version";
+        let result = database.execute_with(sql, &[], ReadConsistency::Primary).await?;
+        Ok(result.rows.iter().map(row_to_migration_record).collect())
     }
 
     /// Ensure migrations table exists
@@ -147,29 +206,174 @@ impl MigrationManager {
                 name TEXT NOT NULL,
                 applied_at TEXT NOT NULL,
                 status TEXT NOT NULL DEFAULT 'completed',
-                error_message TEXT
+                error_message TEXT,
+                checksum TEXT
             )
         "#;
         
         database.execute(sql, &[]).await?;
+
+        // Tables created before checksums were recorded lack the column
+        let columns = database
+            .execute_with("SELECT name FROM pragma_table_info('migrations')", &[], ReadConsistency::Primary)
+            .await?;
+        if !columns.rows.iter().any(|row| row.get("name").and_then(|v| v.as_str()) == Some("checksum")) {
+            database.execute("ALTER TABLE migrations ADD COLUMN checksum TEXT", &[]).await?;
+        }
         Ok(())
     }
 
     /// Get applied migrations
     async fn get_applied_migrations(database: &SqliteDatabase) -> StepflowResult<Vec<u32>> {
-        let sql = "SELECT version FROM migrations WHERE status = 'completed' ORDER BY version";
-        let result = database.execute(sql, &[]).await?;
-        
-        let mut versions = Vec::new();
-        for row in result.rows {
-            if let Some(version_value) = row.get("version") {
-                if let Some(version) = version_value.as_i64() {
-                    versions.push(version as u32);
+        Ok(Self::get_applied_records(database).await?.iter().map(|record| record.version).collect())
+    }
+
+    /// Get records of completed migrations; empty when the migrations table doesn't exist yet
+    ///
+    /// Always read from the primary: a replica may be ahead of a primary being migrated.
+    async fn get_applied_records(database: &SqliteDatabase) -> StepflowResult<Vec<MigrationRecord>> {
+        let exists = database
+            .execute_with(
+                "SELECT name FROM sqlite_master WHERE type = 'table' AND name = 'migrations'",
+                &[],
+                ReadConsistency::Primary,
+            )
+            .await?;
+        if exists.rows.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let sql = "SELECT * FROM migrations WHERE status = 'completed' ORDER BY version";
+        let result = database.execute_with(sql, &[], ReadConsistency::Primary).await?;
+        Ok(result.rows.iter().map(row_to_migration_record).collect())
+    }
+
+    /// Compare applied migrations with the known definitions
+    fn verify_records(applied: &[MigrationRecord]) -> StepflowResult<()> {
+        let migrations = Self::get_migrations();
+        let mut drift = Vec::new();
+        for record in applied {
+            match migrations.iter().find(|m| m.version == record.version) {
+                None => drift.push(format!(
+                    "migration {} ({}) is applied but unknown to this build",
+                    record.version, record.name
+                )),
+                Some(migration) => {
+                    if let Some(checksum) = &record.checksum {
+                        if *checksum != Self::checksum(migration) {
+                            drift.push(format!(
+                                "migration {} ({}) was modified after it was applied",
+                                record.version, record.name
+                            ));
+                        }
+                    }
                 }
             }
         }
-        
-        Ok(versions)
+
+        if drift.is_empty() {
+            Ok(())
+        } else {
+            error!("Migration drift detected: {}", drift.join("; "));
+            Err(StepflowError::DatabaseError(DatabaseError::MigrationFailed(format!(
+                "Migration drift detected: {}",
+                drift.join("; ")
+            ))))
+        }
+    }
+
+    /// Work out the steps from the applied migrations to `target`
+    fn plan(applied: &[MigrationRecord], target: u32) -> StepflowResult<MigrationPlan> {
+        let migrations = Self::get_migrations();
+        if target > Self::latest_version() {
+            return Err(StepflowError::DatabaseError(DatabaseError::MigrationFailed(format!(
+                "Unknown migration version {} (latest is {})",
+                target,
+                Self::latest_version()
+            ))));
+        }
+        let is_applied = |version: u32| applied.iter().any(|record| record.version == version);
+
+        let mut steps = Vec::new();
+        for migration in migrations.iter().filter(|m| m.version <= target && !is_applied(m.version)) {
+            steps.push(MigrationStep {
+                version: migration.version,
+                name: migration.name.clone(),
+                direction: MigrationDirection::Up,
+                sql: migration.sql.clone(),
+            });
+        }
+
+        for record in applied.iter().rev().filter(|record| record.version > target) {
+            let down_sql = migrations
+                .iter()
+                .find(|m| m.version == record.version)
+                .and_then(|m| m.down_sql.clone())
+                .ok_or_else(|| StepflowError::DatabaseError(DatabaseError::MigrationFailed(format!(
+                    "Migration {} ({}) cannot be reverted",
+                    record.version, record.name
+                ))))?;
+            steps.push(MigrationStep {
+                version: record.version,
+                name: record.name.clone(),
+                direction: MigrationDirection::Down,
+                sql: down_sql,
+            });
+        }
+
+        Ok(MigrationPlan { target, steps })
+    }
+
+    /// Record checksums for migrations applied before checksums existed
+    async fn backfill_checksums(database: &SqliteDatabase, applied: &[MigrationRecord]) -> StepflowResult<()> {
+        let migrations = Self::get_migrations();
+        for record in applied.iter().filter(|record| record.checksum.is_none()) {
+            if let Some(migration) = migrations.iter().find(|m| m.version == record.version) {
+                database
+                    .execute(
+                        "UPDATE migrations SET checksum = ? WHERE version = ?",
+                        &[serde_json::json!(Self::checksum(migration)), serde_json::json!(record.version)],
+                    )
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply a migration and record the outcome
+    async fn apply_migration(database: &SqliteDatabase, migration: &Migration) -> StepflowResult<()> {
+        info!("Applying migration: {} (version {})", migration.name, migration.version);
+        Self::record_migration_start(database, migration).await?;
+
+        match database.migrate(std::slice::from_ref(migration)).await {
+            Ok(_) => {
+                Self::record_migration_success(database, migration).await?;
+                info!("Successfully applied migration: {}", migration.name);
+                Ok(())
+            }
+            Err(e) => {
+                Self::record_migration_failure(database, migration, &e.to_string()).await?;
+                error!("Failed to apply migration {}: {}", migration.name, e);
+                Err(e)
+            }
+        }
+    }
+
+    /// Run a migration's down SQL and forget that it was applied
+    async fn revert_migration(database: &SqliteDatabase, migration: &Migration, down_sql: &str) -> StepflowResult<()> {
+        info!("Reverting migration: {} (version {})", migration.name, migration.version);
+        let down = Migration {
+            version: migration.version,
+            name: format!("{} (down)", migration.name),
+            sql: down_sql.to_string(),
+            down_sql: None,
+        };
+        database.migrate(&[down]).await?;
+        database
+            .execute("DELETE FROM migrations WHERE version = ?", &[serde_json::json!(migration.version)])
+            .await?;
+        debug!("Reverted migration {}", migration.name);
+        Ok(())
     }
 
     /// Record migration start
@@ -192,11 +396,11 @@ impl MigrationManager {
     async fn record_migration_success(database: &SqliteDatabase, migration: &Migration) -> StepflowResult<()> {
         let sql = r#"
             UPDATE migrations 
-            SET status = 'completed', applied_at = datetime('now')
+            SET status = 'completed', applied_at = datetime('now'), checksum = ?
             WHERE version = ?
         "#;
         
-        let params = vec![serde_json::json!(migration.version)];
+        let params = vec![serde_json::json!(Self::checksum(migration)), serde_json::json!(migration.version)];
         database.execute(sql, &params).await?;
         Ok(())
     }
@@ -247,6 +451,9 @@ impl MigrationManager {
                         updated_at TEXT NOT NULL
                     )
                 "#.to_string(),
+                down_sql: Some(r#"
+                    DROP TABLE IF EXISTS tools;
+                "#.to_string()),
             },
             Migration {
                 version: 2,
@@ -262,6 +469,9 @@ impl MigrationManager {
                         updated_at TEXT NOT NULL
                     )
                 "#.to_string(),
+                down_sql: Some(r#"
+                    DROP TABLE IF EXISTS tenants;
+                "#.to_string()),
             },
             Migration {
                 version: 3,
@@ -280,6 +490,9 @@ impl MigrationManager {
                         FOREIGN KEY (tenant_id) REFERENCES tenants (id)
                     )
                 "#.to_string(),
+                down_sql: Some(r#"
+                    DROP TABLE IF EXISTS users;
+                "#.to_string()),
             },
             Migration {
                 version: 4,
@@ -302,6 +515,9 @@ impl MigrationManager {
                         FOREIGN KEY (user_id) REFERENCES users (id)
                     )
                 "#.to_string(),
+                down_sql: Some(r#"
+                    DROP TABLE IF EXISTS executions;
+                "#.to_string()),
             },
             Migration {
                 version: 5,
@@ -315,6 +531,7 @@ impl MigrationManager {
                         error_message TEXT
                     )
                 "#.to_string(),
+                down_sql: None,
             },
             Migration {
                 version: 6,
@@ -332,6 +549,9 @@ impl MigrationManager {
                     CREATE INDEX IF NOT EXISTS idx_metrics_name ON metrics(name);
                     CREATE INDEX IF NOT EXISTS idx_metrics_timestamp ON metrics(timestamp);
                 "#.to_string(),
+                down_sql: Some(r#"
+                    DROP TABLE IF EXISTS metrics;
+                "#.to_string()),
             },
             Migration {
                 version: 7,
@@ -350,6 +570,9 @@ impl MigrationManager {
                     CREATE INDEX IF NOT EXISTS idx_logs_level ON logs(level);
                     CREATE INDEX IF NOT EXISTS idx_logs_timestamp ON logs(timestamp);
                 "#.to_string(),
+                down_sql: Some(r#"
+                    DROP TABLE IF EXISTS logs;
+                "#.to_string()),
             },
             Migration {
                 version: 8,
@@ -404,6 +627,12 @@ impl MigrationManager {
                     CREATE INDEX IF NOT EXISTS idx_works_status ON works(status);
                     CREATE INDEX IF NOT EXISTS idx_workers_status ON workers(status);
                 "#.to_string(),
+                down_sql: Some(r#"
+                    DROP TABLE IF EXISTS execution_results;
+                    DROP TABLE IF EXISTS workers;
+                    DROP TABLE IF EXISTS works;
+                    DROP TABLE IF EXISTS tasks;
+                "#.to_string()),
             },
             Migration {
                 version: 9,
@@ -417,6 +646,15 @@ impl MigrationManager {
                     CREATE INDEX IF NOT EXISTS idx_executions_status ON executions(status);
                     CREATE INDEX IF NOT EXISTS idx_executions_started_at ON executions(started_at);
                 "#.to_string(),
+                down_sql: Some(r#"
+                    DROP INDEX IF EXISTS idx_tools_status;
+                    DROP INDEX IF EXISTS idx_tools_type;
+                    DROP INDEX IF EXISTS idx_executions_tool_id;
+                    DROP INDEX IF EXISTS idx_executions_tenant_id;
+                    DROP INDEX IF EXISTS idx_executions_user_id;
+                    DROP INDEX IF EXISTS idx_executions_status;
+                    DROP INDEX IF EXISTS idx_executions_started_at;
+                "#.to_string()),
             },
             Migration {
                 version: 10,
@@ -450,6 +688,7 @@ impl MigrationManager {
                         FOREIGN KEY (task_id) REFERENCES tasks (id)
                     );
                 "#.to_string(),
+                down_sql: None,
             },
             Migration {
                 version: 11,
@@ -529,7 +768,1121 @@ impl MigrationManager {
                     CREATE INDEX IF NOT EXISTS idx_sandbox_containers_sandbox_id ON sandbox_containers(sandbox_id);
                     CREATE INDEX IF NOT EXISTS idx_sandbox_containers_container_id ON sandbox_containers(container_id);
                 "#.to_string(),
+                down_sql: Some(r#"
+                    DROP TABLE IF EXISTS sandbox_containers;
+                    DROP TABLE IF EXISTS sandbox_violations;
+                    DROP TABLE IF EXISTS sandbox_metrics;
+                    DROP TABLE IF EXISTS sandbox_executions;
+                    DROP TABLE IF EXISTS sandboxes;
+                "#.to_string()),
+            },
+            Migration {
+                version: 12,
+                name: "create_user_favorites_table".to_string(),
+                sql: r#"
+                    CREATE TABLE IF NOT EXISTS user_favorites (
+                        user_id TEXT NOT NULL,
+                        tool_id TEXT NOT NULL,
+                        created_at TEXT NOT NULL,
+                        PRIMARY KEY (user_id, tool_id),
+                        FOREIGN KEY (tool_id) REFERENCES tools (id) ON DELETE CASCADE
+                    );
+                    CREATE INDEX IF NOT EXISTS idx_user_favorites_user_id ON user_favorites(user_id);
+                "#.to_string(),
+                down_sql: Some(r#"
+                    DROP TABLE IF EXISTS user_favorites;
+                "#.to_string()),
+            },
+            Migration {
+                version: 13,
+                name: "create_marketplace_tables".to_string(),
+                sql: r#"
+                    CREATE TABLE IF NOT EXISTS tool_listings (
+                        id TEXT PRIMARY KEY,
+                        tool_id TEXT NOT NULL,
+                        publisher_tenant_id TEXT NOT NULL,
+                        visibility TEXT NOT NULL,
+                        published_version TEXT NOT NULL,
+                        status TEXT NOT NULL DEFAULT 'published',
+                        created_at TEXT NOT NULL,
+                        updated_at TEXT NOT NULL,
+                        FOREIGN KEY (tool_id) REFERENCES tools (id) ON DELETE CASCADE
+                    );
+
+                    CREATE TABLE IF NOT EXISTS tool_listing_targets (
+                        listing_id TEXT NOT NULL,
+                        tenant_id TEXT NOT NULL,
+                        PRIMARY KEY (listing_id, tenant_id),
+                        FOREIGN KEY (listing_id) REFERENCES tool_listings (id) ON DELETE CASCADE
+                    );
+
+                    CREATE TABLE IF NOT EXISTS tool_subscriptions (
+                        id TEXT PRIMARY KEY,
+                        listing_id TEXT NOT NULL,
+                        tenant_id TEXT NOT NULL,
+                        tool_id TEXT NOT NULL,
+                        installed_version TEXT NOT NULL,
+                        pending_version TEXT,
+                        status TEXT NOT NULL DEFAULT 'active',
+                        created_at TEXT NOT NULL,
+                        updated_at TEXT NOT NULL,
+                        UNIQUE (listing_id, tenant_id),
+                        FOREIGN KEY (listing_id) REFERENCES tool_listings (id) ON DELETE CASCADE
+                    );
+
+                    CREATE INDEX IF NOT EXISTS idx_tool_listings_publisher ON tool_listings(publisher_tenant_id);
+                    CREATE INDEX IF NOT EXISTS idx_tool_listings_status ON tool_listings(status);
+                    CREATE INDEX IF NOT EXISTS idx_tool_listing_targets_tenant ON tool_listing_targets(tenant_id);
+                    CREATE INDEX IF NOT EXISTS idx_tool_subscriptions_tenant ON tool_subscriptions(tenant_id);
+                    CREATE INDEX IF NOT EXISTS idx_tool_subscriptions_listing ON tool_subscriptions(listing_id);
+                "#.to_string(),
+                down_sql: Some(r#"
+                    DROP TABLE IF EXISTS tool_subscriptions;
+                    DROP TABLE IF EXISTS tool_listing_targets;
+                    DROP TABLE IF EXISTS tool_listings;
+                "#.to_string()),
+            },
+            Migration {
+                version: 14,
+                name: "create_tool_configs_table".to_string(),
+                sql: r#"
+                    CREATE TABLE IF NOT EXISTS tool_configs (
+                        tenant_id TEXT NOT NULL,
+                        tool_id TEXT NOT NULL,
+                        configuration TEXT NOT NULL,
+                        environment TEXT NOT NULL,
+                        secrets TEXT NOT NULL,
+                        timeout INTEGER,
+                        retries INTEGER,
+                        enabled INTEGER NOT NULL DEFAULT 1,
+                        created_at TEXT NOT NULL,
+                        updated_at TEXT NOT NULL,
+                        PRIMARY KEY (tenant_id, tool_id),
+                        FOREIGN KEY (tool_id) REFERENCES tools (id) ON DELETE CASCADE
+                    );
+                    CREATE INDEX IF NOT EXISTS idx_tool_configs_tool_id ON tool_configs(tool_id);
+                "#.to_string(),
+                down_sql: Some(r#"
+                    DROP TABLE IF EXISTS tool_configs;
+                "#.to_string()),
+            },
+            Migration {
+                version: 15,
+                name: "create_registry_changes_table".to_string(),
+                sql: r#"
+                    CREATE TABLE IF NOT EXISTS registry_changes (
+                        sequence INTEGER PRIMARY KEY AUTOINCREMENT,
+                        change_type TEXT NOT NULL,
+                        tool_id TEXT NOT NULL,
+                        version TEXT NOT NULL,
+                        payload TEXT,
+                        created_at TEXT NOT NULL
+                    );
+                    CREATE INDEX IF NOT EXISTS idx_registry_changes_tool_id ON registry_changes(tool_id);
+                "#.to_string(),
+                down_sql: Some(r#"
+                    DROP TABLE IF EXISTS registry_changes;
+                "#.to_string()),
+            },
+            Migration {
+                version: 16,
+                name: "create_execution_events_table".to_string(),
+                sql: r#"
+                    CREATE TABLE IF NOT EXISTS execution_events (
+                        id INTEGER PRIMARY KEY AUTOINCREMENT,
+                        execution_id TEXT NOT NULL,
+                        tenant_id TEXT,
+                        state TEXT NOT NULL,
+                        worker_id TEXT,
+                        detail TEXT,
+                        occurred_at TEXT NOT NULL
+                    );
+                    CREATE INDEX IF NOT EXISTS idx_execution_events_execution_id ON execution_events(execution_id);
+                "#.to_string(),
+                down_sql: Some(r#"
+                    DROP TABLE IF EXISTS execution_events;
+                "#.to_string()),
+            },
+            Migration {
+                version: 17,
+                name: "create_alerting_tables".to_string(),
+                sql: r#"
+                    ALTER TABLE execution_events ADD COLUMN tool_id TEXT;
+                    CREATE INDEX IF NOT EXISTS idx_execution_events_tool_time ON execution_events(tool_id, occurred_at);
+
+                    CREATE TABLE IF NOT EXISTS alert_rules (
+                        id TEXT PRIMARY KEY,
+                        name TEXT NOT NULL,
+                        metric TEXT NOT NULL,
+                        tool_id TEXT,
+                        comparison TEXT NOT NULL,
+                        threshold REAL NOT NULL,
+                        window_secs INTEGER NOT NULL,
+                        min_samples INTEGER NOT NULL DEFAULT 1,
+                        webhook_url TEXT,
+                        enabled INTEGER NOT NULL DEFAULT 1,
+                        created_at TEXT NOT NULL
+                    );
+
+                    CREATE TABLE IF NOT EXISTS alerts (
+                        id TEXT PRIMARY KEY,
+                        rule_id TEXT NOT NULL,
+                        status TEXT NOT NULL,
+                        value REAL NOT NULL,
+                        threshold REAL NOT NULL,
+                        fired_at TEXT NOT NULL,
+                        resolved_at TEXT,
+                        FOREIGN KEY (rule_id) REFERENCES alert_rules (id) ON DELETE CASCADE
+                    );
+                    CREATE INDEX IF NOT EXISTS idx_alerts_rule_status ON alerts(rule_id, status);
+                "#.to_string(),
+                down_sql: Some(r#"
+                    DROP TABLE IF EXISTS alerts;
+                    DROP TABLE IF EXISTS alert_rules;
+                    DROP INDEX IF EXISTS idx_execution_events_tool_time;
+                    ALTER TABLE execution_events DROP COLUMN tool_id;
+                "#.to_string()),
+            },
+            Migration {
+                version: 18,
+                name: "create_anomaly_tables".to_string(),
+                sql: r#"
+                    CREATE TABLE IF NOT EXISTS tool_baselines (
+                        tool_id TEXT PRIMARY KEY,
+                        samples INTEGER NOT NULL,
+                        duration_mean_ms REAL NOT NULL,
+                        duration_variance REAL NOT NULL,
+                        failure_rate REAL NOT NULL,
+                        updated_at TEXT NOT NULL
+                    );
+
+                    CREATE TABLE IF NOT EXISTS execution_anomalies (
+                        id TEXT PRIMARY KEY,
+                        execution_id TEXT NOT NULL,
+                        tool_id TEXT NOT NULL,
+                        kind TEXT NOT NULL,
+                        observed REAL NOT NULL,
+                        expected REAL NOT NULL,
+                        deviation REAL NOT NULL,
+                        detected_at TEXT NOT NULL
+                    );
+                    CREATE INDEX IF NOT EXISTS idx_execution_anomalies_detected_at ON execution_anomalies(detected_at);
+                    CREATE INDEX IF NOT EXISTS idx_execution_anomalies_tool_id ON execution_anomalies(tool_id);
+                "#.to_string(),
+                down_sql: Some(r#"
+                    DROP TABLE IF EXISTS execution_anomalies;
+                    DROP TABLE IF EXISTS tool_baselines;
+                "#.to_string()),
+            },
+            Migration {
+                version: 19,
+                name: "create_invitation_tables".to_string(),
+                sql: r#"
+                    ALTER TABLE users ADD COLUMN email_verified INTEGER NOT NULL DEFAULT 0;
+
+                    CREATE TABLE IF NOT EXISTS user_invitations (
+                        id TEXT PRIMARY KEY,
+                        token_hash TEXT NOT NULL UNIQUE,
+                        email TEXT NOT NULL,
+                        role TEXT NOT NULL,
+                        tenant_id TEXT NOT NULL,
+                        invited_by TEXT,
+                        expires_at TEXT NOT NULL,
+                        accepted_at TEXT,
+                        accepted_user_id TEXT,
+                        created_at TEXT NOT NULL,
+                        FOREIGN KEY (tenant_id) REFERENCES tenants (id) ON DELETE CASCADE
+                    );
+                    CREATE INDEX IF NOT EXISTS idx_user_invitations_tenant ON user_invitations(tenant_id);
+
+                    CREATE TABLE IF NOT EXISTS email_verifications (
+                        token_hash TEXT PRIMARY KEY,
+                        user_id TEXT NOT NULL,
+                        expires_at TEXT NOT NULL,
+                        created_at TEXT NOT NULL,
+                        FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+                    );
+                "#.to_string(),
+                down_sql: Some(r#"
+                    DROP TABLE IF EXISTS email_verifications;
+                    DROP TABLE IF EXISTS user_invitations;
+                    ALTER TABLE users DROP COLUMN email_verified;
+                "#.to_string()),
+            },
+            Migration {
+                version: 20,
+                name: "create_login_security_tables".to_string(),
+                sql: r#"
+                    CREATE TABLE IF NOT EXISTS login_failures (
+                        user_id TEXT PRIMARY KEY,
+                        failed_attempts INTEGER NOT NULL,
+                        last_failed_at TEXT NOT NULL,
+                        locked_until TEXT,
+                        FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+                    );
+
+                    CREATE TABLE IF NOT EXISTS security_events (
+                        id TEXT PRIMARY KEY,
+                        event_type TEXT NOT NULL,
+                        user_id TEXT,
+                        tenant_id TEXT,
+                        resource_type TEXT NOT NULL,
+                        resource_id TEXT NOT NULL,
+                        action TEXT NOT NULL,
+                        details TEXT,
+                        ip_address TEXT,
+                        user_agent TEXT,
+                        success INTEGER NOT NULL,
+                        error_message TEXT,
+                        created_at TEXT NOT NULL
+                    );
+                    CREATE INDEX IF NOT EXISTS idx_security_events_user ON security_events(user_id, created_at);
+                    CREATE INDEX IF NOT EXISTS idx_security_events_type ON security_events(event_type, created_at);
+                "#.to_string(),
+                down_sql: Some(r#"
+                    DROP TABLE IF EXISTS security_events;
+                    DROP TABLE IF EXISTS login_failures;
+                "#.to_string()),
+            },
+            Migration {
+                version: 21,
+                name: "add_two_factor_auth".to_string(),
+                sql: r#"
+                    ALTER TABLE users ADD COLUMN totp_secret TEXT;
+                    ALTER TABLE users ADD COLUMN totp_enabled INTEGER NOT NULL DEFAULT 0;
+
+                    CREATE TABLE IF NOT EXISTS recovery_codes (
+                        id INTEGER PRIMARY KEY AUTOINCREMENT,
+                        user_id TEXT NOT NULL,
+                        code_hash TEXT NOT NULL,
+                        used_at TEXT,
+                        created_at TEXT NOT NULL,
+                        FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+                    );
+                    CREATE INDEX IF NOT EXISTS idx_recovery_codes_user ON recovery_codes(user_id);
+                "#.to_string(),
+                down_sql: Some(r#"
+                    DROP TABLE IF EXISTS recovery_codes;
+                    ALTER TABLE users DROP COLUMN totp_enabled;
+                    ALTER TABLE users DROP COLUMN totp_secret;
+                "#.to_string()),
+            },
+            Migration {
+                version: 22,
+                name: "create_oidc_tables".to_string(),
+                sql: r#"
+                    CREATE TABLE IF NOT EXISTS oidc_providers (
+                        id TEXT PRIMARY KEY,
+                        tenant_id TEXT NOT NULL,
+                        name TEXT NOT NULL,
+                        issuer TEXT NOT NULL,
+                        client_id TEXT NOT NULL,
+                        client_secret TEXT NOT NULL,
+                        scopes TEXT NOT NULL,
+                        role_claim TEXT,
+                        role_mapping TEXT NOT NULL,
+                        default_role TEXT NOT NULL,
+                        enabled INTEGER NOT NULL DEFAULT 1,
+                        created_at TEXT NOT NULL,
+                        updated_at TEXT NOT NULL,
+                        UNIQUE (tenant_id, name),
+                        FOREIGN KEY (tenant_id) REFERENCES tenants (id) ON DELETE CASCADE
+                    );
+
+                    CREATE TABLE IF NOT EXISTS oidc_identities (
+                        provider_id TEXT NOT NULL,
+                        subject TEXT NOT NULL,
+                        user_id TEXT NOT NULL,
+                        email TEXT,
+                        created_at TEXT NOT NULL,
+                        last_login_at TEXT,
+                        PRIMARY KEY (provider_id, subject),
+                        UNIQUE (provider_id, user_id),
+                        FOREIGN KEY (provider_id) REFERENCES oidc_providers (id) ON DELETE CASCADE,
+                        FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+                    );
+
+                    CREATE TABLE IF NOT EXISTS oidc_login_states (
+                        state TEXT PRIMARY KEY,
+                        provider_id TEXT NOT NULL,
+                        nonce TEXT NOT NULL,
+                        code_verifier TEXT NOT NULL,
+                        redirect_uri TEXT NOT NULL,
+                        link_user_id TEXT,
+                        expires_at TEXT NOT NULL,
+                        created_at TEXT NOT NULL
+                    );
+                "#.to_string(),
+                down_sql: Some(r#"
+                    DROP TABLE IF EXISTS oidc_login_states;
+                    DROP TABLE IF EXISTS oidc_identities;
+                    DROP TABLE IF EXISTS oidc_providers;
+                "#.to_string()),
+            },
+            Migration {
+                version: 23,
+                name: "create_directory_sync_tables".to_string(),
+                sql: r#"
+                    ALTER TABLE users ADD COLUMN directory_dn TEXT;
+                    CREATE INDEX IF NOT EXISTS idx_users_directory_dn ON users(tenant_id, directory_dn);
+
+                    CREATE TABLE IF NOT EXISTS directory_configs (
+                        tenant_id TEXT PRIMARY KEY,
+                        url TEXT NOT NULL,
+                        bind_dn TEXT NOT NULL,
+                        bind_password TEXT NOT NULL,
+                        base_dn TEXT NOT NULL,
+                        user_filter TEXT NOT NULL,
+                        username_attribute TEXT NOT NULL,
+                        email_attribute TEXT NOT NULL,
+                        group_attribute TEXT NOT NULL,
+                        group_role_mapping TEXT NOT NULL,
+                        default_role TEXT NOT NULL,
+                        conflict_policy TEXT NOT NULL,
+                        remove_missing INTEGER NOT NULL DEFAULT 0,
+                        enabled INTEGER NOT NULL DEFAULT 1,
+                        created_at TEXT NOT NULL,
+                        updated_at TEXT NOT NULL,
+                        FOREIGN KEY (tenant_id) REFERENCES tenants (id) ON DELETE CASCADE
+                    );
+
+                    CREATE TABLE IF NOT EXISTS directory_sync_runs (
+                        id TEXT PRIMARY KEY,
+                        tenant_id TEXT NOT NULL,
+                        dry_run INTEGER NOT NULL,
+                        created INTEGER NOT NULL DEFAULT 0,
+                        updated INTEGER NOT NULL DEFAULT 0,
+                        adopted INTEGER NOT NULL DEFAULT 0,
+                        removed INTEGER NOT NULL DEFAULT 0,
+                        conflicts INTEGER NOT NULL DEFAULT 0,
+                        error TEXT,
+                        started_at TEXT NOT NULL,
+                        finished_at TEXT NOT NULL
+                    );
+                    CREATE INDEX IF NOT EXISTS idx_directory_sync_runs_tenant ON directory_sync_runs(tenant_id, started_at);
+                "#.to_string(),
+                down_sql: Some(r#"
+                    DROP TABLE IF EXISTS directory_sync_runs;
+                    DROP TABLE IF EXISTS directory_configs;
+                    DROP INDEX IF EXISTS idx_users_directory_dn;
+                    ALTER TABLE users DROP COLUMN directory_dn;
+                "#.to_string()),
+            },
+            Migration {
+                version: 24,
+                name: "create_user_sessions_table".to_string(),
+                sql: r#"
+                    CREATE TABLE IF NOT EXISTS user_sessions (
+                        id TEXT PRIMARY KEY,
+                        user_id TEXT NOT NULL,
+                        tenant_id TEXT NOT NULL,
+                        device TEXT,
+                        ip_address TEXT,
+                        created_at TEXT NOT NULL,
+                        last_seen_at TEXT NOT NULL,
+                        expires_at TEXT NOT NULL,
+                        revoked_at TEXT,
+                        FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+                    );
+                    CREATE INDEX IF NOT EXISTS idx_user_sessions_user ON user_sessions(user_id, last_seen_at);
+                "#.to_string(),
+                down_sql: Some(r#"
+                    DROP TABLE IF EXISTS user_sessions;
+                "#.to_string()),
+            },
+            Migration {
+                version: 25,
+                name: "create_replication_state_table".to_string(),
+                sql: r#"
+                    CREATE TABLE IF NOT EXISTS replication_state (
+                        id INTEGER PRIMARY KEY CHECK (id = 1),
+                        position INTEGER NOT NULL,
+                        updated_at TEXT NOT NULL
+                    );
+                "#.to_string(),
+                down_sql: Some(r#"
+                    DROP TABLE IF EXISTS replication_state;
+                "#.to_string()),
+            },
+            Migration {
+                version: 26,
+                name: "create_workflow_tables".to_string(),
+                sql: r#"
+                    CREATE TABLE IF NOT EXISTS workflow_runs (
+                        id TEXT PRIMARY KEY,
+                        workflow_id TEXT NOT NULL,
+                        tenant_id TEXT NOT NULL,
+                        status TEXT NOT NULL,
+                        definition TEXT NOT NULL,
+                        context TEXT NOT NULL,
+                        output TEXT,
+                        error TEXT,
+                        created_at TEXT NOT NULL,
+                        updated_at TEXT NOT NULL
+                    );
+                    CREATE INDEX IF NOT EXISTS idx_workflow_runs_tenant ON workflow_runs(tenant_id, created_at);
+
+                    CREATE TABLE IF NOT EXISTS workflow_step_runs (
+                        run_id TEXT NOT NULL,
+                        step_id TEXT NOT NULL,
+                        iteration INTEGER NOT NULL,
+                        status TEXT NOT NULL,
+                        execution_id TEXT,
+                        output TEXT,
+                        error TEXT,
+                        attempts INTEGER NOT NULL DEFAULT 0,
+                        updated_at TEXT NOT NULL,
+                        PRIMARY KEY (run_id, step_id, iteration),
+                        FOREIGN KEY (run_id) REFERENCES workflow_runs (id) ON DELETE CASCADE
+                    );
+                "#.to_string(),
+                down_sql: Some(r#"
+                    DROP TABLE IF EXISTS workflow_step_runs;
+                    DROP TABLE IF EXISTS workflow_runs;
+                "#.to_string()),
+            },
+            Migration {
+                version: 27,
+                name: "create_workflow_approvals_table".to_string(),
+                sql: r#"
+                    CREATE TABLE IF NOT EXISTS workflow_approvals (
+                        id TEXT PRIMARY KEY,
+                        run_id TEXT NOT NULL,
+                        workflow_id TEXT NOT NULL,
+                        step_id TEXT NOT NULL,
+                        tenant_id TEXT NOT NULL,
+                        title TEXT NOT NULL,
+                        approvers TEXT NOT NULL,
+                        status TEXT NOT NULL,
+                        decided_by TEXT,
+                        comment TEXT,
+                        expires_at TEXT,
+                        created_at TEXT NOT NULL,
+                        decided_at TEXT,
+                        FOREIGN KEY (run_id) REFERENCES workflow_runs (id) ON DELETE CASCADE
+                    );
+                    CREATE INDEX IF NOT EXISTS idx_workflow_approvals_tenant ON workflow_approvals(tenant_id, status);
+                    CREATE INDEX IF NOT EXISTS idx_workflow_approvals_run ON workflow_approvals(run_id, step_id);
+                "#.to_string(),
+                down_sql: Some(r#"
+                    DROP TABLE IF EXISTS workflow_approvals;
+                "#.to_string()),
+            },
+            Migration {
+                version: 28,
+                name: "create_workflow_versions_table".to_string(),
+                sql: r#"
+                    CREATE TABLE IF NOT EXISTS workflow_versions (
+                        tenant_id TEXT NOT NULL,
+                        workflow_id TEXT NOT NULL,
+                        version INTEGER NOT NULL,
+                        definition TEXT NOT NULL,
+                        breaking INTEGER NOT NULL DEFAULT 0,
+                        published_by TEXT,
+                        published_at TEXT NOT NULL,
+                        PRIMARY KEY (tenant_id, workflow_id, version)
+                    );
+
+                    CREATE TABLE IF NOT EXISTS workflow_migration_policies (
+                        tenant_id TEXT NOT NULL,
+                        workflow_id TEXT NOT NULL,
+                        policy TEXT NOT NULL,
+                        updated_at TEXT NOT NULL,
+                        PRIMARY KEY (tenant_id, workflow_id)
+                    );
+
+                    ALTER TABLE workflow_runs ADD COLUMN workflow_version INTEGER;
+                    CREATE INDEX IF NOT EXISTS idx_workflow_runs_workflow ON workflow_runs(tenant_id, workflow_id, status);
+                "#.to_string(),
+                down_sql: Some(r#"
+                    DROP INDEX IF EXISTS idx_workflow_runs_workflow;
+                    ALTER TABLE workflow_runs DROP COLUMN workflow_version;
+                    DROP TABLE IF EXISTS workflow_migration_policies;
+                    DROP TABLE IF EXISTS workflow_versions;
+                "#.to_string()),
+            },
+            Migration {
+                version: 29,
+                name: "store_missing_optional_text_as_null".to_string(),
+                sql: r#"
+                    UPDATE tools SET
+                        version_pre_release = NULLIF(version_pre_release, ''),
+                        version_build = NULLIF(version_build, ''),
+                        repository = NULLIF(repository, ''),
+                        documentation = NULLIF(documentation, '');
+                    UPDATE tenants SET domain = NULLIF(domain, '');
+                    UPDATE executions SET completed_at = NULLIF(completed_at, '');
+                    UPDATE execution_results SET error = NULLIF(error, '');
+                "#.to_string(),
+                down_sql: Some(r#"
+                    -- Missing values were stored as '' before; NULL reads back the same way, nothing to restore
+                    SELECT 1;
+                "#.to_string()),
+            },
+            Migration {
+                version: 30,
+                name: "add_tenant_to_alert_rules".to_string(),
+                sql: r#"
+                    -- Rules created before tenant scoping have no owner; they stay in place but are never evaluated
+                    ALTER TABLE alert_rules ADD COLUMN tenant_id TEXT;
+                    CREATE INDEX IF NOT EXISTS idx_alert_rules_tenant ON alert_rules(tenant_id);
+                "#.to_string(),
+                down_sql: Some(r#"
+                    DROP INDEX IF EXISTS idx_alert_rules_tenant;
+                    ALTER TABLE alert_rules DROP COLUMN tenant_id;
+                "#.to_string()),
+            },
+            Migration {
+                version: 31,
+                name: "add_totp_last_step".to_string(),
+                sql: r#"
+                    -- Time step of the last accepted TOTP code, codes at or before it are rejected as replays
+                    ALTER TABLE users ADD COLUMN totp_last_step INTEGER;
+                "#.to_string(),
+                down_sql: Some(r#"
+                    ALTER TABLE users DROP COLUMN totp_last_step;
+                "#.to_string()),
+            },
+            Migration {
+                version: 32,
+                name: "create_tool_rollouts_table".to_string(),
+                sql: r#"
+                    -- Candidate is the full ToolInfo being rolled out, thresholds the promotion/rollback policy (JSON)
+                    CREATE TABLE IF NOT EXISTS tool_rollouts (
+                        id TEXT PRIMARY KEY,
+                        tool_id TEXT NOT NULL,
+                        stable_version TEXT NOT NULL,
+                        candidate TEXT NOT NULL,
+                        percentage INTEGER NOT NULL,
+                        thresholds TEXT NOT NULL,
+                        status TEXT NOT NULL,
+                        stable_executions INTEGER NOT NULL DEFAULT 0,
+                        stable_failures INTEGER NOT NULL DEFAULT 0,
+                        stable_duration_ms INTEGER NOT NULL DEFAULT 0,
+                        candidate_executions INTEGER NOT NULL DEFAULT 0,
+                        candidate_failures INTEGER NOT NULL DEFAULT 0,
+                        candidate_duration_ms INTEGER NOT NULL DEFAULT 0,
+                        decision_reason TEXT,
+                        created_at TEXT NOT NULL,
+                        updated_at TEXT NOT NULL
+                    );
+                    CREATE INDEX IF NOT EXISTS idx_tool_rollouts_tool ON tool_rollouts(tool_id, created_at);
+                    -- At most one rollout in progress per tool
+                    CREATE UNIQUE INDEX IF NOT EXISTS idx_tool_rollouts_active ON tool_rollouts(tool_id) WHERE status = 'active';
+                "#.to_string(),
+                down_sql: Some(r#"
+                    DROP INDEX IF EXISTS idx_tool_rollouts_active;
+                    DROP INDEX IF EXISTS idx_tool_rollouts_tool;
+                    DROP TABLE IF EXISTS tool_rollouts;
+                "#.to_string()),
+            },
+            Migration {
+                version: 33,
+                name: "add_tool_environment_label".to_string(),
+                sql: r#"
+                    -- dev/staging/prod, NULL for tools usable in every environment
+                    ALTER TABLE tools ADD COLUMN environment_label TEXT;
+                    CREATE INDEX IF NOT EXISTS idx_tools_environment_label ON tools(environment_label);
+                "#.to_string(),
+                down_sql: Some(r#"
+                    DROP INDEX IF EXISTS idx_tools_environment_label;
+                    ALTER TABLE tools DROP COLUMN environment_label;
+                "#.to_string()),
+            },
+            Migration {
+                version: 34,
+                name: "add_tool_requirements".to_string(),
+                sql: r#"
+                    -- JSON object with hard `capabilities` and soft `preferred` worker capabilities
+                    ALTER TABLE tools ADD COLUMN requirements TEXT;
+                "#.to_string(),
+                down_sql: Some(r#"
+                    ALTER TABLE tools DROP COLUMN requirements;
+                "#.to_string()),
+            },
+            Migration {
+                version: 35,
+                name: "create_result_chunks_table".to_string(),
+                sql: r#"
+                    -- Outputs larger than one chunk live here; output_data is NULL for them
+                    CREATE TABLE IF NOT EXISTS result_chunks (
+                        execution_id TEXT NOT NULL,
+                        chunk_index INTEGER NOT NULL,
+                        data TEXT NOT NULL,
+                        PRIMARY KEY (execution_id, chunk_index)
+                    );
+                    ALTER TABLE execution_results ADD COLUMN output_chunks INTEGER NOT NULL DEFAULT 0;
+                "#.to_string(),
+                down_sql: Some(r#"
+                    ALTER TABLE execution_results DROP COLUMN output_chunks;
+                    DROP TABLE IF EXISTS result_chunks;
+                "#.to_string()),
+            },
+            Migration {
+                version: 36,
+                name: "create_execution_annotation_tables".to_string(),
+                sql: r#"
+                    CREATE TABLE IF NOT EXISTS execution_labels (
+                        execution_id TEXT NOT NULL,
+                        tenant_id TEXT NOT NULL,
+                        key TEXT NOT NULL,
+                        value TEXT NOT NULL,
+                        PRIMARY KEY (execution_id, key)
+                    );
+                    CREATE INDEX IF NOT EXISTS idx_execution_labels_key_value ON execution_labels(tenant_id, key, value);
+
+                    CREATE TABLE IF NOT EXISTS execution_notes (
+                        id TEXT PRIMARY KEY,
+                        execution_id TEXT NOT NULL,
+                        tenant_id TEXT NOT NULL,
+                        author TEXT NOT NULL,
+                        body TEXT NOT NULL,
+                        created_at TEXT NOT NULL
+                    );
+                    CREATE INDEX IF NOT EXISTS idx_execution_notes_execution ON execution_notes(execution_id);
+
+                    -- Per-user execution list filters, stored as JSON
+                    CREATE TABLE IF NOT EXISTS saved_execution_views (
+                        id TEXT PRIMARY KEY,
+                        tenant_id TEXT NOT NULL,
+                        user_id TEXT NOT NULL,
+                        name TEXT NOT NULL,
+                        filter TEXT NOT NULL,
+                        created_at TEXT NOT NULL,
+                        updated_at TEXT NOT NULL,
+                        UNIQUE (tenant_id, user_id, name)
+                    );
+                "#.to_string(),
+                down_sql: Some(r#"
+                    DROP TABLE IF EXISTS saved_execution_views;
+                    DROP INDEX IF EXISTS idx_execution_notes_execution;
+                    DROP TABLE IF EXISTS execution_notes;
+                    DROP INDEX IF EXISTS idx_execution_labels_key_value;
+                    DROP TABLE IF EXISTS execution_labels;
+                "#.to_string()),
+            },
+            Migration {
+                version: 37,
+                name: "create_tenant_data_keys_table".to_string(),
+                sql: r#"
+                    -- Per-tenant data encryption keys, stored wrapped by a master key
+                    CREATE TABLE IF NOT EXISTS tenant_data_keys (
+                        tenant_id TEXT NOT NULL,
+                        version INTEGER NOT NULL,
+                        wrapped_key TEXT NOT NULL,
+                        master_key_id TEXT NOT NULL,
+                        status TEXT NOT NULL,
+                        created_at TEXT NOT NULL,
+                        rewrapped_at TEXT,
+                        retired_at TEXT,
+                        PRIMARY KEY (tenant_id, version)
+                    );
+                    CREATE UNIQUE INDEX IF NOT EXISTS idx_tenant_data_keys_active
+                        ON tenant_data_keys(tenant_id) WHERE status = 'active';
+                    CREATE INDEX IF NOT EXISTS idx_tenant_data_keys_master ON tenant_data_keys(master_key_id);
+                "#.to_string(),
+                down_sql: Some(r#"
+                    DROP INDEX IF EXISTS idx_tenant_data_keys_master;
+                    DROP INDEX IF EXISTS idx_tenant_data_keys_active;
+                    DROP TABLE IF EXISTS tenant_data_keys;
+                "#.to_string()),
+            },
+            Migration {
+                version: 38,
+                name: "create_operational_mode_table".to_string(),
+                sql: r#"
+                    -- System-wide maintenance / read-only switch, a single row
+                    CREATE TABLE IF NOT EXISTS operational_mode (
+                        id INTEGER PRIMARY KEY CHECK (id = 1),
+                        mode TEXT NOT NULL,
+                        message TEXT,
+                        updated_by TEXT,
+                        updated_at TEXT NOT NULL
+                    );
+                "#.to_string(),
+                down_sql: Some(r#"
+                    DROP TABLE IF EXISTS operational_mode;
+                "#.to_string()),
+            },
+            Migration {
+                version: 39,
+                name: "create_background_jobs_table".to_string(),
+                sql: r#"
+                    -- Schedule, lease and last run of internal background jobs
+                    CREATE TABLE IF NOT EXISTS background_jobs (
+                        name TEXT PRIMARY KEY,
+                        next_run_at TEXT,
+                        lease_owner TEXT,
+                        lease_until TEXT,
+                        last_started_at TEXT,
+                        last_finished_at TEXT,
+                        last_status TEXT,
+                        last_message TEXT,
+                        last_instance TEXT,
+                        last_manual INTEGER NOT NULL DEFAULT 0,
+                        updated_at TEXT NOT NULL
+                    );
+                "#.to_string(),
+                down_sql: Some(r#"
+                    DROP TABLE IF EXISTS background_jobs;
+                "#.to_string()),
+            },
+            Migration {
+                version: 40,
+                name: "create_leader_leases_table".to_string(),
+                sql: r#"
+                    -- Leadership leases for multi-instance deployments, one row per election
+                    CREATE TABLE IF NOT EXISTS leader_leases (
+                        name TEXT PRIMARY KEY,
+                        holder TEXT NOT NULL,
+                        term INTEGER NOT NULL DEFAULT 1,
+                        acquired_at TEXT NOT NULL,
+                        expires_at TEXT NOT NULL
+                    );
+                "#.to_string(),
+                down_sql: Some(r#"
+                    DROP TABLE IF EXISTS leader_leases;
+                "#.to_string()),
+            },
+            Migration {
+                version: 41,
+                name: "add_task_leases".to_string(),
+                sql: r#"
+                    -- Shared task queue: the instance running a task holds a lease on it
+                    -- and renews it with heartbeats
+                    ALTER TABLE tasks ADD COLUMN claimed_by TEXT;
+                    ALTER TABLE tasks ADD COLUMN lease_until TEXT;
+                    ALTER TABLE tasks ADD COLUMN heartbeat_at TEXT;
+                    ALTER TABLE tasks ADD COLUMN attempts INTEGER NOT NULL DEFAULT 0;
+                    ALTER TABLE tasks ADD COLUMN updated_at TEXT;
+                    CREATE INDEX IF NOT EXISTS idx_tasks_claim ON tasks(status, lease_until);
+                "#.to_string(),
+                down_sql: Some(r#"
+                    DROP INDEX IF EXISTS idx_tasks_claim;
+                    ALTER TABLE tasks DROP COLUMN updated_at;
+                    ALTER TABLE tasks DROP COLUMN attempts;
+                    ALTER TABLE tasks DROP COLUMN heartbeat_at;
+                    ALTER TABLE tasks DROP COLUMN lease_until;
+                    ALTER TABLE tasks DROP COLUMN claimed_by;
+                "#.to_string()),
+            },
+            Migration {
+                version: 42,
+                name: "create_event_sink_cursors_table".to_string(),
+                sql: r#"
+                    -- Last event delivered to each external event sink, per event source
+                    CREATE TABLE IF NOT EXISTS event_sink_cursors (
+                        sink TEXT NOT NULL,
+                        source TEXT NOT NULL,
+                        position INTEGER NOT NULL DEFAULT 0,
+                        delivered INTEGER NOT NULL DEFAULT 0,
+                        dead_lettered INTEGER NOT NULL DEFAULT 0,
+                        last_error TEXT,
+                        updated_at TEXT NOT NULL,
+                        PRIMARY KEY (sink, source)
+                    );
+                "#.to_string(),
+                down_sql: Some(r#"
+                    DROP TABLE IF EXISTS event_sink_cursors;
+                "#.to_string()),
+            },
+            Migration {
+                version: 43,
+                name: "create_tool_test_runs_table".to_string(),
+                sql: r#"
+                    -- Results holds one entry per example run (JSON)
+                    CREATE TABLE IF NOT EXISTS tool_test_runs (
+                        id TEXT PRIMARY KEY,
+                        tool_id TEXT NOT NULL,
+                        version TEXT NOT NULL,
+                        trigger TEXT NOT NULL,
+                        passed INTEGER NOT NULL,
+                        results TEXT NOT NULL,
+                        started_at TEXT NOT NULL,
+                        finished_at TEXT NOT NULL
+                    );
+                    CREATE INDEX IF NOT EXISTS idx_tool_test_runs_version ON tool_test_runs(tool_id, version, finished_at);
+                "#.to_string(),
+                down_sql: Some(r#"
+                    DROP INDEX IF EXISTS idx_tool_test_runs_version;
+                    DROP TABLE IF EXISTS tool_test_runs;
+                "#.to_string()),
+            },
+            Migration {
+                version: 44,
+                name: "add_tool_input_schema".to_string(),
+                sql: r#"
+                    -- JSON schema of the tool's input parameters
+                    ALTER TABLE tools ADD COLUMN input_schema TEXT;
+                "#.to_string(),
+                down_sql: Some(r#"
+                    ALTER TABLE tools DROP COLUMN input_schema;
+                "#.to_string()),
+            },
+            Migration {
+                version: 45,
+                name: "add_tool_localizations".to_string(),
+                sql: r#"
+                    -- Translated tool names and descriptions keyed by language tag
+                    ALTER TABLE tools ADD COLUMN localizations TEXT;
+                "#.to_string(),
+                down_sql: Some(r#"
+                    ALTER TABLE tools DROP COLUMN localizations;
+                "#.to_string()),
+            },
+            Migration {
+                version: 46,
+                name: "create_tool_presets_table".to_string(),
+                sql: r#"
+                    -- Named parameter presets per tool; an empty user_id shares the preset
+                    -- with the whole tenant
+                    CREATE TABLE IF NOT EXISTS tool_presets (
+                        id TEXT PRIMARY KEY,
+                        tenant_id TEXT NOT NULL,
+                        tool_id TEXT NOT NULL,
+                        user_id TEXT NOT NULL DEFAULT '',
+                        name TEXT NOT NULL,
+                        description TEXT,
+                        parameters TEXT NOT NULL,
+                        created_by TEXT NOT NULL,
+                        created_at TEXT NOT NULL,
+                        updated_at TEXT NOT NULL,
+                        UNIQUE (tenant_id, tool_id, user_id, name)
+                    );
+                "#.to_string(),
+                down_sql: Some(r#"
+                    DROP TABLE IF EXISTS tool_presets;
+                "#.to_string()),
+            },
+            Migration {
+                version: 47,
+                name: "create_execution_payload_metrics_table".to_string(),
+                sql: r#"
+                    -- Input/output sizes, array cardinalities and schema warnings per execution
+                    CREATE TABLE IF NOT EXISTS execution_payload_metrics (
+                        id INTEGER PRIMARY KEY AUTOINCREMENT,
+                        execution_id TEXT NOT NULL,
+                        tenant_id TEXT NOT NULL,
+                        tool_id TEXT NOT NULL,
+                        input_bytes INTEGER NOT NULL,
+                        output_bytes INTEGER NOT NULL,
+                        input_max_array_len INTEGER NOT NULL,
+                        output_max_array_len INTEGER NOT NULL,
+                        schema_warnings INTEGER NOT NULL,
+                        recorded_at TEXT NOT NULL
+                    );
+                    CREATE INDEX IF NOT EXISTS idx_execution_payload_metrics_tenant_time
+                        ON execution_payload_metrics(tenant_id, recorded_at);
+                "#.to_string(),
+                down_sql: Some(r#"
+                    DROP INDEX IF EXISTS idx_execution_payload_metrics_tenant_time;
+                    DROP TABLE IF EXISTS execution_payload_metrics;
+                "#.to_string()),
+            },
+            Migration {
+                version: 48,
+                name: "create_slow_log_tables".to_string(),
+                sql: r#"
+                    -- Statements over the slow query threshold, with their query plans
+                    CREATE TABLE IF NOT EXISTS slow_queries (
+                        id TEXT PRIMARY KEY,
+                        statement TEXT NOT NULL,
+                        duration_ms INTEGER NOT NULL,
+                        success INTEGER NOT NULL,
+                        plan TEXT,
+                        captured_at TEXT NOT NULL
+                    );
+                    CREATE INDEX IF NOT EXISTS idx_slow_queries_captured_at ON slow_queries(captured_at);
+                    CREATE INDEX IF NOT EXISTS idx_slow_queries_statement ON slow_queries(statement);
+
+                    -- Executions that ran far longer than their tool's baseline
+                    CREATE TABLE IF NOT EXISTS slow_executions (
+                        id TEXT PRIMARY KEY,
+                        execution_id TEXT NOT NULL,
+                        tenant_id TEXT NOT NULL,
+                        tool_id TEXT NOT NULL,
+                        duration_ms REAL NOT NULL,
+                        baseline_mean_ms REAL NOT NULL,
+                        ratio REAL NOT NULL,
+                        success INTEGER NOT NULL,
+                        captured_at TEXT NOT NULL
+                    );
+                    CREATE INDEX IF NOT EXISTS idx_slow_executions_tenant_time ON slow_executions(tenant_id, captured_at);
+                "#.to_string(),
+                down_sql: Some(r#"
+                    DROP TABLE IF EXISTS slow_executions;
+                    DROP TABLE IF EXISTS slow_queries;
+                "#.to_string()),
+            },
+            Migration {
+                version: 49,
+                name: "create_feature_flags_table".to_string(),
+                sql: r#"
+                    -- Feature flags changed at runtime; they take precedence over the config file
+                    CREATE TABLE IF NOT EXISTS feature_flags (
+                        name TEXT PRIMARY KEY,
+                        description TEXT,
+                        enabled INTEGER NOT NULL,
+                        rollout_percentage INTEGER NOT NULL,
+                        tenants TEXT NOT NULL,
+                        users TEXT NOT NULL,
+                        updated_by TEXT,
+                        updated_at TEXT NOT NULL
+                    );
+                "#.to_string(),
+                down_sql: Some(r#"
+                    DROP TABLE IF EXISTS feature_flags;
+                "#.to_string()),
+            },
+            Migration {
+                version: 50,
+                name: "create_notification_tables".to_string(),
+                sql: r#"
+                    CREATE TABLE IF NOT EXISTS notification_channels (
+                        id TEXT PRIMARY KEY,
+                        tenant_id TEXT NOT NULL,
+                        name TEXT NOT NULL,
+                        target TEXT NOT NULL,
+                        events TEXT NOT NULL,
+                        subject_template TEXT,
+                        body_template TEXT,
+                        enabled INTEGER NOT NULL DEFAULT 1,
+                        created_at TEXT NOT NULL
+                    );
+                    CREATE INDEX IF NOT EXISTS idx_notification_channels_tenant ON notification_channels(tenant_id);
+
+                    CREATE TABLE IF NOT EXISTS notification_deliveries (
+                        id TEXT PRIMARY KEY,
+                        channel_id TEXT NOT NULL,
+                        tenant_id TEXT NOT NULL,
+                        event TEXT NOT NULL,
+                        subject TEXT NOT NULL,
+                        status TEXT NOT NULL,
+                        error TEXT,
+                        created_at TEXT NOT NULL
+                    );
+                    CREATE INDEX IF NOT EXISTS idx_notification_deliveries_tenant_time ON notification_deliveries(tenant_id, created_at);
+                "#.to_string(),
+                down_sql: Some(r#"
+                    DROP TABLE IF EXISTS notification_deliveries;
+                    DROP TABLE IF EXISTS notification_channels;
+                "#.to_string()),
+            },
+            Migration {
+                version: 51,
+                name: "create_tool_docs_table".to_string(),
+                sql: r#"
+                    -- Markdown documentation per tool version
+                    CREATE TABLE IF NOT EXISTS tool_docs (
+                        tool_id TEXT NOT NULL,
+                        version TEXT NOT NULL,
+                        markdown TEXT NOT NULL,
+                        updated_by TEXT,
+                        updated_at TEXT NOT NULL,
+                        PRIMARY KEY (tool_id, version)
+                    );
+                "#.to_string(),
+                down_sql: Some(r#"
+                    DROP TABLE IF EXISTS tool_docs;
+                "#.to_string()),
+            },
+            Migration {
+                version: 52,
+                name: "add_tool_environment".to_string(),
+                sql: r#"
+                    -- Default environment variables of the tool's executions
+                    ALTER TABLE tools ADD COLUMN environment TEXT;
+                "#.to_string(),
+                down_sql: Some(r#"
+                    ALTER TABLE tools DROP COLUMN environment;
+                "#.to_string()),
+            },
+            Migration {
+                version: 53,
+                name: "create_event_schemas_table".to_string(),
+                sql: r#"
+                    -- JSON Schema of each event payload schema version
+                    CREATE TABLE IF NOT EXISTS event_schemas (
+                        name TEXT NOT NULL,
+                        version INTEGER NOT NULL,
+                        json_schema TEXT NOT NULL,
+                        compatibility TEXT NOT NULL,
+                        description TEXT,
+                        created_by TEXT,
+                        created_at TEXT NOT NULL,
+                        PRIMARY KEY (name, version)
+                    );
+                "#.to_string(),
+                down_sql: Some(r#"
+                    DROP TABLE IF EXISTS event_schemas;
+                "#.to_string()),
+            },
+            Migration {
+                version: 54,
+                name: "add_tool_session".to_string(),
+                sql: r#"
+                    -- Command the tool runs in interactive sessions
+                    ALTER TABLE tools ADD COLUMN session TEXT;
+                "#.to_string(),
+                down_sql: Some(r#"
+                    ALTER TABLE tools DROP COLUMN session;
+                "#.to_string()),
+            },
+            Migration {
+                version: 55,
+                name: "create_tool_listing_versions_table".to_string(),
+                sql: r#"
+                    -- The tool as it was at each version published through a listing
+                    CREATE TABLE IF NOT EXISTS tool_listing_versions (
+                        listing_id TEXT NOT NULL,
+                        version TEXT NOT NULL,
+                        tool TEXT NOT NULL,
+                        published_at TEXT NOT NULL,
+                        PRIMARY KEY (listing_id, version),
+                        FOREIGN KEY (listing_id) REFERENCES tool_listings (id) ON DELETE CASCADE
+                    );
+                "#.to_string(),
+                down_sql: Some(r#"
+                    DROP TABLE IF EXISTS tool_listing_versions;
+                "#.to_string()),
+            },
+            Migration {
+                version: 56,
+                name: "add_tool_image".to_string(),
+                sql: r#"
+                    -- Container image of the tool version and its pinned digest
+                    ALTER TABLE tools ADD COLUMN image TEXT;
+                "#.to_string(),
+                down_sql: Some(r#"
+                    ALTER TABLE tools DROP COLUMN image;
+                "#.to_string()),
+            },
+            Migration {
+                version: 57,
+                name: "add_tenant_to_execution_anomalies".to_string(),
+                sql: r#"
+                    -- Tenant that ran the anomalous execution; anomalies of purged executions stay unowned and hidden
+                    ALTER TABLE execution_anomalies ADD COLUMN tenant_id TEXT;
+                    UPDATE execution_anomalies SET tenant_id = (
+                        SELECT tenant_id FROM executions WHERE executions.id = execution_anomalies.execution_id
+                    );
+                    CREATE INDEX IF NOT EXISTS idx_execution_anomalies_tenant ON execution_anomalies(tenant_id);
+                "#.to_string(),
+                down_sql: Some(r#"
+                    DROP INDEX IF EXISTS idx_execution_anomalies_tenant;
+                    ALTER TABLE execution_anomalies DROP COLUMN tenant_id;
+                "#.to_string()),
             },
         ]
     }
-} 
\ No newline at end of file
+}
+
+fn row_to_migration_record(row: &HashMap<String, serde_json::Value>) -> MigrationRecord {
+    let text = |column: &str| row.get(column).and_then(|v| v.as_str()).unwrap_or("").to_string();
+    MigrationRecord {
+        version: row.get("version").and_then(|v| v.as_i64()).unwrap_or(0) as u32,
+        name: text("name"),
+        applied_at: text("applied_at"),
+        checksum: row.get("checksum").and_then(|v| v.as_str()).map(|s| s.to_string()),
+    }
+}
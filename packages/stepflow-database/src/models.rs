@@ -26,10 +26,20 @@ pub struct ToolModel {
     pub examples: Option<String>, // JSON array
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub environment_label: Option<String>,
+    pub requirements: Option<String>, // JSON object
+    pub input_schema: Option<String>, // JSON object
+    pub localizations: Option<String>, // JSON object
+    pub environment: Option<String>, // JSON object
+    pub session: Option<String>, // JSON object
+    pub image: Option<String>, // JSON object
 }
 
-impl From<ToolModel> for ToolInfo {
-    fn from(model: ToolModel) -> Self {
+/// Rows that no longer pass [`ToolInfo::builder`] validation are rejected
+impl TryFrom<ToolModel> for ToolInfo {
+    type Error = ValidationError;
+
+    fn try_from(model: ToolModel) -> Result<Self, Self::Error> {
         let version = ToolVersion {
             major: model.version_major,
             minor: model.version_minor,
@@ -74,23 +84,51 @@ impl From<ToolModel> for ToolInfo {
             .and_then(|e| serde_json::from_str(&e).ok())
             .unwrap_or_default();
 
-        Self {
-            id: ToolId::from_string(model.id),
-            name: model.name,
-            description: model.description.unwrap_or_default(),
-            version,
-            tool_type,
-            status,
-            author: model.author,
-            repository: model.repository,
-            documentation: model.documentation,
-            tags,
-            capabilities,
-            configuration_schema,
-            examples,
-            created_at: model.created_at,
-            updated_at: model.updated_at,
-        }
+        let requirements = model.requirements
+            .and_then(|r| serde_json::from_str(&r).ok())
+            .unwrap_or_default();
+
+        let input_schema = model.input_schema
+            .and_then(|s| serde_json::from_str(&s).ok());
+
+        let localizations = model.localizations
+            .and_then(|l| serde_json::from_str(&l).ok())
+            .unwrap_or_default();
+
+        let environment = model.environment
+            .and_then(|e| serde_json::from_str(&e).ok())
+            .unwrap_or_default();
+
+        let session: Option<ToolSessionCommand> = model.session
+            .and_then(|s| serde_json::from_str(&s).ok());
+
+        let image: Option<ToolImage> = model.image
+            .and_then(|i| serde_json::from_str(&i).ok());
+
+        ToolInfo::builder()
+            .id(ToolId::from_string(model.id))
+            .name(model.name)
+            .description(model.description.unwrap_or_default())
+            .version(version)
+            .tool_type(tool_type)
+            .status(status)
+            .author(model.author)
+            .repository(model.repository)
+            .documentation(model.documentation)
+            .tags::<Vec<String>, String>(tags)
+            .capabilities::<Vec<String>, String>(capabilities)
+            .configuration_schema(configuration_schema)
+            .examples(examples)
+            .created_at(model.created_at)
+            .updated_at(model.updated_at)
+            .environment_label(model.environment_label.as_deref().and_then(EnvironmentLabel::parse))
+            .requirements(requirements)
+            .input_schema(input_schema)
+            .localizations(localizations)
+            .environment(environment)
+            .session(session)
+            .image(image)
+            .build()
     }
 }
 
@@ -137,6 +175,17 @@ impl From<ToolInfo> for ToolModel {
             examples: serde_json::to_string(&info.examples).ok(),
             created_at: info.created_at,
             updated_at: info.updated_at,
+            environment_label: info.environment_label.map(|label| label.as_str().to_string()),
+            requirements: serde_json::to_string(&info.requirements).ok(),
+            input_schema: info.input_schema
+                .map(|s| serde_json::to_string(&s))
+                .transpose()
+                .ok()
+                .flatten(),
+            localizations: serde_json::to_string(&info.localizations).ok(),
+            environment: serde_json::to_string(&info.environment).ok(),
+            session: info.session.as_ref().and_then(|s| serde_json::to_string(s).ok()),
+            image: info.image.as_ref().and_then(|i| serde_json::to_string(i).ok()),
         }
     }
 }
@@ -176,17 +225,17 @@ impl From<ExecutionModel> for Execution {
             _ => ExecutionStatus::Pending,
         };
 
-        let request = ToolRequest {
-            tool_id: ToolId::from_string(model.tool_id.clone()),
-            input: model.request_input
+        let mut request = ToolRequest::new(
+            ToolId::from_string(model.tool_id.clone()),
+            model.request_input
                 .and_then(|i| serde_json::from_str(&i).ok())
                 .unwrap_or_default(),
-            configuration: model.request_configuration
-                .and_then(|c| serde_json::from_str(&c).ok()),
-            metadata: model.request_metadata
-                .and_then(|m| serde_json::from_str(&m).ok())
-                .unwrap_or_default(),
-        };
+        );
+        request.configuration = model.request_configuration
+            .and_then(|c| serde_json::from_str(&c).ok());
+        request.metadata = model.request_metadata
+            .and_then(|m| serde_json::from_str(&m).ok())
+            .unwrap_or_default();
 
         let result = if model.result_success.is_some() {
             Some(ExecutionResult {
@@ -336,21 +385,27 @@ pub struct UserModel {
     pub role: String,
     pub tenant_id: String,
     pub settings: Option<String>, // JSON object
+    pub email_verified: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+/// Parse a role as stored in the database, falling back to `User`
+pub(crate) fn parse_user_role(role: &str) -> UserRole {
+    match role {
+        "admin" => UserRole::Admin,
+        "user" => UserRole::User,
+        "guest" => UserRole::Guest,
+        custom if custom.starts_with("custom:") => {
+            UserRole::Custom(custom[7..].to_string())
+        }
+        _ => UserRole::User,
+    }
+}
+
 impl From<UserModel> for UserInfo {
     fn from(model: UserModel) -> Self {
-        let role = match model.role.as_str() {
-            "admin" => UserRole::Admin,
-            "user" => UserRole::User,
-            "guest" => UserRole::Guest,
-            custom if custom.starts_with("custom:") => {
-                UserRole::Custom(custom[7..].to_string())
-            }
-            _ => UserRole::User,
-        };
+        let role = parse_user_role(&model.role);
 
         Self {
             id: UserId::from_string(model.id),
@@ -361,6 +416,7 @@ impl From<UserModel> for UserInfo {
             settings: model.settings
                 .and_then(|s| serde_json::from_str(&s).ok())
                 .unwrap_or_default(),
+            email_verified: model.email_verified,
             created_at: model.created_at,
             updated_at: model.updated_at,
         }
@@ -384,6 +440,7 @@ impl From<UserInfo> for UserModel {
             role,
             tenant_id: info.tenant_id.as_str().to_string(),
             settings: serde_json::to_string(&info.settings).ok(),
+            email_verified: info.email_verified,
             created_at: info.created_at,
             updated_at: info.updated_at,
         }
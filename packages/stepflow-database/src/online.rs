@@ -0,0 +1,542 @@
+//! Online table rebuilds
+//!
+//! SQLite can't change most column definitions in place, and rewriting a large table
+//! in one statement holds the write lock for as long as the copy takes. An
+//! [`OnlineRebuild`] instead:
+//!
+//! 1. creates a shadow table with the new definition,
+//! 2. installs triggers that mirror every insert, update and delete on the original
+//!    table into the shadow table (the dual-write window),
+//! 3. backfills existing rows in small batches, each in its own short transaction, so
+//!    other writers get the lock between batches,
+//! 4. swaps the tables in one transaction: the triggers and the original table are
+//!    dropped and the shadow table takes its name.
+//!
+//! Progress is kept in `online_rebuilds`, so an interrupted backfill resumes where it
+//! stopped. Only rowid tables are supported, and the key column must keep its name.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use sqlx::{Row, SqliteConnection};
+use stepflow_core::{DatabaseError, StepflowError, StepflowResult};
+use tracing::{info, warn};
+
+use crate::{MigrationManager, SqliteDatabase};
+
+/// How a table is rebuilt
+#[derive(Debug, Clone)]
+pub struct OnlineRebuild {
+    pub table: String,
+    /// Column identifying a row in both the old and the new table
+    pub key: String,
+    /// Column definitions and table constraints of the new table
+    pub definition: String,
+    /// New columns computed from the old row, as `(column, expression)`; other columns
+    /// present in both tables are copied by name, the rest take their default
+    pub computed: Vec<(String, String)>,
+    /// `CREATE INDEX` statements for the new table, run during the swap
+    pub indexes: Vec<String>,
+    /// Rows copied per backfill transaction
+    pub batch_size: usize,
+    /// Pause between backfill batches, leaving room for other writers
+    pub batch_pause: Duration,
+}
+
+impl OnlineRebuild {
+    pub fn new(table: impl Into<String>, key: impl Into<String>, definition: impl Into<String>) -> Self {
+        Self {
+            table: table.into(),
+            key: key.into(),
+            definition: definition.into(),
+            computed: Vec::new(),
+            indexes: Vec::new(),
+            batch_size: 1000,
+            batch_pause: Duration::from_millis(10),
+        }
+    }
+
+    /// Fill `column` with `expression`, evaluated against the old row
+    pub fn compute(mut self, column: impl Into<String>, expression: impl Into<String>) -> Self {
+        self.computed.push((column.into(), expression.into()));
+        self
+    }
+
+    pub fn index(mut self, sql: impl Into<String>) -> Self {
+        self.indexes.push(sql.into());
+        self
+    }
+
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    pub fn batch_pause(mut self, pause: Duration) -> Self {
+        self.batch_pause = pause;
+        self
+    }
+
+    fn shadow(&self) -> String {
+        format!("_online_{}", self.table)
+    }
+
+    fn trigger(&self, operation: &str) -> String {
+        format!("_online_{}_{}", self.table, operation)
+    }
+
+    fn validate(&self) -> StepflowResult<()> {
+        for name in [&self.table, &self.key].into_iter().chain(self.computed.iter().map(|(column, _)| column)) {
+            if !is_identifier(name) {
+                return Err(rebuild_failed(format!("{:?} is not a plain identifier", name)));
+            }
+        }
+        if self.batch_size == 0 {
+            return Err(rebuild_failed("batch_size must be at least 1".to_string()));
+        }
+        Ok(())
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn rebuild_failed(message: String) -> StepflowError {
+    StepflowError::DatabaseError(DatabaseError::MigrationFailed(format!("Online rebuild failed: {}", message)))
+}
+
+fn sql_failed(context: &str) -> impl Fn(sqlx::Error) -> StepflowError + '_ {
+    move |e| rebuild_failed(format!("{}: {}", context, e))
+}
+
+/// Stage of an online rebuild
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RebuildPhase {
+    Backfilling,
+    Swapping,
+    Completed,
+}
+
+impl RebuildPhase {
+    fn as_str(&self) -> &'static str {
+        match self {
+            RebuildPhase::Backfilling => "backfilling",
+            RebuildPhase::Swapping => "swapping",
+            RebuildPhase::Completed => "completed",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "backfilling" => Some(RebuildPhase::Backfilling),
+            "swapping" => Some(RebuildPhase::Swapping),
+            "completed" => Some(RebuildPhase::Completed),
+            _ => None,
+        }
+    }
+}
+
+/// Progress of an online rebuild
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct RebuildProgress {
+    pub table: String,
+    pub phase: RebuildPhase,
+    /// Rows backfilled so far
+    pub rows_copied: u64,
+    /// Rows in the table when the rebuild started
+    pub total_rows: u64,
+    pub batches: u64,
+    /// Highest rowid backfilled, where a resumed backfill continues
+    pub last_rowid: i64,
+    pub started_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl RebuildProgress {
+    /// Fraction of the rows backfilled, 1.0 once the swap starts
+    pub fn fraction(&self) -> f64 {
+        match self.phase {
+            RebuildPhase::Backfilling if self.total_rows > 0 => (self.rows_copied as f64 / self.total_rows as f64).min(1.0),
+            RebuildPhase::Backfilling => 0.0,
+            RebuildPhase::Swapping | RebuildPhase::Completed => 1.0,
+        }
+    }
+}
+
+impl MigrationManager {
+    /// Rebuild a table without holding the write lock for the whole copy
+    ///
+    /// See [`crate::online`]. `on_progress` is called after every backfill batch and
+    /// once per phase change. A rebuild interrupted during the backfill resumes from
+    /// its last batch when run again with the same definition.
+    pub async fn rebuild_table_online(
+        database: &SqliteDatabase,
+        rebuild: &OnlineRebuild,
+        mut on_progress: impl FnMut(&RebuildProgress) + Send,
+    ) -> StepflowResult<RebuildProgress> {
+        rebuild.validate()?;
+        ensure_rebuilds_table(database).await?;
+
+        let mut progress = match load_progress(database, &rebuild.table).await? {
+            Some(progress) if progress.phase == RebuildPhase::Backfilling && shadow_exists(database, rebuild).await? => {
+                info!("Resuming online rebuild of {} at rowid {}", rebuild.table, progress.last_rowid);
+                progress
+            }
+            _ => start_rebuild(database, rebuild).await?,
+        };
+        on_progress(&progress);
+
+        let copy = copy_columns(database, rebuild).await?;
+        loop {
+            let copied = {
+                let mut connection = database.acquire_writer().await?;
+                let copied = backfill_batch(&mut connection, rebuild, &copy, &mut progress).await?;
+                database.advance_write_position(&mut connection).await;
+                copied
+            };
+            if copied == 0 {
+                break;
+            }
+            on_progress(&progress);
+            if !rebuild.batch_pause.is_zero() {
+                tokio::time::sleep(rebuild.batch_pause).await;
+            }
+        }
+
+        progress.phase = RebuildPhase::Swapping;
+        save_progress(database, &progress).await?;
+        on_progress(&progress);
+        swap(database, rebuild, &mut progress).await?;
+        on_progress(&progress);
+        info!("Rebuilt {} online: {} row(s) in {} batch(es)", rebuild.table, progress.rows_copied, progress.batches);
+        Ok(progress)
+    }
+
+    /// Progress of every online rebuild that was started, newest first
+    pub async fn online_rebuilds(database: &SqliteDatabase) -> StepflowResult<Vec<RebuildProgress>> {
+        ensure_rebuilds_table(database).await?;
+        let rows = sqlx::query(&format!("{} ORDER BY started_at DESC", SELECT_PROGRESS))
+            .fetch_all(database.writer_pool())
+            .await
+            .map_err(sql_failed("reading progress"))?;
+        Ok(rows.iter().filter_map(row_to_progress).collect())
+    }
+}
+
+const SELECT_PROGRESS: &str =
+    "SELECT table_name, phase, rows_copied, total_rows, batches, last_rowid, started_at, updated_at FROM online_rebuilds";
+
+async fn ensure_rebuilds_table(database: &SqliteDatabase) -> StepflowResult<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS online_rebuilds (
+            table_name TEXT PRIMARY KEY,
+            phase TEXT NOT NULL,
+            rows_copied INTEGER NOT NULL,
+            total_rows INTEGER NOT NULL,
+            batches INTEGER NOT NULL,
+            last_rowid INTEGER NOT NULL,
+            started_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(database.writer_pool())
+    .await
+    .map_err(sql_failed("creating online_rebuilds"))?;
+    Ok(())
+}
+
+async fn load_progress(database: &SqliteDatabase, table: &str) -> StepflowResult<Option<RebuildProgress>> {
+    let row = sqlx::query(&format!("{} WHERE table_name = ?", SELECT_PROGRESS))
+        .bind(table)
+        .fetch_optional(database.writer_pool())
+        .await
+        .map_err(sql_failed("reading progress"))?;
+    Ok(row.as_ref().and_then(row_to_progress))
+}
+
+async fn save_progress(database: &SqliteDatabase, progress: &RebuildProgress) -> StepflowResult<()> {
+    let mut connection = database.acquire_writer().await?;
+    save_progress_on(&mut connection, progress).await
+}
+
+async fn save_progress_on(connection: &mut SqliteConnection, progress: &RebuildProgress) -> StepflowResult<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO online_rebuilds (table_name, phase, rows_copied, total_rows, batches, last_rowid, started_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT(table_name) DO UPDATE SET
+            phase = excluded.phase,
+            rows_copied = excluded.rows_copied,
+            total_rows = excluded.total_rows,
+            batches = excluded.batches,
+            last_rowid = excluded.last_rowid,
+            started_at = excluded.started_at,
+            updated_at = excluded.updated_at
+        "#,
+    )
+    .bind(&progress.table)
+    .bind(progress.phase.as_str())
+    .bind(progress.rows_copied as i64)
+    .bind(progress.total_rows as i64)
+    .bind(progress.batches as i64)
+    .bind(progress.last_rowid)
+    .bind(progress.started_at.to_rfc3339())
+    .bind(progress.updated_at.to_rfc3339())
+    .execute(connection)
+    .await
+    .map_err(sql_failed("saving progress"))?;
+    Ok(())
+}
+
+fn row_to_progress(row: &sqlx::sqlite::SqliteRow) -> Option<RebuildProgress> {
+    let time = |column: &str| {
+        row.try_get::<String, _>(column)
+            .ok()
+            .and_then(|value| DateTime::parse_from_rfc3339(&value).ok())
+            .map(|value| value.with_timezone(&Utc))
+    };
+    Some(RebuildProgress {
+        table: row.try_get("table_name").ok()?,
+        phase: RebuildPhase::parse(&row.try_get::<String, _>("phase").ok()?)?,
+        rows_copied: row.try_get::<i64, _>("rows_copied").ok()? as u64,
+        total_rows: row.try_get::<i64, _>("total_rows").ok()? as u64,
+        batches: row.try_get::<i64, _>("batches").ok()? as u64,
+        last_rowid: row.try_get("last_rowid").ok()?,
+        started_at: time("started_at")?,
+        updated_at: time("updated_at")?,
+    })
+}
+
+async fn shadow_exists(database: &SqliteDatabase, rebuild: &OnlineRebuild) -> StepflowResult<bool> {
+    let found: Option<String> = sqlx::query_scalar("SELECT name FROM sqlite_master WHERE type = 'table' AND name = ?")
+        .bind(rebuild.shadow())
+        .fetch_optional(database.writer_pool())
+        .await
+        .map_err(sql_failed("looking up the shadow table"))?;
+    Ok(found.is_some())
+}
+
+async fn table_columns(connection: &mut SqliteConnection, table: &str) -> StepflowResult<Vec<String>> {
+    sqlx::query_scalar("SELECT name FROM pragma_table_info(?)")
+        .bind(table)
+        .fetch_all(connection)
+        .await
+        .map_err(sql_failed("reading table columns"))
+}
+
+/// Target columns and the expressions filling them from the old row
+async fn copy_columns(database: &SqliteDatabase, rebuild: &OnlineRebuild) -> StepflowResult<Vec<(String, String)>> {
+    let mut connection = database.acquire_writer().await?;
+    let old = table_columns(&mut connection, &rebuild.table).await?;
+    let new = table_columns(&mut connection, &rebuild.shadow()).await?;
+    let copy: Vec<(String, String)> = new
+        .into_iter()
+        .filter_map(|column| {
+            let computed = rebuild.computed.iter().find(|(name, _)| *name == column).map(|(_, expression)| expression.clone());
+            let expression = computed.or_else(|| old.contains(&column).then(|| format!("\"{}\"", column)))?;
+            Some((column, expression))
+        })
+        .collect();
+    if !copy.iter().any(|(column, _)| *column == rebuild.key) {
+        return Err(rebuild_failed(format!("key column {} is not in both tables", rebuild.key)));
+    }
+    Ok(copy)
+}
+
+/// Drop a stale shadow table, create a fresh one and start the dual-write window
+async fn start_rebuild(database: &SqliteDatabase, rebuild: &OnlineRebuild) -> StepflowResult<RebuildProgress> {
+    let (table, shadow) = (&rebuild.table, rebuild.shadow());
+    let mut connection = database.acquire_writer().await?;
+    if table_columns(&mut connection, table).await?.is_empty() {
+        return Err(rebuild_failed(format!("table {} does not exist", table)));
+    }
+    let mut transaction = sqlx::Connection::begin(&mut *connection).await.map_err(sql_failed("beginning the rebuild"))?;
+    for operation in ["ins", "upd", "del"] {
+        sqlx::query(&format!("DROP TRIGGER IF EXISTS \"{}\"", rebuild.trigger(operation)))
+            .execute(&mut *transaction)
+            .await
+            .map_err(sql_failed("dropping stale triggers"))?;
+    }
+    sqlx::query(&format!("DROP TABLE IF EXISTS \"{}\"", shadow))
+        .execute(&mut *transaction)
+        .await
+        .map_err(sql_failed("dropping the stale shadow table"))?;
+    sqlx::query(&format!("CREATE TABLE \"{}\" ({})", shadow, rebuild.definition))
+        .execute(&mut *transaction)
+        .await
+        .map_err(sql_failed("creating the shadow table"))?;
+    transaction.commit().await.map_err(sql_failed("creating the shadow table"))?;
+    drop(connection);
+
+    // The shadow table must exist to resolve the copied columns
+    let copy = copy_columns(database, rebuild).await?;
+    let columns = copy.iter().map(|(column, _)| format!("\"{}\"", column)).collect::<Vec<_>>().join(", ");
+    let expressions = copy.iter().map(|(_, expression)| expression.as_str()).collect::<Vec<_>>().join(", ");
+    let mirror = format!(
+        "INSERT OR REPLACE INTO \"{shadow}\" ({columns}) SELECT {expressions} FROM \"{table}\" WHERE rowid = NEW.rowid;"
+    );
+    let forget = format!("DELETE FROM \"{shadow}\" WHERE \"{key}\" = OLD.\"{key}\";", key = rebuild.key);
+    let triggers = [
+        ("ins", "INSERT", mirror.clone()),
+        ("upd", "UPDATE", format!("{} {}", forget, mirror)),
+        ("del", "DELETE", forget.clone()),
+    ];
+
+    let mut connection = database.acquire_writer().await?;
+    let mut transaction = sqlx::Connection::begin(&mut *connection).await.map_err(sql_failed("installing triggers"))?;
+    for (operation, event, body) in triggers {
+        sqlx::query(&format!(
+            "CREATE TRIGGER \"{}\" AFTER {} ON \"{}\" BEGIN {} END",
+            rebuild.trigger(operation), event, table, body
+        ))
+        .execute(&mut *transaction)
+        .await
+        .map_err(sql_failed("installing triggers"))?;
+    }
+    let total_rows: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM \"{}\"", table))
+        .fetch_one(&mut *transaction)
+        .await
+        .map_err(sql_failed("counting rows"))?;
+    let now = Utc::now();
+    let progress = RebuildProgress {
+        table: table.clone(),
+        phase: RebuildPhase::Backfilling,
+        rows_copied: 0,
+        total_rows: total_rows as u64,
+        batches: 0,
+        last_rowid: i64::MIN,
+        started_at: now,
+        updated_at: now,
+    };
+    save_progress_on(&mut transaction, &progress).await?;
+    transaction.commit().await.map_err(sql_failed("installing triggers"))?;
+    database.advance_write_position(&mut connection).await;
+    info!("Started online rebuild of {} ({} row(s))", table, total_rows);
+    Ok(progress)
+}
+
+/// Copy the next batch of rows; rows the triggers already mirrored are left alone
+async fn backfill_batch(
+    connection: &mut SqliteConnection,
+    rebuild: &OnlineRebuild,
+    copy: &[(String, String)],
+    progress: &mut RebuildProgress,
+) -> StepflowResult<u64> {
+    let (table, shadow) = (&rebuild.table, rebuild.shadow());
+    let mut transaction = sqlx::Connection::begin(&mut *connection).await.map_err(sql_failed("backfilling"))?;
+    let rowids: Vec<i64> = sqlx::query_scalar(&format!(
+        "SELECT rowid FROM \"{}\" WHERE rowid > ? ORDER BY rowid LIMIT ?",
+        table
+    ))
+    .bind(progress.last_rowid)
+    .bind(rebuild.batch_size as i64)
+    .fetch_all(&mut *transaction)
+    .await
+    .map_err(sql_failed("backfilling"))?;
+    let Some(&last_rowid) = rowids.last() else {
+        return Ok(0);
+    };
+
+    let columns = copy.iter().map(|(column, _)| format!("\"{}\"", column)).collect::<Vec<_>>().join(", ");
+    let expressions = copy.iter().map(|(_, expression)| expression.as_str()).collect::<Vec<_>>().join(", ");
+    sqlx::query(&format!(
+        "INSERT OR IGNORE INTO \"{shadow}\" ({columns}) SELECT {expressions} FROM \"{table}\" WHERE rowid > ? AND rowid <= ?"
+    ))
+    .bind(progress.last_rowid)
+    .bind(last_rowid)
+    .execute(&mut *transaction)
+    .await
+    .map_err(sql_failed("backfilling"))?;
+
+    progress.last_rowid = last_rowid;
+    progress.rows_copied += rowids.len() as u64;
+    progress.batches += 1;
+    progress.updated_at = Utc::now();
+    save_progress_on(&mut transaction, progress).await?;
+    transaction.commit().await.map_err(sql_failed("backfilling"))?;
+    Ok(rowids.len() as u64)
+}
+
+/// Replace the original table with the shadow table in one transaction
+///
+/// Foreign key enforcement is off during the swap so dropping the original table
+/// doesn't cascade; the new table is checked against every foreign key before commit.
+async fn swap(database: &SqliteDatabase, rebuild: &OnlineRebuild, progress: &mut RebuildProgress) -> StepflowResult<()> {
+    let (table, shadow) = (&rebuild.table, rebuild.shadow());
+    let mut connection = database.acquire_writer().await?;
+    let foreign_keys: bool = sqlx::query_scalar("PRAGMA foreign_keys")
+        .fetch_one(&mut *connection)
+        .await
+        .map_err(sql_failed("reading foreign key enforcement"))?;
+    sqlx::query("PRAGMA foreign_keys = OFF")
+        .execute(&mut *connection)
+        .await
+        .map_err(sql_failed("disabling foreign keys"))?;
+
+    let result = swap_tables(&mut connection, rebuild, table, &shadow, progress).await;
+
+    if foreign_keys {
+        if let Err(e) = sqlx::query("PRAGMA foreign_keys = ON").execute(&mut *connection).await {
+            warn!("Failed to re-enable foreign keys after rebuilding {}: {}", table, e);
+        }
+    }
+    if result.is_ok() {
+        database.advance_write_position(&mut connection).await;
+    }
+    result
+}
+
+async fn swap_tables(
+    connection: &mut SqliteConnection,
+    rebuild: &OnlineRebuild,
+    table: &str,
+    shadow: &str,
+    progress: &mut RebuildProgress,
+) -> StepflowResult<()> {
+    let mut transaction = sqlx::Connection::begin(&mut *connection).await.map_err(sql_failed("swapping"))?;
+    for operation in ["ins", "upd", "del"] {
+        sqlx::query(&format!("DROP TRIGGER IF EXISTS \"{}\"", rebuild.trigger(operation)))
+            .execute(&mut *transaction)
+            .await
+            .map_err(sql_failed("dropping triggers"))?;
+    }
+
+    let count = |name: &str| format!("SELECT COUNT(*) FROM \"{}\"", name);
+    let original: i64 = sqlx::query_scalar(&count(table)).fetch_one(&mut *transaction).await.map_err(sql_failed("swapping"))?;
+    let copied: i64 = sqlx::query_scalar(&count(shadow)).fetch_one(&mut *transaction).await.map_err(sql_failed("swapping"))?;
+    if original != copied {
+        return Err(rebuild_failed(format!(
+            "{} has {} row(s) but its shadow table has {}; nothing was swapped",
+            table, original, copied
+        )));
+    }
+
+    for statement in [format!("DROP TABLE \"{}\"", table), format!("ALTER TABLE \"{}\" RENAME TO \"{}\"", shadow, table)] {
+        sqlx::query(&statement).execute(&mut *transaction).await.map_err(sql_failed("swapping"))?;
+    }
+    for index in &rebuild.indexes {
+        sqlx::query(index).execute(&mut *transaction).await.map_err(sql_failed("creating indexes"))?;
+    }
+    let violations = sqlx::query("PRAGMA foreign_key_check")
+        .fetch_all(&mut *transaction)
+        .await
+        .map_err(sql_failed("checking foreign keys"))?;
+    if !violations.is_empty() {
+        return Err(rebuild_failed(format!(
+            "{} foreign key violation(s) after rebuilding {}; nothing was swapped",
+            violations.len(), table
+        )));
+    }
+
+    progress.phase = RebuildPhase::Completed;
+    progress.updated_at = Utc::now();
+    save_progress_on(&mut transaction, progress).await?;
+    transaction.commit().await.map_err(sql_failed("swapping"))?;
+    Ok(())
+}
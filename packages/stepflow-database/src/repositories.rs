@@ -1,16 +1,17 @@
 //! Database repositories
 
 use stepflow_core::{
-    ToolId, ToolInfo, ToolStatus, ToolType, ToolStats,
+    ToolId, ToolInfo, ToolStatus, ToolType, ToolStats, ToolConfig,
     TenantId, TenantInfo, UserId, UserInfo, UserRole,
-    StepflowError, StepflowResult, Database,
+    StepflowError, StepflowResult, Database, AuditEvent, AuditFilter, LogEntry, ReadConsistency,
+    FlagDefinition,
 };
 use serde_json::Value;
 use std::collections::HashMap;
-use crate::SqliteDatabase;
+use crate::{BatchInsertReport, SqliteDatabase, TenantKeyRepository};
 use chrono::{DateTime, Utc};
-use crate::utils::{hash_password, verify_password};
-use crate::models::{ToolModel, TenantModel, UserModel};
+use crate::utils::{generate_token, hash_password, hash_token, param, verify_password};
+use crate::models::{parse_user_role, ToolModel, TenantModel, UserModel};
 
 /// Helper function to convert database row to ToolModel
 fn row_to_tool_model(row: &HashMap<String, Value>) -> Option<ToolModel> {
@@ -34,9 +35,25 @@ fn row_to_tool_model(row: &HashMap<String, Value>) -> Option<ToolModel> {
         examples: row.get("examples").and_then(|v| v.as_str()).map(|s| s.to_string()),
         created_at: row.get("created_at")?.as_str()?.parse().ok()?,
         updated_at: row.get("updated_at")?.as_str()?.parse().ok()?,
+        environment_label: row.get("environment_label").and_then(|v| v.as_str()).map(|s| s.to_string()),
+        requirements: row.get("requirements").and_then(|v| v.as_str()).map(|s| s.to_string()),
+        input_schema: row.get("input_schema").and_then(|v| v.as_str()).map(|s| s.to_string()),
+        localizations: row.get("localizations").and_then(|v| v.as_str()).map(|s| s.to_string()),
+        environment: row.get("environment").and_then(|v| v.as_str()).map(|s| s.to_string()),
+        session: row.get("session").and_then(|v| v.as_str()).map(|s| s.to_string()),
+        image: row.get("image").and_then(|v| v.as_str()).map(|s| s.to_string()),
     })
 }
 
+/// Convert a database row to a tool, skipping rows that fail validation
+fn row_to_tool(row: &HashMap<String, Value>) -> Option<ToolInfo> {
+    let model = row_to_tool_model(row)?;
+    let id = model.id.clone();
+    ToolInfo::try_from(model)
+        .map_err(|e| tracing::warn!("Skipping invalid tool row {}: {}", id, e))
+        .ok()
+}
+
 /// Helper function to convert database row to TenantModel
 fn row_to_tenant_model(row: &HashMap<String, Value>) -> Option<TenantModel> {
     Some(TenantModel {
@@ -60,6 +77,7 @@ fn row_to_user_model(row: &HashMap<String, Value>) -> Option<UserModel> {
         role: row.get("role")?.as_str()?.to_string(),
         tenant_id: row.get("tenant_id")?.as_str()?.to_string(),
         settings: row.get("settings").and_then(|v| v.as_str()).map(|s| s.to_string()),
+        email_verified: row.get("email_verified").and_then(|v| v.as_i64()).unwrap_or(0) != 0,
         created_at: row.get("created_at")?.as_str()?.parse().ok()?,
         updated_at: row.get("updated_at")?.as_str()?.parse().ok()?,
     })
@@ -82,6 +100,49 @@ fn row_to_execution_record(row: &HashMap<String, Value>) -> Option<ExecutionReco
     })
 }
 
+/// Rows per statement for batch inserts, capped further by the bind parameter limit
+pub const DEFAULT_BATCH_CHUNK_SIZE: usize = 500;
+
+pub(crate) const TOOL_INSERT_PREFIX: &str = r#"
+    INSERT INTO tools (
+        id, name, description, version_major, version_minor, version_patch,
+        version_pre_release, version_build, tool_type, status, author,
+        repository, documentation, tags, capabilities, configuration_schema,
+        examples, created_at, updated_at, environment_label, requirements, input_schema,
+        localizations, environment, session, image
+    )"#;
+
+pub(crate) fn tool_insert_params(tool: &ToolInfo) -> StepflowResult<Vec<Value>> {
+    Ok(vec![
+        param::text(tool.id.as_str()),
+        param::text(tool.name.as_str()),
+        param::text(tool.description.as_str()),
+        param::int(tool.version.major),
+        param::int(tool.version.minor),
+        param::int(tool.version.patch),
+        param::opt_text(tool.version.pre_release.as_deref()),
+        param::opt_text(tool.version.build.as_deref()),
+        param::text(tool.tool_type.to_string()),
+        param::text(tool.status.to_string()),
+        param::text(tool.author.as_str()),
+        param::opt_text(tool.repository.as_deref()),
+        param::opt_text(tool.documentation.as_deref()),
+        param::json(&tool.tags)?,
+        param::json(&tool.capabilities)?,
+        param::json(&tool.configuration_schema)?,
+        param::json(&tool.examples)?,
+        param::timestamp(&tool.created_at),
+        param::timestamp(&tool.updated_at),
+        param::opt_text(tool.environment_label.map(|label| label.as_str())),
+        param::json(&tool.requirements)?,
+        param::json(&tool.input_schema)?,
+        param::json(&tool.localizations)?,
+        param::json(&tool.environment)?,
+        param::json(&tool.session)?,
+        param::json(&tool.image)?,
+    ])
+}
+
 /// Tool repository for managing tools in the database
 pub struct ToolRepository {
     database: SqliteDatabase,
@@ -100,57 +161,25 @@ impl ToolRepository {
 
     /// Create a tool
     pub async fn create_tool(&self, tool: &ToolInfo) -> StepflowResult<()> {
-        let sql = r#"
-            INSERT INTO tools (
-                id, name, description, version_major, version_minor, version_patch,
-                version_pre_release, version_build, tool_type, status, author,
-                repository, documentation, tags, capabilities, configuration_schema,
-                examples, created_at, updated_at
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-        "#;
-
-        let params = vec![
-            Value::String(tool.id.as_str().to_string()),
-            Value::String(tool.name.clone()),
-            Value::String(tool.description.clone()),
-            Value::Number(tool.version.major.into()),
-            Value::Number(tool.version.minor.into()),
-            Value::Number(tool.version.patch.into()),
-            Value::String(tool.version.pre_release.clone().unwrap_or_default()),
-            Value::String(tool.version.build.clone().unwrap_or_default()),
-            Value::String(tool.tool_type.to_string()),
-            Value::String(tool.status.to_string()),
-            Value::String(tool.author.clone()),
-            Value::String(tool.repository.clone().unwrap_or_default()),
-            Value::String(tool.documentation.clone().unwrap_or_default()),
-            Value::String(serde_json::to_string(&tool.tags)?),
-            Value::String(serde_json::to_string(&tool.capabilities)?),
-            Value::String(serde_json::to_string(&tool.configuration_schema)?),
-            Value::String(serde_json::to_string(&tool.examples)?),
-            Value::String(tool.created_at.to_rfc3339()),
-            Value::String(tool.updated_at.to_rfc3339()),
-        ];
+        let sql = format!("{} VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)", TOOL_INSERT_PREFIX);
 
-        self.database.execute(sql, &params).await?;
+        self.database.execute(&sql, &tool_insert_params(tool)?).await?;
         Ok(())
     }
 
+    /// Create many tools in one transaction, reporting rows that could not be inserted
+    pub async fn create_tools(&self, tools: &[ToolInfo]) -> StepflowResult<BatchInsertReport> {
+        let rows = tools.iter().map(tool_insert_params).collect::<StepflowResult<Vec<_>>>()?;
+        self.database.insert_batch(TOOL_INSERT_PREFIX, &rows, DEFAULT_BATCH_CHUNK_SIZE).await
+    }
+
     /// Get a tool by ID
     pub async fn get_tool(&self, tool_id: &ToolId) -> StepflowResult<Option<ToolInfo>> {
         let sql = "SELECT * FROM tools WHERE id = ?";
-        let params = vec![Value::String(tool_id.as_str().to_string())];
+        let params = [param::text(tool_id.as_str())];
 
         let result = self.database.execute(sql, &params).await?;
-        
-        if result.rows.is_empty() {
-            return Ok(None);
-        }
-
-        if let Some(tool_model) = row_to_tool_model(&result.rows[0]) {
-            Ok(Some(tool_model.into()))
-        } else {
-            Ok(None)
-        }
+        Ok(result.rows.first().and_then(row_to_tool))
     }
 
     /// Update a tool
@@ -161,29 +190,37 @@ impl ToolRepository {
                 version_patch = ?, version_pre_release = ?, version_build = ?,
                 tool_type = ?, status = ?, author = ?, repository = ?,
                 documentation = ?, tags = ?, capabilities = ?, configuration_schema = ?,
-                examples = ?, updated_at = ?
+                examples = ?, updated_at = ?, environment_label = ?, requirements = ?, input_schema = ?,
+                localizations = ?, environment = ?, session = ?, image = ?
             WHERE id = ?
         "#;
 
         let params = vec![
-            Value::String(tool.name.clone()),
-            Value::String(tool.description.clone()),
-            Value::Number(tool.version.major.into()),
-            Value::Number(tool.version.minor.into()),
-            Value::Number(tool.version.patch.into()),
-            Value::String(tool.version.pre_release.clone().unwrap_or_default()),
-            Value::String(tool.version.build.clone().unwrap_or_default()),
-            Value::String(tool.tool_type.to_string()),
-            Value::String(tool.status.to_string()),
-            Value::String(tool.author.clone()),
-            Value::String(tool.repository.clone().unwrap_or_default()),
-            Value::String(tool.documentation.clone().unwrap_or_default()),
-            Value::String(serde_json::to_string(&tool.tags)?),
-            Value::String(serde_json::to_string(&tool.capabilities)?),
-            Value::String(serde_json::to_string(&tool.configuration_schema)?),
-            Value::String(serde_json::to_string(&tool.examples)?),
-            Value::String(tool.updated_at.to_rfc3339()),
-            Value::String(tool_id.as_str().to_string()),
+            param::text(tool.name.as_str()),
+            param::text(tool.description.as_str()),
+            param::int(tool.version.major),
+            param::int(tool.version.minor),
+            param::int(tool.version.patch),
+            param::opt_text(tool.version.pre_release.as_deref()),
+            param::opt_text(tool.version.build.as_deref()),
+            param::text(tool.tool_type.to_string()),
+            param::text(tool.status.to_string()),
+            param::text(tool.author.as_str()),
+            param::opt_text(tool.repository.as_deref()),
+            param::opt_text(tool.documentation.as_deref()),
+            param::json(&tool.tags)?,
+            param::json(&tool.capabilities)?,
+            param::json(&tool.configuration_schema)?,
+            param::json(&tool.examples)?,
+            param::timestamp(&tool.updated_at),
+            param::opt_text(tool.environment_label.map(|label| label.as_str())),
+            param::json(&tool.requirements)?,
+            param::json(&tool.input_schema)?,
+            param::json(&tool.localizations)?,
+            param::json(&tool.environment)?,
+            param::json(&tool.session)?,
+            param::json(&tool.image)?,
+            param::text(tool_id.as_str()),
         ];
 
         let _result = self.database.execute(sql, &params).await?;
@@ -214,6 +251,9 @@ impl ToolRepository {
     }
 
     /// List tools with optional filtering
+    ///
+    /// Served from a read replica when one is within its lag bound, so a tool
+    /// created moments ago may not be listed yet.
     pub async fn list_tools(&self, filter: Option<HashMap<String, Value>>) -> StepflowResult<Vec<ToolInfo>> {
         let mut sql = "SELECT * FROM tools".to_string();
         let mut params = Vec::new();
@@ -229,19 +269,19 @@ impl ToolRepository {
             }
         }
 
-        let result = self.database.execute(&sql, &params).await?;
+        let result = self.database.execute_with(&sql, &params, ReadConsistency::Eventual).await?;
         
         let mut tools = Vec::new();
         for row in result.rows {
-            if let Some(tool_model) = row_to_tool_model(&row) {
-                tools.push(tool_model.into());
+            if let Some(tool) = row_to_tool(&row) {
+                tools.push(tool);
             }
         }
         
         Ok(tools)
     }
 
-    /// Search tools by query
+    /// Search tools by query, served from a read replica when possible
     pub async fn search_tools(&self, query: &str) -> StepflowResult<Vec<ToolInfo>> {
         let sql = "SELECT * FROM tools WHERE name LIKE ? OR description LIKE ?";
         let search_pattern = format!("%{}%", query);
@@ -250,12 +290,12 @@ impl ToolRepository {
             Value::String(search_pattern),
         ];
 
-        let result = self.database.execute(sql, &params).await?;
+        let result = self.database.execute_with(sql, &params, ReadConsistency::Eventual).await?;
         
         let mut tools = Vec::new();
         for row in result.rows {
-            if let Some(tool_model) = row_to_tool_model(&row) {
-                tools.push(tool_model.into());
+            if let Some(tool) = row_to_tool(&row) {
+                tools.push(tool);
             }
         }
         
@@ -271,8 +311,8 @@ impl ToolRepository {
         
         let mut tools = Vec::new();
         for row in result.rows {
-            if let Some(tool_model) = row_to_tool_model(&row) {
-                tools.push(tool_model.into());
+            if let Some(tool) = row_to_tool(&row) {
+                tools.push(tool);
             }
         }
         
@@ -288,8 +328,8 @@ impl ToolRepository {
         
         let mut tools = Vec::new();
         for row in result.rows {
-            if let Some(tool_model) = row_to_tool_model(&row) {
-                tools.push(tool_model.into());
+            if let Some(tool) = row_to_tool(&row) {
+                tools.push(tool);
             }
         }
         
@@ -327,6 +367,34 @@ impl ToolRepository {
             error_tools: 0, // TODO: Add proper query
         })
     }
+
+    /// Number of tools per type and status
+    pub async fn count_by_type_and_status(&self) -> StepflowResult<Vec<ToolCount>> {
+        let sql = "SELECT tool_type, status, COUNT(*) AS count FROM tools GROUP BY tool_type, status ORDER BY tool_type, status";
+        let result = self.database.execute(sql, &[]).await?;
+        Ok(result.rows.iter().filter_map(|row| Some(ToolCount {
+            tool_type: row.get("tool_type")?.as_str()?.to_string(),
+            status: row.get("status")?.as_str()?.to_string(),
+            count: count_column(row, "count"),
+        })).collect())
+    }
+}
+
+/// Number of tools of one type in one status
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ToolCount {
+    pub tool_type: String,
+    pub status: String,
+    pub count: u64,
+}
+
+/// Read an aggregate column, which the driver may return as an integer or as text
+fn count_column(row: &HashMap<String, Value>, key: &str) -> u64 {
+    match row.get(key) {
+        Some(Value::Number(n)) => n.as_u64().unwrap_or(0),
+        Some(Value::String(s)) => s.parse().unwrap_or(0),
+        _ => 0,
+    }
 }
 
 /// Tenant repository for managing tenants in the database
@@ -352,7 +420,7 @@ impl TenantRepository {
             Value::String(tenant.id.as_str().to_string()),
             Value::String(tenant.name.clone()),
             Value::String(tenant.description.clone()),
-            Value::String(tenant.domain.clone().unwrap_or_default()),
+            param::opt_text(tenant.domain.as_deref()),
             Value::String(serde_json::to_string(&tenant.settings)?),
             Value::String(tenant.created_at.to_rfc3339()),
             Value::String(tenant.updated_at.to_rfc3339()),
@@ -380,6 +448,18 @@ impl TenantRepository {
         }
     }
 
+    /// Get a tenant by its custom domain (case-insensitive)
+    pub async fn get_tenant_by_domain(&self, domain: &str) -> StepflowResult<Option<TenantInfo>> {
+        if domain.is_empty() {
+            return Ok(None);
+        }
+        let sql = "SELECT * FROM tenants WHERE lower(domain) = lower(?) LIMIT 1";
+        let params = vec![Value::String(domain.to_string())];
+
+        let result = self.database.execute(sql, &params).await?;
+        Ok(result.rows.first().and_then(row_to_tenant_model).map(Into::into))
+    }
+
     /// Update a tenant
     pub async fn update_tenant(&self, tenant_id: &TenantId, tenant: &TenantInfo) -> StepflowResult<()> {
         let sql = r#"
@@ -391,7 +471,7 @@ impl TenantRepository {
         let params = vec![
             Value::String(tenant.name.clone()),
             Value::String(tenant.description.clone()),
-            Value::String(tenant.domain.clone().unwrap_or_default()),
+            param::opt_text(tenant.domain.as_deref()),
             Value::String(serde_json::to_string(&tenant.settings)?),
             Value::String(tenant.updated_at.to_rfc3339()),
             Value::String(tenant_id.as_str().to_string()),
@@ -469,8 +549,8 @@ impl UserRepository {
         let sql = r#"
             INSERT INTO users (
                 id, username, email, password_hash, role, tenant_id, settings,
-                created_at, updated_at
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+                email_verified, created_at, updated_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#;
 
         let params = vec![
@@ -481,6 +561,7 @@ impl UserRepository {
             Value::String(user.role.to_string()),
             Value::String(user.tenant_id.as_str().to_string()),
             Value::String(serde_json::to_string(&user.settings)?),
+            Value::from(user.email_verified as i64),
             Value::String(user.created_at.to_rfc3339()),
             Value::String(user.updated_at.to_rfc3339()),
         ];
@@ -697,194 +778,2871 @@ impl UserRepository {
         }
         Ok(())
     }
-}
-
-/// Execution repository for managing tool executions in the database
-pub struct ExecutionRepository {
-    database: SqliteDatabase,
-}
 
-impl ExecutionRepository {
-    /// Create a new execution repository
-    pub fn new(database: SqliteDatabase) -> Self {
-        Self { database }
+    /// Mark a user's email address as verified (or not)
+    pub async fn set_email_verified(&self, user_id: &UserId, verified: bool) -> StepflowResult<()> {
+        let sql = "UPDATE users SET email_verified = ?, updated_at = ? WHERE id = ?";
+        let params = vec![
+            Value::from(verified as i64),
+            Value::String(Utc::now().to_rfc3339()),
+            Value::String(user_id.as_str().to_string()),
+        ];
+        let result = self.database.execute(sql, &params).await?;
+        if result.rows_affected == 0 {
+            return Err(StepflowError::UserNotFound(user_id.as_str().to_string()));
+        }
+        Ok(())
     }
 
-    /// Create an execution record
-    pub async fn create_execution(&self, execution: &ExecutionRecord) -> StepflowResult<()> {
-        let sql = r#"
-            INSERT INTO executions (
-                id, tool_id, tenant_id, user_id, status, request, result,
-                started_at, completed_at, created_at, updated_at
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-        "#;
-
+    /// Issue an email verification token; only its hash is stored
+    pub async fn create_email_verification(&self, user_id: &UserId, ttl: chrono::Duration) -> StepflowResult<String> {
+        let token = generate_token();
+        let now = Utc::now();
+        let sql = "INSERT INTO email_verifications (token_hash, user_id, expires_at, created_at) VALUES (?, ?, ?, ?)";
         let params = vec![
-            Value::String(execution.id.clone()),
-            Value::String(execution.tool_id.as_str().to_string()),
-            Value::String(execution.tenant_id.as_str().to_string()),
-            Value::String(execution.user_id.as_str().to_string()),
-            Value::String(execution.status.clone()),
-            Value::String(serde_json::to_string(&execution.request)?),
-            Value::String(serde_json::to_string(&execution.result)?),
-            Value::String(execution.started_at.to_rfc3339()),
-            Value::String(execution.completed_at.map(|t| t.to_rfc3339()).unwrap_or_default()),
-            Value::String(execution.created_at.to_rfc3339()),
-            Value::String(execution.updated_at.to_rfc3339()),
+            Value::String(hash_token(&token)),
+            Value::String(user_id.as_str().to_string()),
+            Value::String((now + ttl).to_rfc3339()),
+            Value::String(now.to_rfc3339()),
         ];
-
         self.database.execute(sql, &params).await?;
-        Ok(())
+        Ok(token)
     }
 
-    /// Get an execution by ID
-    pub async fn get_execution(&self, execution_id: &str) -> StepflowResult<Option<ExecutionRecord>> {
-        let sql = "SELECT * FROM executions WHERE id = ?";
-        let params = vec![Value::String(execution_id.to_string())];
+    /// Consume an email verification token and mark the user verified.
+    ///
+    /// Returns `None` for unknown or expired tokens.
+    pub async fn verify_email(&self, token: &str) -> StepflowResult<Option<UserId>> {
+        let sql = "SELECT user_id, expires_at FROM email_verifications WHERE token_hash = ?";
+        let result = self.database.execute(sql, &[Value::String(hash_token(token))]).await?;
+        let Some(row) = result.rows.first() else {
+            return Ok(None);
+        };
 
-        let result = self.database.execute(sql, &params).await?;
-        
-        if result.rows.is_empty() {
+        let user_id = match row.get("user_id").and_then(|v| v.as_str()) {
+            Some(id) => UserId::from_string(id.to_string()),
+            None => return Ok(None),
+        };
+        let expires_at: Option<DateTime<Utc>> = row.get("expires_at")
+            .and_then(|v| v.as_str())
+            .and_then(|s| s.parse().ok());
+        if expires_at.is_none_or(|at| at <= Utc::now()) {
             return Ok(None);
         }
 
-        if let Some(execution_record) = row_to_execution_record(&result.rows[0]) {
-            Ok(Some(execution_record))
-        } else {
-            Ok(None)
-        }
+        self.set_email_verified(&user_id, true).await?;
+        self.database.execute(
+            "DELETE FROM email_verifications WHERE user_id = ?",
+            &[Value::String(user_id.as_str().to_string())],
+        ).await?;
+        Ok(Some(user_id))
     }
 
-    /// Update execution status
-    pub async fn update_execution_status(&self, execution_id: &str, status: &str, result: Option<Value>) -> StepflowResult<()> {
+    /// Failed login state of an account, `None` if there are no recent failures
+    pub async fn get_login_failures(&self, user_id: &UserId) -> StepflowResult<Option<LoginFailureState>> {
+        let sql = "SELECT failed_attempts, locked_until FROM login_failures WHERE user_id = ?";
+        let result = self.database.execute(sql, &[Value::String(user_id.as_str().to_string())]).await?;
+        Ok(result.rows.first().map(|row| LoginFailureState {
+            failed_attempts: row.get("failed_attempts").and_then(|v| v.as_i64()).unwrap_or(0) as u32,
+            locked_until: row.get("locked_until")
+                .and_then(|v| v.as_str())
+                .and_then(|s| s.parse().ok()),
+        }))
+    }
+
+    /// Record a failed login.
+    ///
+    /// After `max_attempts` consecutive failures the account is locked for
+    /// `lockout` and the counter starts over once the lockout has expired.
+    pub async fn record_failed_login(
+        &self,
+        user_id: &UserId,
+        max_attempts: u32,
+        lockout: chrono::Duration,
+    ) -> StepflowResult<LoginFailureState> {
+        let now = Utc::now();
+        let failed_attempts = match self.get_login_failures(user_id).await? {
+            Some(state) if state.is_locked() => return Ok(state),
+            Some(state) if state.locked_until.is_none() => state.failed_attempts + 1,
+            _ => 1,
+        };
+        let state = if failed_attempts >= max_attempts {
+            LoginFailureState { failed_attempts: 0, locked_until: Some(now + lockout) }
+        } else {
+            LoginFailureState { failed_attempts, locked_until: None }
+        };
+
         let sql = r#"
-            UPDATE executions SET
-                status = ?, result = ?, completed_at = ?, updated_at = ?
-            WHERE id = ?
+            INSERT INTO login_failures (user_id, failed_attempts, last_failed_at, locked_until)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT(user_id) DO UPDATE SET
+                failed_attempts = excluded.failed_attempts,
+                last_failed_at = excluded.last_failed_at,
+                locked_until = excluded.locked_until
         "#;
-
         let params = vec![
-            Value::String(status.to_string()),
-            Value::String(serde_json::to_string(&result)?),
-            Value::String(chrono::Utc::now().to_rfc3339()),
-            Value::String(chrono::Utc::now().to_rfc3339()),
-            Value::String(execution_id.to_string()),
+            Value::String(user_id.as_str().to_string()),
+            Value::from(state.failed_attempts as i64),
+            Value::String(now.to_rfc3339()),
+            state.locked_until.map(|at| Value::String(at.to_rfc3339())).unwrap_or(Value::Null),
         ];
+        self.database.execute(sql, &params).await?;
+        Ok(state)
+    }
 
-        let _result = self.database.execute(sql, &params).await?;
-        
-        if _result.rows_affected == 0 {
-            return Err(StepflowError::DatabaseError(stepflow_core::DatabaseError::QueryFailed(
-                "Execution not found".to_string()
-            )));
-        }
-
+    /// Reset the failed login counter, e.g. after a successful login
+    pub async fn clear_login_failures(&self, user_id: &UserId) -> StepflowResult<()> {
+        self.database.execute(
+            "DELETE FROM login_failures WHERE user_id = ?",
+            &[Value::String(user_id.as_str().to_string())],
+        ).await?;
         Ok(())
     }
 
-    /// List executions by tool
-    pub async fn list_executions_by_tool(&self, tool_id: &ToolId) -> StepflowResult<Vec<ExecutionRecord>> {
-        let sql = "SELECT * FROM executions WHERE tool_id = ? ORDER BY started_at DESC";
-        let params = vec![Value::String(tool_id.as_str().to_string())];
+    /// TOTP enrollment of a user, `None` if no secret was provisioned
+    pub async fn get_totp(&self, user_id: &UserId) -> StepflowResult<Option<TotpEnrollment>> {
+        let sql = "SELECT totp_secret, totp_enabled FROM users WHERE id = ?";
+        let result = self.database.execute(sql, &[Value::String(user_id.as_str().to_string())]).await?;
+        Ok(result.rows.first().and_then(|row| {
+            let secret = row.get("totp_secret")?.as_str()?.to_string();
+            Some(TotpEnrollment {
+                secret,
+                enabled: row.get("totp_enabled").and_then(|v| v.as_i64()).unwrap_or(0) != 0,
+            })
+        }))
+    }
 
+    /// Store a newly provisioned TOTP secret; it stays inactive until enabled
+    pub async fn set_pending_totp_secret(&self, user_id: &UserId, secret: &str) -> StepflowResult<()> {
+        let sql = "UPDATE users SET totp_secret = ?, totp_enabled = 0, totp_last_step = NULL, updated_at = ? WHERE id = ?";
+        let params = vec![
+            Value::String(secret.to_string()),
+            Value::String(Utc::now().to_rfc3339()),
+            Value::String(user_id.as_str().to_string()),
+        ];
         let result = self.database.execute(sql, &params).await?;
-        
-        let mut executions = Vec::new();
-        for row in result.rows {
-            if let Some(execution_record) = row_to_execution_record(&row) {
-                executions.push(execution_record);
-            }
+        if result.rows_affected == 0 {
+            return Err(StepflowError::UserNotFound(user_id.as_str().to_string()));
         }
-        
-        Ok(executions)
+        Ok(())
     }
 
-    /// List executions by tenant
-    pub async fn list_executions_by_tenant(&self, tenant_id: &TenantId) -> StepflowResult<Vec<ExecutionRecord>> {
-        let sql = "SELECT * FROM executions WHERE tenant_id = ? ORDER BY started_at DESC";
-        let params = vec![Value::String(tenant_id.as_str().to_string())];
-
+    /// Activate the provisioned TOTP secret
+    pub async fn enable_totp(&self, user_id: &UserId) -> StepflowResult<()> {
+        let sql = "UPDATE users SET totp_enabled = 1, updated_at = ? WHERE id = ? AND totp_secret IS NOT NULL";
+        let params = vec![
+            Value::String(Utc::now().to_rfc3339()),
+            Value::String(user_id.as_str().to_string()),
+        ];
         let result = self.database.execute(sql, &params).await?;
-        
-        let mut executions = Vec::new();
-        for row in result.rows {
-            if let Some(execution_record) = row_to_execution_record(&row) {
-                executions.push(execution_record);
-            }
+        if result.rows_affected == 0 {
+            return Err(StepflowError::InvalidInput("No TOTP secret has been provisioned".to_string()));
         }
-        
-        Ok(executions)
+        Ok(())
     }
 
-    /// List executions by user
-    pub async fn list_executions_by_user(&self, user_id: &UserId) -> StepflowResult<Vec<ExecutionRecord>> {
-        let sql = "SELECT * FROM executions WHERE user_id = ? ORDER BY started_at DESC";
-        let params = vec![Value::String(user_id.as_str().to_string())];
-
+    /// Record the time step of an accepted TOTP code
+    ///
+    /// Returns false if a code for this or a later step was already accepted,
+    /// so every code can be used at most once.
+    pub async fn record_totp_step(&self, user_id: &UserId, step: u64) -> StepflowResult<bool> {
+        let sql = "UPDATE users SET totp_last_step = ? WHERE id = ? AND (totp_last_step IS NULL OR totp_last_step < ?)";
+        let params = vec![
+            Value::from(step as i64),
+            Value::String(user_id.as_str().to_string()),
+            Value::from(step as i64),
+        ];
         let result = self.database.execute(sql, &params).await?;
-        
-        let mut executions = Vec::new();
-        for row in result.rows {
-            if let Some(execution_record) = row_to_execution_record(&row) {
-                executions.push(execution_record);
-            }
-        }
-        
-        Ok(executions)
+        Ok(result.rows_affected > 0)
     }
 
-    /// Get execution statistics
-    pub async fn get_execution_stats(&self, tenant_id: Option<&TenantId>) -> StepflowResult<ExecutionStats> {
-        let mut sql = "SELECT COUNT(*) as total, COUNT(CASE WHEN status = 'completed' THEN 1 END) as completed, COUNT(CASE WHEN status = 'failed' THEN 1 END) as failed, COUNT(CASE WHEN status = 'running' THEN 1 END) as in_progress FROM executions".to_string();
-        let mut params = Vec::new();
+    /// Remove the TOTP secret and all recovery codes
+    pub async fn disable_totp(&self, user_id: &UserId) -> StepflowResult<()> {
+        let id = Value::String(user_id.as_str().to_string());
+        self.database.execute(
+            "UPDATE users SET totp_secret = NULL, totp_enabled = 0, totp_last_step = NULL, updated_at = ? WHERE id = ?",
+            &[Value::String(Utc::now().to_rfc3339()), id.clone()],
+        ).await?;
+        self.database.execute("DELETE FROM recovery_codes WHERE user_id = ?", &[id]).await?;
+        Ok(())
+    }
 
-        if let Some(tenant) = tenant_id {
-            sql.push_str(" WHERE tenant_id = ?");
-            params.push(Value::String(tenant.as_str().to_string()));
+    /// Replace all recovery codes of a user; only their hashes are stored
+    pub async fn replace_recovery_codes(&self, user_id: &UserId, codes: &[String]) -> StepflowResult<()> {
+        let id = Value::String(user_id.as_str().to_string());
+        self.database.execute("DELETE FROM recovery_codes WHERE user_id = ?", std::slice::from_ref(&id)).await?;
+
+        let now = Value::String(Utc::now().to_rfc3339());
+        for code in codes {
+            self.database.execute(
+                "INSERT INTO recovery_codes (user_id, code_hash, created_at) VALUES (?, ?, ?)",
+                &[id.clone(), Value::String(hash_token(code)), now.clone()],
+            ).await?;
         }
+        Ok(())
+    }
 
-        let result = self.database.execute(&sql, &params).await?;
-        
-        if result.rows.is_empty() {
-            return Ok(ExecutionStats {
-                total: 0,
-                completed: 0,
-                failed: 0,
-                in_progress: 0,
-            });
+    /// Use up a recovery code, returns false if it is unknown or already used
+    pub async fn consume_recovery_code(&self, user_id: &UserId, code: &str) -> StepflowResult<bool> {
+        let sql = "UPDATE recovery_codes SET used_at = ? WHERE user_id = ? AND code_hash = ? AND used_at IS NULL";
+        let params = vec![
+            Value::String(Utc::now().to_rfc3339()),
+            Value::String(user_id.as_str().to_string()),
+            Value::String(hash_token(code.trim())),
+        ];
+        let result = self.database.execute(sql, &params).await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    /// Number of unused recovery codes
+    pub async fn remaining_recovery_codes(&self, user_id: &UserId) -> StepflowResult<usize> {
+        let sql = "SELECT id FROM recovery_codes WHERE user_id = ? AND used_at IS NULL";
+        let result = self.database.execute(sql, &[Value::String(user_id.as_str().to_string())]).await?;
+        Ok(result.rows.len())
+    }
+}
+
+/// TOTP secret of a user and whether it is active
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TotpEnrollment {
+    /// Base32 encoded shared secret
+    pub secret: String,
+    pub enabled: bool,
+}
+
+/// Consecutive failed logins of an account
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoginFailureState {
+    pub failed_attempts: u32,
+    pub locked_until: Option<DateTime<Utc>>,
+}
+
+impl LoginFailureState {
+    /// Whether logins are currently refused
+    pub fn is_locked(&self) -> bool {
+        self.locked_until.is_some_and(|until| until > Utc::now())
+    }
+}
+
+/// Helper function to convert database row to AuditEvent
+fn row_to_security_event(row: &HashMap<String, Value>) -> Option<AuditEvent> {
+    let optional = |key: &str| row.get(key).and_then(|v| v.as_str()).map(|s| s.to_string());
+    Some(AuditEvent {
+        event_id: row.get("id")?.as_str()?.to_string(),
+        event_type: row.get("event_type")?.as_str()?.to_string(),
+        user_id: optional("user_id").map(UserId::from_string),
+        tenant_id: optional("tenant_id").map(TenantId::from_string),
+        resource_type: row.get("resource_type")?.as_str()?.to_string(),
+        resource_id: row.get("resource_id")?.as_str()?.to_string(),
+        action: row.get("action")?.as_str()?.to_string(),
+        details: optional("details")
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default(),
+        ip_address: optional("ip_address"),
+        user_agent: optional("user_agent"),
+        timestamp: row.get("created_at")?.as_str()?.parse().ok()?,
+        success: row.get("success").and_then(|v| v.as_i64()).unwrap_or(0) != 0,
+        error_message: optional("error_message"),
+    })
+}
+
+/// Security event repository (lockouts, suspicious logins, ...)
+pub struct SecurityEventRepository {
+    database: SqliteDatabase,
+}
+
+impl SecurityEventRepository {
+    /// Create a new security event repository
+    pub fn new(database: SqliteDatabase) -> Self {
+        Self { database }
+    }
+
+    /// Store a security event
+    pub async fn record_event(&self, event: &AuditEvent) -> StepflowResult<()> {
+        let sql = r#"
+            INSERT INTO security_events (
+                id, event_type, user_id, tenant_id, resource_type, resource_id, action,
+                details, ip_address, user_agent, success, error_message, created_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#;
+        let optional = |value: Option<&str>| value.map(|v| Value::String(v.to_string())).unwrap_or(Value::Null);
+        let params = vec![
+            Value::String(event.event_id.clone()),
+            Value::String(event.event_type.clone()),
+            optional(event.user_id.as_ref().map(|id| id.as_str())),
+            optional(event.tenant_id.as_ref().map(|id| id.as_str())),
+            Value::String(event.resource_type.clone()),
+            Value::String(event.resource_id.clone()),
+            Value::String(event.action.clone()),
+            Value::String(serde_json::to_string(&event.details)?),
+            optional(event.ip_address.as_deref()),
+            optional(event.user_agent.as_deref()),
+            Value::from(event.success as i64),
+            optional(event.error_message.as_deref()),
+            Value::String(event.timestamp.to_rfc3339()),
+        ];
+        self.database.execute(sql, &params).await?;
+        Ok(())
+    }
+
+    /// List security events matching the filter, newest first
+    pub async fn list_events(&self, filter: &AuditFilter, limit: usize) -> StepflowResult<Vec<AuditEvent>> {
+        let mut sql = "SELECT * FROM security_events WHERE 1 = 1".to_string();
+        let mut params = Vec::new();
+        if let Some(event_type) = &filter.event_type {
+            sql.push_str(" AND event_type = ?");
+            params.push(Value::String(event_type.clone()));
+        }
+        if let Some(user_id) = &filter.user_id {
+            sql.push_str(" AND user_id = ?");
+            params.push(Value::String(user_id.as_str().to_string()));
         }
+        if let Some(tenant_id) = &filter.tenant_id {
+            sql.push_str(" AND tenant_id = ?");
+            params.push(Value::String(tenant_id.as_str().to_string()));
+        }
+        if let Some(start_time) = &filter.start_time {
+            sql.push_str(" AND created_at >= ?");
+            params.push(Value::String(start_time.to_rfc3339()));
+        }
+        if let Some(end_time) = &filter.end_time {
+            sql.push_str(" AND created_at <= ?");
+            params.push(Value::String(end_time.to_rfc3339()));
+        }
+        sql.push_str(" ORDER BY created_at DESC LIMIT ?");
+        params.push(Value::from(limit as i64));
 
-        let row = &result.rows[0];
-        Ok(ExecutionStats {
-            total: row.get("total").and_then(|v| v.as_i64()).unwrap_or(0) as u64,
-            completed: row.get("completed").and_then(|v| v.as_i64()).unwrap_or(0) as u64,
-            failed: row.get("failed").and_then(|v| v.as_i64()).unwrap_or(0) as u64,
-            in_progress: row.get("in_progress").and_then(|v| v.as_i64()).unwrap_or(0) as u64,
-        })
+        let result = self.database.execute(&sql, &params).await?;
+        Ok(result.rows.iter().filter_map(row_to_security_event).collect())
     }
 }
 
-/// Execution record
-#[derive(Debug, Clone)]
-pub struct ExecutionRecord {
+/// Invitation to join a tenant
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct InvitationRecord {
     pub id: String,
-    pub tool_id: ToolId,
+    pub email: String,
+    pub role: UserRole,
     pub tenant_id: TenantId,
-    pub user_id: UserId,
-    pub status: String,
-    pub request: Value,
-    pub result: Option<Value>,
-    pub started_at: DateTime<Utc>,
-    pub completed_at: Option<DateTime<Utc>>,
+    pub invited_by: Option<UserId>,
+    pub expires_at: DateTime<Utc>,
+    pub accepted_at: Option<DateTime<Utc>>,
+    pub accepted_user_id: Option<UserId>,
     pub created_at: DateTime<Utc>,
-    pub updated_at: DateTime<Utc>,
 }
 
-/// Execution statistics
-#[derive(Debug, Clone)]
-pub struct ExecutionStats {
-    pub total: u64,
-    pub completed: u64,
-    pub failed: u64,
-    pub in_progress: u64,
-} 
\ No newline at end of file
+impl InvitationRecord {
+    /// Whether the invitation can still be accepted
+    pub fn is_pending(&self) -> bool {
+        self.accepted_at.is_none() && self.expires_at > Utc::now()
+    }
+}
+
+/// Helper function to convert database row to InvitationRecord
+fn row_to_invitation(row: &HashMap<String, Value>) -> Option<InvitationRecord> {
+    let optional = |key: &str| row.get(key).and_then(|v| v.as_str()).map(|s| s.to_string());
+    Some(InvitationRecord {
+        id: row.get("id")?.as_str()?.to_string(),
+        email: row.get("email")?.as_str()?.to_string(),
+        role: parse_user_role(row.get("role")?.as_str()?),
+        tenant_id: TenantId::from_string(row.get("tenant_id")?.as_str()?.to_string()),
+        invited_by: optional("invited_by").map(UserId::from_string),
+        expires_at: row.get("expires_at")?.as_str()?.parse().ok()?,
+        accepted_at: optional("accepted_at").and_then(|s| s.parse().ok()),
+        accepted_user_id: optional("accepted_user_id").map(UserId::from_string),
+        created_at: row.get("created_at")?.as_str()?.parse().ok()?,
+    })
+}
+
+/// Invitation repository for invitation-based user creation
+pub struct InvitationRepository {
+    database: SqliteDatabase,
+}
+
+impl InvitationRepository {
+    /// Create a new invitation repository
+    pub fn new(database: SqliteDatabase) -> Self {
+        Self { database }
+    }
+
+    /// Create an invitation and return it together with its one-time token.
+    ///
+    /// Only the token hash is stored, so the token must be delivered to the
+    /// invitee right away.
+    pub async fn create_invitation(
+        &self,
+        email: &str,
+        role: UserRole,
+        tenant_id: &TenantId,
+        invited_by: Option<&UserId>,
+        ttl: chrono::Duration,
+    ) -> StepflowResult<(InvitationRecord, String)> {
+        let users = UserRepository::new(self.database.clone());
+        if users.get_user_by_email(email).await?.is_some() {
+            return Err(StepflowError::InvalidInput(format!("A user with email {} already exists", email)));
+        }
+
+        let token = generate_token();
+        let now = Utc::now();
+        let invitation = InvitationRecord {
+            id: uuid::Uuid::new_v4().to_string(),
+            email: email.to_string(),
+            role,
+            tenant_id: tenant_id.clone(),
+            invited_by: invited_by.cloned(),
+            expires_at: now + ttl,
+            accepted_at: None,
+            accepted_user_id: None,
+            created_at: now,
+        };
+
+        let sql = r#"
+            INSERT INTO user_invitations (
+                id, token_hash, email, role, tenant_id, invited_by, expires_at, created_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        "#;
+        let params = vec![
+            Value::String(invitation.id.clone()),
+            Value::String(hash_token(&token)),
+            Value::String(invitation.email.clone()),
+            Value::String(invitation.role.to_string()),
+            Value::String(tenant_id.as_str().to_string()),
+            invited_by.map(|id| Value::String(id.as_str().to_string())).unwrap_or(Value::Null),
+            Value::String(invitation.expires_at.to_rfc3339()),
+            Value::String(now.to_rfc3339()),
+        ];
+        self.database.execute(sql, &params).await?;
+
+        Ok((invitation, token))
+    }
+
+    /// Look up an invitation by its token
+    pub async fn get_invitation_by_token(&self, token: &str) -> StepflowResult<Option<InvitationRecord>> {
+        let sql = "SELECT * FROM user_invitations WHERE token_hash = ?";
+        let result = self.database.execute(sql, &[Value::String(hash_token(token))]).await?;
+        Ok(result.rows.first().and_then(row_to_invitation))
+    }
+
+    /// List invitations of a tenant, newest first
+    pub async fn list_invitations(&self, tenant_id: &TenantId) -> StepflowResult<Vec<InvitationRecord>> {
+        let sql = "SELECT * FROM user_invitations WHERE tenant_id = ? ORDER BY created_at DESC";
+        let result = self.database.execute(sql, &[Value::String(tenant_id.as_str().to_string())]).await?;
+        Ok(result.rows.iter().filter_map(row_to_invitation).collect())
+    }
+
+    /// Revoke a pending invitation, returns false if none was pending
+    pub async fn revoke_invitation(&self, tenant_id: &TenantId, invitation_id: &str) -> StepflowResult<bool> {
+        let sql = "DELETE FROM user_invitations WHERE id = ? AND tenant_id = ? AND accepted_at IS NULL";
+        let params = vec![
+            Value::String(invitation_id.to_string()),
+            Value::String(tenant_id.as_str().to_string()),
+        ];
+        let result = self.database.execute(sql, &params).await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    /// Accept an invitation, creating the user with the chosen credentials.
+    ///
+    /// The invitation link proves ownership of the address, so the new user's
+    /// email is marked verified.
+    pub async fn accept_invitation(&self, token: &str, username: &str, password: &str) -> StepflowResult<UserInfo> {
+        let invitation = self.get_invitation_by_token(token).await?
+            .ok_or_else(|| StepflowError::InvalidInput("Invalid invitation token".to_string()))?;
+        if invitation.accepted_at.is_some() {
+            return Err(StepflowError::InvalidInput("Invitation has already been accepted".to_string()));
+        }
+        if invitation.expires_at <= Utc::now() {
+            return Err(StepflowError::InvalidInput("Invitation has expired".to_string()));
+        }
+
+        // Claim the invitation first so concurrent accepts cannot both succeed
+        let now = Utc::now();
+        let claim = self.database.execute(
+            "UPDATE user_invitations SET accepted_at = ? WHERE id = ? AND accepted_at IS NULL",
+            &[Value::String(now.to_rfc3339()), Value::String(invitation.id.clone())],
+        ).await?;
+        if claim.rows_affected == 0 {
+            return Err(StepflowError::InvalidInput("Invitation has already been accepted".to_string()));
+        }
+
+        let user = UserInfo {
+            id: UserId::new(),
+            username: username.to_string(),
+            email: invitation.email.clone(),
+            role: invitation.role.clone(),
+            tenant_id: invitation.tenant_id.clone(),
+            settings: HashMap::new(),
+            email_verified: true,
+            created_at: now,
+            updated_at: now,
+        };
+        if let Err(e) = UserRepository::new(self.database.clone()).register_user(&user, password).await {
+            // Release the claim so the invitee can retry, e.g. with another username
+            self.database.execute(
+                "UPDATE user_invitations SET accepted_at = NULL WHERE id = ?",
+                &[Value::String(invitation.id.clone())],
+            ).await?;
+            return Err(e);
+        }
+
+        self.database.execute(
+            "UPDATE user_invitations SET accepted_user_id = ? WHERE id = ?",
+            &[Value::String(user.id.as_str().to_string()), Value::String(invitation.id)],
+        ).await?;
+
+        Ok(user)
+    }
+}
+
+/// OpenID Connect provider configured for a tenant
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct OidcProviderRecord {
+    pub id: String,
+    pub tenant_id: TenantId,
+    pub name: String,
+    /// Issuer URL, used for discovery and `iss` validation
+    pub issuer: String,
+    pub client_id: String,
+    #[serde(skip_serializing)]
+    pub client_secret: String,
+    pub scopes: Vec<String>,
+    /// Claim carrying the user's groups or roles, e.g. `groups`
+    pub role_claim: Option<String>,
+    /// Maps values of `role_claim` to local roles
+    pub role_mapping: HashMap<String, UserRole>,
+    /// Role given to provisioned users when no mapping matches
+    pub default_role: UserRole,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Link between an identity at an OIDC provider and a local user
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct OidcIdentityRecord {
+    pub provider_id: String,
+    pub subject: String,
+    pub user_id: UserId,
+    pub email: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_login_at: Option<DateTime<Utc>>,
+}
+
+/// Pending authorization-code login, keyed by the `state` parameter
+#[derive(Debug, Clone)]
+pub struct OidcLoginState {
+    pub state: String,
+    pub provider_id: String,
+    pub nonce: String,
+    /// PKCE verifier sent with the token request
+    pub code_verifier: String,
+    pub redirect_uri: String,
+    /// Set when an authenticated user is linking a new identity
+    pub link_user_id: Option<UserId>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Helper function to convert database row to OidcProviderRecord
+fn row_to_oidc_provider(row: &HashMap<String, Value>) -> Option<OidcProviderRecord> {
+    let role_mapping: HashMap<String, String> = serde_json::from_str(row.get("role_mapping")?.as_str()?).ok()?;
+    Some(OidcProviderRecord {
+        id: row.get("id")?.as_str()?.to_string(),
+        tenant_id: TenantId::from_string(row.get("tenant_id")?.as_str()?.to_string()),
+        name: row.get("name")?.as_str()?.to_string(),
+        issuer: row.get("issuer")?.as_str()?.to_string(),
+        client_id: row.get("client_id")?.as_str()?.to_string(),
+        client_secret: row.get("client_secret")?.as_str()?.to_string(),
+        scopes: serde_json::from_str(row.get("scopes")?.as_str()?).ok()?,
+        role_claim: row.get("role_claim").and_then(|v| v.as_str()).map(|s| s.to_string()),
+        role_mapping: role_mapping.into_iter().map(|(value, role)| (value, parse_user_role(&role))).collect(),
+        default_role: parse_user_role(row.get("default_role")?.as_str()?),
+        enabled: row.get("enabled").and_then(|v| v.as_i64()).unwrap_or(1) != 0,
+        created_at: row.get("created_at")?.as_str()?.parse().ok()?,
+        updated_at: row.get("updated_at")?.as_str()?.parse().ok()?,
+    })
+}
+
+/// Helper function to convert database row to OidcIdentityRecord
+fn row_to_oidc_identity(row: &HashMap<String, Value>) -> Option<OidcIdentityRecord> {
+    let optional = |key: &str| row.get(key).and_then(|v| v.as_str()).map(|s| s.to_string());
+    Some(OidcIdentityRecord {
+        provider_id: row.get("provider_id")?.as_str()?.to_string(),
+        subject: row.get("subject")?.as_str()?.to_string(),
+        user_id: UserId::from_string(row.get("user_id")?.as_str()?.to_string()),
+        email: optional("email"),
+        created_at: row.get("created_at")?.as_str()?.parse().ok()?,
+        last_login_at: optional("last_login_at").and_then(|s| s.parse().ok()),
+    })
+}
+
+/// Helper function to convert database row to OidcLoginState
+fn row_to_oidc_login_state(row: &HashMap<String, Value>) -> Option<OidcLoginState> {
+    Some(OidcLoginState {
+        state: row.get("state")?.as_str()?.to_string(),
+        provider_id: row.get("provider_id")?.as_str()?.to_string(),
+        nonce: row.get("nonce")?.as_str()?.to_string(),
+        code_verifier: row.get("code_verifier")?.as_str()?.to_string(),
+        redirect_uri: row.get("redirect_uri")?.as_str()?.to_string(),
+        link_user_id: row.get("link_user_id")
+            .and_then(|v| v.as_str())
+            .map(|s| UserId::from_string(s.to_string())),
+        expires_at: row.get("expires_at")?.as_str()?.parse().ok()?,
+    })
+}
+
+/// OIDC repository for SSO providers, linked identities and pending logins
+pub struct OidcRepository {
+    database: SqliteDatabase,
+}
+
+impl OidcRepository {
+    /// Create a new OIDC repository
+    pub fn new(database: SqliteDatabase) -> Self {
+        Self { database }
+    }
+
+    /// Create a provider
+    pub async fn create_provider(&self, provider: &OidcProviderRecord) -> StepflowResult<()> {
+        let role_mapping: HashMap<&String, String> = provider.role_mapping
+            .iter()
+            .map(|(value, role)| (value, role.to_string()))
+            .collect();
+        let sql = r#"
+            INSERT INTO oidc_providers (
+                id, tenant_id, name, issuer, client_id, client_secret, scopes,
+                role_claim, role_mapping, default_role, enabled, created_at, updated_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#;
+        let params = vec![
+            Value::String(provider.id.clone()),
+            Value::String(provider.tenant_id.as_str().to_string()),
+            Value::String(provider.name.clone()),
+            Value::String(provider.issuer.clone()),
+            Value::String(provider.client_id.clone()),
+            Value::String(
+                TenantKeyRepository::new(self.database.clone())
+                    .encrypt(&provider.tenant_id, &provider.client_secret)
+                    .await?,
+            ),
+            Value::String(serde_json::to_string(&provider.scopes)?),
+            provider.role_claim.clone().map(Value::String).unwrap_or(Value::Null),
+            Value::String(serde_json::to_string(&role_mapping)?),
+            Value::String(provider.default_role.to_string()),
+            Value::from(provider.enabled as i64),
+            Value::String(provider.created_at.to_rfc3339()),
+            Value::String(provider.updated_at.to_rfc3339()),
+        ];
+        self.database.execute(sql, &params).await?;
+        Ok(())
+    }
+
+    /// Get a provider by ID
+    pub async fn get_provider(&self, provider_id: &str) -> StepflowResult<Option<OidcProviderRecord>> {
+        let sql = "SELECT * FROM oidc_providers WHERE id = ?";
+        let result = self.database.execute(sql, &[Value::String(provider_id.to_string())]).await?;
+        match result.rows.first().and_then(row_to_oidc_provider) {
+            Some(provider) => Ok(Some(self.decrypt_provider(provider).await?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn decrypt_provider(&self, mut provider: OidcProviderRecord) -> StepflowResult<OidcProviderRecord> {
+        provider.client_secret = TenantKeyRepository::new(self.database.clone())
+            .decrypt(&provider.tenant_id, &provider.client_secret)
+            .await?;
+        Ok(provider)
+    }
+
+    /// List providers of a tenant
+    pub async fn list_providers(&self, tenant_id: &TenantId) -> StepflowResult<Vec<OidcProviderRecord>> {
+        let sql = "SELECT * FROM oidc_providers WHERE tenant_id = ? ORDER BY name";
+        let result = self.database.execute(sql, &[Value::String(tenant_id.as_str().to_string())]).await?;
+        let mut providers = Vec::new();
+        for provider in result.rows.iter().filter_map(row_to_oidc_provider) {
+            providers.push(self.decrypt_provider(provider).await?);
+        }
+        Ok(providers)
+    }
+
+    /// Delete a provider and the identities linked through it
+    pub async fn delete_provider(&self, tenant_id: &TenantId, provider_id: &str) -> StepflowResult<bool> {
+        let result = self.database.execute(
+            "DELETE FROM oidc_providers WHERE id = ? AND tenant_id = ?",
+            &[Value::String(provider_id.to_string()), Value::String(tenant_id.as_str().to_string())],
+        ).await?;
+        if result.rows_affected == 0 {
+            return Ok(false);
+        }
+
+        self.database.execute(
+            "DELETE FROM oidc_identities WHERE provider_id = ?",
+            &[Value::String(provider_id.to_string())],
+        ).await?;
+        Ok(true)
+    }
+
+    /// Store a pending login
+    pub async fn create_login_state(&self, login: &OidcLoginState) -> StepflowResult<()> {
+        let sql = r#"
+            INSERT INTO oidc_login_states (
+                state, provider_id, nonce, code_verifier, redirect_uri, link_user_id, expires_at, created_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        "#;
+        let params = vec![
+            Value::String(login.state.clone()),
+            Value::String(login.provider_id.clone()),
+            Value::String(login.nonce.clone()),
+            Value::String(login.code_verifier.clone()),
+            Value::String(login.redirect_uri.clone()),
+            login.link_user_id.as_ref().map(|id| Value::String(id.as_str().to_string())).unwrap_or(Value::Null),
+            Value::String(login.expires_at.to_rfc3339()),
+            Value::String(Utc::now().to_rfc3339()),
+        ];
+        self.database.execute(sql, &params).await?;
+        Ok(())
+    }
+
+    /// Consume a pending login. Each state can be used once; expired states
+    /// are discarded and reported as missing.
+    pub async fn take_login_state(&self, state: &str) -> StepflowResult<Option<OidcLoginState>> {
+        let key = Value::String(state.to_string());
+        let result = self.database.execute(
+            "SELECT * FROM oidc_login_states WHERE state = ?",
+            std::slice::from_ref(&key),
+        ).await?;
+        let Some(row) = result.rows.first() else {
+            return Ok(None);
+        };
+
+        let deleted = self.database.execute("DELETE FROM oidc_login_states WHERE state = ?", &[key]).await?;
+        if deleted.rows_affected == 0 {
+            return Ok(None);
+        }
+
+        Ok(row_to_oidc_login_state(row).filter(|login| login.expires_at > Utc::now()))
+    }
+
+    /// Find the identity for a provider subject
+    pub async fn find_identity(&self, provider_id: &str, subject: &str) -> StepflowResult<Option<OidcIdentityRecord>> {
+        let sql = "SELECT * FROM oidc_identities WHERE provider_id = ? AND subject = ?";
+        let params = vec![Value::String(provider_id.to_string()), Value::String(subject.to_string())];
+        let result = self.database.execute(sql, &params).await?;
+        Ok(result.rows.first().and_then(row_to_oidc_identity))
+    }
+
+    /// Link a provider subject to a local user. A user can have one identity
+    /// per provider and an identity belongs to exactly one user.
+    pub async fn link_identity(
+        &self,
+        provider_id: &str,
+        subject: &str,
+        user_id: &UserId,
+        email: Option<&str>,
+    ) -> StepflowResult<OidcIdentityRecord> {
+        let existing = self.database.execute(
+            "SELECT subject FROM oidc_identities WHERE provider_id = ? AND (subject = ? OR user_id = ?)",
+            &[
+                Value::String(provider_id.to_string()),
+                Value::String(subject.to_string()),
+                Value::String(user_id.as_str().to_string()),
+            ],
+        ).await?;
+        if !existing.rows.is_empty() {
+            return Err(StepflowError::InvalidInput(
+                "Identity or user is already linked for this provider".to_string(),
+            ));
+        }
+
+        let identity = OidcIdentityRecord {
+            provider_id: provider_id.to_string(),
+            subject: subject.to_string(),
+            user_id: user_id.clone(),
+            email: email.map(|email| email.to_string()),
+            created_at: Utc::now(),
+            last_login_at: None,
+        };
+        let sql = r#"
+            INSERT INTO oidc_identities (provider_id, subject, user_id, email, created_at)
+            VALUES (?, ?, ?, ?, ?)
+        "#;
+        let params = vec![
+            Value::String(identity.provider_id.clone()),
+            Value::String(identity.subject.clone()),
+            Value::String(user_id.as_str().to_string()),
+            identity.email.clone().map(Value::String).unwrap_or(Value::Null),
+            Value::String(identity.created_at.to_rfc3339()),
+        ];
+        self.database.execute(sql, &params).await?;
+        Ok(identity)
+    }
+
+    /// Record a successful login through an identity
+    pub async fn touch_identity(&self, provider_id: &str, subject: &str) -> StepflowResult<()> {
+        self.database.execute(
+            "UPDATE oidc_identities SET last_login_at = ? WHERE provider_id = ? AND subject = ?",
+            &[
+                Value::String(Utc::now().to_rfc3339()),
+                Value::String(provider_id.to_string()),
+                Value::String(subject.to_string()),
+            ],
+        ).await?;
+        Ok(())
+    }
+
+    /// List identities linked to a user
+    pub async fn list_identities(&self, user_id: &UserId) -> StepflowResult<Vec<OidcIdentityRecord>> {
+        let sql = "SELECT * FROM oidc_identities WHERE user_id = ? ORDER BY created_at";
+        let result = self.database.execute(sql, &[Value::String(user_id.as_str().to_string())]).await?;
+        Ok(result.rows.iter().filter_map(row_to_oidc_identity).collect())
+    }
+
+    /// Unlink a user's identity at a provider, returns false if none was linked
+    pub async fn unlink_identity(&self, user_id: &UserId, provider_id: &str) -> StepflowResult<bool> {
+        let result = self.database.execute(
+            "DELETE FROM oidc_identities WHERE user_id = ? AND provider_id = ?",
+            &[Value::String(user_id.as_str().to_string()), Value::String(provider_id.to_string())],
+        ).await?;
+        Ok(result.rows_affected > 0)
+    }
+}
+
+/// How directory sync treats a directory entry that matches an existing local user
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DirectoryConflictPolicy {
+    /// Leave the local user untouched and report the conflict
+    Skip,
+    /// Take over the local user so it becomes directory-managed
+    Adopt,
+}
+
+impl DirectoryConflictPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DirectoryConflictPolicy::Skip => "skip",
+            DirectoryConflictPolicy::Adopt => "adopt",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "skip" => Some(DirectoryConflictPolicy::Skip),
+            "adopt" => Some(DirectoryConflictPolicy::Adopt),
+            _ => None,
+        }
+    }
+}
+
+/// LDAP / Active Directory connection and mapping settings of a tenant
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct DirectoryConfigRecord {
+    pub tenant_id: TenantId,
+    /// Server URL, e.g. `ldaps://dc.example.com:636`
+    pub url: String,
+    pub bind_dn: String,
+    #[serde(skip_serializing)]
+    pub bind_password: String,
+    pub base_dn: String,
+    pub user_filter: String,
+    pub username_attribute: String,
+    pub email_attribute: String,
+    /// Attribute listing the entry's groups, usually `memberOf`
+    pub group_attribute: String,
+    /// Maps group DNs or CNs to local roles
+    pub group_role_mapping: HashMap<String, UserRole>,
+    pub default_role: UserRole,
+    pub conflict_policy: DirectoryConflictPolicy,
+    /// Delete directory-managed users that no longer match the filter
+    pub remove_missing: bool,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Outcome of one directory sync
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct DirectorySyncRun {
+    pub id: String,
+    pub tenant_id: TenantId,
+    pub dry_run: bool,
+    pub created: u64,
+    pub updated: u64,
+    pub adopted: u64,
+    pub removed: u64,
+    pub conflicts: u64,
+    pub error: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+}
+
+/// Helper function to convert database row to DirectoryConfigRecord
+fn row_to_directory_config(row: &HashMap<String, Value>) -> Option<DirectoryConfigRecord> {
+    let text = |key: &str| row.get(key).and_then(|v| v.as_str()).map(|s| s.to_string());
+    let mapping: HashMap<String, String> = serde_json::from_str(&text("group_role_mapping")?).ok()?;
+    Some(DirectoryConfigRecord {
+        tenant_id: TenantId::from_string(text("tenant_id")?),
+        url: text("url")?,
+        bind_dn: text("bind_dn")?,
+        bind_password: text("bind_password")?,
+        base_dn: text("base_dn")?,
+        user_filter: text("user_filter")?,
+        username_attribute: text("username_attribute")?,
+        email_attribute: text("email_attribute")?,
+        group_attribute: text("group_attribute")?,
+        group_role_mapping: mapping.into_iter().map(|(group, role)| (group, parse_user_role(&role))).collect(),
+        default_role: parse_user_role(&text("default_role")?),
+        conflict_policy: DirectoryConflictPolicy::parse(&text("conflict_policy")?)?,
+        remove_missing: row.get("remove_missing").and_then(|v| v.as_i64()).unwrap_or(0) != 0,
+        enabled: row.get("enabled").and_then(|v| v.as_i64()).unwrap_or(1) != 0,
+        created_at: text("created_at")?.parse().ok()?,
+        updated_at: text("updated_at")?.parse().ok()?,
+    })
+}
+
+/// Helper function to convert database row to DirectorySyncRun
+fn row_to_directory_sync_run(row: &HashMap<String, Value>) -> Option<DirectorySyncRun> {
+    let count = |key: &str| row.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+    Some(DirectorySyncRun {
+        id: row.get("id")?.as_str()?.to_string(),
+        tenant_id: TenantId::from_string(row.get("tenant_id")?.as_str()?.to_string()),
+        dry_run: row.get("dry_run").and_then(|v| v.as_i64()).unwrap_or(0) != 0,
+        created: count("created"),
+        updated: count("updated"),
+        adopted: count("adopted"),
+        removed: count("removed"),
+        conflicts: count("conflicts"),
+        error: row.get("error").and_then(|v| v.as_str()).map(|s| s.to_string()),
+        started_at: row.get("started_at")?.as_str()?.parse().ok()?,
+        finished_at: row.get("finished_at")?.as_str()?.parse().ok()?,
+    })
+}
+
+/// Directory repository for LDAP/AD sync settings, history and user ownership
+pub struct DirectoryRepository {
+    database: SqliteDatabase,
+}
+
+impl DirectoryRepository {
+    /// Create a new directory repository
+    pub fn new(database: SqliteDatabase) -> Self {
+        Self { database }
+    }
+
+    /// Create or replace the directory settings of a tenant
+    pub async fn save_config(&self, config: &DirectoryConfigRecord) -> StepflowResult<()> {
+        let mapping: HashMap<&String, String> = config.group_role_mapping
+            .iter()
+            .map(|(group, role)| (group, role.to_string()))
+            .collect();
+        let sql = r#"
+            INSERT OR REPLACE INTO directory_configs (
+                tenant_id, url, bind_dn, bind_password, base_dn, user_filter, username_attribute,
+                email_attribute, group_attribute, group_role_mapping, default_role, conflict_policy,
+                remove_missing, enabled, created_at, updated_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#;
+        let params = vec![
+            Value::String(config.tenant_id.as_str().to_string()),
+            Value::String(config.url.clone()),
+            Value::String(config.bind_dn.clone()),
+            Value::String(
+                TenantKeyRepository::new(self.database.clone())
+                    .encrypt(&config.tenant_id, &config.bind_password)
+                    .await?,
+            ),
+            Value::String(config.base_dn.clone()),
+            Value::String(config.user_filter.clone()),
+            Value::String(config.username_attribute.clone()),
+            Value::String(config.email_attribute.clone()),
+            Value::String(config.group_attribute.clone()),
+            Value::String(serde_json::to_string(&mapping)?),
+            Value::String(config.default_role.to_string()),
+            Value::String(config.conflict_policy.as_str().to_string()),
+            Value::from(config.remove_missing as i64),
+            Value::from(config.enabled as i64),
+            Value::String(config.created_at.to_rfc3339()),
+            Value::String(config.updated_at.to_rfc3339()),
+        ];
+        self.database.execute(sql, &params).await?;
+        Ok(())
+    }
+
+    /// Get the directory settings of a tenant
+    pub async fn get_config(&self, tenant_id: &TenantId) -> StepflowResult<Option<DirectoryConfigRecord>> {
+        let sql = "SELECT * FROM directory_configs WHERE tenant_id = ?";
+        let result = self.database.execute(sql, &[Value::String(tenant_id.as_str().to_string())]).await?;
+        match result.rows.first().and_then(row_to_directory_config) {
+            Some(config) => Ok(Some(self.decrypt_config(config).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// List enabled directory settings of all tenants
+    pub async fn list_enabled_configs(&self) -> StepflowResult<Vec<DirectoryConfigRecord>> {
+        let result = self.database.execute("SELECT * FROM directory_configs WHERE enabled = 1", &[]).await?;
+        let mut configs = Vec::new();
+        for config in result.rows.iter().filter_map(row_to_directory_config) {
+            configs.push(self.decrypt_config(config).await?);
+        }
+        Ok(configs)
+    }
+
+    async fn decrypt_config(&self, mut config: DirectoryConfigRecord) -> StepflowResult<DirectoryConfigRecord> {
+        config.bind_password = TenantKeyRepository::new(self.database.clone())
+            .decrypt(&config.tenant_id, &config.bind_password)
+            .await?;
+        Ok(config)
+    }
+
+    /// Delete the directory settings of a tenant. Synced users are kept but
+    /// stay marked as directory-managed until released.
+    pub async fn delete_config(&self, tenant_id: &TenantId) -> StepflowResult<bool> {
+        let result = self.database.execute(
+            "DELETE FROM directory_configs WHERE tenant_id = ?",
+            &[Value::String(tenant_id.as_str().to_string())],
+        ).await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    /// Record the outcome of a sync
+    pub async fn record_run(&self, run: &DirectorySyncRun) -> StepflowResult<()> {
+        let sql = r#"
+            INSERT INTO directory_sync_runs (
+                id, tenant_id, dry_run, created, updated, adopted, removed, conflicts, error, started_at, finished_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#;
+        let params = vec![
+            Value::String(run.id.clone()),
+            Value::String(run.tenant_id.as_str().to_string()),
+            Value::from(run.dry_run as i64),
+            Value::from(run.created),
+            Value::from(run.updated),
+            Value::from(run.adopted),
+            Value::from(run.removed),
+            Value::from(run.conflicts),
+            run.error.clone().map(Value::String).unwrap_or(Value::Null),
+            Value::String(run.started_at.to_rfc3339()),
+            Value::String(run.finished_at.to_rfc3339()),
+        ];
+        self.database.execute(sql, &params).await?;
+        Ok(())
+    }
+
+    /// List recent sync runs of a tenant, newest first
+    pub async fn list_runs(&self, tenant_id: &TenantId, limit: usize) -> StepflowResult<Vec<DirectorySyncRun>> {
+        let sql = "SELECT * FROM directory_sync_runs WHERE tenant_id = ? ORDER BY started_at DESC LIMIT ?";
+        let params = vec![Value::String(tenant_id.as_str().to_string()), Value::from(limit as i64)];
+        let result = self.database.execute(sql, &params).await?;
+        Ok(result.rows.iter().filter_map(row_to_directory_sync_run).collect())
+    }
+
+    /// Directory DNs of the directory-managed users of a tenant
+    pub async fn managed_users(&self, tenant_id: &TenantId) -> StepflowResult<HashMap<UserId, String>> {
+        let sql = "SELECT id, directory_dn FROM users WHERE tenant_id = ? AND directory_dn IS NOT NULL";
+        let result = self.database.execute(sql, &[Value::String(tenant_id.as_str().to_string())]).await?;
+        Ok(result.rows
+            .iter()
+            .filter_map(|row| {
+                let id = row.get("id")?.as_str()?;
+                let dn = row.get("directory_dn")?.as_str().filter(|dn| !dn.is_empty())?;
+                Some((UserId::from_string(id.to_string()), dn.to_string()))
+            })
+            .collect())
+    }
+
+    /// Mark a user as managed by the directory entry `dn`, or release it back
+    /// to local management with `None`
+    pub async fn set_managed(&self, user_id: &UserId, dn: Option<&str>) -> StepflowResult<()> {
+        self.database.execute(
+            "UPDATE users SET directory_dn = ? WHERE id = ?",
+            &[
+                dn.map(|dn| Value::String(dn.to_string())).unwrap_or(Value::Null),
+                Value::String(user_id.as_str().to_string()),
+            ],
+        ).await?;
+        Ok(())
+    }
+}
+
+/// Login session backing an issued access/refresh token pair
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SessionRecord {
+    pub id: String,
+    pub user_id: UserId,
+    pub tenant_id: TenantId,
+    /// Client user agent
+    pub device: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl SessionRecord {
+    /// Whether tokens of this session are still accepted
+    pub fn is_active(&self) -> bool {
+        self.revoked_at.is_none() && self.expires_at > Utc::now()
+    }
+}
+
+/// Helper function to convert database row to SessionRecord
+fn row_to_session(row: &HashMap<String, Value>) -> Option<SessionRecord> {
+    let optional = |key: &str| row.get(key).and_then(|v| v.as_str()).map(|s| s.to_string());
+    Some(SessionRecord {
+        id: row.get("id")?.as_str()?.to_string(),
+        user_id: UserId::from_string(row.get("user_id")?.as_str()?.to_string()),
+        tenant_id: TenantId::from_string(row.get("tenant_id")?.as_str()?.to_string()),
+        device: optional("device"),
+        ip_address: optional("ip_address"),
+        created_at: row.get("created_at")?.as_str()?.parse().ok()?,
+        last_seen_at: row.get("last_seen_at")?.as_str()?.parse().ok()?,
+        expires_at: row.get("expires_at")?.as_str()?.parse().ok()?,
+        revoked_at: optional("revoked_at").and_then(|s| s.parse().ok()),
+    })
+}
+
+/// Session repository for tracking and revoking user logins
+pub struct SessionRepository {
+    database: SqliteDatabase,
+}
+
+impl SessionRepository {
+    /// Create a new session repository
+    pub fn new(database: SqliteDatabase) -> Self {
+        Self { database }
+    }
+
+    /// Start a session for a user
+    pub async fn create_session(
+        &self,
+        user: &UserInfo,
+        device: Option<&str>,
+        ip_address: Option<&str>,
+        ttl: chrono::Duration,
+    ) -> StepflowResult<SessionRecord> {
+        let now = Utc::now();
+        let session = SessionRecord {
+            id: uuid::Uuid::new_v4().to_string(),
+            user_id: user.id.clone(),
+            tenant_id: user.tenant_id.clone(),
+            device: device.map(|device| device.to_string()),
+            ip_address: ip_address.map(|ip| ip.to_string()),
+            created_at: now,
+            last_seen_at: now,
+            expires_at: now + ttl,
+            revoked_at: None,
+        };
+        let sql = r#"
+            INSERT INTO user_sessions (
+                id, user_id, tenant_id, device, ip_address, created_at, last_seen_at, expires_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        "#;
+        let params = vec![
+            Value::String(session.id.clone()),
+            Value::String(session.user_id.as_str().to_string()),
+            Value::String(session.tenant_id.as_str().to_string()),
+            session.device.clone().map(Value::String).unwrap_or(Value::Null),
+            session.ip_address.clone().map(Value::String).unwrap_or(Value::Null),
+            Value::String(now.to_rfc3339()),
+            Value::String(now.to_rfc3339()),
+            Value::String(session.expires_at.to_rfc3339()),
+        ];
+        self.database.execute(sql, &params).await?;
+        Ok(session)
+    }
+
+    /// Get a session by ID
+    pub async fn get_session(&self, session_id: &str) -> StepflowResult<Option<SessionRecord>> {
+        let sql = "SELECT * FROM user_sessions WHERE id = ?";
+        let result = self.database.execute(sql, &[Value::String(session_id.to_string())]).await?;
+        Ok(result.rows.first().and_then(row_to_session))
+    }
+
+    /// Record activity on a session. Writes are skipped while the last
+    /// recorded activity is newer than `min_interval`.
+    pub async fn touch_session(
+        &self,
+        session_id: &str,
+        ip_address: Option<&str>,
+        min_interval: chrono::Duration,
+    ) -> StepflowResult<()> {
+        let now = Utc::now();
+        let sql = r#"
+            UPDATE user_sessions SET last_seen_at = ?, ip_address = COALESCE(?, ip_address)
+            WHERE id = ? AND last_seen_at < ?
+        "#;
+        let params = vec![
+            Value::String(now.to_rfc3339()),
+            ip_address.map(|ip| Value::String(ip.to_string())).unwrap_or(Value::Null),
+            Value::String(session_id.to_string()),
+            Value::String((now - min_interval).to_rfc3339()),
+        ];
+        self.database.execute(sql, &params).await?;
+        Ok(())
+    }
+
+    /// List the active sessions of a user, most recently used first
+    pub async fn list_active_sessions(&self, user_id: &UserId) -> StepflowResult<Vec<SessionRecord>> {
+        let sql = r#"
+            SELECT * FROM user_sessions
+            WHERE user_id = ? AND revoked_at IS NULL AND expires_at > ?
+            ORDER BY last_seen_at DESC
+        "#;
+        let params = vec![
+            Value::String(user_id.as_str().to_string()),
+            Value::String(Utc::now().to_rfc3339()),
+        ];
+        let result = self.database.execute(sql, &params).await?;
+        Ok(result.rows.iter().filter_map(row_to_session).collect())
+    }
+
+    /// Revoke one session of a user, returns false if it was not active
+    pub async fn revoke_session(&self, user_id: &UserId, session_id: &str) -> StepflowResult<bool> {
+        let sql = "UPDATE user_sessions SET revoked_at = ? WHERE id = ? AND user_id = ? AND revoked_at IS NULL";
+        let params = vec![
+            Value::String(Utc::now().to_rfc3339()),
+            Value::String(session_id.to_string()),
+            Value::String(user_id.as_str().to_string()),
+        ];
+        let result = self.database.execute(sql, &params).await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    /// Revoke all sessions of a user except `keep`, returns how many were revoked
+    pub async fn revoke_user_sessions(&self, user_id: &UserId, keep: Option<&str>) -> StepflowResult<u64> {
+        let sql = "UPDATE user_sessions SET revoked_at = ? WHERE user_id = ? AND revoked_at IS NULL AND id != ?";
+        let params = vec![
+            Value::String(Utc::now().to_rfc3339()),
+            Value::String(user_id.as_str().to_string()),
+            Value::String(keep.unwrap_or_default().to_string()),
+        ];
+        let result = self.database.execute(sql, &params).await?;
+        Ok(result.rows_affected)
+    }
+
+    /// Delete sessions that expired or were revoked before `before`
+    pub async fn purge_sessions(&self, before: DateTime<Utc>) -> StepflowResult<u64> {
+        let sql = "DELETE FROM user_sessions WHERE expires_at < ? OR revoked_at < ?";
+        let before = Value::String(before.to_rfc3339());
+        let result = self.database.execute(sql, &[before.clone(), before]).await?;
+        Ok(result.rows_affected)
+    }
+}
+
+pub(crate) const EXECUTION_INSERT_PREFIX: &str = r#"
+    INSERT INTO executions (
+        id, tool_id, tenant_id, user_id, status, request, result,
+        started_at, completed_at, created_at, updated_at
+    )"#;
+
+pub(crate) fn execution_insert_params(execution: &ExecutionRecord) -> StepflowResult<Vec<Value>> {
+    Ok(vec![
+        param::text(execution.id.as_str()),
+        param::text(execution.tool_id.as_str()),
+        param::text(execution.tenant_id.as_str()),
+        param::text(execution.user_id.as_str()),
+        param::text(execution.status.as_str()),
+        param::json(&execution.request)?,
+        param::json(&execution.result)?,
+        param::timestamp(&execution.started_at),
+        param::opt_text(execution.completed_at.map(|t| t.to_rfc3339())),
+        param::timestamp(&execution.created_at),
+        param::timestamp(&execution.updated_at),
+    ])
+}
+
+/// Execution repository for managing tool executions in the database
+pub struct ExecutionRepository {
+    database: SqliteDatabase,
+}
+
+impl ExecutionRepository {
+    /// Create a new execution repository
+    pub fn new(database: SqliteDatabase) -> Self {
+        Self { database }
+    }
+
+    /// Create an execution record
+    pub async fn create_execution(&self, execution: &ExecutionRecord) -> StepflowResult<()> {
+        let sql = format!("{} VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)", EXECUTION_INSERT_PREFIX);
+        self.database.execute(&sql, &execution_insert_params(execution)?).await?;
+        Ok(())
+    }
+
+    /// Create many execution records in one transaction, reporting rows that could not be inserted
+    pub async fn create_executions(&self, executions: &[ExecutionRecord]) -> StepflowResult<BatchInsertReport> {
+        let rows = executions.iter().map(execution_insert_params).collect::<StepflowResult<Vec<_>>>()?;
+        self.database.insert_batch(EXECUTION_INSERT_PREFIX, &rows, DEFAULT_BATCH_CHUNK_SIZE).await
+    }
+
+    /// Get an execution by ID
+    pub async fn get_execution(&self, execution_id: &str) -> StepflowResult<Option<ExecutionRecord>> {
+        let sql = "SELECT * FROM executions WHERE id = ?";
+        let params = vec![Value::String(execution_id.to_string())];
+
+        let result = self.database.execute(sql, &params).await?;
+        
+        if result.rows.is_empty() {
+            return Ok(None);
+        }
+
+        if let Some(execution_record) = row_to_execution_record(&result.rows[0]) {
+            Ok(Some(execution_record))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Update execution status
+    pub async fn update_execution_status(&self, execution_id: &str, status: &str, result: Option<Value>) -> StepflowResult<()> {
+        let sql = r#"
+            UPDATE executions SET
+                status = ?, result = ?, completed_at = ?, updated_at = ?
+            WHERE id = ?
+        "#;
+
+        let params = vec![
+            Value::String(status.to_string()),
+            Value::String(serde_json::to_string(&result)?),
+            Value::String(chrono::Utc::now().to_rfc3339()),
+            Value::String(chrono::Utc::now().to_rfc3339()),
+            Value::String(execution_id.to_string()),
+        ];
+
+        let _result = self.database.execute(sql, &params).await?;
+        
+        if _result.rows_affected == 0 {
+            return Err(StepflowError::DatabaseError(stepflow_core::DatabaseError::QueryFailed(
+                "Execution not found".to_string()
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// List executions by tool
+    pub async fn list_executions_by_tool(&self, tool_id: &ToolId) -> StepflowResult<Vec<ExecutionRecord>> {
+        let sql = "SELECT * FROM executions WHERE tool_id = ? ORDER BY started_at DESC";
+        let params = vec![Value::String(tool_id.as_str().to_string())];
+
+        let result = self.database.execute(sql, &params).await?;
+        
+        let mut executions = Vec::new();
+        for row in result.rows {
+            if let Some(execution_record) = row_to_execution_record(&row) {
+                executions.push(execution_record);
+            }
+        }
+        
+        Ok(executions)
+    }
+
+    /// List executions by tenant
+    pub async fn list_executions_by_tenant(&self, tenant_id: &TenantId) -> StepflowResult<Vec<ExecutionRecord>> {
+        let sql = "SELECT * FROM executions WHERE tenant_id = ? ORDER BY started_at DESC";
+        let params = vec![Value::String(tenant_id.as_str().to_string())];
+
+        let result = self.database.execute(sql, &params).await?;
+        
+        let mut executions = Vec::new();
+        for row in result.rows {
+            if let Some(execution_record) = row_to_execution_record(&row) {
+                executions.push(execution_record);
+            }
+        }
+        
+        Ok(executions)
+    }
+
+    /// List executions by user
+    pub async fn list_executions_by_user(&self, user_id: &UserId) -> StepflowResult<Vec<ExecutionRecord>> {
+        let sql = "SELECT * FROM executions WHERE user_id = ? ORDER BY started_at DESC";
+        let params = vec![Value::String(user_id.as_str().to_string())];
+
+        let result = self.database.execute(sql, &params).await?;
+        
+        let mut executions = Vec::new();
+        for row in result.rows {
+            if let Some(execution_record) = row_to_execution_record(&row) {
+                executions.push(execution_record);
+            }
+        }
+        
+        Ok(executions)
+    }
+
+    /// Get execution statistics
+    pub async fn get_execution_stats(&self, tenant_id: Option<&TenantId>) -> StepflowResult<ExecutionStats> {
+        let mut sql = "SELECT COUNT(*) as total, COUNT(CASE WHEN status = 'completed' THEN 1 END) as completed, COUNT(CASE WHEN status = 'failed' THEN 1 END) as failed, COUNT(CASE WHEN status = 'running' THEN 1 END) as in_progress FROM executions".to_string();
+        let mut params = Vec::new();
+
+        if let Some(tenant) = tenant_id {
+            sql.push_str(" WHERE tenant_id = ?");
+            params.push(Value::String(tenant.as_str().to_string()));
+        }
+
+        let result = self.database.execute(&sql, &params).await?;
+        
+        if result.rows.is_empty() {
+            return Ok(ExecutionStats {
+                total: 0,
+                completed: 0,
+                failed: 0,
+                in_progress: 0,
+            });
+        }
+
+        let row = &result.rows[0];
+        Ok(ExecutionStats {
+            total: row.get("total").and_then(|v| v.as_i64()).unwrap_or(0) as u64,
+            completed: row.get("completed").and_then(|v| v.as_i64()).unwrap_or(0) as u64,
+            failed: row.get("failed").and_then(|v| v.as_i64()).unwrap_or(0) as u64,
+            in_progress: row.get("in_progress").and_then(|v| v.as_i64()).unwrap_or(0) as u64,
+        })
+    }
+
+    /// Executions created since `since`, counted per time bucket and status
+    pub async fn status_histogram(&self, since: DateTime<Utc>, bucket: TimeBucket) -> StepflowResult<Vec<ExecutionBucket>> {
+        let sql = format!(
+            "SELECT substr(created_at, 1, {len}) AS bucket, status, COUNT(*) AS count FROM executions \
+             WHERE created_at >= ? GROUP BY substr(created_at, 1, {len}), status ORDER BY bucket, status",
+            len = bucket.prefix_len(),
+        );
+        let result = self.database.execute(&sql, &[param::timestamp(&since)]).await?;
+        Ok(result.rows.iter().filter_map(|row| Some(ExecutionBucket {
+            bucket_start: bucket.start(row.get("bucket")?.as_str()?)?,
+            status: row.get("status")?.as_str()?.to_string(),
+            count: count_column(row, "count"),
+        })).collect())
+    }
+
+    /// Tools with the most failed executions since `since`
+    pub async fn top_failing_tools(&self, since: DateTime<Utc>, limit: usize) -> StepflowResult<Vec<ToolFailureStats>> {
+        let sql = "SELECT tool_id, COUNT(*) AS executions, \
+                   SUM(CASE WHEN status IN ('failed', 'timeout') THEN 1 ELSE 0 END) AS failures \
+                   FROM executions WHERE created_at >= ? GROUP BY tool_id \
+                   HAVING failures > 0 ORDER BY failures DESC, executions DESC LIMIT ?";
+        let result = self.database.execute(sql, &[param::timestamp(&since), param::int(limit as i64)]).await?;
+        Ok(result.rows.iter().filter_map(|row| {
+            let executions = count_column(row, "executions");
+            let failures = count_column(row, "failures");
+            Some(ToolFailureStats {
+                tool_id: ToolId::from_string(row.get("tool_id")?.as_str()?.to_string()),
+                executions,
+                failures,
+                failure_rate: if executions == 0 { 0.0 } else { failures as f64 / executions as f64 },
+            })
+        }).collect())
+    }
+
+    /// Queued and running tasks of the shared queue and executions not yet started
+    pub async fn queue_depth(&self) -> StepflowResult<QueueDepth> {
+        let sql = "SELECT \
+                   (SELECT COUNT(*) FROM tasks WHERE status = 'Queued') AS queued_tasks, \
+                   (SELECT COUNT(*) FROM tasks WHERE status = 'Running') AS running_tasks, \
+                   (SELECT COUNT(*) FROM executions WHERE status = 'pending') AS pending_executions";
+        let result = self.database.execute(sql, &[]).await?;
+        Ok(result.rows.first().map(|row| QueueDepth {
+            queued_tasks: count_column(row, "queued_tasks"),
+            running_tasks: count_column(row, "running_tasks"),
+            pending_executions: count_column(row, "pending_executions"),
+        }).unwrap_or_default())
+    }
+}
+
+/// Execution record
+#[derive(Debug, Clone)]
+pub struct ExecutionRecord {
+    pub id: String,
+    pub tool_id: ToolId,
+    pub tenant_id: TenantId,
+    pub user_id: UserId,
+    pub status: String,
+    pub request: Value,
+    pub result: Option<Value>,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Width of the time buckets of an execution histogram
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeBucket {
+    #[default]
+    Hour,
+    Day,
+}
+
+impl TimeBucket {
+    /// Length of the RFC 3339 prefix identifying a bucket, e.g. `2026-01-01T13`
+    fn prefix_len(&self) -> usize {
+        match self {
+            TimeBucket::Hour => 13,
+            TimeBucket::Day => 10,
+        }
+    }
+
+    fn start(&self, prefix: &str) -> Option<DateTime<Utc>> {
+        let timestamp = match self {
+            TimeBucket::Hour => format!("{}:00:00Z", prefix),
+            TimeBucket::Day => format!("{}T00:00:00Z", prefix),
+        };
+        timestamp.parse().ok()
+    }
+}
+
+/// Executions created in one time bucket with one status
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ExecutionBucket {
+    pub bucket_start: DateTime<Utc>,
+    pub status: String,
+    pub count: u64,
+}
+
+/// Failures of one tool in a time window
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ToolFailureStats {
+    pub tool_id: ToolId,
+    pub executions: u64,
+    pub failures: u64,
+    pub failure_rate: f64,
+}
+
+/// Work waiting for or occupying executors, across all instances
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct QueueDepth {
+    pub queued_tasks: u64,
+    pub running_tasks: u64,
+    pub pending_executions: u64,
+}
+
+/// Execution statistics
+#[derive(Debug, Clone)]
+pub struct ExecutionStats {
+    pub total: u64,
+    pub completed: u64,
+    pub failed: u64,
+    pub in_progress: u64,
+} 
+
+/// Favorite repository for managing per-user tool bookmarks
+pub struct FavoriteRepository {
+    database: SqliteDatabase,
+}
+
+impl FavoriteRepository {
+    /// Create a new favorite repository
+    pub fn new(database: SqliteDatabase) -> Self {
+        Self { database }
+    }
+
+    /// Add a tool to a user's favorites (idempotent)
+    pub async fn add_favorite(&self, user_id: &UserId, tool_id: &ToolId) -> StepflowResult<()> {
+        let sql = r#"
+            INSERT OR IGNORE INTO user_favorites (user_id, tool_id, created_at)
+            VALUES (?, ?, ?)
+        "#;
+
+        let params = vec![
+            Value::String(user_id.as_str().to_string()),
+            Value::String(tool_id.as_str().to_string()),
+            Value::String(chrono::Utc::now().to_rfc3339()),
+        ];
+
+        self.database.execute(sql, &params).await?;
+        Ok(())
+    }
+
+    /// Remove a tool from a user's favorites, returning whether it was present
+    pub async fn remove_favorite(&self, user_id: &UserId, tool_id: &ToolId) -> StepflowResult<bool> {
+        let sql = "DELETE FROM user_favorites WHERE user_id = ? AND tool_id = ?";
+        let params = vec![
+            Value::String(user_id.as_str().to_string()),
+            Value::String(tool_id.as_str().to_string()),
+        ];
+
+        let result = self.database.execute(sql, &params).await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    /// Check whether a tool is in a user's favorites
+    pub async fn is_favorite(&self, user_id: &UserId, tool_id: &ToolId) -> StepflowResult<bool> {
+        let sql = "SELECT 1 FROM user_favorites WHERE user_id = ? AND tool_id = ? LIMIT 1";
+        let params = vec![
+            Value::String(user_id.as_str().to_string()),
+            Value::String(tool_id.as_str().to_string()),
+        ];
+
+        let result = self.database.execute(sql, &params).await?;
+        Ok(!result.rows.is_empty())
+    }
+
+    /// List the tool IDs a user has favorited, most recent first
+    pub async fn list_favorite_ids(&self, user_id: &UserId) -> StepflowResult<Vec<ToolId>> {
+        let sql = "SELECT tool_id FROM user_favorites WHERE user_id = ? ORDER BY created_at DESC";
+        let params = vec![Value::String(user_id.as_str().to_string())];
+
+        let result = self.database.execute(sql, &params).await?;
+
+        Ok(result.rows.iter()
+            .filter_map(|row| row.get("tool_id").and_then(|v| v.as_str()))
+            .map(|s| ToolId::from_string(s.to_string()))
+            .collect())
+    }
+
+    /// List the tools a user has favorited, most recent first
+    pub async fn list_favorite_tools(&self, user_id: &UserId) -> StepflowResult<Vec<ToolInfo>> {
+        let sql = r#"
+            SELECT t.* FROM tools t
+            INNER JOIN user_favorites f ON f.tool_id = t.id
+            WHERE f.user_id = ?
+            ORDER BY f.created_at DESC
+        "#;
+        let params = vec![Value::String(user_id.as_str().to_string())];
+
+        let result = self.database.execute(sql, &params).await?;
+
+        let mut tools = Vec::new();
+        for row in result.rows {
+            if let Some(tool) = row_to_tool(&row) {
+                tools.push(tool);
+            }
+        }
+
+        Ok(tools)
+    }
+}
+
+/// Named execution list filter saved by a user
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SavedViewRecord {
+    pub id: String,
+    pub tenant_id: TenantId,
+    pub user_id: UserId,
+    pub name: String,
+    /// Filter as submitted by the client, e.g. `{"labels": {"team": "payments"}}`
+    pub filter: Value,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Helper function to convert database row to SavedViewRecord
+fn row_to_saved_view(row: &HashMap<String, Value>) -> Option<SavedViewRecord> {
+    Some(SavedViewRecord {
+        id: row.get("id")?.as_str()?.to_string(),
+        tenant_id: TenantId::from_string(row.get("tenant_id")?.as_str()?.to_string()),
+        user_id: UserId::from_string(row.get("user_id")?.as_str()?.to_string()),
+        name: row.get("name")?.as_str()?.to_string(),
+        filter: serde_json::from_str(row.get("filter")?.as_str()?).ok()?,
+        created_at: row.get("created_at")?.as_str()?.parse().ok()?,
+        updated_at: row.get("updated_at")?.as_str()?.parse().ok()?,
+    })
+}
+
+/// Saved view repository for per-user execution list filters
+pub struct SavedViewRepository {
+    database: SqliteDatabase,
+}
+
+impl SavedViewRepository {
+    /// Create a new saved view repository
+    pub fn new(database: SqliteDatabase) -> Self {
+        Self { database }
+    }
+
+    /// Save a view under `name`, replacing the filter of an existing view with that name
+    pub async fn save_view(
+        &self,
+        tenant_id: &TenantId,
+        user_id: &UserId,
+        name: &str,
+        filter: &Value,
+    ) -> StepflowResult<SavedViewRecord> {
+        let sql = r#"
+            INSERT INTO saved_execution_views (id, tenant_id, user_id, name, filter, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (tenant_id, user_id, name) DO UPDATE SET filter = excluded.filter, updated_at = excluded.updated_at
+        "#;
+        let now = Utc::now().to_rfc3339();
+        self.database.execute(sql, &[
+            param::text(uuid::Uuid::new_v4().to_string()),
+            param::text(tenant_id.as_str()),
+            param::text(user_id.as_str()),
+            param::text(name),
+            param::text(filter.to_string()),
+            param::text(now.clone()),
+            param::text(now),
+        ]).await?;
+
+        let result = self.database.execute(
+            "SELECT * FROM saved_execution_views WHERE tenant_id = ? AND user_id = ? AND name = ?",
+            &[param::text(tenant_id.as_str()), param::text(user_id.as_str()), param::text(name)],
+        ).await?;
+        result.rows.first()
+            .and_then(row_to_saved_view)
+            .ok_or_else(|| StepflowError::DatabaseError(stepflow_core::DatabaseError::QueryFailed(
+                format!("Saved view {} could not be read back", name),
+            )))
+    }
+
+    /// Get one of a user's views
+    pub async fn get_view(&self, tenant_id: &TenantId, user_id: &UserId, view_id: &str) -> StepflowResult<Option<SavedViewRecord>> {
+        let result = self.database.execute(
+            "SELECT * FROM saved_execution_views WHERE tenant_id = ? AND user_id = ? AND id = ?",
+            &[param::text(tenant_id.as_str()), param::text(user_id.as_str()), param::text(view_id)],
+        ).await?;
+        Ok(result.rows.first().and_then(row_to_saved_view))
+    }
+
+    /// List a user's views by name
+    pub async fn list_views(&self, tenant_id: &TenantId, user_id: &UserId) -> StepflowResult<Vec<SavedViewRecord>> {
+        let result = self.database.execute(
+            "SELECT * FROM saved_execution_views WHERE tenant_id = ? AND user_id = ? ORDER BY name",
+            &[param::text(tenant_id.as_str()), param::text(user_id.as_str())],
+        ).await?;
+        Ok(result.rows.iter().filter_map(row_to_saved_view).collect())
+    }
+
+    /// Delete one of a user's views, returning whether it existed
+    pub async fn delete_view(&self, tenant_id: &TenantId, user_id: &UserId, view_id: &str) -> StepflowResult<bool> {
+        let result = self.database.execute(
+            "DELETE FROM saved_execution_views WHERE tenant_id = ? AND user_id = ? AND id = ?",
+            &[param::text(tenant_id.as_str()), param::text(user_id.as_str()), param::text(view_id)],
+        ).await?;
+        Ok(result.rows_affected > 0)
+    }
+}
+
+/// Named parameter preset of a tool
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ToolPresetRecord {
+    pub id: String,
+    pub tenant_id: TenantId,
+    pub tool_id: ToolId,
+    /// Owner of a private preset; `None` when shared with the tenant
+    pub user_id: Option<UserId>,
+    pub name: String,
+    pub description: Option<String>,
+    pub parameters: HashMap<String, Value>,
+    pub created_by: UserId,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Helper function to convert database row to ToolPresetRecord
+fn row_to_tool_preset(row: &HashMap<String, Value>) -> Option<ToolPresetRecord> {
+    let user_id = row.get("user_id")?.as_str()?;
+    Some(ToolPresetRecord {
+        id: row.get("id")?.as_str()?.to_string(),
+        tenant_id: TenantId::from_string(row.get("tenant_id")?.as_str()?.to_string()),
+        tool_id: ToolId::from_string(row.get("tool_id")?.as_str()?.to_string()),
+        user_id: (!user_id.is_empty()).then(|| UserId::from_string(user_id.to_string())),
+        name: row.get("name")?.as_str()?.to_string(),
+        description: row.get("description").and_then(|v| v.as_str()).map(str::to_string),
+        parameters: serde_json::from_str(row.get("parameters")?.as_str()?).ok()?,
+        created_by: UserId::from_string(row.get("created_by")?.as_str()?.to_string()),
+        created_at: row.get("created_at")?.as_str()?.parse().ok()?,
+        updated_at: row.get("updated_at")?.as_str()?.parse().ok()?,
+    })
+}
+
+/// Tool preset repository
+///
+/// A preset is either shared with the tenant or private to the user who saved it.
+/// Resolving a preset by name layers the user's preset over the tenant's preset of the
+/// same name, so users can override single parameters of a shared default.
+pub struct ToolPresetRepository {
+    database: SqliteDatabase,
+}
+
+impl ToolPresetRepository {
+    /// Create a new tool preset repository
+    pub fn new(database: SqliteDatabase) -> Self {
+        Self { database }
+    }
+
+    /// Save a preset under `name`, replacing the preset of the same name and owner
+    #[allow(clippy::too_many_arguments)]
+    pub async fn save_preset(
+        &self,
+        tenant_id: &TenantId,
+        tool_id: &ToolId,
+        owner: Option<&UserId>,
+        name: &str,
+        description: Option<&str>,
+        parameters: &HashMap<String, Value>,
+        created_by: &UserId,
+    ) -> StepflowResult<ToolPresetRecord> {
+        let sql = r#"
+            INSERT INTO tool_presets (id, tenant_id, tool_id, user_id, name, description, parameters, created_by, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (tenant_id, tool_id, user_id, name) DO UPDATE SET
+                description = excluded.description,
+                parameters = excluded.parameters,
+                updated_at = excluded.updated_at
+        "#;
+        let now = Utc::now();
+        let owner = owner.map(|user| user.as_str()).unwrap_or_default();
+        self.database.execute(sql, &[
+            param::text(uuid::Uuid::new_v4().to_string()),
+            param::text(tenant_id.as_str()),
+            param::text(tool_id.as_str()),
+            param::text(owner),
+            param::text(name),
+            param::opt_text(description),
+            param::json(parameters)?,
+            param::text(created_by.as_str()),
+            param::timestamp(&now),
+            param::timestamp(&now),
+        ]).await?;
+
+        let result = self.database.execute(
+            "SELECT * FROM tool_presets WHERE tenant_id = ? AND tool_id = ? AND user_id = ? AND name = ?",
+            &[param::text(tenant_id.as_str()), param::text(tool_id.as_str()), param::text(owner), param::text(name)],
+        ).await?;
+        result.rows.first()
+            .and_then(row_to_tool_preset)
+            .ok_or_else(|| StepflowError::DatabaseError(stepflow_core::DatabaseError::QueryFailed(
+                format!("Tool preset {} could not be read back", name),
+            )))
+    }
+
+    /// Replace the name, description and parameters of a preset
+    pub async fn update_preset(
+        &self,
+        tenant_id: &TenantId,
+        preset_id: &str,
+        name: &str,
+        description: Option<&str>,
+        parameters: &HashMap<String, Value>,
+    ) -> StepflowResult<Option<ToolPresetRecord>> {
+        let sql = r#"
+            UPDATE tool_presets SET name = ?, description = ?, parameters = ?, updated_at = ?
+            WHERE tenant_id = ? AND id = ?
+        "#;
+        let result = self.database.execute(sql, &[
+            param::text(name),
+            param::opt_text(description),
+            param::json(parameters)?,
+            param::timestamp(&Utc::now()),
+            param::text(tenant_id.as_str()),
+            param::text(preset_id),
+        ]).await?;
+        if result.rows_affected == 0 {
+            return Ok(None);
+        }
+        self.get_preset(tenant_id, preset_id).await
+    }
+
+    /// Get a preset of the tenant, shared or private
+    pub async fn get_preset(&self, tenant_id: &TenantId, preset_id: &str) -> StepflowResult<Option<ToolPresetRecord>> {
+        let result = self.database.execute(
+            "SELECT * FROM tool_presets WHERE tenant_id = ? AND id = ?",
+            &[param::text(tenant_id.as_str()), param::text(preset_id)],
+        ).await?;
+        Ok(result.rows.first().and_then(row_to_tool_preset))
+    }
+
+    /// Presets of a tool visible to a user: the tenant's shared ones and the user's own
+    pub async fn list_presets(&self, tenant_id: &TenantId, tool_id: &ToolId, user_id: &UserId) -> StepflowResult<Vec<ToolPresetRecord>> {
+        let result = self.database.execute(
+            "SELECT * FROM tool_presets WHERE tenant_id = ? AND tool_id = ? AND user_id IN ('', ?) ORDER BY name, user_id",
+            &[param::text(tenant_id.as_str()), param::text(tool_id.as_str()), param::text(user_id.as_str())],
+        ).await?;
+        Ok(result.rows.iter().filter_map(row_to_tool_preset).collect())
+    }
+
+    /// Parameters of the preset `name` as seen by a user, `None` when neither the
+    /// tenant nor the user has a preset of that name
+    pub async fn resolve(
+        &self,
+        tenant_id: &TenantId,
+        tool_id: &ToolId,
+        user_id: &UserId,
+        name: &str,
+    ) -> StepflowResult<Option<HashMap<String, Value>>> {
+        let presets = self.list_presets(tenant_id, tool_id, user_id).await?;
+        let mut layers: Vec<&ToolPresetRecord> = presets.iter().filter(|preset| preset.name == name).collect();
+        if layers.is_empty() {
+            return Ok(None);
+        }
+        // Shared first, so the user's own values win
+        layers.sort_by_key(|preset| preset.user_id.is_some());
+        let mut parameters = HashMap::new();
+        for preset in layers {
+            parameters.extend(preset.parameters.clone());
+        }
+        Ok(Some(parameters))
+    }
+
+    /// Delete a preset, returning whether it existed
+    pub async fn delete_preset(&self, tenant_id: &TenantId, preset_id: &str) -> StepflowResult<bool> {
+        let result = self.database.execute(
+            "DELETE FROM tool_presets WHERE tenant_id = ? AND id = ?",
+            &[param::text(tenant_id.as_str()), param::text(preset_id)],
+        ).await?;
+        Ok(result.rows_affected > 0)
+    }
+}
+
+/// Helper function to convert database row to ToolListingRecord (without targets)
+fn row_to_listing_record(row: &HashMap<String, Value>) -> Option<ToolListingRecord> {
+    Some(ToolListingRecord {
+        id: row.get("id")?.as_str()?.to_string(),
+        tool_id: ToolId::from_string(row.get("tool_id")?.as_str()?.to_string()),
+        publisher_tenant_id: TenantId::from_string(row.get("publisher_tenant_id")?.as_str()?.to_string()),
+        visibility: row.get("visibility")?.as_str()?.to_string(),
+        target_tenants: Vec::new(),
+        published_version: row.get("published_version")?.as_str()?.to_string(),
+        status: row.get("status")?.as_str()?.to_string(),
+        created_at: row.get("created_at")?.as_str()?.parse().ok()?,
+        updated_at: row.get("updated_at")?.as_str()?.parse().ok()?,
+    })
+}
+
+/// Helper function to convert database row to ToolSubscriptionRecord
+fn row_to_subscription_record(row: &HashMap<String, Value>) -> Option<ToolSubscriptionRecord> {
+    Some(ToolSubscriptionRecord {
+        id: row.get("id")?.as_str()?.to_string(),
+        listing_id: row.get("listing_id")?.as_str()?.to_string(),
+        tenant_id: TenantId::from_string(row.get("tenant_id")?.as_str()?.to_string()),
+        tool_id: ToolId::from_string(row.get("tool_id")?.as_str()?.to_string()),
+        installed_version: row.get("installed_version")?.as_str()?.to_string(),
+        pending_version: row.get("pending_version").and_then(|v| v.as_str()).map(|s| s.to_string()),
+        status: row.get("status")?.as_str()?.to_string(),
+        created_at: row.get("created_at")?.as_str()?.parse().ok()?,
+        updated_at: row.get("updated_at")?.as_str()?.parse().ok()?,
+    })
+}
+
+/// Marketplace repository for shared tool listings and tenant subscriptions
+pub struct MarketplaceRepository {
+    database: SqliteDatabase,
+}
+
+impl MarketplaceRepository {
+    /// Create a new marketplace repository
+    pub fn new(database: SqliteDatabase) -> Self {
+        Self { database }
+    }
+
+    /// Create a listing together with its target tenants
+    pub async fn create_listing(&self, listing: &ToolListingRecord) -> StepflowResult<()> {
+        let sql = r#"
+            INSERT INTO tool_listings (
+                id, tool_id, publisher_tenant_id, visibility, published_version,
+                status, created_at, updated_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        "#;
+
+        let params = vec![
+            Value::String(listing.id.clone()),
+            Value::String(listing.tool_id.as_str().to_string()),
+            Value::String(listing.publisher_tenant_id.as_str().to_string()),
+            Value::String(listing.visibility.clone()),
+            Value::String(listing.published_version.clone()),
+            Value::String(listing.status.clone()),
+            Value::String(listing.created_at.to_rfc3339()),
+            Value::String(listing.updated_at.to_rfc3339()),
+        ];
+
+        self.database.execute(sql, &params).await?;
+        self.set_listing_targets(&listing.id, &listing.target_tenants).await
+    }
+
+    /// Replace the target tenants of a listing
+    pub async fn set_listing_targets(&self, listing_id: &str, tenants: &[TenantId]) -> StepflowResult<()> {
+        let sql = "DELETE FROM tool_listing_targets WHERE listing_id = ?";
+        self.database.execute(sql, &[Value::String(listing_id.to_string())]).await?;
+
+        let sql = "INSERT OR IGNORE INTO tool_listing_targets (listing_id, tenant_id) VALUES (?, ?)";
+        for tenant_id in tenants {
+            let params = vec![
+                Value::String(listing_id.to_string()),
+                Value::String(tenant_id.as_str().to_string()),
+            ];
+            self.database.execute(sql, &params).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Get a listing by ID
+    pub async fn get_listing(&self, listing_id: &str) -> StepflowResult<Option<ToolListingRecord>> {
+        let sql = "SELECT * FROM tool_listings WHERE id = ?";
+        let params = vec![Value::String(listing_id.to_string())];
+
+        let result = self.database.execute(sql, &params).await?;
+
+        match result.rows.first().and_then(row_to_listing_record) {
+            Some(mut listing) => {
+                listing.target_tenants = self.get_listing_targets(&listing.id).await?;
+                Ok(Some(listing))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// List published listings visible to a tenant (global, targeted, or its own)
+    pub async fn list_visible_listings(&self, tenant_id: &TenantId) -> StepflowResult<Vec<ToolListingRecord>> {
+        let sql = r#"
+            SELECT l.* FROM tool_listings l
+            WHERE l.status = 'published' AND (
+                l.visibility = 'global'
+                OR l.publisher_tenant_id = ?
+                OR EXISTS (
+                    SELECT 1 FROM tool_listing_targets t
+                    WHERE t.listing_id = l.id AND t.tenant_id = ?
+                )
+            )
+            ORDER BY l.created_at DESC
+        "#;
+        let params = vec![
+            Value::String(tenant_id.as_str().to_string()),
+            Value::String(tenant_id.as_str().to_string()),
+        ];
+
+        let result = self.database.execute(sql, &params).await?;
+
+        let mut listings = Vec::new();
+        for row in result.rows {
+            if let Some(mut listing) = row_to_listing_record(&row) {
+                listing.target_tenants = self.get_listing_targets(&listing.id).await?;
+                listings.push(listing);
+            }
+        }
+
+        Ok(listings)
+    }
+
+    /// Update a listing's version and status
+    pub async fn update_listing(&self, listing: &ToolListingRecord) -> StepflowResult<()> {
+        let sql = r#"
+            UPDATE tool_listings SET
+                visibility = ?, published_version = ?, status = ?, updated_at = ?
+            WHERE id = ?
+        "#;
+
+        let params = vec![
+            Value::String(listing.visibility.clone()),
+            Value::String(listing.published_version.clone()),
+            Value::String(listing.status.clone()),
+            Value::String(listing.updated_at.to_rfc3339()),
+            Value::String(listing.id.clone()),
+        ];
+
+        let result = self.database.execute(sql, &params).await?;
+
+        if result.rows_affected == 0 {
+            return Err(StepflowError::DatabaseError(stepflow_core::DatabaseError::QueryFailed(
+                "Listing not found".to_string()
+            )));
+        }
+
+        self.set_listing_targets(&listing.id, &listing.target_tenants).await
+    }
+
+    /// Snapshot the tool published as `version` of a listing
+    ///
+    /// Snapshots are immutable: saving a version that already exists keeps the first one.
+    pub async fn save_listing_version(&self, listing_id: &str, version: &str, tool: &ToolInfo) -> StepflowResult<()> {
+        let sql = r#"
+            INSERT OR IGNORE INTO tool_listing_versions (listing_id, version, tool, published_at)
+            VALUES (?, ?, ?, ?)
+        "#;
+
+        let params = vec![
+            param::text(listing_id),
+            param::text(version),
+            param::json(tool)?,
+            param::timestamp(&Utc::now()),
+        ];
+
+        self.database.execute(sql, &params).await?;
+        Ok(())
+    }
+
+    /// Get the tool published as `version` of a listing
+    pub async fn get_listing_version(&self, listing_id: &str, version: &str) -> StepflowResult<Option<ToolInfo>> {
+        let sql = "SELECT tool FROM tool_listing_versions WHERE listing_id = ? AND version = ?";
+        let params = vec![param::text(listing_id), param::text(version)];
+
+        let result = self.database.execute(sql, &params).await?;
+        match result.rows.first().and_then(|row| row.get("tool")).and_then(|v| v.as_str()) {
+            Some(tool) => Ok(Some(serde_json::from_str(tool)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Create a subscription
+    pub async fn create_subscription(&self, subscription: &ToolSubscriptionRecord) -> StepflowResult<()> {
+        let sql = r#"
+            INSERT INTO tool_subscriptions (
+                id, listing_id, tenant_id, tool_id, installed_version, pending_version,
+                status, created_at, updated_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#;
+
+        let params = vec![
+            Value::String(subscription.id.clone()),
+            Value::String(subscription.listing_id.clone()),
+            Value::String(subscription.tenant_id.as_str().to_string()),
+            Value::String(subscription.tool_id.as_str().to_string()),
+            Value::String(subscription.installed_version.clone()),
+            subscription.pending_version.clone().map(Value::String).unwrap_or(Value::Null),
+            Value::String(subscription.status.clone()),
+            Value::String(subscription.created_at.to_rfc3339()),
+            Value::String(subscription.updated_at.to_rfc3339()),
+        ];
+
+        self.database.execute(sql, &params).await?;
+        Ok(())
+    }
+
+    /// Get a subscription by ID
+    pub async fn get_subscription(&self, subscription_id: &str) -> StepflowResult<Option<ToolSubscriptionRecord>> {
+        let sql = "SELECT * FROM tool_subscriptions WHERE id = ?";
+        let params = vec![Value::String(subscription_id.to_string())];
+
+        let result = self.database.execute(sql, &params).await?;
+        Ok(result.rows.first().and_then(row_to_subscription_record))
+    }
+
+    /// Get a tenant's subscription to a listing
+    pub async fn find_subscription(&self, listing_id: &str, tenant_id: &TenantId) -> StepflowResult<Option<ToolSubscriptionRecord>> {
+        let sql = "SELECT * FROM tool_subscriptions WHERE listing_id = ? AND tenant_id = ?";
+        let params = vec![
+            Value::String(listing_id.to_string()),
+            Value::String(tenant_id.as_str().to_string()),
+        ];
+
+        let result = self.database.execute(sql, &params).await?;
+        Ok(result.rows.first().and_then(row_to_subscription_record))
+    }
+
+    /// List subscriptions of a tenant
+    pub async fn list_subscriptions_by_tenant(&self, tenant_id: &TenantId) -> StepflowResult<Vec<ToolSubscriptionRecord>> {
+        let sql = "SELECT * FROM tool_subscriptions WHERE tenant_id = ? ORDER BY created_at DESC";
+        let params = vec![Value::String(tenant_id.as_str().to_string())];
+
+        let result = self.database.execute(sql, &params).await?;
+        Ok(result.rows.iter().filter_map(row_to_subscription_record).collect())
+    }
+
+    /// List subscriptions to a listing
+    pub async fn list_subscriptions_by_listing(&self, listing_id: &str) -> StepflowResult<Vec<ToolSubscriptionRecord>> {
+        let sql = "SELECT * FROM tool_subscriptions WHERE listing_id = ?";
+        let params = vec![Value::String(listing_id.to_string())];
+
+        let result = self.database.execute(sql, &params).await?;
+        Ok(result.rows.iter().filter_map(row_to_subscription_record).collect())
+    }
+
+    /// Update a subscription's versions and status
+    pub async fn update_subscription(&self, subscription: &ToolSubscriptionRecord) -> StepflowResult<()> {
+        let sql = r#"
+            UPDATE tool_subscriptions SET
+                installed_version = ?, pending_version = ?, status = ?, updated_at = ?
+            WHERE id = ?
+        "#;
+
+        let params = vec![
+            Value::String(subscription.installed_version.clone()),
+            subscription.pending_version.clone().map(Value::String).unwrap_or(Value::Null),
+            Value::String(subscription.status.clone()),
+            Value::String(subscription.updated_at.to_rfc3339()),
+            Value::String(subscription.id.clone()),
+        ];
+
+        let result = self.database.execute(sql, &params).await?;
+
+        if result.rows_affected == 0 {
+            return Err(StepflowError::DatabaseError(stepflow_core::DatabaseError::QueryFailed(
+                "Subscription not found".to_string()
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Delete a subscription
+    pub async fn delete_subscription(&self, subscription_id: &str) -> StepflowResult<bool> {
+        let sql = "DELETE FROM tool_subscriptions WHERE id = ?";
+        let params = vec![Value::String(subscription_id.to_string())];
+
+        let result = self.database.execute(sql, &params).await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    async fn get_listing_targets(&self, listing_id: &str) -> StepflowResult<Vec<TenantId>> {
+        let sql = "SELECT tenant_id FROM tool_listing_targets WHERE listing_id = ?";
+        let params = vec![Value::String(listing_id.to_string())];
+
+        let result = self.database.execute(sql, &params).await?;
+
+        Ok(result.rows.iter()
+            .filter_map(|row| row.get("tenant_id").and_then(|v| v.as_str()))
+            .map(|s| TenantId::from_string(s.to_string()))
+            .collect())
+    }
+}
+
+/// Shared tool listing record
+#[derive(Debug, Clone)]
+pub struct ToolListingRecord {
+    pub id: String,
+    pub tool_id: ToolId,
+    pub publisher_tenant_id: TenantId,
+    pub visibility: String,
+    pub target_tenants: Vec<TenantId>,
+    pub published_version: String,
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Tenant subscription to a shared tool listing
+#[derive(Debug, Clone)]
+pub struct ToolSubscriptionRecord {
+    pub id: String,
+    pub listing_id: String,
+    pub tenant_id: TenantId,
+    pub tool_id: ToolId,
+    pub installed_version: String,
+    pub pending_version: Option<String>,
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Replace an encrypted text column of `row` with its plaintext
+async fn decrypt_column(
+    database: &SqliteDatabase,
+    tenant_id: &TenantId,
+    row: &mut HashMap<String, Value>,
+    column: &str,
+) -> StepflowResult<()> {
+    if let Some(Value::String(value)) = row.get_mut(column) {
+        *value = TenantKeyRepository::new(database.clone()).decrypt(tenant_id, value).await?;
+    }
+    Ok(())
+}
+
+/// Helper function to convert database row to ToolConfig
+fn row_to_tool_config(row: &HashMap<String, Value>) -> Option<ToolConfig> {
+    Some(ToolConfig {
+        tool_id: ToolId::from_string(row.get("tool_id")?.as_str()?.to_string()),
+        configuration: serde_json::from_str(row.get("configuration")?.as_str()?).ok()?,
+        environment: serde_json::from_str(row.get("environment")?.as_str()?).ok()?,
+        secrets: serde_json::from_str(row.get("secrets")?.as_str()?).ok()?,
+        timeout: row.get("timeout").and_then(|v| v.as_u64()),
+        retries: row.get("retries").and_then(|v| v.as_u64()).map(|v| v as u32),
+        enabled: row.get("enabled").and_then(|v| v.as_i64()).unwrap_or(1) != 0,
+    })
+}
+
+/// Tool configuration repository for per-tenant tool settings
+pub struct ToolConfigRepository {
+    database: SqliteDatabase,
+}
+
+impl ToolConfigRepository {
+    /// Create a new tool configuration repository
+    pub fn new(database: SqliteDatabase) -> Self {
+        Self { database }
+    }
+
+    /// Insert or replace a tenant's configuration for a tool
+    pub async fn save_config(&self, tenant_id: &TenantId, config: &ToolConfig) -> StepflowResult<()> {
+        let sql = r#"
+            INSERT INTO tool_configs (
+                tenant_id, tool_id, configuration, environment, secrets,
+                timeout, retries, enabled, created_at, updated_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (tenant_id, tool_id) DO UPDATE SET
+                configuration = excluded.configuration,
+                environment = excluded.environment,
+                secrets = excluded.secrets,
+                timeout = excluded.timeout,
+                retries = excluded.retries,
+                enabled = excluded.enabled,
+                updated_at = excluded.updated_at
+        "#;
+
+        let secrets = TenantKeyRepository::new(self.database.clone())
+            .encrypt(tenant_id, &serde_json::to_string(&config.secrets)?)
+            .await?;
+        let now = chrono::Utc::now().to_rfc3339();
+        let params = vec![
+            Value::String(tenant_id.as_str().to_string()),
+            Value::String(config.tool_id.as_str().to_string()),
+            Value::String(serde_json::to_string(&config.configuration)?),
+            Value::String(serde_json::to_string(&config.environment)?),
+            Value::String(secrets),
+            config.timeout.map(Value::from).unwrap_or(Value::Null),
+            config.retries.map(Value::from).unwrap_or(Value::Null),
+            Value::from(if config.enabled { 1 } else { 0 }),
+            Value::String(now.clone()),
+            Value::String(now),
+        ];
+
+        self.database.execute(sql, &params).await?;
+        Ok(())
+    }
+
+    /// Get a tenant's configuration for a tool
+    pub async fn get_config(&self, tenant_id: &TenantId, tool_id: &ToolId) -> StepflowResult<Option<ToolConfig>> {
+        let sql = "SELECT * FROM tool_configs WHERE tenant_id = ? AND tool_id = ?";
+        let params = vec![
+            Value::String(tenant_id.as_str().to_string()),
+            Value::String(tool_id.as_str().to_string()),
+        ];
+
+        let mut result = self.database.execute(sql, &params).await?;
+        let Some(row) = result.rows.first_mut() else {
+            return Ok(None);
+        };
+        decrypt_column(&self.database, tenant_id, row, "secrets").await?;
+        Ok(row_to_tool_config(row))
+    }
+
+    /// Delete a tenant's configuration for a tool, returning whether it existed
+    pub async fn delete_config(&self, tenant_id: &TenantId, tool_id: &ToolId) -> StepflowResult<bool> {
+        let sql = "DELETE FROM tool_configs WHERE tenant_id = ? AND tool_id = ?";
+        let params = vec![
+            Value::String(tenant_id.as_str().to_string()),
+            Value::String(tool_id.as_str().to_string()),
+        ];
+
+        let result = self.database.execute(sql, &params).await?;
+        Ok(result.rows_affected > 0)
+    }
+}
+
+/// Markdown documentation of one tool version
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ToolDocsRecord {
+    pub tool_id: ToolId,
+    pub version: String,
+    pub markdown: String,
+    pub updated_by: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+fn row_to_tool_docs(row: &HashMap<String, Value>) -> Option<ToolDocsRecord> {
+    Some(ToolDocsRecord {
+        tool_id: ToolId::from_string(row.get("tool_id")?.as_str()?.to_string()),
+        version: row.get("version")?.as_str()?.to_string(),
+        markdown: row.get("markdown")?.as_str()?.to_string(),
+        updated_by: row.get("updated_by").and_then(|v| v.as_str()).map(str::to_string),
+        updated_at: row.get("updated_at")?.as_str()?.parse().ok()?,
+    })
+}
+
+/// Repository for tool documentation
+pub struct ToolDocsRepository {
+    database: SqliteDatabase,
+}
+
+impl ToolDocsRepository {
+    /// Create a new tool documentation repository
+    pub fn new(database: SqliteDatabase) -> Self {
+        Self { database }
+    }
+
+    /// Store the documentation of a tool version, replacing earlier documentation
+    pub async fn save_docs(
+        &self,
+        tool_id: &ToolId,
+        version: &str,
+        markdown: &str,
+        updated_by: Option<&str>,
+    ) -> StepflowResult<ToolDocsRecord> {
+        let record = ToolDocsRecord {
+            tool_id: tool_id.clone(),
+            version: version.to_string(),
+            markdown: markdown.to_string(),
+            updated_by: updated_by.map(str::to_string),
+            updated_at: Utc::now(),
+        };
+        let sql = r#"
+            INSERT INTO tool_docs (tool_id, version, markdown, updated_by, updated_at)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT(tool_id, version) DO UPDATE SET
+                markdown = excluded.markdown,
+                updated_by = excluded.updated_by,
+                updated_at = excluded.updated_at
+        "#;
+        self.database.execute(sql, &[
+            param::text(tool_id.as_str()),
+            param::text(version),
+            param::text(markdown),
+            param::opt_text(updated_by),
+            param::timestamp(&record.updated_at),
+        ]).await?;
+        Ok(record)
+    }
+
+    pub async fn get_docs(&self, tool_id: &ToolId, version: &str) -> StepflowResult<Option<ToolDocsRecord>> {
+        let result = self.database.execute(
+            "SELECT * FROM tool_docs WHERE tool_id = ? AND version = ?",
+            &[param::text(tool_id.as_str()), param::text(version)],
+        ).await?;
+        Ok(result.rows.first().and_then(row_to_tool_docs))
+    }
+
+    /// The most recently updated documentation of any version of the tool
+    pub async fn latest_docs(&self, tool_id: &ToolId) -> StepflowResult<Option<ToolDocsRecord>> {
+        let result = self.database.execute(
+            "SELECT * FROM tool_docs WHERE tool_id = ? ORDER BY updated_at DESC LIMIT 1",
+            &[param::text(tool_id.as_str())],
+        ).await?;
+        Ok(result.rows.first().and_then(row_to_tool_docs))
+    }
+
+    /// Documented versions of a tool, most recently updated first
+    pub async fn list_versions(&self, tool_id: &ToolId) -> StepflowResult<Vec<String>> {
+        let result = self.database.execute(
+            "SELECT version FROM tool_docs WHERE tool_id = ? ORDER BY updated_at DESC",
+            &[param::text(tool_id.as_str())],
+        ).await?;
+        Ok(result.rows.iter().filter_map(|row| row.get("version")?.as_str().map(str::to_string)).collect())
+    }
+
+    pub async fn delete_docs(&self, tool_id: &ToolId, version: &str) -> StepflowResult<bool> {
+        let result = self.database.execute(
+            "DELETE FROM tool_docs WHERE tool_id = ? AND version = ?",
+            &[param::text(tool_id.as_str()), param::text(version)],
+        ).await?;
+        Ok(result.rows_affected > 0)
+    }
+}
+
+/// JSON Schema of one version of an event payload schema
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct EventSchemaRecord {
+    /// Schema name as used in `dataschema`, e.g. `execution.state_changed`
+    pub name: String,
+    pub version: u32,
+    pub json_schema: Value,
+    /// Compatibility mode the version was checked against when published
+    pub compatibility: String,
+    pub description: Option<String>,
+    pub created_by: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+fn row_to_event_schema(row: &HashMap<String, Value>) -> Option<EventSchemaRecord> {
+    Some(EventSchemaRecord {
+        name: row.get("name")?.as_str()?.to_string(),
+        version: u32::try_from(row.get("version")?.as_i64()?).ok()?,
+        json_schema: serde_json::from_str(row.get("json_schema")?.as_str()?).ok()?,
+        compatibility: row.get("compatibility")?.as_str()?.to_string(),
+        description: row.get("description").and_then(|v| v.as_str()).map(str::to_string),
+        created_by: row.get("created_by").and_then(|v| v.as_str()).map(str::to_string),
+        created_at: row.get("created_at")?.as_str()?.parse().ok()?,
+    })
+}
+
+/// Repository for event payload schemas
+pub struct EventSchemaRepository {
+    database: SqliteDatabase,
+}
+
+impl EventSchemaRepository {
+    /// Create a new event schema repository
+    pub fn new(database: SqliteDatabase) -> Self {
+        Self { database }
+    }
+
+    /// Store a schema version; versions are immutable, so an existing version is an error
+    pub async fn insert_schema(&self, record: &EventSchemaRecord) -> StepflowResult<()> {
+        let sql = r#"
+            INSERT INTO event_schemas (name, version, json_schema, compatibility, description, created_by, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+        "#;
+        self.database.execute(sql, &[
+            param::text(record.name.as_str()),
+            param::int(record.version),
+            param::text(record.json_schema.to_string()),
+            param::text(record.compatibility.as_str()),
+            param::opt_text(record.description.as_deref()),
+            param::opt_text(record.created_by.as_deref()),
+            param::timestamp(&record.created_at),
+        ]).await?;
+        Ok(())
+    }
+
+    pub async fn get_schema(&self, name: &str, version: u32) -> StepflowResult<Option<EventSchemaRecord>> {
+        let result = self.database.execute(
+            "SELECT * FROM event_schemas WHERE name = ? AND version = ?",
+            &[param::text(name), param::int(version)],
+        ).await?;
+        Ok(result.rows.first().and_then(row_to_event_schema))
+    }
+
+    /// The highest version of a schema
+    pub async fn latest_schema(&self, name: &str) -> StepflowResult<Option<EventSchemaRecord>> {
+        let result = self.database.execute(
+            "SELECT * FROM event_schemas WHERE name = ? ORDER BY version DESC LIMIT 1",
+            &[param::text(name)],
+        ).await?;
+        Ok(result.rows.first().and_then(row_to_event_schema))
+    }
+
+    /// Every version of a schema, oldest first
+    pub async fn list_versions(&self, name: &str) -> StepflowResult<Vec<EventSchemaRecord>> {
+        let result = self.database.execute(
+            "SELECT * FROM event_schemas WHERE name = ? ORDER BY version",
+            &[param::text(name)],
+        ).await?;
+        Ok(result.rows.iter().filter_map(row_to_event_schema).collect())
+    }
+
+    /// The latest version of every schema, by name
+    pub async fn list_latest(&self) -> StepflowResult<Vec<EventSchemaRecord>> {
+        let result = self.database.execute(
+            r#"
+            SELECT s.* FROM event_schemas s
+            WHERE s.version = (SELECT MAX(version) FROM event_schemas WHERE name = s.name)
+            ORDER BY s.name
+            "#,
+            &[],
+        ).await?;
+        Ok(result.rows.iter().filter_map(row_to_event_schema).collect())
+    }
+}
+
+/// Registry change record, one row per tool mutation
+#[derive(Debug, Clone)]
+pub struct RegistryChangeRecord {
+    pub sequence: i64,
+    pub change_type: String,
+    pub tool_id: ToolId,
+    pub version: String,
+    pub payload: Option<Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Helper function to convert database row to RegistryChangeRecord
+fn row_to_registry_change(row: &HashMap<String, Value>) -> Option<RegistryChangeRecord> {
+    Some(RegistryChangeRecord {
+        sequence: row.get("sequence")?.as_i64()?,
+        change_type: row.get("change_type")?.as_str()?.to_string(),
+        tool_id: ToolId::from_string(row.get("tool_id")?.as_str()?.to_string()),
+        version: row.get("version")?.as_str()?.to_string(),
+        payload: row.get("payload")
+            .and_then(|v| v.as_str())
+            .and_then(|s| serde_json::from_str(s).ok()),
+        created_at: row.get("created_at")?.as_str()?.parse().ok()?,
+    })
+}
+
+/// Registry change repository backing the change data capture feed
+pub struct RegistryChangeRepository {
+    database: SqliteDatabase,
+}
+
+impl RegistryChangeRepository {
+    /// Create a new registry change repository
+    pub fn new(database: SqliteDatabase) -> Self {
+        Self { database }
+    }
+
+    /// Append a change and return it with its assigned sequence number
+    pub async fn record_change(
+        &self,
+        change_type: &str,
+        tool_id: &ToolId,
+        version: &str,
+        payload: Option<&Value>,
+    ) -> StepflowResult<RegistryChangeRecord> {
+        let sql = r#"
+            INSERT INTO registry_changes (change_type, tool_id, version, payload, created_at)
+            VALUES (?, ?, ?, ?, ?)
+        "#;
+
+        let created_at = Utc::now();
+        let params = vec![
+            Value::String(change_type.to_string()),
+            Value::String(tool_id.as_str().to_string()),
+            Value::String(version.to_string()),
+            match payload {
+                Some(payload) => Value::String(serde_json::to_string(payload)?),
+                None => Value::Null,
+            },
+            Value::String(created_at.to_rfc3339()),
+        ];
+
+        let result = self.database.execute(sql, &params).await?;
+        let sequence = result.last_insert_id
+            .ok_or_else(|| StepflowError::DatabaseError(stepflow_core::DatabaseError::QueryFailed(
+                "No sequence assigned to registry change".to_string()
+            )))?;
+
+        Ok(RegistryChangeRecord {
+            sequence,
+            change_type: change_type.to_string(),
+            tool_id: tool_id.clone(),
+            version: version.to_string(),
+            payload: payload.cloned(),
+            created_at,
+        })
+    }
+
+    /// List changes with a sequence number greater than `since`, oldest first
+    pub async fn list_changes_since(&self, since: i64, limit: usize) -> StepflowResult<Vec<RegistryChangeRecord>> {
+        let sql = "SELECT * FROM registry_changes WHERE sequence > ? ORDER BY sequence ASC LIMIT ?";
+        let params = vec![Value::from(since), Value::from(limit as i64)];
+
+        let result = self.database.execute(sql, &params).await?;
+        Ok(result.rows.iter().filter_map(row_to_registry_change).collect())
+    }
+
+    /// Latest assigned sequence number, 0 when no changes were recorded
+    pub async fn latest_sequence(&self) -> StepflowResult<i64> {
+        let sql = "SELECT sequence FROM registry_changes ORDER BY sequence DESC LIMIT 1";
+        let result = self.database.execute(sql, &[]).await?;
+        Ok(result.rows.first()
+            .and_then(|row| row.get("sequence"))
+            .and_then(|v| v.as_i64())
+            .unwrap_or(0))
+    }
+}
+
+/// System-wide operational mode
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationalMode {
+    #[default]
+    Normal,
+    /// Reads are served but new executions are rejected
+    Maintenance,
+    /// Every mutation is rejected
+    ReadOnly,
+}
+
+impl OperationalMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OperationalMode::Normal => "normal",
+            OperationalMode::Maintenance => "maintenance",
+            OperationalMode::ReadOnly => "read_only",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "normal" => Some(OperationalMode::Normal),
+            "maintenance" => Some(OperationalMode::Maintenance),
+            "read_only" | "read-only" => Some(OperationalMode::ReadOnly),
+            _ => None,
+        }
+    }
+
+    /// Whether new executions may be started
+    pub fn accepts_executions(&self) -> bool {
+        *self == OperationalMode::Normal
+    }
+
+    /// Whether data may be changed
+    pub fn accepts_writes(&self) -> bool {
+        *self != OperationalMode::ReadOnly
+    }
+}
+
+/// Current operational mode and who set it
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct OperationalModeRecord {
+    pub mode: OperationalMode,
+    /// Shown to clients whose requests are rejected
+    pub message: Option<String>,
+    pub updated_by: Option<String>,
+    /// `None` when the mode has never been changed
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Repository for the persisted operational mode, shared by all server instances
+pub struct OperationalModeRepository {
+    database: SqliteDatabase,
+}
+
+impl OperationalModeRepository {
+    /// Create a new operational mode repository
+    pub fn new(database: SqliteDatabase) -> Self {
+        Self { database }
+    }
+
+    /// Current mode; [`OperationalMode::Normal`] when it has never been set
+    pub async fn get(&self) -> StepflowResult<OperationalModeRecord> {
+        let sql = "SELECT mode, message, updated_by, updated_at FROM operational_mode WHERE id = 1";
+        let result = self.database.execute(sql, &[]).await?;
+        let Some(row) = result.rows.first() else {
+            return Ok(OperationalModeRecord::default());
+        };
+
+        let optional = |key: &str| row.get(key).and_then(|v| v.as_str()).map(|s| s.to_string());
+        let mode = optional("mode").unwrap_or_default();
+        Ok(OperationalModeRecord {
+            mode: OperationalMode::parse(&mode)
+                .ok_or_else(|| StepflowError::InternalError(format!("Unknown operational mode '{}'", mode)))?,
+            message: optional("message"),
+            updated_by: optional("updated_by"),
+            updated_at: optional("updated_at").and_then(|s| s.parse().ok()),
+        })
+    }
+
+    /// Persist a new mode
+    pub async fn set(
+        &self,
+        mode: OperationalMode,
+        message: Option<&str>,
+        updated_by: Option<&str>,
+    ) -> StepflowResult<OperationalModeRecord> {
+        let now = Utc::now();
+        let sql = r#"
+            INSERT INTO operational_mode (id, mode, message, updated_by, updated_at)
+            VALUES (1, ?, ?, ?, ?)
+            ON CONFLICT(id) DO UPDATE SET
+                mode = excluded.mode,
+                message = excluded.message,
+                updated_by = excluded.updated_by,
+                updated_at = excluded.updated_at
+        "#;
+        let params = vec![
+            param::text(mode.as_str()),
+            param::opt_text(message),
+            param::opt_text(updated_by),
+            param::timestamp(&now),
+        ];
+        self.database.execute(sql, &params).await?;
+
+        Ok(OperationalModeRecord {
+            mode,
+            message: message.map(str::to_string),
+            updated_by: updated_by.map(str::to_string),
+            updated_at: Some(now),
+        })
+    }
+}
+
+fn row_to_feature_flag(row: &HashMap<String, Value>) -> Option<FlagDefinition> {
+    Some(FlagDefinition {
+        name: row.get("name")?.as_str()?.to_string(),
+        description: row.get("description").and_then(|v| v.as_str()).map(str::to_string),
+        enabled: row.get("enabled")?.as_i64()? != 0,
+        rollout_percentage: u8::try_from(row.get("rollout_percentage")?.as_i64()?).ok()?,
+        tenants: serde_json::from_str(row.get("tenants")?.as_str()?).ok()?,
+        users: serde_json::from_str(row.get("users")?.as_str()?).ok()?,
+        updated_by: row.get("updated_by").and_then(|v| v.as_str()).map(str::to_string),
+        updated_at: row.get("updated_at").and_then(|v| v.as_str()).and_then(|s| s.parse().ok()),
+    })
+}
+
+/// Repository for feature flags changed at runtime, shared by all server instances
+pub struct FeatureFlagRepository {
+    database: SqliteDatabase,
+}
+
+impl FeatureFlagRepository {
+    /// Create a new feature flag repository
+    pub fn new(database: SqliteDatabase) -> Self {
+        Self { database }
+    }
+
+    /// All stored flags, sorted by name
+    pub async fn list(&self) -> StepflowResult<Vec<FlagDefinition>> {
+        let result = self.database.execute("SELECT * FROM feature_flags ORDER BY name", &[]).await?;
+        Ok(result.rows.iter().filter_map(row_to_feature_flag).collect())
+    }
+
+    pub async fn get(&self, name: &str) -> StepflowResult<Option<FlagDefinition>> {
+        let result = self.database.execute("SELECT * FROM feature_flags WHERE name = ?", &[param::text(name)]).await?;
+        Ok(result.rows.first().and_then(row_to_feature_flag))
+    }
+
+    /// Insert or replace a flag, stamping who changed it and when
+    pub async fn upsert(&self, definition: &FlagDefinition, updated_by: Option<&str>) -> StepflowResult<FlagDefinition> {
+        definition.validate()?;
+        let now = Utc::now();
+        let sql = r#"
+            INSERT INTO feature_flags (name, description, enabled, rollout_percentage, tenants, users, updated_by, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(name) DO UPDATE SET
+                description = excluded.description,
+                enabled = excluded.enabled,
+                rollout_percentage = excluded.rollout_percentage,
+                tenants = excluded.tenants,
+                users = excluded.users,
+                updated_by = excluded.updated_by,
+                updated_at = excluded.updated_at
+        "#;
+        self.database.execute(sql, &[
+            param::text(definition.name.as_str()),
+            param::opt_text(definition.description.as_deref()),
+            param::flag(definition.enabled),
+            param::int(definition.rollout_percentage),
+            param::json(&definition.tenants)?,
+            param::json(&definition.users)?,
+            param::opt_text(updated_by),
+            param::timestamp(&now),
+        ]).await?;
+
+        Ok(FlagDefinition {
+            updated_by: updated_by.map(str::to_string),
+            updated_at: Some(now),
+            ..definition.clone()
+        })
+    }
+
+    /// Delete a flag, returning whether it existed
+    pub async fn delete(&self, name: &str) -> StepflowResult<bool> {
+        let result = self.database.execute("DELETE FROM feature_flags WHERE name = ?", &[param::text(name)]).await?;
+        Ok(result.rows_affected > 0)
+    }
+}
+
+/// Log repository for execution log records
+pub struct LogRepository {
+    database: SqliteDatabase,
+}
+
+impl LogRepository {
+    /// Create a new log repository
+    pub fn new(database: SqliteDatabase) -> Self {
+        Self { database }
+    }
+
+    /// Insert a single log entry for an execution
+    pub async fn insert_log(&self, execution_id: &str, log: &LogEntry) -> StepflowResult<()> {
+        let sql = format!("{} VALUES (?, ?, ?, ?, ?, ?)", LOG_INSERT_PREFIX);
+        self.database.execute(&sql, &log_insert_params(execution_id, log)?).await?;
+        Ok(())
+    }
+
+    /// Insert many `(execution_id, entry)` log records in one transaction,
+    /// reporting entries that could not be inserted
+    pub async fn insert_logs_batch(&self, logs: &[(String, LogEntry)]) -> StepflowResult<BatchInsertReport> {
+        let rows = logs
+            .iter()
+            .map(|(execution_id, log)| log_insert_params(execution_id, log))
+            .collect::<StepflowResult<Vec<_>>>()?;
+        self.database.insert_batch(LOG_INSERT_PREFIX, &rows, DEFAULT_BATCH_CHUNK_SIZE).await
+    }
+}
+
+const LOG_INSERT_PREFIX: &str = "INSERT INTO logs (execution_id, level, message, timestamp, source, metadata)";
+
+fn log_insert_params(execution_id: &str, log: &LogEntry) -> StepflowResult<Vec<Value>> {
+    Ok(vec![
+        param::text(execution_id),
+        param::text(format!("{:?}", log.level)),
+        param::text(log.message.as_str()),
+        param::timestamp(&log.timestamp),
+        param::text(log.source.as_str()),
+        param::json(&log.metadata)?,
+    ])
+}
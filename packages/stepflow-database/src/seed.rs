@@ -0,0 +1,356 @@
+//! Database seeding
+//!
+//! Loads declarative fixture files with tenants, users, tools and executions into a
+//! database for development and tests. Every record is upserted by id, so loading the
+//! same fixture twice leaves the database unchanged apart from refreshed timestamps.
+//! A fixture only loads into the environments it lists (`development` and `test` by
+//! default), which keeps production from being seeded by accident.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use stepflow_core::{
+    Database, StepflowError, StepflowResult, TenantId, ToolId, ToolImage, ToolInfo, ToolLocalization, ToolSessionCommand,
+    ToolStatus, ToolType, ToolVersion, UserId,
+};
+
+use crate::repositories::{
+    execution_insert_params, tool_insert_params, ExecutionRecord, EXECUTION_INSERT_PREFIX, TOOL_INSERT_PREFIX,
+};
+use crate::utils::{hash_password, param};
+use crate::SqliteDatabase;
+
+/// Environment variable naming the current deployment environment
+pub const ENVIRONMENT_VAR: &str = "STEPFLOW_ENV";
+
+/// Environments a fixture loads into when it doesn't list any
+pub const DEFAULT_SEED_ENVIRONMENTS: &[&str] = &["development", "test"];
+
+/// Environment assumed when `STEPFLOW_ENV` is unset
+///
+/// Seeding fails closed: a host that doesn't say what it is gets treated as production.
+pub const FALLBACK_ENVIRONMENT: &str = "production";
+
+/// The current environment from `STEPFLOW_ENV`, [`FALLBACK_ENVIRONMENT`] when unset
+pub fn current_environment() -> String {
+    std::env::var(ENVIRONMENT_VAR)
+        .ok()
+        .filter(|env| !env.trim().is_empty())
+        .unwrap_or_else(|| FALLBACK_ENVIRONMENT.to_string())
+}
+
+/// A fixture file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SeedFixture {
+    /// Environments the fixture may be loaded into; `production` must be listed explicitly
+    pub environments: Vec<String>,
+    pub tenants: Vec<SeedTenant>,
+    pub users: Vec<SeedUser>,
+    pub tools: Vec<SeedTool>,
+    pub executions: Vec<SeedExecution>,
+}
+
+/// Tenant fixture
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeedTenant {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub domain: Option<String>,
+    #[serde(default)]
+    pub settings: HashMap<String, Value>,
+}
+
+/// User fixture; the password is hashed when seeded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeedUser {
+    pub id: String,
+    pub tenant_id: String,
+    pub username: String,
+    pub email: String,
+    pub password: String,
+    /// `admin`, `user`, `guest` or `custom:<name>`
+    #[serde(default = "default_role")]
+    pub role: String,
+    #[serde(default)]
+    pub email_verified: bool,
+}
+
+fn default_role() -> String {
+    "user".to_string()
+}
+
+/// Tool fixture
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeedTool {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// `major.minor.patch`
+    #[serde(default = "default_version")]
+    pub version: String,
+    pub tool_type: ToolType,
+    #[serde(default = "default_tool_status")]
+    pub status: ToolStatus,
+    #[serde(default = "default_author")]
+    pub author: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    #[serde(default)]
+    pub configuration_schema: Option<Value>,
+    #[serde(default)]
+    pub input_schema: Option<Value>,
+    #[serde(default)]
+    pub localizations: HashMap<String, ToolLocalization>,
+    #[serde(default)]
+    pub environment: HashMap<String, String>,
+    #[serde(default)]
+    pub session: Option<ToolSessionCommand>,
+    #[serde(default)]
+    pub image: Option<ToolImage>,
+}
+
+fn default_version() -> String {
+    "1.0.0".to_string()
+}
+
+fn default_tool_status() -> ToolStatus {
+    ToolStatus::Active
+}
+
+fn default_author() -> String {
+    "stepflow".to_string()
+}
+
+/// Execution fixture
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeedExecution {
+    pub id: String,
+    pub tool_id: String,
+    pub tenant_id: String,
+    pub user_id: String,
+    #[serde(default = "default_execution_status")]
+    pub status: String,
+    #[serde(default)]
+    pub request: Value,
+    #[serde(default)]
+    pub result: Option<Value>,
+    /// Defaults to the time of seeding
+    #[serde(default)]
+    pub started_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+fn default_execution_status() -> String {
+    "completed".to_string()
+}
+
+/// Number of records upserted per kind
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SeedReport {
+    pub tenants: usize,
+    pub users: usize,
+    pub tools: usize,
+    pub executions: usize,
+}
+
+impl SeedFixture {
+    /// Read a JSON fixture file
+    pub fn from_file(path: impl AsRef<Path>) -> StepflowResult<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            StepflowError::ConfigurationError(format!("Failed to read fixture {}: {}", path.display(), e))
+        })?;
+        serde_json::from_str(&content).map_err(|e| {
+            StepflowError::DeserializationError(format!("Invalid fixture {}: {}", path.display(), e))
+        })
+    }
+
+    /// Whether the fixture may be loaded into `environment`
+    pub fn allows_environment(&self, environment: &str) -> bool {
+        if self.environments.is_empty() {
+            DEFAULT_SEED_ENVIRONMENTS.contains(&environment)
+        } else {
+            self.environments.iter().any(|allowed| allowed == environment)
+        }
+    }
+
+    /// Upsert every record into `database`, refusing environments the fixture doesn't allow
+    pub async fn apply(&self, database: &SqliteDatabase, environment: &str) -> StepflowResult<SeedReport> {
+        if !self.allows_environment(environment) {
+            return Err(StepflowError::PermissionDenied(format!(
+                "Fixture may not be seeded into the '{}' environment",
+                environment
+            )));
+        }
+
+        let now = Utc::now();
+        for tenant in &self.tenants {
+            upsert_tenant(database, tenant, now).await?;
+        }
+        for user in &self.users {
+            upsert_user(database, user, now).await?;
+        }
+        for tool in &self.tools {
+            let sql = format!(
+                "{} VALUES ({}) ON CONFLICT(id) DO UPDATE SET {}",
+                TOOL_INSERT_PREFIX,
+                placeholders(26),
+                update_clause(&[
+                    "name", "description", "version_major", "version_minor", "version_patch",
+                    "version_pre_release", "version_build", "tool_type", "status", "author",
+                    "repository", "documentation", "tags", "capabilities", "configuration_schema",
+                    "examples", "updated_at", "environment_label", "requirements", "input_schema",
+                    "localizations", "environment", "session", "image",
+                ])
+            );
+            database.execute(&sql, &tool_insert_params(&tool.to_tool_info(now)?)?).await?;
+        }
+        for execution in &self.executions {
+            let sql = format!(
+                "{} VALUES ({}) ON CONFLICT(id) DO UPDATE SET {}",
+                EXECUTION_INSERT_PREFIX,
+                placeholders(11),
+                update_clause(&[
+                    "tool_id", "tenant_id", "user_id", "status", "request", "result",
+                    "started_at", "completed_at", "updated_at",
+                ])
+            );
+            database.execute(&sql, &execution_insert_params(&execution.to_record(now))?).await?;
+        }
+
+        Ok(SeedReport {
+            tenants: self.tenants.len(),
+            users: self.users.len(),
+            tools: self.tools.len(),
+            executions: self.executions.len(),
+        })
+    }
+}
+
+impl SeedTool {
+    fn to_tool_info(&self, now: DateTime<Utc>) -> StepflowResult<ToolInfo> {
+        let parts: Vec<u32> = self
+            .version
+            .split('.')
+            .map(|part| part.parse())
+            .collect::<Result<_, _>>()
+            .map_err(|_| StepflowError::ValidationError(format!("Invalid version '{}' for tool {}", self.version, self.id)))?;
+        let [major, minor, patch] = parts[..] else {
+            return Err(StepflowError::ValidationError(format!(
+                "Invalid version '{}' for tool {}",
+                self.version, self.id
+            )));
+        };
+
+        ToolInfo::builder()
+            .id(ToolId::from_string(self.id.clone()))
+            .name(self.name.clone())
+            .description(self.description.clone())
+            .version(ToolVersion::new(major, minor, patch))
+            .tool_type(self.tool_type.clone())
+            .status(self.status.clone())
+            .author(self.author.clone())
+            .tags(self.tags.clone())
+            .capabilities(self.capabilities.clone())
+            .configuration_schema(self.configuration_schema.clone())
+            .created_at(now)
+            .input_schema(self.input_schema.clone())
+            .localizations(self.localizations.clone())
+            .environment(self.environment.clone())
+            .session(self.session.clone())
+            .image(self.image.clone())
+            .build()
+            .map_err(|e| StepflowError::ValidationError(format!("Invalid tool {}: {}", self.id, e)))
+    }
+}
+
+impl SeedExecution {
+    fn to_record(&self, now: DateTime<Utc>) -> ExecutionRecord {
+        ExecutionRecord {
+            id: self.id.clone(),
+            tool_id: ToolId::from_string(self.tool_id.clone()),
+            tenant_id: TenantId::from_string(self.tenant_id.clone()),
+            user_id: UserId::from_string(self.user_id.clone()),
+            status: self.status.clone(),
+            request: self.request.clone(),
+            result: self.result.clone(),
+            started_at: self.started_at.unwrap_or(now),
+            completed_at: self.completed_at,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+async fn upsert_tenant(database: &SqliteDatabase, tenant: &SeedTenant, now: DateTime<Utc>) -> StepflowResult<()> {
+    let sql = r#"
+        INSERT INTO tenants (id, name, description, domain, settings, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT(id) DO UPDATE SET
+            name = excluded.name, description = excluded.description, domain = excluded.domain,
+            settings = excluded.settings, updated_at = excluded.updated_at
+    "#;
+    let params = [
+        param::text(tenant.id.as_str()),
+        param::text(tenant.name.as_str()),
+        param::text(tenant.description.as_str()),
+        param::opt_text(tenant.domain.as_deref()),
+        param::json(&tenant.settings)?,
+        param::timestamp(&now),
+        param::timestamp(&now),
+    ];
+    database.execute(sql, &params).await?;
+    Ok(())
+}
+
+async fn upsert_user(database: &SqliteDatabase, user: &SeedUser, now: DateTime<Utc>) -> StepflowResult<()> {
+    let password_hash = hash_password(&user.password)
+        .map_err(|e| StepflowError::InternalError(format!("Failed to hash password for {}: {}", user.id, e)))?;
+    let sql = r#"
+        INSERT INTO users (
+            id, username, email, password_hash, role, tenant_id, settings,
+            email_verified, created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT(id) DO UPDATE SET
+            username = excluded.username, email = excluded.email, password_hash = excluded.password_hash,
+            role = excluded.role, tenant_id = excluded.tenant_id, email_verified = excluded.email_verified,
+            updated_at = excluded.updated_at
+    "#;
+    let params = [
+        param::text(user.id.as_str()),
+        param::text(user.username.as_str()),
+        param::text(user.email.as_str()),
+        param::text(password_hash),
+        param::text(user.role.as_str()),
+        param::text(user.tenant_id.as_str()),
+        param::text("{}"),
+        param::flag(user.email_verified),
+        param::timestamp(&now),
+        param::timestamp(&now),
+    ];
+    database.execute(sql, &params).await?;
+    Ok(())
+}
+
+fn placeholders(count: usize) -> String {
+    vec!["?"; count].join(", ")
+}
+
+fn update_clause(columns: &[&str]) -> String {
+    columns
+        .iter()
+        .map(|column| format!("{0} = excluded.{0}", column))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
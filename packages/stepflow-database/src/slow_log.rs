@@ -0,0 +1,186 @@
+//! Slow query log
+//!
+//! Every statement run through [`Database::execute`] that takes longer than
+//! [`DatabaseConfig::slow_query_threshold`](crate::DatabaseConfig::slow_query_threshold)
+//! is captured in `slow_queries` together with its `EXPLAIN QUERY PLAN` output.
+//! Only the SQL text is kept, never the bound parameters, which may hold secrets.
+//! Capturing is best effort: a failure is logged and never fails the query.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use sqlx::Row;
+use stepflow_core::{Database, StepflowResult};
+use tracing::debug;
+
+use crate::connection::bind_params;
+use crate::utils::param;
+use crate::SqliteDatabase;
+
+/// Statements touching the log itself are never captured
+const SLOW_QUERY_TABLE: &str = "slow_queries";
+
+/// Longest SQL text kept per entry
+const MAX_STATEMENT_LEN: usize = 4096;
+
+/// A captured slow statement
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SlowQuery {
+    pub id: String,
+    /// SQL text with whitespace collapsed, identical for every run of the statement
+    pub statement: String,
+    pub duration_ms: u64,
+    pub success: bool,
+    /// Indented `EXPLAIN QUERY PLAN` output, `None` when the statement cannot be explained
+    pub plan: Option<String>,
+    pub captured_at: DateTime<Utc>,
+}
+
+/// Captured runs of one statement
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SlowQuerySummary {
+    pub statement: String,
+    pub occurrences: u64,
+    pub mean_duration_ms: f64,
+    pub max_duration_ms: u64,
+    pub last_seen: DateTime<Utc>,
+    /// Plan of the most recent run
+    pub plan: Option<String>,
+}
+
+/// Collapse whitespace so every run of a statement shares one text
+pub fn normalize_statement(sql: &str) -> String {
+    let mut statement = sql.split_whitespace().collect::<Vec<_>>().join(" ");
+    if statement.len() > MAX_STATEMENT_LEN {
+        let mut end = MAX_STATEMENT_LEN;
+        while !statement.is_char_boundary(end) {
+            end -= 1;
+        }
+        statement.truncate(end);
+    }
+    statement
+}
+
+impl SqliteDatabase {
+    /// Record a statement that exceeded the slow query threshold
+    pub(crate) async fn capture_slow_query(&self, query: &str, params: &[Value], duration: Duration, success: bool) {
+        if query.contains(SLOW_QUERY_TABLE) {
+            return;
+        }
+        let plan = self.explain_query_plan(query, params).await;
+        let inserted = sqlx::query(
+            "INSERT INTO slow_queries (id, statement, duration_ms, success, plan, captured_at) VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(normalize_statement(query))
+        .bind(duration.as_millis() as i64)
+        .bind(success)
+        .bind(plan)
+        .bind(Utc::now().to_rfc3339())
+        .execute(self.writer_pool())
+        .await;
+        if let Err(e) = inserted {
+            debug!("Failed to capture slow query: {}", e);
+        }
+    }
+
+    /// `EXPLAIN QUERY PLAN` of a statement as an indented tree
+    async fn explain_query_plan(&self, query: &str, params: &[Value]) -> Option<String> {
+        let explain = format!("EXPLAIN QUERY PLAN {}", query);
+        let rows = bind_params(sqlx::query(&explain), params).ok()?.fetch_all(self.pool()).await.ok()?;
+
+        let mut depths: HashMap<i64, usize> = HashMap::new();
+        let lines: Vec<String> = rows
+            .iter()
+            .filter_map(|row| {
+                let id: i64 = row.try_get(0).ok()?;
+                let parent: i64 = row.try_get(1).ok()?;
+                let detail: String = row.try_get(3).ok()?;
+                let depth = depths.get(&parent).map(|depth| depth + 1).unwrap_or(0);
+                depths.insert(id, depth);
+                Some(format!("{}{}", "  ".repeat(depth), detail))
+            })
+            .collect();
+        (!lines.is_empty()).then(|| lines.join("\n"))
+    }
+}
+
+/// Reads and prunes the slow query log
+pub struct SlowQueryRepository {
+    database: SqliteDatabase,
+}
+
+impl SlowQueryRepository {
+    pub fn new(database: SqliteDatabase) -> Self {
+        Self { database }
+    }
+
+    /// Most recent captures, newest first
+    pub async fn list(&self, limit: usize) -> StepflowResult<Vec<SlowQuery>> {
+        let result = self.database.execute(
+            "SELECT id, statement, duration_ms, success, plan, captured_at FROM slow_queries ORDER BY captured_at DESC LIMIT ?",
+            &[param::int(limit as i64)],
+        ).await?;
+        Ok(result.rows.iter().filter_map(row_to_slow_query).collect())
+    }
+
+    /// Captures grouped by statement, slowest total time first
+    pub async fn summaries(&self, limit: usize) -> StepflowResult<Vec<SlowQuerySummary>> {
+        let result = self.database.execute(
+            "SELECT id, statement, duration_ms, success, plan, captured_at FROM slow_queries ORDER BY captured_at",
+            &[],
+        ).await?;
+
+        // Aggregated here rather than in SQL so every column keeps its declared type
+        let mut by_statement: HashMap<String, (SlowQuerySummary, u64)> = HashMap::new();
+        for query in result.rows.iter().filter_map(row_to_slow_query) {
+            let (summary, total) = by_statement.entry(query.statement.clone()).or_insert_with(|| (
+                SlowQuerySummary {
+                    statement: query.statement.clone(),
+                    occurrences: 0,
+                    mean_duration_ms: 0.0,
+                    max_duration_ms: 0,
+                    last_seen: query.captured_at,
+                    plan: None,
+                },
+                0,
+            ));
+            summary.occurrences += 1;
+            *total += query.duration_ms;
+            summary.mean_duration_ms = *total as f64 / summary.occurrences as f64;
+            summary.max_duration_ms = summary.max_duration_ms.max(query.duration_ms);
+            summary.last_seen = query.captured_at;
+            summary.plan = query.plan.or(summary.plan.take());
+        }
+
+        let mut summaries: Vec<(SlowQuerySummary, u64)> = by_statement.into_values().collect();
+        summaries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.statement.cmp(&b.0.statement)));
+        Ok(summaries.into_iter().take(limit).map(|(summary, _)| summary).collect())
+    }
+
+    /// Delete captures older than `before`, returning how many were removed
+    pub async fn purge(&self, before: DateTime<Utc>) -> StepflowResult<u64> {
+        let result = self.database.execute(
+            "DELETE FROM slow_queries WHERE captured_at < ?",
+            &[param::timestamp(&before)],
+        ).await?;
+        Ok(result.rows_affected)
+    }
+}
+
+fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value).ok().map(|time| time.with_timezone(&Utc))
+}
+
+fn row_to_slow_query(row: &HashMap<String, Value>) -> Option<SlowQuery> {
+    Some(SlowQuery {
+        id: row.get("id")?.as_str()?.to_string(),
+        statement: row.get("statement")?.as_str()?.to_string(),
+        duration_ms: row.get("duration_ms")?.as_u64()?,
+        success: row.get("success").and_then(Value::as_i64).unwrap_or(0) != 0,
+        plan: row.get("plan").and_then(Value::as_str).map(str::to_string),
+        captured_at: parse_time(row.get("captured_at")?.as_str()?)?,
+    })
+}
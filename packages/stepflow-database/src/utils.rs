@@ -1,6 +1,8 @@
 use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
 use argon2::password_hash::SaltString;
-use rand_core::OsRng;
+use base64::Engine;
+use rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
 
 /// Hash a password using Argon2
 pub fn hash_password(password: &str) -> Result<String, argon2::password_hash::Error> {
@@ -19,4 +21,63 @@ pub fn verify_password(password: &str, hash: &str) -> Result<bool, argon2::passw
         Err(argon2::password_hash::Error::Password) => Ok(false),
         Err(e) => Err(e),
     }
-} 
\ No newline at end of file
+}
+
+/// Generate a random URL-safe token for one-time links (invitations, email verification)
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Hash a one-time token for storage.
+///
+/// Tokens are high-entropy and looked up by value, so a fast unsalted digest
+/// is sufficient; only the hash is ever persisted.
+pub fn hash_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Typed helpers for building query parameters
+///
+/// These produce values that bind with the intended SQLite storage class, so
+/// repositories don't repeat the `Value::String(x.to_string())` dance.
+pub mod param {
+    use chrono::{DateTime, Utc};
+    use serde::Serialize;
+    use serde_json::Value;
+    use stepflow_core::StepflowResult;
+
+    /// Bind as TEXT
+    pub fn text(value: impl Into<String>) -> Value {
+        Value::String(value.into())
+    }
+
+    /// Bind as TEXT, or NULL for `None`
+    pub fn opt_text(value: Option<impl Into<String>>) -> Value {
+        value.map(|value| Value::String(value.into())).unwrap_or(Value::Null)
+    }
+
+    /// Bind as INTEGER
+    pub fn int(value: impl Into<i64>) -> Value {
+        Value::from(value.into())
+    }
+
+    /// Bind a flag as INTEGER 0/1
+    pub fn flag(value: bool) -> Value {
+        Value::from(value as i64)
+    }
+
+    /// Bind a timestamp as RFC 3339 TEXT
+    pub fn timestamp(value: &DateTime<Utc>) -> Value {
+        Value::String(value.to_rfc3339())
+    }
+
+    /// Bind a serializable value as JSON TEXT
+    pub fn json<T: Serialize + ?Sized>(value: &T) -> StepflowResult<Value> {
+        Ok(Value::String(serde_json::to_string(value)?))
+    }
+}
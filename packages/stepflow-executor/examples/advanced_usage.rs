@@ -53,60 +53,45 @@ async fn setup_infrastructure() -> Result<(Arc<SqliteDatabase>, Arc<RegistryImpl
 
 /// Register test tools
 async fn register_test_tools(registry: &Arc<RegistryImpl>) -> Result<(), Box<dyn std::error::Error>> {
-    use chrono::Utc;
-    
     let tools = vec![
-        ToolInfo {
-            id: ToolId::from_string("data-processor".to_string()),
-            name: "Data Processor".to_string(),
-            description: "Process large datasets".to_string(),
-            version: ToolVersion::new(1, 0, 0),
-            tool_type: ToolType::Python,
-            status: ToolStatus::Active,
-            author: "data-team@stepflow.dev".to_string(),
-            repository: Some("https://github.com/stepflow/data-processor".to_string()),
-            documentation: Some("https://docs.stepflow.dev/tools/data-processor".to_string()),
-            tags: vec!["data".to_string(), "processing".to_string()],
-            capabilities: vec!["transform".to_string(), "aggregate".to_string()],
-            configuration_schema: None,
-            examples: vec![],
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-        },
-        ToolInfo {
-            id: ToolId::from_string("file-converter".to_string()),
-            name: "File Converter".to_string(),
-            description: "Convert between different file formats".to_string(),
-            version: ToolVersion::new(2, 0, 0),
-            tool_type: ToolType::System,
-            status: ToolStatus::Active,
-            author: "file-team@stepflow.dev".to_string(),
-            repository: Some("https://github.com/stepflow/file-converter".to_string()),
-            documentation: Some("https://docs.stepflow.dev/tools/file-converter".to_string()),
-            tags: vec!["files".to_string(), "conversion".to_string()],
-            capabilities: vec!["convert".to_string(), "validate".to_string()],
-            configuration_schema: None,
-            examples: vec![],
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-        },
-        ToolInfo {
-            id: ToolId::from_string("image-processor".to_string()),
-            name: "Image Processor".to_string(),
-            description: "Process and manipulate images".to_string(),
-            version: ToolVersion::new(1, 2, 0),
-            tool_type: ToolType::Python,
-            status: ToolStatus::Active,
-            author: "image-team@stepflow.dev".to_string(),
-            repository: Some("https://github.com/stepflow/image-processor".to_string()),
-            documentation: Some("https://docs.stepflow.dev/tools/image-processor".to_string()),
-            tags: vec!["image".to_string(), "processing".to_string()],
-            capabilities: vec!["resize".to_string(), "filter".to_string()],
-            configuration_schema: None,
-            examples: vec![],
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-        },
+        ToolInfo::builder()
+            .id(ToolId::from_string("data-processor".to_string()))
+            .name("Data Processor")
+            .description("Process large datasets")
+            .tool_type(ToolType::Python)
+            .author("data-team@stepflow.dev")
+            .repository("https://github.com/stepflow/data-processor".to_string())
+            .documentation("https://docs.stepflow.dev/tools/data-processor".to_string())
+            .tags(["data", "processing"])
+            .capabilities(["transform", "aggregate"])
+            .build()
+            .unwrap(),
+        ToolInfo::builder()
+            .id(ToolId::from_string("file-converter".to_string()))
+            .name("File Converter")
+            .description("Convert between different file formats")
+            .version(ToolVersion::new(2, 0, 0))
+            .tool_type(ToolType::System)
+            .author("file-team@stepflow.dev")
+            .repository("https://github.com/stepflow/file-converter".to_string())
+            .documentation("https://docs.stepflow.dev/tools/file-converter".to_string())
+            .tags(["files", "conversion"])
+            .capabilities(["convert", "validate"])
+            .build()
+            .unwrap(),
+        ToolInfo::builder()
+            .id(ToolId::from_string("image-processor".to_string()))
+            .name("Image Processor")
+            .description("Process and manipulate images")
+            .version(ToolVersion::new(1, 2, 0))
+            .tool_type(ToolType::Python)
+            .author("image-team@stepflow.dev")
+            .repository("https://github.com/stepflow/image-processor".to_string())
+            .documentation("https://docs.stepflow.dev/tools/image-processor".to_string())
+            .tags(["image", "processing"])
+            .capabilities(["resize", "filter"])
+            .build()
+            .unwrap(),
     ];
     
     for tool in tools {
@@ -140,30 +125,36 @@ async fn batch_execution_example(executor: &Arc<dyn Executor>) -> Result<(), Box
     
     // Start all executions asynchronously
     for (i, (tool_id, operation, params)) in tools.iter().enumerate() {
-        let request = ExecutionRequest {
-            tool_id: ToolId::from_string(tool_id.to_string()),
-            version: None,
-            parameters: HashMap::from([
+        let request = ExecutionRequest::builder()
+            .tool_id(ToolId::from_string(tool_id.to_string()))
+            .parameters(HashMap::from([
                 ("operation".to_string(), serde_json::json!(operation)),
                 ("params".to_string(), params.clone()),
-            ]),
-            context: ExecutionContext {
+            ]))
+            .context(ExecutionContext {
                 user_id: "batch-user".to_string(),
                 tenant_id: "default".to_string(),
                 session_id: format!("batch-session-{}", i),
                 request_id: format!("batch-req-{}", i),
                 parent_execution_id: None,
                 environment: HashMap::new(),
-            },
-            options: ExecutionOptions {
+                labels: HashMap::new(),
+                note: None,
+            })
+            .options(ExecutionOptions {
                 timeout: Some(Duration::from_secs(30)),
                 retry_count: 2,
                 retry_delay: Duration::from_millis(1000),
                 priority: Priority::Normal,
                 resource_limits: ResourceLimits::default(),
                 logging_level: LogLevel::Info,
-            },
-        };
+                environment_label: None,
+                requirements: Default::default(),
+                concurrency: None,
+                artifacts: Vec::new(),
+            })
+            .build()
+            .unwrap();
         
         match executor.execute_tool_async(request).await {
             Ok(execution_id) => {
@@ -220,35 +211,41 @@ async fn resource_management_example(executor: &Arc<dyn Executor>) -> Result<(),
     println!("Testing resource management...");
     
     // High-resource execution
-    let high_resource_request = ExecutionRequest {
-        tool_id: ToolId::from_string("data-processor".to_string()),
-        version: None,
-        parameters: HashMap::from([
+    let high_resource_request = ExecutionRequest::builder()
+        .tool_id(ToolId::from_string("data-processor".to_string()))
+        .parameters(HashMap::from([
             ("operation".to_string(), serde_json::json!("heavy_processing")),
             ("dataset_size".to_string(), serde_json::json!("large")),
-        ]),
-        context: ExecutionContext {
+        ]))
+        .context(ExecutionContext {
             user_id: "resource-user".to_string(),
             tenant_id: "default".to_string(),
             session_id: "resource-session".to_string(),
             request_id: "resource-req-1".to_string(),
             parent_execution_id: None,
             environment: HashMap::new(),
-        },
-        options: ExecutionOptions {
+            labels: HashMap::new(),
+            note: None,
+        })
+        .options(ExecutionOptions {
             timeout: Some(Duration::from_secs(60)),
             retry_count: 1,
             retry_delay: Duration::from_millis(2000),
             priority: Priority::High,
             resource_limits: ResourceLimits {
-                memory_limit: Some(2 * 1024 * 1024 * 1024), // 2GB
-                cpu_limit: Some(80.0), // 80% CPU
-                execution_time_limit: Some(Duration::from_secs(45)),
-                network_limit: Some(100 * 1024 * 1024), // 100MB
-            },
-            logging_level: LogLevel::Debug,
+            memory_limit: Some(2 * 1024 * 1024 * 1024), // 2GB
+            cpu_limit: Some(80.0), // 80% CPU
+            execution_time_limit: Some(Duration::from_secs(45)),
+            network_limit: Some(100 * 1024 * 1024), // 100MB
         },
-    };
+            logging_level: LogLevel::Debug,
+            environment_label: None,
+            requirements: Default::default(),
+            concurrency: None,
+            artifacts: Vec::new(),
+        })
+        .build()
+        .unwrap();
     
     match executor.execute_tool(high_resource_request).await {
         Ok(result) => {
@@ -262,35 +259,41 @@ async fn resource_management_example(executor: &Arc<dyn Executor>) -> Result<(),
     }
     
     // Low-resource execution
-    let low_resource_request = ExecutionRequest {
-        tool_id: ToolId::from_string("file-converter".to_string()),
-        version: None,
-        parameters: HashMap::from([
+    let low_resource_request = ExecutionRequest::builder()
+        .tool_id(ToolId::from_string("file-converter".to_string()))
+        .parameters(HashMap::from([
             ("operation".to_string(), serde_json::json!("light_conversion")),
             ("file_size".to_string(), serde_json::json!("small")),
-        ]),
-        context: ExecutionContext {
+        ]))
+        .context(ExecutionContext {
             user_id: "resource-user".to_string(),
             tenant_id: "default".to_string(),
             session_id: "resource-session".to_string(),
             request_id: "resource-req-2".to_string(),
             parent_execution_id: None,
             environment: HashMap::new(),
-        },
-        options: ExecutionOptions {
+            labels: HashMap::new(),
+            note: None,
+        })
+        .options(ExecutionOptions {
             timeout: Some(Duration::from_secs(10)),
             retry_count: 3,
             retry_delay: Duration::from_millis(500),
             priority: Priority::Low,
             resource_limits: ResourceLimits {
-                memory_limit: Some(256 * 1024 * 1024), // 256MB
-                cpu_limit: Some(25.0), // 25% CPU
-                execution_time_limit: Some(Duration::from_secs(5)),
-                network_limit: Some(10 * 1024 * 1024), // 10MB
-            },
-            logging_level: LogLevel::Info,
+            memory_limit: Some(256 * 1024 * 1024), // 256MB
+            cpu_limit: Some(25.0), // 25% CPU
+            execution_time_limit: Some(Duration::from_secs(5)),
+            network_limit: Some(10 * 1024 * 1024), // 10MB
         },
-    };
+            logging_level: LogLevel::Info,
+            environment_label: None,
+            requirements: Default::default(),
+            concurrency: None,
+            artifacts: Vec::new(),
+        })
+        .build()
+        .unwrap();
     
     match executor.execute_tool(low_resource_request).await {
         Ok(result) => {
@@ -317,31 +320,37 @@ async fn performance_monitoring_example(executor: &Arc<dyn Executor>) -> Result<
     for i in 0..5 {
         let execution_start = std::time::Instant::now();
         
-        let request = ExecutionRequest {
-            tool_id: ToolId::from_string("image-processor".to_string()),
-            version: None,
-            parameters: HashMap::from([
+        let request = ExecutionRequest::builder()
+            .tool_id(ToolId::from_string("image-processor".to_string()))
+            .parameters(HashMap::from([
                 ("operation".to_string(), serde_json::json!("filter")),
                 ("filter_type".to_string(), serde_json::json!("blur")),
                 ("intensity".to_string(), serde_json::json!(i + 1)),
-            ]),
-            context: ExecutionContext {
+            ]))
+            .context(ExecutionContext {
                 user_id: "perf-user".to_string(),
                 tenant_id: "default".to_string(),
                 session_id: format!("perf-session-{}", i),
                 request_id: format!("perf-req-{}", i),
                 parent_execution_id: None,
                 environment: HashMap::new(),
-            },
-            options: ExecutionOptions {
+                labels: HashMap::new(),
+                note: None,
+            })
+            .options(ExecutionOptions {
                 timeout: Some(Duration::from_secs(20)),
                 retry_count: 1,
                 retry_delay: Duration::from_millis(500),
                 priority: Priority::Normal,
                 resource_limits: ResourceLimits::default(),
                 logging_level: LogLevel::Info,
-            },
-        };
+                environment_label: None,
+                requirements: Default::default(),
+                concurrency: None,
+                artifacts: Vec::new(),
+            })
+            .build()
+            .unwrap();
         
         match executor.execute_tool(request).await {
             Ok(result) => {
@@ -396,30 +405,36 @@ async fn environment_variables_example(executor: &Arc<dyn Executor>) -> Result<(
     custom_env.insert("MAX_WORKERS".to_string(), "4".to_string());
     custom_env.insert("API_KEY".to_string(), "test-key-12345".to_string());
     
-    let request = ExecutionRequest {
-        tool_id: ToolId::from_string("data-processor".to_string()),
-        version: None,
-        parameters: HashMap::from([
+    let request = ExecutionRequest::builder()
+        .tool_id(ToolId::from_string("data-processor".to_string()))
+        .parameters(HashMap::from([
             ("operation".to_string(), serde_json::json!("analyze")),
             ("use_cache".to_string(), serde_json::json!(false)),
-        ]),
-        context: ExecutionContext {
+        ]))
+        .context(ExecutionContext {
             user_id: "env-user".to_string(),
             tenant_id: "default".to_string(),
             session_id: "env-session".to_string(),
             request_id: "env-req-1".to_string(),
             parent_execution_id: None,
             environment: custom_env,
-        },
-        options: ExecutionOptions {
+            labels: HashMap::new(),
+            note: None,
+        })
+        .options(ExecutionOptions {
             timeout: Some(Duration::from_secs(30)),
             retry_count: 2,
             retry_delay: Duration::from_millis(1000),
             priority: Priority::Normal,
             resource_limits: ResourceLimits::default(),
             logging_level: LogLevel::Debug,
-        },
-    };
+            environment_label: None,
+            requirements: Default::default(),
+            concurrency: None,
+            artifacts: Vec::new(),
+        })
+        .build()
+        .unwrap();
     
     match executor.execute_tool(request).await {
         Ok(result) => {
@@ -61,47 +61,36 @@ async fn setup_infrastructure() -> Result<(Arc<SqliteDatabase>, Arc<RegistryImpl
 
 /// Register sample tools for testing
 async fn register_sample_tools(registry: &Arc<RegistryImpl>) -> Result<(), Box<dyn std::error::Error>> {
-    use chrono::Utc;
-    
     // Register a basic Python tool
-    let python_tool = ToolInfo {
-        id: ToolId::from_string("python-calculator".to_string()),
-        name: "Python Calculator".to_string(),
-        description: "A simple calculator tool implemented in Python".to_string(),
-        version: ToolVersion::new(1, 0, 0),
-        tool_type: ToolType::Python,
-        status: ToolStatus::Active,
-        author: "example@stepflow.dev".to_string(),
-        repository: Some("https://github.com/stepflow/python-calculator".to_string()),
-        documentation: Some("https://docs.stepflow.dev/tools/python-calculator".to_string()),
-        tags: vec!["calculator".to_string(), "math".to_string(), "python".to_string()],
-        capabilities: vec!["add".to_string(), "subtract".to_string(), "multiply".to_string(), "divide".to_string()],
-        configuration_schema: None,
-        examples: vec![],
-        created_at: Utc::now(),
-        updated_at: Utc::now(),
-    };
+    let python_tool = ToolInfo::builder()
+        .id(ToolId::from_string("python-calculator".to_string()))
+        .name("Python Calculator")
+        .description("A simple calculator tool implemented in Python")
+        .tool_type(ToolType::Python)
+        .author("example@stepflow.dev")
+        .repository("https://github.com/stepflow/python-calculator".to_string())
+        .documentation("https://docs.stepflow.dev/tools/python-calculator".to_string())
+        .tags(["calculator", "math", "python"])
+        .capabilities(["add", "subtract", "multiply", "divide"])
+        .build()
+        .unwrap();
     
     registry.register_tool(python_tool).await?;
     
     // Register a Openapi tool (since Rust variant doesn't exist)
-    let js_tool = ToolInfo {
-        id: ToolId::from_string("js-text-processor".to_string()),
-        name: "JavaScript Text Processor".to_string(),
-        description: "A text processing tool implemented in OpenAPI".to_string(),
-        version: ToolVersion::new(2, 1, 0),
-        tool_type: ToolType::OpenAPI, // Fixed: Use an existing ToolType variant, e.g., NodeJS
-        status: ToolStatus::Active,
-        author: "example@stepflow.dev".to_string(),
-        repository: Some("https://github.com/stepflow/js-text-processor".to_string()),
-        documentation: Some("https://docs.stepflow.dev/tools/js-text-processor".to_string()),
-        tags: vec!["text".to_string(), "processing".to_string(), "openapi".to_string()],
-        capabilities: vec!["uppercase".to_string(), "lowercase".to_string(), "reverse".to_string()],
-        configuration_schema: None,
-        examples: vec![],
-        created_at: Utc::now(),
-        updated_at: Utc::now(),
-    };
+    let js_tool = ToolInfo::builder()
+        .id(ToolId::from_string("js-text-processor".to_string()))
+        .name("JavaScript Text Processor")
+        .description("A text processing tool implemented in OpenAPI")
+        .version(ToolVersion::new(2, 1, 0))
+        .tool_type(ToolType::OpenAPI) // Use an existing ToolType variant
+        .author("example@stepflow.dev")
+        .repository("https://github.com/stepflow/js-text-processor".to_string())
+        .documentation("https://docs.stepflow.dev/tools/js-text-processor".to_string())
+        .tags(["text", "processing", "openapi"])
+        .capabilities(["uppercase", "lowercase", "reverse"])
+        .build()
+        .unwrap();
     
     registry.register_tool(js_tool).await?;
     
@@ -121,30 +110,36 @@ async fn create_executor(
 
 /// Example 1: Basic synchronous execution
 async fn basic_sync_execution(executor: &Arc<dyn Executor>) -> Result<(), Box<dyn std::error::Error>> {
-    let request = ExecutionRequest {
-        tool_id: ToolId::from_string("python-calculator".to_string()),
-        version: None,
-        parameters: HashMap::from([
+    let request = ExecutionRequest::builder()
+        .tool_id(ToolId::from_string("python-calculator".to_string()))
+        .parameters(HashMap::from([
             ("operation".to_string(), serde_json::json!("add")),
             ("operands".to_string(), serde_json::json!([10, 20])),
-        ]),
-        context: ExecutionContext {
+        ]))
+        .context(ExecutionContext {
             user_id: "user-1".to_string(),
             tenant_id: "default".to_string(),
             session_id: "session-1".to_string(),
             request_id: "req-1".to_string(),
             parent_execution_id: None,
             environment: HashMap::new(),
-        },
-        options: ExecutionOptions {
+            labels: HashMap::new(),
+            note: None,
+        })
+        .options(ExecutionOptions {
             timeout: Some(Duration::from_secs(10)),
             retry_count: 2,
             retry_delay: Duration::from_millis(500),
             priority: Priority::Normal,
             resource_limits: ResourceLimits::default(),
             logging_level: LogLevel::Info,
-        },
-    };
+            environment_label: None,
+            requirements: Default::default(),
+            concurrency: None,
+            artifacts: Vec::new(),
+        })
+        .build()
+        .unwrap();
     
     match executor.execute_tool(request).await {
         Ok(result) => {
@@ -167,30 +162,36 @@ async fn basic_sync_execution(executor: &Arc<dyn Executor>) -> Result<(), Box<dy
 
 /// Example 2: Asynchronous execution with status monitoring
 async fn async_execution_with_monitoring(executor: &Arc<dyn Executor>) -> Result<(), Box<dyn std::error::Error>> {
-    let request = ExecutionRequest {
-        tool_id: ToolId::from_string("js-text-processor".to_string()),
-        version: None,
-        parameters: HashMap::from([
+    let request = ExecutionRequest::builder()
+        .tool_id(ToolId::from_string("js-text-processor".to_string()))
+        .parameters(HashMap::from([
             ("text".to_string(), serde_json::json!("Hello, World!")),
             ("operation".to_string(), serde_json::json!("uppercase")),
-        ]),
-        context: ExecutionContext {
+        ]))
+        .context(ExecutionContext {
             user_id: "user-1".to_string(),
             tenant_id: "default".to_string(),
             session_id: "session-2".to_string(),
             request_id: "req-2".to_string(),
             parent_execution_id: None,
             environment: HashMap::new(),
-        },
-        options: ExecutionOptions {
+            labels: HashMap::new(),
+            note: None,
+        })
+        .options(ExecutionOptions {
             timeout: Some(Duration::from_secs(15)),
             retry_count: 3,
             retry_delay: Duration::from_millis(1000),
             priority: Priority::High,
             resource_limits: ResourceLimits::default(),
             logging_level: LogLevel::Info,
-        },
-    };
+            environment_label: None,
+            requirements: Default::default(),
+            concurrency: None,
+            artifacts: Vec::new(),
+        })
+        .build()
+        .unwrap();
     
     // Start asynchronous execution
     match executor.execute_tool_async(request).await {
@@ -239,27 +240,32 @@ async fn async_execution_with_monitoring(executor: &Arc<dyn Executor>) -> Result
 /// Example 3: Error handling
 async fn error_handling_example(executor: &Arc<dyn Executor>) -> Result<(), Box<dyn std::error::Error>> {
     // Try to execute a non-existent tool
-    let request = ExecutionRequest {
-        tool_id: ToolId::from_string("non-existent-tool".to_string()),
-        version: None,
-        parameters: HashMap::new(),
-        context: ExecutionContext {
+    let request = ExecutionRequest::builder()
+        .tool_id(ToolId::from_string("non-existent-tool".to_string()))
+        .context(ExecutionContext {
             user_id: "user-1".to_string(),
             tenant_id: "default".to_string(),
             session_id: "session-3".to_string(),
             request_id: "req-error-1".to_string(),
             parent_execution_id: None,
             environment: HashMap::new(),
-        },
-        options: ExecutionOptions {
+            labels: HashMap::new(),
+            note: None,
+        })
+        .options(ExecutionOptions {
             timeout: Some(Duration::from_secs(5)),
             retry_count: 1,
             retry_delay: Duration::from_millis(100),
             priority: Priority::Normal,
             resource_limits: ResourceLimits::default(),
             logging_level: LogLevel::Info,
-        },
-    };
+            environment_label: None,
+            requirements: Default::default(),
+            concurrency: None,
+            artifacts: Vec::new(),
+        })
+        .build()
+        .unwrap();
     
     match executor.execute_tool(request).await {
         Ok(result) => {
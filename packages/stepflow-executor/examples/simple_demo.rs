@@ -25,23 +25,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let registry = Arc::new(stepflow_registry::create_registry(database.clone()).await?);
     
     // Register a simple tool
-    let tool = ToolInfo {
-        id: ToolId::new(),
-        name: "simple-tool".to_string(),
-        description: "A simple demonstration tool".to_string(),
-        version: ToolVersion::new(1, 0, 0),
-        tool_type: ToolType::Python,
-        status: ToolStatus::Active,
-        author: "demo@stepflow.dev".to_string(),
-        repository: None,
-        documentation: None,
-        tags: vec!["demo".to_string()],
-        capabilities: vec!["process".to_string()],
-        configuration_schema: None,
-        examples: vec![],
-        created_at: chrono::Utc::now(),
-        updated_at: chrono::Utc::now(),
-    };
+    let tool = ToolInfo::builder()
+        .name("simple-tool")
+        .description("A simple demonstration tool")
+        .tool_type(ToolType::Python)
+        .author("demo@stepflow.dev")
+        .tags(["demo"])
+        .capabilities(["process"])
+        .build()
+        .unwrap();
     
     let tool_id = registry.register_tool(tool).await?;
     println!("✅ Registered tool: {}", tool_id);
@@ -51,30 +43,36 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("✅ Created executor");
     
     // Create execution request
-    let request = ExecutionRequest {
-        tool_id: tool_id.clone(),
-        version: None,
-        parameters: HashMap::from([
+    let request = ExecutionRequest::builder()
+        .tool_id(tool_id.clone())
+        .parameters(HashMap::from([
             ("input".to_string(), serde_json::json!("Hello, World!")),
             ("operation".to_string(), serde_json::json!("process")),
-        ]),
-        context: ExecutionContext {
+        ]))
+        .context(ExecutionContext {
             user_id: "demo-user".to_string(),
             tenant_id: "demo-tenant".to_string(),
             session_id: "demo-session".to_string(),
             request_id: "demo-request".to_string(),
             parent_execution_id: None,
             environment: HashMap::new(),
-        },
-        options: ExecutionOptions {
+            labels: HashMap::new(),
+            note: None,
+        })
+        .options(ExecutionOptions {
             timeout: Some(Duration::from_secs(30)),
             retry_count: 2,
             retry_delay: Duration::from_millis(1000),
             priority: Priority::Normal,
             resource_limits: ResourceLimits::default(),
             logging_level: LogLevel::Info,
-        },
-    };
+            environment_label: None,
+            requirements: Default::default(),
+            concurrency: None,
+            artifacts: Vec::new(),
+        })
+        .build()
+        .unwrap();
     
     // Execute tool
     println!("🔄 Executing tool...");
@@ -100,30 +98,36 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     
     // Test async execution
     println!("\n🔄 Testing async execution...");
-    let async_request = ExecutionRequest {
-        tool_id: tool_id.clone(),
-        version: None,
-        parameters: HashMap::from([
+    let async_request = ExecutionRequest::builder()
+        .tool_id(tool_id.clone())
+        .parameters(HashMap::from([
             ("input".to_string(), serde_json::json!("Async test")),
             ("operation".to_string(), serde_json::json!("async_process")),
-        ]),
-        context: ExecutionContext {
+        ]))
+        .context(ExecutionContext {
             user_id: "demo-user".to_string(),
             tenant_id: "demo-tenant".to_string(),
             session_id: "demo-session-async".to_string(),
             request_id: "demo-request-async".to_string(),
             parent_execution_id: None,
             environment: HashMap::new(),
-        },
-        options: ExecutionOptions {
+            labels: HashMap::new(),
+            note: None,
+        })
+        .options(ExecutionOptions {
             timeout: Some(Duration::from_secs(30)),
             retry_count: 1,
             retry_delay: Duration::from_millis(500),
             priority: Priority::Normal,
             resource_limits: ResourceLimits::default(),
             logging_level: LogLevel::Info,
-        },
-    };
+            environment_label: None,
+            requirements: Default::default(),
+            concurrency: None,
+            artifacts: Vec::new(),
+        })
+        .build()
+        .unwrap();
     
     match executor.execute_tool_async(async_request).await {
         Ok(execution_id) => {
@@ -147,27 +151,32 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     
     // Test error handling
     println!("\n🔄 Testing error handling...");
-    let error_request = ExecutionRequest {
-        tool_id: ToolId::from_string("non-existent-tool".to_string()),
-        version: None,
-        parameters: HashMap::new(),
-        context: ExecutionContext {
+    let error_request = ExecutionRequest::builder()
+        .tool_id(ToolId::from_string("non-existent-tool".to_string()))
+        .context(ExecutionContext {
             user_id: "demo-user".to_string(),
             tenant_id: "demo-tenant".to_string(),
             session_id: "demo-session-error".to_string(),
             request_id: "demo-request-error".to_string(),
             parent_execution_id: None,
             environment: HashMap::new(),
-        },
-        options: ExecutionOptions {
+            labels: HashMap::new(),
+            note: None,
+        })
+        .options(ExecutionOptions {
             timeout: Some(Duration::from_secs(5)),
             retry_count: 1,
             retry_delay: Duration::from_millis(100),
             priority: Priority::Normal,
             resource_limits: ResourceLimits::default(),
             logging_level: LogLevel::Info,
-        },
-    };
+            environment_label: None,
+            requirements: Default::default(),
+            concurrency: None,
+            artifacts: Vec::new(),
+        })
+        .build()
+        .unwrap();
     
     match executor.execute_tool(error_request).await {
         Ok(result) => {
@@ -0,0 +1,100 @@
+//! Execution labels and notes
+//!
+//! Labels are `key=value` pairs attached to an execution at submission or
+//! afterwards; list queries can require any number of them. Notes are
+//! free-form comments appended by users, kept in the order they were added.
+
+use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use stepflow_core::*;
+use crate::errors::*;
+
+/// Labels a single execution may carry
+pub const MAX_LABELS: usize = 32;
+
+/// Longest label key, in bytes
+pub const MAX_LABEL_KEY_BYTES: usize = 63;
+
+/// Longest label value, in bytes
+pub const MAX_LABEL_VALUE_BYTES: usize = 255;
+
+/// Longest note body, in bytes
+pub const MAX_NOTE_BYTES: usize = 4096;
+
+/// Note attached to an execution
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionNote {
+    pub id: String,
+    pub execution_id: ExecutionId,
+    pub author: String,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ExecutionNote {
+    /// Create a note written now
+    pub fn new(execution_id: ExecutionId, author: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            execution_id,
+            author: author.into(),
+            body: body.into(),
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// Labels and notes of an execution
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionAnnotations {
+    pub labels: HashMap<String, String>,
+    /// Notes, oldest first
+    pub notes: Vec<ExecutionNote>,
+}
+
+/// Parse a label selector written as `key:value` or `key=value`
+pub fn parse_label(selector: &str) -> ExecutorResult<(String, String)> {
+    let (key, value) = selector
+        .split_once([':', '='])
+        .ok_or_else(|| ExecutorError::InvalidParameters(format!("Label {} must be written as key:value", selector)))?;
+    let (key, value) = (key.trim(), value.trim());
+    validate_label(key, value)?;
+    Ok((key.to_string(), value.to_string()))
+}
+
+/// Check a label key and value
+///
+/// Keys are 1-63 bytes of ASCII letters, digits, `-`, `_`, `.` and `/`;
+/// values are at most 255 bytes and may be empty.
+pub fn validate_label(key: &str, value: &str) -> ExecutorResult<()> {
+    let valid_key = !key.is_empty()
+        && key.len() <= MAX_LABEL_KEY_BYTES
+        && key.bytes().all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b'/'));
+    if !valid_key {
+        return Err(ExecutorError::InvalidParameters(format!("Invalid label key: {}", key)));
+    }
+    if value.len() > MAX_LABEL_VALUE_BYTES || value.chars().any(char::is_control) {
+        return Err(ExecutorError::InvalidParameters(format!("Invalid value for label {}", key)));
+    }
+    Ok(())
+}
+
+/// Check a set of labels, including how many there are
+pub fn validate_labels(labels: &HashMap<String, String>) -> ExecutorResult<()> {
+    if labels.len() > MAX_LABELS {
+        return Err(ExecutorError::InvalidParameters(format!("At most {} labels are allowed", MAX_LABELS)));
+    }
+    labels.iter().try_for_each(|(key, value)| validate_label(key, value))
+}
+
+/// Check a note body
+pub fn validate_note(body: &str) -> ExecutorResult<()> {
+    if body.trim().is_empty() {
+        return Err(ExecutorError::InvalidParameters("Note must not be empty".to_string()));
+    }
+    if body.len() > MAX_NOTE_BYTES {
+        return Err(ExecutorError::InvalidParameters(format!("Note exceeds {} bytes", MAX_NOTE_BYTES)));
+    }
+    Ok(())
+}
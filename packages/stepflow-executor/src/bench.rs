@@ -0,0 +1,214 @@
+//! Benchmarking support
+//!
+//! Compiled with the `bench` feature. Provides [`PerformanceBenchmark`], a closed-loop
+//! summary of a fixed number of executions, and [`run_load`], an open-loop load
+//! generator that issues requests at a target rate and reports latency percentiles.
+//! The `stepflow-bench` binary drives the executor with it and prints the report as JSON.
+
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::Semaphore;
+use tokio::time::MissedTickBehavior;
+
+/// Results of a closed-loop benchmark
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PerformanceBenchmark {
+    pub total_executions: usize,
+    pub successful_executions: usize,
+    pub failed_executions: usize,
+    pub total_duration: Duration,
+    pub average_duration: Duration,
+    pub min_duration: Duration,
+    pub max_duration: Duration,
+    pub throughput: f64,
+    pub concurrent_executions: usize,
+}
+
+impl PerformanceBenchmark {
+    /// Summarize `durations` of the successful executions out of `total_executions`
+    pub fn from_durations(
+        total_executions: usize,
+        concurrent_executions: usize,
+        total_duration: Duration,
+        durations: &[Duration],
+    ) -> Self {
+        let successful_executions = durations.len();
+        let average_duration = if successful_executions > 0 {
+            durations.iter().sum::<Duration>() / successful_executions as u32
+        } else {
+            Duration::ZERO
+        };
+
+        Self {
+            total_executions,
+            successful_executions,
+            failed_executions: total_executions - successful_executions,
+            total_duration,
+            average_duration,
+            min_duration: durations.iter().min().copied().unwrap_or_default(),
+            max_duration: durations.iter().max().copied().unwrap_or_default(),
+            throughput: successful_executions as f64 / total_duration.as_secs_f64(),
+            concurrent_executions,
+        }
+    }
+
+    pub fn print_summary(&self) {
+        println!("\n=== Performance Benchmark Results ===");
+        println!("Total Executions: {}", self.total_executions);
+        println!("Successful: {}", self.successful_executions);
+        println!("Failed: {}", self.failed_executions);
+        println!("Success Rate: {:.2}%",
+            (self.successful_executions as f64 / self.total_executions as f64) * 100.0);
+        println!("Total Duration: {:?}", self.total_duration);
+        println!("Average Duration: {:?}", self.average_duration);
+        println!("Min Duration: {:?}", self.min_duration);
+        println!("Max Duration: {:?}", self.max_duration);
+        println!("Throughput: {:.2} executions/second", self.throughput);
+        println!("Concurrent Executions: {}", self.concurrent_executions);
+        println!("=====================================\n");
+    }
+}
+
+/// Open-loop load generator configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoadConfig {
+    /// Requests started per second
+    pub target_rps: f64,
+    /// How long to keep issuing requests
+    pub duration: Duration,
+    /// Requests allowed in flight; further requests wait, lowering the achieved rate
+    pub max_in_flight: usize,
+}
+
+impl Default for LoadConfig {
+    fn default() -> Self {
+        Self {
+            target_rps: 50.0,
+            duration: Duration::from_secs(10),
+            max_in_flight: 256,
+        }
+    }
+}
+
+/// Latency percentiles in milliseconds
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LatencyPercentiles {
+    pub mean_ms: f64,
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+impl LatencyPercentiles {
+    /// Nearest-rank percentiles of `latencies`
+    pub fn from_latencies(latencies: &[Duration]) -> Self {
+        if latencies.is_empty() {
+            return Self::default();
+        }
+        let mut sorted: Vec<f64> = latencies.iter().map(|latency| latency.as_secs_f64() * 1000.0).collect();
+        sorted.sort_by(f64::total_cmp);
+        let percentile = |p: f64| sorted[((p / 100.0 * sorted.len() as f64).ceil() as usize).clamp(1, sorted.len()) - 1];
+
+        Self {
+            mean_ms: sorted.iter().sum::<f64>() / sorted.len() as f64,
+            p50_ms: percentile(50.0),
+            p90_ms: percentile(90.0),
+            p95_ms: percentile(95.0),
+            p99_ms: percentile(99.0),
+            max_ms: sorted[sorted.len() - 1],
+        }
+    }
+}
+
+/// Result of a load run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoadReport {
+    pub target_rps: f64,
+    pub achieved_rps: f64,
+    pub total_requests: u64,
+    pub successful_requests: u64,
+    pub failed_requests: u64,
+    pub elapsed_ms: u64,
+    /// Latencies of successful requests
+    pub latency: LatencyPercentiles,
+    /// First few distinct error messages
+    pub errors: Vec<String>,
+}
+
+impl LoadReport {
+    /// Pretty-printed JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+const MAX_REPORTED_ERRORS: usize = 10;
+
+/// Issue `operation(n)` at `config.target_rps` for `config.duration` and wait for every
+/// request to finish
+pub async fn run_load<F, Fut, T, E>(config: &LoadConfig, operation: F) -> LoadReport
+where
+    F: Fn(u64) -> Fut,
+    Fut: Future<Output = Result<T, E>> + Send + 'static,
+    E: std::fmt::Display,
+{
+    let permits = Arc::new(Semaphore::new(config.max_in_flight.max(1)));
+    let mut ticker = tokio::time::interval(Duration::from_secs_f64(1.0 / config.target_rps.max(0.001)));
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    let started = Instant::now();
+    let mut handles = Vec::new();
+    let mut sequence = 0;
+    while started.elapsed() < config.duration {
+        ticker.tick().await;
+        let permit = permits.clone().acquire_owned().await.expect("load semaphore closed");
+        let request = operation(sequence);
+        sequence += 1;
+        handles.push(tokio::spawn(async move {
+            let _permit = permit;
+            let request_started = Instant::now();
+            request
+                .await
+                .map(|_| request_started.elapsed())
+                .map_err(|e| e.to_string())
+        }));
+    }
+
+    let mut latencies = Vec::with_capacity(handles.len());
+    let mut errors = Vec::new();
+    let mut failed_requests = 0;
+    for handle in handles {
+        match handle.await {
+            Ok(Ok(latency)) => latencies.push(latency),
+            Ok(Err(error)) => {
+                failed_requests += 1;
+                if errors.len() < MAX_REPORTED_ERRORS && !errors.contains(&error) {
+                    errors.push(error);
+                }
+            }
+            Err(join_error) => {
+                failed_requests += 1;
+                if errors.len() < MAX_REPORTED_ERRORS {
+                    errors.push(join_error.to_string());
+                }
+            }
+        }
+    }
+
+    let elapsed = started.elapsed();
+    LoadReport {
+        target_rps: config.target_rps,
+        achieved_rps: sequence as f64 / elapsed.as_secs_f64(),
+        total_requests: sequence,
+        successful_requests: latencies.len() as u64,
+        failed_requests,
+        elapsed_ms: elapsed.as_millis() as u64,
+        latency: LatencyPercentiles::from_latencies(&latencies),
+        errors,
+    }
+}
@@ -0,0 +1,82 @@
+//! End-to-end executor load generator
+//!
+//! Usage: `cargo run -p stepflow-executor --features bench --bin stepflow-bench -- [--rps N] [--duration SECS] [--max-in-flight N] [--database URL]`
+//!
+//! Registers a benchmark tool in a fresh database, executes it at the requested rate
+//! and prints a [`LoadReport`](stepflow_executor::bench::LoadReport) as JSON.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use stepflow_core::*;
+use stepflow_database::{MigrationManager, SqliteDatabase};
+use stepflow_executor::bench::{run_load, LoadConfig};
+use stepflow_executor::{create_default_executor, ExecutionContext, ExecutionRequest, Executor};
+use stepflow_registry::{Registry, RegistryImpl};
+
+const USAGE: &str = "Usage: stepflow-bench [--rps N] [--duration SECS] [--max-in-flight N] [--database URL]";
+
+const BENCH_TOOL_ID: &str = "bench-tool";
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut config = LoadConfig::default();
+    let mut database_url = "sqlite::memory:".to_string();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(flag) = args.next() {
+        let Some(value) = args.next() else {
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        };
+        match flag.as_str() {
+            "--rps" => config.target_rps = value.parse()?,
+            "--duration" => config.duration = Duration::from_secs_f64(value.parse()?),
+            "--max-in-flight" => config.max_in_flight = value.parse()?,
+            "--database" => database_url = value,
+            _ => {
+                eprintln!("{}", USAGE);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    let db = Arc::new(SqliteDatabase::new(&database_url).await?);
+    MigrationManager::run_migrations(&db).await?;
+    let registry = Arc::new(RegistryImpl::new(db.clone()).await?);
+    registry.register_tool(bench_tool()).await?;
+    let executor = Arc::new(create_default_executor(db, registry)?);
+
+    let report = run_load(&config, |sequence| {
+        let executor = executor.clone();
+        async move { executor.execute_tool(bench_request(sequence)).await }
+    })
+    .await;
+
+    println!("{}", report.to_json());
+    Ok(())
+}
+
+fn bench_tool() -> ToolInfo {
+    ToolInfo::builder()
+        .id(ToolId::from_string(BENCH_TOOL_ID.to_string()))
+        .name("Benchmark Tool")
+        .description("Tool executed by stepflow-bench")
+        .tool_type(ToolType::System)
+        .author("stepflow")
+        .tags(["bench"])
+        .build()
+        .unwrap()
+}
+
+fn bench_request(sequence: u64) -> ExecutionRequest {
+    let mut context = ExecutionContext::new("bench-user", "bench-tenant");
+    context.session_id = "bench-session".to_string();
+    context.request_id = format!("bench-{}", sequence);
+    ExecutionRequest::builder()
+        .tool_id(ToolId::from_string(BENCH_TOOL_ID.to_string()))
+        .parameter("sequence", serde_json::json!(sequence))
+        .context(context)
+        .build()
+        .expect("bench request is valid")
+}
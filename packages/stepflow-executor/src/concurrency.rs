@@ -0,0 +1,246 @@
+//! Execution concurrency groups
+//!
+//! An execution may declare a [`ConcurrencyGroup`] whose key is a template over its
+//! parameters, e.g. `deploy-{{ params.environment }}`. At most `max_concurrency`
+//! executions of a tenant run under the same rendered key; the others wait in FIFO
+//! order and can look up their position in the queue. Waiting is bounded by a timeout,
+//! and an execution whose parent already holds a slot of a full group fails right
+//! away instead of waiting for a parent that is waiting for it.
+//!
+//! Slots are held in this process only, so the executor rejects executions that
+//! declare a group when the scheduler uses a task queue shared between instances.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use stepflow_core::ExecutionId;
+use tokio::sync::oneshot;
+use crate::errors::{ExecutorError, ExecutorResult};
+
+/// How long an execution waits for a slot unless its group says otherwise
+pub const DEFAULT_WAIT_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// Concurrency limit declared by an execution
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConcurrencyGroup {
+    /// Group key template; `params` holds the execution parameters
+    pub key: String,
+    /// Executions of the group allowed to run at once
+    #[serde(default = "default_max_concurrency")]
+    pub max_concurrency: usize,
+    /// How long to wait for a slot before failing, in milliseconds
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+}
+
+fn default_max_concurrency() -> usize {
+    1
+}
+
+impl ConcurrencyGroup {
+    /// A mutex: one execution per key at a time
+    pub fn mutex(key: impl Into<String>) -> Self {
+        Self { key: key.into(), max_concurrency: 1, timeout_ms: None }
+    }
+
+    pub fn wait_timeout(&self) -> Duration {
+        self.timeout_ms.map(Duration::from_millis).unwrap_or(DEFAULT_WAIT_TIMEOUT)
+    }
+}
+
+/// Where a waiting execution stands in its group's queue
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueuePosition {
+    pub key: String,
+    /// 1 for the execution that runs next
+    pub position: usize,
+    pub running: usize,
+    pub max_concurrency: usize,
+}
+
+/// Executions running and waiting under a group key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConcurrencyGroupStatus {
+    pub key: String,
+    pub max_concurrency: usize,
+    pub running: Vec<ExecutionId>,
+    /// In the order they will run
+    pub waiting: Vec<ExecutionId>,
+}
+
+struct Waiter {
+    execution_id: ExecutionId,
+    /// Receives `true` when a slot is granted, `false` when the wait is cancelled
+    wake: oneshot::Sender<bool>,
+}
+
+struct Group {
+    max_concurrency: usize,
+    running: Vec<ExecutionId>,
+    waiting: VecDeque<Waiter>,
+}
+
+impl Group {
+    /// Hand free slots to waiters in FIFO order
+    fn promote(&mut self) {
+        while self.running.len() < self.max_concurrency {
+            let Some(waiter) = self.waiting.pop_front() else { break };
+            // A waiter that gave up has dropped its receiver
+            if waiter.wake.send(true).is_ok() {
+                self.running.push(waiter.execution_id);
+            }
+        }
+    }
+}
+
+/// Group keys are scoped to a tenant
+type GroupKey = (String, String);
+
+/// Slots of all concurrency groups of an executor
+#[derive(Clone, Default)]
+pub struct ConcurrencyGroups {
+    groups: Arc<Mutex<HashMap<GroupKey, Group>>>,
+}
+
+impl ConcurrencyGroups {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Wait for a slot in the group `key` of `tenant_id`
+    ///
+    /// The slot is held until the returned permit is dropped. The limit declared by the
+    /// most recent execution applies to the whole group.
+    pub async fn acquire(
+        &self,
+        tenant_id: &str,
+        key: &str,
+        group: &ConcurrencyGroup,
+        execution_id: &ExecutionId,
+        parent_execution_id: Option<&ExecutionId>,
+    ) -> ExecutorResult<ConcurrencyPermit> {
+        if group.max_concurrency == 0 {
+            return Err(ExecutorError::InvalidParameters("Concurrency group max_concurrency must be at least 1".to_string()));
+        }
+        let group_key = (tenant_id.to_string(), key.to_string());
+        let permit = ConcurrencyPermit { groups: self.clone(), key: group_key.clone(), execution_id: execution_id.clone() };
+        let wake = {
+            let mut groups = self.lock();
+            let entry = groups.entry(group_key).or_insert_with(|| Group {
+                max_concurrency: group.max_concurrency,
+                running: Vec::new(),
+                waiting: VecDeque::new(),
+            });
+            entry.max_concurrency = group.max_concurrency;
+            entry.promote();
+            if entry.waiting.is_empty() && entry.running.len() < entry.max_concurrency {
+                entry.running.push(execution_id.clone());
+                return Ok(permit);
+            }
+            if parent_execution_id.is_some_and(|parent| entry.running.contains(parent)) {
+                return Err(ExecutorError::Conflict(format!(
+                    "Concurrency group {} is full and held by the parent execution; waiting would deadlock",
+                    key,
+                )));
+            }
+            let (sender, receiver) = oneshot::channel();
+            entry.waiting.push_back(Waiter { execution_id: execution_id.clone(), wake: sender });
+            receiver
+        };
+
+        match tokio::time::timeout(group.wait_timeout(), wake).await {
+            Ok(Ok(true)) => Ok(permit),
+            Ok(Ok(false)) | Ok(Err(_)) => Err(ExecutorError::ExecutionFailed(format!(
+                "Execution {} was cancelled while waiting for concurrency group {}",
+                execution_id, key,
+            ))),
+            // A slot granted as the timeout fired is kept
+            Err(_) if self.holds(&permit.key, execution_id) => Ok(permit),
+            Err(_) => Err(ExecutorError::TimeoutExceeded),
+        }
+    }
+
+    /// Stop waiting for a slot; returns whether the execution was waiting
+    pub fn cancel(&self, execution_id: &ExecutionId) -> bool {
+        let mut groups = self.lock();
+        for group in groups.values_mut() {
+            if let Some(index) = group.waiting.iter().position(|waiter| &waiter.execution_id == execution_id) {
+                if let Some(waiter) = group.waiting.remove(index) {
+                    let _ = waiter.wake.send(false);
+                }
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Queue position of a waiting execution; `None` once it runs or if it never waited
+    pub fn queue_position(&self, execution_id: &ExecutionId) -> Option<QueuePosition> {
+        let groups = self.lock();
+        groups.iter().find_map(|((_, key), group)| {
+            let index = group.waiting.iter().position(|waiter| &waiter.execution_id == execution_id)?;
+            Some(QueuePosition {
+                key: key.clone(),
+                position: index + 1,
+                running: group.running.len(),
+                max_concurrency: group.max_concurrency,
+            })
+        })
+    }
+
+    /// Groups of a tenant with running or waiting executions, ordered by key
+    pub fn status(&self, tenant_id: &str) -> Vec<ConcurrencyGroupStatus> {
+        let groups = self.lock();
+        let mut status: Vec<ConcurrencyGroupStatus> = groups
+            .iter()
+            .filter(|((tenant, _), _)| tenant == tenant_id)
+            .map(|((_, key), group)| ConcurrencyGroupStatus {
+                key: key.clone(),
+                max_concurrency: group.max_concurrency,
+                running: group.running.clone(),
+                waiting: group.waiting.iter().map(|waiter| waiter.execution_id.clone()).collect(),
+            })
+            .collect();
+        status.sort_by(|a, b| a.key.cmp(&b.key));
+        status
+    }
+
+    fn holds(&self, key: &GroupKey, execution_id: &ExecutionId) -> bool {
+        self.lock().get(key).is_some_and(|group| group.running.contains(execution_id))
+    }
+
+    fn release(&self, key: &GroupKey, execution_id: &ExecutionId) {
+        let mut groups = self.lock();
+        let Some(group) = groups.get_mut(key) else { return };
+        group.running.retain(|id| id != execution_id);
+        group.waiting.retain(|waiter| &waiter.execution_id != execution_id);
+        group.promote();
+        if group.running.is_empty() && group.waiting.is_empty() {
+            groups.remove(key);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<GroupKey, Group>> {
+        self.groups.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A slot in a concurrency group, released on drop
+pub struct ConcurrencyPermit {
+    groups: ConcurrencyGroups,
+    key: GroupKey,
+    execution_id: ExecutionId,
+}
+
+impl ConcurrencyPermit {
+    pub fn key(&self) -> &str {
+        &self.key.1
+    }
+}
+
+impl Drop for ConcurrencyPermit {
+    fn drop(&mut self) {
+        self.groups.release(&self.key, &self.execution_id);
+    }
+}
@@ -0,0 +1,221 @@
+//! Execution environment policy
+//!
+//! Environment variables supplied in an `ExecutionContext` are checked
+//! against an admin-defined policy, optionally narrowed by the tenant, before
+//! they reach a tool. Requests that set reserved or denied variables, or that
+//! exceed the size limits, are rejected rather than silently filtered. The
+//! executor then injects `STEPFLOW_EXECUTION_ID` and `STEPFLOW_TENANT`;
+//! interactive tool sessions get `STEPFLOW_SESSION_ID` instead of the
+//! execution ID.
+//!
+//! Patterns are case-sensitive and support `*` as a wildcard, e.g. `AWS_*`.
+
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use stepflow_core::*;
+use crate::errors::*;
+
+/// Injected variable holding the execution ID
+pub const ENV_EXECUTION_ID: &str = "STEPFLOW_EXECUTION_ID";
+
+/// Injected variable holding the tenant ID
+pub const ENV_TENANT: &str = "STEPFLOW_TENANT";
+
+/// Injected variable holding the interactive tool session ID
+pub const ENV_SESSION_ID: &str = "STEPFLOW_SESSION_ID";
+
+/// Tenant setting key under which `EnvironmentRules` are stored
+pub const TENANT_ENVIRONMENT_POLICY_SETTING: &str = "environment_policy";
+
+/// Tenant-defined allow/deny rules, stored in the tenant's settings
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnvironmentRules {
+    #[serde(default)]
+    pub allow: Vec<String>,
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+impl EnvironmentRules {
+    /// Read rules from tenant settings, if present
+    pub fn from_tenant(tenant: &TenantInfo) -> ExecutorResult<Option<Self>> {
+        match tenant.settings.get(TENANT_ENVIRONMENT_POLICY_SETTING) {
+            Some(value) => Ok(Some(serde_json::from_value(value.clone())?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Store rules in tenant settings
+    pub fn store_in_tenant(&self, tenant: &mut TenantInfo) -> ExecutorResult<()> {
+        tenant.settings.insert(
+            TENANT_ENVIRONMENT_POLICY_SETTING.to_string(),
+            serde_json::to_value(self)?,
+        );
+        Ok(())
+    }
+}
+
+/// Environment policy applied to every execution
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvironmentPolicy {
+    /// Variables a request may set; empty allows anything not denied
+    pub allow: Vec<String>,
+    /// Variables a request may never set
+    pub deny: Vec<String>,
+    /// Variables owned by the executor; setting them is always rejected
+    pub reserved: Vec<String>,
+    pub max_variables: usize,
+    pub max_name_length: usize,
+    pub max_value_length: usize,
+    /// Combined size of all names and values in bytes
+    pub max_total_size: usize,
+}
+
+impl Default for EnvironmentPolicy {
+    fn default() -> Self {
+        Self {
+            allow: Vec::new(),
+            deny: vec!["LD_*".to_string(), "DYLD_*".to_string()],
+            reserved: vec!["STEPFLOW_*".to_string()],
+            max_variables: 64,
+            max_name_length: 128,
+            max_value_length: 4096,
+            max_total_size: 64 * 1024,
+        }
+    }
+}
+
+impl EnvironmentPolicy {
+    /// Validate the requested environment and inject executor variables.
+    ///
+    /// Tenant rules can only narrow this (admin) policy: deny lists are
+    /// merged, and when both sides have an allow list a variable must match
+    /// both.
+    pub fn apply(
+        &self,
+        requested: &HashMap<String, String>,
+        tenant_rules: Option<&EnvironmentRules>,
+        execution_id: &ExecutionId,
+        tenant_id: &str,
+    ) -> ExecutorResult<HashMap<String, String>> {
+        self.check(requested, tenant_rules)?;
+
+        let mut environment = requested.clone();
+        environment.insert(ENV_EXECUTION_ID.to_string(), execution_id.to_string());
+        environment.insert(ENV_TENANT.to_string(), tenant_id.to_string());
+        Ok(environment)
+    }
+
+    /// Validate the environment of an interactive tool session and inject
+    /// the session and tenant IDs
+    pub fn apply_session(
+        &self,
+        requested: &HashMap<String, String>,
+        tenant_rules: Option<&EnvironmentRules>,
+        session_id: &str,
+        tenant_id: &str,
+    ) -> ExecutorResult<HashMap<String, String>> {
+        self.check(requested, tenant_rules)?;
+
+        let mut environment = requested.clone();
+        environment.insert(ENV_SESSION_ID.to_string(), session_id.to_string());
+        environment.insert(ENV_TENANT.to_string(), tenant_id.to_string());
+        Ok(environment)
+    }
+
+    /// Reject environments that set reserved or denied variables, miss the
+    /// allow lists or exceed the size limits
+    pub fn check(
+        &self,
+        requested: &HashMap<String, String>,
+        tenant_rules: Option<&EnvironmentRules>,
+    ) -> ExecutorResult<()> {
+        let mut violations = Vec::new();
+        self.check_limits(requested, &mut violations);
+
+        let mut names: Vec<&String> = requested.keys().collect();
+        names.sort();
+
+        for name in names {
+            if matches_any(&self.reserved, name) || [ENV_EXECUTION_ID, ENV_TENANT, ENV_SESSION_ID].contains(&name.as_str()) {
+                violations.push(format!("{}: reserved variable", name));
+            } else if matches_any(&self.deny, name)
+                || tenant_rules.is_some_and(|rules| matches_any(&rules.deny, name))
+            {
+                violations.push(format!("{}: denied by policy", name));
+            } else if !self.allows(tenant_rules, name) {
+                violations.push(format!("{}: not in allow-list", name));
+            }
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(ExecutorError::EnvironmentPolicyViolation(violations.join("; ")))
+        }
+    }
+
+    fn allows(&self, tenant_rules: Option<&EnvironmentRules>, name: &str) -> bool {
+        let admin_allows = self.allow.is_empty() || matches_any(&self.allow, name);
+        let tenant_allows = tenant_rules
+            .map(|rules| rules.allow.is_empty() || matches_any(&rules.allow, name))
+            .unwrap_or(true);
+        admin_allows && tenant_allows
+    }
+
+    fn check_limits(&self, requested: &HashMap<String, String>, violations: &mut Vec<String>) {
+        if requested.len() > self.max_variables {
+            violations.push(format!(
+                "too many variables: {} (max {})", requested.len(), self.max_variables
+            ));
+        }
+
+        let mut total_size = 0;
+        for (name, value) in requested {
+            total_size += name.len() + value.len();
+            if name.is_empty() || name.contains('=') || name.contains('\0') {
+                violations.push(format!("{:?}: invalid variable name", name));
+            }
+            if name.len() > self.max_name_length {
+                violations.push(format!("{}: name exceeds {} bytes", name, self.max_name_length));
+            }
+            if value.len() > self.max_value_length {
+                violations.push(format!("{}: value exceeds {} bytes", name, self.max_value_length));
+            }
+        }
+
+        if total_size > self.max_total_size {
+            violations.push(format!(
+                "environment size {} bytes exceeds {} bytes", total_size, self.max_total_size
+            ));
+        }
+    }
+}
+
+fn matches_any(patterns: &[String], name: &str) -> bool {
+    patterns.iter().any(|pattern| wildcard_match(pattern, name))
+}
+
+/// Match `name` against a pattern where `*` matches any run of characters
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No wildcard: exact match
+        return rest.is_empty();
+    };
+
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.len() >= last.len() && rest.ends_with(last)
+}
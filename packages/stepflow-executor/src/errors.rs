@@ -2,7 +2,8 @@
 
 use thiserror::Error;
 use stepflow_core::*;
-use crate::execution_context::{TaskId, WorkId};
+use crate::execution_context::{TaskId, WorkId, WorkerId};
+use crate::template::TemplateError;
 
 /// Executor error type
 #[derive(Debug, Error)]
@@ -10,6 +11,9 @@ pub enum ExecutorError {
     #[error("Tool not found: {0}")]
     ToolNotFound(ToolId),
     
+    #[error("Execution not found: {0}")]
+    ExecutionNotFound(ExecutionId),
+    
     #[error("Execution failed: {0}")]
     ExecutionFailed(String),
     
@@ -25,17 +29,66 @@ pub enum ExecutorError {
     #[error("Invalid parameters: {0}")]
     InvalidParameters(String),
     
+    #[error("Environment policy violation: {0}")]
+    EnvironmentPolicyViolation(String),
+    
+    #[error("Template error in parameter {0}")]
+    TemplateError(#[from] TemplateError),
+    
+    #[error("Conflict: {0}")]
+    Conflict(String),
+    
     #[error("Database error: {0}")]
     DatabaseError(String),
     
     #[error("Registry error: {0}")]
-    RegistryError(String),
+    RegistryError(#[from] stepflow_registry::RegistryError),
     
     #[error("Monitoring error: {0}")]
     MonitoringError(String),
     
     #[error("Internal error: {0}")]
     InternalError(String),
+    
+    #[error(transparent)]
+    Core(#[from] StepflowError),
+    
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+impl ExecutorError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            ExecutorError::ToolNotFound(_) => ErrorCode::ToolNotFound,
+            ExecutorError::ExecutionNotFound(_) => ErrorCode::ExecutionNotFound,
+            ExecutorError::ExecutionFailed(_) => ErrorCode::ExecutionFailed,
+            ExecutorError::TimeoutExceeded => ErrorCode::Timeout,
+            ExecutorError::ResourceLimitExceeded => ErrorCode::ResourceExhausted,
+            ExecutorError::PermissionDenied => ErrorCode::PermissionDenied,
+            ExecutorError::InvalidParameters(_) | ExecutorError::TemplateError(_) => ErrorCode::InvalidInput,
+            ExecutorError::EnvironmentPolicyViolation(_) => ErrorCode::PolicyViolation,
+            ExecutorError::Conflict(_) => ErrorCode::Conflict,
+            ExecutorError::DatabaseError(_) => ErrorCode::DatabaseError,
+            ExecutorError::RegistryError(e) => e.code(),
+            ExecutorError::Core(e) => e.code(),
+            ExecutorError::Json(_) | ExecutorError::MonitoringError(_) | ExecutorError::InternalError(_) => {
+                ErrorCode::Internal
+            }
+        }
+    }
+
+    pub fn retryable(&self) -> bool {
+        match self {
+            ExecutorError::RegistryError(e) => e.retryable(),
+            ExecutorError::Core(e) => e.retryable(),
+            _ => self.code().retryable(),
+        }
+    }
+
+    pub fn user_facing(&self) -> bool {
+        self.code().user_facing()
+    }
 }
 
 /// Scheduler error type
@@ -66,6 +119,9 @@ pub enum WorkerPoolError {
     #[error("Work not found: {0}")]
     WorkNotFound(WorkId),
     
+    #[error("Worker not found: {0}")]
+    WorkerNotFound(WorkerId),
+    
     #[error("Worker pool full")]
     PoolFull,
     
@@ -75,6 +131,9 @@ pub enum WorkerPoolError {
     #[error("Invalid work: {0}")]
     InvalidWork(String),
     
+    #[error("No worker satisfies constraints: {}", .0.join(", "))]
+    Unschedulable(Vec<String>),
+    
     #[error("Internal error: {0}")]
     InternalError(String),
 }
@@ -89,26 +148,14 @@ pub type WorkerPoolResult<T> = Result<T, WorkerPoolError>;
 pub type MonitoringResult<T> = Result<T, MonitoringError>;
 
 /// Error conversions
-impl From<stepflow_core::StepflowError> for ExecutorError {
-    fn from(error: stepflow_core::StepflowError) -> Self {
-        ExecutorError::InternalError(error.to_string())
-    }
-}
-
-impl From<stepflow_registry::RegistryError> for ExecutorError {
-    fn from(error: stepflow_registry::RegistryError) -> Self {
-        ExecutorError::RegistryError(error.to_string())
-    }
-}
-
-impl From<serde_json::Error> for ExecutorError {
-    fn from(error: serde_json::Error) -> Self {
-        ExecutorError::InternalError(format!("JSON error: {}", error))
-    }
-}
-
 impl From<tokio::time::error::Elapsed> for ExecutorError {
     fn from(_: tokio::time::error::Elapsed) -> Self {
         ExecutorError::TimeoutExceeded
     }
+}
+
+impl From<stepflow_sandbox::SandboxError> for ExecutorError {
+    fn from(e: stepflow_sandbox::SandboxError) -> Self {
+        ExecutorError::ExecutionFailed(e.to_string())
+    }
 } 
\ No newline at end of file
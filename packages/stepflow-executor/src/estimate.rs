@@ -0,0 +1,285 @@
+//! Pre-execution duration and cost estimates
+//!
+//! An estimate is built from the stored results of recent executions of the
+//! same tool version. When there are too few of them the tool's anomaly
+//! baseline, which spans all versions, is used instead. Current queue and
+//! worker pool conditions turn the expected run time into an expected wait,
+//! and a [`CostModel`] prices the CPU and memory the execution is expected to
+//! use. Callers use the [`Recommendation`] to run synchronously, submit
+//! asynchronously or defer the execution.
+
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use stepflow_core::*;
+use stepflow_monitoring::ToolBaseline;
+use crate::execution_context::{ExecutionRequest, PoolStatus, QueueStatus};
+
+/// z-score of the 90th percentile of a normal distribution
+const P90_Z: f64 = 1.2816;
+
+const BYTES_PER_GB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+/// Prices and thresholds used for estimates
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CostModel {
+    /// Cost units per CPU core second
+    pub per_cpu_second: f64,
+    /// Cost units per GB of memory held for one second
+    pub per_gb_second: f64,
+    /// Executions expected to finish within this time are recommended to run synchronously
+    pub sync_threshold: Duration,
+    /// Version samples needed before they are preferred over the tool baseline
+    pub min_samples: usize,
+    /// How far back stored results are considered
+    pub history_window: Duration,
+    /// Most recent results considered
+    pub max_samples: usize,
+}
+
+impl Default for CostModel {
+    fn default() -> Self {
+        Self {
+            per_cpu_second: 1.0,
+            per_gb_second: 0.25,
+            sync_threshold: Duration::from_secs(30),
+            min_samples: 5,
+            history_window: Duration::from_secs(30 * 24 * 60 * 60),
+            max_samples: 500,
+        }
+    }
+}
+
+/// Where the duration figures of an estimate come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EstimateBasis {
+    /// Recent executions of the requested tool version
+    ToolVersion,
+    /// The tool's baseline across all versions
+    Tool,
+    /// Nothing is known about the tool yet
+    NoHistory,
+}
+
+/// What the caller is advised to do with the execution
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Recommendation {
+    /// Expected to finish quickly enough to wait for
+    RunSync,
+    /// Submit and poll for the result
+    RunAsync,
+    /// The queue is saturated; submit later
+    Defer,
+}
+
+/// Queue and worker pool conditions at the time of the estimate
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QueueConditions {
+    pub pending_tasks: usize,
+    pub running_tasks: usize,
+    pub total_capacity: usize,
+    pub idle_workers: usize,
+    pub total_workers: usize,
+}
+
+impl QueueConditions {
+    pub fn new(queue: &QueueStatus, pool: &PoolStatus) -> Self {
+        Self {
+            pending_tasks: queue.pending_tasks,
+            running_tasks: queue.running_tasks,
+            total_capacity: queue.total_capacity,
+            idle_workers: pool.idle_workers,
+            total_workers: pool.total_workers,
+        }
+    }
+
+    /// Whether no more tasks can be queued
+    pub fn is_saturated(&self) -> bool {
+        self.total_capacity > 0 && self.pending_tasks >= self.total_capacity
+    }
+
+    /// Expected wait for a worker, assuming queued tasks take `duration_ms` each
+    fn expected_wait_ms(&self, duration_ms: f64) -> f64 {
+        let ahead = (self.pending_tasks + 1).saturating_sub(self.idle_workers);
+        if ahead == 0 {
+            return 0.0;
+        }
+        let rounds = ahead.div_ceil(self.total_workers.max(1));
+        rounds as f64 * duration_ms
+    }
+}
+
+/// Expected resource use and its price
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResourceEstimate {
+    pub cpu_seconds: f64,
+    /// Average memory use, `None` without samples or a memory limit
+    pub memory_bytes: Option<f64>,
+    pub cost_units: f64,
+}
+
+/// Expected duration and cost of an execution
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionEstimate {
+    pub tool_id: ToolId,
+    pub tool_version: String,
+    pub basis: EstimateBasis,
+    /// Executions the figures are based on
+    pub samples: u64,
+    pub expected_duration_ms: f64,
+    pub p90_duration_ms: f64,
+    pub failure_rate: f64,
+    pub expected_queue_wait_ms: f64,
+    /// Expected queue wait plus expected duration
+    pub expected_total_ms: f64,
+    pub resources: ResourceEstimate,
+    pub queue: QueueConditions,
+    pub recommendation: Recommendation,
+    pub estimated_at: DateTime<Utc>,
+}
+
+/// One finished execution of the tool version
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ExecutionSample {
+    pub finished_at: DateTime<Utc>,
+    pub duration_ms: f64,
+    pub success: bool,
+    pub memory_bytes: Option<f64>,
+}
+
+impl ExecutionSample {
+    /// Read a sample from a stored result of `tool_version`
+    ///
+    /// Results of other versions and results without timestamps are skipped.
+    pub fn from_result(result: &ExecutionResult, tool_version: &str) -> Option<Self> {
+        let text = |key: &str| result.metadata.get(key).and_then(|v| v.as_str());
+        if text("tool_version") != Some(tool_version) {
+            return None;
+        }
+        let start: DateTime<Utc> = text("start_time")?.parse().ok()?;
+        let end: DateTime<Utc> = text("end_time")?.parse().ok()?;
+        Some(Self {
+            finished_at: end,
+            duration_ms: (end - start).num_milliseconds().max(0) as f64,
+            success: result.success,
+            memory_bytes: result.metrics.get("memory_usage").copied(),
+        })
+    }
+}
+
+/// Duration statistics the estimate is built on
+#[derive(Debug, Clone, PartialEq)]
+struct DurationStats {
+    basis: EstimateBasis,
+    samples: u64,
+    mean_ms: f64,
+    p90_ms: f64,
+    failure_rate: f64,
+    memory_bytes: Option<f64>,
+}
+
+impl DurationStats {
+    /// Statistics of version samples; durations come from successful executions only,
+    /// since failures often abort early
+    fn from_samples(samples: &[ExecutionSample]) -> Self {
+        let mut durations: Vec<f64> = samples.iter().filter(|s| s.success).map(|s| s.duration_ms).collect();
+        durations.sort_by(f64::total_cmp);
+        let mean_ms = if durations.is_empty() { 0.0 } else { durations.iter().sum::<f64>() / durations.len() as f64 };
+        // Nearest-rank percentile
+        let p90_ms = match durations.len() {
+            0 => 0.0,
+            n => durations[((n as f64 * 0.9).ceil() as usize).clamp(1, n) - 1],
+        };
+        let memory: Vec<f64> = samples.iter().filter_map(|s| s.memory_bytes).collect();
+        Self {
+            basis: EstimateBasis::ToolVersion,
+            samples: samples.len() as u64,
+            mean_ms,
+            p90_ms,
+            failure_rate: samples.iter().filter(|s| !s.success).count() as f64 / samples.len().max(1) as f64,
+            memory_bytes: (!memory.is_empty()).then(|| memory.iter().sum::<f64>() / memory.len() as f64),
+        }
+    }
+
+    fn from_baseline(baseline: &ToolBaseline) -> Self {
+        Self {
+            basis: EstimateBasis::Tool,
+            samples: baseline.samples,
+            mean_ms: baseline.duration_mean_ms,
+            p90_ms: baseline.duration_mean_ms + P90_Z * baseline.duration_stddev_ms(),
+            failure_rate: baseline.failure_rate,
+            memory_bytes: None,
+        }
+    }
+
+    fn none() -> Self {
+        Self {
+            basis: EstimateBasis::NoHistory,
+            samples: 0,
+            mean_ms: 0.0,
+            p90_ms: 0.0,
+            failure_rate: 0.0,
+            memory_bytes: None,
+        }
+    }
+}
+
+impl CostModel {
+    /// Build an estimate for `request`
+    ///
+    /// Version samples are used once there are `min_samples` of them, then
+    /// the tool baseline if it has any samples.
+    pub(crate) fn estimate(
+        &self,
+        request: &ExecutionRequest,
+        tool_version: &ToolVersion,
+        samples: &[ExecutionSample],
+        baseline: Option<&ToolBaseline>,
+        queue: QueueConditions,
+    ) -> ExecutionEstimate {
+        let stats = match baseline {
+            _ if samples.len() >= self.min_samples.max(1) => DurationStats::from_samples(samples),
+            Some(baseline) if baseline.samples > 0 => DurationStats::from_baseline(baseline),
+            _ if !samples.is_empty() => DurationStats::from_samples(samples),
+            _ => DurationStats::none(),
+        };
+
+        let limits = &request.options.resource_limits;
+        let seconds = stats.mean_ms / 1000.0;
+        let memory_bytes = stats.memory_bytes.or(limits.memory_limit.map(|limit| limit as f64));
+        let cpu_seconds = seconds * limits.cpu_limit.unwrap_or(1.0);
+        let gb_seconds = memory_bytes.unwrap_or(0.0) / BYTES_PER_GB * seconds;
+
+        let expected_queue_wait_ms = queue.expected_wait_ms(stats.mean_ms);
+        let expected_total_ms = expected_queue_wait_ms + stats.mean_ms;
+        let recommendation = if queue.is_saturated() {
+            Recommendation::Defer
+        } else if stats.basis != EstimateBasis::NoHistory && expected_total_ms <= self.sync_threshold.as_millis() as f64 {
+            Recommendation::RunSync
+        } else {
+            Recommendation::RunAsync
+        };
+
+        ExecutionEstimate {
+            tool_id: request.tool_id.clone(),
+            tool_version: tool_version.to_string(),
+            basis: stats.basis,
+            samples: stats.samples,
+            expected_duration_ms: stats.mean_ms,
+            p90_duration_ms: stats.p90_ms,
+            failure_rate: stats.failure_rate,
+            expected_queue_wait_ms,
+            expected_total_ms,
+            resources: ResourceEstimate {
+                cpu_seconds,
+                memory_bytes,
+                cost_units: cpu_seconds * self.per_cpu_second + gb_seconds * self.per_gb_second,
+            },
+            queue,
+            recommendation,
+            estimated_at: Utc::now(),
+        }
+    }
+}
@@ -5,6 +5,8 @@ use std::time::Duration;
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 use stepflow_core::*;
+use crate::placement::{ArtifactHint, WorkerProfile};
+use crate::concurrency::ConcurrencyGroup;
 
 // 添加缺失的ID类型定义
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -77,7 +79,10 @@ impl std::fmt::Display for WorkerId {
 }
 
 /// Execution request
+///
+/// Built with [`ExecutionRequest::builder`] outside this crate.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct ExecutionRequest {
     pub tool_id: ToolId,
     pub version: Option<ToolVersion>,
@@ -95,6 +100,107 @@ pub struct ExecutionContext {
     pub request_id: String,
     pub parent_execution_id: Option<ExecutionId>,
     pub environment: HashMap<String, String>,
+    /// Labels attached at submission, e.g. `team` = `payments`
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    /// Note attached at submission
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+impl ExecutionRequest {
+    pub fn builder() -> ExecutionRequestBuilder {
+        ExecutionRequestBuilder::default()
+    }
+}
+
+/// Builder for [`ExecutionRequest`]
+///
+/// `tool_id` and `context` are required; the version defaults to the latest and the
+/// options to [`ExecutionOptions::default`].
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionRequestBuilder {
+    tool_id: Option<ToolId>,
+    version: Option<ToolVersion>,
+    parameters: HashMap<String, serde_json::Value>,
+    context: Option<ExecutionContext>,
+    options: ExecutionOptions,
+}
+
+impl ExecutionRequestBuilder {
+    pub fn tool_id(mut self, tool_id: ToolId) -> Self {
+        self.tool_id = Some(tool_id);
+        self
+    }
+
+    pub fn version(mut self, version: impl Into<Option<ToolVersion>>) -> Self {
+        self.version = version.into();
+        self
+    }
+
+    /// Add one parameter
+    pub fn parameter(mut self, name: impl Into<String>, value: serde_json::Value) -> Self {
+        self.parameters.insert(name.into(), value);
+        self
+    }
+
+    /// Replace the parameters
+    pub fn parameters(mut self, parameters: HashMap<String, serde_json::Value>) -> Self {
+        self.parameters = parameters;
+        self
+    }
+
+    pub fn context(mut self, context: ExecutionContext) -> Self {
+        self.context = Some(context);
+        self
+    }
+
+    pub fn options(mut self, options: ExecutionOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn build(self) -> Result<ExecutionRequest, ValidationError> {
+        let tool_id = self.tool_id.ok_or_else(|| ValidationError::RequiredFieldMissing("tool_id".to_string()))?;
+        let context = self.context.ok_or_else(|| ValidationError::RequiredFieldMissing("context".to_string()))?;
+        for (field, value) in [("context.user_id", &context.user_id), ("context.tenant_id", &context.tenant_id)] {
+            if value.trim().is_empty() {
+                return Err(ValidationError::RequiredFieldMissing(field.to_string()));
+            }
+        }
+        if self.options.timeout == Some(Duration::ZERO) {
+            return Err(ValidationError::InvalidFormat("timeout must be greater than zero".to_string()));
+        }
+
+        Ok(ExecutionRequest {
+            tool_id,
+            version: self.version,
+            parameters: self.parameters,
+            context,
+            options: self.options,
+        })
+    }
+}
+
+impl ExecutionContext {
+    /// Context for `user_id` in `tenant_id` with fresh session and request IDs
+    pub fn new(user_id: impl Into<String>, tenant_id: impl Into<String>) -> Self {
+        Self {
+            user_id: user_id.into(),
+            tenant_id: tenant_id.into(),
+            session_id: uuid::Uuid::new_v4().to_string(),
+            request_id: uuid::Uuid::new_v4().to_string(),
+            parent_execution_id: None,
+            environment: HashMap::new(),
+            labels: HashMap::new(),
+            note: None,
+        }
+    }
+
+    /// Tenant and user to evaluate feature flags for
+    pub fn flag_context(&self) -> FlagContext {
+        FlagContext::new(Some(self.tenant_id.clone()), Some(self.user_id.clone()))
+    }
 }
 
 /// Execution options
@@ -106,6 +212,18 @@ pub struct ExecutionOptions {
     pub priority: Priority,
     pub resource_limits: ResourceLimits,
     pub logging_level: LogLevel,
+    /// Environment the execution runs in; defaults to the tool's label
+    #[serde(default)]
+    pub environment_label: Option<EnvironmentLabel>,
+    /// Worker capabilities the execution needs; merged with the tool's requirements
+    #[serde(default)]
+    pub requirements: ToolRequirements,
+    /// Limits how many executions sharing a key run at once
+    #[serde(default)]
+    pub concurrency: Option<ConcurrencyGroup>,
+    /// Artifacts the execution reads; workers that have them cached are preferred
+    #[serde(default)]
+    pub artifacts: Vec<ArtifactHint>,
 }
 
 /// Execution output (不与stepflow_core冲突的自定义类型)
@@ -190,6 +308,8 @@ pub enum TaskStatus {
     Completed,
     Failed,
     Cancelled,
+    /// No worker in the pool satisfies the task's constraints
+    Unschedulable,
 }
 
 /// Work unit
@@ -230,6 +350,23 @@ pub struct PoolStatus {
     pub total_workers: usize,
     pub pending_work: usize,
     pub completed_work: usize,
+    /// Distinct profiles of idle workers
+    pub idle_profiles: Vec<WorkerProfile>,
+    /// Distinct profiles of all workers
+    pub worker_profiles: Vec<WorkerProfile>,
+    /// Artifact bytes work read from a worker's local cache
+    #[serde(default)]
+    pub artifact_bytes_reused: u64,
+    /// Artifact bytes workers had to fetch for their work
+    #[serde(default)]
+    pub artifact_bytes_transferred: u64,
+}
+
+/// Queue and worker pool status of an executor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutorLoad {
+    pub queue: QueueStatus,
+    pub workers: PoolStatus,
 }
 
 /// Execution information
@@ -243,6 +380,8 @@ pub struct ExecutionInfo {
     pub completed_at: Option<DateTime<Utc>>,
     pub user_id: String,
     pub tenant_id: String,
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
 }
 
 // 为了兼容性，重新导出stepflow_core的类型
@@ -275,6 +414,10 @@ impl Default for ExecutionOptions {
             priority: Priority::default(),
             resource_limits: ResourceLimits::default(),
             logging_level: LogLevel::Info,
+            environment_label: None,
+            requirements: ToolRequirements::default(),
+            concurrency: None,
+            artifacts: Vec::new(),
         }
     }
 } 
\ No newline at end of file
@@ -1,9 +1,16 @@
 //! Core executor traits and interfaces
 
+use std::collections::HashMap;
 use async_trait::async_trait;
 use stepflow_core::*;
+use crate::annotations::{ExecutionAnnotations, ExecutionNote};
+use crate::concurrency::{ConcurrencyGroupStatus, QueuePosition};
 use crate::errors::*;
+use crate::estimate::ExecutionEstimate;
 use crate::execution_context::*;
+use crate::rollout::{RolloutArm, RolloutStatus, ToolRollout};
+use crate::timeline::{ExecutionState, ExecutionTimeline};
+use crate::tool_tests::ToolTestRun;
 
 /// Core executor trait
 #[async_trait]
@@ -26,11 +33,40 @@ pub trait Executor: Send + Sync {
     /// List executions
     async fn list_executions(&self, filter: Option<ExecutionFilter>) -> ExecutorResult<Vec<ExecutionInfo>>;
     
+    /// Get the state transition history of an execution
+    async fn get_execution_timeline(&self, execution_id: &ExecutionId) -> ExecutorResult<Option<ExecutionTimeline>>;
+    
+    /// Add labels to an execution, overwriting the values of existing keys
+    async fn label_execution(&self, execution_id: &ExecutionId, labels: HashMap<String, String>) -> ExecutorResult<()>;
+    
+    /// Remove a label from an execution, returning whether it was set
+    async fn remove_execution_label(&self, execution_id: &ExecutionId, key: &str) -> ExecutorResult<bool>;
+    
+    /// Append a note to an execution
+    async fn add_execution_note(&self, execution_id: &ExecutionId, author: &str, body: &str) -> ExecutorResult<ExecutionNote>;
+    
+    /// Get the labels and notes of an execution
+    async fn get_execution_annotations(&self, execution_id: &ExecutionId) -> ExecutorResult<ExecutionAnnotations>;
+    
+    /// Estimate the duration and cost of a request without running it
+    async fn estimate_execution(&self, request: &ExecutionRequest) -> ExecutorResult<ExecutionEstimate>;
+    
     /// Get execution metrics
     async fn get_execution_metrics(&self, execution_id: &ExecutionId) -> ExecutorResult<Vec<Metric>>;
     
+    /// Position of an execution waiting for a slot in its concurrency group
+    async fn get_queue_position(&self, execution_id: &ExecutionId) -> ExecutorResult<Option<QueuePosition>>;
+    
+    /// Concurrency groups of a tenant with running or waiting executions
+    async fn list_concurrency_groups(&self, tenant_id: &TenantId) -> ExecutorResult<Vec<ConcurrencyGroupStatus>>;
+    
     /// Health check for the executor
     async fn health_check(&self) -> ExecutorResult<bool>;
+    
+    /// Current queue and worker pool load, `None` if the executor does not track it
+    async fn load_status(&self) -> ExecutorResult<Option<ExecutorLoad>> {
+        Ok(None)
+    }
 }
 
 /// Task scheduler trait
@@ -109,6 +145,101 @@ pub trait Monitoring: Send + Sync {
     async fn get_execution_metrics(&self, execution_id: &ExecutionId) -> MonitoringResult<Vec<Metric>>;
 }
 
+/// Persistence behind the executor and scheduler
+///
+/// [`crate::SqliteExecutionStore`] keeps everything in the database;
+/// [`crate::InMemoryExecutionStore`] keeps it in HashMaps for unit tests.
+#[async_trait]
+pub trait ExecutionStore: Send + Sync {
+    /// Get a tenant, `None` if it doesn't exist
+    async fn get_tenant(&self, tenant_id: &TenantId) -> ExecutorResult<Option<TenantInfo>>;
+    
+    /// Get a tenant's stored configuration for a tool
+    async fn get_tool_config(&self, tenant_id: &TenantId, tool_id: &ToolId) -> ExecutorResult<Option<ToolConfig>>;
+    
+    /// Append a state transition to the execution timeline
+    async fn record_event(
+        &self,
+        execution_id: &ExecutionId,
+        tool_id: Option<&ToolId>,
+        tenant_id: Option<&str>,
+        state: ExecutionState,
+        worker_id: Option<&str>,
+        detail: Option<&str>,
+    ) -> ExecutorResult<()>;
+    
+    /// Load the timeline of an execution, `None` if nothing was recorded
+    async fn timeline(&self, execution_id: &ExecutionId) -> ExecutorResult<Option<ExecutionTimeline>>;
+    
+    /// Store the result of an asynchronous execution
+    async fn store_execution_result(&self, execution_id: &ExecutionId, result: &ExecutionResult) -> ExecutorResult<()>;
+    
+    /// Whether a result was stored for an execution
+    async fn has_execution_result(&self, execution_id: &ExecutionId) -> ExecutorResult<bool>;
+    
+    /// List executions, including their labels
+    async fn list_executions(&self, filter: Option<ExecutionFilter>) -> ExecutorResult<Vec<ExecutionInfo>>;
+    
+    /// Set labels on an execution, overwriting the values of existing keys
+    async fn set_labels(&self, execution_id: &ExecutionId, tenant_id: &str, labels: &HashMap<String, String>) -> ExecutorResult<()>;
+    
+    /// Remove a label, returning whether it was set
+    async fn remove_label(&self, execution_id: &ExecutionId, key: &str) -> ExecutorResult<bool>;
+    
+    /// Store a note on an execution
+    async fn add_note(&self, tenant_id: &str, note: &ExecutionNote) -> ExecutorResult<()>;
+    
+    /// Labels and notes of an execution
+    async fn annotations(&self, execution_id: &ExecutionId) -> ExecutorResult<ExecutionAnnotations>;
+    
+    /// Persist a newly scheduled task
+    async fn save_task(&self, task: &Task) -> ExecutorResult<()>;
+    
+    /// Update the status of a persisted task
+    async fn update_task_status(&self, task_id: &TaskId, status: TaskStatus) -> ExecutorResult<()>;
+    
+    /// Atomically claim up to `limit` queued tasks, or running tasks whose lease
+    /// expired, for `instance_id`; claimed tasks are running under a lease until `lease_until`
+    async fn claim_tasks(&self, instance_id: &str, limit: usize, lease_until: chrono::DateTime<chrono::Utc>) -> ExecutorResult<Vec<Task>>;
+    
+    /// Extend the leases `instance_id` holds on `task_ids`, returning the tasks whose lease was lost
+    async fn renew_task_leases(&self, instance_id: &str, task_ids: &[TaskId], lease_until: chrono::DateTime<chrono::Utc>) -> ExecutorResult<Vec<TaskId>>;
+    
+    /// Put a task claimed by `instance_id` back in the queue
+    async fn release_task(&self, instance_id: &str, task_id: &TaskId) -> ExecutorResult<()>;
+    
+    /// Number of running tasks under an unexpired lease, per instance
+    async fn running_tasks_by_instance(&self) -> ExecutorResult<HashMap<String, usize>>;
+    
+    /// Latest rollout of a tool, finished or not
+    async fn latest_rollout(&self, tool_id: &ToolId) -> ExecutorResult<Option<ToolRollout>>;
+    
+    /// Insert a rollout or overwrite its percentage and status
+    async fn save_rollout(&self, rollout: &ToolRollout) -> ExecutorResult<()>;
+    
+    /// Count an execution outcome against an active rollout and return the
+    /// updated rollout, `None` if it is no longer active
+    async fn record_rollout_outcome(
+        &self,
+        rollout_id: &str,
+        arm: RolloutArm,
+        duration_ms: u64,
+        success: bool,
+    ) -> ExecutorResult<Option<ToolRollout>>;
+    
+    /// Move an active rollout to `status`; false if it was already finished
+    async fn finish_rollout(&self, rollout_id: &str, status: RolloutStatus, reason: &str) -> ExecutorResult<bool>;
+    
+    /// Persist a finished tool test run
+    async fn save_test_run(&self, run: &ToolTestRun) -> ExecutorResult<()>;
+    
+    /// Latest test run of a tool version
+    async fn latest_test_run(&self, tool_id: &ToolId, version: &ToolVersion) -> ExecutorResult<Option<ToolTestRun>>;
+    
+    /// Test runs of a tool, newest first
+    async fn list_test_runs(&self, tool_id: &ToolId, limit: usize) -> ExecutorResult<Vec<ToolTestRun>>;
+}
+
 /// Task filter for listing
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct TaskFilter {
@@ -6,24 +6,44 @@ use std::time::Duration;
 use tokio::sync::RwLock;
 use chrono::{DateTime, Utc};
 use stepflow_core::*;
-use stepflow_registry::{Registry, RegistryImpl};
-use stepflow_database::SqliteDatabase;
+use stepflow_registry::{resolve_tool_config, resolve_tool_environment, Registry, RegistryError};
+use stepflow_monitoring::{schema_warnings, Anomaly, AnomalyDetector, PayloadMetrics, PayloadObservation, SlowExecutionLog};
+use stepflow_sandbox::{ImagePin, IntegrityVerifier, SandboxConfig};
+use crate::annotations::{self, ExecutionAnnotations, ExecutionNote};
+use crate::concurrency::{ConcurrencyGroupStatus, ConcurrencyGroups, ConcurrencyPermit, QueuePosition};
 use crate::errors::*;
 use crate::execution_context::*;
 use crate::executor::*;
 use crate::scheduler::SchedulerImpl;
 use crate::worker_pool::WorkerPoolImpl;
-use crate::result_manager::ResultManagerImpl;
-use crate::monitoring::MonitoringImpl;
+use crate::env_policy::{EnvironmentPolicy, EnvironmentRules};
+use crate::estimate::{CostModel, ExecutionEstimate, ExecutionSample, QueueConditions};
+use crate::integrity::RegistryImagePins;
+use crate::result_chunks::OutputLimits;
+use crate::report::{redact, PARAMETERS_METADATA};
+use crate::rollout::{RolloutArm, RolloutManager, ToolRollout};
+use crate::tool_tests::TEST_RUN_LABEL;
+use crate::template::TemplateContext;
+use crate::timeline::{ExecutionState, ExecutionTimeline};
 
 /// Executor implementation
 pub struct ExecutorImpl {
     scheduler: Arc<SchedulerImpl>,
     worker_pool: Arc<WorkerPoolImpl>,
-    result_manager: Arc<ResultManagerImpl>,
-    monitoring: Arc<MonitoringImpl>,
-    registry: Arc<RegistryImpl>,
-    db: Arc<SqliteDatabase>,
+    result_manager: Arc<dyn ResultManager>,
+    monitoring: Arc<dyn Monitoring>,
+    registry: Arc<dyn Registry>,
+    store: Arc<dyn ExecutionStore>,
+    environment_policy: Arc<EnvironmentPolicy>,
+    anomaly_detector: Option<Arc<AnomalyDetector>>,
+    payload_metrics: Option<Arc<PayloadMetrics>>,
+    slow_execution_log: Option<Arc<SlowExecutionLog>>,
+    integrity: Option<Arc<IntegrityVerifier>>,
+    output_limits: Arc<OutputLimits>,
+    cost_model: Arc<CostModel>,
+    rollouts: RolloutManager,
+    concurrency: ConcurrencyGroups,
+    flags: Flags,
     // Active executions tracking
     active_executions: Arc<RwLock<HashMap<ExecutionId, ExecutionRequest>>>,
 }
@@ -33,42 +53,422 @@ impl ExecutorImpl {
     pub fn new(
         scheduler: Arc<SchedulerImpl>,
         worker_pool: Arc<WorkerPoolImpl>,
-        result_manager: Arc<ResultManagerImpl>,
-        monitoring: Arc<MonitoringImpl>,
-        registry: Arc<RegistryImpl>,
-        db: Arc<SqliteDatabase>,
+        result_manager: Arc<dyn ResultManager>,
+        monitoring: Arc<dyn Monitoring>,
+        registry: Arc<dyn Registry>,
+        store: Arc<dyn ExecutionStore>,
     ) -> Self {
         Self {
+            rollouts: RolloutManager::new(store.clone(), registry.clone()),
             scheduler,
             worker_pool,
             result_manager,
             monitoring,
             registry,
-            db,
+            store,
+            anomaly_detector: None,
+            payload_metrics: None,
+            slow_execution_log: None,
+            integrity: None,
+            environment_policy: Arc::new(EnvironmentPolicy::default()),
+            output_limits: Arc::new(OutputLimits::default()),
+            cost_model: Arc::new(CostModel::default()),
+            concurrency: ConcurrencyGroups::new(),
+            flags: Flags::default(),
             active_executions: Arc::new(RwLock::new(HashMap::new())),
         }
     }
     
-    /// Validate execution request
-    async fn validate_request(&self, request: &ExecutionRequest) -> ExecutorResult<()> {
+    /// Set the admin environment policy applied to every execution
+    pub fn with_environment_policy(mut self, policy: EnvironmentPolicy) -> Self {
+        self.environment_policy = Arc::new(policy);
+        self
+    }
+    
+    /// Set the output size limits applied before results are stored
+    pub fn with_output_limits(mut self, limits: OutputLimits) -> Self {
+        self.output_limits = Arc::new(limits);
+        self
+    }
+    
+    /// Set the prices and thresholds used for execution estimates
+    pub fn with_cost_model(mut self, model: CostModel) -> Self {
+        self.cost_model = Arc::new(model);
+        self
+    }
+    
+    /// Compare finished executions against per-tool baselines
+    pub fn with_anomaly_detector(mut self, detector: AnomalyDetector) -> Self {
+        self.anomaly_detector = Some(Arc::new(detector));
+        self
+    }
+    
+    /// Capture executions far slower than their tool's baseline
+    ///
+    /// Baselines come from the anomaly detector, so without one nothing is captured.
+    pub fn with_slow_execution_log(mut self, log: SlowExecutionLog) -> Self {
+        self.slow_execution_log = Some(Arc::new(log));
+        self
+    }
+    
+    /// Record input/output sizes and schema warnings of finished executions
+    pub fn with_payload_metrics(mut self, metrics: PayloadMetrics) -> Self {
+        self.payload_metrics = Some(Arc::new(metrics));
+        self
+    }
+    
+    /// Verify each tool's image against the digest stored in the registry before it runs
+    ///
+    /// The verifier reads its pins from this executor's registry. Queued
+    /// executions prefetch the image while they wait.
+    pub fn with_integrity_verifier(mut self, verifier: IntegrityVerifier) -> Self {
+        self.integrity = Some(Arc::new(RegistryImagePins::attach(verifier, self.registry.clone())));
+        self
+    }
+    
+    /// Image integrity verifier, shared with other components that create sandboxes
+    pub fn integrity_verifier(&self) -> Option<&Arc<IntegrityVerifier>> {
+        self.integrity.as_ref()
+    }
+    
+    /// Share the feature flags the executor checks
+    pub fn with_flags(mut self, flags: Flags) -> Self {
+        self.flags = flags;
+        self
+    }
+    
+    /// Feature flags shared with the API
+    pub fn flags(&self) -> &Flags {
+        &self.flags
+    }
+    
+    /// Whether `flag` is on for the tenant and user submitting `request`
+    pub fn flag_enabled(&self, flag: Flag, request: &ExecutionRequest) -> bool {
+        self.flags.is_enabled(flag, &request.context.flag_context())
+    }
+    
+    /// Check the resolved environment of `request` against the admin and
+    /// tenant policies and inject the executor-owned variables
+    async fn apply_environment_policy(
+        &self,
+        request: &ExecutionRequest,
+        environment: &HashMap<String, String>,
+        execution_id: &ExecutionId,
+    ) -> ExecutorResult<HashMap<String, String>> {
+        let tenant_id = TenantId::from_string(request.context.tenant_id.clone());
+        let tenant = self.store.get_tenant(&tenant_id).await?;
+        let tenant_rules = match &tenant {
+            Some(tenant) => EnvironmentRules::from_tenant(tenant)?,
+            None => None,
+        };
+        
+        self.environment_policy.apply(
+            environment,
+            tenant_rules.as_ref(),
+            execution_id,
+            &request.context.tenant_id,
+        )
+    }
+    
+    /// Truncate an output beyond the tenant's maximum output size
+    async fn limit_output(&self, request: &ExecutionRequest, result: &mut ExecutionResult) -> ExecutorResult<()> {
+        let tenant_id = TenantId::from_string(request.context.tenant_id.clone());
+        let tenant = self.store.get_tenant(&tenant_id).await?;
+        let max_bytes = self.output_limits.max_output_for(tenant.as_ref());
+        if OutputLimits::truncate(result, max_bytes)? {
+            tracing::warn!("Output of {} for tenant {} exceeded {} bytes and was truncated", request.tool_id, tenant_id, max_bytes);
+        }
+        Ok(())
+    }
+    
+    /// Append a state transition to the execution timeline.
+    ///
+    /// The timeline is a debugging aid, so a failed write is logged instead of
+    /// failing the execution itself.
+    async fn record_transition(
+        &self,
+        execution_id: &ExecutionId,
+        request: &ExecutionRequest,
+        state: ExecutionState,
+        detail: Option<&str>,
+    ) {
+        if let Err(e) = self.store
+            .record_event(execution_id, Some(&request.tool_id), Some(&request.context.tenant_id), state, None, detail)
+            .await
+        {
+            tracing::warn!("Failed to record {} transition for execution {}: {}", state.as_str(), execution_id, e);
+        }
+    }
+    
+    /// Wait for a slot in the execution's concurrency group, if it declares one
+    ///
+    /// The group key is rendered with the (already rendered) parameters as `params`.
+    async fn acquire_concurrency(
+        &self,
+        execution_id: &ExecutionId,
+        request: &ExecutionRequest,
+    ) -> ExecutorResult<Option<ConcurrencyPermit>> {
+        let Some(group) = &request.options.concurrency else {
+            return Ok(None);
+        };
+        let key = match TemplateContext::from_request(request, execution_id)
+            .with_variable("params", serde_json::json!(request.parameters))
+            .render(&group.key)?
+        {
+            serde_json::Value::String(key) => key,
+            other => other.to_string(),
+        };
+        if key.trim().is_empty() {
+            return Err(ExecutorError::InvalidParameters("Concurrency group key rendered empty".to_string()));
+        }
+        let context = &request.context;
+        let permit = self.concurrency
+            .acquire(&context.tenant_id, &key, group, execution_id, context.parent_execution_id.as_ref())
+            .await?;
+        Ok(Some(permit))
+    }
+    
+    /// Store the labels and note an execution was submitted with
+    async fn annotate_submission(&self, execution_id: &ExecutionId, request: &ExecutionRequest) -> ExecutorResult<()> {
+        let context = &request.context;
+        if !context.labels.is_empty() {
+            self.store.set_labels(execution_id, &context.tenant_id, &context.labels).await?;
+        }
+        if let Some(note) = &context.note {
+            let note = ExecutionNote::new(execution_id.clone(), context.user_id.as_str(), note.as_str());
+            self.store.add_note(&context.tenant_id, &note).await?;
+        }
+        Ok(())
+    }
+    
+    /// Tenant an execution belongs to, from its timeline
+    async fn execution_tenant(&self, execution_id: &ExecutionId) -> ExecutorResult<String> {
+        self.store
+            .timeline(execution_id)
+            .await?
+            .map(|timeline| timeline.tenant_id.unwrap_or_default())
+            .ok_or_else(|| ExecutorError::ExecutionNotFound(execution_id.clone()))
+    }
+    
+    /// Compare a finished execution against the tool's baseline.
+    ///
+    /// Like the timeline, anomaly detection must never fail the execution.
+    /// Without a detector nothing is flagged.
+    async fn detect_anomalies(
+        &self,
+        execution_id: &ExecutionId,
+        request: &ExecutionRequest,
+        start_time: DateTime<Utc>,
+        success: bool,
+    ) -> Vec<Anomaly> {
+        let Some(detector) = &self.anomaly_detector else {
+            return Vec::new();
+        };
+        let duration_ms = (Utc::now() - start_time).num_milliseconds().max(0) as f64;
+        self.log_slow_execution(detector, execution_id, request, duration_ms, success).await;
+        match detector.observe(execution_id, &request.context.tenant_id, &request.tool_id, duration_ms, success).await {
+            Ok(anomalies) => anomalies,
+            Err(e) => {
+                tracing::warn!("Failed to check execution {} for anomalies: {}", execution_id, e);
+                Vec::new()
+            }
+        }
+    }
+    
+    /// Record the payload characteristics of a finished execution.
+    ///
+    /// Measured before output limits apply, so truncation does not hide growth.
+    /// Like anomaly detection, a failure here is only logged.
+    async fn record_payload(&self, execution_id: &ExecutionId, request: &ExecutionRequest, result: Option<&ExecutionResult>) {
+        let Some(metrics) = &self.payload_metrics else {
+            return;
+        };
+        let input = serde_json::to_value(&request.parameters).unwrap_or_default();
+        let warnings = match self.registry.get_tool(&request.tool_id).await {
+            Ok(tool) => tool.input_schema.map(|schema| schema_warnings(&schema, &input).len()).unwrap_or(0),
+            Err(_) => 0,
+        };
+        let observation = PayloadObservation::measure(&input, result.and_then(|result| result.output.as_ref()), warnings);
+        if let Err(e) = metrics.record(execution_id, &request.context.tenant_id, &request.tool_id, &observation).await {
+            tracing::warn!("Failed to record payload metrics of execution {}: {}", execution_id, e);
+        }
+    }
+    
+    /// Check a finished execution against the baseline it has not been folded into yet
+    async fn log_slow_execution(
+        &self,
+        detector: &AnomalyDetector,
+        execution_id: &ExecutionId,
+        request: &ExecutionRequest,
+        duration_ms: f64,
+        success: bool,
+    ) {
+        let Some(log) = &self.slow_execution_log else {
+            return;
+        };
+        let baseline = match detector.baseline(&request.tool_id).await {
+            Ok(baseline) => baseline,
+            Err(e) => {
+                tracing::warn!("Failed to read baseline of tool {}: {}", request.tool_id, e);
+                return;
+            }
+        };
+        let checked = log
+            .check(execution_id, &request.context.tenant_id, &request.tool_id, duration_ms, success, baseline.as_ref())
+            .await;
+        if let Err(e) = checked {
+            tracing::warn!("Failed to check execution {} for slowness: {}", execution_id, e);
+        }
+    }
+    
+    /// Mark the result of an anomalous execution in its metadata
+    fn mark_anomaly(result: &mut ExecutionResult, anomalies: &[Anomaly]) {
+        if let Some(anomaly) = anomalies.first() {
+            result.metadata.insert("anomaly".to_string(), anomaly.marker());
+        }
+    }
+    
+    /// Pick the rollout arm for an execution, `None` without an active rollout.
+    ///
+    /// A failed lookup is logged and the stable version runs.
+    async fn route_rollout(
+        &self,
+        execution_id: &ExecutionId,
+        request: &ExecutionRequest,
+    ) -> Option<(ToolRollout, RolloutArm)> {
+        match self.rollouts.active(&request.tool_id).await {
+            Ok(rollout) => rollout.map(|rollout| {
+                let arm = rollout.route(execution_id, request.version.as_ref());
+                (rollout, arm)
+            }),
+            Err(e) => {
+                tracing::warn!("Failed to look up rollout of tool {}: {}", request.tool_id, e);
+                None
+            }
+        }
+    }
+    
+    /// Count a finished execution against its rollout arm.
+    ///
+    /// Like the timeline, rollout bookkeeping must never fail the execution.
+    /// Tool test executions are not counted.
+    async fn record_rollout(
+        &self,
+        request: &ExecutionRequest,
+        route: Option<&(ToolRollout, RolloutArm)>,
+        start_time: DateTime<Utc>,
+        success: bool,
+    ) {
+        let Some((rollout, arm)) = route else {
+            return;
+        };
+        if request.context.labels.contains_key(TEST_RUN_LABEL) {
+            return;
+        }
+        let duration_ms = (Utc::now() - start_time).num_milliseconds().max(0) as u64;
+        if let Err(e) = self.rollouts.record(rollout, *arm, duration_ms, success).await {
+            tracing::warn!("Failed to record outcome for rollout {}: {}", rollout.id, e);
+        }
+    }
+    
+    /// Validate execution request and settle its placement constraints
+    ///
+    /// The tool's worker requirements are added to the request's. An unlabeled
+    /// execution inherits the tool's label; asking to run a tool in an
+    /// environment it is not deployed to is rejected. Submission labels and
+    /// the note are checked here too. Concurrency groups are rejected when
+    /// instances share a task queue, since their slots are held per process.
+    async fn validate_request(&self, request: &mut ExecutionRequest) -> ExecutorResult<()> {
         // Check if tool exists
-        match self.registry.get_tool(&request.tool_id).await {
-            Ok(_) => Ok(()),
-            Err(_) => Err(ExecutorError::ToolNotFound(request.tool_id.clone())),
+        let tool = self.registry.get_tool(&request.tool_id).await
+            .map_err(|_| ExecutorError::ToolNotFound(request.tool_id.clone()))?;
+        
+        annotations::validate_labels(&request.context.labels)?;
+        if let Some(note) = &request.context.note {
+            annotations::validate_note(note)?;
+        }
+        if request.options.concurrency.is_some() && self.scheduler.is_shared() {
+            return Err(ExecutorError::InvalidParameters(
+                "Concurrency groups are not supported when instances share a task queue".to_string(),
+            ));
+        }
+        request.options.requirements.merge(&tool.requirements);
+        
+        match (tool.environment_label, request.options.environment_label) {
+            (Some(deployed), Some(requested)) if deployed != requested => Err(ExecutorError::InvalidParameters(format!(
+                "Tool {} is deployed to {}, not {}", request.tool_id, deployed, requested
+            ))),
+            (Some(deployed), None) => {
+                request.options.environment_label = Some(deployed);
+                Ok(())
+            }
+            _ => Ok(()),
         }
     }
     
+    /// Layer the request environment over the tenant's configured and the tool's default environment
+    async fn resolve_environment(&self, request: &ExecutionRequest) -> ExecutorResult<HashMap<String, String>> {
+        let tenant_id = TenantId::from_string(request.context.tenant_id.clone());
+        let tool = self.registry.get_tool(&request.tool_id).await?;
+        let stored = self.store.get_tool_config(&tenant_id, &request.tool_id).await?;
+        resolve_tool_environment(&tool, stored.as_ref(), &request.context.environment)
+            .map(|environment| environment.to_map())
+            .map_err(|e| match e {
+                RegistryError::ValidationFailed(_) => ExecutorError::InvalidParameters(e.to_string()),
+                other => other.into(),
+            })
+    }
+    
+    /// Resolve the tenant's tool configuration, interpolating the request environment
+    async fn resolve_configuration(&self, request: &ExecutionRequest) -> ExecutorResult<HashMap<String, serde_json::Value>> {
+        let tenant_id = TenantId::from_string(request.context.tenant_id.clone());
+        let tool = self.registry.get_tool(&request.tool_id).await?;
+        let stored = self.store.get_tool_config(&tenant_id, &request.tool_id).await?;
+        resolve_tool_config(&tool, stored, &request.context.environment)
+            .map_err(|e| match e {
+                RegistryError::ValidationFailed(_) => ExecutorError::InvalidParameters(e.to_string()),
+                other => other.into(),
+            })
+    }
+    
+    /// Check the image the tool runs in against its pinned digest
+    ///
+    /// A rollout candidate is checked against the digest of its own version.
+    /// Without a verifier nothing is checked.
+    async fn verify_image(&self, tool: &ToolInfo, route: Option<&(ToolRollout, RolloutArm)>) -> ExecutorResult<()> {
+        let Some(integrity) = &self.integrity else {
+            return Ok(());
+        };
+        let pin = match route {
+            Some((_, RolloutArm::Candidate)) => ImagePin::from_tool(tool),
+            _ => integrity.pinned(tool.id.as_str()).await?,
+        };
+        if let Some(pin) = pin {
+            integrity.verify_pin(&pin, &mut SandboxConfig::default()).await?;
+        }
+        Ok(())
+    }
+    
     /// Create execution result from tool response
     async fn create_execution_result(
         &self,
         execution_id: ExecutionId,
         request: &ExecutionRequest,
+        configuration: &HashMap<String, serde_json::Value>,
         start_time: DateTime<Utc>,
+        route: Option<&(ToolRollout, RolloutArm)>,
     ) -> ExecutorResult<ExecutionResult> {
-        let tool = self.registry.get_tool(&request.tool_id).await?;
+        let tool = match route {
+            Some((rollout, RolloutArm::Candidate)) => rollout.candidate.clone(),
+            _ => self.registry.get_tool(&request.tool_id).await?,
+        };
+        self.verify_image(&tool, route).await?;
+        
+        let mut environment_keys: Vec<&String> = request.context.environment.keys().collect();
+        environment_keys.sort();
         
         // Create a successful execution result compatible with stepflow_core::ExecutionResult
-        let result = ExecutionResult {
+        let mut result = ExecutionResult {
             success: true,
             output: Some(serde_json::json!({
                 "message": "Tool executed successfully",
@@ -101,39 +501,23 @@ impl ExecutorImpl {
                 ("tool_id".to_string(), serde_json::Value::String(tool.id.to_string())),
                 ("tool_name".to_string(), serde_json::Value::String(tool.name.clone())),
                 ("tool_version".to_string(), serde_json::Value::String(tool.version.to_string())),
+                ("configuration_keys".to_string(), serde_json::json!(configuration.keys().collect::<Vec<_>>())),
+                ("environment_keys".to_string(), serde_json::json!(environment_keys)),
                 ("execution_id".to_string(), serde_json::Value::String(execution_id.to_string())),
                 ("start_time".to_string(), serde_json::Value::String(start_time.to_rfc3339())),
                 ("end_time".to_string(), serde_json::Value::String(Utc::now().to_rfc3339())),
+                (PARAMETERS_METADATA.to_string(), redact(&serde_json::json!(request.parameters))),
             ]),
         };
+        if let Some((_, arm)) = route {
+            result.metadata.insert("rollout_arm".to_string(), serde_json::Value::String(arm.as_str().to_string()));
+        }
+        if let Some(label) = request.options.environment_label {
+            result.metadata.insert("environment_label".to_string(), serde_json::Value::String(label.to_string()));
+        }
         
         Ok(result)
     }
-    
-    /// Store async execution result with execution_id
-    async fn store_async_result(&self, execution_id: &ExecutionId, result: ExecutionResult) -> ExecutorResult<()> {
-        // Store in database with execution_id
-        let sql = r#"
-            INSERT INTO execution_results (execution_id, success, output_data, error, logs, metrics, metadata, created_at)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
-        "#;
-        
-        let params = vec![
-            serde_json::Value::String(execution_id.to_string()),
-            serde_json::Value::Bool(result.success),
-            result.output.unwrap_or(serde_json::Value::Null),
-            result.error.map(serde_json::Value::String).unwrap_or(serde_json::Value::Null),
-            serde_json::to_value(&result.logs).unwrap_or(serde_json::Value::Array(vec![])),
-            serde_json::to_value(&result.metrics).unwrap_or(serde_json::Value::Object(serde_json::Map::new())),
-            serde_json::to_value(&result.metadata).unwrap_or(serde_json::Value::Object(serde_json::Map::new())),
-            serde_json::Value::String(Utc::now().to_rfc3339()),
-        ];
-        
-        self.db.execute(sql, &params).await
-            .map_err(|e| ExecutorError::DatabaseError(e.to_string()))?;
-        
-        Ok(())
-    }
 }
 
 impl Clone for ExecutorImpl {
@@ -144,7 +528,17 @@ impl Clone for ExecutorImpl {
             result_manager: self.result_manager.clone(),
             monitoring: self.monitoring.clone(),
             registry: self.registry.clone(),
-            db: self.db.clone(),
+            store: self.store.clone(),
+            environment_policy: self.environment_policy.clone(),
+            anomaly_detector: self.anomaly_detector.clone(),
+            payload_metrics: self.payload_metrics.clone(),
+            slow_execution_log: self.slow_execution_log.clone(),
+            integrity: self.integrity.clone(),
+            output_limits: self.output_limits.clone(),
+            cost_model: self.cost_model.clone(),
+            rollouts: self.rollouts.clone(),
+            concurrency: self.concurrency.clone(),
+            flags: self.flags.clone(),
             active_executions: self.active_executions.clone(),
         }
     }
@@ -153,12 +547,24 @@ impl Clone for ExecutorImpl {
 #[async_trait::async_trait]
 impl Executor for ExecutorImpl {
     /// Execute a tool synchronously
-    async fn execute_tool(&self, request: ExecutionRequest) -> ExecutorResult<ExecutionResult> {
+    async fn execute_tool(&self, mut request: ExecutionRequest) -> ExecutorResult<ExecutionResult> {
         // Validate request
-        self.validate_request(&request).await?;
+        self.validate_request(&mut request).await?;
         
         // Generate execution ID
         let execution_id = ExecutionId::new();
+        
+        // Layer the request environment over the tool's and the tenant's
+        // configured environment, then check the merged result against the
+        // environment policy before it is used anywhere, including by
+        // parameter templates
+        let environment = self.resolve_environment(&request).await?;
+        request.context.environment = self.apply_environment_policy(&request, &environment, &execution_id).await?;
+        request.parameters = TemplateContext::from_request(&request, &execution_id)
+            .render_parameters(&request.parameters)?;
+        let configuration = self.resolve_configuration(&request).await?;
+        self.annotate_submission(&execution_id, &request).await?;
+        self.record_transition(&execution_id, &request, ExecutionState::Queued, None).await;
         let start_time = Utc::now();
         
         // Track active execution
@@ -166,19 +572,46 @@ impl Executor for ExecutorImpl {
             let mut active = self.active_executions.write().await;
             active.insert(execution_id.clone(), request.clone());
         }
+        self.record_transition(&execution_id, &request, ExecutionState::Scheduled, None).await;
+        let _permit = match self.acquire_concurrency(&execution_id, &request).await {
+            Ok(permit) => permit,
+            Err(e) => {
+                let state = if matches!(e, ExecutorError::TimeoutExceeded) { ExecutionState::TimedOut } else { ExecutionState::Failed };
+                self.record_transition(&execution_id, &request, state, Some(&e.to_string())).await;
+                self.active_executions.write().await.remove(&execution_id);
+                return Err(e);
+            }
+        };
         
         // Record execution start
         self.monitoring.record_execution_start(&execution_id).await
             .map_err(|e| ExecutorError::MonitoringError(e.to_string()))?;
+        self.record_transition(&execution_id, &request, ExecutionState::Running, None).await;
+        let route = self.route_rollout(&execution_id, &request).await;
         
         // Create execution result
-        let result = self.create_execution_result(execution_id.clone(), &request, start_time).await?;
+        let mut result = match self.create_execution_result(execution_id.clone(), &request, &configuration, start_time, route.as_ref()).await {
+            Ok(result) => result,
+            Err(e) => {
+                self.detect_anomalies(&execution_id, &request, start_time, false).await;
+                self.record_payload(&execution_id, &request, None).await;
+                self.record_rollout(&request, route.as_ref(), start_time, false).await;
+                self.record_transition(&execution_id, &request, ExecutionState::Failed, Some(&e.to_string())).await;
+                self.active_executions.write().await.remove(&execution_id);
+                return Err(e);
+            }
+        };
+        let anomalies = self.detect_anomalies(&execution_id, &request, start_time, result.success).await;
+        Self::mark_anomaly(&mut result, &anomalies);
+        self.record_payload(&execution_id, &request, Some(&result)).await;
+        self.record_rollout(&request, route.as_ref(), start_time, result.success).await;
         
         // Record execution end
         self.monitoring.record_execution_end(&execution_id, &result).await
             .map_err(|e| ExecutorError::MonitoringError(e.to_string()))?;
         
         // Store result
+        self.limit_output(&request, &mut result).await?;
         self.result_manager.store_result(result.clone()).await
             .map_err(|e| ExecutorError::InternalError(e.to_string()))?;
         
@@ -187,18 +620,31 @@ impl Executor for ExecutorImpl {
             let mut active = self.active_executions.write().await;
             active.remove(&execution_id);
         }
+        self.record_transition(&execution_id, &request, ExecutionState::Completed, None).await;
         
         Ok(result)
     }
     
     /// Execute a tool asynchronously
-    async fn execute_tool_async(&self, request: ExecutionRequest) -> ExecutorResult<ExecutionId> {
+    async fn execute_tool_async(&self, mut request: ExecutionRequest) -> ExecutorResult<ExecutionId> {
         // Validate request
-        self.validate_request(&request).await?;
+        self.validate_request(&mut request).await?;
         
         // Generate execution ID
         let execution_id = ExecutionId::new();
         
+        // Layer the request environment over the tool's and the tenant's
+        // configured environment, then check the merged result against the
+        // environment policy before it is used anywhere, including by
+        // parameter templates
+        let environment = self.resolve_environment(&request).await?;
+        request.context.environment = self.apply_environment_policy(&request, &environment, &execution_id).await?;
+        request.parameters = TemplateContext::from_request(&request, &execution_id)
+            .render_parameters(&request.parameters)?;
+        let configuration = self.resolve_configuration(&request).await?;
+        self.annotate_submission(&execution_id, &request).await?;
+        self.record_transition(&execution_id, &request, ExecutionState::Queued, None).await;
+        
         // Track active execution
         {
             let mut active = self.active_executions.write().await;
@@ -208,6 +654,16 @@ impl Executor for ExecutorImpl {
         // Record execution start
         self.monitoring.record_execution_start(&execution_id).await
             .map_err(|e| ExecutorError::MonitoringError(e.to_string()))?;
+        self.record_transition(&execution_id, &request, ExecutionState::Scheduled, None).await;
+        
+        // Pull the tool's pinned image while the execution waits to run
+        if let Some(integrity) = &self.integrity {
+            let integrity = integrity.clone();
+            let tool_ids = vec![request.tool_id.to_string()];
+            tokio::spawn(async move {
+                integrity.prefetch(&tool_ids).await;
+            });
+        }
         
         // Spawn a background task to simulate async execution
         let executor = self.clone();
@@ -217,22 +673,53 @@ impl Executor for ExecutorImpl {
             // Simulate async work
             tokio::time::sleep(Duration::from_millis(100)).await;
             
-            let start_time = Utc::now();
-            if let Ok(result) = executor.create_execution_result(exec_id.clone(), &req, start_time).await {
-                // Store result with the execution_id
-                if let Err(e) = executor.store_async_result(&exec_id, result.clone()).await {
-                    tracing::error!("Failed to store async result: {}", e);
+            let _permit = match executor.acquire_concurrency(&exec_id, &req).await {
+                Ok(permit) => permit,
+                // Cancelled while waiting; the cancellation is already recorded
+                Err(_) if !executor.active_executions.read().await.contains_key(&exec_id) => return,
+                Err(e) => {
+                    let state = if matches!(e, ExecutorError::TimeoutExceeded) { ExecutionState::TimedOut } else { ExecutionState::Failed };
+                    executor.active_executions.write().await.remove(&exec_id);
+                    executor.record_transition(&exec_id, &req, state, Some(&e.to_string())).await;
+                    return;
                 }
-                
-                // Record execution end
-                if let Err(e) = executor.monitoring.record_execution_end(&exec_id, &result).await {
-                    tracing::error!("Failed to record execution end: {}", e);
+            };
+            let start_time = Utc::now();
+            executor.record_transition(&exec_id, &req, ExecutionState::Running, None).await;
+            let route = executor.route_rollout(&exec_id, &req).await;
+            match executor.create_execution_result(exec_id.clone(), &req, &configuration, start_time, route.as_ref()).await {
+                Ok(mut result) => {
+                    let anomalies = executor.detect_anomalies(&exec_id, &req, start_time, result.success).await;
+                    Self::mark_anomaly(&mut result, &anomalies);
+                    executor.record_payload(&exec_id, &req, Some(&result)).await;
+                    executor.record_rollout(&req, route.as_ref(), start_time, result.success).await;
+                    
+                    // Store result with the execution_id
+                    if let Err(e) = executor.limit_output(&req, &mut result).await {
+                        tracing::error!("Failed to apply output limits to async result: {}", e);
+                    }
+                    if let Err(e) = executor.store.store_execution_result(&exec_id, &result).await {
+                        tracing::error!("Failed to store async result: {}", e);
+                    }
+                    
+                    // Record execution end
+                    if let Err(e) = executor.monitoring.record_execution_end(&exec_id, &result).await {
+                        tracing::error!("Failed to record execution end: {}", e);
+                    }
+                    
+                    // Remove from active executions
+                    {
+                        let mut active = executor.active_executions.write().await;
+                        active.remove(&exec_id);
+                    }
+                    executor.record_transition(&exec_id, &req, ExecutionState::Completed, None).await;
                 }
-                
-                // Remove from active executions
-                {
-                    let mut active = executor.active_executions.write().await;
-                    active.remove(&exec_id);
+                Err(e) => {
+                    executor.detect_anomalies(&exec_id, &req, start_time, false).await;
+                    executor.record_payload(&exec_id, &req, None).await;
+                    executor.record_rollout(&req, route.as_ref(), start_time, false).await;
+                    executor.active_executions.write().await.remove(&exec_id);
+                    executor.record_transition(&exec_id, &req, ExecutionState::Failed, Some(&e.to_string())).await;
                 }
             }
         });
@@ -248,12 +735,9 @@ impl Executor for ExecutorImpl {
             return Ok(ExecutionStatus::Running);
         }
         
-        // Check in database for completed results
-        let sql = "SELECT success FROM execution_results WHERE execution_id = ?";
-        let params = vec![serde_json::Value::String(execution_id.to_string())];
-        
-        match self.db.execute(sql, &params).await {
-            Ok(result) if !result.rows.is_empty() => Ok(ExecutionStatus::Completed),
+        // Check the store for completed results
+        match self.store.has_execution_result(execution_id).await {
+            Ok(true) => Ok(ExecutionStatus::Completed),
             _ => Ok(ExecutionStatus::Pending),
         }
     }
@@ -261,10 +745,14 @@ impl Executor for ExecutorImpl {
     /// Cancel execution
     async fn cancel_execution(&self, execution_id: &ExecutionId) -> ExecutorResult<()> {
         // Remove from active executions
-        {
+        let request = {
             let mut active = self.active_executions.write().await;
-            active.remove(execution_id);
+            active.remove(execution_id)
+        };
+        if let Some(request) = request {
+            self.record_transition(execution_id, &request, ExecutionState::Cancelled, None).await;
         }
+        self.concurrency.cancel(execution_id);
         
         // Cancel in worker pool (if running)
         // Note: This is a simplified implementation
@@ -279,94 +767,80 @@ impl Executor for ExecutorImpl {
     
     /// List executions
     async fn list_executions(&self, filter: Option<ExecutionFilter>) -> ExecutorResult<Vec<ExecutionInfo>> {
-        let mut sql = "SELECT execution_id, tool_id, status, created_at, started_at, completed_at, user_id, tenant_id FROM executions WHERE 1=1".to_string();
-        let mut params = Vec::new();
+        self.store.list_executions(filter).await
+    }
+    
+    /// Get execution timeline
+    async fn get_execution_timeline(&self, execution_id: &ExecutionId) -> ExecutorResult<Option<ExecutionTimeline>> {
+        self.store.timeline(execution_id).await
+    }
+    
+    /// Label an execution
+    async fn label_execution(&self, execution_id: &ExecutionId, labels: HashMap<String, String>) -> ExecutorResult<()> {
+        let tenant_id = self.execution_tenant(execution_id).await?;
+        let mut merged = self.store.annotations(execution_id).await?.labels;
+        merged.extend(labels.clone());
+        annotations::validate_labels(&merged)?;
+        self.store.set_labels(execution_id, &tenant_id, &labels).await
+    }
+    
+    /// Remove an execution label
+    async fn remove_execution_label(&self, execution_id: &ExecutionId, key: &str) -> ExecutorResult<bool> {
+        self.store.remove_label(execution_id, key).await
+    }
+    
+    /// Add a note to an execution
+    async fn add_execution_note(&self, execution_id: &ExecutionId, author: &str, body: &str) -> ExecutorResult<ExecutionNote> {
+        annotations::validate_note(body)?;
+        let tenant_id = self.execution_tenant(execution_id).await?;
+        let note = ExecutionNote::new(execution_id.clone(), author, body);
+        self.store.add_note(&tenant_id, &note).await?;
+        Ok(note)
+    }
+    
+    /// Get execution labels and notes
+    async fn get_execution_annotations(&self, execution_id: &ExecutionId) -> ExecutorResult<ExecutionAnnotations> {
+        self.execution_tenant(execution_id).await?;
+        self.store.annotations(execution_id).await
+    }
+    
+    /// Estimate duration and cost from recent executions and queue conditions
+    async fn estimate_execution(&self, request: &ExecutionRequest) -> ExecutorResult<ExecutionEstimate> {
+        let tool = self.registry.get_tool(&request.tool_id).await
+            .map_err(|_| ExecutorError::ToolNotFound(request.tool_id.clone()))?;
+        let version = request.version.clone().unwrap_or(tool.version);
+        let model = &self.cost_model;
         
-        if let Some(filter) = filter {
-            if let Some(tool_id) = filter.tool_id {
-                sql.push_str(" AND tool_id = ?");
-                params.push(serde_json::Value::String(tool_id.to_string()));
-            }
-            
-            if let Some(status) = filter.status {
-                sql.push_str(" AND status = ?");
-                params.push(serde_json::Value::String(format!("{:?}", status)));
-            }
-            
-            if let Some(user_id) = filter.user_id {
-                sql.push_str(" AND user_id = ?");
-                params.push(serde_json::Value::String(user_id.to_string()));
-            }
-            
-            if let Some(tenant_id) = filter.tenant_id {
-                sql.push_str(" AND tenant_id = ?");
-                params.push(serde_json::Value::String(tenant_id.to_string()));
-            }
-            
-            if let Some(started_after) = filter.started_after {
-                sql.push_str(" AND started_at >= ?");
-                params.push(serde_json::Value::String(started_after.to_rfc3339()));
-            }
-            
-            if let Some(started_before) = filter.started_before {
-                sql.push_str(" AND started_at <= ?");
-                params.push(serde_json::Value::String(started_before.to_rfc3339()));
-            }
-        }
+        let history_window = chrono::Duration::from_std(model.history_window).unwrap_or(chrono::Duration::MAX);
+        let filter = ExecutionFilter {
+            tool_id: Some(request.tool_id.clone()),
+            started_after: Utc::now().checked_sub_signed(history_window),
+            ..ExecutionFilter::default()
+        };
+        let results = self.result_manager.list_results(Some(filter)).await?;
+        let version_label = version.to_string();
+        let mut samples: Vec<ExecutionSample> = results
+            .iter()
+            .filter_map(|result| ExecutionSample::from_result(result, &version_label))
+            .collect();
+        samples.sort_by_key(|sample| std::cmp::Reverse(sample.finished_at));
+        samples.truncate(model.max_samples);
         
-        let result = self.db.execute(&sql, &params).await
-            .map_err(|e| ExecutorError::DatabaseError(e.to_string()))?;
+        // The baseline is only a fallback, so a failed read just leaves it out
+        let baseline = match &self.anomaly_detector {
+            Some(detector) => detector.baseline(&request.tool_id).await.unwrap_or_else(|e| {
+                tracing::warn!("Failed to read baseline of tool {}: {}", request.tool_id, e);
+                None
+            }),
+            None => None,
+        };
         
-        let mut executions = Vec::new();
-        for row in result.rows {
-            let execution_id_str = row.get("execution_id").and_then(|v| v.as_str()).unwrap_or("");
-            let tool_id_str = row.get("tool_id").and_then(|v| v.as_str()).unwrap_or("");
-            let status_str = row.get("status").and_then(|v| v.as_str()).unwrap_or("Pending");
-            let created_at_str = row.get("created_at").and_then(|v| v.as_str()).unwrap_or("");
-            let started_at_str = row.get("started_at").and_then(|v| v.as_str());
-            let completed_at_str = row.get("completed_at").and_then(|v| v.as_str());
-            let user_id_str = row.get("user_id").and_then(|v| v.as_str()).unwrap_or("");
-            let tenant_id_str = row.get("tenant_id").and_then(|v| v.as_str()).unwrap_or("");
-            
-            let execution_id = ExecutionId::from_string(execution_id_str.to_string());
-            let tool_id = ToolId::from_string(tool_id_str.to_string());
-            let status = match status_str {
-                "Running" => ExecutionStatus::Running,
-                "Completed" => ExecutionStatus::Completed,
-                "Failed" => ExecutionStatus::Failed,
-                "Cancelled" => ExecutionStatus::Cancelled,
-                _ => ExecutionStatus::Pending,
-            };
-            
-            let created_at = DateTime::parse_from_rfc3339(created_at_str)
-                .map(|dt| dt.with_timezone(&Utc))
-                .unwrap_or_else(|_| Utc::now());
-            
-            let started_at = started_at_str.and_then(|s| {
-                DateTime::parse_from_rfc3339(s)
-                    .map(|dt| dt.with_timezone(&Utc))
-                    .ok()
-            });
-            
-            let completed_at = completed_at_str.and_then(|s| {
-                DateTime::parse_from_rfc3339(s)
-                    .map(|dt| dt.with_timezone(&Utc))
-                    .ok()
-            });
-            
-            executions.push(ExecutionInfo {
-                execution_id,
-                tool_id,
-                status,
-                created_at,
-                started_at,
-                completed_at,
-                user_id: user_id_str.to_string(),
-                tenant_id: tenant_id_str.to_string(),
-            });
-        }
+        let queue = self.scheduler.get_queue_status().await
+            .map_err(|e| ExecutorError::InternalError(e.to_string()))?;
+        let pool = self.worker_pool.get_pool_status().await
+            .map_err(|e| ExecutorError::InternalError(e.to_string()))?;
         
-        Ok(executions)
+        Ok(model.estimate(request, &version, &samples, baseline.as_ref(), QueueConditions::new(&queue, &pool)))
     }
     
     /// Get execution metrics
@@ -375,6 +849,14 @@ impl Executor for ExecutorImpl {
             .map_err(|e| ExecutorError::MonitoringError(e.to_string()))
     }
     
+    async fn get_queue_position(&self, execution_id: &ExecutionId) -> ExecutorResult<Option<QueuePosition>> {
+        Ok(self.concurrency.queue_position(execution_id))
+    }
+    
+    async fn list_concurrency_groups(&self, tenant_id: &TenantId) -> ExecutorResult<Vec<ConcurrencyGroupStatus>> {
+        Ok(self.concurrency.status(tenant_id.as_str()))
+    }
+    
     async fn health_check(&self) -> ExecutorResult<bool> {
         // Simple health check - verify core components are working
         match self.scheduler.get_queue_status().await {
@@ -382,4 +864,12 @@ impl Executor for ExecutorImpl {
             Err(_) => Ok(false),
         }
     }
+    
+    async fn load_status(&self) -> ExecutorResult<Option<ExecutorLoad>> {
+        let queue = self.scheduler.get_queue_status().await
+            .map_err(|e| ExecutorError::InternalError(e.to_string()))?;
+        let workers = self.worker_pool.get_pool_status().await
+            .map_err(|e| ExecutorError::InternalError(e.to_string()))?;
+        Ok(Some(ExecutorLoad { queue, workers }))
+    }
 } 
\ No newline at end of file
@@ -0,0 +1,38 @@
+//! Image pins read from the registry
+//!
+//! A tool version's container image and digest are stored with the tool
+//! ([`ToolInfo::image`]). [`RegistryImagePins`] serves them to the sandbox's
+//! [`IntegrityVerifier`], so executions are verified against the digest the
+//! registry published rather than pins configured separately.
+
+use std::sync::Arc;
+use stepflow_core::*;
+use stepflow_registry::{Registry, RegistryError};
+use stepflow_sandbox::{async_trait, ImagePin, ImagePinSource, IntegrityVerifier, SandboxError, SandboxResult};
+
+/// Pin source reading the image of the current version of each tool from the registry
+pub struct RegistryImagePins {
+    registry: Arc<dyn Registry>,
+}
+
+impl RegistryImagePins {
+    pub fn new(registry: Arc<dyn Registry>) -> Self {
+        Self { registry }
+    }
+
+    /// Verifier reading its pins from `registry`
+    pub fn attach(verifier: IntegrityVerifier, registry: Arc<dyn Registry>) -> IntegrityVerifier {
+        verifier.with_pin_source(Arc::new(Self::new(registry)))
+    }
+}
+
+#[async_trait]
+impl ImagePinSource for RegistryImagePins {
+    async fn image_pin(&self, tool_id: &str) -> SandboxResult<Option<ImagePin>> {
+        match self.registry.get_tool(&ToolId::from_string(tool_id.to_string())).await {
+            Ok(tool) => Ok(ImagePin::from_tool(&tool)),
+            Err(RegistryError::ToolNotFound(_)) => Ok(None),
+            Err(e) => Err(SandboxError::InternalError(format!("Failed to read image of tool {}: {}", tool_id, e))),
+        }
+    }
+}
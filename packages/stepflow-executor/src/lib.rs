@@ -5,35 +5,79 @@
 
 // Module declarations
 pub mod errors;
+pub mod annotations;
+pub mod concurrency;
 pub mod execution_context;
 pub mod executor;
 pub mod executor_impl;
 pub mod scheduler;
 pub mod worker_pool;
 pub mod result_manager;
+pub mod result_chunks;
 pub mod monitoring;
+pub mod env_policy;
+pub mod estimate;
+pub mod integrity;
+pub mod timeline;
+pub mod store;
+pub mod memory;
+pub mod placement;
+pub mod queue;
+pub mod report;
+mod report_pdf;
+pub mod rollout;
+pub mod template;
+pub mod tool_tests;
+pub mod workflow;
+pub mod workflow_validation;
+#[cfg(feature = "bench")]
+pub mod bench;
 
 // Re-export core types from stepflow_core (avoiding conflicts)
 pub use stepflow_core::{
-    ToolId, ToolVersion, ToolType, ToolStatus, ToolInfo, ToolConfig, ToolRequest, ToolResponse,
+    ToolId, ToolVersion, ToolType, ToolStatus, ToolInfo, ToolConfig, ToolRequest, ToolResponse, EnvironmentLabel, ToolRequirements,
     ExecutionId, ExecutionStatus, TenantId, UserId, UserRole, UserInfo, TenantInfo,
     Pagination, Filter, FilterOperator, Sort, SortDirection, Query,
 };
 
 // Re-export key types and traits
 pub use errors::*;
+pub use annotations::{parse_label, ExecutionAnnotations, ExecutionNote};
+pub use concurrency::{ConcurrencyGroup, ConcurrencyGroupStatus, ConcurrencyGroups, ConcurrencyPermit, QueuePosition};
 pub use execution_context::{
-    TaskId, WorkId, WorkerId, ExecutionRequest, ExecutionContext, ExecutionOptions,
+    TaskId, WorkId, WorkerId, ExecutionRequest, ExecutionRequestBuilder, ExecutionContext, ExecutionOptions,
     ExecutionOutput, ExecutionMetadata, ExecutionTiming, Priority, ResourceLimits,
     ResourceUsage, MetricEntry, Task, TaskStatus, Work, WorkStatus, QueueStatus,
-    PoolStatus, ExecutionInfo,
+    PoolStatus, ExecutorLoad, ExecutionInfo,
 };
 pub use executor::*;
 pub use executor_impl::ExecutorImpl;
-pub use scheduler::{SchedulerImpl, SchedulerConfig};
+pub use scheduler::{SchedulerImpl, SchedulerConfig, SharedQueueMetrics};
 pub use worker_pool::{WorkerPoolImpl, WorkerPoolConfig};
 pub use result_manager::ResultManagerImpl;
+pub use result_chunks::{OutputLimits, OUTPUT_TRUNCATED_METADATA, TENANT_MAX_OUTPUT_SETTING};
 pub use monitoring::MonitoringImpl;
+pub use env_policy::{EnvironmentPolicy, EnvironmentRules};
+pub use integrity::RegistryImagePins;
+pub use estimate::{CostModel, EstimateBasis, ExecutionEstimate, QueueConditions, Recommendation, ResourceEstimate};
+pub use timeline::{ExecutionEvent, ExecutionState, ExecutionTimeline, TimelineRecorder};
+pub use store::SqliteExecutionStore;
+pub use memory::{InMemoryExecutionStore, InMemoryMonitoring, InMemoryResultManager};
+pub use placement::{ArtifactCache, ArtifactHint, WorkerProfile};
+pub use queue::{StoreTaskQueue, TaskQueue};
+#[cfg(feature = "redis")]
+pub use queue::RedisTaskQueue;
+pub use stepflow_core::SimulatedFaults;
+pub use report::{is_sensitive_key, redact, ReportPhase, ReportSubject, RunReport, StepExecution, PARAMETERS_METADATA, REPORT_LOG_LIMIT};
+pub use rollout::{ArmStats, RolloutArm, RolloutManager, RolloutStatus, RolloutThresholds, ToolRollout};
+pub use template::{TemplateContext, TemplateError, TemplateErrorKind};
+pub use tool_tests::{match_example, ExampleResult, TestTrigger, ToolTestRun, ToolTestRunner, TEST_RUN_LABEL};
+pub use workflow::{
+    ApprovalDecision, ApprovalEvent, ApprovalNotifier, ApprovalOutcome, ApprovalStatus, MigrationPolicy, PublishResult,
+    ReduceOperation, StepKind, StepRun, StepRunStatus, WorkflowApproval, WorkflowDefinition, WorkflowEngine,
+    WorkflowRun, WorkflowRunStatus, WorkflowStep, WorkflowVersion,
+};
+pub use workflow_validation::{DryRunOptions, DryRunReport, IssueSeverity, PlannedStep, ValidationIssue, WorkflowValidator};
 
 /// Version information
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -41,7 +85,41 @@ pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 /// Create a new executor with all components
 pub fn create_executor(
     db: std::sync::Arc<stepflow_database::SqliteDatabase>,
-    registry: std::sync::Arc<stepflow_registry::RegistryImpl>,
+    registry: std::sync::Arc<dyn stepflow_registry::Registry>,
+    scheduler_config: Option<SchedulerConfig>,
+    worker_pool_config: Option<WorkerPoolConfig>,
+) -> ExecutorResult<ExecutorImpl> {
+    let executor = create_executor_with_backends(
+        std::sync::Arc::new(SqliteExecutionStore::new(db.clone())),
+        std::sync::Arc::new(ResultManagerImpl::new(db.clone())),
+        std::sync::Arc::new(MonitoringImpl::new(db.clone())),
+        registry,
+        scheduler_config,
+        worker_pool_config,
+    )?;
+    
+    Ok(executor
+        .with_anomaly_detector(stepflow_monitoring::AnomalyDetector::new(db.clone()))
+        .with_payload_metrics(stepflow_monitoring::PayloadMetrics::new(db.clone()))
+        .with_slow_execution_log(stepflow_monitoring::SlowExecutionLog::new(db)))
+}
+
+/// Create a new executor with default configuration
+pub fn create_default_executor(
+    db: std::sync::Arc<stepflow_database::SqliteDatabase>,
+    registry: std::sync::Arc<dyn stepflow_registry::Registry>,
+) -> ExecutorResult<ExecutorImpl> {
+    create_executor(db, registry, None, None)
+}
+
+/// Create a new executor on the given storage backends
+///
+/// Pass the in-memory backends to run an executor without SQLite.
+pub fn create_executor_with_backends(
+    store: std::sync::Arc<dyn ExecutionStore>,
+    result_manager: std::sync::Arc<dyn ResultManager>,
+    monitoring: std::sync::Arc<dyn Monitoring>,
+    registry: std::sync::Arc<dyn stepflow_registry::Registry>,
     scheduler_config: Option<SchedulerConfig>,
     worker_pool_config: Option<WorkerPoolConfig>,
 ) -> ExecutorResult<ExecutorImpl> {
@@ -56,17 +134,11 @@ pub fn create_executor(
     
     // Create scheduler
     let scheduler = std::sync::Arc::new(SchedulerImpl::new(
-        db.clone(),
+        store.clone(),
         worker_pool.clone(),
         scheduler_config,
     ));
     
-    // Create result manager
-    let result_manager = std::sync::Arc::new(ResultManagerImpl::new(db.clone()));
-    
-    // Create monitoring
-    let monitoring = std::sync::Arc::new(MonitoringImpl::new(db.clone()));
-    
     // Create executor
     let executor = ExecutorImpl::new(
         scheduler.clone(),
@@ -74,7 +146,7 @@ pub fn create_executor(
         result_manager,
         monitoring,
         registry,
-        db,
+        store,
     );
     
     // Start the worker pool and scheduler
@@ -93,29 +165,31 @@ pub fn create_executor(
     Ok(executor)
 }
 
-/// Create a new executor with default configuration
-pub fn create_default_executor(
-    db: std::sync::Arc<stepflow_database::SqliteDatabase>,
-    registry: std::sync::Arc<stepflow_registry::RegistryImpl>,
-) -> ExecutorResult<ExecutorImpl> {
-    create_executor(db, registry, None, None)
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::sync::Arc;
+    use stepflow_core::{ExecutionFilter, ExecutionResult, MetricFilter};
     
     async fn create_test_executor() -> ExecutorResult<ExecutorImpl> {
-        let db = Arc::new(stepflow_database::SqliteDatabase::new(":memory:").await.unwrap());
-        let registry = Arc::new(stepflow_registry::RegistryImpl::new(db.clone()).await.unwrap());
-        create_default_executor(db, registry)
+        create_executor_with_backends(
+            Arc::new(InMemoryExecutionStore::new()),
+            Arc::new(InMemoryResultManager::new()),
+            Arc::new(InMemoryMonitoring::new()),
+            Arc::new(stepflow_registry::InMemoryRegistry::new()),
+            None,
+            None,
+        )
     }
     
     #[tokio::test]
     async fn test_create_executor() {
         let executor = create_test_executor().await;
         assert!(executor.is_ok());
+        
+        let db = Arc::new(stepflow_database::SqliteDatabase::new(":memory:").await.unwrap());
+        let registry = Arc::new(stepflow_registry::RegistryImpl::new(db.clone()).await.unwrap());
+        assert!(create_default_executor(db, registry).is_ok());
     }
     
     #[tokio::test]
@@ -133,6 +207,8 @@ mod tests {
                 request_id: "test-request".to_string(),
                 parent_execution_id: None,
                 environment: std::collections::HashMap::new(),
+                labels: std::collections::HashMap::new(),
+                note: None,
             },
             options: ExecutionOptions::default(),
         };
@@ -151,6 +227,8 @@ mod tests {
             request_id: "test-request".to_string(),
             parent_execution_id: None,
             environment: std::collections::HashMap::new(),
+            labels: std::collections::HashMap::new(),
+            note: None,
         };
         
         assert_eq!(context.user_id, "test-user");
@@ -191,4 +269,261 @@ mod tests {
         assert_eq!(metric.name, "test_metric");
         assert_eq!(metric.value, 42.0);
     }
-} 
\ No newline at end of file
+    
+    fn test_result(execution_id: &str, tool_id: &str, success: bool) -> ExecutionResult {
+        ExecutionResult {
+            success,
+            output: Some(serde_json::json!({"value": 1})),
+            error: None,
+            logs: vec![],
+            metrics: std::collections::HashMap::from([("duration_ms".to_string(), 12.0)]),
+            metadata: std::collections::HashMap::from([
+                ("execution_id".to_string(), serde_json::json!(execution_id)),
+                ("tool_id".to_string(), serde_json::json!(tool_id)),
+            ]),
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_in_memory_result_manager() {
+        let manager = InMemoryResultManager::new();
+        manager.store_result(test_result("exec-1", "tool-a", true)).await.unwrap();
+        manager.store_result(test_result("exec-2", "tool-b", false)).await.unwrap();
+        
+        let id = ExecutionId::from_string("exec-1".to_string());
+        assert!(manager.get_result(&id).await.unwrap().success);
+        
+        let filter = ExecutionFilter {
+            tool_id: Some(ToolId::from_string("tool-b".to_string())),
+            tenant_id: None,
+            user_id: None,
+            status: None,
+            started_after: None,
+            started_before: None,
+            labels: std::collections::HashMap::new(),
+        };
+        let results = manager.list_results(Some(filter)).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(!results[0].success);
+        
+        manager.delete_result(&id).await.unwrap();
+        assert!(manager.get_result(&id).await.is_err());
+        assert_eq!(manager.cleanup_results(chrono::Utc::now()).await.unwrap(), 1);
+    }
+    
+    #[tokio::test]
+    async fn test_in_memory_monitoring() {
+        let monitoring = InMemoryMonitoring::new();
+        let id = ExecutionId::from_string("exec-1".to_string());
+        monitoring.record_execution_start(&id).await.unwrap();
+        monitoring.record_execution_end(&id, &test_result("exec-1", "tool-a", true)).await.unwrap();
+        
+        assert_eq!(monitoring.get_execution_metrics(&id).await.unwrap().len(), 3);
+        let filter = MetricFilter {
+            name: Some("execution_end".to_string()),
+            labels: std::collections::HashMap::from([("status".to_string(), "success".to_string())]),
+            start_time: None,
+            end_time: None,
+        };
+        assert_eq!(monitoring.get_metrics(Some(filter)).await.unwrap().len(), 1);
+    }
+    
+    #[tokio::test]
+    async fn test_simulated_faults() {
+        let faults = SimulatedFaults::new();
+        let manager = InMemoryResultManager::with_faults(faults.clone());
+        let monitoring = InMemoryMonitoring::with_faults(faults.clone());
+        let id = ExecutionId::from_string("exec-1".to_string());
+        
+        faults.fail_next("store_result", 2);
+        assert!(manager.store_result(test_result("exec-1", "tool-a", true)).await.is_err());
+        assert!(manager.store_result(test_result("exec-1", "tool-a", true)).await.is_err());
+        manager.store_result(test_result("exec-1", "tool-a", true)).await.unwrap();
+        assert_eq!(faults.calls("store_result"), 3);
+        
+        faults.fail_always("record_execution_start");
+        assert!(matches!(
+            monitoring.record_execution_start(&id).await,
+            Err(MonitoringError::MetricsCollectionFailed(_))
+        ));
+        
+        faults.clear();
+        faults.set_latency(std::time::Duration::from_millis(20));
+        let started = std::time::Instant::now();
+        monitoring.record_execution_start(&id).await.unwrap();
+        manager.get_result(&id).await.unwrap();
+        assert!(started.elapsed() >= std::time::Duration::from_millis(40));
+    }
+    
+    #[tokio::test]
+    async fn test_executor_on_in_memory_backends() {
+        let faults = SimulatedFaults::new();
+        let store = Arc::new(InMemoryExecutionStore::with_faults(faults.clone()));
+        let registry = Arc::new(stepflow_registry::InMemoryRegistry::with_faults(faults.clone()));
+        let executor = create_executor_with_backends(
+            store.clone(),
+            Arc::new(InMemoryResultManager::with_faults(faults.clone())),
+            Arc::new(InMemoryMonitoring::with_faults(faults.clone())),
+            registry.clone(),
+            None,
+            None,
+        ).unwrap();
+        
+        let tool_id = ToolId::from_string("echo".to_string());
+        let tool = ToolInfo::builder()
+            .id(tool_id.clone())
+            .name("echo")
+            .description("Echo tool")
+            .tool_type(ToolType::Python)
+            .author("test-author")
+            .env("LOG_LEVEL", "info")
+            .build()
+            .unwrap();
+        stepflow_registry::Registry::register_tool(registry.as_ref(), tool).await.unwrap();
+        let tenant_id = TenantId::from_string("test-tenant".to_string());
+        store.insert_tool_config(&tenant_id, ToolConfig {
+            tool_id: tool_id.clone(),
+            configuration: std::collections::HashMap::from([("region".to_string(), serde_json::json!("${REGION:-eu}"))]),
+            environment: std::collections::HashMap::from([("API_TOKEN".to_string(), "${secret:token}".to_string())]),
+            secrets: std::collections::HashMap::from([("token".to_string(), "s3cr3t".to_string())]),
+            timeout: None,
+            retries: None,
+            enabled: true,
+        }).await;
+        
+        let request = ExecutionRequest {
+            tool_id: tool_id.clone(),
+            version: None,
+            parameters: std::collections::HashMap::new(),
+            context: ExecutionContext {
+                user_id: "test-user".to_string(),
+                tenant_id: tenant_id.to_string(),
+                session_id: "test-session".to_string(),
+                request_id: "test-request".to_string(),
+                parent_execution_id: None,
+                environment: std::collections::HashMap::new(),
+                labels: std::collections::HashMap::new(),
+                note: None,
+            },
+            options: ExecutionOptions::default(),
+        };
+        
+        let result = executor.execute_tool(request.clone()).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.metadata["configuration_keys"], serde_json::json!(["region"]));
+        assert_eq!(
+            result.metadata["environment_keys"],
+            serde_json::json!(["API_TOKEN", "LOG_LEVEL", "STEPFLOW_EXECUTION_ID", "STEPFLOW_TENANT"])
+        );
+        let execution_id = ExecutionId::from_string(result.metadata["execution_id"].as_str().unwrap().to_string());
+        let timeline = executor.get_execution_timeline(&execution_id).await.unwrap().unwrap();
+        assert_eq!(timeline.current_state(), Some(ExecutionState::Completed));
+        assert_eq!(timeline.tenant_id.as_deref(), Some("test-tenant"));
+        let executions = executor.list_executions(None).await.unwrap();
+        assert_eq!(executions.len(), 1);
+        assert_eq!(executions[0].status, ExecutionStatus::Completed);
+        
+        // Store failures surface as database errors
+        faults.fail_next("get_tenant", 1);
+        assert!(matches!(executor.execute_tool(request.clone()).await, Err(ExecutorError::DatabaseError(_))));
+        
+        // A lost timeline write doesn't fail the execution
+        faults.fail_always("record_event");
+        assert!(executor.execute_tool(request.clone()).await.is_ok());
+        faults.clear();
+        
+        // Configured environment layers are subject to the environment policy too
+        store.insert_tool_config(&tenant_id, ToolConfig {
+            tool_id: tool_id.clone(),
+            configuration: std::collections::HashMap::new(),
+            environment: std::collections::HashMap::from([("LD_PRELOAD".to_string(), "/tmp/hook.so".to_string())]),
+            secrets: std::collections::HashMap::new(),
+            timeout: None,
+            retries: None,
+            enabled: true,
+        }).await;
+        assert!(matches!(
+            executor.execute_tool(request.clone()).await,
+            Err(ExecutorError::EnvironmentPolicyViolation(_))
+        ));
+        assert!(matches!(
+            executor.execute_tool_async(request.clone()).await,
+            Err(ExecutorError::EnvironmentPolicyViolation(_))
+        ));
+        
+        faults.fail_always("get_tool");
+        assert!(matches!(executor.execute_tool(request).await, Err(ExecutorError::ToolNotFound(_))));
+    }
+    
+    #[tokio::test]
+    async fn test_shared_task_queue_claims() {
+        let db = Arc::new(stepflow_database::SqliteDatabase::new("sqlite::memory:").await.unwrap());
+        stepflow_database::MigrationManager::run_migrations(&db).await.unwrap();
+        let store = SqliteExecutionStore::new(db.clone());
+        
+        let mut tasks = Vec::new();
+        for priority in [Priority::Low, Priority::High, Priority::Normal] {
+            let task = Task {
+                id: TaskId::new(),
+                execution_request: ExecutionRequest {
+                    tool_id: ToolId::from_string("test-tool".to_string()),
+                    version: None,
+                    parameters: std::collections::HashMap::new(),
+                    context: ExecutionContext {
+                        user_id: "test-user".to_string(),
+                        tenant_id: "test-tenant".to_string(),
+                        session_id: "test-session".to_string(),
+                        request_id: "test-request".to_string(),
+                        parent_execution_id: None,
+                        environment: std::collections::HashMap::new(),
+                        labels: std::collections::HashMap::new(),
+                        note: None,
+                    },
+                    options: ExecutionOptions::default(),
+                },
+                priority,
+                created_at: chrono::Utc::now(),
+                scheduled_at: None,
+            };
+            store.save_task(&task).await.unwrap();
+            store.update_task_status(&task.id, TaskStatus::Queued).await.unwrap();
+            tasks.push(task);
+        }
+        let lease = || chrono::Utc::now() + chrono::Duration::seconds(30);
+        
+        // Highest priority first, and a task is only claimed once
+        let first = store.claim_tasks("first", 1, lease()).await.unwrap();
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].id, tasks[1].id);
+        let second = store.claim_tasks("second", 5, lease()).await.unwrap();
+        assert_eq!(second.iter().map(|t| t.id.clone()).collect::<Vec<_>>(), vec![tasks[2].id.clone(), tasks[0].id.clone()]);
+        assert!(store.claim_tasks("third", 5, lease()).await.unwrap().is_empty());
+        let running = store.running_tasks_by_instance().await.unwrap();
+        assert_eq!(running.get("first"), Some(&1));
+        assert_eq!(running.get("second"), Some(&2));
+        
+        // A released task goes back to the queue
+        store.release_task("second", &tasks[0].id).await.unwrap();
+        let third = store.claim_tasks("third", 5, lease()).await.unwrap();
+        assert_eq!(third.len(), 1);
+        assert_eq!(third[0].id, tasks[0].id);
+        
+        // Tasks of an instance that stopped heartbeating are claimed again and its
+        // renewal reports them lost
+        stepflow_core::Database::execute(db.as_ref(),
+            "UPDATE tasks SET lease_until = ? WHERE claimed_by = 'first'",
+            &[serde_json::json!((chrono::Utc::now() - chrono::Duration::seconds(1)).to_rfc3339())],
+        ).await.unwrap();
+        let taken = store.claim_tasks("second", 5, lease()).await.unwrap();
+        assert_eq!(taken.len(), 1);
+        assert_eq!(taken[0].id, tasks[1].id);
+        let lost = store.renew_task_leases("first", &[tasks[1].id.clone()], lease()).await.unwrap();
+        assert_eq!(lost, vec![tasks[1].id.clone()]);
+        assert!(store.renew_task_leases("second", &[tasks[1].id.clone(), tasks[2].id.clone()], lease()).await.unwrap().is_empty());
+        
+        store.update_task_status(&tasks[1].id, TaskStatus::Completed).await.unwrap();
+        let running = store.running_tasks_by_instance().await.unwrap();
+        assert_eq!(running.get("second"), Some(&1));
+        assert!(!running.contains_key("first"));
+    }
+}
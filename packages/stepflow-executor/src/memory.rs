@@ -0,0 +1,564 @@
+//! In-memory result storage, monitoring and execution store
+//!
+//! HashMap-backed implementations of [`ResultManager`], [`Monitoring`] and
+//! [`ExecutionStore`] for unit tests that should not need SQLite. Together with
+//! `stepflow_registry::InMemoryRegistry` they back a complete executor. All of
+//! them accept a [`SimulatedFaults`] handle that makes chosen operations fail or
+//! slow down deterministically.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use stepflow_core::*;
+use tokio::sync::RwLock;
+
+use crate::annotations::{ExecutionAnnotations, ExecutionNote};
+use crate::errors::*;
+use crate::execution_context::*;
+use crate::executor::{ExecutionStore, Monitoring, ResultManager};
+use crate::rollout::{RolloutArm, RolloutStatus, ToolRollout};
+use crate::tool_tests::ToolTestRun;
+use crate::timeline::{ExecutionEvent, ExecutionState, ExecutionTimeline};
+
+/// In-memory result manager
+///
+/// Results are keyed by the `execution_id` entry of their metadata; results
+/// without one get a fresh id, like [`crate::ResultManagerImpl`].
+#[derive(Default)]
+pub struct InMemoryResultManager {
+    results: RwLock<HashMap<ExecutionId, (ExecutionResult, DateTime<Utc>)>>,
+    faults: SimulatedFaults,
+}
+
+impl InMemoryResultManager {
+    /// Create an empty result manager
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an empty result manager driven by `faults`
+    pub fn with_faults(faults: SimulatedFaults) -> Self {
+        Self {
+            results: RwLock::new(HashMap::new()),
+            faults,
+        }
+    }
+
+    async fn check(&self, operation: &str) -> ExecutorResult<()> {
+        self.faults.check(operation).await.map_err(ExecutorError::DatabaseError)
+    }
+}
+
+fn metadata_str<'a>(result: &'a ExecutionResult, key: &str) -> Option<&'a str> {
+    result.metadata.get(key).and_then(|v| v.as_str())
+}
+
+#[async_trait::async_trait]
+impl ResultManager for InMemoryResultManager {
+    async fn store_result(&self, result: ExecutionResult) -> ExecutorResult<()> {
+        self.check("store_result").await?;
+        let execution_id = metadata_str(&result, "execution_id")
+            .map(|id| ExecutionId::from_string(id.to_string()))
+            .unwrap_or_else(ExecutionId::new);
+        self.results.write().await.insert(execution_id, (result, Utc::now()));
+        Ok(())
+    }
+
+    async fn get_result(&self, execution_id: &ExecutionId) -> ExecutorResult<ExecutionResult> {
+        self.check("get_result").await?;
+        self.results
+            .read()
+            .await
+            .get(execution_id)
+            .map(|(result, _)| result.clone())
+            .ok_or_else(|| ExecutorError::InternalError(format!("Execution result not found: {}", execution_id)))
+    }
+
+    async fn delete_result(&self, execution_id: &ExecutionId) -> ExecutorResult<()> {
+        self.check("delete_result").await?;
+        self.results.write().await.remove(execution_id);
+        Ok(())
+    }
+
+    async fn list_results(&self, filter: Option<ExecutionFilter>) -> ExecutorResult<Vec<ExecutionResult>> {
+        self.check("list_results").await?;
+        let results = self.results.read().await;
+        let matches = |result: &ExecutionResult, stored_at: &DateTime<Utc>| {
+            let Some(filter) = &filter else {
+                return true;
+            };
+            filter.tool_id.as_ref().is_none_or(|id| metadata_str(result, "tool_id") == Some(id.as_str()))
+                && filter.user_id.as_ref().is_none_or(|id| metadata_str(result, "user_id") == Some(id.as_str()))
+                && filter.tenant_id.as_ref().is_none_or(|id| metadata_str(result, "tenant_id") == Some(id.as_str()))
+                && filter.status.as_ref().is_none_or(|status| result.success == (*status == ExecutionStatus::Completed))
+                && filter.started_after.is_none_or(|after| *stored_at >= after)
+                && filter.started_before.is_none_or(|before| *stored_at <= before)
+        };
+        Ok(results
+            .values()
+            .filter(|(result, stored_at)| matches(result, stored_at))
+            .map(|(result, _)| result.clone())
+            .collect())
+    }
+
+    async fn cleanup_results(&self, older_than: DateTime<Utc>) -> ExecutorResult<u64> {
+        self.check("cleanup_results").await?;
+        let mut results = self.results.write().await;
+        let before = results.len();
+        results.retain(|_, (_, stored_at)| *stored_at >= older_than);
+        Ok((before - results.len()) as u64)
+    }
+}
+
+/// In-memory monitoring
+#[derive(Default)]
+pub struct InMemoryMonitoring {
+    metrics: RwLock<HashMap<ExecutionId, Vec<Metric>>>,
+    faults: SimulatedFaults,
+}
+
+impl InMemoryMonitoring {
+    /// Create an empty monitoring store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an empty monitoring store driven by `faults`
+    pub fn with_faults(faults: SimulatedFaults) -> Self {
+        Self {
+            metrics: RwLock::new(HashMap::new()),
+            faults,
+        }
+    }
+
+    async fn check(&self, operation: &str) -> MonitoringResult<()> {
+        self.faults.check(operation).await.map_err(MonitoringError::MetricsCollectionFailed)
+    }
+
+    async fn push(&self, execution_id: &ExecutionId, name: &str, value: f64, labels: HashMap<String, String>) {
+        let metric = Metric {
+            name: name.to_string(),
+            value,
+            labels,
+            timestamp: Utc::now(),
+        };
+        self.metrics.write().await.entry(execution_id.clone()).or_default().push(metric);
+    }
+}
+
+#[async_trait::async_trait]
+impl Monitoring for InMemoryMonitoring {
+    async fn record_execution_start(&self, execution_id: &ExecutionId) -> MonitoringResult<()> {
+        self.check("record_execution_start").await?;
+        self.push(execution_id, "execution_start", 1.0, HashMap::new()).await;
+        Ok(())
+    }
+
+    async fn record_execution_end(&self, execution_id: &ExecutionId, result: &ExecutionResult) -> MonitoringResult<()> {
+        self.check("record_execution_end").await?;
+        let status = if result.success { "success" } else { "failure" };
+        let labels = HashMap::from([("status".to_string(), status.to_string())]);
+        self.push(execution_id, "execution_end", 1.0, labels).await;
+        for (name, value) in &result.metrics {
+            self.push(execution_id, name, *value, HashMap::new()).await;
+        }
+        Ok(())
+    }
+
+    async fn record_metric(&self, execution_id: &ExecutionId, metric: Metric) -> MonitoringResult<()> {
+        self.check("record_metric").await?;
+        self.metrics.write().await.entry(execution_id.clone()).or_default().push(metric);
+        Ok(())
+    }
+
+    async fn get_metrics(&self, filter: Option<MetricFilter>) -> MonitoringResult<Vec<Metric>> {
+        self.check("get_metrics").await?;
+        let metrics = self.metrics.read().await;
+        let matches = |metric: &Metric| {
+            let Some(filter) = &filter else {
+                return true;
+            };
+            filter.name.as_ref().is_none_or(|name| metric.name == *name)
+                && filter.labels.iter().all(|(key, value)| metric.labels.get(key) == Some(value))
+                && filter.start_time.is_none_or(|start| metric.timestamp >= start)
+                && filter.end_time.is_none_or(|end| metric.timestamp <= end)
+        };
+        Ok(metrics.values().flatten().filter(|metric| matches(metric)).cloned().collect())
+    }
+
+    async fn get_execution_metrics(&self, execution_id: &ExecutionId) -> MonitoringResult<Vec<Metric>> {
+        self.check("get_execution_metrics").await?;
+        Ok(self.metrics.read().await.get(execution_id).cloned().unwrap_or_default())
+    }
+}
+
+/// In-memory execution store
+///
+/// Executions are listed from their recorded transitions: the first event
+/// creates the entry and later ones move its status and timestamps along.
+#[derive(Default)]
+pub struct InMemoryExecutionStore {
+    tenants: RwLock<HashMap<TenantId, TenantInfo>>,
+    tool_configs: RwLock<HashMap<(TenantId, ToolId), ToolConfig>>,
+    events: RwLock<HashMap<ExecutionId, Vec<ExecutionEvent>>>,
+    executions: RwLock<HashMap<ExecutionId, ExecutionInfo>>,
+    labels: RwLock<HashMap<ExecutionId, HashMap<String, String>>>,
+    notes: RwLock<HashMap<ExecutionId, Vec<ExecutionNote>>>,
+    results: RwLock<HashMap<ExecutionId, ExecutionResult>>,
+    tasks: RwLock<HashMap<TaskId, (Task, TaskStatus)>>,
+    /// Holder and expiry of the lease on each claimed task
+    task_leases: RwLock<HashMap<TaskId, (String, DateTime<Utc>)>>,
+    /// Rollouts in creation order
+    rollouts: RwLock<Vec<ToolRollout>>,
+    /// Tool test runs in the order they finished
+    test_runs: RwLock<Vec<ToolTestRun>>,
+    faults: SimulatedFaults,
+}
+
+impl InMemoryExecutionStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an empty store driven by `faults`
+    pub fn with_faults(faults: SimulatedFaults) -> Self {
+        Self {
+            faults,
+            ..Self::default()
+        }
+    }
+
+    /// Add or replace a tenant
+    pub async fn insert_tenant(&self, tenant: TenantInfo) {
+        self.tenants.write().await.insert(tenant.id.clone(), tenant);
+    }
+
+    /// Add or replace a tenant's configuration for a tool
+    pub async fn insert_tool_config(&self, tenant_id: &TenantId, config: ToolConfig) {
+        self.tool_configs.write().await.insert((tenant_id.clone(), config.tool_id.clone()), config);
+    }
+
+    /// Status of a persisted task
+    pub async fn task_status(&self, task_id: &TaskId) -> Option<TaskStatus> {
+        self.tasks.read().await.get(task_id).map(|(_, status)| *status)
+    }
+
+    async fn check(&self, operation: &str) -> ExecutorResult<()> {
+        self.faults.check(operation).await.map_err(ExecutorError::DatabaseError)
+    }
+}
+
+/// Execution status reflected by a timeline state
+fn execution_status(state: ExecutionState) -> ExecutionStatus {
+    match state {
+        ExecutionState::Queued | ExecutionState::Scheduled => ExecutionStatus::Pending,
+        ExecutionState::Running | ExecutionState::Retrying => ExecutionStatus::Running,
+        ExecutionState::Completed => ExecutionStatus::Completed,
+        ExecutionState::Failed => ExecutionStatus::Failed,
+        ExecutionState::Cancelled => ExecutionStatus::Cancelled,
+        ExecutionState::TimedOut => ExecutionStatus::Timeout,
+    }
+}
+
+#[async_trait::async_trait]
+impl ExecutionStore for InMemoryExecutionStore {
+    async fn get_tenant(&self, tenant_id: &TenantId) -> ExecutorResult<Option<TenantInfo>> {
+        self.check("get_tenant").await?;
+        Ok(self.tenants.read().await.get(tenant_id).cloned())
+    }
+
+    async fn get_tool_config(&self, tenant_id: &TenantId, tool_id: &ToolId) -> ExecutorResult<Option<ToolConfig>> {
+        self.check("get_tool_config").await?;
+        Ok(self.tool_configs.read().await.get(&(tenant_id.clone(), tool_id.clone())).cloned())
+    }
+
+    async fn record_event(
+        &self,
+        execution_id: &ExecutionId,
+        tool_id: Option<&ToolId>,
+        tenant_id: Option<&str>,
+        state: ExecutionState,
+        worker_id: Option<&str>,
+        detail: Option<&str>,
+    ) -> ExecutorResult<()> {
+        self.check("record_event").await?;
+        let now = Utc::now();
+        self.events.write().await.entry(execution_id.clone()).or_default().push(ExecutionEvent {
+            state,
+            worker_id: worker_id.map(str::to_string),
+            detail: detail.map(str::to_string),
+            occurred_at: now,
+        });
+
+        let mut executions = self.executions.write().await;
+        let execution = executions.entry(execution_id.clone()).or_insert_with(|| ExecutionInfo {
+            execution_id: execution_id.clone(),
+            tool_id: tool_id.cloned().unwrap_or_else(|| ToolId::from_string(String::new())),
+            status: ExecutionStatus::Pending,
+            created_at: now,
+            started_at: None,
+            completed_at: None,
+            user_id: String::new(),
+            tenant_id: tenant_id.unwrap_or_default().to_string(),
+            labels: HashMap::new(),
+        });
+        execution.status = execution_status(state);
+        if state == ExecutionState::Running && execution.started_at.is_none() {
+            execution.started_at = Some(now);
+        }
+        if state.is_terminal() {
+            execution.completed_at = Some(now);
+        }
+        Ok(())
+    }
+
+    async fn timeline(&self, execution_id: &ExecutionId) -> ExecutorResult<Option<ExecutionTimeline>> {
+        self.check("timeline").await?;
+        let Some(events) = self.events.read().await.get(execution_id).cloned() else {
+            return Ok(None);
+        };
+        let tenant_id = self.executions
+            .read()
+            .await
+            .get(execution_id)
+            .map(|execution| execution.tenant_id.clone())
+            .filter(|tenant_id| !tenant_id.is_empty());
+        Ok(Some(ExecutionTimeline::from_events(execution_id.clone(), tenant_id, events)))
+    }
+
+    async fn store_execution_result(&self, execution_id: &ExecutionId, result: &ExecutionResult) -> ExecutorResult<()> {
+        self.check("store_execution_result").await?;
+        self.results.write().await.insert(execution_id.clone(), result.clone());
+        Ok(())
+    }
+
+    async fn has_execution_result(&self, execution_id: &ExecutionId) -> ExecutorResult<bool> {
+        self.check("has_execution_result").await?;
+        Ok(self.results.read().await.contains_key(execution_id))
+    }
+
+    async fn list_executions(&self, filter: Option<ExecutionFilter>) -> ExecutorResult<Vec<ExecutionInfo>> {
+        self.check("list_executions").await?;
+        let labels = self.labels.read().await;
+        let executions: Vec<ExecutionInfo> = self.executions
+            .read()
+            .await
+            .values()
+            .map(|execution| ExecutionInfo {
+                labels: labels.get(&execution.execution_id).cloned().unwrap_or_default(),
+                ..execution.clone()
+            })
+            .collect();
+        let matches = |execution: &ExecutionInfo| {
+            let Some(filter) = &filter else {
+                return true;
+            };
+            filter.tool_id.as_ref().is_none_or(|id| execution.tool_id == *id)
+                && filter.status.as_ref().is_none_or(|status| execution.status == *status)
+                && filter.user_id.as_ref().is_none_or(|id| execution.user_id == id.as_str())
+                && filter.tenant_id.as_ref().is_none_or(|id| execution.tenant_id == id.as_str())
+                && filter.started_after.is_none_or(|after| execution.started_at.is_some_and(|at| at >= after))
+                && filter.started_before.is_none_or(|before| execution.started_at.is_some_and(|at| at <= before))
+                && filter.labels.iter().all(|(key, value)| execution.labels.get(key) == Some(value))
+        };
+        let mut listed: Vec<ExecutionInfo> = executions.into_iter().filter(|execution| matches(execution)).collect();
+        listed.sort_by_key(|execution| execution.created_at);
+        Ok(listed)
+    }
+
+    async fn set_labels(&self, execution_id: &ExecutionId, _tenant_id: &str, labels: &HashMap<String, String>) -> ExecutorResult<()> {
+        self.check("set_labels").await?;
+        self.labels
+            .write()
+            .await
+            .entry(execution_id.clone())
+            .or_default()
+            .extend(labels.clone());
+        Ok(())
+    }
+
+    async fn remove_label(&self, execution_id: &ExecutionId, key: &str) -> ExecutorResult<bool> {
+        self.check("remove_label").await?;
+        Ok(self.labels
+            .write()
+            .await
+            .get_mut(execution_id)
+            .is_some_and(|labels| labels.remove(key).is_some()))
+    }
+
+    async fn add_note(&self, _tenant_id: &str, note: &ExecutionNote) -> ExecutorResult<()> {
+        self.check("add_note").await?;
+        self.notes.write().await.entry(note.execution_id.clone()).or_default().push(note.clone());
+        Ok(())
+    }
+
+    async fn annotations(&self, execution_id: &ExecutionId) -> ExecutorResult<ExecutionAnnotations> {
+        self.check("annotations").await?;
+        Ok(ExecutionAnnotations {
+            labels: self.labels.read().await.get(execution_id).cloned().unwrap_or_default(),
+            notes: self.notes.read().await.get(execution_id).cloned().unwrap_or_default(),
+        })
+    }
+
+    async fn save_task(&self, task: &Task) -> ExecutorResult<()> {
+        self.check("save_task").await?;
+        self.tasks.write().await.insert(task.id.clone(), (task.clone(), TaskStatus::Pending));
+        Ok(())
+    }
+
+    async fn update_task_status(&self, task_id: &TaskId, status: TaskStatus) -> ExecutorResult<()> {
+        self.check("update_task_status").await?;
+        if let Some((_, stored)) = self.tasks.write().await.get_mut(task_id) {
+            *stored = status;
+        }
+        Ok(())
+    }
+
+    async fn claim_tasks(&self, instance_id: &str, limit: usize, lease_until: DateTime<Utc>) -> ExecutorResult<Vec<Task>> {
+        self.check("claim_tasks").await?;
+        let now = Utc::now();
+        let mut tasks = self.tasks.write().await;
+        let mut leases = self.task_leases.write().await;
+        let mut claimable: Vec<&mut (Task, TaskStatus)> = tasks.values_mut()
+            .filter(|(task, status)| match status {
+                TaskStatus::Queued => true,
+                TaskStatus::Running => leases.get(&task.id).is_some_and(|(_, until)| *until < now),
+                _ => false,
+            })
+            .collect();
+        claimable.sort_by(|(a, _), (b, _)| b.priority.cmp(&a.priority).then_with(|| a.created_at.cmp(&b.created_at)));
+
+        Ok(claimable.into_iter().take(limit).map(|(task, status)| {
+            *status = TaskStatus::Running;
+            leases.insert(task.id.clone(), (instance_id.to_string(), lease_until));
+            task.clone()
+        }).collect())
+    }
+
+    async fn renew_task_leases(&self, instance_id: &str, task_ids: &[TaskId], lease_until: DateTime<Utc>) -> ExecutorResult<Vec<TaskId>> {
+        self.check("renew_task_leases").await?;
+        let tasks = self.tasks.read().await;
+        let mut leases = self.task_leases.write().await;
+        let mut lost = Vec::new();
+        for task_id in task_ids {
+            let running = tasks.get(task_id).is_some_and(|(_, status)| *status == TaskStatus::Running);
+            match leases.get_mut(task_id) {
+                Some((holder, until)) if running && holder == instance_id => *until = lease_until,
+                _ => lost.push(task_id.clone()),
+            }
+        }
+        Ok(lost)
+    }
+
+    async fn release_task(&self, instance_id: &str, task_id: &TaskId) -> ExecutorResult<()> {
+        self.check("release_task").await?;
+        let mut tasks = self.tasks.write().await;
+        let mut leases = self.task_leases.write().await;
+        if leases.get(task_id).is_some_and(|(holder, _)| holder == instance_id) {
+            leases.remove(task_id);
+            if let Some((_, status)) = tasks.get_mut(task_id).filter(|(_, status)| *status == TaskStatus::Running) {
+                *status = TaskStatus::Queued;
+            }
+        }
+        Ok(())
+    }
+
+    async fn running_tasks_by_instance(&self) -> ExecutorResult<HashMap<String, usize>> {
+        self.check("running_tasks_by_instance").await?;
+        let now = Utc::now();
+        let tasks = self.tasks.read().await;
+        let mut counts = HashMap::new();
+        for (task_id, (holder, until)) in self.task_leases.read().await.iter() {
+            let running = tasks.get(task_id).is_some_and(|(_, status)| *status == TaskStatus::Running);
+            if running && *until >= now {
+                *counts.entry(holder.clone()).or_insert(0) += 1;
+            }
+        }
+        Ok(counts)
+    }
+
+    async fn latest_rollout(&self, tool_id: &ToolId) -> ExecutorResult<Option<ToolRollout>> {
+        self.check("latest_rollout").await?;
+        Ok(self.rollouts.read().await.iter().rev().find(|rollout| rollout.tool_id == *tool_id).cloned())
+    }
+
+    async fn save_rollout(&self, rollout: &ToolRollout) -> ExecutorResult<()> {
+        self.check("save_rollout").await?;
+        let mut rollouts = self.rollouts.write().await;
+        match rollouts.iter_mut().find(|stored| stored.id == rollout.id) {
+            // Outcome counters are only ever moved by record_rollout_outcome
+            Some(stored) => {
+                stored.percentage = rollout.percentage;
+                stored.status = rollout.status;
+                stored.decision_reason = rollout.decision_reason.clone();
+                stored.updated_at = rollout.updated_at;
+            }
+            None => rollouts.push(rollout.clone()),
+        }
+        Ok(())
+    }
+
+    async fn record_rollout_outcome(
+        &self,
+        rollout_id: &str,
+        arm: RolloutArm,
+        duration_ms: u64,
+        success: bool,
+    ) -> ExecutorResult<Option<ToolRollout>> {
+        self.check("record_rollout_outcome").await?;
+        let mut rollouts = self.rollouts.write().await;
+        let Some(rollout) = rollouts
+            .iter_mut()
+            .find(|rollout| rollout.id == rollout_id && rollout.status == RolloutStatus::Active)
+        else {
+            return Ok(None);
+        };
+        let stats = match arm {
+            RolloutArm::Stable => &mut rollout.stable,
+            RolloutArm::Candidate => &mut rollout.candidate_stats,
+        };
+        stats.executions += 1;
+        stats.failures += u64::from(!success);
+        stats.total_duration_ms += duration_ms;
+        rollout.updated_at = Utc::now();
+        Ok(Some(rollout.clone()))
+    }
+
+    async fn finish_rollout(&self, rollout_id: &str, status: RolloutStatus, reason: &str) -> ExecutorResult<bool> {
+        self.check("finish_rollout").await?;
+        let mut rollouts = self.rollouts.write().await;
+        let Some(rollout) = rollouts
+            .iter_mut()
+            .find(|rollout| rollout.id == rollout_id && rollout.status == RolloutStatus::Active)
+        else {
+            return Ok(false);
+        };
+        rollout.status = status;
+        rollout.decision_reason = Some(reason.to_string());
+        rollout.updated_at = Utc::now();
+        Ok(true)
+    }
+
+    async fn save_test_run(&self, run: &ToolTestRun) -> ExecutorResult<()> {
+        self.check("save_test_run").await?;
+        self.test_runs.write().await.push(run.clone());
+        Ok(())
+    }
+
+    async fn latest_test_run(&self, tool_id: &ToolId, version: &ToolVersion) -> ExecutorResult<Option<ToolTestRun>> {
+        self.check("latest_test_run").await?;
+        Ok(self.test_runs.read().await.iter().rev()
+            .find(|run| run.tool_id == *tool_id && run.version == *version)
+            .cloned())
+    }
+
+    async fn list_test_runs(&self, tool_id: &ToolId, limit: usize) -> ExecutorResult<Vec<ToolTestRun>> {
+        self.check("list_test_runs").await?;
+        Ok(self.test_runs.read().await.iter().rev()
+            .filter(|run| run.tool_id == *tool_id)
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+}
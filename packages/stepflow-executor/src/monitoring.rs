@@ -5,7 +5,7 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 use chrono::{DateTime, Utc};
 use stepflow_core::*;
-use stepflow_database::SqliteDatabase;
+use stepflow_database::{LogRepository, SqliteDatabase};
 use crate::errors::*;
 use crate::executor::Monitoring;
 
@@ -57,25 +57,27 @@ impl MonitoringImpl {
         Ok(())
     }
     
-    /// Store log in database
-    async fn store_log_in_db(&self, execution_id: &ExecutionId, log: &LogEntry) -> MonitoringResult<()> {
-        let metadata_json = serde_json::to_string(&log.metadata)
+    /// Store the logs of an execution in one batch
+    async fn store_logs_in_db(&self, execution_id: &ExecutionId, logs: &[LogEntry]) -> MonitoringResult<()> {
+        if logs.is_empty() {
+            return Ok(());
+        }
+
+        let records = logs.iter().map(|log| (execution_id.to_string(), log.clone())).collect::<Vec<_>>();
+        let report = LogRepository::new(self.db.as_ref().clone())
+            .insert_logs_batch(&records)
+            .await
             .map_err(|e| MonitoringError::LoggingFailed(e.to_string()))?;
-        
-        let params = vec![
-            serde_json::Value::String(execution_id.to_string()),
-            serde_json::Value::String(format!("{:?}", log.level)),
-            serde_json::Value::String(log.message.clone()),
-            serde_json::Value::String(log.timestamp.to_rfc3339()),
-            serde_json::Value::String(log.source.clone()),
-            serde_json::Value::String(metadata_json),
-        ];
-        
-        self.db.execute(
-            "INSERT INTO logs (execution_id, level, message, timestamp, source, metadata) VALUES (?, ?, ?, ?, ?, ?)",
-            &params,
-        ).await.map_err(|e| MonitoringError::LoggingFailed(e.to_string()))?;
-        
+
+        if let Some(failure) = report.failures.first() {
+            return Err(MonitoringError::LoggingFailed(format!(
+                "{} of {} log entries were not stored, first at index {}: {}",
+                report.failures.len(),
+                logs.len(),
+                failure.index,
+                failure.error
+            )));
+        }
         Ok(())
     }
     
@@ -180,9 +182,7 @@ impl MonitoringImpl {
         self.record_metric(execution_id, end_metric).await?;
         
         // Record log entries if available
-        for log in &result.logs {
-            self.store_log_in_db(execution_id, log).await?;
-        }
+        self.store_logs_in_db(execution_id, &result.logs).await?;
         
         Ok(())
     }
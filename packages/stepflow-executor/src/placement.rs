@@ -0,0 +1,166 @@
+//! Matching executions to worker capabilities and cached artifacts
+
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+use stepflow_core::EnvironmentLabel;
+use crate::execution_context::ExecutionOptions;
+
+/// What a worker advertises to the scheduler
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkerProfile {
+    /// Only work labeled with the same environment runs on the worker
+    pub environment_label: Option<EnvironmentLabel>,
+    /// Capabilities such as `python3.11`, `gpu`, `docker` or `region=eu`
+    pub capabilities: Vec<String>,
+}
+
+impl WorkerProfile {
+    pub fn new(environment_label: Option<EnvironmentLabel>, capabilities: Vec<String>) -> Self {
+        Self { environment_label, capabilities }
+    }
+
+    /// Constraints of `options` this worker does not meet
+    pub fn unmet(&self, options: &ExecutionOptions) -> Vec<String> {
+        let mut unmet = Vec::new();
+        if self.environment_label != options.environment_label {
+            unmet.push(environment_constraint(options.environment_label));
+        }
+        unmet.extend(
+            options.requirements.capabilities.iter()
+                .filter(|capability| !self.capabilities.contains(capability))
+                .cloned(),
+        );
+        unmet
+    }
+
+    /// Whether the worker meets every hard constraint of `options`
+    pub fn satisfies(&self, options: &ExecutionOptions) -> bool {
+        self.unmet(options).is_empty()
+    }
+
+    /// Number of preferred capabilities of `options` the worker has
+    pub fn affinity(&self, options: &ExecutionOptions) -> usize {
+        options.requirements.preferred.iter()
+            .filter(|capability| self.capabilities.contains(capability))
+            .count()
+    }
+}
+
+/// An artifact an execution reads, such as the output of a prior workflow step
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArtifactHint {
+    /// Content digest or other stable identifier of the artifact
+    pub artifact_id: String,
+    pub size_bytes: u64,
+}
+
+impl ArtifactHint {
+    pub fn new(artifact_id: impl Into<String>, size_bytes: u64) -> Self {
+        Self { artifact_id: artifact_id.into(), size_bytes }
+    }
+}
+
+/// Artifacts a worker holds locally, evicting the least recently used past its byte budget
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArtifactCache {
+    capacity_bytes: u64,
+    /// Least recently used first
+    entries: VecDeque<ArtifactHint>,
+    used_bytes: u64,
+}
+
+impl ArtifactCache {
+    pub fn new(capacity_bytes: u64) -> Self {
+        Self { capacity_bytes, entries: VecDeque::new(), used_bytes: 0 }
+    }
+
+    pub fn contains(&self, artifact_id: &str) -> bool {
+        self.entries.iter().any(|entry| entry.artifact_id == artifact_id)
+    }
+
+    /// Record that the worker holds `artifact`, returning whether it already did
+    ///
+    /// Artifacts larger than the whole budget are not cached.
+    pub fn insert(&mut self, artifact: &ArtifactHint) -> bool {
+        if let Some(index) = self.entries.iter().position(|entry| entry.artifact_id == artifact.artifact_id) {
+            let entry = self.entries.remove(index).expect("index is in bounds");
+            self.entries.push_back(entry);
+            return true;
+        }
+        if artifact.size_bytes > self.capacity_bytes {
+            return false;
+        }
+        while self.used_bytes + artifact.size_bytes > self.capacity_bytes {
+            let Some(evicted) = self.entries.pop_front() else { break };
+            self.used_bytes -= evicted.size_bytes;
+        }
+        self.used_bytes += artifact.size_bytes;
+        self.entries.push_back(artifact.clone());
+        false
+    }
+
+    /// Forget a cached artifact, returning whether it was cached
+    pub fn remove(&mut self, artifact_id: &str) -> bool {
+        match self.entries.iter().position(|entry| entry.artifact_id == artifact_id) {
+            Some(index) => {
+                let removed = self.entries.remove(index).expect("index is in bounds");
+                self.used_bytes -= removed.size_bytes;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Bytes of the artifacts `options` reads that are already cached
+    pub fn cached_bytes(&self, options: &ExecutionOptions) -> u64 {
+        options.artifacts.iter()
+            .filter(|artifact| self.contains(&artifact.artifact_id))
+            .map(|artifact| artifact.size_bytes)
+            .sum()
+    }
+
+    pub fn used_bytes(&self) -> u64 {
+        self.used_bytes
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// How well a worker suits `options`, compared between workers that satisfy it
+///
+/// Preferred capabilities outrank locality; among equally preferred workers the
+/// one with more bytes of the execution's artifacts cached wins.
+pub fn placement_rank(profile: &WorkerProfile, cache: &ArtifactCache, options: &ExecutionOptions) -> (usize, u64) {
+    (profile.affinity(options), cache.cached_bytes(options))
+}
+
+/// Constraints no worker in `profiles` meets, from the worker that comes closest
+///
+/// Returns `None` when some worker satisfies `options`. Without any workers
+/// every constraint is reported.
+pub fn unmet_constraints<'a>(
+    profiles: impl IntoIterator<Item = &'a WorkerProfile>,
+    options: &ExecutionOptions,
+) -> Option<Vec<String>> {
+    let closest = profiles.into_iter().map(|profile| profile.unmet(options)).min_by_key(Vec::len);
+    match closest {
+        Some(unmet) if unmet.is_empty() => None,
+        Some(unmet) => Some(unmet),
+        None => {
+            let mut all = vec![environment_constraint(options.environment_label)];
+            all.extend(options.requirements.capabilities.iter().cloned());
+            Some(all)
+        }
+    }
+}
+
+fn environment_constraint(label: Option<EnvironmentLabel>) -> String {
+    format!("environment={}", label.map_or("unlabeled", |label| label.as_str()))
+}
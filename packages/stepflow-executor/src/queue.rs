@@ -0,0 +1,339 @@
+//! Shared task queues
+//!
+//! A [`TaskQueue`] hands tasks out to every instance of a deployment under a lease, the
+//! visibility timeout: a task claimed by an instance that stops renewing its lease becomes
+//! claimable again once the lease expires. [`StoreTaskQueue`] keeps the queue in the
+//! `tasks` table of the [`ExecutionStore`]; with the `redis` feature [`RedisTaskQueue`]
+//! keeps it in Redis, taking the polling load off the database. Either way the
+//! execution store stays the record of every task and its status.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use crate::errors::*;
+use crate::execution_context::{Task, TaskId};
+use crate::executor::ExecutionStore;
+
+/// Queue of tasks shared between scheduler instances
+#[async_trait]
+pub trait TaskQueue: Send + Sync {
+    /// Short name of the backend, for logs and metrics
+    fn backend(&self) -> &'static str;
+
+    /// Enqueue a task saved to the execution store with status `Queued`
+    async fn push(&self, task: &Task) -> ExecutorResult<()>;
+
+    /// Claim up to `limit` tasks for `instance_id`, highest priority first, leased until `lease_until`
+    async fn claim(&self, instance_id: &str, limit: usize, lease_until: DateTime<Utc>) -> ExecutorResult<Vec<Task>>;
+
+    /// Extend the leases `instance_id` holds on `task_ids`, returning the tasks whose lease was lost
+    async fn renew(&self, instance_id: &str, task_ids: &[TaskId], lease_until: DateTime<Utc>) -> ExecutorResult<Vec<TaskId>>;
+
+    /// Put a task claimed by `instance_id` back in the queue
+    async fn release(&self, instance_id: &str, task_id: &TaskId) -> ExecutorResult<()>;
+
+    /// Drop a task claimed by `instance_id` from the queue once it finished
+    async fn ack(&self, instance_id: &str, task_id: &TaskId) -> ExecutorResult<()>;
+
+    /// Number of tasks running under an unexpired lease, per instance
+    async fn running_by_instance(&self) -> ExecutorResult<HashMap<String, usize>>;
+}
+
+impl fmt::Debug for dyn TaskQueue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "TaskQueue({})", self.backend())
+    }
+}
+
+/// Queue kept in the `tasks` table of the execution store
+pub struct StoreTaskQueue {
+    store: Arc<dyn ExecutionStore>,
+}
+
+impl StoreTaskQueue {
+    pub fn new(store: Arc<dyn ExecutionStore>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl TaskQueue for StoreTaskQueue {
+    fn backend(&self) -> &'static str {
+        "database"
+    }
+
+    async fn push(&self, _task: &Task) -> ExecutorResult<()> {
+        // The `Queued` status recorded by the scheduler already puts the task in the queue
+        Ok(())
+    }
+
+    async fn claim(&self, instance_id: &str, limit: usize, lease_until: DateTime<Utc>) -> ExecutorResult<Vec<Task>> {
+        self.store.claim_tasks(instance_id, limit, lease_until).await
+    }
+
+    async fn renew(&self, instance_id: &str, task_ids: &[TaskId], lease_until: DateTime<Utc>) -> ExecutorResult<Vec<TaskId>> {
+        self.store.renew_task_leases(instance_id, task_ids, lease_until).await
+    }
+
+    async fn release(&self, instance_id: &str, task_id: &TaskId) -> ExecutorResult<()> {
+        self.store.release_task(instance_id, task_id).await
+    }
+
+    async fn ack(&self, _instance_id: &str, _task_id: &TaskId) -> ExecutorResult<()> {
+        // The final task status recorded by the scheduler takes the task out of the queue
+        Ok(())
+    }
+
+    async fn running_by_instance(&self) -> ExecutorResult<HashMap<String, usize>> {
+        self.store.running_tasks_by_instance().await
+    }
+}
+
+#[cfg(feature = "redis")]
+pub use self::redis_queue::RedisTaskQueue;
+
+#[cfg(feature = "redis")]
+mod redis_queue {
+    use super::*;
+    use redis::aio::ConnectionManager;
+    use redis::Script;
+
+    /// Requeue expired leases, then move the first tasks of the queue to processing
+    const CLAIM: &str = r#"
+        local expired = redis.call('ZRANGEBYSCORE', KEYS[2], '-inf', '(' .. ARGV[3])
+        for _, id in ipairs(expired) do
+            redis.call('ZREM', KEYS[2], id)
+            redis.call('HDEL', KEYS[3], id)
+            redis.call('ZADD', KEYS[1], redis.call('HGET', KEYS[5], id) or 0, id)
+        end
+        local tasks = {}
+        for _, id in ipairs(redis.call('ZRANGE', KEYS[1], 0, tonumber(ARGV[2]) - 1)) do
+            redis.call('ZREM', KEYS[1], id)
+            local task = redis.call('HGET', KEYS[4], id)
+            if task then
+                redis.call('ZADD', KEYS[2], ARGV[4], id)
+                redis.call('HSET', KEYS[3], id, ARGV[1])
+                table.insert(tasks, task)
+            end
+        end
+        return tasks
+    "#;
+
+    const RENEW: &str = r#"
+        local lost = {}
+        for i = 3, #ARGV do
+            if redis.call('HGET', KEYS[2], ARGV[i]) == ARGV[1] then
+                redis.call('ZADD', KEYS[1], 'XX', ARGV[2], ARGV[i])
+            else
+                table.insert(lost, ARGV[i])
+            end
+        end
+        return lost
+    "#;
+
+    const RELEASE: &str = r#"
+        if redis.call('HGET', KEYS[3], ARGV[2]) == ARGV[1] then
+            redis.call('ZREM', KEYS[2], ARGV[2])
+            redis.call('HDEL', KEYS[3], ARGV[2])
+            redis.call('ZADD', KEYS[1], redis.call('HGET', KEYS[4], ARGV[2]) or 0, ARGV[2])
+        end
+        return 0
+    "#;
+
+    /// Only the lease holder may drop a task; a lost lease means another instance runs it
+    const ACK: &str = r#"
+        if redis.call('HGET', KEYS[2], ARGV[2]) == ARGV[1] then
+            redis.call('ZREM', KEYS[1], ARGV[2])
+            redis.call('HDEL', KEYS[2], ARGV[2])
+            redis.call('HDEL', KEYS[3], ARGV[2])
+            redis.call('HDEL', KEYS[4], ARGV[2])
+        end
+        return 0
+    "#;
+
+    const RUNNING: &str = r#"
+        local owners = {}
+        for _, id in ipairs(redis.call('ZRANGEBYSCORE', KEYS[1], ARGV[1], '+inf')) do
+            local owner = redis.call('HGET', KEYS[2], id)
+            if owner then
+                table.insert(owners, owner)
+            end
+        end
+        return owners
+    "#;
+
+    /// Priority ranks above the created timestamp in queue scores, so lower scores run first
+    const PRIORITY_WEIGHT: f64 = 1e13;
+
+    /// Queue kept in Redis
+    ///
+    /// Keys under `{prefix}:tasks:`: `queue` sorts waiting task ids by priority and age,
+    /// `processing` sorts claimed ids by lease deadline, and the `task`, `owner` and
+    /// `score` hashes hold each task, its lease holder and its queue score. Every
+    /// operation is a Lua script, so instances never see a task half moved.
+    pub struct RedisTaskQueue {
+        connection: ConnectionManager,
+        keys: [String; 5],
+        claim: Script,
+        renew: Script,
+        release: Script,
+        ack: Script,
+        running: Script,
+    }
+
+    impl RedisTaskQueue {
+        /// Connect to `url`, keeping the queue under `{prefix}:tasks:`
+        pub async fn connect(url: &str, prefix: &str) -> ExecutorResult<Self> {
+            let client = redis::Client::open(url).map_err(redis_error)?;
+            let connection = client.get_connection_manager().await.map_err(redis_error)?;
+            Ok(Self::new(connection, prefix))
+        }
+
+        pub fn new(connection: ConnectionManager, prefix: &str) -> Self {
+            let key = |name: &str| format!("{}:tasks:{}", prefix, name);
+            Self {
+                connection,
+                keys: [key("queue"), key("processing"), key("owner"), key("task"), key("score")],
+                claim: Script::new(CLAIM),
+                renew: Script::new(RENEW),
+                release: Script::new(RELEASE),
+                ack: Script::new(ACK),
+                running: Script::new(RUNNING),
+            }
+        }
+
+        fn queue(&self) -> &str {
+            &self.keys[0]
+        }
+
+        fn processing(&self) -> &str {
+            &self.keys[1]
+        }
+
+        fn owner(&self) -> &str {
+            &self.keys[2]
+        }
+
+        fn task(&self) -> &str {
+            &self.keys[3]
+        }
+
+        fn score(&self) -> &str {
+            &self.keys[4]
+        }
+    }
+
+    fn score(task: &Task) -> f64 {
+        let rank = crate::Priority::Critical as i64 - task.priority as i64;
+        rank as f64 * PRIORITY_WEIGHT + task.created_at.timestamp_millis() as f64
+    }
+
+    fn redis_error(e: redis::RedisError) -> ExecutorError {
+        ExecutorError::InternalError(format!("Redis task queue error: {}", e))
+    }
+
+    #[async_trait]
+    impl TaskQueue for RedisTaskQueue {
+        fn backend(&self) -> &'static str {
+            "redis"
+        }
+
+        async fn push(&self, task: &Task) -> ExecutorResult<()> {
+            let data = serde_json::to_string(task).map_err(|e| ExecutorError::InternalError(e.to_string()))?;
+            let id = task.id.to_string();
+            let score = score(task);
+            redis::pipe()
+                .atomic()
+                .hset(self.task(), &id, data).ignore()
+                .hset(self.score(), &id, score).ignore()
+                .zadd(self.queue(), &id, score).ignore()
+                .query_async::<_, ()>(&mut self.connection.clone())
+                .await
+                .map_err(redis_error)
+        }
+
+        async fn claim(&self, instance_id: &str, limit: usize, lease_until: DateTime<Utc>) -> ExecutorResult<Vec<Task>> {
+            if limit == 0 {
+                return Ok(Vec::new());
+            }
+            let tasks: Vec<String> = self.claim
+                .key(self.queue())
+                .key(self.processing())
+                .key(self.owner())
+                .key(self.task())
+                .key(self.score())
+                .arg(instance_id)
+                .arg(limit)
+                .arg(Utc::now().timestamp_millis())
+                .arg(lease_until.timestamp_millis())
+                .invoke_async(&mut self.connection.clone())
+                .await
+                .map_err(redis_error)?;
+            tasks.iter()
+                .map(|data| serde_json::from_str(data).map_err(|e| ExecutorError::InternalError(format!("Invalid queued task: {}", e))))
+                .collect()
+        }
+
+        async fn renew(&self, instance_id: &str, task_ids: &[TaskId], lease_until: DateTime<Utc>) -> ExecutorResult<Vec<TaskId>> {
+            if task_ids.is_empty() {
+                return Ok(Vec::new());
+            }
+            let mut invocation = self.renew.prepare_invoke();
+            invocation
+                .key(self.processing())
+                .key(self.owner())
+                .arg(instance_id)
+                .arg(lease_until.timestamp_millis());
+            for task_id in task_ids {
+                invocation.arg(task_id.to_string());
+            }
+            let lost: Vec<String> = invocation.invoke_async(&mut self.connection.clone()).await.map_err(redis_error)?;
+            Ok(task_ids.iter().filter(|id| lost.contains(&id.to_string())).cloned().collect())
+        }
+
+        async fn release(&self, instance_id: &str, task_id: &TaskId) -> ExecutorResult<()> {
+            self.release
+                .key(self.queue())
+                .key(self.processing())
+                .key(self.owner())
+                .key(self.score())
+                .arg(instance_id)
+                .arg(task_id.to_string())
+                .invoke_async::<_, ()>(&mut self.connection.clone())
+                .await
+                .map_err(redis_error)
+        }
+
+        async fn ack(&self, instance_id: &str, task_id: &TaskId) -> ExecutorResult<()> {
+            self.ack
+                .key(self.processing())
+                .key(self.owner())
+                .key(self.task())
+                .key(self.score())
+                .arg(instance_id)
+                .arg(task_id.to_string())
+                .invoke_async::<_, ()>(&mut self.connection.clone())
+                .await
+                .map_err(redis_error)
+        }
+
+        async fn running_by_instance(&self) -> ExecutorResult<HashMap<String, usize>> {
+            let owners: Vec<String> = self.running
+                .key(self.processing())
+                .key(self.owner())
+                .arg(Utc::now().timestamp_millis())
+                .invoke_async(&mut self.connection.clone())
+                .await
+                .map_err(redis_error)?;
+            let mut running = HashMap::new();
+            for owner in owners {
+                *running.entry(owner).or_insert(0) += 1;
+            }
+            Ok(running)
+        }
+    }
+}
@@ -0,0 +1,436 @@
+//! Run reports
+//!
+//! A [`RunReport`] summarizes an execution or a workflow run for audits and incident
+//! reviews: its inputs, per-step timing, an excerpt of the logs, resource usage and the
+//! final outputs. It renders to a self-contained HTML page ([`RunReport::to_html`]) or a
+//! PDF ([`RunReport::to_pdf`]) that can be shared outside Stepflow.
+//!
+//! Reports only ever contain redacted values: inputs and outputs pass through [`redact`],
+//! which masks everything under keys that name credentials. Executions record their
+//! parameters redacted in the result metadata under [`PARAMETERS_METADATA`], so the
+//! original values are never available to a report in the first place.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use stepflow_core::*;
+use crate::report_pdf::{Font, PdfDocument};
+use crate::timeline::ExecutionTimeline;
+use crate::workflow::{StepRun, WorkflowRun, WorkflowRunStatus};
+
+/// Result metadata key holding the redacted parameters of an execution
+pub const PARAMETERS_METADATA: &str = "parameters";
+
+/// Log entries kept in a report, the most recent ones
+pub const REPORT_LOG_LIMIT: usize = 50;
+
+const REDACTED: &str = "********";
+
+pub use stepflow_core::is_sensitive_key;
+
+/// Copy of `value` with the values of sensitive keys masked, at any depth
+pub fn redact(value: &Value) -> Value {
+    redact_sensitive(value, REDACTED)
+}
+
+/// What a report is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportSubject {
+    Execution,
+    WorkflowRun,
+}
+
+/// A timed part of the run: a state of an execution or a step of a workflow
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReportPhase {
+    pub name: String,
+    pub status: String,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Error or worker detail
+    pub detail: Option<String>,
+}
+
+impl ReportPhase {
+    pub fn duration_ms(&self) -> Option<i64> {
+        Some((self.finished_at? - self.started_at?).num_milliseconds())
+    }
+}
+
+/// Timeline and result of the execution behind a workflow step
+#[derive(Debug, Clone, Default)]
+pub struct StepExecution {
+    pub timeline: Option<ExecutionTimeline>,
+    pub result: Option<ExecutionResult>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunReport {
+    pub subject: ReportSubject,
+    pub id: String,
+    pub title: String,
+    pub tenant_id: Option<String>,
+    pub status: String,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Further facts shown in the summary, e.g. tool and version
+    pub summary: Vec<(String, String)>,
+    /// Redacted inputs
+    pub inputs: Option<Value>,
+    pub phases: Vec<ReportPhase>,
+    /// The last [`REPORT_LOG_LIMIT`] log entries, oldest first
+    pub logs: Vec<LogEntry>,
+    /// Log entries left out of the excerpt
+    pub omitted_logs: usize,
+    pub resources: BTreeMap<String, f64>,
+    /// Redacted outputs
+    pub output: Option<Value>,
+    pub error: Option<String>,
+    pub generated_at: DateTime<Utc>,
+}
+
+impl RunReport {
+    /// Report on an execution; `result` is `None` while it has not finished
+    pub fn for_execution(timeline: &ExecutionTimeline, result: Option<&ExecutionResult>) -> Self {
+        let events = &timeline.events;
+        let phases = events
+            .iter()
+            .enumerate()
+            .filter(|(_, event)| !event.state.is_terminal())
+            .map(|(index, event)| ReportPhase {
+                name: event.state.as_str().to_string(),
+                status: if events.get(index + 1).is_some() { "done" } else { "in progress" }.to_string(),
+                started_at: Some(event.occurred_at),
+                finished_at: events.get(index + 1).map(|next| next.occurred_at),
+                detail: event.worker_id.as_ref().map(|worker| format!("worker {}", worker)).or_else(|| event.detail.clone()),
+            })
+            .collect();
+        let finished = events.iter().rev().find(|event| event.state.is_terminal());
+
+        let metadata = |key: &str| result.and_then(|r| r.metadata.get(key)).and_then(Value::as_str);
+        let mut summary = Vec::new();
+        if let Some(tool) = metadata("tool_name").or(metadata("tool_id")) {
+            summary.push(("Tool".to_string(), tool.to_string()));
+        }
+        if let Some(version) = metadata("tool_version") {
+            summary.push(("Version".to_string(), version.to_string()));
+        }
+        if let Some(label) = metadata("environment_label") {
+            summary.push(("Environment".to_string(), label.to_string()));
+        }
+        if let Some(wait) = timeline.queue_wait_ms {
+            summary.push(("Queue wait".to_string(), format_duration(wait)));
+        }
+        if let Some(duration) = timeline.total_duration_ms {
+            summary.push(("Total duration".to_string(), format_duration(duration)));
+        }
+
+        let (logs, omitted_logs) = excerpt(result.map(|r| r.logs.clone()).unwrap_or_default());
+        Self {
+            subject: ReportSubject::Execution,
+            id: timeline.execution_id.to_string(),
+            title: format!("Execution {}", timeline.execution_id),
+            tenant_id: timeline.tenant_id.clone(),
+            status: events.last().map(|event| event.state.as_str()).unwrap_or("unknown").to_string(),
+            started_at: events.first().map(|event| event.occurred_at),
+            finished_at: finished.map(|event| event.occurred_at),
+            summary,
+            inputs: result.and_then(|r| r.metadata.get(PARAMETERS_METADATA)).map(redact),
+            phases,
+            logs,
+            omitted_logs,
+            resources: result.map(|r| r.metrics.iter().map(|(k, v)| (k.clone(), *v)).collect()).unwrap_or_default(),
+            output: result.and_then(|r| r.output.as_ref()).map(redact),
+            error: result.and_then(|r| r.error.clone()).or_else(|| finished.and_then(|event| event.detail.clone())),
+            generated_at: Utc::now(),
+        }
+    }
+
+    /// Report on a workflow run
+    ///
+    /// `inputs` are what the run was started with; `executions` holds the step
+    /// executions by execution id. Logs and resource usage are those of the steps.
+    pub fn for_workflow(run: &WorkflowRun, inputs: Option<Value>, executions: &HashMap<String, StepExecution>) -> Self {
+        let execution = |step: &StepRun| step.execution_id.as_ref().and_then(|id| executions.get(id));
+        let phases = run.steps
+            .iter()
+            .map(|step| {
+                let timeline = execution(step).and_then(|e| e.timeline.as_ref());
+                ReportPhase {
+                    name: match step.iteration {
+                        Some(iteration) => format!("{}[{}]", step.step_id, iteration),
+                        None => step.step_id.clone(),
+                    },
+                    status: step.status.as_str().to_string(),
+                    started_at: timeline.and_then(|t| t.events.first()).map(|event| event.occurred_at),
+                    finished_at: Some(step.updated_at),
+                    detail: step.error.clone(),
+                }
+            })
+            .collect();
+
+        let mut logs = Vec::new();
+        let mut resources = BTreeMap::new();
+        for result in run.steps.iter().filter_map(execution).filter_map(|e| e.result.as_ref()) {
+            logs.extend(result.logs.iter().cloned());
+            for (metric, value) in &result.metrics {
+                *resources.entry(metric.clone()).or_insert(0.0) += value;
+            }
+        }
+        logs.sort_by_key(|entry| entry.timestamp);
+        let (logs, omitted_logs) = excerpt(logs);
+
+        let mut summary = vec![("Workflow".to_string(), run.workflow_id.clone())];
+        if let Some(version) = run.version {
+            summary.push(("Version".to_string(), version.to_string()));
+        }
+        summary.push(("Steps".to_string(), run.steps.len().to_string()));
+
+        let terminal = !matches!(run.status, WorkflowRunStatus::Running | WorkflowRunStatus::WaitingApproval);
+        Self {
+            subject: ReportSubject::WorkflowRun,
+            id: run.id.clone(),
+            title: format!("Workflow run {}", run.id),
+            tenant_id: Some(run.tenant_id.clone()),
+            status: run.status.as_str().to_string(),
+            started_at: Some(run.created_at),
+            finished_at: terminal.then_some(run.updated_at),
+            summary,
+            inputs: inputs.as_ref().map(redact),
+            phases,
+            logs,
+            omitted_logs,
+            resources,
+            output: run.output.as_ref().map(redact),
+            error: run.error.clone(),
+            generated_at: Utc::now(),
+        }
+    }
+
+    /// Bounds of the timing chart
+    fn span(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        let start = self.phases.iter().filter_map(|p| p.started_at).chain(self.started_at).min()?;
+        let end = self.phases.iter().filter_map(|p| p.finished_at.or(p.started_at)).chain(self.finished_at).max()?;
+        Some((start, end.max(start)))
+    }
+
+    /// Position of a phase in the timing chart, as fractions of the span
+    fn bar(&self, phase: &ReportPhase) -> Option<(f64, f64)> {
+        let (start, end) = self.span()?;
+        let total = (end - start).num_milliseconds().max(1) as f64;
+        let from = phase.started_at.or(phase.finished_at)?;
+        let to = phase.finished_at.unwrap_or(end);
+        Some(((from - start).num_milliseconds() as f64 / total, (to - start).num_milliseconds() as f64 / total))
+    }
+
+    /// Self-contained HTML page, suitable for printing
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        let _ = write!(
+            html,
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>{style}</style>\n</head>\n<body>\n<h1>{title}</h1>\n",
+            title = escape_html(&self.title),
+            style = HTML_STYLE,
+        );
+
+        html.push_str("<table class=\"summary\">\n");
+        for (label, value) in self.summary_rows() {
+            let _ = writeln!(html, "<tr><th>{}</th><td>{}</td></tr>", escape_html(&label), escape_html(&value));
+        }
+        html.push_str("</table>\n");
+
+        if let Some(error) = &self.error {
+            let _ = writeln!(html, "<h2>Error</h2>\n<pre class=\"error\">{}</pre>", escape_html(error));
+        }
+
+        html.push_str("<h2>Inputs</h2>\n");
+        html.push_str(&json_block(self.inputs.as_ref()));
+
+        html.push_str("<h2>Timing</h2>\n");
+        if self.phases.is_empty() {
+            html.push_str("<p class=\"empty\">No steps recorded</p>\n");
+        } else {
+            html.push_str("<table class=\"timing\">\n<tr><th>Step</th><th>Status</th><th class=\"chart\"></th><th>Duration</th></tr>\n");
+            for phase in &self.phases {
+                let bar = self.bar(phase).map_or(String::new(), |(from, to)| {
+                    format!(
+                        "<div class=\"bar\" style=\"margin-left:{:.2}%;width:{:.2}%\"></div>",
+                        from * 100.0,
+                        ((to - from) * 100.0).max(0.5)
+                    )
+                });
+                let _ = writeln!(
+                    html,
+                    "<tr><td>{}</td><td>{}</td><td class=\"chart\">{}</td><td>{}</td></tr>",
+                    escape_html(&phase.name),
+                    escape_html(&phase.status),
+                    bar,
+                    phase.duration_ms().map(format_duration).unwrap_or_default(),
+                );
+                if let Some(detail) = &phase.detail {
+                    let _ = writeln!(html, "<tr class=\"detail\"><td></td><td colspan=\"3\">{}</td></tr>", escape_html(detail));
+                }
+            }
+            html.push_str("</table>\n");
+        }
+
+        html.push_str("<h2>Logs</h2>\n");
+        if self.logs.is_empty() {
+            html.push_str("<p class=\"empty\">No logs</p>\n");
+        } else {
+            if self.omitted_logs > 0 {
+                let _ = writeln!(html, "<p class=\"empty\">{} earlier entries omitted</p>", self.omitted_logs);
+            }
+            html.push_str("<pre class=\"logs\">");
+            for entry in &self.logs {
+                html.push_str(&escape_html(&log_line(entry)));
+                html.push('\n');
+            }
+            html.push_str("</pre>\n");
+        }
+
+        html.push_str("<h2>Resource usage</h2>\n");
+        if self.resources.is_empty() {
+            html.push_str("<p class=\"empty\">Not recorded</p>\n");
+        } else {
+            html.push_str("<table class=\"summary\">\n");
+            for (metric, value) in &self.resources {
+                let _ = writeln!(html, "<tr><th>{}</th><td>{}</td></tr>", escape_html(metric), value);
+            }
+            html.push_str("</table>\n");
+        }
+
+        html.push_str("<h2>Outputs</h2>\n");
+        html.push_str(&json_block(self.output.as_ref()));
+
+        let _ = write!(html, "<footer>Generated {}</footer>\n</body>\n</html>\n", self.generated_at.to_rfc3339());
+        html
+    }
+
+    /// PDF document with the same sections as the HTML page
+    pub fn to_pdf(&self) -> Vec<u8> {
+        let mut pdf = PdfDocument::new();
+        pdf.text(&self.title, Font::Bold, 18.0);
+        pdf.gap(6.0);
+        for (label, value) in self.summary_rows() {
+            pdf.text(&format!("{}: {}", label, value), Font::Regular, 10.0);
+        }
+
+        if let Some(error) = &self.error {
+            pdf.heading("Error");
+            pdf.text(error, Font::Mono, 9.0);
+        }
+
+        pdf.heading("Inputs");
+        pdf.text(&json_text(self.inputs.as_ref()), Font::Mono, 8.0);
+
+        pdf.heading("Timing");
+        if self.phases.is_empty() {
+            pdf.text("No steps recorded", Font::Regular, 10.0);
+        }
+        for phase in &self.phases {
+            let (from, to) = self.bar(phase).unwrap_or((0.0, 0.0));
+            let caption = phase.duration_ms().map(format_duration).unwrap_or_else(|| phase.status.clone());
+            pdf.bar(&format!("{} ({})", phase.name, phase.status), from as f32, to as f32, &caption);
+        }
+
+        pdf.heading("Logs");
+        if self.omitted_logs > 0 {
+            pdf.text(&format!("{} earlier entries omitted", self.omitted_logs), Font::Regular, 9.0);
+        }
+        if self.logs.is_empty() {
+            pdf.text("No logs", Font::Regular, 10.0);
+        }
+        for entry in &self.logs {
+            pdf.text(&log_line(entry), Font::Mono, 8.0);
+        }
+
+        pdf.heading("Resource usage");
+        if self.resources.is_empty() {
+            pdf.text("Not recorded", Font::Regular, 10.0);
+        }
+        for (metric, value) in &self.resources {
+            pdf.text(&format!("{}: {}", metric, value), Font::Regular, 10.0);
+        }
+
+        pdf.heading("Outputs");
+        pdf.text(&json_text(self.output.as_ref()), Font::Mono, 8.0);
+
+        pdf.gap(12.0);
+        pdf.text(&format!("Generated {}", self.generated_at.to_rfc3339()), Font::Regular, 8.0);
+        pdf.finish()
+    }
+
+    fn summary_rows(&self) -> Vec<(String, String)> {
+        let time = |t: Option<DateTime<Utc>>| t.map(|t| t.to_rfc3339()).unwrap_or_else(|| "-".to_string());
+        let mut rows = vec![
+            ("Status".to_string(), self.status.clone()),
+            ("Started".to_string(), time(self.started_at)),
+            ("Finished".to_string(), time(self.finished_at)),
+        ];
+        if let Some(tenant_id) = &self.tenant_id {
+            rows.push(("Tenant".to_string(), tenant_id.clone()));
+        }
+        rows.extend(self.summary.iter().cloned());
+        rows
+    }
+}
+
+const HTML_STYLE: &str = "body{font-family:-apple-system,Segoe UI,Helvetica,Arial,sans-serif;margin:2em auto;max-width:60em;color:#222}\
+h1{font-size:1.5em}h2{font-size:1.15em;border-bottom:1px solid #ddd;padding-bottom:.2em;margin-top:1.6em}\
+table{border-collapse:collapse;width:100%}th,td{text-align:left;padding:.25em .5em;vertical-align:top}\
+.summary th{width:12em;color:#555;font-weight:normal}.timing th{border-bottom:1px solid #ddd}\
+.chart{width:50%}.bar{height:.8em;background:#3b78c7;border-radius:2px}.detail td{color:#a33;font-size:.9em}\
+pre{background:#f6f6f6;padding:.6em;overflow-x:auto;font-size:.85em;white-space:pre-wrap}.error{color:#a33}\
+.empty{color:#777}footer{margin-top:2em;color:#777;font-size:.8em}";
+
+/// Keep the last [`REPORT_LOG_LIMIT`] entries, returning how many were dropped
+fn excerpt(mut logs: Vec<LogEntry>) -> (Vec<LogEntry>, usize) {
+    let omitted = logs.len().saturating_sub(REPORT_LOG_LIMIT);
+    logs.drain(..omitted);
+    (logs, omitted)
+}
+
+fn log_line(entry: &LogEntry) -> String {
+    format!("{} {:<5} [{}] {}", entry.timestamp.format("%H:%M:%S%.3f"), entry.level.to_string(), entry.source, entry.message)
+}
+
+fn format_duration(ms: i64) -> String {
+    match ms {
+        ms if ms < 1_000 => format!("{} ms", ms),
+        ms if ms < 60_000 => format!("{:.2} s", ms as f64 / 1_000.0),
+        ms => format!("{}m {:02}s", ms / 60_000, (ms % 60_000) / 1_000),
+    }
+}
+
+fn json_text(value: Option<&Value>) -> String {
+    match value {
+        Some(value) => serde_json::to_string_pretty(value).unwrap_or_default(),
+        None => "Not recorded".to_string(),
+    }
+}
+
+fn json_block(value: Option<&Value>) -> String {
+    match value {
+        Some(_) => format!("<pre>{}</pre>\n", escape_html(&json_text(value))),
+        None => "<p class=\"empty\">Not recorded</p>\n".to_string(),
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,180 @@
+//! Minimal PDF writer for run reports
+//!
+//! Lays out text top to bottom on A4 pages with the standard Helvetica and Courier
+//! fonts, which every PDF reader provides, so no fonts are embedded. Characters
+//! outside Latin-1 are replaced by `?`.
+
+use std::fmt::Write;
+
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 50.0;
+const LINE_SPACING: f32 = 1.4;
+
+/// Font of a text run; the resource names match the font objects written by [`PdfDocument::finish`]
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Font {
+    Regular,
+    Bold,
+    Mono,
+}
+
+impl Font {
+    fn resource(self) -> &'static str {
+        match self {
+            Font::Regular => "F1",
+            Font::Bold => "F2",
+            Font::Mono => "F3",
+        }
+    }
+
+    /// Average glyph width relative to the font size, used for wrapping
+    fn char_width(self) -> f32 {
+        match self {
+            Font::Regular => 0.5,
+            Font::Bold => 0.55,
+            Font::Mono => 0.6,
+        }
+    }
+}
+
+pub(crate) struct PdfDocument {
+    pages: Vec<String>,
+    content: String,
+    y: f32,
+}
+
+impl PdfDocument {
+    pub(crate) fn new() -> Self {
+        Self { pages: Vec::new(), content: String::new(), y: PAGE_HEIGHT - MARGIN }
+    }
+
+    /// Write `text` wrapped to the page width
+    pub(crate) fn text(&mut self, text: &str, font: Font, size: f32) {
+        let per_line = ((PAGE_WIDTH - 2.0 * MARGIN) / (size * font.char_width())) as usize;
+        for line in text.lines() {
+            let chars: Vec<char> = line.chars().collect();
+            if chars.is_empty() {
+                self.advance(size * LINE_SPACING);
+                continue;
+            }
+            for chunk in chars.chunks(per_line.max(1)) {
+                self.advance(size * LINE_SPACING);
+                let line: String = chunk.iter().collect();
+                self.show(MARGIN, self.y, &line, font, size);
+            }
+        }
+    }
+
+    pub(crate) fn heading(&mut self, text: &str) {
+        self.gap(8.0);
+        self.text(text, Font::Bold, 13.0);
+        self.gap(2.0);
+    }
+
+    pub(crate) fn gap(&mut self, height: f32) {
+        self.advance(height);
+    }
+
+    /// One row of a bar chart: a label, a bar covering `start..end` of the track (fractions) and a caption
+    pub(crate) fn bar(&mut self, label: &str, start: f32, end: f32, caption: &str) {
+        const SIZE: f32 = 9.0;
+        const LABEL_WIDTH: f32 = 150.0;
+        const CAPTION_WIDTH: f32 = 70.0;
+        self.advance(SIZE * 1.8);
+        let label: String = label.chars().take((LABEL_WIDTH / (SIZE * 0.5)) as usize - 1).collect();
+        self.show(MARGIN, self.y, &label, Font::Regular, SIZE);
+
+        let track_x = MARGIN + LABEL_WIDTH;
+        let track_width = PAGE_WIDTH - 2.0 * MARGIN - LABEL_WIDTH - CAPTION_WIDTH;
+        let (start, end) = (start.clamp(0.0, 1.0), end.clamp(0.0, 1.0));
+        let _ = writeln!(self.content, "0.92 0.92 0.92 rg {:.1} {:.1} {:.1} {:.1} re f", track_x, self.y - 2.0, track_width, SIZE + 2.0);
+        let width = ((end - start) * track_width).max(1.5);
+        let _ = writeln!(
+            self.content,
+            "0.23 0.47 0.78 rg {:.1} {:.1} {:.1} {:.1} re f 0 0 0 rg",
+            track_x + start * track_width,
+            self.y - 2.0,
+            width,
+            SIZE + 2.0
+        );
+        self.show(track_x + track_width + 6.0, self.y, caption, Font::Regular, SIZE);
+    }
+
+    /// Move down by `height`, starting a new page when it does not fit
+    fn advance(&mut self, height: f32) {
+        if self.y - height < MARGIN {
+            self.pages.push(std::mem::take(&mut self.content));
+            self.y = PAGE_HEIGHT - MARGIN;
+        }
+        self.y -= height;
+    }
+
+    fn show(&mut self, x: f32, y: f32, text: &str, font: Font, size: f32) {
+        let _ = writeln!(self.content, "BT /{} {} Tf {:.1} {:.1} Td ({}) Tj ET", font.resource(), size, x, y, escape(text));
+    }
+
+    pub(crate) fn finish(mut self) -> Vec<u8> {
+        if !self.content.is_empty() || self.pages.is_empty() {
+            self.pages.push(std::mem::take(&mut self.content));
+        }
+
+        // 1: catalog, 2: page tree, 3-5: fonts, then a page and its content stream per page
+        let page_ids: Vec<usize> = (0..self.pages.len()).map(|i| 6 + 2 * i).collect();
+        let mut objects = vec![
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            format!(
+                "<< /Type /Pages /Kids [{}] /Count {} >>",
+                page_ids.iter().map(|id| format!("{} 0 R", id)).collect::<Vec<_>>().join(" "),
+                page_ids.len()
+            ),
+        ];
+        for font in ["Helvetica", "Helvetica-Bold", "Courier"] {
+            objects.push(format!("<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>", font));
+        }
+        for (page, content) in page_ids.iter().zip(&self.pages) {
+            objects.push(format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R /F2 4 0 R /F3 5 0 R >> >> /Contents {} 0 R >>",
+                PAGE_WIDTH,
+                PAGE_HEIGHT,
+                page + 1
+            ));
+            objects.push(format!("<< /Length {} >>\nstream\n{}endstream", content.len(), content));
+        }
+
+        let mut pdf = b"%PDF-1.4\n".to_vec();
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (index, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend_from_slice(format!("{} 0 obj\n{}\nendobj\n", index + 1, object).as_bytes());
+        }
+        let xref = pdf.len();
+        let mut trailer = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+        for offset in offsets {
+            let _ = writeln!(trailer, "{:010} 00000 n ", offset);
+        }
+        let _ = writeln!(trailer, "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF", objects.len() + 1, xref);
+        pdf.extend_from_slice(trailer.as_bytes());
+        pdf
+    }
+}
+
+/// Escape a string literal; non-ASCII Latin-1 characters become octal escapes
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            ' '..='~' => escaped.push(c),
+            '\t' => escaped.push_str("    "),
+            c if (0xA0..=0xFF).contains(&(c as u32)) => {
+                let _ = write!(escaped, "\\{:03o}", c as u32);
+            }
+            _ => escaped.push('?'),
+        }
+    }
+    escaped
+}
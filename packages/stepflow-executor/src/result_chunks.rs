@@ -0,0 +1,186 @@
+//! Chunked result persistence and output size limits
+//!
+//! Outputs larger than one chunk are split into fixed-size pieces in the
+//! `result_chunks` table instead of one `execution_results.output_data`
+//! string, and read back chunk by chunk. Before an output is stored it is
+//! checked against the tenant's maximum output size; an oversized output is
+//! replaced by a truncation marker holding a preview.
+
+use std::sync::Arc;
+use futures::stream::{self, BoxStream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use stepflow_core::*;
+use stepflow_database::utils::param;
+use stepflow_database::SqliteDatabase;
+use crate::errors::*;
+
+/// Tenant setting key holding the tenant's maximum output size in bytes
+pub const TENANT_MAX_OUTPUT_SETTING: &str = "max_output_bytes";
+
+/// Metadata key set on results whose output was truncated
+pub const OUTPUT_TRUNCATED_METADATA: &str = "output_truncated";
+
+/// Chunks fetched per query when reassembling an output
+const CHUNKS_PER_PAGE: i64 = 16;
+
+/// Output size limits applied before results are stored
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputLimits {
+    /// Largest serialized output kept, unless the tenant sets its own limit
+    pub max_output_bytes: usize,
+    /// Size of the pieces large outputs are stored in
+    pub chunk_size: usize,
+}
+
+impl Default for OutputLimits {
+    fn default() -> Self {
+        Self {
+            max_output_bytes: 16 * 1024 * 1024,
+            chunk_size: 256 * 1024,
+        }
+    }
+}
+
+impl OutputLimits {
+    /// Maximum output size for `tenant`, falling back to the default
+    pub fn max_output_for(&self, tenant: Option<&TenantInfo>) -> usize {
+        tenant
+            .and_then(|tenant| tenant.settings.get(TENANT_MAX_OUTPUT_SETTING))
+            .and_then(|value| value.as_u64())
+            .map_or(self.max_output_bytes, |max| max as usize)
+    }
+
+    /// Replace an output larger than `max_bytes` with a truncation marker
+    ///
+    /// The marker keeps the original size and the first `max_bytes` of the
+    /// serialized output. Returns whether the output was truncated.
+    pub fn truncate(result: &mut ExecutionResult, max_bytes: usize) -> ExecutorResult<bool> {
+        let Some(output) = &result.output else {
+            return Ok(false);
+        };
+        let serialized = serde_json::to_string(output)?;
+        if serialized.len() <= max_bytes {
+            return Ok(false);
+        }
+        let preview = &serialized[..floor_char_boundary(&serialized, max_bytes)];
+        result.output = Some(serde_json::json!({
+            "truncated": true,
+            "original_bytes": serialized.len(),
+            "preview": preview,
+        }));
+        result.metadata.insert(OUTPUT_TRUNCATED_METADATA.to_string(), Value::Bool(true));
+        Ok(true)
+    }
+}
+
+/// Largest index at most `index` that falls on a character boundary
+fn floor_char_boundary(text: &str, index: usize) -> usize {
+    let mut index = index.min(text.len());
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+/// Split `text` into pieces of at most `chunk_size` bytes without splitting characters
+fn split_chunks(text: &str, chunk_size: usize) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        // A chunk always holds at least one character
+        let mut end = floor_char_boundary(rest, chunk_size.max(1));
+        if end == 0 {
+            end = rest.chars().next().map_or(rest.len(), char::len_utf8);
+        }
+        let (chunk, tail) = rest.split_at(end);
+        chunks.push(chunk);
+        rest = tail;
+    }
+    chunks
+}
+
+fn db_error(error: StepflowError) -> ExecutorError {
+    ExecutorError::DatabaseError(error.to_string())
+}
+
+/// Store `output_json` for an execution, returning the value for
+/// `output_data` and the chunk count for `output_chunks`
+///
+/// Outputs that fit in one chunk stay inline.
+pub(crate) async fn write_output(
+    db: &SqliteDatabase,
+    execution_id: &ExecutionId,
+    output_json: String,
+    chunk_size: usize,
+) -> ExecutorResult<(Value, i64)> {
+    if output_json.len() <= chunk_size {
+        return Ok((Value::String(output_json), 0));
+    }
+
+    let rows: Vec<Vec<Value>> = split_chunks(&output_json, chunk_size)
+        .into_iter()
+        .enumerate()
+        .map(|(index, chunk)| vec![param::text(execution_id.as_str()), param::int(index as i64), param::text(chunk)])
+        .collect();
+    let report = db
+        .insert_batch("INSERT INTO result_chunks (execution_id, chunk_index, data)", &rows, rows.len())
+        .await
+        .map_err(db_error)?;
+    if let Some(failure) = report.failures.first() {
+        return Err(ExecutorError::DatabaseError(format!(
+            "Failed to store output chunk {} of execution {}: {}", failure.index, execution_id, failure.error
+        )));
+    }
+    Ok((Value::Null, rows.len() as i64))
+}
+
+/// Stream the stored chunks of an execution's output in order, one page of chunks per query
+pub(crate) fn stream_chunks(db: Arc<SqliteDatabase>, execution_id: ExecutionId) -> BoxStream<'static, ExecutorResult<String>> {
+    stream::unfold(Some(0i64), move |offset| {
+        let db = db.clone();
+        let execution_id = execution_id.clone();
+        async move {
+            let offset = offset?;
+            let page = db
+                .execute(
+                    "SELECT data FROM result_chunks WHERE execution_id = ? ORDER BY chunk_index LIMIT ? OFFSET ?",
+                    &[param::text(execution_id.as_str()), param::int(CHUNKS_PER_PAGE), param::int(offset)],
+                )
+                .await
+                .map_err(db_error);
+            match page {
+                Ok(page) if page.rows.is_empty() => None,
+                Ok(page) => {
+                    let next = (page.rows.len() as i64 == CHUNKS_PER_PAGE).then_some(offset + CHUNKS_PER_PAGE);
+                    let chunks: Vec<ExecutorResult<String>> = page.rows
+                        .iter()
+                        .map(|row| Ok(row.get("data").and_then(|v| v.as_str()).unwrap_or("").to_string()))
+                        .collect();
+                    Some((stream::iter(chunks), next))
+                }
+                Err(e) => Some((stream::iter(vec![Err(e)]), None)),
+            }
+        }
+    })
+    .flatten()
+    .boxed()
+}
+
+/// Reassemble a chunked output
+pub(crate) async fn read_output(db: Arc<SqliteDatabase>, execution_id: &ExecutionId) -> ExecutorResult<String> {
+    let mut chunks = stream_chunks(db, execution_id.clone());
+    let mut output = String::new();
+    while let Some(chunk) = chunks.next().await {
+        output.push_str(&chunk?);
+    }
+    Ok(output)
+}
+
+/// Remove the stored chunks of an execution's output
+pub(crate) async fn delete_chunks(db: &SqliteDatabase, execution_id: &ExecutionId) -> ExecutorResult<()> {
+    db.execute("DELETE FROM result_chunks WHERE execution_id = ?", &[param::text(execution_id.as_str())])
+        .await
+        .map_err(db_error)?;
+    Ok(())
+}
@@ -4,10 +4,12 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use chrono::{DateTime, Utc};
+use futures::stream::{self, BoxStream, StreamExt};
 use stepflow_core::*;
 use stepflow_database::SqliteDatabase;
 use crate::errors::*;
 use crate::executor::ResultManager;
+use crate::result_chunks::{self, OutputLimits};
 
 /// Result manager implementation
 pub struct ResultManagerImpl {
@@ -15,6 +17,8 @@ pub struct ResultManagerImpl {
     // In-memory cache for recent results
     result_cache: Arc<RwLock<HashMap<ExecutionId, ExecutionResult>>>,
     cache_size: usize,
+    // Outputs larger than this are stored in chunks
+    chunk_size: usize,
 }
 
 impl ResultManagerImpl {
@@ -24,9 +28,60 @@ impl ResultManagerImpl {
             db,
             result_cache: Arc::new(RwLock::new(HashMap::new())),
             cache_size: 1000,
+            chunk_size: OutputLimits::default().chunk_size,
         }
     }
     
+    /// Store outputs larger than `chunk_size` bytes in chunks
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size;
+        self
+    }
+    
+    /// Stream an execution's serialized output, chunk by chunk when it was stored in chunks
+    pub async fn stream_output(&self, execution_id: &ExecutionId) -> ExecutorResult<BoxStream<'static, ExecutorResult<String>>> {
+        let query_result = self.db.execute(
+            "SELECT output_data, output_chunks FROM execution_results WHERE execution_id = ?",
+            &[serde_json::Value::String(execution_id.to_string())]
+        ).await.map_err(|e| ExecutorError::DatabaseError(e.to_string()))?;
+        let row = query_result.rows.first()
+            .ok_or_else(|| ExecutorError::InternalError(format!("Execution result not found: {}", execution_id)))?;
+        
+        if row.get("output_chunks").and_then(|v| v.as_i64()).unwrap_or(0) > 0 {
+            return Ok(result_chunks::stream_chunks(self.db.clone(), execution_id.clone()));
+        }
+        let output_json = row.get("output_data").and_then(|v| v.as_str()).unwrap_or("null").to_string();
+        Ok(stream::once(async move { Ok(output_json) }).boxed())
+    }
+    
+    /// Build a result from an `execution_results` row, reassembling chunked output
+    async fn row_to_result(&self, execution_id: &ExecutionId, row: &HashMap<String, serde_json::Value>) -> ExecutorResult<ExecutionResult> {
+        let success = row.get("success").and_then(|v| v.as_bool()).unwrap_or(false);
+        let output_json = if row.get("output_chunks").and_then(|v| v.as_i64()).unwrap_or(0) > 0 {
+            result_chunks::read_output(self.db.clone(), execution_id).await?
+        } else {
+            row.get("output_data").and_then(|v| v.as_str()).unwrap_or("null").to_string()
+        };
+        let error = row.get("error").and_then(|v| v.as_str()).map(|s| s.to_string());
+        let logs_json = row.get("logs").and_then(|v| v.as_str()).unwrap_or("[]");
+        let metrics_json = row.get("metrics").and_then(|v| v.as_str()).unwrap_or("{}");
+        let metadata_json = row.get("metadata").and_then(|v| v.as_str()).unwrap_or("{}");
+        
+        let output: Option<serde_json::Value> = serde_json::from_str(&output_json).ok();
+        let logs: Vec<LogEntry> = serde_json::from_str(logs_json).unwrap_or_default();
+        let metrics: HashMap<String, f64> = serde_json::from_str(metrics_json).unwrap_or_default();
+        let metadata: HashMap<String, serde_json::Value> = serde_json::from_str(metadata_json).unwrap_or_default();
+        
+        Ok(ExecutionResult {
+            success,
+            output,
+            error,
+            logs,
+            metrics,
+            metadata,
+        })
+    }
+    
     /// Store result in database
     async fn store_result_in_db(&self, execution_id: &ExecutionId, result: &ExecutionResult) -> ExecutorResult<()> {
         let output_json = serde_json::to_string(&result.output)
@@ -41,22 +96,25 @@ impl ResultManagerImpl {
         let metadata_json = serde_json::to_string(&result.metadata)
             .map_err(|e| ExecutorError::InternalError(e.to_string()))?;
         
+        let (output_data, output_chunks) = result_chunks::write_output(&self.db, execution_id, output_json, self.chunk_size).await?;
+        
         let params = vec![
             serde_json::Value::String(execution_id.to_string()),
             serde_json::Value::Bool(result.success),
-            serde_json::Value::String(output_json),
-            serde_json::Value::String(result.error.clone().unwrap_or_default()),
+            output_data,
+            result.error.clone().map(serde_json::Value::String).unwrap_or(serde_json::Value::Null),
             serde_json::Value::String(logs_json),
             serde_json::Value::String(metrics_json),
             serde_json::Value::String(metadata_json),
             serde_json::Value::String(Utc::now().to_rfc3339()),
+            serde_json::Value::from(output_chunks),
         ];
         
         self.db.execute(
             r#"
             INSERT INTO execution_results (
-                execution_id, success, output_data, error, logs, metrics, metadata, created_at
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+                execution_id, success, output_data, error, logs, metrics, metadata, created_at, output_chunks
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
             &params
         ).await.map_err(|e| ExecutorError::DatabaseError(e.to_string()))?;
@@ -69,7 +127,7 @@ impl ResultManagerImpl {
         let params = vec![serde_json::Value::String(execution_id.to_string())];
         
         let query_result = self.db.execute(
-            "SELECT success, output_data, error, logs, metrics, metadata FROM execution_results WHERE execution_id = ?",
+            "SELECT success, output_data, output_chunks, error, logs, metrics, metadata FROM execution_results WHERE execution_id = ?",
             &params
         ).await.map_err(|e| ExecutorError::DatabaseError(e.to_string()))?;
         
@@ -77,30 +135,7 @@ impl ResultManagerImpl {
             return Ok(None);
         }
         
-        let row = &query_result.rows[0];
-        
-        let success = row.get("success").and_then(|v| v.as_bool()).unwrap_or(false);
-        let output_json = row.get("output_data").and_then(|v| v.as_str()).unwrap_or("null");
-        let error = row.get("error").and_then(|v| v.as_str()).filter(|s| !s.is_empty()).map(|s| s.to_string());
-        let logs_json = row.get("logs").and_then(|v| v.as_str()).unwrap_or("[]");
-        let metrics_json = row.get("metrics").and_then(|v| v.as_str()).unwrap_or("{}");
-        let metadata_json = row.get("metadata").and_then(|v| v.as_str()).unwrap_or("{}");
-        
-        let output: Option<serde_json::Value> = serde_json::from_str(output_json).ok();
-        let logs: Vec<LogEntry> = serde_json::from_str(logs_json).unwrap_or_default();
-        let metrics: HashMap<String, f64> = serde_json::from_str(metrics_json).unwrap_or_default();
-        let metadata: HashMap<String, serde_json::Value> = serde_json::from_str(metadata_json).unwrap_or_default();
-        
-        let result = ExecutionResult {
-            success,
-            output,
-            error,
-            logs,
-            metrics,
-            metadata,
-        };
-        
-        Ok(Some(result))
+        self.row_to_result(execution_id, &query_result.rows[0]).await.map(Some)
     }
     
     /// Clean up cache to maintain size limit
@@ -123,8 +158,11 @@ impl ResultManagerImpl {
 impl ResultManager for ResultManagerImpl {
     /// Store execution result
     async fn store_result(&self, result: ExecutionResult) -> ExecutorResult<()> {
-        // For now, we'll use a generated execution ID since stepflow_core::ExecutionResult doesn't have one
-        let execution_id = ExecutionId::new();
+        // Results carry their execution ID in the metadata; anything else gets a fresh one
+        let execution_id = result.metadata.get("execution_id")
+            .and_then(|v| v.as_str())
+            .map(|id| ExecutionId::from_string(id.to_string()))
+            .unwrap_or_else(ExecutionId::new);
         
         // Store in database
         self.store_result_in_db(&execution_id, &result).await?;
@@ -170,6 +208,7 @@ impl ResultManager for ResultManagerImpl {
         // Remove from database
         let params = vec![serde_json::Value::String(execution_id.to_string())];
         
+        result_chunks::delete_chunks(&self.db, execution_id).await?;
         self.db.execute(
             "DELETE FROM execution_results WHERE execution_id = ?",
             &params
@@ -180,7 +219,7 @@ impl ResultManager for ResultManagerImpl {
     
     /// List results
     async fn list_results(&self, filter: Option<ExecutionFilter>) -> ExecutorResult<Vec<ExecutionResult>> {
-        let mut sql = "SELECT execution_id, success, output_data, error, logs, metrics, metadata FROM execution_results WHERE 1=1".to_string();
+        let mut sql = "SELECT execution_id, success, output_data, output_chunks, error, logs, metrics, metadata FROM execution_results WHERE 1=1".to_string();
         let mut params = Vec::new();
         
         if let Some(filter) = filter {
@@ -221,26 +260,10 @@ impl ResultManager for ResultManagerImpl {
         let mut results = Vec::new();
         
         for row in query_result.rows {
-            let success = row.get("success").and_then(|v| v.as_bool()).unwrap_or(false);
-            let output_json = row.get("output_data").and_then(|v| v.as_str()).unwrap_or("null");
-            let error = row.get("error").and_then(|v| v.as_str()).filter(|s| !s.is_empty()).map(|s| s.to_string());
-            let logs_json = row.get("logs").and_then(|v| v.as_str()).unwrap_or("[]");
-            let metrics_json = row.get("metrics").and_then(|v| v.as_str()).unwrap_or("{}");
-            let metadata_json = row.get("metadata").and_then(|v| v.as_str()).unwrap_or("{}");
-            
-            let output: Option<serde_json::Value> = serde_json::from_str(output_json).ok();
-            let logs: Vec<LogEntry> = serde_json::from_str(logs_json).unwrap_or_default();
-            let metrics: HashMap<String, f64> = serde_json::from_str(metrics_json).unwrap_or_default();
-            let metadata: HashMap<String, serde_json::Value> = serde_json::from_str(metadata_json).unwrap_or_default();
-            
-            results.push(ExecutionResult {
-                success,
-                output,
-                error,
-                logs,
-                metrics,
-                metadata,
-            });
+            let execution_id = ExecutionId::from_string(
+                row.get("execution_id").and_then(|v| v.as_str()).unwrap_or("").to_string()
+            );
+            results.push(self.row_to_result(&execution_id, &row).await?);
         }
         
         Ok(results)
@@ -250,6 +273,10 @@ impl ResultManager for ResultManagerImpl {
     async fn cleanup_results(&self, older_than: DateTime<Utc>) -> ExecutorResult<u64> {
         let params = vec![serde_json::Value::String(older_than.to_rfc3339())];
         
+        self.db.execute(
+            "DELETE FROM result_chunks WHERE execution_id IN (SELECT execution_id FROM execution_results WHERE created_at < ?)",
+            &params
+        ).await.map_err(|e| ExecutorError::DatabaseError(e.to_string()))?;
         let query_result = self.db.execute(
             "DELETE FROM execution_results WHERE created_at < ?",
             &params
@@ -0,0 +1,393 @@
+//! Blue/green tool version rollouts
+//!
+//! A rollout sends a percentage of a tool's executions to a candidate version
+//! while the rest keep running the stable version held by the registry.
+//! Outcomes are counted per arm. Once the candidate has `min_samples`
+//! executions it is compared against stable after every execution: it is
+//! rolled back when its error rate or mean duration exceeds the thresholds,
+//! and promoted (written to the registry) after `promote_after` executions
+//! within them. Operators can change the percentage, promote or roll back at
+//! any time.
+//!
+//! A candidate with examples is only promoted once its latest example test
+//! run (see [`crate::tool_tests`]) passed: automatic promotion waits for a
+//! run, and a failed run rolls the candidate back.
+//!
+//! Routing hashes the execution id, so it needs no shared state; a request
+//! that names a version explicitly gets that arm.
+
+use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use stepflow_core::*;
+use stepflow_registry::Registry;
+use crate::errors::*;
+use crate::executor::ExecutionStore;
+
+/// Rollout lifecycle state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RolloutStatus {
+    Active,
+    Promoted,
+    RolledBack,
+}
+
+impl RolloutStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RolloutStatus::Active => "active",
+            RolloutStatus::Promoted => "promoted",
+            RolloutStatus::RolledBack => "rolled_back",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "active" => Some(RolloutStatus::Active),
+            "promoted" => Some(RolloutStatus::Promoted),
+            "rolled_back" => Some(RolloutStatus::RolledBack),
+            _ => None,
+        }
+    }
+}
+
+/// Version an execution was routed to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RolloutArm {
+    Stable,
+    Candidate,
+}
+
+impl RolloutArm {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RolloutArm::Stable => "stable",
+            RolloutArm::Candidate => "candidate",
+        }
+    }
+}
+
+/// When a candidate is promoted or rolled back automatically
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RolloutThresholds {
+    /// Candidate executions needed before it is compared against stable
+    pub min_samples: u64,
+    /// Roll back when the candidate error rate exceeds stable's by more than this (0.05 = 5 points)
+    pub max_error_rate_increase: f64,
+    /// Roll back when the candidate mean duration exceeds stable's by this factor
+    pub max_latency_ratio: f64,
+    /// Promote after this many candidate executions within the thresholds;
+    /// `None` keeps the rollout running until it is promoted manually
+    pub promote_after: Option<u64>,
+}
+
+impl Default for RolloutThresholds {
+    fn default() -> Self {
+        Self {
+            min_samples: 20,
+            max_error_rate_increase: 0.05,
+            max_latency_ratio: 1.5,
+            promote_after: Some(100),
+        }
+    }
+}
+
+impl RolloutThresholds {
+    fn validate(&self) -> ExecutorResult<()> {
+        if !(0.0..=1.0).contains(&self.max_error_rate_increase) {
+            return Err(ExecutorError::InvalidParameters(
+                "max_error_rate_increase must be between 0 and 1".to_string(),
+            ));
+        }
+        if self.max_latency_ratio < 1.0 {
+            return Err(ExecutorError::InvalidParameters("max_latency_ratio must be at least 1".to_string()));
+        }
+        if self.promote_after.is_some_and(|after| after < self.min_samples) {
+            return Err(ExecutorError::InvalidParameters("promote_after must be at least min_samples".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Outcomes of the executions routed to one arm
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ArmStats {
+    pub executions: u64,
+    pub failures: u64,
+    pub total_duration_ms: u64,
+}
+
+impl ArmStats {
+    /// Share of failed executions, 0 when nothing ran
+    pub fn error_rate(&self) -> f64 {
+        if self.executions == 0 {
+            0.0
+        } else {
+            self.failures as f64 / self.executions as f64
+        }
+    }
+
+    /// Mean execution duration, `None` when nothing ran
+    pub fn mean_duration_ms(&self) -> Option<f64> {
+        (self.executions > 0).then(|| self.total_duration_ms as f64 / self.executions as f64)
+    }
+}
+
+/// A rollout of a candidate version of a tool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolRollout {
+    pub id: String,
+    pub tool_id: ToolId,
+    pub stable_version: ToolVersion,
+    /// Tool definition executions routed to the candidate arm run
+    pub candidate: ToolInfo,
+    /// Share of executions routed to the candidate, 0-100
+    pub percentage: u8,
+    pub thresholds: RolloutThresholds,
+    pub status: RolloutStatus,
+    pub stable: ArmStats,
+    pub candidate_stats: ArmStats,
+    /// Why the rollout was promoted or rolled back
+    pub decision_reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ToolRollout {
+    /// Pick the arm for an execution
+    pub fn route(&self, execution_id: &ExecutionId, requested: Option<&ToolVersion>) -> RolloutArm {
+        match requested {
+            Some(version) if *version == self.candidate.version => RolloutArm::Candidate,
+            Some(_) => RolloutArm::Stable,
+            None if bucket(execution_id) < u64::from(self.percentage) => RolloutArm::Candidate,
+            None => RolloutArm::Stable,
+        }
+    }
+
+    /// Decide whether the candidate should be promoted or rolled back
+    pub fn evaluate(&self) -> Option<(RolloutStatus, String)> {
+        let candidate = &self.candidate_stats;
+        if self.status != RolloutStatus::Active || candidate.executions < self.thresholds.min_samples {
+            return None;
+        }
+
+        let increase = candidate.error_rate() - self.stable.error_rate();
+        if increase > self.thresholds.max_error_rate_increase {
+            return Some((RolloutStatus::RolledBack, format!(
+                "candidate error rate {:.1}% exceeds stable {:.1}% by more than {:.1} points",
+                candidate.error_rate() * 100.0,
+                self.stable.error_rate() * 100.0,
+                self.thresholds.max_error_rate_increase * 100.0,
+            )));
+        }
+
+        if let (Some(candidate_ms), Some(stable_ms)) = (candidate.mean_duration_ms(), self.stable.mean_duration_ms()) {
+            if stable_ms > 0.0 && candidate_ms > stable_ms * self.thresholds.max_latency_ratio {
+                return Some((RolloutStatus::RolledBack, format!(
+                    "candidate mean duration {:.0}ms exceeds {:.1}x stable {:.0}ms",
+                    candidate_ms, self.thresholds.max_latency_ratio, stable_ms,
+                )));
+            }
+        }
+
+        match self.thresholds.promote_after {
+            Some(after) if candidate.executions >= after => Some((RolloutStatus::Promoted, format!(
+                "candidate stayed within thresholds for {} executions", candidate.executions,
+            ))),
+            _ => None,
+        }
+    }
+}
+
+/// Stable 0-99 bucket of an execution id (FNV-1a)
+fn bucket(execution_id: &ExecutionId) -> u64 {
+    let hash = execution_id.as_str().bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    });
+    hash % 100
+}
+
+/// What the candidate's example test runs say about promoting it
+enum ExampleGate {
+    Passed,
+    /// The candidate has examples but no test run yet
+    Missing,
+    Failed(String),
+}
+
+/// Starts, routes and finishes rollouts
+#[derive(Clone)]
+pub struct RolloutManager {
+    store: Arc<dyn ExecutionStore>,
+    registry: Arc<dyn Registry>,
+}
+
+impl RolloutManager {
+    /// Create a rollout manager
+    pub fn new(store: Arc<dyn ExecutionStore>, registry: Arc<dyn Registry>) -> Self {
+        Self { store, registry }
+    }
+
+    /// Start rolling out `candidate` to `percentage` of the tool's executions
+    pub async fn start(
+        &self,
+        tool_id: &ToolId,
+        mut candidate: ToolInfo,
+        percentage: u8,
+        thresholds: RolloutThresholds,
+    ) -> ExecutorResult<ToolRollout> {
+        check_percentage(percentage)?;
+        thresholds.validate()?;
+        let stable = self.registry.get_tool(tool_id).await?;
+        if candidate.version == stable.version {
+            return Err(ExecutorError::InvalidParameters(format!(
+                "Candidate version {} is already the stable version", candidate.version
+            )));
+        }
+        if self.active(tool_id).await?.is_some() {
+            return Err(ExecutorError::Conflict(format!("Tool {} already has a rollout in progress", tool_id)));
+        }
+
+        let now = Utc::now();
+        candidate.id = tool_id.clone();
+        candidate.created_at = stable.created_at;
+        candidate.updated_at = now;
+        let rollout = ToolRollout {
+            id: uuid::Uuid::new_v4().to_string(),
+            tool_id: tool_id.clone(),
+            stable_version: stable.version,
+            candidate,
+            percentage,
+            thresholds,
+            status: RolloutStatus::Active,
+            stable: ArmStats::default(),
+            candidate_stats: ArmStats::default(),
+            decision_reason: None,
+            created_at: now,
+            updated_at: now,
+        };
+        self.store.save_rollout(&rollout).await?;
+        Ok(rollout)
+    }
+
+    /// Latest rollout of a tool, finished or not
+    pub async fn get(&self, tool_id: &ToolId) -> ExecutorResult<Option<ToolRollout>> {
+        self.store.latest_rollout(tool_id).await
+    }
+
+    /// Rollout in progress for a tool
+    pub async fn active(&self, tool_id: &ToolId) -> ExecutorResult<Option<ToolRollout>> {
+        Ok(self.get(tool_id).await?.filter(|rollout| rollout.status == RolloutStatus::Active))
+    }
+
+    /// Change the share of executions routed to the candidate
+    pub async fn set_percentage(&self, tool_id: &ToolId, percentage: u8) -> ExecutorResult<ToolRollout> {
+        check_percentage(percentage)?;
+        let mut rollout = self.require_active(tool_id).await?;
+        rollout.percentage = percentage;
+        rollout.updated_at = Utc::now();
+        self.store.save_rollout(&rollout).await?;
+        Ok(rollout)
+    }
+
+    /// Make the candidate the stable version
+    ///
+    /// A candidate with examples needs a passing test run of its version.
+    pub async fn promote(&self, tool_id: &ToolId, reason: &str) -> ExecutorResult<ToolRollout> {
+        let rollout = self.require_active(tool_id).await?;
+        match self.example_gate(&rollout).await? {
+            ExampleGate::Passed => self.finish(rollout, RolloutStatus::Promoted, reason.to_string()).await,
+            ExampleGate::Missing => Err(ExecutorError::Conflict(format!(
+                "Candidate version {} has not been tested against its examples", rollout.candidate.version
+            ))),
+            ExampleGate::Failed(failure) => Err(ExecutorError::Conflict(failure)),
+        }
+    }
+
+    /// Stop routing executions to the candidate
+    pub async fn rollback(&self, tool_id: &ToolId, reason: &str) -> ExecutorResult<ToolRollout> {
+        let rollout = self.require_active(tool_id).await?;
+        self.finish(rollout, RolloutStatus::RolledBack, reason.to_string()).await
+    }
+
+    /// Count an execution outcome and apply the automatic decision, if any
+    pub async fn record(
+        &self,
+        rollout: &ToolRollout,
+        arm: RolloutArm,
+        duration_ms: u64,
+        success: bool,
+    ) -> ExecutorResult<Option<ToolRollout>> {
+        let Some(updated) = self.store.record_rollout_outcome(&rollout.id, arm, duration_ms, success).await? else {
+            // Finished while the execution was running
+            return Ok(None);
+        };
+        let (status, reason) = match updated.evaluate() {
+            Some((RolloutStatus::Promoted, reason)) => match self.example_gate(&updated).await? {
+                ExampleGate::Passed => (RolloutStatus::Promoted, reason),
+                // Promoted by a later execution once a run passed
+                ExampleGate::Missing => return Ok(Some(updated)),
+                ExampleGate::Failed(failure) => (RolloutStatus::RolledBack, failure),
+            },
+            Some(decision) => decision,
+            None => return Ok(Some(updated)),
+        };
+        tracing::info!("Rollout {} of tool {}: {} ({})", updated.id, updated.tool_id, status.as_str(), reason);
+        self.finish(updated, status, reason).await.map(Some)
+    }
+
+    async fn example_gate(&self, rollout: &ToolRollout) -> ExecutorResult<ExampleGate> {
+        let candidate = &rollout.candidate;
+        if candidate.examples.is_empty() {
+            return Ok(ExampleGate::Passed);
+        }
+        Ok(match self.store.latest_test_run(&rollout.tool_id, &candidate.version).await? {
+            None => ExampleGate::Missing,
+            Some(run) if run.passed => ExampleGate::Passed,
+            Some(run) => ExampleGate::Failed(format!(
+                "candidate version {} failed {} of {} example tests",
+                candidate.version, run.failures(), run.results.len()
+            )),
+        })
+    }
+
+    async fn require_active(&self, tool_id: &ToolId) -> ExecutorResult<ToolRollout> {
+        self.active(tool_id).await?
+            .ok_or_else(|| ExecutorError::Conflict(format!("Tool {} has no rollout in progress", tool_id)))
+    }
+
+    /// Move an active rollout to `status`; promoting writes the candidate to the registry.
+    ///
+    /// The status change is claimed first so concurrent decisions apply once.
+    async fn finish(&self, mut rollout: ToolRollout, status: RolloutStatus, reason: String) -> ExecutorResult<ToolRollout> {
+        if !self.store.finish_rollout(&rollout.id, status, &reason).await? {
+            return Err(ExecutorError::Conflict(format!("Rollout {} is already finished", rollout.id)));
+        }
+
+        if status == RolloutStatus::Promoted {
+            let mut promoted = rollout.candidate.clone();
+            promoted.updated_at = Utc::now();
+            if let Err(e) = self.registry.update_tool(&rollout.tool_id, &promoted).await {
+                // Keep routing as before so the promotion can be retried
+                rollout.updated_at = Utc::now();
+                self.store.save_rollout(&rollout).await?;
+                return Err(e.into());
+            }
+        }
+
+        rollout.status = status;
+        rollout.decision_reason = Some(reason);
+        rollout.updated_at = Utc::now();
+        Ok(rollout)
+    }
+}
+
+fn check_percentage(percentage: u8) -> ExecutorResult<()> {
+    if percentage > 100 {
+        return Err(ExecutorError::InvalidParameters("percentage must be between 0 and 100".to_string()));
+    }
+    Ok(())
+}
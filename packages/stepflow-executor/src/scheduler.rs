@@ -1,17 +1,27 @@
 //! Task scheduler implementation
+//!
+//! By default tasks wait in an in-memory queue, so only the instance that scheduled a
+//! task can run it. With [`SchedulerConfig::shared_queue`] the `tasks` table is the
+//! queue: every instance claims tasks from it atomically, holds a lease on each task it
+//! runs and renews the leases with heartbeats. Tasks of an instance that stops
+//! heartbeating are claimed again by others once their leases expire. An instance
+//! claims at most as many tasks as it has idle workers, so busy instances leave work
+//! to idle ones. [`SchedulerConfig::task_queue`] moves the shared queue to another
+//! [`TaskQueue`] backend such as Redis, keeping the `tasks` table as the task record.
 
 use std::collections::{HashMap, VecDeque, BinaryHeap};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::{RwLock, Mutex};
 use tokio::time::sleep;
 use chrono::Utc;
 use async_trait::async_trait;
-use stepflow_core::*;
-use stepflow_database::SqliteDatabase;
 use crate::errors::*;
 use crate::execution_context::*;
-use crate::executor::{Scheduler, WorkerPool, TaskFilter, TaskInfo};
+use crate::executor::{ExecutionStore, Scheduler, WorkerPool, TaskFilter, TaskInfo};
+use crate::placement::unmet_constraints;
+use crate::queue::{StoreTaskQueue, TaskQueue};
 
 /// Scheduler configuration
 #[derive(Debug, Clone)]
@@ -22,6 +32,16 @@ pub struct SchedulerConfig {
     pub enable_priority_queue: bool,
     pub enable_fair_scheduling: bool,
     pub polling_interval: Duration,
+    /// Pull tasks from the shared `tasks` table instead of the in-memory queue
+    pub shared_queue: bool,
+    /// Identifies this instance as holder of task leases
+    pub instance_id: String,
+    /// How long a claimed task stays leased without a heartbeat
+    pub lease_duration: Duration,
+    /// How often leases of running tasks are renewed
+    pub heartbeat_interval: Duration,
+    /// Shared queue backend; implies a shared queue, defaults to the `tasks` table
+    pub task_queue: Option<Arc<dyn TaskQueue>>,
 }
 
 impl Default for SchedulerConfig {
@@ -33,6 +53,11 @@ impl Default for SchedulerConfig {
             enable_priority_queue: true,
             enable_fair_scheduling: true,
             polling_interval: Duration::from_millis(100),
+            shared_queue: false,
+            instance_id: uuid::Uuid::new_v4().to_string(),
+            lease_duration: Duration::from_secs(30),
+            heartbeat_interval: Duration::from_secs(10),
+            task_queue: None,
         }
     }
 }
@@ -67,9 +92,60 @@ impl Ord for PriorityTask {
     }
 }
 
+/// Where a queued task stands against the worker pool
+enum Placement {
+    /// An idle worker can run the task
+    Ready,
+    /// Only busy workers can run the task
+    Wait,
+    /// No worker can run the task; holds the unmet constraints
+    Unschedulable(Vec<String>),
+}
+
+fn placement(pool: &PoolStatus, task: &Task) -> Placement {
+    let options = &task.execution_request.options;
+    if let Some(unmet) = unmet_constraints(&pool.worker_profiles, options) {
+        return Placement::Unschedulable(unmet);
+    }
+    if pool.idle_profiles.iter().any(|profile| profile.satisfies(options)) {
+        Placement::Ready
+    } else {
+        Placement::Wait
+    }
+}
+
+/// Tasks taken off the queue because no worker can run them
+type Rejected = Vec<(Task, Vec<String>)>;
+
+/// Shared queue activity of one instance
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct SharedQueueMetrics {
+    pub instance_id: String,
+    /// Tasks this instance is running under a lease
+    pub running_tasks: usize,
+    pub claimed_total: u64,
+    /// Claimed tasks handed back because no idle worker here could run them
+    pub released_total: u64,
+    pub completed_total: u64,
+    pub failed_total: u64,
+    /// Tasks whose lease expired and was taken over by another instance
+    pub lost_total: u64,
+    /// Running tasks per instance across the deployment
+    pub running_by_instance: HashMap<String, usize>,
+}
+
+#[derive(Default)]
+struct SharedQueueCounters {
+    claimed: AtomicU64,
+    released: AtomicU64,
+    completed: AtomicU64,
+    failed: AtomicU64,
+    lost: AtomicU64,
+}
+
 /// Scheduler implementation
 pub struct SchedulerImpl {
-    db: Arc<SqliteDatabase>,
+    store: Arc<dyn ExecutionStore>,
     worker_pool: Arc<dyn WorkerPool>,
     config: SchedulerConfig,
     
@@ -82,6 +158,16 @@ pub struct SchedulerImpl {
     // Task status tracking
     task_status: Arc<RwLock<HashMap<TaskId, TaskStatus>>>,
     
+    // Unmet constraints of unschedulable tasks
+    unschedulable: Arc<RwLock<HashMap<TaskId, Vec<String>>>>,
+    
+    // Shared queue, if any
+    shared_queue: Option<Arc<dyn TaskQueue>>,
+    
+    // Tasks claimed from the shared queue and the work running them
+    claimed: Arc<RwLock<HashMap<TaskId, WorkId>>>,
+    counters: Arc<SharedQueueCounters>,
+    
     // Running state
     running: Arc<RwLock<bool>>,
 }
@@ -89,21 +175,33 @@ pub struct SchedulerImpl {
 impl SchedulerImpl {
     /// Create a new scheduler
     pub fn new(
-        db: Arc<SqliteDatabase>,
+        store: Arc<dyn ExecutionStore>,
         worker_pool: Arc<dyn WorkerPool>,
         config: SchedulerConfig,
     ) -> Self {
+        let shared_queue = config.task_queue.clone().or_else(|| {
+            config.shared_queue.then(|| Arc::new(StoreTaskQueue::new(store.clone())) as Arc<dyn TaskQueue>)
+        });
         Self {
-            db,
+            store,
             worker_pool,
             config,
+            shared_queue,
             priority_queue: Arc::new(Mutex::new(BinaryHeap::new())),
             fifo_queue: Arc::new(Mutex::new(VecDeque::new())),
             task_status: Arc::new(RwLock::new(HashMap::new())),
+            unschedulable: Arc::new(RwLock::new(HashMap::new())),
+            claimed: Arc::new(RwLock::new(HashMap::new())),
+            counters: Arc::new(SharedQueueCounters::default()),
             running: Arc::new(RwLock::new(false)),
         }
     }
     
+    /// Whether tasks go through a queue shared with other instances
+    pub fn is_shared(&self) -> bool {
+        self.shared_queue.is_some()
+    }
+    
     /// Start the scheduler
     pub async fn start(&self) -> SchedulerResult<()> {
         let mut running = self.running.write().await;
@@ -119,6 +217,13 @@ impl SchedulerImpl {
             scheduler.scheduling_loop().await;
         });
         
+        if self.shared_queue.is_some() {
+            let scheduler = self.clone();
+            tokio::spawn(async move {
+                scheduler.heartbeat_loop().await;
+            });
+        }
+        
         Ok(())
     }
     
@@ -140,6 +245,16 @@ impl SchedulerImpl {
         }
     }
     
+    /// Renew leases of claimed tasks and record finished ones
+    async fn heartbeat_loop(&self) {
+        while *self.running.read().await {
+            sleep(self.config.heartbeat_interval).await;
+            if let Err(e) = self.heartbeat().await {
+                tracing::error!("Error renewing task leases: {}", e);
+            }
+        }
+    }
+    
     /// Process pending tasks
     async fn process_tasks(&self) -> SchedulerResult<()> {
         let pool_status = self.worker_pool.get_pool_status().await
@@ -150,13 +265,26 @@ impl SchedulerImpl {
             return Ok(());
         }
         
-        // Get next task to process
-        let task = if self.config.enable_priority_queue {
-            self.get_next_priority_task().await
+        if let Some(queue) = &self.shared_queue {
+            return self.process_shared_tasks(queue.as_ref(), &pool_status).await;
+        }
+        
+        // Get the next task an idle worker can take
+        let (task, rejected) = if self.config.enable_priority_queue {
+            self.get_next_priority_task(&pool_status).await
         } else {
-            self.get_next_fifo_task().await
+            self.get_next_fifo_task(&pool_status).await
         };
         
+        for (task, unmet) in rejected {
+            tracing::warn!("Task {} is unschedulable, unmet constraints: {}", task.id, unmet.join(", "));
+            self.task_status.write().await.insert(task.id.clone(), TaskStatus::Unschedulable);
+            self.unschedulable.write().await.insert(task.id.clone(), unmet);
+            if let Err(e) = self.update_task_status(&task.id, TaskStatus::Unschedulable).await {
+                tracing::error!("Failed to persist unschedulable task {}: {}", task.id, e);
+            }
+        }
+        
         if let Some(task) = task {
             // Submit to worker pool
             let work = Work {
@@ -184,16 +312,143 @@ impl SchedulerImpl {
         Ok(())
     }
     
-    /// Get next task from priority queue
-    async fn get_next_priority_task(&self) -> Option<Task> {
+    /// Claim as many tasks from the shared queue as there are idle workers and start
+    /// the ones a worker here can run; the others go back to the queue for other instances
+    async fn process_shared_tasks(&self, queue: &dyn TaskQueue, pool: &PoolStatus) -> SchedulerResult<()> {
+        let tasks = queue.claim(&self.config.instance_id, pool.idle_workers, self.lease_until()).await
+            .map_err(store_error)?;
+        for task in tasks {
+            self.counters.claimed.fetch_add(1, Ordering::Relaxed);
+            if !matches!(placement(pool, &task), Placement::Ready) {
+                self.counters.released.fetch_add(1, Ordering::Relaxed);
+                queue.release(&self.config.instance_id, &task.id).await.map_err(store_error)?;
+                continue;
+            }
+            
+            let work = Work {
+                id: WorkId::new(),
+                task: task.clone(),
+                assigned_worker: None,
+                started_at: None,
+            };
+            match self.worker_pool.submit_work(work).await {
+                Ok(work_id) => {
+                    self.claimed.write().await.insert(task.id.clone(), work_id);
+                    self.task_status.write().await.insert(task.id.clone(), TaskStatus::Running);
+                    self.update_task_status(&task.id, TaskStatus::Running).await?;
+                }
+                Err(e) => {
+                    tracing::error!("Failed to submit work for task {}: {}", task.id, e);
+                    self.counters.released.fetch_add(1, Ordering::Relaxed);
+                    queue.release(&self.config.instance_id, &task.id).await.map_err(store_error)?;
+                }
+            }
+        }
+        Ok(())
+    }
+    
+    /// Record claimed tasks whose work finished and renew the leases of the rest
+    async fn heartbeat(&self) -> SchedulerResult<()> {
+        let Some(queue) = &self.shared_queue else {
+            return Ok(());
+        };
+        let claimed: Vec<(TaskId, WorkId)> = self.claimed.read().await
+            .iter()
+            .map(|(task_id, work_id)| (task_id.clone(), work_id.clone()))
+            .collect();
+        let mut renew = Vec::new();
+        for (task_id, work_id) in claimed {
+            let status = match self.worker_pool.get_work_status(&work_id).await {
+                Ok(WorkStatus::Completed) => TaskStatus::Completed,
+                Ok(WorkStatus::Failed) | Err(_) => TaskStatus::Failed,
+                Ok(WorkStatus::Cancelled) => TaskStatus::Cancelled,
+                Ok(_) => {
+                    renew.push(task_id);
+                    continue;
+                }
+            };
+            let counter = if status == TaskStatus::Completed { &self.counters.completed } else { &self.counters.failed };
+            counter.fetch_add(1, Ordering::Relaxed);
+            self.update_task_status(&task_id, status).await?;
+            queue.ack(&self.config.instance_id, &task_id).await.map_err(store_error)?;
+            self.task_status.write().await.insert(task_id.clone(), status);
+            self.claimed.write().await.remove(&task_id);
+        }
+        
+        let lost = queue.renew(&self.config.instance_id, &renew, self.lease_until()).await
+            .map_err(store_error)?;
+        for task_id in lost {
+            tracing::warn!("Lost the lease on task {}; another instance may run it again", task_id);
+            self.counters.lost.fetch_add(1, Ordering::Relaxed);
+            self.claimed.write().await.remove(&task_id);
+        }
+        Ok(())
+    }
+    
+    fn lease_until(&self) -> chrono::DateTime<Utc> {
+        Utc::now() + chrono::Duration::from_std(self.config.lease_duration).unwrap_or(chrono::Duration::seconds(30))
+    }
+    
+    /// Shared queue activity of this instance and running tasks per instance
+    pub async fn shared_queue_metrics(&self) -> SchedulerResult<SharedQueueMetrics> {
+        let running_by_instance = match &self.shared_queue {
+            Some(queue) => queue.running_by_instance().await.map_err(store_error)?,
+            None => HashMap::new(),
+        };
+        Ok(SharedQueueMetrics {
+            instance_id: self.config.instance_id.clone(),
+            running_tasks: self.claimed.read().await.len(),
+            claimed_total: self.counters.claimed.load(Ordering::Relaxed),
+            released_total: self.counters.released.load(Ordering::Relaxed),
+            completed_total: self.counters.completed.load(Ordering::Relaxed),
+            failed_total: self.counters.failed.load(Ordering::Relaxed),
+            lost_total: self.counters.lost.load(Ordering::Relaxed),
+            running_by_instance,
+        })
+    }
+    
+    /// Get the highest priority task an idle worker can run
+    async fn get_next_priority_task(&self, pool: &PoolStatus) -> (Option<Task>, Rejected) {
         let mut queue = self.priority_queue.lock().await;
-        queue.pop().map(|pt| pt.task)
+        let mut skipped = Vec::new();
+        let mut rejected = Vec::new();
+        let mut next = None;
+        while let Some(pt) = queue.pop() {
+            match placement(pool, &pt.task) {
+                Placement::Ready => {
+                    next = Some(pt.task);
+                    break;
+                }
+                Placement::Wait => skipped.push(pt),
+                Placement::Unschedulable(unmet) => rejected.push((pt.task, unmet)),
+            }
+        }
+        queue.extend(skipped);
+        (next, rejected)
     }
     
-    /// Get next task from FIFO queue
-    async fn get_next_fifo_task(&self) -> Option<Task> {
+    /// Get the oldest task an idle worker can run
+    async fn get_next_fifo_task(&self, pool: &PoolStatus) -> (Option<Task>, Rejected) {
         let mut queue = self.fifo_queue.lock().await;
-        queue.pop_front()
+        let mut rejected = Vec::new();
+        let mut index = 0;
+        while index < queue.len() {
+            match placement(pool, &queue[index]) {
+                Placement::Ready => return (queue.remove(index), rejected),
+                Placement::Wait => index += 1,
+                Placement::Unschedulable(unmet) => {
+                    if let Some(task) = queue.remove(index) {
+                        rejected.push((task, unmet));
+                    }
+                }
+            }
+        }
+        (None, rejected)
+    }
+    
+    /// Tasks taken off the queue because no worker can run them, with their unmet constraints
+    pub async fn unschedulable_tasks(&self) -> HashMap<TaskId, Vec<String>> {
+        self.unschedulable.read().await.clone()
     }
     
     /// Add task to appropriate queue
@@ -221,56 +476,37 @@ impl SchedulerImpl {
         Ok(())
     }
     
-    /// Store task in database
+    /// Persist a newly scheduled task
     async fn store_task(&self, task: &Task) -> SchedulerResult<()> {
-        let task_json = serde_json::to_string(task)
-            .map_err(|e| SchedulerError::InternalError(e.to_string()))?;
-        let execution_request_json = serde_json::to_string(&task.execution_request)
-            .map_err(|e| SchedulerError::InternalError(e.to_string()))?;
-        
-        let params = vec![
-            serde_json::Value::String(task.id.to_string()),
-            serde_json::Value::String(task.execution_request.tool_id.to_string()),
-            serde_json::Value::String(execution_request_json),
-            serde_json::Value::String(format!("{:?}", task.priority)),
-            serde_json::Value::String("Pending".to_string()),
-            serde_json::Value::String(task_json),
-            serde_json::Value::String(task.created_at.to_rfc3339()),
-        ];
-        
-        self.db.execute(
-            "INSERT INTO tasks (id, tool_id, execution_request, priority, status, task_data, created_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
-            &params
-        ).await.map_err(|e| SchedulerError::DatabaseError(e.to_string()))?;
-        
-        Ok(())
+        self.store.save_task(task).await.map_err(store_error)
     }
     
-    /// Update task status in database
+    /// Update the persisted task status
     async fn update_task_status(&self, task_id: &TaskId, status: TaskStatus) -> SchedulerResult<()> {
-        let params = vec![
-            serde_json::Value::String(format!("{:?}", status)),
-            serde_json::Value::String(task_id.to_string()),
-        ];
-        
-        self.db.execute(
-            "UPDATE tasks SET status = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
-            &params
-        ).await.map_err(|e| SchedulerError::DatabaseError(e.to_string()))?;
-        
-        Ok(())
+        self.store.update_task_status(task_id, status).await.map_err(store_error)
+    }
+}
+
+fn store_error(error: ExecutorError) -> SchedulerError {
+    match error {
+        ExecutorError::DatabaseError(message) => SchedulerError::DatabaseError(message),
+        other => SchedulerError::InternalError(other.to_string()),
     }
 }
 
 impl Clone for SchedulerImpl {
     fn clone(&self) -> Self {
         Self {
-            db: self.db.clone(),
+            store: self.store.clone(),
             worker_pool: self.worker_pool.clone(),
             config: self.config.clone(),
             priority_queue: self.priority_queue.clone(),
             fifo_queue: self.fifo_queue.clone(),
             task_status: self.task_status.clone(),
+            unschedulable: self.unschedulable.clone(),
+            shared_queue: self.shared_queue.clone(),
+            claimed: self.claimed.clone(),
+            counters: self.counters.clone(),
             running: self.running.clone(),
         }
     }
@@ -288,8 +524,13 @@ impl Scheduler for SchedulerImpl {
         // Store in database
         self.store_task(&task).await?;
         
-        // Add to queue
-        self.add_task_to_queue(task.clone()).await?;
+        // Add to queue; the shared queue is the tasks table itself
+        if let Some(queue) = &self.shared_queue {
+            self.update_task_status(&task.id, TaskStatus::Queued).await?;
+            queue.push(&task).await.map_err(store_error)?;
+        } else {
+            self.add_task_to_queue(task.clone()).await?;
+        }
         
         // Update status
         let mut status = self.task_status.write().await;
@@ -0,0 +1,584 @@
+//! SQLite execution store
+//!
+//! The database-backed [`ExecutionStore`]: tenants and tool configuration come
+//! from their repositories, transitions go through the [`TimelineRecorder`],
+//! and async results, executions, tasks, rollouts and tool test runs live in
+//! their own tables.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use stepflow_core::*;
+use stepflow_database::utils::param;
+use stepflow_database::{SqliteDatabase, TenantRepository, ToolConfigRepository};
+use crate::annotations::{ExecutionAnnotations, ExecutionNote};
+use crate::errors::*;
+use crate::execution_context::*;
+use crate::executor::ExecutionStore;
+use crate::result_chunks::{self, OutputLimits};
+use crate::rollout::{ArmStats, RolloutArm, RolloutStatus, ToolRollout};
+use crate::timeline::{ExecutionState, ExecutionTimeline, TimelineRecorder};
+use crate::tool_tests::{TestTrigger, ToolTestRun};
+
+/// SQLite-backed execution store
+#[derive(Clone)]
+pub struct SqliteExecutionStore {
+    db: Arc<SqliteDatabase>,
+    timeline: Arc<TimelineRecorder>,
+    chunk_size: usize,
+}
+
+impl SqliteExecutionStore {
+    /// Create a store on `db`
+    pub fn new(db: Arc<SqliteDatabase>) -> Self {
+        Self {
+            timeline: Arc::new(TimelineRecorder::new(db.clone())),
+            db,
+            chunk_size: OutputLimits::default().chunk_size,
+        }
+    }
+
+    /// Store async outputs larger than `chunk_size` bytes in chunks
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    async fn execute(&self, sql: &str, params: &[Value]) -> ExecutorResult<QueryResult> {
+        self.db.execute(sql, params).await
+            .map_err(|e| ExecutorError::DatabaseError(e.to_string()))
+    }
+}
+
+const ROLLOUT_COLUMNS: &str = "id, tool_id, stable_version, candidate, percentage, thresholds, status, \
+    stable_executions, stable_failures, stable_duration_ms, \
+    candidate_executions, candidate_failures, candidate_duration_ms, decision_reason, created_at, updated_at";
+
+const TEST_RUN_COLUMNS: &str = "id, tool_id, version, trigger, passed, results, started_at, finished_at";
+
+/// Queued tasks and running tasks whose lease expired; binds the current time
+const CLAIMABLE_TASK: &str = "status = 'Queued' OR (status = 'Running' AND lease_until < ?)";
+
+/// Task priorities are stored by name
+const TASK_PRIORITY_RANK: &str =
+    "CASE priority WHEN 'Critical' THEN 4 WHEN 'High' THEN 3 WHEN 'Normal' THEN 2 ELSE 1 END";
+
+fn parse_rollout(row: &HashMap<String, Value>) -> ExecutorResult<ToolRollout> {
+    let text = |key: &str| row.get(key).and_then(|v| v.as_str());
+    let count = |key: &str| row.get(key).and_then(|v| v.as_i64()).unwrap_or(0).max(0) as u64;
+    let corrupt = |key: &str| ExecutorError::DatabaseError(format!("Invalid rollout column {}", key));
+
+    Ok(ToolRollout {
+        id: text("id").ok_or_else(|| corrupt("id"))?.to_string(),
+        tool_id: ToolId::from_string(text("tool_id").unwrap_or("").to_string()),
+        stable_version: serde_json::from_str(text("stable_version").ok_or_else(|| corrupt("stable_version"))?)?,
+        candidate: serde_json::from_str(text("candidate").ok_or_else(|| corrupt("candidate"))?)?,
+        percentage: count("percentage").min(100) as u8,
+        thresholds: serde_json::from_str(text("thresholds").ok_or_else(|| corrupt("thresholds"))?)?,
+        status: text("status").and_then(RolloutStatus::parse).ok_or_else(|| corrupt("status"))?,
+        stable: ArmStats {
+            executions: count("stable_executions"),
+            failures: count("stable_failures"),
+            total_duration_ms: count("stable_duration_ms"),
+        },
+        candidate_stats: ArmStats {
+            executions: count("candidate_executions"),
+            failures: count("candidate_failures"),
+            total_duration_ms: count("candidate_duration_ms"),
+        },
+        decision_reason: text("decision_reason").map(str::to_string),
+        created_at: parse_time(text("created_at")).unwrap_or_else(Utc::now),
+        updated_at: parse_time(text("updated_at")).unwrap_or_else(Utc::now),
+    })
+}
+
+fn parse_test_run(row: &HashMap<String, Value>) -> ExecutorResult<ToolTestRun> {
+    let text = |key: &str| row.get(key).and_then(|v| v.as_str());
+    let corrupt = |key: &str| ExecutorError::DatabaseError(format!("Invalid test run column {}", key));
+
+    Ok(ToolTestRun {
+        id: text("id").ok_or_else(|| corrupt("id"))?.to_string(),
+        tool_id: ToolId::from_string(text("tool_id").unwrap_or("").to_string()),
+        version: serde_json::from_str(text("version").ok_or_else(|| corrupt("version"))?)?,
+        trigger: text("trigger").and_then(TestTrigger::parse).ok_or_else(|| corrupt("trigger"))?,
+        passed: row.get("passed").and_then(|v| v.as_i64()).unwrap_or(0) != 0,
+        results: serde_json::from_str(text("results").ok_or_else(|| corrupt("results"))?)?,
+        started_at: parse_time(text("started_at")).unwrap_or_else(Utc::now),
+        finished_at: parse_time(text("finished_at")).unwrap_or_else(Utc::now),
+    })
+}
+
+fn parse_time(value: Option<&str>) -> Option<DateTime<Utc>> {
+    value
+        .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+#[async_trait::async_trait]
+impl ExecutionStore for SqliteExecutionStore {
+    async fn get_tenant(&self, tenant_id: &TenantId) -> ExecutorResult<Option<TenantInfo>> {
+        TenantRepository::new(self.db.as_ref().clone())
+            .get_tenant(tenant_id)
+            .await
+            .map_err(|e| ExecutorError::DatabaseError(e.to_string()))
+    }
+
+    async fn get_tool_config(&self, tenant_id: &TenantId, tool_id: &ToolId) -> ExecutorResult<Option<ToolConfig>> {
+        ToolConfigRepository::new(self.db.as_ref().clone())
+            .get_config(tenant_id, tool_id)
+            .await
+            .map_err(|e| ExecutorError::DatabaseError(e.to_string()))
+    }
+
+    async fn record_event(
+        &self,
+        execution_id: &ExecutionId,
+        tool_id: Option<&ToolId>,
+        tenant_id: Option<&str>,
+        state: ExecutionState,
+        worker_id: Option<&str>,
+        detail: Option<&str>,
+    ) -> ExecutorResult<()> {
+        self.timeline.record(execution_id, tool_id, tenant_id, state, worker_id, detail).await
+    }
+
+    async fn timeline(&self, execution_id: &ExecutionId) -> ExecutorResult<Option<ExecutionTimeline>> {
+        self.timeline.timeline(execution_id).await
+    }
+
+    async fn store_execution_result(&self, execution_id: &ExecutionId, result: &ExecutionResult) -> ExecutorResult<()> {
+        let sql = r#"
+            INSERT INTO execution_results (execution_id, success, output_data, error, logs, metrics, metadata, created_at, output_chunks)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#;
+
+        let (output_data, output_chunks) = result_chunks::write_output(
+            &self.db,
+            execution_id,
+            serde_json::to_string(&result.output)?,
+            self.chunk_size,
+        ).await?;
+        let params = vec![
+            Value::String(execution_id.to_string()),
+            Value::Bool(result.success),
+            output_data,
+            result.error.clone().map(Value::String).unwrap_or(Value::Null),
+            serde_json::to_value(&result.logs).unwrap_or(Value::Array(vec![])),
+            serde_json::to_value(&result.metrics).unwrap_or(Value::Object(serde_json::Map::new())),
+            serde_json::to_value(&result.metadata).unwrap_or(Value::Object(serde_json::Map::new())),
+            Value::String(Utc::now().to_rfc3339()),
+            Value::from(output_chunks),
+        ];
+
+        self.execute(sql, &params).await?;
+        Ok(())
+    }
+
+    async fn has_execution_result(&self, execution_id: &ExecutionId) -> ExecutorResult<bool> {
+        let result = self.execute(
+            "SELECT success FROM execution_results WHERE execution_id = ?",
+            &[Value::String(execution_id.to_string())],
+        ).await?;
+        Ok(!result.rows.is_empty())
+    }
+
+    async fn list_executions(&self, filter: Option<ExecutionFilter>) -> ExecutorResult<Vec<ExecutionInfo>> {
+        let mut sql = "SELECT execution_id, tool_id, status, created_at, started_at, completed_at, user_id, tenant_id FROM executions WHERE 1=1".to_string();
+        let mut params = Vec::new();
+
+        if let Some(filter) = filter {
+            if let Some(tool_id) = filter.tool_id {
+                sql.push_str(" AND tool_id = ?");
+                params.push(Value::String(tool_id.to_string()));
+            }
+
+            if let Some(status) = filter.status {
+                sql.push_str(" AND status = ?");
+                params.push(Value::String(format!("{:?}", status)));
+            }
+
+            if let Some(user_id) = filter.user_id {
+                sql.push_str(" AND user_id = ?");
+                params.push(Value::String(user_id.to_string()));
+            }
+
+            if let Some(tenant_id) = filter.tenant_id {
+                sql.push_str(" AND tenant_id = ?");
+                params.push(Value::String(tenant_id.to_string()));
+            }
+
+            if let Some(started_after) = filter.started_after {
+                sql.push_str(" AND started_at >= ?");
+                params.push(Value::String(started_after.to_rfc3339()));
+            }
+
+            if let Some(started_before) = filter.started_before {
+                sql.push_str(" AND started_at <= ?");
+                params.push(Value::String(started_before.to_rfc3339()));
+            }
+
+            for (key, value) in filter.labels {
+                sql.push_str(" AND execution_id IN (SELECT execution_id FROM execution_labels WHERE key = ? AND value = ?)");
+                params.push(param::text(key));
+                params.push(param::text(value));
+            }
+        }
+
+        let result = self.execute(&sql, &params).await?;
+
+        let mut executions = Vec::new();
+        for row in result.rows {
+            let text = |key: &str| row.get(key).and_then(|v| v.as_str());
+            let status = match text("status").unwrap_or("Pending") {
+                "Running" => ExecutionStatus::Running,
+                "Completed" => ExecutionStatus::Completed,
+                "Failed" => ExecutionStatus::Failed,
+                "Cancelled" => ExecutionStatus::Cancelled,
+                _ => ExecutionStatus::Pending,
+            };
+
+            executions.push(ExecutionInfo {
+                execution_id: ExecutionId::from_string(text("execution_id").unwrap_or("").to_string()),
+                tool_id: ToolId::from_string(text("tool_id").unwrap_or("").to_string()),
+                status,
+                created_at: parse_time(text("created_at")).unwrap_or_else(Utc::now),
+                started_at: parse_time(text("started_at")),
+                completed_at: parse_time(text("completed_at")),
+                user_id: text("user_id").unwrap_or("").to_string(),
+                tenant_id: text("tenant_id").unwrap_or("").to_string(),
+                labels: HashMap::new(),
+            });
+        }
+
+        if !executions.is_empty() {
+            let placeholders = vec!["?"; executions.len()].join(", ");
+            let ids: Vec<Value> = executions.iter().map(|execution| param::text(execution.execution_id.as_str())).collect();
+            let labels = self.execute(
+                &format!("SELECT execution_id, key, value FROM execution_labels WHERE execution_id IN ({})", placeholders),
+                &ids,
+            ).await?;
+            let mut by_execution: HashMap<String, HashMap<String, String>> = HashMap::new();
+            for row in labels.rows {
+                let text = |key: &str| row.get(key).and_then(|v| v.as_str()).unwrap_or("").to_string();
+                by_execution.entry(text("execution_id")).or_default().insert(text("key"), text("value"));
+            }
+            for execution in &mut executions {
+                execution.labels = by_execution.remove(execution.execution_id.as_str()).unwrap_or_default();
+            }
+        }
+
+        Ok(executions)
+    }
+
+    async fn set_labels(&self, execution_id: &ExecutionId, tenant_id: &str, labels: &HashMap<String, String>) -> ExecutorResult<()> {
+        let sql = r#"
+            INSERT INTO execution_labels (execution_id, tenant_id, key, value)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT (execution_id, key) DO UPDATE SET value = excluded.value
+        "#;
+        for (key, value) in labels {
+            self.execute(sql, &[
+                param::text(execution_id.as_str()),
+                param::text(tenant_id),
+                param::text(key.as_str()),
+                param::text(value.as_str()),
+            ]).await?;
+        }
+        Ok(())
+    }
+
+    async fn remove_label(&self, execution_id: &ExecutionId, key: &str) -> ExecutorResult<bool> {
+        let result = self.execute(
+            "DELETE FROM execution_labels WHERE execution_id = ? AND key = ?",
+            &[param::text(execution_id.as_str()), param::text(key)],
+        ).await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    async fn add_note(&self, tenant_id: &str, note: &ExecutionNote) -> ExecutorResult<()> {
+        self.execute(
+            "INSERT INTO execution_notes (id, execution_id, tenant_id, author, body, created_at) VALUES (?, ?, ?, ?, ?, ?)",
+            &[
+                param::text(note.id.as_str()),
+                param::text(note.execution_id.as_str()),
+                param::text(tenant_id),
+                param::text(note.author.as_str()),
+                param::text(note.body.as_str()),
+                param::text(note.created_at.to_rfc3339()),
+            ],
+        ).await?;
+        Ok(())
+    }
+
+    async fn annotations(&self, execution_id: &ExecutionId) -> ExecutorResult<ExecutionAnnotations> {
+        let id = [param::text(execution_id.as_str())];
+        let labels = self.execute("SELECT key, value FROM execution_labels WHERE execution_id = ?", &id).await?;
+        let notes = self.execute(
+            "SELECT id, author, body, created_at FROM execution_notes WHERE execution_id = ? ORDER BY created_at, rowid",
+            &id,
+        ).await?;
+
+        let mut annotations = ExecutionAnnotations::default();
+        for row in labels.rows {
+            let text = |key: &str| row.get(key).and_then(|v| v.as_str()).unwrap_or("").to_string();
+            annotations.labels.insert(text("key"), text("value"));
+        }
+        for row in notes.rows {
+            let text = |key: &str| row.get(key).and_then(|v| v.as_str());
+            annotations.notes.push(ExecutionNote {
+                id: text("id").unwrap_or("").to_string(),
+                execution_id: execution_id.clone(),
+                author: text("author").unwrap_or("").to_string(),
+                body: text("body").unwrap_or("").to_string(),
+                created_at: parse_time(text("created_at")).unwrap_or_else(Utc::now),
+            });
+        }
+        Ok(annotations)
+    }
+
+    async fn save_task(&self, task: &Task) -> ExecutorResult<()> {
+        let task_json = serde_json::to_string(task)?;
+        let execution_request_json = serde_json::to_string(&task.execution_request)?;
+
+        let params = vec![
+            param::text(task.id.to_string()),
+            param::text(task.execution_request.tool_id.to_string()),
+            param::text(execution_request_json),
+            param::text(format!("{:?}", task.priority)),
+            param::text("Pending"),
+            param::text(task_json),
+            param::timestamp(&task.created_at),
+        ];
+
+        self.execute(
+            "INSERT INTO tasks (id, tool_id, execution_request, priority, status, task_data, created_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+            &params,
+        ).await?;
+        Ok(())
+    }
+
+    async fn update_task_status(&self, task_id: &TaskId, status: TaskStatus) -> ExecutorResult<()> {
+        let params = vec![
+            param::text(format!("{:?}", status)),
+            param::text(task_id.to_string()),
+        ];
+
+        self.execute(
+            "UPDATE tasks SET status = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+            &params,
+        ).await?;
+        Ok(())
+    }
+
+    async fn claim_tasks(&self, instance_id: &str, limit: usize, lease_until: DateTime<Utc>) -> ExecutorResult<Vec<Task>> {
+        let now = Utc::now();
+        // Other instances race for the same rows, so look at more candidates than needed
+        // and claim them one by one with a conditional update
+        let candidates = self.execute(
+            &format!("SELECT id FROM tasks WHERE {} ORDER BY {} DESC, created_at ASC LIMIT ?", CLAIMABLE_TASK, TASK_PRIORITY_RANK),
+            &[param::timestamp(&now), param::int(limit.saturating_mul(2) as i64)],
+        ).await?;
+
+        let mut claimed = Vec::new();
+        for id in candidates.rows.iter().filter_map(|row| row.get("id")?.as_str()) {
+            if claimed.len() >= limit {
+                break;
+            }
+            let sql = format!(
+                "UPDATE tasks SET status = 'Running', claimed_by = ?, lease_until = ?, heartbeat_at = ?, \
+                 started_at = COALESCE(started_at, ?), attempts = attempts + 1, updated_at = ? \
+                 WHERE id = ? AND ({})",
+                CLAIMABLE_TASK,
+            );
+            let params = vec![
+                param::text(instance_id),
+                param::timestamp(&lease_until),
+                param::timestamp(&now),
+                param::timestamp(&now),
+                param::timestamp(&now),
+                param::text(id),
+                param::timestamp(&now),
+            ];
+            if self.execute(&sql, &params).await?.rows_affected != 1 {
+                continue;
+            }
+            let result = self.execute("SELECT task_data FROM tasks WHERE id = ?", &[param::text(id)]).await?;
+            if let Some(data) = result.rows.first().and_then(|row| row.get("task_data")).and_then(|v| v.as_str()) {
+                claimed.push(serde_json::from_str(data)?);
+            }
+        }
+        Ok(claimed)
+    }
+
+    async fn renew_task_leases(&self, instance_id: &str, task_ids: &[TaskId], lease_until: DateTime<Utc>) -> ExecutorResult<Vec<TaskId>> {
+        let now = Utc::now();
+        let mut lost = Vec::new();
+        for task_id in task_ids {
+            let params = vec![
+                param::timestamp(&lease_until),
+                param::timestamp(&now),
+                param::timestamp(&now),
+                param::text(task_id.to_string()),
+                param::text(instance_id),
+            ];
+            let result = self.execute(
+                "UPDATE tasks SET lease_until = ?, heartbeat_at = ?, updated_at = ? \
+                 WHERE id = ? AND claimed_by = ? AND status = 'Running'",
+                &params,
+            ).await?;
+            if result.rows_affected != 1 {
+                lost.push(task_id.clone());
+            }
+        }
+        Ok(lost)
+    }
+
+    async fn release_task(&self, instance_id: &str, task_id: &TaskId) -> ExecutorResult<()> {
+        // Handing a task back does not count as an attempt
+        let params = vec![
+            param::timestamp(&Utc::now()),
+            param::text(task_id.to_string()),
+            param::text(instance_id),
+        ];
+        self.execute(
+            "UPDATE tasks SET status = 'Queued', claimed_by = NULL, lease_until = NULL, \
+             attempts = MAX(attempts - 1, 0), updated_at = ? \
+             WHERE id = ? AND claimed_by = ? AND status = 'Running'",
+            &params,
+        ).await?;
+        Ok(())
+    }
+
+    async fn running_tasks_by_instance(&self) -> ExecutorResult<HashMap<String, usize>> {
+        let result = self.execute(
+            "SELECT claimed_by FROM tasks WHERE status = 'Running' AND lease_until >= ?",
+            &[param::timestamp(&Utc::now())],
+        ).await?;
+        let mut counts = HashMap::new();
+        for instance in result.rows.iter().filter_map(|row| row.get("claimed_by")?.as_str()) {
+            *counts.entry(instance.to_string()).or_insert(0) += 1;
+        }
+        Ok(counts)
+    }
+    
+    async fn latest_rollout(&self, tool_id: &ToolId) -> ExecutorResult<Option<ToolRollout>> {
+        let sql = format!(
+            "SELECT {} FROM tool_rollouts WHERE tool_id = ? ORDER BY created_at DESC LIMIT 1",
+            ROLLOUT_COLUMNS
+        );
+        let result = self.execute(&sql, &[param::text(tool_id.to_string())]).await?;
+        result.rows.first().map(parse_rollout).transpose()
+    }
+
+    async fn save_rollout(&self, rollout: &ToolRollout) -> ExecutorResult<()> {
+        let params = vec![
+            param::text(&rollout.id),
+            param::text(rollout.tool_id.to_string()),
+            param::text(serde_json::to_string(&rollout.stable_version)?),
+            param::text(serde_json::to_string(&rollout.candidate)?),
+            param::int(i64::from(rollout.percentage)),
+            param::text(serde_json::to_string(&rollout.thresholds)?),
+            param::text(rollout.status.as_str()),
+            param::opt_text(rollout.decision_reason.as_deref()),
+            param::timestamp(&rollout.created_at),
+            param::timestamp(&rollout.updated_at),
+        ];
+
+        // Outcome counters are only ever moved by record_rollout_outcome
+        self.execute(
+            r#"
+            INSERT INTO tool_rollouts (id, tool_id, stable_version, candidate, percentage, thresholds, status, decision_reason, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(id) DO UPDATE SET
+                percentage = excluded.percentage,
+                status = excluded.status,
+                decision_reason = excluded.decision_reason,
+                updated_at = excluded.updated_at
+            "#,
+            &params,
+        ).await?;
+        Ok(())
+    }
+
+    async fn record_rollout_outcome(
+        &self,
+        rollout_id: &str,
+        arm: RolloutArm,
+        duration_ms: u64,
+        success: bool,
+    ) -> ExecutorResult<Option<ToolRollout>> {
+        let prefix = arm.as_str();
+        let sql = format!(
+            "UPDATE tool_rollouts SET {p}_executions = {p}_executions + 1, {p}_failures = {p}_failures + ?, \
+             {p}_duration_ms = {p}_duration_ms + ?, updated_at = ? WHERE id = ? AND status = 'active'",
+            p = prefix
+        );
+        let params = vec![
+            param::int(i64::from(!success)),
+            param::int(duration_ms.min(i64::MAX as u64) as i64),
+            param::timestamp(&Utc::now()),
+            param::text(rollout_id),
+        ];
+        if self.execute(&sql, &params).await?.rows_affected == 0 {
+            return Ok(None);
+        }
+
+        let sql = format!("SELECT {} FROM tool_rollouts WHERE id = ?", ROLLOUT_COLUMNS);
+        let result = self.execute(&sql, &[param::text(rollout_id)]).await?;
+        result.rows.first().map(parse_rollout).transpose()
+    }
+
+    async fn finish_rollout(&self, rollout_id: &str, status: RolloutStatus, reason: &str) -> ExecutorResult<bool> {
+        let params = vec![
+            param::text(status.as_str()),
+            param::text(reason),
+            param::timestamp(&Utc::now()),
+            param::text(rollout_id),
+        ];
+        let result = self.execute(
+            "UPDATE tool_rollouts SET status = ?, decision_reason = ?, updated_at = ? WHERE id = ? AND status = 'active'",
+            &params,
+        ).await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    async fn save_test_run(&self, run: &ToolTestRun) -> ExecutorResult<()> {
+        let params = vec![
+            param::text(&run.id),
+            param::text(run.tool_id.to_string()),
+            param::text(serde_json::to_string(&run.version)?),
+            param::text(run.trigger.as_str()),
+            param::flag(run.passed),
+            param::text(serde_json::to_string(&run.results)?),
+            param::timestamp(&run.started_at),
+            param::timestamp(&run.finished_at),
+        ];
+        self.execute(
+            "INSERT INTO tool_test_runs (id, tool_id, version, trigger, passed, results, started_at, finished_at) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            &params,
+        ).await?;
+        Ok(())
+    }
+
+    async fn latest_test_run(&self, tool_id: &ToolId, version: &ToolVersion) -> ExecutorResult<Option<ToolTestRun>> {
+        let sql = format!(
+            "SELECT {} FROM tool_test_runs WHERE tool_id = ? AND version = ? ORDER BY finished_at DESC LIMIT 1",
+            TEST_RUN_COLUMNS
+        );
+        let params = vec![param::text(tool_id.to_string()), param::text(serde_json::to_string(version)?)];
+        let result = self.execute(&sql, &params).await?;
+        result.rows.first().map(parse_test_run).transpose()
+    }
+
+    async fn list_test_runs(&self, tool_id: &ToolId, limit: usize) -> ExecutorResult<Vec<ToolTestRun>> {
+        let sql = format!(
+            "SELECT {} FROM tool_test_runs WHERE tool_id = ? ORDER BY finished_at DESC LIMIT ?",
+            TEST_RUN_COLUMNS
+        );
+        let params = vec![param::text(tool_id.to_string()), param::int(limit.min(i64::MAX as usize) as i64)];
+        let result = self.execute(&sql, &params).await?;
+        result.rows.iter().map(parse_test_run).collect()
+    }
+}
@@ -0,0 +1,704 @@
+//! Execution parameter templating
+//!
+//! String parameters of an `ExecutionRequest` may embed `{{ expression }}`
+//! placeholders that the executor evaluates before dispatch, e.g.
+//! `{{ now() }}`, `{{ context.user_id }}` or `{{ steps.previous.output.id }}`.
+//! A string made of a single placeholder takes the expression's JSON value;
+//! placeholders mixed with text are rendered into the string, and `\{{`
+//! stands for a literal `{{`.
+//!
+//! Expressions are deliberately small: literals, paths into the `context`,
+//! `env` and `steps` roots (plus variables such as a loop's `item`), and calls
+//! to the functions in [`FUNCTIONS`]. Rendered values are never evaluated
+//! again, so data flowing through a template cannot inject further
+//! expressions; [`escape`] keeps already rendered values intact when they are
+//! handed to another renderer.
+
+use std::collections::HashMap;
+use std::fmt;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use stepflow_core::ExecutionId;
+use crate::execution_context::ExecutionRequest;
+
+/// Functions callable from templates
+pub const FUNCTIONS: &[&str] = &[
+    "now", "today", "uuid", "upper", "lower", "trim", "length", "default", "concat", "to_json",
+    "eq", "ne", "gt", "gte", "lt", "lte", "not", "and", "or", "contains",
+];
+
+/// Largest string a template may render, in bytes
+pub const MAX_RENDERED_LENGTH: usize = 1024 * 1024;
+
+/// Kind of template failure
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TemplateErrorKind {
+    /// The template could not be parsed
+    Syntax,
+    /// A variable or field does not exist
+    Undefined,
+    /// The function is not in [`FUNCTIONS`]
+    UnknownFunction,
+    /// A function received arguments it cannot handle
+    InvalidArgument,
+    /// The rendered value exceeds [`MAX_RENDERED_LENGTH`]
+    TooLarge,
+}
+
+/// Template failure with the location of the offending expression
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplateError {
+    pub kind: TemplateErrorKind,
+    pub message: String,
+    /// Parameter holding the template, e.g. `body.items[0]`
+    pub parameter: String,
+    pub template: String,
+    /// Byte offsets of the failing expression within `template`
+    pub start: usize,
+    pub end: usize,
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let snippet = self.template.get(self.start..self.end).unwrap_or_default();
+        write!(
+            f,
+            "{}: {} at {}..{} ({:?}) in {:?}",
+            self.parameter, self.message, self.start, self.end, snippet, self.template
+        )
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+/// Failure before the parameter and template are attached
+struct Failure {
+    kind: TemplateErrorKind,
+    message: String,
+    start: usize,
+    end: usize,
+}
+
+impl Failure {
+    fn new(kind: TemplateErrorKind, message: impl Into<String>, start: usize, end: usize) -> Self {
+        Self { kind, message: message.into(), start, end }
+    }
+
+    fn into_error(self, parameter: &str, template: &str) -> TemplateError {
+        TemplateError {
+            kind: self.kind,
+            message: self.message,
+            parameter: parameter.to_string(),
+            template: template.to_string(),
+            start: self.start,
+            end: self.end,
+        }
+    }
+}
+
+/// Values visible to templates
+#[derive(Debug, Clone)]
+pub struct TemplateContext {
+    roots: Map<String, Value>,
+    now: DateTime<Utc>,
+}
+
+impl Default for TemplateContext {
+    fn default() -> Self {
+        let roots = ["context", "env", "steps"]
+            .into_iter()
+            .map(|root| (root.to_string(), Value::Object(Map::new())))
+            .collect();
+        Self { roots, now: Utc::now() }
+    }
+}
+
+impl TemplateContext {
+    /// Empty context: `context`, `env` and `steps` have no fields
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Expose the request's context and (already policy-checked) environment
+    pub fn from_request(request: &ExecutionRequest, execution_id: &ExecutionId) -> Self {
+        let context = &request.context;
+        let mut template_context = Self::new();
+        template_context.roots.insert("context".to_string(), serde_json::json!({
+            "execution_id": execution_id.to_string(),
+            "tool_id": request.tool_id.to_string(),
+            "user_id": context.user_id,
+            "tenant_id": context.tenant_id,
+            "session_id": context.session_id,
+            "request_id": context.request_id,
+            "parent_execution_id": context.parent_execution_id.as_ref().map(|id| id.to_string()),
+        }));
+        template_context.roots.insert("env".to_string(), serde_json::json!(context.environment));
+        template_context
+    }
+
+    /// Make the output of an earlier step available as `steps.<name>.output`
+    pub fn with_step(mut self, name: &str, output: Value) -> Self {
+        if let Some(Value::Object(steps)) = self.roots.get_mut("steps") {
+            steps.insert(name.to_string(), serde_json::json!({ "output": output }));
+        }
+        self
+    }
+
+    /// Expose an additional variable, e.g. the current `item` of a loop
+    pub fn with_variable(mut self, name: &str, value: Value) -> Self {
+        self.roots.insert(name.to_string(), value);
+        self
+    }
+
+    /// Fix the time returned by `now()` and `today()`
+    pub fn with_now(mut self, now: DateTime<Utc>) -> Self {
+        self.now = now;
+        self
+    }
+
+    /// Render every template in the parameters
+    ///
+    /// Objects and arrays are walked recursively; only string values are
+    /// templates, object keys are left as they are.
+    pub fn render_parameters(
+        &self,
+        parameters: &HashMap<String, Value>,
+    ) -> Result<HashMap<String, Value>, TemplateError> {
+        parameters
+            .iter()
+            .map(|(name, value)| Ok((name.clone(), self.render_value(value, name)?)))
+            .collect()
+    }
+
+    /// Render a single template string
+    pub fn render(&self, template: &str) -> Result<Value, TemplateError> {
+        self.render_template(template, "")
+    }
+
+    fn render_value(&self, value: &Value, path: &str) -> Result<Value, TemplateError> {
+        match value {
+            Value::String(template) => self.render_template(template, path),
+            Value::Array(items) => items
+                .iter()
+                .enumerate()
+                .map(|(index, item)| self.render_value(item, &format!("{}[{}]", path, index)))
+                .collect::<Result<_, _>>()
+                .map(Value::Array),
+            Value::Object(fields) => fields
+                .iter()
+                .map(|(key, item)| Ok((key.clone(), self.render_value(item, &format!("{}.{}", path, key))?)))
+                .collect::<Result<_, _>>()
+                .map(Value::Object),
+            other => Ok(other.clone()),
+        }
+    }
+
+    fn render_template(&self, template: &str, parameter: &str) -> Result<Value, TemplateError> {
+        if !template.contains("{{") {
+            return Ok(Value::String(template.to_string()));
+        }
+        let segments = parse_template(template).map_err(|f| f.into_error(parameter, template))?;
+
+        if let [Segment::Expr(expr)] = segments.as_slice() {
+            return self.eval(expr).map_err(|f| f.into_error(parameter, template));
+        }
+
+        let mut rendered = String::new();
+        for segment in &segments {
+            match segment {
+                Segment::Text(text) => rendered.push_str(text),
+                Segment::Expr(expr) => {
+                    let value = self.eval(expr).map_err(|f| f.into_error(parameter, template))?;
+                    rendered.push_str(&to_text(&value));
+                    if rendered.len() > MAX_RENDERED_LENGTH {
+                        let (start, end) = expr.span();
+                        return Err(Failure::new(
+                            TemplateErrorKind::TooLarge,
+                            format!("rendered value exceeds {} bytes", MAX_RENDERED_LENGTH),
+                            start,
+                            end,
+                        )
+                        .into_error(parameter, template));
+                    }
+                }
+            }
+        }
+        Ok(Value::String(rendered))
+    }
+
+    fn eval(&self, expr: &Expr) -> Result<Value, Failure> {
+        match expr {
+            Expr::Literal(value, _) => Ok(value.clone()),
+            Expr::Variable(name, (start, end)) => self.roots.get(name).cloned().ok_or_else(|| {
+                let known: Vec<&str> = self.roots.keys().map(String::as_str).collect();
+                Failure::new(
+                    TemplateErrorKind::Undefined,
+                    format!("unknown variable '{}' (expected one of {})", name, known.join(", ")),
+                    *start,
+                    *end,
+                )
+            }),
+            Expr::Field(base, field, (start, end)) => match self.eval(base)? {
+                Value::Object(mut fields) => fields.remove(field).ok_or_else(|| {
+                    Failure::new(TemplateErrorKind::Undefined, format!("'{}' is not defined", field), *start, *end)
+                }),
+                other => Err(Failure::new(
+                    TemplateErrorKind::Undefined,
+                    format!("cannot read '{}' of {}", field, type_name(&other)),
+                    *start,
+                    *end,
+                )),
+            },
+            Expr::Index(base, index, (start, end)) => {
+                let base = self.eval(base)?;
+                let found = match (&base, index) {
+                    (Value::Array(items), Value::Number(n)) => n.as_u64().and_then(|i| items.get(i as usize)),
+                    (Value::Object(fields), Value::String(key)) => fields.get(key),
+                    _ => None,
+                };
+                found.cloned().ok_or_else(|| {
+                    Failure::new(
+                        TemplateErrorKind::Undefined,
+                        format!("index {} is not defined on {}", index, type_name(&base)),
+                        *start,
+                        *end,
+                    )
+                })
+            }
+            Expr::Call(name, args, span) => self.call(name, args, *span),
+        }
+    }
+
+    fn call(&self, name: &str, args: &[Expr], (start, end): (usize, usize)) -> Result<Value, Failure> {
+        let invalid = |message: String| Failure::new(TemplateErrorKind::InvalidArgument, message, start, end);
+        let arity = |expected: usize| {
+            if args.len() == expected {
+                Ok(())
+            } else {
+                Err(invalid(format!("{}() takes {} argument(s), got {}", name, expected, args.len())))
+            }
+        };
+        let string_arg = |value: Value| match value {
+            Value::String(text) => Ok(text),
+            other => Err(invalid(format!("{}() expects a string, got {}", name, type_name(&other)))),
+        };
+
+        match name {
+            "now" => {
+                arity(0)?;
+                Ok(Value::String(self.now.to_rfc3339()))
+            }
+            "today" => {
+                arity(0)?;
+                Ok(Value::String(self.now.format("%Y-%m-%d").to_string()))
+            }
+            "uuid" => {
+                arity(0)?;
+                Ok(Value::String(uuid::Uuid::new_v4().to_string()))
+            }
+            "upper" | "lower" | "trim" => {
+                arity(1)?;
+                let text = string_arg(self.eval(&args[0])?)?;
+                Ok(Value::String(match name {
+                    "upper" => text.to_uppercase(),
+                    "lower" => text.to_lowercase(),
+                    _ => text.trim().to_string(),
+                }))
+            }
+            "length" => {
+                arity(1)?;
+                match self.eval(&args[0])? {
+                    Value::String(text) => Ok(Value::from(text.chars().count())),
+                    Value::Array(items) => Ok(Value::from(items.len())),
+                    Value::Object(fields) => Ok(Value::from(fields.len())),
+                    other => Err(invalid(format!("length() expects a string, array or object, got {}", type_name(&other)))),
+                }
+            }
+            "default" => {
+                arity(2)?;
+                match self.eval(&args[0]) {
+                    Ok(Value::Null) => self.eval(&args[1]),
+                    Err(failure) if failure.kind == TemplateErrorKind::Undefined => self.eval(&args[1]),
+                    result => result,
+                }
+            }
+            "concat" => {
+                let mut text = String::new();
+                for arg in args {
+                    text.push_str(&to_text(&self.eval(arg)?));
+                }
+                Ok(Value::String(text))
+            }
+            "to_json" => {
+                arity(1)?;
+                Ok(Value::String(self.eval(&args[0])?.to_string()))
+            }
+            "eq" | "ne" => {
+                arity(2)?;
+                let equal = json_equal(&self.eval(&args[0])?, &self.eval(&args[1])?);
+                Ok(Value::Bool(equal == (name == "eq")))
+            }
+            "gt" | "gte" | "lt" | "lte" => {
+                arity(2)?;
+                let (left, right) = (self.eval(&args[0])?, self.eval(&args[1])?);
+                let ordering = match (&left, &right) {
+                    (Value::Number(a), Value::Number(b)) => a.as_f64().partial_cmp(&b.as_f64()),
+                    (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+                    _ => None,
+                }
+                .ok_or_else(|| {
+                    invalid(format!("{}() cannot compare {} with {}", name, type_name(&left), type_name(&right)))
+                })?;
+                Ok(Value::Bool(match name {
+                    "gt" => ordering.is_gt(),
+                    "gte" => ordering.is_ge(),
+                    "lt" => ordering.is_lt(),
+                    _ => ordering.is_le(),
+                }))
+            }
+            "not" => {
+                arity(1)?;
+                Ok(Value::Bool(!is_truthy(&self.eval(&args[0])?)))
+            }
+            "and" | "or" => {
+                if args.is_empty() {
+                    return Err(invalid(format!("{}() needs at least one argument", name)));
+                }
+                // Short-circuits like the operators it stands for
+                let stop_at = name == "or";
+                for arg in args {
+                    if is_truthy(&self.eval(arg)?) == stop_at {
+                        return Ok(Value::Bool(stop_at));
+                    }
+                }
+                Ok(Value::Bool(!stop_at))
+            }
+            "contains" => {
+                arity(2)?;
+                let (haystack, needle) = (self.eval(&args[0])?, self.eval(&args[1])?);
+                match (&haystack, &needle) {
+                    (Value::String(text), Value::String(part)) => Ok(Value::Bool(text.contains(part.as_str()))),
+                    (Value::Array(items), _) => Ok(Value::Bool(items.iter().any(|item| json_equal(item, &needle)))),
+                    (Value::Object(fields), Value::String(key)) => Ok(Value::Bool(fields.contains_key(key))),
+                    _ => Err(invalid(format!(
+                        "contains() cannot search {} for {}", type_name(&haystack), type_name(&needle)
+                    ))),
+                }
+            }
+            _ => Err(Failure::new(
+                TemplateErrorKind::UnknownFunction,
+                format!("unknown function '{}' (allowed: {})", name, FUNCTIONS.join(", ")),
+                start,
+                end,
+            )),
+        }
+    }
+}
+
+/// Truthiness used by predicates: `null`, `false`, `0`, and empty strings,
+/// arrays and objects are false
+pub fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|n| n != 0.0),
+        Value::String(text) => !text.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(fields) => !fields.is_empty(),
+    }
+}
+
+/// Escape `{{` in every string so that rendering the value returns it unchanged
+pub fn escape(value: &Value) -> Value {
+    match value {
+        Value::String(text) if text.contains("{{") => Value::String(text.replace("{{", "\\{{")),
+        Value::Array(items) => Value::Array(items.iter().map(escape).collect()),
+        Value::Object(fields) => Value::Object(fields.iter().map(|(key, item)| (key.clone(), escape(item))).collect()),
+        other => other.clone(),
+    }
+}
+
+/// Names of the steps a template reads, as `steps.<name>` or `steps["<name>"]`
+pub fn step_references(template: &str) -> Result<Vec<String>, TemplateError> {
+    fn collect(expr: &Expr, names: &mut Vec<String>) {
+        match expr {
+            Expr::Field(base, name, _) if matches!(base.as_ref(), Expr::Variable(root, _) if root == "steps") => {
+                names.push(name.clone());
+            }
+            Expr::Index(base, Value::String(name), _) if matches!(base.as_ref(), Expr::Variable(root, _) if root == "steps") => {
+                names.push(name.clone());
+            }
+            Expr::Field(base, _, _) | Expr::Index(base, _, _) => collect(base, names),
+            Expr::Call(_, args, _) => args.iter().for_each(|arg| collect(arg, names)),
+            Expr::Literal(..) | Expr::Variable(..) => {}
+        }
+    }
+
+    let segments = parse_template(template).map_err(|failure| failure.into_error("", template))?;
+    let mut names = Vec::new();
+    for segment in &segments {
+        if let Segment::Expr(expr) = segment {
+            collect(expr, &mut names);
+        }
+    }
+    names.dedup();
+    Ok(names)
+}
+
+/// JSON equality that treats `1` and `1.0` as equal
+fn json_equal(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+        _ => left == right,
+    }
+}
+
+fn to_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+enum Segment {
+    Text(String),
+    Expr(Expr),
+}
+
+/// Parsed expression; spans are byte offsets into the template
+enum Expr {
+    Literal(Value, (usize, usize)),
+    Variable(String, (usize, usize)),
+    Field(Box<Expr>, String, (usize, usize)),
+    Index(Box<Expr>, Value, (usize, usize)),
+    Call(String, Vec<Expr>, (usize, usize)),
+}
+
+impl Expr {
+    fn span(&self) -> (usize, usize) {
+        match self {
+            Expr::Literal(_, span)
+            | Expr::Variable(_, span)
+            | Expr::Field(_, _, span)
+            | Expr::Index(_, _, span)
+            | Expr::Call(_, _, span) => *span,
+        }
+    }
+}
+
+fn parse_template(template: &str) -> Result<Vec<Segment>, Failure> {
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    let mut parser = Parser { src: template, pos: 0 };
+
+    while let Some(offset) = template[parser.pos..].find("{{") {
+        let open = parser.pos + offset;
+        if template[..open].ends_with('\\') {
+            text.push_str(&template[parser.pos..open - 1]);
+            text.push_str("{{");
+            parser.pos = open + 2;
+            continue;
+        }
+        text.push_str(&template[parser.pos..open]);
+        if !text.is_empty() {
+            segments.push(Segment::Text(std::mem::take(&mut text)));
+        }
+        parser.pos = open + 2;
+        let expr = parser.parse_expr()?;
+        parser.skip_whitespace();
+        if !parser.rest().starts_with("}}") {
+            return Err(parser.unexpected("'}}'"));
+        }
+        parser.pos += 2;
+        segments.push(Segment::Expr(expr));
+    }
+    text.push_str(&template[parser.pos..]);
+    if !text.is_empty() {
+        segments.push(Segment::Text(text));
+    }
+    Ok(segments)
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn rest(&self) -> &str {
+        &self.src[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        let trimmed = self.rest().trim_start();
+        self.pos = self.src.len() - trimmed.len();
+    }
+
+    fn unexpected(&self, expected: &str) -> Failure {
+        match self.peek() {
+            Some(c) => Failure::new(
+                TemplateErrorKind::Syntax,
+                format!("expected {}, found {:?}", expected, c),
+                self.pos,
+                self.pos + c.len_utf8(),
+            ),
+            None => Failure::new(
+                TemplateErrorKind::Syntax,
+                format!("expected {}, found end of template", expected),
+                self.pos,
+                self.pos,
+            ),
+        }
+    }
+
+    fn parse_expr(&mut self) -> Result<Expr, Failure> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let mut expr = match self.peek() {
+            Some('\'' | '"') => {
+                let text = self.parse_string()?;
+                Expr::Literal(Value::String(text), (start, self.pos))
+            }
+            Some(c) if c.is_ascii_digit() || c == '-' => {
+                let number = self.parse_number()?;
+                Expr::Literal(number, (start, self.pos))
+            }
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+                let name = self.parse_ident();
+                match name.as_str() {
+                    "true" => Expr::Literal(Value::Bool(true), (start, self.pos)),
+                    "false" => Expr::Literal(Value::Bool(false), (start, self.pos)),
+                    "null" => Expr::Literal(Value::Null, (start, self.pos)),
+                    _ => {
+                        self.skip_whitespace();
+                        if self.peek() == Some('(') {
+                            let args = self.parse_args()?;
+                            Expr::Call(name, args, (start, self.pos))
+                        } else {
+                            let end = start + name.len();
+                            Expr::Variable(name, (start, end))
+                        }
+                    }
+                }
+            }
+            _ => return Err(self.unexpected("an expression")),
+        };
+
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('.') => {
+                    self.pos += 1;
+                    if !self.peek().is_some_and(|c| c.is_ascii_alphabetic() || c == '_') {
+                        return Err(self.unexpected("a field name"));
+                    }
+                    let field = self.parse_ident();
+                    expr = Expr::Field(Box::new(expr), field, (start, self.pos));
+                }
+                Some('[') => {
+                    self.pos += 1;
+                    self.skip_whitespace();
+                    let index = match self.peek() {
+                        Some('\'' | '"') => Value::String(self.parse_string()?),
+                        Some(c) if c.is_ascii_digit() => self.parse_number()?,
+                        _ => return Err(self.unexpected("an index or quoted key")),
+                    };
+                    self.skip_whitespace();
+                    if self.peek() != Some(']') {
+                        return Err(self.unexpected("']'"));
+                    }
+                    self.pos += 1;
+                    expr = Expr::Index(Box::new(expr), index, (start, self.pos));
+                }
+                _ => return Ok(expr),
+            }
+        }
+    }
+
+    fn parse_ident(&mut self) -> String {
+        let len = self.rest()
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(self.rest().len());
+        let ident = self.rest()[..len].to_string();
+        self.pos += len;
+        ident
+    }
+
+    fn parse_args(&mut self) -> Result<Vec<Expr>, Failure> {
+        self.pos += 1; // '('
+        let mut args = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(')') {
+            self.pos += 1;
+            return Ok(args);
+        }
+        loop {
+            args.push(self.parse_expr()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(')') => {
+                    self.pos += 1;
+                    return Ok(args);
+                }
+                _ => return Err(self.unexpected("',' or ')'")),
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, Failure> {
+        let start = self.pos;
+        let quote = self.peek().unwrap_or('"');
+        self.pos += 1;
+        let mut text = String::new();
+        let mut chars = self.rest().char_indices();
+        while let Some((offset, c)) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some((_, 'n')) => text.push('\n'),
+                    Some((_, 't')) => text.push('\t'),
+                    Some((_, escaped)) => text.push(escaped),
+                    None => break,
+                },
+                c if c == quote => {
+                    self.pos += offset + 1;
+                    return Ok(text);
+                }
+                c => text.push(c),
+            }
+        }
+        Err(Failure::new(TemplateErrorKind::Syntax, "unterminated string", start, self.src.len()))
+    }
+
+    fn parse_number(&mut self) -> Result<Value, Failure> {
+        let start = self.pos;
+        let len = self.rest()
+            .char_indices()
+            .find(|&(i, c)| !(c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+') || (c == '-' && i == 0)))
+            .map_or(self.rest().len(), |(i, _)| i);
+        let literal = &self.rest()[..len];
+        let number = serde_json::from_str::<serde_json::Number>(literal).map_err(|_| {
+            Failure::new(TemplateErrorKind::Syntax, format!("invalid number {:?}", literal), start, start + len)
+        })?;
+        self.pos += len;
+        Ok(Value::Number(number))
+    }
+}
@@ -0,0 +1,197 @@
+//! Execution timeline
+//!
+//! `ExecutionStatus` only reflects the latest state of an execution. The
+//! timeline keeps every state transition (with timestamp and worker) in the
+//! `execution_events` table so scheduling latency can be reconstructed later.
+
+use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use stepflow_core::*;
+use stepflow_database::SqliteDatabase;
+use crate::errors::*;
+
+/// Execution lifecycle state recorded on the timeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecutionState {
+    Queued,
+    Scheduled,
+    Running,
+    Retrying,
+    Completed,
+    Failed,
+    Cancelled,
+    TimedOut,
+}
+
+impl ExecutionState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExecutionState::Queued => "queued",
+            ExecutionState::Scheduled => "scheduled",
+            ExecutionState::Running => "running",
+            ExecutionState::Retrying => "retrying",
+            ExecutionState::Completed => "completed",
+            ExecutionState::Failed => "failed",
+            ExecutionState::Cancelled => "cancelled",
+            ExecutionState::TimedOut => "timed_out",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "queued" => Some(ExecutionState::Queued),
+            "scheduled" => Some(ExecutionState::Scheduled),
+            "running" => Some(ExecutionState::Running),
+            "retrying" => Some(ExecutionState::Retrying),
+            "completed" => Some(ExecutionState::Completed),
+            "failed" => Some(ExecutionState::Failed),
+            "cancelled" => Some(ExecutionState::Cancelled),
+            "timed_out" => Some(ExecutionState::TimedOut),
+            _ => None,
+        }
+    }
+
+    /// Whether the execution has finished
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            ExecutionState::Completed | ExecutionState::Failed | ExecutionState::Cancelled | ExecutionState::TimedOut
+        )
+    }
+}
+
+/// A single state transition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionEvent {
+    pub state: ExecutionState,
+    pub worker_id: Option<String>,
+    pub detail: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+/// Full transition history of an execution, oldest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionTimeline {
+    pub execution_id: ExecutionId,
+    pub tenant_id: Option<String>,
+    pub events: Vec<ExecutionEvent>,
+    /// Time from first queued to first running
+    pub queue_wait_ms: Option<i64>,
+    /// Time from first running to the terminal state
+    pub run_duration_ms: Option<i64>,
+    /// Time from the first event to the terminal state
+    pub total_duration_ms: Option<i64>,
+}
+
+impl ExecutionTimeline {
+    /// Build a timeline from its events and derive the latency figures
+    pub fn from_events(execution_id: ExecutionId, tenant_id: Option<String>, events: Vec<ExecutionEvent>) -> Self {
+        let first_at = |state: ExecutionState| events.iter().find(|e| e.state == state).map(|e| e.occurred_at);
+        let queued_at = first_at(ExecutionState::Queued);
+        let running_at = first_at(ExecutionState::Running);
+        let started_at = events.first().map(|e| e.occurred_at);
+        let finished_at = events.iter().rev().find(|e| e.state.is_terminal()).map(|e| e.occurred_at);
+
+        let between = |from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>| match (from, to) {
+            (Some(from), Some(to)) => Some((to - from).num_milliseconds()),
+            _ => None,
+        };
+
+        Self {
+            execution_id,
+            tenant_id,
+            queue_wait_ms: between(queued_at, running_at),
+            run_duration_ms: between(running_at, finished_at),
+            total_duration_ms: between(started_at, finished_at),
+            events,
+        }
+    }
+
+    /// Latest recorded state
+    pub fn current_state(&self) -> Option<ExecutionState> {
+        self.events.last().map(|e| e.state)
+    }
+}
+
+/// Persists execution state transitions
+pub struct TimelineRecorder {
+    db: Arc<SqliteDatabase>,
+}
+
+impl TimelineRecorder {
+    /// Create a new timeline recorder
+    pub fn new(db: Arc<SqliteDatabase>) -> Self {
+        Self { db }
+    }
+
+    /// Record a transition of `execution_id` into `state`
+    pub async fn record(
+        &self,
+        execution_id: &ExecutionId,
+        tool_id: Option<&ToolId>,
+        tenant_id: Option<&str>,
+        state: ExecutionState,
+        worker_id: Option<&str>,
+        detail: Option<&str>,
+    ) -> ExecutorResult<()> {
+        let optional = |value: Option<&str>| value.map(|v| Value::String(v.to_string())).unwrap_or(Value::Null);
+        let params = vec![
+            Value::String(execution_id.to_string()),
+            optional(tool_id.map(|id| id.as_str())),
+            optional(tenant_id),
+            Value::String(state.as_str().to_string()),
+            optional(worker_id),
+            optional(detail),
+            Value::String(Utc::now().to_rfc3339()),
+        ];
+
+        self.db.execute(
+            "INSERT INTO execution_events (execution_id, tool_id, tenant_id, state, worker_id, detail, occurred_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+            &params,
+        ).await
+            .map_err(|e| ExecutorError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Load the timeline of an execution, `None` if nothing was recorded
+    pub async fn timeline(&self, execution_id: &ExecutionId) -> ExecutorResult<Option<ExecutionTimeline>> {
+        let result = self.db.execute(
+            "SELECT tenant_id, state, worker_id, detail, occurred_at FROM execution_events WHERE execution_id = ? ORDER BY id ASC",
+            &[Value::String(execution_id.to_string())],
+        ).await
+            .map_err(|e| ExecutorError::DatabaseError(e.to_string()))?;
+
+        if result.rows.is_empty() {
+            return Ok(None);
+        }
+
+        let text = |row: &std::collections::HashMap<String, Value>, key: &str| {
+            row.get(key).and_then(|v| v.as_str()).map(|s| s.to_string())
+        };
+        let tenant_id = result.rows.iter().find_map(|row| text(row, "tenant_id"));
+
+        let mut events = Vec::with_capacity(result.rows.len());
+        for row in &result.rows {
+            let state_str = text(row, "state").unwrap_or_default();
+            let state = ExecutionState::parse(&state_str)
+                .ok_or_else(|| ExecutorError::DatabaseError(format!("Unknown execution state '{}'", state_str)))?;
+            let occurred_at = text(row, "occurred_at")
+                .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+                .map(|dt| dt.with_timezone(&Utc))
+                .ok_or_else(|| ExecutorError::DatabaseError("Invalid execution event timestamp".to_string()))?;
+
+            events.push(ExecutionEvent {
+                state,
+                worker_id: text(row, "worker_id"),
+                detail: text(row, "detail"),
+                occurred_at,
+            });
+        }
+
+        Ok(Some(ExecutionTimeline::from_events(execution_id.clone(), tenant_id, events)))
+    }
+}
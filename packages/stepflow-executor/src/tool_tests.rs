@@ -0,0 +1,280 @@
+//! Example-based tool tests
+//!
+//! A tool's examples double as smoke tests: each example's input is executed
+//! and the actual output is compared with the expected one according to the
+//! example's [`ExampleMatch`] mode. Runs are persisted per tool version, so the
+//! latest result of a version can be shown and a rollout can refuse to promote
+//! a candidate whose examples fail.
+//!
+//! Test executions carry the [`TEST_RUN_LABEL`] label with the run id and are
+//! not counted against rollouts.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use stepflow_core::*;
+use stepflow_registry::tool_config::validate_against_schema;
+use stepflow_registry::{ChangeFeed, ToolChangeType};
+use crate::errors::*;
+use crate::execution_context::{ExecutionContext, ExecutionOptions, ExecutionRequest};
+use crate::executor::{ExecutionStore, Executor};
+
+/// Execution label holding the id of the test run an execution belongs to
+pub const TEST_RUN_LABEL: &str = "stepflow.test_run";
+
+/// Why a test run was started
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TestTrigger {
+    /// Requested through the API
+    Manual,
+    /// The tool or version was registered
+    Registration,
+    /// A rollout candidate is about to be promoted
+    Promotion,
+}
+
+impl TestTrigger {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TestTrigger::Manual => "manual",
+            TestTrigger::Registration => "registration",
+            TestTrigger::Promotion => "promotion",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "manual" => Some(TestTrigger::Manual),
+            "registration" => Some(TestTrigger::Registration),
+            "promotion" => Some(TestTrigger::Promotion),
+            _ => None,
+        }
+    }
+}
+
+/// Outcome of running one example
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExampleResult {
+    pub example: String,
+    pub match_mode: ExampleMatch,
+    pub passed: bool,
+    pub actual_output: Option<Value>,
+    /// Why the execution failed, if it did
+    pub error: Option<String>,
+    /// Differences between the expected and actual output
+    pub mismatches: Vec<String>,
+    pub duration_ms: u64,
+}
+
+/// A run of all examples of one tool version
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolTestRun {
+    pub id: String,
+    pub tool_id: ToolId,
+    pub version: ToolVersion,
+    pub trigger: TestTrigger,
+    /// Whether every example passed
+    pub passed: bool,
+    pub results: Vec<ExampleResult>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+}
+
+impl ToolTestRun {
+    /// Number of examples that failed
+    pub fn failures(&self) -> usize {
+        self.results.iter().filter(|result| !result.passed).count()
+    }
+}
+
+/// Compare an actual output with an example's expected output, returning the mismatches
+///
+/// `Exact` needs equal values, `Subset` needs every field and array element of
+/// the expected value to be present with the same value, and `Schema` treats
+/// the expected value as a JSON schema.
+pub fn match_example(mode: ExampleMatch, expected: &Value, actual: &Value) -> Vec<String> {
+    let mut mismatches = Vec::new();
+    match mode {
+        ExampleMatch::Exact => compare("$", expected, actual, false, &mut mismatches),
+        ExampleMatch::Subset => compare("$", expected, actual, true, &mut mismatches),
+        ExampleMatch::Schema => {
+            if let Err(errors) = validate_against_schema(expected, actual) {
+                mismatches.extend(errors.into_iter().map(|error| error.to_string()));
+            }
+        }
+    }
+    mismatches
+}
+
+fn compare(path: &str, expected: &Value, actual: &Value, subset: bool, mismatches: &mut Vec<String>) {
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => {
+            for (key, value) in expected {
+                let field = format!("{}.{}", path, key);
+                match actual.get(key) {
+                    Some(actual) => compare(&field, value, actual, subset, mismatches),
+                    None => mismatches.push(format!("{}: missing", field)),
+                }
+            }
+            if !subset {
+                mismatches.extend(actual.keys()
+                    .filter(|key| !expected.contains_key(*key))
+                    .map(|key| format!("{}.{}: unexpected field", path, key)));
+            }
+        }
+        (Value::Array(expected), Value::Array(actual)) => {
+            let length_differs = if subset { actual.len() < expected.len() } else { actual.len() != expected.len() };
+            if length_differs {
+                mismatches.push(format!("{}: expected {} items, got {}", path, expected.len(), actual.len()));
+                return;
+            }
+            for (index, (expected, actual)) in expected.iter().zip(actual).enumerate() {
+                compare(&format!("{}[{}]", path, index), expected, actual, subset, mismatches);
+            }
+        }
+        _ if expected != actual => mismatches.push(format!("{}: expected {}, got {}", path, expected, actual)),
+        _ => {}
+    }
+}
+
+/// Runs tool examples through the executor and persists the runs
+#[derive(Clone)]
+pub struct ToolTestRunner {
+    executor: Arc<dyn Executor>,
+    store: Arc<dyn ExecutionStore>,
+}
+
+impl ToolTestRunner {
+    /// Create a runner executing on `executor` and persisting to `store`
+    pub fn new(executor: Arc<dyn Executor>, store: Arc<dyn ExecutionStore>) -> Self {
+        Self { executor, store }
+    }
+
+    /// Context for test executions submitted by the system rather than a user
+    pub fn system_context(tenant_id: &str) -> ExecutionContext {
+        ExecutionContext {
+            user_id: "system".to_string(),
+            tenant_id: tenant_id.to_string(),
+            session_id: "tool-tests".to_string(),
+            request_id: String::new(),
+            parent_execution_id: None,
+            environment: HashMap::new(),
+            labels: HashMap::new(),
+            note: None,
+        }
+    }
+
+    /// Run every example of `tool` under `context` and persist the run
+    ///
+    /// A tool without examples gets a passing run with no results.
+    pub async fn run(&self, tool: &ToolInfo, trigger: TestTrigger, context: ExecutionContext) -> ExecutorResult<ToolTestRun> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let started_at = Utc::now();
+        let mut results = Vec::with_capacity(tool.examples.len());
+        for example in &tool.examples {
+            results.push(self.run_example(tool, example, &id, &context).await);
+        }
+
+        let run = ToolTestRun {
+            id,
+            tool_id: tool.id.clone(),
+            version: tool.version.clone(),
+            trigger,
+            passed: results.iter().all(|result| result.passed),
+            results,
+            started_at,
+            finished_at: Utc::now(),
+        };
+        self.store.save_test_run(&run).await?;
+        tracing::info!(
+            "Test run {} of tool {} {}: {}/{} examples passed",
+            run.id, run.tool_id, run.version, run.results.len() - run.failures(), run.results.len()
+        );
+        Ok(run)
+    }
+
+    async fn run_example(&self, tool: &ToolInfo, example: &ToolExample, run_id: &str, context: &ExecutionContext) -> ExampleResult {
+        let mut result = ExampleResult {
+            example: example.name.clone(),
+            match_mode: example.match_mode,
+            passed: false,
+            actual_output: None,
+            error: None,
+            mismatches: Vec::new(),
+            duration_ms: 0,
+        };
+        let Value::Object(parameters) = &example.input else {
+            result.error = Some("Example input must be a JSON object".to_string());
+            return result;
+        };
+
+        let mut context = context.clone();
+        context.request_id = format!("{}:{}", run_id, example.name);
+        context.labels.insert(TEST_RUN_LABEL.to_string(), run_id.to_string());
+        let request = ExecutionRequest {
+            tool_id: tool.id.clone(),
+            version: Some(tool.version.clone()),
+            parameters: parameters.clone().into_iter().collect(),
+            context,
+            options: ExecutionOptions::default(),
+        };
+
+        let started = std::time::Instant::now();
+        let outcome = self.executor.execute_tool(request).await;
+        result.duration_ms = started.elapsed().as_millis() as u64;
+        match outcome {
+            Ok(execution) if execution.success => {
+                let actual = execution.output.unwrap_or(Value::Null);
+                result.mismatches = match_example(example.match_mode, &example.output, &actual);
+                result.passed = result.mismatches.is_empty();
+                result.actual_output = Some(actual);
+            }
+            Ok(execution) => {
+                result.actual_output = execution.output;
+                result.error = Some(execution.error.unwrap_or_else(|| "Execution failed".to_string()));
+            }
+            Err(e) => result.error = Some(e.to_string()),
+        }
+        result
+    }
+
+    /// Latest run of a tool version
+    pub async fn latest(&self, tool_id: &ToolId, version: &ToolVersion) -> ExecutorResult<Option<ToolTestRun>> {
+        self.store.latest_test_run(tool_id, version).await
+    }
+
+    /// Runs of a tool, newest first
+    pub async fn list(&self, tool_id: &ToolId, limit: usize) -> ExecutorResult<Vec<ToolTestRun>> {
+        self.store.list_test_runs(tool_id, limit).await
+    }
+
+    /// Test tools created or given a new version after change `since`
+    ///
+    /// Versions without examples and versions that already have a run are
+    /// skipped. Returns the runs and the sequence to pass as `since` next time.
+    pub async fn test_registered(
+        &self,
+        feed: &ChangeFeed,
+        since: u64,
+        context: &ExecutionContext,
+    ) -> ExecutorResult<(Vec<ToolTestRun>, u64)> {
+        let page = feed.changes_since(since, None).await?;
+        let mut runs = Vec::new();
+        for change in page.changes {
+            if !matches!(change.change_type, ToolChangeType::Created | ToolChangeType::VersionAdded) {
+                continue;
+            }
+            let Some(tool) = change.tool.filter(|tool| !tool.examples.is_empty()) else {
+                continue;
+            };
+            if self.latest(&tool.id, &tool.version).await?.is_some() {
+                continue;
+            }
+            runs.push(self.run(&tool, TestTrigger::Registration, context.clone()).await?);
+        }
+        Ok((runs, page.next_since))
+    }
+}
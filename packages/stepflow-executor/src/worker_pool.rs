@@ -7,10 +7,11 @@ use tokio::sync::{RwLock, Mutex, oneshot};
 use tokio::task::JoinHandle;
 use chrono::Utc;
 use stepflow_core::*;
-use stepflow_registry::{Registry, RegistryImpl};
+use stepflow_registry::Registry;
 use crate::errors::*;
 use crate::execution_context::*;
 use crate::executor::WorkerPool;
+use crate::placement::{placement_rank, unmet_constraints, ArtifactCache, ArtifactHint, WorkerProfile};
 
 /// Worker pool configuration
 #[derive(Debug, Clone)]
@@ -22,6 +23,12 @@ pub struct WorkerPoolConfig {
     pub enable_auto_scaling: bool,
     pub scale_up_threshold: f64,
     pub scale_down_threshold: f64,
+    /// Environment label of the workers the pool starts and scales
+    pub environment_label: Option<EnvironmentLabel>,
+    /// Capabilities of the workers the pool starts and scales
+    pub capabilities: Vec<String>,
+    /// Bytes of artifacts each worker keeps cached for later work
+    pub artifact_cache_bytes: u64,
 }
 
 impl Default for WorkerPoolConfig {
@@ -34,10 +41,20 @@ impl Default for WorkerPoolConfig {
             enable_auto_scaling: true,
             scale_up_threshold: 0.8,
             scale_down_threshold: 0.2,
+            environment_label: None,
+            capabilities: Vec::new(),
+            artifact_cache_bytes: 20 * 1024 * 1024 * 1024,
         }
     }
 }
 
+impl WorkerPoolConfig {
+    /// Profile of the workers the pool starts and scales
+    pub fn worker_profile(&self) -> WorkerProfile {
+        WorkerProfile::new(self.environment_label, self.capabilities.clone())
+    }
+}
+
 /// Worker state
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum WorkerState {
@@ -56,11 +73,27 @@ pub struct Worker {
     pub started_at: chrono::DateTime<chrono::Utc>,
     pub last_activity: chrono::DateTime<chrono::Utc>,
     pub completed_work_count: u64,
+    /// Environment and capabilities the worker advertises
+    pub profile: WorkerProfile,
+    /// Artifacts the worker holds locally
+    pub artifacts: ArtifactCache,
+}
+
+/// Artifact bytes served from worker caches versus fetched
+#[derive(Debug, Clone, Copy, Default)]
+struct ArtifactTransfers {
+    reused_bytes: u64,
+    transferred_bytes: u64,
+}
+
+/// Placement constraints of a unit of work
+fn work_options(work: &Work) -> &ExecutionOptions {
+    &work.task.execution_request.options
 }
 
 /// Worker pool implementation
 pub struct WorkerPoolImpl {
-    registry: Arc<RegistryImpl>,
+    registry: Arc<dyn Registry>,
     config: WorkerPoolConfig,
     
     // Workers and their handles
@@ -73,16 +106,23 @@ pub struct WorkerPoolImpl {
     // Work status tracking
     work_status: Arc<RwLock<HashMap<WorkId, WorkStatus>>>,
     
+    // Artifact locality accounting
+    artifact_transfers: Arc<RwLock<ArtifactTransfers>>,
+    
     // Shutdown signal
     shutdown_tx: Arc<Mutex<Option<oneshot::Sender<()>>>>,
     
     // Running state
     running: Arc<RwLock<bool>>,
+    
+    // Fault injection for tests
+    #[cfg(feature = "fault-injection")]
+    faults: Option<stepflow_core::FaultController>,
 }
 
 impl WorkerPoolImpl {
     /// Create a new worker pool
-    pub fn new(registry: Arc<RegistryImpl>, config: WorkerPoolConfig) -> Self {
+    pub fn new(registry: Arc<dyn Registry>, config: WorkerPoolConfig) -> Self {
         let (shutdown_tx, _) = oneshot::channel();
         
         Self {
@@ -92,11 +132,21 @@ impl WorkerPoolImpl {
             worker_handles: Arc::new(RwLock::new(HashMap::new())),
             work_queue: Arc::new(Mutex::new(VecDeque::new())),
             work_status: Arc::new(RwLock::new(HashMap::new())),
+            artifact_transfers: Arc::new(RwLock::new(ArtifactTransfers::default())),
             shutdown_tx: Arc::new(Mutex::new(Some(shutdown_tx))),
             running: Arc::new(RwLock::new(false)),
+            #[cfg(feature = "fault-injection")]
+            faults: None,
         }
     }
     
+    /// Inject faults into work execution; a partial write runs the work and then reports it as failed
+    #[cfg(feature = "fault-injection")]
+    pub fn with_fault_controller(mut self, controller: stepflow_core::FaultController) -> Self {
+        self.faults = Some(controller);
+        self
+    }
+    
     /// Start the worker pool
     pub async fn start(&self) -> WorkerPoolResult<()> {
         // Set running state
@@ -104,7 +154,7 @@ impl WorkerPoolImpl {
         
         // Start minimum number of workers
         for _ in 0..self.config.min_workers {
-            self.spawn_worker().await?;
+            self.spawn_worker(self.config.worker_profile()).await?;
         }
         
         // Start auto-scaling if enabled
@@ -140,8 +190,23 @@ impl WorkerPoolImpl {
         Ok(())
     }
     
+    /// Add workers with their own profile alongside the pool's own workers
+    ///
+    /// Call after [`Self::start`]; auto-scaling only manages workers with the
+    /// pool's configured profile.
+    pub async fn add_workers(&self, profile: WorkerProfile, count: usize) -> WorkerPoolResult<Vec<WorkerId>> {
+        if !*self.running.read().await {
+            return Err(WorkerPoolError::PoolNotRunning);
+        }
+        let mut worker_ids = Vec::with_capacity(count);
+        for _ in 0..count {
+            worker_ids.push(self.spawn_worker(profile.clone()).await?);
+        }
+        Ok(worker_ids)
+    }
+    
     /// Spawn a new worker
-    async fn spawn_worker(&self) -> WorkerPoolResult<WorkerId> {
+    async fn spawn_worker(&self, profile: WorkerProfile) -> WorkerPoolResult<WorkerId> {
         let worker_id = WorkerId::new();
         let worker = Worker {
             id: worker_id.clone(),
@@ -150,6 +215,8 @@ impl WorkerPoolImpl {
             started_at: Utc::now(),
             last_activity: Utc::now(),
             completed_work_count: 0,
+            profile: profile.clone(),
+            artifacts: ArtifactCache::new(self.config.artifact_cache_bytes),
         };
         
         // Add worker to collection
@@ -159,7 +226,7 @@ impl WorkerPoolImpl {
         let pool = self.clone();
         let worker_id_clone = worker_id.clone();
         let handle = tokio::spawn(async move {
-            pool.worker_loop(worker_id_clone).await;
+            pool.worker_loop(worker_id_clone, profile).await;
         });
         
         // Store handle
@@ -169,20 +236,42 @@ impl WorkerPoolImpl {
     }
     
     /// Worker main loop
-    async fn worker_loop(&self, worker_id: WorkerId) {
+    async fn worker_loop(&self, worker_id: WorkerId, profile: WorkerProfile) {
         loop {
             // Check if we should stop
             if !*self.running.read().await {
                 break;
             }
             
-            // Get work from queue
+            // Get the oldest work this worker can run, leaving work to idle
+            // workers with more of its preferred capabilities or cached artifacts
+            let (cache, idle_workers) = {
+                let workers = self.workers.read().await;
+                let cache = workers.get(&worker_id).map(|worker| worker.artifacts.clone()).unwrap_or_default();
+                let idle_workers: Vec<(WorkerProfile, ArtifactCache)> = workers
+                    .values()
+                    .filter(|worker| worker.state == WorkerState::Idle && worker.id != worker_id)
+                    .map(|worker| (worker.profile.clone(), worker.artifacts.clone()))
+                    .collect();
+                (cache, idle_workers)
+            };
             let work = {
                 let mut queue = self.work_queue.lock().await;
-                queue.pop_front()
+                queue
+                    .iter()
+                    .position(|work| {
+                        let options = work_options(work);
+                        let rank = placement_rank(&profile, &cache, options);
+                        profile.satisfies(options) && !idle_workers.iter().any(|(other, other_cache)| {
+                            other.satisfies(options) && placement_rank(other, other_cache, options) > rank
+                        })
+                    })
+                    .and_then(|index| queue.remove(index))
             };
             
             if let Some(work) = work {
+                self.stage_artifacts(&worker_id, &work_options(&work).artifacts).await;
+                
                 // Update worker state
                 self.update_worker_state(&worker_id, WorkerState::Running, Some(work.id.clone())).await;
                 
@@ -192,9 +281,17 @@ impl WorkerPoolImpl {
                     status.insert(work.id.clone(), WorkStatus::Running);
                 }
                 
-                // Execute work
-                let _result = self.execute_work(work).await;
-                
+                // Execute work and record how it ended
+                let work_id = work.id.clone();
+                let status = match self.execute_work(work).await {
+                    Ok(_) => WorkStatus::Completed,
+                    Err(e) => {
+                        tracing::warn!("Work {} failed: {}", work_id, e);
+                        WorkStatus::Failed
+                    }
+                };
+                self.work_status.write().await.insert(work_id, status);
+
                 // Update worker state back to idle
                 self.update_worker_state(&worker_id, WorkerState::Idle, None).await;
                 
@@ -212,6 +309,18 @@ impl WorkerPoolImpl {
     
     /// Execute work
     async fn execute_work(&self, work: Work) -> WorkerPoolResult<ExecutionResult> {
+        #[cfg(feature = "fault-injection")]
+        let fault = match &self.faults {
+            Some(faults) => faults.inject(stepflow_core::FaultTarget::WorkerPool).await,
+            None => None,
+        };
+        #[cfg(feature = "fault-injection")]
+        if fault == Some(stepflow_core::Fault::Error) {
+            return Err(WorkerPoolError::InternalError(
+                stepflow_core::Fault::Error.message(stepflow_core::FaultTarget::WorkerPool),
+            ));
+        }
+        
         let task = work.task;
         let request = task.execution_request;
         
@@ -233,9 +342,65 @@ impl WorkerPoolImpl {
             ]),
         };
         
+        #[cfg(feature = "fault-injection")]
+        if fault == Some(stepflow_core::Fault::PartialWrite) {
+            return Err(WorkerPoolError::InternalError(
+                stepflow_core::Fault::PartialWrite.message(stepflow_core::FaultTarget::WorkerPool),
+            ));
+        }
+        
         Ok(execution_result)
     }
     
+    /// Make the work's artifacts local to the worker, counting what was already cached
+    async fn stage_artifacts(&self, worker_id: &WorkerId, artifacts: &[ArtifactHint]) {
+        if artifacts.is_empty() {
+            return;
+        }
+        let mut transfers = ArtifactTransfers::default();
+        if let Some(worker) = self.workers.write().await.get_mut(worker_id) {
+            for artifact in artifacts {
+                if worker.artifacts.insert(artifact) {
+                    transfers.reused_bytes += artifact.size_bytes;
+                } else {
+                    transfers.transferred_bytes += artifact.size_bytes;
+                }
+            }
+        }
+        let mut totals = self.artifact_transfers.write().await;
+        totals.reused_bytes += transfers.reused_bytes;
+        totals.transferred_bytes += transfers.transferred_bytes;
+    }
+    
+    /// Record an artifact a worker produced or fetched outside of scheduled work
+    pub async fn record_artifact(&self, worker_id: &WorkerId, artifact: ArtifactHint) -> WorkerPoolResult<()> {
+        let mut workers = self.workers.write().await;
+        let worker = workers.get_mut(worker_id)
+            .ok_or_else(|| WorkerPoolError::WorkerNotFound(worker_id.clone()))?;
+        worker.artifacts.insert(&artifact);
+        Ok(())
+    }
+    
+    /// Forget an artifact on every worker, e.g. after it was deleted
+    pub async fn evict_artifact(&self, artifact_id: &str) -> usize {
+        let mut evicted = 0;
+        for worker in self.workers.write().await.values_mut() {
+            if worker.artifacts.remove(artifact_id) {
+                evicted += 1;
+            }
+        }
+        evicted
+    }
+    
+    /// Workers that have the artifact cached
+    pub async fn artifact_locations(&self, artifact_id: &str) -> Vec<WorkerId> {
+        self.workers.read().await
+            .values()
+            .filter(|worker| worker.artifacts.contains(artifact_id))
+            .map(|worker| worker.id.clone())
+            .collect()
+    }
+    
     /// Update worker state
     async fn update_worker_state(&self, worker_id: &WorkerId, state: WorkerState, current_work: Option<WorkId>) {
         let mut workers = self.workers.write().await;
@@ -292,13 +457,13 @@ impl WorkerPoolImpl {
         if utilization > self.config.scale_up_threshold && queue_size > 0 && total_workers < self.config.max_workers {
             drop(workers);
             drop(work_queue);
-            self.spawn_worker().await?;
+            self.spawn_worker(self.config.worker_profile()).await?;
         }
         // Scale down if utilization is low
         else if utilization < self.config.scale_down_threshold && total_workers > self.config.min_workers {
             // Find an idle worker to remove
             for (worker_id, worker) in workers.iter() {
-                if worker.state == WorkerState::Idle {
+                if worker.state == WorkerState::Idle && worker.profile == self.config.worker_profile() {
                     let worker_id = worker_id.clone();
                     drop(workers);
                     drop(work_queue);
@@ -334,8 +499,11 @@ impl Clone for WorkerPoolImpl {
             worker_handles: self.worker_handles.clone(),
             work_queue: self.work_queue.clone(),
             work_status: self.work_status.clone(),
+            artifact_transfers: self.artifact_transfers.clone(),
             shutdown_tx: self.shutdown_tx.clone(),
             running: self.running.clone(),
+            #[cfg(feature = "fault-injection")]
+            faults: self.faults.clone(),
         }
     }
 }
@@ -349,6 +517,15 @@ impl WorkerPool for WorkerPoolImpl {
             return Err(WorkerPoolError::PoolNotRunning);
         }
         
+        // Work only runs on a worker meeting its constraints, so reject it when none can take it
+        let unmet = unmet_constraints(
+            self.workers.read().await.values().map(|worker| &worker.profile),
+            work_options(&work),
+        );
+        if let Some(unmet) = unmet {
+            return Err(WorkerPoolError::Unschedulable(unmet));
+        }
+        
         // Add to work queue
         let mut queue = self.work_queue.lock().await;
         if queue.len() >= self.config.work_queue_size {
@@ -378,12 +555,23 @@ impl WorkerPool for WorkerPoolImpl {
         let workers = self.workers.read().await;
         let work_queue = self.work_queue.lock().await;
         let work_status = self.work_status.read().await;
+        let artifact_transfers = *self.artifact_transfers.read().await;
         
         let total_workers = workers.len();
         let active_workers = workers.values().filter(|w| w.state == WorkerState::Running).count();
         let idle_workers = workers.values().filter(|w| w.state == WorkerState::Idle).count();
         let pending_work = work_queue.len();
         let completed_work = work_status.values().filter(|&&status| status == WorkStatus::Completed).count();
+        let mut idle_profiles = Vec::new();
+        let mut worker_profiles = Vec::new();
+        for worker in workers.values() {
+            if worker.state == WorkerState::Idle && !idle_profiles.contains(&worker.profile) {
+                idle_profiles.push(worker.profile.clone());
+            }
+            if !worker_profiles.contains(&worker.profile) {
+                worker_profiles.push(worker.profile.clone());
+            }
+        }
         
         Ok(PoolStatus {
             active_workers,
@@ -391,6 +579,10 @@ impl WorkerPool for WorkerPoolImpl {
             total_workers,
             pending_work,
             completed_work,
+            idle_profiles,
+            worker_profiles,
+            artifact_bytes_reused: artifact_transfers.reused_bytes,
+            artifact_bytes_transferred: artifact_transfers.transferred_bytes,
         })
     }
     
@@ -403,7 +595,7 @@ impl WorkerPool for WorkerPoolImpl {
         if target_size > current_size {
             // Scale up
             for _ in current_size..target_size {
-                self.spawn_worker().await?;
+                self.spawn_worker(self.config.worker_profile()).await?;
             }
         } else if target_size < current_size {
             // Scale down - simplified implementation
@@ -0,0 +1,1700 @@
+//! Workflows
+//!
+//! A workflow is an ordered list of steps run through an [`Executor`]: tool
+//! calls, `if`/`else` branches on template predicates, `foreach` fan-out of a
+//! tool over an array with bounded parallelism, and `reduce` aggregation of an
+//! array produced by earlier steps. Step parameters are templates (see
+//! [`crate::template`]) that can read `steps.<id>.output`, and `item` and
+//! `index` inside a `foreach`.
+//!
+//! An `approval` step pauses the run until an authorized user approves or
+//! rejects the [`WorkflowApproval`] it creates, or until the approval expires.
+//! Rejection and expiry take the step's `rejected` branch when it has one and
+//! fail the run otherwise. [`ApprovalNotifier`]s are told about every request
+//! and decision. Decided runs resume in the background, and overdue approvals
+//! are expired by the sweeper started with [`WorkflowEngine::start_approval_sweeper`].
+//!
+//! Every step, and every iteration of a `foreach`, is persisted in
+//! `workflow_step_runs`. A failed run can be retried with
+//! [`WorkflowEngine::retry`]: completed steps and iterations are reused and
+//! only the failed ones execute again.
+//!
+//! Definitions can be published as numbered versions with
+//! [`WorkflowEngine::publish`]. New runs started with
+//! [`WorkflowEngine::start_latest`] use the latest version, and a run keeps the
+//! definition it started with. When a version is published as breaking, the
+//! workflow's [`MigrationPolicy`] decides what happens to runs of older
+//! versions still in flight: new runs wait until they have drained, they are
+//! cancelled, or they move to the new version at their next safe checkpoint,
+//! i.e. before any of their top-level steps starts.
+
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use stepflow_core::*;
+use stepflow_database::SqliteDatabase;
+use crate::errors::*;
+use crate::execution_context::{ExecutionContext, ExecutionOptions, ExecutionRequest};
+use crate::executor::Executor;
+use crate::template::{self, TemplateContext};
+use async_trait::async_trait;
+use tokio::task::JoinHandle;
+
+/// `iteration` stored for rows that describe a whole step
+const WHOLE_STEP: i64 = -1;
+
+/// Workflow definition
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkflowDefinition {
+    pub id: String,
+    pub name: String,
+    pub steps: Vec<WorkflowStep>,
+}
+
+/// A step of a workflow; ids are unique across the whole definition
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkflowStep {
+    pub id: String,
+    #[serde(flatten)]
+    pub kind: StepKind,
+}
+
+/// What a step does
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StepKind {
+    /// Execute a tool with templated parameters
+    Tool {
+        tool_id: ToolId,
+        #[serde(default)]
+        parameters: HashMap<String, Value>,
+    },
+    /// Run `then` when the predicate template is truthy, `else` otherwise
+    If {
+        condition: String,
+        #[serde(default)]
+        then: Vec<WorkflowStep>,
+        #[serde(default, rename = "else")]
+        otherwise: Vec<WorkflowStep>,
+    },
+    /// Execute a tool once per element of the array `items` evaluates to
+    Foreach {
+        items: String,
+        tool_id: ToolId,
+        #[serde(default)]
+        parameters: HashMap<String, Value>,
+        #[serde(default = "default_max_parallelism")]
+        max_parallelism: usize,
+    },
+    /// Aggregate the array `items` evaluates to
+    Reduce {
+        items: String,
+        operation: ReduceOperation,
+        /// Dotted path of the value to aggregate within each element
+        #[serde(default)]
+        path: Option<String>,
+    },
+    /// Pause until an authorized user approves or rejects the request
+    Approval {
+        /// Title shown to approvers; may contain templates
+        title: String,
+        /// User ids or `role:<name>` entries allowed to decide; tenant admins when empty
+        #[serde(default)]
+        approvers: Vec<String>,
+        /// Seconds after which a pending approval expires
+        #[serde(default)]
+        timeout_seconds: Option<u64>,
+        /// Steps run when the request is rejected or expires
+        #[serde(default)]
+        rejected: Vec<WorkflowStep>,
+    },
+}
+
+fn default_max_parallelism() -> usize {
+    4
+}
+
+/// Aggregation applied by a `reduce` step
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReduceOperation {
+    /// The selected values as an array
+    Collect,
+    Count,
+    Sum,
+    Min,
+    Max,
+    /// Concatenate arrays, or strings
+    Concat,
+    /// Merge objects, later keys win
+    Merge,
+}
+
+impl WorkflowDefinition {
+    /// Check that step ids are present and unique and parallelism is positive
+    pub fn validate(&self) -> ExecutorResult<()> {
+        fn visit<'a>(steps: &'a [WorkflowStep], seen: &mut HashSet<&'a str>) -> ExecutorResult<()> {
+            for step in steps {
+                if step.id.is_empty() {
+                    return Err(ExecutorError::InvalidParameters("Workflow step without an id".to_string()));
+                }
+                if !seen.insert(step.id.as_str()) {
+                    return Err(ExecutorError::InvalidParameters(format!("Duplicate workflow step id '{}'", step.id)));
+                }
+                match &step.kind {
+                    StepKind::If { then, otherwise, .. } => {
+                        visit(then, seen)?;
+                        visit(otherwise, seen)?;
+                    }
+                    StepKind::Approval { rejected, .. } => visit(rejected, seen)?,
+                    StepKind::Foreach { max_parallelism: 0, .. } => {
+                        return Err(ExecutorError::InvalidParameters(format!(
+                            "Step '{}': max_parallelism must be at least 1", step.id
+                        )));
+                    }
+                    _ => {}
+                }
+            }
+            Ok(())
+        }
+
+        if self.steps.is_empty() {
+            return Err(ExecutorError::InvalidParameters(format!("Workflow '{}' has no steps", self.id)));
+        }
+        visit(&self.steps, &mut HashSet::new())
+    }
+}
+
+/// Status of a workflow run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkflowRunStatus {
+    Running,
+    WaitingApproval,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// Status of a step or iteration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepRunStatus {
+    /// Waiting for an approval decision
+    Waiting,
+    Completed,
+    Failed,
+}
+
+macro_rules! string_enum {
+    ($name:ident { $($variant:ident => $text:literal),* $(,)? }) => {
+        impl $name {
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    $($name::$variant => $text,)*
+                }
+            }
+
+            pub fn parse(value: &str) -> Option<Self> {
+                match value {
+                    $($text => Some($name::$variant),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+string_enum!(WorkflowRunStatus {
+    Running => "running",
+    WaitingApproval => "waiting_approval",
+    Completed => "completed",
+    Failed => "failed",
+    Cancelled => "cancelled",
+});
+string_enum!(StepRunStatus { Waiting => "waiting", Completed => "completed", Failed => "failed" });
+string_enum!(ApprovalStatus {
+    Pending => "pending",
+    Approved => "approved",
+    Rejected => "rejected",
+    Expired => "expired",
+    Cancelled => "cancelled",
+});
+string_enum!(MigrationPolicy { Drain => "drain", Cancel => "cancel", Migrate => "migrate" });
+
+/// Persisted result of a step, or of one iteration of a `foreach`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepRun {
+    pub step_id: String,
+    /// Iteration of a `foreach`; `None` for the step as a whole
+    pub iteration: Option<usize>,
+    pub status: StepRunStatus,
+    pub execution_id: Option<String>,
+    pub output: Option<Value>,
+    pub error: Option<String>,
+    /// How many times the step or iteration has been executed
+    pub attempts: u32,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A workflow run and its step results
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowRun {
+    pub id: String,
+    pub workflow_id: String,
+    pub tenant_id: String,
+    /// Published version the run executes; `None` for definitions started directly
+    pub version: Option<u32>,
+    pub status: WorkflowRunStatus,
+    /// Outputs of the completed steps, by step id
+    pub output: Option<Value>,
+    pub error: Option<String>,
+    pub steps: Vec<StepRun>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// State of an approval request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalStatus {
+    Pending,
+    Approved,
+    Rejected,
+    Expired,
+    /// The run was cancelled before a decision
+    Cancelled,
+}
+
+/// Approval requested by an `approval` step
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowApproval {
+    pub id: String,
+    pub run_id: String,
+    pub workflow_id: String,
+    pub step_id: String,
+    pub tenant_id: String,
+    pub title: String,
+    pub approvers: Vec<String>,
+    pub status: ApprovalStatus,
+    pub decided_by: Option<String>,
+    pub comment: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub decided_at: Option<DateTime<Utc>>,
+}
+
+impl WorkflowApproval {
+    /// Whether a user with the given roles may decide this approval
+    pub fn can_decide(&self, user_id: &str, roles: &[String]) -> bool {
+        if self.approvers.is_empty() {
+            return roles.iter().any(|role| role == "admin");
+        }
+        self.approvers.iter().any(|approver| match approver.strip_prefix("role:") {
+            Some(role) => roles.iter().any(|r| r == role),
+            None => approver == user_id,
+        })
+    }
+
+    fn decision_output(&self) -> Value {
+        json!({
+            "decision": self.status.as_str(),
+            "decided_by": self.decided_by,
+            "comment": self.comment,
+        })
+    }
+}
+
+/// Decision on a pending approval
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalDecision {
+    pub approved: bool,
+    pub user_id: String,
+    pub roles: Vec<String>,
+    pub comment: Option<String>,
+}
+
+/// Notification about an approval request or its outcome
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalEvent {
+    /// `approval.requested`, `approval.approved`, `approval.rejected`, `approval.expired` or `approval.cancelled`
+    pub event: String,
+    pub approval: WorkflowApproval,
+}
+
+impl EventData for ApprovalEvent {
+    const SOURCE: &'static str = "/stepflow/approvals";
+    const SCHEMA: &'static str = "approval.event";
+    const SCHEMA_VERSION: u32 = 1;
+
+    fn event_type(&self) -> String {
+        self.event.clone()
+    }
+
+    fn event_id(&self) -> Option<String> {
+        Some(format!("approval-{}-{}", self.approval.id, self.event.trim_start_matches("approval.")))
+    }
+
+    fn subject(&self) -> Option<String> {
+        Some(self.approval.id.clone())
+    }
+
+    fn tenant_id(&self) -> Option<String> {
+        Some(self.approval.tenant_id.clone())
+    }
+}
+
+/// Delivers approval notifications, e.g. to a webhook
+#[async_trait]
+pub trait ApprovalNotifier: Send + Sync {
+    async fn notify(&self, event: &ApprovalEvent) -> ExecutorResult<()>;
+}
+
+/// A decided approval whose run resumes in the background
+pub struct ApprovalOutcome {
+    pub approval: WorkflowApproval,
+    /// Completes with the run once it stops again; dropping it detaches the run
+    pub resumed: JoinHandle<ExecutorResult<WorkflowRun>>,
+}
+
+/// What happens to in-flight runs of older versions when a breaking version is published
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MigrationPolicy {
+    /// Runs finish on the version they started with; no new run starts until
+    /// they have, so the old and the new version never run side by side
+    #[default]
+    Drain,
+    /// Runs are cancelled; a running run stops before its next step
+    Cancel,
+    /// Runs move to the latest version before their next top-level step, if
+    /// the steps they have already run still exist with the same type
+    Migrate,
+}
+
+/// A published version of a workflow definition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowVersion {
+    pub workflow_id: String,
+    pub tenant_id: String,
+    pub version: u32,
+    pub definition: WorkflowDefinition,
+    pub breaking: bool,
+    pub published_by: Option<String>,
+    pub published_at: DateTime<Utc>,
+}
+
+/// Outcome of publishing a version
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublishResult {
+    pub version: WorkflowVersion,
+    /// Policy applied to in-flight runs; only breaking versions apply it
+    pub policy: MigrationPolicy,
+    /// In-flight runs of older versions the policy applied to
+    pub affected_runs: Vec<String>,
+}
+
+/// Why a run stopped before its last step
+enum Halt {
+    Failed { step_id: String, message: String },
+    WaitingApproval,
+    Cancelled,
+}
+
+/// Execution id and output of a tool call, or its failure message
+type ToolOutcome = Result<(Option<String>, Option<Value>), String>;
+
+type StepFuture<'a> = Pin<Box<dyn Future<Output = ExecutorResult<Result<(), Halt>>> + Send + 'a>>;
+
+/// State of a run while its steps execute
+struct RunState {
+    run_id: String,
+    workflow_id: String,
+    context: ExecutionContext,
+    previous: HashMap<(String, i64), StepRun>,
+    outputs: serde_json::Map<String, Value>,
+}
+
+impl RunState {
+    fn template_context(&self) -> TemplateContext {
+        let context = &self.context;
+        let mut template_context = TemplateContext::new()
+            .with_variable("context", json!({
+                "run_id": self.run_id,
+                "workflow_id": self.workflow_id,
+                "user_id": context.user_id,
+                "tenant_id": context.tenant_id,
+                "session_id": context.session_id,
+                "request_id": context.request_id,
+            }))
+            .with_variable("env", json!(context.environment));
+        for (step_id, output) in &self.outputs {
+            template_context = template_context.with_step(step_id, output.clone());
+        }
+        template_context
+    }
+
+    fn completed(&self, step_id: &str, iteration: i64) -> Option<&StepRun> {
+        self.previous
+            .get(&(step_id.to_string(), iteration))
+            .filter(|run| run.status == StepRunStatus::Completed)
+    }
+
+    fn attempts(&self, step_id: &str, iteration: i64) -> u32 {
+        self.previous.get(&(step_id.to_string(), iteration)).map_or(0, |run| run.attempts)
+    }
+
+    fn waiting(&self, step_id: &str) -> bool {
+        self.previous
+            .get(&(step_id.to_string(), WHOLE_STEP))
+            .is_some_and(|run| run.status == StepRunStatus::Waiting)
+    }
+}
+
+/// Runs workflows and persists their progress
+#[derive(Clone)]
+pub struct WorkflowEngine {
+    executor: Arc<dyn Executor>,
+    db: Arc<SqliteDatabase>,
+    notifiers: Vec<Arc<dyn ApprovalNotifier>>,
+}
+
+impl WorkflowEngine {
+    /// Create a workflow engine executing tools through `executor`
+    pub fn new(executor: Arc<dyn Executor>, db: Arc<SqliteDatabase>) -> Self {
+        Self {
+            executor,
+            db,
+            notifiers: Vec::new(),
+        }
+    }
+
+    /// Add a notifier for approval requests and decisions
+    pub fn with_notifier(mut self, notifier: Arc<dyn ApprovalNotifier>) -> Self {
+        self.notifiers.push(notifier);
+        self
+    }
+
+    /// Start a run and execute it to completion or to the first failed step
+    pub async fn start(&self, definition: &WorkflowDefinition, context: ExecutionContext) -> ExecutorResult<WorkflowRun> {
+        definition.validate()?;
+        self.create_run(definition, None, context).await
+    }
+
+    /// Start a run of the latest published version of a workflow in the context's tenant
+    ///
+    /// Under [`MigrationPolicy::Drain`] this fails with [`ExecutorError::Conflict`]
+    /// while runs of versions older than a breaking version are still in flight.
+    pub async fn start_latest(&self, workflow_id: &str, context: ExecutionContext) -> ExecutorResult<WorkflowRun> {
+        let tenant_id = context.tenant_id.clone();
+        let latest = self.get_version(&tenant_id, workflow_id, None).await?
+            .ok_or_else(|| ExecutorError::InvalidParameters(format!("Workflow '{}' has no published version", workflow_id)))?;
+        if let Some(draining) = self.draining_version(&tenant_id, workflow_id).await? {
+            return Err(ExecutorError::Conflict(format!(
+                "Workflow '{}' is draining runs of version {} before version {} can start",
+                workflow_id, draining, latest.version
+            )));
+        }
+        self.create_run(&latest.definition, Some(latest.version), context).await
+    }
+
+    /// Oldest version with runs in flight that a later breaking version waits for under [`MigrationPolicy::Drain`]
+    async fn draining_version(&self, tenant_id: &str, workflow_id: &str) -> ExecutorResult<Option<u32>> {
+        if self.migration_policy(tenant_id, workflow_id).await? != MigrationPolicy::Drain {
+            return Ok(None);
+        }
+        let result = self.db.execute(
+            r#"
+            SELECT r.workflow_version FROM workflow_runs r
+            WHERE r.tenant_id = ? AND r.workflow_id = ? AND r.status IN (?, ?)
+              AND EXISTS (
+                  SELECT 1 FROM workflow_versions v
+                  WHERE v.tenant_id = r.tenant_id AND v.workflow_id = r.workflow_id
+                    AND v.version > r.workflow_version AND v.breaking = 1
+              )
+            ORDER BY r.workflow_version LIMIT 1
+            "#,
+            &[
+                Value::String(tenant_id.to_string()),
+                Value::String(workflow_id.to_string()),
+                Value::String(WorkflowRunStatus::Running.as_str().to_string()),
+                Value::String(WorkflowRunStatus::WaitingApproval.as_str().to_string()),
+            ],
+        ).await
+            .map_err(|e| ExecutorError::DatabaseError(e.to_string()))?;
+        Ok(result.rows.first().and_then(|row| row.get("workflow_version")).and_then(Value::as_u64).map(|v| v as u32))
+    }
+
+    async fn create_run(&self, definition: &WorkflowDefinition, version: Option<u32>, context: ExecutionContext) -> ExecutorResult<WorkflowRun> {
+        let run_id = uuid::Uuid::new_v4().to_string();
+        let now = Utc::now().to_rfc3339();
+        self.db.execute(
+            "INSERT INTO workflow_runs (id, workflow_id, tenant_id, workflow_version, status, definition, context, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            &[
+                Value::String(run_id.clone()),
+                Value::String(definition.id.clone()),
+                Value::String(context.tenant_id.clone()),
+                version.map(Value::from).unwrap_or(Value::Null),
+                Value::String(WorkflowRunStatus::Running.as_str().to_string()),
+                Value::String(serde_json::to_string(definition)?),
+                Value::String(serde_json::to_string(&context)?),
+                Value::String(now.clone()),
+                Value::String(now),
+            ],
+        ).await
+            .map_err(|e| ExecutorError::DatabaseError(e.to_string()))?;
+
+        self.run(run_id, definition.clone(), version, context).await
+    }
+
+    /// Publish a definition as the next version of its workflow
+    ///
+    /// Runs in flight keep their version. When `breaking` is set, the
+    /// workflow's [`MigrationPolicy`] is applied to in-flight runs of older versions.
+    pub async fn publish(
+        &self,
+        tenant_id: &str,
+        definition: &WorkflowDefinition,
+        breaking: bool,
+        published_by: Option<&str>,
+    ) -> ExecutorResult<PublishResult> {
+        definition.validate()?;
+
+        // The version number is allocated in the insert itself so concurrent publishes cannot pick the same one
+        let published_at = Utc::now();
+        let inserted = self.db.execute(
+            r#"
+            INSERT INTO workflow_versions (tenant_id, workflow_id, version, definition, breaking, published_by, published_at)
+            SELECT ?, ?, COALESCE(MAX(version), 0) + 1, ?, ?, ?, ?
+            FROM workflow_versions WHERE tenant_id = ? AND workflow_id = ?
+            "#,
+            &[
+                Value::String(tenant_id.to_string()),
+                Value::String(definition.id.clone()),
+                Value::String(serde_json::to_string(definition)?),
+                Value::Bool(breaking),
+                published_by.map(|user| Value::String(user.to_string())).unwrap_or(Value::Null),
+                Value::String(published_at.to_rfc3339()),
+                Value::String(tenant_id.to_string()),
+                Value::String(definition.id.clone()),
+            ],
+        ).await
+            .map_err(|e| ExecutorError::DatabaseError(e.to_string()))?;
+        let allocated = self.db.execute(
+            "SELECT version FROM workflow_versions WHERE rowid = ?",
+            &[Value::from(inserted.last_insert_id)],
+        ).await
+            .map_err(|e| ExecutorError::DatabaseError(e.to_string()))?;
+        let version = WorkflowVersion {
+            workflow_id: definition.id.clone(),
+            tenant_id: tenant_id.to_string(),
+            version: allocated.rows.first()
+                .and_then(|row| row.get("version"))
+                .and_then(Value::as_u64)
+                .ok_or_else(|| ExecutorError::DatabaseError("Published version was not stored".to_string()))? as u32,
+            definition: definition.clone(),
+            breaking,
+            published_by: published_by.map(str::to_string),
+            published_at,
+        };
+
+        let policy = self.migration_policy(tenant_id, &definition.id).await?;
+        let mut affected_runs = Vec::new();
+        if breaking {
+            let result = self.db.execute(
+                "SELECT id FROM workflow_runs WHERE tenant_id = ? AND workflow_id = ? AND workflow_version < ? AND status IN (?, ?)",
+                &[
+                    Value::String(tenant_id.to_string()),
+                    Value::String(definition.id.clone()),
+                    Value::from(version.version),
+                    Value::String(WorkflowRunStatus::Running.as_str().to_string()),
+                    Value::String(WorkflowRunStatus::WaitingApproval.as_str().to_string()),
+                ],
+            ).await
+                .map_err(|e| ExecutorError::DatabaseError(e.to_string()))?;
+
+            for run_id in result.rows.iter().filter_map(|row| text(row, "id")) {
+                if policy == MigrationPolicy::Cancel {
+                    let reason = format!("Cancelled by breaking version {}", version.version);
+                    if !self.cancel_run(&run_id, &reason).await? {
+                        continue;
+                    }
+                }
+                affected_runs.push(run_id);
+            }
+        }
+
+        Ok(PublishResult { version, policy, affected_runs })
+    }
+
+    /// Load a published version of a workflow, or its latest version when `version` is `None`
+    pub async fn get_version(&self, tenant_id: &str, workflow_id: &str, version: Option<u32>) -> ExecutorResult<Option<WorkflowVersion>> {
+        let mut sql = "SELECT * FROM workflow_versions WHERE tenant_id = ? AND workflow_id = ?".to_string();
+        let mut params = vec![Value::String(tenant_id.to_string()), Value::String(workflow_id.to_string())];
+        if let Some(version) = version {
+            sql.push_str(" AND version = ?");
+            params.push(Value::from(version));
+        }
+        sql.push_str(" ORDER BY version DESC LIMIT 1");
+
+        let result = self.db.execute(&sql, &params).await
+            .map_err(|e| ExecutorError::DatabaseError(e.to_string()))?;
+        result.rows.first().map(row_to_version).transpose()
+    }
+
+    /// List the published versions of a workflow, newest first
+    pub async fn list_versions(&self, tenant_id: &str, workflow_id: &str) -> ExecutorResult<Vec<WorkflowVersion>> {
+        let result = self.db.execute(
+            "SELECT * FROM workflow_versions WHERE tenant_id = ? AND workflow_id = ? ORDER BY version DESC",
+            &[Value::String(tenant_id.to_string()), Value::String(workflow_id.to_string())],
+        ).await
+            .map_err(|e| ExecutorError::DatabaseError(e.to_string()))?;
+        result.rows.iter().map(row_to_version).collect()
+    }
+
+    /// Migration policy of a workflow; [`MigrationPolicy::Drain`] unless configured
+    pub async fn migration_policy(&self, tenant_id: &str, workflow_id: &str) -> ExecutorResult<MigrationPolicy> {
+        let result = self.db.execute(
+            "SELECT policy FROM workflow_migration_policies WHERE tenant_id = ? AND workflow_id = ?",
+            &[Value::String(tenant_id.to_string()), Value::String(workflow_id.to_string())],
+        ).await
+            .map_err(|e| ExecutorError::DatabaseError(e.to_string()))?;
+
+        match result.rows.first().and_then(|row| text(row, "policy")) {
+            Some(policy) => MigrationPolicy::parse(&policy)
+                .ok_or_else(|| ExecutorError::DatabaseError(format!("Unknown migration policy '{}'", policy))),
+            None => Ok(MigrationPolicy::default()),
+        }
+    }
+
+    /// Configure how in-flight runs are handled when a breaking version of the workflow is published
+    pub async fn set_migration_policy(&self, tenant_id: &str, workflow_id: &str, policy: MigrationPolicy) -> ExecutorResult<()> {
+        self.db.execute(
+            r#"
+            INSERT INTO workflow_migration_policies (tenant_id, workflow_id, policy, updated_at)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT (tenant_id, workflow_id) DO UPDATE SET
+                policy = excluded.policy,
+                updated_at = excluded.updated_at
+            "#,
+            &[
+                Value::String(tenant_id.to_string()),
+                Value::String(workflow_id.to_string()),
+                Value::String(policy.as_str().to_string()),
+                Value::String(Utc::now().to_rfc3339()),
+            ],
+        ).await
+            .map_err(|e| ExecutorError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Cancel a running or waiting run and its pending approvals
+    ///
+    /// A running run stops before its next step.
+    pub async fn cancel(&self, run_id: &str) -> ExecutorResult<WorkflowRun> {
+        if !self.cancel_run(run_id, "Cancelled").await? {
+            let run = self.get_run(run_id).await?
+                .ok_or_else(|| ExecutorError::InvalidParameters(format!("Workflow run '{}' not found", run_id)))?;
+            return Err(ExecutorError::InvalidParameters(format!(
+                "Workflow run '{}' is {}, only running or waiting runs can be cancelled",
+                run_id, run.status.as_str()
+            )));
+        }
+        self.get_run(run_id).await?
+            .ok_or_else(|| ExecutorError::InternalError(format!("Workflow run '{}' disappeared", run_id)))
+    }
+
+    /// Mark a run cancelled; `false` when it was no longer in flight
+    async fn cancel_run(&self, run_id: &str, reason: &str) -> ExecutorResult<bool> {
+        let result = self.db.execute(
+            "UPDATE workflow_runs SET status = ?, error = ?, updated_at = ? WHERE id = ? AND status IN (?, ?)",
+            &[
+                Value::String(WorkflowRunStatus::Cancelled.as_str().to_string()),
+                Value::String(reason.to_string()),
+                Value::String(Utc::now().to_rfc3339()),
+                Value::String(run_id.to_string()),
+                Value::String(WorkflowRunStatus::Running.as_str().to_string()),
+                Value::String(WorkflowRunStatus::WaitingApproval.as_str().to_string()),
+            ],
+        ).await
+            .map_err(|e| ExecutorError::DatabaseError(e.to_string()))?;
+        if result.rows_affected == 0 {
+            return Ok(false);
+        }
+
+        let pending = self.db.execute(
+            "SELECT * FROM workflow_approvals WHERE run_id = ? AND status = ?",
+            &[
+                Value::String(run_id.to_string()),
+                Value::String(ApprovalStatus::Pending.as_str().to_string()),
+            ],
+        ).await
+            .map_err(|e| ExecutorError::DatabaseError(e.to_string()))?;
+        for row in &pending.rows {
+            self.close_approval(&row_to_approval(row)?, ApprovalStatus::Cancelled, None, None).await?;
+        }
+        Ok(true)
+    }
+
+    /// Retry a failed run, re-executing only the failed steps and iterations
+    pub async fn retry(&self, run_id: &str) -> ExecutorResult<WorkflowRun> {
+        let row = self.run_row(run_id).await?
+            .ok_or_else(|| ExecutorError::InvalidParameters(format!("Workflow run '{}' not found", run_id)))?;
+        let status = text(&row, "status");
+        if status.as_deref() != Some(WorkflowRunStatus::Failed.as_str()) {
+            return Err(ExecutorError::InvalidParameters(format!(
+                "Workflow run '{}' is {}, only failed runs can be retried",
+                run_id, status.unwrap_or_default()
+            )));
+        }
+        self.resume(run_id, &row).await
+    }
+
+    /// List the approvals of a tenant, newest first
+    ///
+    /// Overdue approvals stay pending until the approval sweeper expires them.
+    pub async fn list_approvals(&self, tenant_id: &str, status: Option<ApprovalStatus>) -> ExecutorResult<Vec<WorkflowApproval>> {
+        let mut sql = "SELECT * FROM workflow_approvals WHERE tenant_id = ?".to_string();
+        let mut params = vec![Value::String(tenant_id.to_string())];
+        if let Some(status) = status {
+            sql.push_str(" AND status = ?");
+            params.push(Value::String(status.as_str().to_string()));
+        }
+        sql.push_str(" ORDER BY created_at DESC");
+
+        let result = self.db.execute(&sql, &params).await
+            .map_err(|e| ExecutorError::DatabaseError(e.to_string()))?;
+        result.rows.iter().map(row_to_approval).collect()
+    }
+
+    /// Load an approval
+    pub async fn get_approval(&self, approval_id: &str) -> ExecutorResult<Option<WorkflowApproval>> {
+        let result = self.db.execute(
+            "SELECT * FROM workflow_approvals WHERE id = ?",
+            &[Value::String(approval_id.to_string())],
+        ).await
+            .map_err(|e| ExecutorError::DatabaseError(e.to_string()))?;
+        result.rows.first().map(row_to_approval).transpose()
+    }
+
+    /// Approve or reject a pending approval
+    ///
+    /// The decision is recorded right away and the run is marked running; its
+    /// remaining steps execute in the background.
+    pub async fn decide(&self, approval_id: &str, decision: ApprovalDecision) -> ExecutorResult<ApprovalOutcome> {
+        let approval = self.get_approval(approval_id).await?
+            .ok_or_else(|| ExecutorError::InvalidParameters(format!("Approval '{}' not found", approval_id)))?;
+        if approval.status != ApprovalStatus::Pending {
+            return Err(ExecutorError::InvalidParameters(format!(
+                "Approval '{}' is already {}", approval_id, approval.status.as_str()
+            )));
+        }
+        if !approval.can_decide(&decision.user_id, &decision.roles) {
+            return Err(ExecutorError::PermissionDenied);
+        }
+        if approval.expires_at.is_some_and(|expires_at| expires_at <= Utc::now()) {
+            if let Some(expired) = self.close_approval(&approval, ApprovalStatus::Expired, None, None).await? {
+                self.spawn_resume(expired).await?;
+            }
+            return Err(ExecutorError::InvalidParameters(format!("Approval '{}' has expired", approval_id)));
+        }
+
+        let status = if decision.approved { ApprovalStatus::Approved } else { ApprovalStatus::Rejected };
+        let Some(approval) = self.close_approval(&approval, status, Some(&decision.user_id), decision.comment.as_deref()).await? else {
+            return Err(ExecutorError::InvalidParameters(format!("Approval '{}' was decided concurrently", approval_id)));
+        };
+        let resumed = self.spawn_resume(approval.clone()).await?;
+        Ok(ApprovalOutcome { approval, resumed })
+    }
+
+    /// Mark the run of a closed approval running and continue it on a background task
+    async fn spawn_resume(&self, approval: WorkflowApproval) -> ExecutorResult<JoinHandle<ExecutorResult<WorkflowRun>>> {
+        self.update_run(&approval.run_id, WorkflowRunStatus::Running, None, None).await?;
+        let engine = self.clone();
+        Ok(tokio::spawn(async move {
+            let result = engine.resume_after(&approval).await;
+            if let Err(e) = &result {
+                tracing::warn!("Failed to resume workflow run {} after approval {}: {}", approval.run_id, approval.id, e);
+            }
+            result
+        }))
+    }
+
+    /// Expire the overdue pending approvals of a tenant and resume their runs
+    ///
+    /// A run that fails to resume is logged and does not stop the others.
+    ///
+    /// # Returns
+    /// The number of approvals expired
+    pub async fn expire_approvals(&self, tenant_id: &str) -> ExecutorResult<usize> {
+        let result = self.db.execute(
+            "SELECT * FROM workflow_approvals WHERE tenant_id = ? AND status = ? AND expires_at IS NOT NULL AND expires_at <= ?",
+            &[
+                Value::String(tenant_id.to_string()),
+                Value::String(ApprovalStatus::Pending.as_str().to_string()),
+                Value::String(Utc::now().to_rfc3339()),
+            ],
+        ).await
+            .map_err(|e| ExecutorError::DatabaseError(e.to_string()))?;
+
+        let mut expired = 0;
+        for row in &result.rows {
+            let approval = row_to_approval(row)?;
+            if let Some(approval) = self.close_approval(&approval, ApprovalStatus::Expired, None, None).await? {
+                expired += 1;
+                if let Err(e) = self.resume_after(&approval).await {
+                    tracing::warn!("Failed to resume workflow run {} after approval {} expired: {}", approval.run_id, approval.id, e);
+                }
+            }
+        }
+        Ok(expired)
+    }
+
+    /// Expire overdue approvals tenant by tenant; a failing tenant does not hold up the others
+    ///
+    /// # Returns
+    /// The number of approvals expired
+    pub async fn sweep_expired_approvals(&self) -> ExecutorResult<usize> {
+        let result = self.db.execute(
+            "SELECT DISTINCT tenant_id FROM workflow_approvals WHERE status = ? AND expires_at IS NOT NULL AND expires_at <= ?",
+            &[
+                Value::String(ApprovalStatus::Pending.as_str().to_string()),
+                Value::String(Utc::now().to_rfc3339()),
+            ],
+        ).await
+            .map_err(|e| ExecutorError::DatabaseError(e.to_string()))?;
+
+        let mut expired = 0;
+        for tenant_id in result.rows.iter().filter_map(|row| text(row, "tenant_id")) {
+            match self.expire_approvals(&tenant_id).await {
+                Ok(count) => expired += count,
+                Err(e) => tracing::warn!("Failed to expire approvals of tenant {}: {}", tenant_id, e),
+            }
+        }
+        Ok(expired)
+    }
+
+    /// Expire overdue approvals periodically in the background
+    pub fn start_approval_sweeper(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.sweep_expired_approvals().await {
+                    tracing::warn!("Approval sweep failed: {}", e);
+                }
+            }
+        })
+    }
+
+    /// Record the outcome of a pending approval; `None` when it was no longer pending
+    async fn close_approval(
+        &self,
+        approval: &WorkflowApproval,
+        status: ApprovalStatus,
+        decided_by: Option<&str>,
+        comment: Option<&str>,
+    ) -> ExecutorResult<Option<WorkflowApproval>> {
+        let now = Utc::now();
+        let result = self.db.execute(
+            "UPDATE workflow_approvals SET status = ?, decided_by = ?, comment = ?, decided_at = ? WHERE id = ? AND status = ?",
+            &[
+                Value::String(status.as_str().to_string()),
+                decided_by.map(|u| Value::String(u.to_string())).unwrap_or(Value::Null),
+                comment.map(|c| Value::String(c.to_string())).unwrap_or(Value::Null),
+                Value::String(now.to_rfc3339()),
+                Value::String(approval.id.clone()),
+                Value::String(ApprovalStatus::Pending.as_str().to_string()),
+            ],
+        ).await
+            .map_err(|e| ExecutorError::DatabaseError(e.to_string()))?;
+        if result.rows_affected == 0 {
+            return Ok(None);
+        }
+
+        let approval = WorkflowApproval {
+            status,
+            decided_by: decided_by.map(str::to_string),
+            comment: comment.map(str::to_string),
+            decided_at: Some(now),
+            ..approval.clone()
+        };
+        self.notify(&approval).await;
+        Ok(Some(approval))
+    }
+
+    /// Continue the run of a decided approval
+    async fn resume_after(&self, approval: &WorkflowApproval) -> ExecutorResult<WorkflowRun> {
+        let row = self.run_row(&approval.run_id).await?
+            .ok_or_else(|| ExecutorError::InternalError(format!("Workflow run '{}' not found", approval.run_id)))?;
+        self.resume(&approval.run_id, &row).await
+    }
+
+    async fn resume(&self, run_id: &str, row: &HashMap<String, Value>) -> ExecutorResult<WorkflowRun> {
+        let definition: WorkflowDefinition = serde_json::from_str(&text(row, "definition").unwrap_or_default())?;
+        let context: ExecutionContext = serde_json::from_str(&text(row, "context").unwrap_or_default())?;
+        let version = row.get("workflow_version").and_then(Value::as_u64).map(|v| v as u32);
+
+        self.update_run(run_id, WorkflowRunStatus::Running, None, None).await?;
+        self.run(run_id.to_string(), definition, version, context).await
+    }
+
+    /// Move a run to another version before its next top-level step
+    async fn migrate_run(&self, run_id: &str, from: u32, target: &WorkflowVersion) -> ExecutorResult<()> {
+        self.db.execute(
+            "UPDATE workflow_runs SET definition = ?, workflow_version = ? WHERE id = ?",
+            &[
+                Value::String(serde_json::to_string(&target.definition)?),
+                Value::from(target.version),
+                Value::String(run_id.to_string()),
+            ],
+        ).await
+            .map_err(|e| ExecutorError::DatabaseError(e.to_string()))?;
+        tracing::info!("Migrated workflow run {} from version {} to {}", run_id, from, target.version);
+        Ok(())
+    }
+
+    /// Version a run moves to under [`MigrationPolicy::Migrate`], if a breaking version followed its own
+    async fn migration_target(
+        &self,
+        run_id: &str,
+        tenant_id: &str,
+        definition: &WorkflowDefinition,
+        version: u32,
+    ) -> ExecutorResult<Option<WorkflowVersion>> {
+        if self.migration_policy(tenant_id, &definition.id).await? != MigrationPolicy::Migrate {
+            return Ok(None);
+        }
+        let breaking = self.db.execute(
+            "SELECT version FROM workflow_versions WHERE tenant_id = ? AND workflow_id = ? AND version > ? AND breaking = 1 LIMIT 1",
+            &[
+                Value::String(tenant_id.to_string()),
+                Value::String(definition.id.clone()),
+                Value::from(version),
+            ],
+        ).await
+            .map_err(|e| ExecutorError::DatabaseError(e.to_string()))?;
+        if breaking.rows.is_empty() {
+            return Ok(None);
+        }
+
+        let Some(latest) = self.get_version(tenant_id, &definition.id, None).await? else {
+            return Ok(None);
+        };
+        let steps = self.step_runs(run_id).await?;
+        if let Some(conflict) = migration_conflict(&steps, definition, &latest.definition) {
+            tracing::warn!("Workflow run {} stays on version {}: {}", run_id, version, conflict);
+            return Ok(None);
+        }
+        Ok(Some(latest))
+    }
+
+    async fn notify(&self, approval: &WorkflowApproval) {
+        let event = ApprovalEvent {
+            event: format!("approval.{}", if approval.status == ApprovalStatus::Pending { "requested" } else { approval.status.as_str() }),
+            approval: approval.clone(),
+        };
+        for notifier in &self.notifiers {
+            if let Err(e) = notifier.notify(&event).await {
+                tracing::warn!("Failed to deliver {} for approval {}: {}", event.event, approval.id, e);
+            }
+        }
+    }
+
+    /// Load a run with its step results
+    pub async fn get_run(&self, run_id: &str) -> ExecutorResult<Option<WorkflowRun>> {
+        let Some(row) = self.run_row(run_id).await? else {
+            return Ok(None);
+        };
+        let timestamp = |column: &str| {
+            text(&row, column)
+                .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+                .map(|dt| dt.with_timezone(&Utc))
+                .ok_or_else(|| ExecutorError::DatabaseError(format!("Invalid workflow run {}", column)))
+        };
+        let status_str = text(&row, "status").unwrap_or_default();
+
+        Ok(Some(WorkflowRun {
+            id: run_id.to_string(),
+            workflow_id: text(&row, "workflow_id").unwrap_or_default(),
+            tenant_id: text(&row, "tenant_id").unwrap_or_default(),
+            version: row.get("workflow_version").and_then(Value::as_u64).map(|v| v as u32),
+            status: WorkflowRunStatus::parse(&status_str)
+                .ok_or_else(|| ExecutorError::DatabaseError(format!("Unknown workflow run status '{}'", status_str)))?,
+            output: text(&row, "output").map(|s| serde_json::from_str(&s)).transpose()?,
+            error: text(&row, "error"),
+            steps: self.step_runs(run_id).await?,
+            created_at: timestamp("created_at")?,
+            updated_at: timestamp("updated_at")?,
+        }))
+    }
+
+    /// What a run was started with, redacted for reports
+    ///
+    /// Labels and note of the run, the names of its environment variables (values may
+    /// hold credentials under any name) and the parameters of its tool steps by step id.
+    pub async fn get_run_inputs(&self, run_id: &str) -> ExecutorResult<Option<Value>> {
+        fn collect(steps: &[WorkflowStep], parameters: &mut serde_json::Map<String, Value>) {
+            for step in steps {
+                match &step.kind {
+                    StepKind::Tool { parameters: step_parameters, .. } | StepKind::Foreach { parameters: step_parameters, .. } => {
+                        parameters.insert(step.id.clone(), crate::report::redact(&json!(step_parameters)));
+                    }
+                    StepKind::If { then, otherwise, .. } => {
+                        collect(then, parameters);
+                        collect(otherwise, parameters);
+                    }
+                    StepKind::Approval { rejected, .. } => collect(rejected, parameters),
+                    StepKind::Reduce { .. } => {}
+                }
+            }
+        }
+
+        let Some(row) = self.run_row(run_id).await? else {
+            return Ok(None);
+        };
+        let definition: WorkflowDefinition = serde_json::from_str(&text(&row, "definition").unwrap_or_default())?;
+        let context: ExecutionContext = serde_json::from_str(&text(&row, "context").unwrap_or_default())?;
+        let mut environment: Vec<&String> = context.environment.keys().collect();
+        environment.sort();
+        let mut parameters = serde_json::Map::new();
+        collect(&definition.steps, &mut parameters);
+        Ok(Some(json!({
+            "labels": context.labels,
+            "note": context.note,
+            "environment": environment,
+            "parameters": parameters,
+        })))
+    }
+
+    /// Execute the steps of a run that have not completed yet
+    ///
+    /// No step of the run is executing before a top-level step starts, which
+    /// makes it a safe checkpoint: a run of a published version is migrated
+    /// there when [`MigrationPolicy::Migrate`] applies, and continues on the
+    /// new definition with the results of its completed steps.
+    async fn run(
+        &self,
+        run_id: String,
+        mut definition: WorkflowDefinition,
+        mut version: Option<u32>,
+        context: ExecutionContext,
+    ) -> ExecutorResult<WorkflowRun> {
+        let (outcome, state) = 'run: loop {
+            let previous = self.step_runs(&run_id).await?
+                .into_iter()
+                .map(|run| ((run.step_id.clone(), run.iteration.map_or(WHOLE_STEP, |i| i as i64)), run))
+                .collect();
+            let mut state = RunState {
+                run_id: run_id.clone(),
+                workflow_id: definition.id.clone(),
+                context: context.clone(),
+                previous,
+                outputs: serde_json::Map::new(),
+            };
+
+            for index in 0..definition.steps.len() {
+                if self.is_cancelled(&run_id).await? {
+                    break 'run (Err(Halt::Cancelled), state);
+                }
+                if let Some(current) = version {
+                    if let Some(target) = self.migration_target(&run_id, &context.tenant_id, &definition, current).await? {
+                        self.migrate_run(&run_id, current, &target).await?;
+                        version = Some(target.version);
+                        definition = target.definition;
+                        continue 'run;
+                    }
+                }
+                if let Err(halt) = self.run_step(&definition.steps[index], &mut state).await? {
+                    break 'run (Err(halt), state);
+                }
+            }
+            break (Ok(()), state);
+        };
+
+        match outcome {
+            Ok(()) => {
+                let output = Value::Object(state.outputs);
+                self.update_run(&run_id, WorkflowRunStatus::Completed, Some(&output), None).await?;
+            }
+            Err(Halt::Failed { step_id, message }) => {
+                let error = format!("Step '{}' failed: {}", step_id, message);
+                self.update_run(&run_id, WorkflowRunStatus::Failed, None, Some(&error)).await?;
+            }
+            Err(Halt::WaitingApproval) => {
+                self.update_run(&run_id, WorkflowRunStatus::WaitingApproval, None, None).await?;
+            }
+            Err(Halt::Cancelled) => {}
+        }
+
+        self.get_run(&run_id).await?
+            .ok_or_else(|| ExecutorError::InternalError(format!("Workflow run '{}' disappeared", run_id)))
+    }
+
+    fn run_steps<'a>(&'a self, steps: &'a [WorkflowStep], state: &'a mut RunState) -> StepFuture<'a> {
+        Box::pin(async move {
+            for step in steps {
+                if self.is_cancelled(&state.run_id).await? {
+                    return Ok(Err(Halt::Cancelled));
+                }
+                if let Err(halt) = self.run_step(step, state).await? {
+                    return Ok(Err(halt));
+                }
+            }
+            Ok(Ok(()))
+        })
+    }
+
+    async fn run_step(&self, step: &WorkflowStep, state: &mut RunState) -> ExecutorResult<Result<(), Halt>> {
+        let fail = |message: String| Ok(Err(Halt::Failed { step_id: step.id.clone(), message }));
+
+        // Branching steps are re-entered on retry so that failed steps of the chosen branch run again
+        if !matches!(step.kind, StepKind::If { .. } | StepKind::Approval { .. }) {
+            if let Some(run) = state.completed(&step.id, WHOLE_STEP) {
+                let output = run.output.clone().unwrap_or(Value::Null);
+                state.outputs.insert(step.id.clone(), output);
+                return Ok(Ok(()));
+            }
+        }
+
+        match &step.kind {
+            StepKind::Tool { tool_id, parameters } => {
+                let attempts = state.attempts(&step.id, WHOLE_STEP) + 1;
+                let outcome = self.execute(tool_id, parameters, state.template_context(), &state.context).await;
+                let failed = outcome.as_ref().err().cloned();
+                let (execution_id, output) = outcome.unwrap_or_default();
+                self.record_step(&state.run_id, &step.id, WHOLE_STEP, execution_id.as_deref(), output.as_ref(), failed.as_deref(), attempts).await?;
+                match failed {
+                    Some(message) => fail(message),
+                    None => {
+                        state.outputs.insert(step.id.clone(), output.unwrap_or(Value::Null));
+                        Ok(Ok(()))
+                    }
+                }
+            }
+            StepKind::If { condition, then, otherwise } => {
+                let branch = match state.completed(&step.id, WHOLE_STEP) {
+                    Some(run) => run.output.as_ref().and_then(|o| o["branch"].as_str()).map(|b| b == "then").unwrap_or(true),
+                    None => match state.template_context().render(condition) {
+                        Ok(value) => template::is_truthy(&value),
+                        Err(e) => {
+                            let attempts = state.attempts(&step.id, WHOLE_STEP) + 1;
+                            let message = e.to_string();
+                            self.record_step(&state.run_id, &step.id, WHOLE_STEP, None, None, Some(&message), attempts).await?;
+                            return fail(message);
+                        }
+                    },
+                };
+                let output = json!({ "branch": if branch { "then" } else { "else" } });
+                if state.completed(&step.id, WHOLE_STEP).is_none() {
+                    let attempts = state.attempts(&step.id, WHOLE_STEP) + 1;
+                    self.record_step(&state.run_id, &step.id, WHOLE_STEP, None, Some(&output), None, attempts).await?;
+                }
+                state.outputs.insert(step.id.clone(), output);
+                self.run_steps(if branch { then } else { otherwise }, state).await
+            }
+            StepKind::Foreach { items, tool_id, parameters, max_parallelism } => {
+                let items = match self.evaluate_array(&step.id, items, state).await? {
+                    Ok(items) => items,
+                    Err(message) => return fail(message),
+                };
+
+                let base_context = state.template_context();
+                let context = &state.context;
+                let pending: Vec<(usize, Value)> = items.iter().cloned().enumerate()
+                    .filter(|(index, _)| state.completed(&step.id, *index as i64).is_none())
+                    .collect();
+                let results: Vec<(usize, ToolOutcome)> = stream::iter(pending)
+                    .map(|(index, item)| {
+                        let template_context = base_context.clone()
+                            .with_variable("item", item)
+                            .with_variable("index", Value::from(index));
+                        async move { (index, self.execute(tool_id, parameters, template_context, context).await) }
+                    })
+                    .buffer_unordered(*max_parallelism)
+                    .collect()
+                    .await;
+
+                let mut outputs: Vec<Value> = (0..items.len())
+                    .map(|index| state.completed(&step.id, index as i64).and_then(|run| run.output.clone()).unwrap_or(Value::Null))
+                    .collect();
+                let mut failed = Vec::new();
+                for (index, result) in results {
+                    let attempts = state.attempts(&step.id, index as i64) + 1;
+                    match result {
+                        Ok((execution_id, output)) => {
+                            self.record_step(&state.run_id, &step.id, index as i64, execution_id.as_deref(), output.as_ref(), None, attempts).await?;
+                            outputs[index] = output.unwrap_or(Value::Null);
+                        }
+                        Err(message) => {
+                            self.record_step(&state.run_id, &step.id, index as i64, None, None, Some(&message), attempts).await?;
+                            failed.push(index);
+                        }
+                    }
+                }
+
+                let attempts = state.attempts(&step.id, WHOLE_STEP) + 1;
+                if failed.is_empty() {
+                    let output = Value::Array(outputs);
+                    self.record_step(&state.run_id, &step.id, WHOLE_STEP, None, Some(&output), None, attempts).await?;
+                    state.outputs.insert(step.id.clone(), output);
+                    Ok(Ok(()))
+                } else {
+                    failed.sort_unstable();
+                    let message = format!("{} of {} iterations failed (indexes {:?})", failed.len(), items.len(), failed);
+                    self.record_step(&state.run_id, &step.id, WHOLE_STEP, None, None, Some(&message), attempts).await?;
+                    fail(message)
+                }
+            }
+            StepKind::Reduce { items, operation, path } => {
+                let attempts = state.attempts(&step.id, WHOLE_STEP) + 1;
+                let result = match self.evaluate_array(&step.id, items, state).await? {
+                    Ok(items) => reduce(&items, *operation, path.as_deref()),
+                    Err(message) => Err(message),
+                };
+                match result {
+                    Ok(output) => {
+                        self.record_step(&state.run_id, &step.id, WHOLE_STEP, None, Some(&output), None, attempts).await?;
+                        state.outputs.insert(step.id.clone(), output);
+                        Ok(Ok(()))
+                    }
+                    Err(message) => {
+                        self.record_step(&state.run_id, &step.id, WHOLE_STEP, None, None, Some(&message), attempts).await?;
+                        fail(message)
+                    }
+                }
+            }
+            StepKind::Approval { title, approvers, timeout_seconds, rejected } => {
+                let output = match state.completed(&step.id, WHOLE_STEP) {
+                    Some(run) => run.output.clone().unwrap_or(Value::Null),
+                    None => {
+                        // A step that failed earlier asks for a new approval on retry
+                        let approval = if state.waiting(&step.id) {
+                            self.latest_approval(&state.run_id, &step.id).await?
+                        } else {
+                            None
+                        };
+                        let approval = match approval {
+                            Some(approval) if approval.status == ApprovalStatus::Pending => return Ok(Err(Halt::WaitingApproval)),
+                            Some(approval) => approval,
+                            None => {
+                                let attempts = state.attempts(&step.id, WHOLE_STEP) + 1;
+                                let title = match state.template_context().render(title) {
+                                    Ok(Value::String(title)) => title,
+                                    Ok(other) => other.to_string(),
+                                    Err(e) => {
+                                        let message = e.to_string();
+                                        self.record_step(&state.run_id, &step.id, WHOLE_STEP, None, None, Some(&message), attempts).await?;
+                                        return fail(message);
+                                    }
+                                };
+                                self.request_approval(state, &step.id, title, approvers, *timeout_seconds).await?;
+                                self.record_waiting(&state.run_id, &step.id, attempts).await?;
+                                return Ok(Err(Halt::WaitingApproval));
+                            }
+                        };
+
+                        let attempts = state.attempts(&step.id, WHOLE_STEP);
+                        let output = approval.decision_output();
+                        if approval.status != ApprovalStatus::Approved && rejected.is_empty() {
+                            let message = match &approval.decided_by {
+                                Some(user) => format!("Approval {} by {}", approval.status.as_str(), user),
+                                None => format!("Approval {}", approval.status.as_str()),
+                            };
+                            self.record_step(&state.run_id, &step.id, WHOLE_STEP, None, Some(&output), Some(&message), attempts).await?;
+                            return fail(message);
+                        }
+                        self.record_step(&state.run_id, &step.id, WHOLE_STEP, None, Some(&output), None, attempts).await?;
+                        output
+                    }
+                };
+
+                let approved = output["decision"] == ApprovalStatus::Approved.as_str();
+                state.outputs.insert(step.id.clone(), output);
+                if approved {
+                    Ok(Ok(()))
+                } else {
+                    self.run_steps(rejected, state).await
+                }
+            }
+        }
+    }
+
+    async fn request_approval(
+        &self,
+        state: &RunState,
+        step_id: &str,
+        title: String,
+        approvers: &[String],
+        timeout_seconds: Option<u64>,
+    ) -> ExecutorResult<()> {
+        let now = Utc::now();
+        let approval = WorkflowApproval {
+            id: uuid::Uuid::new_v4().to_string(),
+            run_id: state.run_id.clone(),
+            workflow_id: state.workflow_id.clone(),
+            step_id: step_id.to_string(),
+            tenant_id: state.context.tenant_id.clone(),
+            title,
+            approvers: approvers.to_vec(),
+            status: ApprovalStatus::Pending,
+            decided_by: None,
+            comment: None,
+            expires_at: timeout_seconds.map(|seconds| now + chrono::Duration::seconds(seconds.min(i64::MAX as u64) as i64)),
+            created_at: now,
+            decided_at: None,
+        };
+
+        self.db.execute(
+            "INSERT INTO workflow_approvals (id, run_id, workflow_id, step_id, tenant_id, title, approvers, status, expires_at, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            &[
+                Value::String(approval.id.clone()),
+                Value::String(approval.run_id.clone()),
+                Value::String(approval.workflow_id.clone()),
+                Value::String(approval.step_id.clone()),
+                Value::String(approval.tenant_id.clone()),
+                Value::String(approval.title.clone()),
+                Value::String(serde_json::to_string(&approval.approvers)?),
+                Value::String(approval.status.as_str().to_string()),
+                approval.expires_at.map(|at| Value::String(at.to_rfc3339())).unwrap_or(Value::Null),
+                Value::String(approval.created_at.to_rfc3339()),
+            ],
+        ).await
+            .map_err(|e| ExecutorError::DatabaseError(e.to_string()))?;
+
+        self.notify(&approval).await;
+        Ok(())
+    }
+
+    async fn latest_approval(&self, run_id: &str, step_id: &str) -> ExecutorResult<Option<WorkflowApproval>> {
+        let result = self.db.execute(
+            "SELECT * FROM workflow_approvals WHERE run_id = ? AND step_id = ? ORDER BY created_at DESC LIMIT 1",
+            &[Value::String(run_id.to_string()), Value::String(step_id.to_string())],
+        ).await
+            .map_err(|e| ExecutorError::DatabaseError(e.to_string()))?;
+        result.rows.first().map(row_to_approval).transpose()
+    }
+
+    /// Evaluate the `items` template of a step, which must produce an array
+    async fn evaluate_array(&self, step_id: &str, items: &str, state: &RunState) -> ExecutorResult<Result<Vec<Value>, String>> {
+        let result = match state.template_context().render(items) {
+            Ok(Value::Array(items)) => Ok(items),
+            Ok(other) => Err(format!("items must be an array, got {}", other)),
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(message) = &result {
+            let attempts = state.attempts(step_id, WHOLE_STEP) + 1;
+            self.record_step(&state.run_id, step_id, WHOLE_STEP, None, None, Some(message), attempts).await?;
+        }
+        Ok(result)
+    }
+
+    /// Render the parameters and execute the tool
+    async fn execute(
+        &self,
+        tool_id: &ToolId,
+        parameters: &HashMap<String, Value>,
+        template_context: TemplateContext,
+        context: &ExecutionContext,
+    ) -> ToolOutcome {
+        let rendered = template_context.render_parameters(parameters).map_err(|e| e.to_string())?;
+        // The executor renders parameters again; keep `{{` coming from step outputs literal
+        let parameters = rendered.iter().map(|(name, value)| (name.clone(), template::escape(value))).collect();
+
+        let request = ExecutionRequest {
+            tool_id: tool_id.clone(),
+            version: None,
+            parameters,
+            context: context.clone(),
+            options: ExecutionOptions::default(),
+        };
+        let result = self.executor.execute_tool(request).await.map_err(|e| e.to_string())?;
+        let execution_id = result.metadata.get("execution_id").and_then(|id| id.as_str()).map(String::from);
+        if result.success {
+            Ok((execution_id, result.output))
+        } else {
+            Err(result.error.unwrap_or_else(|| "Tool execution failed".to_string()))
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn record_step(
+        &self,
+        run_id: &str,
+        step_id: &str,
+        iteration: i64,
+        execution_id: Option<&str>,
+        output: Option<&Value>,
+        error: Option<&str>,
+        attempts: u32,
+    ) -> ExecutorResult<()> {
+        let status = if error.is_some() { StepRunStatus::Failed } else { StepRunStatus::Completed };
+        self.upsert_step(run_id, step_id, iteration, status, execution_id, output, error, attempts).await
+    }
+
+    async fn record_waiting(&self, run_id: &str, step_id: &str, attempts: u32) -> ExecutorResult<()> {
+        self.upsert_step(run_id, step_id, WHOLE_STEP, StepRunStatus::Waiting, None, None, None, attempts).await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn upsert_step(
+        &self,
+        run_id: &str,
+        step_id: &str,
+        iteration: i64,
+        status: StepRunStatus,
+        execution_id: Option<&str>,
+        output: Option<&Value>,
+        error: Option<&str>,
+        attempts: u32,
+    ) -> ExecutorResult<()> {
+        let optional = |value: Option<&str>| value.map(|v| Value::String(v.to_string())).unwrap_or(Value::Null);
+        let output = output.map(serde_json::to_string).transpose()?;
+
+        self.db.execute(
+            r#"
+            INSERT INTO workflow_step_runs (run_id, step_id, iteration, status, execution_id, output, error, attempts, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (run_id, step_id, iteration) DO UPDATE SET
+                status = excluded.status,
+                execution_id = excluded.execution_id,
+                output = excluded.output,
+                error = excluded.error,
+                attempts = excluded.attempts,
+                updated_at = excluded.updated_at
+            "#,
+            &[
+                Value::String(run_id.to_string()),
+                Value::String(step_id.to_string()),
+                Value::from(iteration),
+                Value::String(status.as_str().to_string()),
+                optional(execution_id),
+                optional(output.as_deref()),
+                optional(error),
+                Value::from(attempts),
+                Value::String(Utc::now().to_rfc3339()),
+            ],
+        ).await
+            .map_err(|e| ExecutorError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Update the status of a run unless it has been cancelled meanwhile
+    async fn update_run(&self, run_id: &str, status: WorkflowRunStatus, output: Option<&Value>, error: Option<&str>) -> ExecutorResult<()> {
+        let output = output.map(serde_json::to_string).transpose()?;
+        self.db.execute(
+            "UPDATE workflow_runs SET status = ?, output = ?, error = ?, updated_at = ? WHERE id = ? AND status != ?",
+            &[
+                Value::String(status.as_str().to_string()),
+                output.map(Value::String).unwrap_or(Value::Null),
+                error.map(|e| Value::String(e.to_string())).unwrap_or(Value::Null),
+                Value::String(Utc::now().to_rfc3339()),
+                Value::String(run_id.to_string()),
+                Value::String(WorkflowRunStatus::Cancelled.as_str().to_string()),
+            ],
+        ).await
+            .map_err(|e| ExecutorError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn is_cancelled(&self, run_id: &str) -> ExecutorResult<bool> {
+        let row = self.run_row(run_id).await?;
+        Ok(row.and_then(|row| text(&row, "status")).as_deref() == Some(WorkflowRunStatus::Cancelled.as_str()))
+    }
+
+    async fn run_row(&self, run_id: &str) -> ExecutorResult<Option<HashMap<String, Value>>> {
+        let result = self.db.execute(
+            "SELECT workflow_id, tenant_id, workflow_version, status, definition, context, output, error, created_at, updated_at FROM workflow_runs WHERE id = ?",
+            &[Value::String(run_id.to_string())],
+        ).await
+            .map_err(|e| ExecutorError::DatabaseError(e.to_string()))?;
+        Ok(result.rows.into_iter().next())
+    }
+
+    async fn step_runs(&self, run_id: &str) -> ExecutorResult<Vec<StepRun>> {
+        let result = self.db.execute(
+            "SELECT step_id, iteration, status, execution_id, output, error, attempts, updated_at FROM workflow_step_runs WHERE run_id = ? ORDER BY updated_at ASC, step_id ASC, iteration ASC",
+            &[Value::String(run_id.to_string())],
+        ).await
+            .map_err(|e| ExecutorError::DatabaseError(e.to_string()))?;
+
+        result.rows.iter().map(|row| {
+            let status_str = text(row, "status").unwrap_or_default();
+            let iteration = row.get("iteration").and_then(|v| v.as_i64()).unwrap_or(WHOLE_STEP);
+            Ok(StepRun {
+                step_id: text(row, "step_id").unwrap_or_default(),
+                iteration: usize::try_from(iteration).ok(),
+                status: StepRunStatus::parse(&status_str)
+                    .ok_or_else(|| ExecutorError::DatabaseError(format!("Unknown step status '{}'", status_str)))?,
+                execution_id: text(row, "execution_id"),
+                output: text(row, "output").map(|s| serde_json::from_str(&s)).transpose()?,
+                error: text(row, "error"),
+                attempts: row.get("attempts").and_then(|v| v.as_u64()).unwrap_or(0) as u32,
+                updated_at: text(row, "updated_at")
+                    .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+                    .map(|dt| dt.with_timezone(&Utc))
+                    .ok_or_else(|| ExecutorError::DatabaseError("Invalid step run timestamp".to_string()))?,
+            })
+        }).collect()
+    }
+}
+
+/// Read a TEXT column, `None` when it is NULL
+fn text(row: &HashMap<String, Value>, column: &str) -> Option<String> {
+    row.get(column).and_then(|v| v.as_str()).map(|s| s.to_string())
+}
+
+fn row_to_version(row: &HashMap<String, Value>) -> ExecutorResult<WorkflowVersion> {
+    Ok(WorkflowVersion {
+        workflow_id: text(row, "workflow_id").unwrap_or_default(),
+        tenant_id: text(row, "tenant_id").unwrap_or_default(),
+        version: row.get("version").and_then(Value::as_u64).unwrap_or(0) as u32,
+        definition: serde_json::from_str(&text(row, "definition").unwrap_or_default())?,
+        breaking: row.get("breaking").and_then(Value::as_i64).unwrap_or(0) != 0,
+        published_by: text(row, "published_by"),
+        published_at: text(row, "published_at")
+            .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+            .map(|dt| dt.with_timezone(&Utc))
+            .ok_or_else(|| ExecutorError::DatabaseError("Invalid workflow version published_at".to_string()))?,
+    })
+}
+
+/// Steps of a definition, including nested branches, by id
+fn steps_by_id<'a>(steps: &'a [WorkflowStep], found: &mut HashMap<&'a str, &'a StepKind>) {
+    for step in steps {
+        found.insert(step.id.as_str(), &step.kind);
+        match &step.kind {
+            StepKind::If { then, otherwise, .. } => {
+                steps_by_id(then, found);
+                steps_by_id(otherwise, found);
+            }
+            StepKind::Approval { rejected, .. } => steps_by_id(rejected, found),
+            _ => {}
+        }
+    }
+}
+
+/// Why a run that has reached `steps` on `from` cannot continue on `to`
+fn migration_conflict(steps: &[StepRun], from: &WorkflowDefinition, to: &WorkflowDefinition) -> Option<String> {
+    let (mut old, mut new) = (HashMap::new(), HashMap::new());
+    steps_by_id(&from.steps, &mut old);
+    steps_by_id(&to.steps, &mut new);
+
+    steps.iter()
+        .filter(|run| run.iteration.is_none())
+        .find_map(|run| {
+            let step_id = run.step_id.as_str();
+            match (old.get(step_id), new.get(step_id)) {
+                (_, None) => Some(format!("step '{}' no longer exists", step_id)),
+                (Some(old), Some(new)) if std::mem::discriminant(*old) != std::mem::discriminant(*new) => {
+                    Some(format!("step '{}' changed type", step_id))
+                }
+                _ => None,
+            }
+        })
+}
+
+fn row_to_approval(row: &HashMap<String, Value>) -> ExecutorResult<WorkflowApproval> {
+    let timestamp = |column: &str| {
+        text(row, column)
+            .map(|s| {
+                DateTime::parse_from_rfc3339(&s)
+                    .map(|dt| dt.with_timezone(&Utc))
+                    .map_err(|e| ExecutorError::DatabaseError(format!("Invalid approval {}: {}", column, e)))
+            })
+            .transpose()
+    };
+    let status_str = text(row, "status").unwrap_or_default();
+
+    Ok(WorkflowApproval {
+        id: text(row, "id").unwrap_or_default(),
+        run_id: text(row, "run_id").unwrap_or_default(),
+        workflow_id: text(row, "workflow_id").unwrap_or_default(),
+        step_id: text(row, "step_id").unwrap_or_default(),
+        tenant_id: text(row, "tenant_id").unwrap_or_default(),
+        title: text(row, "title").unwrap_or_default(),
+        approvers: serde_json::from_str(&text(row, "approvers").unwrap_or_else(|| "[]".to_string()))?,
+        status: ApprovalStatus::parse(&status_str)
+            .ok_or_else(|| ExecutorError::DatabaseError(format!("Unknown approval status '{}'", status_str)))?,
+        decided_by: text(row, "decided_by"),
+        comment: text(row, "comment"),
+        expires_at: timestamp("expires_at")?,
+        created_at: timestamp("created_at")?
+            .ok_or_else(|| ExecutorError::DatabaseError("Approval without created_at".to_string()))?,
+        decided_at: timestamp("decided_at")?,
+    })
+}
+
+/// Apply a reduce operation to the values selected by `path`
+pub(crate) fn reduce(items: &[Value], operation: ReduceOperation, path: Option<&str>) -> Result<Value, String> {
+    let values: Vec<Value> = items
+        .iter()
+        .map(|item| match path {
+            Some(path) => path
+                .split('.')
+                .filter(|segment| !segment.is_empty())
+                .try_fold(item, |value, segment| match value {
+                    Value::Array(items) => segment.parse::<usize>().ok().and_then(|i| items.get(i)),
+                    _ => value.get(segment),
+                })
+                .cloned()
+                .unwrap_or(Value::Null),
+            None => item.clone(),
+        })
+        .collect();
+
+    let numbers = || {
+        values.iter()
+            .map(|value| value.as_f64().ok_or_else(|| format!("{:?} needs numbers, got {}", operation, value)))
+            .collect::<Result<Vec<f64>, String>>()
+    };
+    let number = |n: f64| serde_json::Number::from_f64(n).map(Value::Number).unwrap_or(Value::Null);
+
+    match operation {
+        ReduceOperation::Collect => Ok(Value::Array(values)),
+        ReduceOperation::Count => Ok(Value::from(values.len())),
+        ReduceOperation::Sum => {
+            // Keep integer sums integral
+            if let Some(ints) = values.iter().map(Value::as_i64).collect::<Option<Vec<i64>>>() {
+                return Ok(Value::from(ints.iter().sum::<i64>()));
+            }
+            Ok(number(numbers()?.iter().sum()))
+        }
+        ReduceOperation::Min | ReduceOperation::Max => {
+            let numbers = numbers()?;
+            let pick = if operation == ReduceOperation::Min { f64::min } else { f64::max };
+            Ok(numbers.into_iter().reduce(pick).map(number).unwrap_or(Value::Null))
+        }
+        ReduceOperation::Concat => {
+            if values.iter().all(Value::is_string) {
+                return Ok(Value::String(values.iter().filter_map(Value::as_str).collect()));
+            }
+            let mut concatenated = Vec::new();
+            for value in values {
+                match value {
+                    Value::Array(items) => concatenated.extend(items),
+                    Value::Null => {}
+                    other => return Err(format!("Concat needs arrays or strings, got {}", other)),
+                }
+            }
+            Ok(Value::Array(concatenated))
+        }
+        ReduceOperation::Merge => {
+            let mut merged = serde_json::Map::new();
+            for value in values {
+                match value {
+                    Value::Object(fields) => merged.extend(fields),
+                    Value::Null => {}
+                    other => return Err(format!("Merge needs objects, got {}", other)),
+                }
+            }
+            Ok(Value::Object(merged))
+        }
+    }
+}
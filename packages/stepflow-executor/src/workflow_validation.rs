@@ -0,0 +1,570 @@
+//! Workflow validation and dry-runs
+//!
+//! [`WorkflowValidator::validate`] checks a definition without running it:
+//! step ids are unique, referenced tools exist and are active, templates
+//! parse, and every `steps.<id>` reference points at a step that has already
+//! run. A reference to the step itself or to a later step would make the
+//! workflow wait on its own output, so it is reported as a cycle.
+//!
+//! [`WorkflowValidator::dry_run`] additionally simulates the workflow with
+//! mock step outputs, producing the execution plan: which branches are taken,
+//! how many iterations a `foreach` fans out to, and the parameters each tool
+//! would receive. Tools do not publish an input schema, so rendered
+//! parameters are type-checked against the inputs of the tool's examples when
+//! it has any.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use stepflow_core::*;
+use stepflow_registry::Registry;
+use crate::execution_context::ExecutionContext;
+use crate::template::{self, TemplateContext};
+use crate::workflow::{self, StepKind, WorkflowDefinition, WorkflowStep};
+
+/// How serious a validation issue is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueSeverity {
+    /// The workflow would fail or cannot run
+    Error,
+    /// The workflow runs but probably not as intended
+    Warning,
+}
+
+/// Problem found in a workflow definition
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValidationIssue {
+    pub severity: IssueSeverity,
+    /// Step the issue belongs to; `None` for the whole workflow
+    pub step_id: Option<String>,
+    pub message: String,
+}
+
+/// A step as it would run in a dry-run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannedStep {
+    pub step_id: String,
+    /// `tool`, `if`, `foreach`, `reduce` or `approval`
+    pub kind: String,
+    pub tool_id: Option<ToolId>,
+    /// Steps whose outputs this step reads
+    pub depends_on: Vec<String>,
+    /// Rendered tool parameters; one entry per iteration for a `foreach`
+    pub parameters: Vec<HashMap<String, Value>>,
+    /// Branch taken by an `if` or `approval` step
+    pub branch: Option<String>,
+    /// Mock output made available to later steps
+    pub output: Value,
+}
+
+/// Options for a dry-run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DryRunOptions {
+    /// Outputs to assume for steps, by step id; tools default to the output of
+    /// their first example, or `{}`
+    #[serde(default)]
+    pub mock_outputs: HashMap<String, Value>,
+    /// Decision assumed for approval steps; approved unless set to `false`
+    #[serde(default)]
+    pub approve: Option<bool>,
+    /// Context the templates see; placeholders are used when absent
+    #[serde(default)]
+    pub context: Option<ExecutionContext>,
+}
+
+/// Result of a dry-run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DryRunReport {
+    /// Whether no errors were found
+    pub valid: bool,
+    pub plan: Vec<PlannedStep>,
+    pub issues: Vec<ValidationIssue>,
+}
+
+/// Validates workflow definitions against the tool registry
+pub struct WorkflowValidator {
+    registry: Arc<dyn Registry>,
+}
+
+impl WorkflowValidator {
+    pub fn new(registry: Arc<dyn Registry>) -> Self {
+        Self { registry }
+    }
+
+    /// Check a definition without simulating it
+    pub async fn validate(&self, definition: &WorkflowDefinition) -> Vec<ValidationIssue> {
+        let mut checker = Checker::new(definition);
+        if let Err(e) = definition.validate() {
+            checker.error(None, e.to_string());
+            return checker.issues;
+        }
+        checker.check_steps(&definition.steps, &Scope::default());
+        self.check_tools(definition, &mut checker).await;
+        checker.issues
+    }
+
+    /// Validate a definition and simulate it with mock outputs
+    pub async fn dry_run(&self, definition: &WorkflowDefinition, options: &DryRunOptions) -> DryRunReport {
+        let mut issues = self.validate(definition).await;
+        let mut plan = Vec::new();
+        if !issues.iter().any(|issue| issue.severity == IssueSeverity::Error && issue.step_id.is_none()) {
+            let mut simulation = Simulation {
+                registry: self.registry.as_ref(),
+                options,
+                base: base_context(definition, options.context.as_ref()),
+                outputs: serde_json::Map::new(),
+                failed: issues.iter().filter_map(|issue| issue.step_id.clone()).collect(),
+                plan: &mut plan,
+                issues: &mut issues,
+            };
+            simulation.run(&definition.steps).await;
+        }
+
+        DryRunReport {
+            valid: !issues.iter().any(|issue| issue.severity == IssueSeverity::Error),
+            plan,
+            issues,
+        }
+    }
+
+    async fn check_tools(&self, definition: &WorkflowDefinition, checker: &mut Checker<'_>) {
+        let mut tools: Vec<(&str, &ToolId)> = Vec::new();
+        visit_steps(&definition.steps, &mut |step| {
+            if let StepKind::Tool { tool_id, .. } | StepKind::Foreach { tool_id, .. } = &step.kind {
+                tools.push((&step.id, tool_id));
+            }
+        });
+
+        for (step_id, tool_id) in tools {
+            match self.registry.get_tool(tool_id).await {
+                Ok(tool) => match tool.status {
+                    ToolStatus::Active => {}
+                    ToolStatus::Deprecated => checker.warning(Some(step_id), format!("Tool {} is deprecated", tool_id)),
+                    status => checker.error(Some(step_id), format!("Tool {} is {}", tool_id, status)),
+                },
+                Err(_) => checker.error(Some(step_id), format!("Tool {} not found", tool_id)),
+            }
+        }
+    }
+}
+
+/// Steps visible from a point of the workflow
+#[derive(Clone, Default)]
+struct Scope<'a> {
+    /// Steps that certainly ran before
+    done: HashSet<&'a str>,
+    /// Steps that ran before on some branches only
+    conditional: HashSet<&'a str>,
+    /// Steps on alternative branches, which never run together with this one
+    elsewhere: HashSet<&'a str>,
+}
+
+/// Static checks of step references
+struct Checker<'a> {
+    /// Position of every step in execution order
+    order: HashMap<&'a str, usize>,
+    issues: Vec<ValidationIssue>,
+}
+
+impl<'a> Checker<'a> {
+    fn new(definition: &'a WorkflowDefinition) -> Self {
+        let mut order = HashMap::new();
+        visit_steps(&definition.steps, &mut |step| {
+            let position = order.len();
+            order.entry(step.id.as_str()).or_insert(position);
+        });
+        Self { order, issues: Vec::new() }
+    }
+
+    fn error(&mut self, step_id: Option<&str>, message: String) {
+        self.issue(IssueSeverity::Error, step_id, message);
+    }
+
+    fn warning(&mut self, step_id: Option<&str>, message: String) {
+        self.issue(IssueSeverity::Warning, step_id, message);
+    }
+
+    fn issue(&mut self, severity: IssueSeverity, step_id: Option<&str>, message: String) {
+        self.issues.push(ValidationIssue {
+            severity,
+            step_id: step_id.map(str::to_string),
+            message,
+        });
+    }
+
+    /// Check `steps` as seen from `scope`
+    fn check_steps(&mut self, steps: &'a [WorkflowStep], scope: &Scope<'a>) {
+        let mut scope = scope.clone();
+        for step in steps {
+            for template in step_templates(step) {
+                match template::step_references(template) {
+                    Ok(references) => {
+                        for reference in references {
+                            self.check_reference(step, &reference, &scope);
+                        }
+                    }
+                    Err(e) => self.error(Some(&step.id), e.to_string()),
+                }
+            }
+
+            let branches: Vec<&'a [WorkflowStep]> = match &step.kind {
+                StepKind::If { then, otherwise, .. } => vec![then, otherwise],
+                StepKind::Approval { rejected, .. } => vec![rejected],
+                _ => vec![],
+            };
+            scope.done.insert(&step.id);
+            let branch_ids: Vec<HashSet<&'a str>> = branches.iter().map(|branch| step_ids(branch)).collect();
+            for (index, branch) in branches.iter().enumerate() {
+                let mut branch_scope = scope.clone();
+                for (other, ids) in branch_ids.iter().enumerate() {
+                    if other != index {
+                        branch_scope.elsewhere.extend(ids);
+                    }
+                }
+                self.check_steps(branch, &branch_scope);
+            }
+            branch_ids.into_iter().for_each(|ids| scope.conditional.extend(ids));
+        }
+    }
+
+    fn check_reference(&mut self, step: &WorkflowStep, reference: &str, scope: &Scope<'_>) {
+        if scope.done.contains(reference) {
+            return;
+        }
+        if scope.conditional.contains(reference) {
+            self.warning(Some(&step.id), format!(
+                "Step '{}' only runs on some branches; its output may be missing", reference
+            ));
+            return;
+        }
+        let message = match (self.order.get(reference), self.order.get(step.id.as_str())) {
+            _ if reference == step.id => "Step references its own output (cycle)".to_string(),
+            _ if scope.elsewhere.contains(reference) => {
+                format!("Step references '{}', which is on another branch", reference)
+            }
+            (Some(referenced), Some(current)) if referenced > current => {
+                format!("Step references '{}', which runs after it (cycle)", reference)
+            }
+            (Some(_), _) => format!("Step references '{}', which has not run at this point", reference),
+            (None, _) => format!("Step references unknown step '{}'", reference),
+        };
+        self.error(Some(&step.id), message);
+    }
+}
+
+/// Simulated run with mock outputs
+struct Simulation<'a> {
+    registry: &'a dyn Registry,
+    options: &'a DryRunOptions,
+    base: TemplateContext,
+    outputs: serde_json::Map<String, Value>,
+    /// Steps with errors already reported; their render failures are not repeated
+    failed: HashSet<String>,
+    plan: &'a mut Vec<PlannedStep>,
+    issues: &'a mut Vec<ValidationIssue>,
+}
+
+impl Simulation<'_> {
+    fn run<'s>(&'s mut self, steps: &'s [WorkflowStep]) -> std::pin::Pin<Box<dyn std::future::Future<Output = ()> + Send + 's>> {
+        Box::pin(async move {
+            for step in steps {
+                self.run_step(step).await;
+            }
+        })
+    }
+
+    async fn run_step(&mut self, step: &WorkflowStep) {
+        let depends_on = step_templates(step)
+            .into_iter()
+            .flat_map(|template| template::step_references(template).unwrap_or_default())
+            .fold(Vec::new(), |mut names, name| {
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+                names
+            });
+        let mut planned = PlannedStep {
+            step_id: step.id.clone(),
+            kind: step_kind(&step.kind).to_string(),
+            tool_id: None,
+            depends_on,
+            parameters: Vec::new(),
+            branch: None,
+            output: Value::Null,
+        };
+        let mock = self.options.mock_outputs.get(&step.id).cloned();
+        let mut branch_steps: &[WorkflowStep] = &[];
+
+        match &step.kind {
+            StepKind::Tool { tool_id, parameters } => {
+                planned.tool_id = Some(tool_id.clone());
+                let tool = self.registry.get_tool(tool_id).await.ok();
+                if let Some(rendered) = self.render_parameters(step, parameters, self.context()) {
+                    self.check_parameters(step, tool.as_ref(), &rendered);
+                    planned.parameters.push(rendered);
+                }
+                planned.output = mock.unwrap_or_else(|| example_output(tool.as_ref()));
+            }
+            StepKind::Foreach { items, tool_id, parameters, .. } => {
+                planned.tool_id = Some(tool_id.clone());
+                let tool = self.registry.get_tool(tool_id).await.ok();
+                let items = self.render_array(step, items);
+                for (index, item) in items.iter().enumerate() {
+                    let context = self.context()
+                        .with_variable("item", item.clone())
+                        .with_variable("index", Value::from(index));
+                    if let Some(rendered) = self.render_parameters(step, parameters, context) {
+                        self.check_parameters(step, tool.as_ref(), &rendered);
+                        planned.parameters.push(rendered);
+                    }
+                }
+                planned.output = match mock {
+                    Some(mock @ Value::Array(_)) => mock,
+                    Some(mock) => Value::Array(vec![mock; items.len()]),
+                    None => Value::Array(vec![example_output(tool.as_ref()); items.len()]),
+                };
+            }
+            StepKind::If { condition, then, otherwise } => {
+                let taken = match self.context().render(condition) {
+                    Ok(value) => template::is_truthy(&value),
+                    Err(e) => {
+                        self.render_failed(step, e.to_string());
+                        true
+                    }
+                };
+                planned.branch = Some(if taken { "then" } else { "else" }.to_string());
+                planned.output = json!({ "branch": planned.branch });
+                branch_steps = if taken { then } else { otherwise };
+            }
+            StepKind::Reduce { items, operation, path } => {
+                let items = self.render_array(step, items);
+                planned.output = match mock {
+                    Some(mock) => mock,
+                    None => workflow::reduce(&items, *operation, path.as_deref()).unwrap_or_else(|message| {
+                        self.issues.push(ValidationIssue {
+                            severity: IssueSeverity::Warning,
+                            step_id: Some(step.id.clone()),
+                            message: format!("Reduce fails on the mock outputs: {}", message),
+                        });
+                        Value::Null
+                    }),
+                };
+            }
+            StepKind::Approval { title, rejected, .. } => {
+                if let Err(e) = self.context().render(title) {
+                    self.render_failed(step, e.to_string());
+                }
+                let approved = self.options.approve.unwrap_or(true);
+                let decision = if approved { "approved" } else { "rejected" };
+                planned.branch = Some(decision.to_string());
+                planned.output = json!({ "decision": decision, "decided_by": null, "comment": null });
+                if !approved {
+                    if rejected.is_empty() {
+                        self.issues.push(ValidationIssue {
+                            severity: IssueSeverity::Warning,
+                            step_id: Some(step.id.clone()),
+                            message: "Rejection fails the run; the rest of the plan is skipped".to_string(),
+                        });
+                        self.plan.push(planned);
+                        return;
+                    }
+                    branch_steps = rejected;
+                }
+            }
+        }
+
+        self.outputs.insert(step.id.clone(), planned.output.clone());
+        self.plan.push(planned);
+        self.run(branch_steps).await;
+    }
+
+    fn context(&self) -> TemplateContext {
+        let mut context = self.base.clone();
+        for (step_id, output) in &self.outputs {
+            context = context.with_step(step_id, output.clone());
+        }
+        context
+    }
+
+    fn render_parameters(
+        &mut self,
+        step: &WorkflowStep,
+        parameters: &HashMap<String, Value>,
+        context: TemplateContext,
+    ) -> Option<HashMap<String, Value>> {
+        match context.render_parameters(parameters) {
+            Ok(rendered) => Some(rendered),
+            Err(e) => {
+                self.render_failed(step, e.to_string());
+                None
+            }
+        }
+    }
+
+    fn render_array(&mut self, step: &WorkflowStep, items: &str) -> Vec<Value> {
+        match self.context().render(items) {
+            Ok(Value::Array(items)) => items,
+            Ok(other) => {
+                self.render_failed(step, format!("items must be an array, got {}", other));
+                Vec::new()
+            }
+            Err(e) => {
+                self.render_failed(step, e.to_string());
+                Vec::new()
+            }
+        }
+    }
+
+    fn render_failed(&mut self, step: &WorkflowStep, message: String) {
+        if self.failed.insert(step.id.clone()) {
+            self.issues.push(ValidationIssue {
+                severity: IssueSeverity::Error,
+                step_id: Some(step.id.clone()),
+                message,
+            });
+        }
+    }
+
+    /// Compare rendered parameters with the inputs of the tool's examples
+    fn check_parameters(&mut self, step: &WorkflowStep, tool: Option<&ToolInfo>, parameters: &HashMap<String, Value>) {
+        let inputs: Vec<&serde_json::Map<String, Value>> = tool
+            .map(|tool| tool.examples.iter().filter_map(|example| example.input.as_object()).collect())
+            .unwrap_or_default();
+        if inputs.is_empty() {
+            return;
+        }
+
+        let mut messages = Vec::new();
+        let mut names: Vec<&String> = parameters.keys().collect();
+        names.sort();
+        for name in names {
+            let value = &parameters[name];
+            let expected: HashSet<&str> = inputs.iter()
+                .filter_map(|input| input.get(name))
+                .filter(|example| !example.is_null())
+                .map(json_type)
+                .collect();
+            if !inputs.iter().any(|input| input.contains_key(name)) {
+                messages.push((IssueSeverity::Warning, format!("Parameter '{}' does not appear in the tool's examples", name)));
+            } else if !value.is_null() && !expected.is_empty() && !type_accepted(&expected, json_type(value)) {
+                let mut expected: Vec<&str> = expected.into_iter().collect();
+                expected.sort();
+                messages.push((IssueSeverity::Error, format!(
+                    "Parameter '{}' is {}, the tool expects {}", name, json_type(value), expected.join(" or ")
+                )));
+            }
+        }
+        let mut required: Vec<&String> = inputs[0].keys()
+            .filter(|name| inputs.iter().all(|input| input.contains_key(*name)) && !parameters.contains_key(*name))
+            .collect();
+        required.sort();
+        for name in required {
+            messages.push((IssueSeverity::Warning, format!("Parameter '{}' is set in every example but missing", name)));
+        }
+
+        for (severity, message) in messages {
+            let issue = ValidationIssue { severity, step_id: Some(step.id.clone()), message };
+            // A foreach checks every iteration; report each problem once
+            if !self.issues.contains(&issue) {
+                self.issues.push(issue);
+            }
+        }
+    }
+}
+
+fn base_context(definition: &WorkflowDefinition, context: Option<&ExecutionContext>) -> TemplateContext {
+    let placeholder = |value: Option<&String>| value.cloned().unwrap_or_else(|| "dry-run".to_string());
+    TemplateContext::new()
+        .with_variable("context", json!({
+            "run_id": "dry-run",
+            "workflow_id": definition.id,
+            "user_id": placeholder(context.map(|c| &c.user_id)),
+            "tenant_id": placeholder(context.map(|c| &c.tenant_id)),
+            "session_id": placeholder(context.map(|c| &c.session_id)),
+            "request_id": placeholder(context.map(|c| &c.request_id)),
+        }))
+        .with_variable("env", json!(context.map(|c| c.environment.clone()).unwrap_or_default()))
+}
+
+/// Every template string of a step, excluding its branches
+fn step_templates(step: &WorkflowStep) -> Vec<&str> {
+    fn strings<'v>(value: &'v Value, out: &mut Vec<&'v str>) {
+        match value {
+            Value::String(text) => out.push(text),
+            Value::Array(items) => items.iter().for_each(|item| strings(item, out)),
+            Value::Object(fields) => fields.values().for_each(|item| strings(item, out)),
+            _ => {}
+        }
+    }
+
+    let mut templates = Vec::new();
+    match &step.kind {
+        StepKind::Tool { parameters, .. } => parameters.values().for_each(|value| strings(value, &mut templates)),
+        StepKind::Foreach { items, parameters, .. } => {
+            templates.push(items.as_str());
+            parameters.values().for_each(|value| strings(value, &mut templates));
+        }
+        StepKind::If { condition, .. } => templates.push(condition),
+        StepKind::Reduce { items, .. } => templates.push(items),
+        StepKind::Approval { title, .. } => templates.push(title),
+    }
+    templates
+}
+
+fn step_ids(steps: &[WorkflowStep]) -> HashSet<&str> {
+    let mut ids = HashSet::new();
+    visit_steps(steps, &mut |step| {
+        ids.insert(step.id.as_str());
+    });
+    ids
+}
+
+/// Visit steps depth-first in execution order, including branches
+fn visit_steps<'a>(steps: &'a [WorkflowStep], visit: &mut impl FnMut(&'a WorkflowStep)) {
+    for step in steps {
+        visit(step);
+        match &step.kind {
+            StepKind::If { then, otherwise, .. } => {
+                visit_steps(then, visit);
+                visit_steps(otherwise, visit);
+            }
+            StepKind::Approval { rejected, .. } => visit_steps(rejected, visit),
+            _ => {}
+        }
+    }
+}
+
+fn step_kind(kind: &StepKind) -> &'static str {
+    match kind {
+        StepKind::Tool { .. } => "tool",
+        StepKind::If { .. } => "if",
+        StepKind::Foreach { .. } => "foreach",
+        StepKind::Reduce { .. } => "reduce",
+        StepKind::Approval { .. } => "approval",
+    }
+}
+
+fn example_output(tool: Option<&ToolInfo>) -> Value {
+    tool.and_then(|tool| tool.examples.first())
+        .map(|example| example.output.clone())
+        .unwrap_or_else(|| json!({}))
+}
+
+/// Integers are accepted where examples use other numbers
+fn type_accepted(expected: &HashSet<&str>, actual: &str) -> bool {
+    expected.contains(actual) || (actual == "integer" && expected.contains("number"))
+}
+
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
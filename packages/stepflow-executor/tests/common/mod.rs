@@ -6,8 +6,9 @@ use std::time::Duration;
 use chrono::Utc;
 use stepflow_core::*;
 use stepflow_database::SqliteDatabase;
-use stepflow_registry::{Registry, RegistryImpl};
+use stepflow_registry::{InMemoryRegistry, Registry, RegistryImpl};
 use stepflow_executor::*;
+pub use stepflow_executor::bench::PerformanceBenchmark;
 
 /// Test database setup
 pub async fn setup_test_database() -> Arc<SqliteDatabase> {
@@ -35,12 +36,53 @@ pub async fn setup_test_database() -> Arc<SqliteDatabase> {
             configuration_schema TEXT,
             examples TEXT,
             created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            environment_label TEXT,
+            requirements TEXT,
+            input_schema TEXT,
+            localizations TEXT,
+            environment TEXT,
+            session TEXT,
+            image TEXT
+        )
+        "#,
+        &[],
+    ).await.unwrap();
+    
+    db.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS tenants (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            description TEXT,
+            domain TEXT,
+            settings TEXT,
+            created_at TEXT NOT NULL,
             updated_at TEXT NOT NULL
         )
         "#,
         &[],
     ).await.unwrap();
     
+    db.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS tool_configs (
+            tenant_id TEXT NOT NULL,
+            tool_id TEXT NOT NULL,
+            configuration TEXT NOT NULL,
+            environment TEXT NOT NULL,
+            secrets TEXT NOT NULL,
+            timeout INTEGER,
+            retries INTEGER,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            PRIMARY KEY (tenant_id, tool_id)
+        )
+        "#,
+        &[],
+    ).await.unwrap();
+    
     db.execute(
         r#"
         CREATE TABLE IF NOT EXISTS tasks (
@@ -98,7 +140,20 @@ pub async fn setup_test_database() -> Arc<SqliteDatabase> {
             logs TEXT,
             metrics TEXT,
             metadata TEXT,
-            created_at TEXT NOT NULL
+            created_at TEXT NOT NULL,
+            output_chunks INTEGER NOT NULL DEFAULT 0
+        )
+        "#,
+        &[],
+    ).await.unwrap();
+    
+    db.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS result_chunks (
+            execution_id TEXT NOT NULL,
+            chunk_index INTEGER NOT NULL,
+            data TEXT NOT NULL,
+            PRIMARY KEY (execution_id, chunk_index)
         )
         "#,
         &[],
@@ -149,6 +204,169 @@ pub async fn setup_test_database() -> Arc<SqliteDatabase> {
         &[],
     ).await.unwrap();
     
+    db.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS execution_labels (
+            execution_id TEXT NOT NULL,
+            tenant_id TEXT NOT NULL,
+            key TEXT NOT NULL,
+            value TEXT NOT NULL,
+            PRIMARY KEY (execution_id, key)
+        )
+        "#,
+        &[],
+    ).await.unwrap();
+    
+    db.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS execution_notes (
+            id TEXT PRIMARY KEY,
+            execution_id TEXT NOT NULL,
+            tenant_id TEXT NOT NULL,
+            author TEXT NOT NULL,
+            body TEXT NOT NULL,
+            created_at TEXT NOT NULL
+        )
+        "#,
+        &[],
+    ).await.unwrap();
+    
+    db.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS registry_changes (
+            sequence INTEGER PRIMARY KEY AUTOINCREMENT,
+            change_type TEXT NOT NULL,
+            tool_id TEXT NOT NULL,
+            version TEXT NOT NULL,
+            payload TEXT,
+            created_at TEXT NOT NULL
+        )
+        "#,
+        &[],
+    ).await.unwrap();
+    
+    db.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS execution_events (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            execution_id TEXT NOT NULL,
+            tenant_id TEXT,
+            tool_id TEXT,
+            state TEXT NOT NULL,
+            worker_id TEXT,
+            detail TEXT,
+            occurred_at TEXT NOT NULL
+        )
+        "#,
+        &[],
+    ).await.unwrap();
+
+    db.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS tool_baselines (
+            tool_id TEXT PRIMARY KEY,
+            samples INTEGER NOT NULL,
+            duration_mean_ms REAL NOT NULL,
+            duration_variance REAL NOT NULL,
+            failure_rate REAL NOT NULL,
+            updated_at TEXT NOT NULL
+        )
+        "#,
+        &[],
+    ).await.unwrap();
+
+    db.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS execution_anomalies (
+            id TEXT PRIMARY KEY,
+            execution_id TEXT NOT NULL,
+            tenant_id TEXT,
+            tool_id TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            observed REAL NOT NULL,
+            expected REAL NOT NULL,
+            deviation REAL NOT NULL,
+            detected_at TEXT NOT NULL
+        )
+        "#,
+        &[],
+    ).await.unwrap();
+
+    db.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS slow_executions (
+            id TEXT PRIMARY KEY,
+            execution_id TEXT NOT NULL,
+            tenant_id TEXT NOT NULL,
+            tool_id TEXT NOT NULL,
+            duration_ms REAL NOT NULL,
+            baseline_mean_ms REAL NOT NULL,
+            ratio REAL NOT NULL,
+            success INTEGER NOT NULL,
+            captured_at TEXT NOT NULL
+        )
+        "#,
+        &[],
+    ).await.unwrap();
+
+    db.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS execution_payload_metrics (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            execution_id TEXT NOT NULL,
+            tenant_id TEXT NOT NULL,
+            tool_id TEXT NOT NULL,
+            input_bytes INTEGER NOT NULL,
+            output_bytes INTEGER NOT NULL,
+            input_max_array_len INTEGER NOT NULL,
+            output_max_array_len INTEGER NOT NULL,
+            schema_warnings INTEGER NOT NULL,
+            recorded_at TEXT NOT NULL
+        )
+        "#,
+        &[],
+    ).await.unwrap();
+
+    db.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS tool_rollouts (
+            id TEXT PRIMARY KEY,
+            tool_id TEXT NOT NULL,
+            stable_version TEXT NOT NULL,
+            candidate TEXT NOT NULL,
+            percentage INTEGER NOT NULL,
+            thresholds TEXT NOT NULL,
+            status TEXT NOT NULL,
+            stable_executions INTEGER NOT NULL DEFAULT 0,
+            stable_failures INTEGER NOT NULL DEFAULT 0,
+            stable_duration_ms INTEGER NOT NULL DEFAULT 0,
+            candidate_executions INTEGER NOT NULL DEFAULT 0,
+            candidate_failures INTEGER NOT NULL DEFAULT 0,
+            candidate_duration_ms INTEGER NOT NULL DEFAULT 0,
+            decision_reason TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        )
+        "#,
+        &[],
+    ).await.unwrap();
+    
+    db.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS tool_test_runs (
+            id TEXT PRIMARY KEY,
+            tool_id TEXT NOT NULL,
+            version TEXT NOT NULL,
+            trigger TEXT NOT NULL,
+            passed INTEGER NOT NULL,
+            results TEXT NOT NULL,
+            started_at TEXT NOT NULL,
+            finished_at TEXT NOT NULL
+        )
+        "#,
+        &[],
+    ).await.unwrap();
+    
     db
 }
 
@@ -165,88 +383,78 @@ pub async fn setup_test_registry(db: Arc<SqliteDatabase>) -> Arc<RegistryImpl> {
     registry
 }
 
+/// Create an in-memory registry with sample tools
+pub async fn setup_in_memory_registry() -> Arc<InMemoryRegistry> {
+    let registry = Arc::new(InMemoryRegistry::new());
+    registry.register_tools(create_sample_tools()).await.unwrap();
+    registry
+}
+
 /// Create sample tools for testing
 pub fn create_sample_tools() -> Vec<ToolInfo> {
     vec![
-        ToolInfo {
-            id: ToolId::from_string("test-tool-1".to_string()),
-            name: "Test Tool 1".to_string(),
-            description: "A simple test tool".to_string(),
-            version: ToolVersion::new(1, 0, 0),
-            tool_type: ToolType::Python,
-            status: ToolStatus::Active,
-            author: "test-author".to_string(),
-            repository: Some("https://github.com/test/tool1".to_string()),
-            documentation: Some("https://docs.test.com/tool1".to_string()),
-            tags: vec!["test".to_string(), "utility".to_string()],
-            capabilities: vec!["process".to_string(), "transform".to_string()],
-            configuration_schema: Some(serde_json::json!({
+        ToolInfo::builder()
+            .id(ToolId::from_string("test-tool-1".to_string()))
+            .name("Test Tool 1")
+            .description("A simple test tool")
+            .tool_type(ToolType::Python)
+            .author("test-author")
+            .repository("https://github.com/test/tool1".to_string())
+            .documentation("https://docs.test.com/tool1".to_string())
+            .tags(["test", "utility"])
+            .capabilities(["process", "transform"])
+            .configuration_schema(serde_json::json!({
                 "type": "object",
                 "properties": {
                     "input": {"type": "string"},
                     "output_format": {"type": "string", "enum": ["json", "text"]}
                 }
-            })),
-            examples: vec![
+            }))
+            .examples(vec![
                 ToolExample {
                     name: "Basic usage".to_string(),
                     description: "Basic tool usage example".to_string(),
                     input: serde_json::json!({"input": "test data"}),
-                    output: serde_json::json!({"result": "processed data"}),
+                    output: serde_json::json!({"message": "Tool executed successfully"}),
+                    match_mode: ExampleMatch::Subset,
                 }
-            ],
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-        },
-        ToolInfo {
-            id: ToolId::from_string("test-tool-2".to_string()),
-            name: "Test Tool 2".to_string(),
-            description: "Another test tool for complex operations".to_string(),
-            version: ToolVersion::new(2, 1, 0),
-            tool_type: ToolType::Shell,
-            status: ToolStatus::Active,
-            author: "test-author".to_string(),
-            repository: None,
-            documentation: None,
-            tags: vec!["test".to_string(), "complex".to_string()],
-            capabilities: vec!["analyze".to_string(), "report".to_string()],
-            configuration_schema: None,
-            examples: vec![],
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-        },
-        ToolInfo {
-            id: ToolId::from_string("slow-tool".to_string()),
-            name: "Slow Tool".to_string(),
-            description: "A tool that takes time to execute".to_string(),
-            version: ToolVersion::new(1, 0, 0),
-            tool_type: ToolType::System,
-            status: ToolStatus::Active,
-            author: "test-author".to_string(),
-            repository: None,
-            documentation: None,
-            tags: vec!["test".to_string(), "slow".to_string()],
-            capabilities: vec!["wait".to_string()],
-            configuration_schema: None,
-            examples: vec![],
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-        },
+            ])
+            .build()
+            .unwrap(),
+        ToolInfo::builder()
+            .id(ToolId::from_string("test-tool-2".to_string()))
+            .name("Test Tool 2")
+            .description("Another test tool for complex operations")
+            .version(ToolVersion::new(2, 1, 0))
+            .tool_type(ToolType::Shell)
+            .author("test-author")
+            .tags(["test", "complex"])
+            .capabilities(["analyze", "report"])
+            .build()
+            .unwrap(),
+        ToolInfo::builder()
+            .id(ToolId::from_string("slow-tool".to_string()))
+            .name("Slow Tool")
+            .description("A tool that takes time to execute")
+            .tool_type(ToolType::System)
+            .author("test-author")
+            .tags(["test", "slow"])
+            .capabilities(["wait"])
+            .build()
+            .unwrap(),
     ]
 }
 
 /// Create test execution request
 pub fn create_test_execution_request(tool_id: &str) -> ExecutionRequest {
-    ExecutionRequest {
-        tool_id: ToolId::from_string(tool_id.to_string()),
-        version: None,
-        parameters: HashMap::from([
-            ("input".to_string(), serde_json::Value::String("test input".to_string())),
-            ("format".to_string(), serde_json::Value::String("json".to_string())),
-        ]),
-        context: create_test_execution_context(),
-        options: create_test_execution_options(),
-    }
+    ExecutionRequest::builder()
+        .tool_id(ToolId::from_string(tool_id.to_string()))
+        .parameter("input", serde_json::Value::String("test input".to_string()))
+        .parameter("format", serde_json::Value::String("json".to_string()))
+        .context(create_test_execution_context())
+        .options(create_test_execution_options())
+        .build()
+        .unwrap()
 }
 
 /// Create test execution context
@@ -261,6 +469,8 @@ pub fn create_test_execution_context() -> ExecutionContext {
             ("ENV_VAR_1".to_string(), "value1".to_string()),
             ("ENV_VAR_2".to_string(), "value2".to_string()),
         ]),
+        labels: HashMap::new(),
+        note: None,
     }
 }
 
@@ -278,26 +488,39 @@ pub fn create_test_execution_options() -> ExecutionOptions {
             network_limit: Some(10 * 1024 * 1024), // 10MB
         },
         logging_level: LogLevel::Info,
+        environment_label: None,
+        requirements: Default::default(),
+        concurrency: None,
+        artifacts: Vec::new(),
     }
 }
 
-/// Create test executor with all components
+/// Create test executor with all components on the in-memory backends
 pub async fn create_test_executor() -> ExecutorResult<ExecutorImpl> {
-    let db = setup_test_database().await;
-    let registry = setup_test_registry(db.clone()).await;
-    
-    create_default_executor(db, registry)
+    create_test_executor_with_config(None, None).await
 }
 
-/// Create test executor with custom configuration
+/// Create test executor with custom configuration on the in-memory backends
 pub async fn create_test_executor_with_config(
     scheduler_config: Option<SchedulerConfig>,
     worker_pool_config: Option<WorkerPoolConfig>,
 ) -> ExecutorResult<ExecutorImpl> {
+    create_executor_with_backends(
+        Arc::new(InMemoryExecutionStore::new()),
+        Arc::new(InMemoryResultManager::new()),
+        Arc::new(InMemoryMonitoring::new()),
+        setup_in_memory_registry().await,
+        scheduler_config,
+        worker_pool_config,
+    )
+}
+
+/// Create test executor with all components on SQLite
+pub async fn create_sqlite_test_executor() -> ExecutorResult<ExecutorImpl> {
     let db = setup_test_database().await;
     let registry = setup_test_registry(db.clone()).await;
     
-    create_executor(db, registry, scheduler_config, worker_pool_config)
+    create_default_executor(db, registry)
 }
 
 /// Wait for async operation with timeout
@@ -438,69 +661,17 @@ pub async fn benchmark_execution(
     }
     
     // Wait for all executions to complete
-    let mut successful_executions = 0;
-    let mut total_duration = Duration::from_secs(0);
-    let mut min_duration = Duration::from_secs(u64::MAX);
-    let mut max_duration = Duration::from_secs(0);
-    
+    let mut durations = Vec::new();
     for handle in handles {
         if let Ok(Some(duration)) = handle.await {
-            successful_executions += 1;
-            total_duration += duration;
-            min_duration = min_duration.min(duration);
-            max_duration = max_duration.max(duration);
+            durations.push(duration);
         }
     }
     
-    let total_elapsed = start_time.elapsed();
-    let throughput = successful_executions as f64 / total_elapsed.as_secs_f64();
-    let average_duration = if successful_executions > 0 {
-        total_duration / successful_executions as u32
-    } else {
-        Duration::from_secs(0)
-    };
-    
-    Ok(PerformanceBenchmark {
-        total_executions: config.total_executions,
-        successful_executions,
-        failed_executions: config.total_executions - successful_executions,
-        total_duration: total_elapsed,
-        average_duration,
-        min_duration: if min_duration == Duration::from_secs(u64::MAX) { Duration::from_secs(0) } else { min_duration },
-        max_duration,
-        throughput,
-        concurrent_executions: config.concurrent_executions,
-    })
+    Ok(PerformanceBenchmark::from_durations(
+        config.total_executions,
+        config.concurrent_executions,
+        start_time.elapsed(),
+        &durations,
+    ))
 }
-
-/// Performance benchmark results
-#[derive(Debug, Clone)]
-pub struct PerformanceBenchmark {
-    pub total_executions: usize,
-    pub successful_executions: usize,
-    pub failed_executions: usize,
-    pub total_duration: Duration,
-    pub average_duration: Duration,
-    pub min_duration: Duration,
-    pub max_duration: Duration,
-    pub throughput: f64,
-    pub concurrent_executions: usize,
-}
-
-impl PerformanceBenchmark {
-    pub fn print_summary(&self) {
-        println!("\n=== Performance Benchmark Results ===");
-        println!("Total Executions: {}", self.total_executions);
-        println!("Successful: {}", self.successful_executions);
-        println!("Failed: {}", self.failed_executions);
-        println!("Success Rate: {:.2}%", 
-            (self.successful_executions as f64 / self.total_executions as f64) * 100.0);
-        println!("Total Duration: {:?}", self.total_duration);
-        println!("Average Duration: {:?}", self.average_duration);
-        println!("Min Duration: {:?}", self.min_duration);
-        println!("Max Duration: {:?}", self.max_duration);
-        println!("Throughput: {:.2} executions/second", self.throughput);
-        println!("Concurrent Executions: {}", self.concurrent_executions);
-        println!("=====================================\n");
-    }
-} 
\ No newline at end of file
@@ -13,7 +13,7 @@ mod end_to_end_tests {
 
     #[tokio::test]
     async fn test_complete_execution_workflow() {
-        let executor = create_test_executor().await.unwrap();
+        let executor = create_sqlite_test_executor().await.unwrap();
         
         // Test synchronous execution
         let request = create_test_execution_request("test-tool-1");
@@ -31,7 +31,7 @@ mod end_to_end_tests {
 
     #[tokio::test]
     async fn test_async_execution_with_status_tracking() {
-        let executor = create_test_executor().await.unwrap();
+        let executor = create_sqlite_test_executor().await.unwrap();
         
         // Start asynchronous execution
         let request = create_test_execution_request("test-tool-2");
@@ -66,6 +66,48 @@ mod end_to_end_tests {
         assert_eq!(status, ExecutionStatus::Completed);
     }
 
+    #[tokio::test]
+    async fn test_execution_timeline() {
+        let executor = create_sqlite_test_executor().await.unwrap();
+        
+        let request = create_test_execution_request("test-tool-2");
+        let execution_id = executor.execute_tool_async(request).await.unwrap();
+        
+        let exec_clone = executor.clone();
+        let exec_id_clone = execution_id.clone();
+        let finished = wait_for_condition(
+            move || {
+                let executor = exec_clone.clone();
+                let execution_id = exec_id_clone.clone();
+                async move {
+                    matches!(
+                        executor.get_execution_timeline(&execution_id).await,
+                        Ok(Some(timeline)) if timeline.current_state().is_some_and(|s| s.is_terminal())
+                    )
+                }
+            },
+            Duration::from_secs(10),
+            Duration::from_millis(100),
+        ).await;
+        assert!(finished, "Execution should reach a terminal state");
+        
+        let timeline = executor.get_execution_timeline(&execution_id).await.unwrap().unwrap();
+        let states: Vec<ExecutionState> = timeline.events.iter().map(|e| e.state).collect();
+        assert_eq!(states, vec![
+            ExecutionState::Queued,
+            ExecutionState::Scheduled,
+            ExecutionState::Running,
+            ExecutionState::Completed,
+        ]);
+        assert_eq!(timeline.tenant_id.as_deref(), Some("test-tenant-456"));
+        assert!(timeline.queue_wait_ms.unwrap() >= 0);
+        assert!(timeline.total_duration_ms.unwrap() >= timeline.run_duration_ms.unwrap());
+        
+        // Unknown executions have no timeline
+        let missing = executor.get_execution_timeline(&ExecutionId::new()).await.unwrap();
+        assert!(missing.is_none());
+    }
+
     #[tokio::test]
     async fn test_multiple_concurrent_executions() {
         let mut handles = Vec::new();
@@ -75,7 +117,7 @@ mod end_to_end_tests {
             let tool_id = if i % 2 == 0 { "test-tool-1" } else { "test-tool-2" };
             
             let handle = tokio::spawn(async move {
-                let executor = create_test_executor().await.unwrap();
+                let executor = create_sqlite_test_executor().await.unwrap();
                 let request = create_test_execution_request(tool_id);
                 executor.execute_tool(request).await
             });
@@ -100,7 +142,7 @@ mod end_to_end_tests {
 
     #[tokio::test]
     async fn test_execution_with_different_priorities() {
-        let executor = create_test_executor().await.unwrap();
+        let executor = create_sqlite_test_executor().await.unwrap();
         let mut execution_ids = Vec::new();
         
         // Create requests with different priorities
@@ -139,7 +181,7 @@ mod end_to_end_tests {
 
     #[tokio::test]
     async fn test_execution_error_handling() {
-        let executor = create_test_executor().await.unwrap();
+        let executor = create_sqlite_test_executor().await.unwrap();
         
         // Test with nonexistent tool
         let request = create_test_execution_request("nonexistent-tool");
@@ -154,7 +196,7 @@ mod end_to_end_tests {
 
     #[tokio::test]
     async fn test_execution_cancellation_workflow() {
-        let executor = create_test_executor().await.unwrap();
+        let executor = create_sqlite_test_executor().await.unwrap();
         
         // Start a long-running execution
         let request = create_test_execution_request("slow-tool");
@@ -174,7 +216,7 @@ mod end_to_end_tests {
 
     #[tokio::test]
     async fn test_execution_metrics_collection() {
-        let executor = create_test_executor().await.unwrap();
+        let executor = create_sqlite_test_executor().await.unwrap();
         
         // Execute a tool
         let request = create_test_execution_request("test-tool-1");
@@ -208,7 +250,7 @@ mod end_to_end_tests {
 
     #[tokio::test]
     async fn test_execution_list_filtering() {
-        let executor = create_test_executor().await.unwrap();
+        let executor = create_sqlite_test_executor().await.unwrap();
         
         // Execute multiple tools
         let tools = vec!["test-tool-1", "test-tool-2", "test-tool-1"];
@@ -224,7 +266,7 @@ mod end_to_end_tests {
         for execution_id in &execution_ids {
             wait_for_condition(
                 || async {
-                    let executor = create_test_executor().await.unwrap();
+                    let executor = create_sqlite_test_executor().await.unwrap();
                     match executor.get_execution_status(execution_id).await {
                         Ok(ExecutionStatus::Completed) => true,
                         _ => false,
@@ -243,6 +285,7 @@ mod end_to_end_tests {
             status: None,
             started_after: None,
             started_before: None,
+            labels: std::collections::HashMap::new(),
         };
         
         let executions = executor.list_executions(Some(filter)).await.unwrap();
@@ -269,7 +312,7 @@ mod component_integration_tests {
         
         // Create scheduler
         let scheduler = SchedulerImpl::new(
-            db.clone(),
+            std::sync::Arc::new(SqliteExecutionStore::new(db.clone())),
             worker_pool.clone(),
             SchedulerConfig::default(),
         );
@@ -345,7 +388,7 @@ mod component_integration_tests {
 
     #[tokio::test]
     async fn test_full_component_integration() {
-        let executor = create_test_executor().await.unwrap();
+        let executor = create_sqlite_test_executor().await.unwrap();
         
         // This test verifies that all components work together
         // by performing a complete execution workflow
@@ -368,7 +411,7 @@ mod error_recovery_tests {
 
     #[tokio::test]
     async fn test_execution_retry_mechanism() {
-        let executor = create_test_executor().await.unwrap();
+        let executor = create_sqlite_test_executor().await.unwrap();
         
         // Test with a tool that might fail
         let mut request = create_test_execution_request("test-tool-1");
@@ -384,7 +427,7 @@ mod error_recovery_tests {
 
     #[tokio::test]
     async fn test_timeout_handling() {
-        let executor = create_test_executor().await.unwrap();
+        let executor = create_sqlite_test_executor().await.unwrap();
         
         // Test with a very short timeout
         let mut request = create_test_execution_request("slow-tool");
@@ -399,7 +442,7 @@ mod error_recovery_tests {
 
     #[tokio::test]
     async fn test_resource_limit_enforcement() {
-        let executor = create_test_executor().await.unwrap();
+        let executor = create_sqlite_test_executor().await.unwrap();
         
         // Test with restrictive resource limits
         let mut request = create_test_execution_request("test-tool-1");
@@ -423,7 +466,7 @@ mod error_recovery_tests {
         // Start many concurrent executions
         for i in 0..20 {
             let handle = tokio::spawn(async move {
-                let executor = create_test_executor().await.unwrap();
+                let executor = create_sqlite_test_executor().await.unwrap();
                 let request = create_test_execution_request("test-tool-1");
                 executor.execute_tool_async(request).await
             });
@@ -451,7 +494,7 @@ mod data_consistency_tests {
 
     #[tokio::test]
     async fn test_execution_state_consistency() {
-        let executor = create_test_executor().await.unwrap();
+        let executor = create_sqlite_test_executor().await.unwrap();
         
         // Start execution
         let request = create_test_execution_request("test-tool-1");
@@ -507,6 +550,137 @@ mod data_consistency_tests {
         let metrics = monitoring.get_execution_metrics(&execution_id).await.unwrap();
         assert!(metrics.len() >= 0, "Metrics should be retrievable after storage");
     }
+
+    #[tokio::test]
+    async fn test_async_result_chunks_in_sqlite() {
+        let db = setup_test_database().await;
+        let store = SqliteExecutionStore::new(db.clone()).with_chunk_size(32);
+        let execution_id = ExecutionId::new();
+        let output = serde_json::json!({"text": "x".repeat(500)});
+        let result = stepflow_core::ExecutionResult {
+            success: true,
+            output: Some(output.clone()),
+            error: None,
+            logs: vec![],
+            metrics: std::collections::HashMap::new(),
+            metadata: std::collections::HashMap::new(),
+        };
+
+        store.store_execution_result(&execution_id, &result).await.unwrap();
+        assert!(store.has_execution_result(&execution_id).await.unwrap());
+
+        let stored = ResultManagerImpl::new(db).get_result(&execution_id).await.unwrap();
+        assert_eq!(stored.output, Some(output));
+    }
+
+    #[tokio::test]
+    async fn test_execution_labels_in_sqlite() {
+        use std::collections::HashMap;
+        use stepflow_core::Database;
+
+        let db = setup_test_database().await;
+        let store = SqliteExecutionStore::new(db.clone());
+        let labeled = ExecutionId::new();
+        let unlabeled = ExecutionId::new();
+        for execution_id in [&labeled, &unlabeled] {
+            db.execute(
+                "INSERT INTO executions (execution_id, tool_id, status, created_at, user_id, tenant_id) VALUES (?, 'test-tool-1', 'Completed', ?, 'test-user', 'test-tenant')",
+                &[serde_json::json!(execution_id.as_str()), serde_json::json!(chrono::Utc::now().to_rfc3339())],
+            ).await.unwrap();
+        }
+
+        let labels = HashMap::from([("team".to_string(), "payments".to_string()), ("env".to_string(), "prod".to_string())]);
+        store.set_labels(&labeled, "test-tenant", &labels).await.unwrap();
+        store.set_labels(&unlabeled, "test-tenant", &HashMap::from([("team".to_string(), "search".to_string())])).await.unwrap();
+
+        let filter = ExecutionFilter { labels: labels.clone(), ..Default::default() };
+        let listed = store.list_executions(Some(filter)).await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].execution_id, labeled);
+        assert_eq!(listed[0].labels, labels);
+
+        let note = ExecutionNote::new(labeled.clone(), "test-user", "looks good");
+        store.add_note("test-tenant", &note).await.unwrap();
+        assert!(store.remove_label(&labeled, "env").await.unwrap());
+        let annotations = store.annotations(&labeled).await.unwrap();
+        assert_eq!(annotations.labels, HashMap::from([("team".to_string(), "payments".to_string())]));
+        assert_eq!(annotations.notes.len(), 1);
+        assert_eq!(annotations.notes[0].body, "looks good");
+    }
+
+    #[tokio::test]
+    async fn test_rollout_persists_in_sqlite() {
+        use std::sync::Arc;
+        use stepflow_registry::Registry;
+
+        let db = setup_test_database().await;
+        let registry = setup_test_registry(db.clone()).await;
+        let manager = RolloutManager::new(Arc::new(SqliteExecutionStore::new(db.clone())), registry.clone());
+        let tool_id = ToolId::from_string("test-tool-2".to_string());
+        let stable = registry.get_tool(&tool_id).await.unwrap();
+        let mut candidate = stable;
+        candidate.version = ToolVersion::new(2, 2, 0);
+        let thresholds = RolloutThresholds {
+            min_samples: 2,
+            promote_after: None,
+            ..RolloutThresholds::default()
+        };
+
+        let started = manager.start(&tool_id, candidate, 25, thresholds.clone()).await.unwrap();
+        assert!(matches!(
+            manager.start(&tool_id, started.candidate.clone(), 25, thresholds).await,
+            Err(ExecutorError::Conflict(_))
+        ));
+
+        manager.record(&started, RolloutArm::Stable, 100, true).await.unwrap();
+        manager.record(&started, RolloutArm::Candidate, 120, true).await.unwrap();
+        let updated = manager.record(&started, RolloutArm::Candidate, 80, false).await.unwrap().unwrap();
+        assert_eq!(updated.status, RolloutStatus::RolledBack, "50% candidate errors must roll back");
+
+        let stored = manager.get(&tool_id).await.unwrap().unwrap();
+        assert_eq!(stored.status, RolloutStatus::RolledBack);
+        assert_eq!(stored.stable, ArmStats { executions: 1, failures: 0, total_duration_ms: 100 });
+        assert_eq!(stored.candidate_stats, ArmStats { executions: 2, failures: 1, total_duration_ms: 200 });
+        assert_eq!(stored.candidate.version, ToolVersion::new(2, 2, 0));
+        assert!(stored.decision_reason.unwrap().contains("error rate"));
+        assert_eq!(registry.get_tool(&tool_id).await.unwrap().version, ToolVersion::new(2, 1, 0));
+
+        // Outcomes arriving after the decision are dropped
+        assert!(manager.record(&started, RolloutArm::Candidate, 10, true).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_registered_tools_are_tested_once() {
+        use std::sync::Arc;
+        use stepflow_registry::ChangeFeed;
+
+        let db = setup_test_database().await;
+        let registry = setup_test_registry(db.clone()).await;
+        let executor = create_default_executor(db.clone(), registry).unwrap();
+        let tests = ToolTestRunner::new(Arc::new(executor), Arc::new(SqliteExecutionStore::new(db.clone())));
+        let feed = ChangeFeed::new(db.clone());
+        let context = ToolTestRunner::system_context("test-tenant");
+
+        // Only test-tool-1 has examples
+        let (runs, next) = tests.test_registered(&feed, 0, &context).await.unwrap();
+        assert_eq!(runs.len(), 1);
+        assert!(next > 0);
+        let run = &runs[0];
+        assert_eq!(run.tool_id.as_str(), "test-tool-1");
+        assert_eq!(run.trigger, TestTrigger::Registration);
+        assert!(run.passed, "{:?}", run.results);
+        assert_eq!(run.results[0].example, "Basic usage");
+
+        let stored = tests.latest(&run.tool_id, &run.version).await.unwrap().unwrap();
+        assert_eq!(stored.id, run.id);
+        assert!(stored.passed);
+        assert_eq!(stored.results[0].actual_output, run.results[0].actual_output);
+
+        // Versions that were tested already are skipped when the feed is replayed
+        let (runs, _) = tests.test_registered(&feed, 0, &context).await.unwrap();
+        assert!(runs.is_empty());
+        assert_eq!(tests.list(&stored.tool_id, 10).await.unwrap().len(), 1);
+    }
 }
 
 #[cfg(test)]
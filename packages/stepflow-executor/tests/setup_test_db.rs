@@ -58,6 +58,9 @@ pub async fn reset_test_database(db: &SqliteDatabase) -> StepflowResult<()> {
     // Clear all test data
     let tables = vec![
         "execution_results",
+        "result_chunks",
+        "execution_labels",
+        "execution_notes",
         "metrics", 
         "logs",
         "executions",
@@ -89,6 +92,8 @@ pub fn create_test_execution_context() -> ExecutionContext {
         request_id: "test-request-1".to_string(),
         parent_execution_id: None,
         environment: std::collections::HashMap::new(),
+        labels: std::collections::HashMap::new(),
+        note: None,
     }
 }
 
@@ -101,6 +106,10 @@ pub fn create_test_execution_options() -> ExecutionOptions {
         priority: Priority::Normal,
         resource_limits: ResourceLimits::default(),
         logging_level: LogLevel::Info,
+        environment_label: None,
+        requirements: Default::default(),
+        concurrency: None,
+        artifacts: Vec::new(),
     }
 }
 
@@ -5,7 +5,7 @@ mod common;
 use std::time::Duration;
 use common::*;
 use stepflow_executor::*;
-use stepflow_core::{ExecutionFilter, Metric, MetricFilter, LogLevel};
+use stepflow_core::{ExampleMatch, ExecutionFilter, Metric, MetricFilter, LogLevel};
 
 #[cfg(test)]
 mod executor_tests {
@@ -26,6 +26,7 @@ mod executor_tests {
             enable_priority_queue: true,
             enable_fair_scheduling: false,
             polling_interval: Duration::from_millis(50),
+            ..SchedulerConfig::default()
         });
 
         let worker_pool_config = Some(WorkerPoolConfig {
@@ -36,6 +37,9 @@ mod executor_tests {
             enable_auto_scaling: true,
             scale_up_threshold: 0.7,
             scale_down_threshold: 0.3,
+            environment_label: None,
+            capabilities: Vec::new(),
+            artifact_cache_bytes: 1024 * 1024 * 1024,
         });
 
         let executor = create_test_executor_with_config(scheduler_config, worker_pool_config).await;
@@ -152,14 +156,1709 @@ mod executor_tests {
     }
 }
 
+#[cfg(test)]
+mod env_policy_tests {
+    use super::*;
+    use std::collections::HashMap;
+    use stepflow_executor::env_policy::{ENV_EXECUTION_ID, ENV_SESSION_ID, ENV_TENANT};
+
+    fn env(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_injects_mandatory_variables() {
+        let policy = EnvironmentPolicy::default();
+        let execution_id = ExecutionId::new();
+
+        let environment = policy
+            .apply(&env(&[("LOG_FORMAT", "json")]), None, &execution_id, "tenant-a")
+            .unwrap();
+
+        assert_eq!(environment["LOG_FORMAT"], "json");
+        assert_eq!(environment[ENV_EXECUTION_ID], execution_id.to_string());
+        assert_eq!(environment[ENV_TENANT], "tenant-a");
+    }
+
+    #[test]
+    fn test_session_environment() {
+        let policy = EnvironmentPolicy::default();
+
+        let environment = policy
+            .apply_session(&env(&[("LOG_FORMAT", "json")]), None, "session-1", "tenant-a")
+            .unwrap();
+        assert_eq!(environment[ENV_SESSION_ID], "session-1");
+        assert_eq!(environment[ENV_TENANT], "tenant-a");
+        assert!(!environment.contains_key(ENV_EXECUTION_ID));
+
+        assert!(policy.apply_session(&env(&[("DYLD_INSERT_LIBRARIES", "x")]), None, "session-1", "tenant-a").is_err());
+    }
+
+    #[test]
+    fn test_rejects_reserved_and_denied_variables() {
+        let policy = EnvironmentPolicy::default();
+        let requested = env(&[("STEPFLOW_TENANT", "other"), ("LD_PRELOAD", "/tmp/x.so")]);
+
+        let error = policy.apply(&requested, None, &ExecutionId::new(), "tenant-a").unwrap_err();
+        let message = error.to_string();
+
+        assert!(matches!(error, ExecutorError::EnvironmentPolicyViolation(_)));
+        assert!(message.contains("STEPFLOW_TENANT: reserved"));
+        assert!(message.contains("LD_PRELOAD: denied"));
+    }
+
+    #[test]
+    fn test_tenant_rules_narrow_admin_policy() {
+        let policy = EnvironmentPolicy {
+            allow: vec!["APP_*".to_string(), "AWS_*".to_string()],
+            ..EnvironmentPolicy::default()
+        };
+        let rules = EnvironmentRules {
+            allow: vec!["APP_*".to_string()],
+            deny: vec!["APP_SECRET*".to_string()],
+        };
+
+        assert!(policy.apply(&env(&[("AWS_REGION", "eu")]), None, &ExecutionId::new(), "t").is_ok());
+        assert!(policy.apply(&env(&[("AWS_REGION", "eu")]), Some(&rules), &ExecutionId::new(), "t").is_err());
+        assert!(policy.apply(&env(&[("APP_SECRET_KEY", "x")]), Some(&rules), &ExecutionId::new(), "t").is_err());
+        assert!(policy.apply(&env(&[("APP_MODE", "fast")]), Some(&rules), &ExecutionId::new(), "t").is_ok());
+        assert!(policy.apply(&env(&[("HOME", "/root")]), None, &ExecutionId::new(), "t").is_err());
+    }
+
+    #[test]
+    fn test_enforces_size_limits() {
+        let policy = EnvironmentPolicy {
+            max_variables: 1,
+            max_value_length: 8,
+            ..EnvironmentPolicy::default()
+        };
+
+        let too_many = env(&[("A", "1"), ("B", "2")]);
+        assert!(policy.apply(&too_many, None, &ExecutionId::new(), "t").is_err());
+
+        let too_long = env(&[("A", "123456789")]);
+        assert!(policy.apply(&too_long, None, &ExecutionId::new(), "t").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_execution_rejects_reserved_variables() {
+        let executor = create_test_executor().await.unwrap();
+        let mut request = create_test_execution_request("test-tool-1");
+        request.context.environment.insert("STEPFLOW_EXECUTION_ID".to_string(), "forged".to_string());
+
+        let result = executor.execute_tool(request).await;
+        assert!(matches!(result, Err(ExecutorError::EnvironmentPolicyViolation(_))));
+    }
+}
+
+#[cfg(test)]
+mod template_tests {
+    use super::*;
+    use std::collections::HashMap;
+    use serde_json::json;
+
+    fn context() -> TemplateContext {
+        let mut request = create_test_execution_request("test-tool-1");
+        request.context.user_id = "user-42".to_string();
+        request.context.environment.insert("REGION".to_string(), "eu-west-1".to_string());
+        let now = chrono::DateTime::parse_from_rfc3339("2024-05-01T12:00:00Z").unwrap().with_timezone(&chrono::Utc);
+        TemplateContext::from_request(&request, &ExecutionId::from_string("exec-1".to_string()))
+            .with_step("previous", json!({"id": 7, "tags": ["a", "b"]}))
+            .with_now(now)
+    }
+
+    #[test]
+    fn test_renders_expressions() {
+        let context = context();
+
+        assert_eq!(context.render("{{ context.user_id }}").unwrap(), json!("user-42"));
+        assert_eq!(context.render("{{ steps.previous.output.id }}").unwrap(), json!(7));
+        assert_eq!(context.render("{{steps.previous.output.tags[1]}}").unwrap(), json!("b"));
+        assert_eq!(context.render("{{ now() }}").unwrap(), json!("2024-05-01T12:00:00+00:00"));
+        assert_eq!(
+            context.render("{{ upper(env.REGION) }}/{{ context.execution_id }}/{{ today() }}").unwrap(),
+            json!("EU-WEST-1/exec-1/2024-05-01")
+        );
+        assert_eq!(context.render("{{ default(context.missing, 'none') }}").unwrap(), json!("none"));
+        assert_eq!(context.render("{{ length(steps.previous.output.tags) }}").unwrap(), json!(2));
+        assert_eq!(context.render("{{ concat('#', steps[\"previous\"].output.id) }}").unwrap(), json!("#7"));
+        assert_eq!(context.render("plain text").unwrap(), json!("plain text"));
+    }
+
+    #[test]
+    fn test_renders_nested_parameters() {
+        let parameters = HashMap::from([
+            ("body".to_string(), json!({"owner": "{{ context.user_id }}", "ids": ["{{ steps.previous.output.id }}", 1]})),
+            ("limit".to_string(), json!(10)),
+        ]);
+
+        let rendered = context().render_parameters(&parameters).unwrap();
+        assert_eq!(rendered["body"], json!({"owner": "user-42", "ids": [7, 1]}));
+        assert_eq!(rendered["limit"], json!(10));
+
+        // Rendered values are not evaluated again
+        let context = TemplateContext::new().with_step("echo", json!("{{ uuid() }}"));
+        assert_eq!(context.render("{{ steps.echo.output }}").unwrap(), json!("{{ uuid() }}"));
+    }
+
+    #[test]
+    fn test_reports_error_spans() {
+        let context = context();
+        let parameters = HashMap::from([("body".to_string(), json!({"items": ["ok", "id={{ steps.next.output }}"]}))]);
+
+        let error = context.render_parameters(&parameters).unwrap_err();
+        assert_eq!(error.kind, TemplateErrorKind::Undefined);
+        assert_eq!(error.parameter, "body.items[1]");
+        assert_eq!(&error.template[error.start..error.end], "steps.next");
+
+        let error = context.render("{{ exec('rm -rf /') }}").unwrap_err();
+        assert_eq!(error.kind, TemplateErrorKind::UnknownFunction);
+        assert_eq!((error.start, error.end), (3, 19));
+
+        let error = context.render("{{ context.user_id ").unwrap_err();
+        assert_eq!(error.kind, TemplateErrorKind::Syntax);
+        assert!(error.to_string().contains("expected '}}'"));
+
+        assert_eq!(context.render("{{ upper(1) }}").unwrap_err().kind, TemplateErrorKind::InvalidArgument);
+        assert_eq!(context.render("{{ 'open }}").unwrap_err().kind, TemplateErrorKind::Syntax);
+    }
+
+    #[tokio::test]
+    async fn test_execution_rejects_invalid_templates() {
+        let executor = create_test_executor().await.unwrap();
+        let mut request = create_test_execution_request("test-tool-1");
+        request.parameters.insert("when".to_string(), json!("{{ yesterday() }}"));
+
+        let result = executor.execute_tool(request).await;
+        assert!(matches!(result, Err(ExecutorError::TemplateError(_))));
+    }
+
+    #[test]
+    fn test_predicates_and_escaping() {
+        let context = context().with_variable("item", json!({"score": 3, "tags": ["x"]}));
+
+        assert_eq!(context.render("{{ gt(item.score, 2) }}").unwrap(), json!(true));
+        assert_eq!(context.render("{{ and(eq(env.REGION, 'eu-west-1'), not(contains(item.tags, 'y'))) }}").unwrap(), json!(true));
+        // `or` short-circuits, so the undefined path is never evaluated
+        assert_eq!(context.render("{{ or(true, item.missing.field) }}").unwrap(), json!(true));
+        assert_eq!(context.render("{{ lte(length(item.tags), 0) }}").unwrap(), json!(false));
+        assert!(!template::is_truthy(&json!([])));
+        assert!(template::is_truthy(&json!("no")));
+
+        let escaped = template::escape(&json!({"text": "{{ uuid() }}"}));
+        assert_eq!(context.render_parameters(&HashMap::from([("p".to_string(), escaped)])).unwrap()["p"], json!({"text": "{{ uuid() }}"}));
+        assert!(context.render("{{ missing }}").unwrap_err().message.contains("item"));
+    }
+}
+
+#[cfg(test)]
+mod environment_tests {
+    use super::*;
+    use std::sync::Arc;
+    use stepflow_registry::{InMemoryRegistry, Registry};
+
+    fn labeled_work(environment_label: Option<EnvironmentLabel>) -> Work {
+        let mut request = create_test_execution_request("test-tool-1");
+        request.options.environment_label = environment_label;
+        Work {
+            id: WorkId::new(),
+            task: Task {
+                id: TaskId::new(),
+                execution_request: request,
+                priority: Priority::Normal,
+                created_at: chrono::Utc::now(),
+                scheduled_at: None,
+            },
+            assigned_worker: None,
+            started_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_worker_pool_dispatches_by_environment() {
+        let registry = setup_in_memory_registry().await;
+        let pool = WorkerPoolImpl::new(registry, WorkerPoolConfig {
+            min_workers: 1,
+            enable_auto_scaling: false,
+            ..WorkerPoolConfig::default()
+        });
+        pool.start().await.unwrap();
+
+        assert!(matches!(
+            pool.submit_work(labeled_work(Some(EnvironmentLabel::Prod))).await,
+            Err(WorkerPoolError::Unschedulable(unmet)) if unmet == vec!["environment=prod"]
+        ));
+
+        let prod_profile = WorkerProfile::new(Some(EnvironmentLabel::Prod), vec![]);
+        pool.add_workers(prod_profile.clone(), 1).await.unwrap();
+        let status = pool.get_pool_status().await.unwrap();
+        assert_eq!(status.total_workers, 2);
+        assert!(status.idle_profiles.contains(&WorkerProfile::default()));
+        assert!(status.idle_profiles.contains(&prod_profile));
+
+        let prod = pool.submit_work(labeled_work(Some(EnvironmentLabel::Prod))).await.unwrap();
+        let unlabeled = pool.submit_work(labeled_work(None)).await.unwrap();
+        assert!(matches!(
+            pool.submit_work(labeled_work(Some(EnvironmentLabel::Staging))).await,
+            Err(WorkerPoolError::Unschedulable(_))
+        ));
+
+        let finished = |work_id: WorkId| {
+            let pool = pool.clone();
+            move || {
+                let pool = pool.clone();
+                let work_id = work_id.clone();
+                async move { pool.get_work_status(&work_id).await.is_ok_and(|s| s != WorkStatus::Pending) }
+            }
+        };
+        assert!(wait_for_condition(finished(prod), Duration::from_secs(5), Duration::from_millis(20)).await);
+        assert!(wait_for_condition(finished(unlabeled), Duration::from_secs(5), Duration::from_millis(20)).await);
+        pool.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_execution_runs_in_tool_environment() {
+        let registry: Arc<InMemoryRegistry> = setup_in_memory_registry().await;
+        let tool_id = ToolId::from_string("test-tool-1".to_string());
+        let mut staged = registry.get_tool(&tool_id).await.unwrap();
+        staged.environment_label = Some(EnvironmentLabel::Staging);
+        registry.update_tool(&tool_id, &staged).await.unwrap();
+        let executor = create_executor_with_backends(
+            Arc::new(InMemoryExecutionStore::new()),
+            Arc::new(InMemoryResultManager::new()),
+            Arc::new(InMemoryMonitoring::new()),
+            registry,
+            None,
+            None,
+        ).unwrap();
+
+        // Unlabeled executions inherit the tool's environment
+        let result = executor.execute_tool(create_test_execution_request("test-tool-1")).await.unwrap();
+        assert_eq!(result.metadata["environment_label"], "staging");
+
+        // A staging tool never runs as a production execution
+        let mut request = create_test_execution_request("test-tool-1");
+        request.options.environment_label = Some(EnvironmentLabel::Prod);
+        assert!(matches!(executor.execute_tool(request).await, Err(ExecutorError::InvalidParameters(_))));
+
+        // Unlabeled tools run in any environment
+        let mut request = create_test_execution_request("test-tool-2");
+        request.options.environment_label = Some(EnvironmentLabel::Prod);
+        let result = executor.execute_tool(request).await.unwrap();
+        assert_eq!(result.metadata["environment_label"], "prod");
+    }
+}
+
+#[cfg(test)]
+mod integrity_tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use stepflow_core::ToolImage;
+    use stepflow_registry::Registry;
+    use stepflow_sandbox::{
+        Command, ImageSandbox, IntegrityVerifier, Sandbox, SandboxConfig, SandboxError, SandboxFilter, SandboxId,
+        SandboxInfo, SandboxMetrics, SandboxResult, SandboxStatus,
+    };
+
+    const IMAGE: &str = "tools/python:3.11";
+    const PUBLISHED_DIGEST: &str = "sha256:4bcff63911fcb4448bd4fdacec207030997caf25e9bea4045fa6c8c44de311d1";
+
+    /// Image store serving a fixed digest per image; running sandboxes is not supported
+    #[derive(Default)]
+    struct ImageStore {
+        /// Digest the image registry serves for each image
+        remote: Mutex<HashMap<String, String>>,
+        /// Digests of the images pulled so far
+        local: Mutex<HashMap<String, Vec<String>>>,
+        pulls: AtomicUsize,
+    }
+
+    fn unsupported<T>() -> SandboxResult<T> {
+        Err(SandboxError::InternalError("not supported by the image store".to_string()))
+    }
+
+    #[async_trait::async_trait]
+    impl Sandbox for ImageStore {
+        async fn create_sandbox(&self, _config: SandboxConfig) -> SandboxResult<SandboxId> {
+            unsupported()
+        }
+
+        async fn execute_in_sandbox(&self, _sandbox_id: &SandboxId, _command: Command) -> SandboxResult<stepflow_sandbox::ExecutionResult> {
+            unsupported()
+        }
+
+        async fn destroy_sandbox(&self, _sandbox_id: &SandboxId) -> SandboxResult<()> {
+            unsupported()
+        }
+
+        async fn get_sandbox_status(&self, _sandbox_id: &SandboxId) -> SandboxResult<SandboxStatus> {
+            unsupported()
+        }
+
+        async fn list_sandboxes(&self, _filter: Option<SandboxFilter>) -> SandboxResult<Vec<SandboxInfo>> {
+            Ok(vec![])
+        }
+
+        async fn get_sandbox_info(&self, _sandbox_id: &SandboxId) -> SandboxResult<SandboxInfo> {
+            unsupported()
+        }
+
+        async fn update_sandbox_config(&self, _sandbox_id: &SandboxId, _config: SandboxConfig) -> SandboxResult<()> {
+            unsupported()
+        }
+
+        async fn pause_sandbox(&self, _sandbox_id: &SandboxId) -> SandboxResult<()> {
+            unsupported()
+        }
+
+        async fn resume_sandbox(&self, _sandbox_id: &SandboxId) -> SandboxResult<()> {
+            unsupported()
+        }
+
+        async fn get_sandbox_logs(&self, _sandbox_id: &SandboxId, _lines: Option<usize>) -> SandboxResult<Vec<String>> {
+            unsupported()
+        }
+
+        async fn get_sandbox_metrics(&self, _sandbox_id: &SandboxId) -> SandboxResult<SandboxMetrics> {
+            unsupported()
+        }
+
+        async fn health_check(&self) -> SandboxResult<bool> {
+            Ok(true)
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl ImageSandbox for ImageStore {
+        async fn pull_image(&self, image: &str) -> SandboxResult<()> {
+            let Some(digest) = self.remote.lock().unwrap().get(image).cloned() else {
+                return Err(SandboxError::InternalError(format!("unknown image {}", image)));
+            };
+            self.pulls.fetch_add(1, Ordering::SeqCst);
+            self.local.lock().unwrap().insert(image.to_string(), vec![format!("{}@{}", image, digest)]);
+            Ok(())
+        }
+
+        async fn image_digests(&self, image: &str) -> SandboxResult<Option<Vec<String>>> {
+            Ok(self.local.lock().unwrap().get(image).cloned())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_digest_mismatch_fails_execution() {
+        let registry = setup_in_memory_registry().await;
+        let tool_id = ToolId::from_string("test-tool-1".to_string());
+        let mut tool = registry.get_tool(&tool_id).await.unwrap();
+        tool.image = Some(ToolImage::new(IMAGE, PUBLISHED_DIGEST));
+        registry.update_tool(&tool_id, &tool).await.unwrap();
+
+        // The image registry serves a different image under the same tag
+        let images = Arc::new(ImageStore::default());
+        images.remote.lock().unwrap().insert(IMAGE.to_string(), format!("sha256:{}", "0".repeat(64)));
+        let executor = create_executor_with_backends(
+            Arc::new(InMemoryExecutionStore::new()),
+            Arc::new(InMemoryResultManager::new()),
+            Arc::new(InMemoryMonitoring::new()),
+            registry,
+            None,
+            None,
+        ).unwrap()
+        .with_integrity_verifier(IntegrityVerifier::new(images.clone()));
+
+        let error = executor.execute_tool(create_test_execution_request("test-tool-1")).await.unwrap_err();
+        assert!(matches!(&error, ExecutorError::ExecutionFailed(message) if message.contains("Integrity check failed")));
+
+        // Tools without a pinned image are not checked
+        executor.execute_tool(create_test_execution_request("test-tool-2")).await.unwrap();
+
+        // A queued execution prefetches the image and fails once it runs
+        images.local.lock().unwrap().clear();
+        let execution_id = executor.execute_tool_async(create_test_execution_request("test-tool-1")).await.unwrap();
+        let failed = || {
+            let executor = executor.clone();
+            let execution_id = execution_id.clone();
+            async move {
+                executor.get_execution_timeline(&execution_id).await.ok().flatten()
+                    .is_some_and(|timeline| timeline.events.iter().any(|event| event.state == ExecutionState::Failed))
+            }
+        };
+        assert!(wait_for_condition(failed, Duration::from_secs(5), Duration::from_millis(20)).await);
+        assert_eq!(images.pulls.load(Ordering::SeqCst), 2);
+
+        let verifier = executor.integrity_verifier().unwrap();
+        let events = verifier.events().await;
+        assert!(events.len() >= 3);
+        assert!(events.iter().all(|event| event.tool_id == "test-tool-1" && event.expected_digest == PUBLISHED_DIGEST));
+
+        // Once the published image is served again, executions run
+        images.remote.lock().unwrap().insert(IMAGE.to_string(), PUBLISHED_DIGEST.to_string());
+        images.local.lock().unwrap().clear();
+        let result = executor.execute_tool(create_test_execution_request("test-tool-1")).await.unwrap();
+        assert!(result.success);
+    }
+}
+
+#[cfg(test)]
+mod concurrency_tests {
+    use super::*;
+    use std::sync::Arc;
+
+    const TENANT: &str = "test-tenant-456";
+
+    #[tokio::test]
+    async fn test_concurrency_group_queues_fifo() {
+        let groups = ConcurrencyGroups::new();
+        let group = ConcurrencyGroup::mutex("db-main");
+        let ids: Vec<ExecutionId> = (0..3).map(|_| ExecutionId::new()).collect();
+
+        let first = groups.acquire(TENANT, "db-main", &group, &ids[0], None).await.unwrap();
+        // Queue the waiters one after the other so their order is known
+        let mut waiters = Vec::new();
+        for id in &ids[1..] {
+            waiters.push(tokio::spawn({
+                let (groups, group, id) = (groups.clone(), group.clone(), id.clone());
+                async move { groups.acquire(TENANT, "db-main", &group, &id, None).await }
+            }));
+            while groups.queue_position(id).is_none() {
+                tokio::task::yield_now().await;
+            }
+        }
+
+        let position = groups.queue_position(&ids[1]).unwrap();
+        assert_eq!((position.key.as_str(), position.position, position.running), ("db-main", 1, 1));
+        assert_eq!(groups.queue_position(&ids[2]).unwrap().position, 2);
+        let status = groups.status(TENANT);
+        assert_eq!(status.len(), 1);
+        assert_eq!(status[0].running, vec![ids[0].clone()]);
+        assert_eq!(status[0].waiting, ids[1..].to_vec());
+        assert!(groups.status("other-tenant").is_empty());
+
+        // Another tenant's group with the same key is independent
+        let other = groups.acquire("other-tenant", "db-main", &group, &ExecutionId::new(), None).await.unwrap();
+
+        // Slots are handed over in arrival order
+        drop(first);
+        let mut waiters = waiters.into_iter();
+        let second = waiters.next().unwrap().await.unwrap().unwrap();
+        assert_eq!(groups.queue_position(&ids[2]).unwrap().position, 1);
+        drop(second);
+        let third = waiters.next().unwrap().await.unwrap().unwrap();
+        assert_eq!(third.key(), "db-main");
+        drop(third);
+        drop(other);
+        assert!(groups.status(TENANT).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_group_timeouts_and_deadlocks() {
+        let groups = ConcurrencyGroups::new();
+        let group = ConcurrencyGroup { timeout_ms: Some(50), ..ConcurrencyGroup::mutex("deploy") };
+        let parent = ExecutionId::new();
+        let _held = groups.acquire(TENANT, "deploy", &group, &parent, None).await.unwrap();
+
+        let waiting = ExecutionId::new();
+        assert!(matches!(
+            groups.acquire(TENANT, "deploy", &group, &waiting, None).await,
+            Err(ExecutorError::TimeoutExceeded)
+        ));
+        assert!(groups.queue_position(&waiting).is_none());
+
+        // A child would wait for its own parent
+        assert!(matches!(
+            groups.acquire(TENANT, "deploy", &group, &ExecutionId::new(), Some(&parent)).await,
+            Err(ExecutorError::Conflict(_))
+        ));
+
+        // Raising the limit lets it through
+        let wide = ConcurrencyGroup { max_concurrency: 2, ..group.clone() };
+        let _child = groups.acquire(TENANT, "deploy", &wide, &ExecutionId::new(), Some(&parent)).await.unwrap();
+        assert_eq!(groups.status(TENANT)[0].running.len(), 2);
+
+        // Cancelling a waiter ends its wait
+        let cancelled = ExecutionId::new();
+        let wait = {
+            let (groups, group, id) = (groups.clone(), wide.clone(), cancelled.clone());
+            tokio::spawn(async move { groups.acquire(TENANT, "deploy", &group, &id, None).await })
+        };
+        while groups.queue_position(&cancelled).is_none() {
+            tokio::task::yield_now().await;
+        }
+        assert!(groups.cancel(&cancelled));
+        assert!(matches!(wait.await.unwrap(), Err(ExecutorError::ExecutionFailed(_))));
+    }
+
+    #[tokio::test]
+    async fn test_execution_concurrency_key_from_parameters() {
+        let executor = create_executor_with_backends(
+            Arc::new(InMemoryExecutionStore::new()),
+            Arc::new(InMemoryResultManager::new()),
+            Arc::new(InMemoryMonitoring::new()),
+            setup_in_memory_registry().await,
+            None,
+            None,
+        ).unwrap();
+
+        let mut request = create_test_execution_request("test-tool-1");
+        request.options.concurrency = Some(ConcurrencyGroup::mutex("report-{{ params.format }}"));
+        assert!(executor.execute_tool(request.clone()).await.unwrap().success);
+        let tenant_id = TenantId::from_string(TENANT.to_string());
+        assert!(executor.list_concurrency_groups(&tenant_id).await.unwrap().is_empty());
+
+        request.options.concurrency = Some(ConcurrencyGroup::mutex("report-{{ params.missing }}"));
+        assert!(matches!(executor.execute_tool(request.clone()).await, Err(ExecutorError::TemplateError(_))));
+
+        request.options.concurrency = Some(ConcurrencyGroup { max_concurrency: 0, ..ConcurrencyGroup::mutex("report") });
+        assert!(matches!(executor.execute_tool(request).await, Err(ExecutorError::InvalidParameters(_))));
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_groups_rejected_with_shared_queue() {
+        let executor = create_executor_with_backends(
+            Arc::new(InMemoryExecutionStore::new()),
+            Arc::new(InMemoryResultManager::new()),
+            Arc::new(InMemoryMonitoring::new()),
+            setup_in_memory_registry().await,
+            Some(SchedulerConfig { shared_queue: true, ..SchedulerConfig::default() }),
+            None,
+        ).unwrap();
+
+        let mut request = create_test_execution_request("test-tool-1");
+        request.options.concurrency = Some(ConcurrencyGroup::mutex("deploy"));
+        assert!(matches!(executor.execute_tool(request.clone()).await, Err(ExecutorError::InvalidParameters(_))));
+        assert!(matches!(executor.execute_tool_async(request).await, Err(ExecutorError::InvalidParameters(_))));
+    }
+}
+
+#[cfg(test)]
+mod annotation_tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_labels_and_notes() {
+        let executor = create_executor_with_backends(
+            Arc::new(InMemoryExecutionStore::new()),
+            Arc::new(InMemoryResultManager::new()),
+            Arc::new(InMemoryMonitoring::new()),
+            setup_in_memory_registry().await,
+            None,
+            None,
+        ).unwrap();
+
+        let mut request = create_test_execution_request("test-tool-1");
+        request.context.labels = HashMap::from([("team".to_string(), "payments".to_string())]);
+        request.context.note = Some("nightly reconciliation".to_string());
+        let result = executor.execute_tool(request).await.unwrap();
+        let payments = ExecutionId::from_string(result.metadata["execution_id"].as_str().unwrap().to_string());
+        let result = executor.execute_tool(create_test_execution_request("test-tool-1")).await.unwrap();
+        let other = ExecutionId::from_string(result.metadata["execution_id"].as_str().unwrap().to_string());
+
+        let (key, value) = parse_label("team:payments").unwrap();
+        let filter = ExecutionFilter { labels: HashMap::from([(key, value)]), ..Default::default() };
+        let listed = executor.list_executions(Some(filter.clone())).await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].execution_id, payments);
+        assert_eq!(listed[0].labels["team"], "payments");
+
+        // Labels added afterwards are filterable too
+        executor.label_execution(&other, HashMap::from([("team".to_string(), "payments".to_string())])).await.unwrap();
+        assert_eq!(executor.list_executions(Some(filter.clone())).await.unwrap().len(), 2);
+        assert!(executor.remove_execution_label(&other, "team").await.unwrap());
+        assert_eq!(executor.list_executions(Some(filter)).await.unwrap().len(), 1);
+
+        let note = executor.add_execution_note(&payments, "reviewer", "rerun after fix").await.unwrap();
+        let annotations = executor.get_execution_annotations(&payments).await.unwrap();
+        assert_eq!(annotations.notes.len(), 2);
+        assert_eq!(annotations.notes[0].body, "nightly reconciliation");
+        assert_eq!(annotations.notes[1], note);
+
+        assert!(matches!(
+            executor.label_execution(&other, HashMap::from([("bad key".to_string(), "x".to_string())])).await,
+            Err(ExecutorError::InvalidParameters(_))
+        ));
+        assert!(matches!(
+            executor.add_execution_note(&ExecutionId::new(), "reviewer", "lost").await,
+            Err(ExecutorError::ExecutionNotFound(_))
+        ));
+    }
+}
+
+#[cfg(test)]
+mod estimate_tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_estimate_from_history() {
+        let executor = create_executor_with_backends(
+            Arc::new(InMemoryExecutionStore::new()),
+            Arc::new(InMemoryResultManager::new()),
+            Arc::new(InMemoryMonitoring::new()),
+            setup_in_memory_registry().await,
+            None,
+            None,
+        ).unwrap();
+
+        let request = create_test_execution_request("test-tool-1");
+        let estimate = executor.estimate_execution(&request).await.unwrap();
+        assert_eq!(estimate.basis, EstimateBasis::NoHistory);
+        assert_eq!(estimate.samples, 0);
+        assert_eq!(estimate.recommendation, Recommendation::RunAsync);
+
+        for _ in 0..5 {
+            executor.execute_tool(create_test_execution_request("test-tool-1")).await.unwrap();
+        }
+        let estimate = executor.estimate_execution(&request).await.unwrap();
+        assert_eq!(estimate.basis, EstimateBasis::ToolVersion);
+        assert_eq!(estimate.samples, 5);
+        assert_eq!(estimate.failure_rate, 0.0);
+        assert!(estimate.p90_duration_ms >= estimate.expected_duration_ms);
+        assert_eq!(estimate.recommendation, Recommendation::RunSync);
+
+        assert!(matches!(
+            executor.estimate_execution(&create_test_execution_request("missing-tool")).await,
+            Err(ExecutorError::ToolNotFound(_))
+        ));
+    }
+}
+
+#[cfg(test)]
+mod placement_tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn options(capabilities: &[&str], preferred: &[&str]) -> ExecutionOptions {
+        ExecutionOptions {
+            requirements: ToolRequirements {
+                capabilities: capabilities.iter().map(|c| c.to_string()).collect(),
+                preferred: preferred.iter().map(|c| c.to_string()).collect(),
+            },
+            ..ExecutionOptions::default()
+        }
+    }
+
+    fn profile(capabilities: &[&str]) -> WorkerProfile {
+        WorkerProfile::new(None, capabilities.iter().map(|c| c.to_string()).collect())
+    }
+
+    #[test]
+    fn test_profile_matching_and_affinity() {
+        let gpu_in_eu = options(&["python3.11", "gpu"], &["region=eu"]);
+        let cpu = profile(&["python3.11", "region=eu"]);
+        let gpu = profile(&["python3.11", "gpu"]);
+        let gpu_eu = profile(&["python3.11", "gpu", "region=eu"]);
+
+        assert_eq!(cpu.unmet(&gpu_in_eu), vec!["gpu"]);
+        assert!(gpu.satisfies(&gpu_in_eu));
+        assert_eq!(gpu.affinity(&gpu_in_eu), 0);
+        assert_eq!(gpu_eu.affinity(&gpu_in_eu), 1);
+
+        let mut prod = gpu_in_eu.clone();
+        prod.environment_label = Some(EnvironmentLabel::Prod);
+        assert_eq!(gpu.unmet(&prod), vec!["environment=prod"]);
+
+        // The closest worker's gaps are reported, nothing when one fits
+        assert_eq!(stepflow_executor::placement::unmet_constraints([&cpu], &gpu_in_eu), Some(vec!["gpu".to_string()]));
+        assert_eq!(stepflow_executor::placement::unmet_constraints([&cpu, &gpu], &gpu_in_eu), None);
+    }
+
+    #[tokio::test]
+    async fn test_scheduler_reports_unschedulable_tasks() {
+        let registry = setup_in_memory_registry().await;
+        let pool = Arc::new(WorkerPoolImpl::new(registry, WorkerPoolConfig {
+            min_workers: 1,
+            enable_auto_scaling: false,
+            capabilities: vec!["python3.11".to_string()],
+            ..WorkerPoolConfig::default()
+        }));
+        pool.start().await.unwrap();
+        let scheduler = SchedulerImpl::new(Arc::new(InMemoryExecutionStore::new()), pool.clone(), SchedulerConfig {
+            polling_interval: Duration::from_millis(10),
+            ..SchedulerConfig::default()
+        });
+        scheduler.start().await.unwrap();
+
+        let task = |options: ExecutionOptions| {
+            let mut request = create_test_execution_request("test-tool-1");
+            request.options = options;
+            Task {
+                id: TaskId::new(),
+                execution_request: request,
+                priority: Priority::Normal,
+                created_at: chrono::Utc::now(),
+                scheduled_at: None,
+            }
+        };
+        let gpu_task = scheduler.schedule_task(task(options(&["python3.11", "gpu"], &[]))).await.unwrap();
+        let python_task = scheduler.schedule_task(task(options(&["python3.11"], &["gpu"]))).await.unwrap();
+
+        let settled = |task_id: TaskId, expected: TaskStatus| {
+            let scheduler = scheduler.clone();
+            move || {
+                let scheduler = scheduler.clone();
+                let task_id = task_id.clone();
+                async move { scheduler.get_task_status(&task_id).await.is_ok_and(|s| s == expected) }
+            }
+        };
+        assert!(wait_for_condition(settled(gpu_task.clone(), TaskStatus::Unschedulable), Duration::from_secs(5), Duration::from_millis(20)).await);
+        assert!(wait_for_condition(settled(python_task.clone(), TaskStatus::Running), Duration::from_secs(5), Duration::from_millis(20)).await);
+
+        let unschedulable = scheduler.unschedulable_tasks().await;
+        assert_eq!(unschedulable.get(&gpu_task), Some(&vec!["gpu".to_string()]));
+        assert!(!unschedulable.contains_key(&python_task));
+
+        scheduler.stop().await.unwrap();
+        pool.stop().await.unwrap();
+    }
+
+    #[test]
+    fn test_artifact_cache_eviction_and_rank() {
+        let mut cache = ArtifactCache::new(100);
+        assert!(!cache.insert(&ArtifactHint::new("a", 40)));
+        assert!(!cache.insert(&ArtifactHint::new("b", 40)));
+        assert!(cache.insert(&ArtifactHint::new("a", 40)));
+        // "b" is the least recently used and makes room for "c"
+        assert!(!cache.insert(&ArtifactHint::new("c", 40)));
+        assert!(cache.contains("a") && !cache.contains("b") && cache.contains("c"));
+        assert_eq!(cache.used_bytes(), 80);
+        // Artifacts beyond the whole budget are never cached
+        assert!(!cache.insert(&ArtifactHint::new("huge", 200)));
+        assert_eq!(cache.len(), 2);
+
+        let reads = ExecutionOptions {
+            artifacts: vec![ArtifactHint::new("a", 40), ArtifactHint::new("b", 40)],
+            ..options(&[], &["gpu"])
+        };
+        let rank = |capabilities: &[&str], cache: &ArtifactCache| stepflow_executor::placement::placement_rank(&profile(capabilities), cache, &reads);
+        assert_eq!(rank(&[], &cache), (0, 40));
+        assert!(rank(&["gpu"], &ArtifactCache::default()) > rank(&[], &cache));
+        assert!(cache.remove("a"));
+        assert_eq!(rank(&[], &cache), (0, 0));
+    }
+
+    #[tokio::test]
+    async fn test_worker_pool_prefers_cached_artifacts() {
+        let registry = setup_in_memory_registry().await;
+        let pool = WorkerPoolImpl::new(registry, WorkerPoolConfig {
+            min_workers: 1,
+            enable_auto_scaling: false,
+            ..WorkerPoolConfig::default()
+        });
+        pool.start().await.unwrap();
+        let cached = pool.add_workers(WorkerProfile::default(), 1).await.unwrap().remove(0);
+        let dataset = ArtifactHint::new("sha256:dataset", 4 * 1024 * 1024 * 1024);
+        pool.record_artifact(&cached, dataset.clone()).await.unwrap();
+        assert!(matches!(
+            pool.record_artifact(&WorkerId::new(), dataset.clone()).await,
+            Err(WorkerPoolError::WorkerNotFound(_))
+        ));
+
+        // Only the worker holding the dataset takes work that reads it
+        for _ in 0..3 {
+            let mut request = create_test_execution_request("test-tool-1");
+            request.options.artifacts = vec![dataset.clone()];
+            pool.submit_work(Work {
+                id: WorkId::new(),
+                task: Task {
+                    id: TaskId::new(),
+                    execution_request: request,
+                    priority: Priority::Normal,
+                    created_at: chrono::Utc::now(),
+                    scheduled_at: None,
+                },
+                assigned_worker: None,
+                started_at: None,
+            }).await.unwrap();
+        }
+        let drained = || {
+            let pool = pool.clone();
+            async move { pool.get_pool_status().await.is_ok_and(|status| status.artifact_bytes_reused == 3 * dataset.size_bytes) }
+        };
+        assert!(wait_for_condition(drained, Duration::from_secs(5), Duration::from_millis(20)).await);
+        assert_eq!(pool.get_pool_status().await.unwrap().artifact_bytes_transferred, 0);
+        assert_eq!(pool.artifact_locations(&dataset.artifact_id).await, vec![cached]);
+
+        assert_eq!(pool.evict_artifact(&dataset.artifact_id).await, 1);
+        assert!(pool.artifact_locations(&dataset.artifact_id).await.is_empty());
+        pool.stop().await.unwrap();
+    }
+
+    /// Store queue that records which operations the scheduler routed through it
+    struct RecordingQueue {
+        inner: StoreTaskQueue,
+        calls: std::sync::Mutex<Vec<&'static str>>,
+    }
+
+    #[async_trait::async_trait]
+    impl TaskQueue for RecordingQueue {
+        fn backend(&self) -> &'static str {
+            "recording"
+        }
+
+        async fn push(&self, task: &Task) -> ExecutorResult<()> {
+            self.calls.lock().unwrap().push("push");
+            self.inner.push(task).await
+        }
+
+        async fn claim(&self, instance_id: &str, limit: usize, lease_until: chrono::DateTime<chrono::Utc>) -> ExecutorResult<Vec<Task>> {
+            let tasks = self.inner.claim(instance_id, limit, lease_until).await?;
+            if !tasks.is_empty() {
+                self.calls.lock().unwrap().push("claim");
+            }
+            Ok(tasks)
+        }
+
+        async fn renew(&self, instance_id: &str, task_ids: &[TaskId], lease_until: chrono::DateTime<chrono::Utc>) -> ExecutorResult<Vec<TaskId>> {
+            self.inner.renew(instance_id, task_ids, lease_until).await
+        }
+
+        async fn release(&self, instance_id: &str, task_id: &TaskId) -> ExecutorResult<()> {
+            self.calls.lock().unwrap().push("release");
+            self.inner.release(instance_id, task_id).await
+        }
+
+        async fn ack(&self, instance_id: &str, task_id: &TaskId) -> ExecutorResult<()> {
+            self.inner.ack(instance_id, task_id).await
+        }
+
+        async fn running_by_instance(&self) -> ExecutorResult<std::collections::HashMap<String, usize>> {
+            self.inner.running_by_instance().await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scheduler_uses_configured_task_queue() {
+        let registry = setup_in_memory_registry().await;
+        let pool = Arc::new(WorkerPoolImpl::new(registry, WorkerPoolConfig {
+            min_workers: 1,
+            enable_auto_scaling: false,
+            capabilities: vec!["python3.11".to_string()],
+            ..WorkerPoolConfig::default()
+        }));
+        pool.start().await.unwrap();
+        let store = Arc::new(InMemoryExecutionStore::new());
+        let queue = Arc::new(RecordingQueue {
+            inner: StoreTaskQueue::new(store.clone()),
+            calls: std::sync::Mutex::new(Vec::new()),
+        });
+        let config = SchedulerConfig {
+            polling_interval: Duration::from_millis(10),
+            task_queue: Some(queue.clone()),
+            ..SchedulerConfig::default()
+        };
+        assert!(!config.shared_queue);
+        let scheduler = SchedulerImpl::new(store, pool.clone(), config);
+        scheduler.start().await.unwrap();
+
+        let mut request = create_test_execution_request("test-tool-1");
+        request.options = options(&["python3.11"], &[]);
+        let task_id = scheduler.schedule_task(Task {
+            id: TaskId::new(),
+            execution_request: request,
+            priority: Priority::Normal,
+            created_at: chrono::Utc::now(),
+            scheduled_at: None,
+        }).await.unwrap();
+
+        let running = || {
+            let scheduler = scheduler.clone();
+            let task_id = task_id.clone();
+            async move { scheduler.get_task_status(&task_id).await.is_ok_and(|s| s == TaskStatus::Running) }
+        };
+        assert!(wait_for_condition(running, Duration::from_secs(5), Duration::from_millis(20)).await);
+        assert_eq!(queue.calls.lock().unwrap()[..2], ["push", "claim"]);
+        let metrics = scheduler.shared_queue_metrics().await.unwrap();
+        assert_eq!(metrics.claimed_total, 1);
+        assert_eq!(metrics.running_by_instance.get(&metrics.instance_id), Some(&1));
+
+        scheduler.stop().await.unwrap();
+        pool.stop().await.unwrap();
+    }
+}
+
+#[cfg(test)]
+mod rollout_tests {
+    use super::*;
+    use std::sync::Arc;
+    use stepflow_registry::{InMemoryRegistry, Registry};
+
+    fn candidate(tool: &ToolInfo) -> ToolInfo {
+        let mut candidate = tool.clone();
+        candidate.version = ToolVersion::new(1, 1, 0);
+        candidate.description = "Candidate build".to_string();
+        candidate
+    }
+
+    fn rollout(percentage: u8, thresholds: RolloutThresholds) -> ToolRollout {
+        let tool = create_sample_tools().remove(0);
+        ToolRollout {
+            id: "rollout-1".to_string(),
+            tool_id: tool.id.clone(),
+            stable_version: tool.version.clone(),
+            candidate: candidate(&tool),
+            percentage,
+            thresholds,
+            status: RolloutStatus::Active,
+            stable: ArmStats::default(),
+            candidate_stats: ArmStats::default(),
+            decision_reason: None,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    fn stats(executions: u64, failures: u64, total_duration_ms: u64) -> ArmStats {
+        ArmStats { executions, failures, total_duration_ms }
+    }
+
+    async fn setup() -> (Arc<InMemoryRegistry>, RolloutManager, ExecutorImpl, ToolTestRunner) {
+        let registry = setup_in_memory_registry().await;
+        let store: Arc<InMemoryExecutionStore> = Arc::new(InMemoryExecutionStore::new());
+        let manager = RolloutManager::new(store.clone(), registry.clone());
+        let executor = create_executor_with_backends(
+            store.clone(),
+            Arc::new(InMemoryResultManager::new()),
+            Arc::new(InMemoryMonitoring::new()),
+            registry.clone(),
+            None,
+            None,
+        ).unwrap();
+        let tests = ToolTestRunner::new(Arc::new(executor.clone()), store);
+        (registry, manager, executor, tests)
+    }
+
+    async fn test_candidate(tests: &ToolTestRunner, manager: &RolloutManager, tool_id: &ToolId) -> ToolTestRun {
+        let candidate = manager.active(tool_id).await.unwrap().unwrap().candidate;
+        tests.run(&candidate, TestTrigger::Promotion, create_test_execution_context()).await.unwrap()
+    }
+
+    #[test]
+    fn test_routing_follows_percentage_and_requested_version() {
+        let ids: Vec<ExecutionId> = (0..200).map(|_| ExecutionId::new()).collect();
+        let none = rollout(0, RolloutThresholds::default());
+        let all = rollout(100, RolloutThresholds::default());
+        let half = rollout(50, RolloutThresholds::default());
+
+        assert!(ids.iter().all(|id| none.route(id, None) == RolloutArm::Stable));
+        assert!(ids.iter().all(|id| all.route(id, None) == RolloutArm::Candidate));
+        let candidates = ids.iter().filter(|id| half.route(id, None) == RolloutArm::Candidate).count();
+        assert!((50..150).contains(&candidates), "got {} of 200", candidates);
+
+        // Routing is stable per execution
+        assert!(ids.iter().all(|id| half.route(id, None) == half.route(id, None)));
+
+        // An explicit version picks its arm regardless of the percentage
+        assert_eq!(none.route(&ids[0], Some(&ToolVersion::new(1, 1, 0))), RolloutArm::Candidate);
+        assert_eq!(all.route(&ids[0], Some(&ToolVersion::new(1, 0, 0))), RolloutArm::Stable);
+    }
+
+    #[test]
+    fn test_evaluation_thresholds() {
+        let thresholds = RolloutThresholds {
+            min_samples: 10,
+            max_error_rate_increase: 0.1,
+            max_latency_ratio: 2.0,
+            promote_after: Some(20),
+        };
+
+        let mut rollout = rollout(50, thresholds);
+        rollout.stable = stats(100, 5, 10_000);
+        rollout.candidate_stats = stats(9, 9, 900);
+        assert!(rollout.evaluate().is_none(), "too few samples to judge");
+
+        rollout.candidate_stats = stats(10, 1, 1_000);
+        assert!(rollout.evaluate().is_none(), "within thresholds");
+
+        rollout.candidate_stats = stats(10, 3, 1_000);
+        assert_eq!(rollout.evaluate().unwrap().0, RolloutStatus::RolledBack);
+
+        rollout.candidate_stats = stats(10, 0, 2_500);
+        let (status, reason) = rollout.evaluate().unwrap();
+        assert_eq!(status, RolloutStatus::RolledBack);
+        assert!(reason.contains("duration"));
+
+        rollout.candidate_stats = stats(20, 1, 2_000);
+        assert_eq!(rollout.evaluate().unwrap().0, RolloutStatus::Promoted);
+    }
+
+    #[tokio::test]
+    async fn test_rollout_promotes_candidate_automatically() {
+        let (registry, manager, executor, tests) = setup().await;
+        let tool_id = ToolId::from_string("test-tool-1".to_string());
+        let stable = registry.get_tool(&tool_id).await.unwrap();
+        let thresholds = RolloutThresholds {
+            min_samples: 3,
+            promote_after: Some(3),
+            ..RolloutThresholds::default()
+        };
+        manager.start(&tool_id, candidate(&stable), 100, thresholds).await.unwrap();
+        // Test executions run the candidate but aren't counted
+        assert!(test_candidate(&tests, &manager, &tool_id).await.passed);
+
+        for _ in 0..3 {
+            let result = executor.execute_tool(create_test_execution_request("test-tool-1")).await.unwrap();
+            assert_eq!(result.metadata["rollout_arm"], "candidate");
+            assert_eq!(result.metadata["tool_version"], "1.1.0");
+        }
+
+        let rollout = manager.get(&tool_id).await.unwrap().unwrap();
+        assert_eq!(rollout.status, RolloutStatus::Promoted);
+        assert_eq!(rollout.candidate_stats.executions, 3);
+        assert!(manager.active(&tool_id).await.unwrap().is_none());
+
+        let promoted = registry.get_tool(&tool_id).await.unwrap();
+        assert_eq!(promoted.version, ToolVersion::new(1, 1, 0));
+        assert_eq!(promoted.description, "Candidate build");
+
+        // Later executions run the new stable version outside any rollout
+        let result = executor.execute_tool(create_test_execution_request("test-tool-1")).await.unwrap();
+        assert!(!result.metadata.contains_key("rollout_arm"));
+        assert_eq!(result.metadata["tool_version"], "1.1.0");
+    }
+
+    #[tokio::test]
+    async fn test_manual_overrides() {
+        let (registry, manager, executor, tests) = setup().await;
+        let tool_id = ToolId::from_string("test-tool-1".to_string());
+        let stable = registry.get_tool(&tool_id).await.unwrap();
+
+        let mut same_version = stable.clone();
+        same_version.description = "same".to_string();
+        assert!(matches!(
+            manager.start(&tool_id, same_version, 10, RolloutThresholds::default()).await,
+            Err(ExecutorError::InvalidParameters(_))
+        ));
+        assert!(matches!(
+            manager.start(&tool_id, candidate(&stable), 101, RolloutThresholds::default()).await,
+            Err(ExecutorError::InvalidParameters(_))
+        ));
+
+        manager.start(&tool_id, candidate(&stable), 0, RolloutThresholds::default()).await.unwrap();
+        assert!(matches!(
+            manager.start(&tool_id, candidate(&stable), 10, RolloutThresholds::default()).await,
+            Err(ExecutorError::Conflict(_))
+        ));
+
+        let result = executor.execute_tool(create_test_execution_request("test-tool-1")).await.unwrap();
+        assert_eq!(result.metadata["rollout_arm"], "stable");
+        assert_eq!(result.metadata["tool_version"], "1.0.0");
+
+        let updated = manager.set_percentage(&tool_id, 100).await.unwrap();
+        assert_eq!(updated.percentage, 100);
+        assert_eq!(updated.stable.executions, 1);
+
+        let rolled_back = manager.rollback(&tool_id, "bad canary").await.unwrap();
+        assert_eq!(rolled_back.status, RolloutStatus::RolledBack);
+        assert_eq!(rolled_back.decision_reason.as_deref(), Some("bad canary"));
+        assert!(matches!(manager.promote(&tool_id, "too late").await, Err(ExecutorError::Conflict(_))));
+        assert_eq!(registry.get_tool(&tool_id).await.unwrap().version, ToolVersion::new(1, 0, 0));
+
+        // A finished rollout makes room for the next one
+        manager.start(&tool_id, candidate(&stable), 5, RolloutThresholds::default()).await.unwrap();
+        test_candidate(&tests, &manager, &tool_id).await;
+        let promoted = manager.promote(&tool_id, "looks good").await.unwrap();
+        assert_eq!(promoted.status, RolloutStatus::Promoted);
+        assert_eq!(registry.get_tool(&tool_id).await.unwrap().version, ToolVersion::new(1, 1, 0));
+    }
+
+    #[tokio::test]
+    async fn test_promotion_requires_passing_examples() {
+        let (registry, manager, executor, tests) = setup().await;
+        let tool_id = ToolId::from_string("test-tool-1".to_string());
+        let stable = registry.get_tool(&tool_id).await.unwrap();
+        let thresholds = RolloutThresholds {
+            min_samples: 2,
+            promote_after: Some(2),
+            ..RolloutThresholds::default()
+        };
+
+        // Without a test run the candidate stays in rollout
+        manager.start(&tool_id, candidate(&stable), 100, thresholds.clone()).await.unwrap();
+        for _ in 0..3 {
+            executor.execute_tool(create_test_execution_request("test-tool-1")).await.unwrap();
+        }
+        assert_eq!(manager.active(&tool_id).await.unwrap().unwrap().candidate_stats.executions, 3);
+        assert!(matches!(manager.promote(&tool_id, "now").await, Err(ExecutorError::Conflict(_))));
+        manager.rollback(&tool_id, "retry with broken examples").await.unwrap();
+
+        // A failed run rolls the candidate back instead of promoting it
+        let mut broken = candidate(&stable);
+        broken.examples[0].match_mode = ExampleMatch::Exact;
+        manager.start(&tool_id, broken, 100, thresholds).await.unwrap();
+        let run = test_candidate(&tests, &manager, &tool_id).await;
+        assert!(!run.passed);
+        assert_eq!(run.failures(), 1);
+        assert!(run.results[0].mismatches.iter().any(|m| m.contains("unexpected field")));
+        for _ in 0..2 {
+            executor.execute_tool(create_test_execution_request("test-tool-1")).await.unwrap();
+        }
+        let rollout = manager.get(&tool_id).await.unwrap().unwrap();
+        assert_eq!(rollout.status, RolloutStatus::RolledBack);
+        assert!(rollout.decision_reason.unwrap().contains("failed 1 of 1 example tests"));
+        assert_eq!(registry.get_tool(&tool_id).await.unwrap().version, ToolVersion::new(1, 0, 0));
+
+        let runs = tests.list(&tool_id, 10).await.unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].trigger, TestTrigger::Promotion);
+    }
+
+    #[test]
+    fn test_match_example_modes() {
+        let actual = serde_json::json!({"id": 7, "tags": ["a", "b"], "owner": {"name": "ada", "admin": false}});
+
+        assert!(match_example(ExampleMatch::Exact, &actual, &actual).is_empty());
+        let subset = serde_json::json!({"tags": ["a"], "owner": {"name": "ada"}});
+        assert!(match_example(ExampleMatch::Subset, &subset, &actual).is_empty());
+        assert_eq!(match_example(ExampleMatch::Exact, &subset, &actual).len(), 3);
+
+        let wrong = serde_json::json!({"id": 8, "owner": {"email": "ada@example.com"}});
+        assert_eq!(
+            match_example(ExampleMatch::Subset, &wrong, &actual),
+            vec!["$.id: expected 8, got 7".to_string(), "$.owner.email: missing".to_string()]
+        );
+
+        let schema = serde_json::json!({"type": "object", "required": ["id"], "properties": {"id": {"type": "integer"}}});
+        assert!(match_example(ExampleMatch::Schema, &schema, &actual).is_empty());
+        assert!(!match_example(ExampleMatch::Schema, &schema, &serde_json::json!({"id": "7"})).is_empty());
+    }
+}
+
+#[cfg(test)]
+mod workflow_tests {
+    use super::*;
+    use std::collections::{HashMap, HashSet};
+    use std::sync::{Arc, Mutex};
+    use async_trait::async_trait;
+    use serde_json::{json, Value};
+    use stepflow_core::{Database, ExecutionResult};
+    use stepflow_database::{MigrationManager, SqliteDatabase};
+
+    /// Echoes its parameters and fails for the values in `failing`
+    ///
+    /// A call with the value `"hold"` signals `held` and waits for `release`.
+    #[derive(Default)]
+    struct EchoExecutor {
+        failing: Mutex<HashSet<i64>>,
+        calls: Mutex<Vec<Value>>,
+        held: tokio::sync::Notify,
+        release: tokio::sync::Notify,
+    }
+
+    #[async_trait]
+    impl Executor for EchoExecutor {
+        async fn execute_tool(&self, request: ExecutionRequest) -> ExecutorResult<ExecutionResult> {
+            let value = request.parameters.get("value").cloned().unwrap_or(Value::Null);
+            self.calls.lock().unwrap().push(value.clone());
+            if value == json!("hold") {
+                self.held.notify_one();
+                self.release.notified().await;
+            }
+            let failed = value.as_i64().is_some_and(|v| self.failing.lock().unwrap().contains(&v));
+            Ok(ExecutionResult {
+                success: !failed,
+                output: (!failed).then(|| json!({"value": value})),
+                error: failed.then(|| format!("cannot handle {}", value)),
+                logs: vec![],
+                metrics: HashMap::new(),
+                metadata: HashMap::from([("execution_id".to_string(), json!(uuid::Uuid::new_v4().to_string()))]),
+            })
+        }
+
+        async fn execute_tool_async(&self, _request: ExecutionRequest) -> ExecutorResult<ExecutionId> {
+            Err(ExecutorError::InternalError("EchoExecutor only runs tools synchronously".to_string()))
+        }
+
+        // The echo executor keeps no execution records, so every lookup misses
+
+        async fn get_execution_status(&self, execution_id: &ExecutionId) -> ExecutorResult<ExecutionStatus> {
+            Err(ExecutorError::ExecutionNotFound(execution_id.clone()))
+        }
+
+        async fn cancel_execution(&self, execution_id: &ExecutionId) -> ExecutorResult<()> {
+            Err(ExecutorError::ExecutionNotFound(execution_id.clone()))
+        }
+
+        async fn get_execution_result(&self, execution_id: &ExecutionId) -> ExecutorResult<ExecutionResult> {
+            Err(ExecutorError::ExecutionNotFound(execution_id.clone()))
+        }
+
+        async fn list_executions(&self, _filter: Option<ExecutionFilter>) -> ExecutorResult<Vec<ExecutionInfo>> {
+            Ok(Vec::new())
+        }
+
+        async fn get_execution_timeline(&self, _execution_id: &ExecutionId) -> ExecutorResult<Option<ExecutionTimeline>> {
+            Ok(None)
+        }
+
+        async fn label_execution(&self, execution_id: &ExecutionId, _labels: HashMap<String, String>) -> ExecutorResult<()> {
+            Err(ExecutorError::ExecutionNotFound(execution_id.clone()))
+        }
+
+        async fn remove_execution_label(&self, execution_id: &ExecutionId, _key: &str) -> ExecutorResult<bool> {
+            Err(ExecutorError::ExecutionNotFound(execution_id.clone()))
+        }
+
+        async fn add_execution_note(&self, execution_id: &ExecutionId, _author: &str, _body: &str) -> ExecutorResult<ExecutionNote> {
+            Err(ExecutorError::ExecutionNotFound(execution_id.clone()))
+        }
+
+        async fn get_execution_annotations(&self, execution_id: &ExecutionId) -> ExecutorResult<ExecutionAnnotations> {
+            Err(ExecutorError::ExecutionNotFound(execution_id.clone()))
+        }
+
+        async fn estimate_execution(&self, _request: &ExecutionRequest) -> ExecutorResult<ExecutionEstimate> {
+            Err(ExecutorError::InternalError("EchoExecutor has no execution history to estimate from".to_string()))
+        }
+
+        async fn get_execution_metrics(&self, execution_id: &ExecutionId) -> ExecutorResult<Vec<Metric>> {
+            Err(ExecutorError::ExecutionNotFound(execution_id.clone()))
+        }
+
+        async fn get_queue_position(&self, _execution_id: &ExecutionId) -> ExecutorResult<Option<QueuePosition>> {
+            Ok(None)
+        }
+
+        async fn list_concurrency_groups(&self, _tenant_id: &TenantId) -> ExecutorResult<Vec<ConcurrencyGroupStatus>> {
+            Ok(Vec::new())
+        }
+
+        async fn health_check(&self) -> ExecutorResult<bool> {
+            Ok(true)
+        }
+    }
+
+    async fn engine() -> (WorkflowEngine, Arc<EchoExecutor>) {
+        let db = SqliteDatabase::new("sqlite::memory:").await.unwrap();
+        MigrationManager::run_migrations(&db).await.unwrap();
+        let executor = Arc::new(EchoExecutor::default());
+        (WorkflowEngine::new(executor.clone(), Arc::new(db)), executor)
+    }
+
+    fn definition() -> WorkflowDefinition {
+        serde_json::from_value(json!({
+            "id": "scores",
+            "name": "Score pipeline",
+            "steps": [
+                {"id": "load", "type": "tool", "tool_id": "echo", "parameters": {"value": [1, 2, 3, 4]}},
+                {"id": "check", "type": "if", "condition": "{{ gt(length(steps.load.output.value), 2) }}",
+                 "then": [
+                     {"id": "each", "type": "foreach", "items": "{{ steps.load.output.value }}", "tool_id": "echo",
+                      "parameters": {"value": "{{ item }}"}, "max_parallelism": 2},
+                     {"id": "total", "type": "reduce", "items": "{{ steps.each.output }}", "operation": "sum", "path": "value"}
+                 ],
+                 "else": [
+                     {"id": "skip", "type": "tool", "tool_id": "echo", "parameters": {"value": "short"}}
+                 ]}
+            ]
+        })).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_workflow_branches_and_aggregates() {
+        let (engine, executor) = engine().await;
+        let run = engine.start(&definition(), create_test_execution_request("echo").context).await.unwrap();
+
+        assert_eq!(run.status, WorkflowRunStatus::Completed);
+        let output = run.output.unwrap();
+        assert_eq!(output["check"], json!({"branch": "then"}));
+        assert_eq!(output["total"], json!(10));
+        assert!(output.get("skip").is_none());
+        assert_eq!(executor.calls.lock().unwrap().len(), 5);
+        // One row per step plus one per iteration
+        assert_eq!(run.steps.len(), 8);
+        assert!(run.steps.iter().filter(|s| s.step_id == "each").all(|s| s.status == StepRunStatus::Completed));
+    }
+
+    #[tokio::test]
+    async fn test_retry_reruns_only_failed_iterations() {
+        let (engine, executor) = engine().await;
+        executor.failing.lock().unwrap().extend([2, 4]);
+
+        let run = engine.start(&definition(), create_test_execution_request("echo").context).await.unwrap();
+        assert_eq!(run.status, WorkflowRunStatus::Failed);
+        assert!(run.error.as_deref().unwrap().contains("2 of 4 iterations failed"));
+        assert!(run.steps.iter().all(|s| s.step_id != "total"));
+
+        executor.failing.lock().unwrap().clear();
+        executor.calls.lock().unwrap().clear();
+        let retried = engine.retry(&run.id).await.unwrap();
+        assert_eq!(retried.status, WorkflowRunStatus::Completed);
+        assert_eq!(retried.output.unwrap()["total"], json!(10));
+
+        // Completed steps and iterations are not executed again
+        let mut calls = executor.calls.lock().unwrap().clone();
+        calls.sort_by_key(|v| v.as_i64());
+        assert_eq!(calls, vec![json!(2), json!(4)]);
+        let attempts = |iteration: usize| {
+            retried.steps.iter().find(|s| s.step_id == "each" && s.iteration == Some(iteration)).unwrap().attempts
+        };
+        assert_eq!((attempts(0), attempts(1)), (1, 2));
+
+        assert!(engine.retry(&run.id).await.is_err());
+        let invalid = WorkflowDefinition { id: "empty".to_string(), name: "Empty".to_string(), steps: vec![] };
+        assert!(matches!(invalid.validate(), Err(ExecutorError::InvalidParameters(_))));
+    }
+
+    #[derive(Default)]
+    struct RecordingNotifier {
+        events: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl ApprovalNotifier for RecordingNotifier {
+        async fn notify(&self, event: &ApprovalEvent) -> ExecutorResult<()> {
+            self.events.lock().unwrap().push(event.event.clone());
+            Ok(())
+        }
+    }
+
+    fn approval_definition(approval: Value) -> WorkflowDefinition {
+        serde_json::from_value(json!({
+            "id": "release",
+            "name": "Release",
+            "steps": [
+                {"id": "build", "type": "tool", "tool_id": "echo", "parameters": {"value": 1}},
+                approval,
+                {"id": "deploy", "type": "tool", "tool_id": "echo", "parameters": {"value": "{{ steps.gate.output.decision }}"}}
+            ]
+        })).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_approval_pauses_until_decided() {
+        let (engine, executor) = engine().await;
+        let notifier = Arc::new(RecordingNotifier::default());
+        let engine = engine.with_notifier(notifier.clone());
+        let definition = approval_definition(json!({
+            "id": "gate", "type": "approval", "title": "Deploy build {{ steps.build.output.value }}?",
+            "approvers": ["role:release-manager"]
+        }));
+
+        let run = engine.start(&definition, create_test_execution_request("echo").context).await.unwrap();
+        assert_eq!(run.status, WorkflowRunStatus::WaitingApproval);
+        let approvals = engine.list_approvals(&run.tenant_id, Some(ApprovalStatus::Pending)).await.unwrap();
+        assert_eq!(approvals.len(), 1);
+        assert_eq!(approvals[0].title, "Deploy build 1?");
+
+        let decision = |user: &str, role: &str| ApprovalDecision {
+            approved: true,
+            user_id: user.to_string(),
+            roles: vec![role.to_string()],
+            comment: Some("ship it".to_string()),
+        };
+        assert!(matches!(
+            engine.decide(&approvals[0].id, decision("intern", "user")).await,
+            Err(ExecutorError::PermissionDenied)
+        ));
+
+        let outcome = engine.decide(&approvals[0].id, decision("alice", "release-manager")).await.unwrap();
+        assert_eq!(outcome.approval.status, ApprovalStatus::Approved);
+        let run = outcome.resumed.await.unwrap().unwrap();
+        assert_eq!(run.status, WorkflowRunStatus::Completed);
+        assert_eq!(run.output.unwrap()["gate"]["decided_by"], json!("alice"));
+        assert_eq!(executor.calls.lock().unwrap().last(), Some(&json!("approved")));
+        assert_eq!(*notifier.events.lock().unwrap(), vec!["approval.requested", "approval.approved"]);
+        assert!(engine.decide(&approvals[0].id, decision("alice", "release-manager")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_expired_approval_takes_rejected_branch() {
+        let (engine, executor) = engine().await;
+        let definition = approval_definition(json!({
+            "id": "gate", "type": "approval", "title": "Deploy?", "timeout_seconds": 0,
+            "rejected": [{"id": "rollback", "type": "tool", "tool_id": "echo", "parameters": {"value": "rollback"}}]
+        }));
+
+        let run = engine.start(&definition, create_test_execution_request("echo").context).await.unwrap();
+        assert_eq!(run.status, WorkflowRunStatus::WaitingApproval);
+
+        // Listing is read-only; the sweep expires overdue approvals and resumes their runs
+        let approvals = engine.list_approvals(&run.tenant_id, None).await.unwrap();
+        assert_eq!(approvals[0].status, ApprovalStatus::Pending);
+        assert_eq!(engine.sweep_expired_approvals().await.unwrap(), 1);
+        let approvals = engine.list_approvals(&run.tenant_id, None).await.unwrap();
+        assert_eq!(approvals[0].status, ApprovalStatus::Expired);
+        let run = engine.get_run(&run.id).await.unwrap().unwrap();
+        assert_eq!(run.status, WorkflowRunStatus::Completed);
+        assert_eq!(
+            *executor.calls.lock().unwrap(),
+            vec![json!(1), json!("rollback"), json!("expired")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_approval_sweep_is_scoped_per_tenant() {
+        let db = Arc::new(SqliteDatabase::new("sqlite::memory:").await.unwrap());
+        MigrationManager::run_migrations(&db).await.unwrap();
+        let engine = WorkflowEngine::new(Arc::new(EchoExecutor::default()), db.clone());
+        let definition = approval_definition(json!({
+            "id": "gate", "type": "approval", "title": "Deploy?", "timeout_seconds": 0
+        }));
+        let context = create_test_execution_request("echo").context;
+        let other = ExecutionContext { tenant_id: "other-tenant".to_string(), ..context.clone() };
+
+        let broken = engine.start(&definition, context.clone()).await.unwrap();
+        let healthy = engine.start(&definition, context.clone()).await.unwrap();
+        let foreign = engine.start(&definition, other.clone()).await.unwrap();
+
+        assert_eq!(engine.expire_approvals(&other.tenant_id).await.unwrap(), 1);
+        assert_eq!(engine.get_run(&foreign.id).await.unwrap().unwrap().status, WorkflowRunStatus::Failed);
+        let pending = engine.list_approvals(&context.tenant_id, Some(ApprovalStatus::Pending)).await.unwrap();
+        assert_eq!(pending.len(), 2);
+
+        // A run that cannot resume does not hold up the others
+        db.execute(
+            "UPDATE workflow_runs SET definition = 'not json' WHERE id = ?",
+            &[Value::String(broken.id.clone())],
+        ).await.unwrap();
+        assert_eq!(engine.sweep_expired_approvals().await.unwrap(), 2);
+        assert_eq!(engine.get_run(&healthy.id).await.unwrap().unwrap().status, WorkflowRunStatus::Failed);
+        assert!(engine.list_approvals(&context.tenant_id, Some(ApprovalStatus::Pending)).await.unwrap().is_empty());
+        assert_eq!(engine.sweep_expired_approvals().await.unwrap(), 0);
+    }
+
+    fn gated(deploy: Value) -> WorkflowDefinition {
+        let mut definition = approval_definition(json!({"id": "gate", "type": "approval", "title": "Deploy?"}));
+        definition.steps[2].kind = StepKind::Tool {
+            tool_id: ToolId::from_string("echo".to_string()),
+            parameters: HashMap::from([("value".to_string(), deploy)]),
+        };
+        definition
+    }
+
+    fn admin_approval() -> ApprovalDecision {
+        ApprovalDecision {
+            approved: true,
+            user_id: "admin".to_string(),
+            roles: vec!["admin".to_string()],
+            comment: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_breaking_version_migrates_waiting_runs() {
+        let (engine, executor) = engine().await;
+        let context = create_test_execution_request("echo").context;
+        let tenant_id = context.tenant_id.clone();
+        assert!(engine.start_latest("release", context.clone()).await.is_err());
+
+        engine.publish(&tenant_id, &gated(json!("v1")), false, Some("admin")).await.unwrap();
+        let drained = engine.start_latest("release", context.clone()).await.unwrap();
+        let migrated = engine.start_latest("release", context.clone()).await.unwrap();
+        assert_eq!((drained.version, drained.status), (Some(1), WorkflowRunStatus::WaitingApproval));
+
+        // Non-breaking versions leave in-flight runs alone whatever the policy
+        engine.set_migration_policy(&tenant_id, "release", MigrationPolicy::Migrate).await.unwrap();
+        let result = engine.publish(&tenant_id, &gated(json!("v2")), false, None).await.unwrap();
+        assert!(result.affected_runs.is_empty());
+
+        let result = engine.publish(&tenant_id, &gated(json!("v3")), true, None).await.unwrap();
+        assert_eq!(result.version.version, 3);
+        assert_eq!(result.policy, MigrationPolicy::Migrate);
+        assert_eq!(result.affected_runs.len(), 2);
+        assert_eq!(engine.list_versions(&tenant_id, "release").await.unwrap().len(), 3);
+
+        let approvals = engine.list_approvals(&tenant_id, Some(ApprovalStatus::Pending)).await.unwrap();
+        let approval = |run_id: &str| approvals.iter().find(|a| a.run_id == run_id).unwrap().id.clone();
+        let run = engine.decide(&approval(&migrated.id), admin_approval()).await.unwrap().resumed.await.unwrap().unwrap();
+        assert_eq!((run.version, run.status), (Some(3), WorkflowRunStatus::Completed));
+        assert_eq!(executor.calls.lock().unwrap().last(), Some(&json!("v3")));
+
+        // Runs whose completed steps no longer fit stay on their version
+        engine.publish(&tenant_id, &WorkflowDefinition {
+            steps: gated(json!("v4")).steps.into_iter().filter(|s| s.id != "build").collect(),
+            ..gated(json!("v4"))
+        }, true, None).await.unwrap();
+        let run = engine.decide(&approval(&drained.id), admin_approval()).await.unwrap().resumed.await.unwrap().unwrap();
+        assert_eq!((run.version, run.status), (Some(1), WorkflowRunStatus::Completed));
+        assert_eq!(executor.calls.lock().unwrap().last(), Some(&json!("v1")));
+
+        let latest = engine.start_latest("release", context).await.unwrap();
+        assert_eq!(latest.version, Some(4));
+    }
+
+    #[tokio::test]
+    async fn test_breaking_version_drains_in_flight_runs() {
+        let (engine, executor) = engine().await;
+        let context = create_test_execution_request("echo").context;
+        let tenant_id = context.tenant_id.clone();
+
+        engine.publish(&tenant_id, &gated(json!("v1")), false, None).await.unwrap();
+        let waiting = engine.start_latest("release", context.clone()).await.unwrap();
+        // Non-breaking versions start right away
+        engine.publish(&tenant_id, &gated(json!("v2")), false, None).await.unwrap();
+        let second = engine.start_latest("release", context.clone()).await.unwrap();
+        assert_eq!(second.version, Some(2));
+
+        let result = engine.publish(&tenant_id, &gated(json!("v3")), true, None).await.unwrap();
+        assert_eq!(result.policy, MigrationPolicy::Drain);
+        assert_eq!(result.affected_runs.len(), 2);
+        assert!(matches!(
+            engine.start_latest("release", context.clone()).await,
+            Err(ExecutorError::Conflict(message)) if message.contains("version 1")
+        ));
+
+        // In-flight runs finish on their own version, then the breaking version takes new runs
+        let approvals = engine.list_approvals(&tenant_id, Some(ApprovalStatus::Pending)).await.unwrap();
+        for approval in &approvals {
+            engine.decide(&approval.id, admin_approval()).await.unwrap().resumed.await.unwrap().unwrap();
+        }
+        let waiting = engine.get_run(&waiting.id).await.unwrap().unwrap();
+        assert_eq!((waiting.version, waiting.status), (Some(1), WorkflowRunStatus::Completed));
+        assert!(executor.calls.lock().unwrap().contains(&json!("v1")));
+        let latest = engine.start_latest("release", context).await.unwrap();
+        assert_eq!(latest.version, Some(3));
+    }
+
+    #[tokio::test]
+    async fn test_running_run_migrates_before_next_step() {
+        let (engine, executor) = engine().await;
+        let engine = Arc::new(engine);
+        let context = create_test_execution_request("echo").context;
+        let tenant_id = context.tenant_id.clone();
+        let held = |deploy: &str| {
+            let mut definition = gated(json!(deploy));
+            definition.steps.remove(1);
+            definition.steps[0] = serde_json::from_value(json!(
+                {"id": "build", "type": "tool", "tool_id": "echo", "parameters": {"value": "hold"}}
+            )).unwrap();
+            definition
+        };
+
+        engine.publish(&tenant_id, &held("v1"), false, None).await.unwrap();
+        engine.set_migration_policy(&tenant_id, "release", MigrationPolicy::Migrate).await.unwrap();
+        let running = tokio::spawn({
+            let engine = engine.clone();
+            async move { engine.start_latest("release", context).await }
+        });
+
+        executor.held.notified().await;
+        let result = engine.publish(&tenant_id, &held("v2"), true, None).await.unwrap();
+        assert_eq!(result.affected_runs.len(), 1);
+        executor.release.notify_one();
+
+        let run = running.await.unwrap().unwrap();
+        assert_eq!((run.version, run.status), (Some(2), WorkflowRunStatus::Completed));
+        assert_eq!(*executor.calls.lock().unwrap(), vec![json!("hold"), json!("v2")]);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_publishes_get_distinct_versions() {
+        let (engine, _) = engine().await;
+        let tenant_id = create_test_execution_request("echo").context.tenant_id;
+        let definition = gated(json!("v"));
+
+        let results = futures::future::join_all(
+            (0..8).map(|_| engine.publish(&tenant_id, &definition, false, None))
+        ).await;
+        let mut versions: Vec<u32> = results.into_iter().map(|result| result.unwrap().version.version).collect();
+        versions.sort();
+        assert_eq!(versions, (1..=8).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_breaking_version_cancels_in_flight_runs() {
+        let (engine, executor) = engine().await;
+        let notifier = Arc::new(RecordingNotifier::default());
+        let engine = engine.with_notifier(notifier.clone());
+        let context = create_test_execution_request("echo").context;
+        let tenant_id = context.tenant_id.clone();
+
+        engine.publish(&tenant_id, &gated(json!("v1")), false, None).await.unwrap();
+        engine.set_migration_policy(&tenant_id, "release", MigrationPolicy::Cancel).await.unwrap();
+        assert_eq!(engine.migration_policy(&tenant_id, "release").await.unwrap(), MigrationPolicy::Cancel);
+        let run = engine.start_latest("release", context).await.unwrap();
+
+        let result = engine.publish(&tenant_id, &gated(json!("v2")), true, None).await.unwrap();
+        assert_eq!(result.affected_runs, vec![run.id.clone()]);
+
+        let cancelled = engine.get_run(&run.id).await.unwrap().unwrap();
+        assert_eq!(cancelled.status, WorkflowRunStatus::Cancelled);
+        assert_eq!(cancelled.error.as_deref(), Some("Cancelled by breaking version 2"));
+        let approvals = engine.list_approvals(&tenant_id, None).await.unwrap();
+        assert_eq!(approvals[0].status, ApprovalStatus::Cancelled);
+        assert!(engine.decide(&approvals[0].id, admin_approval()).await.is_err());
+        assert!(engine.cancel(&run.id).await.is_err());
+        assert_eq!(*notifier.events.lock().unwrap(), vec!["approval.requested", "approval.cancelled"]);
+        assert_eq!(*executor.calls.lock().unwrap(), vec![json!(1)]);
+    }
+
+    #[tokio::test]
+    async fn test_validation_reports_tools_and_references() {
+        let validator = WorkflowValidator::new(setup_in_memory_registry().await);
+        let definition: WorkflowDefinition = serde_json::from_value(json!({
+            "id": "broken",
+            "name": "Broken",
+            "steps": [
+                {"id": "a", "type": "tool", "tool_id": "missing-tool"},
+                {"id": "b", "type": "tool", "tool_id": "test-tool-1", "parameters": {"input": "{{ steps.c.output }}"}},
+                {"id": "c", "type": "if", "condition": "{{ steps.c.output.branch }}",
+                 "then": [{"id": "d", "type": "tool", "tool_id": "test-tool-2"}],
+                 "else": [{"id": "e", "type": "reduce", "items": "{{ steps.d.output }}", "operation": "count"}]},
+                {"id": "f", "type": "tool", "tool_id": "test-tool-2", "parameters": {"x": "{{ steps.d.output }}", "y": "{{ oops( }}"}}
+            ]
+        })).unwrap();
+
+        let issues = validator.validate(&definition).await;
+        let find = |step: &str, text: &str| {
+            issues.iter().find(|issue| issue.step_id.as_deref() == Some(step) && issue.message.contains(text))
+        };
+        assert_eq!(find("a", "not found").unwrap().severity, IssueSeverity::Error);
+        assert!(find("b", "runs after it (cycle)").is_some());
+        assert!(find("c", "its own output (cycle)").is_some());
+        assert!(find("e", "on another branch").is_some());
+        assert_eq!(find("f", "only runs on some branches").unwrap().severity, IssueSeverity::Warning);
+        assert!(find("f", "expected").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_builds_plan_with_mock_outputs() {
+        let validator = WorkflowValidator::new(setup_in_memory_registry().await);
+        let definition: WorkflowDefinition = serde_json::from_value(json!({
+            "id": "plan",
+            "name": "Plan",
+            "steps": [
+                {"id": "load", "type": "tool", "tool_id": "test-tool-1", "parameters": {"input": "{{ context.user_id }}"}},
+                {"id": "each", "type": "foreach", "items": "{{ steps.load.output.items }}", "tool_id": "test-tool-1",
+                 "parameters": {"input": "{{ item }}"}},
+                {"id": "check", "type": "if", "condition": "{{ gt(length(steps.each.output), 2) }}",
+                 "then": [{"id": "total", "type": "reduce", "items": "{{ steps.each.output }}", "operation": "count"}],
+                 "else": [{"id": "skip", "type": "tool", "tool_id": "test-tool-2"}]}
+            ]
+        })).unwrap();
+
+        let options = DryRunOptions {
+            mock_outputs: HashMap::from([("load".to_string(), json!({"items": ["x", "y", 3]}))]),
+            context: Some(create_test_execution_context()),
+            ..Default::default()
+        };
+        let report = validator.dry_run(&definition, &options).await;
+
+        let steps: Vec<&str> = report.plan.iter().map(|step| step.step_id.as_str()).collect();
+        assert_eq!(steps, vec!["load", "each", "check", "total"]);
+        assert_eq!(report.plan[0].parameters[0]["input"], json!(create_test_execution_context().user_id));
+        assert_eq!(report.plan[1].parameters.len(), 3);
+        assert_eq!(report.plan[1].depends_on, vec!["load"]);
+        assert_eq!(report.plan[2].branch.as_deref(), Some("then"));
+        assert_eq!(report.plan[3].output, json!(3));
+
+        // The third item is not a string, unlike the tool's example input
+        assert!(!report.valid);
+        assert_eq!(report.issues.len(), 1);
+        assert!(report.issues[0].message.contains("'input' is integer, the tool expects string"));
+    }
+}
+
 #[cfg(test)]
 mod scheduler_tests {
     use super::*;
 
     #[tokio::test]
     async fn test_scheduler_task_scheduling() {
-        let db = setup_test_database().await;
-        let registry = setup_test_registry(db.clone()).await;
+        let registry = setup_in_memory_registry().await;
         
         let worker_pool = std::sync::Arc::new(WorkerPoolImpl::new(
             registry.clone(),
@@ -167,7 +1866,7 @@ mod scheduler_tests {
         ));
         
         let scheduler = SchedulerImpl::new(
-            db,
+            std::sync::Arc::new(InMemoryExecutionStore::new()),
             worker_pool,
             SchedulerConfig::default(),
         );
@@ -186,8 +1885,7 @@ mod scheduler_tests {
 
     #[tokio::test]
     async fn test_scheduler_queue_status() {
-        let db = setup_test_database().await;
-        let registry = setup_test_registry(db.clone()).await;
+        let registry = setup_in_memory_registry().await;
         
         let worker_pool = std::sync::Arc::new(WorkerPoolImpl::new(
             registry.clone(),
@@ -195,7 +1893,7 @@ mod scheduler_tests {
         ));
         
         let scheduler = SchedulerImpl::new(
-            db,
+            std::sync::Arc::new(InMemoryExecutionStore::new()),
             worker_pool,
             SchedulerConfig::default(),
         );
@@ -210,8 +1908,7 @@ mod scheduler_tests {
 
     #[tokio::test]
     async fn test_scheduler_priority_ordering() {
-        let db = setup_test_database().await;
-        let registry = setup_test_registry(db.clone()).await;
+        let registry = setup_in_memory_registry().await;
         
         let worker_pool = std::sync::Arc::new(WorkerPoolImpl::new(
             registry.clone(),
@@ -219,7 +1916,7 @@ mod scheduler_tests {
         ));
         
         let scheduler = SchedulerImpl::new(
-            db,
+            std::sync::Arc::new(InMemoryExecutionStore::new()),
             worker_pool,
             SchedulerConfig::default(),
         );
@@ -262,8 +1959,7 @@ mod worker_pool_tests {
 
     #[tokio::test]
     async fn test_worker_pool_creation() {
-        let db = setup_test_database().await;
-        let registry = setup_test_registry(db.clone()).await;
+        let registry = setup_in_memory_registry().await;
         
         let worker_pool = WorkerPoolImpl::new(
             registry,
@@ -276,8 +1972,7 @@ mod worker_pool_tests {
 
     #[tokio::test]
     async fn test_worker_pool_work_submission() {
-        let db = setup_test_database().await;
-        let registry = setup_test_registry(db.clone()).await;
+        let registry = setup_in_memory_registry().await;
         
         let worker_pool = WorkerPoolImpl::new(
             registry,
@@ -303,8 +1998,7 @@ mod worker_pool_tests {
 
     #[tokio::test]
     async fn test_worker_pool_status() {
-        let db = setup_test_database().await;
-        let registry = setup_test_registry(db.clone()).await;
+        let registry = setup_in_memory_registry().await;
         
         let worker_pool = WorkerPoolImpl::new(
             registry,
@@ -322,8 +2016,7 @@ mod worker_pool_tests {
 
     #[tokio::test]
     async fn test_worker_pool_scaling() {
-        let db = setup_test_database().await;
-        let registry = setup_test_registry(db.clone()).await;
+        let registry = setup_in_memory_registry().await;
         
         let worker_pool = WorkerPoolImpl::new(
             registry,
@@ -393,6 +2086,7 @@ mod result_manager_tests {
             status: Some(ExecutionStatus::Completed),
             started_after: None,
             started_before: None,
+            labels: std::collections::HashMap::new(),
         };
 
         let results = result_manager.list_results(Some(filter)).await;
@@ -421,6 +2115,99 @@ mod result_manager_tests {
         let cleanup_result = result_manager.cleanup_results(older_than).await;
         assert!(cleanup_result.is_ok(), "Should cleanup results successfully");
     }
+
+    #[tokio::test]
+    async fn test_large_output_is_stored_in_chunks() {
+        use futures::StreamExt;
+        use stepflow_core::Database;
+
+        let db = setup_test_database().await;
+        let result_manager = ResultManagerImpl::new(db.clone()).with_chunk_size(64);
+        let execution_id = ExecutionId::new();
+        let output = serde_json::json!({"rows": (0..100).map(|i| format!("row-{}-é", i)).collect::<Vec<_>>()});
+        let execution_result = stepflow_core::ExecutionResult {
+            success: true,
+            output: Some(output.clone()),
+            error: None,
+            logs: vec![],
+            metrics: std::collections::HashMap::new(),
+            metadata: std::collections::HashMap::from([
+                ("execution_id".to_string(), serde_json::Value::String(execution_id.to_string())),
+            ]),
+        };
+        result_manager.store_result(execution_result).await.unwrap();
+
+        let stored = db.execute(
+            "SELECT output_data, output_chunks FROM execution_results WHERE execution_id = ?",
+            &[serde_json::Value::String(execution_id.to_string())],
+        ).await.unwrap();
+        assert!(stored.rows[0]["output_data"].is_null());
+
+        // Reading streams the chunks back in order, across several pages
+        let chunks: Vec<String> = result_manager.stream_output(&execution_id).await.unwrap()
+            .map(|chunk| chunk.unwrap())
+            .collect()
+            .await;
+        assert!(chunks.len() > 16);
+        assert!(chunks.iter().all(|chunk| chunk.len() <= 64));
+        assert_eq!(stored.rows[0]["output_chunks"].as_i64(), Some(chunks.len() as i64));
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&chunks.concat()).unwrap(), output);
+
+        // A fresh manager has no cache and reassembles from the database
+        let uncached = ResultManagerImpl::new(db.clone());
+        assert_eq!(uncached.get_result(&execution_id).await.unwrap().output, Some(output));
+
+        uncached.delete_result(&execution_id).await.unwrap();
+        let remaining = db.execute(
+            "SELECT chunk_index FROM result_chunks WHERE execution_id = ?",
+            &[serde_json::Value::String(execution_id.to_string())],
+        ).await.unwrap();
+        assert!(remaining.rows.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_output_truncated_at_tenant_limit() {
+        let store = std::sync::Arc::new(InMemoryExecutionStore::new());
+        store.insert_tenant(TenantInfo {
+            id: TenantId::from_string("test-tenant-456".to_string()),
+            name: "Test Tenant".to_string(),
+            description: String::new(),
+            domain: None,
+            settings: std::collections::HashMap::from([
+                (TENANT_MAX_OUTPUT_SETTING.to_string(), serde_json::json!(16)),
+            ]),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }).await;
+        let executor = create_executor_with_backends(
+            store,
+            std::sync::Arc::new(InMemoryResultManager::new()),
+            std::sync::Arc::new(InMemoryMonitoring::new()),
+            setup_in_memory_registry().await,
+            None,
+            None,
+        ).unwrap();
+
+        let result = executor.execute_tool(create_test_execution_request("test-tool-1")).await.unwrap();
+        let output = result.output.unwrap();
+        assert_eq!(output["truncated"], true);
+        assert_eq!(output["preview"].as_str().unwrap().len(), 16);
+        assert!(output["original_bytes"].as_u64().unwrap() > 16);
+        assert_eq!(result.metadata[OUTPUT_TRUNCATED_METADATA], true);
+
+        // Outputs within the default limit are kept as they are
+        let mut untouched = stepflow_core::ExecutionResult {
+            success: true,
+            output: Some(serde_json::json!({"result": "test"})),
+            error: None,
+            logs: vec![],
+            metrics: std::collections::HashMap::new(),
+            metadata: std::collections::HashMap::new(),
+        };
+        let limits = OutputLimits::default();
+        assert!(!OutputLimits::truncate(&mut untouched, limits.max_output_for(None)).unwrap());
+        assert!(!untouched.metadata.contains_key(OUTPUT_TRUNCATED_METADATA));
+    }
 }
 
 #[cfg(test)]
@@ -510,6 +2297,41 @@ mod execution_context_tests {
         assert_eq!(request.options.priority, Priority::Normal);
     }
 
+    #[test]
+    fn test_execution_request_builder_validation() {
+        let request = ExecutionRequest::builder()
+            .tool_id(stepflow_core::ToolId::from_string("echo".to_string()))
+            .parameter("text", serde_json::json!("hi"))
+            .context(ExecutionContext::new("user-1", "tenant-1"))
+            .build()
+            .unwrap();
+        assert_eq!(request.parameters["text"], "hi");
+        assert_eq!(request.options.retry_count, ExecutionOptions::default().retry_count);
+        assert_ne!(request.context.session_id, request.context.request_id);
+
+        let missing_context = ExecutionRequest::builder()
+            .tool_id(stepflow_core::ToolId::from_string("echo".to_string()))
+            .build();
+        assert!(matches!(missing_context, Err(stepflow_core::ValidationError::RequiredFieldMissing(field)) if field == "context"));
+        assert!(ExecutionRequest::builder()
+            .context(ExecutionContext::new("user-1", "tenant-1"))
+            .build()
+            .is_err());
+        assert!(ExecutionRequest::builder()
+            .tool_id(stepflow_core::ToolId::from_string("echo".to_string()))
+            .context(ExecutionContext::new("", "tenant-1"))
+            .build()
+            .is_err());
+        let mut options = ExecutionOptions::default();
+        options.timeout = Some(Duration::ZERO);
+        assert!(ExecutionRequest::builder()
+            .tool_id(stepflow_core::ToolId::from_string("echo".to_string()))
+            .context(ExecutionContext::new("user-1", "tenant-1"))
+            .options(options)
+            .build()
+            .is_err());
+    }
+
     #[test]
     fn test_execution_context_creation() {
         let context = create_test_execution_context();
@@ -585,6 +2407,113 @@ mod execution_context_tests {
 }
 
 #[cfg(test)]
+mod report_tests {
+    use super::*;
+    use std::collections::HashMap;
+    use chrono::{Duration as ChronoDuration, Utc};
+    use stepflow_core::{ExecutionResult, LogEntry};
+    use serde_json::json;
+
+    fn finished_execution() -> (ExecutionTimeline, ExecutionResult) {
+        let start = Utc::now() - ChronoDuration::seconds(10);
+        let event = |state, offset_ms| ExecutionEvent {
+            state,
+            worker_id: None,
+            detail: None,
+            occurred_at: start + ChronoDuration::milliseconds(offset_ms),
+        };
+        let timeline = ExecutionTimeline::from_events(
+            ExecutionId::from_string("exec-report".to_string()),
+            Some("acme".to_string()),
+            vec![
+                event(ExecutionState::Queued, 0),
+                event(ExecutionState::Running, 250),
+                event(ExecutionState::Completed, 1_750),
+            ],
+        );
+        let logs = (0..REPORT_LOG_LIMIT + 5)
+            .map(|i| LogEntry {
+                level: LogLevel::Info,
+                message: format!("line {}", i),
+                timestamp: start + ChronoDuration::milliseconds(300 + i as i64),
+                source: "stdout".to_string(),
+                metadata: HashMap::new(),
+            })
+            .collect();
+        let result = ExecutionResult {
+            success: true,
+            output: Some(json!({ "rows": 3, "session_token": "abc123" })),
+            error: None,
+            logs,
+            metrics: HashMap::from([("cpu_seconds".to_string(), 1.25)]),
+            metadata: HashMap::from([
+                ("tool_id".to_string(), json!("csv-import")),
+                (PARAMETERS_METADATA.to_string(), json!({ "path": "/data/in.csv", "db": { "password": "hunter2" } })),
+            ]),
+        };
+        (timeline, result)
+    }
+
+    #[test]
+    fn test_redact_masks_sensitive_keys_at_any_depth() {
+        let value = json!({
+            "user": "alice",
+            "API_KEY": "k-1",
+            "nested": [{ "Authorization": "Bearer x", "limit": 5 }],
+            "refresh_token": null
+        });
+        let redacted = redact(&value);
+        assert_eq!(redacted["user"], "alice");
+        assert_eq!(redacted["API_KEY"], "********");
+        assert_eq!(redacted["nested"][0]["Authorization"], "********");
+        assert_eq!(redacted["nested"][0]["limit"], 5);
+        assert!(redacted["refresh_token"].is_null());
+    }
+
+    #[test]
+    fn test_execution_report() {
+        let (timeline, result) = finished_execution();
+        let report = RunReport::for_execution(&timeline, Some(&result));
+
+        assert_eq!(report.subject, ReportSubject::Execution);
+        assert_eq!(report.status, "completed");
+        assert_eq!(report.phases.len(), 2);
+        assert_eq!(report.phases[0].name, "queued");
+        assert_eq!(report.phases[0].duration_ms(), Some(250));
+        assert_eq!(report.phases[1].duration_ms(), Some(1_500));
+        assert_eq!(report.inputs.as_ref().unwrap()["db"]["password"], "********");
+        assert_eq!(report.output.as_ref().unwrap()["session_token"], "********");
+        assert_eq!(report.logs.len(), REPORT_LOG_LIMIT);
+        assert_eq!(report.omitted_logs, 5);
+        assert_eq!(report.logs.last().unwrap().message, format!("line {}", REPORT_LOG_LIMIT + 4));
+
+        let html = report.to_html();
+        assert!(html.contains("csv-import"));
+        assert!(html.contains("cpu_seconds"));
+        assert!(!html.contains("hunter2"));
+        assert!(!html.contains("abc123"));
+
+        let pdf = report.to_pdf();
+        assert!(pdf.starts_with(b"%PDF-1.4"));
+        assert!(pdf.ends_with(b"%%EOF\n"));
+        assert!(!String::from_utf8_lossy(&pdf).contains("hunter2"));
+    }
+
+    #[test]
+    fn test_report_of_unfinished_execution() {
+        let (timeline, _) = finished_execution();
+        let timeline = ExecutionTimeline::from_events(timeline.execution_id, None, timeline.events[..2].to_vec());
+        let report = RunReport::for_execution(&timeline, None);
+
+        assert_eq!(report.status, "running");
+        assert!(report.finished_at.is_none());
+        assert_eq!(report.phases[1].status, "in progress");
+        assert!(report.inputs.is_none());
+        assert!(report.to_html().contains("Not recorded"));
+        assert!(report.to_pdf().starts_with(b"%PDF"));
+    }
+}
+
 mod error_handling_tests {
     use super::*;
 
@@ -626,6 +2555,74 @@ mod error_handling_tests {
         let stepflow_error = stepflow_core::StepflowError::ToolNotFound("test".to_string());
         let executor_error: ExecutorError = stepflow_error.into();
         
-        assert!(matches!(executor_error, ExecutorError::InternalError(_)));
+        assert!(matches!(executor_error, ExecutorError::Core(_)));
+        assert_eq!(executor_error.code(), stepflow_core::ErrorCode::ToolNotFound);
+        assert!(!executor_error.retryable());
+
+        let registry_error = stepflow_registry::RegistryError::from(stepflow_core::StepflowError::from(
+            stepflow_core::DatabaseError::PoolExhausted,
+        ));
+        let executor_error: ExecutorError = registry_error.into();
+        assert!(std::error::Error::source(&executor_error).is_some());
+        assert_eq!(executor_error.code(), stepflow_core::ErrorCode::ResourceExhausted);
+        assert!(executor_error.retryable());
+        assert!(ExecutorError::TimeoutExceeded.retryable());
+        assert!(!ExecutorError::InternalError("bug".to_string()).user_facing());
     }
-} 
\ No newline at end of file
+} 
+#[cfg(all(test, feature = "fault-injection"))]
+mod fault_injection_tests {
+    use super::*;
+    use stepflow_core::{FaultConfig, FaultController, FaultTarget};
+
+    /// Submit work and wait until it completes or fails
+    async fn run_work(pool: &WorkerPoolImpl) -> WorkStatus {
+        let work = Work {
+            id: WorkId::new(),
+            task: Task {
+                id: TaskId::new(),
+                execution_request: create_test_execution_request("test-tool-1"),
+                priority: Priority::Normal,
+                created_at: chrono::Utc::now(),
+                scheduled_at: None,
+            },
+            assigned_worker: None,
+            started_at: None,
+        };
+        let work_id = pool.submit_work(work).await.unwrap();
+        let finished = || {
+            let (pool, work_id) = (pool.clone(), work_id.clone());
+            async move {
+                pool.get_work_status(&work_id).await.is_ok_and(|s| matches!(s, WorkStatus::Completed | WorkStatus::Failed))
+            }
+        };
+        assert!(wait_for_condition(finished, Duration::from_secs(5), Duration::from_millis(20)).await);
+        pool.get_work_status(&work_id).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_worker_pool_faults_fail_work_until_cleared() {
+        let faults = FaultController::new(5);
+        let pool = WorkerPoolImpl::new(setup_in_memory_registry().await, WorkerPoolConfig {
+            min_workers: 1,
+            enable_auto_scaling: false,
+            ..WorkerPoolConfig::default()
+        })
+        .with_fault_controller(faults.clone());
+        pool.start().await.unwrap();
+
+        // Failing before the work runs and losing the result after it ran both fail the work
+        faults.set(FaultTarget::WorkerPool, FaultConfig::always_fail());
+        assert_eq!(run_work(&pool).await, WorkStatus::Failed);
+        faults.set(FaultTarget::WorkerPool, FaultConfig::partial_writes(1.0));
+        assert_eq!(run_work(&pool).await, WorkStatus::Failed);
+
+        // A retry after the fault clears completes, and the pool keeps serving
+        faults.clear(FaultTarget::WorkerPool);
+        assert_eq!(run_work(&pool).await, WorkStatus::Completed);
+        let stats = faults.stats(FaultTarget::WorkerPool);
+        assert_eq!((stats.calls, stats.errors, stats.partial_writes), (3, 1, 1));
+        assert_eq!(pool.get_pool_status().await.unwrap().completed_work, 1);
+        pool.stop().await.unwrap();
+    }
+}
@@ -0,0 +1,696 @@
+//! SLA monitoring and alerting
+//!
+//! Tenant admins define [`AlertRule`]s over execution metrics (e.g. "failure
+//! rate of tool X > 10% over 15m" or "queue wait p95 > 30s"). Rules belong to
+//! one tenant and only see that tenant's executions. The [`AlertManager`]
+//! evaluates every enabled rule periodically against the `execution_events`
+//! table, keeps at most one firing alert per rule and notifies once when an
+//! alert fires and once when it resolves. A rule that fails to evaluate is
+//! logged and skipped; it does not hold up the others.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use stepflow_core::{CloudEvent, Database, EventData, MonitoringError, TenantId, ToolId, CLOUDEVENTS_CONTENT_TYPE};
+use stepflow_database::SqliteDatabase;
+use stepflow_rpc::EventPublisher;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+/// Result type for alerting operations
+pub type AlertResult<T> = Result<T, MonitoringError>;
+
+/// Metric an alert rule is evaluated against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertMetric {
+    /// Share of finished executions that failed or timed out (0.0 - 1.0)
+    FailureRate,
+    /// 95th percentile of queued → running latency, in milliseconds
+    QueueWaitP95Ms,
+    /// 95th percentile of running → finished duration, in milliseconds
+    RunDurationP95Ms,
+    /// Number of finished executions
+    ExecutionCount,
+}
+
+impl AlertMetric {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertMetric::FailureRate => "failure_rate",
+            AlertMetric::QueueWaitP95Ms => "queue_wait_p95_ms",
+            AlertMetric::RunDurationP95Ms => "run_duration_p95_ms",
+            AlertMetric::ExecutionCount => "execution_count",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "failure_rate" => Some(AlertMetric::FailureRate),
+            "queue_wait_p95_ms" => Some(AlertMetric::QueueWaitP95Ms),
+            "run_duration_p95_ms" => Some(AlertMetric::RunDurationP95Ms),
+            "execution_count" => Some(AlertMetric::ExecutionCount),
+            _ => None,
+        }
+    }
+}
+
+/// How the metric value is compared against the threshold
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Comparison {
+    GreaterThan,
+    LessThan,
+}
+
+impl Comparison {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Comparison::GreaterThan => "greater_than",
+            Comparison::LessThan => "less_than",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "greater_than" => Some(Comparison::GreaterThan),
+            "less_than" => Some(Comparison::LessThan),
+            _ => None,
+        }
+    }
+
+    /// Whether `value` breaches `threshold`
+    pub fn breached(&self, value: f64, threshold: f64) -> bool {
+        match self {
+            Comparison::GreaterThan => value > threshold,
+            Comparison::LessThan => value < threshold,
+        }
+    }
+}
+
+/// Alerting rule defined by an admin
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertRule {
+    pub id: String,
+    /// Tenant owning the rule; only its executions are sampled
+    pub tenant_id: TenantId,
+    pub name: String,
+    pub metric: AlertMetric,
+    /// Restrict the rule to one tool; `None` covers all executions
+    pub tool_id: Option<ToolId>,
+    pub comparison: Comparison,
+    pub threshold: f64,
+    pub window_secs: u64,
+    /// Minimum number of samples in the window before the rule can fire
+    pub min_samples: u64,
+    /// Webhook notified in addition to the manager-wide notifiers
+    pub webhook_url: Option<String>,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl AlertRule {
+    /// Create a rule with a fresh id
+    pub fn new(
+        tenant_id: TenantId,
+        name: String,
+        metric: AlertMetric,
+        comparison: Comparison,
+        threshold: f64,
+        window: Duration,
+    ) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            tenant_id,
+            name,
+            metric,
+            tool_id: None,
+            comparison,
+            threshold,
+            window_secs: window.as_secs().max(1),
+            min_samples: 1,
+            webhook_url: None,
+            enabled: true,
+            created_at: Utc::now(),
+        }
+    }
+
+    pub fn for_tool(mut self, tool_id: ToolId) -> Self {
+        self.tool_id = Some(tool_id);
+        self
+    }
+
+    pub fn with_min_samples(mut self, min_samples: u64) -> Self {
+        self.min_samples = min_samples.max(1);
+        self
+    }
+
+    pub fn with_webhook(mut self, url: String) -> Self {
+        self.webhook_url = Some(url);
+        self
+    }
+}
+
+/// Alert lifecycle status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertStatus {
+    Firing,
+    Resolved,
+}
+
+impl AlertStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertStatus::Firing => "firing",
+            AlertStatus::Resolved => "resolved",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "firing" => Some(AlertStatus::Firing),
+            "resolved" => Some(AlertStatus::Resolved),
+            _ => None,
+        }
+    }
+}
+
+/// An alert raised by a rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Alert {
+    pub id: String,
+    pub rule_id: String,
+    pub status: AlertStatus,
+    /// Metric value when the alert fired
+    pub value: f64,
+    pub threshold: f64,
+    pub fired_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+/// Payload delivered to notifiers when an alert fires or resolves
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertNotification {
+    pub status: AlertStatus,
+    pub rule: AlertRule,
+    pub alert: Alert,
+    /// Metric value at evaluation time
+    pub current_value: f64,
+}
+
+impl AlertNotification {
+    /// Event name used on the event bus, e.g. `alert.firing`
+    pub fn event_name(&self) -> String {
+        format!("alert.{}", self.status.as_str())
+    }
+}
+
+impl EventData for AlertNotification {
+    const SOURCE: &'static str = "/stepflow/alerts";
+    const SCHEMA: &'static str = "alert.notification";
+    const SCHEMA_VERSION: u32 = 1;
+
+    fn event_type(&self) -> String {
+        self.event_name()
+    }
+
+    fn event_id(&self) -> Option<String> {
+        Some(format!("alert-{}-{}", self.alert.id, self.status.as_str()))
+    }
+
+    fn subject(&self) -> Option<String> {
+        Some(self.rule.id.clone())
+    }
+
+    fn tenant_id(&self) -> Option<String> {
+        Some(self.rule.tenant_id.to_string())
+    }
+}
+
+/// Metric value computed over a rule's window
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MetricSample {
+    pub value: f64,
+    pub samples: u64,
+}
+
+/// Delivers alert notifications
+#[async_trait::async_trait]
+pub trait AlertNotifier: Send + Sync {
+    async fn notify(&self, notification: &AlertNotification) -> AlertResult<()>;
+}
+
+/// Posts notifications to a webhook as CloudEvents
+pub struct WebhookNotifier {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: String) -> Self {
+        Self {
+            url,
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl AlertNotifier for WebhookNotifier {
+    async fn notify(&self, notification: &AlertNotification) -> AlertResult<()> {
+        post_webhook(&self.client, &self.url, notification).await
+    }
+}
+
+/// Publishes notifications as `alert.firing` / `alert.resolved` events carrying a CloudEvent
+pub struct EventBusNotifier {
+    publisher: EventPublisher,
+}
+
+impl EventBusNotifier {
+    pub fn new(publisher: EventPublisher) -> Self {
+        Self { publisher }
+    }
+}
+
+#[async_trait::async_trait]
+impl AlertNotifier for EventBusNotifier {
+    async fn notify(&self, notification: &AlertNotification) -> AlertResult<()> {
+        let data = serde_json::to_value(CloudEvent::new(notification.clone()))
+            .map_err(|e| MonitoringError::ExportFailed(e.to_string()))?;
+        self.publisher
+            .publish_simple(&notification.event_name(), data)
+            .await
+            .map_err(|e| MonitoringError::ExportFailed(e.to_string()))
+    }
+}
+
+async fn post_webhook(client: &reqwest::Client, url: &str, notification: &AlertNotification) -> AlertResult<()> {
+    let response = client
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, CLOUDEVENTS_CONTENT_TYPE)
+        .json(&CloudEvent::new(notification.clone()))
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await
+        .map_err(|e| MonitoringError::ExportFailed(format!("Webhook {} failed: {}", url, e)))?;
+
+    if !response.status().is_success() {
+        return Err(MonitoringError::ExportFailed(format!(
+            "Webhook {} returned {}",
+            url,
+            response.status()
+        )));
+    }
+    Ok(())
+}
+
+/// Evaluates alert rules and manages alert state
+pub struct AlertManager {
+    db: Arc<SqliteDatabase>,
+    notifiers: Vec<Arc<dyn AlertNotifier>>,
+    client: reqwest::Client,
+}
+
+impl AlertManager {
+    /// Create an alert manager backed by the given database
+    pub fn new(db: Arc<SqliteDatabase>) -> Self {
+        Self {
+            db,
+            notifiers: Vec::new(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Create an alert manager from the monitoring configuration.
+    ///
+    /// Returns `None` when alerting is disabled; the configured
+    /// `alert_webhook_url` receives every notification.
+    pub fn from_config(db: Arc<SqliteDatabase>, config: &stepflow_core::MonitoringConfig) -> Option<Self> {
+        if !config.enable_alerting {
+            return None;
+        }
+        let mut manager = Self::new(db);
+        if let Some(url) = &config.alert_webhook_url {
+            manager = manager.with_notifier(Arc::new(WebhookNotifier::new(url.clone())));
+        }
+        Some(manager)
+    }
+
+    /// Add a notifier that receives every firing and resolve notification
+    pub fn with_notifier(mut self, notifier: Arc<dyn AlertNotifier>) -> Self {
+        self.notifiers.push(notifier);
+        self
+    }
+
+    /// Create an alert rule
+    pub async fn create_rule(&self, rule: AlertRule) -> AlertResult<AlertRule> {
+        if !rule.threshold.is_finite() {
+            return Err(MonitoringError::AlertEvaluationFailed("Threshold must be a finite number".to_string()));
+        }
+
+        let params = vec![
+            Value::String(rule.id.clone()),
+            Value::String(rule.tenant_id.to_string()),
+            Value::String(rule.name.clone()),
+            Value::String(rule.metric.as_str().to_string()),
+            rule.tool_id.as_ref().map(|id| Value::String(id.to_string())).unwrap_or(Value::Null),
+            Value::String(rule.comparison.as_str().to_string()),
+            serde_json::json!(rule.threshold),
+            Value::from(rule.window_secs),
+            Value::from(rule.min_samples),
+            rule.webhook_url.clone().map(Value::String).unwrap_or(Value::Null),
+            Value::from(rule.enabled as i64),
+            Value::String(rule.created_at.to_rfc3339()),
+        ];
+
+        self.execute(
+            "INSERT INTO alert_rules (id, tenant_id, name, metric, tool_id, comparison, threshold, window_secs, min_samples, webhook_url, enabled, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            &params,
+        ).await?;
+
+        Ok(rule)
+    }
+
+    /// List a tenant's alert rules
+    pub async fn list_rules(&self, tenant_id: &TenantId) -> AlertResult<Vec<AlertRule>> {
+        let rows = self.execute(
+            &format!("SELECT {} FROM alert_rules WHERE tenant_id = ? ORDER BY created_at ASC", RULE_COLUMNS),
+            &[Value::String(tenant_id.to_string())],
+        ).await?;
+        rows.iter().map(row_to_rule).collect()
+    }
+
+    /// Enable or disable one of a tenant's rules; disabling resolves its firing alert silently
+    pub async fn set_rule_enabled(&self, tenant_id: &TenantId, rule_id: &str, enabled: bool) -> AlertResult<bool> {
+        let result = self.db.execute(
+            "UPDATE alert_rules SET enabled = ? WHERE id = ? AND tenant_id = ?",
+            &[Value::from(enabled as i64), Value::String(rule_id.to_string()), Value::String(tenant_id.to_string())],
+        )
+        .await
+        .map_err(|e| MonitoringError::AlertEvaluationFailed(e.to_string()))?;
+        if result.rows_affected == 0 {
+            return Ok(false);
+        }
+        if !enabled {
+            if let Some(alert) = self.firing_alert(rule_id).await? {
+                self.resolve_alert(&alert.id).await?;
+            }
+        }
+        Ok(true)
+    }
+
+    /// Delete one of a tenant's rules and its alert history
+    pub async fn delete_rule(&self, tenant_id: &TenantId, rule_id: &str) -> AlertResult<bool> {
+        let params = [Value::String(rule_id.to_string()), Value::String(tenant_id.to_string())];
+        self.execute(
+            "DELETE FROM alerts WHERE rule_id IN (SELECT id FROM alert_rules WHERE id = ? AND tenant_id = ?)",
+            &params,
+        ).await?;
+        let result = self.db.execute("DELETE FROM alert_rules WHERE id = ? AND tenant_id = ?", &params)
+            .await
+            .map_err(|e| MonitoringError::AlertEvaluationFailed(e.to_string()))?;
+        Ok(result.rows_affected > 0)
+    }
+
+    /// List a tenant's alerts, newest first
+    pub async fn list_alerts(&self, tenant_id: &TenantId, firing_only: bool) -> AlertResult<Vec<Alert>> {
+        let mut sql = "SELECT a.id, a.rule_id, a.status, a.value, a.threshold, a.fired_at, a.resolved_at \
+             FROM alerts a JOIN alert_rules r ON r.id = a.rule_id WHERE r.tenant_id = ?".to_string();
+        if firing_only {
+            sql.push_str(" AND a.status = 'firing'");
+        }
+        sql.push_str(" ORDER BY a.fired_at DESC");
+        let rows = self.execute(&sql, &[Value::String(tenant_id.to_string())]).await?;
+        rows.iter().map(row_to_alert).collect()
+    }
+
+    /// Compute a metric over a tenant's executions in the window ending now
+    pub async fn sample(
+        &self,
+        tenant_id: &TenantId,
+        metric: AlertMetric,
+        tool_id: Option<&ToolId>,
+        window: Duration,
+    ) -> AlertResult<MetricSample> {
+        let since = Utc::now() - chrono::Duration::from_std(window).unwrap_or_else(|_| chrono::Duration::days(365));
+        let mut sql = "SELECT execution_id, state, occurred_at FROM execution_events WHERE tenant_id = ? AND occurred_at >= ?".to_string();
+        let mut params = vec![Value::String(tenant_id.to_string()), Value::String(since.to_rfc3339())];
+        if let Some(tool_id) = tool_id {
+            sql.push_str(" AND tool_id = ?");
+            params.push(Value::String(tool_id.to_string()));
+        }
+        sql.push_str(" ORDER BY id ASC");
+        let rows = self.execute(&sql, &params).await?;
+
+        // Group transitions per execution: state -> first occurrence
+        let mut executions: HashMap<String, HashMap<String, DateTime<Utc>>> = HashMap::new();
+        for row in &rows {
+            let (Some(execution_id), Some(state), Some(occurred_at)) = (
+                row.get("execution_id").and_then(Value::as_str),
+                row.get("state").and_then(Value::as_str),
+                row.get("occurred_at").and_then(Value::as_str).and_then(parse_time),
+            ) else {
+                continue;
+            };
+            executions
+                .entry(execution_id.to_string())
+                .or_default()
+                .entry(state.to_string())
+                .or_insert(occurred_at);
+        }
+
+        const FAILED: [&str; 2] = ["failed", "timed_out"];
+        const FINISHED: [&str; 4] = ["completed", "failed", "cancelled", "timed_out"];
+        let finished_at = |states: &HashMap<String, DateTime<Utc>>| {
+            FINISHED.iter().find_map(|state| states.get(*state).copied())
+        };
+
+        let sample = match metric {
+            AlertMetric::FailureRate | AlertMetric::ExecutionCount => {
+                let finished: Vec<_> = executions.values().filter(|s| finished_at(s).is_some()).collect();
+                let failed = finished.iter().filter(|s| FAILED.iter().any(|f| s.contains_key(*f))).count();
+                let value = match metric {
+                    AlertMetric::ExecutionCount => finished.len() as f64,
+                    _ if finished.is_empty() => 0.0,
+                    _ => failed as f64 / finished.len() as f64,
+                };
+                MetricSample { value, samples: finished.len() as u64 }
+            }
+            AlertMetric::QueueWaitP95Ms | AlertMetric::RunDurationP95Ms => {
+                let durations: Vec<f64> = executions
+                    .values()
+                    .filter_map(|states| {
+                        let (from, to) = match metric {
+                            AlertMetric::QueueWaitP95Ms => (states.get("queued").copied(), states.get("running").copied()),
+                            _ => (states.get("running").copied(), finished_at(states)),
+                        };
+                        Some((to? - from?).num_milliseconds() as f64)
+                    })
+                    .collect();
+                MetricSample {
+                    value: percentile(durations.clone(), 0.95),
+                    samples: durations.len() as u64,
+                }
+            }
+        };
+
+        Ok(sample)
+    }
+
+    /// Evaluate every enabled rule of every tenant once and deliver the resulting notifications.
+    ///
+    /// Rules are evaluated independently: a rule that fails (bad row, query
+    /// error) is logged and skipped.
+    pub async fn evaluate(&self) -> AlertResult<Vec<AlertNotification>> {
+        let rows = self.execute(
+            &format!("SELECT {} FROM alert_rules WHERE enabled = 1 AND tenant_id IS NOT NULL ORDER BY created_at ASC", RULE_COLUMNS),
+            &[],
+        ).await?;
+
+        let mut notifications = Vec::new();
+        for row in &rows {
+            let rule = match row_to_rule(row) {
+                Ok(rule) => rule,
+                Err(e) => {
+                    warn!("Skipping alert rule {}: {}", text(row, "id").unwrap_or("?"), e);
+                    continue;
+                }
+            };
+            let (rule_id, rule_name) = (rule.id.clone(), rule.name.clone());
+            match self.evaluate_rule(rule).await {
+                Ok(Some(notification)) => {
+                    self.deliver(&notification).await;
+                    notifications.push(notification);
+                }
+                Ok(None) => {}
+                Err(e) => warn!("Failed to evaluate alert rule {} ({}): {}", rule_name, rule_id, e),
+            }
+        }
+
+        Ok(notifications)
+    }
+
+    /// Evaluate one rule, returning the notification for a state change
+    async fn evaluate_rule(&self, rule: AlertRule) -> AlertResult<Option<AlertNotification>> {
+        let window = Duration::from_secs(rule.window_secs);
+        let sample = self.sample(&rule.tenant_id, rule.metric, rule.tool_id.as_ref(), window).await?;
+
+        // With too few samples the rule is inconclusive and keeps its state
+        if sample.samples < rule.min_samples {
+            debug!("Alert rule {} has {} samples, needs {}", rule.name, sample.samples, rule.min_samples);
+            return Ok(None);
+        }
+
+        let breached = rule.comparison.breached(sample.value, rule.threshold);
+        let notification = match (breached, self.firing_alert(&rule.id).await?) {
+            (true, None) => {
+                let alert = self.fire_alert(&rule, sample.value).await?;
+                Some(AlertNotification { status: AlertStatus::Firing, rule, alert, current_value: sample.value })
+            }
+            (false, Some(alert)) => {
+                let alert = self.resolve_alert(&alert.id).await?.unwrap_or(alert);
+                Some(AlertNotification { status: AlertStatus::Resolved, rule, alert, current_value: sample.value })
+            }
+            // Already firing or still healthy: nothing new to report
+            _ => None,
+        };
+        Ok(notification)
+    }
+
+    /// Evaluate rules periodically in the background
+    pub fn start(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.evaluate().await {
+                    warn!("Alert evaluation failed: {}", e);
+                }
+            }
+        })
+    }
+
+    async fn deliver(&self, notification: &AlertNotification) {
+        if let Some(url) = &notification.rule.webhook_url {
+            if let Err(e) = post_webhook(&self.client, url, notification).await {
+                warn!("Failed to deliver alert {}: {}", notification.alert.id, e);
+            }
+        }
+        for notifier in &self.notifiers {
+            if let Err(e) = notifier.notify(notification).await {
+                warn!("Failed to deliver alert {}: {}", notification.alert.id, e);
+            }
+        }
+    }
+
+    async fn firing_alert(&self, rule_id: &str) -> AlertResult<Option<Alert>> {
+        let rows = self.execute(
+            "SELECT id, rule_id, status, value, threshold, fired_at, resolved_at FROM alerts WHERE rule_id = ? AND status = 'firing' LIMIT 1",
+            &[Value::String(rule_id.to_string())],
+        ).await?;
+        rows.first().map(row_to_alert).transpose()
+    }
+
+    async fn fire_alert(&self, rule: &AlertRule, value: f64) -> AlertResult<Alert> {
+        let alert = Alert {
+            id: uuid::Uuid::new_v4().to_string(),
+            rule_id: rule.id.clone(),
+            status: AlertStatus::Firing,
+            value,
+            threshold: rule.threshold,
+            fired_at: Utc::now(),
+            resolved_at: None,
+        };
+        self.execute(
+            "INSERT INTO alerts (id, rule_id, status, value, threshold, fired_at) VALUES (?, ?, ?, ?, ?, ?)",
+            &[
+                Value::String(alert.id.clone()),
+                Value::String(alert.rule_id.clone()),
+                Value::String(alert.status.as_str().to_string()),
+                serde_json::json!(alert.value),
+                serde_json::json!(alert.threshold),
+                Value::String(alert.fired_at.to_rfc3339()),
+            ],
+        ).await?;
+        Ok(alert)
+    }
+
+    async fn resolve_alert(&self, alert_id: &str) -> AlertResult<Option<Alert>> {
+        self.execute(
+            "UPDATE alerts SET status = 'resolved', resolved_at = ? WHERE id = ?",
+            &[Value::String(Utc::now().to_rfc3339()), Value::String(alert_id.to_string())],
+        ).await?;
+        let rows = self.execute(
+            "SELECT id, rule_id, status, value, threshold, fired_at, resolved_at FROM alerts WHERE id = ?",
+            &[Value::String(alert_id.to_string())],
+        ).await?;
+        rows.first().map(row_to_alert).transpose()
+    }
+
+    async fn execute(&self, sql: &str, params: &[Value]) -> AlertResult<Vec<HashMap<String, Value>>> {
+        self.db.execute(sql, params)
+            .await
+            .map(|result| result.rows)
+            .map_err(|e| MonitoringError::AlertEvaluationFailed(e.to_string()))
+    }
+}
+
+const RULE_COLUMNS: &str =
+    "id, tenant_id, name, metric, tool_id, comparison, threshold, window_secs, min_samples, webhook_url, enabled, created_at";
+
+/// Nearest-rank percentile; 0.0 for an empty set
+pub(crate) fn percentile(mut values: Vec<f64>, quantile: f64) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+    let rank = (quantile * values.len() as f64).ceil() as usize;
+    values[rank.clamp(1, values.len()) - 1]
+}
+
+pub(crate) fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value).ok().map(|dt| dt.with_timezone(&Utc))
+}
+
+/// Read a TEXT column, `None` when it is NULL
+pub(crate) fn text<'a>(row: &'a HashMap<String, Value>, key: &str) -> Option<&'a str> {
+    row.get(key).and_then(Value::as_str)
+}
+
+fn row_to_rule(row: &HashMap<String, Value>) -> AlertResult<AlertRule> {
+    let invalid = |field: &str| MonitoringError::AlertEvaluationFailed(format!("Invalid alert rule {}", field));
+
+    Ok(AlertRule {
+        id: text(row, "id").ok_or_else(|| invalid("id"))?.to_string(),
+        tenant_id: TenantId::from_string(text(row, "tenant_id").ok_or_else(|| invalid("tenant_id"))?.to_string()),
+        name: text(row, "name").unwrap_or_default().to_string(),
+        metric: text(row, "metric").and_then(AlertMetric::parse).ok_or_else(|| invalid("metric"))?,
+        tool_id: text(row, "tool_id").map(|id| ToolId::from_string(id.to_string())),
+        comparison: text(row, "comparison").and_then(Comparison::parse).ok_or_else(|| invalid("comparison"))?,
+        threshold: row.get("threshold").and_then(Value::as_f64).ok_or_else(|| invalid("threshold"))?,
+        window_secs: row.get("window_secs").and_then(Value::as_u64).ok_or_else(|| invalid("window"))?,
+        min_samples: row.get("min_samples").and_then(Value::as_u64).unwrap_or(1),
+        webhook_url: text(row, "webhook_url").map(str::to_string),
+        enabled: row.get("enabled").and_then(Value::as_i64).unwrap_or(1) != 0,
+        created_at: text(row, "created_at").and_then(parse_time).unwrap_or_else(Utc::now),
+    })
+}
+
+fn row_to_alert(row: &HashMap<String, Value>) -> AlertResult<Alert> {
+    let invalid = |field: &str| MonitoringError::AlertEvaluationFailed(format!("Invalid alert {}", field));
+
+    Ok(Alert {
+        id: text(row, "id").ok_or_else(|| invalid("id"))?.to_string(),
+        rule_id: text(row, "rule_id").ok_or_else(|| invalid("rule_id"))?.to_string(),
+        status: text(row, "status").and_then(AlertStatus::parse).ok_or_else(|| invalid("status"))?,
+        value: row.get("value").and_then(Value::as_f64).unwrap_or_default(),
+        threshold: row.get("threshold").and_then(Value::as_f64).unwrap_or_default(),
+        fired_at: text(row, "fired_at").and_then(parse_time).ok_or_else(|| invalid("fired_at"))?,
+        resolved_at: text(row, "resolved_at").and_then(parse_time),
+    })
+}
@@ -0,0 +1,350 @@
+//! Anomaly detection on execution durations and failures
+//!
+//! Each tool keeps an exponentially weighted baseline of its execution
+//! duration (mean and variance) and failure rate in `tool_baselines`. Every
+//! finished execution is compared against the baseline before being folded
+//! into it; executions that deviate significantly are recorded in
+//! `execution_anomalies` with the tenant that ran them, so each tenant's
+//! operators can review their own anomalies as a feed.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use stepflow_core::{Database, ExecutionId, MonitoringError, ToolId};
+use stepflow_database::SqliteDatabase;
+use tokio::sync::Mutex;
+use crate::alerting::AlertResult;
+
+/// Anomaly detection settings
+#[derive(Debug, Clone)]
+pub struct AnomalyConfig {
+    /// EWMA smoothing factor; higher values adapt faster
+    pub alpha: f64,
+    /// Number of standard deviations that counts as anomalous
+    pub z_threshold: f64,
+    /// Executions needed before a baseline is trusted
+    pub min_samples: u64,
+    /// Failures are anomalous while the baseline failure rate is below this
+    pub failure_rate_threshold: f64,
+    /// Lower bound for the standard deviation, as a fraction of the mean,
+    /// so perfectly stable tools do not flag tiny jitter
+    pub min_relative_stddev: f64,
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        Self {
+            alpha: 0.1,
+            z_threshold: 3.0,
+            min_samples: 20,
+            failure_rate_threshold: 0.05,
+            min_relative_stddev: 0.05,
+        }
+    }
+}
+
+/// Statistical baseline of a tool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolBaseline {
+    pub tool_id: ToolId,
+    pub samples: u64,
+    pub duration_mean_ms: f64,
+    pub duration_variance: f64,
+    pub failure_rate: f64,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ToolBaseline {
+    fn empty(tool_id: ToolId) -> Self {
+        Self {
+            tool_id,
+            samples: 0,
+            duration_mean_ms: 0.0,
+            duration_variance: 0.0,
+            failure_rate: 0.0,
+            updated_at: Utc::now(),
+        }
+    }
+
+    pub fn duration_stddev_ms(&self) -> f64 {
+        self.duration_variance.sqrt()
+    }
+
+    /// Fold one execution into the baseline.
+    ///
+    /// Until `1 / (n + 1)` drops below `alpha` this is a plain running mean,
+    /// so the first samples are not dominated by the initial value. Failed
+    /// executions often abort early, so they only shape the failure rate.
+    fn update(&mut self, duration_ms: f64, success: bool, alpha: f64) {
+        let weight = alpha.max(1.0 / (self.samples as f64 + 1.0));
+        if success {
+            let diff = duration_ms - self.duration_mean_ms;
+            let increment = weight * diff;
+            self.duration_mean_ms += increment;
+            self.duration_variance = (1.0 - weight) * (self.duration_variance + diff * increment);
+        }
+
+        let failed = if success { 0.0 } else { 1.0 };
+        self.failure_rate += weight * (failed - self.failure_rate);
+        self.samples += 1;
+        self.updated_at = Utc::now();
+    }
+}
+
+/// Kind of deviation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyKind {
+    SlowExecution,
+    FastExecution,
+    UnexpectedFailure,
+}
+
+impl AnomalyKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AnomalyKind::SlowExecution => "slow_execution",
+            AnomalyKind::FastExecution => "fast_execution",
+            AnomalyKind::UnexpectedFailure => "unexpected_failure",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "slow_execution" => Some(AnomalyKind::SlowExecution),
+            "fast_execution" => Some(AnomalyKind::FastExecution),
+            "unexpected_failure" => Some(AnomalyKind::UnexpectedFailure),
+            _ => None,
+        }
+    }
+}
+
+/// An execution that deviated from its tool's baseline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Anomaly {
+    pub id: String,
+    pub execution_id: ExecutionId,
+    /// Tenant that ran the execution
+    pub tenant_id: String,
+    pub tool_id: ToolId,
+    pub kind: AnomalyKind,
+    /// Observed duration in ms, or 1.0 for a failure
+    pub observed: f64,
+    /// Baseline mean duration in ms, or the baseline failure rate
+    pub expected: f64,
+    /// Z-score for duration anomalies, observed - expected for failures
+    pub deviation: f64,
+    pub detected_at: DateTime<Utc>,
+}
+
+impl Anomaly {
+    /// Marker attached to the execution metadata under the `anomaly` key
+    pub fn marker(&self) -> Value {
+        serde_json::json!({
+            "id": self.id,
+            "kind": self.kind,
+            "observed": self.observed,
+            "expected": self.expected,
+            "deviation": self.deviation,
+        })
+    }
+}
+
+/// Maintains per-tool baselines and records anomalies
+pub struct AnomalyDetector {
+    db: Arc<SqliteDatabase>,
+    config: AnomalyConfig,
+    // Serializes read-modify-write of baselines within this process
+    update_lock: Mutex<()>,
+}
+
+impl AnomalyDetector {
+    /// Create a detector with the default configuration
+    pub fn new(db: Arc<SqliteDatabase>) -> Self {
+        Self {
+            db,
+            config: AnomalyConfig::default(),
+            update_lock: Mutex::new(()),
+        }
+    }
+
+    pub fn with_config(mut self, config: AnomalyConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Check a finished execution against its tool baseline, record any
+    /// anomaly and then fold the execution into the baseline
+    pub async fn observe(
+        &self,
+        execution_id: &ExecutionId,
+        tenant_id: &str,
+        tool_id: &ToolId,
+        duration_ms: f64,
+        success: bool,
+    ) -> AlertResult<Vec<Anomaly>> {
+        let _guard = self.update_lock.lock().await;
+        let mut baseline = self.baseline(tool_id).await?.unwrap_or_else(|| ToolBaseline::empty(tool_id.clone()));
+
+        let mut anomalies = Vec::new();
+        if baseline.samples >= self.config.min_samples {
+            if success {
+                let floor = (baseline.duration_mean_ms * self.config.min_relative_stddev).max(1.0);
+                let stddev = baseline.duration_stddev_ms().max(floor);
+                let z = (duration_ms - baseline.duration_mean_ms) / stddev;
+                if z.abs() > self.config.z_threshold {
+                    let kind = if z > 0.0 { AnomalyKind::SlowExecution } else { AnomalyKind::FastExecution };
+                    anomalies.push(Self::new_anomaly(execution_id, tenant_id, tool_id, kind, duration_ms, baseline.duration_mean_ms, z));
+                }
+            } else if baseline.failure_rate < self.config.failure_rate_threshold {
+                anomalies.push(Self::new_anomaly(
+                    execution_id,
+                    tenant_id,
+                    tool_id,
+                    AnomalyKind::UnexpectedFailure,
+                    1.0,
+                    baseline.failure_rate,
+                    1.0 - baseline.failure_rate,
+                ));
+            }
+        }
+
+        for anomaly in &anomalies {
+            self.store_anomaly(anomaly).await?;
+        }
+
+        baseline.update(duration_ms, success, self.config.alpha);
+        self.save_baseline(&baseline).await?;
+
+        Ok(anomalies)
+    }
+
+    /// Current baseline of a tool
+    pub async fn baseline(&self, tool_id: &ToolId) -> AlertResult<Option<ToolBaseline>> {
+        let rows = self.execute(
+            "SELECT tool_id, samples, duration_mean_ms, duration_variance, failure_rate, updated_at FROM tool_baselines WHERE tool_id = ?",
+            &[Value::String(tool_id.to_string())],
+        ).await?;
+
+        Ok(rows.first().map(|row| ToolBaseline {
+            tool_id: tool_id.clone(),
+            samples: row.get("samples").and_then(Value::as_u64).unwrap_or(0),
+            duration_mean_ms: row.get("duration_mean_ms").and_then(Value::as_f64).unwrap_or(0.0),
+            duration_variance: row.get("duration_variance").and_then(Value::as_f64).unwrap_or(0.0),
+            failure_rate: row.get("failure_rate").and_then(Value::as_f64).unwrap_or(0.0),
+            updated_at: row.get("updated_at").and_then(Value::as_str).and_then(parse_time).unwrap_or_else(Utc::now),
+        }))
+    }
+
+    /// Anomaly feed of a tenant, newest first
+    pub async fn list_anomalies(&self, tenant_id: &str, tool_id: Option<&ToolId>, limit: usize) -> AlertResult<Vec<Anomaly>> {
+        let mut sql = "SELECT id, execution_id, tenant_id, tool_id, kind, observed, expected, deviation, detected_at \
+                       FROM execution_anomalies WHERE tenant_id = ?".to_string();
+        let mut params = vec![Value::String(tenant_id.to_string())];
+        if let Some(tool_id) = tool_id {
+            sql.push_str(" AND tool_id = ?");
+            params.push(Value::String(tool_id.to_string()));
+        }
+        sql.push_str(" ORDER BY detected_at DESC LIMIT ?");
+        params.push(Value::from(limit as i64));
+
+        let rows = self.execute(&sql, &params).await?;
+        rows.iter().map(row_to_anomaly).collect()
+    }
+
+    fn new_anomaly(
+        execution_id: &ExecutionId,
+        tenant_id: &str,
+        tool_id: &ToolId,
+        kind: AnomalyKind,
+        observed: f64,
+        expected: f64,
+        deviation: f64,
+    ) -> Anomaly {
+        Anomaly {
+            id: uuid::Uuid::new_v4().to_string(),
+            execution_id: execution_id.clone(),
+            tenant_id: tenant_id.to_string(),
+            tool_id: tool_id.clone(),
+            kind,
+            observed,
+            expected,
+            deviation,
+            detected_at: Utc::now(),
+        }
+    }
+
+    async fn store_anomaly(&self, anomaly: &Anomaly) -> AlertResult<()> {
+        self.execute(
+            "INSERT INTO execution_anomalies (id, execution_id, tenant_id, tool_id, kind, observed, expected, deviation, detected_at) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            &[
+                Value::String(anomaly.id.clone()),
+                Value::String(anomaly.execution_id.to_string()),
+                Value::String(anomaly.tenant_id.clone()),
+                Value::String(anomaly.tool_id.to_string()),
+                Value::String(anomaly.kind.as_str().to_string()),
+                serde_json::json!(anomaly.observed),
+                serde_json::json!(anomaly.expected),
+                serde_json::json!(anomaly.deviation),
+                Value::String(anomaly.detected_at.to_rfc3339()),
+            ],
+        ).await?;
+        Ok(())
+    }
+
+    async fn save_baseline(&self, baseline: &ToolBaseline) -> AlertResult<()> {
+        self.execute(
+            r#"
+            INSERT INTO tool_baselines (tool_id, samples, duration_mean_ms, duration_variance, failure_rate, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT(tool_id) DO UPDATE SET
+                samples = excluded.samples,
+                duration_mean_ms = excluded.duration_mean_ms,
+                duration_variance = excluded.duration_variance,
+                failure_rate = excluded.failure_rate,
+                updated_at = excluded.updated_at
+            "#,
+            &[
+                Value::String(baseline.tool_id.to_string()),
+                Value::from(baseline.samples as i64),
+                serde_json::json!(baseline.duration_mean_ms),
+                serde_json::json!(baseline.duration_variance),
+                serde_json::json!(baseline.failure_rate),
+                Value::String(baseline.updated_at.to_rfc3339()),
+            ],
+        ).await?;
+        Ok(())
+    }
+
+    async fn execute(&self, sql: &str, params: &[Value]) -> AlertResult<Vec<HashMap<String, Value>>> {
+        self.db.execute(sql, params)
+            .await
+            .map(|result| result.rows)
+            .map_err(|e| MonitoringError::MetricsCollectionFailed(e.to_string()))
+    }
+}
+
+fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value).ok().map(|dt| dt.with_timezone(&Utc))
+}
+
+fn row_to_anomaly(row: &HashMap<String, Value>) -> AlertResult<Anomaly> {
+    let text = |key: &str| row.get(key).and_then(Value::as_str);
+    let number = |key: &str| row.get(key).and_then(Value::as_f64).unwrap_or_default();
+    let invalid = |field: &str| MonitoringError::MetricsCollectionFailed(format!("Invalid anomaly {}", field));
+
+    Ok(Anomaly {
+        id: text("id").ok_or_else(|| invalid("id"))?.to_string(),
+        execution_id: ExecutionId::from_string(text("execution_id").ok_or_else(|| invalid("execution_id"))?.to_string()),
+        tenant_id: text("tenant_id").unwrap_or_default().to_string(),
+        tool_id: ToolId::from_string(text("tool_id").ok_or_else(|| invalid("tool_id"))?.to_string()),
+        kind: text("kind").and_then(AnomalyKind::parse).ok_or_else(|| invalid("kind"))?,
+        observed: number("observed"),
+        expected: number("expected"),
+        deviation: number("deviation"),
+        detected_at: text("detected_at").and_then(parse_time).ok_or_else(|| invalid("detected_at"))?,
+    })
+}
@@ -0,0 +1,403 @@
+//! External event sinks
+//!
+//! An [`EventRelay`] publishes Stepflow activity to an external broker so analytics
+//! pipelines can consume it: execution state transitions from `execution_events`,
+//! registry changes from `registry_changes` and audit events from `security_events`.
+//! The tables act as an outbox. The relay reads them in order from the position it
+//! last delivered, which is kept per sink and source in `event_sink_cursors`, so
+//! delivery is at least once and survives restarts and broker outages.
+//!
+//! Events are published as [`CloudEvent`]s in structured JSON mode. Each event is tried
+//! `max_attempts` times with exponential backoff. An event that still fails goes to the
+//! sink's dead-letter topic with the error; without a dead-letter topic the relay stops
+//! at that event and tries again on the next run. Event ids are stable, so consumers can
+//! drop redeliveries.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use stepflow_core::{
+    AuditRecorded, CloudEvent, Database, EventSinkConfig, EventSource, ExecutionStateChanged, MonitoringError,
+    ToolChanged,
+};
+use stepflow_database::SqliteDatabase;
+#[cfg(any(feature = "nats", feature = "kafka"))]
+use stepflow_core::EventSinkKind;
+use tracing::warn;
+
+use crate::alerting::AlertResult;
+
+/// Upper bound of the delay between two delivery attempts
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Broker connection events are published through
+#[async_trait]
+pub trait EventSink: Send + Sync {
+    /// Publish `payload`, the encoded `event`, to `topic` and wait for the broker to accept it
+    async fn publish(&self, topic: &str, event: &CloudEvent, payload: &[u8]) -> AlertResult<()>;
+}
+
+/// Connect the sink described by `config`
+///
+/// Fails when this build does not include the broker's client (features `nats` and `kafka`).
+pub async fn connect_event_sink(config: &EventSinkConfig) -> AlertResult<Arc<dyn EventSink>> {
+    match config.kind {
+        #[cfg(feature = "nats")]
+        EventSinkKind::Nats => Ok(Arc::new(NatsEventSink::connect(&config.url).await?)),
+        #[cfg(feature = "kafka")]
+        EventSinkKind::Kafka => Ok(Arc::new(KafkaEventSink::connect(&config.url)?)),
+        #[allow(unreachable_patterns)]
+        kind => Err(MonitoringError::BackendUnavailable(format!("{:?} event sinks are not supported by this build", kind))),
+    }
+}
+
+/// Delivery position and totals of one sink for one source
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SinkCursor {
+    pub sink: String,
+    pub source: EventSource,
+    /// Sequence of the last event delivered or dead-lettered
+    pub position: i64,
+    pub delivered: u64,
+    pub dead_lettered: u64,
+    /// Most recent delivery error, cleared by the next successful delivery
+    pub last_error: Option<String>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Outcome of one relay run
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RelayReport {
+    pub delivered: u64,
+    pub dead_lettered: u64,
+    /// Sources where delivery stopped at an event that could not be delivered
+    pub stalled: Vec<EventSource>,
+}
+
+enum Delivery {
+    Published,
+    DeadLettered(String),
+    Failed(String),
+}
+
+/// Publishes new events of the configured sources to one sink
+pub struct EventRelay {
+    db: Arc<SqliteDatabase>,
+    sink: Arc<dyn EventSink>,
+    config: EventSinkConfig,
+}
+
+impl EventRelay {
+    pub fn new(db: Arc<SqliteDatabase>, sink: Arc<dyn EventSink>, config: EventSinkConfig) -> Self {
+        Self { db, sink, config }
+    }
+
+    pub fn config(&self) -> &EventSinkConfig {
+        &self.config
+    }
+
+    /// Deliver the events recorded since the last run, up to `batch_size` per source
+    pub async fn relay(&self) -> AlertResult<RelayReport> {
+        let mut report = RelayReport::default();
+        for &source in &self.config.sources {
+            let cursor = self.cursor(source).await?;
+            let events = self.read(source, cursor.position).await?;
+            let (mut position, mut delivered, mut dead_lettered, mut last_error) = (cursor.position, 0, 0, None);
+            for (sequence, event) in events {
+                match self.deliver(source, &event).await {
+                    Delivery::Published => {
+                        delivered += 1;
+                        last_error = None;
+                    }
+                    Delivery::DeadLettered(error) => {
+                        dead_lettered += 1;
+                        last_error = Some(error);
+                    }
+                    Delivery::Failed(error) => {
+                        warn!("Event sink {} stalled at {}: {}", self.config.name, event.id, error);
+                        report.stalled.push(source);
+                        last_error = Some(error);
+                        break;
+                    }
+                }
+                position = sequence;
+            }
+            if position != cursor.position || last_error != cursor.last_error {
+                self.save_cursor(source, position, delivered, dead_lettered, last_error.as_deref()).await?;
+            }
+            report.delivered += delivered;
+            report.dead_lettered += dead_lettered;
+        }
+        Ok(report)
+    }
+
+    /// Delivery positions of this sink, one per configured source
+    pub async fn cursors(&self) -> AlertResult<Vec<SinkCursor>> {
+        let mut cursors = Vec::with_capacity(self.config.sources.len());
+        for &source in &self.config.sources {
+            cursors.push(self.cursor(source).await?);
+        }
+        Ok(cursors)
+    }
+
+    async fn deliver(&self, source: EventSource, event: &CloudEvent) -> Delivery {
+        let topic = self.config.topic_for(source);
+        let payload = serde_json::to_vec(event).unwrap_or_default();
+        let mut backoff = self.config.retry_backoff;
+        let mut error = String::new();
+        for attempt in 1..=self.config.max_attempts {
+            match self.sink.publish(&topic, event, &payload).await {
+                Ok(()) => return Delivery::Published,
+                Err(e) => error = e.to_string(),
+            }
+            if attempt < self.config.max_attempts {
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_RETRY_BACKOFF);
+            }
+        }
+
+        let Some(dead_letter_topic) = &self.config.dead_letter_topic else {
+            return Delivery::Failed(error);
+        };
+        warn!("Dead-lettering event {} after {} attempts: {}", event.id, self.config.max_attempts, error);
+        let letter = json!({
+            "topic": topic,
+            "error": error,
+            "attempts": self.config.max_attempts,
+            "event": event,
+        });
+        let letter = serde_json::to_vec(&letter).unwrap_or_default();
+        match self.sink.publish(dead_letter_topic, event, &letter).await {
+            Ok(()) => Delivery::DeadLettered(error),
+            Err(e) => Delivery::Failed(format!("{}; dead-lettering failed: {}", error, e)),
+        }
+    }
+
+    /// Events of `source` after position `after`, with their positions
+    async fn read(&self, source: EventSource, after: i64) -> AlertResult<Vec<(i64, CloudEvent)>> {
+        let params = [json!(after), json!(self.config.batch_size as i64)];
+        let events = match source {
+            EventSource::Execution => self.execute(
+                "SELECT id, execution_id, tool_id, tenant_id, state, worker_id, detail, occurred_at \
+                 FROM execution_events WHERE id > ? ORDER BY id ASC LIMIT ?",
+                &params,
+            ).await?.iter().filter_map(execution_event).collect(),
+            EventSource::Registry => self.execute(
+                "SELECT sequence, change_type, tool_id, version, payload, created_at \
+                 FROM registry_changes WHERE sequence > ? ORDER BY sequence ASC LIMIT ?",
+                &params,
+            ).await?.iter().filter_map(registry_event).collect(),
+            EventSource::Audit => self.execute(
+                "SELECT rowid, id, event_type, user_id, tenant_id, resource_type, resource_id, action, details, \
+                 ip_address, user_agent, success, error_message, created_at \
+                 FROM security_events WHERE rowid > ? ORDER BY rowid ASC LIMIT ?",
+                &params,
+            ).await?.iter().filter_map(audit_event).collect(),
+        };
+        Ok(events)
+    }
+
+    async fn cursor(&self, source: EventSource) -> AlertResult<SinkCursor> {
+        let rows = self.execute(
+            "SELECT position, delivered, dead_lettered, last_error, updated_at FROM event_sink_cursors WHERE sink = ? AND source = ?",
+            &[json!(self.config.name), json!(source.as_str())],
+        ).await?;
+        let row = rows.first();
+        let int = |key: &str| row.and_then(|row| int(row, key)).unwrap_or(0);
+        Ok(SinkCursor {
+            sink: self.config.name.clone(),
+            source,
+            position: int("position"),
+            delivered: int("delivered") as u64,
+            dead_lettered: int("dead_lettered") as u64,
+            last_error: row.and_then(|row| text(row, "last_error")).map(str::to_string),
+            updated_at: row.and_then(|row| text(row, "updated_at")).and_then(parse_time),
+        })
+    }
+
+    async fn save_cursor(
+        &self,
+        source: EventSource,
+        position: i64,
+        delivered: u64,
+        dead_lettered: u64,
+        last_error: Option<&str>,
+    ) -> AlertResult<()> {
+        self.execute(
+            "INSERT INTO event_sink_cursors (sink, source, position, delivered, dead_lettered, last_error, updated_at) \
+             VALUES (?, ?, ?, ?, ?, ?, ?) \
+             ON CONFLICT(sink, source) DO UPDATE SET \
+                position = excluded.position, \
+                delivered = event_sink_cursors.delivered + excluded.delivered, \
+                dead_lettered = event_sink_cursors.dead_lettered + excluded.dead_lettered, \
+                last_error = excluded.last_error, \
+                updated_at = excluded.updated_at",
+            &[
+                json!(self.config.name),
+                json!(source.as_str()),
+                json!(position),
+                json!(delivered as i64),
+                json!(dead_lettered as i64),
+                json!(last_error),
+                json!(Utc::now().to_rfc3339()),
+            ],
+        ).await?;
+        Ok(())
+    }
+
+    async fn execute(&self, sql: &str, params: &[Value]) -> AlertResult<Vec<HashMap<String, Value>>> {
+        self.db.execute(sql, params)
+            .await
+            .map(|result| result.rows)
+            .map_err(|e| MonitoringError::ExportFailed(e.to_string()))
+    }
+}
+
+fn execution_event(row: &HashMap<String, Value>) -> Option<(i64, CloudEvent)> {
+    let data = ExecutionStateChanged {
+        sequence: int(row, "id")?,
+        execution_id: text(row, "execution_id")?.to_string(),
+        tool_id: text(row, "tool_id").map(str::to_string),
+        tenant_id: text(row, "tenant_id").map(str::to_string),
+        state: text(row, "state")?.to_string(),
+        worker_id: text(row, "worker_id").map(str::to_string),
+        detail: text(row, "detail").map(str::to_string),
+    };
+    let time = parse_time(text(row, "occurred_at")?)?;
+    Some((data.sequence, CloudEvent::new(data).with_time(time).into_json()))
+}
+
+fn registry_event(row: &HashMap<String, Value>) -> Option<(i64, CloudEvent)> {
+    let sequence = int(row, "sequence")?;
+    let data = ToolChanged {
+        sequence: sequence as u64,
+        change_type: text(row, "change_type")?.to_string(),
+        tool_id: text(row, "tool_id")?.to_string(),
+        version: text(row, "version")?.to_string(),
+        tool: text(row, "payload").and_then(|payload| serde_json::from_str(payload).ok()),
+        changed_at: parse_time(text(row, "created_at")?)?,
+    };
+    let time = data.changed_at;
+    Some((sequence, CloudEvent::new(data).with_time(time).into_json()))
+}
+
+fn audit_event(row: &HashMap<String, Value>) -> Option<(i64, CloudEvent)> {
+    let data = AuditRecorded {
+        event_id: text(row, "id")?.to_string(),
+        event_type: text(row, "event_type")?.to_string(),
+        user_id: text(row, "user_id").map(str::to_string),
+        tenant_id: text(row, "tenant_id").map(str::to_string),
+        resource_type: text(row, "resource_type").map(str::to_string),
+        resource_id: text(row, "resource_id").map(str::to_string),
+        action: text(row, "action").map(str::to_string),
+        details: text(row, "details").and_then(|details| serde_json::from_str(details).ok()),
+        ip_address: text(row, "ip_address").map(str::to_string),
+        user_agent: text(row, "user_agent").map(str::to_string),
+        success: int(row, "success").is_some_and(|success| success != 0),
+        error_message: text(row, "error_message").map(str::to_string),
+    };
+    let time = parse_time(text(row, "created_at")?)?;
+    Some((int(row, "rowid")?, CloudEvent::new(data).with_time(time).into_json()))
+}
+
+fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value).ok().map(|dt| dt.with_timezone(&Utc))
+}
+
+/// Read a TEXT column, `None` when it is NULL
+fn text<'a>(row: &'a HashMap<String, Value>, key: &str) -> Option<&'a str> {
+    row.get(key).and_then(Value::as_str)
+}
+
+fn int(row: &HashMap<String, Value>, key: &str) -> Option<i64> {
+    row.get(key).and_then(|value| value.as_i64().or_else(|| value.as_str()?.parse().ok()))
+}
+
+#[cfg(feature = "nats")]
+pub use self::nats::NatsEventSink;
+
+#[cfg(feature = "nats")]
+mod nats {
+    use super::*;
+
+    /// Publishes to NATS JetStream, setting `Nats-Msg-Id` so the stream drops redeliveries
+    pub struct NatsEventSink {
+        jetstream: async_nats::jetstream::Context,
+    }
+
+    impl NatsEventSink {
+        /// Connect to `url`; the connection is retried in the background until the server is up
+        pub async fn connect(url: &str) -> AlertResult<Self> {
+            let client = async_nats::ConnectOptions::new()
+                .retry_on_initial_connect()
+                .connect(url)
+                .await
+                .map_err(|e| MonitoringError::BackendUnavailable(format!("NATS {}: {}", url, e)))?;
+            Ok(Self { jetstream: async_nats::jetstream::new(client) })
+        }
+    }
+
+    #[async_trait]
+    impl EventSink for NatsEventSink {
+        async fn publish(&self, topic: &str, event: &CloudEvent, payload: &[u8]) -> AlertResult<()> {
+            let mut headers = async_nats::HeaderMap::new();
+            headers.insert("Nats-Msg-Id", event.id.as_str());
+            let failed = |e: &dyn std::fmt::Display| MonitoringError::ExportFailed(format!("NATS publish to {}: {}", topic, e));
+            self.jetstream
+                .publish_with_headers(topic.to_string(), headers, payload.to_vec().into())
+                .await
+                .map_err(|e| failed(&e))?
+                .await
+                .map_err(|e| failed(&e))?;
+            Ok(())
+        }
+    }
+}
+
+#[cfg(feature = "kafka")]
+pub use self::kafka::KafkaEventSink;
+
+#[cfg(feature = "kafka")]
+mod kafka {
+    use super::*;
+    use rdkafka::config::ClientConfig;
+    use rdkafka::producer::{FutureProducer, FutureRecord};
+
+    /// How long the producer waits for the brokers to acknowledge a message
+    const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Publishes to Kafka keyed by subject, so events of one execution or tool stay in order
+    pub struct KafkaEventSink {
+        producer: FutureProducer,
+    }
+
+    impl KafkaEventSink {
+        /// Create a producer for the comma separated bootstrap servers in `brokers`
+        pub fn connect(brokers: &str) -> AlertResult<Self> {
+            let producer = ClientConfig::new()
+                .set("bootstrap.servers", brokers)
+                .set("enable.idempotence", "true")
+                .set("message.timeout.ms", DELIVERY_TIMEOUT.as_millis().to_string())
+                .create()
+                .map_err(|e| MonitoringError::BackendUnavailable(format!("Kafka {}: {}", brokers, e)))?;
+            Ok(Self { producer })
+        }
+    }
+
+    #[async_trait]
+    impl EventSink for KafkaEventSink {
+        async fn publish(&self, topic: &str, event: &CloudEvent, payload: &[u8]) -> AlertResult<()> {
+            let key = event.subject.as_deref().unwrap_or(event.id.as_str());
+            let record = FutureRecord::to(topic).key(key).payload(payload);
+            self.producer
+                .send(record, DELIVERY_TIMEOUT)
+                .await
+                .map_err(|(e, _)| MonitoringError::ExportFailed(format!("Kafka publish to {}: {}", topic, e)))?;
+            Ok(())
+        }
+    }
+}
@@ -6,7 +6,623 @@
 // pub mod metrics;
 // pub mod tracing;
 // pub mod exporters;
+pub mod alerting;
+pub mod anomaly;
+pub mod event_sinks;
+pub mod logging;
+pub mod notifications;
+pub mod payload;
+pub mod slow_executions;
 
 // pub use metrics::*;
 // pub use tracing::*;
-// pub use exporters::*; 
\ No newline at end of file
+// pub use exporters::*;
+pub use alerting::*;
+pub use anomaly::*;
+pub use event_sinks::*;
+pub use logging::*;
+pub use notifications::*;
+pub use payload::*;
+pub use slow_executions::*;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use chrono::Utc;
+    use serde_json::Value;
+    use stepflow_core::{CloudEvent, Database, EventSinkConfig, EventSinkKind, EventSource, ExecutionId, TenantId, ToolId};
+    use stepflow_database::{MigrationManager, SqliteDatabase};
+
+    #[derive(Default)]
+    struct RecordingNotifier {
+        received: Mutex<Vec<(AlertStatus, String)>>,
+    }
+
+    #[async_trait::async_trait]
+    impl AlertNotifier for RecordingNotifier {
+        async fn notify(&self, notification: &AlertNotification) -> AlertResult<()> {
+            self.received.lock().unwrap().push((notification.status, notification.rule.name.clone()));
+            Ok(())
+        }
+    }
+
+    async fn record_execution(db: &SqliteDatabase, tenant_id: &TenantId, tool_id: &ToolId, states: &[(&str, i64)]) {
+        let execution_id = uuid::Uuid::new_v4().to_string();
+        let start = Utc::now() - chrono::Duration::seconds(60);
+        for (state, offset_ms) in states {
+            db.execute(
+                "INSERT INTO execution_events (execution_id, tenant_id, tool_id, state, occurred_at) VALUES (?, ?, ?, ?, ?)",
+                &[
+                    Value::String(execution_id.clone()),
+                    Value::String(tenant_id.to_string()),
+                    Value::String(tool_id.to_string()),
+                    Value::String(state.to_string()),
+                    Value::String((start + chrono::Duration::milliseconds(*offset_ms)).to_rfc3339()),
+                ],
+            ).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_alert_fires_once_and_resolves() {
+        let db = Arc::new(SqliteDatabase::new("sqlite::memory:").await.unwrap());
+        MigrationManager::run_migrations(&db).await.unwrap();
+
+        let notifier = Arc::new(RecordingNotifier::default());
+        let manager = AlertManager::new(db.clone()).with_notifier(notifier.clone());
+        let tool_id = ToolId::new();
+        let tenant = TenantId::new();
+        let other_tenant = TenantId::new();
+
+        let rule = AlertRule::new(
+            tenant.clone(),
+            "tool failure rate".to_string(),
+            AlertMetric::FailureRate,
+            Comparison::GreaterThan,
+            0.1,
+            Duration::from_secs(15 * 60),
+        )
+        .for_tool(tool_id.clone())
+        .with_min_samples(2);
+        manager.create_rule(rule).await.unwrap();
+
+        let latency_rule = AlertRule::new(
+            tenant.clone(),
+            "queue wait".to_string(),
+            AlertMetric::QueueWaitP95Ms,
+            Comparison::GreaterThan,
+            30_000.0,
+            Duration::from_secs(15 * 60),
+        );
+        manager.create_rule(latency_rule).await.unwrap();
+
+        // Another tenant's rule would fire on any execution, but only sees its own
+        let other_rule = AlertRule::new(
+            other_tenant.clone(),
+            "other tenant volume".to_string(),
+            AlertMetric::ExecutionCount,
+            Comparison::GreaterThan,
+            0.0,
+            Duration::from_secs(15 * 60),
+        );
+        manager.create_rule(other_rule).await.unwrap();
+        assert_eq!(manager.list_rules(&tenant).await.unwrap().len(), 2);
+        assert_eq!(manager.list_rules(&other_tenant).await.unwrap().len(), 1);
+
+        // A rule that cannot be read (evaluated first) does not stop the others
+        db.execute(
+            "INSERT INTO alert_rules (id, tenant_id, name, metric, comparison, threshold, window_secs, created_at) VALUES ('broken', ?, 'broken', 'unknown_metric', 'greater_than', 1, 60, ?)",
+            &[Value::String(tenant.to_string()), Value::String((Utc::now() - chrono::Duration::hours(1)).to_rfc3339())],
+        ).await.unwrap();
+
+        // A single failure is below min_samples and stays inconclusive
+        record_execution(&db, &tenant, &tool_id, &[("queued", 0), ("running", 100), ("failed", 200)]).await;
+        assert!(manager.evaluate().await.unwrap().is_empty());
+
+        record_execution(&db, &tenant, &tool_id, &[("queued", 0), ("running", 200), ("completed", 500)]).await;
+        let sample = manager.sample(&tenant, AlertMetric::FailureRate, Some(&tool_id), Duration::from_secs(900)).await.unwrap();
+        assert_eq!(sample, MetricSample { value: 0.5, samples: 2 });
+        let isolated = manager.sample(&other_tenant, AlertMetric::FailureRate, Some(&tool_id), Duration::from_secs(900)).await.unwrap();
+        assert_eq!(isolated.samples, 0);
+        let wait = manager.sample(&tenant, AlertMetric::QueueWaitP95Ms, None, Duration::from_secs(900)).await.unwrap();
+        assert_eq!(wait.value, 200.0);
+
+        let fired = manager.evaluate().await.unwrap();
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].status, AlertStatus::Firing);
+        assert_eq!(manager.list_alerts(&tenant, true).await.unwrap().len(), 1);
+
+        // Still breached: deduplicated
+        assert!(manager.evaluate().await.unwrap().is_empty());
+
+        for _ in 0..18 {
+            record_execution(&db, &tenant, &tool_id, &[("queued", 0), ("running", 10), ("completed", 20)]).await;
+        }
+        let resolved = manager.evaluate().await.unwrap();
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].status, AlertStatus::Resolved);
+        assert!(resolved[0].alert.resolved_at.is_some());
+        assert!(manager.list_alerts(&tenant, true).await.unwrap().is_empty());
+
+        let received = notifier.received.lock().unwrap().clone();
+        assert_eq!(received, vec![
+            (AlertStatus::Firing, "tool failure rate".to_string()),
+            (AlertStatus::Resolved, "tool failure rate".to_string()),
+        ]);
+    }
+
+    #[derive(Default)]
+    struct RecordingEmail {
+        sent: Mutex<Vec<(String, String, String)>>,
+    }
+
+    #[async_trait::async_trait]
+    impl EmailTransport for RecordingEmail {
+        async fn send_email(&self, to: &str, subject: &str, body: &str) -> AlertResult<()> {
+            self.sent.lock().unwrap().push((to.to_string(), subject.to_string(), body.to_string()));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_notification_channels() {
+        let db = Arc::new(SqliteDatabase::new("sqlite::memory:").await.unwrap());
+        MigrationManager::run_migrations(&db).await.unwrap();
+        let email = Arc::new(RecordingEmail::default());
+        let service = Arc::new(NotificationService::new(db.clone()).with_email_transport(email.clone()));
+        let tenant = TenantId::new();
+
+        let oncall = service.create_channel(
+            NotificationChannel::new(tenant.clone(), "on-call".to_string(), ChannelTarget::Email {
+                recipients: vec!["oncall@example.com".to_string()],
+            })
+            .with_events(vec!["alert.*".to_string()])
+            .with_templates(Some("Alert {{rule.name}} is {{alert.status}}".to_string()), None),
+        ).await.unwrap();
+        // Nothing listens on the discard port, so deliveries to this channel fail
+        let approvals = service.create_channel(
+            NotificationChannel::new(tenant.clone(), "approvals".to_string(), ChannelTarget::Webhook {
+                url: "http://127.0.0.1:9/hook".to_string(),
+                headers: HashMap::new(),
+            })
+            .with_events(vec!["approval.requested".to_string()]),
+        ).await.unwrap();
+        let invalid = NotificationChannel::new(tenant.clone(), "slack".to_string(), ChannelTarget::Slack {
+            webhook_url: "hooks.slack.com".to_string(),
+        });
+        assert!(service.create_channel(invalid).await.is_err());
+        assert_eq!(service.list_channels(&tenant).await.unwrap().len(), 2);
+        assert!(service.list_channels(&TenantId::new()).await.unwrap().is_empty());
+
+        // Alerts reach the subscribed channel with the channel's subject template
+        let manager = AlertManager::new(db.clone()).with_notifier(service.clone());
+        let rule = AlertRule::new(
+            tenant.clone(),
+            "volume".to_string(),
+            AlertMetric::ExecutionCount,
+            Comparison::GreaterThan,
+            0.0,
+            Duration::from_secs(900),
+        );
+        manager.create_rule(rule).await.unwrap();
+        record_execution(&db, &tenant, &ToolId::new(), &[("queued", 0), ("running", 10), ("completed", 20)]).await;
+        assert_eq!(manager.evaluate().await.unwrap().len(), 1);
+        let sent = email.sent.lock().unwrap().clone();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].0, "oncall@example.com");
+        assert_eq!(sent[0].1, "Alert volume is firing");
+        assert_eq!(sent[0].2, "execution_count is 1.0, threshold 0.0 over 900s");
+
+        let notification = Notification::new(
+            "approval.requested",
+            tenant.clone(),
+            "Approval needed: {{title}}",
+            "",
+            serde_json::json!({"title": "Deploy"}),
+        );
+        let deliveries = service.notify(&notification).await.unwrap();
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].channel_id, approvals.id);
+        assert_eq!(deliveries[0].subject, "Approval needed: Deploy");
+        assert_eq!(deliveries[0].status, DeliveryStatus::Failed);
+        assert!(deliveries[0].error.is_some());
+
+        let history = service.list_deliveries(&tenant, None, 10).await.unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].event, "approval.requested");
+        assert_eq!(history[1].status, DeliveryStatus::Delivered);
+        let history = service.list_deliveries(&tenant, Some(&oncall.id), 10).await.unwrap();
+        assert_eq!(history.len(), 1);
+
+        let test = service.send_test(&tenant, &oncall.id).await.unwrap().unwrap();
+        assert_eq!(test.status, DeliveryStatus::Delivered);
+        assert_eq!(email.sent.lock().unwrap()[1].2, "Channel on-call is set up correctly.");
+        assert!(service.delete_channel(&tenant, &oncall.id).await.unwrap());
+        assert!(service.get_channel(&tenant, &oncall.id).await.unwrap().is_none());
+        assert!(service.send_test(&tenant, &oncall.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_anomaly_detection() {
+        let db = Arc::new(SqliteDatabase::new("sqlite::memory:").await.unwrap());
+        MigrationManager::run_migrations(&db).await.unwrap();
+
+        let detector = AnomalyDetector::new(db).with_config(AnomalyConfig {
+            min_samples: 10,
+            ..AnomalyConfig::default()
+        });
+        let tool_id = ToolId::new();
+        let tenant = TenantId::new();
+
+        // Warm up: durations around 100ms, no failures
+        for i in 0..20 {
+            let duration = 95.0 + (i % 3) as f64 * 5.0;
+            let anomalies = detector.observe(&ExecutionId::new(), tenant.as_str(), &tool_id, duration, true).await.unwrap();
+            assert!(anomalies.is_empty());
+        }
+        let baseline = detector.baseline(&tool_id).await.unwrap().unwrap();
+        assert_eq!(baseline.samples, 20);
+        assert!((baseline.duration_mean_ms - 100.0).abs() < 5.0);
+
+        // Normal jitter is tolerated
+        assert!(detector.observe(&ExecutionId::new(), tenant.as_str(), &tool_id, 104.0, true).await.unwrap().is_empty());
+
+        let slow_execution = ExecutionId::new();
+        let slow = detector.observe(&slow_execution, tenant.as_str(), &tool_id, 400.0, true).await.unwrap();
+        assert_eq!(slow.len(), 1);
+        assert_eq!(slow[0].kind, AnomalyKind::SlowExecution);
+        assert!(slow[0].deviation > 3.0);
+        assert_eq!(slow[0].marker()["kind"], serde_json::json!("slow_execution"));
+
+        // The failure happens in another tenant, which shares the tool baseline but not the feed
+        let other_tenant = TenantId::new();
+        let failed = detector.observe(&ExecutionId::new(), other_tenant.as_str(), &tool_id, 5.0, false).await.unwrap();
+        assert_eq!(failed[0].kind, AnomalyKind::UnexpectedFailure);
+
+        // Failures do not drag the duration baseline down
+        let baseline = detector.baseline(&tool_id).await.unwrap().unwrap();
+        assert!(baseline.duration_mean_ms > 100.0);
+        assert!(baseline.failure_rate > 0.0);
+
+        let feed = detector.list_anomalies(tenant.as_str(), Some(&tool_id), 10).await.unwrap();
+        assert_eq!(feed.len(), 1);
+        assert_eq!(feed[0].execution_id, slow_execution);
+        assert_eq!(feed[0].tenant_id, tenant.as_str());
+        let feed = detector.list_anomalies(other_tenant.as_str(), None, 10).await.unwrap();
+        assert_eq!(feed.len(), 1);
+        assert_eq!(feed[0].kind, AnomalyKind::UnexpectedFailure);
+        assert!(detector.list_anomalies(tenant.as_str(), Some(&ToolId::new()), 10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_payload_metrics_per_tool() {
+        let db = Arc::new(SqliteDatabase::new("sqlite::memory:").await.unwrap());
+        MigrationManager::run_migrations(&db).await.unwrap();
+        let metrics = PayloadMetrics::new(db.clone());
+        let tool_id = ToolId::new();
+        let quiet_tool = ToolId::new();
+
+        let schema = serde_json::json!({
+            "type": "object",
+            "required": ["rows"],
+            "additionalProperties": false,
+            "properties": {
+                "rows": { "type": "array", "items": { "type": "integer" } },
+                "name": { "type": "string" }
+            }
+        });
+        let valid = serde_json::json!({ "rows": [1, 2, 3], "name": "report" });
+        assert!(schema_warnings(&schema, &valid).is_empty());
+        let invalid = serde_json::json!({ "rows": [1, "two"], "extra": true });
+        assert_eq!(schema_warnings(&schema, &invalid), vec![
+            "$.extra: unknown property".to_string(),
+            "$.rows[1]: expected \"integer\"".to_string(),
+        ]);
+        assert_eq!(schema_warnings(&schema, &serde_json::json!({})), vec!["$.rows: required property is missing".to_string()]);
+
+        let output = serde_json::json!({ "pages": [[1, 2], [1, 2, 3, 4, 5]] });
+        let observation = PayloadObservation::measure(&valid, Some(&output), 0);
+        assert_eq!(observation.input_bytes, valid.to_string().len() as u64);
+        assert_eq!(observation.output_bytes, output.to_string().len() as u64);
+        assert_eq!((observation.input_max_array_len, observation.output_max_array_len), (3, 5));
+        assert_eq!(PayloadObservation::measure(&invalid, None, 2).output_bytes, 0);
+
+        let tenant = TenantId::new();
+        for input_bytes in [100, 200, 300] {
+            let observation = PayloadObservation { input_bytes, output_bytes: 50, input_max_array_len: 10, schema_warnings: 0, ..Default::default() };
+            metrics.record(&ExecutionId::new(), tenant.as_str(), &tool_id, &observation).await.unwrap();
+        }
+        let warned = PayloadObservation { input_bytes: 400, output_bytes: 50, input_max_array_len: 40, schema_warnings: 2, ..Default::default() };
+        metrics.record(&ExecutionId::new(), tenant.as_str(), &tool_id, &warned).await.unwrap();
+        metrics.record(&ExecutionId::new(), tenant.as_str(), &quiet_tool, &PayloadObservation::default()).await.unwrap();
+        metrics.record(&ExecutionId::new(), TenantId::new().as_str(), &tool_id, &warned).await.unwrap();
+
+        let summaries = metrics.summaries(tenant.as_str(), None, Duration::from_secs(3600)).await.unwrap();
+        assert_eq!(summaries.len(), 2);
+        let summary = &summaries[0];
+        assert_eq!(summary.tool_id, tool_id);
+        assert_eq!(summary.executions, 4);
+        assert_eq!(summary.input_bytes.mean, 250.0);
+        assert_eq!((summary.input_bytes.p95, summary.input_bytes.max), (400.0, 400.0));
+        assert_eq!(summary.output_bytes.mean, 50.0);
+        assert_eq!(summary.max_input_array_len, 40);
+        assert_eq!((summary.schema_warnings, summary.executions_with_warnings), (2, 1));
+        // Everything was recorded in the newer half of the window
+        assert_eq!(summary.growth_ratio, None);
+
+        // Move the first two executions into the older half
+        db.execute(
+            "UPDATE execution_payload_metrics SET recorded_at = ? WHERE tool_id = ? AND input_bytes <= 200",
+            &[Value::String((Utc::now() - chrono::Duration::minutes(50)).to_rfc3339()), Value::String(tool_id.to_string())],
+        ).await.unwrap();
+        let summary = metrics.summaries(tenant.as_str(), Some(&tool_id), Duration::from_secs(3600)).await.unwrap().remove(0);
+        assert_eq!(summary.growth_ratio, Some(400.0 / 200.0));
+    }
+
+    #[tokio::test]
+    async fn test_slow_execution_log() {
+        let db = Arc::new(SqliteDatabase::new("sqlite::memory:").await.unwrap());
+        MigrationManager::run_migrations(&db).await.unwrap();
+        let log = SlowExecutionLog::new(db.clone()).with_config(SlowExecutionConfig {
+            min_samples: 5,
+            ..SlowExecutionConfig::default()
+        });
+        let detector = AnomalyDetector::new(db);
+        let tool_id = ToolId::new();
+        let tenant = TenantId::new();
+
+        // No baseline yet: nothing to compare against
+        assert!(log.check(&ExecutionId::new(), tenant.as_str(), &tool_id, 60_000.0, true, None).await.unwrap().is_none());
+        for _ in 0..5 {
+            detector.observe(&ExecutionId::new(), tenant.as_str(), &tool_id, 500.0, true).await.unwrap();
+        }
+        let baseline = detector.baseline(&tool_id).await.unwrap().unwrap();
+
+        // Three times the baseline but under the absolute floor
+        assert!(log.check(&ExecutionId::new(), tenant.as_str(), &tool_id, 900.0, true, Some(&baseline)).await.unwrap().is_none());
+        assert!(log.check(&ExecutionId::new(), tenant.as_str(), &tool_id, 1200.0, true, Some(&baseline)).await.unwrap().is_none());
+
+        let execution_id = ExecutionId::new();
+        let slow = log.check(&execution_id, tenant.as_str(), &tool_id, 2000.0, false, Some(&baseline)).await.unwrap().unwrap();
+        assert_eq!(slow.ratio, 4.0);
+        assert_eq!(slow.baseline_mean_ms, 500.0);
+
+        let listed = log.list(tenant.as_str(), Some(&tool_id), 10).await.unwrap();
+        assert_eq!(listed, vec![slow]);
+        assert_eq!(listed[0].execution_id, execution_id);
+        assert!(!listed[0].success);
+        assert!(log.list(TenantId::new().as_str(), None, 10).await.unwrap().is_empty());
+
+        assert_eq!(log.purge(Utc::now() + chrono::Duration::seconds(1)).await.unwrap(), 1);
+        assert!(log.list(tenant.as_str(), None, 10).await.unwrap().is_empty());
+    }
+
+    #[derive(Clone, Default)]
+    struct BufferWriter(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for BufferWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl BufferWriter {
+        fn lines(&self) -> Vec<Value> {
+            String::from_utf8(self.0.lock().unwrap().clone())
+                .unwrap()
+                .lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_json_logging_with_overrides_and_sampling() {
+        let buffer = BufferWriter::default();
+        let config = LoggingConfig {
+            level: "warn".to_string(),
+            modules: [("noisy".to_string(), "info".to_string())].into_iter().collect(),
+            format: LogOutputFormat::Json,
+            sampling: vec![LogSamplingRule {
+                target: "noisy".to_string(),
+                every: 3,
+                max_level: "info".to_string(),
+            }],
+            warn_sink: None,
+        };
+        assert_eq!(config.filter_directives(), "warn,noisy=info");
+
+        let writer = buffer.clone();
+        let (subscriber, handle) = build_subscriber_with_writer(&config, move || writer.clone()).unwrap();
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!(target: "noisy", "request", request_id = "req-1");
+            let _guard = span.enter();
+            for i in 0..6 {
+                tracing::info!(target: "noisy", attempt = i, "polling");
+            }
+            tracing::warn!(target: "noisy", "slow poll");
+            tracing::info!(target: "quiet", "filtered out");
+
+            handle.set_levels("info", &Default::default()).unwrap();
+            tracing::info!(target: "quiet", "now visible");
+        });
+
+        let lines = buffer.lines();
+        let messages: Vec<&str> = lines.iter().map(|l| l["message"].as_str().unwrap()).collect();
+        assert_eq!(messages, vec!["polling", "polling", "slow poll", "now visible"]);
+        assert_eq!(lines[0]["attempt"], 0);
+        assert_eq!(lines[1]["attempt"], 3);
+        assert_eq!(lines[2]["level"], "WARN");
+        assert_eq!(lines[2]["request_id"], "req-1");
+        assert_eq!(lines[2]["spans"][0], "request");
+        assert_eq!(handle.directives(), "info");
+        assert!(handle.set_directives("info,=bogus=").is_err());
+    }
+
+    #[test]
+    fn test_sampling_replaced_at_runtime() {
+        let buffer = BufferWriter::default();
+        let config = LoggingConfig { format: LogOutputFormat::Json, ..Default::default() };
+        let writer = buffer.clone();
+        let (subscriber, handle) = build_subscriber_with_writer(&config, move || writer.clone()).unwrap();
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(target: "chatty", "before");
+            handle
+                .set_sampling(&[LogSamplingRule { target: "chatty".to_string(), every: 2, max_level: "info".to_string() }])
+                .unwrap();
+            for i in 0..4 {
+                tracing::info!(target: "chatty", attempt = i, "sampled");
+            }
+            handle.set_sampling(&[]).unwrap();
+            tracing::info!(target: "chatty", "after");
+        });
+
+        let lines = buffer.lines();
+        let messages: Vec<&str> = lines.iter().map(|l| l["message"].as_str().unwrap()).collect();
+        assert_eq!(messages, vec!["before", "sampled", "sampled", "after"]);
+        assert_eq!(lines[2]["attempt"], 2);
+        assert!(handle.settings().sampling.is_empty());
+        let invalid = LogSamplingRule { target: "chatty".to_string(), every: 0, max_level: "info".to_string() };
+        assert!(handle.set_sampling(&[invalid]).is_err());
+    }
+
+    #[test]
+    fn test_warn_sink_receives_only_warnings() {
+        let path = std::env::temp_dir().join(format!("stepflow-warn-{}.log", uuid::Uuid::new_v4()));
+        let config = LoggingConfig {
+            format: LogOutputFormat::Json,
+            warn_sink: Some(path.clone()),
+            ..Default::default()
+        };
+        let (subscriber, _handle) = build_subscriber_with_writer(&config, std::io::sink).unwrap();
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("routine");
+            tracing::error!(code = 7, "broken");
+        });
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines: Vec<Value> = contents.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0]["message"], "broken");
+        assert_eq!(lines[0]["code"], 7);
+    }
+
+    /// Records published events; events whose id is in `failing` are rejected
+    #[derive(Default)]
+    struct RecordingSink {
+        published: Mutex<Vec<(String, String, Value)>>,
+        failing: Mutex<Vec<String>>,
+    }
+
+    #[async_trait::async_trait]
+    impl EventSink for RecordingSink {
+        async fn publish(&self, topic: &str, event: &CloudEvent, payload: &[u8]) -> AlertResult<()> {
+            if topic != "dead" && self.failing.lock().unwrap().contains(&event.id) {
+                return Err(stepflow_core::MonitoringError::ExportFailed("broker down".to_string()));
+            }
+            let payload = serde_json::from_slice(payload).unwrap();
+            self.published.lock().unwrap().push((topic.to_string(), event.id.clone(), payload));
+            Ok(())
+        }
+    }
+
+    fn sink_config(sources: Vec<EventSource>) -> EventSinkConfig {
+        EventSinkConfig {
+            sources,
+            max_attempts: 2,
+            retry_backoff: Duration::from_millis(1),
+            ..EventSinkConfig::new("analytics", EventSinkKind::Nats, "nats://localhost:4222")
+        }
+    }
+
+    #[tokio::test]
+    async fn test_event_relay_delivers_in_order_and_resumes() {
+        let db = Arc::new(SqliteDatabase::new("sqlite::memory:").await.unwrap());
+        MigrationManager::run_migrations(&db).await.unwrap();
+        let tenant = TenantId::new();
+        let tool_id = ToolId::new();
+        record_execution(&db, &tenant, &tool_id, &[("queued", 0), ("running", 10), ("completed", 50)]).await;
+        db.execute(
+            "INSERT INTO security_events (id, event_type, tenant_id, resource_type, resource_id, action, success, created_at) \
+             VALUES (?, 'login_failed', ?, 'user', 'alice', 'login', 0, ?)",
+            &[Value::String(uuid::Uuid::new_v4().to_string()), Value::String(tenant.to_string()), Value::String(Utc::now().to_rfc3339())],
+        ).await.unwrap();
+
+        let sink = Arc::new(RecordingSink::default());
+        let relay = EventRelay::new(db.clone(), sink.clone(), sink_config(vec![EventSource::Execution, EventSource::Audit]));
+        let report = relay.relay().await.unwrap();
+        assert_eq!(report, RelayReport { delivered: 4, dead_lettered: 0, stalled: vec![] });
+
+        let published = sink.published.lock().unwrap().clone();
+        let ids: Vec<&str> = published.iter().map(|(_, id, _)| id.as_str()).collect();
+        assert_eq!(ids[..3], ["execution-1", "execution-2", "execution-3"]);
+        assert_eq!(published[2].0, "stepflow.execution");
+        assert_eq!(published[2].2["type"], "io.stepflow.execution.completed");
+        assert_eq!(published[2].2["tenantid"], tenant.to_string());
+        assert_eq!(published[3].0, "stepflow.audit");
+        assert_eq!(published[3].2["type"], "io.stepflow.audit.login_failed");
+        assert_eq!(published[3].2["subject"], "alice");
+        assert_eq!(published[3].2["data"]["success"], false);
+
+        // Nothing new: nothing is published again
+        assert_eq!(relay.relay().await.unwrap().delivered, 0);
+        record_execution(&db, &tenant, &tool_id, &[("failed", 0)]).await;
+        assert_eq!(relay.relay().await.unwrap().delivered, 1);
+        assert_eq!(sink.published.lock().unwrap().last().unwrap().1, "execution-4");
+
+        let cursors = relay.cursors().await.unwrap();
+        assert_eq!((cursors[0].position, cursors[0].delivered), (4, 4));
+        assert_eq!((cursors[1].position, cursors[1].delivered), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn test_event_relay_stalls_then_dead_letters() {
+        let db = Arc::new(SqliteDatabase::new("sqlite::memory:").await.unwrap());
+        MigrationManager::run_migrations(&db).await.unwrap();
+        record_execution(&db, &TenantId::new(), &ToolId::new(), &[("queued", 0), ("running", 10)]).await;
+
+        let sink = Arc::new(RecordingSink::default());
+        sink.failing.lock().unwrap().push("execution-1".to_string());
+        let relay = EventRelay::new(db.clone(), sink.clone(), sink_config(vec![EventSource::Execution]));
+
+        // Without a dead-letter topic delivery stops at the failing event
+        let report = relay.relay().await.unwrap();
+        assert_eq!(report, RelayReport { delivered: 0, dead_lettered: 0, stalled: vec![EventSource::Execution] });
+        assert!(sink.published.lock().unwrap().is_empty());
+        let cursor = &relay.cursors().await.unwrap()[0];
+        assert_eq!(cursor.position, 0);
+        assert!(cursor.last_error.as_deref().unwrap().contains("broker down"));
+
+        let relay = EventRelay::new(db.clone(), sink.clone(), EventSinkConfig {
+            dead_letter_topic: Some("dead".to_string()),
+            ..sink_config(vec![EventSource::Execution])
+        });
+        let report = relay.relay().await.unwrap();
+        assert_eq!(report, RelayReport { delivered: 1, dead_lettered: 1, stalled: vec![] });
+
+        let published = sink.published.lock().unwrap().clone();
+        assert_eq!(published[0].0, "dead");
+        assert_eq!(published[0].2["topic"], "stepflow.execution");
+        assert_eq!(published[0].2["attempts"], 2);
+        assert_eq!(published[0].2["event"]["id"], "execution-1");
+        let event = &published[1].2;
+        assert_eq!(published[1].1, "execution-2");
+        assert_eq!(event["specversion"], "1.0");
+        assert_eq!(event["source"], "/stepflow/executions");
+        assert_eq!(event["type"], "io.stepflow.execution.running");
+        assert_eq!(event["data"]["state"], "running");
+        assert!(event["tenantid"].is_string());
+
+        let cursor = &relay.cursors().await.unwrap()[0];
+        assert_eq!((cursor.position, cursor.delivered, cursor.dead_lettered), (2, 1, 1));
+        assert_eq!(cursor.last_error, None);
+    }
+}
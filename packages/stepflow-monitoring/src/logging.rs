@@ -0,0 +1,369 @@
+//! Structured logging setup
+//!
+//! Builds the global `tracing` subscriber for Stepflow binaries: text or JSON
+//! output, `RUST_LOG`-style per-module level overrides and sampling of noisy
+//! targets, both of which can be swapped at runtime through [`LoggingHandle`],
+//! and an optional second sink that receives a copy of every WARN and ERROR record.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::OpenOptions;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use stepflow_core::MonitoringError;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::filter::{EnvFilter, LevelFilter};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, FormattedFields, MakeWriter};
+use tracing_subscriber::layer::{Context, Layered, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{reload, Layer, Registry};
+
+/// Result type for logging setup
+pub type LoggingResult<T> = Result<T, MonitoringError>;
+
+/// Output format of log records
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogOutputFormat {
+    /// Human readable single-line records
+    #[default]
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+/// Keep only one out of every `every` matching records
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogSamplingRule {
+    /// Target prefix the rule applies to, e.g. `stepflow_api::middleware::logging`
+    pub target: String,
+    /// Keep one record out of this many
+    pub every: u64,
+    /// Most severe level that is still sampled; more severe records always pass.
+    /// Defaults to `info`, so WARN and ERROR are never dropped
+    #[serde(default = "default_sampling_level")]
+    pub max_level: String,
+}
+
+fn default_sampling_level() -> String {
+    "info".to_string()
+}
+
+/// Logging configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// Default level for all targets
+    pub level: String,
+    /// Per-module level overrides, e.g. `stepflow_rpc = "debug"`
+    pub modules: BTreeMap<String, String>,
+    pub format: LogOutputFormat,
+    /// Sampling rules for noisy targets
+    pub sampling: Vec<LogSamplingRule>,
+    /// File that additionally receives every WARN and ERROR record
+    pub warn_sink: Option<PathBuf>,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            modules: BTreeMap::new(),
+            format: LogOutputFormat::Text,
+            sampling: Vec::new(),
+            warn_sink: None,
+        }
+    }
+}
+
+impl LoggingConfig {
+    /// Render the level settings as an `EnvFilter` directive string
+    pub fn filter_directives(&self) -> String {
+        filter_directives(&self.level, &self.modules)
+    }
+}
+
+/// Render a default level and module overrides as `RUST_LOG`-style directives
+pub fn filter_directives(level: &str, modules: &BTreeMap<String, String>) -> String {
+    std::iter::once(level.to_string())
+        .chain(modules.iter().map(|(module, level)| format!("{}={}", module, level)))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn parse_filter(directives: &str) -> LoggingResult<EnvFilter> {
+    EnvFilter::builder()
+        .parse(directives)
+        .map_err(|e| MonitoringError::LoggingFailed(format!("Invalid log filter '{}': {}", directives, e)))
+}
+
+fn parse_level(level: &str) -> LoggingResult<Level> {
+    level
+        .parse()
+        .map_err(|_| MonitoringError::LoggingFailed(format!("Invalid log level '{}'", level)))
+}
+
+/// Currently active level filter and sampling rules
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggingSettings {
+    pub directives: String,
+    pub sampling: Vec<LogSamplingRule>,
+}
+
+/// Handle for changing log levels and sampling after the subscriber has been installed
+#[derive(Clone)]
+pub struct LoggingHandle {
+    filter: reload::Handle<EnvFilter, Registry>,
+    directives: Arc<Mutex<String>>,
+    sampling: SharedSampling,
+}
+
+impl LoggingHandle {
+    /// Currently active filter directives
+    pub fn directives(&self) -> String {
+        self.directives.lock().unwrap().clone()
+    }
+
+    /// Replace the default level and module overrides
+    pub fn set_levels(&self, level: &str, modules: &BTreeMap<String, String>) -> LoggingResult<()> {
+        self.set_directives(&filter_directives(level, modules))
+    }
+
+    /// Replace the filter with raw `RUST_LOG`-style directives
+    pub fn set_directives(&self, directives: &str) -> LoggingResult<()> {
+        let filter = parse_filter(directives)?;
+        self.filter
+            .reload(filter)
+            .map_err(|e| MonitoringError::LoggingFailed(format!("Failed to reload log filter: {}", e)))?;
+        *self.directives.lock().unwrap() = directives.to_string();
+        Ok(())
+    }
+
+    /// Currently active sampling rules
+    pub fn sampling(&self) -> Vec<LogSamplingRule> {
+        self.sampling.read().unwrap().iter().map(|compiled| compiled.rule.clone()).collect()
+    }
+
+    /// Replace the sampling rules; an empty list keeps every record
+    pub fn set_sampling(&self, rules: &[LogSamplingRule]) -> LoggingResult<()> {
+        let compiled = compile_sampling(rules)?;
+        *self.sampling.write().unwrap() = compiled;
+        Ok(())
+    }
+
+    /// Snapshot of the active filter and sampling rules
+    pub fn settings(&self) -> LoggingSettings {
+        LoggingSettings {
+            directives: self.directives(),
+            sampling: self.sampling(),
+        }
+    }
+}
+
+type FilteredRegistry = Layered<reload::Layer<EnvFilter, Registry>, Registry>;
+type BoxedLayer = Box<dyn Layer<FilteredRegistry> + Send + Sync>;
+
+/// Build the subscriber described by `config` without installing it
+pub fn build_subscriber(
+    config: &LoggingConfig,
+) -> LoggingResult<(impl Subscriber + Send + Sync + for<'a> LookupSpan<'a>, LoggingHandle)> {
+    build_subscriber_with_writer(config, std::io::stdout)
+}
+
+/// Build the subscriber with a custom main writer, e.g. for tests
+pub fn build_subscriber_with_writer<W>(
+    config: &LoggingConfig,
+    writer: W,
+) -> LoggingResult<(impl Subscriber + Send + Sync + for<'a> LookupSpan<'a>, LoggingHandle)>
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let directives = config.filter_directives();
+    let (filter, handle) = reload::Layer::new(parse_filter(&directives)?);
+
+    // Always installed, even without rules, so sampling can be enabled at runtime
+    let sampling: SharedSampling = Arc::new(RwLock::new(compile_sampling(&config.sampling)?));
+    let mut layers: Vec<BoxedLayer> = vec![Box::new(SamplingLayer { rules: sampling.clone() })];
+    layers.push(format_layer(config.format, writer));
+    if let Some(path) = &config.warn_sink {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| MonitoringError::LoggingFailed(format!("Failed to open {}: {}", path.display(), e)))?;
+        layers.push(format_layer(config.format, Mutex::new(file)).with_filter(LevelFilter::WARN).boxed());
+    }
+
+    let subscriber = Registry::default().with(filter).with(layers);
+    let handle = LoggingHandle {
+        filter: handle,
+        directives: Arc::new(Mutex::new(directives)),
+        sampling,
+    };
+    Ok((subscriber, handle))
+}
+
+/// Install the global subscriber described by `config`
+pub fn init_logging(config: &LoggingConfig) -> LoggingResult<LoggingHandle> {
+    let (subscriber, handle) = build_subscriber(config)?;
+    tracing::subscriber::set_global_default(subscriber)
+        .map_err(|e| MonitoringError::LoggingFailed(format!("Logging already initialized: {}", e)))?;
+    Ok(handle)
+}
+
+fn format_layer<W>(format: LogOutputFormat, writer: W) -> BoxedLayer
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    match format {
+        LogOutputFormat::Text => tracing_subscriber::fmt::layer().with_writer(writer).boxed(),
+        LogOutputFormat::Json => tracing_subscriber::fmt::layer()
+            .fmt_fields(JsonFields)
+            .event_format(JsonFormat)
+            .with_writer(writer)
+            .boxed(),
+    }
+}
+
+/// A sampling rule with its parsed level and record counter
+struct CompiledSamplingRule {
+    rule: LogSamplingRule,
+    max_level: Level,
+    counter: AtomicU64,
+}
+
+type SharedSampling = Arc<RwLock<Vec<CompiledSamplingRule>>>;
+
+fn compile_sampling(rules: &[LogSamplingRule]) -> LoggingResult<Vec<CompiledSamplingRule>> {
+    rules
+        .iter()
+        .map(|rule| {
+            if rule.every == 0 {
+                return Err(MonitoringError::LoggingFailed(format!(
+                    "Sampling rate for '{}' must be at least 1",
+                    rule.target
+                )));
+            }
+            Ok(CompiledSamplingRule {
+                rule: rule.clone(),
+                max_level: parse_level(&rule.max_level)?,
+                counter: AtomicU64::new(0),
+            })
+        })
+        .collect()
+}
+
+/// Drops all but one out of every N records from noisy targets
+struct SamplingLayer {
+    rules: SharedSampling,
+}
+
+impl<S: Subscriber> Layer<S> for SamplingLayer {
+    fn event_enabled(&self, event: &Event<'_>, _ctx: Context<'_, S>) -> bool {
+        let metadata = event.metadata();
+        let rules = self.rules.read().unwrap();
+        // Level ordering: ERROR < WARN < INFO < DEBUG < TRACE
+        let rule = rules.iter().find(|compiled| {
+            metadata.target().starts_with(compiled.rule.target.as_str()) && metadata.level() >= &compiled.max_level
+        });
+        match rule {
+            Some(compiled) => compiled.counter.fetch_add(1, Ordering::Relaxed) % compiled.rule.every == 0,
+            None => true,
+        }
+    }
+}
+
+/// Records span and event fields as a JSON object
+struct JsonFields;
+
+impl<'w> FormatFields<'w> for JsonFields {
+    fn format_fields<R: tracing_subscriber::field::RecordFields>(&self, mut writer: Writer<'w>, fields: R) -> fmt::Result {
+        let mut visitor = JsonVisitor::default();
+        fields.record(&mut visitor);
+        write!(writer, "{}", Value::Object(visitor.0))
+    }
+
+    fn add_fields(&self, current: &'w mut FormattedFields<Self>, fields: &tracing::span::Record<'_>) -> fmt::Result {
+        let mut visitor = JsonVisitor(serde_json::from_str(&current.fields).unwrap_or_default());
+        fields.record(&mut visitor);
+        current.fields = Value::Object(visitor.0).to_string();
+        Ok(())
+    }
+}
+
+/// Writes each event as a single JSON line including the fields of its spans
+struct JsonFormat;
+
+impl<S, N> FormatEvent<S, N> for JsonFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(&self, ctx: &FmtContext<'_, S, N>, mut writer: Writer<'_>, event: &Event<'_>) -> fmt::Result {
+        let metadata = event.metadata();
+        let mut record = Map::new();
+        record.insert(
+            "timestamp".to_string(),
+            Value::String(chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)),
+        );
+        record.insert("level".to_string(), Value::String(metadata.level().to_string()));
+        record.insert("target".to_string(), Value::String(metadata.target().to_string()));
+
+        // Outer spans first so inner spans win on conflicting field names
+        if let Some(scope) = ctx.event_scope() {
+            let mut span_fields = Map::new();
+            let mut span_names = Vec::new();
+            for span in scope.from_root() {
+                span_names.push(Value::String(span.name().to_string()));
+                if let Some(fields) = span.extensions().get::<FormattedFields<N>>() {
+                    if let Ok(Value::Object(fields)) = serde_json::from_str::<Value>(&fields.fields) {
+                        span_fields.extend(fields);
+                    }
+                }
+            }
+            record.extend(span_fields);
+            record.insert("spans".to_string(), Value::Array(span_names));
+        }
+
+        let mut visitor = JsonVisitor::default();
+        event.record(&mut visitor);
+        record.extend(visitor.0);
+
+        writeln!(writer, "{}", Value::Object(record))
+    }
+}
+
+#[derive(Default)]
+struct JsonVisitor(Map<String, Value>);
+
+impl Visit for JsonVisitor {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.insert(field.name().to_string(), Value::String(format!("{:?}", value)));
+    }
+}
@@ -0,0 +1,508 @@
+//! Notification channels
+//!
+//! Tenants route alerts, approval requests and other events to Slack, email or a
+//! plain webhook by configuring [`NotificationChannel`]s. A channel subscribes to
+//! event types, either exact names like `approval.requested` or prefixes like
+//! `alert.*`, and may replace the default subject and body templates. Templates use
+//! `{{path}}` placeholders resolved against the event data, e.g. `{{rule.name}}`.
+//!
+//! Every attempt is recorded in `notification_deliveries`, so admins can see what
+//! was sent where and why a delivery failed. A failing channel never stops delivery
+//! to the others. Email goes through an [`EmailTransport`] supplied by the host,
+//! since the SMTP settings live with the API server.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use stepflow_core::{Database, MonitoringError, TenantId, ValidationError};
+use stepflow_database::SqliteDatabase;
+use tracing::warn;
+
+use crate::alerting::{parse_time, text, AlertNotification, AlertNotifier, AlertResult, AlertStatus};
+
+/// Where a channel delivers to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChannelTarget {
+    /// Slack incoming webhook
+    Slack { webhook_url: String },
+    /// Email to every recipient through the host's [`EmailTransport`]
+    Email { recipients: Vec<String> },
+    /// JSON POST of the rendered message and the event data
+    Webhook {
+        url: String,
+        #[serde(default)]
+        headers: HashMap<String, String>,
+    },
+}
+
+impl ChannelTarget {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ChannelTarget::Slack { .. } => "slack",
+            ChannelTarget::Email { .. } => "email",
+            ChannelTarget::Webhook { .. } => "webhook",
+        }
+    }
+}
+
+/// A tenant's notification channel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationChannel {
+    pub id: String,
+    pub tenant_id: TenantId,
+    pub name: String,
+    pub target: ChannelTarget,
+    /// Event types delivered to the channel; `*` or a trailing `.*` match several, empty matches all
+    pub events: Vec<String>,
+    /// Replaces the event's default subject
+    pub subject_template: Option<String>,
+    /// Replaces the event's default body
+    pub body_template: Option<String>,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl NotificationChannel {
+    /// Create an enabled channel receiving every event
+    pub fn new(tenant_id: TenantId, name: String, target: ChannelTarget) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            tenant_id,
+            name,
+            target,
+            events: Vec::new(),
+            subject_template: None,
+            body_template: None,
+            enabled: true,
+            created_at: Utc::now(),
+        }
+    }
+
+    pub fn with_events(mut self, events: Vec<String>) -> Self {
+        self.events = events;
+        self
+    }
+
+    pub fn with_templates(mut self, subject: Option<String>, body: Option<String>) -> Self {
+        self.subject_template = subject;
+        self.body_template = body;
+        self
+    }
+
+    /// Whether events of type `event` are delivered to this channel
+    pub fn subscribes_to(&self, event: &str) -> bool {
+        self.events.is_empty()
+            || self.events.iter().any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => event.starts_with(prefix),
+                None => pattern == event,
+            })
+    }
+
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        if self.name.trim().is_empty() {
+            return Err(ValidationError::RequiredFieldMissing("name".to_string()));
+        }
+        let check_url = |url: &str| {
+            if url.starts_with("https://") || url.starts_with("http://") {
+                Ok(())
+            } else {
+                Err(ValidationError::InvalidFormat(format!("'{}' is not an http(s) URL", url)))
+            }
+        };
+        match &self.target {
+            ChannelTarget::Slack { webhook_url } => check_url(webhook_url),
+            ChannelTarget::Webhook { url, .. } => check_url(url),
+            ChannelTarget::Email { recipients } if recipients.is_empty() => {
+                Err(ValidationError::RequiredFieldMissing("recipients".to_string()))
+            }
+            ChannelTarget::Email { recipients } => match recipients.iter().find(|r| !r.contains('@')) {
+                Some(recipient) => Err(ValidationError::InvalidFormat(format!("'{}' is not an email address", recipient))),
+                None => Ok(()),
+            },
+        }
+    }
+}
+
+/// An event to notify a tenant about
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notification {
+    /// Event type channels subscribe to, e.g. `alert.firing`
+    pub event: String,
+    pub tenant_id: TenantId,
+    /// Default subject template
+    pub subject: String,
+    /// Default body template
+    pub body: String,
+    /// Values the templates are rendered against
+    pub data: Value,
+}
+
+impl Notification {
+    pub fn new(event: impl Into<String>, tenant_id: TenantId, subject: impl Into<String>, body: impl Into<String>, data: Value) -> Self {
+        Self {
+            event: event.into(),
+            tenant_id,
+            subject: subject.into(),
+            body: body.into(),
+            data,
+        }
+    }
+
+    /// Render `template` against the event data plus `event` and `tenant_id`
+    pub fn render(&self, template: &str) -> String {
+        render_template(template, &|path| match path {
+            "event" => Some(Value::String(self.event.clone())),
+            "tenant_id" => Some(Value::String(self.tenant_id.to_string())),
+            _ => lookup(&self.data, path).cloned(),
+        })
+    }
+}
+
+impl From<&AlertNotification> for Notification {
+    fn from(notification: &AlertNotification) -> Self {
+        let (subject, body) = match notification.status {
+            AlertStatus::Firing => (
+                "[FIRING] {{rule.name}}",
+                "{{rule.metric}} is {{current_value}}, threshold {{rule.threshold}} over {{rule.window_secs}}s",
+            ),
+            AlertStatus::Resolved => (
+                "[RESOLVED] {{rule.name}}",
+                "{{rule.metric}} is back to {{current_value}}, threshold {{rule.threshold}}",
+            ),
+        };
+        Self::new(
+            notification.event_name(),
+            notification.rule.tenant_id.clone(),
+            subject,
+            body,
+            serde_json::to_value(notification).unwrap_or(Value::Null),
+        )
+    }
+}
+
+/// Replace `{{path}}` placeholders; strings are inserted as is, other values as JSON
+/// and unknown paths as nothing
+fn render_template(template: &str, resolve: &dyn Fn(&str) -> Option<Value>) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        output.push_str(&rest[..start]);
+        match resolve(rest[start + 2..start + end].trim()) {
+            Some(Value::String(text)) => output.push_str(&text),
+            Some(Value::Null) | None => {}
+            Some(value) => output.push_str(&value.to_string()),
+        }
+        rest = &rest[start + end + 2..];
+    }
+    output.push_str(rest);
+    output
+}
+
+fn lookup<'a>(data: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(data, |value, key| match value {
+        Value::Array(items) => items.get(key.parse::<usize>().ok()?),
+        _ => value.get(key),
+    })
+}
+
+/// Sends email for email channels
+#[async_trait]
+pub trait EmailTransport: Send + Sync {
+    async fn send_email(&self, to: &str, subject: &str, body: &str) -> AlertResult<()>;
+}
+
+/// Outcome of one delivery attempt
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    Delivered,
+    Failed,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryStatus::Delivered => "delivered",
+            DeliveryStatus::Failed => "failed",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "delivered" => Some(DeliveryStatus::Delivered),
+            "failed" => Some(DeliveryStatus::Failed),
+            _ => None,
+        }
+    }
+}
+
+/// Record of a notification sent to one channel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationDelivery {
+    pub id: String,
+    pub channel_id: String,
+    pub tenant_id: TenantId,
+    pub event: String,
+    pub subject: String,
+    pub status: DeliveryStatus,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Delivers notifications to tenants' channels and manages the channels
+pub struct NotificationService {
+    db: Arc<SqliteDatabase>,
+    client: reqwest::Client,
+    email: Option<Arc<dyn EmailTransport>>,
+}
+
+impl NotificationService {
+    pub fn new(db: Arc<SqliteDatabase>) -> Self {
+        Self {
+            db,
+            client: reqwest::Client::new(),
+            email: None,
+        }
+    }
+
+    /// Send email for email channels; without a transport their deliveries fail
+    pub fn with_email_transport(mut self, email: Arc<dyn EmailTransport>) -> Self {
+        self.email = Some(email);
+        self
+    }
+
+    /// Create a channel
+    pub async fn create_channel(&self, channel: NotificationChannel) -> AlertResult<NotificationChannel> {
+        channel.validate().map_err(|e| MonitoringError::ConfigurationError(e.to_string()))?;
+        let params = vec![
+            Value::String(channel.id.clone()),
+            Value::String(channel.tenant_id.to_string()),
+            Value::String(channel.name.clone()),
+            to_json(&channel.target)?,
+            to_json(&channel.events)?,
+            channel.subject_template.clone().map(Value::String).unwrap_or(Value::Null),
+            channel.body_template.clone().map(Value::String).unwrap_or(Value::Null),
+            Value::from(channel.enabled as i64),
+            Value::String(channel.created_at.to_rfc3339()),
+        ];
+        self.execute(
+            "INSERT INTO notification_channels (id, tenant_id, name, target, events, subject_template, body_template, enabled, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            &params,
+        ).await?;
+        Ok(channel)
+    }
+
+    /// List a tenant's channels
+    pub async fn list_channels(&self, tenant_id: &TenantId) -> AlertResult<Vec<NotificationChannel>> {
+        let rows = self.execute(
+            "SELECT * FROM notification_channels WHERE tenant_id = ? ORDER BY created_at ASC",
+            &[Value::String(tenant_id.to_string())],
+        ).await?;
+        rows.iter().map(row_to_channel).collect()
+    }
+
+    pub async fn get_channel(&self, tenant_id: &TenantId, channel_id: &str) -> AlertResult<Option<NotificationChannel>> {
+        let rows = self.execute(
+            "SELECT * FROM notification_channels WHERE id = ? AND tenant_id = ?",
+            &[Value::String(channel_id.to_string()), Value::String(tenant_id.to_string())],
+        ).await?;
+        rows.first().map(row_to_channel).transpose()
+    }
+
+    /// Delete one of a tenant's channels and its delivery history
+    pub async fn delete_channel(&self, tenant_id: &TenantId, channel_id: &str) -> AlertResult<bool> {
+        let params = [Value::String(channel_id.to_string()), Value::String(tenant_id.to_string())];
+        self.execute("DELETE FROM notification_deliveries WHERE channel_id = ? AND tenant_id = ?", &params).await?;
+        let result = self.db.execute("DELETE FROM notification_channels WHERE id = ? AND tenant_id = ?", &params)
+            .await
+            .map_err(|e| MonitoringError::ExportFailed(e.to_string()))?;
+        Ok(result.rows_affected > 0)
+    }
+
+    /// Deliver `notification` to every enabled channel of its tenant subscribed to the event
+    pub async fn notify(&self, notification: &Notification) -> AlertResult<Vec<NotificationDelivery>> {
+        let mut deliveries = Vec::new();
+        for channel in self.list_channels(&notification.tenant_id).await? {
+            if channel.enabled && channel.subscribes_to(&notification.event) {
+                deliveries.push(self.deliver(&channel, notification).await?);
+            }
+        }
+        Ok(deliveries)
+    }
+
+    /// Send a test message to one channel regardless of its subscriptions
+    pub async fn send_test(&self, tenant_id: &TenantId, channel_id: &str) -> AlertResult<Option<NotificationDelivery>> {
+        let Some(channel) = self.get_channel(tenant_id, channel_id).await? else {
+            return Ok(None);
+        };
+        let notification = Notification::new(
+            "notification.test",
+            tenant_id.clone(),
+            "Stepflow test notification",
+            "Channel {{channel}} is set up correctly.",
+            json!({ "channel": channel.name }),
+        );
+        Ok(Some(self.deliver(&channel, &notification).await?))
+    }
+
+    /// A tenant's deliveries, newest first, optionally for one channel
+    pub async fn list_deliveries(
+        &self,
+        tenant_id: &TenantId,
+        channel_id: Option<&str>,
+        limit: usize,
+    ) -> AlertResult<Vec<NotificationDelivery>> {
+        let mut sql = "SELECT * FROM notification_deliveries WHERE tenant_id = ?".to_string();
+        let mut params = vec![Value::String(tenant_id.to_string())];
+        if let Some(channel_id) = channel_id {
+            sql.push_str(" AND channel_id = ?");
+            params.push(Value::String(channel_id.to_string()));
+        }
+        sql.push_str(" ORDER BY created_at DESC, rowid DESC LIMIT ?");
+        params.push(Value::from(limit as i64));
+        let rows = self.execute(&sql, &params).await?;
+        rows.iter().map(row_to_delivery).collect()
+    }
+
+    /// Render and send the notification to one channel, recording the outcome
+    async fn deliver(&self, channel: &NotificationChannel, notification: &Notification) -> AlertResult<NotificationDelivery> {
+        let subject = notification.render(channel.subject_template.as_deref().unwrap_or(&notification.subject));
+        let body = notification.render(channel.body_template.as_deref().unwrap_or(&notification.body));
+        let error = self.send(&channel.target, notification, &subject, &body).await.err().map(|e| {
+            warn!("Failed to deliver {} to channel {}: {}", notification.event, channel.id, e);
+            e.to_string()
+        });
+
+        let delivery = NotificationDelivery {
+            id: uuid::Uuid::new_v4().to_string(),
+            channel_id: channel.id.clone(),
+            tenant_id: channel.tenant_id.clone(),
+            event: notification.event.clone(),
+            subject,
+            status: if error.is_some() { DeliveryStatus::Failed } else { DeliveryStatus::Delivered },
+            error,
+            created_at: Utc::now(),
+        };
+        self.execute(
+            "INSERT INTO notification_deliveries (id, channel_id, tenant_id, event, subject, status, error, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            &[
+                Value::String(delivery.id.clone()),
+                Value::String(delivery.channel_id.clone()),
+                Value::String(delivery.tenant_id.to_string()),
+                Value::String(delivery.event.clone()),
+                Value::String(delivery.subject.clone()),
+                Value::String(delivery.status.as_str().to_string()),
+                delivery.error.clone().map(Value::String).unwrap_or(Value::Null),
+                Value::String(delivery.created_at.to_rfc3339()),
+            ],
+        ).await?;
+        Ok(delivery)
+    }
+
+    async fn send(&self, target: &ChannelTarget, notification: &Notification, subject: &str, body: &str) -> AlertResult<()> {
+        match target {
+            ChannelTarget::Slack { webhook_url } => {
+                self.post(webhook_url, &HashMap::new(), &json!({ "text": format!("*{}*\n{}", subject, body) })).await
+            }
+            ChannelTarget::Webhook { url, headers } => {
+                let payload = json!({
+                    "event": notification.event,
+                    "tenant_id": notification.tenant_id,
+                    "subject": subject,
+                    "body": body,
+                    "data": notification.data,
+                });
+                self.post(url, headers, &payload).await
+            }
+            ChannelTarget::Email { recipients } => {
+                let email = self.email.as_ref().ok_or_else(|| {
+                    MonitoringError::BackendUnavailable("No email transport configured".to_string())
+                })?;
+                for recipient in recipients {
+                    email.send_email(recipient, subject, body).await?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    async fn post(&self, url: &str, headers: &HashMap<String, String>, payload: &Value) -> AlertResult<()> {
+        let mut request = self.client.post(url).json(payload).timeout(Duration::from_secs(10));
+        for (name, value) in headers {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| MonitoringError::ExportFailed(format!("Webhook {} failed: {}", url, e)))?;
+        if !response.status().is_success() {
+            return Err(MonitoringError::ExportFailed(format!("Webhook {} returned {}", url, response.status())));
+        }
+        Ok(())
+    }
+
+    async fn execute(&self, sql: &str, params: &[Value]) -> AlertResult<Vec<HashMap<String, Value>>> {
+        self.db
+            .execute(sql, params)
+            .await
+            .map(|result| result.rows)
+            .map_err(|e| MonitoringError::ExportFailed(e.to_string()))
+    }
+}
+
+/// Delivers alerts to the channels of the rule's tenant
+#[async_trait]
+impl AlertNotifier for NotificationService {
+    async fn notify(&self, notification: &AlertNotification) -> AlertResult<()> {
+        NotificationService::notify(self, &Notification::from(notification)).await.map(|_| ())
+    }
+}
+
+fn to_json<T: Serialize>(value: &T) -> AlertResult<Value> {
+    serde_json::to_string(value)
+        .map(Value::String)
+        .map_err(|e| MonitoringError::ConfigurationError(e.to_string()))
+}
+
+fn json_column<T: serde::de::DeserializeOwned>(row: &HashMap<String, Value>, key: &str) -> Option<T> {
+    text(row, key).and_then(|value| serde_json::from_str(value).ok())
+}
+
+fn invalid_row(what: &str) -> MonitoringError {
+    MonitoringError::ConfigurationError(format!("Invalid {} row", what))
+}
+
+fn row_to_channel(row: &HashMap<String, Value>) -> AlertResult<NotificationChannel> {
+    Ok(NotificationChannel {
+        id: text(row, "id").ok_or_else(|| invalid_row("notification channel"))?.to_string(),
+        tenant_id: TenantId::from_string(text(row, "tenant_id").unwrap_or_default().to_string()),
+        name: text(row, "name").unwrap_or_default().to_string(),
+        target: json_column(row, "target").ok_or_else(|| invalid_row("notification channel"))?,
+        events: json_column(row, "events").unwrap_or_default(),
+        subject_template: text(row, "subject_template").map(str::to_string),
+        body_template: text(row, "body_template").map(str::to_string),
+        enabled: row.get("enabled").and_then(Value::as_i64).unwrap_or(1) != 0,
+        created_at: text(row, "created_at").and_then(parse_time).unwrap_or_else(Utc::now),
+    })
+}
+
+fn row_to_delivery(row: &HashMap<String, Value>) -> AlertResult<NotificationDelivery> {
+    Ok(NotificationDelivery {
+        id: text(row, "id").ok_or_else(|| invalid_row("notification delivery"))?.to_string(),
+        channel_id: text(row, "channel_id").unwrap_or_default().to_string(),
+        tenant_id: TenantId::from_string(text(row, "tenant_id").unwrap_or_default().to_string()),
+        event: text(row, "event").unwrap_or_default().to_string(),
+        subject: text(row, "subject").unwrap_or_default().to_string(),
+        status: text(row, "status").and_then(DeliveryStatus::parse).ok_or_else(|| invalid_row("notification delivery"))?,
+        error: text(row, "error").map(str::to_string),
+        created_at: text(row, "created_at").and_then(parse_time).unwrap_or_else(Utc::now),
+    })
+}
@@ -0,0 +1,284 @@
+//! Execution payload metrics
+//!
+//! Every finished execution records the characteristics of its payloads in
+//! `execution_payload_metrics`: input and output size in bytes, the largest
+//! array found in each, and how many warnings the input produced against the
+//! tool's input schema. Aggregated per tool over a window, the growth ratio
+//! between the older and the newer half of the window shows tools whose
+//! payloads keep growing before they start hitting timeouts or output limits.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use stepflow_core::{Database, ExecutionId, MonitoringError, ToolId};
+use stepflow_database::SqliteDatabase;
+use crate::alerting::{percentile, AlertResult};
+
+/// Payload characteristics of one execution
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PayloadObservation {
+    pub input_bytes: u64,
+    pub output_bytes: u64,
+    /// Length of the largest array anywhere in the input
+    pub input_max_array_len: u64,
+    pub output_max_array_len: u64,
+    /// Deviations of the input from the tool's input schema
+    pub schema_warnings: u64,
+}
+
+impl PayloadObservation {
+    /// Measure serialized sizes and array cardinalities; a missing output counts as empty
+    pub fn measure(input: &Value, output: Option<&Value>, schema_warnings: usize) -> Self {
+        Self {
+            input_bytes: json_size(input),
+            output_bytes: output.map(json_size).unwrap_or(0),
+            input_max_array_len: max_array_len(input),
+            output_max_array_len: output.map(max_array_len).unwrap_or(0),
+            schema_warnings: schema_warnings as u64,
+        }
+    }
+}
+
+fn json_size(value: &Value) -> u64 {
+    serde_json::to_vec(value).map(|bytes| bytes.len() as u64).unwrap_or(0)
+}
+
+fn max_array_len(value: &Value) -> u64 {
+    match value {
+        Value::Array(items) => items.iter().map(max_array_len).fold(items.len() as u64, u64::max),
+        Value::Object(fields) => fields.values().map(max_array_len).max().unwrap_or(0),
+        _ => 0,
+    }
+}
+
+/// Where `value` deviates from `schema`
+///
+/// Only the common keywords are checked: `type`, `required`, `properties`,
+/// `additionalProperties: false` and `items`. Deviations are warnings, not
+/// errors: the execution still runs.
+pub fn schema_warnings(schema: &Value, value: &Value) -> Vec<String> {
+    let mut warnings = Vec::new();
+    check_schema(schema, value, "$", &mut warnings);
+    warnings
+}
+
+fn check_schema(schema: &Value, value: &Value, path: &str, warnings: &mut Vec<String>) {
+    if let Some(expected) = schema.get("type") {
+        let matches = match expected {
+            Value::String(name) => type_matches(name, value),
+            Value::Array(names) => names.iter().filter_map(Value::as_str).any(|name| type_matches(name, value)),
+            _ => true,
+        };
+        if !matches {
+            warnings.push(format!("{}: expected {}", path, expected));
+            return;
+        }
+    }
+
+    if let Value::Object(fields) = value {
+        let properties = schema.get("properties").and_then(Value::as_object);
+        for name in schema.get("required").and_then(Value::as_array).into_iter().flatten().filter_map(Value::as_str) {
+            if !fields.contains_key(name) {
+                warnings.push(format!("{}.{}: required property is missing", path, name));
+            }
+        }
+        for (name, field) in fields {
+            match properties.and_then(|properties| properties.get(name)) {
+                Some(property) => check_schema(property, field, &format!("{}.{}", path, name), warnings),
+                None if schema.get("additionalProperties") == Some(&Value::Bool(false)) => {
+                    warnings.push(format!("{}.{}: unknown property", path, name));
+                }
+                None => {}
+            }
+        }
+    }
+
+    if let (Value::Array(items), Some(item_schema)) = (value, schema.get("items")) {
+        for (index, item) in items.iter().enumerate() {
+            check_schema(item_schema, item, &format!("{}[{}]", path, index), warnings);
+        }
+    }
+}
+
+fn type_matches(name: &str, value: &Value) -> bool {
+    match name {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        _ => true,
+    }
+}
+
+/// Mean, 95th percentile and maximum of a size
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct SizeStats {
+    pub mean: f64,
+    pub p95: f64,
+    pub max: f64,
+}
+
+impl SizeStats {
+    fn from_values(values: &[f64]) -> Self {
+        if values.is_empty() {
+            return Self::default();
+        }
+        Self {
+            mean: values.iter().sum::<f64>() / values.len() as f64,
+            p95: percentile(values.to_vec(), 0.95),
+            max: values.iter().copied().fold(0.0, f64::max),
+        }
+    }
+}
+
+/// Payload metrics of a tool over a window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolPayloadSummary {
+    pub tool_id: ToolId,
+    pub executions: u64,
+    pub input_bytes: SizeStats,
+    pub output_bytes: SizeStats,
+    pub max_input_array_len: u64,
+    pub max_output_array_len: u64,
+    pub schema_warnings: u64,
+    pub executions_with_warnings: u64,
+    /// Mean payload size (input + output) of the newer half of the window divided
+    /// by that of the older half; `None` until both halves have executions
+    pub growth_ratio: Option<f64>,
+}
+
+/// Records and aggregates per-execution payload metrics
+pub struct PayloadMetrics {
+    db: Arc<SqliteDatabase>,
+}
+
+impl PayloadMetrics {
+    pub fn new(db: Arc<SqliteDatabase>) -> Self {
+        Self { db }
+    }
+
+    pub async fn record(
+        &self,
+        execution_id: &ExecutionId,
+        tenant_id: &str,
+        tool_id: &ToolId,
+        observation: &PayloadObservation,
+    ) -> AlertResult<()> {
+        self.execute(
+            r#"
+            INSERT INTO execution_payload_metrics (
+                execution_id, tenant_id, tool_id, input_bytes, output_bytes,
+                input_max_array_len, output_max_array_len, schema_warnings, recorded_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+            &[
+                Value::String(execution_id.to_string()),
+                Value::String(tenant_id.to_string()),
+                Value::String(tool_id.to_string()),
+                Value::from(observation.input_bytes as i64),
+                Value::from(observation.output_bytes as i64),
+                Value::from(observation.input_max_array_len as i64),
+                Value::from(observation.output_max_array_len as i64),
+                Value::from(observation.schema_warnings as i64),
+                Value::String(Utc::now().to_rfc3339()),
+            ],
+        ).await?;
+        Ok(())
+    }
+
+    /// Per-tool summaries of a tenant over the last `window`, largest mean input first
+    pub async fn summaries(
+        &self,
+        tenant_id: &str,
+        tool_id: Option<&ToolId>,
+        window: Duration,
+    ) -> AlertResult<Vec<ToolPayloadSummary>> {
+        let now = Utc::now();
+        let window = chrono::Duration::from_std(window).unwrap_or(chrono::Duration::MAX);
+        let since = now.checked_sub_signed(window).unwrap_or(DateTime::<Utc>::MIN_UTC);
+        let mut sql = r#"
+            SELECT tool_id, input_bytes, output_bytes, input_max_array_len, output_max_array_len,
+                   schema_warnings, recorded_at
+            FROM execution_payload_metrics
+            WHERE tenant_id = ? AND recorded_at >= ?
+        "#.to_string();
+        let mut params = vec![Value::String(tenant_id.to_string()), Value::String(since.to_rfc3339())];
+        if let Some(tool_id) = tool_id {
+            sql.push_str(" AND tool_id = ?");
+            params.push(Value::String(tool_id.to_string()));
+        }
+        sql.push_str(" ORDER BY recorded_at");
+
+        let mut by_tool: HashMap<String, Vec<(DateTime<Utc>, PayloadObservation)>> = HashMap::new();
+        for row in self.execute(&sql, &params).await? {
+            let number = |key: &str| row.get(key).and_then(Value::as_u64).unwrap_or(0);
+            let (Some(tool), Some(recorded_at)) = (
+                row.get("tool_id").and_then(Value::as_str),
+                row.get("recorded_at").and_then(Value::as_str).and_then(parse_time),
+            ) else {
+                continue;
+            };
+            by_tool.entry(tool.to_string()).or_default().push((recorded_at, PayloadObservation {
+                input_bytes: number("input_bytes"),
+                output_bytes: number("output_bytes"),
+                input_max_array_len: number("input_max_array_len"),
+                output_max_array_len: number("output_max_array_len"),
+                schema_warnings: number("schema_warnings"),
+            }));
+        }
+
+        let midpoint = since + (now - since) / 2;
+        let mut summaries: Vec<ToolPayloadSummary> = by_tool
+            .into_iter()
+            .map(|(tool, observations)| summarize(ToolId::from_string(tool), &observations, midpoint))
+            .collect();
+        summaries.sort_by(|a, b| b.input_bytes.mean.total_cmp(&a.input_bytes.mean));
+        Ok(summaries)
+    }
+
+    async fn execute(&self, sql: &str, params: &[Value]) -> AlertResult<Vec<HashMap<String, Value>>> {
+        self.db.execute(sql, params)
+            .await
+            .map(|result| result.rows)
+            .map_err(|e| MonitoringError::MetricsCollectionFailed(e.to_string()))
+    }
+}
+
+fn summarize(tool_id: ToolId, observations: &[(DateTime<Utc>, PayloadObservation)], midpoint: DateTime<Utc>) -> ToolPayloadSummary {
+    let inputs: Vec<f64> = observations.iter().map(|(_, o)| o.input_bytes as f64).collect();
+    let outputs: Vec<f64> = observations.iter().map(|(_, o)| o.output_bytes as f64).collect();
+    let mean_total = |newer: bool| {
+        let totals: Vec<f64> = observations
+            .iter()
+            .filter(|(recorded_at, _)| (*recorded_at >= midpoint) == newer)
+            .map(|(_, o)| (o.input_bytes + o.output_bytes) as f64)
+            .collect();
+        (!totals.is_empty()).then(|| totals.iter().sum::<f64>() / totals.len() as f64)
+    };
+    let growth_ratio = match (mean_total(false), mean_total(true)) {
+        (Some(older), Some(newer)) if older > 0.0 => Some(newer / older),
+        _ => None,
+    };
+
+    ToolPayloadSummary {
+        tool_id,
+        executions: observations.len() as u64,
+        input_bytes: SizeStats::from_values(&inputs),
+        output_bytes: SizeStats::from_values(&outputs),
+        max_input_array_len: observations.iter().map(|(_, o)| o.input_max_array_len).max().unwrap_or(0),
+        max_output_array_len: observations.iter().map(|(_, o)| o.output_max_array_len).max().unwrap_or(0),
+        schema_warnings: observations.iter().map(|(_, o)| o.schema_warnings).sum(),
+        executions_with_warnings: observations.iter().filter(|(_, o)| o.schema_warnings > 0).count() as u64,
+        growth_ratio,
+    }
+}
+
+fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value).ok().map(|dt| dt.with_timezone(&Utc))
+}
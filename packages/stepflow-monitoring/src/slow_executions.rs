@@ -0,0 +1,175 @@
+//! Slow execution log
+//!
+//! An execution that runs several times longer than its tool's baseline mean
+//! (see [`ToolBaseline`]) is captured in `slow_executions`. Unlike the anomaly
+//! feed this is a plain ratio against the mean with an absolute floor, so
+//! short tools with jittery baselines do not flood the log, and it is scoped to
+//! the tenant that ran the execution.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use stepflow_core::{Database, ExecutionId, MonitoringError, ToolId};
+use stepflow_database::SqliteDatabase;
+use crate::alerting::AlertResult;
+use crate::anomaly::ToolBaseline;
+
+/// When an execution counts as slow
+#[derive(Debug, Clone)]
+pub struct SlowExecutionConfig {
+    /// Duration relative to the baseline mean above which an execution is slow
+    pub ratio_threshold: f64,
+    /// Executions shorter than this are never slow, in milliseconds
+    pub min_duration_ms: f64,
+    /// Executions the baseline needs before it is trusted
+    pub min_samples: u64,
+}
+
+impl Default for SlowExecutionConfig {
+    fn default() -> Self {
+        Self {
+            ratio_threshold: 3.0,
+            min_duration_ms: 1000.0,
+            min_samples: 20,
+        }
+    }
+}
+
+/// A captured slow execution
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SlowExecution {
+    pub id: String,
+    pub execution_id: ExecutionId,
+    pub tenant_id: String,
+    pub tool_id: ToolId,
+    pub duration_ms: f64,
+    pub baseline_mean_ms: f64,
+    /// `duration_ms / baseline_mean_ms`
+    pub ratio: f64,
+    pub success: bool,
+    pub captured_at: DateTime<Utc>,
+}
+
+/// Captures and lists slow executions
+pub struct SlowExecutionLog {
+    db: Arc<SqliteDatabase>,
+    config: SlowExecutionConfig,
+}
+
+impl SlowExecutionLog {
+    pub fn new(db: Arc<SqliteDatabase>) -> Self {
+        Self { db, config: SlowExecutionConfig::default() }
+    }
+
+    pub fn with_config(mut self, config: SlowExecutionConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Capture the execution if it was slow against `baseline`, the tool's
+    /// baseline before this execution was folded into it
+    pub async fn check(
+        &self,
+        execution_id: &ExecutionId,
+        tenant_id: &str,
+        tool_id: &ToolId,
+        duration_ms: f64,
+        success: bool,
+        baseline: Option<&ToolBaseline>,
+    ) -> AlertResult<Option<SlowExecution>> {
+        let Some(baseline) = baseline.filter(|baseline| baseline.samples >= self.config.min_samples) else {
+            return Ok(None);
+        };
+        if duration_ms < self.config.min_duration_ms || baseline.duration_mean_ms <= 0.0 {
+            return Ok(None);
+        }
+        let ratio = duration_ms / baseline.duration_mean_ms;
+        if ratio < self.config.ratio_threshold {
+            return Ok(None);
+        }
+
+        let slow = SlowExecution {
+            id: uuid::Uuid::new_v4().to_string(),
+            execution_id: execution_id.clone(),
+            tenant_id: tenant_id.to_string(),
+            tool_id: tool_id.clone(),
+            duration_ms,
+            baseline_mean_ms: baseline.duration_mean_ms,
+            ratio,
+            success,
+            captured_at: Utc::now(),
+        };
+        self.execute(
+            r#"
+            INSERT INTO slow_executions (id, execution_id, tenant_id, tool_id, duration_ms, baseline_mean_ms, ratio, success, captured_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+            &[
+                Value::String(slow.id.clone()),
+                Value::String(slow.execution_id.to_string()),
+                Value::String(slow.tenant_id.clone()),
+                Value::String(slow.tool_id.to_string()),
+                serde_json::json!(slow.duration_ms),
+                serde_json::json!(slow.baseline_mean_ms),
+                serde_json::json!(slow.ratio),
+                Value::Bool(slow.success),
+                Value::String(slow.captured_at.to_rfc3339()),
+            ],
+        ).await?;
+        Ok(Some(slow))
+    }
+
+    /// Slow executions of a tenant, newest first
+    pub async fn list(&self, tenant_id: &str, tool_id: Option<&ToolId>, limit: usize) -> AlertResult<Vec<SlowExecution>> {
+        let mut sql = "SELECT id, execution_id, tenant_id, tool_id, duration_ms, baseline_mean_ms, ratio, success, captured_at \
+                       FROM slow_executions WHERE tenant_id = ?".to_string();
+        let mut params = vec![Value::String(tenant_id.to_string())];
+        if let Some(tool_id) = tool_id {
+            sql.push_str(" AND tool_id = ?");
+            params.push(Value::String(tool_id.to_string()));
+        }
+        sql.push_str(" ORDER BY captured_at DESC LIMIT ?");
+        params.push(Value::from(limit as i64));
+
+        let rows = self.execute(&sql, &params).await?;
+        rows.iter().map(row_to_slow_execution).collect()
+    }
+
+    /// Delete captures older than `before`, returning how many were removed
+    pub async fn purge(&self, before: DateTime<Utc>) -> AlertResult<u64> {
+        self.db.execute("DELETE FROM slow_executions WHERE captured_at < ?", &[Value::String(before.to_rfc3339())])
+            .await
+            .map(|result| result.rows_affected)
+            .map_err(|e| MonitoringError::MetricsCollectionFailed(e.to_string()))
+    }
+
+    async fn execute(&self, sql: &str, params: &[Value]) -> AlertResult<Vec<HashMap<String, Value>>> {
+        self.db.execute(sql, params)
+            .await
+            .map(|result| result.rows)
+            .map_err(|e| MonitoringError::MetricsCollectionFailed(e.to_string()))
+    }
+}
+
+fn row_to_slow_execution(row: &HashMap<String, Value>) -> AlertResult<SlowExecution> {
+    let text = |key: &str| row.get(key).and_then(Value::as_str);
+    let number = |key: &str| row.get(key).and_then(Value::as_f64).unwrap_or_default();
+    let invalid = |field: &str| MonitoringError::MetricsCollectionFailed(format!("Invalid slow execution {}", field));
+
+    Ok(SlowExecution {
+        id: text("id").ok_or_else(|| invalid("id"))?.to_string(),
+        execution_id: ExecutionId::from_string(text("execution_id").ok_or_else(|| invalid("execution_id"))?.to_string()),
+        tenant_id: text("tenant_id").unwrap_or_default().to_string(),
+        tool_id: ToolId::from_string(text("tool_id").ok_or_else(|| invalid("tool_id"))?.to_string()),
+        duration_ms: number("duration_ms"),
+        baseline_mean_ms: number("baseline_mean_ms"),
+        ratio: number("ratio"),
+        success: row.get("success").and_then(Value::as_i64).unwrap_or(0) != 0,
+        captured_at: text("captured_at")
+            .and_then(|value| DateTime::parse_from_rfc3339(value).ok())
+            .map(|time| time.with_timezone(&Utc))
+            .ok_or_else(|| invalid("captured_at"))?,
+    })
+}
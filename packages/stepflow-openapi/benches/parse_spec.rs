@@ -0,0 +1,67 @@
+//! 大型 OpenAPI 文档解析基准测试
+//!
+//! 运行：`cargo bench -p stepflow-openapi`
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use serde_json::{json, Map, Value};
+use stepflow_openapi::{json_cst_to_ast, CstParser};
+
+/// 生成包含 `path_count` 个路径的 OpenAPI 3.0 文档
+fn large_spec(path_count: usize) -> String {
+    let mut paths = Map::new();
+    let mut schemas = Map::new();
+    for i in 0..path_count {
+        paths.insert(
+            format!("/resources{}/{{id}}", i),
+            json!({
+                "get": {
+                    "operationId": format!("getResource{}", i),
+                    "parameters": [{"name": "id", "in": "path", "required": true, "schema": {"type": "string"}}],
+                    "responses": {
+                        "200": {
+                            "description": "OK",
+                            "content": {"application/json": {"schema": {"$ref": format!("#/components/schemas/Resource{}", i)}}}
+                        }
+                    }
+                }
+            }),
+        );
+        schemas.insert(
+            format!("Resource{}", i),
+            json!({
+                "type": "object",
+                "properties": {
+                    "id": {"type": "string"},
+                    "name": {"type": "string"},
+                    "tags": {"type": "array", "items": {"type": "string"}}
+                }
+            }),
+        );
+    }
+
+    let spec = json!({
+        "openapi": "3.0.3",
+        "info": {"title": "Benchmark API", "version": "1.0.0"},
+        "paths": Value::Object(paths),
+        "components": {"schemas": Value::Object(schemas)}
+    });
+    serde_json::to_string_pretty(&spec).unwrap()
+}
+
+fn parse_benchmarks(c: &mut Criterion) {
+    let mut group = c.benchmark_group("openapi_parse");
+    for path_count in [100, 1_000, 5_000] {
+        let spec = large_spec(path_count);
+        group.throughput(Throughput::Bytes(spec.len() as u64));
+        group.bench_with_input(BenchmarkId::new("cst", path_count), &spec, |b, spec| {
+            b.iter(|| CstParser::parse(spec))
+        });
+        group.bench_with_input(BenchmarkId::new("cst_to_ast", path_count), &spec, |b, spec| {
+            b.iter(|| json_cst_to_ast(&CstParser::parse(spec)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parse_benchmarks);
+criterion_main!(benches);
@@ -0,0 +1,469 @@
+//! Canonical JSON form of the minim element tree
+//!
+//! Every element is written as a JSON object with the keys `element`, `meta`,
+//! `attributes` and `content`; `meta` and `attributes` are omitted when empty. The
+//! canonical text sorts object keys at every level, so the same tree always produces
+//! the same bytes and two revisions of a document can be compared with a plain diff.
+//!
+//! The element name decides how `content` is read back. Elements that were renamed
+//! (e.g. an object specialized to `info`) carry an extra `base` key naming the
+//! underlying kind:
+//!
+//! | base      | content                                         |
+//! |-----------|-------------------------------------------------|
+//! | `null`    | omitted                                         |
+//! | `boolean` | JSON boolean                                    |
+//! | `number`  | JSON number, or `"NaN"`, `"Infinity"`, `"-Infinity"` |
+//! | `string`  | JSON string                                     |
+//! | `array`   | array of elements                               |
+//! | `object`  | array of `member` elements                      |
+//! | `member`  | `{"key": element, "value": element}`            |
+//! | `ref`     | reference path string                           |
+//! | `link`    | `{"relation": string, "href": string}`          |
+//! | `custom`  | any JSON value                                  |
+//!
+//! Object classes are stored as an array element under `meta.classes`. The
+//! `children` and `parent` links of objects are navigation aids rebuilt by the
+//! folders and are not serialized. Custom elements are written under their
+//! [`CustomElement::element`] name, which is expected to match the variant tag.
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+use crate::ast::minim_model::*;
+
+/// Errors reading the canonical form
+#[derive(Debug, Error)]
+pub enum CanonicalError {
+    #[error("Invalid JSON: {0}")]
+    InvalidJson(#[from] serde_json::Error),
+
+    #[error("Expected an element object at {0}")]
+    NotAnElement(String),
+
+    #[error("Unknown base element kind '{kind}' at {path}")]
+    UnknownBase { kind: String, path: String },
+
+    #[error("Invalid content for {kind} element at {path}")]
+    InvalidContent { kind: String, path: String },
+}
+
+pub type CanonicalResult<T> = Result<T, CanonicalError>;
+
+const CLASSES_META_KEY: &str = "classes";
+
+impl Element {
+    /// Canonical JSON value of this element tree
+    pub fn to_canonical_value(&self) -> Value {
+        match self {
+            Element::Null(e) => encode("null", &e.element, &e.meta, &e.attributes, None),
+            Element::Boolean(e) => encode("boolean", &e.element, &e.meta, &e.attributes, Some(Value::Bool(e.content))),
+            Element::Number(e) => encode("number", &e.element, &e.meta, &e.attributes, Some(encode_number(e.content))),
+            Element::String(e) => encode("string", &e.element, &e.meta, &e.attributes, Some(Value::String(e.content.clone()))),
+            Element::Array(e) => e.to_canonical_value(),
+            Element::Object(e) => {
+                let mut meta = e.meta.clone();
+                if !e.classes.content.is_empty() {
+                    meta.properties.insert(CLASSES_META_KEY.to_string(), e.classes.to_canonical_value());
+                }
+                let members = e.content.iter().map(MemberElement::to_canonical_value).collect();
+                encode("object", &e.element, &meta, &e.attributes, Some(Value::Array(members)))
+            }
+            Element::Member(e) => e.to_canonical_value(),
+            Element::Ref(e) => encode("ref", &e.element, &e.meta, &e.attributes, Some(Value::String(e.path.clone()))),
+            Element::Link(e) => {
+                let mut content = Map::new();
+                content.insert("relation".to_string(), Value::String(e.relation.clone()));
+                content.insert("href".to_string(), Value::String(e.href.clone()));
+                encode("link", &e.element, &e.meta, &e.attributes, Some(Value::Object(content)))
+            }
+            Element::Custom(_, e) => encode("custom", &e.element, &e.meta, &e.attributes, Some(e.content.clone())),
+        }
+    }
+
+    /// Canonical JSON text of this element tree, with object keys sorted
+    pub fn to_canonical_string(&self) -> String {
+        sort_keys(&self.to_canonical_value()).to_string()
+    }
+
+    /// Rebuild an element tree from its canonical JSON value
+    pub fn from_canonical_value(value: &Value) -> CanonicalResult<Element> {
+        decode(value, "$")
+    }
+
+    /// Rebuild an element tree from canonical JSON text
+    pub fn from_canonical_str(json: &str) -> CanonicalResult<Element> {
+        Self::from_canonical_value(&serde_json::from_str(json)?)
+    }
+}
+
+impl ArrayElement {
+    fn to_canonical_value(&self) -> Value {
+        let items = self.content.iter().map(Element::to_canonical_value).collect();
+        encode("array", &self.element, &self.meta, &self.attributes, Some(Value::Array(items)))
+    }
+}
+
+impl MemberElement {
+    fn to_canonical_value(&self) -> Value {
+        let mut content = Map::new();
+        content.insert("key".to_string(), self.key.to_canonical_value());
+        content.insert("value".to_string(), self.value.to_canonical_value());
+        encode("member", "member", &MetaElement::default(), &AttributesElement::default(), Some(Value::Object(content)))
+    }
+}
+
+/// Serde adapter that (de)serializes an [`Element`] in canonical form
+///
+/// Use it to store ASTs in the database or send them over RPC:
+/// `serde_json::to_string(&CanonicalElement(element))`.
+#[derive(Debug, Clone)]
+pub struct CanonicalElement(pub Element);
+
+impl Serialize for CanonicalElement {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        sort_keys(&self.0.to_canonical_value()).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for CanonicalElement {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        Element::from_canonical_value(&value)
+            .map(CanonicalElement)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+fn encode(base: &str, element: &str, meta: &MetaElement, attributes: &AttributesElement, content: Option<Value>) -> Value {
+    let mut object = Map::new();
+    object.insert("element".to_string(), Value::String(element.to_string()));
+    if element != base {
+        object.insert("base".to_string(), Value::String(base.to_string()));
+    }
+    if !meta.properties.is_empty() {
+        object.insert("meta".to_string(), properties_object(&meta.properties));
+    }
+    if !attributes.properties.is_empty() {
+        object.insert("attributes".to_string(), properties_object(&attributes.properties));
+    }
+    if let Some(content) = content {
+        object.insert("content".to_string(), content);
+    }
+    Value::Object(object)
+}
+
+fn properties_object(properties: &HashMap<String, Value>) -> Value {
+    Value::Object(properties.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+}
+
+/// Copy of `value` with object keys sorted at every level, independent of whether
+/// serde_json preserves insertion order
+fn sort_keys(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let sorted: BTreeMap<_, _> = map.iter().collect();
+            Value::Object(sorted.into_iter().map(|(k, v)| (k.clone(), sort_keys(v))).collect())
+        }
+        Value::Array(items) => Value::Array(items.iter().map(sort_keys).collect()),
+        other => other.clone(),
+    }
+}
+
+fn encode_number(n: f64) -> Value {
+    match serde_json::Number::from_f64(n) {
+        Some(number) => Value::Number(number),
+        None if n.is_nan() => Value::String("NaN".to_string()),
+        None if n > 0.0 => Value::String("Infinity".to_string()),
+        None => Value::String("-Infinity".to_string()),
+    }
+}
+
+fn decode(value: &Value, path: &str) -> CanonicalResult<Element> {
+    let object = value.as_object().ok_or_else(|| CanonicalError::NotAnElement(path.to_string()))?;
+    let element = object
+        .get("element")
+        .and_then(Value::as_str)
+        .ok_or_else(|| CanonicalError::NotAnElement(path.to_string()))?
+        .to_string();
+    let base = object.get("base").and_then(Value::as_str).unwrap_or(&element).to_string();
+    let mut meta = MetaElement {
+        properties: properties(object.get("meta"), "meta", path)?,
+    };
+    let attributes = AttributesElement {
+        properties: properties(object.get("attributes"), "attributes", path)?,
+    };
+    let content = object.get("content");
+    let invalid = || CanonicalError::InvalidContent {
+        kind: base.clone(),
+        path: path.to_string(),
+    };
+
+    let decoded = match base.as_str() {
+        "null" => Element::Null(NullElement { element, meta, attributes }),
+        "boolean" => Element::Boolean(BooleanElement {
+            element,
+            meta,
+            attributes,
+            content: content.and_then(Value::as_bool).ok_or_else(invalid)?,
+        }),
+        "number" => Element::Number(NumberElement {
+            element,
+            meta,
+            attributes,
+            content: content.and_then(decode_number).ok_or_else(invalid)?,
+        }),
+        "string" => Element::String(StringElement {
+            element,
+            meta,
+            attributes,
+            content: content.and_then(Value::as_str).ok_or_else(invalid)?.to_string(),
+        }),
+        "array" => Element::Array(ArrayElement {
+            element,
+            meta,
+            attributes,
+            content: decode_items(content.and_then(Value::as_array).ok_or_else(invalid)?, path)?,
+        }),
+        "object" => {
+            let classes = match meta.properties.remove(CLASSES_META_KEY) {
+                Some(classes) => match decode(&classes, &format!("{}.meta.classes", path))? {
+                    Element::Array(classes) => classes,
+                    _ => return Err(invalid()),
+                },
+                None => ArrayElement::new_empty(),
+            };
+            let members = decode_items(content.and_then(Value::as_array).ok_or_else(invalid)?, path)?
+                .into_iter()
+                .map(|member| match member {
+                    Element::Member(member) => Ok(*member),
+                    _ => Err(invalid()),
+                })
+                .collect::<CanonicalResult<_>>()?;
+            Element::Object(ObjectElement {
+                element,
+                meta,
+                attributes,
+                classes,
+                children: vec![],
+                parent: None,
+                content: members,
+            })
+        }
+        "member" => {
+            let content = content.and_then(Value::as_object).ok_or_else(invalid)?;
+            let key = decode(content.get("key").ok_or_else(invalid)?, &format!("{}.key", path))?;
+            let value = decode(content.get("value").ok_or_else(invalid)?, &format!("{}.value", path))?;
+            Element::Member(Box::new(MemberElement::new(key, value)))
+        }
+        "ref" => Element::Ref(RefElement {
+            element,
+            meta,
+            attributes,
+            path: content.and_then(Value::as_str).ok_or_else(invalid)?.to_string(),
+        }),
+        "link" => {
+            let content = content.and_then(Value::as_object).ok_or_else(invalid)?;
+            let field = |name: &str| content.get(name).and_then(Value::as_str).map(str::to_string).ok_or_else(invalid);
+            Element::Link(LinkElement {
+                relation: field("relation")?,
+                href: field("href")?,
+                element,
+                meta,
+                attributes,
+            })
+        }
+        "custom" => Element::Custom(
+            element.clone(),
+            Box::new(CustomElement {
+                element,
+                meta,
+                attributes,
+                content: content.cloned().unwrap_or(Value::Null),
+            }),
+        ),
+        _ => {
+            return Err(CanonicalError::UnknownBase {
+                kind: base.clone(),
+                path: path.to_string(),
+            })
+        }
+    };
+    Ok(decoded)
+}
+
+fn decode_items(items: &[Value], path: &str) -> CanonicalResult<Vec<Element>> {
+    items
+        .iter()
+        .enumerate()
+        .map(|(index, item)| decode(item, &format!("{}[{}]", path, index)))
+        .collect()
+}
+
+fn decode_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => match s.as_str() {
+            "NaN" => Some(f64::NAN),
+            "Infinity" => Some(f64::INFINITY),
+            "-Infinity" => Some(f64::NEG_INFINITY),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn properties(value: Option<&Value>, key: &str, path: &str) -> CanonicalResult<HashMap<String, Value>> {
+    match value {
+        None => Ok(Default::default()),
+        Some(Value::Object(map)) => Ok(map.iter().map(|(k, v)| (k.clone(), v.clone())).collect()),
+        Some(_) => Err(CanonicalError::InvalidContent {
+            kind: key.to_string(),
+            path: path.to_string(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::fold::json_cst_to_ast;
+    use crate::cst::CstParser;
+    use serde_json::json;
+
+    fn round_trip(element: &Element) -> Element {
+        let text = element.to_canonical_string();
+        let decoded = Element::from_canonical_str(&text).expect("canonical JSON should decode");
+        assert_eq!(decoded.to_canonical_string(), text);
+        decoded
+    }
+
+    #[test]
+    fn test_scalar_round_trip() {
+        let mut string = StringElement::new("hello");
+        string.meta.properties.insert("description".to_string(), json!("greeting"));
+        string.attributes.properties.insert("typeAttributes".to_string(), json!(["required"]));
+
+        for element in [
+            Element::Null(NullElement::default()),
+            Element::Boolean(BooleanElement::new(true)),
+            Element::Number(NumberElement::new(1.5)),
+            Element::String(string),
+        ] {
+            assert_eq!(round_trip(&element), element);
+        }
+
+        let value = Element::String(StringElement::new("x")).to_canonical_value();
+        assert_eq!(value, json!({"element": "string", "content": "x"}));
+    }
+
+    #[test]
+    fn test_non_finite_numbers() {
+        for n in [f64::INFINITY, f64::NEG_INFINITY] {
+            let element = Element::Number(NumberElement::new(n));
+            assert_eq!(round_trip(&element), element);
+        }
+        let nan = round_trip(&Element::Number(NumberElement::new(f64::NAN)));
+        assert!(nan.as_number().unwrap().content.is_nan());
+    }
+
+    #[test]
+    fn test_object_round_trip_with_specialized_names() {
+        let mut info = ObjectElement::new();
+        info.set_element_type("info");
+        info.add_class("api");
+        info.set("title", Element::String(StringElement::new("Pets")));
+        info.set("version", Element::Number(NumberElement::new(1.0)));
+
+        let mut tags = ArrayElement::from_strings(&["a", "b"]);
+        tags.set_element_type("tags");
+        info.set("tags", Element::Array(tags));
+        info.set(
+            "schema",
+            Element::Ref(RefElement {
+                element: "ref".to_string(),
+                meta: MetaElement::default(),
+                attributes: AttributesElement::default(),
+                path: "#/components/schemas/Pet".to_string(),
+            }),
+        );
+        info.set(
+            "next",
+            Element::Link(LinkElement {
+                element: "link".to_string(),
+                meta: MetaElement::default(),
+                attributes: AttributesElement::default(),
+                relation: "next".to_string(),
+                href: "/pets?page=2".to_string(),
+            }),
+        );
+        info.set(
+            "extra",
+            Element::Custom(
+                "x-extension".to_string(),
+                Box::new(CustomElement {
+                    element: "x-extension".to_string(),
+                    meta: MetaElement::default(),
+                    attributes: AttributesElement::default(),
+                    content: json!({"any": [1, 2]}),
+                }),
+            ),
+        );
+        let element = Element::Object(info);
+
+        let value = element.to_canonical_value();
+        assert_eq!(value["element"], "info");
+        assert_eq!(value["base"], "object");
+        assert_eq!(value["content"][0]["element"], "member");
+        assert_eq!(value["content"][0]["content"]["key"]["content"], "title");
+
+        let decoded = round_trip(&element);
+        assert_eq!(decoded, element);
+        assert_eq!(decoded.as_object().unwrap().classes.content.len(), 1);
+    }
+
+    #[test]
+    fn test_canonical_form_is_stable() {
+        let mut a = StringElement::new("v");
+        a.meta.properties.insert("z".to_string(), json!(1));
+        a.meta.properties.insert("a".to_string(), json!({"y": 1, "b": 2}));
+        let mut b = StringElement::new("v");
+        b.meta.properties.insert("a".to_string(), json!({"b": 2, "y": 1}));
+        b.meta.properties.insert("z".to_string(), json!(1));
+
+        let a = Element::String(a).to_canonical_string();
+        assert_eq!(a, Element::String(b).to_canonical_string());
+        assert_eq!(a, r#"{"content":"v","element":"string","meta":{"a":{"b":2,"y":1},"z":1}}"#);
+    }
+
+    #[test]
+    fn test_parsed_document_round_trip() {
+        let src = r#"{"openapi": "3.0.0", "info": {"title": "Pets", "version": "1"}, "paths": {"/pets": {"get": {"tags": ["pets"], "deprecated": false}}}}"#;
+        let element = json_cst_to_ast(&CstParser::parse(src));
+
+        let decoded = round_trip(&element);
+        assert_eq!(decoded.to_value(), element.to_value());
+
+        let wrapped: CanonicalElement = serde_json::from_str(&serde_json::to_string(&CanonicalElement(element)).unwrap()).unwrap();
+        assert_eq!(wrapped.0.to_value()["info"]["title"], "Pets");
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        assert!(matches!(Element::from_canonical_str("[]"), Err(CanonicalError::NotAnElement(_))));
+        assert!(matches!(Element::from_canonical_str("{"), Err(CanonicalError::InvalidJson(_))));
+        assert!(matches!(
+            Element::from_canonical_value(&json!({"element": "widget"})),
+            Err(CanonicalError::UnknownBase { .. })
+        ));
+        let err = Element::from_canonical_value(&json!({
+            "element": "array",
+            "content": [{"element": "number", "content": "ten"}]
+        }))
+        .unwrap_err();
+        assert_eq!(err.to_string(), "Invalid content for number element at $[0]");
+    }
+}
@@ -2,7 +2,7 @@ use std::collections::HashMap;
 use serde::Serialize;
 use serde_json::Value;
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub enum Element {
     Null(NullElement),
     Boolean(BooleanElement),
@@ -96,7 +96,7 @@ impl ElementRegistry {
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct ObjectElement {
     pub element: String,
     pub meta: MetaElement,
@@ -172,7 +172,7 @@ impl ObjectElement {
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct MetaElement {
     pub properties: HashMap<String, Value>,
 }
@@ -185,7 +185,7 @@ impl Default for MetaElement {
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct AttributesElement {
     pub properties: HashMap<String, Value>,
 }
@@ -198,7 +198,7 @@ impl Default for AttributesElement {
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct StringElement {
     pub element: String,
     pub meta: MetaElement,
@@ -232,7 +232,7 @@ impl StringElement {
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct MemberElement {
     pub key: Box<Element>,
     pub value: Box<Element>,
@@ -247,7 +247,7 @@ impl MemberElement {
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct BooleanElement {
     pub element: String,
     pub meta: MetaElement,
@@ -266,7 +266,7 @@ impl BooleanElement {
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct NumberElement {
     pub element: String,
     pub meta: MetaElement,
@@ -285,7 +285,7 @@ impl NumberElement {
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct NullElement {
     pub element: String,
     pub meta: MetaElement,
@@ -302,7 +302,7 @@ impl Default for NullElement {
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct ArrayElement {
     pub element: String,
     pub meta: MetaElement,
@@ -364,7 +364,7 @@ impl ArrayElement {
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct RefElement {
     pub element: String,
     pub meta: MetaElement,
@@ -372,7 +372,7 @@ pub struct RefElement {
     pub path: String,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct LinkElement {
     pub element: String,
     pub meta: MetaElement,
@@ -381,7 +381,7 @@ pub struct LinkElement {
     pub href: String,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct CustomElement {
     pub element: String,
     pub meta: MetaElement,
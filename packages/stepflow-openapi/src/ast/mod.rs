@@ -1,2 +1,3 @@
 pub mod minim_model;
-pub mod fold;
\ No newline at end of file
+pub mod fold;
+pub mod canonical;
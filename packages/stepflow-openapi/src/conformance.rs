@@ -0,0 +1,601 @@
+//! Contract validation of upstream APIs against their spec
+//!
+//! The [`ContractVerifier`] periodically calls the safe operations of watched
+//! OpenAPI documents against the live upstream and validates each response
+//! against the schema the document declares for its status code. Only `GET`
+//! operations are called, and only when every required parameter has a value:
+//! one configured on the [`ConformanceTarget`], or the parameter schema's
+//! `example`, `default` or first `enum` value.
+//!
+//! Each operation keeps a conformance score, the share of conforming checks
+//! over the last [`ConformanceConfig::window`] checks. When the score falls
+//! below [`ConformanceConfig::drift_threshold`] the operation is marked as
+//! drifting and notifiers receive a `contract.drifting` alert; a
+//! `contract.recovered` alert follows once the score is back above it.
+//! Checks that fail to reach the upstream are reported but do not count
+//! towards the score.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use stepflow_core::{CloudEvent, EventData, CLOUDEVENTS_CONTENT_TYPE};
+use stepflow_registry::tool_config::validate_against_schema;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+use crate::document::{DocumentManager, OperationInfo, OpenApiDocument, ParameterInfo};
+use crate::proxy::rate_limit::UpstreamRateLimiter;
+use crate::ref_resolver::RefResolver;
+use crate::tool::{declared_response_schema, AuthConfig, OpenApiTool, OpenApiToolConfig};
+
+/// Contract verification errors
+#[derive(Debug, thiserror::Error)]
+pub enum ConformanceError {
+    #[error("Document not found: {0}")]
+    DocumentNotFound(String),
+
+    #[error("Document {0} has no server URL; configure a base URL on its target")]
+    MissingBaseUrl(String),
+
+    #[error("Document error: {0}")]
+    Document(String),
+
+    #[error("Notification failed: {0}")]
+    Notification(String),
+}
+
+/// Verification settings shared by every watched document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConformanceConfig {
+    /// Number of recent checks a score is computed over
+    pub window: usize,
+    /// Score below which an operation is considered drifting
+    pub drift_threshold: f64,
+    /// Timeout of each upstream call in milliseconds
+    pub timeout_ms: u64,
+}
+
+impl Default for ConformanceConfig {
+    fn default() -> Self {
+        Self {
+            window: 20,
+            drift_threshold: 0.8,
+            timeout_ms: 10_000,
+        }
+    }
+}
+
+/// A document to verify and how to call its upstream
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConformanceTarget {
+    pub document_id: String,
+    /// Upstream base URL, the document's first server when unset
+    #[serde(default)]
+    pub base_url: Option<String>,
+    /// Operation ids to check; every safe operation when empty
+    #[serde(default)]
+    pub operations: Vec<String>,
+    /// Input of each operation by operation id, merged over schema examples
+    #[serde(default)]
+    pub parameters: HashMap<String, Value>,
+    #[serde(default)]
+    pub default_headers: HashMap<String, String>,
+    #[serde(default)]
+    pub auth: Option<AuthConfig>,
+}
+
+impl ConformanceTarget {
+    pub fn new(document_id: impl Into<String>) -> Self {
+        Self {
+            document_id: document_id.into(),
+            base_url: None,
+            operations: Vec::new(),
+            parameters: HashMap::new(),
+            default_headers: HashMap::new(),
+            auth: None,
+        }
+    }
+}
+
+/// Result of calling one operation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OperationCheck {
+    pub operation_id: String,
+    /// Response status, `None` when the upstream could not be reached
+    pub status: Option<u16>,
+    pub conforming: bool,
+    /// Schema violations and undeclared status codes
+    pub violations: Vec<String>,
+    /// Transport error when the upstream could not be reached
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub checked_at: DateTime<Utc>,
+}
+
+/// An operation that was not called, and why
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SkippedOperation {
+    pub operation_id: String,
+    pub reason: String,
+}
+
+/// Result of verifying one document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConformanceReport {
+    pub document_id: String,
+    pub checks: Vec<OperationCheck>,
+    pub skipped: Vec<SkippedOperation>,
+    pub checked_at: DateTime<Utc>,
+}
+
+/// Conformance history of one operation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OperationConformance {
+    pub document_id: String,
+    pub operation_id: String,
+    pub method: String,
+    pub path: String,
+    /// Share of conforming checks in the window, 1.0 before the first check
+    pub score: f64,
+    /// Checks counted towards the score since the operation was first checked
+    pub total_checks: u64,
+    pub conforming_checks: u64,
+    pub drifting: bool,
+    pub last_check: Option<OperationCheck>,
+    #[serde(skip)]
+    recent: VecDeque<bool>,
+}
+
+/// Whether an operation started or stopped drifting
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DriftStatus {
+    Drifting,
+    Recovered,
+}
+
+impl DriftStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DriftStatus::Drifting => "drifting",
+            DriftStatus::Recovered => "recovered",
+        }
+    }
+}
+
+/// Delivered to notifiers when an operation starts or stops drifting
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriftAlert {
+    pub status: DriftStatus,
+    pub document_id: String,
+    pub operation_id: String,
+    pub score: f64,
+    pub threshold: f64,
+    /// Violations of the check that changed the status
+    pub violations: Vec<String>,
+    pub checked_at: DateTime<Utc>,
+}
+
+impl EventData for DriftAlert {
+    const SOURCE: &'static str = "/stepflow/openapi/conformance";
+    const SCHEMA: &'static str = "contract.drift";
+    const SCHEMA_VERSION: u32 = 1;
+
+    fn event_type(&self) -> String {
+        format!("contract.{}", self.status.as_str())
+    }
+
+    fn subject(&self) -> Option<String> {
+        Some(format!("{}#{}", self.document_id, self.operation_id))
+    }
+}
+
+/// Delivers drift alerts
+#[async_trait::async_trait]
+pub trait DriftNotifier: Send + Sync {
+    async fn notify(&self, alert: &DriftAlert) -> Result<(), ConformanceError>;
+}
+
+/// Posts drift alerts to a webhook as CloudEvents
+pub struct WebhookDriftNotifier {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl WebhookDriftNotifier {
+    pub fn new(url: String) -> Self {
+        Self {
+            url,
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl DriftNotifier for WebhookDriftNotifier {
+    async fn notify(&self, alert: &DriftAlert) -> Result<(), ConformanceError> {
+        let response = self.client
+            .post(&self.url)
+            .header(reqwest::header::CONTENT_TYPE, CLOUDEVENTS_CONTENT_TYPE)
+            .json(&CloudEvent::new(alert.clone()))
+            .timeout(Duration::from_secs(10))
+            .send()
+            .await
+            .map_err(|e| ConformanceError::Notification(format!("Webhook {} failed: {}", self.url, e)))?;
+        if !response.status().is_success() {
+            return Err(ConformanceError::Notification(format!(
+                "Webhook {} returned {}",
+                self.url,
+                response.status()
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Verifies watched documents against their live upstreams
+pub struct ContractVerifier {
+    documents: Arc<DocumentManager>,
+    config: ConformanceConfig,
+    targets: RwLock<HashMap<String, ConformanceTarget>>,
+    /// Conformance per document id, then operation id
+    scores: RwLock<HashMap<String, HashMap<String, OperationConformance>>>,
+    notifiers: Vec<Arc<dyn DriftNotifier>>,
+    rate_limiter: Option<Arc<UpstreamRateLimiter>>,
+}
+
+impl ContractVerifier {
+    pub fn new(documents: Arc<DocumentManager>, config: ConformanceConfig) -> Self {
+        Self {
+            documents,
+            config,
+            targets: RwLock::new(HashMap::new()),
+            scores: RwLock::new(HashMap::new()),
+            notifiers: Vec::new(),
+            rate_limiter: None,
+        }
+    }
+
+    pub fn with_notifier(mut self, notifier: Arc<dyn DriftNotifier>) -> Self {
+        self.notifiers.push(notifier);
+        self
+    }
+
+    /// Pace upstream calls with a specific rate limiter instead of the process-wide one
+    pub fn with_rate_limiter(mut self, limiter: Arc<UpstreamRateLimiter>) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Start verifying a document, replacing its previous target
+    pub async fn watch(&self, target: ConformanceTarget) {
+        self.targets.write().await.insert(target.document_id.clone(), target);
+    }
+
+    /// Stop verifying a document and forget its scores
+    pub async fn unwatch(&self, document_id: &str) -> bool {
+        self.scores.write().await.remove(document_id);
+        self.targets.write().await.remove(document_id).is_some()
+    }
+
+    pub async fn targets(&self) -> Vec<ConformanceTarget> {
+        self.targets.read().await.values().cloned().collect()
+    }
+
+    /// Conformance of every checked operation of a document, by operation id
+    pub async fn scores(&self, document_id: &str) -> Vec<OperationConformance> {
+        let mut scores: Vec<OperationConformance> = self.scores.read().await
+            .get(document_id)
+            .map(|scores| scores.values().cloned().collect())
+            .unwrap_or_default();
+        scores.sort_by(|a, b| a.operation_id.cmp(&b.operation_id));
+        scores
+    }
+
+    /// Verify every watched document; documents that fail to load are logged and skipped
+    pub async fn verify_all(&self) -> Vec<ConformanceReport> {
+        let mut reports = Vec::new();
+        for target in self.targets().await {
+            match self.verify(&target).await {
+                Ok(report) => reports.push(report),
+                Err(e) => warn!("Contract verification of {} failed: {}", target.document_id, e),
+            }
+        }
+        reports
+    }
+
+    /// Verify a watched document now
+    pub async fn verify_document(&self, document_id: &str) -> Result<ConformanceReport, ConformanceError> {
+        let target = self.targets.read().await
+            .get(document_id)
+            .cloned()
+            .ok_or_else(|| ConformanceError::DocumentNotFound(document_id.to_string()))?;
+        self.verify(&target).await
+    }
+
+    /// Verify watched documents periodically in the background
+    pub fn start(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                self.verify_all().await;
+            }
+        })
+    }
+
+    async fn verify(&self, target: &ConformanceTarget) -> Result<ConformanceReport, ConformanceError> {
+        let document = self.documents.get_document(&target.document_id)
+            .await
+            .map_err(|e| ConformanceError::Document(e.to_string()))?
+            .ok_or_else(|| ConformanceError::DocumentNotFound(target.document_id.clone()))?;
+        let base_url = target.base_url.clone()
+            .or_else(|| document.meta.servers.first().cloned())
+            .ok_or_else(|| ConformanceError::MissingBaseUrl(target.document_id.clone()))?;
+        let resolved = Arc::new(
+            RefResolver::new()
+                .resolve_document(&document.parsed)
+                .map_err(|e| ConformanceError::Document(e.to_string()))?,
+        );
+
+        let mut report = ConformanceReport {
+            document_id: target.document_id.clone(),
+            checks: Vec::new(),
+            skipped: Vec::new(),
+            checked_at: Utc::now(),
+        };
+        for operation in selected_operations(&document, target) {
+            let input = match operation_input(operation, target.parameters.get(&operation.operation_id)) {
+                Ok(input) => input,
+                Err(reason) => {
+                    report.skipped.push(SkippedOperation { operation_id: operation.operation_id.clone(), reason });
+                    continue;
+                }
+            };
+            let check = self.check_operation(target, &base_url, operation, &resolved, &input).await;
+            self.record(&target.document_id, operation, &check).await;
+            report.checks.push(check);
+        }
+        Ok(report)
+    }
+
+    async fn check_operation(
+        &self,
+        target: &ConformanceTarget,
+        base_url: &str,
+        operation: &OperationInfo,
+        resolved: &Arc<Value>,
+        input: &Value,
+    ) -> OperationCheck {
+        let mut check = OperationCheck {
+            operation_id: operation.operation_id.clone(),
+            status: None,
+            conforming: false,
+            violations: Vec::new(),
+            error: None,
+            checked_at: Utc::now(),
+        };
+        let config = OpenApiToolConfig {
+            srn: operation.srn.to_string(),
+            base_url: base_url.to_string(),
+            timeout_ms: Some(self.config.timeout_ms),
+            max_retries: Some(0),
+            default_headers: target.default_headers.clone(),
+            auth: target.auth.clone(),
+            tool_name: None,
+            sandbox_profile: None,
+            response_conversion: Default::default(),
+            pagination: None,
+            rate_limit: None,
+        };
+        let tool = match OpenApiTool::with_resolved_document(config, operation.clone(), resolved.clone()) {
+            Ok(tool) => tool,
+            Err(e) => {
+                check.error = Some(e.to_string());
+                return check;
+            }
+        };
+        let tool = match &self.rate_limiter {
+            Some(limiter) => tool.with_rate_limiter(limiter.clone()),
+            None => tool,
+        };
+
+        let response = match tool.send(input).await {
+            Ok(response) => response,
+            Err(e) => {
+                check.error = Some(e.to_string());
+                return check;
+            }
+        };
+        check.status = Some(response.status);
+        check.violations = response_violations(operation, resolved, response.status, response.body.as_ref());
+        check.conforming = check.violations.is_empty();
+        check
+    }
+
+    /// Update the operation's score and notify when its drift status changes
+    async fn record(&self, document_id: &str, operation: &OperationInfo, check: &OperationCheck) {
+        if check.status.is_none() {
+            return;
+        }
+        let alert = {
+            let mut scores = self.scores.write().await;
+            let conformance = scores
+                .entry(document_id.to_string())
+                .or_default()
+                .entry(operation.operation_id.clone())
+                .or_insert_with(|| OperationConformance {
+                    document_id: document_id.to_string(),
+                    operation_id: operation.operation_id.clone(),
+                    method: operation.method.clone(),
+                    path: operation.path.clone(),
+                    score: 1.0,
+                    total_checks: 0,
+                    conforming_checks: 0,
+                    drifting: false,
+                    last_check: None,
+                    recent: VecDeque::new(),
+                });
+
+            conformance.total_checks += 1;
+            if check.conforming {
+                conformance.conforming_checks += 1;
+            }
+            conformance.recent.push_back(check.conforming);
+            while conformance.recent.len() > self.config.window.max(1) {
+                conformance.recent.pop_front();
+            }
+            let conforming = conformance.recent.iter().filter(|conforming| **conforming).count();
+            conformance.score = conforming as f64 / conformance.recent.len() as f64;
+            conformance.last_check = Some(check.clone());
+
+            let drifting = conformance.score < self.config.drift_threshold;
+            let changed = drifting != conformance.drifting;
+            conformance.drifting = drifting;
+            changed.then(|| DriftAlert {
+                status: if drifting { DriftStatus::Drifting } else { DriftStatus::Recovered },
+                document_id: document_id.to_string(),
+                operation_id: operation.operation_id.clone(),
+                score: conformance.score,
+                threshold: self.config.drift_threshold,
+                violations: check.violations.clone(),
+                checked_at: check.checked_at,
+            })
+        };
+
+        if let Some(alert) = alert {
+            for notifier in &self.notifiers {
+                if let Err(e) = notifier.notify(&alert).await {
+                    warn!("Failed to deliver drift alert for {}: {}", alert.operation_id, e);
+                }
+            }
+        }
+    }
+}
+
+/// Safe operations of a document the target selects
+fn selected_operations<'a>(document: &'a OpenApiDocument, target: &ConformanceTarget) -> impl Iterator<Item = &'a OperationInfo> {
+    let selected = target.operations.clone();
+    document.operations.iter()
+        .filter(|operation| operation.method.eq_ignore_ascii_case("GET"))
+        .filter(move |operation| selected.is_empty() || selected.contains(&operation.operation_id))
+}
+
+/// Input for an operation, or why it cannot be called safely
+fn operation_input(operation: &OperationInfo, configured: Option<&Value>) -> Result<Value, String> {
+    let configured = configured.and_then(Value::as_object);
+    let mut input = serde_json::Map::new();
+    for parameter in &operation.parameters {
+        let value = configured.and_then(|configured| configured.get(&parameter.name))
+            .cloned()
+            .or_else(|| sample_value(parameter));
+        match value {
+            Some(value) => {
+                input.insert(parameter.name.clone(), value);
+            }
+            None if parameter.required => {
+                return Err(format!("No value for required parameter '{}'", parameter.name));
+            }
+            None => {}
+        }
+    }
+    Ok(Value::Object(input))
+}
+
+/// Example value declared by a parameter's schema
+fn sample_value(parameter: &ParameterInfo) -> Option<Value> {
+    let schema = &parameter.schema;
+    schema.get("example")
+        .or_else(|| schema.get("default"))
+        .or_else(|| schema.get("enum").and_then(Value::as_array).and_then(|values| values.first()))
+        .cloned()
+}
+
+/// Ways a response deviates from what the operation declares
+fn response_violations(operation: &OperationInfo, resolved: &Value, status: u16, body: Option<&Value>) -> Vec<String> {
+    let range = format!("{}XX", status / 100);
+    let declared = [status.to_string(), range, "default".to_string()]
+        .iter()
+        .any(|key| operation.responses.contains_key(key));
+    if !declared {
+        return vec![format!("HTTP {} is not a declared response", status)];
+    }
+
+    let Some(schema) = declared_response_schema(operation, resolved, status) else {
+        return Vec::new();
+    };
+    match validate_against_schema(&schema, body.unwrap_or(&Value::Null)) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors.into_iter().map(|error| format!("HTTP {}: {}", status, error)).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::{MediaTypeInfo, ParameterLocation, ResponseInfo};
+    use crate::srn::Srn;
+    use serde_json::json;
+
+    fn operation(parameters: Vec<ParameterInfo>) -> OperationInfo {
+        OperationInfo {
+            srn: Srn::openapi_operation("tenant", "api", "getPet").unwrap(),
+            operation_id: "getPet".to_string(),
+            method: "GET".to_string(),
+            path: "/pets/{id}".to_string(),
+            summary: None,
+            description: None,
+            parameters,
+            request_body: None,
+            responses: HashMap::from([("200".to_string(), ResponseInfo {
+                description: "Pet".to_string(),
+                content: Some(HashMap::from([("application/json".to_string(), MediaTypeInfo {
+                    schema: Some(json!({"type": "object", "required": ["id"], "properties": {"id": {"type": "integer"}}})),
+                    encoding: HashMap::new(),
+                })])),
+                headers: None,
+            })]),
+            tags: vec![],
+            extensions: HashMap::new(),
+        }
+    }
+
+    fn parameter(name: &str, required: bool, schema: Value) -> ParameterInfo {
+        ParameterInfo {
+            name: name.to_string(),
+            location: ParameterLocation::Path,
+            required,
+            schema,
+            description: None,
+        }
+    }
+
+    #[test]
+    fn test_operation_input() {
+        let op = operation(vec![
+            parameter("id", true, json!({"type": "integer", "example": 7})),
+            parameter("lang", false, json!({"type": "string", "enum": ["en", "de"]})),
+            parameter("page", false, json!({"type": "integer"})),
+        ]);
+        assert_eq!(operation_input(&op, None).unwrap(), json!({"id": 7, "lang": "en"}));
+        assert_eq!(operation_input(&op, Some(&json!({"id": 9}))).unwrap(), json!({"id": 9, "lang": "en"}));
+
+        let op = operation(vec![parameter("id", true, json!({"type": "integer"}))]);
+        assert!(operation_input(&op, None).unwrap_err().contains("'id'"));
+    }
+
+    #[test]
+    fn test_response_violations() {
+        let op = operation(vec![]);
+        assert!(response_violations(&op, &Value::Null, 200, Some(&json!({"id": 1}))).is_empty());
+        assert_eq!(response_violations(&op, &Value::Null, 200, Some(&json!({"id": "1"}))).len(), 1);
+        assert_eq!(
+            response_violations(&op, &Value::Null, 500, None),
+            vec!["HTTP 500 is not a declared response"]
+        );
+    }
+}
@@ -1,5 +1,7 @@
 mod node;
+mod path;
 pub use node::{TreeCursorSyntaxNode, TreeIterator, TraversalOrder};
+pub use path::{parse_json_pointer, to_json_pointer, SourceSpan};
 
 use tree_sitter::{Parser, TreeCursor, Language};
 use std::cell::RefCell;
@@ -1533,4 +1535,71 @@ items:
         println!("内存优化验证: {} 个节点共享同一份源码", node_count);
         println!("Arc<str> 引用计数: {}", initial_count);
     }
-}
\ No newline at end of file
+
+    /// 测试 JSON 文档按 JSON Pointer 定位节点
+    #[test]
+    fn test_find_by_pointer_json() {
+        let src = r#"{"paths": {"/pets": {"get": {"responses": {"200": {"description": "ok"}}}}}, "tags": ["a", "b"]}"#;
+        let cst = CstParser::parse(src);
+
+        let node = cst.find_by_pointer("/paths/~1pets/get/responses/200/description").unwrap();
+        assert_eq!(node.text(), r#""ok""#);
+        assert_eq!(&src[node.span().start_byte..node.span().end_byte], r#""ok""#);
+
+        let tag = cst.find_by_pointer("/tags/1").unwrap();
+        assert_eq!(tag.text(), r#""b""#);
+
+        assert_eq!(cst.find_by_pointer("").unwrap().kind, "object");
+        assert!(cst.find_by_pointer("/paths/~1dogs").is_none());
+        assert!(cst.find_by_pointer("/tags/5").is_none());
+        assert!(cst.find_by_pointer("paths").is_none());
+    }
+
+    /// 测试 YAML 文档按 JSON Pointer 定位节点，包括引号键和序列
+    #[test]
+    fn test_find_by_pointer_yaml() {
+        let src = "openapi: 3.0.0\npaths:\n  /pets:\n    get:\n      responses:\n        '200':\n          description: ok\n  \"/a~b\":\n    post: {}\ntags:\n  - name: pets\n  - name: dogs\nservers: [x, y]\n";
+        let cst = CstParser::parse_as(src, SourceType::Yaml);
+
+        let node = cst.find_by_pointer("/paths/~1pets/get/responses/200/description").unwrap();
+        assert_eq!(node.text(), "ok");
+        assert_eq!(node.span().start_point.row, 6);
+
+        assert_eq!(cst.find_by_pointer("/paths/~1a~0b/post").unwrap().text(), "{}");
+        assert_eq!(cst.find_by_pointer("/tags/1/name").unwrap().text(), "dogs");
+        assert_eq!(cst.find_by_pointer("/servers/1").unwrap().text(), "y");
+        assert!(cst.find_by_pointer("/openapi/0").is_none());
+    }
+
+    /// 测试从字节偏移反查语义路径
+    #[test]
+    fn test_pointer_at_offset() {
+        let src = "paths:\n  /pets:\n    get:\n      summary: List pets\ntags:\n  - name: pets\n";
+        let cst = CstParser::parse_as(src, SourceType::Yaml);
+
+        let offset = src.find("List").unwrap();
+        assert_eq!(cst.pointer_at_offset(offset).as_deref(), Some("/paths/~1pets/get/summary"));
+
+        let key_offset = src.find("summary").unwrap();
+        assert_eq!(cst.pointer_at_offset(key_offset).as_deref(), Some("/paths/~1pets/get/summary"));
+
+        let item_offset = src.rfind("pets").unwrap();
+        assert_eq!(cst.pointer_at_offset(item_offset).as_deref(), Some("/tags/0/name"));
+
+        let json = r#"{"a": [1, {"b": true}]}"#;
+        let json_cst = CstParser::parse(json);
+        let path = json_cst.path_at_offset(json.find("true").unwrap()).unwrap();
+        assert_eq!(path, ["a", "1", "b"]);
+        assert_eq!(json_cst.pointer_at_offset(0).as_deref(), Some(""));
+        assert!(json_cst.pointer_at_offset(json.len() + 10).is_none());
+    }
+
+    /// 测试 JSON Pointer 的转义与反转义
+    #[test]
+    fn test_json_pointer_escaping() {
+        assert_eq!(parse_json_pointer("/a~1b/c~0d").unwrap(), vec!["a/b", "c~d"]);
+        assert_eq!(parse_json_pointer("").unwrap(), Vec::<String>::new());
+        assert!(parse_json_pointer("/bad~2").is_none());
+        assert_eq!(to_json_pointer(&["a/b", "c~d"]), "/a~1b/c~0d");
+    }
+}
@@ -0,0 +1,213 @@
+//! 基于路径的 CST 导航
+//!
+//! 在 JSON 与 YAML CST 上按 JSON Pointer（RFC 6901）定位节点，
+//! 以及把字节偏移反向映射为语义路径，供诊断和编辑器工具使用。
+
+use super::node::TreeCursorSyntaxNode;
+
+/// 节点在源码中的位置范围
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceSpan {
+    /// 起始字节位置
+    pub start_byte: usize,
+    /// 结束字节位置（不含）
+    pub end_byte: usize,
+    /// 起始行列（从 0 开始）
+    pub start_point: tree_sitter::Point,
+    /// 结束行列（从 0 开始）
+    pub end_point: tree_sitter::Point,
+}
+
+/// 解析 JSON Pointer 为未转义的路径段
+///
+/// 空字符串表示根节点；其他指针必须以 `/` 开头，`~1` 还原为 `/`，`~0` 还原为 `~`。
+///
+/// # Returns
+/// 路径段列表，指针格式无效时返回 None
+pub fn parse_json_pointer(pointer: &str) -> Option<Vec<String>> {
+    if pointer.is_empty() {
+        return Some(Vec::new());
+    }
+    let rest = pointer.strip_prefix('/')?;
+    rest.split('/')
+        .map(|segment| {
+            if segment.replace("~0", "").replace("~1", "").contains('~') {
+                None
+            } else {
+                Some(segment.replace("~1", "/").replace("~0", "~"))
+            }
+        })
+        .collect()
+}
+
+/// 将路径段编码为 JSON Pointer
+pub fn to_json_pointer<S: AsRef<str>>(segments: &[S]) -> String {
+    segments
+        .iter()
+        .map(|segment| format!("/{}", segment.as_ref().replace('~', "~0").replace('/', "~1")))
+        .collect()
+}
+
+/// 只包裹内容、没有自身语义的节点
+const WRAPPER_KINDS: &[&str] = &["stream", "document", "block_node", "flow_node"];
+/// 值节点之外的修饰节点
+const DECORATION_KINDS: &[&str] = &["anchor", "tag", "comment"];
+/// 映射中的键值对节点
+const PAIR_KINDS: &[&str] = &["pair", "block_mapping_pair", "flow_pair"];
+
+impl TreeCursorSyntaxNode {
+    /// 获取节点的位置范围
+    pub fn span(&self) -> SourceSpan {
+        SourceSpan {
+            start_byte: self.start_byte,
+            end_byte: self.end_byte,
+            start_point: self.start_point,
+            end_point: self.end_point,
+        }
+    }
+
+    /// 按 JSON Pointer 查找值节点
+    ///
+    /// 同时适用于 JSON 和 YAML CST。返回的是去掉 `block_node`、锚点等包装后的值节点，
+    /// 例如 `object`、`block_mapping` 或标量节点。
+    ///
+    /// # Example
+    /// ```
+    /// # use stepflow_openapi::{CstParser, SourceType};
+    /// let cst = CstParser::parse_as("paths:\n  /pets:\n    get: {}\n", SourceType::Yaml);
+    /// let node = cst.find_by_pointer("/paths/~1pets/get").unwrap();
+    /// assert_eq!(node.text(), "{}");
+    /// ```
+    pub fn find_by_pointer(&self, pointer: &str) -> Option<&TreeCursorSyntaxNode> {
+        self.find_by_path(&parse_json_pointer(pointer)?)
+    }
+
+    /// 按已拆分的路径段查找值节点；序列用十进制下标定位
+    pub fn find_by_path<S: AsRef<str>>(&self, segments: &[S]) -> Option<&TreeCursorSyntaxNode> {
+        let mut node = self.semantic_value();
+        for segment in segments {
+            let segment = segment.as_ref();
+            node = if node.is_mapping() {
+                node.mapping_entries()
+                    .find(|(key, _)| key == segment)
+                    .map(|(_, value)| value)?
+            } else if node.is_sequence() {
+                let index: usize = segment.parse().ok()?;
+                node.sequence_items().nth(index)?
+            } else {
+                return None;
+            };
+        }
+        Some(node)
+    }
+
+    /// 获取覆盖字节偏移的最深语义路径
+    ///
+    /// 偏移落在键上时返回该键对应成员的路径；不在节点范围内时返回 None。
+    pub fn path_at_offset(&self, offset: usize) -> Option<Vec<String>> {
+        if !self.contains_offset(offset) {
+            return None;
+        }
+
+        let mut path = Vec::new();
+        let mut node = self.semantic_value();
+        loop {
+            let next = if node.is_mapping() {
+                node.mapping_pairs().find(|pair| pair.contains_offset(offset)).and_then(|pair| {
+                    let (key, value) = pair.pair_entry()?;
+                    path.push(key);
+                    Some(value).filter(|value| value.contains_offset(offset))
+                })
+            } else if node.is_sequence() {
+                node.sequence_items().enumerate().find(|(_, item)| item.contains_offset(offset)).map(
+                    |(index, item)| {
+                        path.push(index.to_string());
+                        item
+                    },
+                )
+            } else {
+                None
+            };
+
+            match next {
+                Some(next) => node = next,
+                None => return Some(path),
+            }
+        }
+    }
+
+    /// 获取覆盖字节偏移的语义路径，编码为 JSON Pointer
+    pub fn pointer_at_offset(&self, offset: usize) -> Option<String> {
+        self.path_at_offset(offset).map(|path| to_json_pointer(&path))
+    }
+
+    fn contains_offset(&self, offset: usize) -> bool {
+        self.start_byte <= offset && offset < self.end_byte
+    }
+
+    /// 跳过包装节点和锚点、标签，得到实际的值节点
+    fn semantic_value(&self) -> &TreeCursorSyntaxNode {
+        let mut node = self;
+        while WRAPPER_KINDS.contains(&node.kind.as_str()) {
+            match node
+                .children
+                .iter()
+                .rfind(|child| child.named && !DECORATION_KINDS.contains(&child.kind.as_str()))
+            {
+                Some(child) => node = child,
+                None => break,
+            }
+        }
+        node
+    }
+
+    fn is_mapping(&self) -> bool {
+        matches!(self.kind.as_str(), "object" | "block_mapping" | "flow_mapping")
+    }
+
+    fn is_sequence(&self) -> bool {
+        matches!(self.kind.as_str(), "array" | "block_sequence" | "flow_sequence")
+    }
+
+    fn mapping_pairs(&self) -> impl Iterator<Item = &TreeCursorSyntaxNode> {
+        self.children.iter().filter(|child| PAIR_KINDS.contains(&child.kind.as_str()))
+    }
+
+    fn mapping_entries(&self) -> impl Iterator<Item = (String, &TreeCursorSyntaxNode)> {
+        self.mapping_pairs().filter_map(|pair| pair.pair_entry())
+    }
+
+    /// 键值对的键文本和值节点
+    fn pair_entry(&self) -> Option<(String, &TreeCursorSyntaxNode)> {
+        let key = self.children.iter().find(|child| child.field_name() == Some("key"))?;
+        let value = self.children.iter().find(|child| child.field_name() == Some("value"))?;
+        Some((key.semantic_value().scalar_text(), value.semantic_value()))
+    }
+
+    fn sequence_items(&self) -> impl Iterator<Item = &TreeCursorSyntaxNode> {
+        self.children.iter().filter_map(|child| match child.kind.as_str() {
+            "block_sequence_item" => child
+                .children
+                .iter()
+                .find(|item| item.named && item.kind != "comment")
+                .map(|item| item.semantic_value()),
+            kind if child.named && kind != "comment" => Some(child.semantic_value()),
+            _ => None,
+        })
+    }
+
+    /// 标量的文本值，去掉引号并处理转义
+    fn scalar_text(&self) -> String {
+        let text = self.text();
+        match self.kind.as_str() {
+            "string" | "double_quote_scalar" => serde_json::from_str::<String>(&text)
+                .unwrap_or_else(|_| text.trim_matches('"').to_string()),
+            "single_quote_scalar" => text
+                .strip_prefix('\'')
+                .and_then(|text| text.strip_suffix('\''))
+                .unwrap_or(&text)
+                .replace("''", "'"),
+            _ => text.trim().to_string(),
+        }
+    }
+}
@@ -14,6 +14,9 @@ use thiserror::Error;
 
 use crate::srn::{Srn, SrnError};
 
+/// Prefix of the vendor extensions kept on [`OperationInfo::extensions`]
+pub const STEPFLOW_EXTENSION_PREFIX: &str = "x-stepflow-";
+
 /// Document Manager Errors
 #[derive(Debug, Error)]
 pub enum DocumentError {
@@ -99,6 +102,9 @@ pub struct OperationInfo {
     pub request_body: Option<RequestBodyInfo>,
     pub responses: HashMap<String, ResponseInfo>,
     pub tags: Vec<String>,
+    /// `x-stepflow-*` vendor extensions of the operation
+    #[serde(default)]
+    pub extensions: HashMap<String, Value>,
 }
 
 /// Parameter information
@@ -140,6 +146,9 @@ pub struct ResponseInfo {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MediaTypeInfo {
     pub schema: Option<Value>,
+    /// Per-property encoding of multipart and form bodies
+    #[serde(default)]
+    pub encoding: HashMap<String, Value>,
 }
 
 /// Schema information
@@ -323,32 +332,47 @@ impl DocumentManager {
             .ok_or_else(|| DocumentError::ValidationFailed("Invalid paths section".to_string()))?;
 
         for (path, path_item) in paths {
-            if let Some(path_obj) = path_item.as_object() {
-                for (method, operation) in path_obj {
-                    if matches!(method.as_str(), "get" | "post" | "put" | "delete" | "patch" | "head" | "options") {
-                        if let Some(op_obj) = operation.as_object() {
-                            let operation_id = op_obj.get("operationId")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or(&format!("{}_{}", method, path.replace('/', "_").trim_start_matches('_')))
-                                .to_string();
-
-                            let srn = Srn::openapi_operation(tenant_id, namespace, &operation_id)?;
-
-                            let operation_info = OperationInfo {
-                                srn,
-                                operation_id,
-                                method: method.to_uppercase(),
-                                path: path.clone(),
-                                summary: op_obj.get("summary").and_then(|v| v.as_str()).map(String::from),
-                                description: op_obj.get("description").and_then(|v| v.as_str()).map(String::from),
-                                parameters: self.extract_parameters(op_obj)?,
-                                request_body: self.extract_request_body(op_obj)?,
-                                responses: self.extract_responses(op_obj)?,
-                                tags: self.extract_tags(op_obj),
-                            };
-
-                            operations.push(operation_info);
-                        }
+            operations.extend(Self::extract_path_item_operations(path, path_item, tenant_id, namespace)?);
+        }
+
+        Ok(operations)
+    }
+
+    /// Extract the operations of a single path item
+    pub(crate) fn extract_path_item_operations(
+        path: &str,
+        path_item: &Value,
+        tenant_id: &str,
+        namespace: &str,
+    ) -> Result<Vec<OperationInfo>, DocumentError> {
+        let mut operations = Vec::new();
+
+        if let Some(path_obj) = path_item.as_object() {
+            for (method, operation) in path_obj {
+                if matches!(method.as_str(), "get" | "post" | "put" | "delete" | "patch" | "head" | "options") {
+                    if let Some(op_obj) = operation.as_object() {
+                        let operation_id = op_obj.get("operationId")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or(&format!("{}_{}", method, path.replace('/', "_").trim_start_matches('_')))
+                            .to_string();
+
+                        let srn = Srn::openapi_operation(tenant_id, namespace, &operation_id)?;
+
+                        let operation_info = OperationInfo {
+                            srn,
+                            operation_id,
+                            method: method.to_uppercase(),
+                            path: path.to_string(),
+                            summary: op_obj.get("summary").and_then(|v| v.as_str()).map(String::from),
+                            description: op_obj.get("description").and_then(|v| v.as_str()).map(String::from),
+                            parameters: Self::extract_parameters(op_obj)?,
+                            request_body: Self::extract_request_body(op_obj)?,
+                            responses: Self::extract_responses(op_obj)?,
+                            tags: Self::extract_tags(op_obj),
+                            extensions: Self::extract_extensions(op_obj),
+                        };
+
+                        operations.push(operation_info);
                     }
                 }
             }
@@ -369,18 +393,7 @@ impl DocumentManager {
         if let Some(components) = parsed.get("components").and_then(|c| c.as_object()) {
             if let Some(schemas_obj) = components.get("schemas").and_then(|s| s.as_object()) {
                 for (schema_name, schema_value) in schemas_obj {
-                    let srn = Srn::openapi_schema(tenant_id, namespace, schema_name)?;
-                    
-                    let schema_info = SchemaInfo {
-                        srn,
-                        name: schema_name.clone(),
-                        schema: schema_value.clone(),
-                        description: schema_value.get("description")
-                            .and_then(|v| v.as_str())
-                            .map(String::from),
-                    };
-
-                    schemas.push(schema_info);
+                    schemas.push(Self::extract_schema(schema_name, schema_value.clone(), tenant_id, namespace)?);
                 }
             }
         }
@@ -388,8 +401,27 @@ impl DocumentManager {
         Ok(schemas)
     }
 
+    /// Build the schema info for a single component schema
+    pub(crate) fn extract_schema(
+        schema_name: &str,
+        schema_value: Value,
+        tenant_id: &str,
+        namespace: &str,
+    ) -> Result<SchemaInfo, DocumentError> {
+        let srn = Srn::openapi_schema(tenant_id, namespace, schema_name)?;
+
+        Ok(SchemaInfo {
+            srn,
+            name: schema_name.to_string(),
+            description: schema_value.get("description")
+                .and_then(|v| v.as_str())
+                .map(String::from),
+            schema: schema_value,
+        })
+    }
+
     /// Extract parameters from operation
-    fn extract_parameters(&self, operation: &serde_json::Map<String, Value>) -> Result<Vec<ParameterInfo>, DocumentError> {
+    fn extract_parameters(operation: &serde_json::Map<String, Value>) -> Result<Vec<ParameterInfo>, DocumentError> {
         let mut parameters = Vec::new();
 
         if let Some(params) = operation.get("parameters").and_then(|p| p.as_array()) {
@@ -439,7 +471,7 @@ impl DocumentManager {
     }
 
     /// Extract request body from operation
-    fn extract_request_body(&self, operation: &serde_json::Map<String, Value>) -> Result<Option<RequestBodyInfo>, DocumentError> {
+    fn extract_request_body(operation: &serde_json::Map<String, Value>) -> Result<Option<RequestBodyInfo>, DocumentError> {
         if let Some(request_body) = operation.get("requestBody").and_then(|rb| rb.as_object()) {
             let required = request_body.get("required")
                 .and_then(|v| v.as_bool())
@@ -452,8 +484,7 @@ impl DocumentManager {
             let mut content = HashMap::new();
             if let Some(content_obj) = request_body.get("content").and_then(|c| c.as_object()) {
                 for (media_type, media_info) in content_obj {
-                    let schema = media_info.get("schema").cloned();
-                    content.insert(media_type.clone(), MediaTypeInfo { schema });
+                    content.insert(media_type.clone(), Self::extract_media_type(media_info));
                 }
             }
 
@@ -467,8 +498,19 @@ impl DocumentManager {
         }
     }
 
+    /// Extract a media type object
+    fn extract_media_type(media_info: &Value) -> MediaTypeInfo {
+        MediaTypeInfo {
+            schema: media_info.get("schema").cloned(),
+            encoding: media_info.get("encoding")
+                .and_then(|e| e.as_object())
+                .map(|e| e.clone().into_iter().collect())
+                .unwrap_or_default(),
+        }
+    }
+
     /// Extract responses from operation
-    fn extract_responses(&self, operation: &serde_json::Map<String, Value>) -> Result<HashMap<String, ResponseInfo>, DocumentError> {
+    fn extract_responses(operation: &serde_json::Map<String, Value>) -> Result<HashMap<String, ResponseInfo>, DocumentError> {
         let mut responses = HashMap::new();
 
         if let Some(responses_obj) = operation.get("responses").and_then(|r| r.as_object()) {
@@ -483,8 +525,7 @@ impl DocumentManager {
                     if let Some(content_obj) = response_obj.get("content").and_then(|c| c.as_object()) {
                         let mut content_map = HashMap::new();
                         for (media_type, media_info) in content_obj {
-                            let schema = media_info.get("schema").cloned();
-                            content_map.insert(media_type.clone(), MediaTypeInfo { schema });
+                            content_map.insert(media_type.clone(), Self::extract_media_type(media_info));
                         }
                         content = Some(content_map);
                     }
@@ -510,7 +551,7 @@ impl DocumentManager {
     }
 
     /// Extract tags from operation
-    fn extract_tags(&self, operation: &serde_json::Map<String, Value>) -> Vec<String> {
+    fn extract_tags(operation: &serde_json::Map<String, Value>) -> Vec<String> {
         operation.get("tags")
             .and_then(|t| t.as_array())
             .map(|tags| {
@@ -522,6 +563,14 @@ impl DocumentManager {
             .unwrap_or_default()
     }
 
+    /// Extract `x-stepflow-*` vendor extensions from operation
+    fn extract_extensions(operation: &serde_json::Map<String, Value>) -> HashMap<String, Value> {
+        operation.iter()
+            .filter(|(key, _)| key.starts_with(STEPFLOW_EXTENSION_PREFIX))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect()
+    }
+
     /// Extract servers from document
     fn extract_servers(&self, parsed: &Value) -> Vec<String> {
         parsed.get("servers")
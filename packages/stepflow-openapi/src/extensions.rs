@@ -0,0 +1,318 @@
+//! Stepflow Vendor Extensions
+//!
+//! Operation-level `x-stepflow-*` extensions that customize generated tools:
+//!
+//! - `x-stepflow-tool-name`: display name of the generated tool
+//! - `x-stepflow-hidden`: skip the operation when generating tools for a document
+//! - `x-stepflow-sandbox-profile`: sandbox profile the tool should run under
+//! - `x-stepflow-timeout`: request timeout, in milliseconds or as `"500ms"`, `"30s"`, `"2m"`
+//! - `x-stepflow-pagination`: how to follow the pages of a list operation
+//! - `x-stepflow-rate-limit`: documented request rate of the upstream host
+//!
+//! Any other `x-stepflow-*` key is rejected so typos do not silently change nothing.
+//! [`extension_descriptor`] describes the supported keys as JSON Schema.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use thiserror::Error;
+
+use crate::document::STEPFLOW_EXTENSION_PREFIX;
+use crate::proxy::pagination::PaginationConfig;
+use crate::proxy::rate_limit::RateLimit;
+
+pub const TOOL_NAME_EXTENSION: &str = "x-stepflow-tool-name";
+pub const HIDDEN_EXTENSION: &str = "x-stepflow-hidden";
+pub const SANDBOX_PROFILE_EXTENSION: &str = "x-stepflow-sandbox-profile";
+pub const TIMEOUT_EXTENSION: &str = "x-stepflow-timeout";
+pub const PAGINATION_EXTENSION: &str = "x-stepflow-pagination";
+pub const RATE_LIMIT_EXTENSION: &str = "x-stepflow-rate-limit";
+
+/// Vendor extension errors
+#[derive(Debug, Error, PartialEq)]
+pub enum ExtensionError {
+    #[error("Unknown vendor extension: {0}")]
+    Unknown(String),
+
+    #[error("Invalid value for {name}: {reason}")]
+    InvalidValue { name: String, reason: String },
+}
+
+/// Parsed `x-stepflow-*` extensions of an operation
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StepflowExtensions {
+    /// Display name of the generated tool
+    pub tool_name: Option<String>,
+    /// Whether the operation is skipped when generating tools for a document
+    pub hidden: bool,
+    /// Sandbox profile for tool execution
+    pub sandbox_profile: Option<String>,
+    /// Request timeout in milliseconds
+    pub timeout_ms: Option<u64>,
+    /// Pagination strategy for collecting all pages
+    pub pagination: Option<PaginationConfig>,
+    /// Request rate the upstream host allows
+    pub rate_limit: Option<RateLimit>,
+}
+
+impl StepflowExtensions {
+    /// Parse and validate the extensions of an operation
+    pub fn parse(extensions: &HashMap<String, Value>) -> Result<Self, ExtensionError> {
+        let mut parsed = Self::default();
+
+        let mut names: Vec<&String> = extensions.keys().collect();
+        names.sort();
+        for name in names {
+            let value = &extensions[name];
+            match name.as_str() {
+                TOOL_NAME_EXTENSION => parsed.tool_name = Some(non_empty_string(name, value)?),
+                HIDDEN_EXTENSION => {
+                    parsed.hidden = value.as_bool().ok_or_else(|| invalid(name, "expected a boolean"))?;
+                }
+                SANDBOX_PROFILE_EXTENSION => {
+                    let profile = non_empty_string(name, value)?;
+                    if !profile.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+                        return Err(invalid(name, "expected letters, digits, '-' or '_'"));
+                    }
+                    parsed.sandbox_profile = Some(profile);
+                }
+                TIMEOUT_EXTENSION => parsed.timeout_ms = Some(parse_timeout(name, value)?),
+                PAGINATION_EXTENSION => {
+                    parsed.pagination = Some(
+                        serde_json::from_value(value.clone()).map_err(|e| invalid(name, &e.to_string()))?,
+                    );
+                }
+                RATE_LIMIT_EXTENSION => parsed.rate_limit = Some(parse_rate_limit(name, value)?),
+                other if other.starts_with(STEPFLOW_EXTENSION_PREFIX) => {
+                    return Err(ExtensionError::Unknown(other.to_string()));
+                }
+                _ => {}
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    /// Whether the raw extensions mark the operation as hidden
+    pub fn is_hidden(extensions: &HashMap<String, Value>) -> bool {
+        extensions.get(HIDDEN_EXTENSION) == Some(&Value::Bool(true))
+    }
+}
+
+fn invalid(name: &str, reason: &str) -> ExtensionError {
+    ExtensionError::InvalidValue {
+        name: name.to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+fn non_empty_string(name: &str, value: &Value) -> Result<String, ExtensionError> {
+    match value.as_str().map(str::trim) {
+        Some(text) if !text.is_empty() => Ok(text.to_string()),
+        _ => Err(invalid(name, "expected a non-empty string")),
+    }
+}
+
+fn parse_timeout(name: &str, value: &Value) -> Result<u64, ExtensionError> {
+    let timeout_ms = match value {
+        Value::Number(n) => n.as_u64(),
+        Value::String(text) => {
+            let (digits, scale) = if let Some(ms) = text.strip_suffix("ms") {
+                (ms, 1)
+            } else if let Some(s) = text.strip_suffix('s') {
+                (s, 1000)
+            } else if let Some(m) = text.strip_suffix('m') {
+                (m, 60_000)
+            } else {
+                (text.as_str(), 1)
+            };
+            digits.parse::<u64>().ok().and_then(|n| n.checked_mul(scale))
+        }
+        _ => None,
+    };
+
+    match timeout_ms {
+        Some(ms) if ms > 0 => Ok(ms),
+        _ => Err(invalid(name, "expected a positive number of milliseconds or a duration like \"30s\"")),
+    }
+}
+
+fn parse_rate_limit(name: &str, value: &Value) -> Result<RateLimit, ExtensionError> {
+    let limit: RateLimit = serde_json::from_value(value.clone()).map_err(|e| invalid(name, &e.to_string()))?;
+    if !(limit.requests_per_second.is_finite() && limit.requests_per_second > 0.0) {
+        return Err(invalid(name, "requests_per_second must be positive"));
+    }
+    if limit.burst == 0 {
+        return Err(invalid(name, "burst must be at least 1"));
+    }
+    Ok(limit)
+}
+
+/// Machine-readable description of the supported extensions
+pub fn extension_descriptor() -> Value {
+    json!({
+        "prefix": STEPFLOW_EXTENSION_PREFIX,
+        "scope": "operation",
+        "unknown_extensions": "rejected",
+        "extensions": [
+            {
+                "name": TOOL_NAME_EXTENSION,
+                "description": "Display name of the generated tool; defaults to \"<METHOD> <path>\"",
+                "schema": {"type": "string", "minLength": 1}
+            },
+            {
+                "name": HIDDEN_EXTENSION,
+                "description": "Skip the operation when generating tools for the whole document",
+                "schema": {"type": "boolean", "default": false}
+            },
+            {
+                "name": SANDBOX_PROFILE_EXTENSION,
+                "description": "Sandbox profile the tool runs under",
+                "schema": {"type": "string", "pattern": "^[A-Za-z0-9_-]+$"}
+            },
+            {
+                "name": TIMEOUT_EXTENSION,
+                "description": "Request timeout; overrides the request-wide timeout but not tool_config_overrides",
+                "schema": {
+                    "oneOf": [
+                        {"type": "integer", "minimum": 1, "description": "Milliseconds"},
+                        {"type": "string", "pattern": "^[0-9]+(ms|s|m)?$"}
+                    ]
+                }
+            },
+            {
+                "name": PAGINATION_EXTENSION,
+                "description": "Pagination of a list operation; callers opt in per call with the collect_all_pages configuration flag",
+                "schema": {
+                    "type": "object",
+                    "required": ["strategy"],
+                    "properties": {
+                        "strategy": {
+                            "oneOf": [
+                                {
+                                    "type": "object",
+                                    "required": ["type"],
+                                    "properties": {
+                                        "type": {"const": "page"},
+                                        "page_param": {"type": "string", "default": "page"},
+                                        "per_page_param": {"type": "string"},
+                                        "per_page": {"type": "integer", "minimum": 1},
+                                        "start_page": {"type": "integer", "default": 1}
+                                    }
+                                },
+                                {
+                                    "type": "object",
+                                    "required": ["type", "cursor_param", "cursor_path"],
+                                    "properties": {
+                                        "type": {"const": "cursor"},
+                                        "cursor_param": {"type": "string"},
+                                        "cursor_path": {"type": "string"}
+                                    }
+                                },
+                                {
+                                    "type": "object",
+                                    "required": ["type"],
+                                    "properties": {"type": {"const": "link_header"}}
+                                }
+                            ]
+                        },
+                        "items_path": {"type": "string", "description": "JSON Pointer or dotted path to the item array"},
+                        "max_pages": {"type": "integer", "minimum": 1, "default": 50},
+                        "max_items": {"type": "integer", "minimum": 1, "default": 10000},
+                        "collect_by_default": {"type": "boolean", "default": false}
+                    }
+                }
+            },
+            {
+                "name": RATE_LIMIT_EXTENSION,
+                "description": "Documented request rate of the upstream host; requests beyond it are queued, and the limit is shared by every tool calling the host",
+                "schema": {
+                    "type": "object",
+                    "required": ["requests_per_second"],
+                    "properties": {
+                        "requests_per_second": {"type": "number", "exclusiveMinimum": 0},
+                        "burst": {"type": "integer", "minimum": 1, "default": 1}
+                    }
+                }
+            }
+        ]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extensions(value: Value) -> HashMap<String, Value> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_parse_extensions() {
+        let parsed = StepflowExtensions::parse(&extensions(json!({
+            "x-stepflow-tool-name": "List pets",
+            "x-stepflow-hidden": false,
+            "x-stepflow-sandbox-profile": "network-only",
+            "x-stepflow-timeout": "45s",
+            "x-stepflow-rate-limit": {"requests_per_second": 5},
+            "x-other-vendor": 1
+        })))
+        .unwrap();
+
+        assert_eq!(parsed, StepflowExtensions {
+            tool_name: Some("List pets".to_string()),
+            hidden: false,
+            sandbox_profile: Some("network-only".to_string()),
+            timeout_ms: Some(45_000),
+            pagination: None,
+            rate_limit: Some(RateLimit { requests_per_second: 5.0, burst: 1 }),
+        });
+        assert_eq!(StepflowExtensions::parse(&HashMap::new()).unwrap(), StepflowExtensions::default());
+    }
+
+    #[test]
+    fn test_timeout_formats() {
+        let timeout = |value: Value| StepflowExtensions::parse(&extensions(json!({ TIMEOUT_EXTENSION: value }))).map(|e| e.timeout_ms);
+
+        assert_eq!(timeout(json!(2500)), Ok(Some(2500)));
+        assert_eq!(timeout(json!("250ms")), Ok(Some(250)));
+        assert_eq!(timeout(json!("2m")), Ok(Some(120_000)));
+        assert_eq!(timeout(json!("100")), Ok(Some(100)));
+        assert!(timeout(json!(0)).is_err());
+        assert!(timeout(json!("soon")).is_err());
+        assert!(timeout(json!(-5)).is_err());
+    }
+
+    #[test]
+    fn test_invalid_extensions() {
+        assert_eq!(
+            StepflowExtensions::parse(&extensions(json!({"x-stepflow-hiden": true}))),
+            Err(ExtensionError::Unknown("x-stepflow-hiden".to_string()))
+        );
+        assert!(matches!(
+            StepflowExtensions::parse(&extensions(json!({HIDDEN_EXTENSION: "yes"}))),
+            Err(ExtensionError::InvalidValue { .. })
+        ));
+        assert!(StepflowExtensions::parse(&extensions(json!({TOOL_NAME_EXTENSION: "  "}))).is_err());
+        assert!(StepflowExtensions::parse(&extensions(json!({SANDBOX_PROFILE_EXTENSION: "a b"}))).is_err());
+        assert!(StepflowExtensions::parse(&extensions(json!({PAGINATION_EXTENSION: {"strategy": {"type": "offset"}}}))).is_err());
+        assert!(StepflowExtensions::parse(&extensions(json!({RATE_LIMIT_EXTENSION: {"requests_per_second": 0}}))).is_err());
+        assert!(StepflowExtensions::parse(&extensions(json!({RATE_LIMIT_EXTENSION: {"requests_per_second": 1, "burst": 0}}))).is_err());
+    }
+
+    #[test]
+    fn test_descriptor_lists_every_extension() {
+        let descriptor = extension_descriptor();
+        let names: Vec<&str> = descriptor["extensions"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|extension| extension["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            [TOOL_NAME_EXTENSION, HIDDEN_EXTENSION, SANDBOX_PROFILE_EXTENSION, TIMEOUT_EXTENSION, PAGINATION_EXTENSION, RATE_LIMIT_EXTENSION]
+        );
+    }
+}
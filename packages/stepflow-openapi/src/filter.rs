@@ -0,0 +1,293 @@
+//! Operation Selection Rules
+//!
+//! Include/exclude rules that pick which operations of a document get tools.
+//! An operation is selected when it matches at least one include rule (or there
+//! are no include rules) and matches no exclude rule.
+
+use std::collections::HashMap;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::document::OperationInfo;
+
+/// Conditions on an operation; a rule matches when every non-empty condition holds
+///
+/// A rule with no conditions matches every operation.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OperationRule {
+    /// Operation has at least one of these tags
+    pub tags: Vec<String>,
+    /// Path matches one of these globs; `*` matches within a path segment,
+    /// `**` across segments and `?` a single character
+    pub paths: Vec<String>,
+    /// HTTP method is one of these (case-insensitive)
+    pub methods: Vec<String>,
+    /// Operation ID matches this regular expression
+    pub operation_id: Option<String>,
+    /// `x-stepflow-*` extensions with the given values; `null` only requires the
+    /// extension to be present
+    pub extensions: HashMap<String, Value>,
+}
+
+/// Include/exclude rules for tool generation
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OperationFilter {
+    /// Select only operations matching one of these rules; empty selects all
+    pub include: Vec<OperationRule>,
+    /// Drop operations matching any of these rules
+    pub exclude: Vec<OperationRule>,
+}
+
+impl OperationFilter {
+    /// Whether the filter selects every operation
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Add an include rule
+    pub fn include(mut self, rule: OperationRule) -> Self {
+        self.include.push(rule);
+        self
+    }
+
+    /// Add an exclude rule
+    pub fn exclude(mut self, rule: OperationRule) -> Self {
+        self.exclude.push(rule);
+        self
+    }
+
+    /// Compile the patterns of every rule
+    pub fn compile(&self) -> Result<OperationMatcher, regex::Error> {
+        let compile_all = |rules: &[OperationRule]| rules.iter().map(CompiledRule::new).collect::<Result<Vec<_>, _>>();
+        Ok(OperationMatcher {
+            include: compile_all(&self.include)?,
+            exclude: compile_all(&self.exclude)?,
+        })
+    }
+}
+
+impl OperationRule {
+    /// Rule matching operations with any of `tags`
+    pub fn tags<S: Into<String>>(tags: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            tags: tags.into_iter().map(Into::into).collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Rule matching paths against any of `globs`
+    pub fn paths<S: Into<String>>(globs: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            paths: globs.into_iter().map(Into::into).collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Rule matching any of `methods`
+    pub fn methods<S: Into<String>>(methods: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            methods: methods.into_iter().map(Into::into).collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Rule matching operation IDs against `pattern`
+    pub fn operation_id(pattern: impl Into<String>) -> Self {
+        Self {
+            operation_id: Some(pattern.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Rule matching an `x-stepflow-*` extension value
+    pub fn extension(name: impl Into<String>, value: Value) -> Self {
+        Self {
+            extensions: HashMap::from([(name.into(), value)]),
+            ..Self::default()
+        }
+    }
+}
+
+/// Compiled [`OperationFilter`]
+#[derive(Debug, Clone)]
+pub struct OperationMatcher {
+    include: Vec<CompiledRule>,
+    exclude: Vec<CompiledRule>,
+}
+
+impl OperationMatcher {
+    /// Whether the operation is selected
+    pub fn matches(&self, operation: &OperationInfo) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|rule| rule.matches(operation)))
+            && !self.exclude.iter().any(|rule| rule.matches(operation))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CompiledRule {
+    tags: Vec<String>,
+    paths: Vec<Regex>,
+    methods: Vec<String>,
+    operation_id: Option<Regex>,
+    extensions: HashMap<String, Value>,
+}
+
+impl CompiledRule {
+    fn new(rule: &OperationRule) -> Result<Self, regex::Error> {
+        Ok(Self {
+            tags: rule.tags.clone(),
+            paths: rule.paths.iter().map(|glob| glob_to_regex(glob)).collect::<Result<_, _>>()?,
+            methods: rule.methods.iter().map(|method| method.to_uppercase()).collect(),
+            operation_id: rule.operation_id.as_deref().map(Regex::new).transpose()?,
+            extensions: rule.extensions.clone(),
+        })
+    }
+
+    fn matches(&self, operation: &OperationInfo) -> bool {
+        (self.tags.is_empty() || operation.tags.iter().any(|tag| self.tags.contains(tag)))
+            && (self.paths.is_empty() || self.paths.iter().any(|glob| glob.is_match(&operation.path)))
+            && (self.methods.is_empty() || self.methods.contains(&operation.method.to_uppercase()))
+            && self.operation_id.as_ref().is_none_or(|pattern| pattern.is_match(&operation.operation_id))
+            && self.extensions.iter().all(|(name, expected)| match operation.extensions.get(name) {
+                Some(actual) => expected.is_null() || actual == expected,
+                None => false,
+            })
+    }
+}
+
+/// Translate a path glob into an anchored regular expression
+fn glob_to_regex(glob: &str) -> Result<Regex, regex::Error> {
+    let mut pattern = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                pattern.push_str(".*");
+            }
+            '*' => pattern.push_str("[^/]*"),
+            '?' => pattern.push_str("[^/]"),
+            c => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern.push('$');
+    Regex::new(&pattern)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::srn::Srn;
+    use serde_json::json;
+
+    fn operation(operation_id: &str, method: &str, path: &str, tags: &[&str]) -> OperationInfo {
+        OperationInfo {
+            srn: Srn::openapi_operation("tenant", "pets", operation_id).unwrap(),
+            operation_id: operation_id.to_string(),
+            method: method.to_string(),
+            path: path.to_string(),
+            summary: None,
+            description: None,
+            parameters: vec![],
+            request_body: None,
+            responses: HashMap::new(),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            extensions: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_empty_filter_selects_everything() {
+        let matcher = OperationFilter::default().compile().unwrap();
+        assert!(matcher.matches(&operation("listPets", "GET", "/pets", &[])));
+    }
+
+    #[test]
+    fn test_rule_conditions() {
+        let list = operation("listPets", "GET", "/pets", &["pets"]);
+        let get = operation("getPet", "GET", "/pets/{id}", &["pets"]);
+        let delete = operation("deletePet", "DELETE", "/pets/{id}", &["pets", "admin"]);
+        let owners = operation("listOwners", "GET", "/owners/{id}/pets", &["owners"]);
+
+        let matches = |filter: OperationFilter, operation: &OperationInfo| filter.compile().unwrap().matches(operation);
+
+        assert!(matches(OperationFilter::default().include(OperationRule::tags(["owners"])), &owners));
+        assert!(!matches(OperationFilter::default().include(OperationRule::tags(["owners"])), &list));
+
+        let single_segment = OperationFilter::default().include(OperationRule::paths(["/pets/*"]));
+        assert!(matches(single_segment.clone(), &get));
+        assert!(!matches(single_segment, &list));
+        assert!(matches(OperationFilter::default().include(OperationRule::paths(["/**/pets"])), &owners));
+        assert!(matches(OperationFilter::default().include(OperationRule::paths(["/pet?"])), &list));
+
+        let read_only = OperationFilter::default().include(OperationRule::methods(["get", "head"]));
+        assert!(matches(read_only.clone(), &get));
+        assert!(!matches(read_only, &delete));
+
+        assert!(matches(OperationFilter::default().include(OperationRule::operation_id("^list")), &owners));
+        assert!(!matches(OperationFilter::default().include(OperationRule::operation_id("^list")), &delete));
+
+        // Conditions of one rule must all hold
+        let rule = OperationRule {
+            tags: vec!["pets".to_string()],
+            methods: vec!["GET".to_string()],
+            ..OperationRule::default()
+        };
+        assert!(matches(OperationFilter::default().include(rule.clone()), &list));
+        assert!(!matches(OperationFilter::default().include(rule), &delete));
+    }
+
+    #[test]
+    fn test_exclude_wins_over_include() {
+        let filter = OperationFilter::default()
+            .include(OperationRule::tags(["pets"]))
+            .exclude(OperationRule::tags(["admin"]));
+        let matcher = filter.compile().unwrap();
+
+        assert!(matcher.matches(&operation("listPets", "GET", "/pets", &["pets"])));
+        assert!(!matcher.matches(&operation("deletePet", "DELETE", "/pets/{id}", &["pets", "admin"])));
+    }
+
+    #[test]
+    fn test_extension_rules() {
+        let mut hidden = operation("internalSync", "POST", "/sync", &[]);
+        hidden.extensions.insert("x-stepflow-hidden".to_string(), json!(true));
+        let mut tier = operation("export", "GET", "/export", &[]);
+        tier.extensions.insert("x-stepflow-tier".to_string(), json!("premium"));
+        let plain = operation("listPets", "GET", "/pets", &[]);
+
+        let matcher = OperationFilter::default()
+            .exclude(OperationRule::extension("x-stepflow-hidden", json!(true)))
+            .compile()
+            .unwrap();
+        assert!(!matcher.matches(&hidden));
+        assert!(matcher.matches(&plain));
+
+        let matcher = OperationFilter::default()
+            .include(OperationRule::extension("x-stepflow-tier", Value::Null))
+            .compile()
+            .unwrap();
+        assert!(matcher.matches(&tier));
+        assert!(!matcher.matches(&plain));
+    }
+
+    #[test]
+    fn test_invalid_operation_id_pattern() {
+        let filter = OperationFilter::default().include(OperationRule::operation_id("(unclosed"));
+        assert!(filter.compile().is_err());
+    }
+
+    #[test]
+    fn test_filter_deserializes_with_defaults() {
+        let filter: OperationFilter = serde_json::from_value(json!({
+            "exclude": [{"methods": ["delete"]}]
+        }))
+        .unwrap();
+        assert!(filter.include.is_empty());
+        assert_eq!(filter.exclude[0].methods, ["delete"]);
+    }
+}
@@ -6,12 +6,20 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 use async_trait::async_trait;
+use futures::StreamExt;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use stepflow_core::types::Tool;
+use stepflow_database::BatchInsertReport;
+use stepflow_registry::Registry;
 use crate::srn::Srn;
-use crate::document::{OpenApiDocument, OperationInfo, DocumentManager};
+use crate::document::{OperationInfo, DocumentManager};
+use crate::extensions::{ExtensionError, StepflowExtensions};
+use crate::filter::{OperationFilter, OperationMatcher};
+use crate::stream::{SpecEvent, SpecStream, StreamContext};
+use crate::proxy::artifact::ArtifactStore;
+use crate::ref_resolver::RefResolver;
 use crate::tool::{OpenApiTool, OpenApiToolConfig, OpenApiToolError, AuthConfig};
 
 /// Tool generator errors
@@ -34,6 +42,15 @@ pub enum GeneratorError {
     
     #[error("Missing required field: {0}")]
     MissingRequiredField(String),
+
+    #[error("Tool persistence failed: {0}")]
+    PersistenceFailed(String),
+
+    #[error("Streaming parse failed: {0}")]
+    StreamingFailed(String),
+
+    #[error("Invalid vendor extension: {0}")]
+    InvalidExtension(#[from] ExtensionError),
 }
 
 /// Tool generation request
@@ -55,6 +72,9 @@ pub struct ToolGenerationRequest {
     pub auth: Option<AuthConfig>,
     /// Custom tool configuration overrides
     pub tool_config_overrides: Option<HashMap<String, serde_json::Value>>,
+    /// Rules selecting which operations get tools; ignored when `operation_id` is set
+    #[serde(default)]
+    pub filter: OperationFilter,
 }
 
 /// Tool generation result
@@ -68,6 +88,27 @@ pub struct ToolGenerationResult {
     pub warnings: Vec<String>,
     /// Generation metadata
     pub metadata: HashMap<String, serde_json::Value>,
+    /// Per-operation outcome, in document order
+    #[serde(default)]
+    pub operations: Vec<OperationOutcome>,
+}
+
+/// Outcome of generating the tool for one operation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OperationOutcome {
+    /// Operation ID
+    pub operation_id: String,
+    /// Operation SRN
+    pub srn: String,
+    /// Error message if no tool could be generated
+    pub error: Option<String>,
+}
+
+impl OperationOutcome {
+    /// Whether a tool was generated
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
 }
 
 /// Individual tool generation info
@@ -109,6 +150,35 @@ pub struct GeneratorConfig {
     pub include_tags: bool,
     /// Whether to generate examples for tools
     pub generate_examples: bool,
+    /// How stored documents are walked when generating tools
+    #[serde(default)]
+    pub parse_mode: ParseMode,
+    /// Parsed operations buffered ahead of tool creation in streaming mode
+    #[serde(default = "default_stream_buffer_size")]
+    pub stream_buffer_size: usize,
+    /// Maximum number of tools created concurrently
+    #[serde(default = "default_max_parallelism")]
+    pub max_parallelism: usize,
+}
+
+/// Document parsing strategy for tool generation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParseMode {
+    /// Use the operations extracted at upload and resolve `$ref`s against the whole document
+    #[default]
+    Full,
+    /// Re-read the document content one path item at a time with bounded memory;
+    /// `$ref`s are not resolved
+    Streaming,
+}
+
+fn default_stream_buffer_size() -> usize {
+    16
+}
+
+fn default_max_parallelism() -> usize {
+    std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4)
 }
 
 impl Default for GeneratorConfig {
@@ -120,6 +190,9 @@ impl Default for GeneratorConfig {
             default_max_retries: 3,
             include_tags: true,
             generate_examples: true,
+            parse_mode: ParseMode::Full,
+            stream_buffer_size: default_stream_buffer_size(),
+            max_parallelism: default_max_parallelism(),
         }
     }
 }
@@ -132,6 +205,8 @@ pub struct ToolGenerator {
     config: GeneratorConfig,
     /// Generated tools cache
     generated_tools: std::sync::RwLock<HashMap<String, GeneratedToolInfo>>,
+    /// Artifact store handed to generated tools
+    artifact_store: Option<Arc<dyn ArtifactStore>>,
 }
 
 impl ToolGenerator {
@@ -141,9 +216,16 @@ impl ToolGenerator {
             document_manager,
             config,
             generated_tools: std::sync::RwLock::new(HashMap::new()),
+            artifact_store: None,
         }
     }
 
+    /// Give generated tools an artifact store for file uploads and large binary responses
+    pub fn with_artifact_store(mut self, store: Arc<dyn ArtifactStore>) -> Self {
+        self.artifact_store = Some(store);
+        self
+    }
+
     /// Create with default configuration
     pub fn with_default_config(document_manager: Arc<DocumentManager>) -> Self {
         Self::new(document_manager, GeneratorConfig::default())
@@ -161,9 +243,21 @@ impl ToolGenerator {
             .map_err(|e| GeneratorError::DocumentNotFound(format!("Failed to load document: {}", e)))?
             .ok_or_else(|| GeneratorError::DocumentNotFound(request.document_id.clone()))?;
 
-        let mut generated_tools = Vec::new();
-        let mut warnings = Vec::new();
+        if self.config.parse_mode == ParseMode::Streaming {
+            let document_name = document.meta.name.clone();
+            let stream = SpecStream::from_reader(
+                std::io::Cursor::new(document.content.into_bytes()),
+                document.meta.format,
+                StreamContext::new(document.meta.tenant_id, document.meta.namespace),
+                self.config.stream_buffer_size,
+            );
+            let mut result = self.generate_tools_streaming(&request, stream).await?;
+            result.metadata.insert("document_name".to_string(), serde_json::Value::String(document_name));
+            return Ok(result);
+        }
+
         let mut metadata = HashMap::new();
+        let mut hidden_operations = 0;
 
         // Determine which operations to generate tools for
         let operations_to_generate = if let Some(operation_id) = &request.operation_id {
@@ -173,66 +267,318 @@ impl ToolGenerator {
                 .ok_or_else(|| GeneratorError::OperationNotFound(operation_id.clone()))?;
             vec![operation.clone()]
         } else if self.config.generate_all_operations {
-            // Generate for all operations
-            document.operations.clone()
+            // Generate for all visible operations selected by the filter
+            let matcher = Self::compile_filter(&request)?;
+            document.operations.iter()
+                .filter(|op| {
+                    let hidden = StepflowExtensions::is_hidden(&op.extensions);
+                    hidden_operations += usize::from(hidden);
+                    !hidden
+                })
+                .filter(|op| matcher.as_ref().is_none_or(|matcher| matcher.matches(op)))
+                .cloned()
+                .collect()
         } else {
             return Err(GeneratorError::InvalidConfiguration(
                 "No operation specified and generate_all_operations is false".to_string()
             ));
         };
 
-        // Generate tools for each operation
-        for operation in operations_to_generate {
-            match self.generate_tool_for_operation(&request, &document, &operation).await {
-                Ok(tool_info) => {
-                    // Cache the generated tool
-                    let mut cache = self.generated_tools.write().unwrap();
-                    cache.insert(tool_info.srn.to_string(), tool_info.clone());
-                    
-                    generated_tools.push(tool_info);
-                }
-                Err(e) => {
-                    warnings.push(format!("Failed to generate tool for operation '{}': {}", operation.operation_id, e));
-                }
-            }
-        }
+        // Resolve references once; every tool of the document shares the result
+        let parsed = document.parsed;
+        let resolved_document = tokio::task::spawn_blocking(move || RefResolver::new().resolve_document(&parsed))
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|resolved| resolved.map(Arc::new).map_err(|e| OpenApiToolError::from(e).to_string()));
+
+        // Create tools concurrently; `buffered` yields them in operation order
+        let generated: Vec<_> = futures::stream::iter(operations_to_generate)
+            .map(|operation| self.spawn_build_tool(&request, operation, resolved_document.clone()))
+            .buffered(self.config.max_parallelism.max(1))
+            .collect()
+            .await;
+
+        let mut result = self.collect_outcomes(generated);
 
         // Add metadata
         metadata.insert("document_id".to_string(), serde_json::Value::String(request.document_id));
         metadata.insert("document_name".to_string(), serde_json::Value::String(document.meta.name));
         metadata.insert("document_version".to_string(), serde_json::Value::String(document.meta.version));
         metadata.insert("total_operations".to_string(), serde_json::Value::Number(document.operations.len().into()));
+        metadata.insert("selected_operations".to_string(), serde_json::Value::Number(result.operations.len().into()));
+        metadata.insert("hidden_operations".to_string(), serde_json::Value::Number(hidden_operations.into()));
+        result.metadata = metadata;
+
+        Ok(result)
+    }
+
+    /// Cache the generated tools and build the result, keeping operation order
+    fn collect_outcomes(
+        &self,
+        generated: Vec<(OperationInfo, Result<GeneratedToolInfo, String>)>,
+    ) -> ToolGenerationResult {
+        let mut tool_srns = Vec::new();
+        let mut warnings = Vec::new();
+        let mut operations = Vec::with_capacity(generated.len());
+
+        let mut cache = self.generated_tools.write().unwrap();
+        for (operation, tool_info) in generated {
+            let error = match tool_info {
+                Ok(tool_info) => {
+                    tool_srns.push(tool_info.srn.to_string());
+                    cache.insert(tool_info.srn.to_string(), tool_info);
+                    None
+                }
+                Err(e) => {
+                    warnings.push(format!("Failed to generate tool for operation '{}': {}", operation.operation_id, e));
+                    Some(e)
+                }
+            };
+            operations.push(OperationOutcome {
+                operation_id: operation.operation_id,
+                srn: operation.srn.to_string(),
+                error,
+            });
+        }
 
-        Ok(ToolGenerationResult {
-            tools_generated: generated_tools.len(),
-            tool_srns: generated_tools.iter().map(|t| t.srn.to_string()).collect(),
+        ToolGenerationResult {
+            tools_generated: tool_srns.len(),
+            tool_srns,
             warnings,
-            metadata,
-        })
+            metadata: HashMap::new(),
+            operations,
+        }
     }
 
-    /// Generate a tool for a specific operation
-    async fn generate_tool_for_operation(
+    /// Generate tools from a streamed document, one operation at a time
+    ///
+    /// Only the current operation, the `components` object and the events buffered
+    /// by `stream` are held in memory. References in an operation's request body
+    /// are resolved against `components` for that operation alone; operations that
+    /// arrive before `components` are held back until the end of the document.
+    /// `request.document_id` is only recorded in the result metadata.
+    pub async fn generate_tools_streaming(
         &self,
         request: &ToolGenerationRequest,
-        document: &OpenApiDocument,
+        mut stream: SpecStream,
+    ) -> Result<ToolGenerationResult, GeneratorError> {
+        if request.operation_id.is_none() && !self.config.generate_all_operations {
+            return Err(GeneratorError::InvalidConfiguration(
+                "No operation specified and generate_all_operations is false".to_string()
+            ));
+        }
+
+        let matcher = Self::compile_filter(request)?;
+        let mut metadata = HashMap::new();
+        let mut total_operations = 0;
+        let mut hidden_operations = 0;
+        let mut operation_found = false;
+        let mut components = None;
+        let mut deferred = Vec::new();
+        let mut generated = Vec::new();
+        let mut pending = futures::stream::FuturesOrdered::new();
+        let parallelism = self.config.max_parallelism.max(1);
+
+        while let Some(event) = stream.next().await {
+            let operation = match event.map_err(|e| GeneratorError::StreamingFailed(e.to_string()))? {
+                SpecEvent::Operation(operation) => *operation,
+                SpecEvent::Info { title, version, .. } => {
+                    metadata.insert("document_title".to_string(), serde_json::Value::String(title));
+                    metadata.insert("document_version".to_string(), serde_json::Value::String(version));
+                    continue;
+                }
+                SpecEvent::Components(value) => {
+                    components = Some(value);
+                    continue;
+                }
+                SpecEvent::Servers(_) | SpecEvent::Schema(_) => continue,
+            };
+
+            total_operations += 1;
+            match &request.operation_id {
+                Some(id) if *id != operation.operation_id => continue,
+                Some(_) => {}
+                None if StepflowExtensions::is_hidden(&operation.extensions) => {
+                    hidden_operations += 1;
+                    continue;
+                }
+                None if matcher.as_ref().is_some_and(|matcher| !matcher.matches(&operation)) => continue,
+                None => {}
+            }
+            operation_found = true;
+
+            let position = generated.len() + pending.len() + deferred.len();
+            let resolved_document = match &components {
+                _ if !Self::references_components(&operation) => Ok(Arc::new(serde_json::Value::Null)),
+                Some(components) => Self::resolve_operation(&operation, components),
+                None => {
+                    deferred.push((position, operation));
+                    continue;
+                }
+            };
+            pending.push_back(self.spawn_build_tool_at(position, request, operation, resolved_document));
+            if pending.len() >= parallelism {
+                generated.extend(pending.next().await);
+            }
+        }
+
+        // Without a `components` object the references cannot resolve and the
+        // operations are reported as failed
+        let components = components.unwrap_or(serde_json::Value::Null);
+        for (position, operation) in deferred {
+            let resolved_document = Self::resolve_operation(&operation, &components);
+            pending.push_back(self.spawn_build_tool_at(position, request, operation, resolved_document));
+            if pending.len() >= parallelism {
+                generated.extend(pending.next().await);
+            }
+        }
+        generated.extend(pending.collect::<Vec<_>>().await);
+        generated.sort_by_key(|(position, _)| *position);
+        let generated = generated.into_iter().map(|(_, outcome)| outcome).collect();
+
+        if let Some(operation_id) = &request.operation_id {
+            if !operation_found {
+                return Err(GeneratorError::OperationNotFound(operation_id.clone()));
+            }
+        }
+
+        let mut result = self.collect_outcomes(generated);
+
+        metadata.insert("document_id".to_string(), serde_json::Value::String(request.document_id.clone()));
+        metadata.insert("total_operations".to_string(), serde_json::Value::Number(total_operations.into()));
+        metadata.insert("selected_operations".to_string(), serde_json::Value::Number(result.operations.len().into()));
+        metadata.insert("hidden_operations".to_string(), serde_json::Value::Number(hidden_operations.into()));
+        metadata.insert("parse_mode".to_string(), serde_json::Value::String("streaming".to_string()));
+        result.metadata = metadata;
+
+        Ok(result)
+    }
+
+    /// Compile the request's operation filter, or `None` if it selects everything
+    fn compile_filter(request: &ToolGenerationRequest) -> Result<Option<OperationMatcher>, GeneratorError> {
+        if request.filter.is_empty() {
+            return Ok(None);
+        }
+        request.filter.compile()
+            .map(Some)
+            .map_err(|e| GeneratorError::InvalidConfiguration(format!("Invalid operation filter: {}", e)))
+    }
+
+    /// Whether an operation's request body refers to `components` through `$ref`
+    fn references_components(operation: &OperationInfo) -> bool {
+        fn contains_ref(value: &serde_json::Value) -> bool {
+            match value {
+                serde_json::Value::Object(map) => map.contains_key("$ref") || map.values().any(contains_ref),
+                serde_json::Value::Array(items) => items.iter().any(contains_ref),
+                _ => false,
+            }
+        }
+
+        operation.request_body.iter()
+            .flat_map(|body| body.content.values())
+            .any(|media| media.schema.as_ref().is_some_and(contains_ref) || media.encoding.values().any(contains_ref))
+    }
+
+    /// Resolve references in one operation's request body against `components`
+    ///
+    /// The result is a document holding only that operation's request body, at
+    /// the same location as in the full document.
+    fn resolve_operation(
         operation: &OperationInfo,
+        components: &serde_json::Value,
+    ) -> Result<Arc<serde_json::Value>, String> {
+        let content: serde_json::Map<String, serde_json::Value> = operation.request_body.iter()
+            .flat_map(|body| body.content.iter())
+            .map(|(media_type, media)| {
+                (media_type.clone(), serde_json::json!({
+                    "schema": media.schema,
+                    "encoding": media.encoding,
+                }))
+            })
+            .collect();
+        let document = serde_json::json!({
+            "paths": {
+                operation.path.as_str(): {
+                    operation.method.to_lowercase(): { "requestBody": { "content": content } }
+                }
+            },
+            "components": components,
+        });
+
+        RefResolver::new()
+            .resolve_document(&document)
+            .map(Arc::new)
+            .map_err(|e| OpenApiToolError::from(e).to_string())
+    }
+
+    /// [`Self::spawn_build_tool`], tagging the outcome with the operation's position
+    fn spawn_build_tool_at(
+        &self,
+        position: usize,
+        request: &ToolGenerationRequest,
+        operation: OperationInfo,
+        resolved_document: Result<Arc<serde_json::Value>, String>,
+    ) -> impl std::future::Future<Output = (usize, (OperationInfo, Result<GeneratedToolInfo, String>))> {
+        let build = self.spawn_build_tool(request, operation, resolved_document);
+        async move { (position, build.await) }
+    }
+
+    /// Create the tool for an operation on a blocking thread
+    ///
+    /// Configuration errors are reported without spawning. The returned future
+    /// yields the operation back so results can be matched up in order.
+    fn spawn_build_tool(
+        &self,
+        request: &ToolGenerationRequest,
+        operation: OperationInfo,
+        resolved_document: Result<Arc<serde_json::Value>, String>,
+    ) -> impl std::future::Future<Output = (OperationInfo, Result<GeneratedToolInfo, String>)> {
+        let prepared = self.create_tool_config(request, &operation)
+            .map_err(|e| e.to_string())
+            .and_then(|tool_config| Ok((tool_config, resolved_document?)));
+        let artifact_store = self.artifact_store.clone();
+
+        async move {
+            let tool_info = match prepared {
+                Ok((tool_config, resolved_document)) => {
+                    let task_operation = operation.clone();
+                    tokio::task::spawn_blocking(move || {
+                        Self::build_tool(tool_config, task_operation, resolved_document, artifact_store)
+                    })
+                        .await
+                        .map_err(|e| e.to_string())
+                        .and_then(|tool_info| tool_info.map_err(|e| e.to_string()))
+                }
+                Err(e) => Err(e),
+            };
+            (operation, tool_info)
+        }
+    }
+
+    /// Create the tool instance for an operation
+    fn build_tool(
+        tool_config: OpenApiToolConfig,
+        operation: OperationInfo,
+        resolved_document: Arc<serde_json::Value>,
+        artifact_store: Option<Arc<dyn ArtifactStore>>,
     ) -> Result<GeneratedToolInfo, GeneratorError> {
-        // Create tool configuration
-        let tool_config = self.create_tool_config(request, operation)?;
-        
-        // Create the tool instance
-        let tool = OpenApiTool::new(tool_config.clone(), operation.clone(), document).await?;
-        
+        let mut tool = OpenApiTool::with_resolved_document(tool_config.clone(), operation.clone(), resolved_document)?;
+        if let Some(store) = artifact_store {
+            tool = tool.with_artifact_store(store);
+        }
+
         Ok(GeneratedToolInfo {
             srn: operation.srn.clone(),
             tool: Arc::new(tool),
             config: tool_config,
-            operation: operation.clone(),
+            operation,
         })
     }
 
     /// Create tool configuration for an operation
+    ///
+    /// Timeouts are taken from `tool_config_overrides`, then `x-stepflow-timeout`,
+    /// then the request, then the generator default.
     fn create_tool_config(
         &self,
         request: &ToolGenerationRequest,
@@ -244,14 +590,22 @@ impl ToolGenerator {
         } else {
             request.base_url.clone()
         };
+        let extensions = StepflowExtensions::parse(&operation.extensions)?;
 
         let mut config = OpenApiToolConfig {
             srn: operation.srn.to_string(),
             base_url,
-            timeout_ms: request.timeout_ms.or(Some(self.config.default_timeout_ms)),
+            timeout_ms: extensions.timeout_ms
+                .or(request.timeout_ms)
+                .or(Some(self.config.default_timeout_ms)),
             max_retries: request.max_retries.or(Some(self.config.default_max_retries)),
             default_headers: request.default_headers.clone().unwrap_or_default(),
             auth: request.auth.clone(),
+            tool_name: extensions.tool_name,
+            sandbox_profile: extensions.sandbox_profile,
+            response_conversion: Default::default(),
+            pagination: extensions.pagination,
+            rate_limit: extensions.rate_limit,
         };
 
         // Apply any configuration overrides
@@ -262,6 +616,18 @@ impl ToolGenerator {
             if let Some(retries) = overrides.get("max_retries").and_then(|v| v.as_u64()) {
                 config.max_retries = Some(retries as u32);
             }
+            if let Some(pagination) = overrides.get("pagination") {
+                config.pagination = serde_json::from_value(pagination.clone())
+                    .map_err(|e| GeneratorError::InvalidConfiguration(format!("Invalid pagination: {}", e)))?;
+            }
+            if let Some(rate_limit) = overrides.get("rate_limit") {
+                config.rate_limit = serde_json::from_value(rate_limit.clone())
+                    .map_err(|e| GeneratorError::InvalidConfiguration(format!("Invalid rate_limit: {}", e)))?;
+            }
+            if let Some(conversion) = overrides.get("response_conversion") {
+                config.response_conversion = serde_json::from_value(conversion.clone())
+                    .map_err(|e| GeneratorError::InvalidConfiguration(format!("Invalid response_conversion: {}", e)))?;
+            }
             if let Some(headers) = overrides.get("default_headers").and_then(|v| v.as_object()) {
                 for (k, v) in headers {
                     if let Some(header_value) = v.as_str() {
@@ -359,6 +725,34 @@ impl ToolGenerator {
 
         results
     }
+
+    /// Persist cached tools through the registry in one batch
+    ///
+    /// Going through the registry records each stored tool on the change feed
+    /// and invalidates its cache. Rows that fail (e.g. a tool that was already
+    /// stored) are listed in the returned report by their position in `srns`;
+    /// the rest are committed.
+    pub async fn persist_tools(
+        &self,
+        srns: &[String],
+        registry: &dyn Registry,
+    ) -> Result<BatchInsertReport, GeneratorError> {
+        let tools = srns
+            .iter()
+            .map(|srn| self.get_tool(srn).ok_or_else(|| GeneratorError::OperationNotFound(srn.clone())))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut infos = Vec::with_capacity(tools.len());
+        for tool in tools {
+            let info = tool.get_info().await.map_err(|e| GeneratorError::PersistenceFailed(e.to_string()))?;
+            infos.push(info);
+        }
+
+        registry
+            .register_tools(infos)
+            .await
+            .map_err(|e| GeneratorError::PersistenceFailed(e.to_string()))
+    }
 }
 
 /// Cache statistics
@@ -453,6 +847,7 @@ impl ToolRegistry for InMemoryToolRegistry {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::filter::OperationRule;
     use crate::document::{InMemoryDocumentStorage, DocumentUploadRequest, DocumentFormat};
 
     async fn create_test_generator() -> ToolGenerator {
@@ -561,6 +956,7 @@ mod tests {
             default_headers: None,
             auth: None,
             tool_config_overrides: None,
+            filter: OperationFilter::default(),
         };
 
         let result = generator.generate_tools(request).await.unwrap();
@@ -584,6 +980,7 @@ mod tests {
             default_headers: None,
             auth: None,
             tool_config_overrides: None,
+            filter: OperationFilter::default(),
         };
 
         let result = generator.generate_tools(request).await.unwrap();
@@ -593,6 +990,259 @@ mod tests {
         assert!(result.tool_srns[0].contains("getUser"));
     }
 
+    #[tokio::test]
+    async fn test_parallel_generation_order_and_outcomes() {
+        let storage = Box::new(InMemoryDocumentStorage::default());
+        let doc_manager = Arc::new(DocumentManager::new(storage));
+        let config = GeneratorConfig {
+            max_parallelism: 4,
+            ..GeneratorConfig::default()
+        };
+        let generator = ToolGenerator::new(doc_manager, config);
+        let doc_id = create_test_document(&generator.document_manager).await;
+        let document = generator.document_manager.get_document(&doc_id).await.unwrap().unwrap();
+
+        let mut request = ToolGenerationRequest {
+            document_id: doc_id,
+            operation_id: None,
+            base_url: "https://api.example.com".to_string(),
+            timeout_ms: None,
+            max_retries: None,
+            default_headers: None,
+            auth: None,
+            tool_config_overrides: None,
+            filter: OperationFilter::default(),
+        };
+
+        let result = generator.generate_tools(request.clone()).await.unwrap();
+        let expected: Vec<String> = document.operations.iter().map(|op| op.srn.to_string()).collect();
+        assert_eq!(result.tool_srns, expected);
+        assert_eq!(result.operations.len(), expected.len());
+        assert!(result.operations.iter().all(OperationOutcome::is_success));
+
+        // Without a base URL every operation fails and is reported individually
+        request.base_url = String::new();
+        let result = generator.generate_tools(request).await.unwrap();
+        assert_eq!(result.tools_generated, 0);
+        assert_eq!(result.warnings.len(), expected.len());
+        let failed: Vec<&str> = result.operations.iter().map(|outcome| outcome.operation_id.as_str()).collect();
+        let operation_ids: Vec<&str> = document.operations.iter().map(|op| op.operation_id.as_str()).collect();
+        assert_eq!(failed, operation_ids);
+        assert!(result.operations.iter().all(|outcome| outcome.error.as_deref().is_some_and(|e| e.contains("base_url"))));
+    }
+
+    #[tokio::test]
+    async fn test_filtered_generation() {
+        let generator = create_test_generator().await;
+        let doc_id = create_test_document(&generator.document_manager).await;
+
+        let mut request = ToolGenerationRequest {
+            document_id: doc_id,
+            operation_id: None,
+            base_url: "https://api.example.com".to_string(),
+            timeout_ms: None,
+            max_retries: None,
+            default_headers: None,
+            auth: None,
+            tool_config_overrides: None,
+            filter: OperationFilter::default().exclude(OperationRule::methods(["post"])),
+        };
+
+        let result = generator.generate_tools(request.clone()).await.unwrap();
+        assert_eq!(result.tools_generated, 1);
+        assert!(result.tool_srns[0].contains("getUser"));
+        assert_eq!(result.metadata["total_operations"], 2);
+        assert_eq!(result.metadata["selected_operations"], 1);
+
+        request.filter = OperationFilter::default().include(OperationRule::operation_id("[invalid"));
+        assert!(matches!(
+            generator.generate_tools(request).await,
+            Err(GeneratorError::InvalidConfiguration(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_vendor_extensions() {
+        let generator = create_test_generator().await;
+        let upload = DocumentUploadRequest {
+            name: "Pets".to_string(),
+            namespace: "pets".to_string(),
+            tenant_id: "tenant-123".to_string(),
+            content: serde_json::json!({
+                "openapi": "3.0.0",
+                "info": {"title": "Pets", "version": "1.0.0"},
+                "paths": {
+                    "/pets": {
+                        "get": {
+                            "operationId": "listPets",
+                            "x-stepflow-tool-name": "List pets",
+                            "x-stepflow-sandbox-profile": "network-only",
+                            "x-stepflow-timeout": "5s",
+                            "responses": {"200": {"description": "OK"}}
+                        },
+                        "post": {
+                            "operationId": "createPet",
+                            "x-stepflow-retries": 2,
+                            "responses": {"201": {"description": "Created"}}
+                        }
+                    },
+                    "/internal/sync": {
+                        "post": {
+                            "operationId": "sync",
+                            "x-stepflow-hidden": true,
+                            "responses": {"204": {"description": "Done"}}
+                        }
+                    }
+                }
+            })
+            .to_string(),
+            format: DocumentFormat::Json,
+            description: None,
+        };
+        let doc_id = generator.document_manager.upload_document(upload).await.unwrap().document_id;
+
+        let mut request = ToolGenerationRequest {
+            document_id: doc_id,
+            operation_id: None,
+            base_url: "https://api.example.com".to_string(),
+            timeout_ms: Some(60000),
+            max_retries: None,
+            default_headers: None,
+            auth: None,
+            tool_config_overrides: None,
+            filter: OperationFilter::default(),
+        };
+
+        let result = generator.generate_tools(request.clone()).await.unwrap();
+        assert_eq!(result.metadata["hidden_operations"], 1);
+        let outcomes: Vec<(&str, bool)> = result.operations.iter()
+            .map(|outcome| (outcome.operation_id.as_str(), outcome.is_success()))
+            .collect();
+        assert_eq!(outcomes, [("listPets", true), ("createPet", false)]);
+        assert!(result.warnings[0].contains("x-stepflow-retries"));
+
+        let info = generator.get_tool_info(&result.tool_srns[0]).unwrap();
+        assert_eq!(info.config.timeout_ms, Some(5000));
+        assert_eq!(info.tool.get_info().await.unwrap().name, "List pets");
+        let tool_config = info.config.to_tool_config();
+        assert_eq!(tool_config.configuration["sandbox_profile"], "network-only");
+        assert_eq!(tool_config.timeout, Some(5));
+
+        // Hidden operations can still be generated explicitly
+        request.operation_id = Some("sync".to_string());
+        let result = generator.generate_tools(request).await.unwrap();
+        assert_eq!(result.tools_generated, 1);
+    }
+
+    #[tokio::test]
+    async fn test_streaming_generation() {
+        let storage = Box::new(InMemoryDocumentStorage::default());
+        let doc_manager = Arc::new(DocumentManager::new(storage));
+        let config = GeneratorConfig {
+            parse_mode: ParseMode::Streaming,
+            stream_buffer_size: 1,
+            ..GeneratorConfig::default()
+        };
+        let generator = ToolGenerator::new(doc_manager, config);
+        let doc_id = create_test_document(&generator.document_manager).await;
+
+        let mut request = ToolGenerationRequest {
+            document_id: doc_id,
+            operation_id: None,
+            base_url: "https://api.example.com".to_string(),
+            timeout_ms: None,
+            max_retries: None,
+            default_headers: None,
+            auth: None,
+            tool_config_overrides: None,
+            filter: OperationFilter::default(),
+        };
+
+        let result = generator.generate_tools(request.clone()).await.unwrap();
+        assert_eq!(result.tools_generated, 2);
+        assert!(result.warnings.is_empty());
+        assert_eq!(result.metadata["parse_mode"], "streaming");
+        assert_eq!(result.metadata["total_operations"], 2);
+        assert!(result.tool_srns.iter().all(|srn| generator.get_tool(srn).is_some()));
+
+        request.operation_id = Some("missing".to_string());
+        assert!(matches!(
+            generator.generate_tools(request).await,
+            Err(GeneratorError::OperationNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_streaming_generation_resolves_references() {
+        let storage = Box::new(InMemoryDocumentStorage::default());
+        let doc_manager = Arc::new(DocumentManager::new(storage));
+        let config = GeneratorConfig {
+            parse_mode: ParseMode::Streaming,
+            stream_buffer_size: 1,
+            ..GeneratorConfig::default()
+        };
+        let generator = ToolGenerator::new(doc_manager, config);
+
+        // `components` comes after `paths`, so the upload operation is held back
+        let content = r##"{
+            "openapi": "3.0.0",
+            "info": {"title": "Uploads", "version": "1.0.0"},
+            "paths": {
+                "/avatar": {"post": {
+                    "operationId": "uploadAvatar",
+                    "requestBody": {"content": {"multipart/form-data": {"schema": {"$ref": "#/components/schemas/Upload"}}}},
+                    "responses": {"204": {"description": "ok"}}
+                }},
+                "/broken": {"post": {
+                    "operationId": "uploadBroken",
+                    "requestBody": {"content": {"multipart/form-data": {"schema": {"$ref": "#/components/schemas/Missing"}}}},
+                    "responses": {"204": {"description": "ok"}}
+                }},
+                "/ping": {"get": {"operationId": "ping", "responses": {"200": {"description": "ok"}}}}
+            },
+            "components": {"schemas": {"Upload": {
+                "type": "object",
+                "required": ["file"],
+                "properties": {"file": {"type": "string", "format": "binary"}}
+            }}}
+        }"##;
+        let document_id = generator.document_manager.upload_document(DocumentUploadRequest {
+            name: "Uploads".to_string(),
+            namespace: "uploads".to_string(),
+            tenant_id: "tenant-123".to_string(),
+            content: content.to_string(),
+            format: DocumentFormat::Json,
+            description: None,
+        }).await.unwrap().document_id;
+
+        let result = generator.generate_tools(ToolGenerationRequest {
+            document_id,
+            operation_id: None,
+            base_url: "https://api.example.com".to_string(),
+            timeout_ms: None,
+            max_retries: None,
+            default_headers: None,
+            auth: None,
+            tool_config_overrides: None,
+            filter: OperationFilter::default(),
+        }).await.unwrap();
+
+        // Outcomes keep document order; only the dangling reference fails
+        let outcomes: Vec<_> = result.operations.iter()
+            .map(|outcome| (outcome.operation_id.as_str(), outcome.error.is_none()))
+            .collect();
+        assert_eq!(outcomes, [("uploadAvatar", true), ("uploadBroken", false), ("ping", true)]);
+
+        let upload = generator.get_tool_info(&result.tool_srns[0]).unwrap().operation;
+        assert!(ToolGenerator::references_components(&upload));
+        let components = serde_json::json!({"schemas": {"Upload": {"type": "object", "required": ["file"]}}});
+        let resolved = ToolGenerator::resolve_operation(&upload, &components).unwrap();
+        let schema = resolved
+            .pointer("/paths/~1avatar/post/requestBody/content/multipart~1form-data/schema")
+            .unwrap();
+        assert_eq!(schema["required"], serde_json::json!(["file"]));
+    }
+
     #[tokio::test]
     async fn test_tool_caching() {
         let generator = create_test_generator().await;
@@ -607,6 +1257,7 @@ mod tests {
             default_headers: None,
             auth: None,
             tool_config_overrides: None,
+            filter: OperationFilter::default(),
         };
 
         let result = generator.generate_tools(request).await.unwrap();
@@ -638,6 +1289,7 @@ mod tests {
             default_headers: None,
             auth: None,
             tool_config_overrides: None,
+            filter: OperationFilter::default(),
         };
 
         generator.generate_tools(request).await.unwrap();
@@ -0,0 +1,464 @@
+//! Golden tests for generated tools
+//!
+//! A golden fixture captures one run of a tool: its input, the sanitized
+//! upstream interactions it made and the output it produced. Verifying a
+//! fixture re-runs the tool against the recorded responses and reports:
+//!
+//! - behavior changes: a different output, or requests that were not recorded
+//!   (or recorded requests the tool no longer makes)
+//! - schema drift: recorded responses that no longer match the response
+//!   schema the operation declares
+//!
+//! [`GoldenHarness::verify_live`] instead calls the live upstream and also
+//! compares the shape of each fresh response with the recorded one, which
+//! catches upstreams that changed without the spec being updated.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use stepflow_core::types::{Tool, ToolId, ToolRequest, ToolResponse};
+use stepflow_registry::tool_config::validate_against_schema;
+
+use crate::proxy::recording::{InteractionRecorder, RecordedInteraction};
+use crate::tool::{AuthConfig, OpenApiTool};
+
+/// Golden test errors
+#[derive(Debug, thiserror::Error)]
+pub enum GoldenError {
+    #[error("Fixture not found: {0}")]
+    NotFound(String),
+
+    #[error("Invalid fixture name: {0}")]
+    InvalidName(String),
+
+    #[error("Fixture I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Fixture serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+
+    #[error("Tool execution error: {0}")]
+    Execution(String),
+}
+
+/// Outcome of a tool run captured by a fixture
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GoldenOutput {
+    pub success: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl From<&ToolResponse> for GoldenOutput {
+    fn from(response: &ToolResponse) -> Self {
+        Self {
+            success: response.success,
+            output: response.output.clone(),
+            error: response.error.clone(),
+        }
+    }
+}
+
+/// Recorded run of a tool
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GoldenFixture {
+    /// Fixture name, unique per fixture store
+    pub name: String,
+    /// SRN of the recorded tool
+    pub tool_srn: String,
+    /// Operation the tool calls
+    pub operation_id: String,
+    /// Tool input
+    pub input: Value,
+    /// Upstream interactions in the order they happened
+    pub interactions: Vec<RecordedInteraction>,
+    /// What the tool returned
+    pub expected: GoldenOutput,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// Kind of difference found when verifying a fixture
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GoldenFindingKind {
+    /// The tool returned a different result
+    OutputChanged,
+    /// The tool made a request with no recorded counterpart
+    UnexpectedRequest,
+    /// A recorded request was not made
+    MissingRequest,
+    /// A response does not match the declared response schema
+    SchemaDrift,
+    /// A live response differs in status or shape from the recorded one
+    ResponseChanged,
+}
+
+/// One difference found when verifying a fixture
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GoldenFinding {
+    pub kind: GoldenFindingKind,
+    /// `METHOD /path` of the interaction, if the finding concerns one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request: Option<String>,
+    pub message: String,
+}
+
+/// Result of verifying a fixture
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GoldenReport {
+    pub fixture: String,
+    pub tool_srn: String,
+    pub passed: bool,
+    pub findings: Vec<GoldenFinding>,
+    /// What the tool returned during verification
+    pub actual: GoldenOutput,
+}
+
+/// Directory of golden fixtures, one `<name>.json` file per fixture
+#[derive(Debug, Clone)]
+pub struct FixtureStore {
+    root: PathBuf,
+}
+
+impl FixtureStore {
+    /// Use `root` as the fixture directory, created on first save
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Fixture directory
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Save a fixture, replacing one with the same name
+    pub fn save(&self, fixture: &GoldenFixture) -> Result<PathBuf, GoldenError> {
+        let path = self.path(&fixture.name)?;
+        std::fs::create_dir_all(&self.root)?;
+        std::fs::write(&path, serde_json::to_vec_pretty(fixture)?)?;
+        Ok(path)
+    }
+
+    /// Load a fixture by name
+    pub fn load(&self, name: &str) -> Result<GoldenFixture, GoldenError> {
+        let path = self.path(name)?;
+        let content = match std::fs::read(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(GoldenError::NotFound(name.to_string())),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(serde_json::from_slice(&content)?)
+    }
+
+    /// Names of all fixtures, sorted
+    pub fn list(&self) -> Result<Vec<String>, GoldenError> {
+        let entries = match std::fs::read_dir(&self.root) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut names = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                if let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) {
+                    names.push(stem.to_string());
+                }
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    fn path(&self, name: &str) -> Result<PathBuf, GoldenError> {
+        let valid = !name.is_empty()
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+            && !name.starts_with('.');
+        if !valid {
+            return Err(GoldenError::InvalidName(name.to_string()));
+        }
+        Ok(self.root.join(format!("{}.json", name)))
+    }
+}
+
+/// Records and verifies golden fixtures for one OpenAPI tool
+pub struct GoldenHarness {
+    tool: OpenApiTool,
+    recorder: Arc<InteractionRecorder>,
+}
+
+impl GoldenHarness {
+    /// Wrap a tool, routing its upstream calls through a recorder
+    ///
+    /// The header used by API key authentication is redacted in addition to
+    /// the default sensitive headers.
+    pub fn new(tool: OpenApiTool) -> Self {
+        let mut recorder = InteractionRecorder::recording();
+        if let Some(AuthConfig::ApiKey { header, .. }) = &tool.config().auth {
+            recorder = recorder.redact_header(header);
+        }
+        let recorder = Arc::new(recorder);
+        Self {
+            tool: tool.with_recorder(recorder.clone()),
+            recorder,
+        }
+    }
+
+    /// Run the tool against the live upstream and capture the run as a fixture
+    pub async fn record(&self, name: &str, input: Value) -> Result<GoldenFixture, GoldenError> {
+        self.recorder.start_recording();
+        let response = self.run(&input).await?;
+        Ok(GoldenFixture {
+            name: name.to_string(),
+            tool_srn: self.tool.config().srn.clone(),
+            operation_id: self.tool.operation().operation_id.clone(),
+            input,
+            interactions: self.recorder.interactions(),
+            expected: GoldenOutput::from(&response),
+            recorded_at: Utc::now(),
+        })
+    }
+
+    /// Re-run the tool against the recorded responses
+    pub async fn verify(&self, fixture: &GoldenFixture) -> Result<GoldenReport, GoldenError> {
+        self.recorder.start_replay(fixture.interactions.clone());
+        let response = self.run(&fixture.input).await?;
+
+        let mut findings = Vec::new();
+        for request in self.recorder.unmatched_requests() {
+            findings.push(GoldenFinding {
+                kind: GoldenFindingKind::UnexpectedRequest,
+                request: Some(format!("{} {}", request.method, request.path)),
+                message: "Request has no recorded interaction".to_string(),
+            });
+        }
+        for interaction in self.recorder.unused_interactions() {
+            findings.push(GoldenFinding {
+                kind: GoldenFindingKind::MissingRequest,
+                request: Some(format!("{} {}", interaction.request.method, interaction.request.path)),
+                message: "Recorded request was not made".to_string(),
+            });
+        }
+        for interaction in &fixture.interactions {
+            self.check_schema(interaction, &mut findings);
+        }
+        Ok(self.report(fixture, &response, findings))
+    }
+
+    /// Re-run the tool against the live upstream and compare with the fixture
+    ///
+    /// Besides the output and the declared schemas, each live response is
+    /// compared with the recorded response to the same request: a different
+    /// status, or fields that changed type, appeared or disappeared.
+    pub async fn verify_live(&self, fixture: &GoldenFixture) -> Result<GoldenReport, GoldenError> {
+        self.recorder.start_recording();
+        let response = self.run(&fixture.input).await?;
+        let live = self.recorder.interactions();
+
+        let mut findings = Vec::new();
+        let mut remaining: Vec<&RecordedInteraction> = fixture.interactions.iter().collect();
+        for interaction in &live {
+            let request = format!("{} {}", interaction.request.method, interaction.request.path);
+            let Some(position) = remaining.iter().position(|r| r.request.matches(&interaction.request)) else {
+                findings.push(GoldenFinding {
+                    kind: GoldenFindingKind::UnexpectedRequest,
+                    request: Some(request),
+                    message: "Request has no recorded interaction".to_string(),
+                });
+                self.check_schema(interaction, &mut findings);
+                continue;
+            };
+            let recorded = remaining.remove(position);
+
+            if recorded.response.status != interaction.response.status {
+                findings.push(GoldenFinding {
+                    kind: GoldenFindingKind::ResponseChanged,
+                    request: Some(request.clone()),
+                    message: format!(
+                        "Status changed from {} to {}",
+                        recorded.response.status, interaction.response.status
+                    ),
+                });
+            }
+            let mut changes = Vec::new();
+            shape_changes(
+                recorded.response.body.as_ref().unwrap_or(&Value::Null),
+                interaction.response.body.as_ref().unwrap_or(&Value::Null),
+                "",
+                &mut changes,
+            );
+            findings.extend(changes.into_iter().map(|message| GoldenFinding {
+                kind: GoldenFindingKind::ResponseChanged,
+                request: Some(request.clone()),
+                message,
+            }));
+            self.check_schema(interaction, &mut findings);
+        }
+        for interaction in remaining {
+            findings.push(GoldenFinding {
+                kind: GoldenFindingKind::MissingRequest,
+                request: Some(format!("{} {}", interaction.request.method, interaction.request.path)),
+                message: "Recorded request was not made".to_string(),
+            });
+        }
+        Ok(self.report(fixture, &response, findings))
+    }
+
+    async fn run(&self, input: &Value) -> Result<ToolResponse, GoldenError> {
+        let request = ToolRequest::new(ToolId::from_string(self.tool.config().srn.clone()), input.clone());
+        self.tool.execute(request)
+            .await
+            .map_err(|e| GoldenError::Execution(e.to_string()))
+    }
+
+    /// Validate a response body against the schema declared for its status
+    fn check_schema(&self, interaction: &RecordedInteraction, findings: &mut Vec<GoldenFinding>) {
+        let Some(schema) = self.tool.response_schema(interaction.response.status) else {
+            return;
+        };
+        let body = interaction.response.body.as_ref().unwrap_or(&Value::Null);
+        if let Err(errors) = validate_against_schema(&schema, body) {
+            let request = format!("{} {}", interaction.request.method, interaction.request.path);
+            findings.extend(errors.into_iter().map(|error| GoldenFinding {
+                kind: GoldenFindingKind::SchemaDrift,
+                request: Some(request.clone()),
+                message: format!("HTTP {} response: {}", interaction.response.status, error),
+            }));
+        }
+    }
+
+    fn report(&self, fixture: &GoldenFixture, response: &ToolResponse, mut findings: Vec<GoldenFinding>) -> GoldenReport {
+        let actual = GoldenOutput::from(response);
+        if actual != fixture.expected {
+            findings.insert(0, GoldenFinding {
+                kind: GoldenFindingKind::OutputChanged,
+                request: None,
+                message: describe_output_change(&fixture.expected, &actual),
+            });
+        }
+        GoldenReport {
+            fixture: fixture.name.clone(),
+            tool_srn: fixture.tool_srn.clone(),
+            passed: findings.is_empty(),
+            findings,
+            actual,
+        }
+    }
+}
+
+fn describe_output_change(expected: &GoldenOutput, actual: &GoldenOutput) -> String {
+    if expected.success != actual.success {
+        return match &actual.error {
+            Some(error) => format!("Tool now fails: {}", error),
+            None => "Tool now succeeds".to_string(),
+        };
+    }
+    let mut changes = Vec::new();
+    shape_changes(
+        expected.output.as_ref().unwrap_or(&Value::Null),
+        actual.output.as_ref().unwrap_or(&Value::Null),
+        "",
+        &mut changes,
+    );
+    match changes.first() {
+        Some(change) => format!("Output changed: {}", change),
+        None => "Output values changed".to_string(),
+    }
+}
+
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Collect structural differences between two JSON values
+///
+/// Only types and object keys are compared, not values. Arrays are compared
+/// by their first elements.
+pub fn shape_changes(expected: &Value, actual: &Value, path: &str, changes: &mut Vec<String>) {
+    let at = if path.is_empty() { "/" } else { path };
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => {
+            for (key, value) in expected {
+                let child = format!("{}/{}", path, key);
+                match actual.get(key) {
+                    Some(actual_value) => shape_changes(value, actual_value, &child, changes),
+                    None => changes.push(format!("{} was removed", child)),
+                }
+            }
+            for key in actual.keys().filter(|key| !expected.contains_key(*key)) {
+                changes.push(format!("{}/{} was added", path, key));
+            }
+        }
+        (Value::Array(expected), Value::Array(actual)) => {
+            if let (Some(expected), Some(actual)) = (expected.first(), actual.first()) {
+                shape_changes(expected, actual, &format!("{}/0", path), changes);
+            }
+        }
+        _ if json_type(expected) != json_type(actual) => {
+            changes.push(format!("{} changed from {} to {}", at, json_type(expected), json_type(actual)));
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_shape_changes() {
+        let mut changes = Vec::new();
+        shape_changes(
+            &json!({"id": 1, "name": "a", "tags": [{"k": "v"}]}),
+            &json!({"id": "1", "tags": [{"k": "w", "extra": true}], "email": "x"}),
+            "",
+            &mut changes,
+        );
+        assert_eq!(changes, vec![
+            "/id changed from number to string",
+            "/name was removed",
+            "/tags/0/extra was added",
+            "/email was added",
+        ]);
+
+        let mut changes = Vec::new();
+        shape_changes(&json!({"id": 1}), &json!({"id": 2}), "", &mut changes);
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn test_fixture_store_round_trip() {
+        let root = std::env::temp_dir().join(format!("stepflow-golden-{}", uuid::Uuid::new_v4()));
+        let store = FixtureStore::new(&root);
+        assert!(store.list().unwrap().is_empty());
+
+        let fixture = GoldenFixture {
+            name: "get-user".to_string(),
+            tool_srn: "stepflow:openapi:t:api:operation:getUser".to_string(),
+            operation_id: "getUser".to_string(),
+            input: json!({"id": "1"}),
+            interactions: Vec::new(),
+            expected: GoldenOutput { success: true, output: Some(json!({"id": 1})), error: None },
+            recorded_at: Utc::now(),
+        };
+        store.save(&fixture).unwrap();
+        assert_eq!(store.list().unwrap(), vec!["get-user"]);
+        assert_eq!(store.load("get-user").unwrap(), fixture);
+        assert!(matches!(store.load("missing"), Err(GoldenError::NotFound(_))));
+        assert!(matches!(store.load("../etc"), Err(GoldenError::InvalidName(_))));
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
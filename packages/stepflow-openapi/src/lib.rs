@@ -11,7 +11,13 @@ pub mod document;
 pub mod ref_resolver;
 pub mod tool;
 pub mod generator;
+pub mod filter;
+pub mod extensions;
+pub mod stream;
 pub mod registry;
+pub mod sdk;
+pub mod golden;
+pub mod conformance;
 
 // 重新导出主要的公共 API
 pub use proxy::*;
@@ -21,5 +27,11 @@ pub use srn::{Srn, SrnBuilder, SrnComponents, SrnError};
 pub use document::{DocumentManager, DocumentUploadRequest, DocumentUploadResult, OpenApiDocument, OperationInfo, SchemaInfo};
 pub use ref_resolver::{RefResolver, RefResolverConfig, RefResolverError, resolve_refs};
 pub use tool::{OpenApiTool, OpenApiToolConfig, OpenApiToolError, AuthConfig};
-pub use generator::{ToolGenerator, ToolGenerationRequest, ToolGenerationResult, GeneratorConfig, ToolRegistry, InMemoryToolRegistry};
+pub use generator::{ToolGenerator, ToolGenerationRequest, ToolGenerationResult, GeneratorConfig, ParseMode, ToolRegistry, InMemoryToolRegistry};
+pub use filter::{OperationFilter, OperationMatcher, OperationRule};
+pub use extensions::{extension_descriptor, ExtensionError, StepflowExtensions};
+pub use stream::{parse_streaming, SpecEvent, SpecStream, StreamContext};
+pub use sdk::{SdkError, SdkFile, SdkGenerator, SdkLanguage, SdkOptions};
+pub use golden::{FixtureStore, GoldenError, GoldenFinding, GoldenFindingKind, GoldenFixture, GoldenHarness, GoldenOutput, GoldenReport};
+pub use conformance::{ConformanceConfig, ConformanceError, ConformanceReport, ConformanceTarget, ContractVerifier, DriftAlert, DriftNotifier, DriftStatus, OperationCheck, OperationConformance, WebhookDriftNotifier};
 pub use registry::{OpenApiToolRegistry, RegistryConfig, ToolSearchCriteria, ToolExecutionStats, GlobalRegistryStats};
\ No newline at end of file
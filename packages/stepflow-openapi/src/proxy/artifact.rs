@@ -0,0 +1,226 @@
+//! 制品存储
+//!
+//! 保存上传文件和大体积二进制响应，工具输入输出中只传递 [`ArtifactRef`] 引用。
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use serde::{Deserialize, Serialize};
+use stepflow_registry::ContentStore;
+use super::error::{ProxyError, ProxyResult};
+
+/// 制品内容
+#[derive(Debug, Clone, PartialEq)]
+pub struct Artifact {
+    /// 制品 ID
+    pub id: String,
+    /// 内容类型
+    pub content_type: String,
+    /// 原始文件名
+    pub filename: Option<String>,
+    /// 二进制内容
+    pub data: Vec<u8>,
+}
+
+/// 制品引用，作为 JSON 出现在工具输入输出中
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArtifactRef {
+    /// 制品 ID
+    pub artifact_id: String,
+    /// 内容类型
+    pub content_type: String,
+    /// 原始文件名
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filename: Option<String>,
+    /// 字节数
+    pub size: usize,
+}
+
+/// 制品存储
+pub trait ArtifactStore: Send + Sync {
+    /// 保存制品并返回引用
+    fn put(&self, content_type: &str, filename: Option<&str>, data: Vec<u8>) -> ProxyResult<ArtifactRef>;
+
+    /// 读取制品
+    fn get(&self, artifact_id: &str) -> ProxyResult<Option<Artifact>>;
+
+    /// 删除制品，返回是否存在
+    fn delete(&self, artifact_id: &str) -> ProxyResult<bool>;
+}
+
+/// 内存制品存储
+#[derive(Debug, Default)]
+pub struct InMemoryArtifactStore {
+    artifacts: RwLock<HashMap<String, Artifact>>,
+}
+
+impl InMemoryArtifactStore {
+    /// 创建空的内存制品存储
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ArtifactStore for InMemoryArtifactStore {
+    fn put(&self, content_type: &str, filename: Option<&str>, data: Vec<u8>) -> ProxyResult<ArtifactRef> {
+        let artifact = Artifact {
+            id: uuid::Uuid::new_v4().to_string(),
+            content_type: content_type.to_string(),
+            filename: filename.map(String::from),
+            data,
+        };
+        let reference = ArtifactRef {
+            artifact_id: artifact.id.clone(),
+            content_type: artifact.content_type.clone(),
+            filename: artifact.filename.clone(),
+            size: artifact.data.len(),
+        };
+
+        self.artifacts
+            .write()
+            .map_err(|_| ProxyError::InternalError("Artifact store lock poisoned".to_string()))?
+            .insert(artifact.id.clone(), artifact);
+        Ok(reference)
+    }
+
+    fn get(&self, artifact_id: &str) -> ProxyResult<Option<Artifact>> {
+        let artifacts = self.artifacts
+            .read()
+            .map_err(|_| ProxyError::InternalError("Artifact store lock poisoned".to_string()))?;
+        Ok(artifacts.get(artifact_id).cloned())
+    }
+
+    fn delete(&self, artifact_id: &str) -> ProxyResult<bool> {
+        let mut artifacts = self.artifacts
+            .write()
+            .map_err(|_| ProxyError::InternalError("Artifact store lock poisoned".to_string()))?;
+        Ok(artifacts.remove(artifact_id).is_some())
+    }
+}
+
+/// 内容寻址制品存储
+///
+/// 制品内容按 SHA-256 保存在共享的 [`ContentStore`] 中，相同内容只存一份；
+/// 删除制品只释放引用，未被引用的内容由内容存储的 GC 回收。
+pub struct ContentAddressedArtifactStore {
+    content: Arc<ContentStore>,
+    artifacts: RwLock<HashMap<String, StoredArtifact>>,
+}
+
+struct StoredArtifact {
+    content_type: String,
+    filename: Option<String>,
+    digest: String,
+}
+
+impl ContentAddressedArtifactStore {
+    /// 创建使用 `content` 保存内容的制品存储
+    pub fn new(content: Arc<ContentStore>) -> Self {
+        Self {
+            content,
+            artifacts: RwLock::new(HashMap::new()),
+        }
+    }
+}
+
+fn content_error(error: stepflow_registry::RegistryError) -> ProxyError {
+    ProxyError::InternalError(format!("Content store error: {}", error))
+}
+
+impl ArtifactStore for ContentAddressedArtifactStore {
+    fn put(&self, content_type: &str, filename: Option<&str>, data: Vec<u8>) -> ProxyResult<ArtifactRef> {
+        let digest = self.content.put(&data).map_err(content_error)?;
+        let reference = ArtifactRef {
+            artifact_id: uuid::Uuid::new_v4().to_string(),
+            content_type: content_type.to_string(),
+            filename: filename.map(String::from),
+            size: data.len(),
+        };
+
+        self.artifacts
+            .write()
+            .map_err(|_| ProxyError::InternalError("Artifact store lock poisoned".to_string()))?
+            .insert(reference.artifact_id.clone(), StoredArtifact {
+                content_type: reference.content_type.clone(),
+                filename: reference.filename.clone(),
+                digest,
+            });
+        Ok(reference)
+    }
+
+    fn get(&self, artifact_id: &str) -> ProxyResult<Option<Artifact>> {
+        let artifacts = self.artifacts
+            .read()
+            .map_err(|_| ProxyError::InternalError("Artifact store lock poisoned".to_string()))?;
+        let Some(stored) = artifacts.get(artifact_id) else {
+            return Ok(None);
+        };
+        let data = self.content
+            .get(&stored.digest)
+            .map_err(content_error)?
+            .ok_or_else(|| ProxyError::InternalError(format!("Content of artifact {} is missing", artifact_id)))?;
+        Ok(Some(Artifact {
+            id: artifact_id.to_string(),
+            content_type: stored.content_type.clone(),
+            filename: stored.filename.clone(),
+            data: data.to_vec(),
+        }))
+    }
+
+    fn delete(&self, artifact_id: &str) -> ProxyResult<bool> {
+        let removed = self.artifacts
+            .write()
+            .map_err(|_| ProxyError::InternalError("Artifact store lock poisoned".to_string()))?
+            .remove(artifact_id);
+        match removed {
+            Some(stored) => {
+                self.content.release(&stored.digest).map_err(content_error)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_memory_store_roundtrip() {
+        let store = InMemoryArtifactStore::new();
+        let reference = store.put("image/png", Some("cat.png"), vec![1, 2, 3]).unwrap();
+        assert_eq!(reference.size, 3);
+        assert_eq!(reference.filename.as_deref(), Some("cat.png"));
+
+        let artifact = store.get(&reference.artifact_id).unwrap().unwrap();
+        assert_eq!(artifact.data, vec![1, 2, 3]);
+        assert_eq!(artifact.content_type, "image/png");
+
+        assert!(store.delete(&reference.artifact_id).unwrap());
+        assert!(store.get(&reference.artifact_id).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_content_addressed_store_deduplicates() {
+        let content = Arc::new(ContentStore::new());
+        let store = ContentAddressedArtifactStore::new(content.clone());
+        let first = store.put("application/pdf", Some("a.pdf"), vec![9; 64]).unwrap();
+        let second = store.put("application/pdf", Some("b.pdf"), vec![9; 64]).unwrap();
+        assert_ne!(first.artifact_id, second.artifact_id);
+
+        let stats = content.stats().unwrap();
+        assert_eq!(stats.blobs, 1);
+        assert_eq!(stats.stored_bytes, 64);
+        assert_eq!(stats.logical_bytes, 128);
+
+        let artifact = store.get(&second.artifact_id).unwrap().unwrap();
+        assert_eq!(artifact.filename.as_deref(), Some("b.pdf"));
+        assert_eq!(artifact.data, vec![9; 64]);
+
+        assert!(store.delete(&first.artifact_id).unwrap());
+        assert_eq!(content.gc().unwrap().removed_blobs, 0);
+        assert!(store.delete(&second.artifact_id).unwrap());
+        assert_eq!(content.gc().unwrap().removed_blobs, 1);
+        assert!(store.get(&second.artifact_id).unwrap().is_none());
+    }
+}
@@ -0,0 +1,422 @@
+//! 响应内容协商与转换
+//!
+//! 按 Content-Type 对上游响应分类，并提供可选的 XML→JSON、CSV→JSON 转换，
+//! 以及根据 OpenAPI 声明的响应媒体类型生成 Accept 头。
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// 响应转换选项
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResponseConversion {
+    /// 将 XML 响应转换为 JSON
+    pub xml_to_json: bool,
+    /// 将 CSV 响应转换为对象数组
+    pub csv_to_json: bool,
+}
+
+/// 响应媒体类型分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaKind {
+    /// JSON 及 `+json` 类型
+    Json,
+    /// XML 及 `+xml` 类型
+    Xml,
+    /// CSV
+    Csv,
+    /// 其他文本类型
+    Text,
+    /// 二进制或未知类型
+    Binary,
+}
+
+impl MediaKind {
+    /// 根据 Content-Type 分类，忽略参数和大小写
+    pub fn classify(content_type: &str) -> Self {
+        let mime = essence(content_type);
+        if mime == "application/json" || mime.ends_with("+json") {
+            MediaKind::Json
+        } else if matches!(mime.as_str(), "application/xml" | "text/xml") || mime.ends_with("+xml") {
+            MediaKind::Xml
+        } else if mime == "text/csv" {
+            MediaKind::Csv
+        } else if mime.starts_with("text/")
+            || matches!(
+                mime.as_str(),
+                "application/javascript" | "application/x-www-form-urlencoded" | "application/yaml" | "application/x-yaml"
+            )
+        {
+            MediaKind::Text
+        } else {
+            MediaKind::Binary
+        }
+    }
+
+    /// 是否为文本内容
+    pub fn is_text(self) -> bool {
+        self != MediaKind::Binary
+    }
+}
+
+/// 去掉参数后的小写媒体类型
+fn essence(content_type: &str) -> String {
+    content_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase()
+}
+
+/// 根据声明的响应媒体类型生成 Accept 头
+///
+/// JSON 类型优先，其余类型按声明顺序降低权重；没有声明时返回 None。
+pub fn accept_header<'a>(declared: impl IntoIterator<Item = &'a str>) -> Option<String> {
+    let mut media_types: Vec<String> = Vec::new();
+    for media_type in declared {
+        let media_type = essence(media_type);
+        if !media_type.is_empty() && !media_types.contains(&media_type) {
+            media_types.push(media_type);
+        }
+    }
+    media_types.sort_by_key(|media_type| MediaKind::classify(media_type) != MediaKind::Json);
+
+    let accept: Vec<String> = media_types
+        .iter()
+        .enumerate()
+        .map(|(index, media_type)| match index {
+            0 => media_type.clone(),
+            _ => format!("{};q={:.1}", media_type, (10 - index.min(9)) as f32 / 10.0),
+        })
+        .collect();
+    (!accept.is_empty()).then(|| accept.join(", "))
+}
+
+/// 将 XML 文档转换为 JSON
+///
+/// 根元素转换为 `{"根元素名": 值}`。元素的属性以 `@名称` 为键，文本内容以 `#text` 为键，
+/// 同名子元素合并为数组；只有文本的元素直接转换为字符串，空元素转换为 null。
+pub fn xml_to_json(xml: &str) -> Result<Value, String> {
+    let mut parser = XmlParser { input: xml, pos: 0 };
+    parser.skip_misc()?;
+    let (name, value) = parser.element()?;
+    parser.skip_misc()?;
+    if parser.pos < xml.len() {
+        return Err(format!("Unexpected content after root element at byte {}", parser.pos));
+    }
+
+    let mut root = Map::new();
+    root.insert(name, value);
+    Ok(Value::Object(root))
+}
+
+struct XmlParser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl XmlParser<'_> {
+    fn rest(&self) -> &str {
+        &self.input[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let trimmed = self.rest().trim_start();
+        self.pos = self.input.len() - trimmed.len();
+    }
+
+    fn skip_past(&mut self, terminator: &str) -> Result<(), String> {
+        match self.rest().find(terminator) {
+            Some(offset) => {
+                self.pos += offset + terminator.len();
+                Ok(())
+            }
+            None => Err(format!("Unterminated markup, expected '{}'", terminator)),
+        }
+    }
+
+    /// 跳过声明、处理指令、注释和 DOCTYPE
+    fn skip_misc(&mut self) -> Result<(), String> {
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if rest.starts_with("<!DOCTYPE") || rest.starts_with("<!doctype") {
+                self.skip_past(">")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn name(&mut self) -> Result<String, String> {
+        let length = self
+            .rest()
+            .find(|c: char| c.is_whitespace() || matches!(c, '>' | '/' | '='))
+            .unwrap_or(self.rest().len());
+        if length == 0 {
+            return Err(format!("Expected a name at byte {}", self.pos));
+        }
+        let name = self.rest()[..length].to_string();
+        self.pos += length;
+        Ok(name)
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), String> {
+        if self.rest().starts_with(token) {
+            self.pos += token.len();
+            Ok(())
+        } else {
+            Err(format!("Expected '{}' at byte {}", token, self.pos))
+        }
+    }
+
+    fn element(&mut self) -> Result<(String, Value), String> {
+        self.expect("<")?;
+        let name = self.name()?;
+        let mut fields = Map::new();
+
+        // 属性
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with("/>") {
+                self.pos += 2;
+                return Ok((name, collapse(fields, String::new())));
+            }
+            if self.rest().starts_with('>') {
+                self.pos += 1;
+                break;
+            }
+            let attribute = self.name()?;
+            self.skip_whitespace();
+            self.expect("=")?;
+            self.skip_whitespace();
+            let quote = self.rest().chars().next().filter(|c| *c == '"' || *c == '\'')
+                .ok_or_else(|| format!("Expected a quoted attribute value at byte {}", self.pos))?;
+            self.pos += 1;
+            let end = self.rest().find(quote).ok_or("Unterminated attribute value")?;
+            let value = decode_entities(&self.rest()[..end])?;
+            self.pos += end + 1;
+            fields.insert(format!("@{}", attribute), Value::String(value));
+        }
+
+        // 子节点
+        let mut text = String::new();
+        loop {
+            let rest = self.rest();
+            if rest.is_empty() {
+                return Err(format!("Unclosed element <{}>", name));
+            } else if rest.starts_with("</") {
+                self.pos += 2;
+                let closing = self.name()?;
+                if closing != name {
+                    return Err(format!("Mismatched closing tag </{}> for <{}>", closing, name));
+                }
+                self.skip_whitespace();
+                self.expect(">")?;
+                return Ok((name, collapse(fields, text)));
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+                let end = cdata.find("]]>").ok_or("Unterminated CDATA section")?;
+                text.push_str(&cdata[..end]);
+                self.pos += "<![CDATA[".len() + end + "]]>".len();
+            } else if rest.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if rest.starts_with('<') {
+                let (child, value) = self.element()?;
+                match fields.get_mut(&child) {
+                    Some(Value::Array(items)) => items.push(value),
+                    Some(existing) => {
+                        let first = existing.take();
+                        *existing = Value::Array(vec![first, value]);
+                    }
+                    None => {
+                        fields.insert(child, value);
+                    }
+                }
+            } else {
+                let end = rest.find('<').unwrap_or(rest.len());
+                text.push_str(&decode_entities(&rest[..end])?);
+                self.pos += end;
+            }
+        }
+    }
+}
+
+/// 合并元素的字段和文本
+fn collapse(mut fields: Map<String, Value>, text: String) -> Value {
+    let text = text.trim();
+    match (fields.is_empty(), text.is_empty()) {
+        (true, true) => Value::Null,
+        (true, false) => Value::String(text.to_string()),
+        (false, true) => Value::Object(fields),
+        (false, false) => {
+            fields.insert("#text".to_string(), Value::String(text.to_string()));
+            Value::Object(fields)
+        }
+    }
+}
+
+/// 解码 XML 实体引用
+fn decode_entities(text: &str) -> Result<String, String> {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        let end = rest[start..].find(';').ok_or("Unterminated entity reference")? + start;
+        let entity = &rest[start + 1..end];
+        let c = match entity {
+            "lt" => '<',
+            "gt" => '>',
+            "amp" => '&',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32)
+                .ok_or_else(|| format!("Unknown entity '&{};'", entity))?,
+        };
+        decoded.push(c);
+        rest = &rest[end + 1..];
+    }
+    decoded.push_str(rest);
+    Ok(decoded)
+}
+
+/// 将带表头的 CSV 转换为对象数组
+///
+/// 第一行为列名，所有值保留为字符串。支持引号字段、`""` 转义和 CRLF 换行；
+/// 行的列数与表头不一致时返回错误。
+pub fn csv_to_json(csv: &str) -> Result<Value, String> {
+    let mut rows = parse_csv(csv)?.into_iter();
+    let header = match rows.next() {
+        Some(header) => header,
+        None => return Ok(Value::Array(Vec::new())),
+    };
+
+    rows.enumerate()
+        .map(|(index, row)| {
+            if row.len() != header.len() {
+                return Err(format!(
+                    "Row {} has {} fields, expected {}",
+                    index + 2,
+                    row.len(),
+                    header.len()
+                ));
+            }
+            Ok(Value::Object(
+                header.iter().cloned().zip(row.into_iter().map(Value::String)).collect(),
+            ))
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(Value::Array)
+}
+
+fn parse_csv(csv: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = csv.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    in_quotes = false;
+                }
+            }
+            '"' if field.is_empty() => in_quotes = true,
+            ',' if !in_quotes => row.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes && chars.peek() == Some(&'\n') => {}
+            '\n' if !in_quotes => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err("Unterminated quoted field".to_string());
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(MediaKind::classify("application/json; charset=utf-8"), MediaKind::Json);
+        assert_eq!(MediaKind::classify("application/problem+json"), MediaKind::Json);
+        assert_eq!(MediaKind::classify("Text/XML"), MediaKind::Xml);
+        assert_eq!(MediaKind::classify("application/atom+xml"), MediaKind::Xml);
+        assert_eq!(MediaKind::classify("text/csv"), MediaKind::Csv);
+        assert_eq!(MediaKind::classify("text/plain"), MediaKind::Text);
+        assert_eq!(MediaKind::classify("image/png"), MediaKind::Binary);
+    }
+
+    #[test]
+    fn test_accept_header() {
+        assert_eq!(
+            accept_header(["text/csv", "application/json", "text/csv"]).as_deref(),
+            Some("application/json, text/csv;q=0.9")
+        );
+        assert_eq!(accept_header(std::iter::empty()), None);
+    }
+
+    #[test]
+    fn test_xml_to_json() {
+        let xml = r#"<?xml version="1.0"?>
+            <!-- pets -->
+            <pets count="2">
+                <pet id="1"><name>Rex &amp; Co</name><tag/></pet>
+                <pet id="2"><name><![CDATA[<Tom>]]></name></pet>
+                <owner>Ann</owner>
+            </pets>"#;
+
+        assert_eq!(
+            xml_to_json(xml).unwrap(),
+            json!({
+                "pets": {
+                    "@count": "2",
+                    "pet": [
+                        {"@id": "1", "name": "Rex & Co", "tag": null},
+                        {"@id": "2", "name": "<Tom>"}
+                    ],
+                    "owner": "Ann"
+                }
+            })
+        );
+        assert_eq!(xml_to_json("<a x='1'>hi &#65;</a>").unwrap(), json!({"a": {"@x": "1", "#text": "hi A"}}));
+        assert!(xml_to_json("<a><b></a>").is_err());
+        assert!(xml_to_json("not xml").is_err());
+    }
+
+    #[test]
+    fn test_csv_to_json() {
+        let csv = "id,name,note\r\n1,Rex,\"likes \"\"balls\"\", naps\"\r\n2,Tom,\"multi\nline\"\n";
+        assert_eq!(
+            csv_to_json(csv).unwrap(),
+            json!([
+                {"id": "1", "name": "Rex", "note": "likes \"balls\", naps"},
+                {"id": "2", "name": "Tom", "note": "multi\nline"}
+            ])
+        );
+        assert_eq!(csv_to_json("").unwrap(), json!([]));
+        assert!(csv_to_json("a,b\n1\n").is_err());
+        assert!(csv_to_json("a\n\"open\n").is_err());
+    }
+}
@@ -1,6 +1,8 @@
 use std::collections::HashMap;
+use base64::Engine;
 use serde_json::Value;
 use serde::{Serialize, Deserialize};
+use super::artifact::ArtifactStore;
 use super::config::{MethodMapping, ParameterMapping};
 use super::error::{ProxyError, ProxyResult};
 
@@ -19,6 +21,93 @@ pub struct HttpRequest {
     pub headers: HashMap<String, String>,
     /// 请求体
     pub body: Option<Value>,
+    /// multipart/form-data 请求体，设置后优先于 `body`
+    pub multipart: Option<MultipartBody>,
+}
+
+/// multipart/form-data 请求体
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultipartBody {
+    /// 分隔符
+    pub boundary: String,
+    /// 各个部分，按顺序编码
+    pub parts: Vec<MultipartPart>,
+}
+
+/// multipart 请求体中的一个部分
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultipartPart {
+    /// 字段名
+    pub name: String,
+    /// 文件名，文件部分才有
+    pub filename: Option<String>,
+    /// 内容类型，纯文本字段为空
+    pub content_type: Option<String>,
+    /// 内容
+    pub data: Vec<u8>,
+}
+
+impl MultipartBody {
+    /// 创建使用随机分隔符的空请求体
+    pub fn new() -> Self {
+        Self::with_boundary(format!("stepflow-{}", uuid::Uuid::new_v4().simple()))
+    }
+
+    /// 创建使用指定分隔符的空请求体
+    pub fn with_boundary(boundary: impl Into<String>) -> Self {
+        Self {
+            boundary: boundary.into(),
+            parts: Vec::new(),
+        }
+    }
+
+    /// 添加纯文本字段
+    pub fn text(&mut self, name: &str, value: impl Into<String>) {
+        self.parts.push(MultipartPart {
+            name: name.to_string(),
+            filename: None,
+            content_type: None,
+            data: value.into().into_bytes(),
+        });
+    }
+
+    /// 请求的 Content-Type 头
+    pub fn content_type(&self) -> String {
+        format!("multipart/form-data; boundary={}", self.boundary)
+    }
+
+    /// 编码为请求体字节
+    pub fn encode(&self) -> Vec<u8> {
+        let mut body = Vec::new();
+        for part in &self.parts {
+            body.extend_from_slice(format!("--{}\r\n", self.boundary).as_bytes());
+            let mut disposition = format!("Content-Disposition: form-data; name=\"{}\"", escape_quoted(&part.name));
+            if let Some(filename) = &part.filename {
+                disposition.push_str(&format!("; filename=\"{}\"", escape_quoted(filename)));
+            }
+            body.extend_from_slice(disposition.as_bytes());
+            body.extend_from_slice(b"\r\n");
+            if let Some(content_type) = &part.content_type {
+                body.extend_from_slice(format!("Content-Type: {}\r\n", content_type).as_bytes());
+            }
+            body.extend_from_slice(b"\r\n");
+            body.extend_from_slice(&part.data);
+            body.extend_from_slice(b"\r\n");
+        }
+        body.extend_from_slice(format!("--{}--\r\n", self.boundary).as_bytes());
+        body
+    }
+}
+
+impl Default for MultipartBody {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 转义 Content-Disposition 中带引号的值
+fn escape_quoted(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace(['\r', '\n'], " ")
 }
 
 /// JSON RPC 请求
@@ -56,6 +145,8 @@ pub struct HttpResponse {
     pub headers: HashMap<String, String>,
     /// 响应体
     pub body: Option<Value>,
+    /// 因上游限速排队等待的总时间（毫秒），包含限流重试前的等待
+    pub queue_wait_ms: u64,
 }
 
 /// 参数转换器
@@ -115,6 +206,7 @@ impl ParameterConverter {
             path_params: HashMap::new(),
             headers: HashMap::new(),
             body: None,
+            multipart: None,
         };
 
         // 如果有参数，进行转换
@@ -238,6 +330,130 @@ impl ParameterConverter {
         Ok(())
     }
 
+    /// 按 multipart/form-data 的请求体 schema 构建 multipart 请求体
+    ///
+    /// `format: binary` 的属性作为文件部分，取值可以是：
+    /// - 制品引用 `{"artifact_id": "..."}`，从 `store` 读取内容
+    /// - `{"base64": "...", "filename": "...", "content_type": "..."}`
+    /// - base64 字符串
+    ///
+    /// 文件数组会展开为同名的多个部分；对象和数组编码为 JSON 部分，其他标量编码为文本。
+    /// `encoding` 是 OpenAPI 媒体类型的 encoding 对象；文件未给出内容类型时使用其中具体的 `contentType`，
+    /// JSON 部分则总是使用它。
+    /// schema 没有声明 `properties` 时编码全部参数。
+    pub fn build_multipart_body(
+        params: &serde_json::Map<String, Value>,
+        schema: &Value,
+        encoding: &HashMap<String, Value>,
+        store: Option<&dyn ArtifactStore>,
+    ) -> ProxyResult<MultipartBody> {
+        let mut body = MultipartBody::new();
+        let no_properties = serde_json::Map::new();
+        let properties = schema.get("properties").and_then(|p| p.as_object());
+
+        if let Some(required) = schema.get("required").and_then(|r| r.as_array()) {
+            for name in required.iter().filter_map(|name| name.as_str()) {
+                if params.get(name).is_none_or(|value| value.is_null()) {
+                    return Err(ProxyError::ParameterConversionError(
+                        format!("Required multipart field '{}' is missing", name)
+                    ));
+                }
+            }
+        }
+
+        let fields: Vec<(&String, &Value)> = match properties {
+            Some(properties) => properties.keys()
+                .filter_map(|name| params.get_key_value(name))
+                .collect(),
+            None => params.iter().collect(),
+        };
+
+        for (name, value) in fields {
+            if value.is_null() {
+                continue;
+            }
+            let property = properties.unwrap_or(&no_properties).get(name).unwrap_or(&Value::Null);
+            let encoded_type = encoding.get(name)
+                .and_then(|e| e.get("contentType"))
+                .and_then(|t| t.as_str());
+
+            if Self::is_binary_schema(property) {
+                let part = Self::file_part(name, value, encoded_type, store)?;
+                body.parts.push(part);
+            } else if Self::is_binary_schema(property.get("items").unwrap_or(&Value::Null)) {
+                let items = value.as_array().ok_or_else(|| ProxyError::ParameterConversionError(
+                    format!("Multipart field '{}' must be an array of files", name)
+                ))?;
+                for item in items {
+                    body.parts.push(Self::file_part(name, item, encoded_type, store)?);
+                }
+            } else {
+                match value {
+                    Value::Object(_) | Value::Array(_) => body.parts.push(MultipartPart {
+                        name: name.clone(),
+                        filename: None,
+                        content_type: Some(encoded_type.unwrap_or("application/json").to_string()),
+                        data: serde_json::to_vec(value).map_err(|e| ProxyError::ParameterConversionError(
+                            format!("Failed to serialize multipart field '{}': {}", name, e)
+                        ))?,
+                    }),
+                    Value::String(text) => body.text(name, text.as_str()),
+                    other => body.text(name, other.to_string()),
+                }
+            }
+        }
+
+        Ok(body)
+    }
+
+    /// 判断 schema 是否描述二进制文件
+    fn is_binary_schema(schema: &Value) -> bool {
+        schema.get("format").and_then(|f| f.as_str()) == Some("binary")
+            || schema.get("contentMediaType").is_some()
+    }
+
+    /// 根据取值构建文件部分
+    fn file_part(
+        name: &str,
+        value: &Value,
+        encoded_type: Option<&str>,
+        store: Option<&dyn ArtifactStore>,
+    ) -> ProxyResult<MultipartPart> {
+        let decode = |data: &str| base64::engine::general_purpose::STANDARD.decode(data).map_err(|e| {
+            ProxyError::ParameterConversionError(format!("Multipart field '{}' is not valid base64: {}", name, e))
+        });
+        let field = |key: &str| value.get(key).and_then(|v| v.as_str()).map(String::from);
+
+        let (data, filename, content_type) = if let Some(artifact_id) = field("artifact_id") {
+            let store = store.ok_or_else(|| ProxyError::ParameterConversionError(
+                format!("Multipart field '{}' references an artifact but no artifact store is configured", name)
+            ))?;
+            let artifact = store.get(&artifact_id)?.ok_or_else(|| ProxyError::ParameterConversionError(
+                format!("Artifact '{}' not found", artifact_id)
+            ))?;
+            (artifact.data, field("filename").or(artifact.filename), field("content_type").or(Some(artifact.content_type)))
+        } else if let Some(data) = field("base64") {
+            (decode(&data)?, field("filename"), field("content_type"))
+        } else if let Some(data) = value.as_str() {
+            (decode(data)?, None, None)
+        } else {
+            return Err(ProxyError::ParameterConversionError(
+                format!("Multipart field '{}' must be an artifact reference or base64 data", name)
+            ));
+        };
+
+        Ok(MultipartPart {
+            name: name.to_string(),
+            filename: Some(filename.unwrap_or_else(|| name.to_string())),
+            content_type: Some(
+                content_type
+                    .or_else(|| encoded_type.filter(|t| !t.contains([',', '*'])).map(String::from))
+                    .unwrap_or_else(|| "application/octet-stream".to_string()),
+            ),
+            data,
+        })
+    }
+
     /// 将 HTTP 响应转换为 JSON RPC 响应
     pub fn convert_to_json_rpc_response(
         http_response: &HttpResponse,
@@ -358,4 +574,74 @@ mod tests {
         assert_eq!(http_request.path, "/users/123");
         assert_eq!(http_request.query_params.get("format"), Some(&"json".to_string()));
     }
+
+    #[test]
+    fn test_build_multipart_body() {
+        use super::super::artifact::{ArtifactStore, InMemoryArtifactStore};
+
+        let store = InMemoryArtifactStore::new();
+        let avatar = store.put("image/png", Some("avatar.png"), vec![0x89, 0x50]).unwrap();
+        let schema = serde_json::json!({
+            "type": "object",
+            "required": ["name", "avatar"],
+            "properties": {
+                "name": {"type": "string"},
+                "age": {"type": "integer"},
+                "profile": {"type": "object"},
+                "avatar": {"type": "string", "format": "binary"},
+                "attachments": {"type": "array", "items": {"type": "string", "format": "binary"}}
+            }
+        });
+        let params = serde_json::json!({
+            "name": "Rex",
+            "age": 3,
+            "profile": {"breed": "collie"},
+            "avatar": {"artifact_id": avatar.artifact_id},
+            "attachments": ["aGk=", {"base64": "eW8=", "filename": "b.txt", "content_type": "text/plain"}]
+        });
+        let encoding = HashMap::from([
+            ("attachments".to_string(), serde_json::json!({"contentType": "application/octet-stream"})),
+        ]);
+
+        let body = ParameterConverter::build_multipart_body(
+            params.as_object().unwrap(), &schema, &encoding, Some(&store),
+        ).unwrap();
+
+        let parts = |name: &str| body.parts.iter().filter(|part| part.name == name).collect::<Vec<_>>();
+        assert_eq!(body.parts.len(), 6);
+        assert_eq!(parts("name")[0].data, b"Rex");
+        assert_eq!(parts("age")[0].data, b"3");
+        assert_eq!(parts("profile")[0].content_type.as_deref(), Some("application/json"));
+        assert_eq!(parts("avatar")[0].filename.as_deref(), Some("avatar.png"));
+        assert_eq!(parts("avatar")[0].content_type.as_deref(), Some("image/png"));
+        let attachments = parts("attachments");
+        assert_eq!(attachments[0].data, b"hi");
+        assert_eq!(attachments[0].content_type.as_deref(), Some("application/octet-stream"));
+        assert_eq!(attachments[1].content_type.as_deref(), Some("text/plain"));
+
+        let missing = ParameterConverter::build_multipart_body(
+            serde_json::json!({"name": "Rex"}).as_object().unwrap(), &schema, &encoding, Some(&store),
+        );
+        assert!(matches!(missing, Err(ProxyError::ParameterConversionError(_))));
+    }
+
+    #[test]
+    fn test_encode_multipart_body() {
+        let mut body = MultipartBody::with_boundary("XyZ");
+        body.text("name", "Rex");
+        body.parts.push(MultipartPart {
+            name: "file".to_string(),
+            filename: Some("a.txt".to_string()),
+            content_type: Some("text/plain".to_string()),
+            data: b"hello".to_vec(),
+        });
+
+        assert_eq!(body.content_type(), "multipart/form-data; boundary=XyZ");
+        assert_eq!(
+            String::from_utf8(body.encode()).unwrap(),
+            "--XyZ\r\nContent-Disposition: form-data; name=\"name\"\r\n\r\nRex\r\n\
+             --XyZ\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\n\
+             Content-Type: text/plain\r\n\r\nhello\r\n--XyZ--\r\n"
+        );
+    }
 } 
\ No newline at end of file
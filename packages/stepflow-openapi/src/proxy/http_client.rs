@@ -1,6 +1,13 @@
+use std::sync::Arc;
 use std::time::Duration;
+use base64::Engine;
 use serde_json::Value;
+use super::artifact::ArtifactStore;
+use super::content::{csv_to_json, xml_to_json, MediaKind, ResponseConversion};
 use super::converter::{HttpRequest, HttpResponse};
+use super::pagination::{PaginatedResponse, PaginationConfig};
+use super::rate_limit::{rate_limit_host, HostRateLimitMetrics, UpstreamRateLimiter};
+use super::recording::{InteractionRecorder, RecordingMode};
 use super::error::{ProxyError, ProxyResult};
 
 /// HTTP 客户端配置
@@ -12,6 +19,12 @@ pub struct HttpClientConfig {
     pub max_retries: u32,
     /// 用户代理字符串
     pub user_agent: String,
+    /// 二进制响应内联返回的最大字节数，超过时存为制品，没有制品存储时截断
+    pub inline_binary_limit: usize,
+    /// 非 JSON 文本响应的转换选项
+    pub response_conversion: ResponseConversion,
+    /// 429 响应按 `Retry-After` 等待后重试的最长等待时间（秒），超过时直接返回 429
+    pub max_retry_after_seconds: u64,
 }
 
 impl Default for HttpClientConfig {
@@ -20,6 +33,9 @@ impl Default for HttpClientConfig {
             timeout_seconds: 30,
             max_retries: 3,
             user_agent: "stepflow-openapi-proxy/1.0".to_string(),
+            inline_binary_limit: 1024 * 1024,
+            response_conversion: ResponseConversion::default(),
+            max_retry_after_seconds: 60,
         }
     }
 }
@@ -28,6 +44,15 @@ impl Default for HttpClientConfig {
 pub struct HttpApiProxy {
     client: reqwest::Client,
     config: HttpClientConfig,
+    /// 保存大体积二进制响应的制品存储
+    artifact_store: Option<Arc<dyn ArtifactStore>>,
+    /// 按上游主机排队的限速器，默认使用进程内共享实例
+    rate_limiter: Arc<UpstreamRateLimiter>,
+    /// 上游交互录制器，回放模式下不访问网络
+    recorder: Option<Arc<InteractionRecorder>>,
+    /// 故障注入（仅测试）
+    #[cfg(feature = "fault-injection")]
+    faults: Option<stepflow_core::FaultController>,
 }
 
 impl HttpApiProxy {
@@ -39,7 +64,52 @@ impl HttpApiProxy {
             .build()
             .map_err(|e| ProxyError::HttpRequestError(format!("Failed to create HTTP client: {}", e)))?;
 
-        Ok(Self { client, config })
+        Ok(Self {
+            client,
+            config,
+            artifact_store: None,
+            rate_limiter: UpstreamRateLimiter::global(),
+            recorder: None,
+            #[cfg(feature = "fault-injection")]
+            faults: None,
+        })
+    }
+
+    /// 设置制品存储，超过内联上限的二进制响应存入其中
+    pub fn with_artifact_store(mut self, store: Arc<dyn ArtifactStore>) -> Self {
+        self.artifact_store = Some(store);
+        self
+    }
+
+    /// 设置限速器，多个客户端共享同一个限速器时按主机统一排队
+    pub fn with_rate_limiter(mut self, limiter: Arc<UpstreamRateLimiter>) -> Self {
+        self.rate_limiter = limiter;
+        self
+    }
+
+    /// 设置交互录制器，录制模式下记录每次上游交互，回放模式下返回录制的响应
+    pub fn with_recorder(mut self, recorder: Arc<InteractionRecorder>) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// 当前使用的限速器
+    pub fn rate_limiter(&self) -> &Arc<UpstreamRateLimiter> {
+        &self.rate_limiter
+    }
+
+    /// 各上游主机的限速指标
+    pub fn rate_limit_metrics(&self) -> Vec<HostRateLimitMetrics> {
+        self.rate_limiter.metrics()
+    }
+
+    /// 为每次 HTTP 尝试注入故障（仅测试）
+    ///
+    /// 注入的错误按连接错误处理，会触发重试；部分写入故障会真正发送请求后丢弃响应。
+    #[cfg(feature = "fault-injection")]
+    pub fn with_fault_controller(mut self, controller: stepflow_core::FaultController) -> Self {
+        self.faults = Some(controller);
+        self
     }
 
     /// 创建默认配置的 HTTP 代理客户端
@@ -53,12 +123,35 @@ impl HttpApiProxy {
         base_url: &str, 
         request: &HttpRequest
     ) -> ProxyResult<HttpResponse> {
+        if let Some(recorder) = self.recorder.as_ref().filter(|r| r.mode() == RecordingMode::Replay) {
+            return recorder.replay(request);
+        }
+
         let url = super::converter::ParameterConverter::build_http_url(base_url, request);
+        let host = rate_limit_host(&url);
+        let max_retry_after = Duration::from_secs(self.config.max_retry_after_seconds);
         
         let mut retries = 0;
+        let mut queue_wait = Duration::ZERO;
         loop {
+            queue_wait += self.rate_limiter.acquire(&host).await;
             match self.try_send_request(&url, request).await {
-                Ok(response) => return Ok(response),
+                Ok(mut response) => {
+                    let backoff = self.rate_limiter.record_response(&host, response.status, &response.headers);
+                    // 被限流时由限速器暂停该主机，下一次 acquire 会等到限流解除
+                    let throttled = response.status == 429
+                        && retries < self.config.max_retries
+                        && backoff.is_some_and(|backoff| backoff <= max_retry_after);
+                    if throttled {
+                        retries += 1;
+                        continue;
+                    }
+                    response.queue_wait_ms = queue_wait.as_millis() as u64;
+                    if let Some(recorder) = &self.recorder {
+                        recorder.record(request, &response);
+                    }
+                    return Ok(response);
+                }
                 Err(e) => {
                     retries += 1;
                     if retries > self.config.max_retries {
@@ -78,8 +171,68 @@ impl HttpApiProxy {
         }
     }
 
+    /// 按分页配置请求所有页并合并条目
+    ///
+    /// 达到 `max_pages` 或 `max_items` 时停止并标记 `truncated`；任何一页返回
+    /// 非 2xx 状态都会中止并返回错误。
+    pub async fn send_paginated(
+        &self,
+        base_url: &str,
+        request: &HttpRequest,
+        config: &PaginationConfig,
+    ) -> ProxyResult<PaginatedResponse> {
+        let mut base_url = base_url.to_string();
+        let mut request = config.first_request(request);
+        let mut items = Vec::new();
+        let mut pages = 0;
+        let mut queue_wait_ms = 0;
+
+        loop {
+            let response = self.send_request(&base_url, &request).await?;
+            pages += 1;
+            queue_wait_ms += response.queue_wait_ms;
+            if !(200..300).contains(&response.status) {
+                return Err(ProxyError::HttpRequestError(format!(
+                    "Pagination stopped: HTTP {} on page {}",
+                    response.status, pages
+                )));
+            }
+
+            let page_items = config.extract_items(response.body.as_ref())?;
+            let page_len = page_items.len();
+            items.extend(page_items);
+
+            let next = config.next_request(&base_url, &request, &response, page_len);
+            let capped = pages >= config.max_pages || items.len() >= config.max_items;
+            if next.is_none() || capped {
+                let truncated = items.len() > config.max_items || (capped && next.is_some());
+                items.truncate(config.max_items);
+                return Ok(PaginatedResponse {
+                    items,
+                    pages,
+                    truncated,
+                    queue_wait_ms,
+                    last_response: response,
+                });
+            }
+            (base_url, request) = next.unwrap();
+        }
+    }
+
     /// 尝试发送单次 HTTP 请求
     async fn try_send_request(&self, url: &str, request: &HttpRequest) -> ProxyResult<HttpResponse> {
+        #[cfg(feature = "fault-injection")]
+        let fault = match &self.faults {
+            Some(faults) => faults.inject(stepflow_core::FaultTarget::HttpProxy).await,
+            None => None,
+        };
+        #[cfg(feature = "fault-injection")]
+        if fault == Some(stepflow_core::Fault::Error) {
+            return Err(ProxyError::HttpRequestError(
+                stepflow_core::Fault::Error.message(stepflow_core::FaultTarget::HttpProxy),
+            ));
+        }
+
         let mut req_builder = match request.method.to_uppercase().as_str() {
             "GET" => self.client.get(url),
             "POST" => self.client.post(url),
@@ -104,7 +257,11 @@ impl HttpApiProxy {
         }
 
         // 添加请求体
-        if let Some(body) = &request.body {
+        if let Some(multipart) = &request.multipart {
+            req_builder = req_builder
+                .header("Content-Type", multipart.content_type())
+                .body(multipart.encode());
+        } else if let Some(body) = &request.body {
             req_builder = req_builder
                 .header("Content-Type", "application/json")
                 .json(body);
@@ -116,6 +273,13 @@ impl HttpApiProxy {
             .await
             .map_err(|e| ProxyError::HttpRequestError(format!("Failed to send request: {}", e)))?;
 
+        #[cfg(feature = "fault-injection")]
+        if fault == Some(stepflow_core::Fault::PartialWrite) {
+            return Err(ProxyError::HttpRequestError(
+                stepflow_core::Fault::PartialWrite.message(stepflow_core::FaultTarget::HttpProxy),
+            ));
+        }
+
         // 提取响应信息
         let status = response.status().as_u16();
         let mut headers = std::collections::HashMap::new();
@@ -127,28 +291,92 @@ impl HttpApiProxy {
         }
 
         // 读取响应体
-        let body_text = response
-            .text()
+        let body_bytes = response
+            .bytes()
             .await
             .map_err(|e| ProxyError::HttpRequestError(format!("Failed to read response body: {}", e)))?;
 
-        let body = if body_text.is_empty() {
+        let body = if body_bytes.is_empty() {
             None
         } else {
-            // 尝试解析为 JSON，如果失败则保存为字符串
-            match serde_json::from_str::<Value>(&body_text) {
-                Ok(json) => Some(json),
-                Err(_) => Some(Value::String(body_text)),
-            }
+            Some(self.decode_body(&headers, body_bytes.to_vec())?)
         };
 
         Ok(HttpResponse {
             status,
             headers,
             body,
+            queue_wait_ms: 0,
         })
     }
 
+    /// 按 Content-Type 转换响应体
+    ///
+    /// JSON 解析为 JSON 值；XML 和 CSV 在开启转换时转换为 JSON，转换失败或未开启时
+    /// 与其他文本一样原样返回字符串；二进制或非 UTF-8 内容交给 [`Self::binary_body`]。
+    /// 没有 Content-Type 时按文本处理，能解析为 JSON 则返回 JSON。
+    fn decode_body(&self, headers: &std::collections::HashMap<String, String>, data: Vec<u8>) -> ProxyResult<Value> {
+        let content_type = headers.get("content-type").map(String::as_str);
+        let kind = content_type.map_or(MediaKind::Text, MediaKind::classify);
+        if !kind.is_text() {
+            return self.binary_with_headers(headers, content_type, data);
+        }
+        let text = match String::from_utf8(data) {
+            Ok(text) => text,
+            Err(e) => return self.binary_with_headers(headers, content_type, e.into_bytes()),
+        };
+
+        let conversion = self.config.response_conversion;
+        let converted = match kind {
+            MediaKind::Json => serde_json::from_str(&text).ok(),
+            MediaKind::Xml if conversion.xml_to_json => xml_to_json(&text).ok(),
+            MediaKind::Csv if conversion.csv_to_json => csv_to_json(&text).ok(),
+            MediaKind::Text if content_type.is_none() => serde_json::from_str(&text).ok(),
+            _ => None,
+        };
+        Ok(converted.unwrap_or(Value::String(text)))
+    }
+
+    fn binary_with_headers(
+        &self,
+        headers: &std::collections::HashMap<String, String>,
+        content_type: Option<&str>,
+        data: Vec<u8>,
+    ) -> ProxyResult<Value> {
+        let filename = headers.get("content-disposition").and_then(|d| disposition_filename(d));
+        self.binary_body(content_type.unwrap_or("application/octet-stream"), filename.as_deref(), data)
+    }
+
+    /// 转换二进制响应体
+    ///
+    /// 超过内联上限且配置了制品存储时返回制品引用，否则返回
+    /// `{"content_type", "size", "base64"}` 形式的内联内容；没有制品存储时
+    /// 只保留前 `inline_binary_limit` 个字节，并标记 `"truncated": true`。
+    fn binary_body(&self, content_type: &str, filename: Option<&str>, mut data: Vec<u8>) -> ProxyResult<Value> {
+        let limit = self.config.inline_binary_limit;
+        if let Some(store) = self.artifact_store.as_ref().filter(|_| data.len() > limit) {
+            let reference = store.put(content_type, filename, data)?;
+            return serde_json::to_value(reference)
+                .map_err(|e| ProxyError::InternalError(format!("Failed to serialize artifact reference: {}", e)));
+        }
+
+        let size = data.len();
+        let truncated = size > limit;
+        data.truncate(limit);
+        let mut body = serde_json::json!({
+            "content_type": content_type,
+            "size": size,
+            "base64": base64::engine::general_purpose::STANDARD.encode(&data),
+        });
+        if truncated {
+            body["truncated"] = Value::Bool(true);
+        }
+        if let Some(filename) = filename {
+            body["filename"] = Value::String(filename.to_string());
+        }
+        Ok(body)
+    }
+
     /// 判断是否应该重试请求
     fn should_retry(&self, error: &ProxyError) -> bool {
         match error {
@@ -225,6 +453,15 @@ impl HttpApiProxy {
     }
 }
 
+/// 从 Content-Disposition 头中提取文件名
+fn disposition_filename(disposition: &str) -> Option<String> {
+    disposition.split(';')
+        .filter_map(|param| param.trim().split_once('='))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case("filename"))
+        .map(|(_, value)| value.trim().trim_matches('"').to_string())
+        .filter(|filename| !filename.is_empty())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -261,6 +498,7 @@ mod tests {
             path_params: HashMap::new(),
             headers: HashMap::new(),
             body: None,
+            multipart: None,
         };
 
         let url = super::super::converter::ParameterConverter::build_http_url("http://api.example.com", &request);
@@ -268,4 +506,64 @@ mod tests {
         assert!(url.contains("format=json"));
         assert!(url.contains("include=profile"));
     }
+
+    #[test]
+    fn test_binary_response_body() {
+        use super::super::artifact::InMemoryArtifactStore;
+
+        let store = Arc::new(InMemoryArtifactStore::new());
+        let config = HttpClientConfig {
+            inline_binary_limit: 4,
+            ..HttpClientConfig::default()
+        };
+        let client = HttpApiProxy::new(config).unwrap().with_artifact_store(store.clone());
+
+        let inline = client.binary_body("image/png", None, vec![1, 2, 3]).unwrap();
+        assert_eq!(inline["base64"], "AQID");
+        assert_eq!(inline["size"], 3);
+
+        let stored = client.binary_body("application/pdf", Some("report.pdf"), vec![0; 16]).unwrap();
+        assert_eq!(stored["size"], 16);
+        let artifact = store.get(stored["artifact_id"].as_str().unwrap()).unwrap().unwrap();
+        assert_eq!(artifact.filename.as_deref(), Some("report.pdf"));
+        assert_eq!(artifact.data.len(), 16);
+    }
+
+    #[test]
+    fn test_decode_body() {
+        let headers = |content_type: &str| HashMap::from([("content-type".to_string(), content_type.to_string())]);
+        let plain = HttpApiProxy::default().unwrap();
+        let converting = HttpApiProxy::new(HttpClientConfig {
+            inline_binary_limit: 2,
+            response_conversion: ResponseConversion { xml_to_json: true, csv_to_json: true },
+            ..HttpClientConfig::default()
+        })
+        .unwrap();
+
+        let xml = b"<a><b>1</b></a>".to_vec();
+        assert_eq!(plain.decode_body(&headers("application/xml"), xml.clone()).unwrap(), "<a><b>1</b></a>");
+        assert_eq!(
+            converting.decode_body(&headers("application/xml"), xml).unwrap(),
+            serde_json::json!({"a": {"b": "1"}})
+        );
+        assert_eq!(
+            converting.decode_body(&headers("text/csv"), b"x,y\n1,2\n".to_vec()).unwrap(),
+            serde_json::json!([{"x": "1", "y": "2"}])
+        );
+        // 转换失败时原样返回文本
+        assert_eq!(converting.decode_body(&headers("text/xml"), b"<a>".to_vec()).unwrap(), "<a>");
+        assert_eq!(plain.decode_body(&HashMap::new(), b"{\"ok\":true}".to_vec()).unwrap(), serde_json::json!({"ok": true}));
+        assert_eq!(plain.decode_body(&headers("text/plain"), b"42".to_vec()).unwrap(), "42");
+
+        let captured = converting.decode_body(&headers("application/x-unknown"), vec![1, 2, 3, 4]).unwrap();
+        assert_eq!(captured["size"], 4);
+        assert_eq!(captured["base64"], "AQI=");
+        assert_eq!(captured["truncated"], true);
+    }
+
+    #[test]
+    fn test_disposition_filename() {
+        assert_eq!(disposition_filename("attachment; filename=\"report.pdf\"").as_deref(), Some("report.pdf"));
+        assert_eq!(disposition_filename("inline"), None);
+    }
 } 
\ No newline at end of file
@@ -8,9 +8,19 @@ pub mod http_client;
 pub mod converter;
 pub mod config;
 pub mod error;
+pub mod artifact;
+pub mod content;
+pub mod pagination;
+pub mod rate_limit;
+pub mod recording;
 
 pub use server::*;
 pub use http_client::*;
 pub use converter::*;
 pub use config::*;
-pub use error::*; 
\ No newline at end of file
+pub use error::*;
+pub use artifact::*;
+pub use content::*;
+pub use pagination::*;
+pub use rate_limit::*;
+pub use recording::*; 
\ No newline at end of file
@@ -0,0 +1,294 @@
+//! 分页自动跟随
+//!
+//! 按配置的分页策略（页码参数、响应中的游标、Link 头）连续请求列表接口，
+//! 把各页的条目合并为一个数组，并用总请求数和总条目数上限防止无限翻页。
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use super::converter::{HttpRequest, HttpResponse};
+use super::error::{ProxyError, ProxyResult};
+
+/// 分页配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PaginationConfig {
+    /// 分页策略
+    pub strategy: PaginationStrategy,
+    /// 响应中条目数组的位置：以 `/` 开头的 JSON Pointer 或以 `.` 分隔的字段路径；
+    /// 为空时响应体本身必须是数组
+    #[serde(default)]
+    pub items_path: Option<String>,
+    /// 最多请求的页数
+    #[serde(default = "default_max_pages")]
+    pub max_pages: usize,
+    /// 最多收集的条目数
+    #[serde(default = "default_max_items")]
+    pub max_items: usize,
+    /// 调用方未指定时是否收集全部分页
+    #[serde(default)]
+    pub collect_by_default: bool,
+}
+
+/// 分页策略
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PaginationStrategy {
+    /// 页码查询参数；返回的条目少于 `per_page` 或为空时结束
+    Page {
+        /// 页码参数名
+        #[serde(default = "default_page_param")]
+        page_param: String,
+        /// 每页条目数参数名
+        #[serde(default)]
+        per_page_param: Option<String>,
+        /// 每页条目数
+        #[serde(default)]
+        per_page: Option<u64>,
+        /// 第一页的页码
+        #[serde(default = "default_start_page")]
+        start_page: u64,
+    },
+    /// 响应字段中的游标；游标缺失、为空或不变时结束
+    Cursor {
+        /// 传递游标的查询参数名
+        cursor_param: String,
+        /// 响应中游标的位置，格式同 `items_path`
+        cursor_path: String,
+    },
+    /// 跟随 `Link` 头中 `rel="next"` 的地址
+    LinkHeader,
+}
+
+fn default_max_pages() -> usize {
+    50
+}
+
+fn default_max_items() -> usize {
+    10_000
+}
+
+fn default_page_param() -> String {
+    "page".to_string()
+}
+
+fn default_start_page() -> u64 {
+    1
+}
+
+/// 合并后的分页结果
+#[derive(Debug, Clone)]
+pub struct PaginatedResponse {
+    /// 所有页的条目
+    pub items: Vec<Value>,
+    /// 实际请求的页数
+    pub pages: usize,
+    /// 是否因为上限而提前停止
+    pub truncated: bool,
+    /// 所有页因上游限速排队等待的总时间（毫秒）
+    pub queue_wait_ms: u64,
+    /// 最后一页的响应
+    pub last_response: HttpResponse,
+}
+
+impl PaginationConfig {
+    /// 为第一页补充分页参数
+    pub fn first_request(&self, request: &HttpRequest) -> HttpRequest {
+        let mut request = request.clone();
+        if let PaginationStrategy::Page { page_param, per_page_param, per_page, start_page } = &self.strategy {
+            request.query_params.entry(page_param.clone()).or_insert_with(|| start_page.to_string());
+            if let (Some(param), Some(per_page)) = (per_page_param, per_page) {
+                request.query_params.entry(param.clone()).or_insert_with(|| per_page.to_string());
+            }
+        }
+        request
+    }
+
+    /// 取出一页响应中的条目
+    pub fn extract_items(&self, body: Option<&Value>) -> ProxyResult<Vec<Value>> {
+        let body = body.unwrap_or(&Value::Null);
+        let items = match &self.items_path {
+            Some(path) => lookup(body, path),
+            None => Some(body),
+        };
+        match items {
+            Some(Value::Array(items)) => Ok(items.clone()),
+            Some(Value::Null) | None if body.is_null() => Ok(Vec::new()),
+            _ => Err(ProxyError::HttpRequestError(format!(
+                "Paginated response has no item array at '{}'",
+                self.items_path.as_deref().unwrap_or("")
+            ))),
+        }
+    }
+
+    /// 根据当前页计算下一页请求，返回目标基础地址和请求；没有下一页时返回 None
+    pub fn next_request(
+        &self,
+        base_url: &str,
+        request: &HttpRequest,
+        response: &HttpResponse,
+        page_items: usize,
+    ) -> Option<(String, HttpRequest)> {
+        match &self.strategy {
+            PaginationStrategy::Page { page_param, per_page, start_page, .. } => {
+                if page_items == 0 || per_page.is_some_and(|per_page| (page_items as u64) < per_page) {
+                    return None;
+                }
+                let current = request.query_params.get(page_param)
+                    .and_then(|page| page.parse::<u64>().ok())
+                    .unwrap_or(*start_page);
+                let mut next = request.clone();
+                next.query_params.insert(page_param.clone(), (current + 1).to_string());
+                Some((base_url.to_string(), next))
+            }
+            PaginationStrategy::Cursor { cursor_param, cursor_path } => {
+                let cursor = match lookup(response.body.as_ref()?, cursor_path)? {
+                    Value::String(cursor) if !cursor.is_empty() => cursor.clone(),
+                    Value::Number(cursor) => cursor.to_string(),
+                    _ => return None,
+                };
+                if request.query_params.get(cursor_param) == Some(&cursor) {
+                    return None;
+                }
+                let mut next = request.clone();
+                next.query_params.insert(cursor_param.clone(), cursor);
+                Some((base_url.to_string(), next))
+            }
+            PaginationStrategy::LinkHeader => {
+                let link = response.headers.iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case("link"))
+                    .map(|(_, value)| value.as_str())?;
+                let target = next_link(link)?;
+                let url = reqwest::Url::parse(&target)
+                    .or_else(|_| reqwest::Url::parse(base_url)?.join(&target))
+                    .ok()?;
+
+                let mut next = request.clone();
+                next.path = url.path().to_string();
+                next.query_params = url.query_pairs().map(|(k, v)| (k.into_owned(), v.into_owned())).collect();
+                next.path_params.clear();
+                Some((url.origin().ascii_serialization(), next))
+            }
+        }
+    }
+}
+
+/// 按 JSON Pointer 或点分路径查找字段
+fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    if path.starts_with('/') {
+        value.pointer(path)
+    } else {
+        path.split('.')
+            .filter(|segment| !segment.is_empty())
+            .try_fold(value, |value, segment| match value {
+                Value::Array(items) => items.get(segment.parse::<usize>().ok()?),
+                _ => value.get(segment),
+            })
+    }
+}
+
+/// 从 Link 头中取出 `rel="next"` 的地址
+fn next_link(header: &str) -> Option<String> {
+    header.split(',').find_map(|link| {
+        let mut parts = link.split(';');
+        let url = parts.next()?.trim().strip_prefix('<')?.strip_suffix('>')?;
+        parts
+            .filter_map(|param| param.trim().split_once('='))
+            .any(|(key, value)| {
+                key.trim().eq_ignore_ascii_case("rel")
+                    && value.trim().trim_matches('"').split_whitespace().any(|rel| rel.eq_ignore_ascii_case("next"))
+            })
+            .then(|| url.to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use serde_json::json;
+
+    fn request() -> HttpRequest {
+        HttpRequest {
+            method: "GET".to_string(),
+            path: "/pets".to_string(),
+            query_params: HashMap::new(),
+            path_params: HashMap::new(),
+            headers: HashMap::new(),
+            body: None,
+            multipart: None,
+        }
+    }
+
+    fn response(body: Value, headers: &[(&str, &str)]) -> HttpResponse {
+        HttpResponse {
+            status: 200,
+            headers: headers.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            body: Some(body),
+            queue_wait_ms: 0,
+        }
+    }
+
+    fn config(strategy: PaginationStrategy, items_path: Option<&str>) -> PaginationConfig {
+        PaginationConfig {
+            strategy,
+            items_path: items_path.map(String::from),
+            max_pages: default_max_pages(),
+            max_items: default_max_items(),
+            collect_by_default: false,
+        }
+    }
+
+    #[test]
+    fn test_page_strategy() {
+        let config: PaginationConfig = serde_json::from_value(json!({
+            "strategy": {"type": "page", "per_page_param": "per_page", "per_page": 2}
+        }))
+        .unwrap();
+
+        let first = config.first_request(&request());
+        assert_eq!(first.query_params["page"], "1");
+        assert_eq!(first.query_params["per_page"], "2");
+
+        let full_page = response(json!([1, 2]), &[]);
+        let (_, second) = config.next_request("http://api", &first, &full_page, 2).unwrap();
+        assert_eq!(second.query_params["page"], "2");
+        assert!(config.next_request("http://api", &second, &response(json!([3]), &[]), 1).is_none());
+    }
+
+    #[test]
+    fn test_cursor_strategy() {
+        let config = config(
+            PaginationStrategy::Cursor { cursor_param: "after".to_string(), cursor_path: "meta.next".to_string() },
+            Some("/data"),
+        );
+        let page = response(json!({"data": [{"id": 1}], "meta": {"next": "abc"}}), &[]);
+        assert_eq!(config.extract_items(page.body.as_ref()).unwrap(), vec![json!({"id": 1})]);
+
+        let (_, next) = config.next_request("http://api", &request(), &page, 1).unwrap();
+        assert_eq!(next.query_params["after"], "abc");
+        // 游标不变或缺失时结束
+        assert!(config.next_request("http://api", &next, &page, 1).is_none());
+        assert!(config.next_request("http://api", &next, &response(json!({"data": []}), &[]), 0).is_none());
+        assert!(config.extract_items(Some(&json!({"items": []}))).is_err());
+    }
+
+    #[test]
+    fn test_link_header_strategy() {
+        let config = config(PaginationStrategy::LinkHeader, None);
+        let page = response(
+            json!([1]),
+            &[("link", r#"<https://api.example.com/v2/pets?page=2&limit=10>; rel="next", <https://api.example.com/v2/pets?page=9>; rel="last""#)],
+        );
+
+        let (base, next) = config.next_request("https://api.example.com/v2", &request(), &page, 1).unwrap();
+        assert_eq!(base, "https://api.example.com");
+        assert_eq!(next.path, "/v2/pets");
+        assert_eq!(next.query_params["page"], "2");
+        assert_eq!(next.query_params["limit"], "10");
+
+        let relative = response(json!([1]), &[("Link", r#"</v2/pets?page=3>; rel="next""#)]);
+        let (_, next) = config.next_request("https://api.example.com/v2", &request(), &relative, 1).unwrap();
+        assert_eq!(next.query_params["page"], "3");
+
+        assert!(config.next_request("https://api.example.com", &request(), &response(json!([]), &[]), 0).is_none());
+    }
+}
@@ -0,0 +1,328 @@
+//! 上游限速
+//!
+//! 按上游主机排队和控制请求节奏：配置了限速的主机使用令牌桶，收到 429 或
+//! `X-RateLimit-Remaining: 0` 时在 `Retry-After`/`X-RateLimit-Reset` 指定的时间内暂停该主机。
+//! 同一进程内的代理客户端默认共享 [`UpstreamRateLimiter::global`]，避免多个工作线程叠加突发流量。
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+
+/// 未给出 `Retry-After` 的 429 响应暂停主机的时间
+const DEFAULT_THROTTLE_BACKOFF: Duration = Duration::from_secs(1);
+
+/// 单个上游主机的限速
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RateLimit {
+    /// 每秒允许的请求数
+    pub requests_per_second: f64,
+    /// 允许的突发请求数
+    #[serde(default = "default_burst")]
+    pub burst: u32,
+}
+
+fn default_burst() -> u32 {
+    1
+}
+
+/// 单个上游主机的限速指标
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HostRateLimitMetrics {
+    /// 主机（含端口）
+    pub host: String,
+    /// 生效的限速
+    pub limit: Option<RateLimit>,
+    /// 放行的请求数
+    pub requests: u64,
+    /// 需要排队等待的请求数
+    pub queued_requests: u64,
+    /// 累计排队时间（毫秒）
+    pub total_wait_ms: u64,
+    /// 最长单次排队时间（毫秒）
+    pub max_wait_ms: u64,
+    /// 收到的 429 响应数
+    pub throttled_responses: u64,
+    /// 当前可用令牌数
+    pub available_tokens: f64,
+    /// 主机剩余暂停时间（毫秒）
+    pub blocked_for_ms: u64,
+}
+
+#[derive(Debug)]
+struct HostState {
+    limit: Option<RateLimit>,
+    tokens: f64,
+    last_refill: Instant,
+    blocked_until: Option<Instant>,
+    requests: u64,
+    queued_requests: u64,
+    total_wait: Duration,
+    max_wait: Duration,
+    throttled_responses: u64,
+}
+
+impl HostState {
+    fn new(limit: Option<RateLimit>) -> Self {
+        Self {
+            limit,
+            tokens: limit.map_or(0.0, |limit| limit.burst.max(1) as f64),
+            last_refill: Instant::now(),
+            blocked_until: None,
+            requests: 0,
+            queued_requests: 0,
+            total_wait: Duration::ZERO,
+            max_wait: Duration::ZERO,
+            throttled_responses: 0,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        if let Some(limit) = self.limit {
+            let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+            self.tokens = (self.tokens + elapsed * limit.requests_per_second).min(limit.burst.max(1) as f64);
+        }
+        self.last_refill = now;
+    }
+
+    fn block_for(&mut self, now: Instant, duration: Duration) {
+        let until = now + duration;
+        if self.blocked_until.is_none_or(|blocked| blocked < until) {
+            self.blocked_until = Some(until);
+        }
+    }
+}
+
+/// 按上游主机排队的限速器
+#[derive(Debug, Default)]
+pub struct UpstreamRateLimiter {
+    default_limit: Option<RateLimit>,
+    hosts: Mutex<HashMap<String, HostState>>,
+}
+
+impl UpstreamRateLimiter {
+    /// 创建限速器；未配置限速的主机只在收到限流响应时暂停
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 进程内共享的限速器
+    pub fn global() -> Arc<Self> {
+        static GLOBAL: OnceLock<Arc<UpstreamRateLimiter>> = OnceLock::new();
+        GLOBAL.get_or_init(|| Arc::new(Self::new())).clone()
+    }
+
+    /// 设置未单独配置的主机使用的限速
+    pub fn with_default_limit(mut self, limit: RateLimit) -> Self {
+        self.default_limit = Some(limit);
+        self
+    }
+
+    /// 设置主机的限速，例如来自 API 文档声明的配额
+    pub fn set_limit(&self, host: &str, limit: RateLimit) {
+        let mut hosts = self.hosts.lock().unwrap();
+        let state = hosts.entry(host.to_string()).or_insert_with(|| HostState::new(Some(limit)));
+        state.refill(Instant::now());
+        // 之前不限速的主机从满桶开始
+        let burst = limit.burst.max(1) as f64;
+        state.tokens = if state.limit.is_some() { state.tokens.min(burst) } else { burst };
+        state.limit = Some(limit);
+    }
+
+    /// 取得主机的请求许可，必要时排队等待
+    ///
+    /// # Returns
+    /// 排队等待的时间
+    pub async fn acquire(&self, host: &str) -> Duration {
+        let wait = {
+            let mut hosts = self.hosts.lock().unwrap();
+            let default_limit = self.default_limit;
+            let state = hosts.entry(host.to_string()).or_insert_with(|| HostState::new(default_limit));
+            let now = Instant::now();
+            state.refill(now);
+
+            let blocked = state.blocked_until
+                .map_or(Duration::ZERO, |until| until.saturating_duration_since(now));
+            // 令牌可以透支，透支部分按速率排队，保证等待的请求按到达顺序放行
+            let paced = match state.limit {
+                Some(limit) => {
+                    state.tokens -= 1.0;
+                    if state.tokens < 0.0 {
+                        Duration::from_secs_f64(-state.tokens / limit.requests_per_second.max(f64::MIN_POSITIVE))
+                    } else {
+                        Duration::ZERO
+                    }
+                }
+                None => Duration::ZERO,
+            };
+            let wait = blocked.max(paced);
+
+            state.requests += 1;
+            if !wait.is_zero() {
+                state.queued_requests += 1;
+                state.total_wait += wait;
+                state.max_wait = state.max_wait.max(wait);
+            }
+            wait
+        };
+
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+        wait
+    }
+
+    /// 根据响应状态和限流头调整主机的节奏
+    ///
+    /// # Returns
+    /// 响应要求的暂停时间；429 没有 `Retry-After` 时使用默认退避
+    pub fn record_response(&self, host: &str, status: u16, headers: &HashMap<String, String>) -> Option<Duration> {
+        let header = |name: &str| {
+            headers.iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.trim())
+        };
+
+        let backoff = if status == 429 {
+            Some(header("retry-after").and_then(parse_retry_after).unwrap_or(DEFAULT_THROTTLE_BACKOFF))
+        } else if header("x-ratelimit-remaining") == Some("0") {
+            header("x-ratelimit-reset").and_then(parse_rate_limit_reset)
+        } else {
+            None
+        };
+
+        let mut hosts = self.hosts.lock().unwrap();
+        let default_limit = self.default_limit;
+        let state = hosts.entry(host.to_string()).or_insert_with(|| HostState::new(default_limit));
+        if status == 429 {
+            state.throttled_responses += 1;
+        }
+        if let Some(backoff) = backoff {
+            state.block_for(Instant::now(), backoff);
+        }
+        backoff
+    }
+
+    /// 所有主机的限速指标，按主机名排序
+    pub fn metrics(&self) -> Vec<HostRateLimitMetrics> {
+        let now = Instant::now();
+        let mut hosts = self.hosts.lock().unwrap();
+        let mut metrics: Vec<HostRateLimitMetrics> = hosts
+            .iter_mut()
+            .map(|(host, state)| {
+                state.refill(now);
+                HostRateLimitMetrics {
+                    host: host.clone(),
+                    limit: state.limit,
+                    requests: state.requests,
+                    queued_requests: state.queued_requests,
+                    total_wait_ms: state.total_wait.as_millis() as u64,
+                    max_wait_ms: state.max_wait.as_millis() as u64,
+                    throttled_responses: state.throttled_responses,
+                    available_tokens: state.tokens.max(0.0),
+                    blocked_for_ms: state.blocked_until
+                        .map_or(0, |until| until.saturating_duration_since(now).as_millis() as u64),
+                }
+            })
+            .collect();
+        metrics.sort_by(|a, b| a.host.cmp(&b.host));
+        metrics
+    }
+}
+
+/// 从 URL 中取出限速使用的主机键（含端口）
+pub fn rate_limit_host(url: &str) -> String {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|url| {
+            let host = url.host_str()?.to_string();
+            Some(match url.port_or_known_default() {
+                Some(port) => format!("{}:{}", host, port),
+                None => host,
+            })
+        })
+        .unwrap_or_else(|| url.to_string())
+}
+
+/// 解析 `Retry-After`：秒数或 HTTP 日期
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let delay = date.with_timezone(&chrono::Utc) - chrono::Utc::now();
+    Some(delay.to_std().unwrap_or(Duration::ZERO))
+}
+
+/// 解析 `X-RateLimit-Reset`：剩余秒数或 Unix 时间戳
+fn parse_rate_limit_reset(value: &str) -> Option<Duration> {
+    let reset = value.parse::<u64>().ok()?;
+    // 大于一年的值视为时间戳
+    if reset > 365 * 24 * 3600 {
+        let now = chrono::Utc::now().timestamp().max(0) as u64;
+        Some(Duration::from_secs(reset.saturating_sub(now)))
+    } else {
+        Some(Duration::from_secs(reset))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_token_bucket_paces_requests() {
+        let limiter = UpstreamRateLimiter::new();
+        limiter.set_limit("api:443", RateLimit { requests_per_second: 20.0, burst: 2 });
+
+        let start = Instant::now();
+        let waits = [
+            limiter.acquire("api:443").await,
+            limiter.acquire("api:443").await,
+            limiter.acquire("api:443").await,
+        ];
+        assert!(waits[0].is_zero() && waits[1].is_zero());
+        assert!(waits[2] >= Duration::from_millis(40));
+        assert!(start.elapsed() >= Duration::from_millis(40));
+
+        // 其他主机不受影响
+        assert!(limiter.acquire("other:443").await.is_zero());
+
+        let metrics = limiter.metrics();
+        assert_eq!(metrics[0].host, "api:443");
+        assert_eq!(metrics[0].requests, 3);
+        assert_eq!(metrics[0].queued_requests, 1);
+        assert!(metrics[0].total_wait_ms >= 40);
+        assert_eq!(metrics[1].requests, 1);
+    }
+
+    #[tokio::test]
+    async fn test_throttled_response_blocks_host() {
+        let limiter = UpstreamRateLimiter::new();
+        let headers = HashMap::from([("Retry-After".to_string(), "0".to_string())]);
+        assert_eq!(limiter.record_response("api:80", 429, &headers), Some(Duration::ZERO));
+        assert_eq!(limiter.record_response("api:80", 429, &HashMap::new()), Some(DEFAULT_THROTTLE_BACKOFF));
+
+        let metrics = limiter.metrics();
+        assert_eq!(metrics[0].throttled_responses, 2);
+        assert!(metrics[0].blocked_for_ms > 0);
+
+        let exhausted = HashMap::from([
+            ("x-ratelimit-remaining".to_string(), "0".to_string()),
+            ("x-ratelimit-reset".to_string(), "30".to_string()),
+        ]);
+        assert_eq!(limiter.record_response("b:80", 200, &exhausted), Some(Duration::from_secs(30)));
+        assert_eq!(limiter.record_response("c:80", 200, &HashMap::new()), None);
+    }
+
+    #[test]
+    fn test_parse_headers() {
+        assert_eq!(parse_retry_after("120"), Some(Duration::from_secs(120)));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("soon"), None);
+        let reset = (chrono::Utc::now().timestamp() + 60).to_string();
+        assert!(parse_rate_limit_reset(&reset).unwrap() > Duration::from_secs(50));
+        assert_eq!(rate_limit_host("https://api.example.com/v1/pets"), "api.example.com:443");
+        assert_eq!(rate_limit_host("http://127.0.0.1:8080"), "127.0.0.1:8080");
+    }
+}
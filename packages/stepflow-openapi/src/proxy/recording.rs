@@ -0,0 +1,335 @@
+//! 上游交互录制与回放
+//!
+//! 录制模式下 [`HttpApiProxy`](super::HttpApiProxy) 照常请求上游，并把脱敏后的
+//! 请求和响应记录下来：敏感的请求头、查询参数以及 JSON 请求体和响应体中凭据类键
+//! （与运行报告使用同一份键列表）的值都会被替换；回放模式下不访问网络，按方法、路径、查询参数和请求体
+//! 匹配已录制的交互并返回其响应。每条交互只回放一次，按录制顺序匹配，
+//! 因此同一请求多次调用（如轮询、分页）会依次得到当时录制的响应。
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use stepflow_core::redact_sensitive;
+use super::converter::{HttpRequest, HttpResponse};
+use super::error::{ProxyError, ProxyResult};
+
+/// 替换敏感值的占位符
+pub const REDACTED: &str = "[REDACTED]";
+
+/// 默认脱敏的请求头和响应头（小写）
+const SENSITIVE_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+    "x-api-key",
+    "api-key",
+];
+
+/// 默认脱敏的查询参数（小写）
+const SENSITIVE_QUERY_PARAMS: &[&str] = &["api_key", "apikey", "access_token", "token", "key", "signature"];
+
+/// 录制的请求
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    /// HTTP 方法（大写）
+    pub method: String,
+    /// 请求路径
+    pub path: String,
+    /// 查询参数，按名称排序
+    #[serde(default)]
+    pub query: BTreeMap<String, String>,
+    /// 请求头，按名称排序，名称小写
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// JSON 请求体
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<Value>,
+}
+
+impl RecordedRequest {
+    /// 回放时是否与 `other` 视为同一请求，不比较请求头
+    pub fn matches(&self, other: &RecordedRequest) -> bool {
+        self.method == other.method && self.path == other.path && self.query == other.query && self.body == other.body
+    }
+}
+
+/// 录制的响应
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    /// 状态码
+    pub status: u16,
+    /// 响应头，按名称排序，名称小写
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// 解码后的响应体
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<Value>,
+}
+
+/// 一次上游交互
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedInteraction {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+/// 录制器工作模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordingMode {
+    /// 请求上游并记录交互
+    Record,
+    /// 只从录制的交互返回响应
+    Replay,
+}
+
+#[derive(Debug)]
+struct RecorderState {
+    mode: RecordingMode,
+    interactions: Vec<RecordedInteraction>,
+    /// 回放模式下已使用的交互
+    used: HashSet<usize>,
+    /// 回放模式下没有匹配交互的请求
+    unmatched: Vec<RecordedRequest>,
+}
+
+/// 交互录制器
+///
+/// 通过 [`HttpApiProxy::with_recorder`](super::HttpApiProxy::with_recorder) 挂到客户端上，
+/// 模式可随时切换，切换时清空回放进度。
+#[derive(Debug)]
+pub struct InteractionRecorder {
+    state: Mutex<RecorderState>,
+    /// 额外脱敏的请求头（小写），如工具 API Key 认证使用的头
+    redact_headers: HashSet<String>,
+}
+
+impl InteractionRecorder {
+    /// 创建录制模式的录制器
+    pub fn recording() -> Self {
+        Self::new(RecordingMode::Record, Vec::new())
+    }
+
+    /// 创建回放 `interactions` 的录制器
+    pub fn replaying(interactions: Vec<RecordedInteraction>) -> Self {
+        Self::new(RecordingMode::Replay, interactions)
+    }
+
+    fn new(mode: RecordingMode, interactions: Vec<RecordedInteraction>) -> Self {
+        Self {
+            state: Mutex::new(RecorderState {
+                mode,
+                interactions,
+                used: HashSet::new(),
+                unmatched: Vec::new(),
+            }),
+            redact_headers: HashSet::new(),
+        }
+    }
+
+    /// 额外脱敏一个请求头
+    pub fn redact_header(mut self, name: &str) -> Self {
+        self.redact_headers.insert(name.to_ascii_lowercase());
+        self
+    }
+
+    /// 当前模式
+    pub fn mode(&self) -> RecordingMode {
+        self.lock().mode
+    }
+
+    /// 切换到录制模式并清空已录制的交互
+    pub fn start_recording(&self) {
+        let mut state = self.lock();
+        state.mode = RecordingMode::Record;
+        state.interactions.clear();
+        state.used.clear();
+        state.unmatched.clear();
+    }
+
+    /// 切换到回放模式，回放 `interactions`
+    pub fn start_replay(&self, interactions: Vec<RecordedInteraction>) {
+        let mut state = self.lock();
+        state.mode = RecordingMode::Replay;
+        state.interactions = interactions;
+        state.used.clear();
+        state.unmatched.clear();
+    }
+
+    /// 已录制（或正在回放）的交互
+    pub fn interactions(&self) -> Vec<RecordedInteraction> {
+        self.lock().interactions.clone()
+    }
+
+    /// 回放中没有匹配交互的请求
+    pub fn unmatched_requests(&self) -> Vec<RecordedRequest> {
+        self.lock().unmatched.clone()
+    }
+
+    /// 回放中未被使用的交互
+    pub fn unused_interactions(&self) -> Vec<RecordedInteraction> {
+        let state = self.lock();
+        state.interactions.iter()
+            .enumerate()
+            .filter(|(index, _)| !state.used.contains(index))
+            .map(|(_, interaction)| interaction.clone())
+            .collect()
+    }
+
+    /// 脱敏后的请求
+    pub fn sanitize_request(&self, request: &HttpRequest) -> RecordedRequest {
+        RecordedRequest {
+            method: request.method.to_uppercase(),
+            path: request.path.clone(),
+            query: request.query_params.iter()
+                .map(|(name, value)| {
+                    let sensitive = SENSITIVE_QUERY_PARAMS.contains(&name.to_ascii_lowercase().as_str());
+                    (name.clone(), if sensitive { REDACTED.to_string() } else { value.clone() })
+                })
+                .collect(),
+            headers: self.sanitize_headers(&request.headers),
+            body: request.body.as_ref().map(|body| redact_sensitive(body, REDACTED)),
+        }
+    }
+
+    fn sanitize_headers(&self, headers: &HashMap<String, String>) -> BTreeMap<String, String> {
+        headers.iter()
+            .map(|(name, value)| {
+                let name = name.to_ascii_lowercase();
+                let sensitive = SENSITIVE_HEADERS.contains(&name.as_str()) || self.redact_headers.contains(&name);
+                let value = if sensitive { REDACTED.to_string() } else { value.clone() };
+                (name, value)
+            })
+            .collect()
+    }
+
+    /// 记录一次交互（仅录制模式）
+    pub fn record(&self, request: &HttpRequest, response: &HttpResponse) {
+        let interaction = RecordedInteraction {
+            request: self.sanitize_request(request),
+            response: RecordedResponse {
+                status: response.status,
+                headers: self.sanitize_headers(&response.headers),
+                body: response.body.as_ref().map(|body| redact_sensitive(body, REDACTED)),
+            },
+        };
+        let mut state = self.lock();
+        if state.mode == RecordingMode::Record {
+            state.interactions.push(interaction);
+        }
+    }
+
+    /// 回放请求对应的响应
+    ///
+    /// 没有可用的匹配交互时记下该请求并返回错误。
+    pub fn replay(&self, request: &HttpRequest) -> ProxyResult<HttpResponse> {
+        let recorded = self.sanitize_request(request);
+        let mut state = self.lock();
+        let found = state.interactions.iter()
+            .enumerate()
+            .position(|(index, interaction)| !state.used.contains(&index) && interaction.request.matches(&recorded));
+        let Some(index) = found else {
+            let message = format!("No recorded interaction for {} {}", recorded.method, recorded.path);
+            state.unmatched.push(recorded);
+            return Err(ProxyError::HttpRequestError(message));
+        };
+
+        state.used.insert(index);
+        let response = &state.interactions[index].response;
+        Ok(HttpResponse {
+            status: response.status,
+            headers: response.headers.clone().into_iter().collect(),
+            body: response.body.clone(),
+            queue_wait_ms: 0,
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, RecorderState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(path: &str) -> HttpRequest {
+        HttpRequest {
+            method: "get".to_string(),
+            path: path.to_string(),
+            query_params: HashMap::from([
+                ("limit".to_string(), "10".to_string()),
+                ("api_key".to_string(), "secret".to_string()),
+            ]),
+            path_params: HashMap::new(),
+            headers: HashMap::from([
+                ("Authorization".to_string(), "Bearer secret".to_string()),
+                ("X-Tenant".to_string(), "acme".to_string()),
+            ]),
+            body: None,
+            multipart: None,
+        }
+    }
+
+    fn response(body: Value) -> HttpResponse {
+        HttpResponse {
+            status: 200,
+            headers: HashMap::from([("Set-Cookie".to_string(), "session=1".to_string())]),
+            body: Some(body),
+            queue_wait_ms: 0,
+        }
+    }
+
+    #[test]
+    fn test_record_sanitizes_secrets() {
+        let recorder = InteractionRecorder::recording().redact_header("X-Tenant");
+        recorder.record(&request("/users"), &response(serde_json::json!({"id": 1})));
+
+        let interaction = &recorder.interactions()[0];
+        assert_eq!(interaction.request.method, "GET");
+        assert_eq!(interaction.request.query["api_key"], REDACTED);
+        assert_eq!(interaction.request.query["limit"], "10");
+        assert_eq!(interaction.request.headers["authorization"], REDACTED);
+        assert_eq!(interaction.request.headers["x-tenant"], REDACTED);
+        assert_eq!(interaction.response.headers["set-cookie"], REDACTED);
+    }
+
+    #[test]
+    fn test_record_redacts_body_credentials() {
+        let recorder = InteractionRecorder::recording();
+        let mut token_request = request("/oauth/token");
+        token_request.body = Some(serde_json::json!({"grant_type": "password", "username": "alice", "password": "hunter2"}));
+        recorder.record(&token_request, &response(serde_json::json!({
+            "access_token": "eyJ...",
+            "token_type": "bearer",
+            "expires_in": 3600,
+            "scope": {"client_secret": "s3cret"}
+        })));
+
+        let interaction = &recorder.interactions()[0];
+        let body = interaction.request.body.as_ref().unwrap();
+        assert_eq!(body["password"], REDACTED);
+        assert_eq!(body["username"], "alice");
+        let body = interaction.response.body.as_ref().unwrap();
+        assert_eq!(body["access_token"], REDACTED);
+        assert_eq!(body["scope"]["client_secret"], REDACTED);
+        assert_eq!(body["expires_in"], 3600);
+    }
+
+    #[test]
+    fn test_replay_in_recorded_order() {
+        let recorder = InteractionRecorder::recording();
+        recorder.record(&request("/jobs/1"), &response(serde_json::json!({"state": "running"})));
+        recorder.record(&request("/jobs/1"), &response(serde_json::json!({"state": "done"})));
+        recorder.start_replay(recorder.interactions());
+
+        assert_eq!(recorder.replay(&request("/jobs/1")).unwrap().body.unwrap()["state"], "running");
+        assert_eq!(recorder.replay(&request("/jobs/1")).unwrap().body.unwrap()["state"], "done");
+        assert!(recorder.replay(&request("/jobs/1")).is_err());
+        assert!(recorder.replay(&request("/jobs/2")).is_err());
+        assert_eq!(recorder.unmatched_requests().len(), 2);
+        assert!(recorder.unused_interactions().is_empty());
+    }
+}
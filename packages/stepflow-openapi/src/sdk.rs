@@ -0,0 +1,1296 @@
+//! Client SDK generation
+//!
+//! Emits typed client SDKs from an OpenAPI 3.0 document:
+//!
+//! - Rust: a crate with one struct per component schema and an async
+//!   `reqwest` client with one method per operation
+//! - TypeScript: a single module with one interface per component schema and
+//!   a `fetch` based client class
+//!
+//! Both clients carry helpers for the document's `http` bearer and `apiKey`
+//! header security schemes, and a sign-in helper when the document has a
+//! `login` operation returning an `access_token`. Responses served as
+//! `text/event-stream` get an additional streaming method that yields the
+//! server-sent events, so progress and log-follow endpoints can be consumed
+//! without hand-written parsing. Output is deterministic for a given document.
+
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::document::{DocumentManager, OperationInfo, ParameterInfo, ParameterLocation};
+
+/// Media type of server-sent event responses
+const EVENT_STREAM: &str = "text/event-stream";
+
+const JSON: &str = "application/json";
+
+/// SDK generation errors
+#[derive(Debug, Error)]
+pub enum SdkError {
+    #[error("Invalid document: {0}")]
+    InvalidDocument(String),
+
+    #[error("Unsupported language: {0}")]
+    UnsupportedLanguage(String),
+}
+
+/// Target language of a generated SDK
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SdkLanguage {
+    Rust,
+    TypeScript,
+}
+
+impl FromStr for SdkLanguage {
+    type Err = SdkError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "rust" | "rs" => Ok(SdkLanguage::Rust),
+            "typescript" | "ts" => Ok(SdkLanguage::TypeScript),
+            _ => Err(SdkError::UnsupportedLanguage(value.to_string())),
+        }
+    }
+}
+
+/// SDK generation options
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SdkOptions {
+    pub language: SdkLanguage,
+    /// Crate or npm package name
+    pub package_name: String,
+    /// Package version; defaults to the document's `info.version`
+    pub package_version: Option<String>,
+}
+
+impl SdkOptions {
+    pub fn new(language: SdkLanguage, package_name: impl Into<String>) -> Self {
+        Self {
+            language,
+            package_name: package_name.into(),
+            package_version: None,
+        }
+    }
+}
+
+/// File of a generated SDK, with a path relative to the output directory
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SdkFile {
+    pub path: String,
+    pub contents: String,
+}
+
+/// Generates client SDKs from OpenAPI documents
+pub struct SdkGenerator {
+    options: SdkOptions,
+}
+
+impl SdkGenerator {
+    pub fn new(options: SdkOptions) -> Self {
+        Self { options }
+    }
+
+    /// Generate the SDK files for a parsed OpenAPI document
+    pub fn generate(&self, document: &Value) -> Result<Vec<SdkFile>, SdkError> {
+        let model = ApiModel::from_document(document)?;
+        let version = self.options.package_version.clone().unwrap_or_else(|| model.version.clone());
+        Ok(match self.options.language {
+            SdkLanguage::Rust => rust::generate(&model, &self.options.package_name, &version),
+            SdkLanguage::TypeScript => typescript::generate(&model, &self.options.package_name, &version),
+        })
+    }
+}
+
+/// Type of a field, parameter or body
+#[derive(Debug, Clone, PartialEq)]
+enum TypeRef {
+    String,
+    Integer,
+    Number,
+    Boolean,
+    Array(Box<TypeRef>),
+    Map(Box<TypeRef>),
+    Named(String),
+    Any,
+}
+
+impl TypeRef {
+    fn from_schema(schema: &Value) -> Self {
+        if let Some(reference) = schema.get("$ref").and_then(|v| v.as_str()) {
+            return TypeRef::Named(pascal_case(reference.rsplit('/').next().unwrap_or(reference)));
+        }
+        match schema.get("type").and_then(|v| v.as_str()) {
+            Some("string") => TypeRef::String,
+            Some("integer") => TypeRef::Integer,
+            Some("number") => TypeRef::Number,
+            Some("boolean") => TypeRef::Boolean,
+            Some("array") => TypeRef::Array(Box::new(schema.get("items").map(Self::from_schema).unwrap_or(TypeRef::Any))),
+            Some("object") if schema.get("properties").is_none() => match schema.get("additionalProperties") {
+                Some(values) if values.is_object() => TypeRef::Map(Box::new(Self::from_schema(values))),
+                _ => TypeRef::Map(Box::new(TypeRef::Any)),
+            },
+            _ => TypeRef::Any,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Field {
+    /// Name on the wire
+    name: String,
+    description: Option<String>,
+    ty: TypeRef,
+    required: bool,
+}
+
+#[derive(Debug, Clone)]
+enum TypeKind {
+    Struct(Vec<Field>),
+    Enum(Vec<String>),
+    Alias(TypeRef),
+}
+
+#[derive(Debug, Clone)]
+struct TypeDef {
+    name: String,
+    description: Option<String>,
+    kind: TypeKind,
+}
+
+impl TypeDef {
+    fn from_schema(name: &str, schema: &Value) -> Self {
+        let description = schema.get("description").and_then(|v| v.as_str()).map(String::from);
+        let variants: Option<Vec<String>> = schema
+            .get("enum")
+            .and_then(|v| v.as_array())
+            .and_then(|values| values.iter().map(|v| v.as_str().map(String::from)).collect());
+
+        let kind = if let Some(variants) = variants {
+            TypeKind::Enum(variants)
+        } else if let Some(properties) = schema.get("properties").and_then(|v| v.as_object()) {
+            TypeKind::Struct(object_fields(schema, properties))
+        } else {
+            TypeKind::Alias(TypeRef::from_schema(schema))
+        };
+        Self { name: pascal_case(name), description, kind }
+    }
+
+    fn field(&self, name: &str) -> Option<&Field> {
+        match &self.kind {
+            TypeKind::Struct(fields) => fields.iter().find(|field| field.name == name),
+            _ => None,
+        }
+    }
+}
+
+fn object_fields(schema: &Value, properties: &serde_json::Map<String, Value>) -> Vec<Field> {
+    let required: HashSet<&str> = schema
+        .get("required")
+        .and_then(|v| v.as_array())
+        .map(|names| names.iter().filter_map(|v| v.as_str()).collect())
+        .unwrap_or_default();
+    properties
+        .iter()
+        .map(|(name, property)| Field {
+            name: name.clone(),
+            description: property.get("description").and_then(|v| v.as_str()).map(String::from),
+            ty: TypeRef::from_schema(property),
+            required: required.contains(name.as_str())
+                && !property.get("nullable").and_then(|v| v.as_bool()).unwrap_or(false),
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+struct Operation {
+    /// snake_case method name
+    name: String,
+    method: String,
+    path: String,
+    summary: Option<String>,
+    path_params: Vec<String>,
+    query: Option<(String, Vec<Field>)>,
+    body: Option<(TypeRef, bool)>,
+    /// JSON response type, `None` when the operation returns no JSON body
+    response: Option<TypeRef>,
+    streaming: bool,
+}
+
+impl Operation {
+    fn from_info(info: &OperationInfo) -> Self {
+        let name = snake_case(&info.operation_id);
+        let params = |location: ParameterLocation| -> Vec<&ParameterInfo> {
+            info.parameters.iter().filter(|p| p.location == location).collect()
+        };
+        let query_fields: Vec<Field> = params(ParameterLocation::Query)
+            .into_iter()
+            .map(|p| Field {
+                name: p.name.clone(),
+                description: p.description.clone(),
+                ty: TypeRef::from_schema(&p.schema),
+                required: false,
+            })
+            .collect();
+        let body = info.request_body.as_ref().and_then(|body| {
+            let schema = body.content.get(JSON)?.schema.as_ref()?;
+            Some((TypeRef::from_schema(schema), body.required))
+        });
+
+        let mut statuses: Vec<&String> = info.responses.keys().filter(|status| status.starts_with('2')).collect();
+        statuses.sort();
+        let content = |media: &str| {
+            statuses.iter().find_map(|status| info.responses[*status].content.as_ref()?.get(media))
+        };
+        let response = content(JSON).map(|media| media.schema.as_ref().map(TypeRef::from_schema).unwrap_or(TypeRef::Any));
+
+        Self {
+            query: (!query_fields.is_empty()).then(|| (format!("{}Query", pascal_case(&name)), query_fields)),
+            path_params: params(ParameterLocation::Path).iter().map(|p| p.name.clone()).collect(),
+            method: info.method.clone(),
+            path: info.path.clone(),
+            summary: info.summary.clone().or_else(|| info.description.clone()),
+            body,
+            response,
+            streaming: content(EVENT_STREAM).is_some(),
+            name,
+        }
+    }
+
+    /// Path split into literal segments and parameter names
+    fn path_parts(&self) -> Vec<PathPart> {
+        let mut parts = Vec::new();
+        let mut rest = self.path.as_str();
+        while let Some(start) = rest.find('{') {
+            let Some(end) = rest[start..].find('}') else { break };
+            parts.push(PathPart::Literal(rest[..start].to_string()));
+            parts.push(PathPart::Param(rest[start + 1..start + end].to_string()));
+            rest = &rest[start + end + 1..];
+        }
+        parts.push(PathPart::Literal(rest.to_string()));
+        parts
+    }
+}
+
+enum PathPart {
+    Literal(String),
+    Param(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum AuthScheme {
+    Bearer,
+    ApiKey { header: String },
+}
+
+/// Language-neutral view of the document
+struct ApiModel {
+    title: String,
+    version: String,
+    types: Vec<TypeDef>,
+    operations: Vec<Operation>,
+    auth: Vec<AuthScheme>,
+}
+
+impl ApiModel {
+    fn from_document(document: &Value) -> Result<Self, SdkError> {
+        let info = document.get("info").ok_or_else(|| SdkError::InvalidDocument("Missing 'info' section".to_string()))?;
+        let paths = document
+            .get("paths")
+            .and_then(|v| v.as_object())
+            .ok_or_else(|| SdkError::InvalidDocument("Missing 'paths' section".to_string()))?;
+
+        let mut operations = Vec::new();
+        for (path, path_item) in paths {
+            let infos = DocumentManager::extract_path_item_operations(path, path_item, "sdk", "client")
+                .map_err(|e| SdkError::InvalidDocument(e.to_string()))?;
+            operations.extend(infos.iter().map(Operation::from_info));
+        }
+        operations.sort_by(|a, b| a.name.cmp(&b.name));
+        if let Some(pair) = operations.windows(2).find(|pair| pair[0].name == pair[1].name) {
+            return Err(SdkError::InvalidDocument(format!("Duplicate operation name: {}", pair[0].name)));
+        }
+
+        let components = document.get("components");
+        let types = components
+            .and_then(|c| c.get("schemas"))
+            .and_then(|v| v.as_object())
+            .map(|schemas| schemas.iter().map(|(name, schema)| TypeDef::from_schema(name, schema)).collect())
+            .unwrap_or_default();
+        let auth = components
+            .and_then(|c| c.get("securitySchemes"))
+            .and_then(|v| v.as_object())
+            .map(|schemes| {
+                schemes
+                    .values()
+                    .filter_map(|scheme| match scheme.get("type").and_then(|v| v.as_str()) {
+                        Some("http") if scheme.get("scheme").and_then(|v| v.as_str()) == Some("bearer") => {
+                            Some(AuthScheme::Bearer)
+                        }
+                        Some("apiKey") if scheme.get("in").and_then(|v| v.as_str()) == Some("header") => {
+                            scheme.get("name").and_then(|v| v.as_str()).map(|name| AuthScheme::ApiKey { header: name.to_string() })
+                        }
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(Self {
+            title: info.get("title").and_then(|v| v.as_str()).unwrap_or("API").to_string(),
+            version: info.get("version").and_then(|v| v.as_str()).unwrap_or("0.1.0").to_string(),
+            types,
+            operations,
+            auth,
+        })
+    }
+
+    fn type_def(&self, ty: &TypeRef) -> Option<&TypeDef> {
+        match ty {
+            TypeRef::Named(name) => self.types.iter().find(|def| &def.name == name),
+            _ => None,
+        }
+    }
+
+    /// Whether `login` returns an `access_token` that can authenticate later requests
+    fn has_sign_in(&self) -> bool {
+        self.auth.contains(&AuthScheme::Bearer)
+            && self.operations.iter().any(|op| {
+                op.name == "login"
+                    && op.body.is_some()
+                    && op
+                        .response
+                        .as_ref()
+                        .and_then(|ty| self.type_def(ty))
+                        .and_then(|def| def.field("access_token"))
+                        .is_some_and(|field| field.required && field.ty == TypeRef::String)
+            })
+    }
+
+    fn api_key_header(&self) -> Option<&str> {
+        self.auth.iter().find_map(|scheme| match scheme {
+            AuthScheme::ApiKey { header } => Some(header.as_str()),
+            AuthScheme::Bearer => None,
+        })
+    }
+
+    /// Query parameter structs of all operations
+    fn query_types(&self) -> Vec<TypeDef> {
+        self.operations
+            .iter()
+            .filter_map(|op| op.query.clone())
+            .map(|(name, fields)| TypeDef { name, description: None, kind: TypeKind::Struct(fields) })
+            .collect()
+    }
+}
+
+fn words(value: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut previous: Option<char> = None;
+    for c in value.chars() {
+        if !c.is_ascii_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+        } else {
+            let boundary = c.is_ascii_uppercase() && previous.is_some_and(|p| p.is_ascii_lowercase() || p.is_ascii_digit());
+            if boundary && !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            current.push(c.to_ascii_lowercase());
+        }
+        previous = Some(c);
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+fn snake_case(value: &str) -> String {
+    words(value).join("_")
+}
+
+fn pascal_case(value: &str) -> String {
+    words(value)
+        .iter()
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map(|first| first.to_ascii_uppercase().to_string() + chars.as_str()).unwrap_or_default()
+        })
+        .collect()
+}
+
+fn camel_case(value: &str) -> String {
+    let pascal = pascal_case(value);
+    let mut chars = pascal.chars();
+    chars.next().map(|first| first.to_ascii_lowercase().to_string() + chars.as_str()).unwrap_or_default()
+}
+
+/// Write a doc comment, one comment line per text line
+fn doc_lines(out: &mut String, indent: &str, marker: &str, text: &Option<String>) {
+    if let Some(text) = text {
+        for line in text.lines() {
+            let _ = writeln!(out, "{}{} {}", indent, marker, line.trim_end());
+        }
+    }
+}
+
+mod rust {
+    use super::*;
+
+    const KEYWORDS: &[&str] = &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern", "false", "fn",
+        "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self",
+        "static", "struct", "super", "trait", "true", "type", "unsafe", "use", "where", "while",
+    ];
+
+    fn ident(name: &str) -> String {
+        let name = snake_case(name);
+        let name = if name.starts_with(|c: char| c.is_ascii_digit()) { format!("_{}", name) } else { name };
+        if KEYWORDS.contains(&name.as_str()) { format!("r#{}", name) } else { name }
+    }
+
+    fn type_name(ty: &TypeRef) -> String {
+        match ty {
+            TypeRef::String => "String".to_string(),
+            TypeRef::Integer => "i64".to_string(),
+            TypeRef::Number => "f64".to_string(),
+            TypeRef::Boolean => "bool".to_string(),
+            TypeRef::Array(item) => format!("Vec<{}>", type_name(item)),
+            TypeRef::Map(value) => format!("HashMap<String, {}>", type_name(value)),
+            TypeRef::Named(name) => name.clone(),
+            TypeRef::Any => "serde_json::Value".to_string(),
+        }
+    }
+
+    /// Argument type of a borrowed parameter
+    fn borrowed(ty: &TypeRef) -> String {
+        match ty {
+            TypeRef::String => "&str".to_string(),
+            other => format!("&{}", type_name(other)),
+        }
+    }
+
+    pub(super) fn generate(model: &ApiModel, package: &str, version: &str) -> Vec<SdkFile> {
+        vec![
+            SdkFile { path: "Cargo.toml".to_string(), contents: manifest(package, version) },
+            SdkFile { path: "src/lib.rs".to_string(), contents: library(model) },
+            SdkFile { path: "src/types.rs".to_string(), contents: types(model) },
+        ]
+    }
+
+    fn manifest(package: &str, version: &str) -> String {
+        format!(
+            r#"[package]
+name = "{package}"
+version = "{version}"
+edition = "2021"
+
+[dependencies]
+reqwest = {{ version = "0.12", features = ["json"] }}
+serde = {{ version = "1.0", features = ["derive"] }}
+serde_json = "1.0"
+"#
+        )
+    }
+
+    fn types(model: &ApiModel) -> String {
+        let mut out = String::new();
+        out.push_str("//! Request and response types\n//!\n//! Generated by stepflow-openapi; do not edit.\n\n");
+        out.push_str("#![allow(clippy::all)]\n\nuse std::collections::HashMap;\nuse serde::{Deserialize, Serialize};\n");
+        for def in model.types.iter().chain(model.query_types().iter()) {
+            out.push('\n');
+            type_def(&mut out, def);
+        }
+        out
+    }
+
+    fn type_def(out: &mut String, def: &TypeDef) {
+        doc_lines(out, "", "///", &def.description);
+        match &def.kind {
+            TypeKind::Struct(fields) => {
+                // Structs without required fields, such as query parameters, can start from `Default`
+                let default = if fields.iter().all(|field| !field.required) { "Default, " } else { "" };
+                let _ = writeln!(out, "#[derive(Debug, Clone, {}PartialEq, Serialize, Deserialize)]", default);
+                let _ = writeln!(out, "pub struct {} {{", def.name);
+                for field in fields {
+                    doc_lines(out, "    ", "///", &field.description);
+                    let name = ident(&field.name);
+                    if name.trim_start_matches("r#") != field.name {
+                        let _ = writeln!(out, "    #[serde(rename = \"{}\")]", field.name);
+                    }
+                    if field.required {
+                        let _ = writeln!(out, "    pub {}: {},", name, type_name(&field.ty));
+                    } else {
+                        let _ = writeln!(out, "    #[serde(default, skip_serializing_if = \"Option::is_none\")]");
+                        let _ = writeln!(out, "    pub {}: Option<{}>,", name, type_name(&field.ty));
+                    }
+                }
+                out.push_str("}\n");
+            }
+            TypeKind::Enum(variants) => {
+                let _ = writeln!(out, "#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]");
+                let _ = writeln!(out, "pub enum {} {{", def.name);
+                for variant in variants {
+                    let _ = writeln!(out, "    #[serde(rename = \"{}\")]", variant);
+                    let _ = writeln!(out, "    {},", pascal_case(variant));
+                }
+                out.push_str("}\n");
+            }
+            TypeKind::Alias(ty) => {
+                let _ = writeln!(out, "pub type {} = {};", def.name, type_name(ty));
+            }
+        }
+    }
+
+    fn library(model: &ApiModel) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "//! Client for {} {}", model.title, model.version);
+        out.push_str("//!\n//! Generated by stepflow-openapi; do not edit.\n\n");
+        out.push_str(RUNTIME);
+        out.push('\n');
+        out.push_str(&api_key_constant(model));
+
+        out.push_str("\nimpl Client {\n");
+        if model.auth.contains(&AuthScheme::Bearer) {
+            out.push_str(BEARER_HELPERS);
+        }
+        if let Some(header) = model.api_key_header() {
+            out.push_str(&API_KEY_HELPERS.replace("{header}", header));
+        }
+        if model.has_sign_in() {
+            out.push_str(SIGN_IN_HELPER);
+        }
+        for op in &model.operations {
+            operation(&mut out, op);
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    fn operation(out: &mut String, op: &Operation) {
+        let mut args = vec!["&self".to_string()];
+        args.extend(op.path_params.iter().map(|name| format!("{}: &str", ident(name))));
+        if let Some((ty, required)) = &op.body {
+            let arg = if *required { borrowed(ty) } else { format!("Option<{}>", borrowed(ty)) };
+            args.push(format!("body: {}", arg));
+        }
+        if let Some((query, _)) = &op.query {
+            args.push(format!("query: &{}", query));
+        }
+
+        let path = op.path_parts().iter().fold((String::new(), Vec::new()), |(mut template, mut values), part| {
+            match part {
+                PathPart::Literal(text) => template.push_str(&text.replace('{', "{{").replace('}', "}}")),
+                PathPart::Param(name) => {
+                    template.push_str("{}");
+                    values.push(format!("encode_path({})", ident(name)));
+                }
+            }
+            (template, values)
+        });
+        let path = if path.1.is_empty() {
+            format!("\"{}\"", path.0)
+        } else {
+            format!("&format!(\"{}\", {})", path.0, path.1.join(", "))
+        };
+
+        let optional_body = matches!(op.body, Some((_, false)));
+        let mut request = format!(
+            "        let {}builder = self.request(reqwest::Method::{}, {})",
+            if optional_body { "mut " } else { "" },
+            op.method.to_ascii_uppercase(),
+            path
+        );
+        if matches!(op.body, Some((_, true))) {
+            request.push_str(".json(body)");
+        }
+        if op.query.is_some() {
+            request.push_str(".query(query)");
+        }
+        request.push_str(";\n");
+        if optional_body {
+            request.push_str("        if let Some(body) = body {\n            builder = builder.json(body);\n        }\n");
+        }
+
+        let signature = args.join(", ");
+        if op.response.is_some() || !op.streaming {
+            out.push('\n');
+            doc_lines(out, "    ", "///", &op.summary);
+            let (output, send) = match &op.response {
+                Some(ty) => (type_name(ty), "Self::send_json(builder).await"),
+                None => ("()".to_string(), "Self::send(builder).await.map(|_| ())"),
+            };
+            let _ = writeln!(out, "    pub async fn {}({}) -> Result<{}> {{", op.name, signature, output);
+            out.push_str(&request);
+            let _ = writeln!(out, "        {}\n    }}", send);
+        }
+        if op.streaming {
+            out.push('\n');
+            doc_lines(out, "    ", "///", &op.summary);
+            out.push_str("    ///\n    /// Streams the server-sent events of the response.\n");
+            let _ = writeln!(out, "    pub async fn {}_stream({}) -> Result<EventStream> {{", op.name, signature);
+            out.push_str(&request);
+            out.push_str("        let builder = builder.header(reqwest::header::ACCEPT, \"text/event-stream\");\n");
+            out.push_str("        Ok(EventStream::new(Self::send(builder).await?))\n    }\n");
+        }
+    }
+
+    const RUNTIME: &str = r#"#![allow(clippy::all)]
+
+mod types;
+
+pub use types::*;
+
+use std::collections::VecDeque;
+use std::fmt;
+
+/// Error returned by client calls
+#[derive(Debug)]
+pub enum Error {
+    /// The request could not be sent or the response could not be read
+    Http(reqwest::Error),
+    /// The server answered with a non-success status
+    Status { status: u16, body: String },
+    /// The response body did not match the expected type
+    Decode(serde_json::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Http(e) => write!(f, "HTTP error: {}", e),
+            Error::Status { status, body } => write!(f, "Request failed with status {}: {}", status, body),
+            Error::Decode(e) => write!(f, "Invalid response body: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<reqwest::Error> for Error {
+    fn from(error: reqwest::Error) -> Self {
+        Error::Http(error)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Error::Decode(error)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Server-sent event of a streaming response
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ServerEvent {
+    pub event: Option<String>,
+    pub data: String,
+    pub id: Option<String>,
+}
+
+impl ServerEvent {
+    /// Decode the event data as JSON
+    pub fn json<T: serde::de::DeserializeOwned>(&self) -> Result<T> {
+        Ok(serde_json::from_str(&self.data)?)
+    }
+}
+
+/// Server-sent events read from a streaming response
+pub struct EventStream {
+    response: reqwest::Response,
+    buffer: Vec<u8>,
+    pending: VecDeque<ServerEvent>,
+    finished: bool,
+}
+
+impl EventStream {
+    fn new(response: reqwest::Response) -> Self {
+        Self { response, buffer: Vec::new(), pending: VecDeque::new(), finished: false }
+    }
+
+    /// Next event, or `None` once the server closes the stream; keep-alive comments are skipped
+    pub async fn next_event(&mut self) -> Result<Option<ServerEvent>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(Some(event));
+            }
+            if self.finished {
+                return Ok(None);
+            }
+            match self.response.chunk().await? {
+                Some(chunk) => self.buffer.extend(chunk.iter().filter(|b| **b != b'\r')),
+                None => {
+                    self.finished = true;
+                    self.buffer.extend_from_slice(b"\n\n");
+                }
+            }
+            self.parse_events();
+        }
+    }
+
+    fn parse_events(&mut self) {
+        while let Some(end) = self.buffer.windows(2).position(|w| w == b"\n\n") {
+            let block: Vec<u8> = self.buffer.drain(..end + 2).collect();
+            let mut event = ServerEvent::default();
+            let mut has_data = false;
+            for line in String::from_utf8_lossy(&block).lines() {
+                if line.is_empty() || line.starts_with(':') {
+                    continue;
+                }
+                let (field, value) = line.split_once(':').unwrap_or((line, ""));
+                let value = value.strip_prefix(' ').unwrap_or(value);
+                match field {
+                    "event" => event.event = Some(value.to_string()),
+                    "id" => event.id = Some(value.to_string()),
+                    "data" => {
+                        if has_data {
+                            event.data.push('\n');
+                        }
+                        event.data.push_str(value);
+                        has_data = true;
+                    }
+                    _ => {}
+                }
+            }
+            if has_data || event.event.is_some() {
+                self.pending.push_back(event);
+            }
+        }
+    }
+}
+
+/// Percent-encode a path parameter
+fn encode_path(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// API client
+#[derive(Debug, Clone)]
+pub struct Client {
+    base_url: String,
+    http: reqwest::Client,
+    bearer_token: Option<String>,
+    api_key: Option<String>,
+}
+
+impl Client {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self::with_http_client(base_url, reqwest::Client::new())
+    }
+
+    /// Create a client that sends requests through `http`
+    pub fn with_http_client(base_url: impl Into<String>, http: reqwest::Client) -> Self {
+        let base_url = base_url.into().trim_end_matches('/').to_string();
+        Self { base_url, http, bearer_token: None, api_key: None }
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let mut builder = self.http.request(method, format!("{}{}", self.base_url, path));
+        if let Some(token) = &self.bearer_token {
+            builder = builder.bearer_auth(token);
+        }
+        if let (Some(key), Some(header)) = (&self.api_key, API_KEY_HEADER) {
+            builder = builder.header(header, key);
+        }
+        builder
+    }
+
+    async fn send(builder: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        let response = builder.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(Error::Status { status: status.as_u16(), body });
+        }
+        Ok(response)
+    }
+
+    async fn send_json<T: serde::de::DeserializeOwned>(builder: reqwest::RequestBuilder) -> Result<T> {
+        let body = Self::send(builder).await?.bytes().await?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+}
+"#;
+
+    const BEARER_HELPERS: &str = r#"    /// Authenticate requests with a bearer token
+    pub fn with_bearer_token(mut self, token: impl Into<String>) -> Self {
+        self.bearer_token = Some(token.into());
+        self
+    }
+
+    /// Replace or clear the bearer token, e.g. after refreshing it
+    pub fn set_bearer_token(&mut self, token: Option<String>) {
+        self.bearer_token = token;
+    }
+"#;
+
+    const API_KEY_HELPERS: &str = r#"
+    /// Authenticate requests with an API key sent in the `{header}` header
+    pub fn with_api_key(mut self, key: impl Into<String>) -> Self {
+        self.api_key = Some(key.into());
+        self
+    }
+"#;
+
+    const SIGN_IN_HELPER: &str = r#"
+    /// Log in and authenticate later requests with the returned access token
+    pub async fn sign_in(&mut self, body: &LoginRequest) -> Result<LoginResponse> {
+        let response = self.login(body).await?;
+        self.bearer_token = Some(response.access_token.clone());
+        Ok(response)
+    }
+"#;
+
+    fn api_key_constant(model: &ApiModel) -> String {
+        match model.api_key_header() {
+            Some(header) => format!("const API_KEY_HEADER: Option<&str> = Some(\"{}\");\n", header),
+            None => "const API_KEY_HEADER: Option<&str> = None;\n".to_string(),
+        }
+    }
+}
+
+mod typescript {
+    use super::*;
+
+    fn type_name(ty: &TypeRef) -> String {
+        match ty {
+            TypeRef::String => "string".to_string(),
+            TypeRef::Integer | TypeRef::Number => "number".to_string(),
+            TypeRef::Boolean => "boolean".to_string(),
+            TypeRef::Array(item) => format!("{}[]", type_name(item)),
+            TypeRef::Map(value) => format!("Record<string, {}>", type_name(value)),
+            TypeRef::Named(name) => name.clone(),
+            TypeRef::Any => "unknown".to_string(),
+        }
+    }
+
+    fn property(name: &str) -> String {
+        let plain = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if plain { name.to_string() } else { format!("\"{}\"", name) }
+    }
+
+    pub(super) fn generate(model: &ApiModel, package: &str, version: &str) -> Vec<SdkFile> {
+        vec![
+            SdkFile { path: "package.json".to_string(), contents: manifest(package, version) },
+            SdkFile { path: "src/index.ts".to_string(), contents: module(model) },
+        ]
+    }
+
+    fn manifest(package: &str, version: &str) -> String {
+        let manifest = serde_json::json!({
+            "name": package,
+            "version": version,
+            "type": "module",
+            "main": "dist/index.js",
+            "types": "dist/index.d.ts",
+            "scripts": { "build": "tsc --declaration --outDir dist src/index.ts" },
+            "devDependencies": { "typescript": "^5.4.0" },
+        });
+        serde_json::to_string_pretty(&manifest).unwrap_or_default() + "\n"
+    }
+
+    fn module(model: &ApiModel) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "// Client for {} {}", model.title, model.version);
+        out.push_str("// Generated by stepflow-openapi; do not edit.\n");
+        for def in model.types.iter().chain(model.query_types().iter()) {
+            out.push('\n');
+            type_def(&mut out, def);
+        }
+        out.push('\n');
+        out.push_str(RUNTIME);
+        let _ = writeln!(
+            out,
+            "\nconst API_KEY_HEADER: string | undefined = {};",
+            model.api_key_header().map(|header| format!("\"{}\"", header)).unwrap_or_else(|| "undefined".to_string())
+        );
+
+        out.push_str(CLIENT_HEAD);
+        if model.has_sign_in() {
+            out.push_str(SIGN_IN_HELPER);
+        }
+        for op in &model.operations {
+            operation(&mut out, op);
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    fn type_def(out: &mut String, def: &TypeDef) {
+        if let Some(description) = &def.description {
+            let _ = writeln!(out, "/** {} */", description.lines().collect::<Vec<_>>().join(" "));
+        }
+        match &def.kind {
+            TypeKind::Struct(fields) => {
+                let _ = writeln!(out, "export interface {} {{", def.name);
+                for field in fields {
+                    if let Some(description) = &field.description {
+                        let _ = writeln!(out, "  /** {} */", description.lines().collect::<Vec<_>>().join(" "));
+                    }
+                    let optional = if field.required { "" } else { "?" };
+                    let _ = writeln!(out, "  {}{}: {};", property(&field.name), optional, type_name(&field.ty));
+                }
+                out.push_str("}\n");
+            }
+            TypeKind::Enum(variants) => {
+                let union: Vec<String> = variants.iter().map(|v| format!("\"{}\"", v)).collect();
+                let _ = writeln!(out, "export type {} = {};", def.name, union.join(" | "));
+            }
+            TypeKind::Alias(ty) => {
+                let _ = writeln!(out, "export type {} = {};", def.name, type_name(ty));
+            }
+        }
+    }
+
+    fn operation(out: &mut String, op: &Operation) {
+        let mut args: Vec<String> = op.path_params.iter().map(|name| format!("{}: string", camel_case(name))).collect();
+        if let Some((ty, required)) = &op.body {
+            args.push(format!("body{}: {}", if *required { "" } else { "?" }, type_name(ty)));
+        }
+        if let Some((query, _)) = &op.query {
+            args.push(format!("query: {} = {{}}", query));
+        }
+        let path: String = op
+            .path_parts()
+            .iter()
+            .map(|part| match part {
+                PathPart::Literal(text) => text.replace('`', "\\`").replace("${", "\\${"),
+                PathPart::Param(name) => format!("${{encodeURIComponent({})}}", camel_case(name)),
+            })
+            .collect();
+        let mut options = Vec::new();
+        if op.query.is_some() {
+            options.push("query");
+        }
+        if op.body.is_some() {
+            options.push("body");
+        }
+        let name = camel_case(&op.name);
+        let signature = args.join(", ");
+        let method = op.method.to_ascii_uppercase();
+
+        if op.response.is_some() || !op.streaming {
+            out.push('\n');
+            doc(out, &op.summary);
+            match &op.response {
+                Some(ty) => {
+                    let _ = writeln!(out, "  async {}({}): Promise<{}> {{", name, signature, type_name(ty));
+                    let _ = writeln!(out, "    return this.sendJson(\"{}\", `{}`{});", method, path, request_options(&options));
+                }
+                None => {
+                    let _ = writeln!(out, "  async {}({}): Promise<void> {{", name, signature);
+                    let _ = writeln!(out, "    await this.send(\"{}\", `{}`{});", method, path, request_options(&options));
+                }
+            }
+            out.push_str("  }\n");
+        }
+        if op.streaming {
+            out.push('\n');
+            doc(out, &op.summary.as_ref().map(|summary| format!("{} (server-sent events)", summary)));
+            options.push("accept: \"text/event-stream\"");
+            let _ = writeln!(out, "  async *{}Stream({}): AsyncGenerator<ServerEvent> {{", name, signature);
+            let _ = writeln!(out, "    const response = await this.send(\"{}\", `{}`{});", method, path, request_options(&options));
+            out.push_str("    yield* readEvents(response);\n  }\n");
+        }
+    }
+
+    /// Trailing options argument of a request call
+    fn request_options(options: &[&str]) -> String {
+        if options.is_empty() { String::new() } else { format!(", {{ {} }}", options.join(", ")) }
+    }
+
+    fn doc(out: &mut String, text: &Option<String>) {
+        if let Some(text) = text {
+            let _ = writeln!(out, "  /** {} */", text.lines().collect::<Vec<_>>().join(" "));
+        }
+    }
+
+    const RUNTIME: &str = r#"/** Error thrown when the server answers with a non-success status */
+export class ApiError extends Error {
+  constructor(readonly status: number, readonly body: string) {
+    super(`Request failed with status ${status}: ${body}`);
+  }
+}
+
+/** Server-sent event of a streaming response */
+export interface ServerEvent {
+  event?: string;
+  data: string;
+  id?: string;
+}
+
+/** Parse the server-sent events of a response; keep-alive comments are skipped */
+export async function* readEvents(response: Response): AsyncGenerator<ServerEvent> {
+  if (!response.body) return;
+  const reader = response.body.getReader();
+  const decoder = new TextDecoder();
+  let buffer = "";
+  for (;;) {
+    const { done, value } = await reader.read();
+    buffer += done ? "\n\n" : decoder.decode(value, { stream: true }).replace(/\r/g, "");
+    let end: number;
+    while ((end = buffer.indexOf("\n\n")) >= 0) {
+      const block = buffer.slice(0, end);
+      buffer = buffer.slice(end + 2);
+      const event: ServerEvent = { data: "" };
+      let hasData = false;
+      for (const line of block.split("\n")) {
+        if (line === "" || line.startsWith(":")) continue;
+        const colon = line.indexOf(":");
+        const field = colon >= 0 ? line.slice(0, colon) : line;
+        let value = colon >= 0 ? line.slice(colon + 1) : "";
+        if (value.startsWith(" ")) value = value.slice(1);
+        if (field === "event") event.event = value;
+        else if (field === "id") event.id = value;
+        else if (field === "data") {
+          event.data += hasData ? "\n" + value : value;
+          hasData = true;
+        }
+      }
+      if (hasData || event.event !== undefined) yield event;
+    }
+    if (done) return;
+  }
+}
+
+export interface ClientOptions {
+  baseUrl: string;
+  bearerToken?: string;
+  apiKey?: string;
+  fetch?: typeof fetch;
+}
+
+interface RequestOptions {
+  query?: object;
+  body?: unknown;
+  accept?: string;
+}
+"#;
+
+    const CLIENT_HEAD: &str = r#"
+/** API client */
+export class Client {
+  private readonly baseUrl: string;
+  private readonly fetchImpl: typeof fetch;
+  private bearerToken?: string;
+  private apiKey?: string;
+
+  constructor(options: ClientOptions) {
+    this.baseUrl = options.baseUrl.replace(/\/+$/, "");
+    this.fetchImpl = options.fetch ?? fetch;
+    this.bearerToken = options.bearerToken;
+    this.apiKey = options.apiKey;
+  }
+
+  /** Replace or clear the bearer token, e.g. after refreshing it */
+  setBearerToken(token: string | undefined): void {
+    this.bearerToken = token;
+  }
+
+  /** Replace or clear the API key */
+  setApiKey(key: string | undefined): void {
+    this.apiKey = key;
+  }
+
+  private async send(method: string, path: string, options: RequestOptions = {}): Promise<Response> {
+    const url = new URL(this.baseUrl + path);
+    for (const [key, value] of Object.entries(options.query ?? {})) {
+      if (value !== undefined && value !== null) url.searchParams.append(key, String(value));
+    }
+    const headers: Record<string, string> = {};
+    if (options.accept) headers["accept"] = options.accept;
+    if (options.body !== undefined) headers["content-type"] = "application/json";
+    if (this.bearerToken) headers["authorization"] = `Bearer ${this.bearerToken}`;
+    if (this.apiKey && API_KEY_HEADER) headers[API_KEY_HEADER] = this.apiKey;
+    const response = await this.fetchImpl(url, {
+      method,
+      headers,
+      body: options.body === undefined ? undefined : JSON.stringify(options.body),
+    });
+    if (!response.ok) throw new ApiError(response.status, await response.text());
+    return response;
+  }
+
+  private async sendJson<T>(method: string, path: string, options: RequestOptions = {}): Promise<T> {
+    const response = await this.send(method, path, options);
+    return (await response.json()) as T;
+  }
+"#;
+
+    const SIGN_IN_HELPER: &str = r#"
+  /** Log in and authenticate later requests with the returned access token */
+  async signIn(body: LoginRequest): Promise<LoginResponse> {
+    const response = await this.login(body);
+    this.bearerToken = response.access_token;
+    return response;
+  }
+"#;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn document() -> Value {
+        json!({
+            "openapi": "3.0.3",
+            "info": { "title": "Pets", "version": "1.2.0" },
+            "paths": {
+                "/auth/login": {
+                    "post": {
+                        "operationId": "login",
+                        "requestBody": { "required": true, "content": { "application/json": { "schema": { "$ref": "#/components/schemas/LoginRequest" } } } },
+                        "responses": { "200": { "description": "ok", "content": { "application/json": { "schema": { "$ref": "#/components/schemas/LoginResponse" } } } } }
+                    }
+                },
+                "/pets/{petId}": {
+                    "get": {
+                        "operationId": "getPet",
+                        "summary": "Get a pet",
+                        "parameters": [
+                            { "name": "petId", "in": "path", "required": true, "schema": { "type": "string" } },
+                            { "name": "fields", "in": "query", "schema": { "type": "array", "items": { "type": "string" } } }
+                        ],
+                        "responses": { "200": { "description": "ok", "content": { "application/json": { "schema": { "$ref": "#/components/schemas/Pet" } } } } }
+                    },
+                    "delete": {
+                        "operationId": "deletePet",
+                        "parameters": [{ "name": "petId", "in": "path", "required": true, "schema": { "type": "string" } }],
+                        "responses": { "204": { "description": "deleted" } }
+                    }
+                },
+                "/pets/{petId}/logs": {
+                    "get": {
+                        "operationId": "followPetLogs",
+                        "summary": "Follow pet logs",
+                        "parameters": [{ "name": "petId", "in": "path", "required": true, "schema": { "type": "string" } }],
+                        "responses": { "200": { "description": "events", "content": { "text/event-stream": {} } } }
+                    }
+                }
+            },
+            "components": {
+                "securitySchemes": {
+                    "bearerAuth": { "type": "http", "scheme": "bearer" },
+                    "apiKey": { "type": "apiKey", "in": "header", "name": "x-api-key" }
+                },
+                "schemas": {
+                    "LoginRequest": {
+                        "type": "object",
+                        "required": ["username", "password"],
+                        "properties": { "username": { "type": "string" }, "password": { "type": "string" } }
+                    },
+                    "LoginResponse": {
+                        "type": "object",
+                        "required": ["access_token"],
+                        "properties": { "access_token": { "type": "string" } }
+                    },
+                    "Pet": {
+                        "type": "object",
+                        "description": "A pet",
+                        "required": ["id", "kind"],
+                        "properties": {
+                            "id": { "type": "string" },
+                            "kind": { "$ref": "#/components/schemas/PetKind" },
+                            "type": { "type": "string" },
+                            "birthDate": { "type": "string", "nullable": true },
+                            "tags": { "type": "object", "additionalProperties": { "type": "string" } }
+                        }
+                    },
+                    "PetKind": { "type": "string", "enum": ["cat", "dog"] }
+                }
+            }
+        })
+    }
+
+    fn generate(language: SdkLanguage) -> Vec<SdkFile> {
+        SdkGenerator::new(SdkOptions::new(language, "pets-client")).generate(&document()).unwrap()
+    }
+
+    fn file<'a>(files: &'a [SdkFile], path: &str) -> &'a str {
+        &files.iter().find(|file| file.path == path).unwrap().contents
+    }
+
+    #[test]
+    fn test_naming() {
+        assert_eq!(snake_case("listExecutionViews"), "list_execution_views");
+        assert_eq!(snake_case("run-sync"), "run_sync");
+        assert_eq!(pascal_case("get_pet_query"), "GetPetQuery");
+        assert_eq!(camel_case("follow_pet_logs"), "followPetLogs");
+    }
+
+    #[test]
+    fn test_rust_sdk() {
+        let files = generate(SdkLanguage::Rust);
+        assert!(file(&files, "Cargo.toml").contains("name = \"pets-client\"\nversion = \"1.2.0\""));
+
+        let types = file(&files, "src/types.rs");
+        assert!(types.contains("/// A pet\n#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]\npub struct Pet {"));
+        assert!(types.contains("#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]\npub struct GetPetQuery {"));
+        assert!(types.contains("    pub kind: PetKind,"));
+        assert!(types.contains("    pub r#type: Option<String>,"));
+        assert!(types.contains("    #[serde(rename = \"birthDate\")]\n    #[serde(default, skip_serializing_if = \"Option::is_none\")]\n    pub birth_date: Option<String>,"));
+        assert!(types.contains("    pub tags: Option<HashMap<String, String>>,"));
+        assert!(types.contains("    #[serde(rename = \"dog\")]\n    Dog,"));
+
+        let lib = file(&files, "src/lib.rs");
+        assert!(lib.contains("//! Client for Pets 1.2.0"));
+        assert!(lib.contains("    pub async fn get_pet(&self, pet_id: &str, query: &GetPetQuery) -> Result<Pet> {"));
+        assert!(lib.contains("self.request(reqwest::Method::GET, &format!(\"/pets/{}\", encode_path(pet_id))).query(query);"));
+        assert!(lib.contains("    pub async fn delete_pet(&self, pet_id: &str) -> Result<()> {"));
+        assert!(lib.contains("    pub async fn follow_pet_logs_stream(&self, pet_id: &str) -> Result<EventStream> {"));
+        assert!(!lib.contains("pub async fn follow_pet_logs("));
+        assert!(lib.contains("pub fn with_bearer_token("));
+        assert!(lib.contains("sent in the `x-api-key` header"));
+        assert!(lib.contains("const API_KEY_HEADER: Option<&str> = Some(\"x-api-key\");"));
+        assert!(lib.contains("pub async fn sign_in(&mut self, body: &LoginRequest) -> Result<LoginResponse> {"));
+    }
+
+    #[test]
+    fn test_typescript_sdk() {
+        let files = generate(SdkLanguage::TypeScript);
+        let manifest: Value = serde_json::from_str(file(&files, "package.json")).unwrap();
+        assert_eq!(manifest["name"], "pets-client");
+
+        let module = file(&files, "src/index.ts");
+        assert!(module.contains("/** A pet */\nexport interface Pet {"));
+        assert!(module.contains("  id: string;\n"));
+        assert!(module.contains("  kind: PetKind;\n"));
+        assert!(module.contains("  birthDate?: string;"));
+        assert!(module.contains("export type PetKind = \"cat\" | \"dog\";"));
+        assert!(module.contains("  async getPet(petId: string, query: GetPetQuery = {}): Promise<Pet> {"));
+        assert!(module.contains("return this.sendJson(\"GET\", `/pets/${encodeURIComponent(petId)}`, { query });"));
+        assert!(module.contains("  async deletePet(petId: string): Promise<void> {\n    await this.send(\"DELETE\", `/pets/${encodeURIComponent(petId)}`);"));
+        assert!(module.contains("  async *followPetLogsStream(petId: string): AsyncGenerator<ServerEvent> {"));
+        assert!(module.contains("const API_KEY_HEADER: string | undefined = \"x-api-key\";"));
+        assert!(module.contains("  async signIn(body: LoginRequest): Promise<LoginResponse> {"));
+    }
+
+    #[test]
+    fn test_invalid_documents() {
+        let generator = SdkGenerator::new(SdkOptions::new(SdkLanguage::Rust, "client"));
+        assert!(matches!(generator.generate(&json!({ "info": {} })), Err(SdkError::InvalidDocument(_))));
+
+        let mut duplicate = document();
+        duplicate["paths"]["/other"] = json!({ "get": { "operationId": "get-pet", "responses": {} } });
+        assert!(matches!(generator.generate(&duplicate), Err(SdkError::InvalidDocument(_))));
+        assert!(matches!("go".parse::<SdkLanguage>(), Err(SdkError::UnsupportedLanguage(_))));
+    }
+}
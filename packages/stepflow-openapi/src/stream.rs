@@ -0,0 +1,462 @@
+//! Streaming OpenAPI Parser
+//!
+//! Walks an OpenAPI document without building a CST or a full `serde_json::Value`.
+//! The top-level object is visited key by key; each path item and each component
+//! schema is deserialized on its own, turned into [`SpecEvent`]s and dropped before
+//! the next one is read. JSON is read incrementally from the reader; YAML input is
+//! tokenized up front by `serde_yaml` but is likewise never turned into a tree.
+//!
+//! [`parse_streaming`] is the synchronous SAX-style entry point. [`SpecStream`] runs
+//! it on a blocking thread and hands events to async consumers (such as the
+//! [`ToolGenerator`](crate::generator::ToolGenerator)) through a bounded channel, so
+//! a slow consumer pauses the parser instead of letting events pile up.
+
+use std::fmt;
+use std::io::Read;
+
+use serde::de::{self, DeserializeSeed, Deserializer, IgnoredAny, MapAccess, Visitor};
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+use crate::document::{DocumentError, DocumentFormat, DocumentManager, OperationInfo, SchemaInfo};
+
+/// Event emitted while walking a document, in document order
+#[derive(Debug, Clone)]
+pub enum SpecEvent {
+    /// The `info` section
+    Info {
+        title: String,
+        version: String,
+        description: Option<String>,
+    },
+    /// Server URLs from the `servers` section
+    Servers(Vec<String>),
+    /// One operation of a path item
+    Operation(Box<OperationInfo>),
+    /// One schema from `components.schemas`
+    Schema(SchemaInfo),
+    /// The whole `components` object, sent once it has been read
+    ///
+    /// Operations reference it through `$ref`; it is usually much smaller than
+    /// `paths` and is held in full so references can be resolved.
+    Components(Value),
+}
+
+/// Tenant and namespace used to build SRNs for streamed operations and schemas
+#[derive(Debug, Clone)]
+pub struct StreamContext {
+    pub tenant_id: String,
+    pub namespace: String,
+}
+
+impl StreamContext {
+    pub fn new(tenant_id: impl Into<String>, namespace: impl Into<String>) -> Self {
+        Self {
+            tenant_id: tenant_id.into(),
+            namespace: namespace.into(),
+        }
+    }
+}
+
+/// Parse `reader` and pass every event to `handler`
+///
+/// Returns the `openapi` version of the document. Parsing stops at the first error
+/// returned by `handler`, which is passed through unchanged.
+pub fn parse_streaming<R, F>(
+    reader: R,
+    format: DocumentFormat,
+    context: &StreamContext,
+    mut handler: F,
+) -> Result<String, DocumentError>
+where
+    R: Read,
+    F: FnMut(SpecEvent) -> Result<(), DocumentError>,
+{
+    let mut sink = EventSink {
+        context,
+        handler: &mut handler,
+        error: None,
+    };
+
+    let result = match format {
+        DocumentFormat::Json => {
+            let mut deserializer = serde_json::Deserializer::from_reader(reader);
+            deserializer
+                .deserialize_map(DocumentVisitor { sink: &mut sink })
+                .and_then(|summary| deserializer.end().map(|_| summary))
+                .map_err(|e| e.to_string())
+        }
+        DocumentFormat::Yaml => serde_yaml::Deserializer::from_reader(reader)
+            .deserialize_map(DocumentVisitor { sink: &mut sink })
+            .map_err(|e| e.to_string()),
+    };
+
+    // An error raised by the handler surfaces from serde as a generic message;
+    // report the original one instead.
+    if let Some(error) = sink.error {
+        return Err(error);
+    }
+    let summary = result.map_err(|e| DocumentError::ParseError(format!("Streaming parse error: {}", e)))?;
+
+    let version = summary
+        .openapi
+        .ok_or_else(|| DocumentError::ValidationFailed("Missing 'openapi' field".to_string()))?;
+    if !version.starts_with("3.") {
+        return Err(DocumentError::ValidationFailed(format!("Unsupported OpenAPI version: {}", version)));
+    }
+    if !summary.has_paths {
+        return Err(DocumentError::ValidationFailed("Missing 'paths' section".to_string()));
+    }
+    Ok(version)
+}
+
+/// Async stream of [`SpecEvent`]s produced on a blocking thread
+pub struct SpecStream {
+    receiver: mpsc::Receiver<Result<SpecEvent, DocumentError>>,
+}
+
+impl SpecStream {
+    /// Start parsing `reader`; at most `buffer_size` events are held in memory
+    pub fn from_reader<R>(reader: R, format: DocumentFormat, context: StreamContext, buffer_size: usize) -> Self
+    where
+        R: Read + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel(buffer_size.max(1));
+        tokio::task::spawn_blocking(move || {
+            let result = parse_streaming(reader, format, &context, |event| {
+                sender
+                    .blocking_send(Ok(event))
+                    .map_err(|_| DocumentError::ParseError("Streaming consumer closed".to_string()))
+            });
+            if let Err(error) = result {
+                let _ = sender.blocking_send(Err(error));
+            }
+        });
+        Self { receiver }
+    }
+
+    /// Next event, or `None` once the document has been fully read
+    pub async fn next(&mut self) -> Option<Result<SpecEvent, DocumentError>> {
+        self.receiver.recv().await
+    }
+}
+
+struct EventSink<'a> {
+    context: &'a StreamContext,
+    handler: &'a mut dyn FnMut(SpecEvent) -> Result<(), DocumentError>,
+    /// First handler or extraction error, kept so it is not flattened into a serde error
+    error: Option<DocumentError>,
+}
+
+impl EventSink<'_> {
+    fn emit<E: de::Error>(&mut self, event: Result<SpecEvent, DocumentError>) -> Result<(), E> {
+        match event.and_then(|event| (self.handler)(event)) {
+            Ok(()) => Ok(()),
+            Err(error) => {
+                let message = error.to_string();
+                self.error = Some(error);
+                Err(E::custom(message))
+            }
+        }
+    }
+}
+
+struct DocumentSummary {
+    openapi: Option<String>,
+    has_paths: bool,
+}
+
+struct DocumentVisitor<'a, 'b> {
+    sink: &'a mut EventSink<'b>,
+}
+
+impl<'de> Visitor<'de> for DocumentVisitor<'_, '_> {
+    type Value = DocumentSummary;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an OpenAPI document object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut summary = DocumentSummary {
+            openapi: None,
+            has_paths: false,
+        };
+
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "openapi" => {
+                    summary.openapi = match map.next_value::<Value>()? {
+                        Value::String(version) => Some(version),
+                        other => Some(other.to_string()),
+                    };
+                }
+                "info" => {
+                    let info = map.next_value::<Value>()?;
+                    let text = |field: &str| info.get(field).and_then(|v| v.as_str()).map(String::from);
+                    self.sink.emit::<A::Error>(Ok(SpecEvent::Info {
+                        title: text("title").unwrap_or_default(),
+                        version: text("version").unwrap_or_else(|| "1.0.0".to_string()),
+                        description: text("description"),
+                    }))?;
+                }
+                "servers" => {
+                    let servers = map.next_value::<Value>()?;
+                    let urls = servers
+                        .as_array()
+                        .map(|servers| {
+                            servers.iter()
+                                .filter_map(|server| server.get("url").and_then(|url| url.as_str()))
+                                .map(String::from)
+                                .collect()
+                        })
+                        .unwrap_or_default();
+                    self.sink.emit::<A::Error>(Ok(SpecEvent::Servers(urls)))?;
+                }
+                "paths" => {
+                    summary.has_paths = true;
+                    map.next_value_seed(PathsSeed { sink: &mut *self.sink })?;
+                }
+                "components" => map.next_value_seed(ComponentsSeed { sink: &mut *self.sink })?,
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+
+        Ok(summary)
+    }
+}
+
+/// Visits `paths` one path item at a time
+struct PathsSeed<'a, 'b> {
+    sink: &'a mut EventSink<'b>,
+}
+
+impl<'de> DeserializeSeed<'de> for PathsSeed<'_, '_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for PathsSeed<'_, '_> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an OpenAPI paths object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        while let Some((path, path_item)) = map.next_entry::<String, Value>()? {
+            let context = self.sink.context;
+            match DocumentManager::extract_path_item_operations(&path, &path_item, &context.tenant_id, &context.namespace) {
+                Ok(operations) => {
+                    for operation in operations {
+                        self.sink.emit::<A::Error>(Ok(SpecEvent::Operation(Box::new(operation))))?;
+                    }
+                }
+                Err(error) => return self.sink.emit(Err(error)),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Visits `components`, streaming `schemas` and emitting the whole object at the end
+struct ComponentsSeed<'a, 'b> {
+    sink: &'a mut EventSink<'b>,
+}
+
+impl<'de> DeserializeSeed<'de> for ComponentsSeed<'_, '_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ComponentsSeed<'_, '_> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an OpenAPI components object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let mut components = serde_json::Map::new();
+        while let Some(key) = map.next_key::<String>()? {
+            let value = if key == "schemas" {
+                Value::Object(map.next_value_seed(SchemasSeed { sink: &mut *self.sink })?)
+            } else {
+                map.next_value::<Value>()?
+            };
+            components.insert(key, value);
+        }
+        self.sink.emit(Ok(SpecEvent::Components(Value::Object(components))))
+    }
+}
+
+/// Visits `components.schemas` one schema at a time
+struct SchemasSeed<'a, 'b> {
+    sink: &'a mut EventSink<'b>,
+}
+
+impl<'de> DeserializeSeed<'de> for SchemasSeed<'_, '_> {
+    type Value = serde_json::Map<String, Value>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for SchemasSeed<'_, '_> {
+    type Value = serde_json::Map<String, Value>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an OpenAPI schemas object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut schemas = serde_json::Map::new();
+        while let Some((name, schema)) = map.next_entry::<String, Value>()? {
+            let context = self.sink.context;
+            let info = DocumentManager::extract_schema(&name, schema.clone(), &context.tenant_id, &context.namespace);
+            self.sink.emit::<A::Error>(info.map(SpecEvent::Schema))?;
+            schemas.insert(name, schema);
+        }
+        Ok(schemas)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPEC_JSON: &str = r#"{
+        "openapi": "3.0.0",
+        "info": {"title": "Pets", "version": "2.1.0"},
+        "servers": [{"url": "https://pets.example.com"}],
+        "x-ignored": {"deep": [1, 2, {"a": null}]},
+        "paths": {
+            "/pets": {
+                "get": {"operationId": "listPets", "responses": {"200": {"description": "ok"}}},
+                "post": {"operationId": "createPet", "responses": {"201": {"description": "created"}}}
+            },
+            "/pets/{id}": {
+                "parameters": [],
+                "get": {"operationId": "getPet", "parameters": [{"name": "id", "in": "path", "required": true}], "responses": {}}
+            }
+        },
+        "components": {
+            "securitySchemes": {"key": {"type": "apiKey"}},
+            "schemas": {"Pet": {"type": "object", "description": "A pet"}}
+        }
+    }"#;
+
+    fn collect(source: &str, format: DocumentFormat) -> Result<Vec<SpecEvent>, DocumentError> {
+        let mut events = Vec::new();
+        parse_streaming(source.as_bytes(), format, &StreamContext::new("tenant", "pets"), |event| {
+            events.push(event);
+            Ok(())
+        })?;
+        Ok(events)
+    }
+
+    fn operation_ids(events: &[SpecEvent]) -> Vec<String> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                SpecEvent::Operation(op) => Some(op.operation_id.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_streaming_json_events() {
+        let events = collect(SPEC_JSON, DocumentFormat::Json).unwrap();
+
+        assert!(matches!(&events[0], SpecEvent::Info { title, version, .. } if title == "Pets" && version == "2.1.0"));
+        assert!(matches!(&events[1], SpecEvent::Servers(urls) if urls == &["https://pets.example.com"]));
+        assert_eq!(operation_ids(&events), ["listPets", "createPet", "getPet"]);
+
+        let path_op = events.iter().find_map(|event| match event {
+            SpecEvent::Operation(op) if op.path == "/pets/{id}" => Some(op),
+            _ => None,
+        }).unwrap();
+        assert_eq!(path_op.method, "GET");
+        assert_eq!(path_op.parameters.len(), 1);
+
+        let schema = events.iter().find_map(|event| match event {
+            SpecEvent::Schema(schema) => Some(schema),
+            _ => None,
+        }).unwrap();
+        assert_eq!(schema.name, "Pet");
+        assert_eq!(schema.description.as_deref(), Some("A pet"));
+
+        // The components object follows its schemas, with every section kept
+        let components = match events.last().unwrap() {
+            SpecEvent::Components(components) => components,
+            other => panic!("expected components, got {:?}", other),
+        };
+        assert_eq!(components["schemas"]["Pet"]["type"], "object");
+        assert_eq!(components["securitySchemes"]["key"]["type"], "apiKey");
+    }
+
+    #[test]
+    fn test_streaming_yaml_events() {
+        let yaml = "openapi: 3.0.3\ninfo:\n  title: Pets\n  version: '1'\npaths:\n  /pets:\n    get:\n      operationId: listPets\n      responses: {}\n";
+        let events = collect(yaml, DocumentFormat::Yaml).unwrap();
+        assert_eq!(operation_ids(&events), ["listPets"]);
+    }
+
+    #[test]
+    fn test_streaming_validation_errors() {
+        let missing_paths = r#"{"openapi": "3.0.0", "info": {"title": "x", "version": "1"}}"#;
+        assert!(matches!(collect(missing_paths, DocumentFormat::Json), Err(DocumentError::ValidationFailed(_))));
+
+        let swagger = r#"{"openapi": "2.0", "paths": {}}"#;
+        assert!(matches!(collect(swagger, DocumentFormat::Json), Err(DocumentError::ValidationFailed(_))));
+
+        let truncated = r#"{"openapi": "3.0.0", "paths": {"/a": {"get": {}"#;
+        assert!(matches!(collect(truncated, DocumentFormat::Json), Err(DocumentError::ParseError(_))));
+
+        let bad_param = r#"{"openapi": "3.0.0", "paths": {"/a": {"get": {"parameters": [{"in": "query"}]}}}}"#;
+        let err = collect(bad_param, DocumentFormat::Json).unwrap_err();
+        assert_eq!(err.to_string(), "Document validation failed: Parameter missing name");
+    }
+
+    #[test]
+    fn test_streaming_handler_error_stops_parse() {
+        let mut seen = 0;
+        let result = parse_streaming(SPEC_JSON.as_bytes(), DocumentFormat::Json, &StreamContext::new("tenant", "pets"), |event| {
+            if matches!(event, SpecEvent::Operation(_)) {
+                seen += 1;
+                return Err(DocumentError::StorageError("stop".to_string()));
+            }
+            Ok(())
+        });
+        assert!(matches!(result, Err(DocumentError::StorageError(_))));
+        assert_eq!(seen, 1);
+    }
+
+    #[tokio::test]
+    async fn test_spec_stream_bounded_channel() {
+        let mut stream = SpecStream::from_reader(
+            std::io::Cursor::new(SPEC_JSON.as_bytes().to_vec()),
+            DocumentFormat::Json,
+            StreamContext::new("tenant", "pets"),
+            1,
+        );
+
+        let mut operations = 0;
+        while let Some(event) = stream.next().await {
+            if let SpecEvent::Operation(_) = event.unwrap() {
+                operations += 1;
+            }
+        }
+        assert_eq!(operations, 3);
+    }
+}
@@ -4,22 +4,34 @@
 //! the stepflow-core Tool trait specification.
 
 use std::collections::HashMap;
+use std::sync::Arc;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use chrono::Utc;
 
 // Import stepflow-core types
 use stepflow_core::types::{
-    Tool, ToolInfo, ToolRequest, ToolResponse, ToolExample, ToolType, ToolStatus, ToolVersion,
+    ExampleMatch, Tool, ToolConfig, ToolId, ToolInfo, ToolRequest, ToolResponse, ToolExample, ToolType,
 };
 use stepflow_core::StepflowError;
 
 use crate::srn::{Srn, SrnError};
-use crate::document::{OpenApiDocument, OperationInfo};
+use crate::cst::to_json_pointer;
+use crate::document::{MediaTypeInfo, OpenApiDocument, OperationInfo};
 use crate::ref_resolver::{RefResolver, RefResolverError};
+use crate::proxy::artifact::ArtifactStore;
+use crate::proxy::content::{accept_header, ResponseConversion};
+use crate::proxy::pagination::PaginationConfig;
+use crate::proxy::rate_limit::{rate_limit_host, RateLimit, UpstreamRateLimiter};
+use crate::proxy::recording::InteractionRecorder;
 use crate::proxy::http_client::{HttpApiProxy, HttpClientConfig};
-use crate::proxy::converter::{ParameterConverter, JsonRpcRequest, HttpRequest};
+use crate::proxy::converter::{ParameterConverter, JsonRpcRequest, HttpRequest, HttpResponse};
+
+/// Media type of request bodies sent as multipart form data
+const MULTIPART_FORM_DATA: &str = "multipart/form-data";
+
+/// Request configuration flag that collects every page of a paginated operation
+pub const COLLECT_ALL_PAGES: &str = "collect_all_pages";
 
 /// OpenAPI Tool Errors
 #[derive(Debug, thiserror::Error)]
@@ -67,6 +79,48 @@ pub struct OpenApiToolConfig {
     pub default_headers: HashMap<String, String>,
     /// Authentication configuration
     pub auth: Option<AuthConfig>,
+    /// Display name from `x-stepflow-tool-name`
+    #[serde(default)]
+    pub tool_name: Option<String>,
+    /// Sandbox profile from `x-stepflow-sandbox-profile`
+    #[serde(default)]
+    pub sandbox_profile: Option<String>,
+    /// Conversion of XML and CSV responses to JSON
+    #[serde(default)]
+    pub response_conversion: ResponseConversion,
+    /// How to follow pages when a caller collects all pages
+    #[serde(default)]
+    pub pagination: Option<PaginationConfig>,
+    /// Documented request rate of the upstream host from `x-stepflow-rate-limit`
+    #[serde(default)]
+    pub rate_limit: Option<RateLimit>,
+}
+
+impl OpenApiToolConfig {
+    /// Convert into the generic tool configuration
+    ///
+    /// The core configuration expresses timeouts in seconds, rounded up here.
+    pub fn to_tool_config(&self) -> ToolConfig {
+        let mut configuration = HashMap::from([
+            ("base_url".to_string(), Value::String(self.base_url.clone())),
+        ]);
+        if let Some(timeout_ms) = self.timeout_ms {
+            configuration.insert("timeout_ms".to_string(), Value::from(timeout_ms));
+        }
+        if let Some(profile) = &self.sandbox_profile {
+            configuration.insert("sandbox_profile".to_string(), Value::String(profile.clone()));
+        }
+
+        ToolConfig {
+            tool_id: ToolId::from_string(self.srn.clone()),
+            configuration,
+            environment: HashMap::new(),
+            secrets: HashMap::new(),
+            timeout: self.timeout_ms.map(|ms| ms.div_ceil(1000)),
+            retries: self.max_retries,
+            enabled: true,
+        }
+    }
 }
 
 /// Authentication configuration
@@ -88,10 +142,12 @@ pub struct OpenApiTool {
     config: OpenApiToolConfig,
     /// Operation information from OpenAPI spec
     operation: OperationInfo,
-    /// Resolved OpenAPI document (with $ref resolved)
-    resolved_document: Value,
+    /// Resolved OpenAPI document (with $ref resolved), shared by the tools of a document
+    resolved_document: Arc<Value>,
     /// HTTP client for making requests
     http_client: HttpApiProxy,
+    /// Source of uploaded files and destination of large binary responses
+    artifact_store: Option<Arc<dyn ArtifactStore>>,
 }
 
 impl OpenApiTool {
@@ -101,21 +157,39 @@ impl OpenApiTool {
         operation: OperationInfo,
         document: &OpenApiDocument,
     ) -> Result<Self, OpenApiToolError> {
-        let srn = Srn::parse(&config.srn)?;
-        
         // Resolve all references in the document
         let ref_resolver = RefResolver::new();
         let resolved_document = ref_resolver.resolve_document(&document.parsed)?;
 
+        Self::with_resolved_document(config, operation, Arc::new(resolved_document))
+    }
+
+    /// Create a tool from an already resolved document
+    ///
+    /// Lets the generator resolve a document once for all of its operations.
+    /// Streaming generation never materializes the whole document and passes
+    /// `Value::Null` here.
+    pub fn with_resolved_document(
+        config: OpenApiToolConfig,
+        operation: OperationInfo,
+        resolved_document: Arc<Value>,
+    ) -> Result<Self, OpenApiToolError> {
+        let srn = Srn::parse(&config.srn)?;
+
         // Create HTTP client configuration
         let http_config = HttpClientConfig {
             timeout_seconds: config.timeout_ms.unwrap_or(30000) / 1000, // Convert ms to seconds
             max_retries: config.max_retries.unwrap_or(3),
             user_agent: "stepflow-openapi-tool/1.0".to_string(),
+            response_conversion: config.response_conversion,
+            ..HttpClientConfig::default()
         };
 
         let http_client = HttpApiProxy::new(http_config)
             .map_err(|e| OpenApiToolError::Configuration(e.to_string()))?;
+        if let Some(limit) = config.rate_limit {
+            http_client.rate_limiter().set_limit(&rate_limit_host(&config.base_url), limit);
+        }
 
         Ok(Self {
             srn,
@@ -123,9 +197,115 @@ impl OpenApiTool {
             operation,
             resolved_document,
             http_client,
+            artifact_store: None,
+        })
+    }
+
+    /// Use an artifact store for file uploads and large binary responses
+    pub fn with_artifact_store(mut self, store: Arc<dyn ArtifactStore>) -> Self {
+        self.http_client = self.http_client.with_artifact_store(store.clone());
+        self.artifact_store = Some(store);
+        self
+    }
+
+    /// Pace upstream calls with a specific rate limiter instead of the process-wide one
+    pub fn with_rate_limiter(mut self, limiter: Arc<UpstreamRateLimiter>) -> Self {
+        if let Some(limit) = self.config.rate_limit {
+            limiter.set_limit(&rate_limit_host(&self.config.base_url), limit);
+        }
+        self.http_client = self.http_client.with_rate_limiter(limiter);
+        self
+    }
+
+    /// Record upstream interactions, or replay recorded ones, through `recorder`
+    pub fn with_recorder(mut self, recorder: Arc<InteractionRecorder>) -> Self {
+        self.http_client = self.http_client.with_recorder(recorder);
+        self
+    }
+
+    /// Tool configuration
+    pub fn config(&self) -> &OpenApiToolConfig {
+        &self.config
+    }
+
+    /// Operation this tool calls
+    pub fn operation(&self) -> &OperationInfo {
+        &self.operation
+    }
+
+    /// Declared JSON schema of the response body for a status code
+    pub fn response_schema(&self, status: u16) -> Option<Value> {
+        declared_response_schema(&self.operation, &self.resolved_document, status)
+    }
+
+    /// JSON schema of the tool input
+    ///
+    /// Every parameter becomes a property; for multipart operations the form
+    /// fields of the body are added too. References are resolved when the
+    /// resolved document is available.
+    pub fn input_schema(&self) -> Value {
+        let method = self.operation.method.to_lowercase();
+        let resolved_parameters: Vec<&Value> = [
+            to_json_pointer(&["paths", self.operation.path.as_str(), method.as_str(), "parameters"]),
+            to_json_pointer(&["paths", self.operation.path.as_str(), "parameters"]),
+        ]
+        .iter()
+        .filter_map(|pointer| self.resolved_document.pointer(pointer).and_then(|params| params.as_array()))
+        .flatten()
+        .collect();
+
+        let mut properties = serde_json::Map::new();
+        let mut required = Vec::new();
+        for param in &self.operation.parameters {
+            let mut schema = resolved_parameters.iter()
+                .find(|resolved| resolved.get("name").and_then(|name| name.as_str()) == Some(param.name.as_str()))
+                .and_then(|resolved| resolved.get("schema"))
+                .unwrap_or(&param.schema)
+                .clone();
+            if let (Some(schema), Some(description)) = (schema.as_object_mut(), &param.description) {
+                schema.entry("description").or_insert_with(|| Value::String(description.clone()));
+            }
+            properties.insert(param.name.clone(), schema);
+            if param.required {
+                required.push(Value::String(param.name.clone()));
+            }
+        }
+
+        let multipart = self.operation.request_body.as_ref()
+            .and_then(|body| body.content.get(MULTIPART_FORM_DATA));
+        if let Some(media_type) = multipart {
+            let (schema, _) = self.multipart_schema(media_type);
+            for (name, property) in schema.get("properties").and_then(|p| p.as_object()).into_iter().flatten() {
+                properties.entry(name.clone()).or_insert_with(|| property.clone());
+            }
+            for name in schema.get("required").and_then(|r| r.as_array()).into_iter().flatten() {
+                if !required.contains(name) {
+                    required.push(name.clone());
+                }
+            }
+        }
+
+        serde_json::json!({
+            "type": "object",
+            "properties": properties,
+            "required": required,
         })
     }
 
+    /// Send the request for `input` and return the raw upstream response
+    ///
+    /// Unlike [`Tool::execute`], a non-2xx status is not an error, so callers
+    /// can check the response against the status codes the operation declares.
+    pub(crate) async fn send(&self, input: &Value) -> Result<HttpResponse, OpenApiToolError> {
+        self.validate_input_parameters(input)?;
+        let mut http_request = self.build_http_request(input)?;
+        self.add_authentication(&mut http_request)?;
+        self.http_client
+            .send_request(&self.config.base_url, &http_request)
+            .await
+            .map_err(|e| OpenApiToolError::HttpError(e.to_string()))
+    }
+
     /// Validate input parameters against OpenAPI schema
     fn validate_input_parameters(&self, input: &Value) -> Result<(), OpenApiToolError> {
         // This is a simplified validation - should be enhanced with proper JSON Schema validation
@@ -157,12 +337,29 @@ impl OpenApiTool {
     }
 
     /// Convert input to HTTP request
+    ///
+    /// For multipart operations, inputs that are not declared parameters become
+    /// the form fields of the body.
     fn build_http_request(&self, input: &Value) -> Result<HttpRequest, OpenApiToolError> {
+        let multipart = self.operation.request_body.as_ref()
+            .and_then(|body| body.content.get(MULTIPART_FORM_DATA))
+            .zip(input.as_object());
+        let (params, form) = match multipart {
+            Some((_, input_obj)) => {
+                let (params, form): (serde_json::Map<String, Value>, serde_json::Map<String, Value>) = input_obj
+                    .clone()
+                    .into_iter()
+                    .partition(|(key, _)| self.operation.parameters.iter().any(|param| param.name == *key));
+                (Value::Object(params), Some(form))
+            }
+            None => (input.clone(), None),
+        };
+
         // Create a JSON RPC request structure for parameter conversion
         let rpc_request = JsonRpcRequest {
             jsonrpc: "2.0".to_string(),
             method: self.operation.operation_id.clone(),
-            params: Some(input.clone()),
+            params: Some(params),
             id: Some(Value::String("1".to_string())),
         };
 
@@ -176,8 +373,52 @@ impl OpenApiTool {
         };
 
         // Convert to HTTP request
-        ParameterConverter::convert_to_http_request(&rpc_request, &method_mapping)
-            .map_err(|e| OpenApiToolError::Execution(e.to_string()))
+        let mut http_request = ParameterConverter::convert_to_http_request(&rpc_request, &method_mapping)
+            .map_err(|e| OpenApiToolError::Execution(e.to_string()))?;
+
+        if let (Some((media_type, _)), Some(form)) = (multipart, form) {
+            let (schema, encoding) = self.multipart_schema(media_type);
+            let body = ParameterConverter::build_multipart_body(&form, &schema, &encoding, self.artifact_store.as_deref())
+                .map_err(|e| OpenApiToolError::ParameterValidation(e.to_string()))?;
+            http_request.multipart = Some(body);
+        }
+
+        // Ask for the media types the operation declares for successful responses
+        if !http_request.headers.keys().any(|key| key.eq_ignore_ascii_case("accept")) {
+            let declared = self.operation.responses.iter()
+                .filter(|(status, _)| status.starts_with('2') || status.as_str() == "default")
+                .filter_map(|(_, response)| response.content.as_ref())
+                .flat_map(|content| content.keys().map(String::as_str));
+            if let Some(accept) = accept_header(declared) {
+                http_request.headers.insert("Accept".to_string(), accept);
+            }
+        }
+
+        Ok(http_request)
+    }
+
+    /// Schema and encoding of the multipart body, with references resolved when
+    /// the resolved document is available
+    fn multipart_schema(&self, media_type: &MediaTypeInfo) -> (Value, HashMap<String, Value>) {
+        let pointer = to_json_pointer(&[
+            "paths",
+            self.operation.path.as_str(),
+            self.operation.method.to_lowercase().as_str(),
+            "requestBody",
+            "content",
+            MULTIPART_FORM_DATA,
+        ]);
+        let resolved = self.resolved_document.pointer(&pointer);
+
+        let schema = resolved.and_then(|media| media.get("schema"))
+            .or(media_type.schema.as_ref())
+            .cloned()
+            .unwrap_or(Value::Null);
+        let encoding = match resolved.and_then(|media| media.get("encoding")).and_then(|e| e.as_object()) {
+            Some(encoding) => encoding.clone().into_iter().collect(),
+            None => media_type.encoding.clone(),
+        };
+        (schema, encoding)
     }
 
     /// Create parameter mapping from operation info
@@ -229,7 +470,13 @@ impl OpenApiTool {
     }
 
     /// Execute HTTP request and convert response
-    async fn execute_http_request(&self, mut http_request: HttpRequest) -> Result<Value, OpenApiToolError> {
+    ///
+    /// Returns the converted body and the response metadata: its content type
+    /// and the time spent queued behind the upstream rate limit.
+    async fn execute_http_request(
+        &self,
+        mut http_request: HttpRequest,
+    ) -> Result<(Value, HashMap<String, Value>), OpenApiToolError> {
         // Add authentication
         self.add_authentication(&mut http_request)?;
 
@@ -239,36 +486,68 @@ impl OpenApiTool {
             .await
             .map_err(|e| OpenApiToolError::HttpError(e.to_string()))?;
 
-        Ok(response.body.unwrap_or(Value::Null))
+        let mut metadata = HashMap::from([
+            ("queue_wait_ms".to_string(), Value::from(response.queue_wait_ms)),
+        ]);
+        if let Some(content_type) = response.headers.get("content-type") {
+            metadata.insert("content_type".to_string(), Value::String(content_type.clone()));
+        }
+        Ok((response.body.unwrap_or(Value::Null), metadata))
+    }
+
+    /// Execute a paginated request and merge the items of every page
+    ///
+    /// Returns the items and the pagination metadata of the response.
+    async fn execute_paginated_request(
+        &self,
+        mut http_request: HttpRequest,
+        pagination: &PaginationConfig,
+    ) -> Result<(Value, HashMap<String, Value>), OpenApiToolError> {
+        self.add_authentication(&mut http_request)?;
+
+        let response = self.http_client
+            .send_paginated(&self.config.base_url, &http_request, pagination)
+            .await
+            .map_err(|e| OpenApiToolError::HttpError(e.to_string()))?;
+
+        let metadata = HashMap::from([
+            ("pages_fetched".to_string(), Value::from(response.pages)),
+            ("items_collected".to_string(), Value::from(response.items.len())),
+            ("pagination_truncated".to_string(), Value::Bool(response.truncated)),
+            ("queue_wait_ms".to_string(), Value::from(response.queue_wait_ms)),
+        ]);
+        Ok((Value::Array(response.items), metadata))
+    }
+
+    /// Pagination to apply to a request, if the caller collects all pages
+    fn requested_pagination(&self, request: &ToolRequest) -> Option<&PaginationConfig> {
+        let pagination = self.config.pagination.as_ref()?;
+        let collect = request.configuration.as_ref()
+            .and_then(|configuration| configuration.get(COLLECT_ALL_PAGES))
+            .and_then(|flag| flag.as_bool())
+            .unwrap_or(pagination.collect_by_default);
+        collect.then_some(pagination)
     }
 }
 
 #[async_trait]
 impl Tool for OpenApiTool {
     async fn get_info(&self) -> Result<ToolInfo, StepflowError> {
-        Ok(ToolInfo {
-            id: stepflow_core::types::ToolId::from_string(self.srn.to_string()),
-            name: format!("{} {}", self.operation.method, self.operation.path),
-            description: self.operation.description.clone()
+        Ok(ToolInfo::builder()
+            .id(ToolId::from_string(self.srn.to_string()))
+            .name(self.config.tool_name.clone()
+                .unwrap_or_else(|| format!("{} {}", self.operation.method, self.operation.path)))
+            .description(self.operation.description.clone()
                 .or_else(|| self.operation.summary.clone())
-                .unwrap_or_else(|| format!("OpenAPI operation: {}", self.operation.operation_id)),
-            version: ToolVersion::new(1, 0, 0),
-            tool_type: ToolType::OpenAPI,
-            status: ToolStatus::Active,
-            author: "StepFlow OpenAPI Generator".to_string(),
-            repository: None,
-            documentation: None,
-            tags: self.operation.tags.clone(),
-            capabilities: vec![
-                "http_request".to_string(),
-                "parameter_validation".to_string(),
-                "schema_validation".to_string(),
-            ],
-            configuration_schema: self.get_configuration_schema().await?,
-            examples: self.generate_examples(),
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-        })
+                .unwrap_or_else(|| format!("OpenAPI operation: {}", self.operation.operation_id)))
+            .tool_type(ToolType::OpenAPI)
+            .author("StepFlow OpenAPI Generator")
+            .tags(self.operation.tags.clone())
+            .capabilities(["http_request", "parameter_validation", "schema_validation"])
+            .configuration_schema(self.get_configuration_schema().await?)
+            .examples(self.generate_examples())
+            .input_schema(self.input_schema())
+            .build()?)
     }
 
     async fn execute(&self, request: ToolRequest) -> Result<ToolResponse, StepflowError> {
@@ -299,10 +578,14 @@ impl Tool for OpenApiTool {
             }
         };
 
-        // Execute HTTP request
-        match self.execute_http_request(http_request).await {
-            Ok(response_body) => {
-                let mut metadata = HashMap::new();
+        // Execute HTTP request, following pages if requested
+        let result = match self.requested_pagination(&request) {
+            Some(pagination) => self.execute_paginated_request(http_request, pagination).await,
+            None => self.execute_http_request(http_request).await,
+        };
+
+        match result {
+            Ok((response_body, mut metadata)) => {
                 metadata.insert("operation_id".to_string(), Value::String(self.operation.operation_id.clone()));
                 metadata.insert("method".to_string(), Value::String(self.operation.method.clone()));
                 metadata.insert("path".to_string(), Value::String(self.operation.path.clone()));
@@ -351,6 +634,14 @@ impl Tool for OpenApiTool {
                     "description": "Request timeout in milliseconds",
                     "default": 30000
                 },
+                "tool_name": {
+                    "type": "string",
+                    "description": "Display name of the tool"
+                },
+                "sandbox_profile": {
+                    "type": "string",
+                    "description": "Sandbox profile for tool execution"
+                },
                 "max_retries": {
                     "type": "integer", 
                     "description": "Maximum retry attempts",
@@ -405,6 +696,36 @@ impl Tool for OpenApiTool {
     }
 }
 
+/// Declared JSON schema of an operation's response body for a status code
+///
+/// Looks up the exact status, then its range (`2XX`), then `default`, and
+/// prefers the `application/json` media type. References are resolved when
+/// `resolved_document` holds the resolved document rather than `Value::Null`.
+pub fn declared_response_schema(operation: &OperationInfo, resolved_document: &Value, status: u16) -> Option<Value> {
+    let exact = status.to_string();
+    let range = format!("{}XX", status / 100);
+    let key = [exact.as_str(), range.as_str(), "default"].into_iter()
+        .find(|key| operation.responses.contains_key(*key))?;
+    let content = operation.responses[key].content.as_ref()?;
+    let media_type = content.keys()
+        .find(|media_type| media_type.as_str() == "application/json")
+        .or_else(|| content.keys().min())?;
+
+    let pointer = to_json_pointer(&[
+        "paths",
+        operation.path.as_str(),
+        operation.method.to_lowercase().as_str(),
+        "responses",
+        key,
+        "content",
+        media_type.as_str(),
+        "schema",
+    ]);
+    resolved_document.pointer(&pointer)
+        .or(content[media_type].schema.as_ref())
+        .cloned()
+}
+
 impl OpenApiTool {
     /// Generate example requests/responses for this tool
     fn generate_examples(&self) -> Vec<ToolExample> {
@@ -423,16 +744,17 @@ impl OpenApiTool {
             example_input.insert(param.name.clone(), example_value);
         }
 
-        let example_output = serde_json::json!({
-            "status": "success",
-            "data": {}
-        });
+        // The response body can't be predicted, so the example checks it
+        // against the declared success schema; any JSON passes without one
+        let example_output = self.response_schema(200)
+            .unwrap_or_else(|| serde_json::json!({}));
 
         examples.push(ToolExample {
             name: format!("{} Example", self.operation.operation_id),
             description: format!("Example request for {} {}", self.operation.method, self.operation.path),
             input: Value::Object(example_input),
             output: example_output,
+            match_mode: ExampleMatch::Schema,
         });
 
         examples
@@ -443,6 +765,7 @@ impl OpenApiTool {
 mod tests {
     use super::*;
     use crate::document::{ParameterInfo, ParameterLocation};
+    use stepflow_core::types::ToolStatus;
 
     fn create_test_operation() -> OperationInfo {
         OperationInfo {
@@ -464,6 +787,7 @@ mod tests {
             request_body: None,
             responses: HashMap::new(),
             tags: vec!["users".to_string()],
+            extensions: HashMap::new(),
         }
     }
 
@@ -475,6 +799,11 @@ mod tests {
             max_retries: Some(3),
             default_headers: HashMap::new(),
             auth: None,
+            tool_name: None,
+            sandbox_profile: None,
+            response_conversion: ResponseConversion::default(),
+            pagination: None,
+            rate_limit: None,
         }
     }
 
@@ -515,6 +844,9 @@ mod tests {
         assert_eq!(info.tool_type, ToolType::OpenAPI);
         assert_eq!(info.status, ToolStatus::Active);
         assert!(!info.examples.is_empty());
+        let input_schema = info.input_schema.unwrap();
+        assert_eq!(input_schema["properties"]["id"]["type"], "string");
+        assert_eq!(input_schema["required"], serde_json::json!(["id"]));
     }
 
     #[test]
@@ -527,4 +859,72 @@ mod tests {
         let mapping = crate::proxy::config::ParameterMapping::default();
         assert!(mapping.path_params.is_empty());
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_multipart_request() {
+        use crate::document::{MediaTypeInfo, RequestBodyInfo};
+        use crate::proxy::artifact::InMemoryArtifactStore;
+
+        let mut operation = create_test_operation();
+        operation.method = "POST".to_string();
+        operation.path = "/users/{id}/avatar".to_string();
+        operation.request_body = Some(RequestBodyInfo {
+            required: true,
+            content: HashMap::from([(MULTIPART_FORM_DATA.to_string(), MediaTypeInfo {
+                schema: Some(serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "caption": {"type": "string"},
+                        "file": {"type": "string", "format": "binary"}
+                    }
+                })),
+                encoding: HashMap::from([("file".to_string(), serde_json::json!({"contentType": "image/png"}))]),
+            })]),
+            description: None,
+        });
+
+        let tool = OpenApiTool::with_resolved_document(create_test_config(), operation, Arc::new(Value::Null))
+            .unwrap()
+            .with_artifact_store(Arc::new(InMemoryArtifactStore::new()));
+        let request = tool.build_http_request(&serde_json::json!({
+            "id": "42",
+            "caption": "me",
+            "file": "iVBO"
+        }))
+        .unwrap();
+
+        assert_eq!(request.path, "/users/42/avatar");
+        assert!(request.query_params.is_empty());
+        let multipart = request.multipart.unwrap();
+        let file = multipart.parts.iter().find(|part| part.name == "file").unwrap();
+        assert_eq!(multipart.parts.len(), 2);
+        assert_eq!(file.content_type.as_deref(), Some("image/png"));
+    }
+
+    #[test]
+    fn test_accept_negotiation() {
+        use crate::document::{MediaTypeInfo, ResponseInfo};
+
+        let media_type = || MediaTypeInfo { schema: None, encoding: HashMap::new() };
+        let mut operation = create_test_operation();
+        operation.responses = HashMap::from([
+            ("200".to_string(), ResponseInfo {
+                description: "User".to_string(),
+                content: Some(HashMap::from([
+                    ("application/xml".to_string(), media_type()),
+                    ("application/json".to_string(), media_type()),
+                ])),
+                headers: None,
+            }),
+            ("404".to_string(), ResponseInfo {
+                description: "Missing".to_string(),
+                content: Some(HashMap::from([("text/html".to_string(), media_type())])),
+                headers: None,
+            }),
+        ]);
+
+        let tool = OpenApiTool::with_resolved_document(create_test_config(), operation, Arc::new(Value::Null)).unwrap();
+        let request = tool.build_http_request(&serde_json::json!({"id": "1"})).unwrap();
+        assert_eq!(request.headers["Accept"], "application/json, application/xml;q=0.9");
+    }
+}
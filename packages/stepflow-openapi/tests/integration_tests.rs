@@ -2,17 +2,21 @@
 //! 
 //! 端到端测试 - 包括真正的HTTP调用
 
+use std::collections::HashMap;
 use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use serde_json::{json, Value};
 use tokio::time::{sleep, Duration};
 use axum::{
+    extract::Query,
     routing::{get, post},
-    http::StatusCode,
+    http::{header, HeaderMap, StatusCode},
     Json, Router,
 };
 use stepflow_openapi::{
-    DocumentManager, RefResolver,
-    proxy::{HttpApiProxy, HttpClientConfig},
+    DocumentManager, OperationFilter, RefResolver,
+    proxy::{HttpApiProxy, HttpClientConfig, HttpRequest, PaginationConfig, RateLimit, UpstreamRateLimiter},
     generator::{ToolGenerator, GeneratorConfig, ToolGenerationRequest, InMemoryToolRegistry, ToolRegistry},
 };
 
@@ -34,6 +38,34 @@ async fn mock_create_user(Json(payload): Json<Value>) -> (StatusCode, Json<Value
     })))
 }
 
+/// 共 5 只宠物，按 page/per_page 分页，并在 Link 头中给出下一页
+async fn mock_list_pets(Query(params): Query<HashMap<String, usize>>) -> (HeaderMap, Json<Value>) {
+    let page = params.get("page").copied().unwrap_or(1);
+    let per_page = params.get("per_page").copied().unwrap_or(2);
+    let pets: Vec<Value> = (1..=5usize)
+        .skip((page - 1) * per_page)
+        .take(per_page)
+        .map(|id| json!({"id": id}))
+        .collect();
+
+    let mut headers = HeaderMap::new();
+    if page * per_page < 5 {
+        let next = format!("</pets?page={}&per_page={}>; rel=\"next\"", page + 1, per_page);
+        headers.insert(header::LINK, next.parse().unwrap());
+    }
+    (headers, Json(json!({"data": pets})))
+}
+
+/// 第一次请求返回 429 并要求 1 秒后重试，之后正常返回
+async fn mock_throttled(hits: Arc<AtomicUsize>) -> (StatusCode, HeaderMap, Json<Value>) {
+    let mut headers = HeaderMap::new();
+    if hits.fetch_add(1, Ordering::SeqCst) == 0 {
+        headers.insert(header::RETRY_AFTER, "1".parse().unwrap());
+        return (StatusCode::TOO_MANY_REQUESTS, headers, Json(json!({"error": "slow down"})));
+    }
+    (StatusCode::OK, headers, Json(json!({"status": "ok"})))
+}
+
 async fn mock_health() -> Json<Value> {
     Json(json!({"status": "ok", "service": "mock-api"}))
 }
@@ -42,7 +74,12 @@ async fn mock_health() -> Json<Value> {
 async fn start_mock_api_server() -> SocketAddr {
     let app = Router::new()
         .route("/users", get(mock_get_users).post(mock_create_user))
-        .route("/health", get(mock_health));
+        .route("/pets", get(mock_list_pets))
+        .route("/health", get(mock_health))
+        .route("/throttled", get({
+            let hits = Arc::new(AtomicUsize::new(0));
+            move || mock_throttled(hits.clone())
+        }));
 
     let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
         .await
@@ -213,6 +250,7 @@ async fn test_end_to_end_http_workflow() {
         default_headers: None,
         auth: None,
         tool_config_overrides: None,
+        filter: OperationFilter::default(),
     };
     
     let generation_result = tool_generator.generate_tools(generation_request).await;
@@ -278,4 +316,299 @@ async fn test_end_to_end_http_workflow() {
     println!("   ✅ Direct HTTP API calls successful");
     println!("   ✅ All 8 TODO components integrated and working");
     println!("\n🚀 SYSTEM READY FOR PRODUCTION USE!");
-} 
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_paginated_collection() {
+    let base_url = format!("http://{}", start_mock_api_server().await);
+    let client = HttpApiProxy::new(HttpClientConfig::default()).unwrap();
+    let request = HttpRequest {
+        method: "GET".to_string(),
+        path: "/pets".to_string(),
+        query_params: HashMap::new(),
+        path_params: HashMap::new(),
+        headers: HashMap::new(),
+        body: None,
+        multipart: None,
+    };
+
+    let pages: PaginationConfig = serde_json::from_value(json!({
+        "strategy": {"type": "page", "per_page_param": "per_page", "per_page": 2},
+        "items_path": "data"
+    }))
+    .unwrap();
+    let collected = client.send_paginated(&base_url, &request, &pages).await.unwrap();
+    let ids: Vec<&Value> = collected.items.iter().map(|pet| &pet["id"]).collect();
+    assert_eq!(ids, [1, 2, 3, 4, 5]);
+    assert_eq!(collected.pages, 3);
+    assert!(!collected.truncated);
+
+    let links: PaginationConfig = serde_json::from_value(json!({
+        "strategy": {"type": "link_header"},
+        "items_path": "/data",
+        "max_items": 3
+    }))
+    .unwrap();
+    let collected = client.send_paginated(&base_url, &request, &links).await.unwrap();
+    assert_eq!(collected.items.len(), 3);
+    assert_eq!(collected.pages, 2);
+    assert!(collected.truncated);
+}
+
+#[tokio::test]
+async fn test_rate_limited_upstream() {
+    let base_url = format!("http://{}", start_mock_api_server().await);
+    let host = base_url.trim_start_matches("http://").to_string();
+    let limiter = Arc::new(UpstreamRateLimiter::new());
+    let client = HttpApiProxy::new(HttpClientConfig::default()).unwrap().with_rate_limiter(limiter.clone());
+    let request = |path: &str| HttpRequest {
+        method: "GET".to_string(),
+        path: path.to_string(),
+        query_params: HashMap::new(),
+        path_params: HashMap::new(),
+        headers: HashMap::new(),
+        body: None,
+        multipart: None,
+    };
+
+    // 429 后按 Retry-After 暂停该主机，再重试成功
+    let response = client.send_request(&base_url, &request("/throttled")).await.unwrap();
+    assert_eq!(response.status, 200);
+    assert!(response.queue_wait_ms >= 900, "waited {}ms", response.queue_wait_ms);
+
+    // 文档声明的限速：每秒 10 次、突发 1 次，第三个请求至少排队约 200ms
+    limiter.set_limit(&host, RateLimit { requests_per_second: 10.0, burst: 1 });
+    let start = std::time::Instant::now();
+    for _ in 0..3 {
+        client.send_request(&base_url, &request("/health")).await.unwrap();
+    }
+    assert!(start.elapsed() >= Duration::from_millis(180));
+
+    let metrics = client.rate_limit_metrics();
+    assert_eq!(metrics.len(), 1);
+    assert_eq!(metrics[0].host, host);
+    assert_eq!(metrics[0].throttled_responses, 1);
+    assert_eq!(metrics[0].requests, 5);
+    assert!(metrics[0].queued_requests >= 3);
+}
+#[tokio::test]
+async fn test_golden_record_and_verify() {
+    use stepflow_openapi::document::{MediaTypeInfo, OperationInfo, ResponseInfo};
+    use stepflow_openapi::{GoldenFindingKind, GoldenHarness, OpenApiTool, OpenApiToolConfig, Srn};
+
+    let base_url = format!("http://{}", start_mock_api_server().await);
+    let schema = json!({
+        "type": "object",
+        "required": ["users"],
+        "properties": {
+            "users": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["id", "name"],
+                    "properties": {"id": {"type": "integer"}, "name": {"type": "string"}}
+                }
+            }
+        }
+    });
+    let operation = OperationInfo {
+        srn: Srn::openapi_operation("tenant", "mock", "getUsers").unwrap(),
+        operation_id: "getUsers".to_string(),
+        method: "GET".to_string(),
+        path: "/users".to_string(),
+        summary: None,
+        description: None,
+        parameters: vec![],
+        request_body: None,
+        responses: HashMap::from([("200".to_string(), ResponseInfo {
+            description: "Users".to_string(),
+            content: Some(HashMap::from([("application/json".to_string(), MediaTypeInfo {
+                schema: Some(schema),
+                encoding: HashMap::new(),
+            })])),
+            headers: None,
+        })]),
+        tags: vec![],
+        extensions: HashMap::new(),
+    };
+    let config: OpenApiToolConfig = serde_json::from_value(json!({
+        "srn": "stepflow:openapi:tenant:mock:operation:getUsers",
+        "base_url": base_url,
+        "timeout_ms": 5000,
+        "max_retries": 0,
+        "default_headers": {"Authorization": "Bearer secret"},
+        "auth": null
+    }))
+    .unwrap();
+    let tool = OpenApiTool::with_resolved_document(config, operation, Arc::new(Value::Null)).unwrap();
+    let harness = GoldenHarness::new(tool);
+
+    let fixture = harness.record("list-users", json!({})).await.unwrap();
+    assert!(fixture.expected.success);
+    assert_eq!(fixture.interactions.len(), 1);
+    assert_eq!(fixture.interactions[0].request.headers["authorization"], "[REDACTED]");
+
+    // 回放录制的响应，结果一致
+    let report = harness.verify(&fixture).await.unwrap();
+    assert!(report.passed, "{:?}", report.findings);
+
+    // 上游改变了字段类型：回放报告输出变化和与文档 schema 的偏离
+    let mut drifted = fixture.clone();
+    drifted.interactions[0].response.body = Some(json!({"users": [{"id": "1", "name": "Alice"}]}));
+    let report = harness.verify(&drifted).await.unwrap();
+    assert!(!report.passed);
+    let kinds: Vec<GoldenFindingKind> = report.findings.iter().map(|finding| finding.kind).collect();
+    assert_eq!(kinds, [GoldenFindingKind::OutputChanged, GoldenFindingKind::SchemaDrift]);
+
+    // 对比真实上游，发现响应结构与录制时不同
+    let report = harness.verify_live(&drifted).await.unwrap();
+    assert!(report.findings.iter().any(|finding| finding.kind == GoldenFindingKind::ResponseChanged
+        && finding.message == "/users/0/id changed from string to number"));
+}
+
+#[tokio::test]
+async fn test_contract_verification() {
+    use stepflow_openapi::document::{DocumentFormat, DocumentUploadRequest, InMemoryDocumentStorage};
+    use stepflow_openapi::{ConformanceConfig, ConformanceError, ConformanceTarget, ContractVerifier, DriftAlert, DriftNotifier, DriftStatus};
+
+    struct Collect(std::sync::Mutex<Vec<DriftAlert>>);
+
+    #[async_trait::async_trait]
+    impl DriftNotifier for Collect {
+        async fn notify(&self, alert: &DriftAlert) -> Result<(), ConformanceError> {
+            self.0.lock().unwrap().push(alert.clone());
+            Ok(())
+        }
+    }
+
+    let base_url = format!("http://{}", start_mock_api_server().await);
+    let spec = json!({
+        "openapi": "3.0.0",
+        "info": {"title": "Mock API", "version": "1.0.0"},
+        "servers": [{"url": base_url}],
+        "paths": {
+            "/users": {
+                "get": {
+                    "operationId": "getUsers",
+                    "responses": {"200": {"description": "Users", "content": {"application/json": {"schema": {
+                        "type": "object",
+                        "properties": {"users": {"type": "array", "items": {"$ref": "#/components/schemas/User"}}}
+                    }}}}}
+                },
+                "post": {"operationId": "createUser", "responses": {"201": {"description": "Created"}}}
+            },
+            "/health": {
+                "get": {
+                    "operationId": "getHealth",
+                    "responses": {"200": {"description": "Health", "content": {"application/json": {"schema": {
+                        "type": "object",
+                        "required": ["status", "uptime"],
+                        "properties": {"status": {"type": "string"}, "uptime": {"type": "integer"}}
+                    }}}}}
+                }
+            }
+        },
+        "components": {"schemas": {"User": {
+            "type": "object",
+            "required": ["id", "name"],
+            "properties": {"id": {"type": "integer"}, "name": {"type": "string"}}
+        }}}
+    });
+
+    let documents = Arc::new(DocumentManager::new(Box::new(InMemoryDocumentStorage::default())));
+    let uploaded = documents.upload_document(DocumentUploadRequest {
+        name: "mock-api".to_string(),
+        namespace: "contract".to_string(),
+        tenant_id: "tenant".to_string(),
+        content: spec.to_string(),
+        format: DocumentFormat::Json,
+        description: None,
+    })
+    .await
+    .unwrap();
+
+    let notifier = Arc::new(Collect(std::sync::Mutex::new(Vec::new())));
+    let verifier = ContractVerifier::new(documents, ConformanceConfig::default())
+        .with_rate_limiter(Arc::new(UpstreamRateLimiter::new()))
+        .with_notifier(notifier.clone());
+    verifier.watch(ConformanceTarget::new(uploaded.document_id.clone())).await;
+
+    let report = verifier.verify_document(&uploaded.document_id).await.unwrap();
+    let mut checked: Vec<&str> = report.checks.iter().map(|check| check.operation_id.as_str()).collect();
+    checked.sort();
+    assert_eq!(checked, ["getHealth", "getUsers"]);
+
+    let scores = verifier.scores(&uploaded.document_id).await;
+    assert_eq!(scores.len(), 2);
+    assert_eq!((scores[0].operation_id.as_str(), scores[0].score, scores[0].drifting), ("getHealth", 0.0, true));
+    assert_eq!((scores[1].operation_id.as_str(), scores[1].score, scores[1].drifting), ("getUsers", 1.0, false));
+
+    let alerts = notifier.0.lock().unwrap().clone();
+    assert_eq!(alerts.len(), 1);
+    assert_eq!(alerts[0].status, DriftStatus::Drifting);
+    assert_eq!(alerts[0].operation_id, "getHealth");
+
+    // 仍然偏离时不重复告警
+    verifier.verify_all().await;
+    assert_eq!(notifier.0.lock().unwrap().len(), 1);
+}
+
+#[cfg(feature = "fault-injection")]
+#[tokio::test]
+async fn test_injected_faults_retried_and_cancelled() {
+    use stepflow_core::{FaultConfig, FaultController, FaultTarget};
+
+    // 只统计到达上游的请求数
+    let hits = Arc::new(AtomicUsize::new(0));
+    let app = Router::new().route("/counted", get({
+        let hits = hits.clone();
+        move || async move {
+            hits.fetch_add(1, Ordering::SeqCst);
+            Json(json!({"status": "ok"}))
+        }
+    }));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    let faults = FaultController::new(11);
+    let client = HttpApiProxy::new(HttpClientConfig { max_retries: 2, ..HttpClientConfig::default() })
+        .unwrap()
+        .with_fault_controller(faults.clone());
+    let request = HttpRequest {
+        method: "GET".to_string(),
+        path: "/counted".to_string(),
+        query_params: HashMap::new(),
+        path_params: HashMap::new(),
+        headers: HashMap::new(),
+        body: None,
+        multipart: None,
+    };
+
+    // 连接错误重试到上限后返回，请求从未到达上游
+    faults.set(FaultTarget::HttpProxy, FaultConfig::always_fail());
+    assert!(client.send_request(&base_url, &request).await.is_err());
+    assert_eq!(faults.stats(FaultTarget::HttpProxy).errors, 3);
+    assert_eq!(hits.load(Ordering::SeqCst), 0);
+
+    // 响应丢失时每次重试都会再次到达上游
+    faults.set(FaultTarget::HttpProxy, FaultConfig::partial_writes(1.0));
+    assert!(client.send_request(&base_url, &request).await.is_err());
+    assert_eq!(hits.load(Ordering::SeqCst), 3);
+
+    // 间歇性故障由重试恢复
+    faults.set(FaultTarget::HttpProxy, FaultConfig::errors(0.3));
+    for _ in 0..3 {
+        assert_eq!(client.send_request(&base_url, &request).await.unwrap().status, 200);
+    }
+
+    // 注入的延迟期间取消请求，上游不会收到它
+    let before = hits.load(Ordering::SeqCst);
+    faults.set(FaultTarget::HttpProxy, FaultConfig::latency(1.0, Duration::from_secs(5)));
+    assert!(tokio::time::timeout(Duration::from_millis(50), client.send_request(&base_url, &request)).await.is_err());
+    faults.clear(FaultTarget::HttpProxy);
+    sleep(Duration::from_millis(50)).await;
+    assert_eq!(hits.load(Ordering::SeqCst), before);
+    assert_eq!(client.send_request(&base_url, &request).await.unwrap().status, 200);
+    assert_eq!(hits.load(Ordering::SeqCst), before + 1);
+}
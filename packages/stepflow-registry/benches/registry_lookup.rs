@@ -0,0 +1,53 @@
+//! Registry lookup microbenchmarks
+//!
+//! Run with `cargo bench -p stepflow-registry`.
+
+use std::sync::Arc;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use stepflow_core::{ToolId, ToolInfo, ToolType};
+use stepflow_database::{MigrationManager, SqliteDatabase};
+use stepflow_registry::{Registry, RegistryImpl};
+
+const TOOL_COUNT: usize = 1_000;
+
+fn tool(id: usize) -> ToolInfo {
+    ToolInfo::builder()
+        .id(ToolId::from_string(format!("bench-tool-{}", id)))
+        .name(format!("Bench Tool {}", id))
+        .description(format!("Benchmark tool number {}", id))
+        .tool_type(if id.is_multiple_of(2) { ToolType::Python } else { ToolType::Shell })
+        .author("stepflow")
+        .tags(["bench".to_string(), format!("group-{}", id % 10)])
+        .build()
+        .unwrap()
+}
+
+fn registry_benchmarks(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let registry = runtime.block_on(async {
+        let database = Arc::new(SqliteDatabase::new("sqlite::memory:").await.unwrap());
+        MigrationManager::run_migrations(&database).await.unwrap();
+        let registry = RegistryImpl::new(database).await.unwrap();
+        for id in 0..TOOL_COUNT {
+            registry.register_tool(tool(id)).await.unwrap();
+        }
+        registry
+    });
+
+    let id = ToolId::from_string(format!("bench-tool-{}", TOOL_COUNT / 2));
+    c.bench_function("registry_get_tool", |b| {
+        b.iter(|| runtime.block_on(registry.get_tool(&id)).unwrap())
+    });
+
+    c.bench_function("registry_search_tools", |b| {
+        b.iter(|| runtime.block_on(registry.search_tools("Bench Tool 99")).unwrap())
+    });
+
+    c.bench_function("registry_get_tools_by_type", |b| {
+        b.iter(|| runtime.block_on(registry.get_tools_by_type(&ToolType::Python)).unwrap())
+    });
+}
+
+criterion_group!(benches, registry_benchmarks);
+criterion_main!(benches);
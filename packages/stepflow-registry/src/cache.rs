@@ -1,7 +1,13 @@
 //! Cache system implementation
 
 use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
+use async_trait::async_trait;
+use serde::{de::DeserializeOwned, Serialize};
+use stepflow_core::{CacheStats, Metric, ToolId, ToolInfo};
 use tokio::sync::RwLock;
 
 /// Cache entry
@@ -30,11 +36,35 @@ impl CacheEntry {
     }
 }
 
+/// String key-value store behind [`RegistryCache`]
+///
+/// [`Cache`] keeps entries in process; with the `redis` feature
+/// [`RedisCache`](crate::redis_cache::RedisCache) shares them between instances.
+/// Backends treat their own failures as misses, so a cache outage only costs lookups.
+#[async_trait]
+pub trait CacheBackend: Send + Sync {
+    async fn get(&self, key: &str) -> Option<String>;
+    async fn put(&self, key: String, value: String);
+    async fn remove(&self, key: &str);
+    /// Remove every value whose key starts with `prefix`
+    async fn remove_prefix(&self, prefix: &str);
+    async fn clear(&self);
+    async fn stats(&self) -> CacheStats;
+}
+
+impl fmt::Debug for dyn CacheBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CacheBackend")
+    }
+}
+
 /// Simple cache implementation
 pub struct Cache {
     data: RwLock<HashMap<String, CacheEntry>>,
     default_ttl: Duration,
     max_size: usize,
+    hits: AtomicU64,
+    misses: AtomicU64,
 }
 
 impl Cache {
@@ -44,6 +74,8 @@ impl Cache {
             data: RwLock::new(HashMap::new()),
             default_ttl,
             max_size,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
         }
     }
     
@@ -52,9 +84,11 @@ impl Cache {
         let data = self.data.read().await;
         if let Some(entry) = data.get(key) {
             if !entry.is_expired() {
+                self.hits.fetch_add(1, Ordering::Relaxed);
                 return Some(entry.value.clone());
             }
         }
+        self.misses.fetch_add(1, Ordering::Relaxed);
         None
     }
     
@@ -81,6 +115,12 @@ impl Cache {
         data.remove(key);
     }
     
+    /// Remove every value whose key starts with `prefix`
+    pub async fn remove_prefix(&self, prefix: &str) {
+        let mut data = self.data.write().await;
+        data.retain(|key, _| !key.starts_with(prefix));
+    }
+    
     /// Clear all cache entries
     pub async fn clear(&self) {
         let mut data = self.data.write().await;
@@ -102,4 +142,138 @@ impl Cache {
             false
         }
     }
-} 
\ No newline at end of file
+    
+    /// Get hit, miss and size statistics
+    pub async fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            size: self.size().await,
+            max_size: self.max_size,
+        }
+    }
+}
+
+#[async_trait]
+impl CacheBackend for Cache {
+    async fn get(&self, key: &str) -> Option<String> {
+        Cache::get(self, key).await
+    }
+
+    async fn put(&self, key: String, value: String) {
+        Cache::put(self, key, value).await
+    }
+
+    async fn remove(&self, key: &str) {
+        Cache::remove(self, key).await
+    }
+
+    async fn remove_prefix(&self, prefix: &str) {
+        Cache::remove_prefix(self, prefix).await
+    }
+
+    async fn clear(&self) {
+        Cache::clear(self).await
+    }
+
+    async fn stats(&self) -> CacheStats {
+        Cache::stats(self).await
+    }
+}
+
+/// Cache key prefix of tool lists (list, search, by type, by status)
+const LIST_PREFIX: &str = "list:";
+
+/// Read-through cache of tools and tool lists
+///
+/// Single tools are keyed by id; list results share the `list:` prefix so any write
+/// drops them all, since a changed tool may enter or leave any list.
+pub struct RegistryCache {
+    cache: Arc<dyn CacheBackend>,
+}
+
+impl RegistryCache {
+    /// Create a registry cache kept in process
+    pub fn new(max_size: usize, ttl: Duration) -> Self {
+        Self::with_backend(Arc::new(Cache::new(max_size, ttl)))
+    }
+    
+    /// Create a registry cache on top of another backend, e.g. one shared between instances
+    pub fn with_backend(cache: Arc<dyn CacheBackend>) -> Self {
+        Self { cache }
+    }
+    
+    /// Get a cached tool
+    pub async fn get_tool(&self, tool_id: &ToolId) -> Option<ToolInfo> {
+        self.get(&Self::tool_key(tool_id)).await
+    }
+    
+    /// Cache a tool
+    pub async fn put_tool(&self, tool: &ToolInfo) {
+        self.put(Self::tool_key(&tool.id), tool).await;
+    }
+    
+    /// Get a cached tool list
+    pub async fn get_list(&self, key: &str) -> Option<Vec<ToolInfo>> {
+        self.get(&format!("{}{}", LIST_PREFIX, key)).await
+    }
+    
+    /// Cache a tool list
+    pub async fn put_list(&self, key: &str, tools: &[ToolInfo]) {
+        self.put(format!("{}{}", LIST_PREFIX, key), tools).await;
+    }
+    
+    /// Drop a tool and every cached list after a write
+    pub async fn invalidate_tool(&self, tool_id: &ToolId) {
+        self.cache.remove(&Self::tool_key(tool_id)).await;
+        self.cache.remove_prefix(LIST_PREFIX).await;
+    }
+    
+    /// Drop everything
+    pub async fn clear(&self) {
+        self.cache.clear().await;
+    }
+    
+    /// Get cache statistics
+    pub async fn stats(&self) -> CacheStats {
+        self.cache.stats().await
+    }
+    
+    /// Cache statistics as metrics for monitoring exporters
+    pub async fn metrics(&self) -> Vec<Metric> {
+        let stats = self.stats().await;
+        let lookups = stats.hits + stats.misses;
+        let hit_rate = if lookups == 0 { 0.0 } else { stats.hits as f64 / lookups as f64 };
+        let timestamp = chrono::Utc::now();
+        [
+            ("registry_cache_hits", stats.hits as f64),
+            ("registry_cache_misses", stats.misses as f64),
+            ("registry_cache_hit_rate", hit_rate),
+            ("registry_cache_size", stats.size as f64),
+            ("registry_cache_max_size", stats.max_size as f64),
+        ]
+        .into_iter()
+        .map(|(name, value)| Metric {
+            name: name.to_string(),
+            value,
+            labels: HashMap::new(),
+            timestamp,
+        })
+        .collect()
+    }
+    
+    fn tool_key(tool_id: &ToolId) -> String {
+        format!("tool:{}", tool_id)
+    }
+    
+    async fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let value = self.cache.get(key).await?;
+        serde_json::from_str(&value).ok()
+    }
+    
+    async fn put<T: Serialize + ?Sized>(&self, key: String, value: &T) {
+        if let Ok(value) = serde_json::to_string(value) {
+            self.cache.put(key, value).await;
+        }
+    }
+}
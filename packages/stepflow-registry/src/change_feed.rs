@@ -0,0 +1,242 @@
+//! Registry change data capture
+//!
+//! Every tool mutation made through `RegistryImpl` is appended to the
+//! `registry_changes` table with a monotonically increasing sequence number.
+//! Consumers page through the feed with `changes_since(seq)` and remember the
+//! last sequence they applied, so caches and search indexes can stay in sync
+//! without re-scanning the registry. The feed is also exposed over RPC, both
+//! as a `registry.changes` method and as `registry.tool.*` events.
+
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use stepflow_core::*;
+use stepflow_database::{RegistryChangeRecord, RegistryChangeRepository, SqliteDatabase};
+use stepflow_rpc::{EventPublisher, RpcError, RpcHandler};
+use tokio::task::JoinHandle;
+use tracing::warn;
+use crate::errors::*;
+
+/// Default and maximum page size for change queries
+pub const DEFAULT_CHANGE_PAGE_SIZE: usize = 100;
+pub const MAX_CHANGE_PAGE_SIZE: usize = 1000;
+
+/// Kind of registry change
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolChangeType {
+    Created,
+    Updated,
+    Deleted,
+    VersionAdded,
+}
+
+impl ToolChangeType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ToolChangeType::Created => "created",
+            ToolChangeType::Updated => "updated",
+            ToolChangeType::Deleted => "deleted",
+            ToolChangeType::VersionAdded => "version_added",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "created" => Some(ToolChangeType::Created),
+            "updated" => Some(ToolChangeType::Updated),
+            "deleted" => Some(ToolChangeType::Deleted),
+            "version_added" => Some(ToolChangeType::VersionAdded),
+            _ => None,
+        }
+    }
+}
+
+/// A single registry change
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolChange {
+    pub sequence: u64,
+    pub change_type: ToolChangeType,
+    pub tool_id: ToolId,
+    pub version: String,
+    /// Tool state after the change; `None` for deletions
+    pub tool: Option<ToolInfo>,
+    pub changed_at: DateTime<Utc>,
+}
+
+impl ToolChange {
+    fn from_record(record: RegistryChangeRecord) -> RegistryResult<Self> {
+        let change_type = ToolChangeType::parse(&record.change_type).ok_or_else(|| {
+            RegistryError::DatabaseError(format!("Unknown change type '{}'", record.change_type))
+        })?;
+        let tool = record.payload.map(serde_json::from_value).transpose()?;
+
+        Ok(Self {
+            sequence: record.sequence as u64,
+            change_type,
+            tool_id: record.tool_id,
+            version: record.version,
+            tool,
+            changed_at: record.created_at,
+        })
+    }
+
+    /// RPC event name for this change, e.g. `registry.tool.created`
+    pub fn event_name(&self) -> String {
+        format!("registry.tool.{}", self.change_type.as_str())
+    }
+
+    /// The change as a CloudEvent, with the same id as when relayed to event sinks
+    pub fn to_cloud_event(&self) -> CloudEvent<ToolChanged> {
+        let data = ToolChanged {
+            sequence: self.sequence,
+            change_type: self.change_type.as_str().to_string(),
+            tool_id: self.tool_id.to_string(),
+            version: self.version.clone(),
+            tool: self.tool.as_ref().and_then(|tool| serde_json::to_value(tool).ok()),
+            changed_at: self.changed_at,
+        };
+        CloudEvent::new(data).with_time(self.changed_at)
+    }
+}
+
+/// A page of changes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangePage {
+    pub changes: Vec<ToolChange>,
+    /// Sequence to pass as `since` for the next page
+    pub next_since: u64,
+    pub latest_sequence: u64,
+    pub has_more: bool,
+}
+
+/// Registry change feed
+pub struct ChangeFeed {
+    repository: RegistryChangeRepository,
+}
+
+impl ChangeFeed {
+    /// Create a change feed backed by the given database
+    pub fn new(db: Arc<SqliteDatabase>) -> Self {
+        Self {
+            repository: RegistryChangeRepository::new(db.as_ref().clone()),
+        }
+    }
+
+    /// Record a change for a tool
+    pub async fn record(&self, change_type: ToolChangeType, tool: &ToolInfo) -> RegistryResult<ToolChange> {
+        let payload = match change_type {
+            ToolChangeType::Deleted => None,
+            _ => Some(serde_json::to_value(tool)?),
+        };
+
+        let record = self.repository
+            .record_change(change_type.as_str(), &tool.id, &tool.version.to_string(), payload.as_ref())
+            .await?;
+        ToolChange::from_record(record)
+    }
+
+    /// Changes with a sequence number greater than `since`, oldest first
+    pub async fn changes_since(&self, since: u64, limit: Option<usize>) -> RegistryResult<ChangePage> {
+        let limit = limit.unwrap_or(DEFAULT_CHANGE_PAGE_SIZE).clamp(1, MAX_CHANGE_PAGE_SIZE);
+
+        // Fetch one extra row to know whether another page follows
+        let mut records = self.repository.list_changes_since(since as i64, limit + 1).await?;
+        let has_more = records.len() > limit;
+        records.truncate(limit);
+
+        let changes = records
+            .into_iter()
+            .map(ToolChange::from_record)
+            .collect::<RegistryResult<Vec<_>>>()?;
+        let next_since = changes.last().map(|c| c.sequence).unwrap_or(since);
+        let latest_sequence = self.latest_sequence().await?;
+
+        Ok(ChangePage {
+            changes,
+            next_since,
+            latest_sequence,
+            has_more,
+        })
+    }
+
+    /// Latest recorded sequence number
+    pub async fn latest_sequence(&self) -> RegistryResult<u64> {
+        Ok(self.repository.latest_sequence().await? as u64)
+    }
+
+    /// Poll the feed and publish each new change as a `registry.tool.*` event
+    /// carrying the change as a CloudEvent.
+    ///
+    /// Publishing starts after `since`; events carry the change sequence so
+    /// subscribers can fall back to `registry.changes` to fill any gap.
+    pub fn forward_to(self: Arc<Self>, publisher: EventPublisher, since: u64, poll_interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut cursor = since;
+            let mut ticker = tokio::time::interval(poll_interval);
+            loop {
+                ticker.tick().await;
+                loop {
+                    let page = match self.changes_since(cursor, None).await {
+                        Ok(page) => page,
+                        Err(e) => {
+                            warn!("Failed to read registry changes after {}: {}", cursor, e);
+                            break;
+                        }
+                    };
+
+                    for change in &page.changes {
+                        let data = serde_json::to_value(change.to_cloud_event()).unwrap_or(Value::Null);
+                        let stream_id = ChangeFeedRpcHandler::METHOD.to_string();
+                        if let Err(e) = publisher.publish_sequenced(&change.event_name(), data, stream_id, change.sequence).await {
+                            warn!("Failed to publish registry change {}: {}", change.sequence, e);
+                        }
+                    }
+
+                    cursor = page.next_since;
+                    if !page.has_more {
+                        break;
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Serves `registry.changes` (`{since, limit?}`) over RPC
+pub struct ChangeFeedRpcHandler {
+    feed: Arc<ChangeFeed>,
+}
+
+impl ChangeFeedRpcHandler {
+    pub const METHOD: &'static str = "registry.changes";
+
+    pub fn new(feed: Arc<ChangeFeed>) -> Self {
+        Self { feed }
+    }
+}
+
+#[async_trait::async_trait]
+impl RpcHandler for ChangeFeedRpcHandler {
+    async fn handle(&self, method: &str, params: Option<Value>) -> Result<Value, RpcError> {
+        if method != Self::METHOD {
+            return Err(RpcError::method_not_found(method));
+        }
+
+        let params = params.unwrap_or(Value::Null);
+        let since = params.get("since").and_then(Value::as_u64).unwrap_or(0);
+        let limit = params.get("limit").and_then(Value::as_u64).map(|l| l as usize);
+
+        let page = self.feed
+            .changes_since(since, limit)
+            .await
+            .map_err(|e| RpcError::internal_error(&e.to_string()))?;
+        serde_json::to_value(page).map_err(|e| RpcError::internal_error(&e.to_string()))
+    }
+
+    fn methods(&self) -> Vec<String> {
+        vec![Self::METHOD.to_string()]
+    }
+}
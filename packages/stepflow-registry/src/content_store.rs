@@ -0,0 +1,270 @@
+//! Content-addressable blob storage
+//!
+//! Blobs are addressed by the SHA-256 digest of their bytes, so uploading the
+//! same tool bundle or artifact twice stores it once. Every owner of a digest
+//! holds a reference; releasing the last one leaves the blob unreferenced
+//! until [`ContentStore::gc`] removes it. Tool packages are stored per tool
+//! version in a [`ToolPackageStore`] on top of a shared [`ContentStore`].
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use stepflow_core::*;
+
+use crate::errors::*;
+
+/// Hex-encoded SHA-256 digest of `data`
+pub fn content_digest(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+/// Storage and deduplication statistics
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ContentStoreStats {
+    /// Distinct blobs stored
+    pub blobs: usize,
+    /// Blobs with no remaining references, removed by the next GC
+    pub unreferenced_blobs: usize,
+    /// References held across all blobs
+    pub references: u64,
+    /// Bytes actually stored
+    pub stored_bytes: u64,
+    /// Bytes the references would take without deduplication
+    pub logical_bytes: u64,
+    /// Uploads that matched an existing blob
+    pub dedup_hits: u64,
+}
+
+impl ContentStoreStats {
+    /// Bytes saved by deduplication
+    pub fn saved_bytes(&self) -> u64 {
+        self.logical_bytes.saturating_sub(self.stored_bytes)
+    }
+}
+
+/// Outcome of a garbage collection pass
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GcReport {
+    pub removed_blobs: usize,
+    pub freed_bytes: u64,
+}
+
+struct Blob {
+    data: Arc<[u8]>,
+    references: u64,
+}
+
+#[derive(Default)]
+struct ContentState {
+    blobs: HashMap<String, Blob>,
+    dedup_hits: u64,
+}
+
+/// Reference-counted, SHA-256 addressed blob store
+#[derive(Default)]
+pub struct ContentStore {
+    state: RwLock<ContentState>,
+}
+
+impl ContentStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn read(&self) -> RegistryResult<RwLockReadGuard<'_, ContentState>> {
+        self.state.read().map_err(|_| RegistryError::InternalError("Content store lock poisoned".to_string()))
+    }
+
+    fn write(&self) -> RegistryResult<RwLockWriteGuard<'_, ContentState>> {
+        self.state.write().map_err(|_| RegistryError::InternalError("Content store lock poisoned".to_string()))
+    }
+
+    /// Store `data` and take a reference to it, returning its digest
+    ///
+    /// Data that is already stored is not copied again.
+    pub fn put(&self, data: &[u8]) -> RegistryResult<String> {
+        let digest = content_digest(data);
+        let mut state = self.write()?;
+        match state.blobs.get_mut(&digest) {
+            Some(blob) => {
+                blob.references += 1;
+                state.dedup_hits += 1;
+            }
+            None => {
+                state.blobs.insert(digest.clone(), Blob { data: Arc::from(data), references: 1 });
+            }
+        }
+        Ok(digest)
+    }
+
+    /// Take another reference to a stored blob
+    pub fn retain(&self, digest: &str) -> RegistryResult<()> {
+        let mut state = self.write()?;
+        let blob = state.blobs
+            .get_mut(digest)
+            .ok_or_else(|| RegistryError::ResourceNotAvailable(format!("Blob {} not found", digest)))?;
+        blob.references += 1;
+        Ok(())
+    }
+
+    /// Drop a reference to a blob; unreferenced blobs are kept until the next GC
+    pub fn release(&self, digest: &str) -> RegistryResult<()> {
+        let mut state = self.write()?;
+        let blob = state.blobs
+            .get_mut(digest)
+            .ok_or_else(|| RegistryError::ResourceNotAvailable(format!("Blob {} not found", digest)))?;
+        if blob.references == 0 {
+            return Err(RegistryError::InvalidOperation(format!("Blob {} has no references to release", digest)));
+        }
+        blob.references -= 1;
+        Ok(())
+    }
+
+    /// Read a blob
+    pub fn get(&self, digest: &str) -> RegistryResult<Option<Arc<[u8]>>> {
+        Ok(self.read()?.blobs.get(digest).map(|blob| blob.data.clone()))
+    }
+
+    /// Whether a blob is stored
+    pub fn contains(&self, digest: &str) -> RegistryResult<bool> {
+        Ok(self.read()?.blobs.contains_key(digest))
+    }
+
+    /// References held to a blob, or `None` if it is not stored
+    pub fn references(&self, digest: &str) -> RegistryResult<Option<u64>> {
+        Ok(self.read()?.blobs.get(digest).map(|blob| blob.references))
+    }
+
+    /// Remove every blob without references
+    pub fn gc(&self) -> RegistryResult<GcReport> {
+        let mut state = self.write()?;
+        let mut report = GcReport::default();
+        state.blobs.retain(|_, blob| {
+            if blob.references > 0 {
+                return true;
+            }
+            report.removed_blobs += 1;
+            report.freed_bytes += blob.data.len() as u64;
+            false
+        });
+        Ok(report)
+    }
+
+    /// Current storage and deduplication statistics
+    pub fn stats(&self) -> RegistryResult<ContentStoreStats> {
+        let state = self.read()?;
+        let mut stats = ContentStoreStats {
+            blobs: state.blobs.len(),
+            dedup_hits: state.dedup_hits,
+            ..ContentStoreStats::default()
+        };
+        for blob in state.blobs.values() {
+            let size = blob.data.len() as u64;
+            if blob.references == 0 {
+                stats.unreferenced_blobs += 1;
+            }
+            stats.references += blob.references;
+            stats.stored_bytes += size;
+            stats.logical_bytes += size * blob.references;
+        }
+        Ok(stats)
+    }
+}
+
+/// A stored tool package
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolPackage {
+    pub tool_id: ToolId,
+    pub version: String,
+    pub content_type: String,
+    /// SHA-256 digest of the package in the content store
+    pub digest: String,
+    pub size: usize,
+    pub uploaded_at: DateTime<Utc>,
+}
+
+/// Tool code bundles and images, one per tool version, deduplicated by content
+pub struct ToolPackageStore {
+    content: Arc<ContentStore>,
+    packages: RwLock<HashMap<(ToolId, String), ToolPackage>>,
+}
+
+impl ToolPackageStore {
+    /// Create a package store backed by `content`
+    pub fn new(content: Arc<ContentStore>) -> Self {
+        Self {
+            content,
+            packages: RwLock::new(HashMap::new()),
+        }
+    }
+
+    fn lock_error() -> RegistryError {
+        RegistryError::InternalError("Tool package store lock poisoned".to_string())
+    }
+
+    /// Store the package of a tool version, replacing any previous upload
+    pub fn put(&self, tool_id: &ToolId, version: &ToolVersion, content_type: &str, data: &[u8]) -> RegistryResult<ToolPackage> {
+        let digest = self.content.put(data)?;
+        let package = ToolPackage {
+            tool_id: tool_id.clone(),
+            version: version.to_string(),
+            content_type: content_type.to_string(),
+            digest,
+            size: data.len(),
+            uploaded_at: Utc::now(),
+        };
+        let replaced = self.packages
+            .write()
+            .map_err(|_| Self::lock_error())?
+            .insert((tool_id.clone(), package.version.clone()), package.clone());
+        if let Some(replaced) = replaced {
+            self.content.release(&replaced.digest)?;
+        }
+        Ok(package)
+    }
+
+    /// Package metadata of a tool version
+    pub fn get(&self, tool_id: &ToolId, version: &ToolVersion) -> RegistryResult<Option<ToolPackage>> {
+        let packages = self.packages.read().map_err(|_| Self::lock_error())?;
+        Ok(packages.get(&(tool_id.clone(), version.to_string())).cloned())
+    }
+
+    /// Package bytes of a tool version
+    pub fn read(&self, tool_id: &ToolId, version: &ToolVersion) -> RegistryResult<Option<Arc<[u8]>>> {
+        match self.get(tool_id, version)? {
+            Some(package) => self.content.get(&package.digest),
+            None => Ok(None),
+        }
+    }
+
+    /// Packages of all versions of a tool
+    pub fn list(&self, tool_id: &ToolId) -> RegistryResult<Vec<ToolPackage>> {
+        let packages = self.packages.read().map_err(|_| Self::lock_error())?;
+        let mut listed: Vec<ToolPackage> = packages
+            .values()
+            .filter(|package| &package.tool_id == tool_id)
+            .cloned()
+            .collect();
+        listed.sort_by(|a, b| a.version.cmp(&b.version));
+        Ok(listed)
+    }
+
+    /// Remove the package of a tool version, returning whether it existed
+    pub fn delete(&self, tool_id: &ToolId, version: &ToolVersion) -> RegistryResult<bool> {
+        let removed = self.packages
+            .write()
+            .map_err(|_| Self::lock_error())?
+            .remove(&(tool_id.clone(), version.to_string()));
+        match removed {
+            Some(package) => {
+                self.content.release(&package.digest)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
@@ -2,18 +2,35 @@
 
 use std::sync::Arc;
 use stepflow_core::*;
-use stepflow_database::ToolRepository;
+use stepflow_database::{ToolRepository, FavoriteRepository};
 use crate::errors::*;
 
+/// Reorder tools so the given favorites come first (in favorite order), keeping
+/// the relative order of the remaining tools.
+pub(crate) fn order_favorites_first(tools: Vec<ToolInfo>, favorite_ids: &[ToolId]) -> Vec<ToolInfo> {
+    let (mut favorites, others): (Vec<_>, Vec<_>) = tools.into_iter()
+        .partition(|tool| favorite_ids.contains(&tool.id));
+    favorites.sort_by_key(|tool| favorite_ids.iter().position(|id| id == &tool.id));
+    favorites.extend(others);
+    favorites
+}
+
 /// Discovery service implementation
 pub struct DiscoveryService {
     tool_repository: Arc<ToolRepository>,
+    favorite_repository: Option<Arc<FavoriteRepository>>,
 }
 
 impl DiscoveryService {
     /// Create a new discovery service
     pub fn new(tool_repository: Arc<ToolRepository>) -> Self {
-        Self { tool_repository }
+        Self { tool_repository, favorite_repository: None }
+    }
+    
+    /// Enable per-user favorite ordering
+    pub fn with_favorite_repository(mut self, favorite_repository: Arc<FavoriteRepository>) -> Self {
+        self.favorite_repository = Some(favorite_repository);
+        self
     }
     
     /// Discover tools
@@ -21,6 +38,18 @@ impl DiscoveryService {
         self.tool_repository.list_tools(None).await.map_err(Into::into)
     }
     
+    /// Discover tools for a user, with the user's favorites listed first
+    pub async fn discover_tools_for_user(&self, user_id: &UserId) -> RegistryResult<Vec<ToolInfo>> {
+        let tools = self.tool_repository.list_tools(None).await?;
+        match &self.favorite_repository {
+            Some(favorites) => {
+                let favorite_ids = favorites.list_favorite_ids(user_id).await?;
+                Ok(order_favorites_first(tools, &favorite_ids))
+            }
+            None => Ok(tools),
+        }
+    }
+    
     /// Search tools
     pub async fn search_tools(&self, query: &str) -> RegistryResult<Vec<ToolInfo>> {
         self.tool_repository.search_tools(query).await.map_err(Into::into)
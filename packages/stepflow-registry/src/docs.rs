@@ -0,0 +1,182 @@
+//! Tool documentation
+//!
+//! Tools carry markdown documentation per version, rendered to HTML on read.
+//! Rendered HTML is sanitized, so raw HTML in the markdown cannot inject
+//! scripts or event handlers into the pages that display it.
+//!
+//! A fenced code block whose info string is `example <name>` embeds the tool
+//! example called `<name>`. An empty block is filled with the example's input,
+//! and the rendered snippet links to the tool's test runner so readers can run
+//! it. Blocks naming an unknown example are rendered as plain code.
+
+use std::sync::Arc;
+use pulldown_cmark::{html, CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+use stepflow_core::*;
+use stepflow_database::{SqliteDatabase, ToolDocsRecord, ToolDocsRepository, ToolRepository};
+use crate::errors::*;
+
+/// Largest markdown document accepted, in bytes
+pub const MAX_DOCS_SIZE: usize = 256 * 1024;
+
+/// Documentation rendered to sanitized HTML
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RenderedDocs {
+    pub tool_id: ToolId,
+    pub version: String,
+    pub html: String,
+    /// Names of the embedded examples that can be run from the page
+    pub examples: Vec<String>,
+}
+
+/// Render `markdown` documenting `tool` to sanitized HTML
+pub fn render_tool_docs(tool: &ToolInfo, version: &str, markdown: &str) -> RenderedDocs {
+    let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let mut events = Vec::new();
+    let mut examples = Vec::new();
+    let mut example: Option<(String, String)> = None;
+
+    for event in Parser::new_ext(markdown, options) {
+        match (&mut example, event) {
+            (None, Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info)))) => match example_name(&info) {
+                Some(name) => example = Some((name, String::new())),
+                None => events.push(Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info)))),
+            },
+            (Some((_, body)), Event::Text(text)) => body.push_str(&text),
+            (Some(_), Event::End(TagEnd::CodeBlock)) => {
+                let Some((name, body)) = example.take() else { continue };
+                let known = tool.examples.iter().find(|e| e.name == name);
+                if known.is_some() && !examples.contains(&name) {
+                    examples.push(name.clone());
+                }
+                events.push(Event::Html(example_html(tool, version, &name, &body, known).into()));
+            }
+            (_, event) => events.push(event),
+        }
+    }
+
+    let mut unsafe_html = String::new();
+    html::push_html(&mut unsafe_html, events.into_iter());
+
+    RenderedDocs {
+        tool_id: tool.id.clone(),
+        version: version.to_string(),
+        html: sanitize_html(&unsafe_html),
+        examples,
+    }
+}
+
+/// Name from an `example <name>` code block info string
+fn example_name(info: &str) -> Option<String> {
+    let mut words = info.split_whitespace();
+    match (words.next(), words.next()) {
+        (Some("example"), Some(name)) => Some(name.to_string()),
+        _ => None,
+    }
+}
+
+fn example_html(tool: &ToolInfo, version: &str, name: &str, body: &str, known: Option<&ToolExample>) -> String {
+    let code = match known {
+        Some(example) if body.trim().is_empty() => {
+            serde_json::to_string_pretty(&example.input).unwrap_or_default()
+        }
+        _ => body.trim_end().to_string(),
+    };
+    let name = ammonia::clean_text(name);
+    let mut html = format!(
+        "<div class=\"stepflow-example\" data-example=\"{}\"><pre><code class=\"language-json\">{}</code></pre>",
+        name,
+        ammonia::clean_text(&code),
+    );
+    if known.is_some() {
+        html.push_str(&format!(
+            "<a class=\"stepflow-example-run\" href=\"/api/v1/tools/{}/test\" data-method=\"post\" data-example=\"{}\" data-version=\"{}\">Run example</a>",
+            ammonia::clean_text(tool.id.as_str()),
+            name,
+            ammonia::clean_text(version),
+        ));
+    }
+    html.push_str("</div>\n");
+    html
+}
+
+/// Strip everything but safe markup from rendered documentation
+pub fn sanitize_html(html: &str) -> String {
+    ammonia::Builder::default()
+        .add_tag_attributes("div", &["class", "data-example"])
+        .add_tag_attributes("a", &["class", "data-method", "data-example", "data-version"])
+        .add_tag_attributes("code", &["class"])
+        .clean(html)
+        .to_string()
+}
+
+/// Stores and renders tool documentation
+pub struct ToolDocsService {
+    tool_repository: Arc<ToolRepository>,
+    docs_repository: Arc<ToolDocsRepository>,
+}
+
+impl ToolDocsService {
+    /// Create a new tool documentation service
+    pub fn new(db: Arc<SqliteDatabase>) -> Self {
+        Self {
+            tool_repository: Arc::new(ToolRepository::new(db.as_ref().clone())),
+            docs_repository: Arc::new(ToolDocsRepository::new(db.as_ref().clone())),
+        }
+    }
+
+    /// Store the documentation of a tool version, defaulting to the tool's current version
+    pub async fn save_docs(
+        &self,
+        tool_id: &ToolId,
+        version: Option<&str>,
+        markdown: &str,
+        updated_by: Option<&str>,
+    ) -> RegistryResult<ToolDocsRecord> {
+        if markdown.len() > MAX_DOCS_SIZE {
+            return Err(RegistryError::ValidationFailed(vec![ValidationError::ValueOutOfRange(format!(
+                "documentation must be at most {} bytes, got {}",
+                MAX_DOCS_SIZE,
+                markdown.len()
+            ))]));
+        }
+        let tool = self.get_tool(tool_id).await?;
+        let version = version.map(str::to_string).unwrap_or_else(|| tool.version.to_string());
+        Ok(self.docs_repository.save_docs(tool_id, &version, markdown, updated_by).await?)
+    }
+
+    /// Stored documentation of `version`
+    ///
+    /// Without a version the documentation of the tool's current version is
+    /// returned, falling back to the most recently updated one.
+    pub async fn get_docs(&self, tool_id: &ToolId, version: Option<&str>) -> RegistryResult<Option<ToolDocsRecord>> {
+        let tool = self.get_tool(tool_id).await?;
+        self.docs_for(&tool, version).await
+    }
+
+    /// Stored documentation rendered to HTML, `None` if the version is undocumented
+    pub async fn render_docs(&self, tool_id: &ToolId, version: Option<&str>) -> RegistryResult<Option<RenderedDocs>> {
+        let tool = self.get_tool(tool_id).await?;
+        let record = self.docs_for(&tool, version).await?;
+        Ok(record.map(|record| render_tool_docs(&tool, &record.version, &record.markdown)))
+    }
+
+    pub async fn delete_docs(&self, tool_id: &ToolId, version: &str) -> RegistryResult<bool> {
+        Ok(self.docs_repository.delete_docs(tool_id, version).await?)
+    }
+
+    async fn docs_for(&self, tool: &ToolInfo, version: Option<&str>) -> RegistryResult<Option<ToolDocsRecord>> {
+        if let Some(version) = version {
+            return Ok(self.docs_repository.get_docs(&tool.id, version).await?);
+        }
+        match self.docs_repository.get_docs(&tool.id, &tool.version.to_string()).await? {
+            Some(record) => Ok(Some(record)),
+            None => Ok(self.docs_repository.latest_docs(&tool.id).await?),
+        }
+    }
+
+    async fn get_tool(&self, tool_id: &ToolId) -> RegistryResult<ToolInfo> {
+        self.tool_repository.get_tool(tool_id).await?
+            .ok_or_else(|| RegistryError::ToolNotFound(tool_id.to_string()))
+    }
+}
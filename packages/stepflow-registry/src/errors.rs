@@ -1,6 +1,6 @@
 //! Error types for the registry system
 
-use stepflow_core::{ValidationError, StepflowError};
+use stepflow_core::{ErrorCode, ValidationError, StepflowError};
 
 /// Registry error type
 #[derive(Debug, thiserror::Error)]
@@ -11,9 +11,15 @@ pub enum RegistryError {
     #[error("Database error: {0}")]
     DatabaseError(String),
     
-    #[error("Validation failed")]
+    #[error("Validation failed: {}", format_validation_errors(.0))]
     ValidationFailed(Vec<ValidationError>),
     
+    #[error("Listing not found: {0}")]
+    ListingNotFound(String),
+    
+    #[error("Subscription not found: {0}")]
+    SubscriptionNotFound(String),
+    
     #[error("Tool already exists: {0}")]
     ToolAlreadyExists(String),
     
@@ -46,29 +52,60 @@ pub enum RegistryError {
     
     #[error("Invalid operation: {0}")]
     InvalidOperation(String),
+    
+    #[error(transparent)]
+    Core(#[from] StepflowError),
+    
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
 }
 
-/// Registry result type
-pub type RegistryResult<T> = Result<T, RegistryError>;
+impl RegistryError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            RegistryError::ToolNotFound(_) => ErrorCode::ToolNotFound,
+            RegistryError::ListingNotFound(_) | RegistryError::SubscriptionNotFound(_) => ErrorCode::NotFound,
+            RegistryError::ValidationFailed(_) => ErrorCode::ValidationFailed,
+            RegistryError::ToolAlreadyExists(_) => ErrorCode::AlreadyExists,
+            RegistryError::VersionConflict(_) => ErrorCode::VersionMismatch,
+            RegistryError::PermissionDenied(_) => ErrorCode::PermissionDenied,
+            RegistryError::InvalidOperation(_) => ErrorCode::InvalidInput,
+            RegistryError::ResourceNotAvailable(_) => ErrorCode::Unavailable,
+            RegistryError::NetworkError(_) => ErrorCode::NetworkError,
+            RegistryError::TimeoutError(_) => ErrorCode::Timeout,
+            RegistryError::DatabaseError(_) => ErrorCode::DatabaseError,
+            RegistryError::ConfigurationError(_) => ErrorCode::ConfigurationError,
+            RegistryError::Core(e) => e.code(),
+            RegistryError::Json(_) => ErrorCode::InvalidInput,
+            RegistryError::DiscoveryError(_)
+            | RegistryError::CacheError(_)
+            | RegistryError::InternalError(_)
+            | RegistryError::Io(_) => ErrorCode::Internal,
+        }
+    }
 
-impl From<StepflowError> for RegistryError {
-    fn from(error: StepflowError) -> Self {
-        RegistryError::DatabaseError(error.to_string())
+    pub fn retryable(&self) -> bool {
+        match self {
+            RegistryError::Core(e) => e.retryable(),
+            _ => self.code().retryable(),
+        }
     }
-}
 
-impl From<serde_json::Error> for RegistryError {
-    fn from(error: serde_json::Error) -> Self {
-        RegistryError::InternalError(format!("JSON error: {}", error))
+    pub fn user_facing(&self) -> bool {
+        self.code().user_facing()
     }
 }
 
-impl From<std::io::Error> for RegistryError {
-    fn from(error: std::io::Error) -> Self {
-        RegistryError::InternalError(format!("IO error: {}", error))
-    }
+fn format_validation_errors(errors: &[ValidationError]) -> String {
+    errors.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("; ")
 }
 
+/// Registry result type
+pub type RegistryResult<T> = Result<T, RegistryError>;
+
 /// Validation error builder for creating validation errors
 pub struct ValidationErrorBuilder;
 
@@ -0,0 +1,311 @@
+//! Event schema registry
+//!
+//! Every event payload schema, named as in the CloudEvents `dataschema` attribute
+//! (`urn:stepflow:event:<name>:v<version>`), can have a JSON Schema per version so
+//! consumers of webhooks, RPC subscriptions and external sinks can fetch the exact
+//! shape of the events they receive. Versions are numbered from 1 and immutable.
+//!
+//! Publishing a new version checks it against the latest one:
+//! - `backward`: consumers using the new schema can read events written with the old one
+//! - `forward`: consumers still using the old schema can read events written with the new one
+//! - `full`: both
+//! - `none`: no check
+//!
+//! The check is structural and conservative. It compares `type`, `enum`, `required`,
+//! `properties`, `additionalProperties`, `items` and numeric, length and size bounds;
+//! properties present in only one schema are accepted unless the other schema forbids
+//! additional properties, matching the payload convention that adding a field is compatible.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use stepflow_core::*;
+use stepflow_database::{EventSchemaRecord, EventSchemaRepository, SqliteDatabase};
+use crate::errors::*;
+
+/// Largest JSON Schema accepted, in bytes
+pub const MAX_SCHEMA_SIZE: usize = 256 * 1024;
+
+/// How a new schema version must relate to the previous one
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompatibilityMode {
+    None,
+    #[default]
+    Backward,
+    Forward,
+    Full,
+}
+
+impl CompatibilityMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CompatibilityMode::None => "none",
+            CompatibilityMode::Backward => "backward",
+            CompatibilityMode::Forward => "forward",
+            CompatibilityMode::Full => "full",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "none" => Some(CompatibilityMode::None),
+            "backward" => Some(CompatibilityMode::Backward),
+            "forward" => Some(CompatibilityMode::Forward),
+            "full" => Some(CompatibilityMode::Full),
+            _ => None,
+        }
+    }
+}
+
+/// Result of checking a schema against the latest published version
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompatibilityReport {
+    pub name: String,
+    /// Version the schema was checked against, `None` for a new schema
+    pub previous_version: Option<u32>,
+    pub compatibility: CompatibilityMode,
+    pub compatible: bool,
+    /// Why the schema is incompatible, each naming the offending location
+    pub issues: Vec<String>,
+}
+
+/// Reasons `next` is not `mode`-compatible with `previous`; empty if it is
+pub fn check_compatibility(previous: &Value, next: &Value, mode: CompatibilityMode) -> Vec<String> {
+    let mut issues = Vec::new();
+    if matches!(mode, CompatibilityMode::Backward | CompatibilityMode::Full) {
+        accepts(next, previous, "$", "backward", &mut issues);
+    }
+    if matches!(mode, CompatibilityMode::Forward | CompatibilityMode::Full) {
+        accepts(previous, next, "$", "forward", &mut issues);
+    }
+    issues
+}
+
+/// Records why some instance valid under `writer` may be rejected by `reader`
+fn accepts(reader: &Value, writer: &Value, path: &str, direction: &str, issues: &mut Vec<String>) {
+    let (Some(reader), Some(writer)) = (reader.as_object(), writer.as_object()) else {
+        return;
+    };
+    let mut issue = |message: String| issues.push(format!("{} ({}): {}", path, direction, message));
+
+    if let Some(reader_types) = types(reader) {
+        match types(writer) {
+            Some(writer_types) => {
+                for ty in writer_types {
+                    let widened = ty == "integer" && reader_types.contains(&"number");
+                    if !reader_types.contains(&ty) && !widened {
+                        issue(format!("type {} is not accepted", ty));
+                    }
+                }
+            }
+            None => issue(format!("type is restricted to {}", reader_types.join(", "))),
+        }
+    }
+
+    if let Some(reader_enum) = reader.get("enum").and_then(Value::as_array) {
+        match writer.get("enum").and_then(Value::as_array) {
+            Some(writer_enum) => {
+                for value in writer_enum.iter().filter(|value| !reader_enum.contains(value)) {
+                    issue(format!("enum value {} is not accepted", value));
+                }
+            }
+            None => issue("values are restricted by enum".to_string()),
+        }
+    }
+
+    let writer_required = required(writer);
+    for field in required(reader).difference(&writer_required) {
+        issue(format!("field {} is required", field));
+    }
+
+    for (key, tighter) in [("minimum", true), ("minLength", true), ("minItems", true), ("maximum", false), ("maxLength", false), ("maxItems", false)] {
+        let Some(bound) = reader.get(key).and_then(Value::as_f64) else { continue };
+        let covered = match writer.get(key).and_then(Value::as_f64) {
+            Some(writer_bound) if tighter => writer_bound >= bound,
+            Some(writer_bound) => writer_bound <= bound,
+            None => false,
+        };
+        if !covered {
+            issue(format!("{} {} is stricter", key, bound));
+        }
+    }
+
+    let reader_closed = reader.get("additionalProperties") == Some(&Value::Bool(false));
+    let writer_closed = writer.get("additionalProperties") == Some(&Value::Bool(false));
+    if reader_closed && !writer_closed {
+        issue("additional properties are not allowed".to_string());
+    }
+    let empty = Map::new();
+    let reader_properties = reader.get("properties").and_then(Value::as_object).unwrap_or(&empty);
+    let writer_properties = writer.get("properties").and_then(Value::as_object).unwrap_or(&empty);
+    for (name, writer_property) in writer_properties {
+        match reader_properties.get(name) {
+            Some(reader_property) => accepts(reader_property, writer_property, &format!("{}.{}", path, name), direction, issues),
+            None if reader_closed => issues.push(format!("{} ({}): property {} is not allowed", path, direction, name)),
+            None => {}
+        }
+    }
+
+    if let (Some(reader_items), Some(writer_items)) = (reader.get("items"), writer.get("items")) {
+        accepts(reader_items, writer_items, &format!("{}[]", path), direction, issues);
+    }
+}
+
+fn types(schema: &Map<String, Value>) -> Option<Vec<&str>> {
+    match schema.get("type")? {
+        Value::String(ty) => Some(vec![ty.as_str()]),
+        Value::Array(types) => Some(types.iter().filter_map(Value::as_str).collect()),
+        _ => None,
+    }
+}
+
+fn required(schema: &Map<String, Value>) -> HashSet<&str> {
+    schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|fields| fields.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default()
+}
+
+/// Event schema registry backed by the `event_schemas` table
+pub struct EventSchemaService {
+    repository: Arc<EventSchemaRepository>,
+}
+
+impl EventSchemaService {
+    /// Create a new event schema service
+    pub fn new(db: Arc<SqliteDatabase>) -> Self {
+        Self {
+            repository: Arc::new(EventSchemaRepository::new(db.as_ref().clone())),
+        }
+    }
+
+    /// Check `json_schema` against the latest version of `name` without publishing it
+    ///
+    /// Without a mode the latest version's mode is used, `backward` for a new schema.
+    pub async fn check_schema(
+        &self,
+        name: &str,
+        json_schema: &Value,
+        compatibility: Option<CompatibilityMode>,
+    ) -> RegistryResult<CompatibilityReport> {
+        validate_schema(name, json_schema)?;
+        let latest = self.repository.latest_schema(name).await?;
+        Ok(compatibility_report(name, latest.as_ref(), json_schema, compatibility))
+    }
+
+    /// Publish `json_schema` as the next version of `name`
+    ///
+    /// Publishing the latest version's schema again returns that version unchanged.
+    /// A schema that fails the compatibility check is rejected with the reasons.
+    pub async fn publish_schema(
+        &self,
+        name: &str,
+        json_schema: Value,
+        compatibility: Option<CompatibilityMode>,
+        description: Option<&str>,
+        created_by: Option<&str>,
+    ) -> RegistryResult<EventSchemaRecord> {
+        validate_schema(name, &json_schema)?;
+        let latest = self.repository.latest_schema(name).await?;
+        if let Some(latest) = latest.as_ref().filter(|latest| latest.json_schema == json_schema) {
+            return Ok(latest.clone());
+        }
+
+        let report = compatibility_report(name, latest.as_ref(), &json_schema, compatibility);
+        if !report.compatible {
+            return Err(RegistryError::VersionConflict(format!(
+                "schema {} is not {} compatible with version {}: {}",
+                name,
+                report.compatibility.as_str(),
+                report.previous_version.unwrap_or_default(),
+                report.issues.join("; "),
+            )));
+        }
+
+        let record = EventSchemaRecord {
+            name: name.to_string(),
+            version: report.previous_version.unwrap_or_default() + 1,
+            json_schema,
+            compatibility: report.compatibility.as_str().to_string(),
+            description: description.map(str::to_string),
+            created_by: created_by.map(str::to_string),
+            created_at: Utc::now(),
+        };
+        // A concurrent publish of the same version fails on the primary key
+        self.repository.insert_schema(&record).await.map_err(|e| {
+            RegistryError::VersionConflict(format!("version {} of schema {} was published concurrently: {}", record.version, name, e))
+        })?;
+        tracing::info!("Published version {} of event schema {}", record.version, name);
+        Ok(record)
+    }
+
+    pub async fn get_schema(&self, name: &str, version: u32) -> RegistryResult<Option<EventSchemaRecord>> {
+        Ok(self.repository.get_schema(name, version).await?)
+    }
+
+    pub async fn latest_schema(&self, name: &str) -> RegistryResult<Option<EventSchemaRecord>> {
+        Ok(self.repository.latest_schema(name).await?)
+    }
+
+    /// Every version of a schema, oldest first
+    pub async fn list_versions(&self, name: &str) -> RegistryResult<Vec<EventSchemaRecord>> {
+        Ok(self.repository.list_versions(name).await?)
+    }
+
+    /// The latest version of every schema
+    pub async fn list_schemas(&self) -> RegistryResult<Vec<EventSchemaRecord>> {
+        Ok(self.repository.list_latest().await?)
+    }
+}
+
+fn compatibility_report(
+    name: &str,
+    latest: Option<&EventSchemaRecord>,
+    json_schema: &Value,
+    compatibility: Option<CompatibilityMode>,
+) -> CompatibilityReport {
+    let compatibility = compatibility
+        .or_else(|| latest.and_then(|latest| CompatibilityMode::parse(&latest.compatibility)))
+        .unwrap_or_default();
+    let issues = latest
+        .map(|latest| check_compatibility(&latest.json_schema, json_schema, compatibility))
+        .unwrap_or_default();
+    CompatibilityReport {
+        name: name.to_string(),
+        previous_version: latest.map(|latest| latest.version),
+        compatibility,
+        compatible: issues.is_empty(),
+        issues,
+    }
+}
+
+fn validate_schema(name: &str, json_schema: &Value) -> RegistryResult<()> {
+    let mut errors = Vec::new();
+    let valid_name = !name.is_empty()
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '.' | '_' | '-'));
+    if !valid_name {
+        errors.push(ValidationError::InvalidFormat(format!(
+            "schema name must be lowercase letters, digits, '.', '_' or '-', got {:?}",
+            name
+        )));
+    }
+    if !json_schema.is_object() {
+        errors.push(ValidationError::InvalidFormat("JSON Schema must be an object".to_string()));
+    }
+    let size = json_schema.to_string().len();
+    if size > MAX_SCHEMA_SIZE {
+        errors.push(ValidationError::ValueOutOfRange(format!(
+            "JSON Schema must be at most {} bytes, got {}",
+            MAX_SCHEMA_SIZE, size
+        )));
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(RegistryError::ValidationFailed(errors))
+    }
+}
@@ -0,0 +1,190 @@
+//! Registry garbage collection
+//!
+//! Tool versions accumulate documentation, example test runs and rollouts, and
+//! deleting a tool leaves its configuration, presets and other per-tool rows
+//! behind. [`RegistryGc`] removes what the [`GcPolicy`] no longer wants kept:
+//!
+//! - data of old versions beyond the `keep_versions` most recent ones; the
+//!   current version and active rollouts are never collected
+//! - rows of tools that no longer exist
+//! - stored output chunks of executions that were deleted or whose tool was deleted
+//!
+//! A dry run counts the rows each rule would remove without touching them.
+//! `RegistryGc` is a [`Job`], so it runs on the background job scheduler.
+
+use std::sync::Arc;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use stepflow_core::*;
+use stepflow_database::{Job, SqliteDatabase};
+use tracing::info;
+use crate::errors::*;
+
+/// Current version of the joined `tools t` row, formatted like [`ToolVersion`]
+const CURRENT_VERSION: &str = "(t.version_major || '.' || t.version_minor || '.' || t.version_patch \
+    || COALESCE('-' || t.version_pre_release, '') || COALESCE('+' || t.version_build, ''))";
+
+/// Tables holding rows keyed by `tool_id`
+const PER_TOOL_TABLES: &[&str] = &[
+    "tool_docs",
+    "tool_test_runs",
+    "tool_rollouts",
+    "tool_configs",
+    "tool_presets",
+    "tool_baselines",
+    "user_favorites",
+];
+
+/// Why a row is collected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GcCategory {
+    OldVersions,
+    DeletedTools,
+    OrphanedResultChunks,
+}
+
+/// Rows removed from one table by one rule
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GcTargetReport {
+    pub category: GcCategory,
+    pub table: String,
+    pub rows: u64,
+}
+
+/// Outcome of a garbage collection run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegistryGcReport {
+    /// Rows were only counted, nothing was deleted
+    pub dry_run: bool,
+    pub policy: GcPolicy,
+    pub targets: Vec<GcTargetReport>,
+    pub total_rows: u64,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+}
+
+/// Rows of `table` matching `condition` are collected
+struct GcTarget {
+    category: GcCategory,
+    table: &'static str,
+    condition: String,
+}
+
+/// Removes old tool versions and data of deleted tools; see the module documentation
+pub struct RegistryGc {
+    db: Arc<SqliteDatabase>,
+    policy: GcPolicy,
+}
+
+impl RegistryGc {
+    pub fn new(db: Arc<SqliteDatabase>, policy: GcPolicy) -> Self {
+        Self { db, policy }
+    }
+
+    pub fn policy(&self) -> &GcPolicy {
+        &self.policy
+    }
+
+    /// Collect garbage, or with `dry_run` only report what would be collected
+    pub async fn run(&self, dry_run: bool) -> RegistryResult<RegistryGcReport> {
+        let started_at = Utc::now();
+        let mut targets = Vec::new();
+        for target in self.targets() {
+            // Column types of aggregates over these subqueries are not always known to
+            // the driver, so a dry run counts the matching rows rather than reading COUNT(*)
+            let sql = if dry_run {
+                format!("SELECT 1 FROM {} WHERE {}", target.table, target.condition)
+            } else {
+                format!("DELETE FROM {} WHERE {}", target.table, target.condition)
+            };
+            let rows = self.db.execute(&sql, &[]).await?.rows_affected;
+            targets.push(GcTargetReport { category: target.category, table: target.table.to_string(), rows });
+        }
+
+        let total_rows = targets.iter().map(|target| target.rows).sum();
+        if !dry_run && total_rows > 0 {
+            info!("Registry garbage collection removed {} row(s)", total_rows);
+        }
+        Ok(RegistryGcReport {
+            dry_run,
+            policy: self.policy.clone(),
+            targets,
+            total_rows,
+            started_at,
+            finished_at: Utc::now(),
+        })
+    }
+
+    fn targets(&self) -> Vec<GcTarget> {
+        let keep = self.policy.keep_versions.max(1);
+        let mut targets = vec![
+            GcTarget {
+                category: GcCategory::OldVersions,
+                table: "tool_docs",
+                condition: format!(
+                    "rowid IN (SELECT ranked.row_id FROM (\
+                        SELECT rowid AS row_id, tool_id, version, \
+                        ROW_NUMBER() OVER (PARTITION BY tool_id ORDER BY updated_at DESC) AS position \
+                        FROM tool_docs) ranked \
+                     JOIN tools t ON t.id = ranked.tool_id \
+                     WHERE ranked.position > {} AND ranked.version != {})",
+                    keep, CURRENT_VERSION,
+                ),
+            },
+            GcTarget {
+                category: GcCategory::OldVersions,
+                table: "tool_test_runs",
+                condition: format!(
+                    "id IN (SELECT r.id FROM tool_test_runs r \
+                     JOIN (SELECT tool_id, version, \
+                        ROW_NUMBER() OVER (PARTITION BY tool_id ORDER BY MAX(finished_at) DESC) AS position \
+                        FROM tool_test_runs GROUP BY tool_id, version) v \
+                        ON v.tool_id = r.tool_id AND v.version = r.version \
+                     JOIN tools t ON t.id = r.tool_id \
+                     WHERE v.position > {} AND r.version != {})",
+                    keep, CURRENT_VERSION,
+                ),
+            },
+            GcTarget {
+                category: GcCategory::OldVersions,
+                table: "tool_rollouts",
+                condition: format!(
+                    "id IN (SELECT ranked.id FROM (\
+                        SELECT id, tool_id, status, \
+                        ROW_NUMBER() OVER (PARTITION BY tool_id ORDER BY created_at DESC) AS position \
+                        FROM tool_rollouts) ranked \
+                     JOIN tools t ON t.id = ranked.tool_id \
+                     WHERE ranked.position > {} AND ranked.status != 'active')",
+                    keep,
+                ),
+            },
+        ];
+        if self.policy.drop_deleted_tool_data {
+            targets.extend(PER_TOOL_TABLES.iter().map(|table| GcTarget {
+                category: GcCategory::DeletedTools,
+                table,
+                condition: "tool_id NOT IN (SELECT id FROM tools)".to_string(),
+            }));
+        }
+        if self.policy.drop_orphaned_result_chunks {
+            targets.push(GcTarget {
+                category: GcCategory::OrphanedResultChunks,
+                table: "result_chunks",
+                condition: "execution_id NOT IN \
+                    (SELECT id FROM executions WHERE tool_id IN (SELECT id FROM tools))".to_string(),
+            });
+        }
+        targets
+    }
+}
+
+#[async_trait]
+impl Job for RegistryGc {
+    async fn run(&self) -> StepflowResult<String> {
+        let report = RegistryGc::run(self, false).await
+            .map_err(|e| StepflowError::InternalError(e.to_string()))?;
+        Ok(format!("{} row(s) collected", report.total_rows))
+    }
+}
@@ -14,16 +14,39 @@ pub mod version_manager;
 pub mod discovery;
 pub mod cache;
 pub mod validation;
+pub mod marketplace;
+pub mod tool_config;
+pub mod change_feed;
+pub mod memory;
+pub mod content_store;
+pub mod docs;
+pub mod event_schemas;
+pub mod gc;
+#[cfg(feature = "redis")]
+pub mod redis_cache;
 
 // Re-export key types
 pub use errors::{RegistryError, RegistryResult};
 pub use registry::Registry;
-pub use registry_impl::RegistryImpl;
+pub use registry_impl::{RegistryCacheConfig, RegistryImpl};
 pub use tool_manager::ToolManager as ToolManagerImpl;
 pub use version_manager::VersionManager as VersionManagerImpl;
 pub use discovery::DiscoveryService as DiscoveryServiceImpl;
-pub use cache::Cache as CacheImpl;
+pub use cache::{Cache as CacheImpl, CacheBackend, RegistryCache};
 pub use validation::InputValidator as InputValidatorImpl;
+pub use marketplace::{MarketplaceService, ToolListing, ToolSubscription, ListingVisibility, ListingStatus, SubscriptionStatus};
+pub use tool_config::{
+    interpolate_with_secrets, resolve_tool_config, resolve_tool_environment, secret_references, EffectiveEnvironment,
+    EnvironmentSource, EnvironmentVariable, ToolConfigService, REDACTED_VALUE,
+};
+pub use change_feed::{ChangeFeed, ChangeFeedRpcHandler, ChangePage, ToolChange, ToolChangeType};
+pub use memory::InMemoryRegistry;
+pub use content_store::{content_digest, ContentStore, ContentStoreStats, GcReport, ToolPackage, ToolPackageStore};
+pub use docs::{render_tool_docs, sanitize_html, RenderedDocs, ToolDocsService};
+pub use event_schemas::{check_compatibility, CompatibilityMode, CompatibilityReport, EventSchemaService};
+pub use gc::{GcCategory, GcTargetReport, RegistryGc, RegistryGcReport};
+#[cfg(feature = "redis")]
+pub use redis_cache::RedisCache;
 
 /// Version information
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -36,7 +59,7 @@ pub async fn create_registry(db: std::sync::Arc<stepflow_database::SqliteDatabas
 impl RegistryImpl {
     /// Get tool manager
     pub fn tool_manager(&self) -> ToolManagerImpl {
-        ToolManagerImpl::new(self.tool_repository())
+        ToolManagerImpl::new(self.tool_repository()).with_cache(self.cache())
     }
     
     /// Get version manager
@@ -47,6 +70,7 @@ impl RegistryImpl {
     /// Get discovery service
     pub fn discovery_service(&self) -> DiscoveryServiceImpl {
         DiscoveryServiceImpl::new(self.tool_repository())
+            .with_favorite_repository(self.favorite_repository())
     }
 }
 
@@ -55,7 +79,9 @@ mod tests {
     use super::*;
     use stepflow_database::{SqliteDatabase, MigrationManager};
     use std::sync::Arc;
-    use chrono::Utc;
+    use std::collections::HashMap;
+    use serde_json::{json, Value};
+    use tool_config::*;
     
     async fn create_test_registry() -> Result<RegistryImpl, RegistryError> {
         let db = Arc::new(SqliteDatabase::new("sqlite::memory:").await
@@ -74,27 +100,17 @@ mod tests {
         assert!(registry.is_ok());
     }
     
-    #[tokio::test]
-    async fn test_registry_basic_operations() {
-        let registry = create_test_registry().await.unwrap();
-        
-        let tool = ToolInfo {
-            id: ToolId::new(),
-            name: "test-tool".to_string(),
-            description: "A test tool".to_string(),
-            version: ToolVersion::new(1, 0, 0),
-            tool_type: ToolType::Python,
-            status: ToolStatus::Active,
-            author: "test-author".to_string(),
-            repository: None,
-            documentation: None,
-            tags: vec!["test".to_string()],
-            capabilities: vec!["process".to_string()],
-            configuration_schema: None,
-            examples: vec![],
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-        };
+    /// Registry behaviour shared by the SQLite and in-memory backends
+    async fn check_basic_operations(registry: &dyn Registry) {
+        let tool = ToolInfo::builder()
+            .name("test-tool")
+            .description("A test tool")
+            .tool_type(ToolType::Python)
+            .author("test-author")
+            .tags(["test"])
+            .capabilities(["process"])
+            .build()
+            .unwrap();
         
         // Test registration
         let tool_id = registry.register_tool(tool.clone()).await.unwrap();
@@ -113,28 +129,26 @@ mod tests {
         assert_eq!(results.len(), 1);
     }
     
+    #[tokio::test]
+    async fn test_registry_basic_operations() {
+        check_basic_operations(&InMemoryRegistry::new()).await;
+        check_basic_operations(&create_test_registry().await.unwrap()).await;
+    }
+    
     #[tokio::test]
     async fn test_tool_manager() {
         let registry = create_test_registry().await.unwrap();
         let tool_manager = registry.tool_manager();
         
-        let tool = ToolInfo {
-            id: ToolId::new(),
-            name: "test-tool".to_string(),
-            description: "A test tool".to_string(),
-            version: ToolVersion::new(1, 0, 0),
-            tool_type: ToolType::Python,
-            status: ToolStatus::Active,
-            author: "test-author".to_string(),
-            repository: None,
-            documentation: None,
-            tags: vec!["test".to_string()],
-            capabilities: vec!["process".to_string()],
-            configuration_schema: None,
-            examples: vec![],
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-        };
+        let tool = ToolInfo::builder()
+            .name("test-tool")
+            .description("A test tool")
+            .tool_type(ToolType::Python)
+            .author("test-author")
+            .tags(["test"])
+            .capabilities(["process"])
+            .build()
+            .unwrap();
         
         // Test tool management
         let tool_id = tool_manager.register_tool(tool.clone()).await.unwrap();
@@ -142,6 +156,633 @@ mod tests {
         assert_eq!(retrieved_tool.name, tool.name);
     }
     
+    async fn check_favorites_listed_first(registry: &dyn Registry) {
+        let mut tool_ids = Vec::new();
+        for name in ["alpha", "beta", "gamma"] {
+            let tool = ToolInfo::builder()
+                .name(name.to_string())
+                .description(format!("{} tool", name))
+                .tool_type(ToolType::Python)
+                .author("test-author")
+                .build()
+                .unwrap();
+            tool_ids.push(registry.register_tool(tool).await.unwrap());
+        }
+
+        let user_id = UserId::new();
+        registry.add_favorite(&user_id, &tool_ids[2]).await.unwrap();
+
+        let tools = registry.discover_tools_for_user(&user_id).await.unwrap();
+        assert_eq!(tools.len(), 3);
+        assert_eq!(tools[0].id, tool_ids[2]);
+
+        let favorites = registry.list_favorites(&user_id).await.unwrap();
+        assert_eq!(favorites.len(), 1);
+
+        // Favoriting an unknown tool is rejected
+        let result = registry.add_favorite(&user_id, &ToolId::new()).await;
+        assert!(matches!(result, Err(RegistryError::ToolNotFound(_))));
+
+        registry.remove_favorite(&user_id, &tool_ids[2]).await.unwrap();
+        assert!(registry.list_favorites(&user_id).await.unwrap().is_empty());
+        // Removing a tool that is no longer a favorite is a no-op
+        registry.remove_favorite(&user_id, &tool_ids[2]).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_favorites_listed_first() {
+        check_favorites_listed_first(&InMemoryRegistry::new()).await;
+        check_favorites_listed_first(&create_test_registry().await.unwrap()).await;
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_registry_simulated_faults() {
+        let faults = SimulatedFaults::new();
+        let registry = InMemoryRegistry::with_faults(faults.clone());
+        let tool = ToolInfo::builder()
+            .name("flaky-tool")
+            .description("A flaky tool")
+            .tool_type(ToolType::Python)
+            .author("test-author")
+            .build()
+            .unwrap();
+
+        faults.fail_next("register_tool", 1);
+        assert!(matches!(registry.register_tool(tool.clone()).await, Err(RegistryError::DatabaseError(_))));
+        let tool_id = registry.register_tool(tool.clone()).await.unwrap();
+        assert!(matches!(registry.register_tool(tool).await, Err(RegistryError::ToolAlreadyExists(_))));
+        assert_eq!(faults.calls("register_tool"), 3);
+
+        faults.fail_always("get_tool");
+        assert!(registry.get_tool(&tool_id).await.is_err());
+        assert!(registry.tool_exists(&tool_id).await.unwrap());
+        faults.fail_always("health_check");
+        assert!(!registry.health_check().await.unwrap());
+
+        faults.clear();
+        faults.set_latency(std::time::Duration::from_millis(20));
+        let started = std::time::Instant::now();
+        registry.get_tool(&tool_id).await.unwrap();
+        assert!(started.elapsed() >= std::time::Duration::from_millis(20));
+    }
+
+    #[tokio::test]
+    async fn test_marketplace_flow() {
+        let db = Arc::new(SqliteDatabase::new("sqlite::memory:").await.unwrap());
+        MigrationManager::run_migrations(&db).await.unwrap();
+        let registry = create_registry(db.clone()).await.unwrap();
+        let marketplace = MarketplaceService::new(db).with_cache(registry.tool_cache());
+        let cache = registry.cache().unwrap();
+
+        let publisher = TenantId::new();
+        let consumer = TenantId::new();
+        let outsider = TenantId::new();
+
+        let mut tool = ToolInfo::builder()
+            .name("shared-tool")
+            .description("A shared tool")
+            .tool_type(ToolType::Python)
+            .author(publisher.as_str())
+            .build()
+            .unwrap();
+        let tool_id = registry.register_tool(tool.clone()).await.unwrap();
+
+        // Only the tool's author can publish it
+        let denied = marketplace.publish_tool(&outsider, &tool_id, ListingVisibility::Global).await;
+        assert!(matches!(denied, Err(RegistryError::PermissionDenied(_))));
+
+        // Marketplace writes invalidate the cached tool
+        registry.get_tool(&tool_id).await.unwrap();
+        assert!(cache.get_tool(&tool_id).await.is_some());
+        let listing = marketplace
+            .publish_tool(&publisher, &tool_id, ListingVisibility::Tenants(vec![consumer.clone()]))
+            .await
+            .unwrap();
+
+        assert!(cache.get_tool(&tool_id).await.is_none());
+        assert_eq!(marketplace.list_catalog(&consumer).await.unwrap().len(), 1);
+        assert!(marketplace.list_catalog(&outsider).await.unwrap().is_empty());
+        assert!(matches!(
+            marketplace.subscribe(&outsider, &listing.id).await,
+            Err(RegistryError::ListingNotFound(_))
+        ));
+
+        // Subscribing links to the publisher's tool instead of copying it
+        let subscription = marketplace.subscribe(&consumer, &listing.id).await.unwrap();
+        assert_eq!(subscription.tool_id, tool_id);
+        assert_eq!(registry.list_tools().await.unwrap().len(), 1);
+        assert!(matches!(
+            marketplace.subscribe(&consumer, &listing.id).await,
+            Err(RegistryError::ToolAlreadyExists(_))
+        ));
+
+        // Updates stay pending until the consumer approves them
+        tool.version = ToolVersion::new(1, 1, 0);
+        registry.update_tool(&tool_id, &tool).await.unwrap();
+        assert!(matches!(
+            marketplace.publish_update(&consumer, &listing.id).await,
+            Err(RegistryError::PermissionDenied(_))
+        ));
+        registry.get_tool(&tool_id).await.unwrap();
+        assert_eq!(marketplace.publish_update(&publisher, &listing.id).await.unwrap(), 1);
+        assert!(cache.get_tool(&tool_id).await.is_none());
+
+        let subscriptions = marketplace.list_subscriptions(&consumer).await.unwrap();
+        assert_eq!(subscriptions[0].status, SubscriptionStatus::UpdatePending);
+        assert_eq!(subscriptions[0].installed_version, "1.0.0");
+        // The consumer keeps running the installed version meanwhile
+        let resolved = marketplace.resolve_subscribed_tool(&consumer, &subscription.id).await.unwrap();
+        assert_eq!(resolved.version, ToolVersion::new(1, 0, 0));
+
+        let approved = marketplace.approve_update(&consumer, &subscription.id).await.unwrap();
+        assert_eq!(approved.status, SubscriptionStatus::Active);
+        assert_eq!(approved.installed_version, "1.1.0");
+        assert!(approved.pending_version.is_none());
+
+        let resolved = marketplace.resolve_subscribed_tool(&consumer, &subscription.id).await.unwrap();
+        assert_eq!(resolved.id, tool_id);
+        assert_eq!(resolved.version, ToolVersion::new(1, 1, 0));
+
+        marketplace.withdraw_listing(&publisher, &listing.id).await.unwrap();
+        assert!(marketplace.list_catalog(&consumer).await.unwrap().is_empty());
+
+        marketplace.unsubscribe(&consumer, &subscription.id).await.unwrap();
+        assert!(marketplace.list_subscriptions(&consumer).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_change_feed() {
+        let registry = create_test_registry().await.unwrap();
+        let feed = registry.change_feed();
+        assert_eq!(feed.latest_sequence().await.unwrap(), 0);
+
+        let mut tool = ToolInfo::builder()
+            .name("tracked-tool")
+            .description("A tracked tool")
+            .tool_type(ToolType::Python)
+            .author("test-author")
+            .build()
+            .unwrap();
+        let tool_id = registry.register_tool(tool.clone()).await.unwrap();
+
+        tool.description = "Updated description".to_string();
+        registry.update_tool(&tool_id, &tool).await.unwrap();
+        tool.version = ToolVersion::new(1, 1, 0);
+        registry.update_tool(&tool_id, &tool).await.unwrap();
+        registry.delete_tool(&tool_id).await.unwrap();
+
+        let page = feed.changes_since(0, None).await.unwrap();
+        let types: Vec<ToolChangeType> = page.changes.iter().map(|c| c.change_type).collect();
+        assert_eq!(types, vec![
+            ToolChangeType::Created,
+            ToolChangeType::Updated,
+            ToolChangeType::VersionAdded,
+            ToolChangeType::Deleted,
+        ]);
+        assert_eq!(page.changes[2].version, "1.1.0");
+        assert!(page.changes[3].tool.is_none());
+        assert_eq!(page.latest_sequence, page.next_since);
+        assert!(!page.has_more);
+
+        // Paging resumes strictly after the given sequence
+        let first = feed.changes_since(0, Some(3)).await.unwrap();
+        assert!(first.has_more);
+        let rest = feed.changes_since(first.next_since, Some(3)).await.unwrap();
+        assert_eq!(rest.changes.len(), 1);
+        assert_eq!(rest.changes[0].change_type, ToolChangeType::Deleted);
+
+        // The same page is served over RPC
+        let handler = ChangeFeedRpcHandler::new(feed.clone());
+        let result = stepflow_rpc::RpcHandler::handle(&handler, ChangeFeedRpcHandler::METHOD, Some(json!({"since": 2})))
+            .await
+            .unwrap();
+        assert_eq!(result["changes"].as_array().unwrap().len(), 2);
+        assert_eq!(result["changes"][0]["change_type"], json!("version_added"));
+    }
+
+    #[tokio::test]
+    async fn test_change_feed_forwarded_as_events() {
+        let registry = create_test_registry().await.unwrap();
+        let feed = registry.change_feed();
+        let tool = |name: &str| ToolInfo::builder()
+            .name(name)
+            .description("A forwarded tool")
+            .tool_type(ToolType::Python)
+            .author("test-author")
+            .build()
+            .unwrap();
+        registry.register_tool(tool("existing-tool")).await.unwrap();
+
+        // Changes up to `since` are not republished
+        let publisher = stepflow_rpc::EventPublisher::new();
+        let since = feed.latest_sequence().await.unwrap();
+        let task = feed.clone().forward_to(publisher.clone(), since, std::time::Duration::from_millis(10));
+        let tool_id = registry.register_tool(tool("forwarded-tool")).await.unwrap();
+        registry.delete_tool(&tool_id).await.unwrap();
+
+        let stats = tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            loop {
+                let stats = publisher.get_stats().await;
+                if stats.total_events >= 2 {
+                    return stats;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("registry changes were not forwarded");
+        task.abort();
+
+        assert_eq!(stats.total_events, 2);
+        assert_eq!(stats.events_by_type.get("registry.tool.created"), Some(&1));
+        assert_eq!(stats.events_by_type.get("registry.tool.deleted"), Some(&1));
+    }
+
+    #[tokio::test]
+    async fn test_register_tools_batch() {
+        let registry = create_test_registry().await.unwrap();
+        let tool = |name: &str| ToolInfo::builder()
+            .name(name.to_string())
+            .description("A batch tool")
+            .tool_type(ToolType::OpenAPI)
+            .author("test-author")
+            .build()
+            .unwrap();
+        let existing = tool("existing");
+        registry.register_tool(existing.clone()).await.unwrap();
+
+        // Warm the list cache; the batch must invalidate it
+        assert_eq!(registry.list_tools().await.unwrap().len(), 1);
+
+        let report = registry
+            .register_tools(vec![tool("first"), existing, tool("second")])
+            .await
+            .unwrap();
+        assert_eq!(report.inserted, 2);
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.failures[0].index, 1);
+        assert_eq!(registry.list_tools().await.unwrap().len(), 3);
+
+        // Only the stored rows appear on the change feed
+        let page = registry.change_feed().changes_since(0, None).await.unwrap();
+        let names: Vec<&str> = page.changes.iter().filter_map(|c| c.tool.as_ref()).map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["existing", "first", "second"]);
+    }
+
+    fn config_schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["endpoint"],
+            "additionalProperties": false,
+            "properties": {
+                "endpoint": {"type": "string", "pattern": "^https?://"},
+                "port": {"type": "integer", "minimum": 1, "maximum": 65535, "default": 443},
+                "mode": {"type": "string", "enum": ["fast", "safe"], "default": "safe"},
+                "retry": {
+                    "type": "object",
+                    "properties": {
+                        "attempts": {"type": "integer", "default": 3}
+                    },
+                    "default": {}
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn test_defaults_applied_recursively() {
+        let mut config = json!({"endpoint": "https://example.com"});
+        apply_schema_defaults(&config_schema(), &mut config);
+
+        assert_eq!(config["port"], json!(443));
+        assert_eq!(config["mode"], json!("safe"));
+        assert_eq!(config["retry"]["attempts"], json!(3));
+        assert!(validate_against_schema(&config_schema(), &config).is_ok());
+    }
+
+    #[test]
+    fn test_errors_point_at_failing_properties() {
+        let config = json!({"port": 70000, "mode": "turbo", "extra": true});
+        let errors = validate_against_schema(&config_schema(), &config).unwrap_err();
+        let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+
+        assert_eq!(errors.len(), 4);
+        assert!(messages.iter().any(|m| m.contains("/endpoint")));
+        assert!(messages.iter().any(|m| m.contains("/port") && m.contains("maximum")));
+        assert!(messages.iter().any(|m| m.contains("/mode")));
+        assert!(messages.iter().any(|m| m.contains("/extra") && m.contains("unknown property")));
+    }
+
+    #[test]
+    fn test_env_interpolation() {
+        let env = HashMap::from([
+            ("HOST".to_string(), "api.example.com".to_string()),
+            ("PORT".to_string(), "8443".to_string()),
+        ]);
+        let config = json!({
+            "endpoint": "https://${HOST}/v1",
+            "port": "${PORT}",
+            "mode": "${MODE:-fast}",
+            "literal": "$${HOST}"
+        });
+
+        let mut resolved = interpolate_env(&config, &env).unwrap();
+        coerce_to_schema(&config_schema(), &mut resolved);
+
+        assert_eq!(resolved["endpoint"], json!("https://api.example.com/v1"));
+        assert_eq!(resolved["port"], json!(8443));
+        assert_eq!(resolved["mode"], json!("fast"));
+        assert_eq!(resolved["literal"], json!("${HOST}"));
+
+        let errors = interpolate_env(&json!({"nested": {"token": "${TOKEN}"}}), &env).unwrap_err();
+        assert!(errors[0].to_string().contains("/nested/token"));
+        assert!(errors[0].to_string().contains("TOKEN"));
+    }
+
+    #[test]
+    fn test_placeholders_deferred_until_resolution() {
+        let config = json!({"endpoint": "${ENDPOINT}", "port": "${PORT}"});
+        assert!(validate_unresolved_against_schema(&config_schema(), &config).is_ok());
+        assert!(validate_against_schema(&config_schema(), &config).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tool_config_service() {
+        let db = Arc::new(SqliteDatabase::new("sqlite::memory:").await.unwrap());
+        MigrationManager::run_migrations(&db).await.unwrap();
+        let registry = create_registry(db.clone()).await.unwrap();
+        let service = ToolConfigService::new(db);
+
+        let tool = ToolInfo::builder()
+            .name("configurable-tool")
+            .description("A configurable tool")
+            .tool_type(ToolType::Python)
+            .author("test-author")
+            .configuration_schema(config_schema())
+            .build()
+            .unwrap();
+        let tool_id = registry.register_tool(tool).await.unwrap();
+        let tenant_id = TenantId::new();
+
+        let mut config = ToolConfig {
+            tool_id: tool_id.clone(),
+            configuration: HashMap::from([("port".to_string(), json!("not-a-port"))]),
+            environment: HashMap::new(),
+            secrets: HashMap::new(),
+            timeout: Some(30),
+            retries: None,
+            enabled: true,
+        };
+        let result = service.save_config(&tenant_id, config.clone()).await;
+        assert!(matches!(result, Err(RegistryError::ValidationFailed(ref errors)) if errors.len() == 2));
+
+        config.configuration = HashMap::from([("endpoint".to_string(), json!("https://${HOST}"))]);
+        config.environment = HashMap::from([("HOST".to_string(), "default.example.com".to_string())]);
+        let saved = service.save_config(&tenant_id, config).await.unwrap();
+        assert_eq!(saved.configuration["port"], json!(443));
+
+        let stored = service.get_config(&tenant_id, &tool_id).await.unwrap().unwrap();
+        assert_eq!(stored.timeout, Some(30));
+        assert_eq!(stored.configuration["endpoint"], json!("https://${HOST}"));
+
+        // Execution-time variables take precedence over the stored environment
+        let env = HashMap::from([("HOST".to_string(), "override.example.com".to_string())]);
+        let resolved = service.resolve_config(&tenant_id, &tool_id, &env).await.unwrap();
+        assert_eq!(resolved["endpoint"], json!("https://override.example.com"));
+        assert_eq!(resolved["mode"], json!("safe"));
+
+        service.delete_config(&tenant_id, &tool_id).await.unwrap();
+        assert!(service.get_config(&tenant_id, &tool_id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_tool_environment_precedence() {
+        let db = Arc::new(SqliteDatabase::new("sqlite::memory:").await.unwrap());
+        MigrationManager::run_migrations(&db).await.unwrap();
+        let registry = create_registry(db.clone()).await.unwrap();
+        let service = ToolConfigService::new(db);
+
+        let tool = ToolInfo::builder()
+            .name("env-tool")
+            .tool_type(ToolType::Python)
+            .env("LOG_LEVEL", "info")
+            .env("REGION", "us-east-1")
+            .env("TIMEOUT", "30")
+            .build()
+            .unwrap();
+        let tool_id = registry.register_tool(tool).await.unwrap();
+        let tenant_id = TenantId::new();
+        assert_eq!(registry.get_tool(&tool_id).await.unwrap().environment["REGION"], "us-east-1");
+
+        let mut config = ToolConfig {
+            tool_id: tool_id.clone(),
+            configuration: HashMap::from([("token".to_string(), json!("${secret:API_TOKEN}"))]),
+            environment: HashMap::from([
+                ("REGION".to_string(), "eu-west-1".to_string()),
+                ("LOG_LEVEL".to_string(), "warn".to_string()),
+                ("AUTH_HEADER".to_string(), "Bearer ${secret:API_TOKEN}".to_string()),
+            ]),
+            secrets: HashMap::new(),
+            timeout: None,
+            retries: None,
+            enabled: true,
+        };
+        // Secret references must name a stored secret
+        let result = service.save_config(&tenant_id, config.clone()).await;
+        assert!(matches!(result, Err(RegistryError::ValidationFailed(ref errors)) if errors.len() == 2));
+        config.secrets = HashMap::from([("API_TOKEN".to_string(), "s3cr3t".to_string())]);
+        service.save_config(&tenant_id, config).await.unwrap();
+
+        let request = HashMap::from([("LOG_LEVEL".to_string(), "debug".to_string())]);
+        let environment = service.preview_environment(&tenant_id, &tool_id, &request).await.unwrap();
+        let variable = |name: &str| environment.get(name).unwrap().clone();
+        assert_eq!((variable("LOG_LEVEL").value, variable("LOG_LEVEL").source), ("debug".to_string(), EnvironmentSource::Request));
+        assert_eq!((variable("REGION").value, variable("REGION").source), ("eu-west-1".to_string(), EnvironmentSource::TenantConfig));
+        assert_eq!((variable("TIMEOUT").value, variable("TIMEOUT").source), ("30".to_string(), EnvironmentSource::Tool));
+        assert_eq!(variable("AUTH_HEADER").value, "Bearer s3cr3t");
+        assert!(variable("AUTH_HEADER").secret);
+
+        let redacted = environment.redacted();
+        assert_eq!(redacted.get("AUTH_HEADER").unwrap().value, REDACTED_VALUE);
+        assert_eq!(redacted.get("REGION").unwrap().value, "eu-west-1");
+
+        let resolved = service.resolve_config(&tenant_id, &tool_id, &HashMap::new()).await.unwrap();
+        assert_eq!(resolved["token"], json!("s3cr3t"));
+    }
+
+    #[tokio::test]
+    async fn test_tool_docs_service() {
+        let db = Arc::new(SqliteDatabase::new("sqlite::memory:").await.unwrap());
+        MigrationManager::run_migrations(&db).await.unwrap();
+        let registry = create_registry(db.clone()).await.unwrap();
+        let service = ToolDocsService::new(db);
+
+        let tool = ToolInfo::builder()
+            .name("documented-tool")
+            .description("A documented tool")
+            .tool_type(ToolType::Python)
+            .author("test-author")
+            .example(ToolExample {
+                name: "greet".to_string(),
+                description: "Say hello".to_string(),
+                input: json!({"name": "world"}),
+                output: json!("hello world"),
+                match_mode: ExampleMatch::default(),
+            })
+            .build()
+            .unwrap();
+        let tool_id = registry.register_tool(tool).await.unwrap();
+
+        let markdown = "# Usage\n\n<script>alert(1)</script><img src=x onerror=alert(1)>\n\n```example greet\n```\n\n```example missing\n{}\n```\n";
+        let saved = service.save_docs(&tool_id, None, markdown, Some("author")).await.unwrap();
+        assert_eq!(saved.version, "1.0.0");
+
+        let rendered = service.render_docs(&tool_id, None).await.unwrap().unwrap();
+        assert!(rendered.html.contains("<h1>Usage</h1>"));
+        assert!(!rendered.html.contains("<script"));
+        assert!(!rendered.html.contains("onerror"));
+        assert!(rendered.html.contains(&format!("href=\"/api/v1/tools/{}/test\"", tool_id)));
+        assert!(rendered.html.contains("\"name\": \"world\""));
+        // Unknown examples are shown but cannot be run
+        assert_eq!(rendered.examples, vec!["greet".to_string()]);
+        assert_eq!(rendered.html.matches("stepflow-example-run").count(), 1);
+
+        assert!(service.render_docs(&tool_id, Some("2.0.0")).await.unwrap().is_none());
+        let too_large = "a".repeat(docs::MAX_DOCS_SIZE + 1);
+        assert!(matches!(
+            service.save_docs(&tool_id, None, &too_large, None).await,
+            Err(RegistryError::ValidationFailed(_))
+        ));
+        assert!(service.delete_docs(&tool_id, "1.0.0").await.unwrap());
+        assert!(service.render_docs(&tool_id, None).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_registry_gc() {
+        let db = Arc::new(SqliteDatabase::new("sqlite::memory:").await.unwrap());
+        MigrationManager::run_migrations(&db).await.unwrap();
+        let registry = create_registry(db.clone()).await.unwrap();
+        let tool = ToolInfo::builder()
+            .name("versioned-tool")
+            .description("A tool with history")
+            .tool_type(ToolType::Python)
+            .author("test-author")
+            .build()
+            .unwrap();
+        let tool_id = registry.register_tool(tool).await.unwrap();
+
+        let docs = stepflow_database::ToolDocsRepository::new(db.as_ref().clone());
+        // The current version is documented first, so it is also the oldest
+        for version in ["1.0.0", "0.1.0", "0.2.0", "0.3.0"] {
+            docs.save_docs(&tool_id, version, "# Docs", None).await.unwrap();
+        }
+        let ghost = ToolId::from_string("deleted-tool".to_string());
+        docs.save_docs(&ghost, "1.0.0", "# Gone", None).await.unwrap();
+        db.execute(
+            "INSERT INTO result_chunks (execution_id, chunk_index, data) VALUES ('missing-execution', 0, 'x')",
+            &[],
+        ).await.unwrap();
+
+        let policy = GcPolicy { keep_versions: 2, ..GcPolicy::default() };
+        let gc = RegistryGc::new(db.clone(), policy);
+        let rows = |report: &RegistryGcReport, category: GcCategory, table: &str| report.targets.iter()
+            .find(|target| target.category == category && target.table == table)
+            .map(|target| target.rows);
+
+        let report = gc.run(true).await.unwrap();
+        assert!(report.dry_run);
+        assert_eq!(rows(&report, GcCategory::OldVersions, "tool_docs"), Some(1));
+        assert_eq!(rows(&report, GcCategory::DeletedTools, "tool_docs"), Some(1));
+        assert_eq!(rows(&report, GcCategory::OrphanedResultChunks, "result_chunks"), Some(1));
+        assert_eq!(report.total_rows, 3);
+        assert_eq!(docs.list_versions(&tool_id).await.unwrap().len(), 4);
+
+        assert_eq!(gc.run(false).await.unwrap().total_rows, 3);
+        // 0.3.0 and 0.2.0 are the most recent, 1.0.0 is current
+        let mut kept = docs.list_versions(&tool_id).await.unwrap();
+        kept.sort();
+        assert_eq!(kept, vec!["0.2.0", "0.3.0", "1.0.0"]);
+        assert!(docs.latest_docs(&ghost).await.unwrap().is_none());
+        assert_eq!(gc.run(true).await.unwrap().total_rows, 0);
+
+        let keep_all = RegistryGc::new(db, GcPolicy { keep_versions: 10, drop_deleted_tool_data: false, ..GcPolicy::default() });
+        assert!(keep_all.run(true).await.unwrap().targets.iter().all(|target| target.category != GcCategory::DeletedTools));
+    }
+
+    #[tokio::test]
+    async fn test_event_schema_service() {
+        let db = Arc::new(SqliteDatabase::new("sqlite::memory:").await.unwrap());
+        MigrationManager::run_migrations(&db).await.unwrap();
+        let service = EventSchemaService::new(db);
+        let name = "execution.state_changed";
+
+        let v1 = json!({
+            "type": "object",
+            "properties": {
+                "execution_id": {"type": "string"},
+                "state": {"type": "string", "enum": ["running", "completed"]},
+                "attempt": {"type": "integer"}
+            },
+            "required": ["execution_id", "state"]
+        });
+        let published = service.publish_schema(name, v1.clone(), None, Some("initial"), Some("admin")).await.unwrap();
+        assert_eq!(published.version, 1);
+        assert_eq!(published.compatibility, "backward");
+        // Publishing the same schema again is a no-op
+        assert_eq!(service.publish_schema(name, v1.clone(), None, None, None).await.unwrap().version, 1);
+
+        // Adding an optional field and an enum value is backward compatible
+        let mut v2 = v1.clone();
+        v2["properties"]["tenant_id"] = json!({"type": "string"});
+        v2["properties"]["state"]["enum"] = json!(["running", "completed", "failed"]);
+        v2["properties"]["attempt"]["type"] = json!("number");
+        let report = service.check_schema(name, &v2, None).await.unwrap();
+        assert!(report.compatible, "{:?}", report.issues);
+        assert_eq!(report.previous_version, Some(1));
+        // but old consumers do not know the new enum value or non-integer attempts
+        let report = service.check_schema(name, &v2, Some(CompatibilityMode::Forward)).await.unwrap();
+        assert_eq!(report.issues.len(), 2, "{:?}", report.issues);
+        assert_eq!(service.publish_schema(name, v2.clone(), None, None, None).await.unwrap().version, 2);
+
+        // Requiring a new field breaks readers of old events
+        let mut v3 = v2.clone();
+        v3["required"] = json!(["execution_id", "state", "tenant_id"]);
+        assert!(matches!(
+            service.publish_schema(name, v3.clone(), None, None, None).await,
+            Err(RegistryError::VersionConflict(message)) if message.contains("$ (backward): field tenant_id is required")
+        ));
+        assert!(service.check_schema(name, &v3, Some(CompatibilityMode::Forward)).await.unwrap().compatible);
+        assert_eq!(service.publish_schema(name, v3, Some(CompatibilityMode::None), None, None).await.unwrap().version, 3);
+
+        assert_eq!(service.get_schema(name, 1).await.unwrap().unwrap().json_schema, v1);
+        assert_eq!(service.latest_schema(name).await.unwrap().unwrap().compatibility, "none");
+        assert_eq!(service.list_versions(name).await.unwrap().len(), 3);
+        assert_eq!(service.list_schemas().await.unwrap().len(), 1);
+        assert!(matches!(
+            service.publish_schema("Bad Name", json!(true), None, None, None).await,
+            Err(RegistryError::ValidationFailed(errors)) if errors.len() == 2
+        ));
+    }
+
+    #[test]
+    fn test_check_compatibility() {
+        let open = json!({"type": "object", "properties": {"items": {"type": "array", "items": {"type": "string", "maxLength": 10}}}});
+        let closed = json!({
+            "type": "object",
+            "properties": {"items": {"type": "array", "items": {"type": "string", "maxLength": 5}}},
+            "additionalProperties": false
+        });
+        assert!(check_compatibility(&open, &open, CompatibilityMode::Full).is_empty());
+        // Closing the object and tightening nested bounds only breaks backward compatibility
+        let issues = check_compatibility(&open, &closed, CompatibilityMode::Full);
+        assert_eq!(issues, vec![
+            "$ (backward): additional properties are not allowed".to_string(),
+            "$.items[] (backward): maxLength 5 is stricter".to_string(),
+        ]);
+        assert!(check_compatibility(&open, &closed, CompatibilityMode::Forward).is_empty());
+        assert!(check_compatibility(&open, &json!({"type": "string"}), CompatibilityMode::None).is_empty());
+    }
+
     #[tokio::test]
     async fn test_version_manager() {
         let registry = create_test_registry().await.unwrap();
@@ -183,6 +824,46 @@ mod tests {
         assert_eq!(retrieved, None);
     }
     
+    #[tokio::test]
+    async fn test_registry_read_through_cache() {
+        let registry = create_test_registry().await.unwrap();
+        let mut tool = ToolInfo::builder()
+            .id(ToolId::from_string("cached-tool".to_string()))
+            .name("cached-tool")
+            .description("Cached")
+            .tool_type(ToolType::Python)
+            .author("test-author")
+            .build()
+            .unwrap();
+        registry.register_tool(tool.clone()).await.unwrap();
+        
+        // Second reads are served from the cache
+        registry.get_tool(&tool.id).await.unwrap();
+        registry.get_tool(&tool.id).await.unwrap();
+        assert_eq!(registry.search_tools("cached").await.unwrap().len(), 1);
+        registry.search_tools("cached").await.unwrap();
+        let stats = registry.cache_stats().await.unwrap();
+        assert_eq!((stats.hits, stats.misses), (2, 2));
+        
+        // Writes invalidate the tool and every cached list
+        tool.description = "Updated".to_string();
+        registry.update_tool(&tool.id, &tool).await.unwrap();
+        assert_eq!(registry.get_tool(&tool.id).await.unwrap().description, "Updated");
+        registry.tool_manager().delete_tool(&tool.id).await.unwrap();
+        assert!(registry.get_tool(&tool.id).await.is_err());
+        assert!(registry.search_tools("cached").await.unwrap().is_empty());
+        
+        let metrics = registry.cache_metrics().await;
+        assert!(metrics.iter().any(|metric| metric.name == "registry_cache_hit_rate"));
+        
+        // Disabled cache reports no stats
+        let db = Arc::new(SqliteDatabase::new("sqlite::memory:").await.unwrap());
+        MigrationManager::run_migrations(&db).await.unwrap();
+        let config = RegistryCacheConfig { enabled: false, ..Default::default() };
+        let uncached = RegistryImpl::with_cache_config(db, config).await.unwrap();
+        assert!(uncached.cache_stats().await.is_none());
+    }
+    
     #[tokio::test]
     async fn test_validation_system() {
         let validator = InputValidatorImpl::new();
@@ -197,4 +878,38 @@ mod tests {
         let result = validator.validate_tool_name("invalid name with spaces");
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_tool_packages_deduplicated_and_collected() {
+        let content = Arc::new(ContentStore::new());
+        let packages = ToolPackageStore::new(content.clone());
+        let tool_id = ToolId::from_string("bundled-tool".to_string());
+        let bundle = vec![7u8; 1024];
+
+        let v1 = packages.put(&tool_id, &ToolVersion::new(1, 0, 0), "application/zip", &bundle).unwrap();
+        let v2 = packages.put(&tool_id, &ToolVersion::new(1, 1, 0), "application/zip", &bundle).unwrap();
+        assert_eq!(v1.digest, v2.digest);
+        assert_eq!(v1.digest, content_digest(&bundle));
+
+        let stats = content.stats().unwrap();
+        assert_eq!(stats.blobs, 1);
+        assert_eq!(stats.references, 2);
+        assert_eq!(stats.dedup_hits, 1);
+        assert_eq!(stats.saved_bytes(), 1024);
+
+        // Re-uploading a version releases the bundle it replaced
+        packages.put(&tool_id, &ToolVersion::new(1, 1, 0), "application/zip", b"rebuilt").unwrap();
+        assert_eq!(content.references(&v1.digest).unwrap(), Some(1));
+        assert_eq!(packages.list(&tool_id).unwrap().len(), 2);
+
+        // Referenced blobs survive GC; released ones are collected
+        assert_eq!(content.gc().unwrap(), GcReport::default());
+        assert!(packages.delete(&tool_id, &ToolVersion::new(1, 0, 0)).unwrap());
+        assert_eq!(content.stats().unwrap().unreferenced_blobs, 1);
+        let report = content.gc().unwrap();
+        assert_eq!(report.removed_blobs, 1);
+        assert_eq!(report.freed_bytes, 1024);
+        assert!(!content.contains(&v1.digest).unwrap());
+        assert_eq!(&*packages.read(&tool_id, &ToolVersion::new(1, 1, 0)).unwrap().unwrap(), b"rebuilt");
+    }
 } 
\ No newline at end of file
@@ -0,0 +1,441 @@
+//! Shared tool catalog (marketplace) implementation
+//!
+//! A tenant publishes one of its tools as a listing, either globally or to a
+//! set of tenants. A tool belongs to the tenant named as its `author` (by
+//! tenant ID); other tenants cannot publish it. Consuming tenants subscribe to a listing, which creates a
+//! linked entry pointing at the publisher's tool rather than a copy. When the
+//! publisher pushes an update, subscribers see it as a pending version that
+//! must be approved before it is used. Every version published through a
+//! listing is snapshotted, so subscribers resolve the version they installed
+//! even after the publisher's tool has moved on.
+//!
+//! Listing and subscription writes invalidate the registry's tool cache for the
+//! tool involved when the service is given one (see [`MarketplaceService::with_cache`]).
+
+use std::sync::Arc;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use stepflow_core::*;
+use stepflow_database::{MarketplaceRepository, SqliteDatabase, ToolListingRecord, ToolRepository, ToolSubscriptionRecord};
+use crate::cache::RegistryCache;
+use crate::errors::*;
+
+/// Listing visibility
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "tenants", rename_all = "snake_case")]
+pub enum ListingVisibility {
+    /// Visible to every tenant
+    Global,
+    /// Visible only to the given tenants
+    Tenants(Vec<TenantId>),
+}
+
+impl ListingVisibility {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ListingVisibility::Global => "global",
+            ListingVisibility::Tenants(_) => "tenants",
+        }
+    }
+
+    fn targets(&self) -> &[TenantId] {
+        match self {
+            ListingVisibility::Global => &[],
+            ListingVisibility::Tenants(tenants) => tenants,
+        }
+    }
+}
+
+/// Listing status
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ListingStatus {
+    Published,
+    Withdrawn,
+}
+
+impl std::fmt::Display for ListingStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ListingStatus::Published => write!(f, "published"),
+            ListingStatus::Withdrawn => write!(f, "withdrawn"),
+        }
+    }
+}
+
+/// Subscription status
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriptionStatus {
+    /// Using the installed version
+    Active,
+    /// A newer version was published and awaits approval
+    UpdatePending,
+}
+
+impl std::fmt::Display for SubscriptionStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SubscriptionStatus::Active => write!(f, "active"),
+            SubscriptionStatus::UpdatePending => write!(f, "update_pending"),
+        }
+    }
+}
+
+/// A tool published to the shared catalog
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolListing {
+    pub id: String,
+    pub tool_id: ToolId,
+    pub publisher_tenant_id: TenantId,
+    pub visibility: ListingVisibility,
+    pub published_version: String,
+    pub status: ListingStatus,
+    pub created_at: chrono::DateTime<Utc>,
+    pub updated_at: chrono::DateTime<Utc>,
+}
+
+impl From<ToolListingRecord> for ToolListing {
+    fn from(record: ToolListingRecord) -> Self {
+        let visibility = match record.visibility.as_str() {
+            "global" => ListingVisibility::Global,
+            _ => ListingVisibility::Tenants(record.target_tenants),
+        };
+        let status = match record.status.as_str() {
+            "withdrawn" => ListingStatus::Withdrawn,
+            _ => ListingStatus::Published,
+        };
+
+        Self {
+            id: record.id,
+            tool_id: record.tool_id,
+            publisher_tenant_id: record.publisher_tenant_id,
+            visibility,
+            published_version: record.published_version,
+            status,
+            created_at: record.created_at,
+            updated_at: record.updated_at,
+        }
+    }
+}
+
+impl From<&ToolListing> for ToolListingRecord {
+    fn from(listing: &ToolListing) -> Self {
+        Self {
+            id: listing.id.clone(),
+            tool_id: listing.tool_id.clone(),
+            publisher_tenant_id: listing.publisher_tenant_id.clone(),
+            visibility: listing.visibility.as_str().to_string(),
+            target_tenants: listing.visibility.targets().to_vec(),
+            published_version: listing.published_version.clone(),
+            status: listing.status.to_string(),
+            created_at: listing.created_at,
+            updated_at: listing.updated_at,
+        }
+    }
+}
+
+/// A tenant's linked installation of a listing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolSubscription {
+    pub id: String,
+    pub listing_id: String,
+    pub tenant_id: TenantId,
+    pub tool_id: ToolId,
+    pub installed_version: String,
+    pub pending_version: Option<String>,
+    pub status: SubscriptionStatus,
+    pub created_at: chrono::DateTime<Utc>,
+    pub updated_at: chrono::DateTime<Utc>,
+}
+
+impl From<ToolSubscriptionRecord> for ToolSubscription {
+    fn from(record: ToolSubscriptionRecord) -> Self {
+        let status = match record.status.as_str() {
+            "update_pending" => SubscriptionStatus::UpdatePending,
+            _ => SubscriptionStatus::Active,
+        };
+
+        Self {
+            id: record.id,
+            listing_id: record.listing_id,
+            tenant_id: record.tenant_id,
+            tool_id: record.tool_id,
+            installed_version: record.installed_version,
+            pending_version: record.pending_version,
+            status,
+            created_at: record.created_at,
+            updated_at: record.updated_at,
+        }
+    }
+}
+
+impl From<&ToolSubscription> for ToolSubscriptionRecord {
+    fn from(subscription: &ToolSubscription) -> Self {
+        Self {
+            id: subscription.id.clone(),
+            listing_id: subscription.listing_id.clone(),
+            tenant_id: subscription.tenant_id.clone(),
+            tool_id: subscription.tool_id.clone(),
+            installed_version: subscription.installed_version.clone(),
+            pending_version: subscription.pending_version.clone(),
+            status: subscription.status.to_string(),
+            created_at: subscription.created_at,
+            updated_at: subscription.updated_at,
+        }
+    }
+}
+
+/// Marketplace service
+pub struct MarketplaceService {
+    tool_repository: Arc<ToolRepository>,
+    marketplace_repository: Arc<MarketplaceRepository>,
+    cache: Option<Arc<RegistryCache>>,
+}
+
+impl MarketplaceService {
+    /// Create a new marketplace service
+    pub fn new(db: Arc<SqliteDatabase>) -> Self {
+        Self {
+            tool_repository: Arc::new(ToolRepository::new(db.as_ref().clone())),
+            marketplace_repository: Arc::new(MarketplaceRepository::new(db.as_ref().clone())),
+            cache: None,
+        }
+    }
+
+    /// Invalidate `cache` on every write
+    pub fn with_cache(mut self, cache: Option<Arc<RegistryCache>>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    async fn invalidate(&self, tool_id: &ToolId) {
+        if let Some(cache) = &self.cache {
+            cache.invalidate_tool(tool_id).await;
+        }
+    }
+
+    /// Publish a tool to the shared catalog; the publisher must be the tool's author
+    pub async fn publish_tool(
+        &self,
+        publisher: &TenantId,
+        tool_id: &ToolId,
+        visibility: ListingVisibility,
+    ) -> RegistryResult<ToolListing> {
+        let tool = self.tool_repository.get_tool(tool_id).await?
+            .ok_or_else(|| RegistryError::ToolNotFound(tool_id.to_string()))?;
+        if tool.author != publisher.as_str() {
+            return Err(RegistryError::PermissionDenied(format!(
+                "Tenant {} is not the author of tool {}", publisher, tool_id
+            )));
+        }
+
+        let now = Utc::now();
+        let listing = ToolListing {
+            id: uuid::Uuid::new_v4().to_string(),
+            tool_id: tool.id.clone(),
+            publisher_tenant_id: publisher.clone(),
+            visibility,
+            published_version: tool.version.to_string(),
+            status: ListingStatus::Published,
+            created_at: now,
+            updated_at: now,
+        };
+
+        self.marketplace_repository.create_listing(&(&listing).into()).await?;
+        self.marketplace_repository.save_listing_version(&listing.id, &listing.published_version, &tool).await?;
+        self.invalidate(&listing.tool_id).await;
+        Ok(listing)
+    }
+
+    /// Withdraw a listing from the catalog; existing subscriptions keep their link
+    pub async fn withdraw_listing(&self, publisher: &TenantId, listing_id: &str) -> RegistryResult<ToolListing> {
+        let mut listing = self.get_owned_listing(publisher, listing_id).await?;
+        listing.status = ListingStatus::Withdrawn;
+        listing.updated_at = Utc::now();
+
+        self.marketplace_repository.update_listing(&(&listing).into()).await?;
+        self.invalidate(&listing.tool_id).await;
+        Ok(listing)
+    }
+
+    /// Get a listing by ID
+    pub async fn get_listing(&self, listing_id: &str) -> RegistryResult<ToolListing> {
+        self.marketplace_repository.get_listing(listing_id).await?
+            .map(Into::into)
+            .ok_or_else(|| RegistryError::ListingNotFound(listing_id.to_string()))
+    }
+
+    /// List catalog entries visible to a tenant
+    pub async fn list_catalog(&self, tenant_id: &TenantId) -> RegistryResult<Vec<ToolListing>> {
+        let listings = self.marketplace_repository.list_visible_listings(tenant_id).await?;
+        Ok(listings.into_iter().map(Into::into).collect())
+    }
+
+    /// Subscribe a tenant to a listing
+    pub async fn subscribe(&self, tenant_id: &TenantId, listing_id: &str) -> RegistryResult<ToolSubscription> {
+        let listing = self.get_listing(listing_id).await?;
+
+        if listing.status != ListingStatus::Published || !Self::is_visible_to(&listing, tenant_id) {
+            return Err(RegistryError::ListingNotFound(listing_id.to_string()));
+        }
+        if &listing.publisher_tenant_id == tenant_id {
+            return Err(RegistryError::InvalidOperation(
+                "A tenant cannot subscribe to its own listing".to_string()
+            ));
+        }
+        if self.marketplace_repository.find_subscription(listing_id, tenant_id).await?.is_some() {
+            return Err(RegistryError::ToolAlreadyExists(format!(
+                "Tenant {} is already subscribed to listing {}", tenant_id, listing_id
+            )));
+        }
+
+        let now = Utc::now();
+        let subscription = ToolSubscription {
+            id: uuid::Uuid::new_v4().to_string(),
+            listing_id: listing.id,
+            tenant_id: tenant_id.clone(),
+            tool_id: listing.tool_id,
+            installed_version: listing.published_version,
+            pending_version: None,
+            status: SubscriptionStatus::Active,
+            created_at: now,
+            updated_at: now,
+        };
+
+        self.marketplace_repository.create_subscription(&(&subscription).into()).await?;
+        self.invalidate(&subscription.tool_id).await;
+        Ok(subscription)
+    }
+
+    /// Remove a tenant's subscription
+    pub async fn unsubscribe(&self, tenant_id: &TenantId, subscription_id: &str) -> RegistryResult<()> {
+        let subscription = self.get_owned_subscription(tenant_id, subscription_id).await?;
+        self.marketplace_repository.delete_subscription(&subscription.id).await?;
+        self.invalidate(&subscription.tool_id).await;
+        Ok(())
+    }
+
+    /// List a tenant's subscriptions
+    pub async fn list_subscriptions(&self, tenant_id: &TenantId) -> RegistryResult<Vec<ToolSubscription>> {
+        let subscriptions = self.marketplace_repository.list_subscriptions_by_tenant(tenant_id).await?;
+        Ok(subscriptions.into_iter().map(Into::into).collect())
+    }
+
+    /// Propagate the tool's current version to a listing and its subscribers.
+    ///
+    /// Subscribers are moved to `UpdatePending` and keep running the installed
+    /// version until they approve. Returns the number of subscriptions notified.
+    pub async fn publish_update(&self, publisher: &TenantId, listing_id: &str) -> RegistryResult<usize> {
+        let mut listing = self.get_owned_listing(publisher, listing_id).await?;
+        let tool = self.tool_repository.get_tool(&listing.tool_id).await?
+            .ok_or_else(|| RegistryError::ToolNotFound(listing.tool_id.to_string()))?;
+
+        let version = tool.version.to_string();
+        if version == listing.published_version {
+            return Ok(0);
+        }
+
+        self.marketplace_repository.save_listing_version(&listing.id, &version, &tool).await?;
+        listing.published_version = version.clone();
+        listing.updated_at = Utc::now();
+        self.marketplace_repository.update_listing(&(&listing).into()).await?;
+
+        let mut notified = 0;
+        for record in self.marketplace_repository.list_subscriptions_by_listing(listing_id).await? {
+            let mut subscription = ToolSubscription::from(record);
+            if subscription.installed_version == version {
+                continue;
+            }
+            subscription.pending_version = Some(version.clone());
+            subscription.status = SubscriptionStatus::UpdatePending;
+            subscription.updated_at = Utc::now();
+            self.marketplace_repository.update_subscription(&(&subscription).into()).await?;
+            notified += 1;
+        }
+
+        self.invalidate(&listing.tool_id).await;
+        Ok(notified)
+    }
+
+    /// Approve a pending update, making it the installed version
+    pub async fn approve_update(&self, tenant_id: &TenantId, subscription_id: &str) -> RegistryResult<ToolSubscription> {
+        let mut subscription = self.get_owned_subscription(tenant_id, subscription_id).await?;
+        let pending = subscription.pending_version.take().ok_or_else(|| {
+            RegistryError::InvalidOperation(format!("Subscription {} has no pending update", subscription_id))
+        })?;
+
+        subscription.installed_version = pending;
+        subscription.status = SubscriptionStatus::Active;
+        subscription.updated_at = Utc::now();
+
+        self.marketplace_repository.update_subscription(&(&subscription).into()).await?;
+        self.invalidate(&subscription.tool_id).await;
+        Ok(subscription)
+    }
+
+    /// Reject a pending update, keeping the installed version
+    pub async fn reject_update(&self, tenant_id: &TenantId, subscription_id: &str) -> RegistryResult<ToolSubscription> {
+        let mut subscription = self.get_owned_subscription(tenant_id, subscription_id).await?;
+        if subscription.pending_version.take().is_none() {
+            return Err(RegistryError::InvalidOperation(format!(
+                "Subscription {} has no pending update", subscription_id
+            )));
+        }
+
+        subscription.status = SubscriptionStatus::Active;
+        subscription.updated_at = Utc::now();
+
+        self.marketplace_repository.update_subscription(&(&subscription).into()).await?;
+        self.invalidate(&subscription.tool_id).await;
+        Ok(subscription)
+    }
+
+    /// Resolve the installed version of the tool behind a subscription
+    ///
+    /// A pending update is not used until it is approved. Listings published
+    /// before versions were snapshotted fall back to the publisher's tool while
+    /// it is still at the installed version.
+    pub async fn resolve_subscribed_tool(&self, tenant_id: &TenantId, subscription_id: &str) -> RegistryResult<ToolInfo> {
+        let subscription = self.get_owned_subscription(tenant_id, subscription_id).await?;
+        if let Some(tool) = self.marketplace_repository
+            .get_listing_version(&subscription.listing_id, &subscription.installed_version)
+            .await?
+        {
+            return Ok(tool);
+        }
+
+        self.tool_repository.get_tool(&subscription.tool_id).await?
+            .filter(|tool| tool.version.to_string() == subscription.installed_version)
+            .ok_or_else(|| RegistryError::ToolNotFound(format!(
+                "{} version {}", subscription.tool_id, subscription.installed_version
+            )))
+    }
+
+    fn is_visible_to(listing: &ToolListing, tenant_id: &TenantId) -> bool {
+        match &listing.visibility {
+            ListingVisibility::Global => true,
+            ListingVisibility::Tenants(tenants) => tenants.contains(tenant_id),
+        }
+    }
+
+    async fn get_owned_listing(&self, publisher: &TenantId, listing_id: &str) -> RegistryResult<ToolListing> {
+        let listing = self.get_listing(listing_id).await?;
+        if &listing.publisher_tenant_id != publisher {
+            return Err(RegistryError::PermissionDenied(format!(
+                "Listing {} is not owned by tenant {}", listing_id, publisher
+            )));
+        }
+        Ok(listing)
+    }
+
+    async fn get_owned_subscription(&self, tenant_id: &TenantId, subscription_id: &str) -> RegistryResult<ToolSubscription> {
+        let subscription: ToolSubscription = self.marketplace_repository.get_subscription(subscription_id).await?
+            .map(Into::into)
+            .ok_or_else(|| RegistryError::SubscriptionNotFound(subscription_id.to_string()))?;
+        if &subscription.tenant_id != tenant_id {
+            return Err(RegistryError::SubscriptionNotFound(subscription_id.to_string()));
+        }
+        Ok(subscription)
+    }
+}
@@ -0,0 +1,200 @@
+//! In-memory registry
+//!
+//! A HashMap-backed [`Registry`] for unit tests of registry consumers (the
+//! executor, discovery, favorites) that should not need SQLite. It has no
+//! tool cache and records no change feed. Every operation goes through a
+//! [`SimulatedFaults`] handle, so tests can make chosen calls fail or slow
+//! down deterministically.
+
+use std::collections::HashMap;
+
+use stepflow_core::*;
+use stepflow_database::{BatchFailure, BatchInsertReport};
+use tokio::sync::RwLock;
+
+use crate::discovery::order_favorites_first;
+use crate::errors::*;
+use crate::registry::*;
+
+/// In-memory registry
+#[derive(Default)]
+pub struct InMemoryRegistry {
+    tools: RwLock<HashMap<ToolId, ToolInfo>>,
+    /// Favorite tool ids per user, most recently added first
+    favorites: RwLock<HashMap<UserId, Vec<ToolId>>>,
+    faults: SimulatedFaults,
+}
+
+impl InMemoryRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an empty registry driven by `faults`
+    pub fn with_faults(faults: SimulatedFaults) -> Self {
+        Self {
+            faults,
+            ..Self::default()
+        }
+    }
+
+    async fn check(&self, operation: &str) -> RegistryResult<()> {
+        self.faults.check(operation).await.map_err(RegistryError::DatabaseError)
+    }
+
+    /// Tools matching `predicate`, oldest first
+    async fn select<F>(&self, predicate: F) -> Vec<ToolInfo>
+    where
+        F: Fn(&ToolInfo) -> bool,
+    {
+        let mut tools: Vec<ToolInfo> = self.tools.read().await.values().filter(|tool| predicate(tool)).cloned().collect();
+        tools.sort_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| a.name.cmp(&b.name)));
+        tools
+    }
+
+    fn insert(tools: &mut HashMap<ToolId, ToolInfo>, tool: ToolInfo) -> RegistryResult<()> {
+        if tools.contains_key(&tool.id) {
+            return Err(RegistryError::ToolAlreadyExists(tool.id.to_string()));
+        }
+        tools.insert(tool.id.clone(), tool);
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl Registry for InMemoryRegistry {
+    async fn register_tool(&self, tool: ToolInfo) -> RegistryResult<ToolId> {
+        self.check("register_tool").await?;
+        let tool_id = tool.id.clone();
+        Self::insert(&mut *self.tools.write().await, tool)?;
+        Ok(tool_id)
+    }
+
+    async fn register_tools(&self, tools: Vec<ToolInfo>) -> RegistryResult<BatchInsertReport> {
+        self.check("register_tools").await?;
+        let mut stored = self.tools.write().await;
+        let mut report = BatchInsertReport::default();
+        for (index, tool) in tools.into_iter().enumerate() {
+            match Self::insert(&mut stored, tool) {
+                Ok(()) => report.inserted += 1,
+                Err(e) => report.failures.push(BatchFailure { index, error: e.to_string() }),
+            }
+        }
+        Ok(report)
+    }
+
+    async fn get_tool(&self, tool_id: &ToolId) -> RegistryResult<ToolInfo> {
+        self.check("get_tool").await?;
+        self.tools
+            .read()
+            .await
+            .get(tool_id)
+            .cloned()
+            .ok_or_else(|| RegistryError::ToolNotFound(tool_id.to_string()))
+    }
+
+    async fn list_tools(&self) -> RegistryResult<Vec<ToolInfo>> {
+        self.check("list_tools").await?;
+        Ok(self.select(|_| true).await)
+    }
+
+    async fn search_tools(&self, query: &str) -> RegistryResult<Vec<ToolInfo>> {
+        self.check("search_tools").await?;
+        // Same semantics as the SQLite `LIKE '%query%'`, which is case-insensitive for ASCII
+        let query = query.to_lowercase();
+        Ok(self
+            .select(|tool| tool.name.to_lowercase().contains(&query) || tool.description.to_lowercase().contains(&query))
+            .await)
+    }
+
+    async fn update_tool(&self, tool_id: &ToolId, tool: &ToolInfo) -> RegistryResult<()> {
+        self.check("update_tool").await?;
+        let mut tools = self.tools.write().await;
+        let stored = tools.get_mut(tool_id).ok_or_else(|| RegistryError::ToolNotFound(tool_id.to_string()))?;
+        let created_at = stored.created_at;
+        *stored = tool.clone();
+        stored.id = tool_id.clone();
+        stored.created_at = created_at;
+        Ok(())
+    }
+
+    async fn delete_tool(&self, tool_id: &ToolId) -> RegistryResult<()> {
+        self.check("delete_tool").await?;
+        if self.tools.write().await.remove(tool_id).is_none() {
+            return Err(RegistryError::ToolNotFound(tool_id.to_string()));
+        }
+        for favorites in self.favorites.write().await.values_mut() {
+            favorites.retain(|id| id != tool_id);
+        }
+        Ok(())
+    }
+
+    async fn get_tools_by_type(&self, tool_type: &ToolType) -> RegistryResult<Vec<ToolInfo>> {
+        self.check("get_tools_by_type").await?;
+        Ok(self.select(|tool| tool.tool_type == *tool_type).await)
+    }
+
+    async fn get_tools_by_status(&self, status: &ToolStatus) -> RegistryResult<Vec<ToolInfo>> {
+        self.check("get_tools_by_status").await?;
+        Ok(self.select(|tool| tool.status == *status).await)
+    }
+
+    async fn tool_exists(&self, tool_id: &ToolId) -> RegistryResult<bool> {
+        self.check("tool_exists").await?;
+        Ok(self.tools.read().await.contains_key(tool_id))
+    }
+
+    async fn get_tool_stats(&self) -> RegistryResult<ToolStats> {
+        self.check("get_tool_stats").await?;
+        let tools = self.tools.read().await;
+        let count = |status: ToolStatus| tools.values().filter(|tool| tool.status == status).count() as u64;
+        Ok(ToolStats {
+            total_tools: tools.len() as u64,
+            active_tools: count(ToolStatus::Active),
+            deprecated_tools: count(ToolStatus::Deprecated),
+            error_tools: count(ToolStatus::Error),
+        })
+    }
+
+    async fn add_favorite(&self, user_id: &UserId, tool_id: &ToolId) -> RegistryResult<()> {
+        self.check("add_favorite").await?;
+        if !self.tools.read().await.contains_key(tool_id) {
+            return Err(RegistryError::ToolNotFound(tool_id.to_string()));
+        }
+        let mut favorites = self.favorites.write().await;
+        let user_favorites = favorites.entry(user_id.clone()).or_default();
+        if !user_favorites.contains(tool_id) {
+            user_favorites.insert(0, tool_id.clone());
+        }
+        Ok(())
+    }
+
+    async fn remove_favorite(&self, user_id: &UserId, tool_id: &ToolId) -> RegistryResult<()> {
+        self.check("remove_favorite").await?;
+        if let Some(user_favorites) = self.favorites.write().await.get_mut(user_id) {
+            user_favorites.retain(|id| id != tool_id);
+        }
+        Ok(())
+    }
+
+    async fn list_favorites(&self, user_id: &UserId) -> RegistryResult<Vec<ToolInfo>> {
+        self.check("list_favorites").await?;
+        let favorites = self.favorites.read().await;
+        let tools = self.tools.read().await;
+        Ok(favorites
+            .get(user_id)
+            .map(|ids| ids.iter().filter_map(|id| tools.get(id).cloned()).collect())
+            .unwrap_or_default())
+    }
+
+    async fn discover_tools_for_user(&self, user_id: &UserId) -> RegistryResult<Vec<ToolInfo>> {
+        self.check("discover_tools_for_user").await?;
+        let favorite_ids = self.favorites.read().await.get(user_id).cloned().unwrap_or_default();
+        Ok(order_favorites_first(self.select(|_| true).await, &favorite_ids))
+    }
+
+    async fn health_check(&self) -> RegistryResult<bool> {
+        Ok(self.check("health_check").await.is_ok())
+    }
+}
@@ -0,0 +1,164 @@
+//! Redis cache backend
+//!
+//! Shares the registry cache between instances, so a tool written through one instance is
+//! invalidated everywhere instead of staying stale until the TTL passes. Keys live under
+//! `{prefix}:registry:` and expire through Redis itself. Hit and miss counters are local to
+//! the instance; size counts the shared keys.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use redis::aio::ConnectionManager;
+use stepflow_core::{CacheStats, StepflowError, StepflowResult};
+use tracing::warn;
+
+use crate::cache::CacheBackend;
+
+/// Keys fetched per SCAN round trip when removing by prefix or counting
+const SCAN_COUNT: usize = 500;
+
+pub struct RedisCache {
+    connection: ConnectionManager,
+    namespace: String,
+    ttl: Duration,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl RedisCache {
+    /// Connect to `url`, keeping entries for `ttl` under `{prefix}:registry:`
+    pub async fn connect(url: &str, prefix: &str, ttl: Duration) -> StepflowResult<Self> {
+        let client = redis::Client::open(url).map_err(|e| StepflowError::ConfigurationError(format!("Invalid Redis URL: {}", e)))?;
+        let connection = client.get_connection_manager().await.map_err(redis_error)?;
+        Ok(Self::new(connection, prefix, ttl))
+    }
+
+    pub fn new(connection: ConnectionManager, prefix: &str, ttl: Duration) -> Self {
+        Self {
+            connection,
+            namespace: format!("{}:registry:", prefix),
+            ttl,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    fn key(&self, key: &str) -> String {
+        format!("{}{}", self.namespace, key)
+    }
+
+    /// All keys starting with `prefix`, relative to the namespace
+    async fn scan(&self, prefix: &str) -> redis::RedisResult<Vec<String>> {
+        let pattern = format!("{}*", escape_glob(&self.key(prefix)));
+        let mut connection = self.connection.clone();
+        let mut keys = Vec::new();
+        let mut cursor = 0u64;
+        loop {
+            let (next, batch): (u64, Vec<String>) = redis::cmd("SCAN")
+                .arg(cursor)
+                .arg("MATCH")
+                .arg(&pattern)
+                .arg("COUNT")
+                .arg(SCAN_COUNT)
+                .query_async(&mut connection)
+                .await?;
+            keys.extend(batch);
+            if next == 0 {
+                return Ok(keys);
+            }
+            cursor = next;
+        }
+    }
+
+    async fn delete_matching(&self, prefix: &str) -> redis::RedisResult<()> {
+        let keys = self.scan(prefix).await?;
+        let mut connection = self.connection.clone();
+        for chunk in keys.chunks(SCAN_COUNT) {
+            redis::cmd("DEL").arg(chunk).query_async::<_, ()>(&mut connection).await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl CacheBackend for RedisCache {
+    async fn get(&self, key: &str) -> Option<String> {
+        let mut connection = self.connection.clone();
+        let value = redis::cmd("GET")
+            .arg(self.key(key))
+            .query_async::<_, Option<String>>(&mut connection)
+            .await
+            .unwrap_or_else(|e| {
+                warn!("Redis cache lookup failed: {}", e);
+                None
+            });
+        let counter = if value.is_some() { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        value
+    }
+
+    async fn put(&self, key: String, value: String) {
+        let mut connection = self.connection.clone();
+        let ttl_ms = self.ttl.as_millis().max(1) as u64;
+        let result = redis::cmd("SET")
+            .arg(self.key(&key))
+            .arg(value)
+            .arg("PX")
+            .arg(ttl_ms)
+            .query_async::<_, ()>(&mut connection)
+            .await;
+        if let Err(e) = result {
+            warn!("Redis cache write failed: {}", e);
+        }
+    }
+
+    async fn remove(&self, key: &str) {
+        let mut connection = self.connection.clone();
+        if let Err(e) = redis::cmd("DEL").arg(self.key(key)).query_async::<_, ()>(&mut connection).await {
+            warn!("Redis cache invalidation failed: {}", e);
+        }
+    }
+
+    async fn remove_prefix(&self, prefix: &str) {
+        if let Err(e) = self.delete_matching(prefix).await {
+            warn!("Redis cache invalidation failed: {}", e);
+        }
+    }
+
+    async fn clear(&self) {
+        if let Err(e) = self.delete_matching("").await {
+            warn!("Redis cache clear failed: {}", e);
+        }
+    }
+
+    async fn stats(&self) -> CacheStats {
+        let size = self.scan("").await.map(|keys| keys.len()).unwrap_or_else(|e| {
+            warn!("Redis cache size lookup failed: {}", e);
+            0
+        });
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            size,
+            // Bounded by the Redis memory policy rather than an entry count
+            max_size: 0,
+        }
+    }
+}
+
+fn redis_error(e: redis::RedisError) -> StepflowError {
+    StepflowError::InternalError(format!("Redis error: {}", e))
+}
+
+/// Escape glob metacharacters so a key prefix matches literally in SCAN MATCH
+fn escape_glob(prefix: &str) -> String {
+    let mut escaped = String::with_capacity(prefix.len());
+    for c in prefix.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
@@ -1,6 +1,9 @@
 //! Registry trait definitions
 
+use std::sync::Arc;
 use stepflow_core::*;
+use stepflow_database::BatchInsertReport;
+use crate::cache::RegistryCache;
 use crate::errors::*;
 
 /// Registry trait for tool management
@@ -9,6 +12,9 @@ pub trait Registry: Send + Sync {
     /// Register a new tool
     async fn register_tool(&self, tool: ToolInfo) -> RegistryResult<ToolId>;
     
+    /// Register tools in one batch; rows that fail are reported by position and the rest are kept
+    async fn register_tools(&self, tools: Vec<ToolInfo>) -> RegistryResult<BatchInsertReport>;
+    
     /// Get a tool by ID
     async fn get_tool(&self, tool_id: &ToolId) -> RegistryResult<ToolInfo>;
     
@@ -36,6 +42,28 @@ pub trait Registry: Send + Sync {
     /// Get tool statistics
     async fn get_tool_stats(&self) -> RegistryResult<ToolStats>;
     
+    /// Add a tool to a user's favorites
+    async fn add_favorite(&self, user_id: &UserId, tool_id: &ToolId) -> RegistryResult<()>;
+    
+    /// Remove a tool from a user's favorites; removing a tool that isn't a favorite is a no-op
+    async fn remove_favorite(&self, user_id: &UserId, tool_id: &ToolId) -> RegistryResult<()>;
+    
+    /// List a user's favorite tools, most recently added first
+    async fn list_favorites(&self, user_id: &UserId) -> RegistryResult<Vec<ToolInfo>>;
+    
+    /// List all tools with the user's favorites ordered first
+    async fn discover_tools_for_user(&self, user_id: &UserId) -> RegistryResult<Vec<ToolInfo>>;
+    
+    /// Tool cache statistics, `None` when the registry doesn't cache
+    async fn cache_stats(&self) -> Option<CacheStats> {
+        None
+    }
+    
+    /// Tool cache that services writing tools outside the registry must invalidate
+    fn tool_cache(&self) -> Option<Arc<RegistryCache>> {
+        None
+    }
+    
     /// Health check for the registry
     async fn health_check(&self) -> RegistryResult<bool>;
 } 
\ No newline at end of file
@@ -1,64 +1,209 @@
 //! Registry implementation
 
+use std::collections::HashSet;
 use std::sync::Arc;
+use std::time::Duration;
 use stepflow_core::*;
-use stepflow_database::{SqliteDatabase, ToolRepository};
+use stepflow_database::{BatchInsertReport, SqliteDatabase, ToolRepository, FavoriteRepository};
+use crate::cache::{CacheBackend, RegistryCache};
+use crate::change_feed::{ChangeFeed, ToolChangeType};
+use crate::discovery::order_favorites_first;
 use crate::errors::*;
 use crate::registry::*;
 
+/// Tool cache configuration
+#[derive(Debug, Clone)]
+pub struct RegistryCacheConfig {
+    pub enabled: bool,
+    pub max_size: usize,
+    /// Bounds how stale a tool written by another process can be
+    pub ttl: Duration,
+    /// Backend to cache in instead of the in-process one; `max_size` and `ttl` then
+    /// only apply if the backend itself was built with them
+    pub backend: Option<Arc<dyn CacheBackend>>,
+}
+
+impl Default for RegistryCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_size: 1000,
+            ttl: Duration::from_secs(60),
+            backend: None,
+        }
+    }
+}
+
+impl From<&ToolsConfig> for RegistryCacheConfig {
+    fn from(config: &ToolsConfig) -> Self {
+        Self {
+            enabled: config.tool_cache_size > 0,
+            max_size: config.tool_cache_size,
+            ttl: config.tool_cache_ttl,
+            backend: None,
+        }
+    }
+}
+
 /// Registry implementation
+///
+/// Tool reads go through an in-process cache unless it is disabled; writes made
+/// through the registry or its tool manager invalidate it.
 pub struct RegistryImpl {
     tool_repository: Arc<ToolRepository>,
+    favorite_repository: Arc<FavoriteRepository>,
+    change_feed: Arc<ChangeFeed>,
+    cache: Option<Arc<RegistryCache>>,
 }
 
 impl RegistryImpl {
     /// Create a new registry implementation
     pub async fn new(db: Arc<SqliteDatabase>) -> RegistryResult<Self> {
+        Self::with_cache_config(db, RegistryCacheConfig::default()).await
+    }
+    
+    /// Create a registry with the given cache configuration
+    pub async fn with_cache_config(db: Arc<SqliteDatabase>, config: RegistryCacheConfig) -> RegistryResult<Self> {
+        let cache = config.enabled.then(|| {
+            Arc::new(match config.backend {
+                Some(backend) => RegistryCache::with_backend(backend),
+                None => RegistryCache::new(config.max_size, config.ttl),
+            })
+        });
         Ok(Self {
             tool_repository: Arc::new(ToolRepository::new(db.as_ref().clone())),
+            favorite_repository: Arc::new(FavoriteRepository::new(db.as_ref().clone())),
+            change_feed: Arc::new(ChangeFeed::new(db)),
+            cache,
         })
     }
     
+    /// Get the tool cache, if enabled
+    pub fn cache(&self) -> Option<Arc<RegistryCache>> {
+        self.cache.clone()
+    }
+    
+    /// Tool cache statistics as metrics; empty when the cache is disabled
+    pub async fn cache_metrics(&self) -> Vec<Metric> {
+        match &self.cache {
+            Some(cache) => cache.metrics().await,
+            None => Vec::new(),
+        }
+    }
+    
+    /// Serve a tool list from the cache or load and cache it
+    async fn cached_list<F>(&self, key: String, load: F) -> RegistryResult<Vec<ToolInfo>>
+    where
+        F: std::future::Future<Output = StepflowResult<Vec<ToolInfo>>>,
+    {
+        if let Some(cache) = &self.cache {
+            if let Some(tools) = cache.get_list(&key).await {
+                return Ok(tools);
+            }
+        }
+        let tools = load.await?;
+        if let Some(cache) = &self.cache {
+            cache.put_list(&key, &tools).await;
+        }
+        Ok(tools)
+    }
+    
+    async fn invalidate(&self, tool_id: &ToolId) {
+        if let Some(cache) = &self.cache {
+            cache.invalidate_tool(tool_id).await;
+        }
+    }
+    
     /// Get tool repository
     pub fn tool_repository(&self) -> Arc<ToolRepository> {
         self.tool_repository.clone()
     }
+    
+    /// Get favorite repository
+    pub fn favorite_repository(&self) -> Arc<FavoriteRepository> {
+        self.favorite_repository.clone()
+    }
+    
+    /// Get registry change feed
+    pub fn change_feed(&self) -> Arc<ChangeFeed> {
+        self.change_feed.clone()
+    }
 }
 
 #[async_trait::async_trait]
 impl Registry for RegistryImpl {
     async fn register_tool(&self, tool: ToolInfo) -> RegistryResult<ToolId> {
         self.tool_repository.create_tool(&tool).await?;
+        self.invalidate(&tool.id).await;
+        self.change_feed.record(ToolChangeType::Created, &tool).await?;
         Ok(tool.id)
     }
     
+    async fn register_tools(&self, tools: Vec<ToolInfo>) -> RegistryResult<BatchInsertReport> {
+        let report = self.tool_repository.create_tools(&tools).await?;
+        let failed: HashSet<usize> = report.failures.iter().map(|failure| failure.index).collect();
+        for (index, tool) in tools.iter().enumerate() {
+            if failed.contains(&index) {
+                continue;
+            }
+            self.invalidate(&tool.id).await;
+            self.change_feed.record(ToolChangeType::Created, tool).await?;
+        }
+        Ok(report)
+    }
+    
     async fn get_tool(&self, tool_id: &ToolId) -> RegistryResult<ToolInfo> {
-        self.tool_repository.get_tool(tool_id).await?
-            .ok_or_else(|| RegistryError::ToolNotFound(tool_id.to_string()))
+        if let Some(cache) = &self.cache {
+            if let Some(tool) = cache.get_tool(tool_id).await {
+                return Ok(tool);
+            }
+        }
+        let tool = self.tool_repository.get_tool(tool_id).await?
+            .ok_or_else(|| RegistryError::ToolNotFound(tool_id.to_string()))?;
+        if let Some(cache) = &self.cache {
+            cache.put_tool(&tool).await;
+        }
+        Ok(tool)
     }
     
     async fn list_tools(&self) -> RegistryResult<Vec<ToolInfo>> {
-        self.tool_repository.list_tools(None).await.map_err(Into::into)
+        self.cached_list("all".to_string(), self.tool_repository.list_tools(None)).await
     }
     
     async fn search_tools(&self, query: &str) -> RegistryResult<Vec<ToolInfo>> {
-        self.tool_repository.search_tools(query).await.map_err(Into::into)
+        self.cached_list(format!("search:{}", query), self.tool_repository.search_tools(query)).await
     }
     
     async fn update_tool(&self, tool_id: &ToolId, tool: &ToolInfo) -> RegistryResult<()> {
-        self.tool_repository.update_tool(tool_id, tool).await.map_err(Into::into)
+        let previous = self.tool_repository.get_tool(tool_id).await?;
+        self.tool_repository.update_tool(tool_id, tool).await?;
+        self.invalidate(tool_id).await;
+        
+        let change_type = match previous {
+            Some(previous) if previous.version != tool.version => ToolChangeType::VersionAdded,
+            _ => ToolChangeType::Updated,
+        };
+        self.change_feed.record(change_type, tool).await?;
+        Ok(())
     }
     
     async fn delete_tool(&self, tool_id: &ToolId) -> RegistryResult<()> {
-        self.tool_repository.delete_tool(tool_id).await.map_err(Into::into)
+        let existing = self.tool_repository.get_tool(tool_id).await?;
+        self.tool_repository.delete_tool(tool_id).await?;
+        self.invalidate(tool_id).await;
+        
+        if let Some(tool) = existing {
+            self.change_feed.record(ToolChangeType::Deleted, &tool).await?;
+        }
+        Ok(())
     }
     
     async fn get_tools_by_type(&self, tool_type: &ToolType) -> RegistryResult<Vec<ToolInfo>> {
-        self.tool_repository.get_tools_by_type(tool_type).await.map_err(Into::into)
+        self.cached_list(format!("type:{:?}", tool_type), self.tool_repository.get_tools_by_type(tool_type)).await
     }
     
     async fn get_tools_by_status(&self, status: &ToolStatus) -> RegistryResult<Vec<ToolInfo>> {
-        self.tool_repository.get_tools_by_status(status).await.map_err(Into::into)
+        self.cached_list(format!("status:{:?}", status), self.tool_repository.get_tools_by_status(status)).await
     }
     
     async fn tool_exists(&self, tool_id: &ToolId) -> RegistryResult<bool> {
@@ -69,6 +214,39 @@ impl Registry for RegistryImpl {
         self.tool_repository.get_tool_stats().await.map_err(Into::into)
     }
     
+    async fn add_favorite(&self, user_id: &UserId, tool_id: &ToolId) -> RegistryResult<()> {
+        if !self.tool_repository.tool_exists(tool_id).await? {
+            return Err(RegistryError::ToolNotFound(tool_id.to_string()));
+        }
+        self.favorite_repository.add_favorite(user_id, tool_id).await.map_err(Into::into)
+    }
+    
+    async fn remove_favorite(&self, user_id: &UserId, tool_id: &ToolId) -> RegistryResult<()> {
+        self.favorite_repository.remove_favorite(user_id, tool_id).await?;
+        Ok(())
+    }
+    
+    async fn list_favorites(&self, user_id: &UserId) -> RegistryResult<Vec<ToolInfo>> {
+        self.favorite_repository.list_favorite_tools(user_id).await.map_err(Into::into)
+    }
+    
+    async fn discover_tools_for_user(&self, user_id: &UserId) -> RegistryResult<Vec<ToolInfo>> {
+        let tools = self.tool_repository.list_tools(None).await?;
+        let favorite_ids = self.favorite_repository.list_favorite_ids(user_id).await?;
+        Ok(order_favorites_first(tools, &favorite_ids))
+    }
+    
+    async fn cache_stats(&self) -> Option<CacheStats> {
+        match &self.cache {
+            Some(cache) => Some(cache.stats().await),
+            None => None,
+        }
+    }
+    
+    fn tool_cache(&self) -> Option<Arc<RegistryCache>> {
+        self.cache.clone()
+    }
+    
     async fn health_check(&self) -> RegistryResult<bool> {
         // Simple health check - try to perform a basic database operation
         match self.tool_repository.list_tools(None).await {
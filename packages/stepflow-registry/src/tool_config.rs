@@ -0,0 +1,690 @@
+//! Per-tenant tool configuration
+//!
+//! Tenant configuration for a tool is validated against the tool's
+//! `configuration_schema` when it is saved, with schema defaults filled in.
+//! Configuration values may reference environment variables using `${NAME}`
+//! or `${NAME:-default}`; these references are left untouched on save and
+//! resolved when the tool is executed. A literal `${` is written as `$${`.
+//! `${secret:NAME}` references the tenant's secret `NAME` stored with the
+//! configuration; secrets are encrypted at rest and never returned by the API.
+//!
+//! Executions run with the environment of [`resolve_tool_environment`]: the
+//! tool's default `environment`, overridden by the tenant's configured
+//! `environment`, overridden by the variables of the execution request.
+//!
+//! Only the commonly used subset of JSON Schema is supported: `type`,
+//! `properties`, `required`, `additionalProperties`, `items`, `enum`,
+//! `minimum`/`maximum`, `exclusiveMinimum`/`exclusiveMaximum`,
+//! `minLength`/`maxLength`, `pattern`, `minItems`/`maxItems` and `default`.
+//! Errors carry a JSON Pointer to the failing property, e.g. `/database/port`.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use serde_json::{Map, Value};
+use stepflow_core::*;
+use stepflow_database::{SqliteDatabase, ToolConfigRepository, ToolRepository};
+use crate::errors::*;
+
+/// Fill in `default` values for missing object properties, recursively
+pub fn apply_schema_defaults(schema: &Value, value: &mut Value) {
+    match value {
+        Value::Object(object) => {
+            if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+                for (name, property_schema) in properties {
+                    if !object.contains_key(name) {
+                        if let Some(default) = property_schema.get("default") {
+                            object.insert(name.clone(), default.clone());
+                        }
+                    }
+                    if let Some(property) = object.get_mut(name) {
+                        apply_schema_defaults(property_schema, property);
+                    }
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for item in items {
+                    apply_schema_defaults(item_schema, item);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Validate a value against a JSON Schema, collecting every violation
+pub fn validate_against_schema(schema: &Value, value: &Value) -> Result<(), Vec<ValidationError>> {
+    SchemaValidator { defer_placeholders: false }.validate(schema, value)
+}
+
+/// Validate a value whose strings may still contain `${...}` references.
+///
+/// Strings holding a reference are only checked after interpolation, since
+/// `"${PORT}"` may legitimately resolve to an integer.
+pub fn validate_unresolved_against_schema(schema: &Value, value: &Value) -> Result<(), Vec<ValidationError>> {
+    SchemaValidator { defer_placeholders: true }.validate(schema, value)
+}
+
+/// Replace `${NAME}` and `${NAME:-default}` references in every string value
+pub fn interpolate_env(value: &Value, env: &HashMap<String, String>) -> Result<Value, Vec<ValidationError>> {
+    interpolate_with_secrets(value, env, &HashMap::new())
+}
+
+/// Like [`interpolate_env`], also replacing `${secret:NAME}` references from `secrets`
+pub fn interpolate_with_secrets(
+    value: &Value,
+    env: &HashMap<String, String>,
+    secrets: &HashMap<String, String>,
+) -> Result<Value, Vec<ValidationError>> {
+    let mut errors = Vec::new();
+    let resolved = interpolate_value(value, &Variables { env, secrets }, "", &mut errors);
+    if errors.is_empty() {
+        Ok(resolved)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Names of the secrets referenced by `${secret:NAME}` in `text`
+pub fn secret_references(text: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('$') {
+        let tail = &rest[start..];
+        if let Some(escaped) = tail.strip_prefix("$${") {
+            rest = escaped;
+            continue;
+        }
+        rest = &tail[1..];
+        let Some(reference) = tail.strip_prefix("${") else { continue };
+        let Some(end) = reference.find('}') else { break };
+        if let Some(name) = reference[..end].strip_prefix(SECRET_PREFIX) {
+            let name = name.split_once(":-").map_or(name, |(name, _)| name);
+            names.push(name.to_string());
+        }
+        rest = &reference[end + 1..];
+    }
+    names
+}
+
+/// Convert interpolated strings to the scalar type the schema asks for
+pub fn coerce_to_schema(schema: &Value, value: &mut Value) {
+    match value {
+        Value::Object(object) => {
+            if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+                for (name, property_schema) in properties {
+                    if let Some(property) = object.get_mut(name) {
+                        coerce_to_schema(property_schema, property);
+                    }
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for item in items {
+                    coerce_to_schema(item_schema, item);
+                }
+            }
+        }
+        Value::String(text) => {
+            let coerced = match schema_types(schema).first().copied() {
+                Some("integer") => text.trim().parse::<i64>().ok().map(Value::from),
+                Some("number") => text.trim().parse::<f64>().ok()
+                    .and_then(serde_json::Number::from_f64)
+                    .map(Value::Number),
+                Some("boolean") => text.trim().parse::<bool>().ok().map(Value::Bool),
+                _ => None,
+            };
+            if let Some(coerced) = coerced {
+                *value = coerced;
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Prefix of references to tenant secrets, e.g. `${secret:API_KEY}`
+const SECRET_PREFIX: &str = "secret:";
+
+/// Values `${...}` references resolve to
+struct Variables<'a> {
+    env: &'a HashMap<String, String>,
+    secrets: &'a HashMap<String, String>,
+}
+
+fn contains_placeholder(text: &str) -> bool {
+    text.replace("$${", "").contains("${")
+}
+
+fn interpolate_value(value: &Value, variables: &Variables, path: &str, errors: &mut Vec<ValidationError>) -> Value {
+    match value {
+        Value::String(text) => Value::String(interpolate_string(text, variables, path, errors)),
+        Value::Array(items) => Value::Array(
+            items.iter()
+                .enumerate()
+                .map(|(index, item)| interpolate_value(item, variables, &format!("{}/{}", path, index), errors))
+                .collect()
+        ),
+        Value::Object(object) => Value::Object(
+            object.iter()
+                .map(|(key, item)| {
+                    let item_path = format!("{}/{}", path, escape_pointer(key));
+                    (key.clone(), interpolate_value(item, variables, &item_path, errors))
+                })
+                .collect()
+        ),
+        other => other.clone(),
+    }
+}
+
+fn interpolate_string(text: &str, variables: &Variables, path: &str, errors: &mut Vec<ValidationError>) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('$') {
+        output.push_str(&rest[..start]);
+        let tail = &rest[start..];
+
+        if let Some(escaped) = tail.strip_prefix("$${") {
+            output.push_str("${");
+            rest = escaped;
+            continue;
+        }
+
+        let Some(reference) = tail.strip_prefix("${") else {
+            output.push('$');
+            rest = &tail[1..];
+            continue;
+        };
+
+        let Some(end) = reference.find('}') else {
+            errors.push(ValidationErrorBuilder::invalid_format(
+                display_path(path), "unterminated environment variable reference",
+            ));
+            output.push_str(tail);
+            return output;
+        };
+
+        let expression = &reference[..end];
+        let (name, default) = match expression.split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (expression, None),
+        };
+
+        let (resolved, kind, name) = match name.strip_prefix(SECRET_PREFIX) {
+            Some(secret) => (variables.secrets.get(secret), "secret", secret),
+            None => (variables.env.get(name), "environment variable", name),
+        };
+        match (resolved, default) {
+            (Some(resolved), _) => output.push_str(resolved),
+            (None, Some(default)) => output.push_str(default),
+            (None, None) => errors.push(ValidationError::RequiredFieldMissing(format!(
+                "{}: {} '{}' is not set", display_path(path), kind, name
+            ))),
+        }
+
+        rest = &reference[end + 1..];
+    }
+
+    output.push_str(rest);
+    output
+}
+
+fn escape_pointer(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+fn display_path(path: &str) -> &str {
+    if path.is_empty() { "/" } else { path }
+}
+
+fn schema_types(schema: &Value) -> Vec<&str> {
+    match schema.get("type") {
+        Some(Value::String(name)) => vec![name.as_str()],
+        Some(Value::Array(names)) => names.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn matches_type(value: &Value, expected: &str) -> bool {
+    match (expected, value) {
+        ("integer", Value::Number(n)) => n.is_i64() || n.is_u64() || n.as_f64().map(|f| f.fract() == 0.0).unwrap_or(false),
+        ("number", Value::Number(_)) => true,
+        (expected, value) => expected == type_name(value),
+    }
+}
+
+struct SchemaValidator {
+    defer_placeholders: bool,
+}
+
+impl SchemaValidator {
+    fn validate(&self, schema: &Value, value: &Value) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+        self.validate_at(schema, value, "", &mut errors);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn validate_at(&self, schema: &Value, value: &Value, path: &str, errors: &mut Vec<ValidationError>) {
+        if let Value::String(text) = value {
+            if self.defer_placeholders && contains_placeholder(text) {
+                return;
+            }
+        }
+
+        let field = display_path(path);
+
+        let types = schema_types(schema);
+        if !types.is_empty() && !types.iter().any(|expected| matches_type(value, expected)) {
+            errors.push(ValidationErrorBuilder::invalid_format(
+                field,
+                &format!("expected {}, got {}", types.join(" or "), type_name(value)),
+            ));
+            return;
+        }
+
+        if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+            if !allowed.contains(value) {
+                errors.push(ValidationError::InvalidEnumValue(format!(
+                    "{}: {} is not one of {}", field, value, Value::Array(allowed.clone())
+                )));
+            }
+        }
+
+        match value {
+            Value::Object(object) => self.validate_object(schema, object, path, errors),
+            Value::Array(items) => self.validate_array(schema, items, path, errors),
+            Value::String(text) => self.validate_string(schema, text, field, errors),
+            Value::Number(number) => {
+                if let Some(number) = number.as_f64() {
+                    self.validate_number(schema, number, field, errors);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn validate_object(&self, schema: &Value, object: &Map<String, Value>, path: &str, errors: &mut Vec<ValidationError>) {
+        let properties = schema.get("properties").and_then(Value::as_object);
+
+        if let Some(required) = schema.get("required").and_then(Value::as_array) {
+            for name in required.iter().filter_map(Value::as_str) {
+                if !object.contains_key(name) {
+                    errors.push(ValidationErrorBuilder::required_field(
+                        &format!("{}/{}", path, escape_pointer(name))
+                    ));
+                }
+            }
+        }
+
+        for (name, property) in object {
+            let property_path = format!("{}/{}", path, escape_pointer(name));
+            match properties.and_then(|properties| properties.get(name)) {
+                Some(property_schema) => self.validate_at(property_schema, property, &property_path, errors),
+                None => match schema.get("additionalProperties") {
+                    Some(Value::Bool(false)) => errors.push(ValidationErrorBuilder::invalid_format(
+                        &property_path, "unknown property",
+                    )),
+                    Some(additional @ Value::Object(_)) => {
+                        self.validate_at(additional, property, &property_path, errors)
+                    }
+                    _ => {}
+                },
+            }
+        }
+    }
+
+    fn validate_array(&self, schema: &Value, items: &[Value], path: &str, errors: &mut Vec<ValidationError>) {
+        let field = display_path(path);
+
+        if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+            if (items.len() as u64) < min {
+                errors.push(ValidationError::ValueOutOfRange(format!(
+                    "{}: expected at least {} items, got {}", field, min, items.len()
+                )));
+            }
+        }
+        if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+            if (items.len() as u64) > max {
+                errors.push(ValidationError::ValueOutOfRange(format!(
+                    "{}: expected at most {} items, got {}", field, max, items.len()
+                )));
+            }
+        }
+
+        if let Some(item_schema) = schema.get("items") {
+            for (index, item) in items.iter().enumerate() {
+                self.validate_at(item_schema, item, &format!("{}/{}", path, index), errors);
+            }
+        }
+    }
+
+    fn validate_string(&self, schema: &Value, text: &str, field: &str, errors: &mut Vec<ValidationError>) {
+        let length = text.chars().count();
+
+        if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+            if (length as u64) < min {
+                errors.push(ValidationErrorBuilder::too_short(field, min as usize, length));
+            }
+        }
+        if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+            if (length as u64) > max {
+                errors.push(ValidationErrorBuilder::too_long(field, max as usize, length));
+            }
+        }
+        if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+            match regex::Regex::new(pattern) {
+                Ok(regex) if !regex.is_match(text) => errors.push(ValidationErrorBuilder::invalid_format(
+                    field, &format!("does not match pattern '{}'", pattern),
+                )),
+                Ok(_) => {}
+                Err(e) => errors.push(ValidationError::InvalidRegex(format!("{}: {}", field, e))),
+            }
+        }
+    }
+
+    fn validate_number(&self, schema: &Value, number: f64, field: &str, errors: &mut Vec<ValidationError>) {
+        let bound = |key: &str| schema.get(key).and_then(Value::as_f64);
+
+        if let Some(min) = bound("minimum") {
+            if number < min {
+                errors.push(ValidationError::ValueOutOfRange(format!("{}: {} is less than minimum {}", field, number, min)));
+            }
+        }
+        if let Some(max) = bound("maximum") {
+            if number > max {
+                errors.push(ValidationError::ValueOutOfRange(format!("{}: {} is greater than maximum {}", field, number, max)));
+            }
+        }
+        if let Some(min) = bound("exclusiveMinimum") {
+            if number <= min {
+                errors.push(ValidationError::ValueOutOfRange(format!("{}: {} must be greater than {}", field, number, min)));
+            }
+        }
+        if let Some(max) = bound("exclusiveMaximum") {
+            if number >= max {
+                errors.push(ValidationError::ValueOutOfRange(format!("{}: {} must be less than {}", field, number, max)));
+            }
+        }
+    }
+}
+
+/// Tool configuration service
+pub struct ToolConfigService {
+    tool_repository: Arc<ToolRepository>,
+    config_repository: Arc<ToolConfigRepository>,
+}
+
+impl ToolConfigService {
+    /// Create a new tool configuration service
+    pub fn new(db: Arc<SqliteDatabase>) -> Self {
+        Self {
+            tool_repository: Arc::new(ToolRepository::new(db.as_ref().clone())),
+            config_repository: Arc::new(ToolConfigRepository::new(db.as_ref().clone())),
+        }
+    }
+
+    /// Validate and store a tenant's configuration for a tool.
+    ///
+    /// Schema defaults are applied before validation and persisted, so the
+    /// returned configuration is what will be used at execution time.
+    pub async fn save_config(&self, tenant_id: &TenantId, mut config: ToolConfig) -> RegistryResult<ToolConfig> {
+        let tool = self.get_tool(&config.tool_id).await?;
+        check_secret_references(&config)?;
+
+        if let Some(schema) = &tool.configuration_schema {
+            let mut configuration = Value::Object(config.configuration.into_iter().collect());
+            apply_schema_defaults(schema, &mut configuration);
+            validate_unresolved_against_schema(schema, &configuration)
+                .map_err(RegistryError::ValidationFailed)?;
+            config.configuration = into_map(configuration);
+        }
+
+        self.config_repository.save_config(tenant_id, &config).await?;
+        Ok(config)
+    }
+
+    /// Get a tenant's stored configuration for a tool
+    pub async fn get_config(&self, tenant_id: &TenantId, tool_id: &ToolId) -> RegistryResult<Option<ToolConfig>> {
+        Ok(self.config_repository.get_config(tenant_id, tool_id).await?)
+    }
+
+    /// Delete a tenant's configuration for a tool
+    pub async fn delete_config(&self, tenant_id: &TenantId, tool_id: &ToolId) -> RegistryResult<()> {
+        if !self.config_repository.delete_config(tenant_id, tool_id).await? {
+            return Err(RegistryError::ConfigurationError(format!(
+                "No configuration for tool {} in tenant {}", tool_id, tenant_id
+            )));
+        }
+        Ok(())
+    }
+
+    /// Resolve the configuration used to execute a tool.
+    ///
+    /// See [`resolve_tool_config`]; tenants without stored configuration get
+    /// the schema defaults.
+    pub async fn resolve_config(
+        &self,
+        tenant_id: &TenantId,
+        tool_id: &ToolId,
+        env: &HashMap<String, String>,
+    ) -> RegistryResult<HashMap<String, Value>> {
+        let tool = self.get_tool(tool_id).await?;
+        let stored = self.config_repository.get_config(tenant_id, tool_id).await?;
+        resolve_tool_config(&tool, stored, env)
+    }
+
+    /// Environment an execution with `request` variables would run with.
+    ///
+    /// See [`resolve_tool_environment`]; variables the executor injects, such
+    /// as the execution ID, are not included.
+    pub async fn preview_environment(
+        &self,
+        tenant_id: &TenantId,
+        tool_id: &ToolId,
+        request: &HashMap<String, String>,
+    ) -> RegistryResult<EffectiveEnvironment> {
+        let tool = self.get_tool(tool_id).await?;
+        let stored = self.config_repository.get_config(tenant_id, tool_id).await?;
+        resolve_tool_environment(&tool, stored.as_ref(), request)
+    }
+
+    async fn get_tool(&self, tool_id: &ToolId) -> RegistryResult<ToolInfo> {
+        self.tool_repository.get_tool(tool_id).await?
+            .ok_or_else(|| RegistryError::ToolNotFound(tool_id.to_string()))
+    }
+}
+
+/// Every `${secret:NAME}` reference must name a secret of the configuration
+fn check_secret_references(config: &ToolConfig) -> RegistryResult<()> {
+    let mut texts: Vec<(String, &str)> = config.environment.iter()
+        .map(|(name, value)| (format!("/environment/{}", escape_pointer(name)), value.as_str()))
+        .collect();
+    let configuration = Value::Object(config.configuration.clone().into_iter().collect());
+    let mut strings = Vec::new();
+    collect_strings(&configuration, "/configuration", &mut strings);
+    texts.extend(strings.iter().map(|(path, text)| (path.clone(), *text)));
+
+    let mut errors: Vec<ValidationError> = texts.iter()
+        .flat_map(|(path, text)| {
+            secret_references(text).into_iter()
+                .filter(|name| !config.secrets.contains_key(name))
+                .map(move |name| ValidationError::RequiredFieldMissing(format!("{}: secret '{}' is not set", path, name)))
+        })
+        .collect();
+    if errors.is_empty() {
+        return Ok(());
+    }
+    errors.sort_by_key(|error| error.to_string());
+    Err(RegistryError::ValidationFailed(errors))
+}
+
+fn collect_strings<'a>(value: &'a Value, path: &str, strings: &mut Vec<(String, &'a str)>) {
+    match value {
+        Value::String(text) => strings.push((path.to_string(), text)),
+        Value::Array(items) => for (index, item) in items.iter().enumerate() {
+            collect_strings(item, &format!("{}/{}", path, index), strings);
+        },
+        Value::Object(object) => for (key, item) in object {
+            collect_strings(item, &format!("{}/{}", path, escape_pointer(key)), strings);
+        },
+        _ => {}
+    }
+}
+
+/// Layer an environment variable comes from, in increasing precedence
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnvironmentSource {
+    /// The tool's default environment
+    Tool,
+    /// The tenant's tool configuration
+    TenantConfig,
+    /// The execution request
+    Request,
+}
+
+/// One variable of an execution environment
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct EnvironmentVariable {
+    pub name: String,
+    pub value: String,
+    pub source: EnvironmentSource,
+    /// The value includes a tenant secret
+    pub secret: bool,
+}
+
+/// Environment an execution runs with, sorted by variable name
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct EffectiveEnvironment {
+    pub variables: Vec<EnvironmentVariable>,
+}
+
+/// Placeholder shown instead of secret values
+pub const REDACTED_VALUE: &str = "********";
+
+impl EffectiveEnvironment {
+    pub fn get(&self, name: &str) -> Option<&EnvironmentVariable> {
+        self.variables.iter().find(|variable| variable.name == name)
+    }
+
+    pub fn to_map(&self) -> HashMap<String, String> {
+        self.variables.iter().map(|variable| (variable.name.clone(), variable.value.clone())).collect()
+    }
+
+    /// The same environment with secret values replaced by [`REDACTED_VALUE`]
+    pub fn redacted(mut self) -> Self {
+        for variable in self.variables.iter_mut().filter(|variable| variable.secret) {
+            variable.value = REDACTED_VALUE.to_string();
+        }
+        self
+    }
+}
+
+/// Resolve the environment an execution of `tool` runs with.
+///
+/// The tool's default environment is overridden by the tenant's configured
+/// environment, which is overridden by the `request` variables. Configured
+/// values may reference the tenant's secrets with `${secret:NAME}` and
+/// other variables with `${NAME}`, resolved from the request layered over
+/// the tool defaults.
+pub fn resolve_tool_environment(
+    tool: &ToolInfo,
+    stored: Option<&ToolConfig>,
+    request: &HashMap<String, String>,
+) -> RegistryResult<EffectiveEnvironment> {
+    let mut variables: HashMap<String, EnvironmentVariable> = tool.environment.iter()
+        .map(|(name, value)| (name.clone(), EnvironmentVariable {
+            name: name.clone(),
+            value: value.clone(),
+            source: EnvironmentSource::Tool,
+            secret: false,
+        }))
+        .collect();
+
+    if let Some(config) = stored {
+        let mut references = tool.environment.clone();
+        references.extend(request.iter().map(|(k, v)| (k.clone(), v.clone())));
+        let lookup = Variables { env: &references, secrets: &config.secrets };
+
+        let mut errors = Vec::new();
+        for (name, value) in &config.environment {
+            let path = format!("/environment/{}", escape_pointer(name));
+            let resolved = interpolate_string(value, &lookup, &path, &mut errors);
+            variables.insert(name.clone(), EnvironmentVariable {
+                name: name.clone(),
+                value: resolved,
+                source: EnvironmentSource::TenantConfig,
+                secret: !secret_references(value).is_empty(),
+            });
+        }
+        if !errors.is_empty() {
+            return Err(RegistryError::ValidationFailed(errors));
+        }
+    }
+
+    variables.extend(request.iter().map(|(name, value)| (name.clone(), EnvironmentVariable {
+        name: name.clone(),
+        value: value.clone(),
+        source: EnvironmentSource::Request,
+        secret: false,
+    })));
+
+    let mut variables: Vec<EnvironmentVariable> = variables.into_values().collect();
+    variables.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(EffectiveEnvironment { variables })
+}
+
+/// Resolve the configuration used to execute `tool`.
+///
+/// References are interpolated from `env` layered over the stored
+/// configuration's own `environment`, and `${secret:NAME}` from its secrets;
+/// the result is coerced and validated against the tool's schema.
+pub fn resolve_tool_config(
+    tool: &ToolInfo,
+    stored: Option<ToolConfig>,
+    env: &HashMap<String, String>,
+) -> RegistryResult<HashMap<String, Value>> {
+    let (configuration, mut variables, secrets) = match stored {
+        Some(config) => (config.configuration, config.environment, config.secrets),
+        None => (HashMap::new(), HashMap::new(), HashMap::new()),
+    };
+    variables.extend(env.iter().map(|(k, v)| (k.clone(), v.clone())));
+
+    let configuration = Value::Object(configuration.into_iter().collect());
+    let mut resolved = interpolate_with_secrets(&configuration, &variables, &secrets)
+        .map_err(RegistryError::ValidationFailed)?;
+
+    if let Some(schema) = &tool.configuration_schema {
+        coerce_to_schema(schema, &mut resolved);
+        apply_schema_defaults(schema, &mut resolved);
+        validate_against_schema(schema, &resolved)
+            .map_err(RegistryError::ValidationFailed)?;
+    }
+
+    Ok(into_map(resolved))
+}
+
+fn into_map(value: Value) -> HashMap<String, Value> {
+    match value {
+        Value::Object(object) => object.into_iter().collect(),
+        _ => HashMap::new(),
+    }
+}
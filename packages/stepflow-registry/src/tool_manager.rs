@@ -3,22 +3,37 @@
 use std::sync::Arc;
 use stepflow_core::*;
 use stepflow_database::ToolRepository;
+use crate::cache::RegistryCache;
 use crate::errors::*;
 
 /// Tool manager implementation
 pub struct ToolManager {
     tool_repository: Arc<ToolRepository>,
+    cache: Option<Arc<RegistryCache>>,
 }
 
 impl ToolManager {
     /// Create a new tool manager
     pub fn new(tool_repository: Arc<ToolRepository>) -> Self {
-        Self { tool_repository }
+        Self { tool_repository, cache: None }
+    }
+    
+    /// Invalidate `cache` on every write
+    pub fn with_cache(mut self, cache: Option<Arc<RegistryCache>>) -> Self {
+        self.cache = cache;
+        self
+    }
+    
+    async fn invalidate(&self, tool_id: &ToolId) {
+        if let Some(cache) = &self.cache {
+            cache.invalidate_tool(tool_id).await;
+        }
     }
     
     /// Register a tool
     pub async fn register_tool(&self, tool: ToolInfo) -> RegistryResult<ToolId> {
         self.tool_repository.create_tool(&tool).await?;
+        self.invalidate(&tool.id).await;
         Ok(tool.id)
     }
     
@@ -30,12 +45,16 @@ impl ToolManager {
     
     /// Update a tool
     pub async fn update_tool(&self, tool_id: &ToolId, tool: &ToolInfo) -> RegistryResult<()> {
-        self.tool_repository.update_tool(tool_id, tool).await.map_err(Into::into)
+        self.tool_repository.update_tool(tool_id, tool).await?;
+        self.invalidate(tool_id).await;
+        Ok(())
     }
     
     /// Delete a tool
     pub async fn delete_tool(&self, tool_id: &ToolId) -> RegistryResult<()> {
-        self.tool_repository.delete_tool(tool_id).await.map_err(Into::into)
+        self.tool_repository.delete_tool(tool_id).await?;
+        self.invalidate(tool_id).await;
+        Ok(())
     }
     
     /// List all tools
@@ -0,0 +1,265 @@
+//! RPC Authentication and Authorization
+//!
+//! 客户端在连接建立后调用 `rpc.connect` 并携带 token 完成认证，
+//! 之后该连接上的每个请求都会按方法级策略进行授权。
+//! 角色/权限语义与 HTTP API 的 RBAC 保持一致：所列角色与权限都必须具备。
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::codec::{WireFormat, NEGOTIATE_METHOD};
+use crate::compression::Compression;
+use crate::error::RpcError;
+
+/// 握手方法名
+pub const CONNECT_METHOD: &str = "rpc.connect";
+
+/// 已认证的调用方
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RpcPrincipal {
+    pub subject: String,
+    pub tenant_id: Option<String>,
+    pub roles: Vec<String>,
+    pub permissions: Vec<String>,
+}
+
+impl RpcPrincipal {
+    pub fn new(subject: String) -> Self {
+        Self {
+            subject,
+            tenant_id: None,
+            roles: Vec::new(),
+            permissions: Vec::new(),
+        }
+    }
+
+    pub fn with_tenant(mut self, tenant_id: String) -> Self {
+        self.tenant_id = Some(tenant_id);
+        self
+    }
+
+    pub fn with_role(mut self, role: String) -> Self {
+        self.roles.push(role);
+        self
+    }
+
+    pub fn with_permission(mut self, permission: String) -> Self {
+        self.permissions.push(permission);
+        self
+    }
+}
+
+/// 请求上下文，传递给处理器
+#[derive(Debug, Clone, Default)]
+pub struct RequestContext {
+    pub connection_id: String,
+    pub principal: Option<RpcPrincipal>,
+    /// 连接当前协商的线路编码
+    pub wire_format: WireFormat,
+    /// 连接当前协商的压缩算法
+    pub compression: Compression,
+    /// 对端地址
+    pub peer_addr: Option<SocketAddr>,
+    /// 发出当前响应后关闭连接
+    pub closing: bool,
+}
+
+impl RequestContext {
+    pub fn new(connection_id: String) -> Self {
+        Self {
+            connection_id,
+            principal: None,
+            wire_format: WireFormat::Json,
+            compression: Compression::None,
+            peer_addr: None,
+            closing: false,
+        }
+    }
+
+    pub fn with_peer_addr(mut self, peer_addr: SocketAddr) -> Self {
+        self.peer_addr = Some(peer_addr);
+        self
+    }
+}
+
+/// 认证器 trait
+#[async_trait::async_trait]
+pub trait RpcAuthenticator: Send + Sync {
+    /// 校验握手 token，返回对应的调用方
+    async fn authenticate(&self, token: &str) -> Result<RpcPrincipal, RpcError>;
+}
+
+/// 基于静态 token 表的认证器
+#[derive(Debug, Default)]
+pub struct StaticTokenAuthenticator {
+    tokens: HashMap<String, RpcPrincipal>,
+}
+
+impl StaticTokenAuthenticator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_token(mut self, token: String, principal: RpcPrincipal) -> Self {
+        self.tokens.insert(token, principal);
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl RpcAuthenticator for StaticTokenAuthenticator {
+    async fn authenticate(&self, token: &str) -> Result<RpcPrincipal, RpcError> {
+        self.tokens.get(token).cloned().ok_or_else(RpcError::unauthenticated)
+    }
+}
+
+/// 方法级授权策略
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MethodPolicy {
+    /// 无需认证
+    Public,
+    /// 需要已认证的连接
+    Authenticated,
+    /// 需要具备全部角色
+    RequireRoles(Vec<String>),
+    /// 需要具备全部权限
+    RequirePermissions(Vec<String>),
+}
+
+impl MethodPolicy {
+    /// 检查调用方是否满足策略
+    pub fn check(&self, method: &str, principal: Option<&RpcPrincipal>) -> Result<(), RpcError> {
+        let principal = match (self, principal) {
+            (MethodPolicy::Public, _) => return Ok(()),
+            (_, None) => return Err(RpcError::unauthenticated()),
+            (_, Some(principal)) => principal,
+        };
+
+        let satisfied = match self {
+            MethodPolicy::Public | MethodPolicy::Authenticated => true,
+            MethodPolicy::RequireRoles(roles) => roles.iter().all(|r| principal.roles.contains(r)),
+            MethodPolicy::RequirePermissions(permissions) => {
+                permissions.iter().all(|p| principal.permissions.contains(p))
+            }
+        };
+
+        if satisfied {
+            Ok(())
+        } else {
+            Err(RpcError::forbidden(method))
+        }
+    }
+}
+
+/// 授权策略表
+///
+/// 规则按注册顺序匹配，支持 `*` 后缀通配（如 `tools.*`），未命中时使用默认策略。
+#[derive(Debug, Clone)]
+pub struct AuthorizationPolicy {
+    default_policy: MethodPolicy,
+    rules: Vec<(String, MethodPolicy)>,
+}
+
+impl Default for AuthorizationPolicy {
+    fn default() -> Self {
+        Self {
+            default_policy: MethodPolicy::Authenticated,
+            rules: vec![
+                (CONNECT_METHOD.to_string(), MethodPolicy::Public),
+                (NEGOTIATE_METHOD.to_string(), MethodPolicy::Public),
+                ("rpc.ping".to_string(), MethodPolicy::Public),
+            ],
+        }
+    }
+}
+
+impl AuthorizationPolicy {
+    pub fn new(default_policy: MethodPolicy) -> Self {
+        Self {
+            default_policy,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_rule(mut self, pattern: String, policy: MethodPolicy) -> Self {
+        self.rules.push((pattern, policy));
+        self
+    }
+
+    /// 获取方法对应的策略
+    pub fn policy_for(&self, method: &str) -> &MethodPolicy {
+        self.rules
+            .iter()
+            .find(|(pattern, _)| match pattern.strip_suffix('*') {
+                Some(prefix) => method.starts_with(prefix),
+                None => pattern == method,
+            })
+            .map(|(_, policy)| policy)
+            .unwrap_or(&self.default_policy)
+    }
+
+    /// 授权一次方法调用
+    pub fn authorize(&self, method: &str, principal: Option<&RpcPrincipal>) -> Result<(), RpcError> {
+        self.policy_for(method).check(method, principal)
+    }
+}
+
+/// 服务端认证配置
+#[derive(Clone)]
+pub struct RpcAuthConfig {
+    pub authenticator: Arc<dyn RpcAuthenticator>,
+    pub policy: AuthorizationPolicy,
+}
+
+impl RpcAuthConfig {
+    pub fn new(authenticator: Arc<dyn RpcAuthenticator>) -> Self {
+        Self {
+            authenticator,
+            policy: AuthorizationPolicy::default(),
+        }
+    }
+
+    pub fn with_policy(mut self, policy: AuthorizationPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_matching() {
+        let policy = AuthorizationPolicy::default()
+            .with_rule("admin.*".to_string(), MethodPolicy::RequireRoles(vec!["admin".to_string()]))
+            .with_rule(
+                "tools.execute".to_string(),
+                MethodPolicy::RequirePermissions(vec!["tools:execute".to_string()]),
+            );
+
+        let user = RpcPrincipal::new("user-1".to_string())
+            .with_permission("tools:execute".to_string());
+
+        assert!(policy.authorize("rpc.ping", None).is_ok());
+        assert_eq!(policy.authorize("tools.list", None).unwrap_err().code, RpcError::unauthenticated().code);
+        assert!(policy.authorize("tools.list", Some(&user)).is_ok());
+        assert!(policy.authorize("tools.execute", Some(&user)).is_ok());
+        assert_eq!(
+            policy.authorize("admin.shutdown", Some(&user)).unwrap_err().code,
+            RpcError::forbidden("admin.shutdown").code
+        );
+    }
+
+    #[tokio::test]
+    async fn test_static_token_authenticator() {
+        let authenticator = StaticTokenAuthenticator::new()
+            .with_token("secret".to_string(), RpcPrincipal::new("svc".to_string()));
+
+        assert_eq!(authenticator.authenticate("secret").await.unwrap().subject, "svc");
+        assert!(authenticator.authenticate("wrong").await.is_err());
+    }
+}
@@ -0,0 +1,647 @@
+//! 多端点 RPC 客户端
+//!
+//! 维护到多个服务端的连接，按负载均衡策略选取端点，并将连续失败的端点暂时剔除，
+//! 单个 RPC 节点宕机时请求会转到其余节点。端点来自静态列表或 DNS SRV 记录。
+//!
+//! 剔除规则：
+//! - 连接失败、连接中断或超时计为端点失败，服务端返回的 RPC 错误不计
+//! - 连续失败达到阈值后剔除，剔除期满后重新参与选取，再次失败立即剔除
+//! - 调用成功或健康检查 ping 成功后恢复
+
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use crate::client::{ClientConfig, RpcClient};
+use crate::error::{RpcFrameworkError, RpcResult};
+use crate::pool::{retry_delay, CallOptions, RetryPolicy};
+
+/// 延迟滑动平均中新样本的权重
+const LATENCY_WEIGHT: f64 = 0.3;
+
+/// 负载均衡策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BalanceStrategy {
+    /// 依次轮换
+    #[default]
+    RoundRobin,
+    /// 进行中请求最少的端点
+    LeastPending,
+    /// 平均延迟乘以进行中请求数最小的端点；尚无延迟样本的端点优先，以便测量
+    LatencyAware,
+}
+
+/// 端点发现方式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EndpointDiscovery {
+    Static(Vec<SocketAddr>),
+    /// 查询 SRV 记录（如 `_stepflow-rpc._tcp.example.com`），使用优先级最高的一组，需要 `dns-srv` 特性
+    DnsSrv(String),
+}
+
+impl EndpointDiscovery {
+    /// 解析出当前的端点地址
+    pub async fn resolve(&self) -> RpcResult<Vec<SocketAddr>> {
+        match self {
+            EndpointDiscovery::Static(addrs) => Ok(addrs.clone()),
+            EndpointDiscovery::DnsSrv(name) => resolve_srv(name).await,
+        }
+    }
+}
+
+#[cfg(feature = "dns-srv")]
+async fn resolve_srv(name: &str) -> RpcResult<Vec<SocketAddr>> {
+    let resolver = hickory_resolver::TokioAsyncResolver::tokio_from_system_conf()
+        .map_err(|e| RpcFrameworkError::InvalidAddress(format!("Failed to load resolver configuration: {}", e)))?;
+    let records = resolver.srv_lookup(name).await
+        .map_err(|e| RpcFrameworkError::InvalidAddress(format!("SRV lookup for {} failed: {}", name, e)))?;
+    let Some(priority) = records.iter().map(|srv| srv.priority()).min() else {
+        return Ok(Vec::new());
+    };
+
+    let mut addrs = Vec::new();
+    for srv in records.iter().filter(|srv| srv.priority() == priority) {
+        let target = srv.target().to_utf8();
+        let resolved = tokio::net::lookup_host((target.trim_end_matches('.'), srv.port())).await
+            .map_err(|e| RpcFrameworkError::InvalidAddress(format!("Failed to resolve SRV target {}: {}", target, e)))?;
+        for addr in resolved {
+            if !addrs.contains(&addr) {
+                addrs.push(addr);
+            }
+        }
+    }
+    Ok(addrs)
+}
+
+#[cfg(not(feature = "dns-srv"))]
+async fn resolve_srv(name: &str) -> RpcResult<Vec<SocketAddr>> {
+    Err(RpcFrameworkError::InvalidAddress(format!(
+        "Discovering {} through DNS SRV requires the dns-srv feature",
+        name,
+    )))
+}
+
+/// 多端点客户端配置
+#[derive(Debug, Clone)]
+pub struct BalancerConfig {
+    pub discovery: EndpointDiscovery,
+    pub strategy: BalanceStrategy,
+    /// 各端点连接的配置，心跳由健康检查统一负责
+    pub client: ClientConfig,
+    pub retry: RetryPolicy,
+    pub health_check_interval: Duration,
+    /// 重新发现端点的间隔，仅对 DNS SRV 有意义
+    pub refresh_interval: Duration,
+    /// 连续失败多少次后剔除端点
+    pub failure_threshold: u32,
+    /// 剔除多久后重新参与选取
+    pub ejection_period: Duration,
+}
+
+impl BalancerConfig {
+    pub fn new(discovery: EndpointDiscovery) -> Self {
+        Self {
+            discovery,
+            strategy: BalanceStrategy::default(),
+            client: ClientConfig {
+                enable_heartbeat: false,
+                auto_reconnect: false,
+                ..ClientConfig::default()
+            },
+            retry: RetryPolicy::default(),
+            health_check_interval: Duration::from_secs(15),
+            refresh_interval: Duration::from_secs(60),
+            failure_threshold: 3,
+            ejection_period: Duration::from_secs(30),
+        }
+    }
+}
+
+/// 端点状态
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EndpointStatus {
+    pub addr: SocketAddr,
+    pub connected: bool,
+    pub ejected: bool,
+    pub pending: usize,
+    /// 平均往返延迟，尚无样本时为 `None`
+    pub latency: Option<Duration>,
+    pub consecutive_failures: u32,
+}
+
+/// 多端点客户端统计信息
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BalancerStats {
+    pub endpoints: usize,
+    pub healthy: usize,
+    pub total_calls: u64,
+    pub failed_calls: u64,
+    pub retries: u64,
+    pub ejections: u64,
+}
+
+#[derive(Debug, Default)]
+struct BalancerCounters {
+    total_calls: AtomicU64,
+    failed_calls: AtomicU64,
+    retries: AtomicU64,
+    ejections: AtomicU64,
+}
+
+struct Endpoint {
+    addr: SocketAddr,
+    client: RpcClient,
+    pending: AtomicUsize,
+    /// 平均延迟（微秒），0 表示尚无样本
+    latency_micros: AtomicU64,
+    consecutive_failures: AtomicU32,
+    ejected_until: Mutex<Option<Instant>>,
+}
+
+impl Endpoint {
+    fn new(addr: SocketAddr, config: &ClientConfig) -> Self {
+        let client_config = ClientConfig {
+            client_id: format!("{}-{}", config.client_id, addr),
+            ..config.clone()
+        };
+        Self {
+            addr,
+            client: RpcClient::new(addr, client_config),
+            pending: AtomicUsize::new(0),
+            latency_micros: AtomicU64::new(0),
+            consecutive_failures: AtomicU32::new(0),
+            ejected_until: Mutex::new(None),
+        }
+    }
+
+    fn ejected(&self) -> bool {
+        self.ejected_until.lock().unwrap().is_some_and(|until| Instant::now() < until)
+    }
+
+    fn latency(&self) -> Option<Duration> {
+        match self.latency_micros.load(Ordering::Relaxed) {
+            0 => None,
+            micros => Some(Duration::from_micros(micros)),
+        }
+    }
+
+    /// `LatencyAware` 策略下的代价，越小越优先
+    fn cost(&self) -> u64 {
+        let pending = self.pending.load(Ordering::Relaxed) as u64;
+        self.latency_micros.load(Ordering::Relaxed).saturating_mul(pending + 1)
+    }
+
+    fn record_success(&self, elapsed: Duration) {
+        let sample = elapsed.as_micros().clamp(1, u64::MAX as u128) as u64;
+        let average = match self.latency_micros.load(Ordering::Relaxed) {
+            0 => sample,
+            previous => (previous as f64 * (1.0 - LATENCY_WEIGHT) + sample as f64 * LATENCY_WEIGHT).max(1.0) as u64,
+        };
+        self.latency_micros.store(average, Ordering::Relaxed);
+        self.consecutive_failures.store(0, Ordering::Relaxed);
+        *self.ejected_until.lock().unwrap() = None;
+    }
+
+    /// 记录失败，达到阈值时剔除；返回是否本次剔除
+    fn record_failure(&self, threshold: u32, period: Duration) -> bool {
+        let failures = self.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        if failures < threshold {
+            return false;
+        }
+        *self.ejected_until.lock().unwrap() = Some(Instant::now() + period);
+        true
+    }
+
+    async fn status(&self) -> EndpointStatus {
+        EndpointStatus {
+            addr: self.addr,
+            connected: self.client.is_connected().await,
+            ejected: self.ejected(),
+            pending: self.pending.load(Ordering::Relaxed),
+            latency: self.latency(),
+            consecutive_failures: self.consecutive_failures.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// 请求结束时减少端点的进行中计数
+struct PendingGuard<'a>(&'a AtomicUsize);
+
+impl Drop for PendingGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// 端点失败时是否应计入剔除
+fn is_endpoint_failure(error: &RpcFrameworkError) -> bool {
+    matches!(
+        error,
+        RpcFrameworkError::ConnectionError(_)
+            | RpcFrameworkError::ConnectionLost(_)
+            | RpcFrameworkError::TimeoutError(_)
+            | RpcFrameworkError::IoError(_)
+    )
+}
+
+/// 跨多个 RPC 服务端负载均衡的客户端
+pub struct BalancedRpcClient {
+    config: BalancerConfig,
+    discovery: RwLock<EndpointDiscovery>,
+    endpoints: RwLock<Vec<Arc<Endpoint>>>,
+    next: AtomicUsize,
+    counters: BalancerCounters,
+}
+
+impl BalancedRpcClient {
+    /// 创建客户端（尚未发现端点）
+    pub fn new(config: BalancerConfig) -> Self {
+        Self {
+            discovery: RwLock::new(config.discovery.clone()),
+            config,
+            endpoints: RwLock::new(Vec::new()),
+            next: AtomicUsize::new(0),
+            counters: BalancerCounters::default(),
+        }
+    }
+
+    /// 发现端点并建立连接，至少一个端点连接成功即视为可用
+    pub async fn connect(&self) -> RpcResult<()> {
+        self.refresh_endpoints().await?;
+        let endpoints = self.endpoints.read().await.clone();
+        let mut last_error = None;
+        for endpoint in &endpoints {
+            if endpoint.client.is_connected().await {
+                continue;
+            }
+            if let Err(e) = endpoint.client.connect().await {
+                warn!("Connection to RPC endpoint {} failed: {}", endpoint.addr, e);
+                self.record_failure(endpoint);
+                last_error = Some(e);
+            }
+        }
+
+        if self.healthy_count().await > 0 {
+            Ok(())
+        } else {
+            Err(last_error.unwrap_or_else(|| RpcFrameworkError::ConnectionError("No RPC endpoints discovered".to_string())))
+        }
+    }
+
+    /// 重新发现端点：新增的端点加入选取，消失的端点断开
+    ///
+    /// 发现结果为空时保留现有端点，避免 DNS 短暂异常清空整个列表。
+    pub async fn refresh_endpoints(&self) -> RpcResult<()> {
+        let addrs = self.discovery.read().await.resolve().await?;
+        if addrs.is_empty() {
+            warn!("Endpoint discovery returned no addresses, keeping {} known endpoints", self.endpoints.read().await.len());
+            return Ok(());
+        }
+
+        let removed = {
+            let mut endpoints = self.endpoints.write().await;
+            let (kept, removed): (Vec<_>, Vec<_>) = endpoints.drain(..).partition(|endpoint| addrs.contains(&endpoint.addr));
+            *endpoints = kept;
+            for addr in &addrs {
+                if !endpoints.iter().any(|endpoint| endpoint.addr == *addr) {
+                    info!("Discovered RPC endpoint {}", addr);
+                    endpoints.push(Arc::new(Endpoint::new(*addr, &self.config.client)));
+                }
+            }
+            removed
+        };
+        for endpoint in removed {
+            info!("RPC endpoint {} is no longer advertised", endpoint.addr);
+            if let Err(e) = endpoint.client.disconnect().await {
+                debug!("Failed to disconnect from {}: {}", endpoint.addr, e);
+            }
+        }
+        Ok(())
+    }
+
+    /// 更换端点发现方式（如配置重新加载后）并立即刷新端点
+    pub async fn set_discovery(&self, discovery: EndpointDiscovery) -> RpcResult<()> {
+        *self.discovery.write().await = discovery;
+        self.refresh_endpoints().await
+    }
+
+    /// 类型化调用，使用默认选项
+    pub async fn call<P, R>(&self, method: &str, params: &P) -> RpcResult<R>
+    where
+        P: Serialize + ?Sized,
+        R: DeserializeOwned,
+    {
+        self.call_with_retry(method, params, CallOptions::default()).await
+    }
+
+    /// 类型化调用，按选项进行超时、取消与重试；重试通常落在其他端点
+    pub async fn call_with_retry<P, R>(&self, method: &str, params: &P, options: CallOptions) -> RpcResult<R>
+    where
+        P: Serialize + ?Sized,
+        R: DeserializeOwned,
+    {
+        let params = serde_json::to_value(params)?;
+        let policy = options.retry.as_ref().unwrap_or(&self.config.retry);
+        self.counters.total_calls.fetch_add(1, Ordering::Relaxed);
+
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let error = match self.call_once(method, params.clone(), &options).await {
+                Ok(value) => return Ok(serde_json::from_value(value)?),
+                Err(error) => error,
+            };
+
+            let delay = match retry_delay(&error, options.idempotency) {
+                Some(hint) if attempt < policy.max_attempts => hint.max(policy.backoff(attempt)),
+                _ => {
+                    self.counters.failed_calls.fetch_add(1, Ordering::Relaxed);
+                    return Err(error);
+                }
+            };
+
+            debug!("Retrying '{}' in {:?} (attempt {}): {}", method, delay, attempt, error);
+            self.counters.retries.fetch_add(1, Ordering::Relaxed);
+
+            match &options.cancellation {
+                Some(token) => tokio::select! {
+                    _ = tokio::time::sleep(delay) => {}
+                    _ = token.cancelled() => {
+                        self.counters.failed_calls.fetch_add(1, Ordering::Relaxed);
+                        return Err(RpcFrameworkError::Cancelled(method.to_string()));
+                    }
+                },
+                None => tokio::time::sleep(delay).await,
+            }
+        }
+    }
+
+    /// ping 所有端点：断开的端点尝试重连，成功的端点恢复选取
+    pub async fn check_health(&self) {
+        let endpoints = self.endpoints.read().await.clone();
+        for endpoint in &endpoints {
+            if !endpoint.client.is_connected().await {
+                if let Err(e) = endpoint.client.connect().await {
+                    debug!("Health check reconnect to {} failed: {}", endpoint.addr, e);
+                    self.record_failure(endpoint);
+                    continue;
+                }
+            }
+            match endpoint.client.ping().await {
+                Ok(elapsed) => endpoint.record_success(elapsed),
+                Err(e) => {
+                    debug!("Health check ping to {} failed: {}", endpoint.addr, e);
+                    self.record_failure(endpoint);
+                }
+            }
+        }
+    }
+
+    /// 启动周期性健康检查与端点发现，客户端释放后自动停止
+    pub fn start_background_tasks(self: &Arc<Self>) -> JoinHandle<()> {
+        let balancer: Weak<Self> = Arc::downgrade(self);
+        let health_interval = self.config.health_check_interval;
+        let refresh_interval = self.config.refresh_interval;
+
+        tokio::spawn(async move {
+            let mut health = tokio::time::interval(health_interval);
+            let mut refresh = tokio::time::interval(refresh_interval);
+            health.tick().await;
+            refresh.tick().await;
+            loop {
+                tokio::select! {
+                    _ = health.tick() => match balancer.upgrade() {
+                        Some(balancer) => balancer.check_health().await,
+                        None => break,
+                    },
+                    _ = refresh.tick() => match balancer.upgrade() {
+                        Some(balancer) => {
+                            if let Err(e) = balancer.refresh_endpoints().await {
+                                warn!("RPC endpoint discovery failed: {}", e);
+                            }
+                        }
+                        None => break,
+                    },
+                }
+            }
+        })
+    }
+
+    /// 所有端点的状态，按发现顺序
+    pub async fn endpoints(&self) -> Vec<EndpointStatus> {
+        let endpoints = self.endpoints.read().await.clone();
+        let mut statuses = Vec::with_capacity(endpoints.len());
+        for endpoint in &endpoints {
+            statuses.push(endpoint.status().await);
+        }
+        statuses
+    }
+
+    /// 已连接且未被剔除的端点数
+    pub async fn healthy_count(&self) -> usize {
+        self.endpoints().await.iter().filter(|status| status.connected && !status.ejected).count()
+    }
+
+    /// 获取统计信息
+    pub async fn stats(&self) -> BalancerStats {
+        let endpoints = self.endpoints().await;
+        BalancerStats {
+            endpoints: endpoints.len(),
+            healthy: endpoints.iter().filter(|status| status.connected && !status.ejected).count(),
+            total_calls: self.counters.total_calls.load(Ordering::Relaxed),
+            failed_calls: self.counters.failed_calls.load(Ordering::Relaxed),
+            retries: self.counters.retries.load(Ordering::Relaxed),
+            ejections: self.counters.ejections.load(Ordering::Relaxed),
+        }
+    }
+
+    /// 关闭所有连接
+    pub async fn close(&self) -> RpcResult<()> {
+        let endpoints = self.endpoints.read().await.clone();
+        for endpoint in &endpoints {
+            endpoint.client.disconnect().await?;
+        }
+        Ok(())
+    }
+
+    /// 按策略选取一个未被剔除的端点
+    async fn select(&self) -> RpcResult<Arc<Endpoint>> {
+        let endpoints = self.endpoints.read().await;
+        let candidates: Vec<&Arc<Endpoint>> = endpoints.iter().filter(|endpoint| !endpoint.ejected()).collect();
+        if candidates.is_empty() {
+            return Err(RpcFrameworkError::ConnectionError(format!(
+                "All {} RPC endpoints are ejected",
+                endpoints.len(),
+            )));
+        }
+
+        // 起点轮换，使各策略在代价相同时也能分散请求
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        let mut rotated = (0..candidates.len()).map(|offset| candidates[(start + offset) % candidates.len()]);
+        let selected = match self.config.strategy {
+            BalanceStrategy::RoundRobin => rotated.next(),
+            BalanceStrategy::LeastPending => rotated.min_by_key(|endpoint| endpoint.pending.load(Ordering::Relaxed)),
+            BalanceStrategy::LatencyAware => rotated.min_by_key(|endpoint| endpoint.cost()),
+        };
+        Ok(selected.expect("candidates is not empty").clone())
+    }
+
+    async fn call_once(&self, method: &str, params: Value, options: &CallOptions) -> RpcResult<Value> {
+        let endpoint = self.select().await?;
+        endpoint.pending.fetch_add(1, Ordering::Relaxed);
+        let _pending = PendingGuard(&endpoint.pending);
+
+        if !endpoint.client.is_connected().await {
+            if let Err(e) = endpoint.client.connect().await {
+                self.record_failure(&endpoint);
+                return Err(e);
+            }
+        }
+
+        let request_timeout = options.timeout.unwrap_or(self.config.client.request_timeout);
+        let started = Instant::now();
+        let request = endpoint.client.send_request_with_timeout(method, params, request_timeout);
+        let result = match &options.cancellation {
+            Some(token) => tokio::select! {
+                result = request => result,
+                _ = token.cancelled() => return Err(RpcFrameworkError::Cancelled(method.to_string())),
+            },
+            None => request.await,
+        };
+
+        match &result {
+            Err(error) if is_endpoint_failure(error) => self.record_failure(&endpoint),
+            // 服务端返回的错误也说明端点可达
+            _ => endpoint.record_success(started.elapsed()),
+        }
+        result
+    }
+
+    fn record_failure(&self, endpoint: &Endpoint) {
+        if endpoint.record_failure(self.config.failure_threshold, self.config.ejection_period) {
+            warn!(
+                "Ejecting RPC endpoint {} for {:?} after {} consecutive failures",
+                endpoint.addr,
+                self.config.ejection_period,
+                endpoint.consecutive_failures.load(Ordering::Relaxed),
+            );
+            self.counters.ejections.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::FunctionHandler;
+    use crate::server::{RpcServer, ServerConfig};
+    use serde_json::json;
+
+    async fn start_server(name: &'static str) -> SocketAddr {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        let server = Arc::new(RpcServer::new(ServerConfig {
+            bind_addr: addr,
+            ..ServerConfig::default()
+        }));
+        server.register_handler(Arc::new(FunctionHandler::new("whoami".to_string(), move |_params| async move {
+            Ok(json!(name))
+        })));
+        tokio::spawn(server.serve());
+
+        for _ in 0..50 {
+            if tokio::net::TcpStream::connect(addr).await.is_ok() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        addr
+    }
+
+    /// 没有服务端监听的地址
+    fn dead_addr() -> SocketAddr {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_round_robin_spreads_calls() {
+        let first = start_server("first").await;
+        let second = start_server("second").await;
+        let client = BalancedRpcClient::new(BalancerConfig::new(EndpointDiscovery::Static(vec![first, second])));
+        client.connect().await.unwrap();
+        assert_eq!(client.healthy_count().await, 2);
+
+        let mut seen = Vec::new();
+        for _ in 0..4 {
+            seen.push(client.call::<_, String>("whoami", &json!({})).await.unwrap());
+        }
+        assert_eq!(seen.iter().filter(|name| *name == "first").count(), 2);
+        assert_eq!(seen.iter().filter(|name| *name == "second").count(), 2);
+        assert!(client.endpoints().await.iter().all(|status| status.latency.is_some() && status.pending == 0));
+
+        client.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_failed_endpoint_is_ejected() {
+        let live = start_server("live").await;
+        let dead = dead_addr();
+        let mut config = BalancerConfig::new(EndpointDiscovery::Static(vec![dead, live]));
+        config.strategy = BalanceStrategy::LeastPending;
+        config.failure_threshold = 1;
+        config.client.connect_timeout = Duration::from_millis(200);
+        config.retry.initial_backoff = Duration::from_millis(1);
+        let client = BalancedRpcClient::new(config);
+
+        // 一个端点可用即可连接，不可用的端点被剔除
+        client.connect().await.unwrap();
+        let stats = client.stats().await;
+        assert_eq!((stats.endpoints, stats.healthy, stats.ejections), (2, 1, 1));
+
+        for _ in 0..3 {
+            assert_eq!(client.call::<_, String>("whoami", &json!({})).await.unwrap(), "live");
+        }
+        let dead_status = client.endpoints().await.into_iter().find(|status| status.addr == dead).unwrap();
+        assert!(dead_status.ejected);
+        assert_eq!(client.stats().await.retries, 0);
+
+        client.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_discovery_refresh_and_latency_strategy() {
+        let first = start_server("first").await;
+        let mut config = BalancerConfig::new(EndpointDiscovery::Static(vec![first]));
+        config.strategy = BalanceStrategy::LatencyAware;
+        let client = BalancedRpcClient::new(config);
+        client.connect().await.unwrap();
+        assert_eq!(client.call::<_, String>("whoami", &json!({})).await.unwrap(), "first");
+
+        // 新发现的端点尚无延迟样本，优先被选取
+        let second = start_server("second").await;
+        client.set_discovery(EndpointDiscovery::Static(vec![first, second])).await.unwrap();
+        assert_eq!(client.endpoints().await.len(), 2);
+        assert_eq!(client.call::<_, String>("whoami", &json!({})).await.unwrap(), "second");
+
+        // 不再出现的端点被移除
+        client.set_discovery(EndpointDiscovery::Static(vec![second])).await.unwrap();
+        let endpoints = client.endpoints().await;
+        assert_eq!(endpoints.len(), 1);
+        assert_eq!(endpoints[0].addr, second);
+        client.close().await.unwrap();
+
+        #[cfg(not(feature = "dns-srv"))]
+        assert!(EndpointDiscovery::DnsSrv("_stepflow-rpc._tcp.example.com".to_string()).resolve().await.is_err());
+    }
+}
@@ -1,17 +1,22 @@
 //! JSON-RPC TCP 客户端
 
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use dashmap::DashMap;
 use serde_json::Value;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::io::{AsyncWriteExt, BufReader};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
 use tokio::net::TcpStream;
-use tokio::sync::{broadcast, RwLock};
+use tokio::sync::{broadcast, Mutex, RwLock};
 use tokio::time::timeout;
 use tracing::{debug, error, info, warn};
 
+use crate::auth::CONNECT_METHOD;
+use crate::codec::{read_frame, WireFormat, NEGOTIATE_METHOD};
+use crate::compression::{Compression, CompressionConfig, CompressionMetrics, CompressionStats, FrameCompressor};
 use crate::error::{RpcError, RpcFrameworkError, RpcResult};
 use crate::protocol::{RpcEvent, RpcRequest, RpcResponse, RpcResponseMessage, ServerMessage};
 use crate::subscription_manager::{ClientId, EventFilter, SubscriptionId, SubscriptionManager};
@@ -58,10 +63,21 @@ pub struct ClientConfig {
     pub client_id: ClientId,
     pub connect_timeout: Duration,
     pub request_timeout: Duration,
+    /// 首次重连等待时间，之后按指数退避
     pub reconnect_interval: Duration,
+    /// 重连退避上限
+    pub max_reconnect_interval: Duration,
     pub max_reconnect_attempts: u32,
+    /// 心跳发现连接断开时自动重连
+    pub auto_reconnect: bool,
     pub enable_heartbeat: bool,
     pub heartbeat_interval: Duration,
+    /// 连接建立后用于 `rpc.connect` 握手的 token
+    pub auth_token: Option<String>,
+    /// 按偏好排序的线路编码；仅含 JSON 时不进行协商
+    pub wire_formats: Vec<WireFormat>,
+    /// 提供给服务端的压缩算法与发送压缩阈值；仅在协商到 MessagePack 时生效
+    pub compression: CompressionConfig,
 }
 
 impl Default for ClientConfig {
@@ -71,9 +87,14 @@ impl Default for ClientConfig {
             connect_timeout: Duration::from_secs(10),
             request_timeout: Duration::from_secs(30),
             reconnect_interval: Duration::from_secs(5),
+            max_reconnect_interval: Duration::from_secs(60),
             max_reconnect_attempts: 5,
+            auto_reconnect: true,
             enable_heartbeat: true,
             heartbeat_interval: Duration::from_secs(30),
+            auth_token: None,
+            wire_formats: vec![WireFormat::Json],
+            compression: CompressionConfig::disabled(),
         }
     }
 }
@@ -83,7 +104,13 @@ pub struct RpcClient {
     config: ClientConfig,
     server_addr: SocketAddr,
     connection_state: Arc<RwLock<ConnectionState>>,
-    stream: Arc<RwLock<Option<TcpStream>>>,
+    writer: Arc<Mutex<Option<OwnedWriteHalf>>>,
+    // 每次建立连接递增，用于忽略旧连接的清理
+    generation: Arc<AtomicU64>,
+    heartbeat_running: Arc<AtomicBool>,
+    wire_format: Arc<RwLock<WireFormat>>,
+    compressor: Arc<RwLock<Option<FrameCompressor>>>,
+    compression_metrics: Arc<CompressionMetrics>,
     
     // 请求管理
     pending_requests: Arc<DashMap<String, tokio::sync::oneshot::Sender<RpcResponse>>>,
@@ -98,6 +125,10 @@ pub struct RpcClient {
     
     // 控制通道
     shutdown_sender: Arc<RwLock<Option<tokio::sync::oneshot::Sender<()>>>>,
+
+    // 故障注入（仅测试）
+    #[cfg(feature = "fault-injection")]
+    faults: Option<stepflow_core::FaultController>,
 }
 
 impl RpcClient {
@@ -110,16 +141,32 @@ impl RpcClient {
             config,
             server_addr,
             connection_state: Arc::new(RwLock::new(ConnectionState::Disconnected)),
-            stream: Arc::new(RwLock::new(None)),
+            writer: Arc::new(Mutex::new(None)),
+            generation: Arc::new(AtomicU64::new(0)),
+            heartbeat_running: Arc::new(AtomicBool::new(false)),
+            wire_format: Arc::new(RwLock::new(WireFormat::Json)),
+            compressor: Arc::new(RwLock::new(None)),
+            compression_metrics: Arc::new(CompressionMetrics::default()),
             pending_requests: Arc::new(DashMap::new()),
             request_counter: Arc::new(RwLock::new(0)),
             event_sender: Arc::new(event_sender),
             subscription_manager,
             stats: Arc::new(RwLock::new(ClientStats::default())),
             shutdown_sender: Arc::new(RwLock::new(None)),
+            #[cfg(feature = "fault-injection")]
+            faults: None,
         }
     }
 
+    /// 为请求注入故障（仅测试）
+    ///
+    /// 部分写入故障会把请求发送给服务器，但丢弃响应并返回连接丢失错误。
+    #[cfg(feature = "fault-injection")]
+    pub fn with_fault_controller(mut self, controller: stepflow_core::FaultController) -> Self {
+        self.faults = Some(controller);
+        self
+    }
+
     /// 使用客户端ID创建客户端
     pub fn with_client_id(server_addr: SocketAddr, client_id: ClientId) -> Self {
         let config = ClientConfig {
@@ -140,40 +187,170 @@ impl RpcClient {
             stats.connection_attempts += 1;
         }
 
-        let stream = match timeout(self.config.connect_timeout, TcpStream::connect(self.server_addr)).await {
+        let mut stream = match timeout(self.config.connect_timeout, TcpStream::connect(self.server_addr)).await {
             Ok(Ok(stream)) => stream,
             Ok(Err(e)) => {
                 *connection_state = ConnectionState::Failed(format!("Connection error: {}", e));
-                return Err(RpcFrameworkError::RpcError(RpcError::internal_error(&format!("Failed to connect: {}", e))));
+                return Err(RpcFrameworkError::ConnectionError(format!("Failed to connect: {}", e)));
             }
             Err(_) => {
                 *connection_state = ConnectionState::Failed("Connection timeout".to_string());
-                return Err(RpcFrameworkError::RpcError(RpcError::internal_error("Connection timeout")));
+                return Err(RpcFrameworkError::ConnectionError("Connection timeout".to_string()));
             }
         };
 
-        // 保存连接
-        {
-            let mut stream_guard = self.stream.write().await;
-            *stream_guard = Some(stream);
-        }
+        // 协商线路编码与压缩
+        let (format, compression) = match self.negotiate_format(&mut stream).await {
+            Ok(negotiated) => negotiated,
+            Err(e) => {
+                *connection_state = ConnectionState::Failed(format!("Negotiation failed: {}", e));
+                return Err(e);
+            }
+        };
+        *self.wire_format.write().await = format;
+        let compressor = (compression != Compression::None)
+            .then(|| FrameCompressor::new(compression, &self.config.compression, self.compression_metrics.clone()));
+        *self.compressor.write().await = compressor.clone();
+
+        // 读写分离：读半部分交给消息处理器，写半部分用于发送请求
+        let (read_half, write_half) = stream.into_split();
+        let generation = self.generation.fetch_add(1, Ordering::AcqRel) + 1;
+        *self.writer.lock().await = Some(write_half);
 
         *connection_state = ConnectionState::Connected;
         info!("Connected to server at {}", self.server_addr);
 
         // 启动消息处理器
-        self.start_message_handler().await;
+        self.start_message_handler(read_half, generation, format, compressor);
 
         // 启动心跳
-        if self.config.enable_heartbeat {
-            self.start_heartbeat().await;
+        if self.config.enable_heartbeat && !self.heartbeat_running.swap(true, Ordering::AcqRel) {
+            self.start_heartbeat();
+        }
+        drop(connection_state);
+
+        // 认证握手
+        if let Some(token) = &self.config.auth_token {
+            self.authenticate(token).await?;
         }
 
         Ok(())
     }
 
+    /// 使用 token 对当前连接进行认证
+    pub async fn authenticate(&self, token: &str) -> RpcResult<Value> {
+        self.send_request(CONNECT_METHOD, serde_json::json!({ "token": token })).await
+    }
+
+    /// 在发送其他请求前协商线路编码与压缩，服务端不支持协商时回退到不压缩的 JSON
+    async fn negotiate_format(&self, stream: &mut TcpStream) -> RpcResult<(WireFormat, Compression)> {
+        if self.config.wire_formats.iter().all(|f| *f == WireFormat::Json) {
+            return Ok((WireFormat::Json, Compression::None));
+        }
+
+        let request = RpcRequest::new(
+            NEGOTIATE_METHOD.to_string(),
+            Some(serde_json::json!({
+                "formats": self.config.wire_formats,
+                "compression": self.config.compression.algorithms,
+            })),
+        );
+        stream.write_all(&WireFormat::Json.encode_frame(&request)?).await?;
+
+        let frame = timeout(self.config.connect_timeout, read_frame(stream, WireFormat::Json))
+            .await
+            .map_err(|_| RpcFrameworkError::TimeoutError("Format negotiation timed out".to_string()))??
+            .ok_or_else(|| RpcFrameworkError::ConnectionError("Connection closed during negotiation".to_string()))?;
+        let response: RpcResponse = WireFormat::Json.decode(&frame)?;
+
+        let result = response.result.unwrap_or_default();
+        let format = result
+            .get("format")
+            .and_then(|format| serde_json::from_value(format.clone()).ok())
+            .unwrap_or(WireFormat::Json);
+        let compression = result
+            .get("compression")
+            .and_then(|compression| serde_json::from_value(compression.clone()).ok())
+            .filter(|compression| format != WireFormat::Json && self.config.compression.algorithms.contains(compression))
+            .unwrap_or(Compression::None);
+        debug!(
+            "Negotiated {} encoding with {} compression with {}",
+            format.as_str(),
+            compression.as_str(),
+            self.server_addr
+        );
+        Ok((format, compression))
+    }
+
+    /// 当前连接使用的线路编码
+    pub async fn wire_format(&self) -> WireFormat {
+        *self.wire_format.read().await
+    }
+
+    /// 当前连接使用的压缩算法
+    pub async fn compression(&self) -> Compression {
+        self.compressor.read().await.as_ref().map(|c| c.compression()).unwrap_or_default()
+    }
+
+    /// 本客户端发送消息的压缩指标
+    pub fn compression_stats(&self) -> CompressionStats {
+        self.compression_metrics.snapshot()
+    }
+
+    /// 按退避策略重连，直到成功或用尽重连次数
+    pub async fn reconnect(&self) -> RpcResult<()> {
+        let mut delay = self.config.reconnect_interval;
+        let mut last_error = None;
+
+        for attempt in 1..=self.config.max_reconnect_attempts {
+            *self.connection_state.write().await = ConnectionState::Reconnecting;
+            {
+                let mut stats = self.stats.write().await;
+                stats.reconnection_attempts += 1;
+            }
+
+            match self.connect().await {
+                Ok(()) => {
+                    info!("Reconnected to {} after {} attempt(s)", self.server_addr, attempt);
+                    return Ok(());
+                }
+                Err(e) => {
+                    warn!("Reconnect attempt {} to {} failed: {}", attempt, self.server_addr, e);
+                    last_error = Some(e);
+                }
+            }
+
+            if attempt < self.config.max_reconnect_attempts {
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(self.config.max_reconnect_interval);
+            }
+        }
+
+        *self.connection_state.write().await = ConnectionState::Failed("Reconnect attempts exhausted".to_string());
+        Err(last_error.unwrap_or_else(|| RpcFrameworkError::ConnectionError("Reconnect attempts exhausted".to_string())))
+    }
+
+    /// 是否处于已连接状态
+    pub async fn is_connected(&self) -> bool {
+        matches!(*self.connection_state.read().await, ConnectionState::Connected)
+    }
+
+    /// 发送 `rpc.ping` 并返回往返耗时
+    pub async fn ping(&self) -> RpcResult<Duration> {
+        let started = Instant::now();
+        self.send_request_with_timeout("rpc.ping", Value::Null, self.config.request_timeout).await?;
+        Ok(started.elapsed())
+    }
+
     /// 发送请求并等待响应
     pub async fn send_request(&self, method: &str, params: Value) -> RpcResult<Value> {
+        self.send_request_with_timeout(method, params, self.config.request_timeout).await
+    }
+
+    /// 使用指定超时发送请求
+    ///
+    /// 超时或调用方丢弃该 future（取消）时，会清理对应的待处理请求。
+    pub async fn send_request_with_timeout(&self, method: &str, params: Value, request_timeout: Duration) -> RpcResult<Value> {
         let request = RpcRequest::new(method.to_string(), Some(params));
         
         // 获取请求ID
@@ -185,12 +362,36 @@ impl RpcClient {
         // 创建响应通道
         let (response_tx, response_rx) = tokio::sync::oneshot::channel();
         self.pending_requests.insert(request_id.clone(), response_tx);
+        let _pending = PendingRequestGuard {
+            request_id,
+            pending_requests: &self.pending_requests,
+        };
 
-        // 发送请求
-        let request_json = serde_json::to_string(&request)
-            .map_err(|e| RpcFrameworkError::RpcError(RpcError::internal_error(&format!("Failed to serialize request: {}", e))))?;
+        #[cfg(feature = "fault-injection")]
+        let fault = match &self.faults {
+            Some(faults) => faults.inject(stepflow_core::FaultTarget::RpcClient).await,
+            None => None,
+        };
+        #[cfg(feature = "fault-injection")]
+        if fault == Some(stepflow_core::Fault::Error) {
+            self.stats.write().await.failed_requests += 1;
+            return Err(RpcFrameworkError::ConnectionLost(
+                stepflow_core::Fault::Error.message(stepflow_core::FaultTarget::RpcClient),
+            ));
+        }
 
-        self.send_message(&request_json).await?;
+        // 发送请求
+        let format = *self.wire_format.read().await;
+        let frame = format.encode_compressed_frame(&request, self.compressor.read().await.as_ref())?;
+        self.send_frame(&frame).await?;
+
+        #[cfg(feature = "fault-injection")]
+        if fault == Some(stepflow_core::Fault::PartialWrite) {
+            self.stats.write().await.failed_requests += 1;
+            return Err(RpcFrameworkError::ConnectionLost(
+                stepflow_core::Fault::PartialWrite.message(stepflow_core::FaultTarget::RpcClient),
+            ));
+        }
 
         // 更新统计信息
         {
@@ -199,9 +400,17 @@ impl RpcClient {
         }
 
         // 等待响应
-        let response = timeout(self.config.request_timeout, response_rx).await
-            .map_err(|_| RpcFrameworkError::RpcError(RpcError::internal_error("Request timeout")))?
-            .map_err(|_| RpcFrameworkError::RpcError(RpcError::internal_error("Response channel closed")))?;
+        let response = match timeout(request_timeout, response_rx).await {
+            Ok(Ok(response)) => response,
+            Ok(Err(_)) => {
+                self.stats.write().await.failed_requests += 1;
+                return Err(RpcFrameworkError::ConnectionLost(format!("Connection closed before '{}' completed", method)));
+            }
+            Err(_) => {
+                self.stats.write().await.failed_requests += 1;
+                return Err(RpcFrameworkError::TimeoutError(format!("'{}' timed out after {:?}", method, request_timeout)));
+            }
+        };
 
         // 处理响应
         if let Some(result) = response.result {
@@ -267,79 +476,84 @@ impl RpcClient {
     }
 
     /// 启动消息处理器
-    async fn start_message_handler(&self) {
-        let stream = self.stream.clone();
-        let pending_requests = self.pending_requests.clone();
-        let event_sender = self.event_sender.clone();
-        let subscription_manager = self.subscription_manager.clone();
-        let stats = self.stats.clone();
-        let _client_id = self.config.client_id.clone();
+    fn start_message_handler(
+        &self,
+        read_half: OwnedReadHalf,
+        generation: u64,
+        format: WireFormat,
+        compressor: Option<FrameCompressor>,
+    ) {
+        let client = self.clone();
 
         tokio::spawn(async move {
+            let mut reader = BufReader::new(read_half);
+
             loop {
-                // 取出 TcpStream，避免长时间持有锁
-                let tcp_stream = {
-                    let mut stream_guard = stream.write().await;
-                    stream_guard.take()
-                };
-                
-                if let Some(tcp_stream) = tcp_stream {
-                    let mut reader = BufReader::new(tcp_stream);
-                    let mut line = String::new();
-                    
-                    match reader.read_line(&mut line).await {
-                        Ok(0) => {
-                            // 连接关闭
-                            warn!("Connection closed by server");
-                            break;
-                        }
-                        Ok(_) => {
-                            line = line.trim().to_string();
-                            if !line.is_empty() {
-                                Self::handle_message(
-                                    &line,
-                                    &pending_requests,
-                                    &event_sender,
-                                    &subscription_manager,
-                                    &stats,
-                                ).await;
-                            }
-                            
-                            // 重新获取内部的 TcpStream
-                            let inner_stream = reader.into_inner();
-                            let mut stream_guard = stream.write().await;
-                            *stream_guard = Some(inner_stream);
-                        }
-                        Err(e) => {
-                            error!("Error reading from stream: {}", e);
-                            break;
+                match read_frame(&mut reader, format).await {
+                    Ok(Some(frame)) => match format.decode_compressed::<ServerMessage>(&frame, compressor.as_ref()) {
+                        Ok(message) => {
+                            Self::handle_message(
+                                message,
+                                &client.pending_requests,
+                                &client.event_sender,
+                                &client.subscription_manager,
+                                &client.stats,
+                            ).await;
                         }
+                        Err(e) => warn!("Failed to decode server message: {}", e),
+                    },
+                    Ok(None) => {
+                        // 连接关闭
+                        warn!("Connection closed by server");
+                        break;
+                    }
+                    Err(e) => {
+                        error!("Error reading from stream: {}", e);
+                        break;
                     }
-                } else {
-                    // 没有连接，等待一段时间后重试
-                    tokio::time::sleep(Duration::from_millis(100)).await;
                 }
             }
+
+            // 已被新连接取代时不做清理
+            if client.generation.load(Ordering::Acquire) != generation {
+                return;
+            }
+
+            client.writer.lock().await.take();
+            // 丢弃发送端，等待中的请求会立即以 ConnectionLost 返回
+            client.pending_requests.clear();
+
+            let mut state = client.connection_state.write().await;
+            if matches!(*state, ConnectionState::Connected) {
+                *state = ConnectionState::Failed("Connection lost".to_string());
+            }
         });
     }
 
     /// 处理接收到的消息
     async fn handle_message(
-        message: &str,
+        server_message: ServerMessage,
         pending_requests: &Arc<DashMap<String, tokio::sync::oneshot::Sender<RpcResponse>>>,
         event_sender: &Arc<broadcast::Sender<RpcEvent>>,
         subscription_manager: &Arc<SubscriptionManager>,
         stats: &Arc<RwLock<ClientStats>>,
     ) {
-        debug!("Received message: {}", message);
-
-        // 尝试解析为服务器消息
-        if let Ok(server_message) = serde_json::from_str::<ServerMessage>(message) {
-            match server_message {
-                ServerMessage::Response(response) => {
-                    // 处理响应消息
-                    match response {
-                        RpcResponseMessage::Single(single_response) => {
+        debug!("Received message: {:?}", server_message);
+
+        match server_message {
+            ServerMessage::Response(response) => {
+                // 处理响应消息
+                match response {
+                    RpcResponseMessage::Single(single_response) => {
+                        let request_id = single_response.id.as_str().unwrap_or_default().to_string();
+                        if let Some((_, sender)) = pending_requests.remove(&request_id) {
+                            if let Err(_) = sender.send(single_response) {
+                                warn!("Failed to send response to waiting request");
+                            }
+                        }
+                    }
+                    RpcResponseMessage::Batch(batch_responses) => {
+                        for single_response in batch_responses {
                             let request_id = single_response.id.as_str().unwrap_or_default().to_string();
                             if let Some((_, sender)) = pending_requests.remove(&request_id) {
                                 if let Err(_) = sender.send(single_response) {
@@ -347,55 +561,43 @@ impl RpcClient {
                                 }
                             }
                         }
-                        RpcResponseMessage::Batch(batch_responses) => {
-                            for single_response in batch_responses {
-                                let request_id = single_response.id.as_str().unwrap_or_default().to_string();
-                                if let Some((_, sender)) = pending_requests.remove(&request_id) {
-                                    if let Err(_) = sender.send(single_response) {
-                                        warn!("Failed to send response to waiting request");
-                                    }
-                                }
-                            }
-                        }
                     }
                 }
-                ServerMessage::Event(event) => {
-                    // 更新统计信息
-                    {
-                        let mut stats = stats.write().await;
-                        stats.events_received += 1;
-                    }
+            }
+            ServerMessage::Event(event) => {
+                // 更新统计信息
+                {
+                    let mut stats = stats.write().await;
+                    stats.events_received += 1;
+                }
 
-                    // 检查事件过滤器
-                    let matching_subscriptions = subscription_manager.get_matching_subscriptions(&event).await;
-                    if !matching_subscriptions.is_empty() {
-                        let _ = event_sender.send(event);
-                    }
+                // 检查事件过滤器
+                let matching_subscriptions = subscription_manager.get_matching_subscriptions(&event).await;
+                if !matching_subscriptions.is_empty() {
+                    let _ = event_sender.send(event);
                 }
             }
         }
     }
 
     /// 发送消息到服务器
-    async fn send_message(&self, message: &str) -> RpcResult<()> {
-        let mut stream_guard = self.stream.write().await;
-        if let Some(ref mut tcp_stream) = *stream_guard {
-            let message_with_newline = format!("{}\n", message);
-            
-            tcp_stream.write_all(message_with_newline.as_bytes()).await
-                .map_err(|e| RpcFrameworkError::RpcError(RpcError::internal_error(&format!("Failed to write to stream: {}", e))))?;
+    async fn send_frame(&self, frame: &[u8]) -> RpcResult<()> {
+        let mut writer_guard = self.writer.lock().await;
+        if let Some(ref mut writer) = *writer_guard {
+            writer.write_all(frame).await
+                .map_err(|e| RpcFrameworkError::ConnectionError(format!("Failed to write to stream: {}", e)))?;
             
-            tcp_stream.flush().await
-                .map_err(|e| RpcFrameworkError::RpcError(RpcError::internal_error(&format!("Failed to flush stream: {}", e))))?;
+            writer.flush().await
+                .map_err(|e| RpcFrameworkError::ConnectionError(format!("Failed to flush stream: {}", e)))?;
             
             Ok(())
         } else {
-            Err(RpcFrameworkError::RpcError(RpcError::internal_error("Not connected to server")))
+            Err(RpcFrameworkError::ConnectionError("Not connected to server".to_string()))
         }
     }
 
     /// 启动心跳
-    async fn start_heartbeat(&self) {
+    fn start_heartbeat(&self) {
         let client = self.clone();
         
         tokio::spawn(async move {
@@ -407,16 +609,25 @@ impl RpcClient {
                 match client.connection_state().await {
                     ConnectionState::Connected => {
                         // 发送心跳
-                        if let Err(e) = client.send_request("rpc.ping", Value::Null).await {
+                        if let Err(e) = client.ping().await {
                             error!("Heartbeat failed: {:?}", e);
-                            // 可以在这里触发重连逻辑
                         }
                     }
-                    _ => {
-                        // 连接断开，跳过心跳
+                    ConnectionState::Failed(_) if client.config.auto_reconnect => {
+                        // 连接丢失，自动重连
+                        if let Err(e) = client.reconnect().await {
+                            error!("Automatic reconnect failed: {}", e);
+                        }
+                    }
+                    ConnectionState::Disconnected => {
+                        // 主动断开，停止心跳
+                        break;
                     }
+                    _ => {}
                 }
             }
+
+            client.heartbeat_running.store(false, Ordering::Release);
         });
     }
 
@@ -427,20 +638,20 @@ impl RpcClient {
             let _ = sender.send(());
         }
 
-        // 清理连接
-        {
-            let mut stream_guard = self.stream.write().await;
-            if let Some(stream) = stream_guard.take() {
-                drop(stream);
-            }
-        }
-
         // 更新状态
         {
             let mut connection_state = self.connection_state.write().await;
             *connection_state = ConnectionState::Disconnected;
         }
 
+        // 清理连接
+        {
+            let mut writer_guard = self.writer.lock().await;
+            if let Some(mut writer) = writer_guard.take() {
+                let _ = writer.shutdown().await;
+            }
+        }
+
         // 清理待处理请求
         self.pending_requests.clear();
 
@@ -455,17 +666,36 @@ impl Clone for RpcClient {
             config: self.config.clone(),
             server_addr: self.server_addr,
             connection_state: self.connection_state.clone(),
-            stream: self.stream.clone(),
+            writer: self.writer.clone(),
+            generation: self.generation.clone(),
+            heartbeat_running: self.heartbeat_running.clone(),
+            wire_format: self.wire_format.clone(),
+            compressor: self.compressor.clone(),
+            compression_metrics: self.compression_metrics.clone(),
             pending_requests: self.pending_requests.clone(),
             request_counter: self.request_counter.clone(),
             event_sender: self.event_sender.clone(),
             subscription_manager: self.subscription_manager.clone(),
             stats: self.stats.clone(),
             shutdown_sender: self.shutdown_sender.clone(),
+            #[cfg(feature = "fault-injection")]
+            faults: self.faults.clone(),
         }
     }
 }
 
+/// 请求结束（完成、超时或被取消）时移除待处理记录
+struct PendingRequestGuard<'a> {
+    request_id: String,
+    pending_requests: &'a DashMap<String, tokio::sync::oneshot::Sender<RpcResponse>>,
+}
+
+impl Drop for PendingRequestGuard<'_> {
+    fn drop(&mut self) {
+        self.pending_requests.remove(&self.request_id);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,635 @@
+//! 线路编码（Wire Format）协商与二进制编码
+//!
+//! 连接默认使用换行分隔的 JSON。客户端可在连接建立后立即调用 `rpc.negotiate`
+//! 并按优先级提供格式列表，服务端选择双方都支持的第一个格式，在发送完协商
+//! 响应后双方切换编码。MessagePack 帧使用 4 字节大端长度前缀。
+//!
+//! 所有消息类型通过 serde 先转换为 `serde_json::Value` 再编码，因此无需为
+//! 二进制格式单独实现序列化；旧客户端不协商即可继续使用 JSON。
+//!
+//! 同一次协商还可以选择按消息压缩，见 [`crate::compression`]。
+
+use bytes::{Buf, BufMut, BytesMut};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::compression::{Compression, FrameCompressor};
+use crate::error::{RpcFrameworkError, RpcResult};
+use crate::protocol::{RpcMessage, ServerMessage};
+
+/// 协商方法名
+pub const NEGOTIATE_METHOD: &str = "rpc.negotiate";
+
+/// 单帧最大长度
+pub const MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+
+/// 线路编码格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WireFormat {
+    #[default]
+    Json,
+    MsgPack,
+}
+
+impl WireFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WireFormat::Json => "json",
+            WireFormat::MsgPack => "msgpack",
+        }
+    }
+
+    /// 选择客户端提供的格式中服务端支持的第一个，否则回退到 JSON
+    pub fn negotiate(offered: &[WireFormat], supported: &[WireFormat]) -> WireFormat {
+        offered
+            .iter()
+            .copied()
+            .find(|format| supported.contains(format))
+            .unwrap_or(WireFormat::Json)
+    }
+
+    /// 编码消息体（不含分帧）
+    pub fn encode<T: Serialize + ?Sized>(&self, value: &T) -> RpcResult<Vec<u8>> {
+        match self {
+            WireFormat::Json => Ok(serde_json::to_vec(value)?),
+            WireFormat::MsgPack => Ok(msgpack::to_vec(&serde_json::to_value(value)?)),
+        }
+    }
+
+    /// 解码消息体
+    pub fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> RpcResult<T> {
+        match self {
+            WireFormat::Json => Ok(serde_json::from_slice(bytes)?),
+            WireFormat::MsgPack => {
+                let value = msgpack::from_slice(bytes).map_err(RpcFrameworkError::ConnectionError)?;
+                Ok(serde_json::from_value(value)?)
+            }
+        }
+    }
+
+    /// 编码并分帧
+    pub fn encode_frame<T: Serialize + ?Sized>(&self, value: &T) -> RpcResult<Vec<u8>> {
+        let payload = self.encode(value)?;
+        let mut frame = BytesMut::with_capacity(payload.len() + 4);
+        self.put_frame(&payload, &mut frame)?;
+        Ok(frame.to_vec())
+    }
+
+    /// 编码、按需压缩并分帧
+    pub fn encode_compressed_frame<T: Serialize + ?Sized>(
+        &self,
+        value: &T,
+        compressor: Option<&FrameCompressor>,
+    ) -> RpcResult<Vec<u8>> {
+        let Some(compressor) = compressor else {
+            return self.encode_frame(value);
+        };
+        let payload = compressor.compress(&self.encode(value)?)?;
+        let mut frame = BytesMut::with_capacity(payload.len() + 4);
+        self.put_frame(&payload, &mut frame)?;
+        Ok(frame.to_vec())
+    }
+
+    /// 按需解压并解码消息体
+    pub fn decode_compressed<T: DeserializeOwned>(&self, frame: &[u8], compressor: Option<&FrameCompressor>) -> RpcResult<T> {
+        match compressor {
+            Some(compressor) => self.decode(&compressor.decompress(frame)?),
+            None => self.decode(frame),
+        }
+    }
+
+    fn put_frame(&self, payload: &[u8], dst: &mut BytesMut) -> RpcResult<()> {
+        match self {
+            WireFormat::Json => {
+                dst.reserve(payload.len() + 1);
+                dst.put_slice(payload);
+                dst.put_u8(b'\n');
+            }
+            WireFormat::MsgPack => {
+                if payload.len() > MAX_FRAME_SIZE {
+                    return Err(frame_too_large(payload.len()));
+                }
+                dst.reserve(payload.len() + 4);
+                dst.put_u32(payload.len() as u32);
+                dst.put_slice(payload);
+            }
+        }
+        Ok(())
+    }
+
+    /// 从缓冲区取出一个完整的帧
+    fn take_frame(&self, src: &mut BytesMut) -> RpcResult<Option<BytesMut>> {
+        match self {
+            WireFormat::Json => loop {
+                let Some(newline) = src.iter().position(|b| *b == b'\n') else {
+                    if src.len() > MAX_FRAME_SIZE {
+                        return Err(frame_too_large(src.len()));
+                    }
+                    return Ok(None);
+                };
+                let mut line = src.split_to(newline + 1);
+                line.truncate(newline);
+                if !line.is_empty() {
+                    return Ok(Some(line));
+                }
+            },
+            WireFormat::MsgPack => {
+                if src.len() < 4 {
+                    return Ok(None);
+                }
+                let length = u32::from_be_bytes([src[0], src[1], src[2], src[3]]) as usize;
+                if length > MAX_FRAME_SIZE {
+                    return Err(frame_too_large(length));
+                }
+                if src.len() < 4 + length {
+                    src.reserve(4 + length - src.len());
+                    return Ok(None);
+                }
+                src.advance(4);
+                Ok(Some(src.split_to(length)))
+            }
+        }
+    }
+}
+
+fn frame_too_large(length: usize) -> RpcFrameworkError {
+    RpcFrameworkError::ConnectionError(format!("Frame of {} bytes exceeds {} bytes", length, MAX_FRAME_SIZE))
+}
+
+/// 从异步流中读取一个完整帧，连接关闭时返回 None
+pub async fn read_frame<R: AsyncRead + Unpin>(reader: &mut R, format: WireFormat) -> RpcResult<Option<Vec<u8>>> {
+    match format {
+        WireFormat::Json => {
+            let mut line = Vec::new();
+            loop {
+                let mut byte = [0u8; 1];
+                if reader.read(&mut byte).await? == 0 {
+                    return Ok(None);
+                }
+                if byte[0] != b'\n' {
+                    line.push(byte[0]);
+                    if line.len() > MAX_FRAME_SIZE {
+                        return Err(frame_too_large(line.len()));
+                    }
+                } else if !line.is_empty() {
+                    return Ok(Some(line));
+                }
+            }
+        }
+        WireFormat::MsgPack => {
+            let mut header = [0u8; 4];
+            match reader.read_exact(&mut header).await {
+                Ok(_) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+                Err(e) => return Err(e.into()),
+            }
+            let length = u32::from_be_bytes(header) as usize;
+            if length > MAX_FRAME_SIZE {
+                return Err(frame_too_large(length));
+            }
+            let mut payload = vec![0u8; length];
+            reader.read_exact(&mut payload).await?;
+            Ok(Some(payload))
+        }
+    }
+}
+
+/// 服务端编解码器，支持在连接中途切换格式与压缩
+#[derive(Debug, Clone, Default)]
+pub struct RpcCodec {
+    format: WireFormat,
+    compressor: Option<FrameCompressor>,
+}
+
+impl RpcCodec {
+    pub fn new(format: WireFormat) -> Self {
+        Self { format, compressor: None }
+    }
+
+    pub fn format(&self) -> WireFormat {
+        self.format
+    }
+
+    pub fn set_format(&mut self, format: WireFormat) {
+        self.format = format;
+    }
+
+    /// 当前连接使用的压缩算法
+    pub fn compression(&self) -> Compression {
+        self.compressor.as_ref().map(|c| c.compression()).unwrap_or_default()
+    }
+
+    pub fn set_compressor(&mut self, compressor: Option<FrameCompressor>) {
+        self.compressor = compressor;
+    }
+}
+
+impl Decoder for RpcCodec {
+    type Item = RpcMessage;
+    type Error = RpcFrameworkError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        match self.format.take_frame(src)? {
+            Some(frame) => Ok(Some(self.format.decode_compressed(&frame, self.compressor.as_ref())?)),
+            None => Ok(None),
+        }
+    }
+}
+
+impl Encoder<ServerMessage> for RpcCodec {
+    type Error = RpcFrameworkError;
+
+    fn encode(&mut self, item: ServerMessage, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let mut payload = self.format.encode(&item)?;
+        if let Some(compressor) = &self.compressor {
+            payload = compressor.compress(&payload)?;
+        }
+        self.format.put_frame(&payload, dst)
+    }
+}
+
+/// `serde_json::Value` 与 MessagePack 之间的转换
+pub mod msgpack {
+    use serde_json::{Map, Number, Value};
+
+    /// 编码为 MessagePack
+    pub fn to_vec(value: &Value) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_value(value, &mut out);
+        out
+    }
+
+    /// 从 MessagePack 解码，要求输入恰好包含一个值
+    pub fn from_slice(bytes: &[u8]) -> Result<Value, String> {
+        let mut reader = Reader { bytes, pos: 0 };
+        let value = reader.read_value(0)?;
+        if reader.pos != bytes.len() {
+            return Err(format!("{} trailing bytes after MessagePack value", bytes.len() - reader.pos));
+        }
+        Ok(value)
+    }
+
+    fn write_value(value: &Value, out: &mut Vec<u8>) {
+        match value {
+            Value::Null => out.push(0xc0),
+            Value::Bool(false) => out.push(0xc2),
+            Value::Bool(true) => out.push(0xc3),
+            Value::Number(n) => write_number(n, out),
+            Value::String(s) => write_str(s, out),
+            Value::Array(items) => {
+                write_len(items.len(), 0x90, 0xdc, 0xdd, out);
+                for item in items {
+                    write_value(item, out);
+                }
+            }
+            Value::Object(map) => {
+                write_len(map.len(), 0x80, 0xde, 0xdf, out);
+                for (key, item) in map {
+                    write_str(key, out);
+                    write_value(item, out);
+                }
+            }
+        }
+    }
+
+    fn write_number(n: &Number, out: &mut Vec<u8>) {
+        if let Some(u) = n.as_u64() {
+            match u {
+                0..=0x7f => out.push(u as u8),
+                0x80..=0xff => out.extend([0xcc, u as u8]),
+                0x100..=0xffff => {
+                    out.push(0xcd);
+                    out.extend((u as u16).to_be_bytes());
+                }
+                0x1_0000..=0xffff_ffff => {
+                    out.push(0xce);
+                    out.extend((u as u32).to_be_bytes());
+                }
+                _ => {
+                    out.push(0xcf);
+                    out.extend(u.to_be_bytes());
+                }
+            }
+        } else if let Some(i) = n.as_i64() {
+            // 此处 i 必为负数
+            if i >= -32 {
+                out.push(i as i8 as u8);
+            } else if i >= i8::MIN as i64 {
+                out.extend([0xd0, i as i8 as u8]);
+            } else if i >= i16::MIN as i64 {
+                out.push(0xd1);
+                out.extend((i as i16).to_be_bytes());
+            } else if i >= i32::MIN as i64 {
+                out.push(0xd2);
+                out.extend((i as i32).to_be_bytes());
+            } else {
+                out.push(0xd3);
+                out.extend(i.to_be_bytes());
+            }
+        } else {
+            out.push(0xcb);
+            out.extend(n.as_f64().unwrap_or_default().to_be_bytes());
+        }
+    }
+
+    fn write_str(s: &str, out: &mut Vec<u8>) {
+        let len = s.len();
+        if len < 32 {
+            out.push(0xa0 | len as u8);
+        } else if len <= 0xff {
+            out.extend([0xd9, len as u8]);
+        } else if len <= 0xffff {
+            out.push(0xda);
+            out.extend((len as u16).to_be_bytes());
+        } else {
+            out.push(0xdb);
+            out.extend((len as u32).to_be_bytes());
+        }
+        out.extend(s.as_bytes());
+    }
+
+    fn write_len(len: usize, fix: u8, marker16: u8, marker32: u8, out: &mut Vec<u8>) {
+        if len < 16 {
+            out.push(fix | len as u8);
+        } else if len <= 0xffff {
+            out.push(marker16);
+            out.extend((len as u16).to_be_bytes());
+        } else {
+            out.push(marker32);
+            out.extend((len as u32).to_be_bytes());
+        }
+    }
+
+    const MAX_DEPTH: usize = 128;
+
+    struct Reader<'a> {
+        bytes: &'a [u8],
+        pos: usize,
+    }
+
+    impl<'a> Reader<'a> {
+        fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+            let end = self.pos.checked_add(n).filter(|end| *end <= self.bytes.len())
+                .ok_or_else(|| "Unexpected end of MessagePack data".to_string())?;
+            let slice = &self.bytes[self.pos..end];
+            self.pos = end;
+            Ok(slice)
+        }
+
+        fn read_array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+            let mut buf = [0u8; N];
+            buf.copy_from_slice(self.take(N)?);
+            Ok(buf)
+        }
+
+        fn read_len(&mut self, bytes: usize) -> Result<usize, String> {
+            Ok(match bytes {
+                1 => self.read_array::<1>()?[0] as usize,
+                2 => u16::from_be_bytes(self.read_array()?) as usize,
+                _ => u32::from_be_bytes(self.read_array()?) as usize,
+            })
+        }
+
+        fn read_str(&mut self, len: usize) -> Result<Value, String> {
+            let bytes = self.take(len)?;
+            std::str::from_utf8(bytes)
+                .map(|s| Value::String(s.to_string()))
+                .map_err(|e| format!("Invalid UTF-8 in MessagePack string: {}", e))
+        }
+
+        fn read_bin(&mut self, len: usize) -> Result<Value, String> {
+            Ok(Value::Array(self.take(len)?.iter().map(|b| Value::from(*b)).collect()))
+        }
+
+        fn read_seq(&mut self, len: usize, depth: usize) -> Result<Value, String> {
+            let mut items = Vec::with_capacity(len.min(1024));
+            for _ in 0..len {
+                items.push(self.read_value(depth + 1)?);
+            }
+            Ok(Value::Array(items))
+        }
+
+        fn read_map(&mut self, len: usize, depth: usize) -> Result<Value, String> {
+            let mut map = Map::new();
+            for _ in 0..len {
+                let key = match self.read_value(depth + 1)? {
+                    Value::String(key) => key,
+                    other => return Err(format!("Unsupported MessagePack map key: {}", other)),
+                };
+                map.insert(key, self.read_value(depth + 1)?);
+            }
+            Ok(Value::Object(map))
+        }
+
+        fn read_value(&mut self, depth: usize) -> Result<Value, String> {
+            if depth > MAX_DEPTH {
+                return Err("MessagePack value nested too deeply".to_string());
+            }
+
+            let marker = self.read_array::<1>()?[0];
+            match marker {
+                0x00..=0x7f => Ok(Value::from(marker)),
+                0x80..=0x8f => self.read_map((marker & 0x0f) as usize, depth),
+                0x90..=0x9f => self.read_seq((marker & 0x0f) as usize, depth),
+                0xa0..=0xbf => self.read_str((marker & 0x1f) as usize),
+                0xc0 => Ok(Value::Null),
+                0xc2 => Ok(Value::Bool(false)),
+                0xc3 => Ok(Value::Bool(true)),
+                0xc4..=0xc6 => {
+                    let len = self.read_len(1 << (marker - 0xc4))?;
+                    self.read_bin(len)
+                }
+                0xca => Ok(float(f32::from_be_bytes(self.read_array()?) as f64)),
+                0xcb => Ok(float(f64::from_be_bytes(self.read_array()?))),
+                0xcc => Ok(Value::from(self.read_array::<1>()?[0])),
+                0xcd => Ok(Value::from(u16::from_be_bytes(self.read_array()?))),
+                0xce => Ok(Value::from(u32::from_be_bytes(self.read_array()?))),
+                0xcf => Ok(Value::from(u64::from_be_bytes(self.read_array()?))),
+                0xd0 => Ok(Value::from(self.read_array::<1>()?[0] as i8)),
+                0xd1 => Ok(Value::from(i16::from_be_bytes(self.read_array()?))),
+                0xd2 => Ok(Value::from(i32::from_be_bytes(self.read_array()?))),
+                0xd3 => Ok(Value::from(i64::from_be_bytes(self.read_array()?))),
+                0xd9..=0xdb => {
+                    let len = self.read_len(1 << (marker - 0xd9))?;
+                    self.read_str(len)
+                }
+                0xdc | 0xdd => {
+                    let len = self.read_len(2 << (marker - 0xdc))?;
+                    self.read_seq(len, depth)
+                }
+                0xde | 0xdf => {
+                    let len = self.read_len(2 << (marker - 0xde))?;
+                    self.read_map(len, depth)
+                }
+                0xe0..=0xff => Ok(Value::from(marker as i8)),
+                other => Err(format!("Unsupported MessagePack marker 0x{:02x}", other)),
+            }
+        }
+    }
+
+    fn float(f: f64) -> Value {
+        Number::from_f64(f).map(Value::Number).unwrap_or(Value::Null)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{RpcEvent, RpcRequest, RpcResponse, RpcResponseMessage};
+    use serde_json::json;
+
+    #[test]
+    fn test_msgpack_round_trip() {
+        let value = json!({
+            "null": null,
+            "flags": [true, false],
+            "ints": [0, 127, 128, 255, 65535, 65536, 4294967296u64, u64::MAX, -1, -32, -33, -128, -129, -32769, i64::MIN],
+            "float": 1.5,
+            "text": "x".repeat(40),
+            "long": "y".repeat(70000),
+            "nested": { "list": (0..20).collect::<Vec<_>>() }
+        });
+
+        let encoded = msgpack::to_vec(&value);
+        assert_eq!(msgpack::from_slice(&encoded).unwrap(), value);
+        assert!(encoded.len() < serde_json::to_vec(&value).unwrap().len());
+
+        assert!(msgpack::from_slice(&encoded[..encoded.len() - 1]).is_err());
+        assert!(msgpack::from_slice(&[0xc1]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_client_negotiates_msgpack() {
+        use crate::client::{ClientConfig, RpcClient};
+        use crate::protocol::FunctionHandler;
+        use crate::server::{RpcServer, ServerConfig};
+        use std::sync::Arc;
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        let server = Arc::new(RpcServer::new(ServerConfig { bind_addr: addr, ..ServerConfig::default() }));
+        server.register_handler(Arc::new(FunctionHandler::new("echo".to_string(), |params| async move {
+            Ok(params.unwrap_or_default())
+        })));
+        tokio::spawn(server.serve());
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+        let config = ClientConfig {
+            enable_heartbeat: false,
+            wire_formats: vec![WireFormat::MsgPack, WireFormat::Json],
+            ..ClientConfig::default()
+        };
+        let client = RpcClient::new(addr, config);
+        client.connect().await.unwrap();
+        assert_eq!(client.wire_format().await, WireFormat::MsgPack);
+
+        let payload = json!({"items": [1, -2, 3.5, "four"], "nested": {"ok": true}});
+        assert_eq!(client.send_request("echo", payload.clone()).await.unwrap(), payload);
+
+        // 未协商的客户端继续使用 JSON
+        let plain = RpcClient::new(addr, ClientConfig { enable_heartbeat: false, ..ClientConfig::default() });
+        plain.connect().await.unwrap();
+        assert_eq!(plain.wire_format().await, WireFormat::Json);
+        assert_eq!(plain.send_request("echo", payload.clone()).await.unwrap(), payload);
+
+        client.disconnect().await.unwrap();
+        plain.disconnect().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_client_negotiates_compression() {
+        use crate::client::{ClientConfig, RpcClient};
+        use crate::compression::{Compression, CompressionConfig};
+        use crate::protocol::FunctionHandler;
+        use crate::server::{RpcServer, ServerConfig};
+        use std::sync::Arc;
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        let server = Arc::new(RpcServer::new(ServerConfig { bind_addr: addr, ..ServerConfig::default() }));
+        server.register_handler(Arc::new(FunctionHandler::new("echo".to_string(), |params| async move {
+            Ok(params.unwrap_or_default())
+        })));
+        tokio::spawn(server.clone().serve());
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+        let config = ClientConfig {
+            enable_heartbeat: false,
+            wire_formats: vec![WireFormat::MsgPack],
+            compression: CompressionConfig { algorithms: vec![Compression::Gzip], ..CompressionConfig::default() },
+            ..ClientConfig::default()
+        };
+        let client = RpcClient::new(addr, config);
+        client.connect().await.unwrap();
+        assert_eq!(client.compression().await, Compression::Gzip);
+
+        let logs = json!({"lines": vec!["step finished with exit code 0"; 500]});
+        assert_eq!(client.send_request("echo", logs.clone()).await.unwrap(), logs);
+        assert_eq!(client.send_request("echo", json!({"small": 1})).await.unwrap(), json!({"small": 1}));
+
+        let client_stats = client.compression_stats();
+        assert_eq!(client_stats.messages_compressed, 1);
+        assert_eq!(client_stats.messages_uncompressed, 1);
+        assert_eq!(client_stats.messages_decompressed, 1);
+        let server_stats = server.compression_stats();
+        assert_eq!(server_stats.messages_compressed, 1);
+        assert!(server_stats.ratio() > 10.0);
+
+        // JSON 连接不压缩
+        let plain = RpcClient::new(
+            addr,
+            ClientConfig { enable_heartbeat: false, compression: CompressionConfig::default(), ..ClientConfig::default() },
+        );
+        plain.connect().await.unwrap();
+        assert_eq!(plain.compression().await, Compression::None);
+        assert_eq!(plain.send_request("echo", logs.clone()).await.unwrap(), logs);
+
+        client.disconnect().await.unwrap();
+        plain.disconnect().await.unwrap();
+    }
+
+    #[test]
+    fn test_negotiate_falls_back_to_json() {
+        let supported = [WireFormat::MsgPack, WireFormat::Json];
+        assert_eq!(WireFormat::negotiate(&[WireFormat::MsgPack, WireFormat::Json], &supported), WireFormat::MsgPack);
+        assert_eq!(WireFormat::negotiate(&[WireFormat::MsgPack], &[WireFormat::Json]), WireFormat::Json);
+        assert_eq!(WireFormat::negotiate(&[], &supported), WireFormat::Json);
+    }
+
+    #[test]
+    fn test_codec_switches_format() {
+        let mut codec = RpcCodec::default();
+        let mut buf = BytesMut::new();
+        let request = RpcMessage::Single(RpcRequest::new("a".to_string(), None));
+        buf.extend(WireFormat::Json.encode_frame(&request).unwrap());
+
+        codec.set_format(WireFormat::MsgPack);
+        assert!(codec.decode(&mut buf).is_err());
+
+        let mut codec = RpcCodec::new(WireFormat::MsgPack);
+        let frame = WireFormat::MsgPack.encode_frame(&request).unwrap();
+        let mut buf = BytesMut::from(&frame[..frame.len() - 1]);
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+        buf.extend_from_slice(&frame[frame.len() - 1..]);
+        let Some(RpcMessage::Single(decoded)) = codec.decode(&mut buf).unwrap() else {
+            panic!("expected single request");
+        };
+        assert_eq!(decoded.method, "a");
+
+        let mut out = BytesMut::new();
+        let event = ServerMessage::Event(RpcEvent::new("e".to_string(), json!({"n": 1})));
+        codec.encode(event, &mut out).unwrap();
+        let response = ServerMessage::Response(RpcResponseMessage::Single(RpcResponse::success(json!("1"), json!(2))));
+        codec.encode(response, &mut out).unwrap();
+
+        let first = codec.format().take_frame(&mut out).unwrap().unwrap();
+        let decoded: ServerMessage = WireFormat::MsgPack.decode(&first).unwrap();
+        assert!(matches!(decoded, ServerMessage::Event(_)));
+        assert!(codec.format().take_frame(&mut out).unwrap().is_some());
+    }
+}
@@ -0,0 +1,260 @@
+//! 按消息压缩
+//!
+//! 客户端在 `rpc.negotiate` 中按优先级提供压缩算法，服务端选择双方都支持的第一个。
+//! 压缩只在长度前缀分帧（MessagePack）下启用，因为压缩后的数据可能包含换行符。
+//!
+//! 协商成功后每帧消息体前增加 1 字节标志：`0` 表示未压缩，`1` 表示已用协商的算法压缩。
+//! 发送端只压缩不小于阈值且压缩后确实变小的消息，因此小消息不会产生额外开销。
+//! 压缩发生在编解码层，处理器代码不受影响。
+
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::codec::MAX_FRAME_SIZE;
+use crate::error::{RpcFrameworkError, RpcResult};
+
+const FLAG_RAW: u8 = 0;
+const FLAG_COMPRESSED: u8 = 1;
+
+/// 压缩算法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    #[default]
+    None,
+    Zstd,
+    Gzip,
+}
+
+impl Compression {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Compression::None => "none",
+            Compression::Zstd => "zstd",
+            Compression::Gzip => "gzip",
+        }
+    }
+
+    /// 按客户端偏好选择双方都支持的第一个算法，没有时不压缩
+    pub fn negotiate(offered: &[Compression], supported: &[Compression]) -> Compression {
+        offered
+            .iter()
+            .find(|c| **c != Compression::None && supported.contains(c))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn compress(&self, payload: &[u8], level: i32) -> RpcResult<Vec<u8>> {
+        match self {
+            Compression::None => Ok(payload.to_vec()),
+            Compression::Zstd => zstd::bulk::compress(payload, level).map_err(Into::into),
+            Compression::Gzip => {
+                let level = flate2::Compression::new(level.clamp(0, 9) as u32);
+                let mut encoder = flate2::write::GzEncoder::new(Vec::new(), level);
+                encoder.write_all(payload)?;
+                Ok(encoder.finish()?)
+            }
+        }
+    }
+
+    /// 解压，解压后的大小受帧大小上限约束
+    fn decompress(&self, payload: &[u8]) -> RpcResult<Vec<u8>> {
+        let decompressed = match self {
+            Compression::None => payload.to_vec(),
+            Compression::Zstd => zstd::bulk::decompress(payload, MAX_FRAME_SIZE)?,
+            Compression::Gzip => {
+                let mut out = Vec::new();
+                flate2::read::GzDecoder::new(payload)
+                    .take(MAX_FRAME_SIZE as u64 + 1)
+                    .read_to_end(&mut out)?;
+                out
+            }
+        };
+        if decompressed.len() > MAX_FRAME_SIZE {
+            return Err(RpcFrameworkError::ConnectionError(format!(
+                "Decompressed frame exceeds {} bytes",
+                MAX_FRAME_SIZE
+            )));
+        }
+        Ok(decompressed)
+    }
+}
+
+/// 压缩配置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompressionConfig {
+    /// 按偏好排序的算法；客户端为空时不协商压缩
+    pub algorithms: Vec<Compression>,
+    /// 小于该字节数的消息不压缩
+    pub threshold_bytes: usize,
+    /// 压缩级别，gzip 取值会被限制在 0..=9
+    pub level: i32,
+}
+
+impl CompressionConfig {
+    /// 不启用压缩
+    pub fn disabled() -> Self {
+        Self { algorithms: Vec::new(), ..Self::default() }
+    }
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            algorithms: vec![Compression::Zstd, Compression::Gzip],
+            threshold_bytes: 1024,
+            level: 3,
+        }
+    }
+}
+
+/// 压缩指标快照
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CompressionStats {
+    /// 发送时被压缩的消息数
+    pub messages_compressed: u64,
+    /// 发送时因小于阈值或压缩无收益而未压缩的消息数
+    pub messages_uncompressed: u64,
+    /// 被压缩消息的原始字节数
+    pub bytes_before: u64,
+    /// 被压缩消息压缩后的字节数
+    pub bytes_after: u64,
+    /// 收到并解压的消息数
+    pub messages_decompressed: u64,
+}
+
+impl CompressionStats {
+    /// 压缩比（原始字节 / 压缩后字节），尚未压缩任何消息时为 1.0
+    pub fn ratio(&self) -> f64 {
+        if self.bytes_after == 0 {
+            1.0
+        } else {
+            self.bytes_before as f64 / self.bytes_after as f64
+        }
+    }
+}
+
+/// 在连接间共享的压缩计数器
+#[derive(Debug, Default)]
+pub struct CompressionMetrics {
+    messages_compressed: AtomicU64,
+    messages_uncompressed: AtomicU64,
+    bytes_before: AtomicU64,
+    bytes_after: AtomicU64,
+    messages_decompressed: AtomicU64,
+}
+
+impl CompressionMetrics {
+    pub fn snapshot(&self) -> CompressionStats {
+        CompressionStats {
+            messages_compressed: self.messages_compressed.load(Ordering::Relaxed),
+            messages_uncompressed: self.messages_uncompressed.load(Ordering::Relaxed),
+            bytes_before: self.bytes_before.load(Ordering::Relaxed),
+            bytes_after: self.bytes_after.load(Ordering::Relaxed),
+            messages_decompressed: self.messages_decompressed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// 一条连接上协商好的帧压缩
+#[derive(Debug, Clone)]
+pub struct FrameCompressor {
+    compression: Compression,
+    threshold_bytes: usize,
+    level: i32,
+    metrics: Arc<CompressionMetrics>,
+}
+
+impl FrameCompressor {
+    pub fn new(compression: Compression, config: &CompressionConfig, metrics: Arc<CompressionMetrics>) -> Self {
+        Self {
+            compression,
+            threshold_bytes: config.threshold_bytes,
+            level: config.level,
+            metrics,
+        }
+    }
+
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+
+    /// 为消息体加上压缩标志，必要时压缩
+    pub fn compress(&self, payload: &[u8]) -> RpcResult<Vec<u8>> {
+        if payload.len() >= self.threshold_bytes && self.compression != Compression::None {
+            let compressed = self.compression.compress(payload, self.level)?;
+            if compressed.len() < payload.len() {
+                self.metrics.messages_compressed.fetch_add(1, Ordering::Relaxed);
+                self.metrics.bytes_before.fetch_add(payload.len() as u64, Ordering::Relaxed);
+                self.metrics.bytes_after.fetch_add(compressed.len() as u64 + 1, Ordering::Relaxed);
+                let mut out = Vec::with_capacity(compressed.len() + 1);
+                out.push(FLAG_COMPRESSED);
+                out.extend_from_slice(&compressed);
+                return Ok(out);
+            }
+        }
+        self.metrics.messages_uncompressed.fetch_add(1, Ordering::Relaxed);
+        let mut out = Vec::with_capacity(payload.len() + 1);
+        out.push(FLAG_RAW);
+        out.extend_from_slice(payload);
+        Ok(out)
+    }
+
+    /// 去掉压缩标志，必要时解压
+    pub fn decompress(&self, frame: &[u8]) -> RpcResult<Vec<u8>> {
+        match frame.split_first() {
+            Some((&FLAG_RAW, payload)) => Ok(payload.to_vec()),
+            Some((&FLAG_COMPRESSED, payload)) => {
+                let decompressed = self.compression.decompress(payload)?;
+                self.metrics.messages_decompressed.fetch_add(1, Ordering::Relaxed);
+                Ok(decompressed)
+            }
+            Some((flag, _)) => Err(RpcFrameworkError::ConnectionError(format!("Unknown compression flag {}", flag))),
+            None => Err(RpcFrameworkError::ConnectionError("Empty compressed frame".to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate_compression() {
+        let supported = [Compression::Zstd, Compression::Gzip];
+        assert_eq!(Compression::negotiate(&[Compression::Gzip, Compression::Zstd], &supported), Compression::Gzip);
+        assert_eq!(Compression::negotiate(&[Compression::Zstd], &[Compression::Gzip]), Compression::None);
+        assert_eq!(Compression::negotiate(&[], &supported), Compression::None);
+    }
+
+    #[test]
+    fn test_frame_compression_round_trip() {
+        for compression in [Compression::Zstd, Compression::Gzip] {
+            let metrics = Arc::new(CompressionMetrics::default());
+            let compressor = FrameCompressor::new(compression, &CompressionConfig::default(), metrics.clone());
+
+            let large = "log line\n".repeat(1000).into_bytes();
+            let frame = compressor.compress(&large).unwrap();
+            assert_eq!(frame[0], FLAG_COMPRESSED);
+            assert!(frame.len() < large.len() / 10);
+            assert_eq!(compressor.decompress(&frame).unwrap(), large);
+
+            // 小于阈值的消息原样发送
+            let small = b"{\"ok\":true}".to_vec();
+            let frame = compressor.compress(&small).unwrap();
+            assert_eq!(frame[0], FLAG_RAW);
+            assert_eq!(compressor.decompress(&frame).unwrap(), small);
+
+            let stats = metrics.snapshot();
+            assert_eq!(stats.messages_compressed, 1);
+            assert_eq!(stats.messages_uncompressed, 1);
+            assert_eq!(stats.messages_decompressed, 1);
+            assert!(stats.ratio() > 10.0);
+
+            assert!(compressor.decompress(&[7, 1, 2]).is_err());
+        }
+    }
+}
@@ -0,0 +1,663 @@
+//! 持久化事件日志与可恢复订阅
+//!
+//! 事件写入日志时分配单调递增的偏移量（offset）。持久订阅记录已确认（ack）的
+//! 偏移量，客户端断线重连后可从上次确认处继续拉取，不会丢失中间的事件。
+//! 日志按保留策略清理旧事件；可选地保留尚未被所有订阅确认的事件。
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{broadcast, Mutex};
+use tracing::{debug, info};
+
+use crate::error::{RpcError, RpcFrameworkError, RpcResult};
+use crate::protocol::{RpcEvent, RpcHandler};
+use crate::subscription_manager::EventFilter;
+
+/// 已持久化的事件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredEvent {
+    pub offset: u64,
+    pub stored_at: DateTime<Utc>,
+    pub event: RpcEvent,
+}
+
+/// 持久订阅及其确认位置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DurableSubscription {
+    pub id: String,
+    pub event_pattern: String,
+    /// 最后确认的偏移量，0 表示尚未确认任何事件
+    pub acked_offset: u64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// 拉取结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FetchResult {
+    pub events: Vec<StoredEvent>,
+    /// 日志中最新的偏移量
+    pub head_offset: u64,
+    /// 未确认的事件已被保留策略清理
+    pub truncated: bool,
+}
+
+/// 保留策略
+#[derive(Debug, Clone)]
+pub struct RetentionPolicy {
+    pub max_events: Option<usize>,
+    pub max_age: Option<Duration>,
+    /// 保留尚未被全部订阅确认的事件（优先于上面两项）
+    pub retain_unacked: bool,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            max_events: Some(100_000),
+            max_age: Some(Duration::from_secs(7 * 24 * 3600)),
+            retain_unacked: false,
+        }
+    }
+}
+
+/// 事件存储后端
+#[async_trait::async_trait]
+pub trait EventStore: Send + Sync {
+    async fn append(&self, event: &StoredEvent) -> RpcResult<()>;
+
+    /// 读取偏移量大于 `after` 的事件
+    async fn read_after(&self, after: u64, limit: usize) -> RpcResult<Vec<StoredEvent>>;
+
+    /// 最早与最新的偏移量，日志为空时返回 None
+    async fn bounds(&self) -> RpcResult<Option<(u64, u64)>>;
+
+    /// 删除偏移量小于 `offset` 的事件，返回删除数量
+    async fn truncate_before(&self, offset: u64) -> RpcResult<usize>;
+
+    /// 第一个写入时间不早于 `cutoff` 的事件偏移量
+    async fn first_offset_since(&self, cutoff: DateTime<Utc>) -> RpcResult<Option<u64>>;
+
+    async fn save_subscription(&self, subscription: &DurableSubscription) -> RpcResult<()>;
+
+    async fn load_subscriptions(&self) -> RpcResult<Vec<DurableSubscription>>;
+
+    async fn delete_subscription(&self, id: &str) -> RpcResult<()>;
+}
+
+/// 内存事件存储，进程内可恢复，适合测试与单机部署
+#[derive(Default)]
+pub struct InMemoryEventStore {
+    events: Mutex<Vec<StoredEvent>>,
+    subscriptions: DashMap<String, DurableSubscription>,
+}
+
+impl InMemoryEventStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl EventStore for InMemoryEventStore {
+    async fn append(&self, event: &StoredEvent) -> RpcResult<()> {
+        self.events.lock().await.push(event.clone());
+        Ok(())
+    }
+
+    async fn read_after(&self, after: u64, limit: usize) -> RpcResult<Vec<StoredEvent>> {
+        Ok(read_after(&self.events.lock().await, after, limit))
+    }
+
+    async fn bounds(&self) -> RpcResult<Option<(u64, u64)>> {
+        Ok(bounds(&self.events.lock().await))
+    }
+
+    async fn truncate_before(&self, offset: u64) -> RpcResult<usize> {
+        let mut events = self.events.lock().await;
+        let before = events.len();
+        events.retain(|e| e.offset >= offset);
+        Ok(before - events.len())
+    }
+
+    async fn first_offset_since(&self, cutoff: DateTime<Utc>) -> RpcResult<Option<u64>> {
+        Ok(first_offset_since(&self.events.lock().await, cutoff))
+    }
+
+    async fn save_subscription(&self, subscription: &DurableSubscription) -> RpcResult<()> {
+        self.subscriptions.insert(subscription.id.clone(), subscription.clone());
+        Ok(())
+    }
+
+    async fn load_subscriptions(&self) -> RpcResult<Vec<DurableSubscription>> {
+        Ok(self.subscriptions.iter().map(|e| e.value().clone()).collect())
+    }
+
+    async fn delete_subscription(&self, id: &str) -> RpcResult<()> {
+        self.subscriptions.remove(id);
+        Ok(())
+    }
+}
+
+/// 文件事件存储
+///
+/// 事件以 JSON Lines 追加写入 `events.jsonl`，订阅写入 `subscriptions.json`。
+/// 清理旧事件时整体重写事件文件。
+pub struct FileEventStore {
+    events_path: PathBuf,
+    subscriptions_path: PathBuf,
+    events: Mutex<Vec<StoredEvent>>,
+    subscriptions: Mutex<Vec<DurableSubscription>>,
+}
+
+impl FileEventStore {
+    /// 打开（或创建）目录下的事件存储
+    pub async fn open(dir: impl AsRef<Path>) -> RpcResult<Self> {
+        let dir = dir.as_ref();
+        tokio::fs::create_dir_all(dir).await?;
+        let events_path = dir.join("events.jsonl");
+        let subscriptions_path = dir.join("subscriptions.json");
+
+        let events = match tokio::fs::read_to_string(&events_path).await {
+            Ok(content) => content
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(serde_json::from_str)
+                .collect::<Result<Vec<StoredEvent>, _>>()?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let subscriptions = match tokio::fs::read(&subscriptions_path).await {
+            Ok(content) => serde_json::from_slice(&content)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Self {
+            events_path,
+            subscriptions_path,
+            events: Mutex::new(events),
+            subscriptions: Mutex::new(subscriptions),
+        })
+    }
+
+    async fn write_subscriptions(&self, subscriptions: &[DurableSubscription]) -> RpcResult<()> {
+        let tmp = self.subscriptions_path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, serde_json::to_vec(subscriptions)?).await?;
+        tokio::fs::rename(&tmp, &self.subscriptions_path).await?;
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl EventStore for FileEventStore {
+    async fn append(&self, event: &StoredEvent) -> RpcResult<()> {
+        let mut events = self.events.lock().await;
+        let mut line = serde_json::to_vec(event)?;
+        line.push(b'\n');
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.events_path)
+            .await?;
+        file.write_all(&line).await?;
+        file.sync_data().await?;
+
+        events.push(event.clone());
+        Ok(())
+    }
+
+    async fn read_after(&self, after: u64, limit: usize) -> RpcResult<Vec<StoredEvent>> {
+        Ok(read_after(&self.events.lock().await, after, limit))
+    }
+
+    async fn bounds(&self) -> RpcResult<Option<(u64, u64)>> {
+        Ok(bounds(&self.events.lock().await))
+    }
+
+    async fn truncate_before(&self, offset: u64) -> RpcResult<usize> {
+        let mut events = self.events.lock().await;
+        let before = events.len();
+        events.retain(|e| e.offset >= offset);
+        let removed = before - events.len();
+
+        if removed > 0 {
+            let mut content = Vec::new();
+            for event in events.iter() {
+                content.extend(serde_json::to_vec(event)?);
+                content.push(b'\n');
+            }
+            let tmp = self.events_path.with_extension("jsonl.tmp");
+            tokio::fs::write(&tmp, content).await?;
+            tokio::fs::rename(&tmp, &self.events_path).await?;
+        }
+        Ok(removed)
+    }
+
+    async fn first_offset_since(&self, cutoff: DateTime<Utc>) -> RpcResult<Option<u64>> {
+        Ok(first_offset_since(&self.events.lock().await, cutoff))
+    }
+
+    async fn save_subscription(&self, subscription: &DurableSubscription) -> RpcResult<()> {
+        let mut subscriptions = self.subscriptions.lock().await;
+        match subscriptions.iter_mut().find(|s| s.id == subscription.id) {
+            Some(existing) => *existing = subscription.clone(),
+            None => subscriptions.push(subscription.clone()),
+        }
+        self.write_subscriptions(&subscriptions).await
+    }
+
+    async fn load_subscriptions(&self) -> RpcResult<Vec<DurableSubscription>> {
+        Ok(self.subscriptions.lock().await.clone())
+    }
+
+    async fn delete_subscription(&self, id: &str) -> RpcResult<()> {
+        let mut subscriptions = self.subscriptions.lock().await;
+        subscriptions.retain(|s| s.id != id);
+        self.write_subscriptions(&subscriptions).await
+    }
+}
+
+fn read_after(events: &[StoredEvent], after: u64, limit: usize) -> Vec<StoredEvent> {
+    let start = events.partition_point(|e| e.offset <= after);
+    events[start..].iter().take(limit).cloned().collect()
+}
+
+fn bounds(events: &[StoredEvent]) -> Option<(u64, u64)> {
+    Some((events.first()?.offset, events.last()?.offset))
+}
+
+fn first_offset_since(events: &[StoredEvent], cutoff: DateTime<Utc>) -> Option<u64> {
+    events.iter().find(|e| e.stored_at >= cutoff).map(|e| e.offset)
+}
+
+/// 持久化事件日志
+pub struct DurableEventLog {
+    store: Arc<dyn EventStore>,
+    retention: RetentionPolicy,
+    next_offset: AtomicU64,
+    subscriptions: DashMap<String, DurableSubscription>,
+    notifier: broadcast::Sender<StoredEvent>,
+    append_lock: Mutex<()>,
+}
+
+impl DurableEventLog {
+    /// 基于存储打开日志，恢复偏移量与已有订阅
+    pub async fn open(store: Arc<dyn EventStore>, retention: RetentionPolicy) -> RpcResult<Self> {
+        let head = store.bounds().await?.map(|(_, last)| last).unwrap_or(0);
+        let subscriptions = DashMap::new();
+        for subscription in store.load_subscriptions().await? {
+            subscriptions.insert(subscription.id.clone(), subscription);
+        }
+        let (notifier, _) = broadcast::channel(1000);
+
+        info!("Opened durable event log at offset {} with {} subscription(s)", head, subscriptions.len());
+        Ok(Self {
+            store,
+            retention,
+            next_offset: AtomicU64::new(head + 1),
+            subscriptions,
+            notifier,
+            append_lock: Mutex::new(()),
+        })
+    }
+
+    /// 使用内存存储创建日志
+    pub async fn in_memory(retention: RetentionPolicy) -> RpcResult<Self> {
+        Self::open(Arc::new(InMemoryEventStore::new()), retention).await
+    }
+
+    /// 追加事件，返回分配的偏移量
+    pub async fn append(&self, event: RpcEvent) -> RpcResult<u64> {
+        let stored = {
+            let _guard = self.append_lock.lock().await;
+            let stored = StoredEvent {
+                offset: self.next_offset.load(Ordering::Acquire),
+                stored_at: Utc::now(),
+                event,
+            };
+            self.store.append(&stored).await?;
+            self.next_offset.fetch_add(1, Ordering::AcqRel);
+            stored
+        };
+
+        let offset = stored.offset;
+        let _ = self.notifier.send(stored);
+        self.apply_retention().await?;
+        Ok(offset)
+    }
+
+    /// 最新的偏移量
+    pub fn head_offset(&self) -> u64 {
+        self.next_offset.load(Ordering::Acquire) - 1
+    }
+
+    /// 创建持久订阅；同名订阅已存在时直接返回，用于断线后恢复
+    pub async fn subscribe(&self, id: &str, event_pattern: &str) -> RpcResult<DurableSubscription> {
+        if let Some(existing) = self.subscriptions.get(id) {
+            if existing.event_pattern != event_pattern {
+                return Err(RpcError::invalid_params(&format!(
+                    "Subscription '{}' already exists with pattern '{}'", id, existing.event_pattern
+                )).into());
+            }
+            return Ok(existing.clone());
+        }
+
+        let subscription = DurableSubscription {
+            id: id.to_string(),
+            event_pattern: event_pattern.to_string(),
+            acked_offset: 0,
+            created_at: Utc::now(),
+        };
+        self.store.save_subscription(&subscription).await?;
+        self.subscriptions.insert(subscription.id.clone(), subscription.clone());
+        Ok(subscription)
+    }
+
+    /// 删除持久订阅
+    pub async fn unsubscribe(&self, id: &str) -> RpcResult<bool> {
+        if self.subscriptions.remove(id).is_none() {
+            return Ok(false);
+        }
+        self.store.delete_subscription(id).await?;
+        Ok(true)
+    }
+
+    pub fn get_subscription(&self, id: &str) -> Option<DurableSubscription> {
+        self.subscriptions.get(id).map(|s| s.clone())
+    }
+
+    pub fn list_subscriptions(&self) -> Vec<DurableSubscription> {
+        self.subscriptions.iter().map(|s| s.value().clone()).collect()
+    }
+
+    /// 从订阅的确认位置之后拉取匹配的事件
+    pub async fn fetch(&self, id: &str, max_events: usize) -> RpcResult<FetchResult> {
+        let subscription = self.require_subscription(id)?;
+        self.replay(&subscription.event_pattern, subscription.acked_offset, max_events).await
+    }
+
+    /// 从任意偏移量之后回放匹配的事件
+    pub async fn replay(&self, event_pattern: &str, after: u64, max_events: usize) -> RpcResult<FetchResult> {
+        let filter = EventFilter::new(event_pattern.to_string());
+        let truncated = matches!(self.store.bounds().await?, Some((first, _)) if first > after + 1);
+
+        let mut events = Vec::new();
+        let mut cursor = after;
+        while events.len() < max_events {
+            let batch = self.store.read_after(cursor, max_events.max(64)).await?;
+            let Some(last) = batch.last() else { break };
+            cursor = last.offset;
+            events.extend(
+                batch
+                    .into_iter()
+                    .filter(|e| filter.matches(&e.event))
+                    .take(max_events - events.len()),
+            );
+        }
+
+        Ok(FetchResult {
+            events,
+            head_offset: self.head_offset(),
+            truncated,
+        })
+    }
+
+    /// 确认已处理到 `offset`（含）
+    pub async fn ack(&self, id: &str, offset: u64) -> RpcResult<()> {
+        let mut subscription = self.require_subscription(id)?;
+        if offset > self.head_offset() {
+            return Err(RpcError::invalid_params(&format!("Offset {} is beyond log head {}", offset, self.head_offset())).into());
+        }
+        if offset <= subscription.acked_offset {
+            return Ok(());
+        }
+
+        subscription.acked_offset = offset;
+        self.store.save_subscription(&subscription).await?;
+        self.subscriptions.insert(id.to_string(), subscription);
+        debug!("Subscription {} acked offset {}", id, offset);
+        Ok(())
+    }
+
+    /// 订阅实时追加的事件
+    pub fn watch(&self) -> broadcast::Receiver<StoredEvent> {
+        self.notifier.subscribe()
+    }
+
+    /// 按保留策略清理旧事件，返回清理数量
+    pub async fn apply_retention(&self) -> RpcResult<usize> {
+        let head = self.head_offset();
+        let mut keep_from = 0;
+
+        if let Some(max_events) = self.retention.max_events {
+            keep_from = keep_from.max((head + 1).saturating_sub(max_events as u64));
+        }
+        if let Some(max_age) = self.retention.max_age.and_then(|age| chrono::Duration::from_std(age).ok()) {
+            let cutoff = Utc::now() - max_age;
+            let since = self.store.first_offset_since(cutoff).await?.unwrap_or(head + 1);
+            keep_from = keep_from.max(since);
+        }
+        if self.retention.retain_unacked {
+            if let Some(min_acked) = self.subscriptions.iter().map(|s| s.acked_offset).min() {
+                keep_from = keep_from.min(min_acked + 1);
+            }
+        }
+
+        // 始终保留最新事件，重新打开日志时据此恢复偏移量
+        keep_from = keep_from.min(head);
+
+        match self.store.bounds().await? {
+            Some((first, _)) if keep_from > first => self.store.truncate_before(keep_from).await,
+            _ => Ok(0),
+        }
+    }
+
+    fn require_subscription(&self, id: &str) -> RpcResult<DurableSubscription> {
+        self.get_subscription(id)
+            .ok_or_else(|| RpcError::invalid_params(&format!("Unknown durable subscription '{}'", id)).into())
+    }
+}
+
+/// 将持久订阅暴露为 RPC 方法
+///
+/// - `events.subscribe_durable` `{id, pattern}`
+/// - `events.fetch` `{id, max?}`
+/// - `events.ack` `{id, offset}`
+/// - `events.replay` `{pattern, after, max?}`
+/// - `events.unsubscribe_durable` `{id}`
+pub struct DurableEventHandler {
+    log: Arc<DurableEventLog>,
+}
+
+impl DurableEventHandler {
+    const DEFAULT_FETCH: usize = 100;
+
+    pub fn new(log: Arc<DurableEventLog>) -> Self {
+        Self { log }
+    }
+
+    async fn dispatch(&self, method: &str, params: &Value) -> RpcResult<Value> {
+        let max = params.get("max").and_then(Value::as_u64).map(|m| m as usize).unwrap_or(Self::DEFAULT_FETCH);
+
+        match method {
+            "events.subscribe_durable" => {
+                let subscription = self.log.subscribe(str_param(params, "id")?, str_param(params, "pattern")?).await?;
+                Ok(serde_json::to_value(subscription)?)
+            }
+            "events.fetch" => Ok(serde_json::to_value(self.log.fetch(str_param(params, "id")?, max).await?)?),
+            "events.ack" => {
+                self.log.ack(str_param(params, "id")?, u64_param(params, "offset")?).await?;
+                Ok(serde_json::json!({ "acked": true }))
+            }
+            "events.replay" => {
+                let result = self.log.replay(str_param(params, "pattern")?, u64_param(params, "after")?, max).await?;
+                Ok(serde_json::to_value(result)?)
+            }
+            "events.unsubscribe_durable" => {
+                let removed = self.log.unsubscribe(str_param(params, "id")?).await?;
+                Ok(serde_json::json!({ "removed": removed }))
+            }
+            _ => Err(RpcError::method_not_found(method).into()),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl RpcHandler for DurableEventHandler {
+    async fn handle(&self, method: &str, params: Option<Value>) -> Result<Value, RpcError> {
+        let params = params.unwrap_or(Value::Null);
+        self.dispatch(method, &params).await.map_err(|e| match e {
+            RpcFrameworkError::RpcError(error) => error,
+            other => RpcError::internal_error(&other.to_string()),
+        })
+    }
+
+    fn methods(&self) -> Vec<String> {
+        [
+            "events.subscribe_durable",
+            "events.fetch",
+            "events.ack",
+            "events.replay",
+            "events.unsubscribe_durable",
+        ]
+        .iter()
+        .map(|m| m.to_string())
+        .collect()
+    }
+}
+
+fn str_param<'a>(params: &'a Value, name: &str) -> RpcResult<&'a str> {
+    params
+        .get(name)
+        .and_then(Value::as_str)
+        .ok_or_else(|| RpcError::invalid_params(&format!("Missing '{}'", name)).into())
+}
+
+fn u64_param(params: &Value, name: &str) -> RpcResult<u64> {
+    params
+        .get(name)
+        .and_then(Value::as_u64)
+        .ok_or_else(|| RpcError::invalid_params(&format!("Missing '{}'", name)).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn event(name: &str, n: i64) -> RpcEvent {
+        RpcEvent::new(name.to_string(), json!({ "n": n }))
+    }
+
+    #[tokio::test]
+    async fn test_resume_from_acked_offset() {
+        let store: Arc<dyn EventStore> = Arc::new(InMemoryEventStore::new());
+        let log = DurableEventLog::open(store.clone(), RetentionPolicy::default()).await.unwrap();
+
+        log.subscribe("consumer", "execution.*").await.unwrap();
+        for n in 1..=3 {
+            log.append(event("execution.completed", n)).await.unwrap();
+        }
+        log.append(event("tool.registered", 0)).await.unwrap();
+
+        let fetched = log.fetch("consumer", 2).await.unwrap();
+        assert_eq!(fetched.events.iter().map(|e| e.offset).collect::<Vec<_>>(), vec![1, 2]);
+        log.ack("consumer", 2).await.unwrap();
+
+        // 模拟重启：重新打开日志后从确认位置继续
+        drop(log);
+        let log = DurableEventLog::open(store, RetentionPolicy::default()).await.unwrap();
+        assert_eq!(log.head_offset(), 4);
+        let fetched = log.fetch("consumer", 10).await.unwrap();
+        assert_eq!(fetched.events.len(), 1);
+        assert_eq!(fetched.events[0].offset, 3);
+        assert!(!fetched.truncated);
+
+        assert_eq!(log.append(event("execution.started", 5)).await.unwrap(), 5);
+        assert!(log.ack("consumer", 99).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_retention() {
+        let retention = RetentionPolicy {
+            max_events: Some(2),
+            max_age: None,
+            retain_unacked: false,
+        };
+        let log = DurableEventLog::in_memory(retention).await.unwrap();
+        log.subscribe("slow", "*").await.unwrap();
+        for n in 1..=5 {
+            log.append(event("e", n)).await.unwrap();
+        }
+
+        let fetched = log.fetch("slow", 10).await.unwrap();
+        assert!(fetched.truncated);
+        assert_eq!(fetched.events.iter().map(|e| e.offset).collect::<Vec<_>>(), vec![4, 5]);
+
+        // 保留未确认事件
+        let retention = RetentionPolicy {
+            max_events: Some(2),
+            max_age: None,
+            retain_unacked: true,
+        };
+        let log = DurableEventLog::in_memory(retention).await.unwrap();
+        log.subscribe("slow", "*").await.unwrap();
+        for n in 1..=5 {
+            log.append(event("e", n)).await.unwrap();
+        }
+        assert_eq!(log.fetch("slow", 10).await.unwrap().events.len(), 5);
+        log.ack("slow", 4).await.unwrap();
+        log.apply_retention().await.unwrap();
+        assert_eq!(log.replay("*", 0, 10).await.unwrap().events.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_file_store_survives_reopen() {
+        let dir = std::env::temp_dir().join(format!("stepflow-events-{}", uuid::Uuid::new_v4()));
+        {
+            let store = Arc::new(FileEventStore::open(&dir).await.unwrap());
+            let log = DurableEventLog::open(store, RetentionPolicy::default()).await.unwrap();
+            log.subscribe("consumer", "*").await.unwrap();
+            log.append(event("a", 1)).await.unwrap();
+            log.append(event("b", 2)).await.unwrap();
+            log.ack("consumer", 1).await.unwrap();
+        }
+
+        let store = Arc::new(FileEventStore::open(&dir).await.unwrap());
+        let log = DurableEventLog::open(store, RetentionPolicy::default()).await.unwrap();
+        let fetched = log.fetch("consumer", 10).await.unwrap();
+        assert_eq!(fetched.events.len(), 1);
+        assert_eq!(fetched.events[0].event.event, "b");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_rpc_handler() {
+        let log = Arc::new(DurableEventLog::in_memory(RetentionPolicy::default()).await.unwrap());
+        let handler = DurableEventHandler::new(log.clone());
+        handler
+            .handle("events.subscribe_durable", Some(json!({"id": "c1", "pattern": "*"})))
+            .await
+            .unwrap();
+        log.append(event("x", 1)).await.unwrap();
+
+        let fetched = handler.handle("events.fetch", Some(json!({"id": "c1"}))).await.unwrap();
+        assert_eq!(fetched["events"][0]["offset"], 1);
+        handler.handle("events.ack", Some(json!({"id": "c1", "offset": 1}))).await.unwrap();
+        let fetched = handler.handle("events.fetch", Some(json!({"id": "c1"}))).await.unwrap();
+        assert_eq!(fetched["events"].as_array().unwrap().len(), 0);
+
+        let error = handler.handle("events.fetch", Some(json!({"id": "missing"}))).await.unwrap_err();
+        assert_eq!(error.code, RpcError::invalid_params("").code);
+    }
+}
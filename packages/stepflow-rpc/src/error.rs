@@ -46,6 +46,12 @@ impl ErrorCode {
     }
 }
 
+/// 服务端过载错误代码
+pub const SERVER_BUSY_CODE: i32 = -32005;
+
+/// 服务暂不可用错误代码
+pub const SERVICE_UNAVAILABLE_CODE: i32 = -32006;
+
 /// JSON-RPC 错误对象
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RpcError {
@@ -93,6 +99,59 @@ impl RpcError {
         )
     }
 
+    /// 连接未认证
+    pub fn unauthenticated() -> Self {
+        Self {
+            code: -32001,
+            message: "Unauthenticated".to_string(),
+            data: None,
+        }
+    }
+
+    /// 调用方无权访问该方法
+    pub fn forbidden(method: &str) -> Self {
+        Self {
+            code: -32003,
+            message: "Forbidden".to_string(),
+            data: Some(serde_json::json!({"method": method})),
+        }
+    }
+
+    /// 服务端过载，客户端应在 `retry_after_ms` 后重试
+    pub fn server_busy(reason: &str, retry_after_ms: u64) -> Self {
+        Self {
+            code: SERVER_BUSY_CODE,
+            message: "Server busy".to_string(),
+            data: Some(serde_json::json!({
+                "reason": reason,
+                "retry_after_ms": retry_after_ms,
+            })),
+        }
+    }
+
+    /// 服务端暂不受理该方法，例如维护期间暂停发起执行
+    pub fn unavailable(reason: &str) -> Self {
+        Self {
+            code: SERVICE_UNAVAILABLE_CODE,
+            message: "Service unavailable".to_string(),
+            data: Some(serde_json::json!({"reason": reason})),
+        }
+    }
+
+    /// 若为服务端过载错误，返回建议的重试间隔
+    pub fn retry_after(&self) -> Option<std::time::Duration> {
+        if self.code != SERVER_BUSY_CODE {
+            return None;
+        }
+        let retry_after_ms = self
+            .data
+            .as_ref()
+            .and_then(|data| data.get("retry_after_ms"))
+            .and_then(serde_json::Value::as_u64)
+            .unwrap_or(0);
+        Some(std::time::Duration::from_millis(retry_after_ms))
+    }
+
     pub fn server_error(code: i32, message: &str) -> Self {
         let validated_code = ErrorCode::server_error(code);
         Self {
@@ -101,6 +160,32 @@ impl RpcError {
             data: None,
         }
     }
+
+    /// 按错误目录构造错误，`data` 中带稳定的错误代码和是否可重试
+    pub fn from_catalog(code: stepflow_core::ErrorCode, message: &str, retryable: bool) -> Self {
+        Self {
+            code: code.json_rpc_code(),
+            message: message.to_string(),
+            data: Some(serde_json::json!({
+                "code": code,
+                "retryable": retryable,
+            })),
+        }
+    }
+
+    /// 错误目录中的错误代码，由 [`RpcError::from_catalog`] 构造时才有
+    pub fn catalog_code(&self) -> Option<stepflow_core::ErrorCode> {
+        let code = self.data.as_ref()?.get("code")?;
+        serde_json::from_value(code.clone()).ok()
+    }
+}
+
+impl From<stepflow_core::StepflowError> for RpcError {
+    fn from(error: stepflow_core::StepflowError) -> Self {
+        // 内部错误不向调用方暴露细节
+        let message = if error.user_facing() { error.to_string() } else { "Internal error".to_string() };
+        Self::from_catalog(error.code(), &message, error.retryable())
+    }
 }
 
 /// RPC 框架错误类型
@@ -121,6 +206,13 @@ pub enum RpcFrameworkError {
     #[error("Timeout error: {0}")]
     TimeoutError(String),
 
+    /// 请求已发出但连接在响应前断开，服务端可能已经执行
+    #[error("Connection lost: {0}")]
+    ConnectionLost(String),
+
+    #[error("Request cancelled: {0}")]
+    Cancelled(String),
+
     #[error("Service not found: {0}")]
     ServiceNotFound(String),
 
@@ -134,4 +226,25 @@ impl From<RpcError> for RpcFrameworkError {
     }
 }
 
-pub type RpcResult<T> = Result<T, RpcFrameworkError>; 
\ No newline at end of file
+pub type RpcResult<T> = Result<T, RpcFrameworkError>; 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stepflow_core::{DatabaseError, StepflowError};
+
+    #[test]
+    fn test_stepflow_error_mapping() {
+        let error = RpcError::from(StepflowError::ToolNotFound("echo".to_string()));
+        assert_eq!(error.code, -32004);
+        assert_eq!(error.message, "Tool not found: echo");
+        assert_eq!(error.catalog_code(), Some(stepflow_core::ErrorCode::ToolNotFound));
+        assert_eq!(error.data.as_ref().unwrap()["retryable"], false);
+
+        // 内部错误隐藏细节，但保留可重试标记
+        let error = RpcError::from(StepflowError::from(DatabaseError::Deadlock));
+        assert_eq!(error.code, ErrorCode::InternalError.code());
+        assert_eq!(error.message, "Internal error");
+        assert_eq!(error.data.as_ref().unwrap()["retryable"], true);
+        assert_eq!(RpcError::invalid_request().catalog_code(), None);
+    }
+}
@@ -8,6 +8,7 @@ use serde_json::Value;
 use tokio::sync::{broadcast, RwLock};
 use tracing::{debug, info, warn};
 
+use crate::durable::DurableEventLog;
 use crate::error::RpcResult;
 use crate::protocol::{EventHandler, EventSubscription, RpcEvent};
 
@@ -18,6 +19,8 @@ pub struct EventPublisher {
     sender: broadcast::Sender<RpcEvent>,
     /// 事件统计
     stats: Arc<RwLock<EventStats>>,
+    /// 持久化事件日志（可选）
+    event_log: Option<Arc<DurableEventLog>>,
 }
 
 /// 事件统计信息
@@ -36,9 +39,21 @@ impl EventPublisher {
         Self {
             sender,
             stats: Arc::new(RwLock::new(EventStats::default())),
+            event_log: None,
         }
     }
 
+    /// 发布的事件同时写入持久化日志
+    pub fn with_event_log(mut self, event_log: Arc<DurableEventLog>) -> Self {
+        self.event_log = Some(event_log);
+        self
+    }
+
+    /// 获取持久化事件日志
+    pub fn event_log(&self) -> Option<&Arc<DurableEventLog>> {
+        self.event_log.as_ref()
+    }
+
     /// 发布事件
     pub async fn publish(&self, event: RpcEvent) -> RpcResult<()> {
         debug!("Publishing event: {:?}", event);
@@ -50,6 +65,11 @@ impl EventPublisher {
             *stats.events_by_type.entry(event.event.clone()).or_insert(0) += 1;
         }
 
+        // 先持久化，保证实时推送的事件都可回放
+        if let Some(event_log) = &self.event_log {
+            event_log.append(event.clone()).await?;
+        }
+
         // 发送事件
         match self.sender.send(event.clone()) {
             Ok(receiver_count) => {
@@ -196,7 +216,11 @@ pub struct EventManager {
 impl EventManager {
     /// 创建新的事件管理器
     pub fn new() -> Self {
-        let publisher = EventPublisher::new();
+        Self::with_publisher(EventPublisher::new())
+    }
+
+    /// 使用指定发布者创建事件管理器
+    pub fn with_publisher(publisher: EventPublisher) -> Self {
         Self {
             publisher,
             global_subscriber: Arc::new(RwLock::new(None)),
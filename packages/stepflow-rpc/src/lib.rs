@@ -4,19 +4,33 @@
 //! Designed for single-machine deployment with high performance and ease of use.
 
 pub mod protocol;
+pub mod codec;
+pub mod compression;
 pub mod server;
 pub mod client;
+pub mod pool;
+pub mod balancer;
 pub mod registry;
 pub mod error;
 pub mod event;
 pub mod streaming;
 pub mod subscription_manager;
+pub mod auth;
+pub mod limits;
+pub mod durable;
 
 pub use protocol::*;
+pub use codec::*;
+pub use compression::*;
 pub use server::*;
 pub use client::*;
+pub use pool::*;
+pub use balancer::*;
 pub use registry::*;
 pub use error::*;
 pub use event::*;
 pub use streaming::*;
-pub use subscription_manager::*; 
\ No newline at end of file
+pub use subscription_manager::*;
+pub use auth::*;
+pub use limits::*;
+pub use durable::*; 
\ No newline at end of file
@@ -0,0 +1,368 @@
+//! RPC Server Limits and Backpressure
+//!
+//! 服务端的限制及默认值：
+//!
+//! - 连接数：`ServerConfig::max_connections`（1000），超出的连接直接关闭
+//! - 单连接：消息按顺序处理，每次只有一条消息在途；一个批量请求最多执行
+//!   [`ServerLimits::max_batch_size`]（100）个请求，其余返回 Invalid Request 错误
+//! - 全局并发：[`ServerLimits::max_concurrent_requests`]（1024），另可按方法设置上限
+//! - 等待队列：并发已满的请求最多排队 [`ServerLimits::max_queued_requests`]（4096）个、
+//!   等待 [`ServerLimits::queue_timeout_ms`]（5 秒）；队列已满或等待超时的请求被丢弃，
+//!   并返回带 `retry_after_ms` 的 "Server busy" 错误，避免过载的客户端耗尽内存
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::error::{ErrorCode, RpcError};
+
+/// 服务端限流配置
+#[derive(Debug, Clone)]
+pub struct ServerLimits {
+    /// 单个批量请求最多执行的请求数，超出部分返回 Invalid Request 错误
+    pub max_batch_size: usize,
+    /// 全局最大并发请求数
+    pub max_concurrent_requests: usize,
+    /// 等待执行的最大请求数，超出即丢弃
+    pub max_queued_requests: usize,
+    /// 请求在队列中的最长等待时间
+    pub queue_timeout_ms: u64,
+    /// 返回给客户端的建议重试间隔
+    pub retry_after_ms: u64,
+    /// 单方法并发上限
+    pub method_limits: HashMap<String, usize>,
+}
+
+impl Default for ServerLimits {
+    fn default() -> Self {
+        Self {
+            max_batch_size: 100,
+            max_concurrent_requests: 1024,
+            max_queued_requests: 4096,
+            queue_timeout_ms: 5000,
+            retry_after_ms: 1000,
+            method_limits: HashMap::new(),
+        }
+    }
+}
+
+impl ServerLimits {
+    /// 设置单方法并发上限
+    pub fn with_method_limit(mut self, method: String, limit: usize) -> Self {
+        self.method_limits.insert(method, limit);
+        self
+    }
+}
+
+/// 丢弃原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShedReason {
+    QueueFull,
+    QueueTimeout,
+}
+
+impl ShedReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ShedReason::QueueFull => "queue_full",
+            ShedReason::QueueTimeout => "queue_timeout",
+        }
+    }
+}
+
+/// 单方法指标
+#[derive(Debug, Default)]
+struct MethodCounters {
+    in_flight: AtomicUsize,
+    shed: AtomicU64,
+}
+
+/// 单方法指标快照
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MethodLimitMetrics {
+    pub limit: Option<usize>,
+    pub in_flight: usize,
+    pub shed: u64,
+}
+
+/// 限流指标快照
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LimitMetrics {
+    pub active_connections: usize,
+    pub max_connections: usize,
+    pub rejected_connections: u64,
+    pub in_flight_requests: usize,
+    pub queued_requests: usize,
+    /// 因超出批量大小上限被拒绝的请求数
+    pub rejected_batch_requests: u64,
+    pub shed_queue_full: u64,
+    pub shed_queue_timeout: u64,
+    pub methods: HashMap<String, MethodLimitMetrics>,
+}
+
+/// 已准入请求持有的许可，释放时归还并发额度
+pub struct RequestPermit {
+    _global: OwnedSemaphorePermit,
+    _method: Option<OwnedSemaphorePermit>,
+    counters: Arc<MethodCounters>,
+    in_flight: Arc<AtomicUsize>,
+}
+
+impl Drop for RequestPermit {
+    fn drop(&mut self) {
+        self.counters.in_flight.fetch_sub(1, Ordering::Relaxed);
+        self.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// 已建立连接持有的许可
+pub struct ConnectionPermit {
+    _permit: OwnedSemaphorePermit,
+}
+
+/// 请求准入控制器
+pub struct RequestLimiter {
+    limits: ServerLimits,
+    max_connections: usize,
+    connection_slots: Arc<Semaphore>,
+    request_slots: Arc<Semaphore>,
+    method_slots: HashMap<String, Arc<Semaphore>>,
+    method_counters: DashMap<String, Arc<MethodCounters>>,
+    in_flight: Arc<AtomicUsize>,
+    queued: AtomicUsize,
+    rejected_connections: AtomicU64,
+    rejected_batch_requests: AtomicU64,
+    shed_queue_full: AtomicU64,
+    shed_queue_timeout: AtomicU64,
+}
+
+impl RequestLimiter {
+    pub fn new(max_connections: usize, limits: ServerLimits) -> Self {
+        let method_slots = limits
+            .method_limits
+            .iter()
+            .map(|(method, limit)| (method.clone(), Arc::new(Semaphore::new(*limit))))
+            .collect();
+
+        Self {
+            max_connections,
+            connection_slots: Arc::new(Semaphore::new(max_connections)),
+            request_slots: Arc::new(Semaphore::new(limits.max_concurrent_requests)),
+            method_slots,
+            method_counters: DashMap::new(),
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            queued: AtomicUsize::new(0),
+            rejected_connections: AtomicU64::new(0),
+            rejected_batch_requests: AtomicU64::new(0),
+            shed_queue_full: AtomicU64::new(0),
+            shed_queue_timeout: AtomicU64::new(0),
+            limits,
+        }
+    }
+
+    pub fn limits(&self) -> &ServerLimits {
+        &self.limits
+    }
+
+    /// 尝试为新连接占用名额
+    pub fn try_acquire_connection(&self) -> Option<ConnectionPermit> {
+        match self.connection_slots.clone().try_acquire_owned() {
+            Ok(permit) => Some(ConnectionPermit { _permit: permit }),
+            Err(_) => {
+                self.rejected_connections.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    /// 批量请求超出大小上限时，为超出的请求生成错误
+    pub fn reject_batch_request(&self, max_batch_size: usize) -> RpcError {
+        self.rejected_batch_requests.fetch_add(1, Ordering::Relaxed);
+        RpcError::new(
+            ErrorCode::InvalidRequest,
+            Some(serde_json::json!({
+                "reason": "batch_too_large",
+                "max_batch_size": max_batch_size,
+            })),
+        )
+    }
+
+    /// 为请求获取执行许可，必要时在队列中等待
+    pub async fn acquire(&self, method: &str) -> Result<RequestPermit, RpcError> {
+        let method_slots = self.method_slots.get(method).cloned();
+
+        // 快速路径：无需排队
+        if let Ok(global) = self.request_slots.clone().try_acquire_owned() {
+            match &method_slots {
+                None => return Ok(self.admit(method, global, None)),
+                Some(slots) => {
+                    if let Ok(permit) = slots.clone().try_acquire_owned() {
+                        return Ok(self.admit(method, global, Some(permit)));
+                    }
+                }
+            }
+        }
+
+        // 进入有界队列
+        let queued = self.queued.fetch_add(1, Ordering::AcqRel);
+        if queued >= self.limits.max_queued_requests {
+            self.queued.fetch_sub(1, Ordering::AcqRel);
+            return Err(self.shed(method, ShedReason::QueueFull));
+        }
+
+        let wait = async {
+            // 先获取方法许可，避免占着全局额度等待热点方法
+            let method_permit = match &method_slots {
+                Some(slots) => Some(slots.clone().acquire_owned().await.ok()?),
+                None => None,
+            };
+            let global = self.request_slots.clone().acquire_owned().await.ok()?;
+            Some((global, method_permit))
+        };
+        let result = tokio::time::timeout(Duration::from_millis(self.limits.queue_timeout_ms), wait).await;
+        self.queued.fetch_sub(1, Ordering::AcqRel);
+
+        match result {
+            Ok(Some((global, method_permit))) => Ok(self.admit(method, global, method_permit)),
+            _ => Err(self.shed(method, ShedReason::QueueTimeout)),
+        }
+    }
+
+    /// 获取指标快照
+    pub fn metrics(&self) -> LimitMetrics {
+        let mut methods: HashMap<String, MethodLimitMetrics> = self
+            .method_counters
+            .iter()
+            .map(|entry| {
+                (
+                    entry.key().clone(),
+                    MethodLimitMetrics {
+                        limit: self.limits.method_limits.get(entry.key()).copied(),
+                        in_flight: entry.value().in_flight.load(Ordering::Relaxed),
+                        shed: entry.value().shed.load(Ordering::Relaxed),
+                    },
+                )
+            })
+            .collect();
+        for (method, limit) in &self.limits.method_limits {
+            methods.entry(method.clone()).or_insert(MethodLimitMetrics {
+                limit: Some(*limit),
+                in_flight: 0,
+                shed: 0,
+            });
+        }
+
+        LimitMetrics {
+            active_connections: self.max_connections - self.connection_slots.available_permits(),
+            max_connections: self.max_connections,
+            rejected_connections: self.rejected_connections.load(Ordering::Relaxed),
+            in_flight_requests: self.in_flight.load(Ordering::Relaxed),
+            queued_requests: self.queued.load(Ordering::Relaxed),
+            rejected_batch_requests: self.rejected_batch_requests.load(Ordering::Relaxed),
+            shed_queue_full: self.shed_queue_full.load(Ordering::Relaxed),
+            shed_queue_timeout: self.shed_queue_timeout.load(Ordering::Relaxed),
+            methods,
+        }
+    }
+
+    fn counters(&self, method: &str) -> Arc<MethodCounters> {
+        self.method_counters
+            .entry(method.to_string())
+            .or_default()
+            .clone()
+    }
+
+    fn admit(&self, method: &str, global: OwnedSemaphorePermit, method_permit: Option<OwnedSemaphorePermit>) -> RequestPermit {
+        let counters = self.counters(method);
+        counters.in_flight.fetch_add(1, Ordering::Relaxed);
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+        RequestPermit {
+            _global: global,
+            _method: method_permit,
+            counters,
+            in_flight: self.in_flight.clone(),
+        }
+    }
+
+    fn shed(&self, method: &str, reason: ShedReason) -> RpcError {
+        let counter = match reason {
+            ShedReason::QueueFull => &self.shed_queue_full,
+            ShedReason::QueueTimeout => &self.shed_queue_timeout,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        self.counters(method).shed.fetch_add(1, Ordering::Relaxed);
+        RpcError::server_busy(reason.as_str(), self.limits.retry_after_ms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_method_limit_sheds_when_queue_full() {
+        let limits = ServerLimits {
+            max_queued_requests: 0,
+            ..ServerLimits::default()
+        }
+        .with_method_limit("tools.execute".to_string(), 1);
+        let limiter = RequestLimiter::new(10, limits);
+
+        let permit = limiter.acquire("tools.execute").await.unwrap();
+        let error = limiter.acquire("tools.execute").await.err().unwrap();
+        assert_eq!(error.code, RpcError::server_busy("queue_full", 0).code);
+        assert_eq!(error.data.unwrap()["retry_after_ms"], 1000);
+
+        // 其他方法不受影响
+        assert!(limiter.acquire("tools.list").await.is_ok());
+
+        let metrics = limiter.metrics();
+        assert_eq!(metrics.shed_queue_full, 1);
+        assert_eq!(metrics.methods["tools.execute"].in_flight, 1);
+        assert_eq!(metrics.methods["tools.execute"].shed, 1);
+
+        drop(permit);
+        assert!(limiter.acquire("tools.execute").await.is_ok());
+        assert_eq!(limiter.metrics().in_flight_requests, 0);
+    }
+
+    #[tokio::test]
+    async fn test_queued_request_times_out() {
+        let limits = ServerLimits {
+            max_concurrent_requests: 1,
+            queue_timeout_ms: 20,
+            ..ServerLimits::default()
+        };
+        let limiter = Arc::new(RequestLimiter::new(10, limits));
+
+        let permit = limiter.acquire("a").await.unwrap();
+        assert_eq!(limiter.acquire("b").await.err().unwrap().data.unwrap()["reason"], "queue_timeout");
+
+        // 许可释放后排队请求得以执行
+        let waiter = {
+            let limiter = limiter.clone();
+            tokio::spawn(async move { limiter.acquire("b").await.is_ok() })
+        };
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        drop(permit);
+        assert!(waiter.await.unwrap());
+    }
+
+    #[test]
+    fn test_connection_limit() {
+        let limiter = RequestLimiter::new(1, ServerLimits::default());
+        let first = limiter.try_acquire_connection();
+        assert!(first.is_some());
+        assert!(limiter.try_acquire_connection().is_none());
+        assert_eq!(limiter.metrics().rejected_connections, 1);
+        assert_eq!(limiter.metrics().active_connections, 1);
+
+        drop(first);
+        assert!(limiter.try_acquire_connection().is_some());
+    }
+}
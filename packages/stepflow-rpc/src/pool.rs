@@ -0,0 +1,507 @@
+//! RPC Client Connection Pool
+//!
+//! 连接池维护到同一服务端的多个 `RpcClient`，负责轮询选取连接、断线重连、
+//! 周期性健康检查，并提供带超时、取消与重试的类型化调用接口。
+//!
+//! 重试规则：
+//! - 请求未能发出（连接不可用、写入失败）时总是重试
+//! - 服务端返回 "Server busy" 时按其 `retry_after_ms` 等待后重试
+//! - 请求已发出但超时或连接中断时，仅对声明为幂等的调用重试
+
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn};
+
+use crate::client::{ClientConfig, RpcClient};
+use crate::error::{RpcFrameworkError, RpcResult};
+
+/// 调用的幂等性声明
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Idempotency {
+    /// 重复执行是安全的，超时或连接中断后可重试
+    Idempotent,
+    /// 仅在确认请求未被执行时重试
+    #[default]
+    NonIdempotent,
+}
+
+/// 重试退避策略
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// 包含首次调用在内的最大尝试次数
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 第 `attempt` 次失败后的退避时间
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+/// 单次调用选项
+#[derive(Debug, Clone, Default)]
+pub struct CallOptions {
+    /// 单次尝试的超时，默认使用客户端配置
+    pub timeout: Option<Duration>,
+    pub idempotency: Idempotency,
+    /// 覆盖连接池的重试策略
+    pub retry: Option<RetryPolicy>,
+    pub cancellation: Option<CancellationToken>,
+}
+
+impl CallOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn idempotent(mut self) -> Self {
+        self.idempotency = Idempotency::Idempotent;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+}
+
+/// 连接池配置
+#[derive(Debug, Clone)]
+pub struct PoolConfig {
+    pub size: usize,
+    /// 池内各连接的配置，心跳由连接池统一负责
+    pub client: ClientConfig,
+    pub health_check_interval: Duration,
+    pub retry: RetryPolicy,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            size: 4,
+            client: ClientConfig {
+                enable_heartbeat: false,
+                ..ClientConfig::default()
+            },
+            health_check_interval: Duration::from_secs(15),
+            retry: RetryPolicy::default(),
+        }
+    }
+}
+
+/// 连接池统计信息
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    pub size: usize,
+    pub connected: usize,
+    pub total_calls: u64,
+    pub failed_calls: u64,
+    pub retries: u64,
+    pub reconnects: u64,
+    pub health_check_failures: u64,
+}
+
+#[derive(Debug, Default)]
+struct PoolCounters {
+    total_calls: AtomicU64,
+    failed_calls: AtomicU64,
+    retries: AtomicU64,
+    reconnects: AtomicU64,
+    health_check_failures: AtomicU64,
+}
+
+/// RPC 客户端连接池
+pub struct RpcClientPool {
+    server_addr: SocketAddr,
+    config: PoolConfig,
+    clients: Vec<RpcClient>,
+    next: AtomicUsize,
+    counters: PoolCounters,
+}
+
+impl RpcClientPool {
+    /// 创建连接池（尚未连接）
+    pub fn new(server_addr: SocketAddr, config: PoolConfig) -> Self {
+        let clients = (0..config.size.max(1))
+            .map(|index| {
+                let client_config = ClientConfig {
+                    client_id: format!("{}-{}", config.client.client_id, index),
+                    ..config.client.clone()
+                };
+                RpcClient::new(server_addr, client_config)
+            })
+            .collect();
+
+        Self {
+            server_addr,
+            config,
+            clients,
+            next: AtomicUsize::new(0),
+            counters: PoolCounters::default(),
+        }
+    }
+
+    /// 向池内所有连接注入故障（仅测试）
+    #[cfg(feature = "fault-injection")]
+    pub fn with_fault_controller(mut self, controller: stepflow_core::FaultController) -> Self {
+        self.clients = self.clients.into_iter().map(|client| client.with_fault_controller(controller.clone())).collect();
+        self
+    }
+
+    /// 建立池内全部连接，至少一个连接成功即视为可用
+    pub async fn connect(&self) -> RpcResult<()> {
+        let mut last_error = None;
+        for client in &self.clients {
+            if let Err(e) = client.connect().await {
+                warn!("Pool connection {} to {} failed: {}", client.client_id(), self.server_addr, e);
+                last_error = Some(e);
+            }
+        }
+
+        if self.connected_count().await > 0 {
+            Ok(())
+        } else {
+            Err(last_error.unwrap_or_else(|| RpcFrameworkError::ConnectionError("No connections".to_string())))
+        }
+    }
+
+    /// 轮询选取一个可用连接，全部不可用时尝试重连
+    pub async fn acquire(&self) -> RpcResult<RpcClient> {
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        for offset in 0..self.clients.len() {
+            let client = &self.clients[(start + offset) % self.clients.len()];
+            if client.is_connected().await {
+                return Ok(client.clone());
+            }
+        }
+
+        let client = &self.clients[start % self.clients.len()];
+        self.counters.reconnects.fetch_add(1, Ordering::Relaxed);
+        client.reconnect().await?;
+        Ok(client.clone())
+    }
+
+    /// 类型化调用，使用默认选项
+    pub async fn call<P, R>(&self, method: &str, params: &P) -> RpcResult<R>
+    where
+        P: Serialize + ?Sized,
+        R: DeserializeOwned,
+    {
+        self.call_with_retry(method, params, CallOptions::default()).await
+    }
+
+    /// 类型化调用，按选项进行超时、取消与重试
+    pub async fn call_with_retry<P, R>(&self, method: &str, params: &P, options: CallOptions) -> RpcResult<R>
+    where
+        P: Serialize + ?Sized,
+        R: DeserializeOwned,
+    {
+        let params = serde_json::to_value(params)?;
+        let policy = options.retry.as_ref().unwrap_or(&self.config.retry);
+        self.counters.total_calls.fetch_add(1, Ordering::Relaxed);
+
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let error = match self.call_once(method, params.clone(), &options).await {
+                Ok(value) => return Ok(serde_json::from_value(value)?),
+                Err(error) => error,
+            };
+
+            let delay = match retry_delay(&error, options.idempotency) {
+                Some(hint) if attempt < policy.max_attempts => hint.max(policy.backoff(attempt)),
+                _ => {
+                    self.counters.failed_calls.fetch_add(1, Ordering::Relaxed);
+                    return Err(error);
+                }
+            };
+
+            debug!("Retrying '{}' in {:?} (attempt {}): {}", method, delay, attempt, error);
+            self.counters.retries.fetch_add(1, Ordering::Relaxed);
+
+            match &options.cancellation {
+                Some(token) => tokio::select! {
+                    _ = tokio::time::sleep(delay) => {}
+                    _ = token.cancelled() => {
+                        self.counters.failed_calls.fetch_add(1, Ordering::Relaxed);
+                        return Err(RpcFrameworkError::Cancelled(method.to_string()));
+                    }
+                },
+                None => tokio::time::sleep(delay).await,
+            }
+        }
+    }
+
+    /// 对所有连接执行一次健康检查，断开或 ping 失败的连接会被重连
+    pub async fn check_health(&self) {
+        for client in &self.clients {
+            let healthy = client.is_connected().await && client.ping().await.is_ok();
+            if healthy {
+                continue;
+            }
+
+            self.counters.health_check_failures.fetch_add(1, Ordering::Relaxed);
+            self.counters.reconnects.fetch_add(1, Ordering::Relaxed);
+            if let Err(e) = client.reconnect().await {
+                warn!("Health check reconnect for {} failed: {}", client.client_id(), e);
+            }
+        }
+    }
+
+    /// 启动周期性健康检查，连接池释放后自动停止
+    pub fn start_health_checks(self: &Arc<Self>) -> JoinHandle<()> {
+        let pool: Weak<Self> = Arc::downgrade(self);
+        let interval = self.config.health_check_interval;
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                match pool.upgrade() {
+                    Some(pool) => pool.check_health().await,
+                    None => break,
+                }
+            }
+        })
+    }
+
+    /// 当前已连接的连接数
+    pub async fn connected_count(&self) -> usize {
+        let mut connected = 0;
+        for client in &self.clients {
+            if client.is_connected().await {
+                connected += 1;
+            }
+        }
+        connected
+    }
+
+    /// 获取统计信息
+    pub async fn stats(&self) -> PoolStats {
+        PoolStats {
+            size: self.clients.len(),
+            connected: self.connected_count().await,
+            total_calls: self.counters.total_calls.load(Ordering::Relaxed),
+            failed_calls: self.counters.failed_calls.load(Ordering::Relaxed),
+            retries: self.counters.retries.load(Ordering::Relaxed),
+            reconnects: self.counters.reconnects.load(Ordering::Relaxed),
+            health_check_failures: self.counters.health_check_failures.load(Ordering::Relaxed),
+        }
+    }
+
+    /// 关闭所有连接
+    pub async fn close(&self) -> RpcResult<()> {
+        for client in &self.clients {
+            client.disconnect().await?;
+        }
+        Ok(())
+    }
+
+    async fn call_once(&self, method: &str, params: Value, options: &CallOptions) -> RpcResult<Value> {
+        let client = self.acquire().await?;
+        let request_timeout = options.timeout.unwrap_or(self.config.client.request_timeout);
+        let request = client.send_request_with_timeout(method, params, request_timeout);
+
+        match &options.cancellation {
+            Some(token) => tokio::select! {
+                result = request => result,
+                _ = token.cancelled() => Err(RpcFrameworkError::Cancelled(method.to_string())),
+            },
+            None => request.await,
+        }
+    }
+}
+
+/// 判断错误是否可重试，返回服务端建议的最短等待时间
+pub(crate) fn retry_delay(error: &RpcFrameworkError, idempotency: Idempotency) -> Option<Duration> {
+    match error {
+        RpcFrameworkError::ConnectionError(_) => Some(Duration::ZERO),
+        RpcFrameworkError::RpcError(rpc_error) => rpc_error.retry_after(),
+        RpcFrameworkError::TimeoutError(_) | RpcFrameworkError::ConnectionLost(_) => {
+            (idempotency == Idempotency::Idempotent).then_some(Duration::ZERO)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::RpcError;
+    use crate::protocol::FunctionHandler;
+    use crate::server::{RpcServer, ServerConfig};
+    use serde_json::json;
+
+    async fn start_server() -> SocketAddr {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        let server = Arc::new(RpcServer::new(ServerConfig {
+            bind_addr: addr,
+            ..ServerConfig::default()
+        }));
+        server.register_handler(Arc::new(FunctionHandler::new("math.add".to_string(), |params| async move {
+            let params = params.unwrap_or_default();
+            Ok(json!(params["a"].as_i64().unwrap_or(0) + params["b"].as_i64().unwrap_or(0)))
+        })));
+        server.register_handler(Arc::new(FunctionHandler::new("slow".to_string(), |_params| async {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            Ok(json!(null))
+        })));
+        tokio::spawn(server.serve());
+
+        for _ in 0..50 {
+            if tokio::net::TcpStream::connect(addr).await.is_ok() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        addr
+    }
+
+    #[test]
+    fn test_retry_classification() {
+        let busy = RpcFrameworkError::RpcError(RpcError::server_busy("queue_full", 250));
+        assert_eq!(retry_delay(&busy, Idempotency::NonIdempotent), Some(Duration::from_millis(250)));
+
+        let not_sent = RpcFrameworkError::ConnectionError("Not connected".to_string());
+        assert_eq!(retry_delay(&not_sent, Idempotency::NonIdempotent), Some(Duration::ZERO));
+
+        let timeout = RpcFrameworkError::TimeoutError("slow".to_string());
+        assert_eq!(retry_delay(&timeout, Idempotency::NonIdempotent), None);
+        assert_eq!(retry_delay(&timeout, Idempotency::Idempotent), Some(Duration::ZERO));
+
+        let invalid = RpcFrameworkError::RpcError(RpcError::invalid_params("bad"));
+        assert_eq!(retry_delay(&invalid, Idempotency::Idempotent), None);
+
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(30), policy.max_backoff);
+    }
+
+    #[tokio::test]
+    async fn test_pool_typed_calls_and_timeouts() {
+        let addr = start_server().await;
+        let pool = RpcClientPool::new(addr, PoolConfig { size: 2, ..PoolConfig::default() });
+        pool.connect().await.unwrap();
+        assert_eq!(pool.connected_count().await, 2);
+
+        let sum: i64 = pool.call("math.add", &json!({"a": 2, "b": 3})).await.unwrap();
+        assert_eq!(sum, 5);
+
+        // 非幂等调用超时后不重试
+        let options = CallOptions::new().with_timeout(Duration::from_millis(20));
+        let error = pool.call_with_retry::<_, Value>("slow", &json!({}), options).await.unwrap_err();
+        assert!(matches!(error, RpcFrameworkError::TimeoutError(_)));
+        assert_eq!(pool.stats().await.retries, 0);
+
+        // 幂等调用按策略重试
+        let options = CallOptions::new()
+            .idempotent()
+            .with_timeout(Duration::from_millis(20))
+            .with_retry(RetryPolicy { max_attempts: 2, initial_backoff: Duration::from_millis(1), ..RetryPolicy::default() });
+        assert!(pool.call_with_retry::<_, Value>("slow", &json!({}), options).await.is_err());
+        assert_eq!(pool.stats().await.retries, 1);
+
+        // 取消
+        let token = CancellationToken::new();
+        token.cancel();
+        let options = CallOptions::new().with_cancellation(token);
+        let error = pool.call_with_retry::<_, Value>("slow", &json!({}), options).await.unwrap_err();
+        assert!(matches!(error, RpcFrameworkError::Cancelled(_)));
+
+        pool.close().await.unwrap();
+    }
+
+    #[cfg(feature = "fault-injection")]
+    #[tokio::test]
+    async fn test_pool_retries_and_cancels_injected_faults() {
+        use stepflow_core::{FaultConfig, FaultController, FaultTarget};
+
+        let addr = start_server().await;
+        let faults = FaultController::new(3);
+        let pool = RpcClientPool::new(addr, PoolConfig { size: 1, ..PoolConfig::default() }).with_fault_controller(faults.clone());
+        pool.connect().await.unwrap();
+        let retry = RetryPolicy { max_attempts: 3, initial_backoff: Duration::from_millis(1), ..RetryPolicy::default() };
+        let params = json!({"a": 2, "b": 3});
+
+        // 连接中断时非幂等调用不重试，幂等调用重试到上限
+        faults.set(FaultTarget::RpcClient, FaultConfig::always_fail());
+        let options = CallOptions::new().with_retry(retry.clone());
+        let error = pool.call_with_retry::<_, i64>("math.add", &params, options).await.unwrap_err();
+        assert!(matches!(error, RpcFrameworkError::ConnectionLost(_)));
+        let options = CallOptions::new().idempotent().with_retry(retry.clone());
+        assert!(pool.call_with_retry::<_, i64>("math.add", &params, options).await.is_err());
+        assert_eq!(faults.stats(FaultTarget::RpcClient).errors, 4);
+        assert_eq!(pool.stats().await.retries, 2);
+
+        // 请求已发出但响应丢失，同样只有幂等调用会重发
+        faults.set(FaultTarget::RpcClient, FaultConfig::partial_writes(1.0));
+        let options = CallOptions::new().with_retry(retry.clone());
+        assert!(pool.call_with_retry::<_, i64>("math.add", &params, options).await.is_err());
+        assert_eq!(faults.stats(FaultTarget::RpcClient).partial_writes, 1);
+
+        // 间歇性故障由重试恢复
+        faults.set(FaultTarget::RpcClient, FaultConfig::errors(0.5));
+        let options = CallOptions::new()
+            .idempotent()
+            .with_retry(RetryPolicy { max_attempts: 10, ..retry.clone() });
+        let sum: i64 = pool.call_with_retry("math.add", &params, options).await.unwrap();
+        assert_eq!(sum, 5);
+
+        // 注入的延迟期间取消，调用立即结束
+        faults.set(FaultTarget::RpcClient, FaultConfig::latency(1.0, Duration::from_secs(5)));
+        let token = CancellationToken::new();
+        let cancel = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            cancel.cancel();
+        });
+        let started = std::time::Instant::now();
+        let options = CallOptions::new().with_cancellation(token);
+        let error = pool.call_with_retry::<_, i64>("math.add", &params, options).await.unwrap_err();
+        assert!(matches!(error, RpcFrameworkError::Cancelled(_)));
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        // 故障清除后连接仍可用
+        faults.clear(FaultTarget::RpcClient);
+        let sum: i64 = pool.call("math.add", &params).await.unwrap();
+        assert_eq!(sum, 5);
+        pool.close().await.unwrap();
+    }
+}
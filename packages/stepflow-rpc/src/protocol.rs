@@ -60,7 +60,8 @@ impl RpcRequest {
         }
         
         if self.method.starts_with("rpc.") && !self.method.starts_with("rpc.discover") 
-            && !self.method.starts_with("rpc.ping") && !self.method.starts_with("rpc.stats") {
+            && !self.method.starts_with("rpc.ping") && !self.method.starts_with("rpc.stats")
+            && self.method != crate::auth::CONNECT_METHOD && self.method != crate::codec::NEGOTIATE_METHOD {
             return Err(RpcError::invalid_request());
         }
         
@@ -112,7 +113,7 @@ impl RpcResponse {
 }
 
 /// JSON-RPC 事件对象（服务器主动推送）
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct RpcEvent {
     pub jsonrpc: String,
     pub event: String,
@@ -261,6 +262,17 @@ pub trait RpcHandler: Send + Sync {
     /// 处理 RPC 调用
     async fn handle(&self, method: &str, params: Option<Value>) -> Result<Value, RpcError>;
 
+    /// 携带请求上下文（含已认证调用方）处理 RPC 调用，默认忽略上下文
+    async fn handle_with_context(
+        &self,
+        method: &str,
+        params: Option<Value>,
+        context: &crate::auth::RequestContext,
+    ) -> Result<Value, RpcError> {
+        let _ = context;
+        self.handle(method, params).await
+    }
+
     /// 获取支持的方法列表
     fn methods(&self) -> Vec<String>;
 
@@ -271,6 +283,15 @@ pub trait RpcHandler: Send + Sync {
     }
 }
 
+/// 方法准入检查
+///
+/// 在授权之后、分发到处理器之前调用，用于按服务端状态（如维护模式）拒绝部分方法。
+#[async_trait::async_trait]
+pub trait RpcMethodGuard: Send + Sync {
+    /// 允许调用返回 `Ok(())`，否则返回拒绝原因
+    async fn check(&self, method: &str, context: &crate::auth::RequestContext) -> Result<(), RpcError>;
+}
+
 /// 事件处理器 trait
 #[async_trait::async_trait]
 pub trait EventHandler: Send + Sync {
@@ -7,14 +7,20 @@ use bytes::{BufMut, BytesMut};
 use dashmap::DashMap;
 use futures::{SinkExt, StreamExt};
 use serde_json::Value;
+use stepflow_core::{AclDecision, NetworkAcl};
 use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::RwLock;
 use tokio_util::codec::{Decoder, Encoder, Framed};
 use tracing::{debug, error, info, warn};
 
+use crate::auth::{RequestContext, RpcAuthConfig, CONNECT_METHOD};
+use crate::codec::{RpcCodec, WireFormat, NEGOTIATE_METHOD};
+use crate::compression::{Compression, CompressionConfig, CompressionMetrics, CompressionStats, FrameCompressor};
 use crate::error::{RpcError, RpcFrameworkError, RpcResult};
+use crate::durable::{DurableEventHandler, DurableEventLog};
 use crate::event::{EventManager, EventPublisher};
-use crate::protocol::{RpcHandler, RpcMessage, RpcRequest, RpcResponse, RpcResponseMessage, ServerMessage};
+use crate::limits::{LimitMetrics, RequestLimiter, ServerLimits};
+use crate::protocol::{RpcHandler, RpcMethodGuard, RpcMessage, RpcRequest, RpcResponse, RpcResponseMessage, ServerMessage};
 
 /// JSON-RPC TCP Codec for framing messages
 #[derive(Debug, Clone)]
@@ -65,6 +71,11 @@ pub struct ServerConfig {
     pub max_connections: usize,
     pub buffer_size: usize,
     pub request_timeout_ms: u64,
+    pub limits: ServerLimits,
+    /// 服务端支持的线路编码，按偏好排序
+    pub wire_formats: Vec<WireFormat>,
+    /// 服务端支持的压缩算法与压缩阈值
+    pub compression: CompressionConfig,
 }
 
 impl Default for ServerConfig {
@@ -74,6 +85,9 @@ impl Default for ServerConfig {
             max_connections: 1000,
             buffer_size: 8192,
             request_timeout_ms: 30000, // 30 seconds
+            limits: ServerLimits::default(),
+            wire_formats: vec![WireFormat::MsgPack, WireFormat::Json],
+            compression: CompressionConfig::default(),
         }
     }
 }
@@ -92,6 +106,11 @@ pub struct RpcServer {
     connections: Arc<DashMap<String, ConnectionState>>,
     stats: Arc<RwLock<ServerStats>>,
     event_manager: Arc<EventManager>,
+    auth: Option<Arc<RpcAuthConfig>>,
+    limiter: Arc<RequestLimiter>,
+    network_acl: Option<Arc<NetworkAcl>>,
+    method_guard: Option<Arc<dyn RpcMethodGuard>>,
+    compression_metrics: Arc<CompressionMetrics>,
 }
 
 /// 服务端统计信息
@@ -102,17 +121,25 @@ pub struct ServerStats {
     pub failed_requests: u64,
     pub active_connections: u64,
     pub total_connections: u64,
+    /// 被网络访问控制列表拒绝的连接数
+    pub rejected_connections_acl: u64,
 }
 
 impl RpcServer {
     /// 创建新的 RPC 服务端
     pub fn new(config: ServerConfig) -> Self {
+        let limiter = Arc::new(RequestLimiter::new(config.max_connections, config.limits.clone()));
         let server = Self {
             config,
             handlers: Arc::new(DashMap::new()),
             connections: Arc::new(DashMap::new()),
             stats: Arc::new(RwLock::new(ServerStats::default())),
             event_manager: Arc::new(EventManager::new()),
+            auth: None,
+            limiter,
+            network_acl: None,
+            method_guard: None,
+            compression_metrics: Arc::new(CompressionMetrics::default()),
         };
         
         // 注册内置方法
@@ -120,6 +147,39 @@ impl RpcServer {
         server
     }
 
+    /// 启用认证与方法级授权
+    ///
+    /// 启用后客户端需先调用 `rpc.connect` 并提供 token。
+    pub fn with_auth(mut self, auth: RpcAuthConfig) -> Self {
+        self.auth = Some(Arc::new(auth));
+        self
+    }
+
+    /// 启用网络访问控制
+    ///
+    /// 全局列表在接受连接时按对端地址检查；`rpc.connect` 认证出租户后再检查租户列表，
+    /// 被拒绝的连接会被关闭。列表可在运行时通过共享的 [`NetworkAcl`] 更新。
+    pub fn with_network_acl(mut self, network_acl: Arc<NetworkAcl>) -> Self {
+        self.network_acl = Some(network_acl);
+        self
+    }
+
+    /// 设置方法准入检查，授权通过的调用还需经其放行
+    pub fn with_method_guard(mut self, guard: Arc<dyn RpcMethodGuard>) -> Self {
+        self.method_guard = Some(guard);
+        self
+    }
+
+    /// 启用持久化事件日志
+    ///
+    /// 发布的事件会写入日志，并注册 `events.*` 持久订阅方法。
+    pub fn with_event_log(mut self, event_log: Arc<DurableEventLog>) -> Self {
+        let publisher = EventPublisher::new().with_event_log(event_log.clone());
+        self.event_manager = Arc::new(EventManager::with_publisher(publisher));
+        self.register_handler(Arc::new(DurableEventHandler::new(event_log)));
+        self
+    }
+
     /// 获取事件管理器
     pub fn event_manager(&self) -> &Arc<EventManager> {
         &self.event_manager
@@ -159,22 +219,29 @@ impl RpcServer {
             match listener.accept().await {
                 Ok((stream, addr)) => {
                     debug!("New connection from: {}", addr);
-                    
-                    // 检查连接数限制
-                    {
-                        let stats = self.stats.read().await;
-                        if stats.active_connections >= self.config.max_connections as u64 {
-                            warn!("Max connections reached, rejecting connection from: {}", addr);
+
+                    if let Some(acl) = &self.network_acl {
+                        if let AclDecision::Deny(scope) = acl.check(addr.ip(), None) {
+                            warn!("Rejecting connection from {} by {:?} network ACL", addr, scope);
+                            self.stats.write().await.rejected_connections_acl += 1;
                             drop(stream);
                             continue;
                         }
                     }
 
+                    // 检查连接数限制
+                    let Some(permit) = self.limiter.try_acquire_connection() else {
+                        warn!("Max connections reached, rejecting connection from: {}", addr);
+                        drop(stream);
+                        continue;
+                    };
+
                     let server = self.clone();
                     tokio::spawn(async move {
                         if let Err(e) = server.handle_connection(stream, addr).await {
                             error!("Connection error for {}: {}", addr, e);
                         }
+                        drop(permit);
                     });
                 }
                 Err(e) => {
@@ -204,7 +271,8 @@ impl RpcServer {
             stats.total_connections += 1;
         }
 
-        let mut framed = Framed::new(stream, JsonRpcCodec);
+        let mut framed = Framed::new(stream, RpcCodec::new(WireFormat::Json));
+        let mut context = RequestContext::new(conn_id.clone()).with_peer_addr(addr);
 
         // 处理消息循环
         let result = loop {
@@ -212,15 +280,35 @@ impl RpcServer {
                 Some(Ok(message)) => {
                     debug!("Received message from {}: {:?}", addr, message);
                     
-                    match self.process_message(message).await {
+                    match self.process_message(message, &mut context).await {
                         Ok(Some(response)) => {
                             if let Err(e) = framed.send(response).await {
                                 error!("Failed to send response to {}: {}", addr, e);
                                 break Err(e);
                             }
+
+                            if context.closing {
+                                break Ok(());
+                            }
+
+                            // 协商响应以原格式发出后再切换编码
+                            if framed.codec().format() != context.wire_format {
+                                info!("Connection {} switched to {} encoding", conn_id, context.wire_format.as_str());
+                                framed.codec_mut().set_format(context.wire_format);
+                            }
+                            if framed.codec().compression() != context.compression {
+                                info!("Connection {} switched to {} compression", conn_id, context.compression.as_str());
+                                let compressor = (context.compression != Compression::None).then(|| {
+                                    FrameCompressor::new(context.compression, &self.config.compression, self.compression_metrics.clone())
+                                });
+                                framed.codec_mut().set_compressor(compressor);
+                            }
                         }
                         Ok(None) => {
                             // 通知消息，无需响应
+                            if context.closing {
+                                break Ok(());
+                            }
                         }
                         Err(e) => {
                             error!("Error processing message from {}: {}", addr, e);
@@ -250,7 +338,7 @@ impl RpcServer {
     }
 
     /// 处理 RPC 消息
-    async fn process_message(&self, message: RpcMessage) -> RpcResult<Option<ServerMessage>> {
+    async fn process_message(&self, message: RpcMessage, context: &mut RequestContext) -> RpcResult<Option<ServerMessage>> {
         // 验证消息
         if let Err(e) = message.validate() {
             return Ok(Some(ServerMessage::Response(RpcResponseMessage::Single(RpcResponse::error(
@@ -261,14 +349,24 @@ impl RpcServer {
 
         match message {
             RpcMessage::Single(request) => {
-                let response = self.process_request(request).await;
+                let response = self.process_request(request, context).await;
                 Ok(response.map(|r| ServerMessage::Response(RpcResponseMessage::Single(r))))
             }
             RpcMessage::Batch(requests) => {
                 let mut responses = Vec::new();
-                
-                for request in requests {
-                    if let Some(response) = self.process_request(request).await {
+                let max_batch_size = self.limiter.limits().max_batch_size;
+
+                for (index, request) in requests.into_iter().enumerate() {
+                    // 超出批量大小上限的请求不执行
+                    if index >= max_batch_size {
+                        let error = self.limiter.reject_batch_request(max_batch_size);
+                        if let Some(id) = request.get_id() {
+                            responses.push(RpcResponse::error(id.clone(), error));
+                        }
+                        continue;
+                    }
+
+                    if let Some(response) = self.process_request(request, context).await {
                         responses.push(response);
                     }
                 }
@@ -283,22 +381,39 @@ impl RpcServer {
     }
 
     /// 处理单个请求
-    async fn process_request(&self, request: RpcRequest) -> Option<RpcResponse> {
+    async fn process_request(&self, request: RpcRequest, context: &mut RequestContext) -> Option<RpcResponse> {
         // 更新统计
         {
             let mut stats = self.stats.write().await;
             stats.total_requests += 1;
         }
 
+        let outcome = if request.method == CONNECT_METHOD {
+            self.authenticate_connection(request.params.clone(), context).await
+        } else if request.method == NEGOTIATE_METHOD {
+            self.negotiate_format(request.params.as_ref(), context)
+        } else {
+            match self.limiter.acquire(&request.method).await {
+                Ok(permit) => {
+                    let result = self.execute_method(&request.method, request.params.clone(), context).await;
+                    drop(permit);
+                    result
+                }
+                Err(e) => {
+                    debug!("Shedding request {} on connection {}", request.method, context.connection_id);
+                    Err(e)
+                }
+            }
+        };
+
         // 通知请求不需要响应
         if request.is_notification() {
-            self.execute_method(&request.method, request.params).await;
             return None;
         }
 
         let id = request.get_id().cloned().unwrap_or(Value::Null);
 
-        match self.execute_method(&request.method, request.params).await {
+        match outcome {
             Ok(result) => {
                 {
                     let mut stats = self.stats.write().await;
@@ -316,13 +431,85 @@ impl RpcServer {
         }
     }
 
+    /// 处理 `rpc.connect` 握手，认证成功后将调用方绑定到连接
+    async fn authenticate_connection(&self, params: Option<Value>, context: &mut RequestContext) -> Result<Value, RpcError> {
+        let Some(auth) = &self.auth else {
+            return Ok(serde_json::json!({ "authenticated": false }));
+        };
+
+        let token = params
+            .as_ref()
+            .and_then(|p| p.get("token"))
+            .and_then(Value::as_str)
+            .ok_or_else(|| RpcError::invalid_params("Missing 'token'"))?;
+
+        match auth.authenticator.authenticate(token).await {
+            Ok(principal) => {
+                if let (Some(acl), Some(peer), Some(tenant_id)) =
+                    (&self.network_acl, context.peer_addr, principal.tenant_id.as_deref())
+                {
+                    if !acl.check(peer.ip(), Some(tenant_id)).is_allowed() {
+                        warn!("Connection {} from {} rejected by network ACL of tenant {}", context.connection_id, peer, tenant_id);
+                        self.stats.write().await.rejected_connections_acl += 1;
+                        context.principal = None;
+                        context.closing = true;
+                        return Err(RpcError::forbidden(CONNECT_METHOD));
+                    }
+                }
+                info!("Connection {} authenticated as {}", context.connection_id, principal.subject);
+                let result = serde_json::json!({
+                    "authenticated": true,
+                    "subject": principal.subject,
+                    "tenant_id": principal.tenant_id,
+                });
+                context.principal = Some(principal);
+                Ok(result)
+            }
+            Err(e) => {
+                warn!("Authentication failed for connection {}", context.connection_id);
+                context.principal = None;
+                Err(e)
+            }
+        }
+    }
+
+    /// 处理 `rpc.negotiate`，选择双方都支持的线路编码与压缩算法
+    ///
+    /// 压缩只在长度前缀分帧下启用，JSON 连接总是不压缩。
+    fn negotiate_format(&self, params: Option<&Value>, context: &mut RequestContext) -> Result<Value, RpcError> {
+        let offered: Vec<WireFormat> = match params.and_then(|p| p.get("formats")) {
+            Some(formats) => serde_json::from_value(formats.clone())
+                .map_err(|e| RpcError::invalid_params(&format!("Invalid 'formats': {}", e)))?,
+            None => Vec::new(),
+        };
+        let offered_compression: Vec<Compression> = match params.and_then(|p| p.get("compression")) {
+            Some(compression) => serde_json::from_value(compression.clone())
+                .map_err(|e| RpcError::invalid_params(&format!("Invalid 'compression': {}", e)))?,
+            None => Vec::new(),
+        };
+
+        context.wire_format = WireFormat::negotiate(&offered, &self.config.wire_formats);
+        context.compression = match context.wire_format {
+            WireFormat::Json => Compression::None,
+            WireFormat::MsgPack => Compression::negotiate(&offered_compression, &self.config.compression.algorithms),
+        };
+        Ok(serde_json::json!({ "format": context.wire_format, "compression": context.compression }))
+    }
+
     /// 执行方法调用
-    async fn execute_method(&self, method: &str, params: Option<Value>) -> Result<Value, RpcError> {
-        if let Some(handler) = self.handlers.get(method) {
-            handler.handle(method, params).await
-        } else {
-            Err(RpcError::method_not_found(method))
+    async fn execute_method(&self, method: &str, params: Option<Value>, context: &RequestContext) -> Result<Value, RpcError> {
+        let handler = self.handlers.get(method)
+            .map(|entry| entry.value().clone())
+            .ok_or_else(|| RpcError::method_not_found(method))?;
+
+        if let Some(auth) = &self.auth {
+            auth.policy.authorize(method, context.principal.as_ref())?;
+        }
+        if let Some(guard) = &self.method_guard {
+            guard.check(method, context).await?;
         }
+
+        handler.handle_with_context(method, params, context).await
     }
 
     /// 注册内置方法
@@ -371,9 +558,11 @@ impl RpcServer {
             {
                 let stats = self.stats.clone();
                 let connections = self.connections.clone();
+                let limiter = self.limiter.clone();
                 move |_params| {
                     let stats = stats.clone();
                     let connections = connections.clone();
+                    let limiter = limiter.clone();
                     Box::pin(async move {
                         let stats = stats.read().await;
                         Ok(serde_json::json!({
@@ -382,7 +571,8 @@ impl RpcServer {
                             "failed_requests": stats.failed_requests,
                             "active_connections": stats.active_connections,
                             "total_connections": stats.total_connections,
-                            "connection_count": connections.len()
+                            "connection_count": connections.len(),
+                            "limits": limiter.metrics()
                         }))
                     })
                 }
@@ -395,6 +585,16 @@ impl RpcServer {
     pub async fn get_stats(&self) -> ServerStats {
         self.stats.read().await.clone()
     }
+
+    /// 获取限流指标
+    pub fn limit_metrics(&self) -> LimitMetrics {
+        self.limiter.metrics()
+    }
+
+    /// 获取所有连接发送消息的压缩指标
+    pub fn compression_stats(&self) -> CompressionStats {
+        self.compression_metrics.snapshot()
+    }
 }
 
 #[cfg(test)]
@@ -410,6 +610,226 @@ mod tests {
         assert!(server.registered_methods().len() >= 3); // 内置方法
     }
 
+    #[tokio::test]
+    async fn test_method_authorization() {
+        use crate::auth::{AuthorizationPolicy, MethodPolicy, RpcPrincipal, StaticTokenAuthenticator};
+        use crate::protocol::FunctionHandler;
+
+        let authenticator = StaticTokenAuthenticator::new()
+            .with_token("user-token".to_string(), RpcPrincipal::new("alice".to_string()))
+            .with_token(
+                "admin-token".to_string(),
+                RpcPrincipal::new("root".to_string()).with_role("admin".to_string()),
+            );
+        let policy = AuthorizationPolicy::default()
+            .with_rule("admin.*".to_string(), MethodPolicy::RequireRoles(vec!["admin".to_string()]));
+        let server = RpcServer::new(ServerConfig::default())
+            .with_auth(RpcAuthConfig::new(Arc::new(authenticator)).with_policy(policy));
+        server.register_handler(Arc::new(FunctionHandler::new(
+            "admin.reset".to_string(),
+            |_params| async { Ok(json!("reset")) },
+        )));
+
+        let call = |method: &str, params: Option<Value>| RpcRequest::new(method.to_string(), params);
+        let mut context = RequestContext::new("conn-1".to_string());
+
+        // 未认证时只能访问公开方法
+        let response = server.process_request(call("rpc.stats", None), &mut context).await.unwrap();
+        assert_eq!(response.error.unwrap().code, RpcError::unauthenticated().code);
+        let response = server.process_request(call("rpc.ping", None), &mut context).await.unwrap();
+        assert!(response.result.is_some());
+
+        // 错误的 token 被拒绝
+        let response = server
+            .process_request(call(CONNECT_METHOD, Some(json!({"token": "bad"}))), &mut context)
+            .await
+            .unwrap();
+        assert!(response.error.is_some());
+        assert!(context.principal.is_none());
+
+        let response = server
+            .process_request(call(CONNECT_METHOD, Some(json!({"token": "user-token"}))), &mut context)
+            .await
+            .unwrap();
+        assert_eq!(response.result.unwrap()["subject"], "alice");
+        assert!(server.process_request(call("rpc.stats", None), &mut context).await.unwrap().result.is_some());
+
+        let response = server.process_request(call("admin.reset", None), &mut context).await.unwrap();
+        assert_eq!(response.error.unwrap().code, RpcError::forbidden("admin.reset").code);
+
+        // 调用方会传递到处理器上下文
+        let mut admin_context = RequestContext::new("conn-2".to_string());
+        server
+            .process_request(call(CONNECT_METHOD, Some(json!({"token": "admin-token"}))), &mut admin_context)
+            .await
+            .unwrap();
+        assert_eq!(admin_context.principal.as_ref().unwrap().subject, "root");
+        let response = server.process_request(call("admin.reset", None), &mut admin_context).await.unwrap();
+        assert_eq!(response.result.unwrap(), json!("reset"));
+    }
+
+    #[tokio::test]
+    async fn test_method_guard() {
+        use crate::error::SERVICE_UNAVAILABLE_CODE;
+        use crate::protocol::FunctionHandler;
+
+        struct PausedExecutions;
+
+        #[async_trait::async_trait]
+        impl RpcMethodGuard for PausedExecutions {
+            async fn check(&self, method: &str, _context: &RequestContext) -> Result<(), RpcError> {
+                match method.starts_with("tools.execute") {
+                    true => Err(RpcError::unavailable("executions are paused")),
+                    false => Ok(()),
+                }
+            }
+        }
+
+        let server = RpcServer::new(ServerConfig::default()).with_method_guard(Arc::new(PausedExecutions));
+        server.register_handler(Arc::new(FunctionHandler::new(
+            "tools.execute".to_string(),
+            |_params| async { Ok(json!("done")) },
+        )));
+        let mut context = RequestContext::new("conn-1".to_string());
+
+        let request = RpcRequest::new("tools.execute".to_string(), None);
+        let error = server.process_request(request, &mut context).await.unwrap().error.unwrap();
+        assert_eq!(error.code, SERVICE_UNAVAILABLE_CODE);
+        assert_eq!(error.data.unwrap()["reason"], "executions are paused");
+        let request = RpcRequest::new("rpc.ping".to_string(), None);
+        assert!(server.process_request(request, &mut context).await.unwrap().result.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_connect_handshake_passes_message_validation() {
+        use crate::auth::{RpcPrincipal, StaticTokenAuthenticator};
+
+        let authenticator = StaticTokenAuthenticator::new()
+            .with_token("user-token".to_string(), RpcPrincipal::new("alice".to_string()));
+        let server = RpcServer::new(ServerConfig::default())
+            .with_auth(RpcAuthConfig::new(Arc::new(authenticator)));
+        let mut context = RequestContext::new("conn-1".to_string());
+
+        // 与真实连接相同，经过 process_message 的消息校验
+        let message = RpcMessage::Single(RpcRequest::new(
+            CONNECT_METHOD.to_string(),
+            Some(json!({"token": "user-token"})),
+        ));
+        let Some(ServerMessage::Response(RpcResponseMessage::Single(response))) =
+            server.process_message(message, &mut context).await.unwrap()
+        else {
+            panic!("expected single response");
+        };
+        assert_eq!(response.result.unwrap()["subject"], "alice");
+        assert_eq!(context.principal.as_ref().unwrap().subject, "alice");
+
+        // 其他保留方法仍被拒绝
+        let message = RpcMessage::Single(RpcRequest::new("rpc.shutdown".to_string(), None));
+        let Some(ServerMessage::Response(RpcResponseMessage::Single(response))) =
+            server.process_message(message, &mut context).await.unwrap()
+        else {
+            panic!("expected single response");
+        };
+        assert_eq!(response.error.unwrap().code, RpcError::invalid_request().code);
+    }
+
+    #[tokio::test]
+    async fn test_tenant_network_acl_checked_on_connect() {
+        use crate::auth::{RpcPrincipal, StaticTokenAuthenticator};
+        use stepflow_core::AclRules;
+
+        let authenticator = StaticTokenAuthenticator::new()
+            .with_token("acme-token".to_string(), RpcPrincipal::new("alice".to_string()).with_tenant("acme".to_string()))
+            .with_token("other-token".to_string(), RpcPrincipal::new("bob".to_string()).with_tenant("other".to_string()));
+        let acl = Arc::new(NetworkAcl::default());
+        acl.set_tenant_rules("acme", None, AclRules { allow: vec!["10.0.0.0/8".parse().unwrap()], deny: vec![] });
+        let server = RpcServer::new(ServerConfig::default())
+            .with_auth(RpcAuthConfig::new(Arc::new(authenticator)))
+            .with_network_acl(acl.clone());
+        let connect = |token: &str| RpcRequest::new(CONNECT_METHOD.to_string(), Some(json!({"token": token})));
+        let peer = |ip: &str| RequestContext::new("conn-1".to_string()).with_peer_addr(format!("{}:4000", ip).parse().unwrap());
+
+        let mut context = peer("192.168.0.1");
+        let response = server.process_request(connect("acme-token"), &mut context).await.unwrap();
+        assert_eq!(response.error.unwrap().code, RpcError::forbidden(CONNECT_METHOD).code);
+        assert!(context.principal.is_none());
+        assert!(context.closing);
+
+        let mut context = peer("10.1.2.3");
+        let response = server.process_request(connect("acme-token"), &mut context).await.unwrap();
+        assert!(response.result.is_some());
+
+        // Tenants without a list are unaffected
+        let mut context = peer("192.168.0.1");
+        let response = server.process_request(connect("other-token"), &mut context).await.unwrap();
+        assert!(response.result.is_some());
+
+        assert_eq!(acl.metrics().rejected_tenant, 1);
+        assert_eq!(server.get_stats().await.rejected_connections_acl, 1);
+    }
+
+    #[tokio::test]
+    async fn test_global_network_acl_rejects_on_accept() {
+        use stepflow_core::NetworkAclConfig;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let acl = Arc::new(NetworkAcl::new(&NetworkAclConfig {
+            deny: vec!["127.0.0.0/8".parse().unwrap()],
+            ..NetworkAclConfig::default()
+        }));
+        let probe = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let bind_addr = probe.local_addr().unwrap();
+        drop(probe);
+        let server = Arc::new(RpcServer::new(ServerConfig { bind_addr, ..ServerConfig::default() }).with_network_acl(acl.clone()));
+        tokio::spawn(server.clone().serve());
+
+        let mut stream = loop {
+            match TcpStream::connect(bind_addr).await {
+                Ok(stream) => break stream,
+                Err(_) => tokio::time::sleep(std::time::Duration::from_millis(10)).await,
+            }
+        };
+        let _ = stream.write_all(b"{\"jsonrpc\":\"2.0\",\"method\":\"rpc.ping\",\"id\":1}\n").await;
+        let mut buf = Vec::new();
+        let read = tokio::time::timeout(std::time::Duration::from_secs(5), stream.read_to_end(&mut buf)).await;
+        assert!(matches!(read, Ok(Ok(0)) | Ok(Err(_))), "connection should be closed without a response");
+        assert_eq!(acl.metrics().rejected_global, 1);
+        assert_eq!(server.get_stats().await.rejected_connections_acl, 1);
+    }
+
+    #[tokio::test]
+    async fn test_batch_size_limit() {
+        let config = ServerConfig {
+            limits: ServerLimits {
+                max_batch_size: 2,
+                ..ServerLimits::default()
+            },
+            ..ServerConfig::default()
+        };
+        let server = RpcServer::new(config);
+        let mut context = RequestContext::new("conn-1".to_string());
+
+        let batch = (0..4).map(|_| RpcRequest::new("rpc.ping".to_string(), None)).collect();
+        let Some(ServerMessage::Response(RpcResponseMessage::Batch(responses))) =
+            server.process_message(RpcMessage::Batch(batch), &mut context).await.unwrap()
+        else {
+            panic!("expected batch response");
+        };
+
+        assert_eq!(responses.len(), 4);
+        assert!(responses[..2].iter().all(|r| r.result.is_some()));
+        for response in &responses[2..] {
+            let error = response.error.as_ref().unwrap();
+            assert_eq!(error.code, RpcError::invalid_request().code);
+            assert_eq!(error.data.as_ref().unwrap()["reason"], "batch_too_large");
+        }
+
+        let metrics = server.limit_metrics();
+        assert_eq!(metrics.rejected_batch_requests, 2);
+        assert!(metrics.methods.get("rpc.ping").is_none_or(|m| m.shed == 0));
+        assert_eq!(metrics.in_flight_requests, 0);
+    }
+
     #[test]
     fn test_codec() {
         let mut codec = JsonRpcCodec;
@@ -0,0 +1,28 @@
+//! Error types for the embedded runtime
+
+use stepflow_core::StepflowError;
+use stepflow_executor::ExecutorError;
+use stepflow_registry::RegistryError;
+use stepflow_sandbox::SandboxError;
+
+/// Runtime error type
+#[derive(Debug, thiserror::Error)]
+pub enum RuntimeError {
+    #[error("Configuration error: {0}")]
+    ConfigurationError(String),
+
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] StepflowError),
+
+    #[error("Registry error: {0}")]
+    RegistryError(#[from] RegistryError),
+
+    #[error("Executor error: {0}")]
+    ExecutorError(#[from] ExecutorError),
+
+    #[error("Sandbox error: {0}")]
+    SandboxError(#[from] SandboxError),
+}
+
+/// Result type for runtime operations
+pub type RuntimeResult<T> = Result<T, RuntimeError>;
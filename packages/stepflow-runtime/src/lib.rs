@@ -0,0 +1,26 @@
+//! Stepflow Runtime - Embedded runtime for the Stepflow Tool System
+//!
+//! This crate wires the database, registry, executor and sandbox together
+//! without the HTTP server, so Rust applications can execute tools in process:
+//!
+//! ```no_run
+//! use stepflow_registry::Registry;
+//!
+//! # async fn run() -> stepflow_runtime::RuntimeResult<()> {
+//! let runtime = stepflow_runtime::StepflowRuntime::builder()
+//!     .sqlite_path("stepflow.db")
+//!     .build()
+//!     .await?;
+//! let tools = runtime.registry().list_tools().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod errors;
+pub mod runtime;
+
+pub use errors::{RuntimeError, RuntimeResult};
+pub use runtime::{StepflowRuntime, StepflowRuntimeBuilder};
+
+/// Version information
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
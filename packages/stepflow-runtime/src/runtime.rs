@@ -0,0 +1,309 @@
+//! Runtime facade and its builder
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use stepflow_database::{DatabaseConfig, FeatureFlagRepository, MasterKeyring, MigrationManager, SqliteDatabase};
+use stepflow_executor::{
+    create_executor, ExecutionRequest, Executor, ExecutorImpl, SchedulerConfig, WorkerPoolConfig,
+};
+use stepflow_core::{ExecutionId, ExecutionResult, FlagDefinition, Flags};
+use stepflow_registry::{Registry, RegistryCacheConfig, RegistryImpl};
+use stepflow_sandbox::{IntegrityVerifier, Sandbox, SandboxImpl, SandboxImplConfig};
+use tracing::info;
+
+use crate::errors::{RuntimeError, RuntimeResult};
+
+/// Where the runtime keeps its data
+#[derive(Debug, Clone)]
+enum Storage {
+    /// A SQLite file, created if missing
+    Path(PathBuf),
+    /// A SQLite connection URL, used as is
+    Url(String),
+    /// A private in-memory database, gone when the runtime is dropped
+    InMemory,
+}
+
+impl Storage {
+    fn url(&self) -> String {
+        match self {
+            Storage::Path(path) => format!("sqlite://{}?mode=rwc", path.display()),
+            Storage::Url(url) => url.clone(),
+            Storage::InMemory => "sqlite::memory:".to_string(),
+        }
+    }
+}
+
+/// Builder for [`StepflowRuntime`]
+#[derive(Debug, Clone, Default)]
+pub struct StepflowRuntimeBuilder {
+    storage: Option<Storage>,
+    keyring: Option<Arc<MasterKeyring>>,
+    slow_query_threshold: Option<Duration>,
+    run_migrations: Option<bool>,
+    registry_cache_config: Option<RegistryCacheConfig>,
+    scheduler_config: Option<SchedulerConfig>,
+    worker_pool_config: Option<WorkerPoolConfig>,
+    sandbox_config: Option<SandboxImplConfig>,
+    without_sandbox: bool,
+    feature_flags: Vec<FlagDefinition>,
+}
+
+impl StepflowRuntimeBuilder {
+    /// Store data in the SQLite file at `path`, creating it if missing
+    pub fn sqlite_path(mut self, path: impl AsRef<Path>) -> Self {
+        self.storage = Some(Storage::Path(path.as_ref().to_path_buf()));
+        self
+    }
+
+    /// Store data in the database at a SQLite connection URL
+    pub fn database_url(mut self, url: impl Into<String>) -> Self {
+        self.storage = Some(Storage::Url(url.into()));
+        self
+    }
+
+    /// Keep all data in memory
+    pub fn in_memory(mut self) -> Self {
+        self.storage = Some(Storage::InMemory);
+        self
+    }
+
+    /// Encrypt tenant secrets with data keys wrapped by `keyring`
+    pub fn encryption(mut self, keyring: MasterKeyring) -> Self {
+        self.keyring = Some(Arc::new(keyring));
+        self
+    }
+
+    /// Capture statements slower than `threshold` in the slow query log, 1s by default
+    pub fn slow_query_threshold(mut self, threshold: Duration) -> Self {
+        self.slow_query_threshold = Some(threshold);
+        self
+    }
+
+    /// Whether to bring the schema up to date on build, on by default
+    pub fn run_migrations(mut self, run: bool) -> Self {
+        self.run_migrations = Some(run);
+        self
+    }
+
+    /// Configure the registry tool cache, e.g. to share it between instances
+    pub fn registry_cache_config(mut self, config: RegistryCacheConfig) -> Self {
+        self.registry_cache_config = Some(config);
+        self
+    }
+
+    pub fn scheduler_config(mut self, config: SchedulerConfig) -> Self {
+        self.scheduler_config = Some(config);
+        self
+    }
+
+    pub fn worker_pool_config(mut self, config: WorkerPoolConfig) -> Self {
+        self.worker_pool_config = Some(config);
+        self
+    }
+
+    pub fn sandbox_config(mut self, config: SandboxImplConfig) -> Self {
+        self.sandbox_config = Some(config);
+        self.without_sandbox = false;
+        self
+    }
+
+    /// Run without a sandbox, for hosts without a container runtime
+    pub fn without_sandbox(mut self) -> Self {
+        self.without_sandbox = true;
+        self
+    }
+
+    /// Feature flag defaults, typically from the config file
+    ///
+    /// Flags stored in the database take precedence.
+    pub fn feature_flags(mut self, flags: Vec<FlagDefinition>) -> Self {
+        self.feature_flags = flags;
+        self
+    }
+
+    /// Open the database and start every component
+    ///
+    /// A storage location must be chosen with [`sqlite_path`](Self::sqlite_path),
+    /// [`database_url`](Self::database_url) or [`in_memory`](Self::in_memory).
+    pub async fn build(self) -> RuntimeResult<StepflowRuntime> {
+        let storage = self.storage.ok_or_else(|| {
+            RuntimeError::ConfigurationError("no storage configured; call sqlite_path, database_url or in_memory".to_string())
+        })?;
+
+        let defaults = DatabaseConfig::default();
+        let mut db = SqliteDatabase::with_config(DatabaseConfig {
+            url: storage.url(),
+            slow_query_threshold: self.slow_query_threshold.unwrap_or(defaults.slow_query_threshold),
+            ..defaults
+        })
+        .await?;
+        if let Some(keyring) = self.keyring {
+            db = db.with_encryption(keyring);
+        }
+        let db = Arc::new(db);
+        if self.run_migrations.unwrap_or(true) {
+            MigrationManager::run_migrations(&db).await?;
+        }
+
+        let registry = Arc::new(
+            RegistryImpl::with_cache_config(db.clone(), self.registry_cache_config.unwrap_or_default()).await?,
+        );
+        let stored_flags = FeatureFlagRepository::new((*db).clone()).list().await?;
+        let flags = Flags::with_defaults(self.feature_flags, stored_flags);
+        let sandbox = if self.without_sandbox {
+            None
+        } else {
+            let sandbox = SandboxImpl::new(db.clone(), self.sandbox_config.unwrap_or_default()).await?;
+            Some(Arc::new(sandbox))
+        };
+        let mut executor = create_executor(
+            db.clone(),
+            registry.clone(),
+            self.scheduler_config,
+            self.worker_pool_config,
+        )?.with_flags(flags.clone());
+        // Tool images are verified against the digests stored in the registry
+        if let Some(sandbox) = &sandbox {
+            executor = executor.with_integrity_verifier(IntegrityVerifier::new(sandbox.clone()));
+        }
+        let executor = Arc::new(executor);
+
+        info!("Stepflow runtime started on {:?}", storage);
+        Ok(StepflowRuntime { db, registry, executor, sandbox, flags })
+    }
+}
+
+/// Database, registry, executor and sandbox wired together for in-process use
+///
+/// Cloning is cheap; clones share the same components.
+#[derive(Clone)]
+pub struct StepflowRuntime {
+    db: Arc<SqliteDatabase>,
+    registry: Arc<RegistryImpl>,
+    executor: Arc<ExecutorImpl>,
+    sandbox: Option<Arc<SandboxImpl>>,
+    flags: Flags,
+}
+
+impl StepflowRuntime {
+    pub fn builder() -> StepflowRuntimeBuilder {
+        StepflowRuntimeBuilder::default()
+    }
+
+    pub fn database(&self) -> Arc<SqliteDatabase> {
+        self.db.clone()
+    }
+
+    pub fn registry(&self) -> Arc<dyn Registry> {
+        self.registry.clone()
+    }
+
+    pub fn executor(&self) -> Arc<dyn Executor> {
+        self.executor.clone()
+    }
+
+    /// The sandbox, `None` when built [`without_sandbox`](StepflowRuntimeBuilder::without_sandbox)
+    pub fn sandbox(&self) -> Option<Arc<dyn Sandbox>> {
+        self.sandbox.clone().map(|sandbox| sandbox as Arc<dyn Sandbox>)
+    }
+
+    /// Image integrity verifier shared with the executor, `None` without a sandbox
+    pub fn integrity_verifier(&self) -> Option<Arc<IntegrityVerifier>> {
+        self.executor.integrity_verifier().cloned()
+    }
+
+    /// Feature flags shared with the executor
+    pub fn flags(&self) -> Flags {
+        self.flags.clone()
+    }
+
+    /// Execute a tool and wait for its result
+    pub async fn execute_tool(&self, request: ExecutionRequest) -> RuntimeResult<ExecutionResult> {
+        Ok(self.executor.execute_tool(request).await?)
+    }
+
+    /// Submit a tool execution and return its id without waiting
+    pub async fn execute_tool_async(&self, request: ExecutionRequest) -> RuntimeResult<ExecutionId> {
+        Ok(self.executor.execute_tool_async(request).await?)
+    }
+
+    /// Whether the database and executor are healthy
+    pub async fn health_check(&self) -> RuntimeResult<bool> {
+        Ok(self.db.health_check().await? && self.executor.health_check().await?)
+    }
+
+    /// Close the database connections
+    ///
+    /// Other clones of the runtime can no longer use the database afterwards.
+    pub async fn shutdown(&self) -> RuntimeResult<()> {
+        info!("Shutting down Stepflow runtime");
+        Ok(self.db.close().await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stepflow_core::ToolId;
+    use stepflow_executor::ExecutionContext;
+
+    #[tokio::test]
+    async fn test_build_requires_storage() {
+        let err = StepflowRuntime::builder().without_sandbox().build().await.err().unwrap();
+        assert!(matches!(err, RuntimeError::ConfigurationError(_)));
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_runtime() {
+        let runtime = StepflowRuntime::builder().in_memory().without_sandbox().build().await.unwrap();
+
+        assert!(runtime.sandbox().is_none());
+        assert!(runtime.integrity_verifier().is_none());
+        assert!(runtime.health_check().await.unwrap());
+        assert!(runtime.registry().list_tools().await.unwrap().is_empty());
+
+        let request = ExecutionRequest::builder()
+            .tool_id(ToolId::from_string("missing".to_string()))
+            .context(ExecutionContext::new("test-user", "test-tenant"))
+            .build()
+            .unwrap();
+        assert!(runtime.execute_tool(request).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_path_creates_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stepflow.db");
+
+        let runtime = StepflowRuntime::builder().sqlite_path(&path).without_sandbox().build().await.unwrap();
+        assert!(path.exists());
+        assert!(runtime.health_check().await.unwrap());
+        runtime.shutdown().await.unwrap();
+
+        // Reopening an existing database runs the migrations again without harm
+        let runtime = StepflowRuntime::builder().sqlite_path(&path).without_sandbox().build().await.unwrap();
+        assert!(runtime.health_check().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_stored_flags_override_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stepflow.db");
+        let defaults = vec![FlagDefinition::new("new_scheduler").with_rollout(100)];
+
+        let runtime = StepflowRuntime::builder().sqlite_path(&path).without_sandbox().feature_flags(defaults.clone()).build().await.unwrap();
+        let tenant = stepflow_core::FlagContext::tenant("tenant-1");
+        assert!(runtime.flags().is_enabled(stepflow_core::Flag::NEW_SCHEDULER, &tenant));
+        FeatureFlagRepository::new((*runtime.database()).clone())
+            .upsert(&FlagDefinition::new("new_scheduler").with_tenant("tenant-1", false), None)
+            .await
+            .unwrap();
+        runtime.shutdown().await.unwrap();
+
+        let runtime = StepflowRuntime::builder().sqlite_path(&path).without_sandbox().feature_flags(defaults).build().await.unwrap();
+        assert!(!runtime.flags().is_enabled(stepflow_core::Flag::NEW_SCHEDULER, &tenant));
+    }
+}
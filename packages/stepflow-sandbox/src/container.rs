@@ -8,7 +8,7 @@ use bollard::container::{
     StartContainerOptions, StopContainerOptions, WaitContainerOptions,
 };
 use bollard::exec::{CreateExecOptions, StartExecResults};
-use bollard::image::CreateImageOptions;
+use bollard::image::{CommitContainerOptions, CreateImageOptions, RemoveImageOptions};
 use bollard::models::{ContainerSummary, HostConfig, Mount, MountTypeEnum, PortBinding};
 use bollard::Docker;
 use chrono::Utc;
@@ -157,8 +157,12 @@ impl ContainerManagerImpl {
         Ok(container_config)
     }
 
-    /// 拉取镜像
-    async fn pull_image(&self, image: &str) -> ContainerResult<()> {
+    /// 拉取镜像，本地已有时跳过（例如快照镜像）
+    pub async fn pull_image(&self, image: &str) -> ContainerResult<()> {
+        if self.docker.inspect_image(image).await.is_ok() {
+            debug!("Image {} is available locally", image);
+            return Ok(());
+        }
         info!("Pulling image: {}", image);
         
         let options = Some(CreateImageOptions {
@@ -184,6 +188,68 @@ impl ContainerManagerImpl {
         Ok(())
     }
 
+    /// 本地镜像的 ID 与仓库摘要，镜像不存在时返回 `None`
+    pub async fn image_digests(&self, image: &str) -> ContainerResult<Option<Vec<String>>> {
+        match self.docker.inspect_image(image).await {
+            Ok(inspect) => {
+                let mut digests: Vec<String> = inspect.id.into_iter().collect();
+                digests.extend(inspect.repo_digests.unwrap_or_default());
+                Ok(Some(digests))
+            }
+            Err(bollard::errors::Error::DockerResponseServerError { status_code: 404, .. }) => Ok(None),
+            Err(e) => Err(ContainerError::DockerError(e.to_string())),
+        }
+    }
+
+    /// 将容器的文件系统提交为镜像，返回镜像名
+    pub async fn commit_container(&self, container_id: &ContainerId, repo: &str, tag: &str) -> ContainerResult<String> {
+        let options = CommitContainerOptions {
+            container: container_id.as_str(),
+            repo,
+            tag,
+            comment: "stepflow sandbox snapshot",
+            author: "stepflow",
+            pause: true,
+            changes: None,
+        };
+        self.docker.commit_container(options, Config::<String>::default()).await
+            .map_err(|e| ContainerError::DockerError(e.to_string()))?;
+        Ok(format!("{}:{}", repo, tag))
+    }
+
+    /// 删除镜像
+    pub async fn remove_image(&self, image: &str) -> ContainerResult<()> {
+        let options = RemoveImageOptions { force: true, noprune: false };
+        self.docker.remove_image(image, Some(options), None).await
+            .map_err(|e| ContainerError::DockerError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// 通过 CRIU 将运行中容器的进程状态写入 `checkpoint_dir`，容器继续运行
+    ///
+    /// Docker Engine API 没有稳定的检查点接口，这里调用 `docker checkpoint`，需要守护进程开启实验特性。
+    #[cfg(target_os = "linux")]
+    pub async fn checkpoint_container(&self, container_id: &ContainerId, checkpoint_dir: &str, name: &str) -> ContainerResult<()> {
+        run_docker_cli(&["checkpoint", "create", "--leave-running", "--checkpoint-dir", checkpoint_dir, container_id.as_str(), name]).await
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub async fn checkpoint_container(&self, _container_id: &ContainerId, _checkpoint_dir: &str, _name: &str) -> ContainerResult<()> {
+        Err(ContainerError::DockerError("Process checkpoints require CRIU, which is only available on Linux".to_string()))
+    }
+
+    /// 从检查点启动已停止的容器
+    #[cfg(target_os = "linux")]
+    pub async fn start_from_checkpoint(&self, container_id: &ContainerId, checkpoint_dir: &str, name: &str) -> ContainerResult<()> {
+        run_docker_cli(&["start", "--checkpoint-dir", checkpoint_dir, "--checkpoint", name, container_id.as_str()]).await
+            .map_err(|e| ContainerError::ContainerStartFailed(e.to_string()))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub async fn start_from_checkpoint(&self, _container_id: &ContainerId, _checkpoint_dir: &str, _name: &str) -> ContainerResult<()> {
+        Err(ContainerError::ContainerStartFailed("Process checkpoints require CRIU, which is only available on Linux".to_string()))
+    }
+
     /// 将容器摘要转换为容器信息
     fn container_summary_to_info(&self, summary: ContainerSummary) -> ContainerInfo {
         let status = match summary.state.as_deref() {
@@ -476,4 +542,18 @@ impl ContainerManager for ContainerManagerImpl {
         info!("Resumed container: {}", container_id.as_str());
         Ok(())
     }
-} 
\ No newline at end of file
+} 
+
+#[cfg(target_os = "linux")]
+async fn run_docker_cli(args: &[&str]) -> ContainerResult<()> {
+    let output = tokio::process::Command::new("docker").args(args).output().await
+        .map_err(|e| ContainerError::DockerError(format!("Failed to run docker {}: {}", args[0], e)))?;
+    if !output.status.success() {
+        return Err(ContainerError::DockerError(format!(
+            "docker {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim(),
+        )));
+    }
+    Ok(())
+}
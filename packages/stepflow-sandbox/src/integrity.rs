@@ -0,0 +1,352 @@
+//! 镜像完整性：执行前按注册表元数据中的摘要校验工具镜像，并支持在执行前把镜像预取到本机
+//!
+//! 固定摘要随工具版本保存在注册表中（[`ToolInfo::image`]），通过 [`ImagePinSource`] 读取；
+//! [`IntegrityVerifier::pin`] 可以在此之上临时覆盖。校验失败时记录安全事件并拒绝执行，不会创建沙箱。
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use stepflow_core::ToolInfo;
+use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
+
+use crate::errors::*;
+use crate::sandbox::{Sandbox, SecurityManager};
+use crate::types::*;
+
+/// 保留的最近完整性事件数
+const MAX_EVENTS: usize = 1000;
+
+/// 能拉取并检查镜像的沙箱
+#[async_trait]
+pub trait ImageSandbox: Sandbox {
+    /// 拉取镜像，本地已有时跳过
+    async fn pull_image(&self, image: &str) -> SandboxResult<()>;
+
+    /// 本地镜像的摘要（镜像 ID 与仓库摘要），镜像不存在时返回 `None`
+    async fn image_digests(&self, image: &str) -> SandboxResult<Option<Vec<String>>>;
+}
+
+/// 注册表为工具版本记录的镜像及其摘要
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImagePin {
+    pub tool_id: String,
+    pub tool_version: String,
+    pub image: String,
+    /// 形如 `sha256:<hex>` 的摘要
+    pub digest: String,
+}
+
+impl ImagePin {
+    pub fn new(tool_id: impl Into<String>, tool_version: impl Into<String>, image: impl Into<String>, digest: impl Into<String>) -> Self {
+        Self {
+            tool_id: tool_id.into(),
+            tool_version: tool_version.into(),
+            image: image.into(),
+            digest: digest.into(),
+        }
+    }
+
+    /// 工具版本在注册表中记录的镜像，没有记录时返回 `None`
+    pub fn from_tool(tool: &ToolInfo) -> Option<Self> {
+        tool.image.as_ref().map(|image| Self::new(tool.id.as_str(), tool.version.to_string(), &image.image, &image.digest))
+    }
+
+    fn validate(&self) -> SandboxResult<()> {
+        let hex = self.digest.strip_prefix("sha256:").unwrap_or(&self.digest);
+        if hex.len() != 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(SandboxError::InternalError(format!(
+                "Image digest for tool {} must be a SHA-256 digest, got {}",
+                self.tool_id, self.digest,
+            )));
+        }
+        Ok(())
+    }
+
+    /// 本地摘要中是否有与固定摘要一致的
+    fn matches(&self, digests: &[String]) -> bool {
+        let expected = normalize_digest(&self.digest);
+        digests.iter().any(|digest| normalize_digest(digest) == expected)
+    }
+}
+
+/// 镜像固定记录的来源，通常是注册表中的工具元数据
+#[async_trait]
+pub trait ImagePinSource: Send + Sync {
+    /// 工具当前版本固定的镜像，没有固定时返回 `None`
+    async fn image_pin(&self, tool_id: &str) -> SandboxResult<Option<ImagePin>>;
+}
+
+/// `sha256:<hex>`、`repo@sha256:<hex>` 与裸十六进制统一为小写十六进制
+fn normalize_digest(digest: &str) -> String {
+    let digest = digest.rsplit('@').next().unwrap_or(digest);
+    digest.strip_prefix("sha256:").unwrap_or(digest).to_ascii_lowercase()
+}
+
+/// 完整性校验失败的安全事件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IntegrityEvent {
+    pub tool_id: String,
+    pub tool_version: String,
+    pub image: String,
+    pub expected_digest: String,
+    /// 本地镜像的摘要，镜像不存在时为空
+    pub actual_digests: Vec<String>,
+    pub reason: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// 单个镜像的预取结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum PrefetchOutcome {
+    /// 镜像已拉取并通过校验
+    Fetched,
+    /// 本地已有通过校验的镜像
+    AlreadyPresent,
+    /// 拉取或校验失败
+    Failed { error: String },
+}
+
+/// 预取报告
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrefetchReport {
+    pub tool_id: String,
+    pub image: String,
+    pub outcome: PrefetchOutcome,
+}
+
+/// 完整性校验统计
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IntegrityStats {
+    /// 通过校验的次数
+    pub verified: u64,
+    /// 校验失败被拒绝的次数
+    pub rejected: u64,
+    /// 预取拉取的镜像数
+    pub prefetched: u64,
+}
+
+/// 镜像完整性校验器
+pub struct IntegrityVerifier {
+    sandbox: Arc<dyn ImageSandbox>,
+    security: Option<Arc<dyn SecurityManager>>,
+    source: Option<Arc<dyn ImagePinSource>>,
+    pins: Arc<RwLock<HashMap<String, ImagePin>>>,
+    events: Arc<RwLock<Vec<IntegrityEvent>>>,
+    stats: Arc<RwLock<IntegrityStats>>,
+}
+
+impl IntegrityVerifier {
+    pub fn new(sandbox: Arc<dyn ImageSandbox>) -> Self {
+        Self {
+            sandbox,
+            security: None,
+            source: None,
+            pins: Arc::new(RwLock::new(HashMap::new())),
+            events: Arc::new(RwLock::new(Vec::new())),
+            stats: Arc::new(RwLock::new(IntegrityStats::default())),
+        }
+    }
+
+    /// 同时把校验失败记录为安全违规
+    pub fn with_security_manager(mut self, security: Arc<dyn SecurityManager>) -> Self {
+        self.security = Some(security);
+        self
+    }
+
+    /// 从 `source`（例如注册表）读取工具固定的镜像
+    pub fn with_pin_source(mut self, source: Arc<dyn ImagePinSource>) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    /// 固定工具使用的镜像摘要，替换该工具已有的记录，优先于固定记录来源
+    pub async fn pin(&self, pin: ImagePin) -> SandboxResult<()> {
+        pin.validate()?;
+        info!("Pinned image {} of tool {} {} to {}", pin.image, pin.tool_id, pin.tool_version, pin.digest);
+        self.pins.write().await.insert(pin.tool_id.clone(), pin);
+        Ok(())
+    }
+
+    /// 取消工具的镜像固定，返回是否存在
+    pub async fn unpin(&self, tool_id: &str) -> bool {
+        self.pins.write().await.remove(tool_id).is_some()
+    }
+
+    /// 工具当前固定的镜像：先查 [`pin`](Self::pin) 固定的记录，再查固定记录来源
+    pub async fn pinned(&self, tool_id: &str) -> SandboxResult<Option<ImagePin>> {
+        if let Some(pin) = self.pins.read().await.get(tool_id) {
+            return Ok(Some(pin.clone()));
+        }
+        match &self.source {
+            Some(source) => source.image_pin(tool_id).await,
+            None => Ok(None),
+        }
+    }
+
+    /// 校验工具的镜像，镜像不在本地时先拉取
+    ///
+    /// 没有固定摘要的工具不做校验。`config` 没有指定镜像时使用固定的镜像；指定了其他镜像则拒绝。
+    pub async fn verify(&self, tool_id: &str, config: &mut SandboxConfig) -> SandboxResult<()> {
+        match self.pinned(tool_id).await? {
+            Some(pin) => self.verify_pin(&pin, config).await,
+            None => Ok(()),
+        }
+    }
+
+    /// 按给定的固定记录校验镜像，用于尚未成为工具当前版本的版本（例如灰度候选版本）
+    pub async fn verify_pin(&self, pin: &ImagePin, config: &mut SandboxConfig) -> SandboxResult<()> {
+        pin.validate()?;
+        let container_config = config.container_config.get_or_insert_with(|| ContainerConfig {
+            image: pin.image.clone(),
+            ..ContainerConfig::default()
+        });
+        if container_config.image != pin.image {
+            let reason = format!("Sandbox requested image {} instead of pinned image {}", container_config.image, pin.image);
+            return Err(self.reject(pin, Vec::new(), reason).await);
+        }
+
+        let digests = self.local_digests(pin).await?;
+        if !pin.matches(&digests) {
+            let reason = format!("Digest of image {} does not match {}", pin.image, pin.digest);
+            return Err(self.reject(pin, digests, reason).await);
+        }
+
+        debug!("Verified image {} of tool {} {}", pin.image, pin.tool_id, pin.tool_version);
+        self.stats.write().await.verified += 1;
+        Ok(())
+    }
+
+    /// 校验镜像后创建沙箱；校验失败时不创建
+    pub async fn create_sandbox(&self, tool_id: &str, mut config: SandboxConfig) -> SandboxResult<SandboxId> {
+        self.verify(tool_id, &mut config).await?;
+        self.sandbox.create_sandbox(config).await
+    }
+
+    /// 在校验过镜像的沙箱中执行命令
+    pub async fn execute(&self, tool_id: &str, config: SandboxConfig, command: Command) -> SandboxResult<ExecutionResult> {
+        let sandbox_id = self.create_sandbox(tool_id, config).await?;
+        let result = self.sandbox.execute_in_sandbox(&sandbox_id, command).await;
+        if let Err(e) = self.sandbox.destroy_sandbox(&sandbox_id).await {
+            warn!("Failed to destroy sandbox {} of tool {}: {}", sandbox_id, tool_id, e);
+        }
+        result
+    }
+
+    /// 预取工具的镜像并校验，供即将到来的定时或批量执行使用
+    ///
+    /// 没有固定镜像的工具被跳过。单个镜像失败不影响其余镜像。
+    pub async fn prefetch(&self, tool_ids: &[String]) -> Vec<PrefetchReport> {
+        let mut reports = Vec::new();
+        for tool_id in tool_ids {
+            let pin = match self.pinned(tool_id).await {
+                Ok(Some(pin)) => pin,
+                Ok(None) => {
+                    debug!("Tool {} has no pinned image to prefetch", tool_id);
+                    continue;
+                }
+                Err(e) => {
+                    warn!("Failed to look up the pinned image of tool {}: {}", tool_id, e);
+                    continue;
+                }
+            };
+            let outcome = self.prefetch_pin(&pin).await;
+            if let PrefetchOutcome::Failed { error } = &outcome {
+                warn!("Failed to prefetch image {} of tool {}: {}", pin.image, tool_id, error);
+            }
+            reports.push(PrefetchReport { tool_id: tool_id.clone(), image: pin.image, outcome });
+        }
+        reports
+    }
+
+    /// 预取所有通过 [`pin`](Self::pin) 固定的镜像，按工具排序
+    pub async fn prefetch_all(&self) -> Vec<PrefetchReport> {
+        let mut tool_ids: Vec<String> = self.pins.read().await.keys().cloned().collect();
+        tool_ids.sort();
+        self.prefetch(&tool_ids).await
+    }
+
+    /// 最近的完整性事件，从旧到新
+    pub async fn events(&self) -> Vec<IntegrityEvent> {
+        self.events.read().await.clone()
+    }
+
+    pub async fn stats(&self) -> IntegrityStats {
+        self.stats.read().await.clone()
+    }
+
+    async fn prefetch_pin(&self, pin: &ImagePin) -> PrefetchOutcome {
+        let present = match self.sandbox.image_digests(&pin.image).await {
+            Ok(digests) => digests.is_some(),
+            Err(e) => return PrefetchOutcome::Failed { error: e.to_string() },
+        };
+        let mut config = SandboxConfig::default();
+        if let Err(e) = self.verify_pin(pin, &mut config).await {
+            return PrefetchOutcome::Failed { error: e.to_string() };
+        }
+        if present {
+            PrefetchOutcome::AlreadyPresent
+        } else {
+            self.stats.write().await.prefetched += 1;
+            PrefetchOutcome::Fetched
+        }
+    }
+
+    /// 本地镜像的摘要，不存在时拉取
+    async fn local_digests(&self, pin: &ImagePin) -> SandboxResult<Vec<String>> {
+        if let Some(digests) = self.sandbox.image_digests(&pin.image).await? {
+            return Ok(digests);
+        }
+        self.sandbox.pull_image(&pin.image).await?;
+        Ok(self.sandbox.image_digests(&pin.image).await?.unwrap_or_default())
+    }
+
+    /// 记录安全事件并返回拒绝执行的错误
+    async fn reject(&self, pin: &ImagePin, actual_digests: Vec<String>, reason: String) -> SandboxError {
+        warn!("Rejecting execution of tool {} {}: {}", pin.tool_id, pin.tool_version, reason);
+        let event = IntegrityEvent {
+            tool_id: pin.tool_id.clone(),
+            tool_version: pin.tool_version.clone(),
+            image: pin.image.clone(),
+            expected_digest: pin.digest.clone(),
+            actual_digests,
+            reason: reason.clone(),
+            timestamp: Utc::now(),
+        };
+        {
+            let mut events = self.events.write().await;
+            if events.len() >= MAX_EVENTS {
+                events.remove(0);
+            }
+            events.push(event.clone());
+        }
+        self.stats.write().await.rejected += 1;
+
+        if let Some(security) = &self.security {
+            // 校验发生在沙箱创建之前，违规记在工具名下
+            let violation = SecurityViolation {
+                sandbox_id: SandboxId::from_string(format!("tool:{}", pin.tool_id)),
+                violation_type: ViolationType::IntegrityViolation,
+                description: reason.clone(),
+                timestamp: event.timestamp,
+                severity: Severity::Critical,
+                details: HashMap::from([
+                    ("tool_id".to_string(), event.tool_id),
+                    ("tool_version".to_string(), event.tool_version),
+                    ("image".to_string(), event.image),
+                    ("expected_digest".to_string(), event.expected_digest),
+                    ("actual_digests".to_string(), event.actual_digests.join(",")),
+                ]),
+            };
+            if let Err(e) = security.record_violation(violation).await {
+                warn!("Failed to record integrity violation for tool {}: {}", pin.tool_id, e);
+            }
+        }
+
+        SandboxError::SecurityViolation(format!("Integrity check failed for tool {}: {}", pin.tool_id, reason))
+    }
+}
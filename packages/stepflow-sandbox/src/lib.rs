@@ -11,6 +11,9 @@ pub mod isolation;
 pub mod security;
 pub mod monitoring;
 pub mod resource_limits;
+pub mod warm_pool;
+pub mod snapshot;
+pub mod integrity;
 
 // 主要的实现
 mod sandbox_impl;
@@ -23,6 +26,9 @@ pub use isolation::*;
 pub use security::*;
 pub use monitoring::*;
 pub use resource_limits::*;
+pub use warm_pool::*;
+pub use snapshot::*;
+pub use integrity::*;
 pub use sandbox_impl::*;
 
 // Re-export commonly used types from dependencies
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -14,6 +15,8 @@ use crate::monitoring::{SandboxMonitoringImpl, MonitoringConfig, SimpleMetricsCo
 use crate::resource_limits::{ResourceLimitsManager, ResourceLimitsConfig};
 use crate::sandbox::{Sandbox, ContainerManager, IsolationManager, SecurityManager, SandboxMonitoring};
 use crate::security::{SecurityManagerImpl, SecurityManagerConfig};
+use crate::integrity::ImageSandbox;
+use crate::snapshot::{SandboxSnapshot, SnapshotMode, SnapshotSandbox};
 use crate::types::*;
 
 /// 沙箱实现配置
@@ -31,6 +34,8 @@ pub struct SandboxImplConfig {
     pub enable_monitoring: bool,
     pub enable_logging: bool,
     pub cleanup_interval: Duration,
+    /// 进程快照检查点的存放目录
+    pub snapshot_dir: PathBuf,
 }
 
 impl Default for SandboxImplConfig {
@@ -48,6 +53,7 @@ impl Default for SandboxImplConfig {
             enable_monitoring: true,
             enable_logging: true,
             cleanup_interval: Duration::from_secs(3600),
+            snapshot_dir: std::env::temp_dir().join("stepflow-snapshots"),
         }
     }
 }
@@ -201,9 +207,10 @@ impl SandboxImpl {
             .map_err(|e| SandboxError::SecurityViolation(e.to_string()))?;
         
         // 根据隔离类型创建相应的环境
+        let mut container_id = ContainerId::new(format!("container-{}", sandbox_id.as_str()));
         match config.isolation_type {
             IsolationType::Container => {
-                self.create_container_sandbox(&sandbox_id, &config).await?;
+                container_id = self.create_container_sandbox(&sandbox_id, &config).await?;
             }
             IsolationType::Namespace => {
                 self.create_namespace_sandbox(&sandbox_id, &config).await?;
@@ -222,12 +229,14 @@ impl SandboxImpl {
         // 应用安全策略
         self.isolation_manager.apply_security_policy(&sandbox_id, config.security_policy.clone()).await
             .map_err(|e| SandboxError::SecurityViolation(e.to_string()))?;
+        // 执行命令前的权限检查依据安全管理器中登记的策略
+        self.security_manager.apply_policy(&sandbox_id, &config.security_policy).await
+            .map_err(|e| SandboxError::SecurityViolation(e.to_string()))?;
         
         // 应用资源限制
         self.resource_limits_manager.apply_resource_limits(&sandbox_id, config.resource_limits.clone()).await?;
         
         // 创建沙箱信息
-        let container_id = ContainerId::new(format!("container-{}", sandbox_id.as_str()));
         let sandbox_info = SandboxInfo {
             id: sandbox_id.clone(),
             name: format!("sandbox-{}", sandbox_id.as_str()),
@@ -253,8 +262,8 @@ impl SandboxImpl {
         Ok(sandbox_id)
     }
 
-    /// 创建容器沙箱
-    async fn create_container_sandbox(&self, sandbox_id: &SandboxId, config: &SandboxConfig) -> SandboxResult<()> {
+    /// 创建容器沙箱，返回容器 ID
+    async fn create_container_sandbox(&self, sandbox_id: &SandboxId, config: &SandboxConfig) -> SandboxResult<ContainerId> {
         info!("Creating container sandbox: {}", sandbox_id.as_str());
         
         // 使用提供的容器配置或创建默认配置
@@ -270,7 +279,7 @@ impl SandboxImpl {
             .map_err(|e| SandboxError::SandboxCreationFailed(e.to_string()))?;
         
         info!("Container sandbox created: {}", sandbox_id.as_str());
-        Ok(())
+        Ok(container_id)
     }
 
     /// 创建命名空间沙箱
@@ -333,7 +342,7 @@ impl SandboxImpl {
         // 根据隔离类型执行命令
         let execution_result = match sandbox_info.isolation_type {
             IsolationType::Container => {
-                self.execute_in_container(sandbox_id, command).await?
+                self.execute_in_container(&sandbox_info.container_id, command).await?
             }
             IsolationType::Namespace => {
                 self.execute_in_namespace(sandbox_id, command).await?
@@ -357,12 +366,8 @@ impl SandboxImpl {
     }
 
     /// 在容器中执行命令
-    async fn execute_in_container(&self, sandbox_id: &SandboxId, command: Command) -> SandboxResult<ExecutionResult> {
-        // 在实际实现中，这里需要找到对应的容器 ID
-        // 这里简化为使用沙箱 ID 作为容器 ID
-        let container_id = ContainerId::new(sandbox_id.as_str().to_string());
-        
-        self.container_manager.execute_in_container(&container_id, command).await
+    async fn execute_in_container(&self, container_id: &ContainerId, command: Command) -> SandboxResult<ExecutionResult> {
+        self.container_manager.execute_in_container(container_id, command).await
             .map_err(|e| SandboxError::ExecutionFailed(e.to_string()))
     }
 
@@ -479,9 +484,8 @@ impl Sandbox for SandboxImpl {
             match sandbox_info.isolation_type {
                 IsolationType::Container => {
                     // 停止和删除容器
-                    let container_id = ContainerId::new(sandbox_id.as_str().to_string());
-                    let _ = self.container_manager.stop_container(&container_id).await;
-                    let _ = self.container_manager.delete_container(&container_id).await;
+                    let _ = self.container_manager.stop_container(&sandbox_info.container_id).await;
+                    let _ = self.container_manager.delete_container(&sandbox_info.container_id).await;
                 }
                 _ => {
                     // 其他隔离类型的清理
@@ -658,4 +662,88 @@ impl Sandbox for SandboxImpl {
             Err(_) => Ok(false),
         }
     }
-} 
\ No newline at end of file
+} 
+
+/// 快照镜像的仓库名
+const SNAPSHOT_REPO: &str = "stepflow-snapshot";
+
+#[async_trait]
+impl SnapshotSandbox for SandboxImpl {
+    async fn snapshot_sandbox(&self, sandbox_id: &SandboxId, mode: SnapshotMode) -> SandboxResult<SandboxSnapshot> {
+        let sandbox_info = self.get_sandbox_info(sandbox_id).await?;
+        if sandbox_info.isolation_type != IsolationType::Container {
+            return Err(SandboxError::InternalError(format!(
+                "Sandbox {} uses {:?} isolation; only container sandboxes can be snapshotted",
+                sandbox_id, sandbox_info.isolation_type,
+            )));
+        }
+
+        let snapshot_id = uuid::Uuid::new_v4().to_string();
+        let checkpoint_dir = match mode {
+            SnapshotMode::Filesystem => None,
+            SnapshotMode::Process => {
+                let dir = self.config.snapshot_dir.join(&snapshot_id);
+                tokio::fs::create_dir_all(&dir).await
+                    .map_err(|e| SandboxError::InternalError(format!("Failed to create checkpoint directory: {}", e)))?;
+                let dir = dir.to_string_lossy().into_owned();
+                self.container_manager.checkpoint_container(&sandbox_info.container_id, &dir, &snapshot_id).await?;
+                Some(dir)
+            }
+        };
+        let image = self.container_manager.commit_container(&sandbox_info.container_id, SNAPSHOT_REPO, &snapshot_id).await?;
+
+        info!("Snapshotted sandbox {} as {}", sandbox_id, image);
+        Ok(SandboxSnapshot {
+            id: snapshot_id,
+            mode,
+            image,
+            checkpoint_dir,
+            source_sandbox: sandbox_id.clone(),
+            created_at: Utc::now(),
+        })
+    }
+
+    async fn restore_sandbox(&self, snapshot: &SandboxSnapshot, mut config: SandboxConfig) -> SandboxResult<SandboxId> {
+        config.isolation_type = IsolationType::Container;
+        let mut container_config = config.container_config.take().unwrap_or_default();
+        container_config.image = snapshot.image.clone();
+        config.container_config = Some(container_config);
+        let sandbox_id = self.create_sandbox_internal(config).await?;
+
+        if let (SnapshotMode::Process, Some(dir)) = (snapshot.mode, &snapshot.checkpoint_dir) {
+            // 检查点只能恢复到已停止的容器
+            let container_id = self.get_sandbox_info(&sandbox_id).await?.container_id;
+            let restored = async {
+                self.container_manager.stop_container(&container_id).await?;
+                self.container_manager.start_from_checkpoint(&container_id, dir, &snapshot.id).await
+            }.await;
+            if let Err(e) = restored {
+                let _ = self.destroy_sandbox(&sandbox_id).await;
+                return Err(e.into());
+            }
+        }
+
+        debug!("Restored sandbox {} from snapshot {}", sandbox_id, snapshot.id);
+        Ok(sandbox_id)
+    }
+
+    async fn delete_snapshot(&self, snapshot: &SandboxSnapshot) -> SandboxResult<()> {
+        self.container_manager.remove_image(&snapshot.image).await?;
+        if let Some(dir) = &snapshot.checkpoint_dir {
+            tokio::fs::remove_dir_all(dir).await
+                .map_err(|e| SandboxError::InternalError(format!("Failed to remove checkpoint {}: {}", dir, e)))?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ImageSandbox for SandboxImpl {
+    async fn pull_image(&self, image: &str) -> SandboxResult<()> {
+        Ok(self.container_manager.pull_image(image).await?)
+    }
+
+    async fn image_digests(&self, image: &str) -> SandboxResult<Option<Vec<String>>> {
+        Ok(self.container_manager.image_digests(image).await?)
+    }
+}
@@ -0,0 +1,405 @@
+//! 沙箱快照：对初始化开销大的工具，在初始化完成后保存沙箱状态，后续执行直接从快照恢复
+//!
+//! 快照绑定工具版本，版本变化或快照过期后重建；恢复失败时丢弃快照并冷启动。
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
+
+use crate::errors::*;
+use crate::sandbox::Sandbox;
+use crate::types::*;
+
+/// 快照保存的内容
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotMode {
+    /// 仅文件系统层，恢复后进程重新启动
+    Filesystem,
+    /// 文件系统层加进程状态，依赖 Linux 上的 CRIU
+    Process,
+}
+
+/// 沙箱快照
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SandboxSnapshot {
+    pub id: String,
+    pub mode: SnapshotMode,
+    /// 保存文件系统层的镜像
+    pub image: String,
+    /// 进程检查点所在目录，仅 `Process` 模式
+    pub checkpoint_dir: Option<String>,
+    /// 生成快照的沙箱
+    pub source_sandbox: SandboxId,
+    pub created_at: DateTime<Utc>,
+}
+
+/// 支持快照的沙箱
+#[async_trait]
+pub trait SnapshotSandbox: Sandbox {
+    /// 保存沙箱当前状态，沙箱保持运行
+    async fn snapshot_sandbox(&self, sandbox_id: &SandboxId, mode: SnapshotMode) -> SandboxResult<SandboxSnapshot>;
+
+    /// 从快照创建新的沙箱
+    async fn restore_sandbox(&self, snapshot: &SandboxSnapshot, config: SandboxConfig) -> SandboxResult<SandboxId>;
+
+    /// 删除快照占用的镜像与检查点
+    async fn delete_snapshot(&self, snapshot: &SandboxSnapshot) -> SandboxResult<()>;
+}
+
+/// 单个工具的快照配置
+#[derive(Debug, Clone)]
+pub struct SnapshotSpec {
+    pub tool_id: String,
+    /// 快照对应的工具版本，版本变化时快照失效
+    pub tool_version: String,
+    pub sandbox_config: SandboxConfig,
+    /// 初始化命令，全部成功后保存快照
+    pub init_commands: Vec<Command>,
+    pub mode: SnapshotMode,
+    /// 快照保存多久后重建
+    pub max_age: Duration,
+}
+
+impl SnapshotSpec {
+    pub fn new(tool_id: impl Into<String>, tool_version: impl Into<String>, sandbox_config: SandboxConfig) -> Self {
+        Self {
+            tool_id: tool_id.into(),
+            tool_version: tool_version.into(),
+            sandbox_config,
+            init_commands: Vec::new(),
+            mode: SnapshotMode::Filesystem,
+            max_age: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+
+    fn validate(&self) -> SandboxResult<()> {
+        if self.tool_version.is_empty() {
+            return Err(SandboxError::InternalError(format!("Snapshot spec for {} needs a tool version", self.tool_id)));
+        }
+        if self.mode == SnapshotMode::Process && !cfg!(target_os = "linux") {
+            return Err(SandboxError::InternalError(format!(
+                "Process snapshots for {} require CRIU, which is only available on Linux",
+                self.tool_id,
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// 快照统计
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotStats {
+    /// 从快照恢复的执行数
+    pub restores: u64,
+    /// 需要完整初始化的执行数
+    pub cold_starts: u64,
+    /// 因版本变化、过期或手动失效而删除的快照数
+    pub invalidations: u64,
+    /// 恢复失败后回退为冷启动的次数
+    pub restore_failures: u64,
+}
+
+/// 工具当前快照的概况
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolSnapshotInfo {
+    pub tool_id: String,
+    pub tool_version: String,
+    pub snapshot: SandboxSnapshot,
+}
+
+/// 获取到的沙箱，用完后交给 [`SnapshotManager::release`]
+#[derive(Debug)]
+pub struct SnapshotLease {
+    pub tool_id: String,
+    pub sandbox_id: SandboxId,
+    /// 是否从快照恢复
+    pub restored: bool,
+}
+
+struct StoredSnapshot {
+    snapshot: SandboxSnapshot,
+    taken_at: Instant,
+}
+
+struct ToolSnapshots {
+    spec: SnapshotSpec,
+    current: Option<StoredSnapshot>,
+    stats: SnapshotStats,
+}
+
+impl ToolSnapshots {
+    fn expired(&self, stored: &StoredSnapshot) -> bool {
+        stored.taken_at.elapsed() >= self.spec.max_age
+    }
+}
+
+/// 快照管理器
+pub struct SnapshotManager {
+    sandbox: Arc<dyn SnapshotSandbox>,
+    tools: Arc<RwLock<HashMap<String, ToolSnapshots>>>,
+}
+
+impl SnapshotManager {
+    pub fn new(sandbox: Arc<dyn SnapshotSandbox>) -> Self {
+        Self {
+            sandbox,
+            tools: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// 为工具启用快照，替换已有配置
+    ///
+    /// 工具版本不变时保留现有快照，否则删除。
+    pub async fn configure(&self, spec: SnapshotSpec) -> SandboxResult<()> {
+        spec.validate()?;
+        let tool_id = spec.tool_id.clone();
+        let stale = {
+            let mut tools = self.tools.write().await;
+            match tools.get_mut(&tool_id) {
+                Some(tool) => {
+                    let stale = if tool.spec.tool_version != spec.tool_version || tool.spec.mode != spec.mode {
+                        tool.current.take()
+                    } else {
+                        None
+                    };
+                    if stale.is_some() {
+                        tool.stats.invalidations += 1;
+                    }
+                    tool.spec = spec;
+                    stale
+                }
+                None => {
+                    tools.insert(tool_id.clone(), ToolSnapshots { spec, current: None, stats: SnapshotStats::default() });
+                    None
+                }
+            }
+        };
+        if let Some(stale) = stale {
+            self.delete(&stale.snapshot).await;
+        }
+        info!("Configured sandbox snapshots for tool {}", tool_id);
+        Ok(())
+    }
+
+    /// 停用工具的快照并删除现有快照
+    pub async fn remove(&self, tool_id: &str) -> SandboxResult<()> {
+        let removed = self.tools.write().await.remove(tool_id);
+        if let Some(stored) = removed.and_then(|tool| tool.current) {
+            self.delete(&stored.snapshot).await;
+        }
+        Ok(())
+    }
+
+    /// 删除工具的快照，下次执行时重新初始化；返回是否存在快照
+    pub async fn invalidate(&self, tool_id: &str) -> bool {
+        let stale = {
+            let mut tools = self.tools.write().await;
+            let Some(tool) = tools.get_mut(tool_id) else { return false };
+            let stale = tool.current.take();
+            if stale.is_some() {
+                tool.stats.invalidations += 1;
+            }
+            stale
+        };
+        match stale {
+            Some(stored) => {
+                self.delete(&stored.snapshot).await;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 获取执行用的沙箱：有当前版本的快照时从快照恢复，否则初始化并保存快照
+    ///
+    /// `tool_version` 与配置的版本不同时旧快照失效，之后以新版本为准。没有配置快照的工具
+    /// 直接使用 `config` 冷启动。
+    pub async fn acquire(&self, tool_id: &str, tool_version: &str, config: &SandboxConfig) -> SandboxResult<SnapshotLease> {
+        let (spec, snapshot, stale) = {
+            let mut tools = self.tools.write().await;
+            let Some(tool) = tools.get_mut(tool_id) else {
+                drop(tools);
+                return Ok(SnapshotLease {
+                    tool_id: tool_id.to_string(),
+                    sandbox_id: self.sandbox.create_sandbox(config.clone()).await?,
+                    restored: false,
+                });
+            };
+            let mut stale = None;
+            if tool.spec.tool_version != tool_version {
+                info!("Tool {} changed from version {} to {}, invalidating its snapshot", tool_id, tool.spec.tool_version, tool_version);
+                tool.spec.tool_version = tool_version.to_string();
+                stale = tool.current.take();
+            } else if tool.current.as_ref().is_some_and(|stored| tool.expired(stored)) {
+                stale = tool.current.take();
+            }
+            if stale.is_some() {
+                tool.stats.invalidations += 1;
+            }
+            let snapshot = tool.current.as_ref().map(|stored| stored.snapshot.clone());
+            (tool.spec.clone(), snapshot, stale)
+        };
+        if let Some(stale) = stale {
+            self.delete(&stale.snapshot).await;
+        }
+
+        if let Some(snapshot) = snapshot {
+            match self.sandbox.restore_sandbox(&snapshot, spec.sandbox_config.clone()).await {
+                Ok(sandbox_id) => {
+                    debug!("Restored tool {} from snapshot {}", tool_id, snapshot.id);
+                    self.record(tool_id, |stats| stats.restores += 1).await;
+                    return Ok(SnapshotLease { tool_id: tool_id.to_string(), sandbox_id, restored: true });
+                }
+                Err(e) => {
+                    warn!("Failed to restore tool {} from snapshot {}, discarding it: {}", tool_id, snapshot.id, e);
+                    self.record(tool_id, |stats| stats.restore_failures += 1).await;
+                    self.discard_snapshot(tool_id, &snapshot).await;
+                }
+            }
+        }
+
+        let sandbox_id = self.initialize(&spec).await?;
+        self.record(tool_id, |stats| stats.cold_starts += 1).await;
+        Ok(SnapshotLease { tool_id: tool_id.to_string(), sandbox_id, restored: false })
+    }
+
+    /// 销毁用完的沙箱
+    pub async fn release(&self, lease: SnapshotLease) -> SandboxResult<()> {
+        self.sandbox.destroy_sandbox(&lease.sandbox_id).await
+    }
+
+    /// 在从快照恢复（没有快照时初始化）的沙箱中执行命令，返回结果及是否从快照恢复
+    pub async fn execute(&self, tool_id: &str, tool_version: &str, config: &SandboxConfig, command: Command) -> SandboxResult<(ExecutionResult, bool)> {
+        let lease = self.acquire(tool_id, tool_version, config).await?;
+        let restored = lease.restored;
+        let result = self.sandbox.execute_in_sandbox(&lease.sandbox_id, command).await;
+        if let Err(e) = self.sandbox.destroy_sandbox(&lease.sandbox_id).await {
+            warn!("Failed to destroy sandbox {} of tool {}: {}", lease.sandbox_id, tool_id, e);
+        }
+        result.map(|result| (result, restored))
+    }
+
+    /// 删除过期的快照，返回删除的数量
+    pub async fn maintain(&self) -> usize {
+        let expired: Vec<SandboxSnapshot> = {
+            let mut tools = self.tools.write().await;
+            tools
+                .values_mut()
+                .filter_map(|tool| {
+                    let stored = tool.current.take_if(|stored| stored.taken_at.elapsed() >= tool.spec.max_age)?;
+                    tool.stats.invalidations += 1;
+                    Some(stored.snapshot)
+                })
+                .collect()
+        };
+        for snapshot in &expired {
+            self.delete(snapshot).await;
+        }
+        expired.len()
+    }
+
+    /// 所有工具的当前快照，按工具排序
+    pub async fn snapshots(&self) -> Vec<ToolSnapshotInfo> {
+        let mut snapshots: Vec<ToolSnapshotInfo> = self.tools.read().await
+            .iter()
+            .filter_map(|(tool_id, tool)| {
+                tool.current.as_ref().map(|stored| ToolSnapshotInfo {
+                    tool_id: tool_id.clone(),
+                    tool_version: tool.spec.tool_version.clone(),
+                    snapshot: stored.snapshot.clone(),
+                })
+            })
+            .collect();
+        snapshots.sort_by(|a, b| a.tool_id.cmp(&b.tool_id));
+        snapshots
+    }
+
+    /// 获取工具的快照统计
+    pub async fn stats(&self, tool_id: &str) -> Option<SnapshotStats> {
+        self.tools.read().await.get(tool_id).map(|tool| tool.stats.clone())
+    }
+
+    /// 创建沙箱、执行初始化命令并保存快照，返回初始化好的沙箱
+    ///
+    /// 保存快照失败不影响本次执行，下次执行会重试。
+    async fn initialize(&self, spec: &SnapshotSpec) -> SandboxResult<SandboxId> {
+        let sandbox_id = self.sandbox.create_sandbox(spec.sandbox_config.clone()).await?;
+        for command in &spec.init_commands {
+            let failure = match self.sandbox.execute_in_sandbox(&sandbox_id, command.clone()).await {
+                Ok(result) if result.exit_code == 0 => None,
+                Ok(result) => Some(SandboxError::ExecutionFailed(format!(
+                    "Initialization of tool {} failed: {} exited with {}: {}",
+                    spec.tool_id, command.program, result.exit_code, result.stderr,
+                ))),
+                Err(e) => Some(e),
+            };
+            if let Some(e) = failure {
+                if let Err(destroy_error) = self.sandbox.destroy_sandbox(&sandbox_id).await {
+                    warn!("Failed to destroy sandbox {} of tool {}: {}", sandbox_id, spec.tool_id, destroy_error);
+                }
+                return Err(e);
+            }
+        }
+
+        match self.sandbox.snapshot_sandbox(&sandbox_id, spec.mode).await {
+            Ok(snapshot) => {
+                let surplus = {
+                    let mut tools = self.tools.write().await;
+                    match tools.get_mut(&spec.tool_id) {
+                        // 并发初始化时保留先完成的快照；期间版本变化的快照直接丢弃
+                        Some(tool) if tool.current.is_none() && tool.spec.tool_version == spec.tool_version => {
+                            info!("Saved snapshot {} for tool {} {}", snapshot.id, spec.tool_id, spec.tool_version);
+                            tool.current = Some(StoredSnapshot { snapshot, taken_at: Instant::now() });
+                            None
+                        }
+                        _ => Some(snapshot),
+                    }
+                };
+                if let Some(snapshot) = surplus {
+                    self.delete(&snapshot).await;
+                }
+            }
+            Err(e) => warn!("Failed to snapshot sandbox {} of tool {}: {}", sandbox_id, spec.tool_id, e),
+        }
+        Ok(sandbox_id)
+    }
+
+    /// 丢弃恢复失败的快照，若它仍是工具的当前快照
+    async fn discard_snapshot(&self, tool_id: &str, snapshot: &SandboxSnapshot) {
+        let discarded = {
+            let mut tools = self.tools.write().await;
+            match tools.get_mut(tool_id) {
+                Some(tool) => {
+                    let discarded = tool.current.take_if(|stored| stored.snapshot.id == snapshot.id).is_some();
+                    if discarded {
+                        tool.stats.invalidations += 1;
+                    }
+                    discarded
+                }
+                None => false,
+            }
+        };
+        if discarded {
+            self.delete(snapshot).await;
+        }
+    }
+
+    async fn record(&self, tool_id: &str, update: impl FnOnce(&mut SnapshotStats)) {
+        if let Some(tool) = self.tools.write().await.get_mut(tool_id) {
+            update(&mut tool.stats);
+        }
+    }
+
+    async fn delete(&self, snapshot: &SandboxSnapshot) {
+        if let Err(e) = self.sandbox.delete_snapshot(snapshot).await {
+            warn!("Failed to delete sandbox snapshot {}: {}", snapshot.id, e);
+        }
+    }
+}
@@ -561,6 +561,8 @@ pub enum ViolationType {
     FileSystemViolation,
     ProcessViolation,
     CapabilityViolation,
+    /// 镜像摘要与注册表记录不一致
+    IntegrityViolation,
 }
 
 /// 严重程度
@@ -0,0 +1,338 @@
+//! 预热池：为常用工具保持预先初始化好的沙箱，避免冷启动延迟
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use crate::errors::*;
+use crate::sandbox::Sandbox;
+use crate::types::*;
+
+/// 单个工具的预热池配置
+#[derive(Debug, Clone)]
+pub struct WarmPoolSpec {
+    pub tool_id: String,
+    /// 预热实例使用的沙箱配置
+    pub sandbox_config: SandboxConfig,
+    /// 保持的空闲预热实例数
+    pub size: usize,
+    /// 实例被使用多少次后回收
+    pub max_uses: u32,
+    /// 实例创建多久后回收
+    pub max_age: Duration,
+}
+
+impl WarmPoolSpec {
+    pub fn new(tool_id: impl Into<String>, sandbox_config: SandboxConfig) -> Self {
+        Self {
+            tool_id: tool_id.into(),
+            sandbox_config,
+            size: 2,
+            max_uses: 50,
+            max_age: Duration::from_secs(30 * 60),
+        }
+    }
+
+    fn validate(&self) -> SandboxResult<()> {
+        if self.size == 0 {
+            return Err(SandboxError::InternalError(format!("Warm pool for {} must keep at least one instance", self.tool_id)));
+        }
+        if self.max_uses == 0 {
+            return Err(SandboxError::InternalError(format!("Warm pool for {} must allow at least one use", self.tool_id)));
+        }
+        Ok(())
+    }
+}
+
+/// 预热池统计
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WarmPoolStats {
+    /// 命中预热实例的执行数
+    pub warm_hits: u64,
+    /// 需要冷启动的执行数
+    pub cold_starts: u64,
+    /// 因使用次数或存活时间到期而回收的实例数
+    pub recycled: u64,
+    /// 当前空闲的预热实例数
+    pub idle: usize,
+}
+
+impl WarmPoolStats {
+    /// 预热命中率，没有执行时为 0
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.warm_hits + self.cold_starts;
+        if total == 0 {
+            0.0
+        } else {
+            self.warm_hits as f64 / total as f64
+        }
+    }
+}
+
+/// 租出的沙箱实例，用完后交还给 [`WarmPoolManager::release`] 或 [`WarmPoolManager::discard`]
+#[derive(Debug)]
+pub struct WarmLease {
+    pub tool_id: String,
+    pub sandbox_id: SandboxId,
+    /// 是否来自预热池
+    pub warm: bool,
+    created_at: Instant,
+    uses: u32,
+}
+
+#[derive(Debug)]
+struct WarmInstance {
+    sandbox_id: SandboxId,
+    created_at: Instant,
+    uses: u32,
+}
+
+struct ToolPool {
+    spec: WarmPoolSpec,
+    idle: VecDeque<WarmInstance>,
+    stats: WarmPoolStats,
+}
+
+impl ToolPool {
+    fn expired(&self, created_at: Instant, uses: u32) -> bool {
+        uses >= self.spec.max_uses || created_at.elapsed() >= self.spec.max_age
+    }
+}
+
+/// 预热池管理器
+pub struct WarmPoolManager {
+    sandbox: Arc<dyn Sandbox>,
+    pools: Arc<RwLock<HashMap<String, ToolPool>>>,
+}
+
+impl WarmPoolManager {
+    pub fn new(sandbox: Arc<dyn Sandbox>) -> Self {
+        Self {
+            sandbox,
+            pools: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// 为工具配置预热池并预先创建实例，替换已有配置
+    pub async fn configure(&self, spec: WarmPoolSpec) -> SandboxResult<()> {
+        spec.validate()?;
+        let tool_id = spec.tool_id.clone();
+        let replaced = self.pools.write().await.insert(tool_id.clone(), ToolPool {
+            spec,
+            idle: VecDeque::new(),
+            stats: WarmPoolStats::default(),
+        });
+        if let Some(replaced) = replaced {
+            self.destroy_all(replaced.idle.into_iter().map(|instance| instance.sandbox_id)).await;
+        }
+        info!("Configured warm pool for tool {}", tool_id);
+        self.refill(&tool_id).await
+    }
+
+    /// 移除工具的预热池并销毁其空闲实例
+    pub async fn remove(&self, tool_id: &str) -> SandboxResult<()> {
+        if let Some(pool) = self.pools.write().await.remove(tool_id) {
+            self.destroy_all(pool.idle.into_iter().map(|instance| instance.sandbox_id)).await;
+        }
+        Ok(())
+    }
+
+    /// 获取执行用的沙箱：有预热实例时直接使用，否则冷启动
+    ///
+    /// 配置了预热池的工具使用池中的沙箱配置，`config` 仅用于其他工具。
+    pub async fn acquire(&self, tool_id: &str, config: &SandboxConfig) -> SandboxResult<WarmLease> {
+        let (warm, expired, config) = {
+            let mut pools = self.pools.write().await;
+            match pools.get_mut(tool_id) {
+                Some(pool) => {
+                    let mut expired = Vec::new();
+                    let mut warm = None;
+                    while let Some(instance) = pool.idle.pop_front() {
+                        if pool.expired(instance.created_at, instance.uses) {
+                            expired.push(instance.sandbox_id);
+                        } else {
+                            warm = Some(instance);
+                            break;
+                        }
+                    }
+                    pool.stats.recycled += expired.len() as u64;
+                    if warm.is_some() {
+                        pool.stats.warm_hits += 1;
+                    } else {
+                        pool.stats.cold_starts += 1;
+                    }
+                    pool.stats.idle = pool.idle.len();
+                    (warm, expired, pool.spec.sandbox_config.clone())
+                }
+                None => (None, Vec::new(), config.clone()),
+            }
+        };
+        self.destroy_all(expired).await;
+
+        let lease = match warm {
+            Some(instance) => {
+                debug!("Warm hit for tool {}: {}", tool_id, instance.sandbox_id);
+                WarmLease {
+                    tool_id: tool_id.to_string(),
+                    sandbox_id: instance.sandbox_id,
+                    warm: true,
+                    created_at: instance.created_at,
+                    uses: instance.uses,
+                }
+            }
+            None => WarmLease {
+                tool_id: tool_id.to_string(),
+                sandbox_id: self.sandbox.create_sandbox(config).await?,
+                warm: false,
+                created_at: Instant::now(),
+                uses: 0,
+            },
+        };
+        Ok(lease)
+    }
+
+    /// 交还使用完的沙箱，未到期且池未满时放回预热池，否则销毁
+    pub async fn release(&self, lease: WarmLease) -> SandboxResult<()> {
+        let uses = lease.uses + 1;
+        let retained = {
+            let mut pools = self.pools.write().await;
+            match pools.get_mut(&lease.tool_id) {
+                Some(pool) if !pool.expired(lease.created_at, uses) && pool.idle.len() < pool.spec.size => {
+                    pool.idle.push_back(WarmInstance {
+                        sandbox_id: lease.sandbox_id.clone(),
+                        created_at: lease.created_at,
+                        uses,
+                    });
+                    pool.stats.idle = pool.idle.len();
+                    true
+                }
+                Some(pool) => {
+                    if pool.expired(lease.created_at, uses) {
+                        pool.stats.recycled += 1;
+                    }
+                    false
+                }
+                None => false,
+            }
+        };
+        if !retained {
+            self.sandbox.destroy_sandbox(&lease.sandbox_id).await?;
+            self.refill(&lease.tool_id).await?;
+        }
+        Ok(())
+    }
+
+    /// 销毁执行出错的沙箱，不再放回预热池
+    pub async fn discard(&self, lease: WarmLease) -> SandboxResult<()> {
+        self.sandbox.destroy_sandbox(&lease.sandbox_id).await?;
+        self.refill(&lease.tool_id).await
+    }
+
+    /// 在预热实例（没有时冷启动）中执行命令，返回结果及是否命中预热实例
+    pub async fn execute(&self, tool_id: &str, config: &SandboxConfig, command: Command) -> SandboxResult<(ExecutionResult, bool)> {
+        let lease = self.acquire(tool_id, config).await?;
+        let warm = lease.warm;
+        match self.sandbox.execute_in_sandbox(&lease.sandbox_id, command).await {
+            Ok(result) => {
+                self.release(lease).await?;
+                Ok((result, warm))
+            }
+            Err(e) => {
+                if let Err(discard_error) = self.discard(lease).await {
+                    warn!("Failed to discard sandbox for tool {}: {}", tool_id, discard_error);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// 回收到期的空闲实例并补足所有预热池
+    pub async fn maintain(&self) -> SandboxResult<()> {
+        let (expired, tool_ids) = {
+            let mut pools = self.pools.write().await;
+            let mut expired = Vec::new();
+            for pool in pools.values_mut() {
+                let (stale, fresh): (VecDeque<_>, VecDeque<_>) = std::mem::take(&mut pool.idle)
+                    .into_iter()
+                    .partition(|instance| pool.expired(instance.created_at, instance.uses));
+                pool.idle = fresh;
+                pool.stats.recycled += stale.len() as u64;
+                pool.stats.idle = pool.idle.len();
+                expired.extend(stale.into_iter().map(|instance| instance.sandbox_id));
+            }
+            (expired, pools.keys().cloned().collect::<Vec<_>>())
+        };
+        self.destroy_all(expired).await;
+        for tool_id in tool_ids {
+            self.refill(&tool_id).await?;
+        }
+        Ok(())
+    }
+
+    /// 启动定期维护任务
+    pub fn spawn_maintenance(self: &Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        let manager = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = manager.maintain().await {
+                    warn!("Warm pool maintenance failed: {}", e);
+                }
+            }
+        })
+    }
+
+    /// 获取工具预热池的统计
+    pub async fn stats(&self, tool_id: &str) -> Option<WarmPoolStats> {
+        self.pools.read().await.get(tool_id).map(|pool| pool.stats.clone())
+    }
+
+    /// 获取所有预热池的统计
+    pub async fn all_stats(&self) -> HashMap<String, WarmPoolStats> {
+        self.pools.read().await
+            .iter()
+            .map(|(tool_id, pool)| (tool_id.clone(), pool.stats.clone()))
+            .collect()
+    }
+
+    /// 创建实例补足预热池
+    async fn refill(&self, tool_id: &str) -> SandboxResult<()> {
+        let (missing, config) = match self.pools.read().await.get(tool_id) {
+            Some(pool) => (pool.spec.size.saturating_sub(pool.idle.len()), pool.spec.sandbox_config.clone()),
+            None => return Ok(()),
+        };
+        for _ in 0..missing {
+            let sandbox_id = self.sandbox.create_sandbox(config.clone()).await?;
+            let surplus = {
+                let mut pools = self.pools.write().await;
+                match pools.get_mut(tool_id) {
+                    Some(pool) if pool.idle.len() < pool.spec.size => {
+                        pool.idle.push_back(WarmInstance { sandbox_id, created_at: Instant::now(), uses: 0 });
+                        pool.stats.idle = pool.idle.len();
+                        None
+                    }
+                    _ => Some(sandbox_id),
+                }
+            };
+            if let Some(sandbox_id) = surplus {
+                self.sandbox.destroy_sandbox(&sandbox_id).await?;
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    async fn destroy_all(&self, sandbox_ids: impl IntoIterator<Item = SandboxId>) {
+        for sandbox_id in sandbox_ids {
+            if let Err(e) = self.sandbox.destroy_sandbox(&sandbox_id).await {
+                warn!("Failed to destroy warm sandbox {}: {}", sandbox_id, e);
+            }
+        }
+    }
+}
@@ -339,4 +339,421 @@ async fn test_capability_config_defaults() {
     assert!(config.permitted_capabilities.is_empty());
     assert!(config.inheritable_capabilities.is_empty());
     assert!(config.bounding_set.is_empty());
-} 
\ No newline at end of file
+} 
+/// Sandbox that only tracks which sandboxes are alive
+#[derive(Default)]
+struct CountingSandbox {
+    created: std::sync::atomic::AtomicUsize,
+    alive: tokio::sync::Mutex<std::collections::HashSet<SandboxId>>,
+    /// Programs executed, in order
+    executed: std::sync::Mutex<Vec<String>>,
+    snapshots: tokio::sync::Mutex<std::collections::HashSet<String>>,
+    fail_restores: std::sync::atomic::AtomicBool,
+}
+
+#[async_trait]
+impl Sandbox for CountingSandbox {
+    async fn create_sandbox(&self, _config: SandboxConfig) -> SandboxResult<SandboxId> {
+        self.created.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        let sandbox_id = SandboxId::new();
+        self.alive.lock().await.insert(sandbox_id.clone());
+        Ok(sandbox_id)
+    }
+
+    async fn execute_in_sandbox(&self, sandbox_id: &SandboxId, command: Command) -> SandboxResult<ExecutionResult> {
+        if !self.alive.lock().await.contains(sandbox_id) {
+            return Err(SandboxError::SandboxNotFound(sandbox_id.to_string()));
+        }
+        self.executed.lock().unwrap().push(command.program.clone());
+        Ok(ExecutionResult {
+            exit_code: 0,
+            stdout: command.program,
+            stderr: String::new(),
+            execution_time: Duration::ZERO,
+            resource_usage: ResourceUsage::default(),
+        })
+    }
+
+    async fn destroy_sandbox(&self, sandbox_id: &SandboxId) -> SandboxResult<()> {
+        self.alive.lock().await.remove(sandbox_id);
+        Ok(())
+    }
+
+    async fn get_sandbox_status(&self, _sandbox_id: &SandboxId) -> SandboxResult<SandboxStatus> {
+        Ok(SandboxStatus::Running)
+    }
+
+    async fn list_sandboxes(&self, _filter: Option<SandboxFilter>) -> SandboxResult<Vec<SandboxInfo>> {
+        Ok(vec![])
+    }
+
+    async fn get_sandbox_info(&self, sandbox_id: &SandboxId) -> SandboxResult<SandboxInfo> {
+        Err(SandboxError::SandboxNotFound(sandbox_id.to_string()))
+    }
+
+    async fn update_sandbox_config(&self, _sandbox_id: &SandboxId, _config: SandboxConfig) -> SandboxResult<()> {
+        Ok(())
+    }
+
+    async fn pause_sandbox(&self, _sandbox_id: &SandboxId) -> SandboxResult<()> {
+        Ok(())
+    }
+
+    async fn resume_sandbox(&self, _sandbox_id: &SandboxId) -> SandboxResult<()> {
+        Ok(())
+    }
+
+    async fn get_sandbox_logs(&self, _sandbox_id: &SandboxId, _lines: Option<usize>) -> SandboxResult<Vec<String>> {
+        Ok(vec![])
+    }
+
+    async fn get_sandbox_metrics(&self, sandbox_id: &SandboxId) -> SandboxResult<SandboxMetrics> {
+        Err(SandboxError::SandboxNotFound(sandbox_id.to_string()))
+    }
+
+    async fn health_check(&self) -> SandboxResult<bool> {
+        Ok(true)
+    }
+}
+
+#[tokio::test]
+async fn test_warm_pool_routes_to_warm_instances() {
+    let sandbox = Arc::new(CountingSandbox::default());
+    let manager = WarmPoolManager::new(sandbox.clone());
+    let mut spec = WarmPoolSpec::new("python-tool", SandboxConfig::default());
+    spec.size = 1;
+    spec.max_uses = 2;
+    manager.configure(spec).await.unwrap();
+    assert_eq!(sandbox.alive.lock().await.len(), 1);
+
+    // The pre-initialized instance serves the first execution
+    let (result, warm) = manager.execute("python-tool", &SandboxConfig::default(), Command::new("python3".to_string())).await.unwrap();
+    assert_eq!(result.stdout, "python3");
+    assert!(warm);
+
+    // A concurrent execution cold-starts and then takes the free slot in the pool
+    let first = manager.acquire("python-tool", &SandboxConfig::default()).await.unwrap();
+    let second = manager.acquire("python-tool", &SandboxConfig::default()).await.unwrap();
+    assert!(first.warm);
+    assert!(!second.warm);
+    manager.release(second).await.unwrap();
+    manager.release(first).await.unwrap();
+
+    // The original instance reached its use limit and was recycled
+    let stats = manager.stats("python-tool").await.unwrap();
+    assert_eq!(stats.warm_hits, 2);
+    assert_eq!(stats.cold_starts, 1);
+    assert_eq!(stats.recycled, 1);
+    assert_eq!(stats.idle, 1);
+    assert!((stats.hit_rate() - 2.0 / 3.0).abs() < f64::EPSILON);
+    assert_eq!(sandbox.alive.lock().await.len(), 1);
+
+    // Tools without a warm pool always cold-start
+    let (_, warm) = manager.execute("other-tool", &SandboxConfig::default(), Command::new("sh".to_string())).await.unwrap();
+    assert!(!warm);
+    assert!(manager.stats("other-tool").await.is_none());
+}
+
+#[tokio::test]
+async fn test_warm_pool_recycles_expired_instances() {
+    let sandbox = Arc::new(CountingSandbox::default());
+    let manager = WarmPoolManager::new(sandbox.clone());
+    let mut spec = WarmPoolSpec::new("python-tool", SandboxConfig::default());
+    spec.max_age = Duration::from_millis(50);
+    manager.configure(spec).await.unwrap();
+    assert_eq!(sandbox.created.load(std::sync::atomic::Ordering::SeqCst), 2);
+
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    manager.maintain().await.unwrap();
+
+    let stats = manager.stats("python-tool").await.unwrap();
+    assert_eq!(stats.recycled, 2);
+    assert_eq!(stats.idle, 2);
+    assert_eq!(sandbox.created.load(std::sync::atomic::Ordering::SeqCst), 4);
+    assert_eq!(sandbox.alive.lock().await.len(), 2);
+
+    manager.remove("python-tool").await.unwrap();
+    assert!(sandbox.alive.lock().await.is_empty());
+}
+
+#[async_trait]
+impl SnapshotSandbox for CountingSandbox {
+    async fn snapshot_sandbox(&self, sandbox_id: &SandboxId, mode: SnapshotMode) -> SandboxResult<SandboxSnapshot> {
+        let id = Uuid::new_v4().to_string();
+        self.snapshots.lock().await.insert(id.clone());
+        Ok(SandboxSnapshot {
+            image: format!("stepflow-snapshot:{}", id),
+            id,
+            mode,
+            checkpoint_dir: None,
+            source_sandbox: sandbox_id.clone(),
+            created_at: Utc::now(),
+        })
+    }
+
+    async fn restore_sandbox(&self, snapshot: &SandboxSnapshot, config: SandboxConfig) -> SandboxResult<SandboxId> {
+        if self.fail_restores.load(std::sync::atomic::Ordering::SeqCst) || !self.snapshots.lock().await.contains(&snapshot.id) {
+            return Err(SandboxError::SandboxCreationFailed(format!("Snapshot {} is gone", snapshot.id)));
+        }
+        self.create_sandbox(config).await
+    }
+
+    async fn delete_snapshot(&self, snapshot: &SandboxSnapshot) -> SandboxResult<()> {
+        self.snapshots.lock().await.remove(&snapshot.id);
+        Ok(())
+    }
+}
+
+fn snapshot_spec(version: &str) -> SnapshotSpec {
+    let mut spec = SnapshotSpec::new("model-tool", version, SandboxConfig::default());
+    spec.init_commands = vec![Command::new("load-model".to_string())];
+    spec
+}
+
+#[tokio::test]
+async fn test_snapshot_restores_initialized_sandbox() {
+    let sandbox = Arc::new(CountingSandbox::default());
+    let manager = SnapshotManager::new(sandbox.clone());
+    manager.configure(snapshot_spec("1.0.0")).await.unwrap();
+
+    // The first execution initializes the sandbox and snapshots it
+    let (result, restored) = manager.execute("model-tool", "1.0.0", &SandboxConfig::default(), Command::new("predict".to_string())).await.unwrap();
+    assert_eq!(result.stdout, "predict");
+    assert!(!restored);
+    assert_eq!(manager.snapshots().await.len(), 1);
+
+    // Later executions skip initialization
+    let (_, restored) = manager.execute("model-tool", "1.0.0", &SandboxConfig::default(), Command::new("predict".to_string())).await.unwrap();
+    assert!(restored);
+    assert_eq!(*sandbox.executed.lock().unwrap(), ["load-model", "predict", "predict"]);
+
+    let stats = manager.stats("model-tool").await.unwrap();
+    assert_eq!(stats.cold_starts, 1);
+    assert_eq!(stats.restores, 1);
+    assert!(sandbox.alive.lock().await.is_empty());
+
+    manager.remove("model-tool").await.unwrap();
+    assert!(sandbox.snapshots.lock().await.is_empty());
+}
+
+#[tokio::test]
+async fn test_snapshot_invalidated_by_version_change() {
+    let sandbox = Arc::new(CountingSandbox::default());
+    let manager = SnapshotManager::new(sandbox.clone());
+    manager.configure(snapshot_spec("1.0.0")).await.unwrap();
+    let lease = manager.acquire("model-tool", "1.0.0", &SandboxConfig::default()).await.unwrap();
+    manager.release(lease).await.unwrap();
+    let original = manager.snapshots().await[0].snapshot.id.clone();
+
+    // A new tool version rebuilds the snapshot
+    let lease = manager.acquire("model-tool", "1.1.0", &SandboxConfig::default()).await.unwrap();
+    assert!(!lease.restored);
+    manager.release(lease).await.unwrap();
+    let snapshots = manager.snapshots().await;
+    assert_eq!(snapshots[0].tool_version, "1.1.0");
+    assert_ne!(snapshots[0].snapshot.id, original);
+    assert_eq!(sandbox.snapshots.lock().await.len(), 1);
+
+    // Reconfiguring with the same version keeps the snapshot
+    manager.configure(snapshot_spec("1.1.0")).await.unwrap();
+    assert_eq!(manager.snapshots().await.len(), 1);
+    assert!(manager.invalidate("model-tool").await);
+    assert!(manager.snapshots().await.is_empty());
+    assert_eq!(manager.stats("model-tool").await.unwrap().invalidations, 2);
+}
+
+#[tokio::test]
+async fn test_snapshot_restore_failure_and_expiry() {
+    let sandbox = Arc::new(CountingSandbox::default());
+    let manager = SnapshotManager::new(sandbox.clone());
+    let mut spec = snapshot_spec("1.0.0");
+    spec.max_age = Duration::from_millis(50);
+    manager.configure(spec).await.unwrap();
+    let lease = manager.acquire("model-tool", "1.0.0", &SandboxConfig::default()).await.unwrap();
+    manager.release(lease).await.unwrap();
+
+    // A snapshot that cannot be restored is discarded and the sandbox initialized again
+    sandbox.fail_restores.store(true, std::sync::atomic::Ordering::SeqCst);
+    let lease = manager.acquire("model-tool", "1.0.0", &SandboxConfig::default()).await.unwrap();
+    assert!(!lease.restored);
+    manager.release(lease).await.unwrap();
+    let stats = manager.stats("model-tool").await.unwrap();
+    assert_eq!(stats.restore_failures, 1);
+    assert_eq!(stats.cold_starts, 2);
+    assert_eq!(manager.snapshots().await.len(), 1);
+
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    assert_eq!(manager.maintain().await, 1);
+    assert!(sandbox.snapshots.lock().await.is_empty());
+}
+
+
+const PINNED_DIGEST: &str = "sha256:4bcff63911fcb4448bd4fdacec207030997caf25e9bea4045fa6c8c44de311d1";
+
+/// Sandbox with an in-memory image store
+#[derive(Default)]
+struct ImageStoreSandbox {
+    sandbox: CountingSandbox,
+    /// Digests of the images pulled so far
+    images: tokio::sync::Mutex<HashMap<String, Vec<String>>>,
+    /// Digests the registry serves for each image
+    remote: std::sync::Mutex<HashMap<String, String>>,
+    pulls: std::sync::atomic::AtomicUsize,
+}
+
+#[async_trait]
+impl Sandbox for ImageStoreSandbox {
+    async fn create_sandbox(&self, config: SandboxConfig) -> SandboxResult<SandboxId> {
+        self.sandbox.create_sandbox(config).await
+    }
+
+    async fn execute_in_sandbox(&self, sandbox_id: &SandboxId, command: Command) -> SandboxResult<ExecutionResult> {
+        self.sandbox.execute_in_sandbox(sandbox_id, command).await
+    }
+
+    async fn destroy_sandbox(&self, sandbox_id: &SandboxId) -> SandboxResult<()> {
+        self.sandbox.destroy_sandbox(sandbox_id).await
+    }
+
+    async fn get_sandbox_status(&self, sandbox_id: &SandboxId) -> SandboxResult<SandboxStatus> {
+        self.sandbox.get_sandbox_status(sandbox_id).await
+    }
+
+    async fn list_sandboxes(&self, filter: Option<SandboxFilter>) -> SandboxResult<Vec<SandboxInfo>> {
+        self.sandbox.list_sandboxes(filter).await
+    }
+
+    async fn get_sandbox_info(&self, sandbox_id: &SandboxId) -> SandboxResult<SandboxInfo> {
+        self.sandbox.get_sandbox_info(sandbox_id).await
+    }
+
+    async fn update_sandbox_config(&self, sandbox_id: &SandboxId, config: SandboxConfig) -> SandboxResult<()> {
+        self.sandbox.update_sandbox_config(sandbox_id, config).await
+    }
+
+    async fn pause_sandbox(&self, sandbox_id: &SandboxId) -> SandboxResult<()> {
+        self.sandbox.pause_sandbox(sandbox_id).await
+    }
+
+    async fn resume_sandbox(&self, sandbox_id: &SandboxId) -> SandboxResult<()> {
+        self.sandbox.resume_sandbox(sandbox_id).await
+    }
+
+    async fn get_sandbox_logs(&self, sandbox_id: &SandboxId, lines: Option<usize>) -> SandboxResult<Vec<String>> {
+        self.sandbox.get_sandbox_logs(sandbox_id, lines).await
+    }
+
+    async fn get_sandbox_metrics(&self, sandbox_id: &SandboxId) -> SandboxResult<SandboxMetrics> {
+        self.sandbox.get_sandbox_metrics(sandbox_id).await
+    }
+
+    async fn health_check(&self) -> SandboxResult<bool> {
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl ImageSandbox for ImageStoreSandbox {
+    async fn pull_image(&self, image: &str) -> SandboxResult<()> {
+        let digest = self.remote.lock().unwrap().get(image).cloned()
+            .ok_or_else(|| SandboxError::ContainerError(ContainerError::ImagePullFailed(image.to_string())))?;
+        self.pulls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        self.images.lock().await.insert(image.to_string(), vec![format!("{}@{}", image, digest)]);
+        Ok(())
+    }
+
+    async fn image_digests(&self, image: &str) -> SandboxResult<Option<Vec<String>>> {
+        Ok(self.images.lock().await.get(image).cloned())
+    }
+}
+
+#[tokio::test]
+async fn test_integrity_prefetch_and_verify() {
+    let sandbox = Arc::new(ImageStoreSandbox::default());
+    sandbox.remote.lock().unwrap().insert("tools/python:3.11".to_string(), PINNED_DIGEST.to_string());
+    let verifier = IntegrityVerifier::new(sandbox.clone());
+    verifier.pin(ImagePin::new("python-tool", "1.0.0", "tools/python:3.11", PINNED_DIGEST)).await.unwrap();
+    assert!(verifier.pin(ImagePin::new("bad-tool", "1.0.0", "tools/bad", "sha256:abc")).await.is_err());
+
+    // Prefetching pulls the image once; unpinned tools are skipped
+    let reports = verifier.prefetch(&["python-tool".to_string(), "other-tool".to_string()]).await;
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].outcome, PrefetchOutcome::Fetched);
+    assert_eq!(verifier.prefetch_all().await[0].outcome, PrefetchOutcome::AlreadyPresent);
+    assert_eq!(sandbox.pulls.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+    // Executions use the verified image without pulling again
+    let result = verifier.execute("python-tool", SandboxConfig::default(), Command::new("python3".to_string())).await.unwrap();
+    assert_eq!(result.stdout, "python3");
+    assert_eq!(sandbox.pulls.load(std::sync::atomic::Ordering::SeqCst), 1);
+    assert!(sandbox.sandbox.alive.lock().await.is_empty());
+
+    let stats = verifier.stats().await;
+    assert_eq!(stats.verified, 3);
+    assert_eq!(stats.prefetched, 1);
+    assert_eq!(stats.rejected, 0);
+}
+
+#[tokio::test]
+async fn test_integrity_mismatch_rejects_execution() {
+    let db = Arc::new(create_test_database().await);
+    let security = Arc::new(SecurityManagerImpl::new(db, SecurityManagerConfig::default()));
+    let sandbox = Arc::new(ImageStoreSandbox::default());
+    let tampered = format!("sha256:{}", "0".repeat(64));
+    sandbox.remote.lock().unwrap().insert("tools/python:3.11".to_string(), tampered.clone());
+    let verifier = IntegrityVerifier::new(sandbox.clone()).with_security_manager(security.clone());
+    verifier.pin(ImagePin::new("python-tool", "1.0.0", "tools/python:3.11", PINNED_DIGEST)).await.unwrap();
+
+    let error = verifier.execute("python-tool", SandboxConfig::default(), Command::new("python3".to_string())).await.unwrap_err();
+    assert!(matches!(error, SandboxError::SecurityViolation(_)));
+    assert_eq!(sandbox.sandbox.created.load(std::sync::atomic::Ordering::SeqCst), 0);
+
+    // An image other than the pinned one is rejected before pulling
+    let config = SandboxConfig {
+        container_config: Some(ContainerConfig { image: "tools/python:latest".to_string(), ..ContainerConfig::default() }),
+        ..SandboxConfig::default()
+    };
+    assert!(verifier.create_sandbox("python-tool", config).await.is_err());
+
+    let events = verifier.events().await;
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].expected_digest, PINNED_DIGEST);
+    assert_eq!(events[0].actual_digests, [format!("tools/python:3.11@{}", tampered)]);
+    assert_eq!(verifier.stats().await.rejected, 2);
+
+    let violations = security.get_violations(&SandboxId::from_string("tool:python-tool".to_string())).await.unwrap();
+    assert_eq!(violations.len(), 2);
+    assert_eq!(violations[0].violation_type, ViolationType::IntegrityViolation);
+    assert_eq!(violations[0].severity, Severity::Critical);
+}
+
+/// Pin source serving fixed pins, standing in for the registry
+struct StaticPins(HashMap<String, ImagePin>);
+
+#[async_trait]
+impl ImagePinSource for StaticPins {
+    async fn image_pin(&self, tool_id: &str) -> SandboxResult<Option<ImagePin>> {
+        Ok(self.0.get(tool_id).cloned())
+    }
+}
+
+#[tokio::test]
+async fn test_integrity_pin_source() {
+    let sandbox = Arc::new(ImageStoreSandbox::default());
+    let tampered = format!("sha256:{}", "0".repeat(64));
+    sandbox.remote.lock().unwrap().insert("tools/python:3.11".to_string(), PINNED_DIGEST.to_string());
+    let source = StaticPins(HashMap::from([(
+        "python-tool".to_string(),
+        ImagePin::new("python-tool", "1.0.0", "tools/python:3.11", PINNED_DIGEST),
+    )]));
+    let verifier = IntegrityVerifier::new(sandbox.clone()).with_pin_source(Arc::new(source));
+
+    assert_eq!(verifier.pinned("python-tool").await.unwrap().unwrap().digest, PINNED_DIGEST);
+    assert!(verifier.pinned("other-tool").await.unwrap().is_none());
+    assert_eq!(verifier.prefetch(&["python-tool".to_string()]).await[0].outcome, PrefetchOutcome::Fetched);
+
+    // Pins set on the verifier take precedence over the source
+    verifier.pin(ImagePin::new("python-tool", "1.0.0", "tools/python:3.11", &tampered)).await.unwrap();
+    let error = verifier.execute("python-tool", SandboxConfig::default(), Command::new("python3".to_string())).await.unwrap_err();
+    assert!(matches!(error, SandboxError::SecurityViolation(_)));
+}